@@ -0,0 +1,277 @@
+//! Implementation of the `stratum audit` command.
+//!
+//! Checks the packages in `stratum.lock` against an advisory database and
+//! reports vulnerable, unmaintained and yanked versions. The database is
+//! given with `--db` or `[audit] db-url` in the config, as a URL or a path;
+//! downloaded databases are cached for a day.
+
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use stratum_pkg::api::ApiError;
+use stratum_pkg::audit::{Advisory, AdvisoryDatabase, AdvisoryKind, Finding};
+use stratum_pkg::{Lockfile, PackageLayout, LOCK_FILE};
+
+/// How long a downloaded database is used before it is fetched again.
+const CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Options for the audit command.
+#[derive(Debug)]
+pub struct AuditOptions {
+    /// URL or path of the advisory database. Defaults to the configured one.
+    pub db: Option<String>,
+    /// Use the cached database and skip the registry yank check.
+    pub offline: bool,
+    /// Output format (text or json).
+    pub format: String,
+    /// Fail on unmaintained and yanked packages too.
+    pub deny_warnings: bool,
+}
+
+/// Audit the current package's lockfile.
+pub fn run_audit(options: AuditOptions) -> Result<()> {
+    let json = match options.format.as_str() {
+        "text" => false,
+        "json" => true,
+        format => anyhow::bail!("Unknown audit format '{format}', expected text or json"),
+    };
+
+    let cwd = std::env::current_dir()?;
+    let layout = PackageLayout::find_root(&cwd)
+        .map_err(|e| anyhow::anyhow!("Not in a Stratum package: {e}"))?;
+    let lock_path = layout.root.join(LOCK_FILE);
+    if !lock_path.exists() {
+        anyhow::bail!("No {LOCK_FILE} found. Run `stratum update` to create one.");
+    }
+    let lockfile = Lockfile::from_path(&lock_path)
+        .with_context(|| format!("Failed to read {}", lock_path.display()))?;
+
+    let source = options
+        .db
+        .or_else(|| crate::config::current().audit.db_url.clone())
+        .context(
+            "No advisory database configured. Pass --db <URL or path> or set `db-url` in \
+             the [audit] section of the config",
+        )?;
+    let database = load_database(
+        &source,
+        &AdvisoryDatabase::default_cache_path(),
+        options.offline,
+    )?;
+
+    let mut findings = stratum_pkg::audit::audit(&lockfile, &database)?;
+    if !options.offline {
+        findings.extend(registry_yanks(&lockfile, &findings));
+    }
+
+    let errors = findings
+        .iter()
+        .filter(|f| f.is_error() || options.deny_warnings)
+        .count();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&findings)?);
+    } else {
+        print_report(
+            &findings,
+            lockfile.packages.len(),
+            database.advisories.len(),
+        );
+    }
+
+    if errors > 0 {
+        anyhow::bail!("{errors} package(s) failed the audit");
+    }
+    Ok(())
+}
+
+/// Load the database from a path, or from a URL through the cache at
+/// `cache`.
+fn load_database(source: &str, cache: &Path, offline: bool) -> Result<AdvisoryDatabase> {
+    if !source.starts_with("http://") && !source.starts_with("https://") {
+        return AdvisoryDatabase::from_path(source)
+            .with_context(|| format!("Failed to load advisory database '{source}'"));
+    }
+
+    let fresh = std::fs::metadata(cache)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age < CACHE_TTL);
+    if offline || fresh {
+        return AdvisoryDatabase::from_path(cache).with_context(|| {
+            format!(
+                "No cached advisory database at {}; run `stratum audit` without --offline",
+                cache.display()
+            )
+        });
+    }
+
+    match fetch_database(source) {
+        Ok(content) => {
+            let database = AdvisoryDatabase::parse(&content)?;
+            if let Some(parent) = cache.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(cache, content)
+                .with_context(|| format!("Failed to cache {}", cache.display()))?;
+            Ok(database)
+        }
+        Err(e) if cache.exists() => {
+            eprintln!("warning: {e:#}; using the cached advisory database");
+            Ok(AdvisoryDatabase::from_path(cache)?)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Download the database at `url`.
+fn fetch_database(url: &str) -> Result<String> {
+    let client = crate::config::current().http_client(Duration::from_secs(60))?;
+    let response = client
+        .get(url)
+        .send()
+        .with_context(|| format!("Failed to fetch advisory database from {url}"))?;
+    if !response.status().is_success() {
+        anyhow::bail!(
+            "Failed to fetch advisory database from {url}: HTTP {}",
+            response.status()
+        );
+    }
+    Ok(response.text()?)
+}
+
+/// Findings for registry packages whose locked version the configured
+/// registry reports as yanked, skipping those the database already covers.
+fn registry_yanks(lockfile: &Lockfile, known: &[Finding]) -> Vec<Finding> {
+    let Some(url) = crate::config::current().registry.url.clone() else {
+        return Vec::new();
+    };
+    let api = match crate::self_cmd::get_stratum_home()
+        .and_then(|home| crate::registry::client(&url, &home))
+    {
+        Ok(api) => api,
+        Err(e) => {
+            eprintln!("warning: skipping the registry yank check: {e:#}");
+            return Vec::new();
+        }
+    };
+
+    let mut findings = Vec::new();
+    for package in lockfile.packages.iter().filter(|p| p.source == "registry") {
+        let Some(version) = &package.version else {
+            continue;
+        };
+        let already_known = known
+            .iter()
+            .any(|f| f.package == package.name && f.advisory.kind == AdvisoryKind::Yanked);
+        if already_known {
+            continue;
+        }
+        let info = match api.info(&package.name) {
+            Ok(info) => info,
+            Err(ApiError::NotFound(_)) => continue,
+            Err(e) => {
+                eprintln!("warning: could not check '{}' for yanks: {e}", package.name);
+                continue;
+            }
+        };
+        if info.versions.iter().any(|v| v.yanked && &v.num == version) {
+            let patched = info.latest().map(|v| format!(">={}", v.num));
+            findings.push(Finding {
+                package: package.name.clone(),
+                version: version.clone(),
+                advisory: Advisory {
+                    id: "yanked".to_string(),
+                    package: package.name.clone(),
+                    kind: AdvisoryKind::Yanked,
+                    severity: None,
+                    title: format!("{} {version} was yanked from {url}", package.name),
+                    url: None,
+                    affected: Some(format!("={version}")),
+                    patched: patched.into_iter().collect(),
+                },
+            });
+        }
+    }
+    findings
+}
+
+/// Print findings for a terminal.
+fn print_report(findings: &[Finding], packages: usize, advisories: usize) {
+    eprintln!("Scanned {packages} package(s) against {advisories} advisory(ies)");
+    for finding in findings {
+        let advisory = &finding.advisory;
+        let label = if finding.is_error() {
+            "error"
+        } else {
+            "warning"
+        };
+        let severity = advisory
+            .severity
+            .map(|s| format!(" ({} severity)", s.name()))
+            .unwrap_or_default();
+        println!(
+            "\n{label}[{}]: {} {} is {}{severity}",
+            advisory.id,
+            finding.package,
+            finding.version,
+            match advisory.kind {
+                AdvisoryKind::Vulnerability => "vulnerable",
+                AdvisoryKind::Unmaintained => "unmaintained",
+                AdvisoryKind::Yanked => "yanked",
+            }
+        );
+        println!("  {}", advisory.title);
+        if advisory.patched.is_empty() {
+            println!("  fix: no fixed version; consider an alternative package");
+        } else {
+            println!("  fix: upgrade to {}", advisory.patched.join(" or "));
+        }
+        if let Some(url) = &advisory.url {
+            println!("  see: {url}");
+        }
+    }
+
+    let errors = findings.iter().filter(|f| f.is_error()).count();
+    let warnings = findings.len() - errors;
+    if findings.is_empty() {
+        eprintln!("No known problems found");
+    } else {
+        eprintln!("\n{errors} vulnerability(ies), {warnings} warning(s)");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATABASE: &str = r#"{"advisories": [{"id": "STRAT-2025-0001", "package": "http", "kind": "vulnerability", "severity": "high", "title": "Header injection", "affected": "<1.2.3", "patched": [">=1.2.3"]}]}"#;
+
+    #[test]
+    fn test_load_database_from_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("advisories.json");
+        std::fs::write(&path, DATABASE).unwrap();
+        let cache = dir.path().join("cache.json");
+
+        let database = load_database(path.to_str().unwrap(), &cache, false).unwrap();
+        assert_eq!(database.advisories.len(), 1);
+        assert!(!cache.exists());
+    }
+
+    #[test]
+    fn test_load_database_offline_uses_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = dir.path().join("cache.json");
+        let url = "https://advisories.invalid/db.json";
+
+        assert!(load_database(url, &cache, true).is_err());
+
+        std::fs::write(&cache, DATABASE).unwrap();
+        let database = load_database(url, &cache, true).unwrap();
+        assert_eq!(database.advisories[0].id, "STRAT-2025-0001");
+        // A fresh cache is used without going to the network
+        let database = load_database(url, &cache, false).unwrap();
+        assert_eq!(database.advisories.len(), 1);
+    }
+}
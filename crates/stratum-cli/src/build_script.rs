@@ -0,0 +1,152 @@
+//! Running package build scripts before `stratum run` and `stratum build`.
+//!
+//! The script is run with `stratum run` in a child process, so it gets a VM
+//! of its own; see [`stratum_pkg::build_script`] for the variables it sees
+//! and the directives it prints.
+
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use stratum_pkg::build_script::{BuildOutput, BuildScript};
+use stratum_pkg::{Manifest, PackageLayout};
+
+/// Run the build script of the package containing `path`, if it has one,
+/// and return what the script asked for.
+///
+/// Nothing is run for files outside a package, or for the build script
+/// itself. Output from an earlier run is reused when it is still fresh.
+pub fn prepare(path: &Path, release: bool) -> Result<Option<BuildOutput>> {
+    let Ok(path) = std::fs::canonicalize(path) else {
+        return Ok(None);
+    };
+    let Ok(layout) = PackageLayout::find_root(path.parent().unwrap_or(&path)) else {
+        return Ok(None);
+    };
+    let manifest = Manifest::from_path(&layout.manifest_path)
+        .with_context(|| format!("Failed to read {}", layout.manifest_path.display()))?;
+    let Some(script) = BuildScript::find(&layout, &manifest)? else {
+        return Ok(None);
+    };
+    if std::fs::canonicalize(&script.script).is_ok_and(|script| script == path) {
+        return Ok(None);
+    }
+    if let Some(output) = script.cached(release) {
+        return Ok(Some(output));
+    }
+    run(&script, release, &std::env::current_exe()?).map(Some)
+}
+
+/// Run `script` with the `stratum` executable at `stratum`.
+fn run(script: &BuildScript, release: bool, stratum: &Path) -> Result<BuildOutput> {
+    eprintln!("Running build script for {}", script.name);
+    let out_dir = script.out_dir();
+    std::fs::create_dir_all(&out_dir)
+        .with_context(|| format!("Failed to create {}", out_dir.display()))?;
+
+    let result = Command::new(stratum)
+        .arg("run")
+        .arg(&script.script)
+        .current_dir(&script.root)
+        .envs(script.env(release))
+        .stdin(Stdio::null())
+        .stderr(Stdio::inherit())
+        .output()
+        .with_context(|| format!("Failed to run {}", script.script.display()))?;
+    let stdout = String::from_utf8_lossy(&result.stdout);
+    if !result.status.success() {
+        anyhow::bail!(
+            "Build script {} failed ({})\n--- stdout\n{}",
+            script.script.display(),
+            result.status,
+            stdout.trim_end()
+        );
+    }
+
+    let output = BuildOutput::parse(&stdout)
+        .with_context(|| format!("Invalid output from {}", script.script.display()))?;
+    for warning in &output.warnings {
+        eprintln!("warning: build script for {}: {warning}", script.name);
+    }
+    script.save(&stdout, &output, release)?;
+    Ok(output)
+}
+
+/// Set the variables a build script asked for in this process, for programs
+/// run by the VM.
+pub fn apply_env(output: &BuildOutput) {
+    for (key, value) in &output.env {
+        std::env::set_var(key, value);
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    /// A package whose build script is run by `stub`, a shell script
+    /// standing in for `stratum` that prints the given stdout.
+    fn package(dir: &Path, stdout: &str, exit: i32) -> (BuildScript, std::path::PathBuf) {
+        use std::os::unix::fs::PermissionsExt;
+
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(dir.join("src").join("main.strat"), "").unwrap();
+        std::fs::write(dir.join("build.strat"), "").unwrap();
+        std::fs::write(
+            dir.join("stratum.toml"),
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2025\"\n",
+        )
+        .unwrap();
+        let stub = dir.join("stratum-stub");
+        std::fs::write(
+            &stub,
+            format!("#!/bin/sh\necho \"out=$OUT_DIR\"\nprintf '{stdout}'\nexit {exit}\n"),
+        )
+        .unwrap();
+        std::fs::set_permissions(&stub, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let layout = PackageLayout::discover(dir).unwrap();
+        let manifest = Manifest::from_path(&layout.manifest_path).unwrap();
+        let script = BuildScript::find(&layout, &manifest).unwrap().unwrap();
+        (script, stub)
+    }
+
+    #[test]
+    fn test_run_build_script() {
+        let dir = tempfile::tempdir().unwrap();
+        let (script, stub) = package(
+            dir.path(),
+            "stratum:env=API_URL=https://example.com\\nstratum:rerun-if-changed=build.strat\\n",
+            0,
+        );
+        let output = run(&script, false, &stub).unwrap();
+        assert_eq!(output.env["API_URL"], "https://example.com");
+        assert!(script.out_dir().is_dir());
+        assert_eq!(script.cached(false), Some(output));
+    }
+
+    #[test]
+    fn test_failing_build_script() {
+        let dir = tempfile::tempdir().unwrap();
+        let (script, stub) = package(dir.path(), "stratum:env=A=1\\n", 3);
+        let err = run(&script, false, &stub).unwrap_err();
+        assert!(err.to_string().contains("out="), "{err}");
+        assert!(script.cached(false).is_none());
+
+        let (script, stub) = package(dir.path(), "stratum:bogus=1\\n", 0);
+        assert!(run(&script, false, &stub).is_err());
+    }
+
+    #[test]
+    fn test_prepare_outside_package() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("hello.strat");
+        std::fs::write(&file, "").unwrap();
+        assert!(prepare(&file, false).unwrap().is_none());
+
+        // The build script itself is not prepared
+        package(dir.path(), "", 0);
+        assert!(prepare(&dir.path().join("build.strat"), false)
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,93 @@
+//! Implementation of the `stratum clean` command.
+//!
+//! Removes the target directory of the current package or workspace, or
+//! only some of the artifacts in it; see [`stratum_pkg::TargetDir`].
+
+use anyhow::{Context, Result};
+use stratum_pkg::{Artifacts, TargetDir};
+
+/// Clean the target directory of the package or workspace around the
+/// current directory.
+pub fn clean(doc: bool, cache: bool, release: bool) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let target = TargetDir::find(&cwd)
+        .ok_or_else(|| anyhow::anyhow!("Not in a Stratum package or workspace"))?;
+    eprintln!("{}", clean_target(&target, doc, cache, release)?);
+    Ok(())
+}
+
+/// Clean `target`, returning a message saying what was removed.
+fn clean_target(target: &TargetDir, doc: bool, cache: bool, release: bool) -> Result<String> {
+    let only: Vec<Artifacts> = [
+        (doc, Artifacts::Doc),
+        (cache, Artifacts::Cache),
+        (release, Artifacts::Release),
+    ]
+    .into_iter()
+    .filter_map(|(selected, artifacts)| selected.then_some(artifacts))
+    .collect();
+
+    let summary = target
+        .clean(&only)
+        .with_context(|| format!("Failed to clean {}", target.root.display()))?;
+    if summary.files == 0 {
+        return Ok("Nothing to clean".to_string());
+    }
+    Ok(format!(
+        "Removed {} file(s), {}",
+        summary.files,
+        format_size(summary.bytes)
+    ))
+}
+
+/// `bytes` in the largest binary unit that keeps the number at least 1.
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MiB");
+    }
+
+    #[test]
+    fn test_clean_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = TargetDir::new(dir.path().join("target"));
+        std::fs::create_dir_all(target.doc()).unwrap();
+        std::fs::create_dir_all(target.profile(false)).unwrap();
+        std::fs::write(target.doc().join("index.html"), "<html>").unwrap();
+        std::fs::write(target.profile(false).join("app"), vec![0; 2048]).unwrap();
+
+        assert_eq!(
+            clean_target(&target, true, false, false).unwrap(),
+            "Removed 1 file(s), 6 B"
+        );
+        assert_eq!(
+            clean_target(&target, false, true, true).unwrap(),
+            "Nothing to clean"
+        );
+        assert_eq!(
+            clean_target(&target, false, false, false).unwrap(),
+            "Removed 1 file(s), 2.0 KiB"
+        );
+        assert!(!target.root.exists());
+    }
+}
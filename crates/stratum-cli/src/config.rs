@@ -0,0 +1,337 @@
+//! Layered configuration for the CLI
+//!
+//! Settings are merged from these sources, each overriding the ones before:
+//!
+//! 1. Built-in defaults
+//! 2. The user config, `~/.config/stratum/config.toml` (or
+//!    `$XDG_CONFIG_HOME/stratum/config.toml`)
+//! 3. The project config, `.stratum/config.toml` in the current directory or
+//!    the nearest ancestor that has one
+//! 4. `STRATUM_<SECTION>_<KEY>` environment variables, such as
+//!    `STRATUM_RUN_HOT_THRESHOLD=500`
+//! 5. Command-line flags
+//!
+//! ```toml
+//! [run]
+//! jit = true
+//! hot-threshold = 1000
+//!
+//! [fmt]
+//! indent-size = 4
+//! organize-imports = false
+//!
+//! [registry]
+//! api-url = "https://api.github.com"
+//! url = "https://registry.example.com"
+//!
+//! [network]
+//! proxy = "http://proxy.internal:3128"
+//!
+//! [audit]
+//! db-url = "https://example.com/advisories.json"
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+/// File name of the user and project config
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// Directory holding the project config
+pub const PROJECT_CONFIG_DIR: &str = ".stratum";
+
+/// Prefix of the environment variables that override config keys
+const ENV_PREFIX: &str = "STRATUM_";
+
+/// GitHub API used when no registry URL is configured
+pub const DEFAULT_API_URL: &str = "https://api.github.com";
+
+/// The merged configuration of the running command
+static CURRENT: OnceLock<Config> = OnceLock::new();
+
+/// CLI configuration; unset keys fall through to the next layer down
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// `stratum run` defaults
+    pub run: RunConfig,
+    /// `stratum fmt` defaults
+    pub fmt: FmtConfig,
+    /// Package and release registry
+    pub registry: RegistryConfig,
+    /// HTTP settings
+    pub network: NetworkConfig,
+    /// `stratum audit` defaults
+    pub audit: AuditConfig,
+}
+
+/// `[run]` section
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct RunConfig {
+    /// Compile hot functions with the JIT
+    pub jit: Option<bool>,
+    /// Calls before a function is considered hot
+    pub hot_threshold: Option<usize>,
+}
+
+/// `[fmt]` section
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct FmtConfig {
+    /// Spaces per indentation level
+    pub indent_size: Option<usize>,
+    /// Sort and group imports and remove unused ones
+    pub organize_imports: Option<bool>,
+}
+
+/// `[registry]` section
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct RegistryConfig {
+    /// Base URL of the GitHub API releases are looked up in
+    pub api_url: Option<String>,
+    /// Package registry used by `publish`, `search`, `info` and `yank`
+    pub url: Option<String>,
+    /// Registry token, normally set with `STRATUM_REGISTRY_TOKEN` in CI;
+    /// `stratum login` saves tokens in `~/.stratum/credentials.toml` instead
+    pub token: Option<String>,
+    /// Whether `url` comes from the project config, which `token` is never
+    /// sent to
+    #[serde(skip)]
+    pub url_from_project: bool,
+}
+
+/// `[network]` section
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct NetworkConfig {
+    /// Proxy for all HTTP requests
+    pub proxy: Option<String>,
+}
+
+/// `[audit]` section
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct AuditConfig {
+    /// URL or path of the advisory database
+    pub db_url: Option<String>,
+}
+
+impl Config {
+    /// Load the user config, the project config for `cwd` and the
+    /// environment, merged in precedence order
+    pub fn load(cwd: &Path) -> Result<Self> {
+        let mut config = Self::default();
+        if let Some(path) = user_config_path().filter(|path| path.is_file()) {
+            config.merge(Self::from_path(&path)?);
+        }
+        if let Some(path) = project_config_path(cwd) {
+            let mut project = Self::from_path(&path)?;
+            project.registry.url_from_project = project.registry.url.is_some();
+            config.merge(project);
+        }
+        config.merge(Self::from_env(std::env::vars())?);
+        Ok(config)
+    }
+
+    /// Read a config file
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config '{}'", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Invalid config '{}'", path.display()))
+    }
+
+    /// Collect the keys set by `STRATUM_<SECTION>_<KEY>` variables
+    ///
+    /// Values are read as TOML (`true`, `500`), falling back to a string.
+    /// Variables that name no config section, such as `STRATUM_HOME`, are
+    /// ignored.
+    pub fn from_env(vars: impl IntoIterator<Item = (String, String)>) -> Result<Self> {
+        const SECTIONS: [&str; 5] = ["run", "fmt", "registry", "network", "audit"];
+
+        let mut table = toml::Table::new();
+        for (name, value) in vars {
+            let Some(rest) = name.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            let rest = rest.to_lowercase();
+            let Some((section, key)) = rest.split_once('_') else {
+                continue;
+            };
+            if !SECTIONS.contains(&section) {
+                continue;
+            }
+            let value = toml::from_str::<toml::Table>(&format!("v = {value}"))
+                .ok()
+                .and_then(|mut t| t.remove("v"))
+                .unwrap_or(toml::Value::String(value));
+            table
+                .entry(section)
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+                .as_table_mut()
+                .expect("config sections are tables")
+                .insert(key.replace('_', "-"), value);
+        }
+        toml::Value::Table(table)
+            .try_into()
+            .context("Invalid STRATUM_ environment variable")
+    }
+
+    /// Override the keys `other` sets
+    pub fn merge(&mut self, other: Self) {
+        fn set<T>(slot: &mut Option<T>, value: Option<T>) {
+            if value.is_some() {
+                *slot = value;
+            }
+        }
+        set(&mut self.run.jit, other.run.jit);
+        set(&mut self.run.hot_threshold, other.run.hot_threshold);
+        set(&mut self.fmt.indent_size, other.fmt.indent_size);
+        set(&mut self.fmt.organize_imports, other.fmt.organize_imports);
+        set(&mut self.registry.api_url, other.registry.api_url);
+        if other.registry.url.is_some() {
+            self.registry.url_from_project = other.registry.url_from_project;
+        }
+        set(&mut self.registry.url, other.registry.url);
+        set(&mut self.registry.token, other.registry.token);
+        set(&mut self.network.proxy, other.network.proxy);
+        set(&mut self.audit.db_url, other.audit.db_url);
+    }
+
+    /// The GitHub API base URL, without a trailing slash
+    pub fn api_url(&self) -> &str {
+        self.registry
+            .api_url
+            .as_deref()
+            .unwrap_or(DEFAULT_API_URL)
+            .trim_end_matches('/')
+    }
+
+    /// An HTTP client using the configured proxy
+    pub fn http_client(&self, timeout: std::time::Duration) -> Result<reqwest::blocking::Client> {
+        let mut builder = reqwest::blocking::Client::builder()
+            .user_agent("stratum-cli")
+            .timeout(timeout);
+        if let Some(proxy) = &self.network.proxy {
+            builder = builder.proxy(
+                reqwest::Proxy::all(proxy).with_context(|| format!("Invalid proxy '{proxy}'"))?,
+            );
+        }
+        builder.build().context("Failed to create HTTP client")
+    }
+}
+
+/// Make `config` the configuration [`current`] returns
+pub fn install(config: Config) {
+    let _ = CURRENT.set(config);
+}
+
+/// The configuration of the running command, or the defaults if none was
+/// installed
+pub fn current() -> &'static Config {
+    CURRENT.get_or_init(Config::default)
+}
+
+/// `~/.config/stratum/config.toml`, honouring `XDG_CONFIG_HOME`
+fn user_config_path() -> Option<PathBuf> {
+    let dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".config")))?;
+    Some(dir.join("stratum").join(CONFIG_FILE))
+}
+
+/// `.stratum/config.toml` in `cwd` or its nearest ancestor that has one
+fn project_config_path(cwd: &Path) -> Option<PathBuf> {
+    cwd.ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG_DIR).join(CONFIG_FILE))
+        .find(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_config_file() {
+        let config: Config = toml::from_str(
+            "[run]\njit = false\nhot-threshold = 50\n\n[fmt]\nindent-size = 2\n\n[network]\nproxy = \"http://proxy:3128\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.run.jit, Some(false));
+        assert_eq!(config.run.hot_threshold, Some(50));
+        assert_eq!(config.fmt.indent_size, Some(2));
+        assert_eq!(config.fmt.organize_imports, None);
+        assert_eq!(config.network.proxy.as_deref(), Some("http://proxy:3128"));
+
+        assert!(toml::from_str::<Config>("[run]\njitt = true\n").is_err());
+    }
+
+    #[test]
+    fn test_config_from_env() {
+        let config = Config::from_env(vars(&[
+            ("STRATUM_RUN_HOT_THRESHOLD", "500"),
+            ("STRATUM_RUN_JIT", "false"),
+            (
+                "STRATUM_REGISTRY_API_URL",
+                "https://ghe.example.com/api/v3/",
+            ),
+            ("STRATUM_REGISTRY_TOKEN", "secret"),
+            ("STRATUM_HOME", "/opt/stratum"),
+            ("PATH", "/usr/bin"),
+        ]))
+        .unwrap();
+        assert_eq!(config.run.hot_threshold, Some(500));
+        assert_eq!(config.run.jit, Some(false));
+        assert_eq!(config.api_url(), "https://ghe.example.com/api/v3");
+        assert_eq!(config.registry.token.as_deref(), Some("secret"));
+
+        assert!(Config::from_env(vars(&[("STRATUM_RUN_HOT_THRESHOLD", "many")])).is_err());
+        assert!(Config::from_env(vars(&[("STRATUM_FMT_TABS", "true")])).is_err());
+    }
+
+    #[test]
+    fn test_merge_precedence() {
+        let mut config: Config =
+            toml::from_str("[run]\njit = false\nhot-threshold = 50\n").unwrap();
+        config.merge(toml::from_str("[run]\nhot-threshold = 10\n").unwrap());
+        assert_eq!(config.run.jit, Some(false));
+        assert_eq!(config.run.hot_threshold, Some(10));
+        assert_eq!(Config::default().api_url(), DEFAULT_API_URL);
+
+        let mut project: Config =
+            toml::from_str("[registry]\nurl = \"https://evil.example.com\"\n").unwrap();
+        project.registry.url_from_project = true;
+        let mut config = Config::default();
+        config.merge(project);
+        assert!(config.registry.url_from_project);
+        config.merge(toml::from_str("[registry]\ntoken = \"secret\"\n").unwrap());
+        assert!(config.registry.url_from_project);
+        config.merge(toml::from_str("[registry]\nurl = \"https://r.example.com\"\n").unwrap());
+        assert!(!config.registry.url_from_project);
+    }
+
+    #[test]
+    fn test_project_config_in_ancestor() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("src").join("nested");
+        std::fs::create_dir_all(&nested).unwrap();
+        assert_eq!(project_config_path(&nested), None);
+
+        let config_dir = dir.path().join(PROJECT_CONFIG_DIR);
+        std::fs::create_dir_all(&config_dir).unwrap();
+        std::fs::write(config_dir.join(CONFIG_FILE), "[fmt]\nindent-size = 2\n").unwrap();
+        let path = project_config_path(&nested).unwrap();
+        assert_eq!(Config::from_path(&path).unwrap().fmt.indent_size, Some(2));
+    }
+}
@@ -10,17 +10,20 @@ use std::rc::Rc;
 use std::sync::atomic::{AtomicI64, Ordering};
 
 use anyhow::{anyhow, Result};
-use dap::events::{OutputEventBody, StoppedEventBody};
+use dap::events::{BreakpointEventBody, OutputEventBody, StoppedEventBody};
 use dap::responses::{
-    ContinueResponse, ScopesResponse, SetBreakpointsResponse, StackTraceResponse, ThreadsResponse,
-    VariablesResponse,
+    ContinueResponse, EvaluateResponse, ScopesResponse, SetBreakpointsResponse, StackTraceResponse,
+    ThreadsResponse, VariablesResponse,
 };
 use dap::types::{
-    Breakpoint, Capabilities, OutputEventCategory, Scope, Source, StackFrame, StoppedEventReason,
-    Thread, Variable,
+    Breakpoint, BreakpointEventReason, Capabilities, OutputEventCategory, Scope, Source,
+    StackFrame, StoppedEventReason, Thread, Variable,
 };
 use dap::{events::Event, requests::Command, responses::ResponseBody, server::Server};
-use stratum_core::{DebugStackFrame, DebugState, DebugStepResult, DebugVariable, PauseReason, VM};
+use stratum_core::{
+    DebugStackFrame, DebugState, DebugStepResult, DebugVariable, HitCondition, HotReload,
+    PauseReason, VM,
+};
 
 /// Thread ID for the main thread (Stratum is single-threaded)
 const MAIN_THREAD_ID: i64 = 1;
@@ -31,13 +34,16 @@ pub struct StratumDebugAdapter {
     vm: Option<VM>,
     /// Current source file being debugged
     source_file: Option<PathBuf>,
+    /// The source last compiled or hot reloaded, to detect edits
+    source_text: Option<String>,
     /// Compiled function (if any)
     compiled_function: Option<Rc<stratum_core::bytecode::Function>>,
     /// Whether the debug session has started
     session_started: bool,
     /// Next variable reference ID
     next_var_ref: AtomicI64,
-    /// Variable references (scope_id -> variables)
+    /// Variable references (scope or container id -> variables), valid
+    /// until execution resumes
     variable_scopes: HashMap<i64, Vec<DebugVariable>>,
     /// Current debug state (when paused)
     current_state: Option<DebugState>,
@@ -59,6 +65,7 @@ impl StratumDebugAdapter {
         Self {
             vm: None,
             source_file: None,
+            source_text: None,
             compiled_function: None,
             session_started: false,
             next_var_ref: AtomicI64::new(1),
@@ -73,27 +80,11 @@ impl StratumDebugAdapter {
     fn compile_source(&mut self, source_path: &PathBuf) -> Result<()> {
         let source = std::fs::read_to_string(source_path)
             .map_err(|e| anyhow!("Failed to read source file: {}", e))?;
+        let module = check_source(&source)?;
 
-        // Parse as module
-        let module = stratum_core::Parser::parse_module(&source).map_err(|errors| {
-            let error_msgs: Vec<String> = errors.iter().map(|e| format!("{}", e)).collect();
-            anyhow!("Parse errors:\n{}", error_msgs.join("\n"))
-        })?;
-
-        // Type check
-        let mut type_checker = stratum_core::TypeChecker::new();
-        let type_result = type_checker.check_module(&module);
-        if !type_result.errors.is_empty() {
-            let error_msgs: Vec<String> = type_result
-                .errors
-                .iter()
-                .map(|e| format!("{}", e))
-                .collect();
-            return Err(anyhow!("Type errors:\n{}", error_msgs.join("\n")));
-        }
-
-        // Compile
+        // Compile without optimization so every line keeps its instructions
         let function = stratum_core::Compiler::with_source(source_path.display().to_string())
+            .with_optimization(false)
             .compile_module(&module)
             .map_err(|errors| {
                 let error_msgs: Vec<String> = errors.iter().map(|e| format!("{}", e)).collect();
@@ -102,13 +93,58 @@ impl StratumDebugAdapter {
 
         self.compiled_function = Some(function);
         self.source_file = Some(source_path.clone());
+        self.source_text = Some(source);
 
         Ok(())
     }
 
+    /// Step back one line, or back to the previous breakpoint, through the
+    /// VM's execution trace; at the start of the trace, stay paused where
+    /// execution is
+    fn run_backwards(&mut self, to_breakpoint: bool) -> Option<DebugStepResult> {
+        let vm = self.vm.as_mut()?;
+        self.current_state.as_ref()?;
+        self.variable_scopes.clear();
+
+        let state = if to_breakpoint {
+            vm.reverse_continue()
+        } else {
+            vm.step_back()
+        };
+        let state = state.unwrap_or_else(|| vm.get_debug_state(PauseReason::Step));
+        Some(DebugStepResult::Paused(state))
+    }
+
+    /// Swap the functions of the source file into the VM if the file was
+    /// edited since it was last compiled, returning `None` if it wasn't
+    fn reload_source(&mut self) -> Option<Result<HotReload>> {
+        let source_path = self.source_file.clone()?;
+        let source = std::fs::read_to_string(&source_path).ok()?;
+        if self.source_text.as_deref() == Some(source.as_str()) {
+            return None;
+        }
+        let vm = self.vm.as_mut()?;
+
+        let result = check_source(&source).and_then(|module| {
+            stratum_core::Compiler::with_source(source_path.display().to_string())
+                .with_optimization(false)
+                .compile_definitions(&module)
+                .map(|definitions| vm.hot_reload(definitions))
+                .map_err(|errors| {
+                    let error_msgs: Vec<String> = errors.iter().map(|e| format!("{}", e)).collect();
+                    anyhow!("Compile errors:\n{}", error_msgs.join("\n"))
+                })
+        });
+
+        // An edit that doesn't compile is reported once, not on every step
+        self.source_text = Some(source);
+        Some(result)
+    }
+
     /// Start or continue execution
     fn run_execution(&mut self) -> Option<DebugStepResult> {
         let vm = self.vm.as_mut()?;
+        self.variable_scopes.clear();
 
         let result = if self.session_started {
             vm.continue_debug()
@@ -160,18 +196,42 @@ impl StratumDebugAdapter {
         self.next_var_ref.fetch_add(1, Ordering::SeqCst)
     }
 
+    /// Convert a DebugVariable to a DAP Variable, allocating a reference
+    /// for its children if it has any
+    fn to_dap_variable(&mut self, var: &DebugVariable) -> Variable {
+        let variables_reference = if var.has_children() {
+            let var_ref = self.alloc_var_ref();
+            self.variable_scopes.insert(var_ref, var.children());
+            var_ref
+        } else {
+            0
+        };
+
+        Variable {
+            name: var.name.clone(),
+            value: var.value.clone(),
+            type_field: Some(var.type_name.clone()),
+            presentation_hint: None,
+            evaluate_name: None,
+            variables_reference,
+            named_variables: None,
+            indexed_variables: None,
+            memory_reference: None,
+        }
+    }
+
     /// Get capabilities
     fn get_capabilities() -> Capabilities {
         Capabilities {
             supports_configuration_done_request: Some(true),
             supports_function_breakpoints: Some(false),
-            supports_conditional_breakpoints: Some(false),
-            supports_hit_conditional_breakpoints: Some(false),
-            supports_evaluate_for_hovers: Some(false),
+            supports_conditional_breakpoints: Some(true),
+            supports_hit_conditional_breakpoints: Some(true),
+            supports_evaluate_for_hovers: Some(true),
             exception_breakpoint_filters: None,
-            supports_step_back: Some(false),
+            supports_step_back: Some(true),
             supports_set_variable: Some(false),
-            supports_restart_frame: Some(false),
+            supports_restart_frame: Some(true),
             supports_goto_targets_request: Some(false),
             supports_step_in_targets_request: Some(false),
             supports_completions_request: Some(false),
@@ -187,7 +247,7 @@ impl StratumDebugAdapter {
             support_suspend_debuggee: Some(false),
             supports_delayed_stack_trace_loading: Some(false),
             supports_loaded_sources_request: Some(false),
-            supports_log_points: Some(false),
+            supports_log_points: Some(true),
             supports_terminate_threads_request: Some(false),
             supports_set_expression: Some(false),
             supports_terminate_request: Some(true),
@@ -321,11 +381,25 @@ pub fn run_dap_server() -> Result<()> {
                         for bp in source_breakpoints {
                             let line = bp.line as u32;
                             let bp_id = vm.add_breakpoint(source_path.clone(), line);
+                            let message = breakpoint_error(
+                                bp.condition.as_deref(),
+                                bp.hit_condition.as_deref(),
+                            );
+                            match (vm.breakpoint_mut(bp_id), &message) {
+                                (Some(breakpoint), None) => {
+                                    breakpoint.condition = bp.condition.clone();
+                                    breakpoint.hit_condition = bp.hit_condition.clone();
+                                    breakpoint.log_message = bp.log_message.clone();
+                                }
+                                // An invalid breakpoint never triggers
+                                (Some(breakpoint), Some(_)) => breakpoint.enabled = false,
+                                (None, _) => {}
+                            }
 
                             let dap_bp = Breakpoint {
                                 id: Some(bp_id as i64),
-                                verified: true,
-                                message: None,
+                                verified: message.is_none(),
+                                message,
                                 source: Some(args.source.clone()),
                                 line: Some(bp.line),
                                 column: None,
@@ -364,6 +438,7 @@ pub fn run_dap_server() -> Result<()> {
                 } else {
                     // Run until breakpoint or completion
                     if let Some(result) = adapter.run_execution() {
+                        send_log_messages(&mut adapter, &mut server)?;
                         match result {
                             DebugStepResult::Paused(state) => {
                                 adapter.current_state = Some(state.clone());
@@ -429,15 +504,10 @@ pub fn run_dap_server() -> Result<()> {
                 server.respond(rsp)?;
             }
 
-            Command::Scopes(ref _args) => {
-                // Clear old variable scopes
-                adapter.variable_scopes.clear();
-
+            Command::Scopes(ref args) => {
                 // Get locals for the requested frame
-                let locals = if let Some(state) = &adapter.current_state {
-                    state.locals.clone()
-                } else if let Some(vm) = &adapter.vm {
-                    vm.get_local_variables()
+                let locals = if let Some(vm) = &adapter.vm {
+                    vm.get_frame_variables(args.frame_id as usize)
                 } else {
                     Vec::new()
                 };
@@ -467,30 +537,51 @@ pub fn run_dap_server() -> Result<()> {
             Command::Variables(ref args) => {
                 let var_ref = args.variables_reference;
 
-                let variables = if let Some(debug_vars) = adapter.variable_scopes.get(&var_ref) {
-                    debug_vars
-                        .iter()
-                        .map(|v| Variable {
-                            name: v.name.clone(),
-                            value: v.value.clone(),
-                            type_field: Some(v.type_name.clone()),
+                let debug_vars = adapter
+                    .variable_scopes
+                    .get(&var_ref)
+                    .cloned()
+                    .unwrap_or_default();
+                let variables = debug_vars
+                    .iter()
+                    .map(|v| adapter.to_dap_variable(v))
+                    .collect();
+
+                let rsp = req.success(ResponseBody::Variables(VariablesResponse { variables }));
+                server.respond(rsp)?;
+            }
+
+            Command::Evaluate(ref args) => {
+                // Expressions run in the selected frame, or the innermost one
+                let frame_index = args.frame_id.unwrap_or(0) as usize;
+                let result = match adapter.vm.as_mut() {
+                    Some(vm) if adapter.current_state.is_some() => {
+                        vm.evaluate_in_frame(frame_index, &args.expression)
+                    }
+                    _ => Err("Expressions can only be evaluated while paused".to_string()),
+                };
+
+                let rsp = match result {
+                    Ok(value) => {
+                        let var = DebugVariable::from_value(args.expression.clone(), &value);
+                        let variable = adapter.to_dap_variable(&var);
+                        req.success(ResponseBody::Evaluate(EvaluateResponse {
+                            result: variable.value,
+                            type_field: variable.type_field,
                             presentation_hint: None,
-                            evaluate_name: None,
-                            variables_reference: 0,
+                            variables_reference: variable.variables_reference,
                             named_variables: None,
                             indexed_variables: None,
                             memory_reference: None,
-                        })
-                        .collect()
-                } else {
-                    Vec::new()
+                        }))
+                    }
+                    Err(e) => req.error(&e),
                 };
-
-                let rsp = req.success(ResponseBody::Variables(VariablesResponse { variables }));
                 server.respond(rsp)?;
             }
 
             Command::Continue(ref _args) => {
+                hot_reload_source(&mut adapter, &mut server)?;
                 let rsp = req.success(ResponseBody::Continue(ContinueResponse {
                     all_threads_continued: Some(true),
                 }));
@@ -502,6 +593,7 @@ pub fn run_dap_server() -> Result<()> {
             }
 
             Command::Next(ref _args) => {
+                hot_reload_source(&mut adapter, &mut server)?;
                 if let Some(vm) = adapter.vm.as_mut() {
                     vm.step_over();
                 }
@@ -515,6 +607,7 @@ pub fn run_dap_server() -> Result<()> {
             }
 
             Command::StepIn(ref _args) => {
+                hot_reload_source(&mut adapter, &mut server)?;
                 if let Some(vm) = adapter.vm.as_mut() {
                     vm.step_into();
                 }
@@ -528,6 +621,7 @@ pub fn run_dap_server() -> Result<()> {
             }
 
             Command::StepOut(ref _args) => {
+                hot_reload_source(&mut adapter, &mut server)?;
                 if let Some(vm) = adapter.vm.as_mut() {
                     vm.step_out();
                 }
@@ -540,6 +634,48 @@ pub fn run_dap_server() -> Result<()> {
                 }
             }
 
+            Command::StepBack(ref _args) => {
+                let rsp = req.success(ResponseBody::StepBack);
+                server.respond(rsp)?;
+
+                if let Some(result) = adapter.run_backwards(false) {
+                    handle_execution_result(&mut adapter, &mut server, result)?;
+                }
+            }
+
+            Command::ReverseContinue(ref _args) => {
+                let rsp = req.success(ResponseBody::ReverseContinue);
+                server.respond(rsp)?;
+
+                if let Some(result) = adapter.run_backwards(true) {
+                    handle_execution_result(&mut adapter, &mut server, result)?;
+                }
+            }
+
+            Command::RestartFrame(ref args) => {
+                hot_reload_source(&mut adapter, &mut server)?;
+
+                let result = match adapter.vm.as_mut() {
+                    Some(vm) if adapter.current_state.is_some() => {
+                        vm.restart_frame(args.frame_id as usize)
+                    }
+                    _ => Err("Frames can only be restarted while paused".to_string()),
+                };
+
+                match result {
+                    Ok(state) => {
+                        server.respond(req.success(ResponseBody::RestartFrame))?;
+                        adapter.variable_scopes.clear();
+                        adapter.current_state = Some(state);
+                        server.send_event(StratumDebugAdapter::create_stopped_event(
+                            StoppedEventReason::Step,
+                            Some("Restarted frame".to_string()),
+                        ))?;
+                    }
+                    Err(e) => server.respond(req.error(&e))?,
+                }
+            }
+
             Command::Pause(ref _args) => {
                 let rsp = req.success(ResponseBody::Pause);
                 server.respond(rsp)?;
@@ -574,11 +710,120 @@ pub fn run_dap_server() -> Result<()> {
     Ok(())
 }
 
+/// Parse and type check a source file
+fn check_source(source: &str) -> Result<stratum_core::ast::Module> {
+    let module = stratum_core::Parser::parse_module(source).map_err(|errors| {
+        let error_msgs: Vec<String> = errors.iter().map(|e| format!("{}", e)).collect();
+        anyhow!("Parse errors:\n{}", error_msgs.join("\n"))
+    })?;
+
+    let mut type_checker = stratum_core::TypeChecker::new();
+    let type_result = type_checker.check_module(&module);
+    if !type_result.errors.is_empty() {
+        let error_msgs: Vec<String> = type_result
+            .errors
+            .iter()
+            .map(|e| format!("{}", e))
+            .collect();
+        return Err(anyhow!("Type errors:\n{}", error_msgs.join("\n")));
+    }
+
+    Ok(module)
+}
+
+/// Hot reload the source file if it was edited while paused: report what
+/// changed on the debug console and move breakpoints with their functions
+fn hot_reload_source<R: std::io::Read, W: std::io::Write>(
+    adapter: &mut StratumDebugAdapter,
+    server: &mut Server<R, W>,
+) -> Result<()> {
+    let report = match adapter.reload_source() {
+        None => return Ok(()),
+        Some(Ok(report)) => report,
+        Some(Err(e)) => {
+            server.send_event(StratumDebugAdapter::create_output_event(
+                OutputEventCategory::Stderr,
+                format!("Hot reload failed: {}\n", e),
+            ))?;
+            return Ok(());
+        }
+    };
+
+    server.send_event(StratumDebugAdapter::create_output_event(
+        OutputEventCategory::Console,
+        hot_reload_summary(&report),
+    ))?;
+    for (id, line) in &report.moved_breakpoints {
+        if let Some(breakpoint) = adapter.breakpoint_map.get_mut(id) {
+            breakpoint.line = Some(i64::from(*line));
+            server.send_event(Event::Breakpoint(BreakpointEventBody {
+                reason: BreakpointEventReason::Changed,
+                breakpoint: breakpoint.clone(),
+            }))?;
+        }
+    }
+    Ok(())
+}
+
+/// Debug console lines describing a hot reload
+fn hot_reload_summary(report: &HotReload) -> String {
+    let mut lines = Vec::new();
+    if !report.reloaded.is_empty() {
+        lines.push(format!("Reloaded {}", report.reloaded.join(", ")));
+    }
+    if !report.added.is_empty() {
+        lines.push(format!("Added {}", report.added.join(", ")));
+    }
+    for (name, reason) in &report.skipped {
+        lines.push(format!("Could not reload {}: {}", name, reason));
+    }
+    if lines.is_empty() {
+        lines.push("No functions changed; edits to top-level code apply on restart".to_string());
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+/// Why a breakpoint's condition or hit condition is invalid, if it is
+fn breakpoint_error(condition: Option<&str>, hit_condition: Option<&str>) -> Option<String> {
+    if let Some(condition) = condition {
+        if let Err(errors) = stratum_core::Parser::parse_expression(condition) {
+            let error_msgs: Vec<String> = errors.iter().map(|e| format!("{}", e)).collect();
+            return Some(format!("Invalid condition: {}", error_msgs.join("; ")));
+        }
+    }
+    match hit_condition {
+        Some(hit_condition) if HitCondition::parse(hit_condition).is_none() => Some(format!(
+            "Invalid hit count '{}': expected a number, optionally after ==, >, >=, <, <= or %",
+            hit_condition
+        )),
+        _ => None,
+    }
+}
+
+/// Send the messages logged by logpoints during the last run
+fn send_log_messages<R: std::io::Read, W: std::io::Write>(
+    adapter: &mut StratumDebugAdapter,
+    server: &mut Server<R, W>,
+) -> Result<()> {
+    let Some(vm) = adapter.vm.as_mut() else {
+        return Ok(());
+    };
+    for message in vm.take_log_messages() {
+        server.send_event(StratumDebugAdapter::create_output_event(
+            OutputEventCategory::Console,
+            format!("{}\n", message),
+        ))?;
+    }
+    Ok(())
+}
+
 fn handle_execution_result<R: std::io::Read, W: std::io::Write>(
     adapter: &mut StratumDebugAdapter,
     server: &mut Server<R, W>,
     result: DebugStepResult,
 ) -> Result<()> {
+    send_log_messages(adapter, server)?;
     match result {
         DebugStepResult::Paused(state) => {
             adapter.current_state = Some(state.clone());
@@ -637,5 +882,55 @@ mod tests {
         let caps = StratumDebugAdapter::get_capabilities();
         assert!(caps.supports_configuration_done_request.unwrap_or(false));
         assert!(caps.support_terminate_debuggee.unwrap_or(false));
+        assert!(caps.supports_conditional_breakpoints.unwrap_or(false));
+        assert!(caps.supports_hit_conditional_breakpoints.unwrap_or(false));
+        assert!(caps.supports_log_points.unwrap_or(false));
+        assert!(caps.supports_evaluate_for_hovers.unwrap_or(false));
+        assert!(caps.supports_restart_frame.unwrap_or(false));
+        assert!(caps.supports_step_back.unwrap_or(false));
+    }
+
+    #[test]
+    fn test_hot_reload_summary() {
+        let report = HotReload {
+            reloaded: vec!["double".to_string(), "main".to_string()],
+            skipped: vec![("add".to_string(), "its parameter count changed".to_string())],
+            ..HotReload::default()
+        };
+        assert_eq!(
+            hot_reload_summary(&report),
+            "Reloaded double, main\nCould not reload add: its parameter count changed\n"
+        );
+        assert!(hot_reload_summary(&HotReload::default()).starts_with("No functions changed"));
+    }
+
+    #[test]
+    fn test_expandable_variables() {
+        use stratum_core::bytecode::Value;
+
+        let mut adapter = StratumDebugAdapter::new();
+        let list = Value::list(vec![Value::Int(1), Value::Int(2)]);
+
+        let variable = adapter.to_dap_variable(&DebugVariable::from_value("xs".to_string(), &list));
+        assert_eq!(variable.value, "[1, 2]");
+        assert_ne!(variable.variables_reference, 0);
+        let children = &adapter.variable_scopes[&variable.variables_reference];
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[1].name, "[1]");
+
+        let scalar = DebugVariable::from_value("n".to_string(), &Value::Int(3));
+        assert_eq!(adapter.to_dap_variable(&scalar).variables_reference, 0);
+    }
+
+    #[test]
+    fn test_breakpoint_error() {
+        assert!(breakpoint_error(None, None).is_none());
+        assert!(breakpoint_error(Some("x > 1"), Some(">= 2")).is_none());
+        assert!(breakpoint_error(Some("x >"), None)
+            .unwrap()
+            .starts_with("Invalid condition"));
+        assert!(breakpoint_error(None, Some("sometimes"))
+            .unwrap()
+            .starts_with("Invalid hit count"));
     }
 }
@@ -0,0 +1,431 @@
+//! `--message-format json`: structured diagnostics for editors and CI
+//!
+//! With `--message-format json`, `run`, `check`, `test` and `fmt` print each
+//! problem they find as one JSON object per line on stdout, rather than as
+//! human-readable text:
+//!
+//! ```json
+//! {"file":"main.strat","kind":"type","code":"E0100","severity":"error",
+//!  "message":"type mismatch: expected `Int`, found `String`",
+//!  "spans":[{"line":3,"column":9,"end_line":3,"end_column":14,"label":null,"primary":true}],
+//!  "suggestions":[{"message":"convert with int()","replacement":null}]}
+//! ```
+//!
+//! Lines and columns are 1-based; a suggestion's `replacement` holds the text
+//! and span to substitute when the fix is mechanical. Language errors carry
+//! their stable code (see [`stratum_core::error_codes`]); test failures and
+//! unformatted files use `test-failed` and `unformatted`.
+
+use std::path::Path;
+
+use stratum_core::lexer::{LineIndex, Span};
+
+/// How diagnostics are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MessageFormat {
+    /// Human-readable text
+    #[default]
+    Human,
+    /// One JSON object per line on stdout
+    Json,
+}
+
+impl std::str::FromStr for MessageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "human" => Ok(MessageFormat::Human),
+            "json" => Ok(MessageFormat::Json),
+            _ => Err(format!(
+                "Unknown message format '{s}' (expected human or json)"
+            )),
+        }
+    }
+}
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    fn name(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+/// Where a diagnostic points
+#[derive(Debug, Clone)]
+enum Location {
+    /// A span of the source
+    Span(Span),
+    /// A whole line, for runtime errors, whose stack frames only know lines
+    Line(u32),
+}
+
+/// A location a diagnostic points at, with an optional label
+#[derive(Debug, Clone)]
+struct Label {
+    location: Location,
+    message: Option<String>,
+    primary: bool,
+}
+
+/// A suggested fix
+#[derive(Debug, Clone)]
+struct Suggestion {
+    message: String,
+    replacement: Option<(Span, String)>,
+}
+
+/// A problem found in a source file
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    /// Which stage found the problem: parse, type, compile, runtime, test
+    /// or fmt
+    kind: &'static str,
+    code: String,
+    severity: Severity,
+    message: String,
+    /// The human-readable message, as printed without `--message-format
+    /// json`, before the `error[code]:` prefix
+    display: String,
+    labels: Vec<Label>,
+    suggestions: Vec<Suggestion>,
+}
+
+impl Diagnostic {
+    fn new(
+        kind: &'static str,
+        code: impl Into<String>,
+        message: String,
+        display: String,
+        location: Location,
+    ) -> Self {
+        Self {
+            kind,
+            code: code.into(),
+            severity: Severity::Error,
+            message,
+            display,
+            labels: vec![Label {
+                location,
+                message: None,
+                primary: true,
+            }],
+            suggestions: Vec::new(),
+        }
+    }
+
+    fn with_hint(mut self, hint: Option<&String>) -> Self {
+        if let Some(hint) = hint {
+            self.suggestions.push(Suggestion {
+                message: hint.clone(),
+                replacement: None,
+            });
+        }
+        self
+    }
+
+    /// A parse error
+    pub fn parse(error: &stratum_core::parser::ParseError) -> Self {
+        Self::new(
+            "parse",
+            error.kind.code(),
+            error.kind.to_string(),
+            error.to_string(),
+            Location::Span(error.span),
+        )
+        .with_hint(error.hint.as_ref())
+    }
+
+    /// A type error, with the locations it relates to as secondary spans
+    pub fn type_error(error: &stratum_core::types::TypeError) -> Self {
+        let mut diagnostic = Self::new(
+            "type",
+            error.kind.code(),
+            error.kind.to_string(),
+            error.to_string(),
+            Location::Span(error.span),
+        )
+        .with_hint(error.hint.as_ref());
+        diagnostic
+            .labels
+            .extend(error.related.iter().map(|(span, message)| Label {
+                location: Location::Span(*span),
+                message: Some(message.clone()),
+                primary: false,
+            }));
+        diagnostic
+    }
+
+    /// A compile error
+    pub fn compile(error: &stratum_core::bytecode::CompileError) -> Self {
+        Self::new(
+            "compile",
+            error.kind.code(),
+            error.kind.to_string(),
+            error.to_string(),
+            Location::Span(error.span),
+        )
+        .with_hint(error.hint.as_ref())
+    }
+
+    /// A runtime error, pointing at the innermost line of `file` in its
+    /// stack trace
+    pub fn runtime(error: &stratum_core::vm::RuntimeError, file: &str) -> Self {
+        let line = error
+            .stack_trace
+            .iter()
+            .find(|frame| {
+                frame
+                    .source
+                    .as_deref()
+                    .map_or(true, |source| source == file)
+            })
+            .map_or(1, |frame| frame.line.max(1));
+        Self::new(
+            "runtime",
+            error.kind.code(),
+            error.kind.to_string(),
+            error.to_string(),
+            Location::Line(line),
+        )
+    }
+
+    /// A failed test, pointing at the test function
+    pub fn test_failure(name: &str, error: Option<&str>, span: Span) -> Self {
+        let message = match error {
+            Some(error) => format!("test '{name}' failed: {error}"),
+            None => format!("test '{name}' failed"),
+        };
+        Self::new(
+            "test",
+            "test-failed",
+            message.clone(),
+            message,
+            Location::Span(span),
+        )
+    }
+
+    /// A file `stratum fmt --check` would reformat, with the formatted text
+    /// as a suggested replacement of the whole file
+    pub fn unformatted(source: &str, formatted: String) -> Self {
+        let whole = Span::new(0, source.len() as u32);
+        let mut diagnostic = Self::new(
+            "fmt",
+            "unformatted",
+            "file is not formatted".to_string(),
+            "file is not formatted".to_string(),
+            Location::Span(whole),
+        );
+        diagnostic.severity = Severity::Warning;
+        diagnostic.suggestions.push(Suggestion {
+            message: "run `stratum fmt`".to_string(),
+            replacement: Some((whole, formatted)),
+        });
+        diagnostic
+    }
+
+    /// The JSON object for this diagnostic in `file`
+    pub fn to_json(&self, file: &Path, index: &LineIndex) -> serde_json::Value {
+        let position = |span: Span| {
+            let start = index.location(span.start);
+            let end = index.location(span.end);
+            (start.line, start.column, end.line, end.column)
+        };
+        let spans: Vec<_> = self
+            .labels
+            .iter()
+            .map(|label| {
+                let (line, column, end_line, end_column) = match label.location {
+                    Location::Span(span) => position(span),
+                    Location::Line(line) => (line, 1, line, 1),
+                };
+                serde_json::json!({
+                    "line": line,
+                    "column": column,
+                    "end_line": end_line,
+                    "end_column": end_column,
+                    "label": label.message,
+                    "primary": label.primary,
+                })
+            })
+            .collect();
+        let suggestions: Vec<_> = self
+            .suggestions
+            .iter()
+            .map(|suggestion| {
+                let replacement = suggestion.replacement.as_ref().map(|(span, text)| {
+                    let (line, column, end_line, end_column) = position(*span);
+                    serde_json::json!({
+                        "line": line,
+                        "column": column,
+                        "end_line": end_line,
+                        "end_column": end_column,
+                        "text": text,
+                    })
+                });
+                serde_json::json!({
+                    "message": suggestion.message,
+                    "replacement": replacement,
+                })
+            })
+            .collect();
+        serde_json::json!({
+            "file": file.display().to_string(),
+            "kind": self.kind,
+            "code": self.code,
+            "severity": self.severity.name(),
+            "message": self.message,
+            "spans": spans,
+            "suggestions": suggestions,
+        })
+    }
+}
+
+/// Prints the diagnostics found in one source file
+pub struct Reporter<'a> {
+    format: MessageFormat,
+    file: &'a Path,
+    index: LineIndex,
+}
+
+impl<'a> Reporter<'a> {
+    /// A reporter for `source`, read from `file`
+    pub fn new(format: MessageFormat, file: &'a Path, source: &str) -> Self {
+        Self {
+            format,
+            file,
+            index: LineIndex::new(source),
+        }
+    }
+
+    /// Whether diagnostics are printed as JSON
+    pub fn is_json(&self) -> bool {
+        self.format == MessageFormat::Json
+    }
+
+    /// Print a diagnostic as a JSON line; human-readable diagnostics are
+    /// left to the caller
+    pub fn emit(&self, diagnostic: &Diagnostic) {
+        if self.is_json() {
+            println!("{}", diagnostic.to_json(self.file, &self.index));
+        }
+    }
+
+    /// The error a command fails with after finding `diagnostics`
+    ///
+    /// As JSON, each diagnostic is printed and the error only counts them;
+    /// otherwise the error lists them under `heading`.
+    pub fn fail(
+        &self,
+        heading: &str,
+        diagnostics: impl IntoIterator<Item = Diagnostic>,
+    ) -> anyhow::Error {
+        let diagnostics: Vec<_> = diagnostics.into_iter().collect();
+        if self.is_json() {
+            for diagnostic in &diagnostics {
+                self.emit(diagnostic);
+            }
+            return anyhow::anyhow!(
+                "{} error(s) in '{}'",
+                diagnostics.len(),
+                self.file.display()
+            );
+        }
+        let mut messages: Vec<String> = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                format!(
+                    "  {}[{}]: {}",
+                    diagnostic.severity.name(),
+                    diagnostic.code,
+                    diagnostic.display
+                )
+            })
+            .collect();
+        if let Some(first) = diagnostics.first() {
+            messages.push(explain_hint(&first.code));
+        }
+        anyhow::anyhow!("{heading}:\n{}", messages.join("\n"))
+    }
+
+    /// The error a program fails with after a runtime error
+    pub fn runtime_error(&self, error: &stratum_core::vm::RuntimeError) -> anyhow::Error {
+        if !self.is_json() {
+            return anyhow::anyhow!(
+                "Runtime error[{}]: {}\n{}",
+                error.kind.code(),
+                error.to_string().trim_end(),
+                explain_hint(error.kind.code())
+            );
+        }
+        self.emit(&Diagnostic::runtime(
+            error,
+            &self.file.display().to_string(),
+        ));
+        anyhow::anyhow!("Runtime error in '{}'", self.file.display())
+    }
+}
+
+/// Where to read more about an error code
+fn explain_hint(code: &str) -> String {
+    format!("For more information about this error, try `stratum explain {code}`.")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_message_format() {
+        assert_eq!("json".parse::<MessageFormat>(), Ok(MessageFormat::Json));
+        assert_eq!("Human".parse::<MessageFormat>(), Ok(MessageFormat::Human));
+        assert!("xml".parse::<MessageFormat>().is_err());
+    }
+
+    #[test]
+    fn test_type_error_json() {
+        let source = "fx main() {\n    let x: Int = \"a\"\n}\n";
+        let module = stratum_core::Parser::parse_module(source).unwrap();
+        let result = stratum_core::TypeChecker::new().check_module(&module);
+        let diagnostic = Diagnostic::type_error(&result.errors[0]);
+
+        let json = diagnostic.to_json(Path::new("main.strat"), &LineIndex::new(source));
+        assert_eq!(json["file"], "main.strat");
+        assert_eq!(json["kind"], "type");
+        assert_eq!(json["code"], "E0100");
+        assert_eq!(json["severity"], "error");
+        assert_eq!(json["spans"][0]["line"], 2);
+        assert_eq!(json["spans"][0]["primary"], true);
+        assert!(!json.to_string().contains('\n'));
+    }
+
+    #[test]
+    fn test_unformatted_suggestion() {
+        let source = "let x=1\n";
+        let diagnostic = Diagnostic::unformatted(source, "let x = 1\n".to_string());
+        let json = diagnostic.to_json(Path::new("a.strat"), &LineIndex::new(source));
+        assert_eq!(json["severity"], "warning");
+        assert_eq!(json["suggestions"][0]["replacement"]["text"], "let x = 1\n");
+        assert_eq!(json["suggestions"][0]["replacement"]["end_line"], 2);
+    }
+
+    #[test]
+    fn test_human_failure_lists_errors() {
+        let source = "let = 1\n";
+        let errors = stratum_core::Parser::parse_module(source).unwrap_err();
+        let reporter = Reporter::new(MessageFormat::Human, Path::new("a.strat"), source);
+        let error = reporter.fail("Parse errors", errors.iter().map(Diagnostic::parse));
+        let message = error.to_string();
+        assert!(message.starts_with("Parse errors:\n  error[E0"));
+        assert!(message.contains("try `stratum explain E0"));
+    }
+}
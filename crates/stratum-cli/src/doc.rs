@@ -0,0 +1,697 @@
+//! Implementation of the `stratum doc` command.
+//!
+//! Inside a package, the whole package is documented along with the
+//! dependencies whose sources are available locally, each in a versioned
+//! directory of the output so documentation of older releases is kept:
+//!
+//! ```text
+//! target/doc/
+//! ├── index.html            # Redirects to the package's documentation
+//! ├── app/1.2.0/
+//! │   ├── index.html
+//! │   ├── main.html
+//! │   ├── util.text.html
+//! │   └── search-index.json
+//! └── json/2.0.1/           # A dependency, linked from app's sidebar
+//! ```
+//!
+//! Outside a package, the given file or the `.strat` files under the given
+//! directory are documented directly into the output directory.
+//!
+//! With `--book`, the package's guide is generated instead: the Markdown
+//! chapters under its `docs/` directory, in the order of `docs/SUMMARY.md` if
+//! there is one, together with its API documentation in `book/` of the
+//! output directory. `stratum` code blocks in chapters are run and their
+//! output shown beside them.
+//!
+//! With `--coverage`, nothing is generated; instead the public functions,
+//! structs and enums without doc comments are reported per module.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use stratum_core::doc::{
+    generate_search_index, Book, BookGenerator, DependencyDoc, DocCoverage, DocExtractor,
+    DocumentedModule, HtmlGenerator, HtmlOptions, MarkdownGenerator, ProjectDoc,
+};
+use stratum_pkg::registry::RegistryConfig;
+use stratum_pkg::{Manifest, PackageStructure, Resolver, TargetDir, Workspace};
+
+/// Generate documentation for `path`, writing it to `output` or the doc
+/// directory of the package's target directory.
+///
+/// Private items, those named with a leading underscore, are left out
+/// unless `private` is set. With `book`, the package's book is generated.
+pub fn generate_documentation(
+    path: &Path,
+    output: Option<PathBuf>,
+    format: &str,
+    open: bool,
+    private: bool,
+    book: bool,
+) -> Result<()> {
+    if !path.exists() {
+        anyhow::bail!("Path '{}' does not exist", path.display());
+    }
+    let is_html = format != "markdown" && format != "md";
+
+    // Determine output directory: doc/ in the target directory of a package,
+    // or next to the sources
+    let source_dir = if path.is_file() {
+        path.parent().unwrap_or(path)
+    } else {
+        path
+    };
+    let package = if path.is_dir() {
+        std::fs::canonicalize(path)
+            .ok()
+            .and_then(|dir| PackageStructure::find(dir).ok())
+    } else {
+        None
+    };
+    let output_dir = output
+        .or_else(|| {
+            let source_dir = std::fs::canonicalize(source_dir).ok()?;
+            TargetDir::find(&source_dir).map(|target| target.doc())
+        })
+        .unwrap_or_else(|| source_dir.join("doc"));
+
+    let generated = match &package {
+        Some(package) if book => document_book(package, &output_dir.join("book"), private)?,
+        None if book => anyhow::bail!("--book can only be used inside a package"),
+        Some(package) => document_package(
+            package,
+            &RegistryConfig::default().cache_dir,
+            &output_dir,
+            is_html,
+            private,
+        )?,
+        None => document_files(path, &output_dir, is_html, private)?,
+    };
+
+    // Open in browser if requested
+    if open {
+        if let Err(e) = open_in_browser(&generated[0]) {
+            eprintln!("Warning: Could not open browser: {}", e);
+        }
+    }
+
+    println!("\nDocumentation generated in: {}", output_dir.display());
+    if is_html {
+        println!("Features enabled: cross-linking, search");
+    }
+
+    Ok(())
+}
+
+/// Report which public functions, structs and enums of `path` lack doc
+/// comments, per module.
+///
+/// Fails if `fail_under` is given and overall coverage is below it.
+pub fn report_coverage(path: &Path, fail_under: Option<f64>) -> Result<()> {
+    if !path.exists() {
+        anyhow::bail!("Path '{}' does not exist", path.display());
+    }
+    let package = if path.is_dir() {
+        std::fs::canonicalize(path)
+            .ok()
+            .and_then(|dir| PackageStructure::find(dir).ok())
+    } else {
+        None
+    };
+    let project = match &package {
+        Some(package) => package_project(package, false)?,
+        None => files_project(path, false)?,
+    };
+
+    let coverage = DocCoverage::of(&project);
+    print!("{}", format_coverage(&coverage));
+
+    if let Some(threshold) = fail_under {
+        let percent = coverage.percentage();
+        if percent < threshold {
+            anyhow::bail!(
+                "Documentation coverage {percent:.1}% is below the --fail-under threshold of {threshold:.1}%"
+            );
+        }
+    }
+    Ok(())
+}
+
+/// The coverage report: one line per module with the items missing docs,
+/// then the total.
+fn format_coverage(coverage: &DocCoverage) -> String {
+    let width = coverage
+        .modules
+        .iter()
+        .map(|m| m.module.len())
+        .max()
+        .unwrap_or(0)
+        .max("Module".len());
+    let mut output = format!("{:<width$}  {:>9}  {:>7}\n", "Module", "Items", "Cover");
+    for module in &coverage.modules {
+        output.push_str(&format!(
+            "{:<width$}  {:>9}  {:>6.1}%",
+            module.module,
+            format!("{}/{}", module.documented(), module.total),
+            module.percentage()
+        ));
+        if !module.undocumented.is_empty() {
+            output.push_str(&format!("  missing: {}", module.undocumented.join(", ")));
+        }
+        output.push('\n');
+    }
+    output.push_str(&format!(
+        "{:<width$}  {:>9}  {:>6.1}%\n",
+        "Total",
+        format!("{}/{}", coverage.documented(), coverage.total()),
+        coverage.percentage()
+    ));
+    output
+}
+
+/// Document `package` and its locally available dependencies into versioned
+/// directories of `output_dir`, finding registry packages in `cache_dir`.
+///
+/// Returns the generated files, the top-level index first.
+fn document_package(
+    package: &PackageStructure,
+    cache_dir: &Path,
+    output_dir: &Path,
+    is_html: bool,
+    private: bool,
+) -> Result<Vec<PathBuf>> {
+    let root = &package.layout.root;
+    let mut manifest = package.manifest.clone();
+    if let Ok(workspace) = Workspace::find(root) {
+        workspace.apply_patches(root, &mut manifest);
+    }
+
+    let mut project = package_project(package, private)?;
+    if project.modules.is_empty() {
+        anyhow::bail!("No documentation was generated");
+    }
+
+    let resolver = Resolver::new();
+    let resolved = resolver.resolve(&manifest)?;
+    for manifest_path in resolver
+        .locate_manifests(&resolved, root, cache_dir)
+        .values()
+    {
+        let dependency = match manifest_path.parent().map(PackageStructure::load) {
+            Some(Ok(dependency)) => dependency,
+            Some(Err(e)) => {
+                eprintln!(
+                    "warning: skipping documentation of {}: {e}",
+                    manifest_path.display()
+                );
+                continue;
+            }
+            None => continue,
+        };
+        // Private items of dependencies are never documented
+        let dependency_project = package_project(&dependency, false)?;
+        if dependency_project.modules.is_empty() {
+            continue;
+        }
+        write_project(
+            &dependency_project,
+            &versioned_dir(output_dir, &dependency.manifest),
+            is_html,
+        )?;
+
+        let name = &dependency.manifest.package.name;
+        let version = &dependency.manifest.package.version;
+        project.add_dependency(DependencyDoc {
+            name: name.clone(),
+            version: version.clone(),
+            href: format!("../../{name}/{version}/index.{}", extension(is_html)),
+        });
+    }
+
+    let mut generated = write_project(
+        &project,
+        &versioned_dir(output_dir, &package.manifest),
+        is_html,
+    )?;
+
+    // The top-level index leads to the most recently documented version
+    let target = format!(
+        "{}/{}/index.{}",
+        package.manifest.package.name,
+        package.manifest.package.version,
+        extension(is_html)
+    );
+    let (index_file, index_content) = if is_html {
+        (output_dir.join("index.html"), redirect_page(&target))
+    } else {
+        (
+            output_dir.join("index.md"),
+            format!(
+                "# Documentation\n\n- [{} {}]({target})\n",
+                package.manifest.package.name, package.manifest.package.version
+            ),
+        )
+    };
+    write_file(&index_file, &index_content)?;
+    generated.insert(0, index_file);
+    Ok(generated)
+}
+
+/// Generate the book of `package` from the chapters in its `docs/`
+/// directory, with the package's API documentation, into `output_dir`.
+///
+/// Returns the generated files, the top-level index first.
+fn document_book(
+    package: &PackageStructure,
+    output_dir: &Path,
+    private: bool,
+) -> Result<Vec<PathBuf>> {
+    let docs = package.layout.root.join("docs");
+    if !docs.is_dir() {
+        anyhow::bail!(
+            "No book chapters found: '{}' does not exist",
+            docs.display()
+        );
+    }
+    let book = Book::load(&docs, &package.manifest.package.name)
+        .with_context(|| format!("Failed to load the book in '{}'", docs.display()))?;
+    if book.chapters.is_empty() {
+        anyhow::bail!("No book chapters found in '{}'", docs.display());
+    }
+    let project = package_project(package, private)?;
+
+    let runner = |source: &str| {
+        run_example(source).map_err(|error| {
+            eprintln!("warning: book example failed: {error}");
+            error
+        })
+    };
+    let mut generator = BookGenerator::new(&book).with_runner(&runner);
+    if !project.modules.is_empty() {
+        generator = generator.with_api(&project);
+    }
+
+    let mut generated = Vec::new();
+    for page in generator.generate() {
+        let file = output_dir.join(&page.path);
+        if let Some(parent) = file.parent() {
+            std::fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create output directory {}", parent.display())
+            })?;
+        }
+        write_file(&file, &page.content)?;
+        if page.path == "index.html" {
+            generated.insert(0, file);
+        } else {
+            generated.push(file);
+        }
+    }
+    Ok(generated)
+}
+
+/// Run a book example as a program of its own, returning what it printed or
+/// the errors it failed with.
+fn run_example(source: &str) -> std::result::Result<String, String> {
+    let module = stratum_core::Parser::parse_module(source).map_err(|e| join_errors(&e))?;
+    let type_result = stratum_core::TypeChecker::new().check_module(&module);
+    if !type_result.errors.is_empty() {
+        return Err(join_errors(&type_result.errors));
+    }
+    let function = stratum_core::Compiler::with_source("<example>".to_string())
+        .compile_module(&module)
+        .map_err(|e| join_errors(&e))?;
+
+    let (result, output) = stratum_core::with_output_capture(|| {
+        let mut vm = stratum_core::VM::new();
+        vm.run(function).map_err(|e| e.to_string())?;
+        if vm.globals().contains_key("main") {
+            let main_call =
+                stratum_core::Parser::parse_expression("main()").map_err(|e| join_errors(&e))?;
+            let main_fn = stratum_core::Compiler::new()
+                .compile_expression(&main_call)
+                .map_err(|e| join_errors(&e))?;
+            vm.run(main_fn).map_err(|e| e.to_string())?;
+        }
+        Ok::<(), String>(())
+    });
+
+    let printed = output.stdout.join("\n");
+    match result {
+        Ok(()) => Ok(printed),
+        Err(error) if printed.is_empty() => Err(error),
+        Err(error) => Err(format!("{printed}\n{error}")),
+    }
+}
+
+fn join_errors(errors: &[impl std::fmt::Display]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Document the file at `path`, or the `.strat` files under it, into
+/// `output_dir`.
+///
+/// Returns the generated files, the index first if there is one.
+fn document_files(
+    path: &Path,
+    output_dir: &Path,
+    is_html: bool,
+    private: bool,
+) -> Result<Vec<PathBuf>> {
+    let project = files_project(path, private)?;
+    if project.modules.is_empty() {
+        anyhow::bail!("No documentation was generated");
+    }
+
+    write_project(&project, output_dir, is_html)
+}
+
+/// Documentation of the file at `path`, or of the `.strat` files under it.
+fn files_project(path: &Path, private: bool) -> Result<ProjectDoc> {
+    // Collect source files
+    let files = if path.is_file() {
+        vec![path.to_path_buf()]
+    } else {
+        crate::collect_stratum_files(&path.to_path_buf())?
+    };
+    if files.is_empty() {
+        anyhow::bail!("No .strat files found in '{}'", path.display());
+    }
+
+    // Determine project name
+    let project_name = path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or("Project");
+
+    let mut project = ProjectDoc::new(project_name);
+    for file in &files {
+        let module_name = file
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("unknown");
+        if let Some(module) = extract_module(file, module_name, private)? {
+            project.add_module(module);
+        }
+    }
+    Ok(project)
+}
+
+/// Documentation of every module in the source directory of `package`.
+fn package_project(package: &PackageStructure, private: bool) -> Result<ProjectDoc> {
+    let mut project = ProjectDoc::new(&package.manifest.package.name)
+        .with_version(&package.manifest.package.version);
+    let files = package
+        .layout
+        .source_files()
+        .map_err(|e| anyhow::anyhow!("Failed to read package sources: {e}"))?;
+    for file in &files {
+        let Some(module_name) = package.layout.module_path(file) else {
+            continue;
+        };
+        if let Some(module) = extract_module(file, &module_name, private)? {
+            project.add_module(module);
+        }
+    }
+    Ok(project)
+}
+
+/// Extract the documentation of the module in `file`, reporting parse
+/// errors and returning `None` if it does not parse.
+fn extract_module(file: &Path, name: &str, private: bool) -> Result<Option<DocumentedModule>> {
+    let source = std::fs::read_to_string(file)
+        .with_context(|| format!("Failed to read '{}'", file.display()))?;
+    let module = match stratum_core::Parser::parse_module(&source) {
+        Ok(m) => m,
+        Err(errors) => {
+            eprintln!("Parse errors in '{}':", file.display());
+            for e in &errors {
+                eprintln!("  {}", e);
+            }
+            return Ok(None);
+        }
+    };
+
+    let mut doc_module = DocExtractor::extract(&module, name);
+    if !private {
+        doc_module.remove_private();
+    }
+    Ok(Some(doc_module))
+}
+
+/// Write the pages of `project` to `dir`, returning the generated files with
+/// the index first if there is one.
+fn write_project(project: &ProjectDoc, dir: &Path, is_html: bool) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create output directory {}", dir.display()))?;
+
+    // HTML options with search and cross-linking enabled
+    let options = HtmlOptions {
+        enable_search: is_html,
+        enable_crosslinks: is_html,
+    };
+
+    let mut generated = Vec::new();
+    for module in &project.modules {
+        let content = if is_html {
+            HtmlGenerator::generate_with_project(module, project, &options)
+        } else {
+            MarkdownGenerator::generate(module)
+        };
+        let output_file = dir.join(format!("{}.{}", module.name, extension(is_html)));
+        write_file(&output_file, &content)?;
+        generated.push(output_file);
+    }
+
+    if is_html {
+        // HTML index with full project view
+        let index_file = dir.join("index.html");
+        write_file(
+            &index_file,
+            &HtmlGenerator::generate_index(project, &options),
+        )?;
+        generated.insert(0, index_file);
+
+        // Generate search index JSON
+        write_file(
+            &dir.join("search-index.json"),
+            &generate_search_index(project),
+        )?;
+    } else if project.modules.len() > 1 || project.version.is_some() {
+        let index_file = dir.join("index.md");
+        write_file(&index_file, &markdown_index(project))?;
+        generated.insert(0, index_file);
+    }
+
+    Ok(generated)
+}
+
+/// The index page of a project documented as Markdown.
+fn markdown_index(project: &ProjectDoc) -> String {
+    let mut output = match &project.version {
+        Some(version) => format!("# {} {version}\n\n", project.name),
+        None => String::from("# Documentation\n\n"),
+    };
+    output.push_str("## Modules\n\n");
+    for module in &project.modules {
+        output.push_str(&format!("- [{}]({}.md)\n", module.name, module.name));
+    }
+    if !project.dependencies.is_empty() {
+        output.push_str("\n## Dependencies\n\n");
+        for dependency in &project.dependencies {
+            output.push_str(&format!(
+                "- [{} {}]({})\n",
+                dependency.name, dependency.version, dependency.href
+            ));
+        }
+    }
+    output
+}
+
+/// A page that immediately redirects to `target`.
+fn redirect_page(target: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n  <meta charset=\"UTF-8\">\n  \
+         <meta http-equiv=\"refresh\" content=\"0; url={target}\">\n  \
+         <title>Redirecting</title>\n</head>\n<body>\n  \
+         <a href=\"{target}\">{target}</a>\n</body>\n</html>\n"
+    )
+}
+
+/// The directory the documentation of the package with `manifest` goes in.
+fn versioned_dir(output_dir: &Path, manifest: &Manifest) -> PathBuf {
+    output_dir
+        .join(&manifest.package.name)
+        .join(&manifest.package.version)
+}
+
+fn extension(is_html: bool) -> &'static str {
+    if is_html {
+        "html"
+    } else {
+        "md"
+    }
+}
+
+fn write_file(path: &Path, content: &str) -> Result<()> {
+    std::fs::write(path, content)
+        .with_context(|| format!("Failed to write '{}'", path.display()))?;
+    println!("Generated: {}", path.display());
+    Ok(())
+}
+
+/// Open a file in the default browser
+fn open_in_browser(path: &Path) -> Result<()> {
+    let url = format!("file://{}", path.canonicalize()?.display());
+
+    #[cfg(target_os = "macos")]
+    {
+        std::process::Command::new("open").arg(&url).spawn()?;
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        std::process::Command::new("xdg-open").arg(&url).spawn()?;
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        std::process::Command::new("cmd")
+            .args(["/C", "start", "", &url])
+            .spawn()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::write_package;
+
+    #[test]
+    fn test_document_package() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = dir.path().join("app");
+        let json = dir.path().join("json");
+        write_package(
+            &app,
+            "app",
+            "1.2.0",
+            "\n[dependencies]\njson = { path = \"../json\" }\n",
+        );
+        write_package(&json, "json", "2.0.1", "");
+        std::fs::create_dir_all(app.join("src/util")).unwrap();
+        std::fs::write(
+            app.join("src/main.strat"),
+            "/// Run the app.\nfx run() {}\n\nfx _helper() {}\n",
+        )
+        .unwrap();
+        std::fs::write(app.join("src/util/text.strat"), "fx shout() {}\n").unwrap();
+        std::fs::write(
+            json.join("src/lib.strat"),
+            "fx parse() {}\n\nfx _scan() {}\n",
+        )
+        .unwrap();
+        let cache = tempfile::tempdir().unwrap();
+        let out = dir.path().join("doc");
+
+        let package = PackageStructure::load(&app).unwrap();
+        let generated = document_package(&package, cache.path(), &out, true, false).unwrap();
+        assert_eq!(generated[0], out.join("index.html"));
+        let redirect = std::fs::read_to_string(out.join("index.html")).unwrap();
+        assert!(redirect.contains("url=app/1.2.0/index.html"));
+
+        let site = out.join("app").join("1.2.0");
+        assert!(site.join("search-index.json").is_file());
+        let main = std::fs::read_to_string(site.join("main.html")).unwrap();
+        assert!(main.contains("run"));
+        assert!(!main.contains("_helper"));
+        assert!(site.join("util.text.html").is_file());
+        let index = std::fs::read_to_string(site.join("index.html")).unwrap();
+        assert!(index.contains("../../json/2.0.1/index.html"), "{index}");
+
+        let dependency = std::fs::read_to_string(out.join("json/2.0.1/lib.html")).unwrap();
+        assert!(dependency.contains("parse"));
+        assert!(!dependency.contains("_scan"));
+
+        // --private includes the package's private items, not its dependencies'
+        document_package(&package, cache.path(), &out, true, true).unwrap();
+        let main = std::fs::read_to_string(site.join("main.html")).unwrap();
+        assert!(main.contains("_helper"));
+        let dependency = std::fs::read_to_string(out.join("json/2.0.1/lib.html")).unwrap();
+        assert!(!dependency.contains("_scan"));
+    }
+
+    #[test]
+    fn test_document_book() {
+        let dir = tempfile::tempdir().unwrap();
+        write_package(dir.path(), "app", "0.3.0", "");
+        std::fs::write(dir.path().join("src/main.strat"), "/// Run.\nfx run() {}\n").unwrap();
+        std::fs::create_dir_all(dir.path().join("docs/guide")).unwrap();
+        std::fs::write(
+            dir.path().join("docs/SUMMARY.md"),
+            "- [Introduction](intro.md)\n  - [Files](guide/files.md)\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("docs/intro.md"), "# Introduction\n").unwrap();
+        std::fs::write(dir.path().join("docs/guide/files.md"), "# Files\n").unwrap();
+        let out = dir.path().join("doc/book");
+
+        let package = PackageStructure::load(dir.path()).unwrap();
+        let generated = document_book(&package, &out, false).unwrap();
+        assert_eq!(generated[0], out.join("index.html"));
+        let redirect = std::fs::read_to_string(out.join("index.html")).unwrap();
+        assert!(redirect.contains("url=intro.html"));
+        let files = std::fs::read_to_string(out.join("guide/files.html")).unwrap();
+        assert!(files.contains("<a href=\"../intro.html\">Introduction</a>"));
+        assert!(out.join("api/main.html").is_file());
+        assert!(out.join("search-index.json").is_file());
+
+        std::fs::remove_dir_all(dir.path().join("docs")).unwrap();
+        assert!(document_book(&package, &out, false).is_err());
+    }
+
+    #[test]
+    fn test_format_coverage() {
+        let mut project = ProjectDoc::new("app");
+        for (name, source) in [
+            (
+                "main",
+                "/// Run.\nfx run() {}\nfx stop() {}\nstruct Config {}",
+            ),
+            ("util.text", "/// Shout.\nfx shout() {}"),
+        ] {
+            let module = stratum_core::Parser::parse_module(source).unwrap();
+            project.add_module(DocExtractor::extract(&module, name));
+        }
+        assert_eq!(
+            format_coverage(&DocCoverage::of(&project)),
+            "Module         Items    Cover\n\
+             main             1/3    33.3%  missing: stop, Config\n\
+             util.text        1/1   100.0%\n\
+             Total            2/4    50.0%\n"
+        );
+    }
+
+    #[test]
+    fn test_document_package_markdown() {
+        let dir = tempfile::tempdir().unwrap();
+        write_package(dir.path(), "app", "0.3.0", "");
+        std::fs::write(dir.path().join("src/main.strat"), "fx run() {}\n").unwrap();
+        let cache = tempfile::tempdir().unwrap();
+        let out = dir.path().join("doc");
+
+        let package = PackageStructure::load(dir.path()).unwrap();
+        document_package(&package, cache.path(), &out, false, false).unwrap();
+        let index = std::fs::read_to_string(out.join("index.md")).unwrap();
+        assert!(index.contains("[app 0.3.0](app/0.3.0/index.md)"));
+        let index = std::fs::read_to_string(out.join("app/0.3.0/index.md")).unwrap();
+        assert!(index.starts_with("# app 0.3.0\n"));
+        assert!(index.contains("- [main](main.md)"));
+        assert!(out.join("app/0.3.0/main.md").is_file());
+    }
+}
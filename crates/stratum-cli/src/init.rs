@@ -134,6 +134,7 @@ fn create_manifest(name: &str) -> Manifest {
             exclude: Vec::new(),
             include: Vec::new(),
             default_run: None,
+            build: None,
         },
         ..Default::default()
     }
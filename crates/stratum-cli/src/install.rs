@@ -0,0 +1,602 @@
+//! Implementation of the `stratum install` and `stratum uninstall` commands.
+//!
+//! Installs the binary targets of a Stratum package as command-line tools.
+//! Everything lives under the Stratum home (`~/.stratum`):
+//!
+//! ```text
+//! ~/.stratum/
+//! ├── bin/<binary>          # Launchers, on PATH
+//! └── tools/
+//!     ├── installed.toml    # Installed packages and where they came from
+//!     └── <package>/        # Built executables, or the package sources
+//! ```
+//!
+//! Each binary is built ahead of time into a native executable. A binary
+//! that cannot be built, or any binary with `--wrap`, is instead run from a
+//! copy of the package with `stratum run`.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use stratum_pkg::registry::{GitHubPackage, RegistryClient};
+use stratum_pkg::{Lockfile, PackageStructure, Workspace, LOCK_FILE};
+
+/// Directory under the Stratum home holding launchers.
+const BIN_DIR: &str = "bin";
+
+/// Directory under the Stratum home holding installed packages.
+const TOOLS_DIR: &str = "tools";
+
+/// File recording the installed packages.
+const INSTALLED_FILE: &str = "installed.toml";
+
+/// Options for installing a package.
+#[derive(Debug)]
+pub struct InstallOptions {
+    /// Package to install: a path, a git URL, or `github:user/repo[@tag]`.
+    pub source: String,
+    /// Install only this binary target.
+    pub bin: Option<String>,
+    /// Run the binaries with `stratum run` instead of building them.
+    pub wrap: bool,
+    /// Replace an existing installation of the package.
+    pub force: bool,
+}
+
+/// Where a package is installed from.
+#[derive(Debug, PartialEq)]
+enum Source {
+    /// A package directory on disk.
+    Path(PathBuf),
+    /// A git repository, cloned at its default branch.
+    Git(String),
+    /// A GitHub release.
+    GitHub(GitHubPackage),
+}
+
+impl Source {
+    /// Interpret an install argument.
+    fn parse(spec: &str) -> Result<Self> {
+        if spec.starts_with("github:") {
+            let pkg = GitHubPackage::parse(spec).map_err(|e| anyhow::anyhow!("{e}"))?;
+            return Ok(Self::GitHub(pkg));
+        }
+        let is_git_url = ["https://", "http://", "ssh://", "git@", "file://"]
+            .iter()
+            .any(|prefix| spec.starts_with(prefix))
+            || spec.ends_with(".git");
+        if is_git_url {
+            return Ok(Self::Git(spec.to_string()));
+        }
+        let path = Path::new(spec);
+        if path.is_dir() {
+            return Ok(Self::Path(path.to_path_buf()));
+        }
+        // `user/repo` is shorthand for a GitHub release
+        if spec.matches('/').count() == 1 && !spec.starts_with('.') {
+            let pkg = GitHubPackage::parse(&format!("github:{spec}"))
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+            return Ok(Self::GitHub(pkg));
+        }
+        Err(anyhow::anyhow!(
+            "Cannot install '{spec}': expected a package directory, a git URL, or github:user/repo"
+        ))
+    }
+}
+
+/// How an installed binary is run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum InstallMode {
+    /// Built ahead of time into a native executable.
+    Aot,
+    /// Run from the package sources with `stratum run`.
+    Wrapped,
+}
+
+/// An installed package.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct InstalledTool {
+    /// Package version.
+    version: String,
+    /// The argument it was installed from.
+    source: String,
+    /// Installed binaries and how each is run.
+    bins: BTreeMap<String, InstallMode>,
+}
+
+/// The record of installed packages, by package name.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct InstalledTools {
+    #[serde(default)]
+    tools: BTreeMap<String, InstalledTool>,
+}
+
+impl InstalledTools {
+    fn path(home: &Path) -> PathBuf {
+        home.join(TOOLS_DIR).join(INSTALLED_FILE)
+    }
+
+    fn load(home: &Path) -> Result<Self> {
+        let path = Self::path(home);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Invalid {}", path.display()))
+    }
+
+    fn save(&self, home: &Path) -> Result<()> {
+        let path = Self::path(home);
+        std::fs::create_dir_all(home.join(TOOLS_DIR))?;
+        let content =
+            toml::to_string_pretty(self).context("Failed to serialize installed tools")?;
+        std::fs::write(&path, content)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// The package that installed binary `bin`, if any.
+    fn owner_of(&self, bin: &str) -> Option<&str> {
+        self.tools
+            .iter()
+            .find(|(_, tool)| tool.bins.contains_key(bin))
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+/// Install a package's binary targets into `home`.
+pub fn install_package(options: InstallOptions, home: &Path) -> Result<()> {
+    let source = Source::parse(&options.source)?;
+
+    // Keep a cloned repository alive until the install is done
+    let mut _clone_dir = None;
+    let package_dir = match &source {
+        Source::Path(path) => path
+            .canonicalize()
+            .with_context(|| format!("Failed to read '{}'", path.display()))?,
+        Source::Git(url) => {
+            let dir = tempfile::tempdir().context("Failed to create temporary directory")?;
+            clone_repository(url, dir.path())?;
+            let path = dir.path().to_path_buf();
+            _clone_dir = Some(dir);
+            path
+        }
+        Source::GitHub(pkg) => {
+            println!("Fetching {pkg}...");
+            let client = RegistryClient::new().map_err(|e| anyhow::anyhow!("{e}"))?;
+            let fetched = client
+                .fetch_package(pkg)
+                .map_err(|e| anyhow::anyhow!("Failed to fetch {pkg}: {e}"))?;
+            fetched.path
+        }
+    };
+
+    let package = PackageStructure::load(&package_dir)
+        .map_err(|e| anyhow::anyhow!("Not a Stratum package: {e}"))?;
+    let name = package.manifest.package.name.clone();
+    let version = package.manifest.package.version.clone();
+
+    let bins: Vec<_> = package
+        .bins()
+        .into_iter()
+        .filter(|bin| {
+            options
+                .bin
+                .as_ref()
+                .map_or(true, |wanted| &bin.name == wanted)
+        })
+        .collect();
+    if bins.is_empty() {
+        return Err(match &options.bin {
+            Some(wanted) => anyhow::anyhow!("Package '{name}' has no binary named '{wanted}'"),
+            None => anyhow::anyhow!("Package '{name}' has no binary targets to install"),
+        });
+    }
+    for bin in &bins {
+        validate_bin_name(&bin.name)?;
+    }
+
+    let mut installed = InstalledTools::load(home)?;
+    if installed.tools.contains_key(&name) {
+        if !options.force {
+            return Err(anyhow::anyhow!(
+                "'{name}' is already installed; use --force to reinstall it"
+            ));
+        }
+        remove_tool(home, &name, &mut installed)?;
+    }
+    for bin in &bins {
+        if let Some(owner) = installed.owner_of(&bin.name) {
+            return Err(anyhow::anyhow!(
+                "Binary '{}' is already installed by '{owner}'",
+                bin.name
+            ));
+        }
+    }
+
+    // Resolve dependencies up front, so a wrapped binary starts in sync
+    let tool_dir = home.join(TOOLS_DIR).join(&name);
+    let sources_dir = tool_dir.join("src");
+    let lock_path = package.layout.root.join(LOCK_FILE);
+    let mut manifest = package.manifest.clone();
+    if let Ok(workspace) = Workspace::find(&package.layout.root) {
+        workspace.apply_patches(&package.layout.root, &mut manifest);
+    }
+    let lockfile = match Lockfile::from_path(&lock_path) {
+        Ok(lockfile) if lockfile.check_sync(&manifest).is_ok() => lockfile,
+        _ => Lockfile::generate(&manifest, false)
+            .map_err(|e| anyhow::anyhow!("Failed to resolve dependencies of '{name}': {e}"))?,
+    };
+
+    let bin_dir = home.join(BIN_DIR);
+    std::fs::create_dir_all(&bin_dir)?;
+    std::fs::create_dir_all(&tool_dir)?;
+
+    let mut modes = BTreeMap::new();
+    for bin in &bins {
+        let built = tool_dir.join(BIN_DIR).join(&bin.name);
+        let aot = !options.wrap
+            && match crate::build_executable(&bin.path, Some(built.clone()), true) {
+                Ok(()) => true,
+                Err(e) => {
+                    eprintln!(
+                        "warning: could not build '{}' ({e}); it will run with `stratum run`",
+                        bin.name
+                    );
+                    false
+                }
+            };
+
+        let target = if aot {
+            Launch::Executable(built)
+        } else {
+            if !sources_dir.exists() {
+                copy_package(&package.layout.root, &sources_dir)?;
+                lockfile
+                    .write(sources_dir.join(LOCK_FILE))
+                    .map_err(|e| anyhow::anyhow!("Failed to write lock file: {e}"))?;
+            }
+            let relative = bin
+                .path
+                .strip_prefix(&package.layout.root)
+                .unwrap_or(&bin.path);
+            Launch::Script {
+                stratum: std::env::current_exe().context("Failed to locate stratum")?,
+                script: sources_dir.join(relative),
+            }
+        };
+        write_launcher(&bin_dir, &bin.name, &target)?;
+        let mode = if aot {
+            InstallMode::Aot
+        } else {
+            InstallMode::Wrapped
+        };
+        modes.insert(bin.name.clone(), mode);
+    }
+
+    let bin_names: Vec<_> = modes.keys().cloned().collect();
+    installed.tools.insert(
+        name.clone(),
+        InstalledTool {
+            version: version.clone(),
+            source: options.source,
+            bins: modes,
+        },
+    );
+    installed.save(home)?;
+
+    println!(
+        "Installed {name} v{version}: {} in {}",
+        bin_names.join(", "),
+        bin_dir.display()
+    );
+    if !path_contains(&bin_dir) {
+        println!("Add {} to your PATH to run them", bin_dir.display());
+    }
+    Ok(())
+}
+
+/// Uninstall a package installed with `stratum install`.
+pub fn uninstall_tool(name: &str, home: &Path) -> Result<()> {
+    let mut installed = InstalledTools::load(home)?;
+    if !installed.tools.contains_key(name) {
+        return Err(anyhow::anyhow!("'{name}' is not installed"));
+    }
+    remove_tool(home, name, &mut installed)?;
+    installed.save(home)?;
+    println!("Uninstalled {name}");
+    Ok(())
+}
+
+/// List the installed packages and their binaries.
+pub fn list_tools(home: &Path) -> Result<()> {
+    let installed = InstalledTools::load(home)?;
+    if installed.tools.is_empty() {
+        println!("No tools installed");
+        return Ok(());
+    }
+    for (name, tool) in &installed.tools {
+        println!("{name} v{} ({})", tool.version, tool.source);
+        for (bin, mode) in &tool.bins {
+            let how = match mode {
+                InstallMode::Aot => "native",
+                InstallMode::Wrapped => "stratum run",
+            };
+            println!("    {bin} [{how}]");
+        }
+    }
+    Ok(())
+}
+
+/// Delete a package's launchers and files and drop it from the record.
+fn remove_tool(home: &Path, name: &str, installed: &mut InstalledTools) -> Result<()> {
+    let Some(tool) = installed.tools.remove(name) else {
+        return Ok(());
+    };
+    for bin in tool.bins.keys() {
+        let launcher = launcher_path(&home.join(BIN_DIR), bin);
+        if launcher.exists() {
+            std::fs::remove_file(&launcher)
+                .with_context(|| format!("Failed to remove {}", launcher.display()))?;
+        }
+    }
+    let tool_dir = home.join(TOOLS_DIR).join(name);
+    if tool_dir.exists() {
+        std::fs::remove_dir_all(&tool_dir)
+            .with_context(|| format!("Failed to remove {}", tool_dir.display()))?;
+    }
+    Ok(())
+}
+
+/// What a launcher runs.
+#[derive(Debug)]
+enum Launch {
+    /// A native executable.
+    Executable(PathBuf),
+    /// A source file, with `stratum run`.
+    Script { stratum: PathBuf, script: PathBuf },
+}
+
+/// Path of the launcher for `bin`.
+fn launcher_path(bin_dir: &Path, bin: &str) -> PathBuf {
+    if cfg!(windows) {
+        bin_dir.join(format!("{bin}.cmd"))
+    } else {
+        bin_dir.join(bin)
+    }
+}
+
+/// The launcher script for `target`.
+fn launcher_script(target: &Launch) -> String {
+    // An installed tool gets the access its compiled binary would have
+    if cfg!(windows) {
+        match target {
+            Launch::Executable(exe) => format!("@echo off\r\n\"{}\" %*\r\n", exe.display()),
+            Launch::Script { stratum, script } => format!(
+                "@echo off\r\n\"{}\" run --allow-all \"{}\" -- %*\r\n",
+                stratum.display(),
+                script.display()
+            ),
+        }
+    } else {
+        match target {
+            Launch::Executable(exe) => format!("#!/bin/sh\nexec \"{}\" \"$@\"\n", exe.display()),
+            Launch::Script { stratum, script } => format!(
+                "#!/bin/sh\nexec \"{}\" run --allow-all \"{}\" -- \"$@\"\n",
+                stratum.display(),
+                script.display()
+            ),
+        }
+    }
+}
+
+/// Check that a binary's name is a plain file name, since it names the
+/// launcher written to the Stratum home's `bin/`.
+fn validate_bin_name(bin: &str) -> Result<()> {
+    let valid = !bin.is_empty()
+        && bin
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        && matches!(
+            Path::new(bin).components().collect::<Vec<_>>()[..],
+            [std::path::Component::Normal(_)]
+        );
+    if !valid {
+        return Err(anyhow::anyhow!(
+            "Invalid binary name '{bin}': it can only contain letters, numbers, hyphens, \
+             and underscores"
+        ));
+    }
+    Ok(())
+}
+
+/// Write an executable launcher for `bin`.
+fn write_launcher(bin_dir: &Path, bin: &str, target: &Launch) -> Result<()> {
+    let path = launcher_path(bin_dir, bin);
+    std::fs::write(&path, launcher_script(target))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(())
+}
+
+/// Shallow-clone a git repository.
+fn clone_repository(url: &str, dest: &Path) -> Result<()> {
+    println!("Cloning {url}...");
+    let status = Command::new("git")
+        .args(["clone", "--depth", "1", "--quiet", url])
+        .arg(dest)
+        .status()
+        .context("Failed to run git; is it installed?")?;
+    if !status.success() {
+        return Err(anyhow::anyhow!("Failed to clone {url}"));
+    }
+    Ok(())
+}
+
+/// Copy a package's files, leaving out version control and build output.
+fn copy_package(from: &Path, to: &Path) -> Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if name == ".git" || name == "target" {
+            continue;
+        }
+        let dest = to.join(&name);
+        if entry.file_type()?.is_dir() {
+            copy_package(&entry.path(), &dest)?;
+        } else {
+            std::fs::copy(entry.path(), &dest)
+                .with_context(|| format!("Failed to copy {}", entry.path().display()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether `dir` is on PATH.
+fn path_contains(dir: &Path) -> bool {
+    std::env::var_os("PATH").is_some_and(|path| std::env::split_paths(&path).any(|p| p == dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The `greeter` package, with a `greeter` binary
+    fn write_greeter(dir: &Path) {
+        crate::testutil::write_package(dir, "greeter", "0.2.0", "");
+        std::fs::write(
+            dir.join("src/main.strat"),
+            "fx main() {\n    println(\"hi\")\n}\n",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_parse_source() {
+        assert_eq!(
+            Source::parse("https://example.com/tools/greeter.git").unwrap(),
+            Source::Git("https://example.com/tools/greeter.git".to_string())
+        );
+        assert!(matches!(
+            Source::parse("github:acme/greeter@v1.0.0").unwrap(),
+            Source::GitHub(pkg) if pkg.repo == "greeter"
+        ));
+        assert!(matches!(
+            Source::parse("acme/greeter").unwrap(),
+            Source::GitHub(pkg) if pkg.owner == "acme"
+        ));
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            Source::parse(dir.path().to_str().unwrap()).unwrap(),
+            Source::Path(dir.path().to_path_buf())
+        );
+        assert!(Source::parse("no such package").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_launcher_script() {
+        let script = launcher_script(&Launch::Script {
+            stratum: PathBuf::from("/usr/bin/stratum"),
+            script: PathBuf::from("/home/u/.stratum/tools/greeter/src/src/main.strat"),
+        });
+        assert_eq!(
+            script,
+            "#!/bin/sh\nexec \"/usr/bin/stratum\" run --allow-all \"/home/u/.stratum/tools/greeter/src/src/main.strat\" -- \"$@\"\n"
+        );
+    }
+
+    #[test]
+    fn test_install_wrapped_and_uninstall() {
+        let package = tempfile::tempdir().unwrap();
+        let home = tempfile::tempdir().unwrap();
+        write_greeter(package.path());
+        let options = |force| InstallOptions {
+            source: package.path().display().to_string(),
+            bin: None,
+            wrap: true,
+            force,
+        };
+
+        install_package(options(false), home.path()).unwrap();
+        let launcher = launcher_path(&home.path().join(BIN_DIR), "greeter");
+        assert!(launcher.exists());
+        assert!(home
+            .path()
+            .join("tools/greeter/src/src/main.strat")
+            .exists());
+        let installed = InstalledTools::load(home.path()).unwrap();
+        assert_eq!(installed.tools["greeter"].version, "0.2.0");
+        assert_eq!(
+            installed.tools["greeter"].bins["greeter"],
+            InstallMode::Wrapped
+        );
+
+        assert!(install_package(options(false), home.path()).is_err());
+        install_package(options(true), home.path()).unwrap();
+
+        uninstall_tool("greeter", home.path()).unwrap();
+        assert!(!launcher.exists());
+        assert!(!home.path().join("tools/greeter").exists());
+        assert!(InstalledTools::load(home.path()).unwrap().tools.is_empty());
+        assert!(uninstall_tool("greeter", home.path()).is_err());
+    }
+
+    #[test]
+    fn test_install_unknown_bin() {
+        let package = tempfile::tempdir().unwrap();
+        let home = tempfile::tempdir().unwrap();
+        write_greeter(package.path());
+        let err = install_package(
+            InstallOptions {
+                source: package.path().display().to_string(),
+                bin: Some("other".to_string()),
+                wrap: true,
+                force: false,
+            },
+            home.path(),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("no binary named 'other'"));
+    }
+
+    #[test]
+    fn test_install_rejects_bin_outside_bin_dir() {
+        let package = tempfile::tempdir().unwrap();
+        let home = tempfile::tempdir().unwrap();
+        write_greeter(package.path());
+        let manifest = package.path().join("stratum.toml");
+        let mut contents = std::fs::read_to_string(&manifest).unwrap();
+        contents.push_str("\n[[bin]]\nname = \"../../.profile\"\npath = \"src/main.strat\"\n");
+        std::fs::write(&manifest, contents).unwrap();
+
+        let err = install_package(
+            InstallOptions {
+                source: package.path().display().to_string(),
+                bin: None,
+                wrap: true,
+                force: false,
+            },
+            home.path(),
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Invalid binary name '../../.profile'"));
+        assert!(!home.path().join(".profile").exists());
+        assert!(!home.path().join(BIN_DIR).exists());
+
+        assert!(validate_bin_name("greeter-cli_2").is_ok());
+        assert!(validate_bin_name("").is_err());
+        assert!(validate_bin_name("..").is_err());
+        assert!(validate_bin_name("a/b").is_err());
+    }
+}
@@ -0,0 +1,173 @@
+//! Implementation of the `stratum license check` command.
+//!
+//! Checks the license of every dependency of the current package against the
+//! `[licenses]` policy of its manifest, or the `[workspace.licenses]` policy
+//! of its workspace, and optionally writes an SPDX report.
+
+use anyhow::{Context, Result};
+use std::path::Path;
+use stratum_pkg::license::{spdx_report, LicensePolicy, LicenseVerdict, UnknownLicense};
+use stratum_pkg::registry::RegistryConfig;
+use stratum_pkg::{Manifest, PackageLayout, Resolver, Workspace};
+
+/// Check the licenses of the current package's dependencies.
+///
+/// `report` is a file to write an SPDX tag-value document to, or `-` for
+/// stdout.
+pub fn check_licenses(report: Option<&Path>) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let layout = PackageLayout::find_root(&cwd)
+        .map_err(|e| anyhow::anyhow!("Not in a Stratum package: {e}"))?;
+    check_package(&layout, &RegistryConfig::default().cache_dir, report)
+}
+
+/// Check the package at `layout`, finding registry packages in `cache_dir`.
+fn check_package(layout: &PackageLayout, cache_dir: &Path, report: Option<&Path>) -> Result<()> {
+    let mut manifest = Manifest::from_path(&layout.manifest_path)
+        .with_context(|| format!("Failed to read {}", layout.manifest_path.display()))?;
+    if let Ok(workspace) = Workspace::find(&layout.root) {
+        workspace.apply_patches(&layout.root, &mut manifest);
+    }
+    let policy = policy_for(layout, &manifest);
+
+    let resolver = Resolver::new().with_dev(true).with_build(true);
+    let mut resolved = resolver.resolve(&manifest)?;
+    resolver.collect_licenses(&mut resolved, &layout.root, cache_dir);
+
+    let checks = policy.check(&resolved);
+    let width = checks.iter().map(|c| c.name.len()).max().unwrap_or(0);
+    let mut denied = 0;
+    let mut unknown = 0;
+    for check in &checks {
+        let license = check.license.as_deref().unwrap_or("(unknown)");
+        let status = match &check.verdict {
+            LicenseVerdict::Allowed => "ok".to_string(),
+            LicenseVerdict::Denied(reason) => {
+                denied += 1;
+                format!("error: {reason}")
+            }
+            LicenseVerdict::Unknown => {
+                unknown += 1;
+                match policy.unknown {
+                    UnknownLicense::Allow => "ok".to_string(),
+                    UnknownLicense::Warn => "warning: no license found".to_string(),
+                    UnknownLicense::Deny => "error: no license found".to_string(),
+                }
+            }
+        };
+        eprintln!("{:<width$}  {license}  {status}", check.name);
+    }
+
+    if let Some(path) = report {
+        let created = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+        let document = spdx_report(&manifest, &resolved, &created);
+        if path == Path::new("-") {
+            print!("{document}");
+        } else {
+            std::fs::write(path, document)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            eprintln!("Wrote SPDX report to {}", path.display());
+        }
+    }
+
+    let failed = denied
+        + if policy.unknown == UnknownLicense::Deny {
+            unknown
+        } else {
+            0
+        };
+    if failed > 0 {
+        anyhow::bail!("{failed} dependency license(s) are not allowed");
+    }
+    eprintln!(
+        "Checked {} dependency license(s){}",
+        checks.len(),
+        if unknown > 0 {
+            format!(", {unknown} unknown")
+        } else {
+            String::new()
+        }
+    );
+    Ok(())
+}
+
+/// The package's own policy, or else its workspace's.
+fn policy_for(layout: &PackageLayout, manifest: &Manifest) -> LicensePolicy {
+    if !manifest.licenses.is_empty() {
+        return manifest.licenses.clone();
+    }
+    Workspace::find(&layout.root)
+        .map(|workspace| workspace.config.licenses)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::write_package;
+
+    #[test]
+    fn test_check_package_policy() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = dir.path().join("app");
+        write_package(
+            &dir.path().join("mit"),
+            "mit",
+            "0.1.0",
+            "license = \"MIT\"\n",
+        );
+        write_package(
+            &dir.path().join("gpl"),
+            "gpl",
+            "0.1.0",
+            "license = \"GPL-3.0-only\"\n",
+        );
+        write_package(
+            &app,
+            "app",
+            "0.1.0",
+            "license = \"MIT\"\n\n[dependencies]\nmit = { path = \"../mit\" }\n\n[licenses]\nallow = [\"MIT\"]\n",
+        );
+        let cache = tempfile::tempdir().unwrap();
+        let report = dir.path().join("app.spdx");
+
+        let layout = PackageLayout::find_root(&app).unwrap();
+        check_package(&layout, cache.path(), Some(&report)).unwrap();
+        let document = std::fs::read_to_string(&report).unwrap();
+        assert!(document.contains("PackageName: mit\n"));
+        assert!(document.contains("PackageLicenseDeclared: MIT\n"));
+
+        write_package(
+            &app,
+            "app",
+            "0.1.0",
+            "[dependencies]\nmit = { path = \"../mit\" }\ngpl = { path = \"../gpl\" }\n\n[licenses]\nallow = [\"MIT\"]\n",
+        );
+        let err = check_package(&layout, cache.path(), None).unwrap_err();
+        assert!(err.to_string().contains("1 dependency license"), "{err}");
+    }
+
+    #[test]
+    fn test_unknown_license_policy() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = dir.path().join("app");
+        write_package(&dir.path().join("bare"), "bare", "0.1.0", "");
+        write_package(
+            &app,
+            "app",
+            "0.1.0",
+            "[dependencies]\nbare = { path = \"../bare\" }\n",
+        );
+        let cache = tempfile::tempdir().unwrap();
+        let layout = PackageLayout::find_root(&app).unwrap();
+        check_package(&layout, cache.path(), None).unwrap();
+
+        write_package(
+            &app,
+            "app",
+            "0.1.0",
+            "[dependencies]\nbare = { path = \"../bare\" }\n\n[licenses]\nunknown = \"deny\"\n",
+        );
+        assert!(check_package(&layout, cache.path(), None).is_err());
+    }
+}
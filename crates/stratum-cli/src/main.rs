@@ -19,20 +19,33 @@
 //! | Full | `full` (default) | ~120 MB |
 
 use anyhow::Result;
-use clap::{CommandFactory, Parser, Subcommand};
+use clap::{Args, CommandFactory, Parser, Subcommand};
 use clap_complete::{generate, Shell};
 use std::io;
 use std::path::PathBuf;
 
 mod add;
+mod audit;
+mod build_script;
+mod clean;
+mod config;
 mod dap;
+mod diagnostics;
+mod doc;
 mod extension;
 mod init;
+mod install;
+mod license;
 mod publish;
+mod registry;
 mod remove;
 mod repl;
 mod self_cmd;
+mod telemetry;
+#[cfg(test)]
+mod testutil;
 mod update;
+mod workspace;
 
 #[derive(Parser)]
 #[command(name = "stratum")]
@@ -41,6 +54,36 @@ mod update;
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+
+    /// Script to run, as invoked by a `#!/usr/bin/env stratum` line
+    #[arg(value_name = "SCRIPT")]
+    script: Option<PathBuf>,
+
+    /// Arguments passed to the script
+    #[arg(
+        value_name = "ARGS",
+        requires = "script",
+        trailing_var_arg = true,
+        allow_hyphen_values = true
+    )]
+    script_args: Vec<String>,
+
+    #[command(flatten)]
+    permissions: PermissionFlags,
+
+    /// Record function calls, JIT compiles, GC cycles and dependency fetches,
+    /// and write the trace to this file (or post it to an OTLP/HTTP URL)
+    #[arg(long, global = true, value_name = "PATH")]
+    trace: Option<PathBuf>,
+
+    /// Trace format (chrome, otlp)
+    #[arg(long, global = true, default_value = "chrome", requires = "trace")]
+    trace_format: String,
+
+    /// Most detailed spans to trace (info, debug, trace); only trace records
+    /// every function call
+    #[arg(long, global = true, default_value = "trace", requires = "trace")]
+    trace_level: String,
 }
 
 #[derive(Subcommand)]
@@ -134,6 +177,64 @@ enum Commands {
         build: bool,
     },
 
+    /// Check locked dependencies for known vulnerabilities and yanked or
+    /// unmaintained packages
+    Audit {
+        /// URL or path of the advisory database. Defaults to `[audit] db-url`
+        /// in the config
+        #[arg(long)]
+        db: Option<String>,
+
+        /// Use the cached advisory database and skip the registry yank check
+        #[arg(long)]
+        offline: bool,
+
+        /// Output format (text or json)
+        #[arg(long, default_value = "text")]
+        format: String,
+
+        /// Fail on unmaintained and yanked packages, not only vulnerable ones
+        #[arg(long)]
+        deny_warnings: bool,
+    },
+
+    /// Check dependency licenses against the package's license policy
+    #[command(subcommand)]
+    License(LicenseCommand),
+
+    /// Install a package's binaries as commands in ~/.stratum/bin
+    ///
+    /// - `stratum install ./tool` - Install from a local directory
+    /// - `stratum install github:user/repo@v1.0` - Install from GitHub
+    /// - `stratum install https://example.com/tool.git` - Install from git
+    Install {
+        /// Path, git URL, or GitHub spec of the package
+        #[arg(required_unless_present = "list")]
+        package: Option<String>,
+
+        /// Install only this binary target
+        #[arg(long)]
+        bin: Option<String>,
+
+        /// Run the binaries with `stratum run` instead of compiling them
+        #[arg(long)]
+        wrap: bool,
+
+        /// Replace an existing installation of the package
+        #[arg(long)]
+        force: bool,
+
+        /// List installed packages and their binaries
+        #[arg(long, conflicts_with_all = ["package", "bin", "wrap", "force"])]
+        list: bool,
+    },
+
+    /// Uninstall a package installed with `stratum install`
+    Uninstall {
+        /// Name of the installed package
+        name: String,
+    },
+
     /// Update dependencies to latest compatible versions
     Update {
         /// Only update specific packages
@@ -146,6 +247,14 @@ enum Commands {
         /// Only sync lock file with manifest (no version updates)
         #[arg(long, conflicts_with_all = ["packages", "dry_run"])]
         sync: bool,
+
+        /// Lock the oldest version each requirement accepts
+        #[arg(long, conflicts_with = "sync")]
+        minimal_versions: bool,
+
+        /// Lock a dependency to an exact version (repeatable)
+        #[arg(long, value_name = "PKG@VERSION", conflicts_with = "sync")]
+        precise: Vec<String>,
     },
 
     /// Run a Stratum source file
@@ -162,12 +271,105 @@ enum Commands {
         compile_all: bool,
 
         /// Enable JIT compilation for hot paths (default behavior)
-        #[arg(long)]
+        #[arg(long, conflicts_with = "no_jit")]
         jit: bool,
 
+        /// Run every function in the interpreter
+        #[arg(long)]
+        no_jit: bool,
+
+        /// Calls before a function is compiled with the JIT
+        #[arg(long, value_name = "CALLS")]
+        hot_threshold: Option<usize>,
+
         /// Enable memory profiling and print report after execution
         #[arg(long)]
         memory_profile: bool,
+
+        /// Memory profile report format (summary, html)
+        #[arg(long, default_value = "summary", requires = "memory_profile")]
+        format: String,
+
+        /// Where to write the HTML memory profile report
+        #[arg(long, requires = "memory_profile")]
+        profile_output: Option<PathBuf>,
+
+        /// Collect line and branch coverage and print a summary to stderr
+        #[arg(long)]
+        coverage: bool,
+
+        /// Write coverage data to this file, for `stratum coverage merge`
+        #[arg(long)]
+        coverage_data: Option<PathBuf>,
+
+        /// Print the bytecode before and after optimization instead of running
+        #[arg(long)]
+        dump_bytecode: bool,
+
+        /// Reload the view of a GUI app when the file changes, keeping its state
+        #[arg(long)]
+        hot_reload: bool,
+
+        #[command(flatten)]
+        permissions: PermissionFlags,
+
+        /// Arguments passed to the program, available through `Args`
+        #[arg(last = true)]
+        args: Vec<String>,
+
+        /// Diagnostic output (human, or json for one object per line)
+        #[arg(long, default_value = "human", value_name = "FORMAT")]
+        message_format: String,
+    },
+
+    /// Parse and type check source files without running them
+    Check {
+        /// Files to check (defaults to every source and test file in the
+        /// current package)
+        files: Vec<PathBuf>,
+
+        /// Diagnostic output (human, or json for one object per line)
+        #[arg(long, default_value = "human", value_name = "FORMAT")]
+        message_format: String,
+
+        /// Run for every member of the current workspace
+        #[arg(long, conflicts_with = "files")]
+        workspace: bool,
+
+        /// Run for this workspace member (may be repeated)
+        #[arg(
+            short = 'p',
+            long = "package",
+            value_name = "MEMBER",
+            conflicts_with = "files"
+        )]
+        package: Vec<String>,
+    },
+
+    /// Profile a Stratum source file and report where it spends its time
+    ///
+    /// The call stack is sampled every `--interval` instructions. The folded
+    /// format is the input of flamegraph.pl and inferno; speedscope JSON opens
+    /// in https://www.speedscope.app.
+    Profile {
+        /// Path to the source file
+        file: PathBuf,
+
+        /// Output format (summary, folded, speedscope)
+        #[arg(long, default_value = "summary")]
+        format: String,
+
+        /// Write the profile to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Instructions executed between samples
+        #[arg(long, default_value_t = stratum_core::DEFAULT_SAMPLE_INTERVAL)]
+        interval: u64,
+
+        /// Name folded stack frames by function and line
+        #[arg(long)]
+        lines: bool,
     },
 
     /// Evaluate a Stratum expression
@@ -178,8 +380,9 @@ enum Commands {
 
     /// Run tests in a Stratum source file
     Test {
-        /// Path to the source file containing tests
-        file: PathBuf,
+        /// Path to the source file containing tests (defaults to every
+        /// source and test file in the current package)
+        file: Option<PathBuf>,
 
         /// Filter tests by name (runs only tests containing this string)
         #[arg(short, long)]
@@ -193,13 +396,82 @@ enum Commands {
         #[arg(long)]
         coverage: bool,
 
-        /// Coverage report format (summary, html, lcov)
+        /// Coverage report format (summary, html, lcov, cobertura)
         #[arg(long, default_value = "summary")]
         format: String,
 
-        /// Output directory for coverage reports (used with --format=html)
+        /// Output directory for coverage reports (used with --format=html
+        /// and --format=cobertura; defaults to the package's target/coverage)
         #[arg(long)]
         coverage_dir: Option<PathBuf>,
+
+        /// Fail if line coverage is below this percentage (implies --coverage)
+        #[arg(long, value_name = "PCT")]
+        fail_under: Option<f64>,
+
+        /// Write coverage data to this file, for `stratum coverage merge`
+        #[arg(long)]
+        coverage_data: Option<PathBuf>,
+
+        /// Number of tests to run in parallel (defaults to the number of CPUs)
+        #[arg(short, long)]
+        jobs: Option<usize>,
+
+        /// Fail any test that runs longer than this many seconds
+        #[arg(long)]
+        timeout: Option<u64>,
+
+        /// Test result format (pretty, junit, json, tap)
+        #[arg(long, default_value = "pretty")]
+        reporter: String,
+
+        /// Write the report to this file instead of stdout
+        #[arg(long, requires = "reporter")]
+        report_file: Option<PathBuf>,
+
+        /// Diagnostic output (human, or json for one object per line)
+        #[arg(long, default_value = "human", value_name = "FORMAT")]
+        message_format: String,
+
+        /// Run for every member of the current workspace
+        #[arg(long, conflicts_with_all = ["file", "report_file", "coverage_data"])]
+        workspace: bool,
+
+        /// Run for this workspace member (may be repeated)
+        #[arg(short = 'p', long = "package", value_name = "MEMBER", conflicts_with_all = ["file", "report_file", "coverage_data"])]
+        package: Vec<String>,
+    },
+
+    /// Work with coverage data saved by `--coverage-data`
+    #[command(subcommand)]
+    Coverage(CoverageCommand),
+
+    /// Fuzz a function with coverage-guided mutated inputs
+    Fuzz {
+        /// Function to call with each input; it must take one parameter,
+        /// a String or a List of byte values
+        target: String,
+
+        /// Source file defining the function (defaults to searching the
+        /// current package's sources)
+        #[arg(short, long)]
+        file: Option<PathBuf>,
+
+        /// Number of mutated inputs to try
+        #[arg(long, default_value = "10000")]
+        runs: u64,
+
+        /// Longest input to generate, in bytes
+        #[arg(long, default_value = "256")]
+        max_len: usize,
+
+        /// Seed for the mutation RNG (defaults to a random seed)
+        #[arg(long)]
+        seed: Option<u64>,
+
+        /// Fail any input that runs longer than this many seconds
+        #[arg(long, default_value = "1")]
+        timeout: u64,
     },
 
     /// Format Stratum source files
@@ -210,12 +482,63 @@ enum Commands {
         /// Check if files are formatted without modifying
         #[arg(short, long)]
         check: bool,
+
+        /// Also sort and group imports and remove unused ones
+        #[arg(long)]
+        organize_imports: bool,
+
+        /// Spaces per indentation level
+        #[arg(long, value_name = "SPACES")]
+        indent_size: Option<usize>,
+
+        /// Diagnostic output (human, or json for one object per line)
+        #[arg(long, default_value = "human", value_name = "FORMAT")]
+        message_format: String,
+
+        /// Run for every member of the current workspace
+        #[arg(long, conflicts_with = "files")]
+        workspace: bool,
+
+        /// Run for this workspace member (may be repeated)
+        #[arg(
+            short = 'p',
+            long = "package",
+            value_name = "MEMBER",
+            conflicts_with = "files"
+        )]
+        package: Vec<String>,
+    },
+
+    /// Check Stratum source files for likely mistakes
+    ///
+    /// Rule levels are read from the `[lints]` table of the enclosing stratum.toml.
+    Lint {
+        /// Files or directories to lint (defaults to the current directory)
+        paths: Vec<PathBuf>,
+
+        /// Output format (text or json)
+        #[arg(long, default_value = "text")]
+        format: String,
+
+        /// Apply safe automatic fixes to the files
+        #[arg(long)]
+        fix: bool,
+    },
+
+    /// Explain an error code, such as E0100
+    Explain {
+        /// The error code (lists every code if omitted)
+        code: Option<String>,
     },
 
     /// Build a Stratum source file into a standalone executable
+    ///
+    /// With `--workspace` or `-p`, the binary targets of workspace members are
+    /// built into the workspace's `target/debug` or `target/release`.
     Build {
         /// Path to the source file
-        file: PathBuf,
+        #[arg(required_unless_present_any = ["workspace", "package"])]
+        file: Option<PathBuf>,
 
         /// Output executable path
         #[arg(short, long)]
@@ -224,6 +547,33 @@ enum Commands {
         /// Build with optimizations
         #[arg(long)]
         release: bool,
+
+        /// Run for every member of the current workspace
+        #[arg(long, conflicts_with_all = ["file", "output"])]
+        workspace: bool,
+
+        /// Run for this workspace member (may be repeated)
+        #[arg(short = 'p', long = "package", value_name = "MEMBER", conflicts_with_all = ["file", "output"])]
+        package: Vec<String>,
+    },
+
+    /// Remove build artifacts from the target directory
+    ///
+    /// The target directory is `target/` at the package or workspace root, or
+    /// the directory named by `STRATUM_TARGET_DIR`. The flags can be combined;
+    /// without any, the whole directory is removed.
+    Clean {
+        /// Remove generated documentation
+        #[arg(long)]
+        doc: bool,
+
+        /// Remove cached build script results, so build scripts run again
+        #[arg(long)]
+        cache: bool,
+
+        /// Remove everything built with --release
+        #[arg(long)]
+        release: bool,
     },
 
     /// Open Stratum Workshop IDE
@@ -260,6 +610,25 @@ enum Commands {
         /// Open the documentation in a browser after generation
         #[arg(long)]
         open: bool,
+
+        /// Include private items, those named with a leading underscore
+        #[arg(long)]
+        private: bool,
+
+        /// Generate the package's book: the Markdown chapters under docs/
+        /// together with its API documentation
+        #[arg(long)]
+        book: bool,
+
+        /// Report public items without doc comments instead of generating
+        /// documentation
+        #[arg(long)]
+        coverage: bool,
+
+        /// Fail if documentation coverage is below this percentage (implies
+        /// --coverage)
+        #[arg(long, value_name = "PCT")]
+        fail_under: Option<f64>,
     },
 
     /// Generate shell completions for bash, zsh, fish, or PowerShell
@@ -269,10 +638,12 @@ enum Commands {
         shell: Shell,
     },
 
-    /// Publish a package to GitHub Releases
+    /// Publish a package to a registry or GitHub Releases
     ///
-    /// Creates a tarball of your package and publishes it as a GitHub release.
-    /// Requires the GitHub CLI (gh) to be installed and authenticated.
+    /// Creates a tarball of your package and uploads it to the registry given
+    /// with --registry or configured in `[registry] url`. Without a registry,
+    /// publishes it as a GitHub release, which requires the GitHub CLI (gh)
+    /// to be installed and authenticated.
     Publish {
         /// Version tag to publish (e.g., "v1.0.0"). If not specified, uses version from stratum.toml
         #[arg(long)]
@@ -289,6 +660,65 @@ enum Commands {
         /// Target GitHub repository (owner/repo). Defaults to origin remote.
         #[arg(long)]
         target: Option<String>,
+
+        /// Registry URL to publish to instead of GitHub Releases
+        #[arg(long, conflicts_with_all = ["tag", "target"])]
+        registry: Option<String>,
+    },
+
+    /// Save an API token for a package registry
+    Login {
+        /// The token; read from stdin if omitted
+        token: Option<String>,
+
+        /// Registry URL. Defaults to `[registry] url` in the config
+        #[arg(long)]
+        registry: Option<String>,
+    },
+
+    /// Remove the saved token for a package registry
+    Logout {
+        /// Registry URL. Defaults to `[registry] url` in the config
+        #[arg(long)]
+        registry: Option<String>,
+    },
+
+    /// Search the package registry
+    Search {
+        /// Words to search package names, descriptions and keywords for
+        query: String,
+
+        /// Maximum number of results
+        #[arg(long, default_value = "10")]
+        limit: usize,
+
+        /// Registry URL. Defaults to `[registry] url` in the config
+        #[arg(long)]
+        registry: Option<String>,
+    },
+
+    /// Show a registry package's metadata and versions
+    Info {
+        /// Package name
+        package: String,
+
+        /// Registry URL. Defaults to `[registry] url` in the config
+        #[arg(long)]
+        registry: Option<String>,
+    },
+
+    /// Yank a published version so new lockfiles no longer select it
+    Yank {
+        /// Version to yank, as NAME@VERSION (e.g., "http@1.0.0")
+        package: String,
+
+        /// Restore a yanked version
+        #[arg(long)]
+        undo: bool,
+
+        /// Registry URL. Defaults to `[registry] url` in the config
+        #[arg(long)]
+        registry: Option<String>,
     },
 
     /// Manage VS Code extension
@@ -300,6 +730,131 @@ enum Commands {
     SelfCmd(SelfCommand),
 }
 
+/// Capabilities granted to a program by `stratum run`, which denies file,
+/// network, environment and process access unless a flag allows it
+#[derive(Args)]
+struct PermissionFlags {
+    /// Allow reading files, or only these paths and what is inside them
+    #[arg(
+        long,
+        value_name = "PATHS",
+        num_args = 0..=1,
+        require_equals = true,
+        value_delimiter = ','
+    )]
+    allow_read: Option<Vec<String>>,
+
+    /// Allow writing files, or only these paths and what is inside them
+    #[arg(
+        long,
+        value_name = "PATHS",
+        num_args = 0..=1,
+        require_equals = true,
+        value_delimiter = ','
+    )]
+    allow_write: Option<Vec<String>>,
+
+    /// Allow network access, or only to these hosts (`host` or `host:port`)
+    #[arg(
+        long,
+        value_name = "HOSTS",
+        num_args = 0..=1,
+        require_equals = true,
+        value_delimiter = ','
+    )]
+    allow_net: Option<Vec<String>>,
+
+    /// Allow reading and setting environment variables, or only these
+    #[arg(
+        long,
+        value_name = "VARS",
+        num_args = 0..=1,
+        require_equals = true,
+        value_delimiter = ','
+    )]
+    allow_env: Option<Vec<String>>,
+
+    /// Allow running processes, or only these programs
+    #[arg(
+        long,
+        value_name = "PROGRAMS",
+        num_args = 0..=1,
+        require_equals = true,
+        value_delimiter = ','
+    )]
+    allow_run: Option<Vec<String>>,
+
+    /// Allow everything
+    #[arg(short = 'A', long)]
+    allow_all: bool,
+}
+
+impl PermissionFlags {
+    /// The permissions to run with: a flag without a list grants all of its
+    /// capability, and a capability without a flag is denied
+    fn to_permissions(&self) -> stratum_core::Permissions {
+        use stratum_core::{Capability, Grant, Permissions};
+
+        if self.allow_all {
+            return Permissions::allow_all();
+        }
+        let flags = [
+            (Capability::Read, &self.allow_read),
+            (Capability::Write, &self.allow_write),
+            (Capability::Net, &self.allow_net),
+            (Capability::Env, &self.allow_env),
+            (Capability::Run, &self.allow_run),
+        ];
+        flags.into_iter().fold(
+            Permissions::deny_all(),
+            |permissions, (capability, flag)| {
+                let grant = match flag {
+                    None => Grant::Denied,
+                    Some(list) if list.is_empty() => Grant::All,
+                    Some(list) => Grant::Only(list.clone()),
+                };
+                permissions.with(capability, grant)
+            },
+        )
+    }
+}
+
+/// Subcommands for `stratum license`
+#[derive(Subcommand)]
+enum LicenseCommand {
+    /// Fail if a dependency's license is denied by the `[licenses]` policy
+    Check {
+        /// Also write an SPDX tag-value report to this file (`-` for stdout)
+        #[arg(long, value_name = "FILE")]
+        report: Option<PathBuf>,
+    },
+}
+
+/// Subcommands for `stratum coverage`
+#[derive(Subcommand)]
+enum CoverageCommand {
+    /// Combine coverage data files, such as those of sharded CI jobs, into
+    /// one report
+    Merge {
+        /// Coverage data files written with --coverage-data
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+
+        /// Report format (summary, html, lcov, cobertura)
+        #[arg(long, default_value = "summary")]
+        format: String,
+
+        /// Output directory for coverage reports (used with --format=html
+        /// and --format=cobertura)
+        #[arg(long)]
+        coverage_dir: Option<PathBuf>,
+
+        /// Also write the merged coverage data to this file
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
 /// Subcommands for `stratum extension`
 #[derive(Subcommand)]
 enum ExtensionCommand {
@@ -384,6 +939,23 @@ enum SelfCommand {
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    config::install(config::Config::load(&std::env::current_dir()?)?);
+
+    // The recorder writes the trace when it is dropped, after the command
+    let trace = match cli.trace {
+        Some(ref path) => {
+            let format = cli
+                .trace_format
+                .parse::<telemetry::TraceFormat>()
+                .map_err(anyhow::Error::msg)?;
+            let level = telemetry::parse_level(&cli.trace_level)?;
+            Some(telemetry::install(path, format, level)?)
+        }
+        None => None,
+    };
+
+    // Exit code returned by a program's main()
+    let mut exit_code = 0;
 
     match cli.command {
         Some(Commands::Repl) => {
@@ -451,15 +1023,66 @@ fn main() -> Result<()> {
             remove::remove_dependency(options)?;
         }
 
+        Some(Commands::Audit {
+            db,
+            offline,
+            format,
+            deny_warnings,
+        }) => {
+            let options = audit::AuditOptions {
+                db,
+                offline,
+                format,
+                deny_warnings,
+            };
+            audit::run_audit(options)?;
+        }
+
+        Some(Commands::License(LicenseCommand::Check { report })) => {
+            license::check_licenses(report.as_deref())?;
+        }
+
+        Some(Commands::Install {
+            package,
+            bin,
+            wrap,
+            force,
+            list,
+        }) => {
+            let home = self_cmd::get_stratum_home()?;
+            if list {
+                install::list_tools(&home)?;
+            } else {
+                let options = install::InstallOptions {
+                    source: package.expect("clap requires a package unless --list"),
+                    bin,
+                    wrap,
+                    force,
+                };
+                install::install_package(options, &home)?;
+            }
+        }
+
+        Some(Commands::Uninstall { name }) => {
+            install::uninstall_tool(&name, &self_cmd::get_stratum_home()?)?;
+        }
+
         Some(Commands::Update {
             packages,
             dry_run,
             sync,
+            minimal_versions,
+            precise,
         }) => {
             if sync {
                 update::sync_lockfile()?;
             } else {
-                let options = update::UpdateOptions { packages, dry_run };
+                let options = update::UpdateOptions {
+                    packages,
+                    dry_run,
+                    minimal_versions,
+                    precise,
+                };
                 let result = update::update_dependencies(options)?;
                 result.print_summary();
             }
@@ -469,9 +1092,28 @@ fn main() -> Result<()> {
             file,
             interpret_all,
             compile_all,
-            jit: _,
+            jit,
+            no_jit,
+            hot_threshold,
             memory_profile,
+            format,
+            profile_output,
+            coverage,
+            coverage_data,
+            dump_bytecode,
+            hot_reload,
+            permissions,
+            args,
+            message_format,
         }) => {
+            let message_format = message_format
+                .parse::<diagnostics::MessageFormat>()
+                .map_err(anyhow::Error::msg)?;
+            let mut run_config = config::current().run.clone();
+            if jit || no_jit {
+                run_config.jit = Some(jit);
+            }
+            run_config.hot_threshold = hot_threshold.or(run_config.hot_threshold);
             let mode_override = if interpret_all {
                 Some(stratum_core::ExecutionModeOverride::InterpretAll)
             } else if compile_all {
@@ -479,7 +1121,60 @@ fn main() -> Result<()> {
             } else {
                 None // Respect directives
             };
-            run_file(&file, mode_override, memory_profile)?;
+            if hot_reload {
+                enable_hot_reload(&file)?;
+            }
+            if dump_bytecode {
+                dump_file_bytecode(&file, mode_override)?;
+            } else {
+                let memory_profile = if memory_profile {
+                    let format = format
+                        .parse::<stratum_core::MemoryReportFormat>()
+                        .map_err(|e| anyhow::anyhow!("{e} (expected summary or html)"))?;
+                    Some((format, profile_output))
+                } else {
+                    None
+                };
+                exit_code = run_file(
+                    &file,
+                    &args,
+                    &run_config,
+                    permissions.to_permissions(),
+                    mode_override,
+                    memory_profile,
+                    coverage,
+                    coverage_data.as_deref(),
+                    message_format,
+                )?;
+            }
+        }
+
+        Some(Commands::Check {
+            files,
+            message_format,
+            workspace,
+            package,
+        }) => {
+            let message_format = message_format
+                .parse::<diagnostics::MessageFormat>()
+                .map_err(anyhow::Error::msg)?;
+            if workspace || !package.is_empty() {
+                workspace::for_each_member(&package, |_, member| {
+                    check_files(&workspace::package_files(&member.package)?, message_format)
+                })?;
+            } else {
+                check_files(&files, message_format)?;
+            }
+        }
+
+        Some(Commands::Profile {
+            file,
+            format,
+            output,
+            interval,
+            lines,
+        }) => {
+            profile_file(&file, &format, output.as_deref(), interval, lines)?;
         }
 
         Some(Commands::Eval { expression }) => {
@@ -493,27 +1188,181 @@ fn main() -> Result<()> {
             coverage,
             format,
             coverage_dir,
+            fail_under,
+            coverage_data,
+            jobs,
+            timeout,
+            reporter,
+            report_file,
+            message_format,
+            workspace,
+            package,
         }) => {
-            run_tests(
-                &file,
-                filter.as_deref(),
-                verbose,
-                coverage,
-                &format,
-                coverage_dir.as_deref(),
-            )?;
+            let message_format = message_format
+                .parse::<diagnostics::MessageFormat>()
+                .map_err(anyhow::Error::msg)?;
+            // HTML and Cobertura reports go in the target directory of a package
+            let coverage_dir = coverage_dir.or_else(|| {
+                let cwd = std::env::current_dir().ok()?;
+                stratum_pkg::TargetDir::find(&cwd).map(|target| target.coverage())
+            });
+            let test = |suite: String, files: Vec<PathBuf>, coverage_dir: Option<PathBuf>| {
+                run_tests(
+                    &suite,
+                    &files,
+                    filter.as_deref(),
+                    verbose,
+                    coverage,
+                    &format,
+                    coverage_dir.as_deref(),
+                    fail_under,
+                    coverage_data.as_deref(),
+                    jobs,
+                    timeout.map(std::time::Duration::from_secs),
+                    &reporter,
+                    report_file.as_deref(),
+                    message_format,
+                )
+            };
+            if workspace || !package.is_empty() {
+                // Each member's coverage report goes in a directory of its own
+                workspace::for_each_member(&package, |_, member| {
+                    test(
+                        member.name.clone(),
+                        workspace::package_files(&member.package)?,
+                        coverage_dir.as_ref().map(|dir| dir.join(&member.name)),
+                    )
+                })?;
+            } else {
+                let (suite, files) = match file {
+                    Some(path) => (path.display().to_string(), vec![path]),
+                    None => package_test_files()?,
+                };
+                test(suite, files, coverage_dir.clone())?;
+            }
+        }
+
+        Some(Commands::Coverage(CoverageCommand::Merge {
+            files,
+            format,
+            coverage_dir,
+            output,
+        })) => {
+            merge_coverage(&files, &format, coverage_dir.as_deref(), output.as_deref())?;
+        }
+
+        Some(Commands::Fuzz {
+            target,
+            file,
+            runs,
+            max_len,
+            seed,
+            timeout,
+        }) => {
+            let options = stratum_core::fuzz::FuzzOptions {
+                runs,
+                max_len,
+                seed: seed.unwrap_or_else(|| {
+                    std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+                }),
+                timeout: Some(std::time::Duration::from_secs(timeout)),
+            };
+            fuzz_function(&target, file.as_deref(), options)?;
+        }
+
+        Some(Commands::Fmt {
+            files,
+            check,
+            organize_imports,
+            indent_size,
+            message_format,
+            workspace,
+            package,
+        }) => {
+            let message_format = message_format
+                .parse::<diagnostics::MessageFormat>()
+                .map_err(anyhow::Error::msg)?;
+            let fmt = &config::current().fmt;
+            let organize_imports = organize_imports || fmt.organize_imports.unwrap_or(false);
+            let mut format_config = stratum_core::FormatConfig::default();
+            if let Some(indent_size) = indent_size.or(fmt.indent_size) {
+                format_config.indent_size = indent_size;
+            }
+            if workspace || !package.is_empty() {
+                workspace::for_each_member(&package, |_, member| {
+                    let files = workspace::package_files(&member.package)?;
+                    if files.is_empty() {
+                        return Ok(());
+                    }
+                    format_files(
+                        &files,
+                        check,
+                        organize_imports,
+                        &format_config,
+                        message_format,
+                    )
+                })?;
+            } else {
+                format_files(
+                    &files,
+                    check,
+                    organize_imports,
+                    &format_config,
+                    message_format,
+                )?;
+            }
+        }
+
+        Some(Commands::Lint { paths, format, fix }) => {
+            lint_files(&paths, &format, fix)?;
         }
 
-        Some(Commands::Fmt { files, check }) => {
-            format_files(&files, check)?;
+        Some(Commands::Explain { code }) => {
+            explain_error(code.as_deref())?;
         }
 
         Some(Commands::Build {
             file,
             output,
             release,
+            workspace,
+            package,
+        }) => {
+            if workspace || !package.is_empty() {
+                workspace::for_each_member(&package, |ws, member| {
+                    let binaries: Vec<_> = member
+                        .package
+                        .targets
+                        .iter()
+                        .filter(|target| target.kind == stratum_pkg::TargetKind::Bin)
+                        .collect();
+                    if binaries.is_empty() {
+                        println!("No binary targets in {}", member.name);
+                    }
+                    for target in binaries {
+                        let output = workspace::binary_path(ws, &target.name, release);
+                        if let Some(dir) = output.parent() {
+                            std::fs::create_dir_all(dir).map_err(|e| {
+                                anyhow::anyhow!("Failed to create '{}': {e}", dir.display())
+                            })?;
+                        }
+                        build_executable(&target.path, Some(output), release)?;
+                    }
+                    Ok(())
+                })?;
+            } else if let Some(file) = file {
+                build_executable(&file, output, release)?;
+            }
+        }
+
+        Some(Commands::Clean {
+            doc,
+            cache,
+            release,
         }) => {
-            build_executable(&file, output, release)?;
+            clean::clean(doc, cache, release)?;
         }
 
         #[cfg(feature = "workshop")]
@@ -535,8 +1384,16 @@ fn main() -> Result<()> {
             output,
             format,
             open,
+            private,
+            book,
+            coverage,
+            fail_under,
         }) => {
-            generate_documentation(&path, output, &format, open)?;
+            if coverage || fail_under.is_some() {
+                doc::report_coverage(&path, fail_under)?;
+            } else {
+                doc::generate_documentation(&path, output, &format, open, private, book)?;
+            }
         }
 
         Some(Commands::Completions { shell }) => {
@@ -548,18 +1405,53 @@ fn main() -> Result<()> {
             dry_run,
             allow_dirty,
             target,
+            registry,
         }) => {
             let options = publish::PublishOptions {
                 tag,
                 dry_run,
                 allow_dirty,
                 target,
+                registry,
             };
             publish::publish_package(options)?;
         }
 
-        Some(Commands::Extension(cmd)) => match cmd {
-            ExtensionCommand::Install { vsix } => {
+        Some(Commands::Login { token, registry }) => {
+            let url = registry::registry_url(registry)?;
+            registry::login(&url, token, &self_cmd::get_stratum_home()?)?;
+        }
+
+        Some(Commands::Logout { registry }) => {
+            let url = registry::registry_url(registry)?;
+            registry::logout(&url, &self_cmd::get_stratum_home()?)?;
+        }
+
+        Some(Commands::Search {
+            query,
+            limit,
+            registry,
+        }) => {
+            let url = registry::registry_url(registry)?;
+            registry::search(&url, &query, limit, &self_cmd::get_stratum_home()?)?;
+        }
+
+        Some(Commands::Info { package, registry }) => {
+            let url = registry::registry_url(registry)?;
+            registry::info(&url, &package, &self_cmd::get_stratum_home()?)?;
+        }
+
+        Some(Commands::Yank {
+            package,
+            undo,
+            registry,
+        }) => {
+            let url = registry::registry_url(registry)?;
+            registry::yank(&url, &package, undo, &self_cmd::get_stratum_home()?)?;
+        }
+
+        Some(Commands::Extension(cmd)) => match cmd {
+            ExtensionCommand::Install { vsix } => {
                 extension::install_extension(vsix)?;
             }
             ExtensionCommand::List => {
@@ -612,46 +1504,120 @@ fn main() -> Result<()> {
         },
 
         None => {
-            // Default behavior: start REPL
-            let mut repl = repl::Repl::new()?;
-            repl.run()?;
+            if let Some(script) = cli.script {
+                exit_code = run_file(
+                    &script,
+                    &cli.script_args,
+                    &config::current().run,
+                    cli.permissions.to_permissions(),
+                    None,
+                    None,
+                    false,
+                    None,
+                    diagnostics::MessageFormat::Human,
+                )?;
+            } else {
+                // Default behavior: start REPL
+                let mut repl = repl::Repl::new()?;
+                repl.run()?;
+            }
         }
     }
 
+    if exit_code != 0 {
+        // Exiting skips destructors, so write the trace first
+        drop(trace);
+        std::process::exit(exit_code);
+    }
+    Ok(())
+}
+
+/// Resolve the dependencies a script declares in `//! deps:` comments
+///
+/// The resolved environment is cached by its dependencies, so only the first
+/// run of a script (or of any script with the same dependencies) resolves.
+fn prepare_script_environment(path: &std::path::Path, source: &str) -> Result<()> {
+    let deps = stratum_pkg::ScriptDependencies::parse(source)
+        .map_err(|e| anyhow::anyhow!("{}: {e}", path.display()))?;
+    if deps.is_empty() {
+        return Ok(());
+    }
+
+    let path = std::fs::canonicalize(path)?;
+    let script_dir = path.parent().unwrap_or(std::path::Path::new("."));
+    let cache_dir = stratum_pkg::ScriptEnvironment::default_cache_dir();
+    let env = stratum_pkg::ScriptEnvironment::prepare(&deps, script_dir, &cache_dir)?;
+    if env.created {
+        eprintln!(
+            "Resolved {} script dependencies into {}",
+            env.lockfile.len(),
+            env.root.display()
+        );
+    }
     Ok(())
 }
 
-/// Run a Stratum source file
+/// Default file name of the HTML memory profile report
+const MEMORY_PROFILE_HTML: &str = "memory-profile.html";
+
+/// Run a Stratum source file, returning the process exit code
+///
+/// Errors are reported as `message_format` diagnostics.
+///
+/// `args` are the program arguments reported by the `Args` namespace, and
+/// `run` the merged `[run]` config and flags. An
+/// `Int` returned from `main()` is the exit code; any other non-null result is
+/// printed and the exit code is 0.
+///
+/// With `memory_profile`, allocations are profiled and reported in the given
+/// format; an HTML report is written to the given path or
+/// [`MEMORY_PROFILE_HTML`].
+#[allow(clippy::too_many_arguments)]
 fn run_file(
     path: &PathBuf,
+    args: &[String],
+    run: &config::RunConfig,
+    permissions: stratum_core::Permissions,
     mode_override: Option<stratum_core::ExecutionModeOverride>,
-    memory_profile: bool,
-) -> Result<()> {
+    memory_profile: Option<(stratum_core::MemoryReportFormat, Option<PathBuf>)>,
+    coverage: bool,
+    coverage_data: Option<&std::path::Path>,
+    message_format: diagnostics::MessageFormat,
+) -> Result<i32> {
     // Enable memory profiling if requested
-    if memory_profile {
+    if memory_profile.is_some() {
         stratum_core::reset_profiler();
         stratum_core::enable_profiling();
     }
 
     let source = std::fs::read_to_string(path)
         .map_err(|e| anyhow::anyhow!("Failed to read file '{}': {}", path.display(), e))?;
+    prepare_script_environment(path, &source)?;
+    if let Some(output) = build_script::prepare(path, false)? {
+        build_script::apply_env(&output);
+    }
+    stratum_core::set_program_args(args.to_vec());
+    let reporter = diagnostics::Reporter::new(message_format, path, &source);
 
     // Parse as module
     let module = stratum_core::Parser::parse_module(&source).map_err(|errors| {
-        let error_msgs: Vec<String> = errors.iter().map(|e| format!("  {e}")).collect();
-        anyhow::anyhow!("Parse errors:\n{}", error_msgs.join("\n"))
+        reporter.fail(
+            "Parse errors",
+            errors.iter().map(diagnostics::Diagnostic::parse),
+        )
     })?;
 
     // Type check
     let mut type_checker = stratum_core::TypeChecker::new();
     let type_result = type_checker.check_module(&module);
     if !type_result.errors.is_empty() {
-        let error_msgs: Vec<String> = type_result
-            .errors
-            .iter()
-            .map(|e| format!("  {e}"))
-            .collect();
-        return Err(anyhow::anyhow!("Type errors:\n{}", error_msgs.join("\n")));
+        return Err(reporter.fail(
+            "Type errors",
+            type_result
+                .errors
+                .iter()
+                .map(diagnostics::Diagnostic::type_error),
+        ));
     }
 
     // Compile with execution mode override if specified
@@ -659,22 +1625,37 @@ fn run_file(
         .with_mode_override(mode_override)
         .compile_module(&module)
         .map_err(|errors| {
-            let error_msgs: Vec<String> = errors.iter().map(|e| format!("  {e}")).collect();
-            anyhow::anyhow!("Compile errors:\n{}", error_msgs.join("\n"))
+            reporter.fail(
+                "Compile errors",
+                errors.iter().map(diagnostics::Diagnostic::compile),
+            )
         })?;
 
     // Run the module to register functions
     let mut vm = stratum_core::VM::new();
+    vm.set_jit_enabled(run.jit.unwrap_or(true));
+    if let Some(threshold) = run.hot_threshold {
+        vm.set_hot_threshold(threshold);
+    }
+    vm.set_permissions(permissions);
+    // Ctrl-C and SIGTERM run the program's System.on_exit callbacks
+    vm.handle_shutdown_signals()
+        .map_err(|e| anyhow::anyhow!("Failed to install signal handlers: {e}"))?;
+    vm.set_allocation_profiling(memory_profile.is_some());
+    if coverage || coverage_data.is_some() {
+        vm.enable_coverage();
+    }
 
     // Register GUI bindings so Stratum code can use Gui.* functions
     #[cfg(feature = "gui")]
     stratum_gui::register_gui(&mut vm);
 
-    let _ = vm
-        .run(function)
-        .map_err(|e| anyhow::anyhow!("Runtime error: {e}"))?;
+    if let Err(e) = vm.run(function) {
+        return stop_program(&mut vm, &e, &reporter);
+    }
 
     // Check if main() exists and call it
+    let mut exit_code = 0;
     if vm.globals().contains_key("main") {
         // Compile and run a call to main()
         let main_call = stratum_core::Parser::parse_expression("main()").map_err(|errors| {
@@ -689,206 +1670,691 @@ fn run_file(
                 anyhow::anyhow!("Internal error: {}", error_msgs.join("\n"))
             })?;
 
-        let result = vm
-            .run(main_fn)
-            .map_err(|e| anyhow::anyhow!("Runtime error: {e}"))?;
+        let result = match vm.run(main_fn) {
+            Ok(result) => result,
+            Err(e) => return stop_program(&mut vm, &e, &reporter),
+        };
 
-        // Print result if not null
-        if !matches!(result, stratum_core::bytecode::Value::Null) {
-            println!("{result}");
+        // An Int result is the exit code; print any other non-null result
+        match result {
+            stratum_core::bytecode::Value::Int(code) => {
+                exit_code = i32::try_from(code).unwrap_or(1);
+            }
+            stratum_core::bytecode::Value::Null => {}
+            result => println!("{result}"),
+        }
+    }
+    vm.run_exit_hooks()
+        .map_err(|e| reporter.runtime_error(&e))?;
+
+    if let Some(collector) = vm.take_coverage() {
+        if let Some(path) = coverage_data {
+            write_coverage_data(&collector, path)?;
+        }
+        if coverage {
+            eprintln!(
+                "{}",
+                stratum_core::generate_report(
+                    &collector,
+                    stratum_core::CoverageFormat::Summary,
+                    None
+                )
+            );
         }
     }
 
     // Print memory profile report if enabled
-    if memory_profile {
-        // Capture GC stats before printing report
+    if let Some((format, output)) = memory_profile {
+        // Capture freed values and GC stats before printing report
+        vm.sweep_allocations();
         stratum_core::set_profiler_gc_stats(vm.gc_stats());
         stratum_core::disable_profiling();
 
-        eprintln!();
-        eprintln!("{}", stratum_core::profiler_summary());
+        match format {
+            stratum_core::MemoryReportFormat::Summary => {
+                eprintln!();
+                eprintln!("{}", stratum_core::profiler_summary());
+            }
+            stratum_core::MemoryReportFormat::Html => {
+                let output = output.unwrap_or_else(|| PathBuf::from(MEMORY_PROFILE_HTML));
+                std::fs::write(&output, stratum_core::profiler_html_report()).map_err(|e| {
+                    anyhow::anyhow!(
+                        "Failed to write memory profile '{}': {}",
+                        output.display(),
+                        e
+                    )
+                })?;
+                eprintln!("Memory profile written to {}", output.display());
+            }
+        }
     }
 
-    Ok(())
+    Ok(exit_code)
 }
 
-/// Run tests in a Stratum source file
-fn run_tests(
-    path: &PathBuf,
-    filter: Option<&str>,
-    verbose: bool,
-    coverage: bool,
+/// End a program that stopped with `error`. An interrupted program has run
+/// its exit callbacks already and exits as if killed by the signal; a failed
+/// one runs them before the error is reported.
+fn stop_program(
+    vm: &mut stratum_core::VM,
+    error: &stratum_core::vm::RuntimeError,
+    reporter: &diagnostics::Reporter<'_>,
+) -> Result<i32> {
+    if let stratum_core::vm::RuntimeErrorKind::Interrupted(signal) = error.kind {
+        return Ok(128 + signal);
+    }
+    if let Err(e) = vm.run_exit_hooks() {
+        eprintln!("error in exit callback: {e}");
+    }
+    Err(reporter.runtime_error(error))
+}
+
+/// Save coverage data for `stratum coverage merge`
+fn write_coverage_data(
+    collector: &stratum_core::CoverageCollector,
+    path: &std::path::Path,
+) -> Result<()> {
+    std::fs::write(path, collector.to_data())
+        .map_err(|e| anyhow::anyhow!("Failed to write coverage data '{}': {}", path.display(), e))
+}
+
+/// Merge coverage data files and print a report of the combined coverage
+fn merge_coverage(
+    files: &[PathBuf],
     format: &str,
     coverage_dir: Option<&std::path::Path>,
+    output: Option<&std::path::Path>,
 ) -> Result<()> {
-    use stratum_core::coverage::{generate_report, CoverageFormat};
-    use stratum_core::testing::{self, TestRunner};
+    use stratum_core::coverage::{generate_report, CoverageCollector, CoverageFormat};
 
-    let source = std::fs::read_to_string(path)
-        .map_err(|e| anyhow::anyhow!("Failed to read file '{}': {}", path.display(), e))?;
+    let format = format
+        .parse::<CoverageFormat>()
+        .map_err(|e| anyhow::anyhow!("{e} (expected summary, html, lcov or cobertura)"))?;
 
-    // Parse as module
-    let module = stratum_core::Parser::parse_module(&source).map_err(|errors| {
-        let error_msgs: Vec<String> = errors.iter().map(|e| format!("  {e}")).collect();
-        anyhow::anyhow!("Parse errors:\n{}", error_msgs.join("\n"))
-    })?;
+    let mut merged = CoverageCollector::new();
+    for file in files {
+        let data = std::fs::read_to_string(file)
+            .map_err(|e| anyhow::anyhow!("Failed to read file '{}': {}", file.display(), e))?;
+        let collector = CoverageCollector::from_data(&data).map_err(|e| {
+            anyhow::anyhow!("Failed to load coverage data '{}': {e}", file.display())
+        })?;
+        merged.merge(&collector);
+    }
 
-    // Type check
-    let mut type_checker = stratum_core::TypeChecker::new();
-    let type_result = type_checker.check_module(&module);
-    if !type_result.errors.is_empty() {
-        let error_msgs: Vec<String> = type_result
-            .errors
-            .iter()
-            .map(|e| format!("  {e}"))
-            .collect();
-        return Err(anyhow::anyhow!("Type errors:\n{}", error_msgs.join("\n")));
+    if let Some(output) = output {
+        write_coverage_data(&merged, output)?;
     }
+    println!("{}", generate_report(&merged, format, coverage_dir));
+    Ok(())
+}
 
-    // Discover and filter tests
-    let tests = testing::discover_tests(&module);
-    let tests = testing::filter_tests(tests, filter);
+/// Fuzz a function, saving inputs that reach new coverage and the minimized
+/// input of any crash to `fuzz/corpus/<target>/`
+fn fuzz_function(
+    target: &str,
+    file: Option<&std::path::Path>,
+    options: stratum_core::fuzz::FuzzOptions,
+) -> Result<()> {
+    use stratum_core::fuzz::Fuzzer;
 
-    if tests.is_empty() {
-        if filter.is_some() {
-            println!("No tests matching filter found");
-        } else {
-            println!("No tests found");
+    let (path, root) = match file {
+        Some(path) => (path.to_path_buf(), std::env::current_dir()?),
+        None => find_fuzz_target(target)?,
+    };
+    let module = check_source_file(&path, diagnostics::MessageFormat::Human)?;
+    let seed = options.seed;
+    let mut fuzzer = Fuzzer::new(&module, target, &path.display().to_string(), options)
+        .map_err(|e| anyhow::anyhow!("Cannot fuzz '{}': {e}", path.display()))?;
+
+    let corpus_dir = root.join("fuzz").join("corpus").join(target);
+    let mut seeds = 0;
+    if corpus_dir.is_dir() {
+        let mut entries: Vec<PathBuf> = std::fs::read_dir(&corpus_dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.is_file()
+                    && !path
+                        .file_name()
+                        .is_some_and(|name| name.to_string_lossy().starts_with("crash-"))
+            })
+            .collect();
+        entries.sort();
+        for entry in entries {
+            fuzzer.add_seed(std::fs::read(&entry)?);
+            seeds += 1;
         }
-        return Ok(());
     }
 
-    println!("Running {} test(s)...\n", tests.len());
-
-    // Run tests with coverage if enabled
-    let runner = TestRunner::new().verbose(verbose).with_coverage(coverage);
-    let summary = runner.run_tests(&tests, &path.display().to_string());
-
-    // Print results
-    for result in &summary.results {
-        let status = if result.passed { "PASS" } else { "FAIL" };
-        let duration_ms = result.duration.as_secs_f64() * 1000.0;
-
-        if result.should_panic && result.passed {
-            println!(
-                "  {} {} (expected panic) [{:.2}ms]",
-                status, result.name, duration_ms
-            );
-        } else {
-            println!("  {} {} [{:.2}ms]", status, result.name, duration_ms);
-        }
+    println!(
+        "Fuzzing {target} in {} ({seeds} corpus input(s), seed {seed})",
+        path.display()
+    );
+    let report = fuzzer.run();
 
-        if !result.passed {
-            if let Some(ref error) = result.error {
-                println!("       Error: {error}");
-            }
-        }
+    if !report.new_inputs.is_empty() || report.crash.is_some() {
+        std::fs::create_dir_all(&corpus_dir)
+            .map_err(|e| anyhow::anyhow!("Failed to create '{}': {}", corpus_dir.display(), e))?;
+    }
+    for input in &report.new_inputs {
+        std::fs::write(corpus_dir.join(input_hash(input)), input)?;
     }
-
-    // Print summary
-    println!();
     println!(
-        "Test result: {} passed, {} failed, {} total (in {:.2}ms)",
-        summary.passed,
-        summary.failed,
-        summary.total,
-        summary.duration.as_secs_f64() * 1000.0
+        "{} run(s), {} new corpus input(s), {} coverage point(s)",
+        report.runs,
+        report.new_inputs.len(),
+        report.coverage_points
     );
 
-    // Print coverage report if enabled
-    if coverage {
-        if let Some(ref collector) = summary.coverage {
-            let cov_format = format.parse::<CoverageFormat>().unwrap_or_default();
-            let report = generate_report(collector, cov_format, coverage_dir);
-            println!("{}", report);
-        }
+    if let Some(crash) = report.crash {
+        let crash_path = corpus_dir.join(format!("crash-{}", input_hash(&crash.minimized)));
+        std::fs::write(&crash_path, &crash.minimized)?;
+        println!(
+            "Crash: {}\n  input ({} bytes, minimized from {}): {:?}\n  saved to {}",
+            crash.error,
+            crash.minimized.len(),
+            crash.input.len(),
+            String::from_utf8_lossy(&crash.minimized),
+            crash_path.display()
+        );
+        return Err(anyhow::anyhow!("Fuzz target '{target}' crashed"));
     }
+    Ok(())
+}
 
-    if summary.all_passed() {
-        Ok(())
-    } else {
-        Err(anyhow::anyhow!("Some tests failed"))
+/// The package source defining a fuzz target, and the package root
+fn find_fuzz_target(target: &str) -> Result<(PathBuf, PathBuf)> {
+    let cwd = std::env::current_dir()?;
+    let package = stratum_pkg::PackageStructure::find(&cwd)
+        .map_err(|e| anyhow::anyhow!("No file given and no package found: {e}"))?;
+    let files = package
+        .layout
+        .source_files()
+        .map_err(|e| anyhow::anyhow!("Failed to read package sources: {e}"))?;
+
+    for file in files {
+        let Ok(source) = std::fs::read_to_string(&file) else {
+            continue;
+        };
+        let Ok(module) = stratum_core::Parser::parse_module(&source) else {
+            continue;
+        };
+        let defines_target = module.items().into_iter().any(|item| {
+            matches!(&item.kind, stratum_core::ast::ItemKind::Function(f) if f.name.name == target)
+        });
+        if defines_target {
+            return Ok((file, package.layout.root));
+        }
     }
+    Err(anyhow::anyhow!(
+        "No function named '{target}' in package '{}'",
+        package.manifest.package.name
+    ))
 }
 
-/// Evaluate a single expression
-fn eval_expression(expression: &str) -> Result<()> {
-    // Parse as expression
-    let expr = stratum_core::Parser::parse_expression(expression).map_err(|errors| {
+/// A file name for a corpus input, derived from its contents
+fn input_hash(input: &[u8]) -> String {
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    input.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Run a Stratum source file under the CPU profiler and write the profile
+fn profile_file(
+    path: &PathBuf,
+    format: &str,
+    output: Option<&std::path::Path>,
+    interval: u64,
+    lines: bool,
+) -> Result<()> {
+    use stratum_core::ProfileFormat;
+
+    let format = format
+        .parse::<ProfileFormat>()
+        .map_err(|e| anyhow::anyhow!("{e} (expected summary, folded or speedscope)"))?;
+
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read file '{}': {}", path.display(), e))?;
+
+    let module = stratum_core::Parser::parse_module(&source).map_err(|errors| {
         let error_msgs: Vec<String> = errors.iter().map(|e| format!("  {e}")).collect();
         anyhow::anyhow!("Parse errors:\n{}", error_msgs.join("\n"))
     })?;
 
-    // Compile
-    let function = stratum_core::Compiler::new()
-        .compile_expression(&expr)
+    let function = stratum_core::Compiler::with_source(path.display().to_string())
+        .compile_module(&module)
         .map_err(|errors| {
             let error_msgs: Vec<String> = errors.iter().map(|e| format!("  {e}")).collect();
             anyhow::anyhow!("Compile errors:\n{}", error_msgs.join("\n"))
         })?;
 
-    // Run
-    let mut vm = stratum_core::VM::new();
-    let result = vm
-        .run(function)
+    // JIT-compiled code runs outside the interpreter and cannot be sampled
+    let mut vm = stratum_core::VM::new_without_jit();
+    vm.enable_cpu_profiling(interval);
+
+    #[cfg(feature = "gui")]
+    stratum_gui::register_gui(&mut vm);
+
+    vm.run(function)
         .map_err(|e| anyhow::anyhow!("Runtime error: {e}"))?;
 
-    // Print result
-    println!("{result}");
+    if vm.globals().contains_key("main") {
+        let main_call = stratum_core::Parser::parse_expression("main()").map_err(|errors| {
+            let error_msgs: Vec<String> = errors.iter().map(|e| format!("  {e}")).collect();
+            anyhow::anyhow!("Internal error: {}", error_msgs.join("\n"))
+        })?;
+        let main_fn = stratum_core::Compiler::new()
+            .compile_expression(&main_call)
+            .map_err(|errors| {
+                let error_msgs: Vec<String> = errors.iter().map(|e| format!("  {e}")).collect();
+                anyhow::anyhow!("Internal error: {}", error_msgs.join("\n"))
+            })?;
+        vm.run(main_fn)
+            .map_err(|e| anyhow::anyhow!("Runtime error: {e}"))?;
+    }
+
+    let profile = vm
+        .take_cpu_profile()
+        .ok_or_else(|| anyhow::anyhow!("Internal error: profiler was not enabled"))?;
+    let report = match format {
+        ProfileFormat::Summary => profile.summary(),
+        ProfileFormat::Folded => profile.to_folded(lines),
+        ProfileFormat::Speedscope => profile.to_speedscope(&path.display().to_string()),
+    };
+
+    match output {
+        Some(output) => {
+            std::fs::write(output, report).map_err(|e| {
+                anyhow::anyhow!("Failed to write profile '{}': {}", output.display(), e)
+            })?;
+            eprintln!(
+                "Wrote {} samples to {}",
+                profile.sample_count(),
+                output.display()
+            );
+        }
+        None => print!("{report}"),
+    }
 
     Ok(())
 }
 
-/// Build a Stratum source file into a standalone executable
-fn build_executable(path: &PathBuf, output: Option<PathBuf>, release: bool) -> Result<()> {
-    use stratum_core::aot::{AotCompiler, Linker, LinkerConfig};
-    use stratum_core::ast::ExecutionMode;
-
+/// Print a source file's bytecode as compiled and after optimization
+fn dump_file_bytecode(
+    path: &PathBuf,
+    mode_override: Option<stratum_core::ExecutionModeOverride>,
+) -> Result<()> {
     let source = std::fs::read_to_string(path)
         .map_err(|e| anyhow::anyhow!("Failed to read file '{}': {}", path.display(), e))?;
 
-    // Parse as module
     let module = stratum_core::Parser::parse_module(&source).map_err(|errors| {
         let error_msgs: Vec<String> = errors.iter().map(|e| format!("  {e}")).collect();
         anyhow::anyhow!("Parse errors:\n{}", error_msgs.join("\n"))
     })?;
 
-    // Type check
-    let mut type_checker = stratum_core::TypeChecker::new();
-    let type_result = type_checker.check_module(&module);
-    if !type_result.errors.is_empty() {
-        let error_msgs: Vec<String> = type_result
-            .errors
-            .iter()
-            .map(|e| format!("  {e}"))
-            .collect();
-        return Err(anyhow::anyhow!("Type errors:\n{}", error_msgs.join("\n")));
+    for (heading, optimize) in [("Before optimization", false), ("After optimization", true)] {
+        let function = stratum_core::Compiler::with_source(path.display().to_string())
+            .with_mode_override(mode_override)
+            .with_optimization(optimize)
+            .compile_module(&module)
+            .map_err(|errors| {
+                let error_msgs: Vec<String> = errors.iter().map(|e| format!("  {e}")).collect();
+                anyhow::anyhow!("Compile errors:\n{}", error_msgs.join("\n"))
+            })?;
+
+        println!("# {heading}\n");
+        println!(
+            "{}",
+            stratum_core::bytecode::disassemble_function(&function)
+        );
     }
 
-    // Compile to bytecode
-    let bytecode_fn = stratum_core::Compiler::with_source(path.display().to_string())
-        .compile_module(&module)
-        .map_err(|errors| {
-            let error_msgs: Vec<String> = errors.iter().map(|e| format!("  {e}")).collect();
-            anyhow::anyhow!("Compile errors:\n{}", error_msgs.join("\n"))
-        })?;
+    Ok(())
+}
 
-    // Create AOT compiler
-    let mut aot =
-        AotCompiler::new().map_err(|e| anyhow::anyhow!("Failed to create AOT compiler: {e}"))?;
+/// Run the tests and doctests in a Stratum source file, or across the
+/// current package
+#[allow(clippy::too_many_arguments)]
+fn run_tests(
+    suite: &str,
+    files: &[PathBuf],
+    filter: Option<&str>,
+    verbose: bool,
+    coverage: bool,
+    format: &str,
+    coverage_dir: Option<&std::path::Path>,
+    fail_under: Option<f64>,
+    coverage_data: Option<&std::path::Path>,
+    jobs: Option<usize>,
+    timeout: Option<std::time::Duration>,
+    reporter: &str,
+    report_file: Option<&std::path::Path>,
+    message_format: diagnostics::MessageFormat,
+) -> Result<()> {
+    use stratum_core::coverage::{generate_report, CoverageFormat};
+    use stratum_core::testing::{self, TestReportFormat, TestRunner};
+
+    let report_format = match reporter {
+        "pretty" => None,
+        other => Some(
+            other
+                .parse::<TestReportFormat>()
+                .map_err(|e| anyhow::anyhow!("{e}"))?,
+        ),
+    };
+    // A report or JSON diagnostics on stdout replace the human-readable output
+    let json = message_format == diagnostics::MessageFormat::Json;
+    let pretty = (report_format.is_none() || report_file.is_some()) && !json;
+    let coverage = coverage || fail_under.is_some();
 
-    // Find all functions in the module that should be compiled
-    let mut has_main = false;
-    for constant in bytecode_fn.chunk.constants() {
-        if let stratum_core::bytecode::Value::Function(func) = constant {
-            // Only compile functions marked for compilation, or all if building
-            let should_compile = matches!(
-                func.execution_mode,
-                ExecutionMode::Compile | ExecutionMode::CompileHot
-            ) || true; // For now, compile all functions in build mode
+    // Discover and filter each file's tests and doctests
+    let mut file_tests = Vec::new();
+    for file in files {
+        let module = check_source_file(file, message_format)?;
+        let mut tests = testing::discover_tests(&module);
+        tests.extend(testing::discover_doctests(&module));
+        let tests = testing::filter_tests(tests, filter);
+        if !tests.is_empty() {
+            file_tests.push((file, tests));
+        }
+    }
+    let test_count: usize = file_tests.iter().map(|(_, tests)| tests.len()).sum();
 
-            if should_compile {
-                aot.compile_function(func).map_err(|e| {
+    // A report is still written for an empty run, so CI sees the suite
+    if test_count == 0 && report_format.is_none() && !json {
+        if filter.is_some() {
+            println!("No tests matching filter found");
+        } else {
+            println!("No tests found");
+        }
+        return Ok(());
+    }
+
+    if pretty {
+        println!("Running {test_count} test(s)...\n");
+    }
+
+    // Run tests with coverage if enabled
+    let jobs = jobs.unwrap_or_else(|| {
+        std::thread::available_parallelism()
+            .map(std::num::NonZeroUsize::get)
+            .unwrap_or(1)
+    });
+    let runner = TestRunner::new()
+        .verbose(verbose)
+        .with_coverage(coverage || coverage_data.is_some())
+        .with_jobs(jobs)
+        .with_timeout(timeout);
+    let mut summary = testing::TestSummary::new();
+    for (file, tests) in &file_tests {
+        let file_summary = runner.run_tests(tests, &file.display().to_string());
+        if json {
+            emit_test_failures(file, tests, &file_summary)?;
+        }
+        summary.merge(file_summary);
+    }
+
+    if let Some(report_format) = report_format {
+        let report = testing::generate_test_report(&summary, suite, report_format);
+        match report_file {
+            Some(report_file) => std::fs::write(report_file, report).map_err(|e| {
+                anyhow::anyhow!("Failed to write report '{}': {}", report_file.display(), e)
+            })?,
+            None => print!("{report}"),
+        }
+    }
+
+    if pretty {
+        print_test_results(&summary, verbose);
+    }
+
+    if let (Some(path), Some(collector)) = (coverage_data, &summary.coverage) {
+        write_coverage_data(collector, path)?;
+    }
+
+    // Print coverage report if enabled
+    if coverage {
+        if let Some(ref collector) = summary.coverage {
+            let cov_format = format.parse::<CoverageFormat>().unwrap_or_default();
+            let report = generate_report(collector, cov_format, coverage_dir);
+            if pretty {
+                println!("{}", report);
+            } else {
+                eprintln!("{}", report);
+            }
+        }
+    }
+
+    if !summary.all_passed() {
+        return Err(anyhow::anyhow!("Some tests failed"));
+    }
+    if let (Some(threshold), Some(collector)) = (fail_under, &summary.coverage) {
+        let percent = collector.generate_summary().line_coverage_percent();
+        if percent < threshold {
+            return Err(anyhow::anyhow!(
+                "Line coverage {percent:.1}% is below the --fail-under threshold of {threshold:.1}%"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Print a JSON diagnostic for each failed test in `file`
+fn emit_test_failures(
+    file: &std::path::Path,
+    tests: &[stratum_core::testing::TestCase],
+    summary: &stratum_core::testing::TestSummary,
+) -> Result<()> {
+    let source = std::fs::read_to_string(file)
+        .map_err(|e| anyhow::anyhow!("Failed to read file '{}': {}", file.display(), e))?;
+    let reporter = diagnostics::Reporter::new(diagnostics::MessageFormat::Json, file, &source);
+    for result in summary.results.iter().filter(|result| !result.passed) {
+        let span = tests
+            .iter()
+            .find(|test| test.name == result.name)
+            .map_or_else(stratum_core::lexer::Span::dummy, |test| {
+                test.function.name.span
+            });
+        reporter.emit(&diagnostics::Diagnostic::test_failure(
+            &result.name,
+            result.error.as_deref(),
+            span,
+        ));
+    }
+    Ok(())
+}
+
+/// The files `stratum test` runs without a file argument: the package's
+/// sources and test targets, along with the package name
+fn package_test_files() -> Result<(String, Vec<PathBuf>)> {
+    let cwd = std::env::current_dir()?;
+    let package = stratum_pkg::PackageStructure::find(&cwd)
+        .map_err(|e| anyhow::anyhow!("No test file given and no package found: {e}"))?;
+    let files = workspace::package_files(&package)?;
+    Ok((package.manifest.package.name, files))
+}
+
+/// Parse and type check a source file, reporting errors as
+/// `message_format` diagnostics
+fn check_source_file(
+    path: &std::path::Path,
+    message_format: diagnostics::MessageFormat,
+) -> Result<stratum_core::ast::Module> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read file '{}': {}", path.display(), e))?;
+    let reporter = diagnostics::Reporter::new(message_format, path, &source);
+
+    // Parse as module
+    let module = stratum_core::Parser::parse_module(&source).map_err(|errors| {
+        reporter.fail(
+            &format!("Parse errors in '{}'", path.display()),
+            errors.iter().map(diagnostics::Diagnostic::parse),
+        )
+    })?;
+
+    // Type check
+    let mut type_checker = stratum_core::TypeChecker::new();
+    let type_result = type_checker.check_module(&module);
+    if !type_result.errors.is_empty() {
+        return Err(reporter.fail(
+            &format!("Type errors in '{}'", path.display()),
+            type_result
+                .errors
+                .iter()
+                .map(diagnostics::Diagnostic::type_error),
+        ));
+    }
+    Ok(module)
+}
+
+/// Parse and type check files without running them, or the current
+/// package's sources and tests if none are given
+fn check_files(files: &[PathBuf], message_format: diagnostics::MessageFormat) -> Result<()> {
+    let files = if files.is_empty() {
+        package_test_files()?.1
+    } else {
+        files.to_vec()
+    };
+
+    let mut failed = 0;
+    for file in &files {
+        if let Err(e) = check_source_file(file, message_format) {
+            eprintln!("{e}");
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        return Err(anyhow::anyhow!("{failed} file(s) had errors"));
+    }
+    if message_format == diagnostics::MessageFormat::Human {
+        println!("Checked {} file(s)", files.len());
+    }
+    Ok(())
+}
+
+/// Print each test's result and the totals
+fn print_test_results(summary: &stratum_core::testing::TestSummary, verbose: bool) {
+    for result in &summary.results {
+        let status = if result.passed { "PASS" } else { "FAIL" };
+        let duration_ms = result.duration.as_secs_f64() * 1000.0;
+
+        if result.should_panic && result.passed {
+            println!(
+                "  {} {} (expected panic) [{:.2}ms]",
+                status, result.name, duration_ms
+            );
+        } else {
+            println!("  {} {} [{:.2}ms]", status, result.name, duration_ms);
+        }
+
+        // Output is shown for failures, or for every test when verbose
+        if verbose || !result.passed {
+            for line in &result.output {
+                println!("       | {line}");
+            }
+        }
+
+        if !result.passed {
+            if let Some(ref error) = result.error {
+                println!("       Error: {error}");
+            }
+        }
+    }
+
+    // Print summary
+    println!();
+    println!(
+        "Test result: {} passed, {} failed, {} total (in {:.2}ms)",
+        summary.passed,
+        summary.failed,
+        summary.total,
+        summary.duration.as_secs_f64() * 1000.0
+    );
+}
+
+/// Evaluate a single expression
+fn eval_expression(expression: &str) -> Result<()> {
+    // Parse as expression
+    let expr = stratum_core::Parser::parse_expression(expression).map_err(|errors| {
+        let error_msgs: Vec<String> = errors.iter().map(|e| format!("  {e}")).collect();
+        anyhow::anyhow!("Parse errors:\n{}", error_msgs.join("\n"))
+    })?;
+
+    // Compile
+    let function = stratum_core::Compiler::new()
+        .compile_expression(&expr)
+        .map_err(|errors| {
+            let error_msgs: Vec<String> = errors.iter().map(|e| format!("  {e}")).collect();
+            anyhow::anyhow!("Compile errors:\n{}", error_msgs.join("\n"))
+        })?;
+
+    // Run
+    let mut vm = stratum_core::VM::new();
+    let result = vm
+        .run(function)
+        .map_err(|e| anyhow::anyhow!("Runtime error: {e}"))?;
+
+    // Print result
+    println!("{result}");
+
+    Ok(())
+}
+
+/// Build a Stratum source file into a standalone executable
+fn build_executable(path: &PathBuf, output: Option<PathBuf>, release: bool) -> Result<()> {
+    use stratum_core::aot::{AotCompiler, Linker, LinkerConfig};
+    use stratum_core::ast::ExecutionMode;
+
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read file '{}': {}", path.display(), e))?;
+
+    // Run the package's build script, if any, for the environment to embed
+    let build_output = build_script::prepare(path, release)?;
+
+    // Parse as module
+    let module = stratum_core::Parser::parse_module(&source).map_err(|errors| {
+        let error_msgs: Vec<String> = errors.iter().map(|e| format!("  {e}")).collect();
+        anyhow::anyhow!("Parse errors:\n{}", error_msgs.join("\n"))
+    })?;
+
+    // Type check
+    let mut type_checker = stratum_core::TypeChecker::new();
+    let type_result = type_checker.check_module(&module);
+    if !type_result.errors.is_empty() {
+        let error_msgs: Vec<String> = type_result
+            .errors
+            .iter()
+            .map(|e| format!("  {e}"))
+            .collect();
+        return Err(anyhow::anyhow!("Type errors:\n{}", error_msgs.join("\n")));
+    }
+
+    // Compile to bytecode
+    let bytecode_fn = stratum_core::Compiler::with_source(path.display().to_string())
+        .compile_module(&module)
+        .map_err(|errors| {
+            let error_msgs: Vec<String> = errors.iter().map(|e| format!("  {e}")).collect();
+            anyhow::anyhow!("Compile errors:\n{}", error_msgs.join("\n"))
+        })?;
+
+    // Create AOT compiler
+    let mut aot =
+        AotCompiler::new().map_err(|e| anyhow::anyhow!("Failed to create AOT compiler: {e}"))?;
+
+    // Find all functions in the module that should be compiled
+    let mut has_main = false;
+    for constant in bytecode_fn.chunk.constants() {
+        if let stratum_core::bytecode::Value::Function(func) = constant {
+            // Only compile functions marked for compilation, or all if building
+            let should_compile = matches!(
+                func.execution_mode,
+                ExecutionMode::Compile | ExecutionMode::CompileHot
+            ) || true; // For now, compile all functions in build mode
+
+            if should_compile {
+                aot.compile_function(func).map_err(|e| {
                     anyhow::anyhow!("Failed to compile function '{}': {e}", func.name)
                 })?;
                 if func.name == "main" {
@@ -915,11 +2381,20 @@ fn build_executable(path: &PathBuf, output: Option<PathBuf>, release: bool) -> R
         PathBuf::from(stem)
     });
 
-    // Link into executable
+    // Link into executable, keeping the object files of package sources in
+    // the target directory
+    let object_dir = std::fs::canonicalize(path)
+        .ok()
+        .and_then(|path| stratum_pkg::TargetDir::find(path.parent()?))
+        .map(|target| target.objects(release));
     let linker = Linker::new(LinkerConfig {
         output: output_path.clone(),
         optimize: release,
         extra_flags: Vec::new(),
+        env: build_output
+            .map(|output| output.env_pairs())
+            .unwrap_or_default(),
+        object_dir,
     });
 
     linker
@@ -932,6 +2407,19 @@ fn build_executable(path: &PathBuf, output: Option<PathBuf>, release: bool) -> R
 }
 
 /// Launch Stratum Workshop IDE
+/// Watch a GUI app's source file, reloading its view when it changes
+#[cfg(feature = "gui")]
+#[allow(clippy::unnecessary_wraps)]
+fn enable_hot_reload(path: &std::path::Path) -> Result<()> {
+    stratum_gui::enable_hot_reload(path);
+    Ok(())
+}
+
+#[cfg(not(feature = "gui"))]
+fn enable_hot_reload(_path: &std::path::Path) -> Result<()> {
+    anyhow::bail!("--hot-reload needs stratum built with the gui feature")
+}
+
 #[cfg(feature = "workshop")]
 fn launch_workshop(path: Option<PathBuf>) -> Result<()> {
     stratum_workshop::launch(path).map_err(|e| anyhow::anyhow!("Workshop error: {e}"))
@@ -951,152 +2439,6 @@ fn run_dap_server() -> Result<()> {
     dap::run_dap_server()
 }
 
-/// Generate documentation for Stratum source files
-fn generate_documentation(
-    path: &PathBuf,
-    output: Option<PathBuf>,
-    format: &str,
-    open: bool,
-) -> Result<()> {
-    use stratum_core::doc::{
-        generate_search_index, DocExtractor, HtmlGenerator, HtmlOptions, MarkdownGenerator,
-        ProjectDoc,
-    };
-
-    // Collect source files
-    let files = if path.is_file() {
-        vec![path.clone()]
-    } else if path.is_dir() {
-        collect_stratum_files(path)?
-    } else {
-        return Err(anyhow::anyhow!("Path '{}' does not exist", path.display()));
-    };
-
-    if files.is_empty() {
-        return Err(anyhow::anyhow!(
-            "No .strat files found in '{}'",
-            path.display()
-        ));
-    }
-
-    // Determine project name
-    let project_name = path
-        .file_name()
-        .and_then(|s| s.to_str())
-        .unwrap_or("Project");
-
-    // Determine output directory
-    let output_dir = output.unwrap_or_else(|| {
-        if path.is_file() {
-            path.parent().unwrap_or(path).join("doc")
-        } else {
-            path.join("doc")
-        }
-    });
-
-    // Create output directory
-    std::fs::create_dir_all(&output_dir)
-        .map_err(|e| anyhow::anyhow!("Failed to create output directory: {}", e))?;
-
-    let is_html = format != "markdown" && format != "md";
-    let extension = if is_html { "html" } else { "md" };
-
-    // Build project-wide documentation
-    let mut project = ProjectDoc::new(project_name);
-    let mut generated_files = Vec::new();
-
-    // First pass: parse all files and build project index
-    for file in &files {
-        let source = std::fs::read_to_string(file)
-            .map_err(|e| anyhow::anyhow!("Failed to read '{}': {}", file.display(), e))?;
-
-        let module = match stratum_core::Parser::parse_module(&source) {
-            Ok(m) => m,
-            Err(errors) => {
-                eprintln!("Parse errors in '{}':", file.display());
-                for e in &errors {
-                    eprintln!("  {}", e);
-                }
-                continue;
-            }
-        };
-
-        let module_name = file
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("unknown");
-        let doc_module = DocExtractor::extract(&module, module_name);
-        project.add_module(doc_module);
-    }
-
-    if project.modules.is_empty() {
-        return Err(anyhow::anyhow!("No documentation was generated"));
-    }
-
-    // HTML options with search and cross-linking enabled
-    let options = HtmlOptions {
-        enable_search: is_html,
-        enable_crosslinks: is_html,
-    };
-
-    // Second pass: generate output with cross-linking
-    for module in &project.modules {
-        let content = if is_html {
-            HtmlGenerator::generate_with_project(module, &project, &options)
-        } else {
-            MarkdownGenerator::generate(module)
-        };
-
-        let output_file = output_dir.join(format!("{}.{}", module.name, extension));
-        std::fs::write(&output_file, &content)
-            .map_err(|e| anyhow::anyhow!("Failed to write '{}': {}", output_file.display(), e))?;
-
-        println!("Generated: {}", output_file.display());
-        generated_files.push(output_file);
-    }
-
-    // Generate index file
-    if is_html {
-        // HTML index with full project view
-        let index_content = HtmlGenerator::generate_index(&project, &options);
-        let index_file = output_dir.join("index.html");
-        std::fs::write(&index_file, &index_content)
-            .map_err(|e| anyhow::anyhow!("Failed to write index: {}", e))?;
-        println!("Generated: {}", index_file.display());
-        generated_files.insert(0, index_file);
-
-        // Generate search index JSON
-        let search_index = generate_search_index(&project);
-        let search_file = output_dir.join("search-index.json");
-        std::fs::write(&search_file, &search_index)
-            .map_err(|e| anyhow::anyhow!("Failed to write search index: {}", e))?;
-        println!("Generated: {}", search_file.display());
-    } else if project.modules.len() > 1 {
-        // Markdown index
-        let index_file = output_dir.join("index.md");
-        let index_content = generate_index(&files, format);
-        std::fs::write(&index_file, &index_content)
-            .map_err(|e| anyhow::anyhow!("Failed to write index: {}", e))?;
-        println!("Generated: {}", index_file.display());
-        generated_files.insert(0, index_file);
-    }
-
-    // Open in browser if requested
-    if open {
-        let file_to_open = &generated_files[0];
-        if let Err(e) = open_in_browser(file_to_open) {
-            eprintln!("Warning: Could not open browser: {}", e);
-        }
-    }
-
-    println!("\nDocumentation generated in: {}", output_dir.display());
-    if is_html {
-        println!("Features enabled: cross-linking, search");
-    }
-
-    Ok(())
-}
-
 /// Collect all .strat files in a directory
 fn collect_stratum_files(dir: &PathBuf) -> Result<Vec<PathBuf>> {
     let mut files = Vec::new();
@@ -1124,83 +2466,31 @@ fn collect_stratum_files(dir: &PathBuf) -> Result<Vec<PathBuf>> {
     Ok(files)
 }
 
-/// Generate an index page for multiple documented modules
-fn generate_index(files: &[PathBuf], format: &str) -> String {
-    if format == "markdown" || format == "md" {
-        let mut output = String::from("# Documentation\n\n");
-        output.push_str("## Modules\n\n");
-        for file in files {
-            let name = file
-                .file_stem()
-                .and_then(|s| s.to_str())
-                .unwrap_or("unknown");
-            output.push_str(&format!("- [{}]({}.md)\n", name, name));
-        }
-        output
-    } else {
-        let mut output = String::new();
-        output.push_str("<!DOCTYPE html>\n");
-        output.push_str("<html lang=\"en\">\n");
-        output.push_str("<head>\n");
-        output.push_str("  <meta charset=\"UTF-8\">\n");
-        output.push_str("  <title>Documentation Index</title>\n");
-        output.push_str("  <style>\n");
-        output.push_str("    body { font-family: sans-serif; max-width: 800px; margin: 2rem auto; padding: 0 1rem; }\n");
-        output.push_str("    h1 { color: #7b68ee; }\n");
-        output.push_str("    ul { list-style: none; padding: 0; }\n");
-        output.push_str("    li { margin: 0.5rem 0; }\n");
-        output.push_str("    a { color: #7b68ee; text-decoration: none; }\n");
-        output.push_str("    a:hover { text-decoration: underline; }\n");
-        output.push_str("  </style>\n");
-        output.push_str("</head>\n");
-        output.push_str("<body>\n");
-        output.push_str("  <h1>Documentation</h1>\n");
-        output.push_str("  <h2>Modules</h2>\n");
-        output.push_str("  <ul>\n");
-        for file in files {
-            let name = file
-                .file_stem()
-                .and_then(|s| s.to_str())
-                .unwrap_or("unknown");
-            output.push_str(&format!(
-                "    <li><a href=\"{}.html\">{}</a></li>\n",
-                name, name
-            ));
+/// Organize the imports of `source` first when `stratum fmt --organize-imports` asks for it
+fn prepare_for_format(source: &str, organize_imports: bool) -> String {
+    let mut input = source.to_string();
+    if organize_imports {
+        if let Some(edit) = stratum_core::Formatter::organize_imports(source) {
+            input.replace_range(edit.span.start as usize..edit.span.end as usize, &edit.text);
         }
-        output.push_str("  </ul>\n");
-        output.push_str("</body>\n");
-        output.push_str("</html>\n");
-        output
     }
+    input
 }
 
-/// Open a file in the default browser
-fn open_in_browser(path: &PathBuf) -> Result<()> {
-    let url = format!("file://{}", path.canonicalize()?.display());
-
-    #[cfg(target_os = "macos")]
-    {
-        std::process::Command::new("open").arg(&url).spawn()?;
-    }
+/// Format Stratum source files
+///
+/// With JSON diagnostics, parse errors and (with `check`) unformatted files
+/// are printed as diagnostics in place of the usual messages.
+fn format_files(
+    files: &[PathBuf],
+    check: bool,
+    organize_imports: bool,
+    config: &stratum_core::FormatConfig,
+    message_format: diagnostics::MessageFormat,
+) -> Result<()> {
+    use std::io::{self, Read, Write};
 
-    #[cfg(target_os = "linux")]
-    {
-        std::process::Command::new("xdg-open").arg(&url).spawn()?;
-    }
-
-    #[cfg(target_os = "windows")]
-    {
-        std::process::Command::new("cmd")
-            .args(["/C", "start", "", &url])
-            .spawn()?;
-    }
-
-    Ok(())
-}
-
-/// Format Stratum source files
-fn format_files(files: &[PathBuf], check: bool) -> Result<()> {
-    use std::io::{self, Read, Write};
+    let json = message_format == diagnostics::MessageFormat::Json;
 
     // If no files specified, read from stdin and write to stdout
     if files.is_empty() {
@@ -1209,15 +2499,22 @@ fn format_files(files: &[PathBuf], check: bool) -> Result<()> {
             .read_to_string(&mut source)
             .map_err(|e| anyhow::anyhow!("Failed to read from stdin: {e}"))?;
 
-        let module = stratum_core::Parser::parse_module(&source).map_err(|errors| {
-            let error_msgs: Vec<String> = errors.iter().map(|e| format!("  {e}")).collect();
-            anyhow::anyhow!("Parse errors:\n{}", error_msgs.join("\n"))
+        let stdin = std::path::Path::new("<stdin>");
+        let input = prepare_for_format(&source, organize_imports);
+        let module = stratum_core::Parser::parse_module(&input).map_err(|errors| {
+            diagnostics::Reporter::new(message_format, stdin, &input).fail(
+                "Parse errors",
+                errors.iter().map(diagnostics::Diagnostic::parse),
+            )
         })?;
 
-        let formatted = stratum_core::Formatter::format_module(&module);
+        let formatted =
+            stratum_core::Formatter::format_source_with_config(&input, &module, config.clone());
 
         if check {
             if source != formatted {
+                diagnostics::Reporter::new(message_format, stdin, &source)
+                    .emit(&diagnostics::Diagnostic::unformatted(&source, formatted));
                 return Err(anyhow::anyhow!("stdin is not formatted"));
             }
         } else {
@@ -1242,161 +2539,1027 @@ fn format_files(files: &[PathBuf], check: bool) -> Result<()> {
             }
         };
 
-        let module = match stratum_core::Parser::parse_module(&source) {
+        let input = prepare_for_format(&source, organize_imports);
+        let module = match stratum_core::Parser::parse_module(&input) {
             Ok(m) => m,
+            Err(errors) => {
+                let error = diagnostics::Reporter::new(message_format, file, &input).fail(
+                    &format!("Parse errors in '{}'", file.display()),
+                    errors.iter().map(diagnostics::Diagnostic::parse),
+                );
+                eprintln!("{error}");
+                error_files.push(file.clone());
+                continue;
+            }
+        };
+
+        let formatted =
+            stratum_core::Formatter::format_source_with_config(&input, &module, config.clone());
+
+        if check {
+            if source != formatted {
+                if json {
+                    diagnostics::Reporter::new(message_format, file, &source)
+                        .emit(&diagnostics::Diagnostic::unformatted(&source, formatted));
+                } else {
+                    println!("Would reformat: {}", file.display());
+                }
+                unformatted_files.push(file.clone());
+            }
+        } else if source != formatted {
+            match std::fs::write(file, &formatted) {
+                Ok(()) if json => {}
+                Ok(()) => println!("Formatted: {}", file.display()),
+                Err(e) => {
+                    eprintln!("Error writing '{}': {}", file.display(), e);
+                    error_files.push(file.clone());
+                }
+            }
+        }
+    }
+
+    // Report results
+    if check {
+        if !unformatted_files.is_empty() {
+            eprintln!("\n{} file(s) would be reformatted", unformatted_files.len());
+            return Err(anyhow::anyhow!("Some files are not formatted"));
+        }
+        if !error_files.is_empty() {
+            return Err(anyhow::anyhow!("Some files had errors"));
+        }
+        if !json {
+            println!("All files are properly formatted");
+        }
+    } else if !error_files.is_empty() {
+        return Err(anyhow::anyhow!("{} file(s) had errors", error_files.len()));
+    }
+
+    Ok(())
+}
+
+/// Print the explanation of an error code, or list every code
+fn explain_error(code: Option<&str>) -> Result<()> {
+    use stratum_core::error_codes;
+
+    let Some(code) = code else {
+        for entry in error_codes::ERROR_CODES {
+            println!("{}  {}", entry.code, entry.summary);
+        }
+        return Ok(());
+    };
+    let entry = error_codes::lookup(code).ok_or_else(|| {
+        anyhow::anyhow!("Unknown error code '{code}' (run `stratum explain` to list them)")
+    })?;
+    println!("{}: {}\n", entry.code, entry.summary);
+    print!("{}", entry.explanation);
+    println!("\nSee also: {}", entry.url());
+    Ok(())
+}
+
+/// Lint Stratum source files, optionally applying safe fixes
+fn lint_files(paths: &[PathBuf], format: &str, fix: bool) -> Result<()> {
+    use stratum_core::lexer::LineIndex;
+    use stratum_core::lint::{apply_fixes, LintLevel};
+
+    let json = match format {
+        "text" => false,
+        "json" => true,
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Unknown lint format '{format}', expected text or json"
+            ))
+        }
+    };
+
+    let linter = stratum_core::Linter::new(load_lint_config()?);
+
+    let roots = if paths.is_empty() {
+        vec![PathBuf::from(".")]
+    } else {
+        paths.to_vec()
+    };
+    let mut files = Vec::new();
+    for root in &roots {
+        if root.is_dir() {
+            files.extend(collect_stratum_files(root)?);
+        } else {
+            files.push(root.clone());
+        }
+    }
+    files.sort();
+
+    let mut entries = Vec::new();
+    let mut warnings = 0;
+    let mut denied = 0;
+    let mut fixed = 0;
+    let mut error_files = 0;
+
+    for file in &files {
+        let mut source = match std::fs::read_to_string(file) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("Error reading '{}': {}", file.display(), e);
+                error_files += 1;
+                continue;
+            }
+        };
+
+        let mut diagnostics = match linter.lint_source(&source) {
+            Ok(diagnostics) => diagnostics,
             Err(errors) => {
                 eprintln!("Parse errors in '{}':", file.display());
                 for e in &errors {
                     eprintln!("  {e}");
                 }
-                error_files.push(file.clone());
+                error_files += 1;
                 continue;
             }
         };
 
-        let formatted = stratum_core::Formatter::format_module(&module);
+        if fix {
+            let (fixed_source, applied) = apply_fixes(&source, &diagnostics);
+            if applied > 0 {
+                std::fs::write(file, &fixed_source)
+                    .map_err(|e| anyhow::anyhow!("Failed to write '{}': {}", file.display(), e))?;
+                fixed += applied;
+                // Report only what the fixes left behind
+                diagnostics = linter.lint_source(&fixed_source).unwrap_or_default();
+                source = fixed_source;
+            }
+        }
+
+        let index = LineIndex::new(&source);
+        for diagnostic in &diagnostics {
+            match diagnostic.level {
+                LintLevel::Deny => denied += 1,
+                LintLevel::Warn => warnings += 1,
+                LintLevel::Allow => {}
+            }
+
+            let start = index.location(diagnostic.span.start);
+            let end = index.location(diagnostic.span.end);
+            if json {
+                let fix = diagnostic.fix.as_ref().map(|fix| {
+                    let edits: Vec<_> = fix
+                        .edits
+                        .iter()
+                        .map(|edit| {
+                            let start = index.location(edit.span.start);
+                            let end = index.location(edit.span.end);
+                            serde_json::json!({
+                                "line": start.line,
+                                "column": start.column,
+                                "end_line": end.line,
+                                "end_column": end.column,
+                                "replacement": edit.replacement,
+                            })
+                        })
+                        .collect();
+                    serde_json::json!({ "description": fix.description, "edits": edits })
+                });
+                entries.push(serde_json::json!({
+                    "file": file.display().to_string(),
+                    "rule": diagnostic.rule.name(),
+                    "level": diagnostic.level.name(),
+                    "message": diagnostic.message,
+                    "line": start.line,
+                    "column": start.column,
+                    "end_line": end.line,
+                    "end_column": end.column,
+                    "fix": fix,
+                }));
+            } else {
+                let label = if diagnostic.level == LintLevel::Deny {
+                    "error"
+                } else {
+                    "warning"
+                };
+                println!(
+                    "{}:{start}: {label}[{}]: {}",
+                    file.display(),
+                    diagnostic.rule,
+                    diagnostic.message
+                );
+                if let Some(fix) = &diagnostic.fix {
+                    println!("  help: {} (fixable with --fix)", fix.description);
+                }
+            }
+        }
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+    } else {
+        if fixed > 0 {
+            eprintln!("Applied {fixed} fix(es)");
+        }
+        if warnings + denied > 0 {
+            eprintln!("{warnings} warning(s), {denied} error(s)");
+        } else if error_files == 0 {
+            eprintln!("No lint findings in {} file(s)", files.len());
+        }
+    }
+
+    if error_files > 0 {
+        return Err(anyhow::anyhow!("{error_files} file(s) could not be linted"));
+    }
+    if denied > 0 {
+        return Err(anyhow::anyhow!("{denied} denied lint finding(s)"));
+    }
+    Ok(())
+}
+
+/// Read lint levels from the `[lints]` table of the package around the
+/// current directory; outside a package every rule keeps its default
+fn load_lint_config() -> Result<stratum_core::lint::LintConfig> {
+    let cwd = std::env::current_dir()?;
+    let Ok(layout) = stratum_pkg::PackageLayout::find_root(&cwd) else {
+        return Ok(stratum_core::lint::LintConfig::default());
+    };
+
+    let manifest = stratum_pkg::Manifest::from_path(&layout.manifest_path)
+        .map_err(|e| anyhow::anyhow!("Failed to read '{}': {e}", layout.manifest_path.display()))?;
+    stratum_core::lint::LintConfig::from_table(
+        manifest
+            .lints
+            .iter()
+            .map(|(rule, level)| (rule.as_str(), level.as_str())),
+    )
+    .map_err(|e| {
+        anyhow::anyhow!(
+            "Invalid [lints] in '{}': {e}",
+            layout.manifest_path.display()
+        )
+    })
+}
+
+/// Generate shell completions and write them to stdout
+fn generate_completions(shell: Shell) {
+    let mut cmd = Cli::command();
+    generate(shell, &mut cmd, "stratum", &mut io::stdout());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_cli() {
+        use clap::CommandFactory;
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_eval_simple_expression() {
+        // This tests the parsing/compilation path without running
+        let expr = stratum_core::Parser::parse_expression("1 + 2").unwrap();
+        let _function = stratum_core::Compiler::new()
+            .compile_expression(&expr)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_script_argument() {
+        use clap::Parser as ClapParser;
+        let cli = Cli::try_parse_from(&["stratum", "script.strat"]).unwrap();
+        assert!(cli.command.is_none());
+        assert_eq!(cli.script, Some(PathBuf::from("script.strat")));
+
+        let cli = Cli::try_parse_from(&["stratum", "script.strat", "a", "--verbose"]).unwrap();
+        assert_eq!(cli.script_args, vec!["a", "--verbose"]);
+
+        let cli = Cli::try_parse_from(&["stratum", "run", "script.strat"]).unwrap();
+        assert!(matches!(cli.command, Some(Commands::Run { .. })));
+        assert!(cli.script.is_none());
+
+        // Permissions go before the script, as on a `#!/usr/bin/env -S` line
+        let cli = Cli::try_parse_from(&["stratum", "--allow-net", "script.strat", "--allow-read"])
+            .unwrap();
+        assert_eq!(cli.permissions.allow_net, Some(Vec::new()));
+        assert_eq!(cli.script_args, vec!["--allow-read"]);
+    }
+
+    #[test]
+    fn test_run_jit_flags() {
+        use clap::Parser as ClapParser;
+        let cli = Cli::try_parse_from(&[
+            "stratum",
+            "run",
+            "test.strat",
+            "--no-jit",
+            "--hot-threshold",
+            "5",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Commands::Run {
+                jit,
+                no_jit,
+                hot_threshold,
+                ..
+            }) => {
+                assert!(!jit);
+                assert!(no_jit);
+                assert_eq!(hot_threshold, Some(5));
+            }
+            _ => panic!("Expected Run command"),
+        }
+        assert!(Cli::try_parse_from(&["stratum", "run", "t.strat", "--jit", "--no-jit"]).is_err());
+    }
+
+    #[test]
+    fn test_run_hot_reload_flag() {
+        use clap::Parser as ClapParser;
+        let cli = Cli::try_parse_from(&["stratum", "run", "app.strat", "--hot-reload"]).unwrap();
+        match cli.command {
+            Some(Commands::Run { hot_reload, .. }) => assert!(hot_reload),
+            _ => panic!("Expected Run command"),
+        }
+    }
+
+    #[test]
+    fn test_run_permission_flags() {
+        use clap::Parser as ClapParser;
+        use stratum_core::{Capability, Grant, Permissions};
+
+        let permissions = |flags: &[&str]| {
+            let cli =
+                Cli::try_parse_from(["stratum", "run", "app.strat"].iter().chain(flags).copied())
+                    .unwrap();
+            match cli.command {
+                Some(Commands::Run { permissions, .. }) => permissions.to_permissions(),
+                _ => panic!("Expected Run command"),
+            }
+        };
+
+        assert_eq!(permissions(&[]), Permissions::deny_all());
+        assert_eq!(permissions(&["-A"]), Permissions::allow_all());
+
+        let granted = permissions(&[
+            "--allow-net=api.example.com,localhost:8080",
+            "--allow-run",
+            "--allow-env=HOME",
+        ]);
+        assert_eq!(
+            granted.grant(Capability::Net),
+            &Grant::Only(vec!["api.example.com".into(), "localhost:8080".into()])
+        );
+        assert_eq!(granted.grant(Capability::Run), &Grant::All);
+        assert_eq!(
+            granted.grant(Capability::Env),
+            &Grant::Only(vec!["HOME".into()])
+        );
+        assert_eq!(granted.grant(Capability::Read), &Grant::Denied);
+
+        // Granted paths are resolved against the current directory
+        let read = permissions(&["--allow-read=./data"]);
+        let data = std::env::current_dir().unwrap().join("data");
+        assert_eq!(
+            read.grant(Capability::Read),
+            &Grant::Only(vec![data.display().to_string()])
+        );
+    }
+
+    #[test]
+    fn test_explain_command() {
+        use clap::Parser as ClapParser;
+        let cli = Cli::try_parse_from(&["stratum", "explain", "E0100"]).unwrap();
+        match cli.command {
+            Some(Commands::Explain { code }) => assert_eq!(code.as_deref(), Some("E0100")),
+            _ => panic!("Expected Explain command"),
+        }
+        assert!(explain_error(Some("e100")).is_ok());
+        assert!(explain_error(Some("E9999")).is_err());
+    }
+
+    #[test]
+    fn test_message_format_flag() {
+        use clap::Parser as ClapParser;
+        let cli = Cli::try_parse_from(&["stratum", "check", "a.strat", "--message-format", "json"])
+            .unwrap();
+        match cli.command {
+            Some(Commands::Check {
+                files,
+                message_format,
+                ..
+            }) => {
+                assert_eq!(files, vec![PathBuf::from("a.strat")]);
+                assert_eq!(message_format, "json");
+            }
+            _ => panic!("Expected Check command"),
+        }
+        let cli = Cli::try_parse_from(&["stratum", "fmt", "--check"]).unwrap();
+        match cli.command {
+            Some(Commands::Fmt { message_format, .. }) => assert_eq!(message_format, "human"),
+            _ => panic!("Expected Fmt command"),
+        }
+    }
+
+    #[test]
+    fn test_check_source_file_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bad.strat");
+        std::fs::write(&path, "fx main() {\n    let x: Int = \"a\"\n}\n").unwrap();
+        let human = check_source_file(&path, diagnostics::MessageFormat::Human).unwrap_err();
+        assert!(human.to_string().starts_with("Type errors in '"));
+        let json = check_source_file(&path, diagnostics::MessageFormat::Json).unwrap_err();
+        assert!(json.to_string().starts_with("1 error(s) in '"));
+    }
+
+    #[test]
+    fn test_run_program_args() {
+        use clap::Parser as ClapParser;
+        let cli = Cli::try_parse_from(&["stratum", "run", "test.strat", "--", "a", "--b"]).unwrap();
+        match cli.command {
+            Some(Commands::Run { args, .. }) => assert_eq!(args, vec!["a", "--b"]),
+            _ => panic!("Expected Run command"),
+        }
+        assert!(Cli::try_parse_from(&["stratum", "run", "test.strat", "a"]).is_err());
+    }
+
+    #[test]
+    fn test_run_file_exit_code() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("exit.strat");
+        std::fs::write(
+            &path,
+            "fx main() -> Int {\n    if Args.get(0) == \"fail\" { return 3 }\n    0\n}\n",
+        )
+        .unwrap();
+        let run = |args: &[&str]| {
+            let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+            run_file(
+                &path,
+                &args,
+                &config::RunConfig::default(),
+                stratum_core::Permissions::deny_all(),
+                None,
+                None,
+                false,
+                None,
+                diagnostics::MessageFormat::Human,
+            )
+            .unwrap()
+        };
+        assert_eq!(run(&["fail"]), 3);
+        assert_eq!(run(&[]), 0);
+    }
+
+    #[test]
+    fn test_prepare_script_without_deps() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("plain.strat");
+        prepare_script_environment(&path, "#!/usr/bin/env stratum\nprintln(1)\n").unwrap();
+        assert!(prepare_script_environment(&path, "//! deps: http = \n").is_err());
+    }
+
+    #[test]
+    fn test_run_with_interpret_all_flag() {
+        use clap::Parser as ClapParser;
+        let cli =
+            Cli::try_parse_from(&["stratum", "run", "test.strat", "--interpret-all"]).unwrap();
+        match cli.command {
+            Some(Commands::Run {
+                interpret_all,
+                compile_all,
+                jit,
+                ..
+            }) => {
+                assert!(interpret_all);
+                assert!(!compile_all);
+                assert!(!jit);
+            }
+            _ => panic!("Expected Run command"),
+        }
+    }
+
+    #[test]
+    fn test_run_with_compile_all_flag() {
+        use clap::Parser as ClapParser;
+        let cli = Cli::try_parse_from(&["stratum", "run", "test.strat", "--compile-all"]).unwrap();
+        match cli.command {
+            Some(Commands::Run {
+                interpret_all,
+                compile_all,
+                jit,
+                ..
+            }) => {
+                assert!(!interpret_all);
+                assert!(compile_all);
+                assert!(!jit);
+            }
+            _ => panic!("Expected Run command"),
+        }
+    }
+
+    #[test]
+    fn test_run_with_jit_flag() {
+        use clap::Parser as ClapParser;
+        let cli = Cli::try_parse_from(&["stratum", "run", "test.strat", "--jit"]).unwrap();
+        match cli.command {
+            Some(Commands::Run {
+                interpret_all,
+                compile_all,
+                jit,
+                ..
+            }) => {
+                assert!(!interpret_all);
+                assert!(!compile_all);
+                assert!(jit);
+            }
+            _ => panic!("Expected Run command"),
+        }
+    }
+
+    #[test]
+    fn test_run_flags_conflict() {
+        use clap::Parser as ClapParser;
+        // --interpret-all and --compile-all are mutually exclusive
+        let result = Cli::try_parse_from(&[
+            "stratum",
+            "run",
+            "test.strat",
+            "--interpret-all",
+            "--compile-all",
+        ]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_run_with_memory_profile_flag() {
+        use clap::Parser as ClapParser;
+        let cli =
+            Cli::try_parse_from(&["stratum", "run", "test.strat", "--memory-profile"]).unwrap();
+        match cli.command {
+            Some(Commands::Run { memory_profile, .. }) => {
+                assert!(memory_profile);
+            }
+            _ => panic!("Expected Run command"),
+        }
+    }
+
+    #[test]
+    fn test_profile_command() {
+        use clap::Parser as ClapParser;
+        let cli = Cli::try_parse_from(&[
+            "stratum",
+            "profile",
+            "test.strat",
+            "--format",
+            "folded",
+            "-o",
+            "out.folded",
+            "--interval",
+            "100",
+            "--lines",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Commands::Profile {
+                file,
+                format,
+                output,
+                interval,
+                lines,
+            }) => {
+                assert_eq!(file, PathBuf::from("test.strat"));
+                assert_eq!(format, "folded");
+                assert_eq!(output, Some(PathBuf::from("out.folded")));
+                assert_eq!(interval, 100);
+                assert!(lines);
+            }
+            _ => panic!("Expected Profile command"),
+        }
+
+        let cli = Cli::try_parse_from(&["stratum", "profile", "test.strat"]).unwrap();
+        match cli.command {
+            Some(Commands::Profile {
+                format,
+                output,
+                interval,
+                lines,
+                ..
+            }) => {
+                assert_eq!(format, "summary");
+                assert_eq!(output, None);
+                assert_eq!(interval, stratum_core::DEFAULT_SAMPLE_INTERVAL);
+                assert!(!lines);
+            }
+            _ => panic!("Expected Profile command"),
+        }
+    }
+
+    #[test]
+    fn test_run_with_memory_profile_format() {
+        use clap::Parser as ClapParser;
+        let cli = Cli::try_parse_from(&[
+            "stratum",
+            "run",
+            "test.strat",
+            "--memory-profile",
+            "--format",
+            "html",
+            "--profile-output",
+            "report.html",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Commands::Run {
+                memory_profile,
+                format,
+                profile_output,
+                ..
+            }) => {
+                assert!(memory_profile);
+                assert_eq!(format, "html");
+                assert_eq!(profile_output, Some(PathBuf::from("report.html")));
+            }
+            _ => panic!("Expected Run command"),
+        }
+
+        // The report options only apply to a memory profile
+        let result = Cli::try_parse_from(&["stratum", "run", "test.strat", "--format", "html"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_test_jobs_and_timeout() {
+        use clap::Parser as ClapParser;
+        let cli = Cli::try_parse_from(&[
+            "stratum",
+            "test",
+            "tests.strat",
+            "-j",
+            "4",
+            "--timeout",
+            "30",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Commands::Test { jobs, timeout, .. }) => {
+                assert_eq!(jobs, Some(4));
+                assert_eq!(timeout, Some(30));
+            }
+            _ => panic!("Expected Test command"),
+        }
+
+        let cli = Cli::try_parse_from(&["stratum", "test", "tests.strat"]).unwrap();
+        match cli.command {
+            Some(Commands::Test { jobs, timeout, .. }) => {
+                assert_eq!(jobs, None);
+                assert_eq!(timeout, None);
+            }
+            _ => panic!("Expected Test command"),
+        }
+    }
+
+    #[test]
+    fn test_test_fail_under() {
+        use clap::Parser as ClapParser;
+        let cli = Cli::try_parse_from(&[
+            "stratum",
+            "test",
+            "tests.strat",
+            "--format",
+            "cobertura",
+            "--fail-under",
+            "85.5",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Commands::Test {
+                format, fail_under, ..
+            }) => {
+                assert_eq!(format, "cobertura");
+                assert_eq!(fail_under, Some(85.5));
+            }
+            _ => panic!("Expected Test command"),
+        }
+    }
+
+    #[test]
+    fn test_doc_coverage() {
+        use clap::Parser as ClapParser;
+        let cli = Cli::try_parse_from(&["stratum", "doc", "--fail-under", "90"]).unwrap();
+        match cli.command {
+            Some(Commands::Doc {
+                coverage,
+                fail_under,
+                ..
+            }) => {
+                assert!(!coverage);
+                assert_eq!(fail_under, Some(90.0));
+            }
+            _ => panic!("Expected Doc command"),
+        }
+    }
+
+    #[test]
+    fn test_coverage_merge() {
+        use clap::Parser as ClapParser;
+        let cli = Cli::try_parse_from(&[
+            "stratum",
+            "coverage",
+            "merge",
+            "shard1.json",
+            "shard2.json",
+            "--format",
+            "lcov",
+            "-o",
+            "merged.json",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Commands::Coverage(CoverageCommand::Merge {
+                files,
+                format,
+                output,
+                ..
+            })) => {
+                assert_eq!(files.len(), 2);
+                assert_eq!(format, "lcov");
+                assert_eq!(output, Some(PathBuf::from("merged.json")));
+            }
+            _ => panic!("Expected Coverage merge command"),
+        }
+
+        // At least one data file is required
+        assert!(Cli::try_parse_from(&["stratum", "coverage", "merge"]).is_err());
 
-        if check {
-            if source != formatted {
-                println!("Would reformat: {}", file.display());
-                unformatted_files.push(file.clone());
-            }
-        } else if source != formatted {
-            match std::fs::write(file, &formatted) {
-                Ok(()) => println!("Formatted: {}", file.display()),
-                Err(e) => {
-                    eprintln!("Error writing '{}': {}", file.display(), e);
-                    error_files.push(file.clone());
-                }
+        let cli = Cli::try_parse_from(&[
+            "stratum",
+            "run",
+            "main.strat",
+            "--coverage-data",
+            "run.json",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Commands::Run { coverage_data, .. }) => {
+                assert_eq!(coverage_data, Some(PathBuf::from("run.json")));
             }
+            _ => panic!("Expected Run command"),
         }
     }
 
-    // Report results
-    if check {
-        if !unformatted_files.is_empty() {
-            eprintln!("\n{} file(s) would be reformatted", unformatted_files.len());
-            return Err(anyhow::anyhow!("Some files are not formatted"));
+    #[test]
+    fn test_fuzz_command() {
+        use clap::Parser as ClapParser;
+        let cli = Cli::try_parse_from(&["stratum", "fuzz", "parse_header"]).unwrap();
+        match cli.command {
+            Some(Commands::Fuzz {
+                target,
+                file,
+                runs,
+                max_len,
+                seed,
+                ..
+            }) => {
+                assert_eq!(target, "parse_header");
+                assert!(file.is_none());
+                assert_eq!(runs, 10000);
+                assert_eq!(max_len, 256);
+                assert!(seed.is_none());
+            }
+            _ => panic!("Expected Fuzz command"),
         }
-        if !error_files.is_empty() {
-            return Err(anyhow::anyhow!("Some files had errors"));
+
+        let cli = Cli::try_parse_from(&[
+            "stratum",
+            "fuzz",
+            "check",
+            "-f",
+            "lib.strat",
+            "--runs",
+            "50",
+            "--seed",
+            "3",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Commands::Fuzz {
+                file, runs, seed, ..
+            }) => {
+                assert_eq!(file, Some(PathBuf::from("lib.strat")));
+                assert_eq!(runs, 50);
+                assert_eq!(seed, Some(3));
+            }
+            _ => panic!("Expected Fuzz command"),
         }
-        println!("All files are properly formatted");
-    } else if !error_files.is_empty() {
-        return Err(anyhow::anyhow!("{} file(s) had errors", error_files.len()));
     }
 
-    Ok(())
-}
-
-/// Generate shell completions and write them to stdout
-fn generate_completions(shell: Shell) {
-    let mut cmd = Cli::command();
-    generate(shell, &mut cmd, "stratum", &mut io::stdout());
-}
+    #[test]
+    fn test_test_file_is_optional() {
+        use clap::Parser as ClapParser;
+        let cli = Cli::try_parse_from(&["stratum", "test"]).unwrap();
+        match cli.command {
+            Some(Commands::Test { file, .. }) => assert_eq!(file, None),
+            _ => panic!("Expected Test command"),
+        }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let cli = Cli::try_parse_from(&["stratum", "test", "tests.strat"]).unwrap();
+        match cli.command {
+            Some(Commands::Test { file, .. }) => {
+                assert_eq!(file, Some(PathBuf::from("tests.strat")));
+            }
+            _ => panic!("Expected Test command"),
+        }
+    }
 
     #[test]
-    fn verify_cli() {
-        use clap::CommandFactory;
-        Cli::command().debug_assert();
+    fn test_test_reporter() {
+        use clap::Parser as ClapParser;
+        let cli = Cli::try_parse_from(&[
+            "stratum",
+            "test",
+            "tests.strat",
+            "--reporter",
+            "junit",
+            "--report-file",
+            "results.xml",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Commands::Test {
+                reporter,
+                report_file,
+                ..
+            }) => {
+                assert_eq!(reporter, "junit");
+                assert_eq!(report_file, Some(PathBuf::from("results.xml")));
+            }
+            _ => panic!("Expected Test command"),
+        }
+
+        let cli = Cli::try_parse_from(&["stratum", "test", "tests.strat"]).unwrap();
+        match cli.command {
+            Some(Commands::Test { reporter, .. }) => assert_eq!(reporter, "pretty"),
+            _ => panic!("Expected Test command"),
+        }
     }
 
     #[test]
-    fn test_eval_simple_expression() {
-        // This tests the parsing/compilation path without running
-        let expr = stratum_core::Parser::parse_expression("1 + 2").unwrap();
-        let _function = stratum_core::Compiler::new()
-            .compile_expression(&expr)
-            .unwrap();
+    fn test_trace_flags() {
+        use clap::Parser as ClapParser;
+        let cli = Cli::try_parse_from(&[
+            "stratum",
+            "run",
+            "test.strat",
+            "--trace",
+            "trace.json",
+            "--trace-format",
+            "otlp",
+        ])
+        .unwrap();
+        assert_eq!(cli.trace, Some(PathBuf::from("trace.json")));
+        assert_eq!(cli.trace_format, "otlp");
+        assert_eq!(cli.trace_level, "trace");
+        assert!(matches!(cli.command, Some(Commands::Run { .. })));
+
+        let cli = Cli::try_parse_from(&["stratum", "--trace", "t.json", "add", "http"]).unwrap();
+        assert_eq!(cli.trace, Some(PathBuf::from("t.json")));
+
+        let result =
+            Cli::try_parse_from(&["stratum", "run", "test.strat", "--trace-level", "debug"]);
+        assert!(result.is_err());
     }
 
     #[test]
-    fn test_run_with_interpret_all_flag() {
+    fn test_run_with_dump_bytecode_flag() {
         use clap::Parser as ClapParser;
         let cli =
-            Cli::try_parse_from(&["stratum", "run", "test.strat", "--interpret-all"]).unwrap();
+            Cli::try_parse_from(&["stratum", "run", "test.strat", "--dump-bytecode"]).unwrap();
         match cli.command {
             Some(Commands::Run {
-                interpret_all,
-                compile_all,
-                jit,
+                dump_bytecode,
+                memory_profile,
                 ..
             }) => {
-                assert!(interpret_all);
-                assert!(!compile_all);
-                assert!(!jit);
+                assert!(dump_bytecode);
+                assert!(!memory_profile);
             }
             _ => panic!("Expected Run command"),
         }
     }
 
     #[test]
-    fn test_run_with_compile_all_flag() {
+    fn test_lint_command() {
         use clap::Parser as ClapParser;
-        let cli = Cli::try_parse_from(&["stratum", "run", "test.strat", "--compile-all"]).unwrap();
+        let cli = Cli::try_parse_from(&["stratum", "lint"]).unwrap();
         match cli.command {
-            Some(Commands::Run {
-                interpret_all,
-                compile_all,
-                jit,
-                ..
-            }) => {
-                assert!(!interpret_all);
-                assert!(compile_all);
-                assert!(!jit);
+            Some(Commands::Lint { paths, format, fix }) => {
+                assert!(paths.is_empty());
+                assert_eq!(format, "text");
+                assert!(!fix);
             }
-            _ => panic!("Expected Run command"),
+            _ => panic!("Expected Lint command"),
+        }
+
+        let cli =
+            Cli::try_parse_from(&["stratum", "lint", "src", "--format", "json", "--fix"]).unwrap();
+        match cli.command {
+            Some(Commands::Lint { paths, format, fix }) => {
+                assert_eq!(paths, vec![PathBuf::from("src")]);
+                assert_eq!(format, "json");
+                assert!(fix);
+            }
+            _ => panic!("Expected Lint command"),
         }
     }
 
     #[test]
-    fn test_run_with_jit_flag() {
+    fn test_workspace_flags() {
         use clap::Parser as ClapParser;
-        let cli = Cli::try_parse_from(&["stratum", "run", "test.strat", "--jit"]).unwrap();
+        let cli = Cli::try_parse_from(&["stratum", "build", "--workspace", "--release"]).unwrap();
         match cli.command {
-            Some(Commands::Run {
-                interpret_all,
-                compile_all,
-                jit,
+            Some(Commands::Build {
+                file,
+                workspace,
+                package,
                 ..
             }) => {
-                assert!(!interpret_all);
-                assert!(!compile_all);
-                assert!(jit);
+                assert_eq!(file, None);
+                assert!(workspace);
+                assert!(package.is_empty());
             }
-            _ => panic!("Expected Run command"),
+            _ => panic!("Expected Build command"),
+        }
+        assert!(Cli::try_parse_from(&["stratum", "build"]).is_err());
+        assert!(Cli::try_parse_from(&["stratum", "build", "-p", "app", "-o", "app"]).is_err());
+
+        let cli =
+            Cli::try_parse_from(&["stratum", "test", "-p", "core", "--package", "util"]).unwrap();
+        match cli.command {
+            Some(Commands::Test { package, .. }) => assert_eq!(package, ["core", "util"]),
+            _ => panic!("Expected Test command"),
+        }
+        assert!(Cli::try_parse_from(&["stratum", "test", "a.strat", "--workspace"]).is_err());
+        assert!(
+            Cli::try_parse_from(&["stratum", "test", "--workspace", "--report-file", "r.xml"])
+                .is_err()
+        );
+
+        let cli = Cli::try_parse_from(&["stratum", "check", "--workspace"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Check {
+                workspace: true,
+                ..
+            })
+        ));
+        let cli = Cli::try_parse_from(&["stratum", "fmt", "--check", "-p", "app"]).unwrap();
+        match cli.command {
+            Some(Commands::Fmt { check, package, .. }) => {
+                assert!(check);
+                assert_eq!(package, ["app"]);
+            }
+            _ => panic!("Expected Fmt command"),
         }
+        assert!(Cli::try_parse_from(&["stratum", "fmt", "a.strat", "--workspace"]).is_err());
     }
 
     #[test]
-    fn test_run_flags_conflict() {
+    fn test_clean_flags() {
         use clap::Parser as ClapParser;
-        // --interpret-all and --compile-all are mutually exclusive
-        let result = Cli::try_parse_from(&[
-            "stratum",
-            "run",
-            "test.strat",
-            "--interpret-all",
-            "--compile-all",
-        ]);
-        assert!(result.is_err());
+        let cli = Cli::try_parse_from(&["stratum", "clean", "--doc", "--release"]).unwrap();
+        match cli.command {
+            Some(Commands::Clean {
+                doc,
+                cache,
+                release,
+            }) => {
+                assert!(doc && release);
+                assert!(!cache);
+            }
+            _ => panic!("Expected Clean command"),
+        }
     }
 
     #[test]
-    fn test_run_with_memory_profile_flag() {
+    fn test_fmt_organize_imports() {
         use clap::Parser as ClapParser;
         let cli =
-            Cli::try_parse_from(&["stratum", "run", "test.strat", "--memory-profile"]).unwrap();
+            Cli::try_parse_from(&["stratum", "fmt", "main.strat", "--organize-imports"]).unwrap();
         match cli.command {
-            Some(Commands::Run { memory_profile, .. }) => {
-                assert!(memory_profile);
+            Some(Commands::Fmt {
+                files,
+                check,
+                organize_imports,
+                ..
+            }) => {
+                assert_eq!(files, vec![PathBuf::from("main.strat")]);
+                assert!(!check);
+                assert!(organize_imports);
             }
-            _ => panic!("Expected Run command"),
+            _ => panic!("Expected Fmt command"),
         }
+
+        let source = "import json\nimport std.io\nimport std.math\n\nio.println(json.encode(1))\n";
+        assert_eq!(prepare_for_format(source, false), source);
+        assert_eq!(
+            prepare_for_format(source, true),
+            "import std.io\n\nimport json\n\nio.println(json.encode(1))\n"
+        );
     }
 
     #[test]
@@ -1646,6 +3809,152 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_audit_command() {
+        use clap::Parser as ClapParser;
+        let cli = Cli::try_parse_from(&[
+            "stratum",
+            "audit",
+            "--db",
+            "advisories.json",
+            "--deny-warnings",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Commands::Audit {
+                db,
+                offline,
+                format,
+                deny_warnings,
+            }) => {
+                assert_eq!(db.as_deref(), Some("advisories.json"));
+                assert!(!offline);
+                assert_eq!(format, "text");
+                assert!(deny_warnings);
+            }
+            _ => panic!("Expected Audit command"),
+        }
+    }
+
+    #[test]
+    fn test_license_check_command() {
+        use clap::Parser as ClapParser;
+        let cli =
+            Cli::try_parse_from(&["stratum", "license", "check", "--report", "app.spdx"]).unwrap();
+        match cli.command {
+            Some(Commands::License(LicenseCommand::Check { report })) => {
+                assert_eq!(report, Some(PathBuf::from("app.spdx")));
+            }
+            _ => panic!("Expected license check command"),
+        }
+        assert!(Cli::try_parse_from(&["stratum", "license"]).is_err());
+    }
+
+    #[test]
+    fn test_install_command() {
+        use clap::Parser as ClapParser;
+        let cli = Cli::try_parse_from(&["stratum", "install", "./tool", "--wrap"]).unwrap();
+        match cli.command {
+            Some(Commands::Install {
+                package,
+                bin,
+                wrap,
+                force,
+                list,
+            }) => {
+                assert_eq!(package.as_deref(), Some("./tool"));
+                assert_eq!(bin, None);
+                assert!(wrap);
+                assert!(!force);
+                assert!(!list);
+            }
+            _ => panic!("Expected Install command"),
+        }
+
+        let cli = Cli::try_parse_from(&["stratum", "install", "--list"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Install {
+                package: None,
+                list: true,
+                ..
+            })
+        ));
+
+        assert!(Cli::try_parse_from(&["stratum", "install"]).is_err());
+        assert!(Cli::try_parse_from(&["stratum", "install", "./tool", "--list"]).is_err());
+    }
+
+    #[test]
+    fn test_uninstall_command() {
+        use clap::Parser as ClapParser;
+        let cli = Cli::try_parse_from(&["stratum", "uninstall", "greeter"]).unwrap();
+        match cli.command {
+            Some(Commands::Uninstall { name }) => assert_eq!(name, "greeter"),
+            _ => panic!("Expected Uninstall command"),
+        }
+    }
+
+    #[test]
+    fn test_registry_commands() {
+        use clap::Parser as ClapParser;
+        let cli = Cli::try_parse_from(&[
+            "stratum",
+            "publish",
+            "--registry",
+            "https://registry.example.com",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Commands::Publish { registry, .. }) => {
+                assert_eq!(registry.as_deref(), Some("https://registry.example.com"));
+            }
+            _ => panic!("Expected Publish command"),
+        }
+        assert!(Cli::try_parse_from(&[
+            "stratum",
+            "publish",
+            "--registry",
+            "https://registry.example.com",
+            "--target",
+            "user/repo",
+        ])
+        .is_err());
+
+        let cli =
+            Cli::try_parse_from(&["stratum", "search", "http client", "--limit", "3"]).unwrap();
+        match cli.command {
+            Some(Commands::Search {
+                query,
+                limit,
+                registry,
+            }) => {
+                assert_eq!(query, "http client");
+                assert_eq!(limit, 3);
+                assert_eq!(registry, None);
+            }
+            _ => panic!("Expected Search command"),
+        }
+
+        let cli = Cli::try_parse_from(&["stratum", "yank", "http@1.0.0", "--undo"]).unwrap();
+        match cli.command {
+            Some(Commands::Yank { package, undo, .. }) => {
+                assert_eq!(package, "http@1.0.0");
+                assert!(undo);
+            }
+            _ => panic!("Expected Yank command"),
+        }
+
+        assert!(matches!(
+            Cli::try_parse_from(&["stratum", "info", "http"]).unwrap().command,
+            Some(Commands::Info { package, .. }) if package == "http"
+        ));
+        assert!(matches!(
+            Cli::try_parse_from(&["stratum", "login"]).unwrap().command,
+            Some(Commands::Login { token: None, .. })
+        ));
+    }
+
     #[test]
     fn test_update_simple() {
         use clap::Parser as ClapParser;
@@ -1655,6 +3964,7 @@ mod tests {
                 packages,
                 dry_run,
                 sync,
+                ..
             }) => {
                 assert!(packages.is_empty());
                 assert!(!dry_run);
@@ -1673,6 +3983,7 @@ mod tests {
                 packages,
                 dry_run,
                 sync,
+                ..
             }) => {
                 assert_eq!(packages, vec!["http", "json"]);
                 assert!(!dry_run);
@@ -1691,6 +4002,7 @@ mod tests {
                 packages,
                 dry_run,
                 sync,
+                ..
             }) => {
                 assert!(packages.is_empty());
                 assert!(dry_run);
@@ -1709,6 +4021,7 @@ mod tests {
                 packages,
                 dry_run,
                 sync,
+                ..
             }) => {
                 assert!(packages.is_empty());
                 assert!(!dry_run);
@@ -1718,6 +4031,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_update_resolution_options() {
+        use clap::Parser as ClapParser;
+        let cli = Cli::try_parse_from(&[
+            "stratum",
+            "update",
+            "--minimal-versions",
+            "--precise",
+            "http@1.2.3",
+            "--precise",
+            "json@2.0.0",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Commands::Update {
+                minimal_versions,
+                precise,
+                ..
+            }) => {
+                assert!(minimal_versions);
+                assert_eq!(precise, vec!["http@1.2.3", "json@2.0.0"]);
+            }
+            _ => panic!("Expected Update command"),
+        }
+
+        assert!(
+            Cli::try_parse_from(&["stratum", "update", "--sync", "--minimal-versions"]).is_err()
+        );
+    }
+
     #[test]
     #[cfg(feature = "lsp")]
     fn test_lsp_command() {
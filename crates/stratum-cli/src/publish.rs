@@ -1,10 +1,12 @@
 //! Implementation of the `stratum publish` command.
 //!
-//! Publishes a Stratum package to GitHub Releases.
+//! Publishes a Stratum package to a package registry, when one is given with
+//! `--registry` or configured, or else to GitHub Releases.
 
 use anyhow::{Context, Result};
 use std::path::Path;
 use std::process::Command;
+use stratum_pkg::api::{PublishDependency, PublishMetadata};
 use stratum_pkg::{Manifest, PackageLayout, MANIFEST_FILE};
 
 /// Options for the publish command.
@@ -18,6 +20,8 @@ pub struct PublishOptions {
     pub allow_dirty: bool,
     /// Target repository (owner/repo). If None, detected from git remote.
     pub target: Option<String>,
+    /// Registry to publish to instead of GitHub Releases.
+    pub registry: Option<String>,
 }
 
 /// Result of package validation.
@@ -27,17 +31,31 @@ struct ValidationResult {
     name: String,
     /// Version from manifest.
     version: String,
-    /// Detected GitHub repository (owner/repo).
-    repository: String,
+    /// The parsed manifest.
+    manifest: Manifest,
     /// Path to the package root.
     package_root: std::path::PathBuf,
 }
 
-/// Publish a package to GitHub Releases.
+/// Publish a package to a registry or GitHub Releases.
 pub fn publish_package(options: PublishOptions) -> Result<()> {
     // Validate the package
     let validation = validate_package(&options)?;
 
+    // --tag and --target only make sense for GitHub Releases
+    let registry = options.registry.clone().or_else(|| {
+        if options.tag.is_none() && options.target.is_none() {
+            crate::config::current().registry.url.clone()
+        } else {
+            None
+        }
+    });
+    if let Some(url) = registry {
+        return publish_to_registry(&options, &validation, url.trim_end_matches('/'));
+    }
+
+    let repository = github_repository(&options, &validation.manifest)?;
+
     // Determine the tag to use
     let tag = options
         .tag
@@ -45,7 +63,9 @@ pub fn publish_package(options: PublishOptions) -> Result<()> {
 
     // Create the package tarball
     let tarball_name = format!("{}-{}.tar.gz", validation.name, validation.version);
-    let tarball_path = validation.package_root.join("target").join(&tarball_name);
+    let tarball_path = stratum_pkg::TargetDir::for_package(&validation.package_root)
+        .root
+        .join(&tarball_name);
 
     println!("Packaging {}...", validation.name);
     create_package_tarball(&validation.package_root, &tarball_path)?;
@@ -55,7 +75,7 @@ pub fn publish_package(options: PublishOptions) -> Result<()> {
         println!("  Package: {}", validation.name);
         println!("  Version: {}", validation.version);
         println!("  Tag: {tag}");
-        println!("  Repository: {}", validation.repository);
+        println!("  Repository: {repository}");
         println!("  Tarball: {}", tarball_path.display());
 
         // Clean up tarball
@@ -72,12 +92,7 @@ pub fn publish_package(options: PublishOptions) -> Result<()> {
 
     // Create GitHub release
     println!("Creating GitHub release {}...", tag);
-    create_github_release(
-        &validation.repository,
-        &tag,
-        &tarball_path,
-        &validation.name,
-    )?;
+    create_github_release(&repository, &tag, &tarball_path, &validation.name)?;
 
     // Clean up
     std::fs::remove_file(&tarball_path)?;
@@ -86,10 +101,7 @@ pub fn publish_package(options: PublishOptions) -> Result<()> {
         "\nPublished {} v{} to GitHub!",
         validation.name, validation.version
     );
-    println!(
-        "View at: https://github.com/{}/releases/tag/{}",
-        validation.repository, tag
-    );
+    println!("View at: https://github.com/{repository}/releases/tag/{tag}");
 
     Ok(())
 }
@@ -144,25 +156,124 @@ fn validate_package(options: &PublishOptions) -> Result<ValidationResult> {
         check_git_clean()?;
     }
 
-    // Determine target repository
-    let repository = if let Some(ref target) = options.target {
+    let package_root = std::env::current_dir()?;
+
+    Ok(ValidationResult {
+        name: manifest.package.name.clone(),
+        version: manifest.package.version.clone(),
+        manifest,
+        package_root,
+    })
+}
+
+/// Determine the GitHub repository (owner/repo) to release to.
+fn github_repository(options: &PublishOptions, manifest: &Manifest) -> Result<String> {
+    if let Some(ref target) = options.target {
         validate_repo_format(target)?;
-        target.clone()
+        Ok(target.clone())
     } else if let Some(ref repo) = manifest.package.repository {
         // Extract owner/repo from URL
-        extract_github_repo(repo)?
+        extract_github_repo(repo)
     } else {
         // Try to detect from git remote
-        detect_github_remote()?
-    };
+        detect_github_remote()
+    }
+}
 
-    let package_root = std::env::current_dir()?;
+/// Upload the package to the registry at `url`.
+fn publish_to_registry(
+    options: &PublishOptions,
+    validation: &ValidationResult,
+    url: &str,
+) -> Result<()> {
+    let tarball_name = format!("{}-{}.tar.gz", validation.name, validation.version);
+    let tarball_path = stratum_pkg::TargetDir::for_package(&validation.package_root)
+        .root
+        .join(&tarball_name);
 
-    Ok(ValidationResult {
-        name: manifest.package.name,
-        version: manifest.package.version,
-        repository,
-        package_root,
+    println!("Packaging {}...", validation.name);
+    create_package_tarball(&validation.package_root, &tarball_path)?;
+    let tarball = std::fs::read(&tarball_path)?;
+    std::fs::remove_file(&tarball_path)?;
+    let metadata = publish_metadata(&validation.manifest, &tarball)?;
+
+    if options.dry_run {
+        println!("\n[Dry run] Would publish:");
+        println!("  Package: {}", validation.name);
+        println!("  Version: {}", validation.version);
+        println!("  Registry: {url}");
+        println!("  Size: {} bytes", tarball.len());
+        println!("  Checksum: {}", metadata.cksum);
+        println!("\nDry run complete. No changes were made.");
+        return Ok(());
+    }
+
+    let home = crate::self_cmd::get_stratum_home()?;
+    let api = crate::registry::client(url, &home)?;
+    api.check_ownership(&validation.name)
+        .with_context(|| format!("Cannot publish '{}'", validation.name))?;
+
+    println!(
+        "Uploading {} v{} to {url}...",
+        validation.name, validation.version
+    );
+    let response = api
+        .publish(&metadata, &tarball)
+        .context("Failed to publish")?;
+    for warning in &response.warnings {
+        println!("warning: {warning}");
+    }
+
+    println!(
+        "\nPublished {} v{} to {url}",
+        validation.name, validation.version
+    );
+    Ok(())
+}
+
+/// Build the registry metadata for a package, rejecting dependencies that
+/// other users could not fetch.
+fn publish_metadata(manifest: &Manifest, tarball: &[u8]) -> Result<PublishMetadata> {
+    use sha2::{Digest, Sha256};
+
+    let package = &manifest.package;
+    let sections = [
+        (&manifest.dependencies, false),
+        (&manifest.build_dependencies, false),
+        (&manifest.dev_dependencies, true),
+    ];
+    let mut deps = Vec::new();
+    for (section, dev) in sections {
+        for (name, spec) in section {
+            if spec.is_path() || spec.is_git() {
+                anyhow::bail!(
+                    "Dependency '{name}' is a path or git dependency; \
+                     registry packages can only depend on registry packages"
+                );
+            }
+            let version_req = spec
+                .version()
+                .with_context(|| format!("Dependency '{name}' needs a version to be published"))?;
+            deps.push(PublishDependency {
+                name: name.clone(),
+                version_req: version_req.to_string(),
+                dev,
+            });
+        }
+    }
+
+    Ok(PublishMetadata {
+        name: package.name.clone(),
+        vers: package.version.clone(),
+        description: package.description.clone(),
+        license: package.license.clone(),
+        repository: package.repository.clone(),
+        documentation: package.documentation.clone(),
+        homepage: package.homepage.clone(),
+        keywords: package.keywords.clone(),
+        authors: package.authors.clone(),
+        deps,
+        cksum: format!("{:x}", Sha256::digest(tarball)),
     })
 }
 
@@ -459,6 +570,54 @@ mod tests {
         assert_eq!(repo, "user/repo");
     }
 
+    #[test]
+    fn test_publish_metadata() {
+        let manifest = Manifest::parse(
+            r#"
+[package]
+name = "http"
+version = "1.2.0"
+edition = "2025"
+description = "HTTP client"
+keywords = ["net"]
+
+[dependencies]
+json = "2.0"
+
+[dev-dependencies]
+mock = { version = "0.3" }
+"#,
+        )
+        .unwrap();
+        let metadata = publish_metadata(&manifest, b"tarball").unwrap();
+        assert_eq!(metadata.name, "http");
+        assert_eq!(metadata.vers, "1.2.0");
+        assert_eq!(metadata.description.as_deref(), Some("HTTP client"));
+        assert_eq!(metadata.keywords, ["net"]);
+        assert_eq!(
+            metadata.deps,
+            [
+                PublishDependency {
+                    name: "json".to_string(),
+                    version_req: "2.0".to_string(),
+                    dev: false,
+                },
+                PublishDependency {
+                    name: "mock".to_string(),
+                    version_req: "0.3".to_string(),
+                    dev: true,
+                },
+            ]
+        );
+        assert_eq!(metadata.cksum.len(), 64);
+
+        let local = Manifest::parse(
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2025\"\n\n[dependencies]\nutil = { path = \"../util\" }\n",
+        )
+        .unwrap();
+        assert!(publish_metadata(&local, b"").is_err());
+    }
+
     #[test]
     fn test_extract_github_repo_invalid() {
         assert!(extract_github_repo("https://gitlab.com/user/repo").is_err());
@@ -0,0 +1,303 @@
+//! Implementation of the registry commands: `stratum login`, `stratum logout`,
+//! `stratum search`, `stratum info` and `stratum yank`.
+//!
+//! The registry URL comes from `--registry`, or else `[registry] url` in the
+//! config. Tokens saved by `stratum login` live in
+//! `~/.stratum/credentials.toml`, keyed by registry URL; the
+//! `STRATUM_REGISTRY_TOKEN` variable takes precedence over them, except for a
+//! registry URL set by the project config, which a checked-out repository
+//! controls.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use stratum_pkg::api::RegistryApi;
+
+/// File holding registry tokens.
+const CREDENTIALS_FILE: &str = "credentials.toml";
+
+/// Tokens saved by `stratum login`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Credentials {
+    #[serde(default)]
+    registries: BTreeMap<String, RegistryCredentials>,
+}
+
+/// The token for one registry.
+#[derive(Debug, Serialize, Deserialize)]
+struct RegistryCredentials {
+    token: String,
+}
+
+impl Credentials {
+    fn path(home: &Path) -> PathBuf {
+        home.join(CREDENTIALS_FILE)
+    }
+
+    fn load(home: &Path) -> Result<Self> {
+        let path = Self::path(home);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Invalid {}", path.display()))
+    }
+
+    /// Write the file readable by the owner only.
+    fn save(&self, home: &Path) -> Result<()> {
+        let path = Self::path(home);
+        let content = toml::to_string_pretty(self)?;
+        std::fs::create_dir_all(home)?;
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+            options.mode(0o600);
+            // An existing file keeps its mode when opened
+            if path.exists() {
+                std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+            }
+        }
+        options
+            .open(&path)
+            .and_then(|mut file| file.write_all(content.as_bytes()))
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// The registry to use: `flag`, or else the configured one.
+pub fn registry_url(flag: Option<String>) -> Result<String> {
+    flag.or_else(|| crate::config::current().registry.url.clone())
+        .map(|url| url.trim_end_matches('/').to_string())
+        .context(
+            "No registry configured. Pass --registry <URL> or set `url` in the [registry] \
+             section of the config",
+        )
+}
+
+/// The token for `url`: `STRATUM_REGISTRY_TOKEN`, or else the saved one.
+///
+/// The variable isn't used for the project config's registry, which could
+/// otherwise be pointed anywhere to collect it.
+fn token(url: &str, home: &Path) -> Result<Option<String>> {
+    let registry = &crate::config::current().registry;
+    let from_project = registry.url_from_project
+        && registry.url.as_deref().map(|u| u.trim_end_matches('/')) == Some(url);
+    if let Some(token) = registry.token.as_ref().filter(|_| !from_project) {
+        return Ok(Some(token.clone()));
+    }
+    Ok(Credentials::load(home)?
+        .registries
+        .remove(url)
+        .map(|c| c.token))
+}
+
+/// A client for the registry at `url`, authenticated if a token is saved.
+pub fn client(url: &str, home: &Path) -> Result<RegistryApi> {
+    let http_client = crate::config::current().http_client(Duration::from_secs(120))?;
+    Ok(RegistryApi::new(url, http_client).with_token(token(url, home)?))
+}
+
+/// Save a token for `url`, reading it from stdin if not given.
+pub fn login(url: &str, token: Option<String>, home: &Path) -> Result<()> {
+    let token = match token {
+        Some(token) => token,
+        None => {
+            println!("Paste your API token for {url}:");
+            let mut line = String::new();
+            std::io::stdin().lock().read_line(&mut line)?;
+            line
+        }
+    };
+    let token = token.trim().to_string();
+    if token.is_empty() {
+        anyhow::bail!("Token cannot be empty");
+    }
+
+    let http_client = crate::config::current().http_client(Duration::from_secs(30))?;
+    let api = RegistryApi::new(url, http_client).with_token(Some(token.clone()));
+    let user = api.me().context("The registry rejected the token")?;
+
+    let mut credentials = Credentials::load(home)?;
+    credentials
+        .registries
+        .insert(url.to_string(), RegistryCredentials { token });
+    credentials.save(home)?;
+    println!(
+        "Logged in to {url} as {}; token saved in {}",
+        user.login,
+        Credentials::path(home).display()
+    );
+    Ok(())
+}
+
+/// Forget the token for `url`.
+pub fn logout(url: &str, home: &Path) -> Result<()> {
+    let mut credentials = Credentials::load(home)?;
+    if credentials.registries.remove(url).is_none() {
+        println!("Not logged in to {url}");
+        return Ok(());
+    }
+    credentials.save(home)?;
+    println!("Removed the token for {url}");
+    Ok(())
+}
+
+/// Print the packages matching `query`.
+pub fn search(url: &str, query: &str, limit: usize, home: &Path) -> Result<()> {
+    let results = client(url, home)?
+        .search(query, limit)
+        .context("Search failed")?;
+    if results.packages.is_empty() {
+        println!("No packages match '{query}'");
+        return Ok(());
+    }
+
+    let width = results
+        .packages
+        .iter()
+        .map(|p| p.name.len() + p.max_version.len() + 3)
+        .max()
+        .unwrap_or(0);
+    for package in &results.packages {
+        let name = format!("{} = \"{}\"", package.name, package.max_version);
+        match &package.description {
+            Some(description) => println!("{name:<width$}  # {description}"),
+            None => println!("{name}"),
+        }
+    }
+    let shown = results.packages.len() as u64;
+    if results.total > shown {
+        println!(
+            "... and {} more; use --limit to see more",
+            results.total - shown
+        );
+    }
+    Ok(())
+}
+
+/// Print a package's metadata and versions.
+pub fn info(url: &str, name: &str, home: &Path) -> Result<()> {
+    let package = client(url, home)?
+        .info(name)
+        .with_context(|| format!("Failed to look up '{name}'"))?;
+
+    match package.latest() {
+        Some(latest) => println!("{} {}", package.name, latest.num),
+        None => println!("{} (all versions yanked)", package.name),
+    }
+    if let Some(description) = &package.description {
+        println!("{description}");
+    }
+    println!();
+    let fields = [
+        ("license", package.license.as_deref()),
+        ("repository", package.repository.as_deref()),
+        ("homepage", package.homepage.as_deref()),
+    ];
+    for (label, value) in fields {
+        if let Some(value) = value {
+            println!("{label}: {value}");
+        }
+    }
+    if !package.keywords.is_empty() {
+        println!("keywords: {}", package.keywords.join(", "));
+    }
+    println!("downloads: {}", package.downloads);
+
+    println!("\nversions:");
+    for version in &package.versions {
+        let date = version
+            .created_at
+            .as_deref()
+            .and_then(|d| d.get(..10))
+            .unwrap_or("");
+        let yanked = if version.yanked { " (yanked)" } else { "" };
+        println!("  {:<12} {date}{yanked}", version.num);
+    }
+    Ok(())
+}
+
+/// Yank `name@version`, or restore it with `undo`.
+pub fn yank(url: &str, spec: &str, undo: bool, home: &Path) -> Result<()> {
+    let (name, version) = parse_versioned_name(spec)?;
+    let api = client(url, home)?;
+    if undo {
+        api.unyank(name, version)
+            .with_context(|| format!("Failed to unyank {name}@{version}"))?;
+        println!("Unyanked {name}@{version}");
+    } else {
+        api.yank(name, version)
+            .with_context(|| format!("Failed to yank {name}@{version}"))?;
+        println!("Yanked {name}@{version}; existing lockfiles can still use it");
+    }
+    Ok(())
+}
+
+/// Split `name@version`, requiring both parts.
+pub(crate) fn parse_versioned_name(spec: &str) -> Result<(&str, &str)> {
+    match spec.split_once('@') {
+        Some((name, version)) if !name.is_empty() && !version.is_empty() => {
+            semver::Version::parse(version)
+                .with_context(|| format!("Invalid version '{version}'"))?;
+            Ok((name, version))
+        }
+        _ => anyhow::bail!("Expected NAME@VERSION, such as http@1.0.0, but got '{spec}'"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_versioned_name() {
+        assert_eq!(
+            parse_versioned_name("http@1.2.0").unwrap(),
+            ("http", "1.2.0")
+        );
+        assert!(parse_versioned_name("http").is_err());
+        assert!(parse_versioned_name("http@").is_err());
+        assert!(parse_versioned_name("@1.0.0").is_err());
+        assert!(parse_versioned_name("http@latest").is_err());
+    }
+
+    #[test]
+    fn test_credentials_round_trip() {
+        let home = tempfile::tempdir().unwrap();
+        let url = "https://registry.example.com";
+
+        let mut credentials = Credentials::load(home.path()).unwrap();
+        assert!(credentials.registries.is_empty());
+        credentials.registries.insert(
+            url.to_string(),
+            RegistryCredentials {
+                token: "secret".to_string(),
+            },
+        );
+        credentials.save(home.path()).unwrap();
+
+        let loaded = Credentials::load(home.path()).unwrap();
+        assert_eq!(loaded.registries[url].token, "secret");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(Credentials::path(home.path()))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        logout(url, home.path()).unwrap();
+        assert!(Credentials::load(home.path())
+            .unwrap()
+            .registries
+            .is_empty());
+    }
+}
@@ -0,0 +1,526 @@
+//! Rendering of data values in the REPL
+//!
+//! DataFrames print as aligned tables with a row of column types under the
+//! header; rows and columns past the display limits are elided from the
+//! middle, so the first and last of each stay in view. Series and Cubes get
+//! the same treatment, and `:plot` draws numeric data as a bar chart, or a
+//! DataFrame as a sparkline per numeric column.
+
+use stratum_core::bytecode::Value;
+use stratum_core::data::{Cube, DataFrame, Series};
+use stratum_core::types::Type;
+
+/// Bar characters from lowest to highest
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+/// Widest a table cell is shown before it is cut short
+const MAX_CELL_WIDTH: usize = 24;
+/// Most columns a chart or sparkline draws; longer data is averaged down
+const CHART_WIDTH: usize = 60;
+/// Rows of bars in a chart
+const CHART_HEIGHT: usize = 8;
+/// Marks rows and columns left out of a table
+const ELLIPSIS: &str = "…";
+
+/// How many rows and columns of data to show
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct DisplayLimits {
+    /// Rows of a DataFrame or Series to show
+    pub max_rows: usize,
+    /// Columns of a DataFrame to show
+    pub max_cols: usize,
+}
+
+impl Default for DisplayLimits {
+    fn default() -> Self {
+        Self {
+            max_rows: 20,
+            max_cols: 10,
+        }
+    }
+}
+
+/// Render a DataFrame, Series or Cube; other values have no rich form
+pub(super) fn render(value: &Value, limits: DisplayLimits) -> Option<String> {
+    match value {
+        Value::DataFrame(df) => Some(render_dataframe(df, limits)),
+        Value::Series(series) => Some(render_series(series, limits)),
+        Value::Cube(cube) => Some(render_cube(cube)),
+        _ => None,
+    }
+}
+
+/// Draw numeric data: a chart for a Series or List, or a sparkline per
+/// numeric column for a DataFrame
+pub(super) fn plot(value: &Value) -> Result<String, String> {
+    match value {
+        Value::DataFrame(df) => {
+            let mut lines = Vec::new();
+            for column in df.iter_columns().flatten() {
+                let Ok(values) = series_numbers(&column) else {
+                    continue;
+                };
+                if values.is_empty() {
+                    continue;
+                }
+                lines.push((column.name().to_string(), values));
+            }
+            if lines.is_empty() {
+                return Err("DataFrame has no numeric columns to plot".to_string());
+            }
+
+            let name_width = lines.iter().map(|(name, _)| width(name)).max().unwrap_or(0);
+            let rendered: Vec<String> = lines
+                .iter()
+                .map(|(name, values)| {
+                    let (min, max) = bounds(values);
+                    format!(
+                        "{}  {}  {} .. {}",
+                        pad(name, name_width, false),
+                        sparkline(values),
+                        format_number(min),
+                        format_number(max)
+                    )
+                })
+                .collect();
+            Ok(rendered.join("\n"))
+        }
+        Value::Series(series) => chart(&series_numbers(series)?),
+        Value::List(list) => {
+            let values = list
+                .borrow()
+                .iter()
+                .filter_map(|value| number(value).transpose())
+                .collect::<Result<Vec<f64>, String>>()?;
+            chart(&values)
+        }
+        other => Err(format!("Cannot plot a {}", other.type_name())),
+    }
+}
+
+/// A DataFrame as an aligned table, headed by its shape
+fn render_dataframe(df: &DataFrame, limits: DisplayLimits) -> String {
+    let (num_rows, num_cols) = (df.num_rows(), df.num_columns());
+    let header = format!("DataFrame: {num_rows} rows x {num_cols} columns");
+    let columns: Vec<Series> = df.iter_columns().flatten().collect();
+    if columns.is_empty() {
+        return header;
+    }
+    let rows = visible(num_rows, limits.max_rows);
+
+    // The row index, then each shown column: name, type, then cells
+    let mut table: Vec<(Vec<String>, bool)> = vec![(index_column(&rows), true)];
+    for column in visible(columns.len(), limits.max_cols) {
+        let Some(column) = column.map(|i| &columns[i]) else {
+            table.push((vec![ELLIPSIS.to_string(); rows.len() + 3], false));
+            continue;
+        };
+        let ty = column.stratum_type();
+        let mut cells = vec![truncate(column.name().to_string()), ty.to_string()];
+        cells.push(String::new());
+        cells.extend(rows.iter().map(|row| match row {
+            Some(row) => cell(column, *row),
+            None => ELLIPSIS.to_string(),
+        }));
+        table.push((cells, is_numeric(&ty)));
+    }
+
+    // Rule off the header under each column's name and type
+    let widths: Vec<usize> = table
+        .iter()
+        .map(|(cells, _)| cells.iter().map(|c| width(c)).max().unwrap_or(0))
+        .collect();
+    for ((cells, _), width) in table.iter_mut().zip(&widths).skip(1) {
+        cells[2] = "-".repeat(*width);
+    }
+
+    let mut out = header;
+    for line in 0..rows.len() + 3 {
+        out.push('\n');
+        let cells: Vec<String> = table
+            .iter()
+            .zip(&widths)
+            .map(|((cells, right), width)| pad(&cells[line], *width, *right))
+            .collect();
+        out.push_str(cells.join("  ").trim_end());
+    }
+    out
+}
+
+/// A Series as an indexed column of values, headed by its name and type
+fn render_series(series: &Series, limits: DisplayLimits) -> String {
+    let ty = series.stratum_type();
+    let mut out = format!("Series '{}': {} rows, {ty}", series.name(), series.len());
+    let rows = visible(series.len(), limits.max_rows);
+    let index = index_column(&rows);
+    let index_width = index.iter().map(|c| width(c)).max().unwrap_or(0);
+    let cells: Vec<String> = rows
+        .iter()
+        .map(|row| match row {
+            Some(row) => cell(series, *row),
+            None => ELLIPSIS.to_string(),
+        })
+        .collect();
+    let cell_width = cells.iter().map(|c| width(c)).max().unwrap_or(0);
+
+    // The index column starts with the header rows a table has
+    for (index, cell) in index.iter().skip(3).zip(&cells) {
+        out.push('\n');
+        out.push_str(&pad(index, index_width, true));
+        out.push_str("  ");
+        out.push_str(pad(cell, cell_width, is_numeric(&ty)).trim_end());
+    }
+    out
+}
+
+/// A Cube's size, dimensions, measures and hierarchies
+fn render_cube(cube: &Cube) -> String {
+    let mut out = format!(
+        "Cube '{}': {} rows",
+        cube.name().unwrap_or("unnamed"),
+        cube.row_count()
+    );
+    out.push_str(&format!(
+        "\n  dimensions:  {}",
+        cube.dimension_names().join(", ")
+    ));
+    out.push_str(&format!(
+        "\n  measures:    {}",
+        cube.measure_names().join(", ")
+    ));
+    let hierarchies = cube.hierarchies_with_levels();
+    if !hierarchies.is_empty() {
+        let hierarchies: Vec<String> = hierarchies
+            .iter()
+            .map(|(name, levels)| format!("{name} ({})", levels.join(" > ")))
+            .collect();
+        out.push_str(&format!("\n  hierarchies: {}", hierarchies.join(", ")));
+    }
+    out
+}
+
+/// The positions to show out of `len`, with `None` where the middle ones
+/// are left out
+fn visible(len: usize, max: usize) -> Vec<Option<usize>> {
+    if len <= max {
+        return (0..len).map(Some).collect();
+    }
+    let head = max.div_ceil(2);
+    let tail = max - head;
+    (0..head)
+        .map(Some)
+        .chain(std::iter::once(None))
+        .chain((len - tail..len).map(Some))
+        .collect()
+}
+
+/// Row numbers for a table, below three header rows
+fn index_column(rows: &[Option<usize>]) -> Vec<String> {
+    let mut index = vec![String::new(); 3];
+    index.extend(rows.iter().map(|row| match row {
+        Some(row) => row.to_string(),
+        None => ELLIPSIS.to_string(),
+    }));
+    index
+}
+
+/// The text of one value in a column
+fn cell(series: &Series, row: usize) -> String {
+    let text = match series.get(row) {
+        Ok(Value::String(s)) => s.to_string(),
+        Ok(value) => value.to_string(),
+        Err(_) => "?".to_string(),
+    };
+    truncate(text)
+}
+
+/// Cut text longer than a cell down to size
+fn truncate(text: String) -> String {
+    if width(&text) <= MAX_CELL_WIDTH {
+        return text;
+    }
+    let mut short: String = text.chars().take(MAX_CELL_WIDTH - 1).collect();
+    short.push_str(ELLIPSIS);
+    short
+}
+
+/// Columns a string takes up, counting each character as one
+fn width(text: &str) -> usize {
+    text.chars().count()
+}
+
+/// Pad text to a width, aligned left or right
+fn pad(text: &str, width: usize, right: bool) -> String {
+    let fill = " ".repeat(width.saturating_sub(self::width(text)));
+    if right {
+        format!("{fill}{text}")
+    } else {
+        format!("{text}{fill}")
+    }
+}
+
+/// Whether a column's values are numbers, which align right
+fn is_numeric(ty: &Type) -> bool {
+    matches!(ty, Type::Int | Type::Float)
+}
+
+/// A number's value, `None` for null
+fn number(value: &Value) -> Result<Option<f64>, String> {
+    match value {
+        Value::Int(n) => Ok(Some(*n as f64)),
+        Value::Float(n) => Ok(Some(*n)),
+        Value::Null => Ok(None),
+        other => Err(format!("Cannot plot a {} value", other.type_name())),
+    }
+}
+
+/// The numbers in a Series, skipping nulls
+fn series_numbers(series: &Series) -> Result<Vec<f64>, String> {
+    if !is_numeric(&series.stratum_type()) {
+        return Err(format!(
+            "Cannot plot '{}', a column of {}",
+            series.name(),
+            series.stratum_type()
+        ));
+    }
+    let values = series.to_values().map_err(|e| e.to_string())?;
+    values
+        .iter()
+        .filter_map(|value| number(value).transpose())
+        .collect()
+}
+
+/// At most `CHART_WIDTH` values, averaging runs of values when there are
+/// more
+fn downsample(values: &[f64]) -> Vec<f64> {
+    if values.len() <= CHART_WIDTH {
+        return values.to_vec();
+    }
+    (0..CHART_WIDTH)
+        .map(|i| {
+            let bucket =
+                &values[i * values.len() / CHART_WIDTH..(i + 1) * values.len() / CHART_WIDTH];
+            bucket.iter().sum::<f64>() / bucket.len() as f64
+        })
+        .collect()
+}
+
+/// The smallest and largest of some values
+fn bounds(values: &[f64]) -> (f64, f64) {
+    values
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &v| {
+            (min.min(v), max.max(v))
+        })
+}
+
+/// Where a value falls between the bounds, from 0 to `steps`
+fn level(value: f64, (min, max): (f64, f64), steps: usize) -> usize {
+    if max > min {
+        ((value - min) / (max - min) * steps as f64).round() as usize
+    } else {
+        steps / 2
+    }
+}
+
+/// One bar character per value
+fn sparkline(values: &[f64]) -> String {
+    let values = downsample(values);
+    let bounds = bounds(&values);
+    values
+        .iter()
+        .map(|&v| BARS[level(v, bounds, BARS.len() - 1)])
+        .collect()
+}
+
+/// A bar chart of values, labelled with the largest and smallest, and a
+/// summary underneath
+fn chart(values: &[f64]) -> Result<String, String> {
+    if values.is_empty() {
+        return Err("Nothing to plot".to_string());
+    }
+    let points = downsample(values);
+    let (min, max) = bounds(&points);
+
+    // Each bar is measured in eighths of a row; the smallest still shows
+    let eighths: Vec<usize> = points
+        .iter()
+        .map(|&v| 1 + level(v, (min, max), CHART_HEIGHT * 8 - 1))
+        .collect();
+
+    let labels = [format_number(max), format_number(min)];
+    let label_width = labels.iter().map(|l| width(l)).max().unwrap_or(0);
+    let mut lines = Vec::new();
+    for row in (0..CHART_HEIGHT).rev() {
+        let label = match row {
+            _ if row == CHART_HEIGHT - 1 => labels[0].as_str(),
+            0 => labels[1].as_str(),
+            _ => "",
+        };
+        let bars: String = eighths
+            .iter()
+            .map(|&e| match e.saturating_sub(row * 8) {
+                0 => ' ',
+                filled if filled >= 8 => BARS[7],
+                filled => BARS[filled - 1],
+            })
+            .collect();
+        lines.push(format!(
+            "{} │{}",
+            pad(label, label_width, true),
+            bars.trim_end()
+        ));
+    }
+
+    let (all_min, all_max) = bounds(values);
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let mut summary = format!(
+        "{} values, min {}, max {}, mean {}",
+        values.len(),
+        format_number(all_min),
+        format_number(all_max),
+        format_number(mean)
+    );
+    if points.len() < values.len() {
+        summary.push_str(&format!(" (averaged into {} bars)", points.len()));
+    }
+    lines.push(summary);
+    Ok(lines.join("\n"))
+}
+
+/// A number with at most two decimal places and no trailing zeros
+fn format_number(n: f64) -> String {
+    let text = format!("{n:.2}");
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" {
+        "0".to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn people() -> DataFrame {
+        DataFrame::from_series(vec![
+            Series::from_strings("name", vec!["Alice", "Bob", "Charlie"]),
+            Series::from_ints("age", vec![30, 25, 35]),
+            Series::from_floats("score", vec![85.5, 92.25, 78.0]),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_render_dataframe() {
+        let rendered = render_dataframe(&people(), DisplayLimits::default());
+        assert_eq!(
+            rendered,
+            "DataFrame: 3 rows x 3 columns\n   \
+             name     age  score\n   \
+             String   Int  Float\n   \
+             -------  ---  -----\n\
+             0  Alice     30   85.5\n\
+             1  Bob       25  92.25\n\
+             2  Charlie   35     78"
+        );
+    }
+
+    #[test]
+    fn test_render_dataframe_elides_rows_and_columns() {
+        let values: Vec<i64> = (0..100).collect();
+        let df = DataFrame::from_series(
+            ["a", "b", "c", "d", "e"]
+                .iter()
+                .map(|name| Series::from_ints(*name, values.clone()))
+                .collect(),
+        )
+        .unwrap();
+        let limits = DisplayLimits {
+            max_rows: 4,
+            max_cols: 3,
+        };
+        let rendered = render_dataframe(&df, limits);
+        let lines: Vec<&str> = rendered.lines().collect();
+
+        assert_eq!(lines[0], "DataFrame: 100 rows x 5 columns");
+        // Header rows, two rows from each end and the elided middle
+        assert_eq!(lines.len(), 1 + 3 + 5);
+        assert_eq!(
+            lines[1].split_whitespace().collect::<Vec<_>>(),
+            ["a", "b", "…", "e"]
+        );
+        assert_eq!(lines[6].split_whitespace().collect::<Vec<_>>(), ["…"; 5]);
+        assert!(lines[8].starts_with("99"));
+    }
+
+    #[test]
+    fn test_render_series() {
+        let series = Series::from_optional_ints("n", vec![Some(5), None, Some(120)]);
+        assert_eq!(
+            render_series(&series, DisplayLimits::default()),
+            "Series 'n': 3 rows, Int\n0     5\n1  null\n2   120"
+        );
+        assert!(render(&Value::Series(Arc::new(series)), DisplayLimits::default()).is_some());
+        assert!(render(&Value::Int(1), DisplayLimits::default()).is_none());
+    }
+
+    #[test]
+    fn test_visible() {
+        assert_eq!(visible(3, 5), [Some(0), Some(1), Some(2)]);
+        assert_eq!(visible(10, 3), [Some(0), Some(1), None, Some(9)]);
+        assert_eq!(visible(10, 1), [Some(0), None]);
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("short".to_string()), "short");
+        let long = truncate("x".repeat(40));
+        assert_eq!(width(&long), MAX_CELL_WIDTH);
+        assert!(long.ends_with(ELLIPSIS));
+    }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(
+            sparkline(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]),
+            "▁▂▃▄▅▆▇█"
+        );
+        assert_eq!(sparkline(&[3.0, 3.0]), "▄▄");
+        assert_eq!(width(&sparkline(&vec![1.0; 500])), CHART_WIDTH);
+    }
+
+    #[test]
+    fn test_chart() {
+        let rendered = chart(&[0.0, 5.0, 10.0]).unwrap();
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines.len(), CHART_HEIGHT + 1);
+        assert_eq!(lines[0], "10 │  █");
+        assert_eq!(lines[CHART_HEIGHT - 1], " 0 │▁██");
+        assert_eq!(lines[CHART_HEIGHT], "3 values, min 0, max 10, mean 5");
+        assert!(chart(&[]).is_err());
+    }
+
+    #[test]
+    fn test_plot() {
+        let plotted = plot(&Value::DataFrame(Arc::new(people()))).unwrap();
+        let lines: Vec<&str> = plotted.lines().collect();
+        // The name column is skipped
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("age    ▅▁█  25 .. 35"), "{plotted}");
+
+        let list = Value::list(vec![Value::Int(1), Value::Null, Value::Float(2.5)]);
+        assert!(plot(&list).unwrap().contains("2 values"));
+        assert!(plot(&Value::list(vec![Value::string("a")])).is_err());
+        assert!(plot(&Value::Bool(true)).is_err());
+    }
+
+    #[test]
+    fn test_format_number() {
+        assert_eq!(format_number(3.0), "3");
+        assert_eq!(format_number(2.5), "2.5");
+        assert_eq!(format_number(1.0 / 3.0), "0.33");
+        assert_eq!(format_number(-0.001), "0");
+    }
+}
@@ -8,23 +8,124 @@
 //! - Function definitions: `fx add(a, b) { a + b }`
 //! - Multiple statements: `let x = 5; let y = 6`
 //! - Control flow: `for`, `while`, `if`
+//!
+//! Input with unbalanced brackets continues onto the next line, and the
+//! whole entry can be edited before it runs. Tab completes the names of
+//! globals, namespaces and REPL commands, and the fields and methods of
+//! globals after a `.`; completions come from the live VM, so they include
+//! everything defined so far. History is saved to `~/.stratum_history`
+//! after each entry.
+//!
+//! `:save` writes the inputs that ran out as a script, and `:type` checks
+//! an expression as though it followed them.
+//!
+//! DataFrames, Series and Cubes print as tables (see [`display`]), and
+//! `:plot` charts numeric data in the terminal.
+
+mod display;
 
 use anyhow::Result;
+use rustyline::completion::{Completer, Pair};
 use rustyline::error::ReadlineError;
+use rustyline::highlight::{CmdKind, Highlighter};
+use rustyline::hint::Hinter;
 use rustyline::history::DefaultHistory;
-use rustyline::{DefaultEditor, Editor};
-use std::collections::HashSet;
+use rustyline::validate::{ValidationContext, ValidationResult, Validator};
+use rustyline::{Config, Context, Editor, Helper};
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::path::Path;
+use std::sync::Arc;
+use std::time::Instant;
 use stratum_core::bytecode::Value;
+use stratum_core::lexer::{Lexer, TokenKind};
 use stratum_core::parser::ReplInput;
-use stratum_core::{Compiler, Parser, VM};
+use stratum_core::{Compiler, Parser, TypeChecker, VM};
+
+use display::DisplayLimits;
 
 /// REPL prompt shown at the start of each line
 const PROMPT: &str = ">>> ";
-/// Continuation prompt for multi-line input
-const CONTINUATION_PROMPT: &str = "... ";
 /// History file name
 const HISTORY_FILE: &str = ".stratum_history";
+/// Most history entries to keep
+const MAX_HISTORY: usize = 1000;
+/// Name `:type` binds an expression to so its type is recorded
+const TYPE_PROBE: &str = "__repl_type";
+
+/// Keywords offered by tab completion
+const KEYWORDS: &[&str] = &[
+    "async",
+    "await",
+    "break",
+    "catch",
+    "const",
+    "continue",
+    "else",
+    "enum",
+    "false",
+    "for",
+    "fx",
+    "if",
+    "impl",
+    "import",
+    "in",
+    "interface",
+    "let",
+    "match",
+    "null",
+    "return",
+    "struct",
+    "true",
+    "try",
+    "while",
+];
+
+/// REPL commands offered by tab completion
+const COMMANDS: &[&str] = &[
+    ":clear", ":funcs", ":help", ":load", ":plot", ":quit", ":reset", ":save", ":set", ":time",
+    ":type", ":vars",
+];
+
+/// Built-in String methods offered by tab completion
+const STRING_METHODS: &[&str] = &[
+    "chars",
+    "contains",
+    "ends_with",
+    "index_of",
+    "is_empty",
+    "len",
+    "repeat",
+    "replace",
+    "split",
+    "starts_with",
+    "substring",
+    "to_lower",
+    "to_upper",
+    "trim",
+    "trim_end",
+    "trim_start",
+];
+
+/// Built-in List methods offered by tab completion
+const LIST_METHODS: &[&str] = &[
+    "contains", "filter", "find", "first", "get", "is_empty", "join", "last", "len", "map", "pop",
+    "push", "reduce", "reverse", "sort",
+];
+
+/// Built-in Map methods offered by tab completion
+const MAP_METHODS: &[&str] = &[
+    "clear",
+    "contains_key",
+    "entries",
+    "get",
+    "is_empty",
+    "keys",
+    "len",
+    "remove",
+    "set",
+    "values",
+];
 
 /// Result of processing a REPL command
 enum CommandResult {
@@ -40,12 +141,17 @@ enum CommandResult {
 pub struct Repl {
     /// The VM instance that persists across inputs
     vm: VM,
-    /// Line editor with history support
-    editor: Editor<(), DefaultHistory>,
+    /// Line editor with history, completion and highlighting
+    editor: Editor<ReplHelper, DefaultHistory>,
     /// Track user-defined function names for :funcs command
     user_functions: HashSet<String>,
     /// Track user-defined variable names for :vars command
     user_variables: HashSet<String>,
+    /// How much of a DataFrame or Series to print
+    limits: DisplayLimits,
+    /// Source of each input and loaded file that ran successfully, for
+    /// :save and as the context :type checks expressions in
+    session: Vec<String>,
 }
 
 impl Repl {
@@ -56,11 +162,15 @@ impl Repl {
         // Register GUI bindings so REPL users can use Gui.* functions
         #[cfg(feature = "gui")]
         stratum_gui::register_gui(&mut vm);
-        let mut editor = DefaultEditor::new()?;
+        let config = Config::builder()
+            .max_history_size(MAX_HISTORY)?
+            .history_ignore_dups(true)?
+            .build();
+        let mut editor = Editor::with_config(config)?;
+        editor.set_helper(Some(ReplHelper::default()));
 
         // Load history if available
-        if let Some(home) = home_dir() {
-            let history_path = home.join(HISTORY_FILE);
+        if let Some(history_path) = history_path() {
             let _ = editor.load_history(&history_path);
         }
 
@@ -69,6 +179,8 @@ impl Repl {
             editor,
             user_functions: HashSet::new(),
             user_variables: HashSet::new(),
+            limits: DisplayLimits::default(),
+            session: Vec::new(),
         })
     }
 
@@ -80,6 +192,7 @@ impl Repl {
         stratum_gui::register_gui(&mut self.vm);
         self.user_functions.clear();
         self.user_variables.clear();
+        self.session.clear();
         println!("REPL state has been reset.");
     }
 
@@ -121,41 +234,28 @@ impl Repl {
             }
         }
 
-        // Save history
-        if let Some(home) = home_dir() {
-            let history_path = home.join(HISTORY_FILE);
-            let _ = self.editor.save_history(&history_path);
-        }
-
         Ok(())
     }
 
-    /// Read input from the user, handling multi-line input
+    /// Read input from the user; the helper's validator keeps reading lines
+    /// until the brackets balance
     fn read_input(&mut self) -> Result<Option<String>, ReadlineError> {
-        let mut input = String::new();
-        let mut prompt = PROMPT;
-
-        loop {
-            let line = self.editor.readline(prompt)?;
-
-            if input.is_empty() && line.trim().is_empty() {
-                return Ok(None);
-            }
-
-            if !input.is_empty() {
-                input.push('\n');
-            }
-            input.push_str(&line);
+        // Complete against everything defined so far
+        if let Some(helper) = self.editor.helper_mut() {
+            helper.refresh(&self.vm);
+        }
 
-            // Check if we need more input
-            if is_complete(&input) {
-                // Add to history
-                let _ = self.editor.add_history_entry(&input);
-                return Ok(Some(input));
-            }
+        let input = self.editor.readline(PROMPT)?;
+        if input.trim().is_empty() {
+            return Ok(None);
+        }
 
-            prompt = CONTINUATION_PROMPT;
+        // Save as we go, so history survives the REPL being killed
+        let _ = self.editor.add_history_entry(input.as_str());
+        if let Some(history_path) = history_path() {
+            let _ = self.editor.save_history(&history_path);
         }
+        Ok(Some(input))
     }
 
     /// Handle REPL commands (starting with :)
@@ -216,6 +316,38 @@ impl Repl {
                 CommandResult::Handled
             }
 
+            "time" => {
+                if args.is_empty() {
+                    println!("Usage: :time <expression>");
+                } else {
+                    self.time_eval(args);
+                }
+                CommandResult::Handled
+            }
+
+            "plot" | "p" => {
+                if args.is_empty() {
+                    println!("Usage: :plot <expression>");
+                } else {
+                    self.plot(args);
+                }
+                CommandResult::Handled
+            }
+
+            "set" => {
+                self.set_limit(args);
+                CommandResult::Handled
+            }
+
+            "save" | "s" => {
+                if args.is_empty() {
+                    println!("Usage: :save <file>");
+                } else {
+                    self.save_session(args);
+                }
+                CommandResult::Handled
+            }
+
             _ => {
                 println!("Unknown command: :{cmd_name}");
                 println!("Type :help for available commands");
@@ -293,6 +425,7 @@ impl Repl {
                                                 self.user_functions.insert(func.name.name.clone());
                                             }
                                         }
+                                        self.session.push(source.trim().to_string());
                                         println!("File loaded successfully.");
                                     }
                                     Err(e) => eprintln!("Runtime error: {e}"),
@@ -316,15 +449,40 @@ impl Repl {
         }
     }
 
+    /// Save the session's inputs to a file as a script that recreates it
+    fn save_session(&self, path: &str) {
+        let path = Path::new(path.trim());
+        if self.session.is_empty() {
+            println!("Nothing to save.");
+            return;
+        }
+
+        let mut source = self.session.join("\n");
+        source.push('\n');
+        match std::fs::write(path, source) {
+            Ok(()) => println!(
+                "Saved {} input(s) to {}",
+                self.session.len(),
+                path.display()
+            ),
+            Err(e) => eprintln!("Error writing file: {e}"),
+        }
+    }
+
     /// Evaluate input and print the result
     fn eval_and_print(&mut self, input: &str) {
-        // Try to parse and compile
         let result = self.eval(input);
+        self.print_result(result);
+    }
 
+    /// Print the result of evaluating input
+    fn print_result(&self, result: Result<Value, String>) {
         match result {
             Ok(value) => {
-                // Don't print null for statements that don't produce a value
-                if !matches!(value, Value::Null) {
+                if let Some(rendered) = display::render(&value, self.limits) {
+                    println!("{rendered}");
+                } else if !matches!(value, Value::Null) {
+                    // Don't print null for statements that don't produce a value
                     println!("{}", pretty_print(&value));
                 }
             }
@@ -334,8 +492,80 @@ impl Repl {
         }
     }
 
-    /// Evaluate a string of Stratum code
+    /// Evaluate an expression and chart its numbers
+    fn plot(&mut self, input: &str) {
+        match self
+            .plot_value(input)
+            .and_then(|value| display::plot(&value))
+        {
+            Ok(chart) => println!("{chart}"),
+            Err(err) => eprintln!("{err}"),
+        }
+    }
+
+    /// Evaluate a `:plot` argument, reading `df.name` as the column `name`
+    /// of the DataFrame `df`
+    fn plot_value(&mut self, input: &str) -> Result<Value, String> {
+        let err = match self.run_input(input) {
+            Ok(value) => return Ok(value),
+            Err(err) => err,
+        };
+        let Some((receiver, column)) = input.trim().rsplit_once('.') else {
+            return Err(err);
+        };
+        match self.run_input(receiver) {
+            Ok(Value::DataFrame(df)) => df
+                .column(column.trim())
+                .map(|series| Value::Series(Arc::new(series)))
+                .map_err(|e| e.to_string()),
+            _ => Err(err),
+        }
+    }
+
+    /// Show or change how much of a DataFrame or Series is printed
+    fn set_limit(&mut self, args: &str) {
+        let mut parts = args.split_whitespace();
+        let (Some(name), Some(value)) = (parts.next(), parts.next()) else {
+            println!(
+                "rows = {}, cols = {}",
+                self.limits.max_rows, self.limits.max_cols
+            );
+            return;
+        };
+        let limit = match name {
+            "rows" => &mut self.limits.max_rows,
+            "cols" => &mut self.limits.max_cols,
+            _ => {
+                println!("Unknown setting: {name} (expected rows or cols)");
+                return;
+            }
+        };
+        match value.parse::<usize>() {
+            Ok(n) if n > 0 => *limit = n,
+            _ => println!("Expected a positive number, got '{value}'"),
+        }
+    }
+
+    /// Evaluate input, then print the result and how long it took
+    fn time_eval(&mut self, input: &str) {
+        let start = Instant::now();
+        let result = self.eval(input);
+        let elapsed = start.elapsed();
+
+        self.print_result(result);
+        println!("Time: {:.3}ms", elapsed.as_secs_f64() * 1000.0);
+    }
+
+    /// Evaluate a string of Stratum code, adding it to the session if it
+    /// runs
     fn eval(&mut self, input: &str) -> Result<Value, String> {
+        let value = self.run_input(input)?;
+        self.session.push(input.trim().to_string());
+        Ok(value)
+    }
+
+    /// Parse, compile and run a string of Stratum code
+    fn run_input(&mut self, input: &str) -> Result<Value, String> {
         // Parse the input - supports expressions, statements, and function definitions
         let repl_input = Parser::parse_repl_input(input).map_err(|errors| {
             errors
@@ -399,19 +629,239 @@ impl Repl {
 
     /// Show the type of an expression without evaluating it
     fn show_type(&self, input: &str) {
-        match Parser::parse_expression(input) {
-            Ok(_expr) => {
-                // For now, just show that it parsed successfully
-                // Full type inference would require the type checker
-                println!("Expression is syntactically valid");
+        match self.type_of(input) {
+            Ok(ty) => println!("{} : {ty}", input.trim()),
+            Err(err) => eprintln!("{err}"),
+        }
+    }
+
+    /// Infer the type of an expression, as if it followed the session's
+    /// inputs
+    fn type_of(&self, input: &str) -> Result<String, String> {
+        Parser::parse_expression(input).map_err(|errors| {
+            errors
+                .iter()
+                .map(|e| format!("Parse error: {e}"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })?;
+
+        // Bind the expression to a name, so the checker records its type
+        let mut source = self.session.join("\n");
+        source.push_str("\nlet ");
+        let probe = source.len();
+        source.push_str(&format!("{TYPE_PROBE} = ({})\n", input.trim()));
+
+        // Earlier inputs ran, so only errors in the expression are reported
+        let (module, _) = Parser::parse_module_recovering(&source);
+        let result = TypeChecker::new().check_module(&module);
+        let errors: Vec<String> = result
+            .errors
+            .iter()
+            .filter(|e| e.span.start as usize >= probe)
+            .map(|e| format!("Type error: {e}"))
+            .collect();
+        if !errors.is_empty() {
+            return Err(errors.join("\n"));
+        }
+
+        result
+            .binding_types
+            .iter()
+            .find(|(span, _)| span.start as usize == probe)
+            .map(|(_, ty)| ty.to_string())
+            .ok_or_else(|| "Could not infer a type for the expression".to_string())
+    }
+}
+
+/// Line editor helper: tab completion, syntax highlighting and multi-line
+/// input
+#[derive(Default)]
+struct ReplHelper {
+    /// Names of the VM's globals
+    globals: BTreeSet<String>,
+    /// Fields and methods of globals that have any, by global name
+    members: HashMap<String, BTreeSet<String>>,
+}
+
+impl ReplHelper {
+    /// Take the names to complete from the VM's current globals
+    fn refresh(&mut self, vm: &VM) {
+        self.globals.clear();
+        self.members.clear();
+
+        for (name, value) in vm.globals() {
+            // Impl methods are stored as `Type.method` globals
+            if let Some((type_name, method)) = name.split_once('.') {
+                self.members
+                    .entry(type_name.to_string())
+                    .or_default()
+                    .insert(method.to_string());
+                continue;
             }
-            Err(errors) => {
-                for e in errors {
-                    eprintln!("Parse error: {e}");
+            self.globals.insert(name.clone());
+
+            let members: Vec<String> = match value {
+                Value::String(_) => STRING_METHODS.iter().map(ToString::to_string).collect(),
+                Value::List(_) => LIST_METHODS.iter().map(ToString::to_string).collect(),
+                Value::Map(_) => MAP_METHODS.iter().map(ToString::to_string).collect(),
+                Value::Struct(instance) => {
+                    let instance = instance.borrow();
+                    let methods = vm
+                        .globals()
+                        .keys()
+                        .filter_map(|global| {
+                            global.strip_prefix(&format!("{}.", instance.type_name))
+                        })
+                        .map(ToString::to_string);
+                    instance.fields.keys().cloned().chain(methods).collect()
                 }
+                _ => continue,
+            };
+            self.members
+                .entry(name.clone())
+                .or_default()
+                .extend(members);
+        }
+    }
+
+    /// The start of the word being completed at `pos`, and its completions
+    fn candidates(&self, line: &str, pos: usize) -> (usize, Vec<String>) {
+        let before = &line[..pos];
+
+        // A REPL command, while it is the only word
+        let trimmed = before.trim_start();
+        if trimmed.starts_with(':') {
+            if trimmed.contains(' ') {
+                return (pos, Vec::new());
             }
+            let candidates = COMMANDS
+                .iter()
+                .filter(|name| name.starts_with(trimmed))
+                .map(ToString::to_string)
+                .collect();
+            return (pos - trimmed.len(), candidates);
+        }
+
+        let start = word_start(before);
+        let prefix = &before[start..];
+        let names: Vec<&str> = match before[..start].strip_suffix('.') {
+            // A member of the global the `.` follows
+            Some(receiver) => match self.members.get(&receiver[word_start(receiver)..]) {
+                Some(members) => members.iter().map(String::as_str).collect(),
+                None => Vec::new(),
+            },
+            None => KEYWORDS
+                .iter()
+                .copied()
+                .chain(self.globals.iter().map(String::as_str))
+                .collect(),
+        };
+
+        let mut candidates: Vec<String> = names
+            .into_iter()
+            .filter(|name| name.starts_with(prefix))
+            .map(ToString::to_string)
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+        (start, candidates)
+    }
+}
+
+impl Completer for ReplHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let (start, candidates) = self.candidates(line, pos);
+        let pairs = candidates
+            .into_iter()
+            .map(|candidate| Pair {
+                display: candidate.clone(),
+                replacement: candidate,
+            })
+            .collect();
+        Ok((start, pairs))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {
+    fn highlight<'l>(&self, line: &'l str, _pos: usize) -> Cow<'l, str> {
+        if std::env::var_os("NO_COLOR").is_some() {
+            return Cow::Borrowed(line);
+        }
+        Cow::Owned(highlight(line))
+    }
+
+    fn highlight_char(&self, _line: &str, _pos: usize, _kind: CmdKind) -> bool {
+        // Any edit can change how the rest of the line lexes
+        true
+    }
+}
+
+impl Validator for ReplHelper {
+    fn validate(&self, ctx: &mut ValidationContext<'_>) -> rustyline::Result<ValidationResult> {
+        let input = ctx.input();
+        if input.trim_start().starts_with(':') || is_complete(input) {
+            Ok(ValidationResult::Valid(None))
+        } else {
+            Ok(ValidationResult::Incomplete)
+        }
+    }
+}
+
+impl Helper for ReplHelper {}
+
+/// The offset of the identifier `text` ends with (its length if none)
+fn word_start(text: &str) -> usize {
+    text.char_indices()
+        .rev()
+        .take_while(|&(_, c)| c.is_alphanumeric() || c == '_')
+        .last()
+        .map_or(text.len(), |(i, _)| i)
+}
+
+/// Color a line of input with ANSI escapes, by token
+fn highlight(line: &str) -> String {
+    let (tokens, _) = Lexer::tokenize(line);
+    let mut out = String::with_capacity(line.len() * 2);
+    let mut end = 0;
+    for token in tokens {
+        let (start, token_end) = (token.span.start as usize, token.span.end as usize);
+        if start < end || token_end > line.len() {
+            break;
+        }
+        out.push_str(&line[end..start]);
+        let text = &line[start..token_end];
+        let color = match token.kind {
+            TokenKind::LineComment | TokenKind::BlockComment => Some("90"),
+            TokenKind::StringStart
+            | TokenKind::StringPart
+            | TokenKind::StringEnd
+            | TokenKind::MultiLineStringStart
+            | TokenKind::MultiLineStringEnd
+            | TokenKind::RawString => Some("32"),
+            kind if kind.is_keyword() => Some("35"),
+            kind if kind.is_literal() => Some("33"),
+            _ => None,
+        };
+        match color {
+            Some(color) => out.push_str(&format!("\x1b[{color}m{text}\x1b[0m")),
+            None => out.push_str(text),
         }
+        end = token_end;
     }
+    out.push_str(&line[end..]);
+    out
 }
 
 /// Check if the input is complete (balanced brackets/braces/parens)
@@ -578,11 +1028,15 @@ Stratum REPL Commands:
   :help, :h, :?     Show this help message
   :quit, :q         Exit the REPL
   :clear, :cls      Clear the screen
-  :type <expr>      Check if an expression is valid
+  :type <expr>      Show the inferred type of an expression
+  :time <expr>      Evaluate an expression and show how long it took
   :vars, :v         Show all user-defined variables
   :funcs, :f        Show all user-defined functions
   :reset, :r        Reset REPL state (clear variables and functions)
   :load <file>, :l  Load and execute a Stratum file
+  :save <file>, :s  Save this session's inputs as a Stratum file
+  :plot <expr>, :p  Chart a Series, list or DataFrame column (df.column)
+  :set rows|cols N  Set how many rows and columns of data to print
 
 Supported Input:
   - Expressions:    1 + 2, foo.bar(), [1,2,3].map(|x| x*2)
@@ -595,7 +1049,9 @@ Tips:
   - Variables and functions persist across inputs
   - Press Ctrl+C to cancel current input
   - Press Ctrl+D to exit
-  - Use up/down arrows for history
+  - Use up/down arrows for history, saved in ~/.stratum_history
+  - Press Tab to complete names, and fields and methods after a `.`
+  - Input with unclosed brackets continues on the next line
 
 Examples:
   >>> 1 + 2 * 3
@@ -619,6 +1075,11 @@ fn home_dir() -> Option<std::path::PathBuf> {
         .map(std::path::PathBuf::from)
 }
 
+/// Where REPL history is saved
+fn history_path() -> Option<std::path::PathBuf> {
+    home_dir().map(|home| home.join(HISTORY_FILE))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -660,6 +1121,81 @@ mod tests {
         assert!(is_complete("{ x /* nested { braces } */ }"));
     }
 
+    #[test]
+    fn test_word_start() {
+        assert_eq!(word_start("let x = gree"), 8);
+        assert_eq!(word_start("foo."), 4);
+        assert_eq!(word_start("naïve"), 0);
+        assert_eq!(word_start(""), 0);
+    }
+
+    #[test]
+    fn test_complete_globals_and_keywords() {
+        let mut repl = Repl::new().unwrap();
+        repl.eval("let greeting = \"hi\"").unwrap();
+        repl.eval("fx greet(name) { name }").unwrap();
+        let mut helper = ReplHelper::default();
+        helper.refresh(&repl.vm);
+
+        let (start, candidates) = helper.candidates("println(gre", 11);
+        assert_eq!(start, 8);
+        assert_eq!(candidates, vec!["greet", "greeting"]);
+
+        // Keywords and namespaces complete alongside globals
+        let (_, candidates) = helper.candidates("whi", 3);
+        assert_eq!(candidates, vec!["while"]);
+        let (_, candidates) = helper.candidates("Mat", 3);
+        assert!(candidates.contains(&"Math".to_string()), "{candidates:?}");
+    }
+
+    #[test]
+    fn test_complete_members() {
+        let mut repl = Repl::new().unwrap();
+        repl.eval("let greeting = \"hi\"").unwrap();
+        repl.eval("let items = [1, 2]").unwrap();
+        let mut helper = ReplHelper::default();
+        helper.refresh(&repl.vm);
+
+        let (start, candidates) = helper.candidates("greeting.to_", 12);
+        assert_eq!(start, 9);
+        assert_eq!(candidates, vec!["to_lower", "to_upper"]);
+
+        let (_, candidates) = helper.candidates("items.p", 7);
+        assert_eq!(candidates, vec!["pop", "push"]);
+
+        // Unknown receivers complete nothing
+        let (_, candidates) = helper.candidates("missing.p", 9);
+        assert!(candidates.is_empty());
+    }
+
+    #[test]
+    fn test_complete_commands() {
+        let helper = ReplHelper::default();
+        let (start, candidates) = helper.candidates(":l", 2);
+        assert_eq!(start, 0);
+        assert_eq!(candidates, vec![":load"]);
+
+        let (_, candidates) = helper.candidates(":load fi", 8);
+        assert!(candidates.is_empty());
+    }
+
+    #[test]
+    fn test_highlight() {
+        assert_eq!(
+            highlight("let x = 1 // one"),
+            "\x1b[35mlet\x1b[0m x = \x1b[33m1\x1b[0m \x1b[90m// one\x1b[0m"
+        );
+        assert_eq!(highlight("x + y"), "x + y");
+        // Only escapes are added, even to unfinished input
+        for input in ["\"unclosed {x", "fx f(a) {\n  a * 2.5\n}", "/* open"] {
+            let plain = highlight(input).replace("\x1b[0m", "");
+            let plain = ["32", "33", "35", "90"].iter().fold(plain, |text, color| {
+                text.replace(&format!("\x1b[{color}m"), "")
+            });
+            assert_eq!(plain, input);
+        }
+    }
+
     #[test]
     fn test_pretty_print() {
         assert_eq!(pretty_print(&Value::Int(42)), "42");
@@ -784,6 +1320,70 @@ mod tests {
         assert!(repl.user_functions.contains("mul"));
     }
 
+    #[test]
+    fn test_repl_type_of() {
+        let mut repl = Repl::new().unwrap();
+        repl.eval("let x = 5").unwrap();
+        repl.eval("let names = [\"a\", \"b\"]").unwrap();
+
+        assert_eq!(repl.type_of("x + 1").unwrap(), "Int");
+        assert_eq!(repl.type_of("names").unwrap(), "List<String>");
+        assert_eq!(repl.type_of("x > 2").unwrap(), "Bool");
+        assert!(repl.type_of("x +").unwrap_err().starts_with("Parse error"));
+        assert!(repl
+            .type_of("x + \"a\"")
+            .unwrap_err()
+            .starts_with("Type error"));
+    }
+
+    #[test]
+    fn test_repl_save_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.strat");
+        let mut repl = Repl::new().unwrap();
+
+        repl.eval("let x = 5").unwrap();
+        assert!(repl.eval("missing_name + 1").is_err());
+        repl.eval("fx double(n) {\n    n * 2\n}").unwrap();
+        repl.save_session(path.to_str().unwrap());
+
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(saved, "let x = 5\nfx double(n) {\n    n * 2\n}\n");
+
+        // The saved session recreates its definitions
+        let mut replay = Repl::new().unwrap();
+        replay.load_file(path.to_str().unwrap());
+        assert_eq!(replay.eval("double(x)").unwrap(), Value::Int(10));
+    }
+
+    #[test]
+    fn test_repl_plot_value_reads_columns() {
+        let mut repl = Repl::new().unwrap();
+        repl.eval("let df = Data.frame([{\"n\": 1}, {\"n\": 4}])")
+            .unwrap();
+
+        match repl.plot_value("df.n") {
+            Ok(Value::Series(series)) => assert_eq!(series.len(), 2),
+            other => panic!("Expected a Series, got {other:?}"),
+        }
+        assert!(repl.plot_value("df.missing").is_err());
+        assert!(repl.plot_value("nothing.n").is_err());
+    }
+
+    #[test]
+    fn test_repl_set_limit() {
+        let mut repl = Repl::new().unwrap();
+        repl.set_limit("rows 5");
+        repl.set_limit("cols 3");
+        assert_eq!(repl.limits.max_rows, 5);
+        assert_eq!(repl.limits.max_cols, 3);
+
+        // Bad values leave the limits alone
+        repl.set_limit("rows 0");
+        repl.set_limit("depth 2");
+        assert_eq!(repl.limits.max_rows, 5);
+    }
+
     #[test]
     fn test_repl_reset() {
         let mut repl = Repl::new().unwrap();
@@ -800,5 +1400,6 @@ mod tests {
         // State should be cleared
         assert!(repl.user_variables.is_empty());
         assert!(repl.user_functions.is_empty());
+        assert!(repl.session.is_empty());
     }
 }
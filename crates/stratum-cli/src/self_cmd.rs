@@ -201,16 +201,20 @@ pub struct ReleaseAsset {
     pub size: u64,
 }
 
+/// The GitHub releases API of Stratum itself, under the configured API URL
+fn releases_api_url() -> String {
+    format!(
+        "{}/repos/horizon-analytic/stratum/releases",
+        crate::config::current().api_url()
+    )
+}
+
 /// Check for the latest version from GitHub releases
 fn fetch_latest_release() -> Result<ReleaseInfo> {
-    let client = reqwest::blocking::Client::builder()
-        .user_agent("stratum-cli")
-        .timeout(std::time::Duration::from_secs(30))
-        .build()
-        .context("Failed to create HTTP client")?;
+    let client = crate::config::current().http_client(std::time::Duration::from_secs(30))?;
 
     let response = client
-        .get("https://api.github.com/repos/horizon-analytic/stratum/releases/latest")
+        .get(format!("{}/latest", releases_api_url()))
         .send()
         .context("Failed to fetch release information")?;
 
@@ -309,11 +313,7 @@ fn is_musl_libc() -> bool {
 
 /// Download a file with progress reporting
 fn download_file(url: &str, dest: &PathBuf) -> Result<()> {
-    let client = reqwest::blocking::Client::builder()
-        .user_agent("stratum-cli")
-        .timeout(std::time::Duration::from_secs(600))
-        .build()
-        .context("Failed to create HTTP client")?;
+    let client = crate::config::current().http_client(std::time::Duration::from_secs(600))?;
 
     let response = client.get(url).send().context("Failed to download file")?;
 
@@ -1414,11 +1414,7 @@ fn is_version_installed(version: &str) -> Result<bool> {
 
 /// Fetch a specific release by version tag from GitHub
 fn fetch_release(version: &str) -> Result<ReleaseInfo> {
-    let client = reqwest::blocking::Client::builder()
-        .user_agent("stratum-cli")
-        .timeout(std::time::Duration::from_secs(30))
-        .build()
-        .context("Failed to create HTTP client")?;
+    let client = crate::config::current().http_client(std::time::Duration::from_secs(30))?;
 
     // Normalize version: add 'v' prefix if not present for the tag
     let tag = if version.starts_with('v') {
@@ -1427,10 +1423,7 @@ fn fetch_release(version: &str) -> Result<ReleaseInfo> {
         format!("v{}", version)
     };
 
-    let url = format!(
-        "https://api.github.com/repos/horizon-analytic/stratum/releases/tags/{}",
-        tag
-    );
+    let url = format!("{}/tags/{}", releases_api_url(), tag);
 
     let response = client
         .get(&url)
@@ -1484,16 +1477,9 @@ fn fetch_release(version: &str) -> Result<ReleaseInfo> {
 
 /// Fetch available releases from GitHub (paginated)
 fn fetch_available_releases(limit: usize) -> Result<Vec<ReleaseInfo>> {
-    let client = reqwest::blocking::Client::builder()
-        .user_agent("stratum-cli")
-        .timeout(std::time::Duration::from_secs(30))
-        .build()
-        .context("Failed to create HTTP client")?;
-
-    let url = format!(
-        "https://api.github.com/repos/horizon-analytic/stratum/releases?per_page={}",
-        limit
-    );
+    let client = crate::config::current().http_client(std::time::Duration::from_secs(30))?;
+
+    let url = format!("{}?per_page={}", releases_api_url(), limit);
 
     let response = client
         .get(&url)
@@ -0,0 +1,532 @@
+//! `--trace`: record the spans emitted by the VM, JIT, garbage collector and
+//! package resolver, and write them out when the command finishes
+//!
+//! Two formats are supported:
+//! - Chrome trace JSON, for `chrome://tracing`, Perfetto or speedscope
+//! - OTLP JSON, written to a file or posted to an OTLP/HTTP collector when
+//!   the trace destination is an `http://` or `https://` URL
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context as _, Result};
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+/// Trace output format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TraceFormat {
+    /// Chrome trace event JSON
+    #[default]
+    Chrome,
+    /// OpenTelemetry OTLP JSON
+    Otlp,
+}
+
+impl std::str::FromStr for TraceFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "chrome" => Ok(TraceFormat::Chrome),
+            "otlp" => Ok(TraceFormat::Otlp),
+            _ => Err(format!(
+                "Unknown trace format: {s} (expected chrome or otlp)"
+            )),
+        }
+    }
+}
+
+/// Parse a `--trace-level` value
+pub fn parse_level(level: &str) -> Result<LevelFilter> {
+    level.parse::<LevelFilter>().map_err(|_| {
+        anyhow::anyhow!("Unknown trace level: {level} (expected info, debug or trace)")
+    })
+}
+
+/// A recorded span or event
+#[derive(Debug, Clone)]
+struct SpanRecord {
+    /// Unique id (tracing reuses the ids of closed spans)
+    id: u64,
+    /// Id of the enclosing span
+    parent: Option<u64>,
+    /// Span or event name
+    name: &'static str,
+    /// Module that emitted it
+    target: &'static str,
+    /// The span's first field, which names what it is about, or the
+    /// event's message
+    subject: Option<String>,
+    /// Recorded fields
+    fields: Vec<(&'static str, String)>,
+    /// Small id of the emitting thread
+    thread: u64,
+    /// Microseconds since recording started
+    start_us: u64,
+    /// Microseconds since recording started; `None` while open or for events
+    end_us: Option<u64>,
+    /// Whether this is an event rather than a span
+    is_event: bool,
+}
+
+impl SpanRecord {
+    /// Name shown on the timeline: an event's message, or a span's name
+    /// and subject
+    fn label(&self) -> String {
+        match &self.subject {
+            Some(subject) if self.is_event => subject.clone(),
+            Some(subject) => format!("{} {}", self.name, subject),
+            None => self.name.to_string(),
+        }
+    }
+}
+
+/// Collects field values as strings
+#[derive(Default)]
+struct FieldVisitor {
+    fields: Vec<(&'static str, String)>,
+}
+
+impl Visit for FieldVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.fields.push((field.name(), format!("{value:?}")));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.fields.push((field.name(), value.to_string()));
+    }
+}
+
+/// Spans and events recorded so far
+#[derive(Default)]
+struct Recording {
+    next_id: u64,
+    /// Unique ids of the open spans, by tracing span id
+    open: HashMap<u64, usize>,
+    records: Vec<SpanRecord>,
+}
+
+/// A tracing layer recording every span and event
+#[derive(Clone)]
+struct RecordingLayer {
+    started: Instant,
+    recording: Arc<Mutex<Recording>>,
+}
+
+thread_local! {
+    static THREAD_ID: Cell<u64> = const { Cell::new(0) };
+}
+
+/// A small, stable id for the current thread (1 = the first thread seen)
+fn thread_id() -> u64 {
+    static NEXT: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(1);
+    THREAD_ID.with(|id| {
+        if id.get() == 0 {
+            id.set(NEXT.fetch_add(1, std::sync::atomic::Ordering::Relaxed));
+        }
+        id.get()
+    })
+}
+
+impl RecordingLayer {
+    fn elapsed_us(&self) -> u64 {
+        self.started.elapsed().as_micros() as u64
+    }
+}
+
+impl<S> Layer<S> for RecordingLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        attrs.record(&mut visitor);
+        let subject = attrs
+            .metadata()
+            .fields()
+            .iter()
+            .next()
+            .and_then(|first| {
+                visitor
+                    .fields
+                    .iter()
+                    .find(|(name, _)| *name == first.name())
+            })
+            .map(|(_, value)| value.clone());
+        let parent = ctx
+            .span(id)
+            .and_then(|span| span.parent())
+            .map(|parent| parent.id().into_u64());
+        let start_us = self.elapsed_us();
+
+        let mut recording = self.recording.lock().unwrap();
+        let parent = parent.and_then(|p| recording.open.get(&p).map(|&i| recording.records[i].id));
+        recording.next_id += 1;
+        let record = SpanRecord {
+            id: recording.next_id,
+            parent,
+            name: attrs.metadata().name(),
+            target: attrs.metadata().target(),
+            subject,
+            fields: visitor.fields,
+            thread: thread_id(),
+            start_us,
+            end_us: None,
+            is_event: false,
+        };
+        let index = recording.records.len();
+        recording.records.push(record);
+        recording.open.insert(id.into_u64(), index);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        values.record(&mut visitor);
+
+        let mut recording = self.recording.lock().unwrap();
+        if let Some(&index) = recording.open.get(&id.into_u64()) {
+            recording.records[index].fields.extend(visitor.fields);
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        let parent = ctx.event_span(event).map(|span| span.id().into_u64());
+        let start_us = self.elapsed_us();
+
+        let mut recording = self.recording.lock().unwrap();
+        let parent = parent.and_then(|p| recording.open.get(&p).map(|&i| recording.records[i].id));
+        recording.next_id += 1;
+        let subject = visitor
+            .fields
+            .iter()
+            .position(|(name, _)| *name == "message")
+            .map(|i| visitor.fields.remove(i).1);
+        let record = SpanRecord {
+            id: recording.next_id,
+            parent,
+            name: event.metadata().name(),
+            target: event.metadata().target(),
+            subject,
+            fields: visitor.fields,
+            thread: thread_id(),
+            start_us,
+            end_us: None,
+            is_event: true,
+        };
+        recording.records.push(record);
+    }
+
+    fn on_close(&self, id: Id, _ctx: Context<'_, S>) {
+        let end_us = self.elapsed_us();
+        let mut recording = self.recording.lock().unwrap();
+        if let Some(index) = recording.open.remove(&id.into_u64()) {
+            recording.records[index].end_us = Some(end_us);
+        }
+    }
+}
+
+/// Writes the recorded trace when dropped, at the end of the command
+pub struct TraceGuard {
+    destination: PathBuf,
+    format: TraceFormat,
+    /// When recording started, on the recording's clock and the wall clock
+    started: Instant,
+    started_at: SystemTime,
+    recording: Arc<Mutex<Recording>>,
+}
+
+/// Start recording spans at `level` and below, to be written to
+/// `destination` in `format` when the returned guard is dropped
+pub fn install(destination: &Path, format: TraceFormat, level: LevelFilter) -> Result<TraceGuard> {
+    let layer = RecordingLayer {
+        started: Instant::now(),
+        recording: Arc::new(Mutex::new(Recording::default())),
+    };
+    let started = layer.started;
+    let recording = layer.recording.clone();
+
+    let subscriber = tracing_subscriber::registry().with(layer.with_filter(level));
+    tracing::subscriber::set_global_default(subscriber)
+        .context("Failed to install the trace recorder")?;
+
+    Ok(TraceGuard {
+        destination: destination.to_path_buf(),
+        format,
+        started,
+        started_at: SystemTime::now(),
+        recording,
+    })
+}
+
+impl TraceGuard {
+    /// Write the trace to its destination
+    fn write(&self) -> Result<()> {
+        let mut recording = self.recording.lock().unwrap();
+
+        // Spans still open (e.g. after an early exit) end now
+        let end_us = self.started.elapsed().as_micros() as u64;
+        let open: Vec<usize> = recording.open.drain().map(|(_, index)| index).collect();
+        for index in open {
+            recording.records[index].end_us = Some(end_us);
+        }
+
+        let started_unix_ns = self
+            .started_at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+        let json = match self.format {
+            TraceFormat::Chrome => chrome_trace(&recording.records),
+            TraceFormat::Otlp => otlp_trace(&recording.records, started_unix_ns),
+        };
+        let body = serde_json::to_string(&json)?;
+
+        let destination = self.destination.to_string_lossy();
+        if destination.starts_with("http://") || destination.starts_with("https://") {
+            crate::config::current()
+                .http_client(std::time::Duration::from_secs(30))?
+                .post(destination.as_ref())
+                .header("Content-Type", "application/json")
+                .body(body)
+                .send()
+                .and_then(|response| response.error_for_status())
+                .with_context(|| format!("Failed to send trace to {destination}"))?;
+        } else {
+            std::fs::write(&self.destination, body)
+                .with_context(|| format!("Failed to write trace to {destination}"))?;
+        }
+
+        eprintln!(
+            "Trace with {} spans written to {}",
+            recording.records.len(),
+            destination
+        );
+        Ok(())
+    }
+}
+
+impl Drop for TraceGuard {
+    fn drop(&mut self) {
+        if let Err(e) = self.write() {
+            eprintln!("warning: {e:#}");
+        }
+    }
+}
+
+/// Fields as a JSON object
+fn fields_json(record: &SpanRecord) -> serde_json::Value {
+    let mut args = serde_json::Map::new();
+    if let (true, Some(message)) = (record.is_event, &record.subject) {
+        args.insert("message".to_string(), message.clone().into());
+    }
+    for (name, value) in &record.fields {
+        args.insert(name.to_string(), value.clone().into());
+    }
+    serde_json::Value::Object(args)
+}
+
+/// Records in the Chrome trace event format
+fn chrome_trace(records: &[SpanRecord]) -> serde_json::Value {
+    let events: Vec<serde_json::Value> = records
+        .iter()
+        .map(|record| {
+            let mut event = serde_json::json!({
+                "name": record.label(),
+                "cat": record.target,
+                "pid": std::process::id(),
+                "tid": record.thread,
+                "ts": record.start_us,
+                "args": fields_json(record),
+            });
+            if record.is_event {
+                event["ph"] = "i".into();
+                event["s"] = "t".into();
+            } else {
+                event["ph"] = "X".into();
+                event["dur"] = record
+                    .end_us
+                    .unwrap_or(record.start_us)
+                    .saturating_sub(record.start_us)
+                    .into();
+            }
+            event
+        })
+        .collect();
+
+    serde_json::json!({ "traceEvents": events, "displayTimeUnit": "ms" })
+}
+
+/// OTLP attribute list for a record's fields
+fn otlp_attributes(record: &SpanRecord) -> Vec<serde_json::Value> {
+    let mut attributes = vec![serde_json::json!({
+        "key": "code.namespace",
+        "value": { "stringValue": record.target },
+    })];
+    for (name, value) in &record.fields {
+        attributes.push(serde_json::json!({
+            "key": name,
+            "value": { "stringValue": value },
+        }));
+    }
+    attributes
+}
+
+/// Records as an OTLP `ExportTraceServiceRequest` in JSON encoding
+fn otlp_trace(records: &[SpanRecord], started_unix_ns: u64) -> serde_json::Value {
+    let trace_id = format!("{:016x}{:016x}", started_unix_ns, std::process::id() as u64);
+    let span_id = |id: u64| format!("{id:016x}");
+    let unix_ns = |us: u64| (started_unix_ns + us * 1000).to_string();
+
+    // Events belong to their span; events outside any span are dropped
+    let mut events: HashMap<u64, Vec<serde_json::Value>> = HashMap::new();
+    for record in records.iter().filter(|r| r.is_event) {
+        if let Some(parent) = record.parent {
+            events.entry(parent).or_default().push(serde_json::json!({
+                "timeUnixNano": unix_ns(record.start_us),
+                "name": record.label(),
+                "attributes": otlp_attributes(record),
+            }));
+        }
+    }
+
+    let spans: Vec<serde_json::Value> = records
+        .iter()
+        .filter(|r| !r.is_event)
+        .map(|record| {
+            let mut span = serde_json::json!({
+                "traceId": trace_id,
+                "spanId": span_id(record.id),
+                "name": record.label(),
+                "kind": 1,
+                "startTimeUnixNano": unix_ns(record.start_us),
+                "endTimeUnixNano": unix_ns(record.end_us.unwrap_or(record.start_us)),
+                "attributes": otlp_attributes(record),
+                "events": events.remove(&record.id).unwrap_or_default(),
+            });
+            if let Some(parent) = record.parent {
+                span["parentSpanId"] = span_id(parent).into();
+            }
+            span
+        })
+        .collect();
+
+    serde_json::json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [{
+                    "key": "service.name",
+                    "value": { "stringValue": "stratum" },
+                }],
+            },
+            "scopeSpans": [{
+                "scope": { "name": "stratum", "version": stratum_core::VERSION },
+                "spans": spans,
+            }],
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Record the spans emitted by `f` without installing a global subscriber
+    fn record(f: impl FnOnce()) -> Vec<SpanRecord> {
+        let layer = RecordingLayer {
+            started: Instant::now(),
+            recording: Arc::new(Mutex::new(Recording::default())),
+        };
+        let recording = layer.recording.clone();
+        let subscriber = tracing_subscriber::registry().with(layer);
+        tracing::subscriber::with_default(subscriber, f);
+        let records = recording.lock().unwrap().records.clone();
+        records
+    }
+
+    #[test]
+    fn test_trace_format_from_str() {
+        assert_eq!("chrome".parse(), Ok(TraceFormat::Chrome));
+        assert_eq!("OTLP".parse(), Ok(TraceFormat::Otlp));
+        assert!("perf".parse::<TraceFormat>().is_err());
+        assert_eq!(parse_level("debug").unwrap(), LevelFilter::DEBUG);
+        assert!(parse_level("loud").is_err());
+    }
+
+    #[test]
+    fn test_records_nested_spans_and_events() {
+        let records = record(|| {
+            let outer = tracing::trace_span!("call", function = "main");
+            let _entered = outer.enter();
+            let inner = tracing::debug_span!("gc_collect", cycles_broken = tracing::field::Empty);
+            inner.record("cycles_broken", 2);
+            let _inner = inner.enter();
+            tracing::debug!(reason = "threshold", "collecting");
+        });
+
+        assert_eq!(records.len(), 3);
+        let (call, gc, event) = (&records[0], &records[1], &records[2]);
+        assert_eq!(call.label(), "call main");
+        assert_eq!(call.parent, None);
+        assert!(call.end_us.is_some());
+
+        assert_eq!(gc.label(), "gc_collect");
+        assert_eq!(gc.parent, Some(call.id));
+        assert_eq!(gc.fields, vec![("cycles_broken", "2".to_string())]);
+
+        assert!(event.is_event);
+        assert_eq!(event.label(), "collecting");
+        assert_eq!(event.parent, Some(gc.id));
+        assert_eq!(event.fields, vec![("reason", "threshold".to_string())]);
+    }
+
+    #[test]
+    fn test_chrome_trace() {
+        let records = record(|| {
+            let _span = tracing::info_span!("fetch_package", package = "github:a/b").entered();
+        });
+        let json = chrome_trace(&records);
+        let event = &json["traceEvents"][0];
+        assert_eq!(event["name"], "fetch_package github:a/b");
+        assert_eq!(event["ph"], "X");
+        assert!(event["dur"].is_u64());
+        assert_eq!(event["args"]["package"], "github:a/b");
+    }
+
+    #[test]
+    fn test_otlp_trace() {
+        let records = record(|| {
+            let _outer = tracing::info_span!("resolve").entered();
+            let _inner = tracing::info_span!("fetch_package", package = "github:a/b").entered();
+            tracing::info!("downloaded");
+        });
+        let json = otlp_trace(&records, 1_000_000_000);
+        let spans = json["resourceSpans"][0]["scopeSpans"][0]["spans"]
+            .as_array()
+            .unwrap();
+        assert_eq!(spans.len(), 2);
+
+        let (resolve, fetch) = (&spans[0], &spans[1]);
+        assert_eq!(resolve["name"], "resolve");
+        assert!(resolve.get("parentSpanId").is_none());
+        assert_eq!(fetch["parentSpanId"], resolve["spanId"]);
+        assert_eq!(fetch["traceId"], resolve["traceId"]);
+        assert_eq!(fetch["traceId"].as_str().unwrap().len(), 32);
+        assert_eq!(fetch["events"].as_array().unwrap().len(), 1);
+        assert!(resolve["startTimeUnixNano"]
+            .as_str()
+            .unwrap()
+            .starts_with("1000"));
+    }
+}
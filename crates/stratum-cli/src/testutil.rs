@@ -0,0 +1,17 @@
+//! Fixtures shared by the command tests
+
+use std::path::Path;
+
+/// Write a package named `name` at `dir` with an empty library, appending
+/// `extra` to its `stratum.toml`
+pub fn write_package(dir: &Path, name: &str, version: &str, extra: &str) {
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(dir.join("src").join("lib.strat"), "").unwrap();
+    std::fs::write(
+        dir.join(stratum_pkg::MANIFEST_FILE),
+        format!(
+            "[package]\nname = \"{name}\"\nversion = \"{version}\"\nedition = \"2025\"\n{extra}"
+        ),
+    )
+    .unwrap();
+}
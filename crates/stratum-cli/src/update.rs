@@ -1,8 +1,14 @@
 //! Implementation of the `stratum update` command.
 
 use anyhow::{Context, Result};
-use std::path::Path;
-use stratum_pkg::{LockError, Lockfile, Manifest, LOCK_FILE, MANIFEST_FILE};
+use semver::{Version, VersionReq};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use stratum_pkg::api::ApiError;
+use stratum_pkg::{
+    find_duplicates, DependencySource, LockError, Lockfile, Manifest, ResolutionStrategy,
+    ResolvedDependencies, Resolver, Workspace, LOCK_FILE, MANIFEST_FILE,
+};
 
 /// Options for the update command.
 #[derive(Debug, Default)]
@@ -11,6 +17,10 @@ pub struct UpdateOptions {
     pub packages: Vec<String>,
     /// Perform a dry run without writing changes.
     pub dry_run: bool,
+    /// Lock the oldest version each requirement accepts.
+    pub minimal_versions: bool,
+    /// Exact versions to lock, as `name@version`.
+    pub precise: Vec<String>,
 }
 
 /// Result of an update operation.
@@ -76,13 +86,16 @@ impl UpdateResult {
 
 /// Update dependencies to their latest compatible versions.
 ///
-/// Currently, without a package registry, this command:
-/// 1. Re-resolves all dependencies from the manifest
+/// This command:
+/// 1. Re-resolves all dependencies from the manifest, choosing among the
+///    versions published to the configured registry, if any
 /// 2. Compares with the existing lock file
 /// 3. Regenerates the lock file if changes are detected
+/// 4. Warns about packages required at incompatible major versions
 ///
-/// Once a registry is available, this will fetch the latest compatible
-/// versions for each dependency.
+/// Without a registry, registry dependencies stay locked to their
+/// requirements, or to the lowest version they accept with
+/// `minimal_versions`.
 pub fn update_dependencies(options: UpdateOptions) -> Result<UpdateResult> {
     let manifest_path = Path::new(MANIFEST_FILE);
     let lock_path = Path::new(LOCK_FILE);
@@ -96,7 +109,7 @@ pub fn update_dependencies(options: UpdateOptions) -> Result<UpdateResult> {
     }
 
     // Load manifest
-    let manifest = Manifest::from_path(manifest_path).context("Failed to read manifest")?;
+    let manifest = load_manifest(manifest_path)?;
 
     // Load existing lock file if present
     let old_lockfile = if lock_path.exists() {
@@ -106,8 +119,11 @@ pub fn update_dependencies(options: UpdateOptions) -> Result<UpdateResult> {
     };
 
     // Generate new lock file from current manifest
-    let new_lockfile =
-        Lockfile::generate(&manifest, true).context("Failed to resolve dependencies")?;
+    let resolver = build_resolver(&manifest, &options, old_lockfile.as_ref())?;
+    let resolved = resolver
+        .resolve(&manifest)
+        .context("Failed to resolve dependencies")?;
+    let new_lockfile = Lockfile::from_resolved(&resolved);
 
     // Compare and compute changes
     let result = compute_changes(&old_lockfile, &new_lockfile, &options.packages);
@@ -127,9 +143,187 @@ pub fn update_dependencies(options: UpdateOptions) -> Result<UpdateResult> {
         println!("Would update {LOCK_FILE} (dry run)");
     }
 
+    let root = std::env::current_dir()?;
+    warn_duplicates(&local_packages(&root, &manifest, resolved));
+
     Ok(result)
 }
 
+/// A resolver for `options`.
+///
+/// With `options.packages`, every other dependency keeps its locked version
+/// while the manifest still accepts it.
+fn build_resolver(
+    manifest: &Manifest,
+    options: &UpdateOptions,
+    old_lockfile: Option<&Lockfile>,
+) -> Result<Resolver> {
+    let strategy = if options.minimal_versions {
+        ResolutionStrategy::Minimal
+    } else {
+        ResolutionStrategy::Latest
+    };
+    let mut resolver = Resolver::new()
+        .with_dev(true)
+        .with_build(true)
+        .with_strategy(strategy);
+
+    let requirements: Vec<(&str, Option<VersionReq>)> = manifest
+        .all_dependencies()
+        .map(|(name, spec)| {
+            let requirement = spec
+                .version()
+                .filter(|_| !spec.is_path() && !spec.is_git())
+                .and_then(|v| VersionReq::parse(v).ok());
+            (name.as_str(), requirement)
+        })
+        .collect();
+
+    if !options.packages.is_empty() {
+        for locked in old_lockfile.into_iter().flat_map(|l| &l.packages) {
+            if options.packages.contains(&locked.name) {
+                continue;
+            }
+            let Some(version) = locked
+                .version
+                .as_deref()
+                .and_then(|v| Version::parse(v).ok())
+            else {
+                continue;
+            };
+            let still_accepted = requirements.iter().any(|(name, req)| {
+                *name == locked.name && req.as_ref().is_some_and(|r| r.matches(&version))
+            });
+            if still_accepted {
+                resolver = resolver.with_precise(&locked.name, version);
+            }
+        }
+    }
+
+    for spec in &options.precise {
+        let (name, version) = crate::registry::parse_versioned_name(spec)?;
+        if !requirements.iter().any(|(dep, _)| *dep == name) {
+            anyhow::bail!("'{name}' is not a dependency of this package");
+        }
+        resolver = resolver.with_precise(name, Version::parse(version)?);
+    }
+
+    let registry_names = requirements
+        .iter()
+        .filter(|(_, req)| req.is_some())
+        .map(|(name, _)| *name);
+    for (name, versions) in registry_versions(registry_names) {
+        resolver = resolver.with_available_versions(&name, versions);
+    }
+    Ok(resolver)
+}
+
+/// The unyanked versions of `names` published to the configured registry.
+///
+/// Nothing is fetched when no registry is configured; failures are warnings,
+/// leaving those packages locked to their requirements.
+fn registry_versions<'a>(names: impl Iterator<Item = &'a str>) -> Vec<(String, Vec<Version>)> {
+    let Some(url) = crate::config::current().registry.url.clone() else {
+        return Vec::new();
+    };
+    let api = match crate::self_cmd::get_stratum_home()
+        .and_then(|home| crate::registry::client(&url, &home))
+    {
+        Ok(api) => api,
+        Err(e) => {
+            eprintln!("warning: not checking {url} for versions: {e:#}");
+            return Vec::new();
+        }
+    };
+
+    let mut versions = Vec::new();
+    for name in names {
+        match api.info(name) {
+            Ok(info) => versions.push((
+                name.to_string(),
+                info.versions
+                    .iter()
+                    .filter(|v| !v.yanked)
+                    .filter_map(|v| Version::parse(&v.num).ok())
+                    .collect(),
+            )),
+            Err(ApiError::NotFound(_)) => {}
+            Err(e) => eprintln!("warning: could not fetch the versions of '{name}': {e}"),
+        }
+    }
+    versions
+}
+
+/// The resolved dependencies of the package at `root` and of every local
+/// package it builds with: its path dependencies, transitively, and the
+/// other members of its workspace.
+fn local_packages(
+    root: &Path,
+    manifest: &Manifest,
+    resolved: ResolvedDependencies,
+) -> Vec<(String, ResolvedDependencies)> {
+    let mut queue: Vec<(PathBuf, String, ResolvedDependencies)> =
+        vec![(root.to_path_buf(), manifest.package.name.clone(), resolved)];
+    let mut seen: HashSet<PathBuf> = HashSet::new();
+    seen.insert(std::fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf()));
+
+    let workspace = Workspace::find(root).ok();
+    if let Some(workspace) = &workspace {
+        for member in &workspace.members {
+            let dir = std::fs::canonicalize(&member.path).unwrap_or_else(|_| member.path.clone());
+            if !seen.insert(dir) {
+                continue;
+            }
+            let mut manifest = member.package.manifest.clone();
+            workspace.apply_patches(&member.path, &mut manifest);
+            let resolver = Resolver::new().with_dev(true).with_build(true);
+            if let Ok(resolved) = resolver.resolve(&manifest) {
+                queue.push((member.path.clone(), member.name.clone(), resolved));
+            }
+        }
+    }
+
+    let mut packages = Vec::new();
+    while let Some((dir, name, resolved)) = queue.pop() {
+        for dep in resolved.path_deps() {
+            let DependencySource::Path { path } = &dep.source else {
+                continue;
+            };
+            let dep_dir = dir.join(path);
+            let canonical = std::fs::canonicalize(&dep_dir).unwrap_or_else(|_| dep_dir.clone());
+            if !seen.insert(canonical) {
+                continue;
+            }
+            let Ok(mut manifest) = Manifest::from_path(dep_dir.join(MANIFEST_FILE)) else {
+                continue;
+            };
+            if let Some(workspace) = &workspace {
+                workspace.apply_patches(&dep_dir, &mut manifest);
+            }
+            if let Ok(resolved) = Resolver::new().with_build(true).resolve(&manifest) {
+                queue.push((dep_dir, manifest.package.name.clone(), resolved));
+            }
+        }
+        packages.push((name, resolved));
+    }
+    packages
+}
+
+/// Warn about packages required at more than one incompatible version.
+fn warn_duplicates(packages: &[(String, ResolvedDependencies)]) {
+    let duplicates = find_duplicates(packages.iter().map(|(name, r)| (name.as_str(), r)));
+    for duplicate in duplicates {
+        eprintln!(
+            "warning: {} is required at {} incompatible versions:",
+            duplicate.name,
+            duplicate.versions.len()
+        );
+        for (range, requirers) in &duplicate.versions {
+            eprintln!("  {range} by {}", requirers.join(", "));
+        }
+    }
+}
+
 /// Compute the changes between old and new lock files.
 fn compute_changes(
     old: &Option<Lockfile>,
@@ -196,6 +390,17 @@ fn compute_changes(
     result
 }
 
+/// Load the manifest in the current directory, with the `[patch]` and
+/// `[replace]` entries of its workspace root.
+fn load_manifest(path: &Path) -> Result<Manifest> {
+    let mut manifest = Manifest::from_path(path).context("Failed to read manifest")?;
+    let root = std::env::current_dir()?;
+    if let Ok(workspace) = Workspace::find(&root) {
+        workspace.apply_patches(&root, &mut manifest);
+    }
+    Ok(manifest)
+}
+
 /// Format a package spec for display.
 fn format_package_spec(pkg: &stratum_pkg::LockedPackage) -> String {
     match pkg.source.as_str() {
@@ -232,7 +437,7 @@ pub fn sync_lockfile() -> Result<()> {
         ));
     }
 
-    let manifest = Manifest::from_path(manifest_path).context("Failed to read manifest")?;
+    let manifest = load_manifest(manifest_path)?;
 
     // Check if lock file exists and is in sync
     if lock_path.exists() {
@@ -263,6 +468,7 @@ pub fn sync_lockfile() -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testutil::write_package;
     use stratum_pkg::LockedPackage;
 
     fn make_locked_package(name: &str, version: &str) -> LockedPackage {
@@ -278,6 +484,7 @@ mod tests {
             features: Vec::new(),
             checksum: None,
             section: Some("dependencies".to_string()),
+            patched: None,
         }
     }
 
@@ -424,6 +631,7 @@ mod tests {
             features: Vec::new(),
             checksum: None,
             section: None,
+            patched: None,
         };
         assert_eq!(format_package_spec(&pkg), "path:../local");
     }
@@ -442,10 +650,101 @@ mod tests {
             features: Vec::new(),
             checksum: None,
             section: None,
+            patched: None,
         };
         assert_eq!(
             format_package_spec(&pkg),
             "git:https://github.com/example/lib#main"
         );
     }
+
+    #[test]
+    fn test_build_resolver() {
+        let manifest =
+            Manifest::parse("[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2025\"\n\n[dependencies]\nhttp = \"^1.2\"\njson = \"2.0\"\n")
+                .unwrap();
+        let old = Lockfile {
+            version: 1,
+            packages: vec![
+                make_locked_package("http", "1.3.0"),
+                make_locked_package("json", "1.0.0"),
+            ],
+        };
+
+        // Unlisted packages keep a locked version the manifest still accepts
+        let options = UpdateOptions {
+            packages: vec!["json".to_string()],
+            minimal_versions: true,
+            ..Default::default()
+        };
+        let resolved = build_resolver(&manifest, &options, Some(&old))
+            .unwrap()
+            .resolve(&manifest)
+            .unwrap();
+        assert_eq!(
+            resolved.get("http").unwrap().version,
+            Some(Version::new(1, 3, 0))
+        );
+        assert_eq!(
+            resolved.get("json").unwrap().version,
+            Some(Version::new(2, 0, 0))
+        );
+
+        let options = UpdateOptions {
+            precise: vec!["json@2.4.1".to_string()],
+            ..Default::default()
+        };
+        let resolved = build_resolver(&manifest, &options, None)
+            .unwrap()
+            .resolve(&manifest)
+            .unwrap();
+        assert_eq!(
+            resolved.get("json").unwrap().version,
+            Some(Version::new(2, 4, 1))
+        );
+        assert_eq!(resolved.get("http").unwrap().version, None);
+
+        let options = UpdateOptions {
+            precise: vec!["yaml@1.0.0".to_string()],
+            ..Default::default()
+        };
+        assert!(build_resolver(&manifest, &options, None).is_err());
+    }
+
+    #[test]
+    fn test_local_packages_duplicates() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = dir.path().join("app");
+        write_package(
+            &dir.path().join("util"),
+            "util",
+            "0.1.0",
+            "\n[dependencies]\nhttp = \"2.0\"\n",
+        );
+        write_package(
+            &dir.path().join("core"),
+            "core",
+            "0.1.0",
+            "\n[dependencies]\nhttp = \"1.4\"\nutil = { path = \"../util\" }\n",
+        );
+        write_package(
+            &app,
+            "app",
+            "0.1.0",
+            "\n[dependencies]\nhttp = \"^1.0\"\ncore = { path = \"../core\" }\n",
+        );
+
+        let manifest = Manifest::from_path(app.join(MANIFEST_FILE)).unwrap();
+        let resolved = Resolver::new().resolve(&manifest).unwrap();
+        let packages = local_packages(&app, &manifest, resolved);
+        let mut names: Vec<_> = packages.iter().map(|(name, _)| name.as_str()).collect();
+        names.sort_unstable();
+        assert_eq!(names, ["app", "core", "util"]);
+
+        let duplicates = find_duplicates(packages.iter().map(|(name, r)| (name.as_str(), r)));
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].name, "http");
+        assert_eq!(duplicates[0].versions["2.x"], ["util"]);
+        assert_eq!(duplicates[0].versions["1.x"].len(), 2);
+    }
 }
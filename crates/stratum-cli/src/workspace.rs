@@ -0,0 +1,169 @@
+//! Running `build`, `test`, `check` and `fmt` over workspace members, for
+//! `--workspace` and `-p <member>`.
+//!
+//! Members share the workspace's target directory, so build scripts and
+//! executables of every member end up in one place.
+
+use anyhow::Result;
+use std::path::PathBuf;
+use stratum_pkg::{PackageStructure, TargetDir, TargetKind, Workspace, WorkspaceMember};
+
+/// Run `action` for each member named in `packages`, or for every member of
+/// the workspace around the current directory if it is empty, and print a
+/// summary of the members that succeeded and failed.
+///
+/// A failing member does not stop the others from running.
+pub fn for_each_member(
+    packages: &[String],
+    action: impl FnMut(&Workspace, &WorkspaceMember) -> Result<()>,
+) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let workspace =
+        Workspace::find(&cwd).map_err(|e| anyhow::anyhow!("Not in a Stratum workspace: {e}"))?;
+    run_members(&workspace, packages, action)
+}
+
+fn run_members(
+    workspace: &Workspace,
+    packages: &[String],
+    mut action: impl FnMut(&Workspace, &WorkspaceMember) -> Result<()>,
+) -> Result<()> {
+    let members = workspace.select(packages).map_err(|e| {
+        anyhow::anyhow!("{e} (members are: {})", workspace.member_names().join(", "))
+    })?;
+    if members.is_empty() {
+        anyhow::bail!("Workspace at {} has no members", workspace.root.display());
+    }
+
+    let mut results = Vec::new();
+    for member in members {
+        eprintln!("==> {}", member.name);
+        results.push((member.name.as_str(), action(workspace, member).err()));
+    }
+
+    let width = results
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+    eprintln!();
+    for (name, error) in &results {
+        match error {
+            None => eprintln!("{name:<width$}  ok"),
+            Some(e) => eprintln!("{name:<width$}  FAILED: {e}"),
+        }
+    }
+    let failed = results.iter().filter(|(_, error)| error.is_some()).count();
+    if failed > 0 {
+        anyhow::bail!("{failed} of {} member(s) failed", results.len());
+    }
+    Ok(())
+}
+
+/// The files of `package` that `check`, `fmt` and `test` work on: its
+/// sources and test targets
+pub fn package_files(package: &PackageStructure) -> Result<Vec<PathBuf>> {
+    let mut files = package
+        .layout
+        .source_files()
+        .map_err(|e| anyhow::anyhow!("Failed to read package sources: {e}"))?;
+    files.extend(
+        package
+            .targets
+            .iter()
+            .filter(|target| target.kind == TargetKind::Test)
+            .map(|target| target.path.clone()),
+    );
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+/// Where `stratum build` puts the executable of binary target `name` of a
+/// workspace member
+pub fn binary_path(workspace: &Workspace, name: &str, release: bool) -> PathBuf {
+    TargetDir::for_package(&workspace.root)
+        .profile(release)
+        .join(format!("{name}{}", std::env::consts::EXE_SUFFIX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn write_workspace(dir: &Path, members: &[&str]) -> Workspace {
+        std::fs::write(
+            dir.join("stratum.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\n",
+        )
+        .unwrap();
+        for name in members {
+            let root = dir.join("crates").join(name);
+            std::fs::create_dir_all(root.join("src")).unwrap();
+            std::fs::create_dir_all(root.join("tests")).unwrap();
+            std::fs::write(root.join("src").join("main.strat"), "").unwrap();
+            std::fs::write(root.join("tests").join("smoke.strat"), "").unwrap();
+            std::fs::write(
+                root.join("stratum.toml"),
+                format!("[package]\nname = \"{name}\"\nversion = \"0.1.0\"\nedition = \"2025\"\n"),
+            )
+            .unwrap();
+        }
+        Workspace::load(dir).unwrap()
+    }
+
+    #[test]
+    fn test_run_members() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = write_workspace(dir.path(), &["app", "core", "util"]);
+
+        let mut seen = Vec::new();
+        run_members(&workspace, &[], |_, member| {
+            seen.push(member.name.clone());
+            Ok(())
+        })
+        .unwrap();
+        seen.sort();
+        assert_eq!(seen, ["app", "core", "util"]);
+
+        // Every member runs even after one fails
+        let mut seen = Vec::new();
+        let err = run_members(&workspace, &[], |_, member| {
+            seen.push(member.name.clone());
+            if member.name == "core" {
+                anyhow::bail!("broken");
+            }
+            Ok(())
+        })
+        .unwrap_err();
+        assert_eq!(seen.len(), 3);
+        assert_eq!(err.to_string(), "1 of 3 member(s) failed");
+
+        let mut seen = Vec::new();
+        run_members(&workspace, &["util".to_string()], |_, member| {
+            seen.push(member.name.clone());
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(seen, ["util"]);
+
+        let err = run_members(&workspace, &["nope".to_string()], |_, _| Ok(())).unwrap_err();
+        assert!(err.to_string().contains("members are: "), "{err}");
+    }
+
+    #[test]
+    fn test_package_files_and_binary_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = write_workspace(dir.path(), &["app"]);
+        let files = package_files(&workspace.members[0].package).unwrap();
+        let names: Vec<_> = files
+            .iter()
+            .map(|file| file.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, ["main.strat", "smoke.strat"]);
+
+        let path = binary_path(&workspace, "app", true);
+        assert!(path.starts_with(dir.path().join("target").join("release")));
+    }
+}
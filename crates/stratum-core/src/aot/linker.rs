@@ -18,6 +18,11 @@ pub struct LinkerConfig {
     pub optimize: bool,
     /// Additional linker flags
     pub extra_flags: Vec<String>,
+    /// Environment variables the executable sets before running `main`
+    pub env: Vec<(String, String)>,
+    /// Directory to keep the object file and C wrapper in, named after the
+    /// output; temporary files are used and removed if unset
+    pub object_dir: Option<PathBuf>,
 }
 
 impl Default for LinkerConfig {
@@ -26,6 +31,8 @@ impl Default for LinkerConfig {
             output: PathBuf::from("a.out"),
             optimize: false,
             extra_flags: Vec::new(),
+            env: Vec::new(),
+            object_dir: None,
         }
     }
 }
@@ -60,8 +67,10 @@ impl Linker {
             .emit()
             .map_err(|e| AotError::LinkError(format!("Failed to emit object file: {}", e)))?;
 
-        let temp_dir = std::env::temp_dir();
-        let obj_path = temp_dir.join("stratum_module.o");
+        if let Some(dir) = &self.config.object_dir {
+            std::fs::create_dir_all(dir)?;
+        }
+        let obj_path = self.work_file("stratum_module.o", ".o");
 
         std::fs::write(&obj_path, &obj_data)?;
 
@@ -69,11 +78,55 @@ impl Linker {
         self.link_object_file(&obj_path)?;
 
         // Clean up temporary file
-        let _ = std::fs::remove_file(&obj_path);
+        self.remove_work_file(&obj_path);
 
         Ok(self.config.output.clone())
     }
 
+    /// Path of an intermediate file: in the object directory, with `suffix`
+    /// after the output's name, or else `temp_name` in the temp directory
+    fn work_file(&self, temp_name: &str, suffix: &str) -> PathBuf {
+        match &self.config.object_dir {
+            Some(dir) => {
+                let stem = self.config.output.file_stem().unwrap_or_default();
+                dir.join(format!("{}{suffix}", stem.to_string_lossy()))
+            }
+            None => std::env::temp_dir().join(temp_name),
+        }
+    }
+
+    /// Remove an intermediate file unless it is kept in the object directory
+    fn remove_work_file(&self, path: &Path) {
+        if self.config.object_dir.is_none() {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    /// Source of the C wrapper whose `main` sets the configured environment
+    /// and calls our entry point, which returns `entry_type`
+    fn wrapper_code(&self, entry_type: &str) -> String {
+        let mut env = String::new();
+        for (key, value) in &self.config.env {
+            let (key, value) = (c_string(key), c_string(value));
+            if cfg!(windows) {
+                env.push_str(&format!("    _putenv_s({key}, {value});\n"));
+            } else {
+                env.push_str(&format!("    setenv({key}, {value}, 1);\n"));
+            }
+        }
+        format!(
+            r#"
+#include <stdlib.h>
+
+extern {entry_type} _stratum_entry(void);
+
+int main(int argc, char** argv) {{
+{env}    return (int)_stratum_entry();
+}}
+"#
+        )
+    }
+
     /// Link an object file into an executable
     fn link_object_file(&self, obj_path: &Path) -> Result<(), AotError> {
         // Detect the platform and use appropriate linker
@@ -104,15 +157,8 @@ impl Linker {
     #[cfg(target_os = "macos")]
     fn link_macos(&self, obj_path: &Path) -> Result<(), AotError> {
         // Create a minimal C wrapper that calls our entry point
-        let wrapper_path = std::env::temp_dir().join("stratum_wrapper.c");
-        let wrapper_code = r#"
-extern long _stratum_entry(void);
-
-int main(int argc, char** argv) {
-    return (int)_stratum_entry();
-}
-"#;
-        std::fs::write(&wrapper_path, wrapper_code)?;
+        let wrapper_path = self.work_file("stratum_wrapper.c", "_wrapper.c");
+        std::fs::write(&wrapper_path, self.wrapper_code("long"))?;
 
         // Use clang to compile the wrapper and link everything
         let mut cmd = Command::new("clang");
@@ -138,7 +184,7 @@ int main(int argc, char** argv) {
             .map_err(|e| AotError::LinkError(format!("Failed to run linker: {}", e)))?;
 
         // Clean up wrapper
-        let _ = std::fs::remove_file(&wrapper_path);
+        self.remove_work_file(&wrapper_path);
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
@@ -152,15 +198,8 @@ int main(int argc, char** argv) {
     #[cfg(target_os = "linux")]
     fn link_linux(&self, obj_path: &Path) -> Result<(), AotError> {
         // Create a minimal C wrapper that calls our entry point
-        let wrapper_path = std::env::temp_dir().join("stratum_wrapper.c");
-        let wrapper_code = r#"
-extern long _stratum_entry(void);
-
-int main(int argc, char** argv) {
-    return (int)_stratum_entry();
-}
-"#;
-        std::fs::write(&wrapper_path, wrapper_code)?;
+        let wrapper_path = self.work_file("stratum_wrapper.c", "_wrapper.c");
+        std::fs::write(&wrapper_path, self.wrapper_code("long"))?;
 
         // Try clang first, then gcc
         let compiler = if Command::new("clang").arg("--version").output().is_ok() {
@@ -192,7 +231,7 @@ int main(int argc, char** argv) {
             .map_err(|e| AotError::LinkError(format!("Failed to run linker: {}", e)))?;
 
         // Clean up wrapper
-        let _ = std::fs::remove_file(&wrapper_path);
+        self.remove_work_file(&wrapper_path);
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
@@ -206,15 +245,8 @@ int main(int argc, char** argv) {
     #[cfg(target_os = "windows")]
     fn link_windows(&self, obj_path: &Path) -> Result<(), AotError> {
         // Create a minimal C wrapper that calls our entry point
-        let wrapper_path = std::env::temp_dir().join("stratum_wrapper.c");
-        let wrapper_code = r#"
-extern __int64 _stratum_entry(void);
-
-int main(int argc, char** argv) {
-    return (int)_stratum_entry();
-}
-"#;
-        std::fs::write(&wrapper_path, wrapper_code)?;
+        let wrapper_path = self.work_file("stratum_wrapper.c", "_wrapper.c");
+        std::fs::write(&wrapper_path, self.wrapper_code("__int64"))?;
 
         // Try to use cl.exe (MSVC) first, then clang, then gcc (MinGW)
         let (compiler, output_flag) = if Command::new("cl").arg("/?").output().is_ok() {
@@ -255,7 +287,7 @@ int main(int argc, char** argv) {
             .map_err(|e| AotError::LinkError(format!("Failed to run linker: {}", e)))?;
 
         // Clean up wrapper
-        let _ = std::fs::remove_file(&wrapper_path);
+        self.remove_work_file(&wrapper_path);
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
@@ -266,6 +298,21 @@ int main(int argc, char** argv) {
     }
 }
 
+/// Quote `s` as a C string literal
+fn c_string(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for byte in s.bytes() {
+        match byte {
+            b'"' => quoted.push_str("\\\""),
+            b'\\' => quoted.push_str("\\\\"),
+            b' '..=b'~' => quoted.push(byte as char),
+            _ => quoted.push_str(&format!("\\{byte:03o}")),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
 /// Build a Stratum module into an executable
 ///
 /// This is a convenience function that combines AOT compilation and linking.
@@ -312,6 +359,54 @@ mod tests {
         assert_eq!(config.output, PathBuf::from("a.out"));
         assert!(!config.optimize);
         assert!(config.extra_flags.is_empty());
+        assert!(config.env.is_empty());
+        assert!(config.object_dir.is_none());
+    }
+
+    #[test]
+    fn work_files_in_object_dir() {
+        let linker = Linker::with_output("build/app");
+        assert_eq!(
+            linker.work_file("stratum_module.o", ".o"),
+            std::env::temp_dir().join("stratum_module.o")
+        );
+
+        let linker = Linker::new(LinkerConfig {
+            output: PathBuf::from("build/app"),
+            object_dir: Some(PathBuf::from("target/release/obj")),
+            ..Default::default()
+        });
+        assert_eq!(
+            linker.work_file("stratum_module.o", ".o"),
+            PathBuf::from("target/release/obj/app.o")
+        );
+        assert_eq!(
+            linker.work_file("stratum_wrapper.c", "_wrapper.c"),
+            PathBuf::from("target/release/obj/app_wrapper.c")
+        );
+    }
+
+    #[test]
+    fn wrapper_sets_environment() {
+        let linker = Linker::new(LinkerConfig {
+            env: vec![
+                ("GREETING".to_string(), "say \"hi\"\n".to_string()),
+                ("EMPTY".to_string(), String::new()),
+            ],
+            ..Default::default()
+        });
+        let code = linker.wrapper_code("long");
+        assert!(code.contains("extern long _stratum_entry(void);"));
+        let set = if cfg!(windows) {
+            "_putenv_s("
+        } else {
+            "setenv("
+        };
+        assert!(code.contains(&format!(r#"{set}"GREETING", "say \"hi\"\012""#)));
+        assert!(code.contains(&format!(r#"{set}"EMPTY", """#)));
+
+        let plain = Linker::with_output("a.out").wrapper_code("long");
+        assert!(!plain.contains("setenv(") && !plain.contains("_putenv_s("));
     }
 
     #[test]
@@ -3,10 +3,9 @@
 //! This module provides runtime functions that AOT-compiled code links against.
 //! These are minimal implementations needed for standalone executables.
 
-use std::cell::RefCell;
 use std::rc::Rc;
 
-use crate::bytecode::Value;
+use crate::bytecode::{CowCell, Value};
 use crate::jit::types::ValueTag;
 
 /// Packed value representation for AOT FFI
@@ -171,7 +170,7 @@ pub unsafe extern "C" fn stratum_aot_rc_dec(ptr: *const (), tag: u8) {
             Rc::decrement_strong_count(ptr);
         }
         t if t == ValueTag::List as u8 => {
-            let ptr = ptr as *const RefCell<Vec<Value>>;
+            let ptr = ptr as *const CowCell<Vec<Value>>;
             Rc::decrement_strong_count(ptr);
         }
         _ => {
@@ -211,8 +210,8 @@ pub unsafe extern "C" fn stratum_aot_concat_strings(
 
 /// Allocate a new empty list
 #[no_mangle]
-pub extern "C" fn stratum_aot_alloc_list() -> *const RefCell<Vec<Value>> {
-    Rc::into_raw(Rc::new(RefCell::new(Vec::new())))
+pub extern "C" fn stratum_aot_alloc_list() -> *const CowCell<Vec<Value>> {
+    Rc::into_raw(Rc::new(CowCell::new(Vec::new())))
 }
 
 /// Get list length
@@ -220,7 +219,7 @@ pub extern "C" fn stratum_aot_alloc_list() -> *const RefCell<Vec<Value>> {
 /// # Safety
 /// ptr must be a valid pointer to a list
 #[no_mangle]
-pub unsafe extern "C" fn stratum_aot_list_len(ptr: *const RefCell<Vec<Value>>) -> i64 {
+pub unsafe extern "C" fn stratum_aot_list_len(ptr: *const CowCell<Vec<Value>>) -> i64 {
     if ptr.is_null() {
         0
     } else {
@@ -2,6 +2,7 @@
 //!
 //! Comments are attached to AST nodes to preserve them during formatting.
 
+use super::DocComment;
 use crate::lexer::Span;
 
 /// A comment in the source code
@@ -106,6 +107,9 @@ pub struct Trivia {
     pub leading: Vec<Comment>,
     /// Comments after this node on the same line
     pub trailing: Option<Comment>,
+    /// The leading doc comments, parsed; kept up to date by
+    /// [`Trivia::with_leading`] and [`Trivia::add_leading`]
+    pub doc: Option<DocComment>,
 }
 
 impl Trivia {
@@ -118,10 +122,13 @@ impl Trivia {
     /// Create trivia with leading comments
     #[must_use]
     pub fn with_leading(leading: Vec<Comment>) -> Self {
-        Self {
+        let mut trivia = Self {
             leading,
             trailing: None,
-        }
+            doc: None,
+        };
+        trivia.doc = trivia.doc_text().map(|text| DocComment::parse(&text));
+        trivia
     }
 
     /// Check if there are no comments
@@ -132,7 +139,11 @@ impl Trivia {
 
     /// Add a leading comment
     pub fn add_leading(&mut self, comment: Comment) {
+        let is_doc = comment.is_doc_comment();
         self.leading.push(comment);
+        if is_doc {
+            self.doc = self.doc_text().map(|text| DocComment::parse(&text));
+        }
     }
 
     /// Set the trailing comment
@@ -0,0 +1,184 @@
+//! Compile-time attribute registry for type declarations
+//!
+//! `#[derive(...)]` on a struct or enum asks the compiler to generate methods
+//! for it. Every derivable behaviour is registered here together with the
+//! methods it provides, so the type checker, compiler, and VM agree on what a
+//! derive means.
+
+use super::{Attribute, AttributeArg};
+use crate::lexer::Span;
+
+/// A behaviour that can be derived with `#[derive(...)]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Derive {
+    /// Structural equality: `==`, `!=` and `a.equals(b)` compare fields
+    Eq,
+    /// Readable rendering: `value.to_string()` in declaration order
+    Show,
+    /// JSON conversion: `value.to_json()` and `Type.from_json(text)`
+    Json,
+}
+
+impl Derive {
+    /// Every registered derive
+    pub const ALL: [Derive; 3] = [Derive::Eq, Derive::Show, Derive::Json];
+
+    /// Look up a derive by the name used in `#[derive(...)]`
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|derive| derive.name() == name)
+    }
+
+    /// The name used in `#[derive(...)]`
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Derive::Eq => "Eq",
+            Derive::Show => "Show",
+            Derive::Json => "Json",
+        }
+    }
+
+    /// Instance methods generated by this derive
+    #[must_use]
+    pub const fn methods(self) -> &'static [&'static str] {
+        match self {
+            Derive::Eq => &["equals"],
+            Derive::Show => &["to_string"],
+            Derive::Json => &["to_json"],
+        }
+    }
+
+    /// Static methods generated on the type itself
+    #[must_use]
+    pub const fn static_methods(self) -> &'static [&'static str] {
+        match self {
+            Derive::Eq | Derive::Show => &[],
+            Derive::Json => &["from_json"],
+        }
+    }
+
+    /// The derive among `derives` that generates the instance method `name`
+    #[must_use]
+    pub fn providing_method(derives: &[Derive], name: &str) -> Option<Self> {
+        derives
+            .iter()
+            .copied()
+            .find(|derive| derive.methods().contains(&name))
+    }
+
+    /// The derive among `derives` that generates the static method `name`
+    #[must_use]
+    pub fn providing_static_method(derives: &[Derive], name: &str) -> Option<Self> {
+        derives
+            .iter()
+            .copied()
+            .find(|derive| derive.static_methods().contains(&name))
+    }
+}
+
+/// An attribute on a struct or enum that the registry does not accept
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeError {
+    /// What is wrong with the attribute
+    pub message: String,
+    /// Location of the offending attribute or argument
+    pub span: Span,
+}
+
+/// Resolve the derives requested by the attributes of a struct or enum
+///
+/// Unknown attributes and derive names are reported as errors and otherwise
+/// ignored; a derive listed twice is generated once.
+#[must_use]
+pub fn resolve_derives(attributes: &[Attribute]) -> (Vec<Derive>, Vec<AttributeError>) {
+    let mut derives = Vec::new();
+    let mut errors = Vec::new();
+
+    for attr in attributes {
+        if attr.name.name != "derive" {
+            errors.push(AttributeError {
+                message: format!(
+                    "unknown attribute `#[{}]` on a type; expected `#[derive(...)]`",
+                    attr.name.name
+                ),
+                span: attr.span,
+            });
+            continue;
+        }
+
+        for arg in &attr.args {
+            let AttributeArg::Ident(ident) = arg else {
+                errors.push(AttributeError {
+                    message: "expected a derive name such as `Eq`".to_string(),
+                    span: attr.span,
+                });
+                continue;
+            };
+            match Derive::from_name(&ident.name) {
+                Some(derive) if !derives.contains(&derive) => derives.push(derive),
+                Some(_) => {}
+                None => errors.push(AttributeError {
+                    message: format!(
+                        "unknown derive `{}`; expected one of {}",
+                        ident.name,
+                        Derive::ALL.map(Derive::name).join(", ")
+                    ),
+                    span: ident.span,
+                }),
+            }
+        }
+    }
+
+    (derives, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::Ident;
+
+    fn attr(name: &str, args: &[&str]) -> Attribute {
+        Attribute::new(
+            Ident::new(name, Span::dummy()),
+            args.iter()
+                .map(|a| AttributeArg::Ident(Ident::new(*a, Span::dummy())))
+                .collect(),
+            Span::dummy(),
+        )
+    }
+
+    #[test]
+    fn resolves_known_derives_once() {
+        let (derives, errors) = resolve_derives(&[
+            attr("derive", &["Eq", "Json"]),
+            attr("derive", &["Eq", "Show"]),
+        ]);
+        assert!(errors.is_empty());
+        assert_eq!(derives, vec![Derive::Eq, Derive::Json, Derive::Show]);
+    }
+
+    #[test]
+    fn reports_unknown_attributes_and_derives() {
+        let (derives, errors) =
+            resolve_derives(&[attr("derive", &["Eq", "Hash"]), attr("inline", &[])]);
+        assert_eq!(derives, vec![Derive::Eq]);
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].message.contains("unknown derive `Hash`"));
+        assert!(errors[1].message.contains("unknown attribute `#[inline]`"));
+    }
+
+    #[test]
+    fn finds_derive_providing_method() {
+        let derives = [Derive::Show, Derive::Json];
+        assert_eq!(
+            Derive::providing_method(&derives, "to_json"),
+            Some(Derive::Json)
+        );
+        assert_eq!(Derive::providing_method(&derives, "equals"), None);
+        assert_eq!(
+            Derive::providing_static_method(&derives, "from_json"),
+            Some(Derive::Json)
+        );
+    }
+}
@@ -0,0 +1,427 @@
+//! Structured doc comments
+//!
+//! `///` and `/** */` comments before an item are parsed into a
+//! [`DocComment`] when the item is parsed, and kept in its [`Trivia`].
+//! Doc comments are Markdown; `[name]` and `` [`name`] `` link to another
+//! documented item, as in `See [parse_row] for the format`.
+//!
+//! [`Trivia`]: super::Trivia
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Parsed documentation comment
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DocComment {
+    /// Brief summary (first paragraph)
+    pub summary: String,
+    /// Full description (everything after the first blank line)
+    pub description: Option<String>,
+    /// Parameter documentation
+    pub params: HashMap<String, ParamDoc>,
+    /// Return value documentation
+    pub returns: Option<String>,
+    /// Example code blocks
+    pub examples: Vec<String>,
+    /// Thrown exceptions
+    pub throws: Vec<String>,
+    /// See also references
+    pub see_also: Vec<String>,
+}
+
+impl DocComment {
+    /// Parse a doc comment from raw text
+    #[must_use]
+    pub fn parse(text: &str) -> Self {
+        let mut doc = DocComment::default();
+        let lines: Vec<&str> = text.lines().collect();
+
+        if lines.is_empty() {
+            return doc;
+        }
+
+        let mut current_section = Section::Summary;
+        let mut summary_lines = Vec::new();
+        let mut description_lines = Vec::new();
+        let mut current_example = Vec::new();
+        let mut in_code_block = false;
+
+        for line in lines {
+            let trimmed = line.trim();
+
+            // Handle code blocks
+            if trimmed.starts_with("```") {
+                if in_code_block {
+                    // End of code block
+                    in_code_block = false;
+                    if current_section == Section::Example {
+                        doc.examples.push(current_example.join("\n"));
+                        current_example.clear();
+                    }
+                } else {
+                    // Start of code block
+                    in_code_block = true;
+                    if trimmed.contains("stratum") || current_section == Section::Example {
+                        current_section = Section::Example;
+                    }
+                }
+                continue;
+            }
+
+            if in_code_block {
+                if current_section == Section::Example {
+                    current_example.push(line.to_string());
+                }
+                continue;
+            }
+
+            // Parse section headers
+            if let Some(section) = parse_section_header(trimmed) {
+                current_section = section;
+                continue;
+            }
+
+            // Parse inline tags like `- param_name: description`
+            if current_section == Section::Arguments {
+                if let Some((name, desc)) = parse_param_line(trimmed) {
+                    doc.params.insert(name, ParamDoc { description: desc });
+                    continue;
+                }
+            }
+
+            // Accumulate content based on current section
+            match current_section {
+                Section::Summary => {
+                    if trimmed.is_empty() && !summary_lines.is_empty() {
+                        current_section = Section::Description;
+                    } else if !trimmed.is_empty() {
+                        summary_lines.push(trimmed);
+                    }
+                }
+                Section::Description => {
+                    description_lines.push(trimmed);
+                }
+                Section::Returns => {
+                    if !trimmed.is_empty() && !trimmed.starts_with('#') {
+                        let existing = doc.returns.take().unwrap_or_default();
+                        if existing.is_empty() {
+                            doc.returns = Some(trimmed.to_string());
+                        } else {
+                            doc.returns = Some(format!("{} {}", existing, trimmed));
+                        }
+                    }
+                }
+                Section::Throws => {
+                    if !trimmed.is_empty() && !trimmed.starts_with('#') {
+                        doc.throws.push(trimmed.to_string());
+                    }
+                }
+                Section::SeeAlso => {
+                    if !trimmed.is_empty() && !trimmed.starts_with('#') {
+                        doc.see_also.push(trimmed.to_string());
+                    }
+                }
+                Section::Arguments | Section::Example => {}
+            }
+        }
+
+        // Finalize summary
+        doc.summary = summary_lines.join(" ");
+
+        // Finalize description
+        let desc = description_lines.join("\n").trim().to_string();
+        if !desc.is_empty() {
+            doc.description = Some(desc);
+        }
+
+        doc
+    }
+
+    /// Check if the doc comment is empty
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.summary.is_empty()
+            && self.description.is_none()
+            && self.params.is_empty()
+            && self.returns.is_none()
+            && self.examples.is_empty()
+    }
+}
+
+/// Documentation for a function parameter
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParamDoc {
+    /// Description of the parameter
+    pub description: String,
+}
+
+/// Section being parsed
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Section {
+    Summary,
+    Description,
+    Arguments,
+    Returns,
+    Example,
+    Throws,
+    SeeAlso,
+}
+
+fn parse_section_header(line: &str) -> Option<Section> {
+    let lower = line.to_lowercase();
+    if lower.starts_with("## arguments") || lower.starts_with("# arguments") {
+        Some(Section::Arguments)
+    } else if lower.starts_with("## returns") || lower.starts_with("# returns") {
+        Some(Section::Returns)
+    } else if lower.starts_with("## example") || lower.starts_with("# example") {
+        Some(Section::Example)
+    } else if lower.starts_with("## throws") || lower.starts_with("# throws") {
+        Some(Section::Throws)
+    } else if lower.starts_with("## see also") || lower.starts_with("# see also") {
+        Some(Section::SeeAlso)
+    } else {
+        None
+    }
+}
+
+fn parse_param_line(line: &str) -> Option<(String, String)> {
+    // Parse lines like "- `name`: description" or "- name: description"
+    let line = line.strip_prefix('-')?.trim();
+
+    // Handle backtick-wrapped names
+    let (name, rest) = if line.starts_with('`') {
+        let end = line[1..].find('`')?;
+        let name = &line[1..=end];
+        let rest = line[end + 2..].trim();
+        (name, rest)
+    } else {
+        // Handle plain names
+        let colon_pos = line.find(':')?;
+        let name = line[..colon_pos].trim();
+        let rest = line[colon_pos + 1..].trim();
+        (name, rest)
+    };
+
+    let description = rest.strip_prefix(':').unwrap_or(rest).trim();
+    Some((name.to_string(), description.to_string()))
+}
+
+impl DocComment {
+    /// Every intra-doc link target in the prose sections, in order
+    #[must_use]
+    pub fn links(&self) -> Vec<String> {
+        self.prose()
+            .flat_map(|text| intra_doc_links(text).into_iter().map(|link| link.target))
+            .collect()
+    }
+
+    /// Render the doc comment as Markdown, with `link` giving the URL of
+    /// each intra-doc link target.
+    ///
+    /// Links that resolve become `` [`name`](url) ``; the rest are left as
+    /// `` `name` ``.
+    #[must_use]
+    pub fn to_markdown(&self, link: impl Fn(&str) -> Option<String>) -> String {
+        let render = |text: &str| render_links(text, &link);
+        let mut sections = Vec::new();
+        if !self.summary.is_empty() {
+            sections.push(render(&self.summary));
+        }
+        if let Some(description) = &self.description {
+            sections.push(render(description));
+        }
+        if !self.params.is_empty() {
+            let mut params: Vec<_> = self.params.iter().collect();
+            params.sort_by(|a, b| a.0.cmp(b.0));
+            let lines: Vec<_> = params
+                .into_iter()
+                .map(|(name, param)| format!("- `{name}`: {}", render(&param.description)))
+                .collect();
+            sections.push(format!("**Arguments:**\n\n{}", lines.join("\n")));
+        }
+        if let Some(returns) = &self.returns {
+            sections.push(format!("**Returns:** {}", render(returns)));
+        }
+        if !self.throws.is_empty() {
+            let lines: Vec<_> = self.throws.iter().map(|t| format!("- {t}")).collect();
+            sections.push(format!("**Throws:**\n\n{}", lines.join("\n")));
+        }
+        for example in &self.examples {
+            sections.push(format!("```stratum\n{example}\n```"));
+        }
+        sections.join("\n\n")
+    }
+
+    /// The sections of free text that may contain intra-doc links
+    fn prose(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.summary.as_str())
+            .chain(self.description.as_deref())
+            .chain(self.params.values().map(|p| p.description.as_str()))
+            .chain(self.returns.as_deref())
+    }
+}
+
+/// A `[name]` link to another item in doc comment text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntraDocLink {
+    /// Byte range of the link in the text, brackets included
+    pub range: Range<usize>,
+    /// The linked name: an item, `Type.member` or `module.item`
+    pub target: String,
+}
+
+/// Find the intra-doc links in `text`.
+///
+/// A link is a name in square brackets, optionally in backticks, that is
+/// not a Markdown link (`[text](url)`) or reference (`[text][ref]`).
+/// Brackets inside code spans are left alone.
+#[must_use]
+pub fn intra_doc_links(text: &str) -> Vec<IntraDocLink> {
+    let bytes = text.as_bytes();
+    let mut links = Vec::new();
+    let mut in_code = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'`' => in_code = !in_code,
+            b'[' if !in_code => {
+                if let Some(link) = link_at(text, i) {
+                    i = link.range.end;
+                    links.push(link);
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    links
+}
+
+/// The intra-doc link starting with the `[` at `start`, if there is one
+fn link_at(text: &str, start: usize) -> Option<IntraDocLink> {
+    let close = start + text[start..].find(']')?;
+    let inner = &text[start + 1..close];
+    let target = inner
+        .strip_prefix('`')
+        .and_then(|s| s.strip_suffix('`'))
+        .unwrap_or(inner);
+    let is_path = target.split('.').all(|segment| {
+        segment
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_alphabetic() || c == '_')
+            && segment.chars().all(|c| c.is_alphanumeric() || c == '_')
+    });
+    if !is_path || matches!(text[close + 1..].chars().next(), Some('(' | '[' | ':')) {
+        return None;
+    }
+    Some(IntraDocLink {
+        range: start..close + 1,
+        target: target.to_string(),
+    })
+}
+
+/// Replace the intra-doc links in `text` with Markdown links
+fn render_links(text: &str, link: &impl Fn(&str) -> Option<String>) -> String {
+    let mut output = String::new();
+    let mut last = 0;
+    for intra in intra_doc_links(text) {
+        output.push_str(&text[last..intra.range.start]);
+        match link(&intra.target) {
+            Some(url) => output.push_str(&format!("[`{}`]({url})", intra.target)),
+            None => output.push_str(&format!("`{}`", intra.target)),
+        }
+        last = intra.range.end;
+    }
+    output.push_str(&text[last..]);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_simple_doc() {
+        let text = "This is a simple function.";
+        let doc = DocComment::parse(text);
+        assert_eq!(doc.summary, "This is a simple function.");
+        assert!(doc.description.is_none());
+    }
+
+    #[test]
+    fn test_parse_doc_with_description() {
+        let text = "Brief summary.\n\nThis is the longer description\nthat spans multiple lines.";
+        let doc = DocComment::parse(text);
+        assert_eq!(doc.summary, "Brief summary.");
+        assert!(doc.description.is_some());
+        assert!(doc.description.unwrap().contains("longer description"));
+    }
+
+    #[test]
+    fn test_parse_doc_with_params() {
+        let text = r#"Greet a user.
+
+## Arguments
+- `name`: The user's name
+- `greeting`: The greeting to use"#;
+        let doc = DocComment::parse(text);
+        assert_eq!(doc.summary, "Greet a user.");
+        assert_eq!(doc.params.len(), 2);
+        assert_eq!(
+            doc.params.get("name").unwrap().description,
+            "The user's name"
+        );
+    }
+
+    #[test]
+    fn test_parse_doc_with_returns() {
+        let text = r#"Add two numbers.
+
+## Returns
+The sum of a and b"#;
+        let doc = DocComment::parse(text);
+        assert_eq!(doc.returns, Some("The sum of a and b".to_string()));
+    }
+
+    #[test]
+    fn test_parse_doc_with_example() {
+        let text = r#"Greet a user.
+
+## Example
+```stratum
+let greeting = greet("World")
+```"#;
+        let doc = DocComment::parse(text);
+        assert_eq!(doc.examples.len(), 1);
+        assert!(doc.examples[0].contains("greet"));
+    }
+
+    #[test]
+    fn test_intra_doc_links() {
+        let links =
+            intra_doc_links("Uses [parse] and [`Row.cells`], see [docs](http://x) or [1, 2]");
+        let targets: Vec<_> = links.iter().map(|l| l.target.as_str()).collect();
+        assert_eq!(targets, ["parse", "Row.cells"]);
+        assert_eq!(links[0].range, 5..12);
+
+        assert!(intra_doc_links("`list[index]` and [ref][1] and [0]").is_empty());
+    }
+
+    #[test]
+    fn test_to_markdown() {
+        let doc = DocComment::parse(
+            "Parse a row, see [split].\n\n## Arguments\n- `line`: Text like [Row]\n\n## Returns\nA [Missing]",
+        );
+        assert_eq!(doc.links(), ["split", "Row", "Missing"]);
+
+        let markdown = doc.to_markdown(|target| {
+            (target != "Missing").then(|| format!("#{}", target.to_lowercase()))
+        });
+        assert_eq!(
+            markdown,
+            "Parse a row, see [`split`](#split).\n\n**Arguments:**\n\n- `line`: Text like [`Row`](#row)\n\n**Returns:** A `Missing`"
+        );
+    }
+}
@@ -117,8 +117,35 @@ impl BinOp {
             BinOp::RangeInclusive => "..=",
         }
     }
+
+    /// The method a struct or enum defines in an impl block to overload this
+    /// operator (`!=` negates `__eq__`)
+    #[must_use]
+    pub const fn method_name(self) -> Option<&'static str> {
+        match self {
+            BinOp::Add => Some("__add__"),
+            BinOp::Sub => Some("__sub__"),
+            BinOp::Mul => Some("__mul__"),
+            BinOp::Div => Some("__div__"),
+            BinOp::Mod => Some("__mod__"),
+            BinOp::Eq | BinOp::Ne => Some("__eq__"),
+            BinOp::Lt => Some("__lt__"),
+            BinOp::Le => Some("__le__"),
+            BinOp::Gt => Some("__gt__"),
+            BinOp::Ge => Some("__ge__"),
+            BinOp::And
+            | BinOp::Or
+            | BinOp::Pipe
+            | BinOp::NullCoalesce
+            | BinOp::Range
+            | BinOp::RangeInclusive => None,
+        }
+    }
 }
 
+/// The method a struct or enum defines to overload indexing (`value[key]`)
+pub const INDEX_METHOD: &str = "__index__";
+
 /// Unary operators
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum UnaryOp {
@@ -137,6 +164,15 @@ impl UnaryOp {
             UnaryOp::Not => "!",
         }
     }
+
+    /// The method a struct or enum defines to overload this operator
+    #[must_use]
+    pub const fn method_name(self) -> Option<&'static str> {
+        match self {
+            UnaryOp::Neg => Some("__neg__"),
+            UnaryOp::Not => None,
+        }
+    }
 }
 
 /// Literal values
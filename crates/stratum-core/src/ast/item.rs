@@ -2,7 +2,9 @@
 
 use crate::lexer::Span;
 
-use super::{Block, Expr, Ident, Param, Spanned, Trivia, TypeAnnotation, TypeParam};
+use super::{
+    resolve_derives, Block, Derive, Expr, Ident, Param, Spanned, Trivia, TypeAnnotation, TypeParam,
+};
 
 /// Execution mode for a function or module
 ///
@@ -29,7 +31,7 @@ pub enum ExecutionModeOverride {
     CompileAll,
 }
 
-/// An attribute on a function or other item
+/// An attribute on a function, struct, or enum
 /// Syntax: #[name] or #[name(args)]
 #[derive(Debug, Clone, PartialEq)]
 pub struct Attribute {
@@ -64,12 +66,34 @@ impl Attribute {
         self.name.name == "test"
     }
 
+    /// Check if this is a parameterized test case: #[test_case(1, 2, 3)]
+    #[must_use]
+    pub fn is_test_case(&self) -> bool {
+        self.name.name == "test_case"
+    }
+
+    /// Check if this marks a test fixture
+    #[must_use]
+    pub fn is_fixture(&self) -> bool {
+        self.name.name == "fixture"
+    }
+
     /// Check if this test should expect a panic
     #[must_use]
     pub fn should_panic(&self) -> bool {
         self.args.iter().any(|arg| match arg {
             AttributeArg::Ident(ident) => ident.name == "should_panic",
             AttributeArg::NameValue { name, .. } => name.name == "should_panic",
+            AttributeArg::Value(_) => false,
+        })
+    }
+
+    /// Get the value of a `name = value` argument
+    #[must_use]
+    pub fn named_value(&self, key: &str) -> Option<&Expr> {
+        self.args.iter().find_map(|arg| match arg {
+            AttributeArg::NameValue { name, value } if name.name == key => Some(&**value),
+            _ => None,
         })
     }
 
@@ -123,6 +147,8 @@ pub enum AttributeArg {
     Ident(Ident),
     /// Name = value pair: #[test(expected = "error message")]
     NameValue { name: Ident, value: Box<Expr> },
+    /// A positional value: #[test_case(1, "one")]
+    Value(Box<Expr>),
 }
 
 /// A complete source file / module
@@ -244,7 +270,7 @@ impl TopLevelItem {
     }
 }
 
-/// A top-level let declaration
+/// A top-level let or const declaration
 #[derive(Debug, Clone, PartialEq)]
 pub struct TopLevelLet {
     /// Variable name or destructuring pattern
@@ -253,6 +279,8 @@ pub struct TopLevelLet {
     pub ty: Option<super::TypeAnnotation>,
     /// Initial value
     pub value: super::Expr,
+    /// Declared with `const`: the value must be known at compile time
+    pub is_const: bool,
     /// Source location
     pub span: Span,
     /// Comments associated with this let
@@ -272,6 +300,7 @@ impl TopLevelLet {
             pattern,
             ty,
             value,
+            is_const: false,
             span,
             trivia: Trivia::empty(),
         }
@@ -290,10 +319,27 @@ impl TopLevelLet {
             pattern,
             ty,
             value,
+            is_const: false,
             span,
             trivia,
         }
     }
+
+    /// Mark this declaration as a `const`
+    #[must_use]
+    pub fn into_const(mut self) -> Self {
+        self.is_const = true;
+        self
+    }
+
+    /// The declared name when the pattern is a plain identifier
+    #[must_use]
+    pub fn name(&self) -> Option<&super::Ident> {
+        match &self.pattern.kind {
+            super::PatternKind::Ident(ident) => Some(ident),
+            _ => None,
+        }
+    }
 }
 
 impl Spanned for TopLevelLet {
@@ -424,10 +470,12 @@ impl Function {
         }
     }
 
-    /// Check if this function has a #[test] attribute
+    /// Check if this function has a #[test] or #[test_case(...)] attribute
     #[must_use]
     pub fn is_test(&self) -> bool {
-        self.attributes.iter().any(Attribute::is_test)
+        self.attributes
+            .iter()
+            .any(|a| a.is_test() || a.is_test_case())
     }
 
     /// Check if this test function should expect a panic
@@ -439,6 +487,17 @@ impl Function {
             .any(Attribute::should_panic)
     }
 
+    /// Get this function's #[test_case(...)] attributes, one per case
+    pub fn test_cases(&self) -> impl Iterator<Item = &Attribute> {
+        self.attributes.iter().filter(|a| a.is_test_case())
+    }
+
+    /// Check if this function has a #[fixture] attribute
+    #[must_use]
+    pub fn is_fixture(&self) -> bool {
+        self.attributes.iter().any(Attribute::is_fixture)
+    }
+
     /// Get the execution mode specified by this function's attributes
     ///
     /// Returns `None` if no execution mode directive is specified on this function.
@@ -485,6 +544,8 @@ pub struct StructDef {
     pub type_params: Vec<TypeParam>,
     /// Struct fields
     pub fields: Vec<StructField>,
+    /// Attributes on this struct (e.g., #[derive(Eq, Show)])
+    pub attributes: Vec<Attribute>,
     /// Source location
     pub span: Span,
     /// Comments associated with this struct
@@ -504,10 +565,17 @@ impl StructDef {
             name,
             type_params,
             fields,
+            attributes: Vec::new(),
             span,
             trivia: Trivia::empty(),
         }
     }
+
+    /// The behaviours requested with `#[derive(...)]`
+    #[must_use]
+    pub fn derives(&self) -> Vec<Derive> {
+        resolve_derives(&self.attributes).0
+    }
 }
 
 impl Spanned for StructDef {
@@ -557,6 +625,8 @@ pub struct EnumDef {
     pub type_params: Vec<TypeParam>,
     /// Enum variants
     pub variants: Vec<EnumVariant>,
+    /// Attributes on this enum (e.g., #[derive(Eq, Json)])
+    pub attributes: Vec<Attribute>,
     /// Source location
     pub span: Span,
     /// Comments associated with this enum
@@ -576,10 +646,17 @@ impl EnumDef {
             name,
             type_params,
             variants,
+            attributes: Vec::new(),
             span,
             trivia: Trivia::empty(),
         }
     }
+
+    /// The behaviours requested with `#[derive(...)]`
+    #[must_use]
+    pub fn derives(&self) -> Vec<Derive> {
+        resolve_derives(&self.attributes).0
+    }
 }
 
 impl Spanned for EnumDef {
@@ -664,6 +741,27 @@ impl InterfaceDef {
             trivia: Trivia::empty(),
         }
     }
+
+    /// The methods with a default implementation, as functions
+    #[must_use]
+    pub fn default_methods(&self) -> Vec<Function> {
+        self.methods
+            .iter()
+            .filter_map(|method| {
+                let body = method.default_body.clone()?;
+                Some(Function::new(
+                    method.name.clone(),
+                    method.type_params.clone(),
+                    method.params.clone(),
+                    method.return_type.clone(),
+                    body,
+                    method.is_async,
+                    Vec::new(),
+                    method.span,
+                ))
+            })
+            .collect()
+    }
 }
 
 impl Spanned for InterfaceDef {
@@ -4,6 +4,8 @@
 //! All AST nodes include source location information via [`Span`] for error reporting.
 
 mod comment;
+mod derive;
+mod doc;
 mod expr;
 mod item;
 mod pretty;
@@ -11,6 +13,8 @@ mod stmt;
 mod types;
 
 pub use comment::*;
+pub use derive::*;
+pub use doc::*;
 pub use expr::*;
 pub use item::*;
 pub use stmt::*;
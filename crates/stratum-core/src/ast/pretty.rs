@@ -491,7 +491,8 @@ impl Display for TopLevelItem {
 
 impl Display for TopLevelLet {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "let {}", self.pattern)?;
+        let keyword = if self.is_const { "const" } else { "let" };
+        write!(f, "{keyword} {}", self.pattern)?;
         if let Some(ty) = &self.ty {
             write!(f, ": {ty}")?;
         }
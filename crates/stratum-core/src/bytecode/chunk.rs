@@ -21,6 +21,38 @@ pub struct Chunk {
 
     /// Source file name (for error messages)
     pub source_name: Option<String>,
+
+    /// Named local variables and where they are live (for the debugger)
+    local_variables: Vec<LocalVariable>,
+
+    /// Names of the captured upvalues, by upvalue index (for the debugger)
+    upvalue_names: Vec<String>,
+}
+
+/// A named local variable, live over a range of bytecode offsets
+///
+/// Recorded by the compiler so the debugger can show locals by name and
+/// evaluate expressions that refer to them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalVariable {
+    /// Variable name
+    pub name: String,
+    /// Stack slot relative to the frame base
+    pub slot: u16,
+    /// First offset at which the variable holds its value
+    pub start: usize,
+    /// Offset at which the variable goes out of scope
+    pub end: usize,
+}
+
+/// A chunk without its constant pool
+#[derive(Debug, Clone)]
+pub(crate) struct ChunkCode {
+    code: Vec<u8>,
+    lines: Vec<(u32, u32)>,
+    source_name: Option<String>,
+    local_variables: Vec<LocalVariable>,
+    upvalue_names: Vec<String>,
 }
 
 impl Chunk {
@@ -32,6 +64,8 @@ impl Chunk {
             constants: Vec::new(),
             lines: Vec::new(),
             source_name: None,
+            local_variables: Vec::new(),
+            upvalue_names: Vec::new(),
         }
     }
 
@@ -43,6 +77,8 @@ impl Chunk {
             constants: Vec::new(),
             lines: Vec::new(),
             source_name: Some(source_name.into()),
+            local_variables: Vec::new(),
+            upvalue_names: Vec::new(),
         }
     }
 
@@ -69,6 +105,76 @@ impl Chunk {
         &mut self.code
     }
 
+    /// Remove all instructions and line information, keeping the constant pool
+    ///
+    /// Used to rewrite a chunk's code in place (see the optimizer). Local
+    /// variable ranges refer to the old offsets, so they are dropped too.
+    pub(crate) fn clear_code(&mut self) {
+        self.code.clear();
+        self.lines.clear();
+        self.local_variables.clear();
+    }
+
+    /// Split into the constant pool and the rest, which is plain data that
+    /// can move between threads
+    pub(crate) fn split(&self) -> (ChunkCode, &[Value]) {
+        let code = ChunkCode {
+            code: self.code.clone(),
+            lines: self.lines.clone(),
+            source_name: self.source_name.clone(),
+            local_variables: self.local_variables.clone(),
+            upvalue_names: self.upvalue_names.clone(),
+        };
+        (code, &self.constants)
+    }
+
+    /// Put a chunk split by [`Chunk::split`] back together, with constants at
+    /// the same indexes
+    pub(crate) fn join(code: ChunkCode, constants: Vec<Value>) -> Self {
+        Self {
+            code: code.code,
+            constants,
+            lines: code.lines,
+            source_name: code.source_name,
+            local_variables: code.local_variables,
+            upvalue_names: code.upvalue_names,
+        }
+    }
+
+    /// Record a named local variable
+    pub fn add_local_variable(&mut self, variable: LocalVariable) {
+        self.local_variables.push(variable);
+    }
+
+    /// All recorded local variables
+    #[must_use]
+    pub fn local_variables(&self) -> &[LocalVariable] {
+        &self.local_variables
+    }
+
+    /// Record the name of the next upvalue
+    pub fn add_upvalue_name(&mut self, name: impl Into<String>) {
+        self.upvalue_names.push(name.into());
+    }
+
+    /// Names of the captured upvalues, by upvalue index
+    #[must_use]
+    pub fn upvalue_names(&self) -> &[String] {
+        &self.upvalue_names
+    }
+
+    /// The local variables live at a bytecode offset, ordered by slot
+    #[must_use]
+    pub fn locals_at(&self, offset: usize) -> Vec<&LocalVariable> {
+        let mut locals: Vec<&LocalVariable> = self
+            .local_variables
+            .iter()
+            .filter(|local| local.start <= offset && offset < local.end)
+            .collect();
+        locals.sort_by_key(|local| local.slot);
+        locals
+    }
+
     /// Returns the constant pool
     #[must_use]
     pub fn constants(&self) -> &[Value] {
@@ -202,6 +308,36 @@ impl Chunk {
         self.lines.last().map_or(0, |(line, _)| *line)
     }
 
+    /// The first and last source lines the code was compiled from
+    #[must_use]
+    pub fn line_range(&self) -> Option<(u32, u32)> {
+        let lines = self
+            .lines
+            .iter()
+            .map(|(line, _)| *line)
+            .filter(|line| *line > 0);
+        Some((lines.clone().min()?, lines.max()?))
+    }
+
+    /// Whether two chunks hold the same code compiled from the same lines,
+    /// comparing nested functions the same way
+    #[must_use]
+    pub fn same_code(&self, other: &Chunk) -> bool {
+        self.code == other.code
+            && self.lines == other.lines
+            && self.constants.len() == other.constants.len()
+            && self
+                .constants
+                .iter()
+                .zip(&other.constants)
+                .all(|pair| match pair {
+                    (Value::Function(a), Value::Function(b)) => {
+                        a.arity == b.arity && a.chunk.same_code(&b.chunk)
+                    }
+                    (a, b) => values_identical(a, b) || a == b,
+                })
+    }
+
     /// Get the current bytecode offset (for jump targets)
     #[must_use]
     pub fn current_offset(&self) -> usize {
@@ -334,4 +470,22 @@ mod tests {
         let loop_offset = chunk.read_i16(3).unwrap();
         assert_eq!(loop_offset, -5);
     }
+
+    #[test]
+    fn chunk_same_code() {
+        let build = |value: i64, line: u32| {
+            let mut chunk = Chunk::new();
+            chunk.emit_constant(Value::Int(value), line);
+            chunk.write_op(OpCode::Return, line + 1);
+            chunk
+        };
+
+        assert!(build(1, 3).same_code(&build(1, 3)));
+        // A different constant, or the same code moved to other lines
+        assert!(!build(1, 3).same_code(&build(2, 3)));
+        assert!(!build(1, 3).same_code(&build(1, 5)));
+
+        assert_eq!(build(1, 3).line_range(), Some((3, 4)));
+        assert_eq!(Chunk::new().line_range(), None);
+    }
 }
@@ -1,19 +1,25 @@
 //! Bytecode compiler - transforms AST into bytecode
 
+use std::collections::HashMap;
 use std::rc::Rc;
 
 use crate::ast::{
-    BinOp, Block, CallArg, CatchClause, CompoundOp, ElseBranch, ExecutionMode,
-    ExecutionModeOverride, Expr, ExprKind, FieldInit, Function, Ident, Item, ItemKind, Literal,
-    MatchArm, Module, Param, Pattern, PatternKind, Stmt, StmtKind, StringPart, TopLevelItem,
-    TopLevelLet, UnaryOp,
+    BinOp, Block, CallArg, CatchClause, CompoundOp, ElseBranch, EnumDef, EnumVariantData,
+    ExecutionMode, ExecutionModeOverride, Expr, ExprKind, FieldInit, Function, Ident, ImplDef,
+    Item, ItemKind, Literal, MatchArm, Module, Param, Pattern, PatternKind, Stmt, StmtKind,
+    StringPart, StructDef, StructField, TopLevelItem, TopLevelLet, TypeAnnotation, TypeKind,
+    UnaryOp,
 };
 use crate::lexer::Span;
 
-use super::chunk::Chunk;
+use super::chunk::{Chunk, LocalVariable};
 use super::error::{CompileError, CompileErrorKind};
+use super::fold;
 use super::opcode::OpCode;
-use super::value::{Function as BytecodeFunction, Value};
+use super::optimize;
+use super::value::{
+    EnumTypeInfo, FieldType, Function as BytecodeFunction, StructTypeInfo, Value, VariantFields,
+};
 
 /// A local variable in scope
 #[derive(Debug, Clone)]
@@ -29,6 +35,9 @@ struct Local {
 
     /// Whether the variable is captured by a closure
     is_captured: bool,
+
+    /// Offset at which the variable was initialized (for debug info)
+    start: Option<usize>,
 }
 
 /// An upvalue (captured variable from outer scope)
@@ -111,15 +120,16 @@ impl CompilerState {
             is_async,
         };
 
-        // Reserve slot 0 for 'this' in methods or empty slot in functions
+        // Reserve slot 0 for the receiver (`self`) in methods or empty slot in functions
         let first_local = if function_type == FunctionType::Method
             || function_type == FunctionType::Initializer
         {
             Local {
-                name: "this".to_string(),
+                name: "self".to_string(),
                 depth: 0,
                 initialized: true,
                 is_captured: false,
+                start: Some(0),
             }
         } else {
             Local {
@@ -127,6 +137,7 @@ impl CompilerState {
                 depth: 0,
                 initialized: true,
                 is_captured: false,
+                start: None,
             }
         };
         state.locals.push(first_local);
@@ -159,6 +170,20 @@ pub struct Compiler {
 
     /// CLI override for execution mode (overrides all directives)
     mode_override: Option<ExecutionModeOverride>,
+
+    /// Default method bodies of the module's interfaces, compiled into each
+    /// impl that doesn't override them
+    interface_defaults: HashMap<String, Vec<Function>>,
+
+    /// Values of `const` declarations, inlined wherever the name isn't
+    /// shadowed by a local
+    constants: HashMap<String, Value>,
+
+    /// Run the peephole optimizer over each compiled function
+    optimize: bool,
+
+    /// Top-level functions and methods compiled so far, by global name
+    definitions: Vec<(String, Rc<BytecodeFunction>)>,
 }
 
 impl Compiler {
@@ -171,6 +196,10 @@ impl Compiler {
             source_name: None,
             module_mode: None,
             mode_override: None,
+            interface_defaults: HashMap::new(),
+            constants: HashMap::new(),
+            optimize: true,
+            definitions: Vec::new(),
         }
     }
 
@@ -193,6 +222,42 @@ impl Compiler {
         self
     }
 
+    /// Enable or disable the peephole optimizer (enabled by default)
+    ///
+    /// Disabling it keeps the bytecode exactly as emitted, which is useful for
+    /// comparing the two with `--dump-bytecode`.
+    #[must_use]
+    pub fn with_optimization(mut self, optimize: bool) -> Self {
+        self.optimize = optimize;
+        self
+    }
+
+    /// Make `state` the function being compiled, enclosed by the current one
+    fn enter_function(&mut self, mut state: CompilerState) {
+        state.function.chunk.source_name = self.source_name.clone();
+        let enclosing = std::mem::replace(&mut self.current, state);
+        self.current.enclosing = Some(Box::new(enclosing));
+    }
+
+    /// Optimize a completed function's chunk unless optimization is disabled
+    fn finish_function(&self, function: &mut BytecodeFunction) {
+        if self.optimize {
+            optimize::optimize_chunk(&mut function.chunk);
+        }
+    }
+
+    /// The compiled script, or the errors collected while compiling it
+    fn finish(self) -> Result<Rc<BytecodeFunction>, Vec<CompileError>> {
+        if !self.errors.is_empty() {
+            return Err(self.errors);
+        }
+        let mut function = self.current.function;
+        if self.optimize {
+            optimize::optimize_chunk(&mut function.chunk);
+        }
+        Ok(Rc::new(function))
+    }
+
     /// Resolve the execution mode for a function, considering overrides and defaults
     fn resolve_function_mode(&self, func: &Function) -> ExecutionMode {
         // CLI override takes precedence over everything
@@ -212,43 +277,87 @@ impl Compiler {
         mut self,
         module: &Module,
     ) -> Result<Rc<BytecodeFunction>, Vec<CompileError>> {
+        self.compile_definitions_of(module);
+
+        // Second pass: compile top-level lets, statements, and non-function items in order
+        for tl_item in &module.top_level {
+            self.compile_top_level_item(tl_item);
+        }
+
+        // Emit implicit return
+        self.emit_return(module.span);
+
+        self.finish()
+    }
+
+    /// Compile only a module's top-level functions and impl methods, keyed
+    /// by the global each is bound to (`name`, or `Type.method`)
+    ///
+    /// Used to hot reload an edited file into a running VM without running
+    /// its top-level statements again.
+    pub fn compile_definitions(
+        mut self,
+        module: &Module,
+    ) -> Result<Vec<(String, Rc<BytecodeFunction>)>, Vec<CompileError>> {
+        self.compile_definitions_of(module);
+        if !self.errors.is_empty() {
+            return Err(self.errors);
+        }
+        Ok(self.definitions)
+    }
+
+    /// Compile a module's constants and hoisted definitions
+    fn compile_definitions_of(&mut self, module: &Module) {
         // Capture module-level execution mode from inner attributes (e.g., #![compile])
         self.module_mode = module.execution_mode();
 
-        // First pass: compile all function definitions (hoisted)
-        // This ensures functions are available before they're called
+        // Collect interface default methods so impls can appear before their interface
+        for item in module.items() {
+            if let ItemKind::Interface(def) = &item.kind {
+                self.interface_defaults
+                    .insert(def.name.name.clone(), def.default_methods());
+            }
+        }
+
+        // Constants come first so every function body can inline them
         for tl_item in &module.top_level {
-            if let TopLevelItem::Item(item) = tl_item {
-                if matches!(item.kind, ItemKind::Function(_)) {
-                    self.compile_item(item);
+            if let TopLevelItem::Let(let_decl) = tl_item {
+                if let_decl.is_const {
+                    self.compile_top_level_let(let_decl);
                 }
             }
         }
 
-        // Second pass: compile top-level lets, statements, and non-function items in order
+        // First pass: compile all function, struct, enum, and impl definitions (hoisted)
+        // This ensures functions, type descriptors, and methods are available before they're used
         for tl_item in &module.top_level {
-            self.compile_top_level_item(tl_item);
+            if let TopLevelItem::Item(item) = tl_item {
+                if Self::is_hoisted(item) {
+                    self.compile_item(item);
+                }
+            }
         }
+    }
 
-        // Emit implicit return
-        self.emit_return(module.span);
-
-        if self.errors.is_empty() {
-            Ok(Rc::new(self.current.function))
-        } else {
-            Err(self.errors)
-        }
+    /// Whether an item is compiled in the first (hoisting) pass
+    fn is_hoisted(item: &Item) -> bool {
+        matches!(
+            item.kind,
+            ItemKind::Function(_) | ItemKind::Struct(_) | ItemKind::Enum(_) | ItemKind::Impl(_)
+        )
     }
 
     /// Compile a top-level item
     fn compile_top_level_item(&mut self, tl_item: &TopLevelItem) {
         match tl_item {
             TopLevelItem::Item(item) => {
-                // Functions are compiled in the first pass (hoisted), skip them here
-                if !matches!(item.kind, ItemKind::Function(_)) {
+                // Definitions are compiled in the first pass (hoisted), skip them here
+                if !Self::is_hoisted(item) {
                     self.compile_item(item);
                 }
             }
+            // Constants are compiled before everything else
+            TopLevelItem::Let(let_decl) if let_decl.is_const => {}
             TopLevelItem::Let(let_decl) => self.compile_top_level_let(let_decl),
             TopLevelItem::Statement(stmt) => {
                 // The statement() method handles popping for expression statements
@@ -261,6 +370,20 @@ impl Compiler {
     fn compile_top_level_let(&mut self, let_decl: &TopLevelLet) {
         let line = self.line_from_span(let_decl.span);
 
+        // A const is evaluated here; it stays a global for the REPL and
+        // imports, but uses in this module are replaced by its value
+        if let (true, Some(name)) = (let_decl.is_const, let_decl.name()) {
+            match self.constant_value(&let_decl.value) {
+                Some(value) => {
+                    self.constants.insert(name.name.clone(), value);
+                }
+                None => self.error(
+                    CompileErrorKind::NotConstant(name.name.clone()),
+                    let_decl.value.span,
+                ),
+            }
+        }
+
         // Compile the value expression
         self.expression(&let_decl.value);
 
@@ -294,11 +417,7 @@ impl Compiler {
         let line = self.line_from_span(expr.span);
         self.emit_op(OpCode::Return, line);
 
-        if self.errors.is_empty() {
-            Ok(Rc::new(self.current.function))
-        } else {
-            Err(self.errors)
-        }
+        self.finish()
     }
 
     /// Compile a test function (used by the test runner)
@@ -324,11 +443,7 @@ impl Compiler {
         // Return the result
         self.emit_op(OpCode::Return, line);
 
-        if self.errors.is_empty() {
-            Ok(Rc::new(self.current.function))
-        } else {
-            Err(self.errors)
-        }
+        self.finish()
     }
 
     /// Compile REPL input (expression, statement(s), or function definition)
@@ -377,11 +492,7 @@ impl Compiler {
             }
         }
 
-        if self.errors.is_empty() {
-            Ok(Rc::new(self.current.function))
-        } else {
-            Err(self.errors)
-        }
+        self.finish()
     }
 
     // ===== Item Compilation =====
@@ -389,22 +500,13 @@ impl Compiler {
     fn compile_item(&mut self, item: &Item) {
         match &item.kind {
             ItemKind::Function(func) => self.compile_function_def(func),
-            ItemKind::Struct(_def) => {
-                // Structs are handled at runtime through type info
-                // The compiler just needs to know the field names/order
-                // This will be more fully implemented when we add the VM
-            }
-            ItemKind::Enum(_def) => {
-                // Similarly, enums are mostly handled at runtime
-            }
+            ItemKind::Struct(def) => self.compile_struct_def(def),
+            ItemKind::Enum(def) => self.compile_enum_def(def),
             ItemKind::Interface(_def) => {
                 // Interfaces are checked at compile time by the type checker
                 // No bytecode generation needed
             }
-            ItemKind::Impl(_def) => {
-                // Impl blocks attach methods to types
-                // This will be handled when we add method dispatch
-            }
+            ItemKind::Impl(def) => self.compile_impl_def(def),
             ItemKind::Import(_import) => {
                 // Imports are resolved by the module system
                 // Will be implemented with the module loader
@@ -423,7 +525,10 @@ impl Compiler {
         }
 
         // Compile the function body
-        self.function(func, FunctionType::Function);
+        let function = self.function(func, FunctionType::Function);
+        if self.current.enclosing.is_none() && self.current.scope_depth == 0 {
+            self.definitions.push((name.clone(), function));
+        }
 
         // Define the global (if at top level)
         self.define_variable(&func.name, line);
@@ -432,20 +537,180 @@ impl Compiler {
         let _ = self.current.chunk_mut().add_constant(Value::string(name));
     }
 
-    fn function(&mut self, func: &Function, function_type: FunctionType) {
+    /// Compile an impl block's methods
+    ///
+    /// Each method becomes a global named `Type.method` (not a valid identifier,
+    /// so it can't clash with user names) that the VM looks up when a method is
+    /// invoked on a `Type` value or on the type itself. The receiver occupies
+    /// the method's slot 0, bound to `self`.
+    fn compile_impl_def(&mut self, def: &ImplDef) {
+        let TypeKind::Named { name: target, .. } = &def.target.kind else {
+            self.error(
+                CompileErrorKind::Unsupported("impl for a non-named type".to_string()),
+                def.target.span,
+            );
+            return;
+        };
+
+        for method in &def.methods {
+            self.compile_method(&target.name, method);
+        }
+
+        // Interface default methods the impl doesn't override
+        if let Some(TypeKind::Named {
+            name: interface, ..
+        }) = def.interface.as_ref().map(|i| &i.kind)
+        {
+            let defaults = self
+                .interface_defaults
+                .get(&interface.name)
+                .cloned()
+                .unwrap_or_default();
+            for method in defaults
+                .iter()
+                .filter(|d| !def.methods.iter().any(|m| m.name.name == d.name.name))
+            {
+                self.compile_method(&target.name, method);
+            }
+        }
+    }
+
+    fn compile_method(&mut self, type_name: &str, method: &Function) {
+        let line = self.line_from_span(method.span);
+        let function = self.function(method, FunctionType::Method);
+        let name = format!("{type_name}.{}", method.name.name);
+        self.definitions.push((name.clone(), function));
+        if let Some(idx) = self.identifier_constant(&name, method.name.span) {
+            self.emit_op_u16(OpCode::DefineGlobal, idx, line);
+        }
+    }
+
+    /// Bind the struct's name to a runtime type descriptor (used by natives
+    /// such as `Json.decode_as` that need field names and declared types)
+    fn compile_struct_def(&mut self, def: &StructDef) {
+        let type_params: Vec<&str> = def
+            .type_params
+            .iter()
+            .map(|p| p.name.name.as_str())
+            .collect();
+        let info = StructTypeInfo {
+            name: def.name.name.clone(),
+            fields: Self::field_types(&def.fields, &type_params),
+            derives: def.derives(),
+        };
+        self.define_type_descriptor(&def.name, Value::StructType(Rc::new(info)), def.span);
+    }
+
+    /// Bind the enum's name to a runtime type descriptor (used by derived
+    /// methods that need variant names and payload shapes)
+    fn compile_enum_def(&mut self, def: &EnumDef) {
+        let type_params: Vec<&str> = def
+            .type_params
+            .iter()
+            .map(|p| p.name.name.as_str())
+            .collect();
+        let variants = def
+            .variants
+            .iter()
+            .map(|variant| {
+                let fields = match &variant.data {
+                    None => VariantFields::Unit,
+                    // Multi-element payloads are stored as a single unchecked value
+                    Some(EnumVariantData::Tuple(types)) => match types.as_slice() {
+                        [ty] => VariantFields::Tuple(Self::field_type(ty, &type_params)),
+                        _ => VariantFields::Tuple(FieldType::Any),
+                    },
+                    Some(EnumVariantData::Struct(fields)) => {
+                        VariantFields::Struct(Self::field_types(fields, &type_params))
+                    }
+                };
+                (variant.name.name.clone(), fields)
+            })
+            .collect();
+        let info = EnumTypeInfo {
+            name: def.name.name.clone(),
+            variants,
+            derives: def.derives(),
+        };
+        self.define_type_descriptor(&def.name, Value::EnumType(Rc::new(info)), def.span);
+    }
+
+    /// Bind `name` to a type descriptor constant
+    fn define_type_descriptor(&mut self, name: &Ident, descriptor: Value, span: Span) {
+        let line = self.line_from_span(span);
+        self.declare_variable(name);
+        if let Some(idx) = self.current.chunk_mut().add_constant(descriptor) {
+            self.emit_op_u16(OpCode::Const, idx, line);
+        } else {
+            self.error(CompileErrorKind::TooManyConstants, span);
+        }
+        self.define_variable(name, line);
+    }
+
+    /// Lower declared fields to `(name, type)` pairs in declaration order
+    fn field_types(fields: &[StructField], type_params: &[&str]) -> Vec<(String, FieldType)> {
+        fields
+            .iter()
+            .map(|field| {
+                (
+                    field.name.name.clone(),
+                    Self::field_type(&field.ty, type_params),
+                )
+            })
+            .collect()
+    }
+
+    /// Lower a field's type annotation to the subset checked at runtime
+    fn field_type(ty: &TypeAnnotation, type_params: &[&str]) -> FieldType {
+        match &ty.kind {
+            TypeKind::Named { name, args } => match name.name.as_str() {
+                "Int" => FieldType::Int,
+                "Float" => FieldType::Float,
+                "String" => FieldType::String,
+                "Bool" => FieldType::Bool,
+                "List" => FieldType::List(Box::new(
+                    args.first()
+                        .map_or(FieldType::Any, |t| Self::field_type(t, type_params)),
+                )),
+                "Map" => FieldType::Map(Box::new(
+                    args.get(1)
+                        .map_or(FieldType::Any, |t| Self::field_type(t, type_params)),
+                )),
+                other if type_params.contains(&other) => FieldType::Any,
+                other => FieldType::Named(other.to_string()),
+            },
+            TypeKind::Nullable(inner) => {
+                FieldType::Nullable(Box::new(Self::field_type(inner, type_params)))
+            }
+            TypeKind::List(inner) => {
+                FieldType::List(Box::new(Self::field_type(inner, type_params)))
+            }
+            TypeKind::Function { .. }
+            | TypeKind::Tuple(_)
+            | TypeKind::Unit
+            | TypeKind::Never
+            | TypeKind::Inferred => FieldType::Any,
+        }
+    }
+
+    fn function(&mut self, func: &Function, function_type: FunctionType) -> Rc<BytecodeFunction> {
         let name = func.name.name.clone();
         let _line = self.line_from_span(func.span);
 
         // Start a new compiler state for the function
-        let enclosing = std::mem::replace(
-            &mut self.current,
-            CompilerState::new(function_type, name, func.is_async),
-        );
-        self.current.enclosing = Some(Box::new(enclosing));
+        self.enter_function(CompilerState::new(function_type, name, func.is_async));
         self.begin_scope();
 
-        // Compile parameters
-        for param in &func.params {
+        // Compile parameters (a method's `self` is the receiver in slot 0)
+        let params = match func.params.split_first() {
+            Some((first, rest))
+                if function_type == FunctionType::Method && first.name.name == "self" =>
+            {
+                rest
+            }
+            _ => func.params.as_slice(),
+        };
+        for param in params {
             if self.current.function.arity == 255 {
                 self.error(CompileErrorKind::TooManyParameters, param.span);
                 break;
@@ -470,8 +735,11 @@ impl Compiler {
             self.emit_return(func.span);
         }
 
-        // End function scope
+        // End function scope; a method's receiver stays live to the end
         self.end_scope(line);
+        if let Some(receiver) = self.current.locals.first().cloned() {
+            self.record_local_variable(&receiver, 0);
+        }
 
         // Get the completed function - need to take enclosing first to avoid borrow issue
         let enclosing = self.current.enclosing.take().unwrap();
@@ -481,11 +749,13 @@ impl Compiler {
         let upvalue_count = function_state.upvalues.len();
         let mut completed_function = function_state.function;
         completed_function.upvalue_count = upvalue_count as u16;
+        self.finish_function(&mut completed_function);
 
         // Set execution mode based on function attributes and module mode
         completed_function.execution_mode = self.resolve_function_mode(func);
 
-        let func_value = Value::Function(Rc::new(completed_function));
+        let completed_function = Rc::new(completed_function);
+        let func_value = Value::Function(completed_function.clone());
         if let Some(const_idx) = self.current.chunk_mut().add_constant(func_value) {
             self.emit_op_u16(OpCode::Closure, const_idx, line);
 
@@ -497,6 +767,7 @@ impl Compiler {
         } else {
             self.error(CompileErrorKind::TooManyConstants, func.span);
         }
+        completed_function
     }
 
     // ===== Statement Compilation =====
@@ -677,6 +948,7 @@ impl Compiler {
             depth: self.current.scope_depth,
             initialized: true,
             is_captured: false,
+            start: None,
         });
 
         // Loop start
@@ -739,7 +1011,9 @@ impl Compiler {
         // When IterNext jumps (iterator exhausted), no value was pushed for the loop var,
         // so end_scope should NOT try to pop it.
         if self.current.locals.len() > iter_slot + 1 {
-            self.current.locals.pop();
+            if let Some(local) = self.current.locals.pop() {
+                self.record_local_variable(&local, iter_slot + 1);
+            }
         }
 
         self.end_scope(line);
@@ -896,6 +1170,21 @@ impl Compiler {
         // Compile finally block if present
         if let Some(finally_block) = finally {
             self.block(finally_block);
+
+            // A second copy, which the VM runs when a shutdown signal unwinds
+            // the frame while the try statement is running. It ends by
+            // returning, which the VM takes as the end of the block.
+            let skip = self.emit_jump(OpCode::Jump, line);
+            let finally_target = self.current.chunk().current_offset();
+            let finally_offset =
+                (finally_target as isize - (finally_offset_pos as isize + 2)) as i16;
+            self.current
+                .chunk_mut()
+                .patch_i16(finally_offset_pos, finally_offset);
+            self.block(finally_block);
+            self.emit_op(OpCode::Null, line);
+            self.emit_op(OpCode::Return, line);
+            self.patch_jump(skip);
         }
     }
 
@@ -904,6 +1193,21 @@ impl Compiler {
     fn expression(&mut self, expr: &Expr) {
         let line = self.line_from_span(expr.span);
 
+        // Operators on constants are evaluated once, at compile time
+        if matches!(
+            expr.kind,
+            ExprKind::Ident(_)
+                | ExprKind::Binary { .. }
+                | ExprKind::Unary { .. }
+                | ExprKind::Paren(_)
+                | ExprKind::If { .. }
+        ) {
+            if let Some(value) = self.constant_value(expr) {
+                self.emit_value(value, line, expr.span);
+                return;
+            }
+        }
+
         match &expr.kind {
             ExprKind::Literal(lit) => self.literal(lit, line, expr.span),
 
@@ -1340,6 +1644,16 @@ impl Compiler {
         else_branch: Option<&ElseBranch>,
         line: u32,
     ) {
+        // A constant condition compiles only the branch it selects
+        if let Some(cond) = self.constant_value(cond) {
+            if cond.is_truthy() {
+                self.block_expression(then_branch, line);
+            } else {
+                self.else_branch(else_branch, line);
+            }
+            return;
+        }
+
         // Condition
         self.expression(cond);
         let else_jump = self.emit_jump(OpCode::JumpIfFalse, line);
@@ -1353,7 +1667,12 @@ impl Compiler {
         // Else branch
         self.patch_jump(else_jump);
         // Note: JumpIfFalse already popped the condition when jumping here
+        self.else_branch(else_branch, line);
 
+        self.patch_jump(end_jump);
+    }
+
+    fn else_branch(&mut self, else_branch: Option<&ElseBranch>, line: u32) {
         match else_branch {
             Some(ElseBranch::Block(block)) => {
                 self.block_expression(block, line);
@@ -1365,8 +1684,82 @@ impl Compiler {
                 self.emit_op(OpCode::Null, line);
             }
         }
+    }
 
-        self.patch_jump(end_jump);
+    // ===== Constant Folding =====
+
+    /// The value of `expr` if it can be computed at compile time: literals,
+    /// constants, operators on them, and `if` with a constant condition
+    fn constant_value(&self, expr: &Expr) -> Option<Value> {
+        match &expr.kind {
+            ExprKind::Literal(Literal::Int(n)) => Some(Value::Int(*n)),
+            ExprKind::Literal(Literal::Float(n)) => Some(Value::Float(*n)),
+            ExprKind::Literal(Literal::String(s)) => Some(Value::string(s.clone())),
+            ExprKind::Literal(Literal::Bool(b)) => Some(Value::Bool(*b)),
+            ExprKind::Literal(Literal::Null) => Some(Value::Null),
+            ExprKind::Ident(name) => {
+                let value = self.constants.get(&name.name)?;
+                (!self.is_local_name(&name.name)).then(|| value.clone())
+            }
+            ExprKind::Paren(inner) => self.constant_value(inner),
+            ExprKind::Unary { op, expr: inner } => fold::unary(*op, &self.constant_value(inner)?),
+            ExprKind::Binary { left, op, right } => fold::binary(
+                *op,
+                &self.constant_value(left)?,
+                &self.constant_value(right)?,
+            ),
+            ExprKind::If {
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                let block_value = |block: &Block| {
+                    if block.stmts.is_empty() {
+                        self.constant_value(block.expr.as_deref()?)
+                    } else {
+                        None
+                    }
+                };
+                if self.constant_value(cond)?.is_truthy() {
+                    block_value(then_branch)
+                } else {
+                    match else_branch {
+                        Some(ElseBranch::Block(block)) => block_value(block),
+                        Some(ElseBranch::ElseIf(else_if)) => self.constant_value(else_if),
+                        None => Some(Value::Null),
+                    }
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether `name` is a local of this function or one it is nested in
+    fn is_local_name(&self, name: &str) -> bool {
+        let mut state = Some(&self.current);
+        while let Some(current) = state {
+            if current.locals.iter().any(|local| local.name == name) {
+                return true;
+            }
+            state = current.enclosing.as_deref();
+        }
+        false
+    }
+
+    /// Emit a folded constant
+    fn emit_value(&mut self, value: Value, line: u32, span: Span) {
+        match value {
+            Value::Null => self.emit_op(OpCode::Null, line),
+            Value::Bool(true) => self.emit_op(OpCode::True, line),
+            Value::Bool(false) => self.emit_op(OpCode::False, line),
+            value => {
+                if let Some(idx) = self.current.chunk_mut().add_constant(value) {
+                    self.emit_op_u16(OpCode::Const, idx, line);
+                } else {
+                    self.error(CompileErrorKind::TooManyConstants, span);
+                }
+            }
+        }
     }
 
     fn match_expression(&mut self, target: &Expr, arms: &[MatchArm], line: u32, span: Span) {
@@ -1466,11 +1859,7 @@ impl Compiler {
         let name = format!("<lambda@{}>", line);
 
         // Start a new compiler state (lambdas are synchronous)
-        let enclosing = std::mem::replace(
-            &mut self.current,
-            CompilerState::new(FunctionType::Function, name, false),
-        );
-        self.current.enclosing = Some(Box::new(enclosing));
+        self.enter_function(CompilerState::new(FunctionType::Function, name, false));
         self.begin_scope();
 
         // Compile parameters
@@ -1499,6 +1888,7 @@ impl Compiler {
         let upvalue_count = function.upvalues.len();
         let mut completed_function = function.function;
         completed_function.upvalue_count = upvalue_count as u16;
+        self.finish_function(&mut completed_function);
 
         let func_value = Value::Function(Rc::new(completed_function));
         if let Some(const_idx) = self.current.chunk_mut().add_constant(func_value) {
@@ -1622,7 +2012,7 @@ impl Compiler {
 
     fn enum_variant(
         &mut self,
-        _enum_name: Option<&Ident>,
+        enum_name: Option<&Ident>,
         variant: &Ident,
         data: Option<&Expr>,
         line: u32,
@@ -1635,8 +2025,12 @@ impl Compiler {
             self.emit_op(OpCode::Null, line);
         }
 
-        // Create variant
-        if let Some(idx) = self.identifier_constant(&variant.name, span) {
+        // Create variant, qualified as "Enum.Variant" when the enum is named
+        let name = enum_name.map_or_else(
+            || variant.name.clone(),
+            |enum_name| format!("{}.{}", enum_name.name, variant.name),
+        );
+        if let Some(idx) = self.identifier_constant(&name, span) {
             self.emit_op_u16(OpCode::NewEnumVariant, idx, line);
         }
     }
@@ -1669,6 +2063,7 @@ impl Compiler {
             depth: self.current.scope_depth,
             initialized: false,
             is_captured: false,
+            start: None,
         });
     }
 
@@ -1689,8 +2084,10 @@ impl Compiler {
         if self.current.scope_depth == 0 {
             return;
         }
+        let offset = self.current.chunk().current_offset();
         if let Some(local) = self.current.locals.last_mut() {
             local.initialized = true;
+            local.start = Some(offset);
         }
     }
 
@@ -1755,7 +2152,7 @@ impl Compiler {
                 let idx = i;
                 // We need to mark the local as captured, but we have a mutable borrow
                 // We'll handle this by returning early and doing the modification
-                return Some(self.add_upvalue(idx as u8, true));
+                return Some(self.add_upvalue(idx as u8, true, name));
             }
         }
 
@@ -1766,7 +2163,7 @@ impl Compiler {
         None
     }
 
-    fn add_upvalue(&mut self, index: u8, is_local: bool) -> u8 {
+    fn add_upvalue(&mut self, index: u8, is_local: bool, name: &str) -> u8 {
         // Check if we already have this upvalue
         for (i, upvalue) in self.current.upvalues.iter().enumerate() {
             if upvalue.index == index && upvalue.is_local == is_local {
@@ -1780,6 +2177,7 @@ impl Compiler {
         }
 
         self.current.upvalues.push(Upvalue { index, is_local });
+        self.current.chunk_mut().add_upvalue_name(name);
         (self.current.upvalues.len() - 1) as u8
     }
 
@@ -1797,6 +2195,7 @@ impl Compiler {
             && self.current.locals.last().unwrap().depth > self.current.scope_depth
         {
             let local = self.current.locals.pop().unwrap();
+            self.record_local_variable(&local, self.current.locals.len());
             if local.is_captured {
                 self.emit_op(OpCode::CloseUpvalue, line);
             } else {
@@ -1805,6 +2204,23 @@ impl Compiler {
         }
     }
 
+    /// Record a local going out of scope in the chunk's debug info
+    fn record_local_variable(&mut self, local: &Local, slot: usize) {
+        let (Some(start), Ok(slot)) = (local.start, u16::try_from(slot)) else {
+            return;
+        };
+        if local.name.is_empty() {
+            return;
+        }
+        let end = self.current.chunk().current_offset();
+        self.current.chunk_mut().add_local_variable(LocalVariable {
+            name: local.name.clone(),
+            slot,
+            start,
+            end,
+        });
+    }
+
     fn close_upvalues_to_depth(&mut self, depth: u32, line: u32) {
         // Collect what we need to emit first to avoid borrow issues
         let ops: Vec<OpCode> = self
@@ -1986,11 +2402,7 @@ impl Compiler {
         let row_param_name = "$row";
 
         // Start a new compiler state (lambdas are synchronous)
-        let enclosing = std::mem::replace(
-            &mut self.current,
-            CompilerState::new(FunctionType::Function, name, false),
-        );
-        self.current.enclosing = Some(Box::new(enclosing));
+        self.enter_function(CompilerState::new(FunctionType::Function, name, false));
         self.begin_scope();
 
         // Declare the $row parameter
@@ -2015,6 +2427,7 @@ impl Compiler {
         let upvalue_count = function.upvalues.len();
         let mut completed_function = function.function;
         completed_function.upvalue_count = upvalue_count as u16;
+        self.finish_function(&mut completed_function);
 
         let func_value = Value::Function(Rc::new(completed_function));
         if let Some(const_idx) = self.current.chunk_mut().add_constant(func_value) {
@@ -2175,6 +2588,7 @@ impl Default for Compiler {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ast::Derive;
     use crate::parser::Parser;
 
     fn compile_module(source: &str) -> Result<Rc<BytecodeFunction>, Vec<CompileError>> {
@@ -2229,6 +2643,188 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn compile_struct_emits_type_descriptor() {
+        let script = compile_module(
+            "struct Pair<T> { name: String, tags: [String], extra: Map<String, Int>?, value: T, next: Pair? }",
+        )
+        .unwrap();
+        let info = script
+            .chunk
+            .constants()
+            .iter()
+            .find_map(|c| match c {
+                Value::StructType(info) => Some(info.clone()),
+                _ => None,
+            })
+            .expect("struct type constant");
+
+        assert_eq!(info.name, "Pair");
+        assert_eq!(
+            info.fields,
+            vec![
+                ("name".to_string(), FieldType::String),
+                (
+                    "tags".to_string(),
+                    FieldType::List(Box::new(FieldType::String))
+                ),
+                (
+                    "extra".to_string(),
+                    FieldType::Nullable(Box::new(FieldType::Map(Box::new(FieldType::Int))))
+                ),
+                ("value".to_string(), FieldType::Any),
+                (
+                    "next".to_string(),
+                    FieldType::Nullable(Box::new(FieldType::Named("Pair".to_string())))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn compile_enum_emits_type_descriptor_with_derives() {
+        let script = compile_module(
+            "#[derive(Eq, Json)]\nenum Shape { Dot, Circle(Float), Rect { w: Int, h: Int } }\nlet s = Shape::Dot",
+        )
+        .unwrap();
+        let constants = script.chunk.constants();
+        let info = constants
+            .iter()
+            .find_map(|c| match c {
+                Value::EnumType(info) => Some(info.clone()),
+                _ => None,
+            })
+            .expect("enum type constant");
+
+        assert_eq!(info.name, "Shape");
+        assert_eq!(info.derives, vec![Derive::Eq, Derive::Json]);
+        assert_eq!(
+            info.variants,
+            vec![
+                ("Dot".to_string(), VariantFields::Unit),
+                ("Circle".to_string(), VariantFields::Tuple(FieldType::Float)),
+                (
+                    "Rect".to_string(),
+                    VariantFields::Struct(vec![
+                        ("w".to_string(), FieldType::Int),
+                        ("h".to_string(), FieldType::Int),
+                    ])
+                ),
+            ]
+        );
+        // Variants carry their enum name so derived methods can find the descriptor
+        assert!(constants
+            .iter()
+            .any(|c| matches!(c, Value::String(s) if s.as_str() == "Shape.Dot")));
+    }
+
+    #[test]
+    fn compile_impl_defines_qualified_methods() {
+        let script = compile_module(
+            "struct Point { x: Int, y: Int }\nimpl Point {\n  fx origin() { Point { x: 0, y: 0 } }\n  fx shifted(self, by) { Point { x: self.x + by, y: self.y + by } }\n}",
+        )
+        .unwrap();
+        let constants = script.chunk.constants();
+        let function = |name: &str| {
+            constants.iter().find_map(|c| match c {
+                Value::Function(func) if func.name == name => Some(func.clone()),
+                _ => None,
+            })
+        };
+
+        // `self` occupies the receiver slot, so it does not count towards arity
+        assert_eq!(function("shifted").expect("shifted").arity, 1);
+        assert_eq!(function("origin").expect("origin").arity, 0);
+        for global in ["Point.origin", "Point.shifted"] {
+            assert!(constants
+                .iter()
+                .any(|c| matches!(c, Value::String(s) if s.as_str() == global)));
+        }
+    }
+
+    // ===== Constant Folding Tests =====
+
+    /// Opcodes of a chunk without closures (whose size depends on upvalues)
+    fn opcodes(chunk: &Chunk) -> Vec<OpCode> {
+        let mut ops = Vec::new();
+        let mut offset = 0;
+        while let Some(byte) = chunk.read_byte(offset) {
+            let op = OpCode::try_from(byte).expect("valid opcode");
+            ops.push(op);
+            offset += op.size();
+        }
+        ops
+    }
+
+    fn function_named(script: &BytecodeFunction, name: &str) -> Rc<BytecodeFunction> {
+        script
+            .chunk
+            .constants()
+            .iter()
+            .find_map(|c| match c {
+                Value::Function(func) if func.name == name => Some(func.clone()),
+                _ => None,
+            })
+            .expect("function constant")
+    }
+
+    #[test]
+    fn fold_arithmetic_and_string_concat() {
+        let func = compile_expr("(1 + 2) * 3 - 4 / 2").unwrap();
+        assert_eq!(opcodes(&func.chunk), vec![OpCode::Const, OpCode::Return]);
+        assert_eq!(func.chunk.constants(), &[Value::Int(7)]);
+
+        let func = compile_expr(r#""total: " + 2.5 * 2"#).unwrap();
+        assert_eq!(func.chunk.constants(), &[Value::string("total: 5")]);
+
+        let func = compile_expr("!(3 > 2 && 1 == 1)").unwrap();
+        assert_eq!(opcodes(&func.chunk), vec![OpCode::False, OpCode::Return]);
+    }
+
+    #[test]
+    fn fold_leaves_runtime_errors_and_variables() {
+        // Division by zero must still fail when the program runs
+        let func = compile_expr("1 / 0").unwrap();
+        assert!(opcodes(&func.chunk).contains(&OpCode::Div));
+
+        let script = compile_module("fx f(x) { x + 1 * 2 }").unwrap();
+        let ops = opcodes(&function_named(&script, "f").chunk);
+        assert!(ops.contains(&OpCode::Add));
+        assert!(!ops.contains(&OpCode::Mul));
+    }
+
+    #[test]
+    fn fold_constant_if_keeps_only_taken_branch() {
+        let script = compile_module("fx f(x) { if 2 > 1 { x } else { x * 2 } }").unwrap();
+        let ops = opcodes(&function_named(&script, "f").chunk);
+        assert!(!ops.contains(&OpCode::JumpIfFalse));
+        assert!(!ops.contains(&OpCode::Mul));
+    }
+
+    #[test]
+    fn const_declarations_are_inlined() {
+        let script = compile_module(
+            "fx area(r) { PI * r * r }\nconst TAU = PI * 2.0\nconst PI = 3.5\nfx shadow(PI) { PI }",
+        );
+        // TAU refers to PI before it is declared
+        assert!(matches!(
+            script.unwrap_err()[0].kind,
+            CompileErrorKind::NotConstant(ref name) if name == "TAU"
+        ));
+
+        let script =
+            compile_module("const PI = 3.5\nconst TAU = PI * 2.0\nfx area(r) { PI * r * r }\nfx shadow(PI) { PI + TAU }")
+                .unwrap();
+        let area = function_named(&script, "area");
+        assert!(!opcodes(&area.chunk).contains(&OpCode::LoadGlobal));
+        assert!(area.chunk.constants().contains(&Value::Float(3.5)));
+
+        // A parameter named like the constant shadows it
+        let shadow = function_named(&script, "shadow");
+        assert!(opcodes(&shadow.chunk).contains(&OpCode::LoadLocal));
+        assert!(shadow.chunk.constants().contains(&Value::Float(7.0)));
+    }
+
     // ===== Execution Mode Propagation Tests =====
 
     /// Helper to get a function's execution mode from compiled module
@@ -2350,4 +2946,50 @@ mod tests {
             Some(ExecutionMode::Compile)
         );
     }
+
+    #[test]
+    fn compile_records_debug_names() {
+        let module = Parser::parse_module(
+            "fx outer(a) {\n    let b = a + 1\n    let add = |x| x + b\n    add(2)\n}",
+        )
+        .unwrap();
+        let script = Compiler::new()
+            .with_optimization(false)
+            .compile_module(&module)
+            .unwrap();
+        let function_named = |chunk: &Chunk, prefix: &str| {
+            chunk
+                .constants()
+                .iter()
+                .find_map(|constant| match constant {
+                    Value::Function(f) if f.name.starts_with(prefix) => Some(f.clone()),
+                    _ => None,
+                })
+                .unwrap()
+        };
+
+        let outer = function_named(&script.chunk, "outer");
+        let mut locals: Vec<(&str, u16)> = outer
+            .chunk
+            .local_variables()
+            .iter()
+            .map(|local| (local.name.as_str(), local.slot))
+            .collect();
+        locals.sort_unstable();
+        assert_eq!(locals, [("a", 1), ("add", 3), ("b", 2)]);
+
+        // `b` is live from its initialization to the end of the function
+        let b = &outer.chunk.local_variables()[1];
+        assert_eq!(b.name, "b");
+        assert!(outer.chunk.locals_at(b.start).iter().any(|l| l.name == "b"));
+        assert!(!outer
+            .chunk
+            .locals_at(b.start - 1)
+            .iter()
+            .any(|l| l.name == "b"));
+
+        let lambda = function_named(&outer.chunk, "<lambda");
+        assert_eq!(lambda.chunk.upvalue_names(), ["b"]);
+        assert_eq!(lambda.chunk.local_variables()[0].name, "x");
+    }
 }
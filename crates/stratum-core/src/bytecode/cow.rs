@@ -0,0 +1,168 @@
+//! Copy-on-write storage for lists and maps
+//!
+//! Lists and maps are shared by reference: assigning one to another variable
+//! or passing it to a function hands over the same collection, and a mutation
+//! through either name is seen through both. `clone()` is how user code asks
+//! for an independent collection instead. Copying every element up front
+//! makes that O(n) even when the copy is only read, so a [`CowCell`] keeps its
+//! elements behind an `Rc` that clones share. The first mutation through a
+//! shared cell copies the elements into that cell alone; cells that are not
+//! shared are mutated in place.
+
+use std::cell::{BorrowError, BorrowMutError, Ref, RefCell, RefMut};
+use std::fmt;
+use std::rc::Rc;
+
+/// A mutable cell whose contents are shared between clones until written
+pub struct CowCell<T> {
+    inner: RefCell<Rc<T>>,
+}
+
+impl<T: Clone> CowCell<T> {
+    /// Create a cell holding `value`
+    #[must_use]
+    pub fn new(value: T) -> Self {
+        Self {
+            inner: RefCell::new(Rc::new(value)),
+        }
+    }
+
+    /// Borrow the contents
+    ///
+    /// # Panics
+    /// Panics if the cell is mutably borrowed
+    #[must_use]
+    pub fn borrow(&self) -> Ref<'_, T> {
+        Ref::map(self.inner.borrow(), |rc| &**rc)
+    }
+
+    /// Borrow the contents, or fail if the cell is mutably borrowed
+    ///
+    /// # Errors
+    /// Returns an error if the cell is mutably borrowed
+    pub fn try_borrow(&self) -> Result<Ref<'_, T>, BorrowError> {
+        self.inner.try_borrow().map(|rc| Ref::map(rc, |rc| &**rc))
+    }
+
+    /// Mutably borrow the contents, copying them first if another cell
+    /// shares them
+    ///
+    /// # Panics
+    /// Panics if the cell is already borrowed
+    #[must_use]
+    pub fn borrow_mut(&self) -> RefMut<'_, T> {
+        RefMut::map(self.inner.borrow_mut(), Rc::make_mut)
+    }
+
+    /// Mutably borrow the contents like [`borrow_mut`](Self::borrow_mut),
+    /// or fail if the cell is already borrowed
+    ///
+    /// # Errors
+    /// Returns an error if the cell is already borrowed
+    pub fn try_borrow_mut(&self) -> Result<RefMut<'_, T>, BorrowMutError> {
+        self.inner
+            .try_borrow_mut()
+            .map(|rc| RefMut::map(rc, Rc::make_mut))
+    }
+
+    /// A new cell with the same contents, in O(1). Neither cell sees
+    /// mutations made through the other.
+    ///
+    /// # Panics
+    /// Panics if the cell is mutably borrowed
+    #[must_use]
+    pub fn snapshot(&self) -> Self {
+        Self {
+            inner: RefCell::new(Rc::clone(&self.inner.borrow())),
+        }
+    }
+
+    /// Whether another cell shares these contents, so the next mutation
+    /// copies them
+    ///
+    /// # Panics
+    /// Panics if the cell is mutably borrowed
+    #[must_use]
+    pub fn is_shared(&self) -> bool {
+        Rc::strong_count(&self.inner.borrow()) > 1
+    }
+
+    /// Take the contents out of the cell, copying them if they are shared
+    #[must_use]
+    pub fn into_inner(self) -> T {
+        Rc::try_unwrap(self.inner.into_inner()).unwrap_or_else(|rc| (*rc).clone())
+    }
+}
+
+impl<T: Clone + Default> Default for CowCell<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: Clone + fmt::Debug> fmt::Debug for CowCell<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.try_borrow() {
+            Ok(value) => f.debug_struct("CowCell").field("value", &*value).finish(),
+            Err(_) => f
+                .debug_struct("CowCell")
+                .field("value", &format_args!("<borrowed>"))
+                .finish(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_shares_until_written() {
+        let original = CowCell::new(vec![1, 2, 3]);
+        let copy = original.snapshot();
+        assert!(original.is_shared());
+        assert!(std::ptr::eq(&*original.borrow(), &*copy.borrow()));
+
+        copy.borrow_mut().push(4);
+        assert_eq!(*original.borrow(), vec![1, 2, 3]);
+        assert_eq!(*copy.borrow(), vec![1, 2, 3, 4]);
+        assert!(!original.is_shared());
+        assert!(!copy.is_shared());
+    }
+
+    #[test]
+    fn test_unshared_mutation_is_in_place() {
+        let cell = CowCell::new(Vec::with_capacity(4));
+        cell.borrow_mut().push(1);
+        let before = cell.borrow().as_ptr();
+        cell.borrow_mut().push(2);
+        assert_eq!(before, cell.borrow().as_ptr());
+    }
+
+    #[test]
+    fn test_shared_handles_see_mutations() {
+        // Rc handles to one cell alias it, as lists and maps always have
+        let cell = Rc::new(CowCell::new(vec![1]));
+        let alias = Rc::clone(&cell);
+        alias.borrow_mut().push(2);
+        assert_eq!(*cell.borrow(), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_borrow_conflicts() {
+        let cell = CowCell::new(vec![1]);
+        let guard = cell.borrow_mut();
+        assert!(cell.try_borrow().is_err());
+        assert!(cell.try_borrow_mut().is_err());
+        drop(guard);
+        assert!(cell.try_borrow().is_ok());
+    }
+
+    #[test]
+    fn test_into_inner() {
+        let cell = CowCell::new(vec![1, 2]);
+        let copy = cell.snapshot();
+        assert_eq!(cell.into_inner(), vec![1, 2]);
+        assert_eq!(copy.into_inner(), vec![1, 2]);
+    }
+}
@@ -2,7 +2,7 @@
 
 use super::chunk::Chunk;
 use super::opcode::OpCode;
-use super::value::Value;
+use super::value::{Function, Value};
 use std::fmt::Write;
 
 /// Disassemble a chunk to a string
@@ -19,6 +19,18 @@ pub fn disassemble_chunk(chunk: &Chunk, name: &str) -> String {
     output
 }
 
+/// Disassemble a function followed by every function nested in its constants
+pub fn disassemble_function(function: &Function) -> String {
+    let mut output = disassemble_chunk(&function.chunk, &function.name);
+    for constant in function.chunk.constants() {
+        if let Value::Function(nested) = constant {
+            output.push('\n');
+            output.push_str(&disassemble_function(nested));
+        }
+    }
+    output
+}
+
 /// Disassemble a single instruction to stdout
 pub fn disassemble_instruction(chunk: &Chunk, offset: usize) {
     let mut output = String::new();
@@ -116,6 +128,31 @@ fn disassemble_instruction_to_string(chunk: &Chunk, offset: usize, output: &mut
             offset + 3
         }
 
+        // u16 local slot + u16 constant operand
+        OpCode::AddLocalConst => {
+            let slot = chunk.read_u16(offset + 1).unwrap_or(0);
+            let idx = chunk.read_u16(offset + 3).unwrap_or(0);
+            let constant = chunk.get_constant(idx);
+            writeln!(
+                output,
+                "{:16} {} {:4} {}",
+                opcode.name(),
+                slot,
+                idx,
+                format_constant(constant)
+            )
+            .unwrap();
+            offset + 5
+        }
+
+        // u16 + u16 local slot operands
+        OpCode::LoadLocalPair => {
+            let first = chunk.read_u16(offset + 1).unwrap_or(0);
+            let second = chunk.read_u16(offset + 3).unwrap_or(0);
+            writeln!(output, "{:16} {} {}", opcode.name(), first, second).unwrap();
+            offset + 5
+        }
+
         // u16 global name operand
         OpCode::LoadGlobal | OpCode::StoreGlobal | OpCode::DefineGlobal => {
             let idx = chunk.read_u16(offset + 1).unwrap_or(0);
@@ -145,6 +182,26 @@ fn disassemble_instruction_to_string(chunk: &Chunk, offset: usize, output: &mut
             offset + 3
         }
 
+        // u8 comparison + i16 jump offset
+        OpCode::CompareJumpIfFalse => {
+            let comparison = chunk
+                .read_byte(offset + 1)
+                .and_then(|byte| OpCode::try_from(byte).ok())
+                .map_or("<invalid>", OpCode::name);
+            let jump = chunk.read_i16(offset + 2).unwrap_or(0);
+            let target = (offset as isize + 4 + jump as isize) as usize;
+            writeln!(
+                output,
+                "{:16} {} {:4} -> {}",
+                opcode.name(),
+                comparison,
+                jump,
+                target
+            )
+            .unwrap();
+            offset + 4
+        }
+
         // u16 field name/path operand
         OpCode::GetField
         | OpCode::SetField
@@ -334,6 +391,35 @@ mod tests {
         assert!(output.contains("42"));
     }
 
+    #[test]
+    fn disassemble_compare_jump() {
+        let mut chunk = Chunk::new();
+        chunk.write_op(OpCode::Null, 1);
+        chunk.write_op(OpCode::Null, 1);
+        chunk.write_op_u8(OpCode::CompareJumpIfFalse, OpCode::Lt as u8, 1);
+        chunk.write_i16(1, 1);
+        chunk.write_op(OpCode::Null, 1);
+        chunk.write_op(OpCode::Return, 1);
+
+        let output = disassemble_chunk(&chunk, "test");
+        assert!(output.contains("COMPARE_JUMP_IF_FALSE LT    1 -> 7"));
+    }
+
+    #[test]
+    fn disassemble_fused_locals() {
+        let mut chunk = Chunk::new();
+        let idx = chunk.add_constant(Value::Int(1)).unwrap();
+        chunk.write_op_u16(OpCode::LoadLocalPair, 1, 1);
+        chunk.write_u16(2, 1);
+        chunk.write_op_u16(OpCode::AddLocalConst, 1, 1);
+        chunk.write_u16(idx, 1);
+        chunk.write_op(OpCode::Return, 1);
+
+        let output = disassemble_chunk(&chunk, "test");
+        assert!(output.contains("LOAD_LOCAL_PAIR  1 2"));
+        assert!(output.contains("ADD_LOCAL_CONST  1    0 1"));
+    }
+
     #[test]
     fn disassemble_jump() {
         let mut chunk = Chunk::new();
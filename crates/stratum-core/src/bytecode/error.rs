@@ -104,10 +104,43 @@ pub enum CompileErrorKind {
     /// Column shorthand (.column) used outside of valid context
     InvalidColumnShorthand(String),
 
+    /// A `const` whose value cannot be computed at compile time
+    NotConstant(String),
+
     /// Internal compiler error
     Internal(String),
 }
 
+impl CompileErrorKind {
+    /// The stable error code for this kind of error, such as `E0100`,
+    /// explained in [`crate::error_codes`]
+    #[must_use]
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::TooManyConstants => "E0200",
+            Self::TooManyLocals => "E0201",
+            Self::TooManyUpvalues => "E0202",
+            Self::UndefinedVariable(..) => "E0203",
+            Self::DuplicateVariable(..) => "E0204",
+            Self::BreakOutsideLoop => "E0205",
+            Self::ContinueOutsideLoop => "E0206",
+            Self::ReturnOutsideFunction => "E0207",
+            Self::JumpTooLarge => "E0208",
+            Self::TooManyParameters => "E0209",
+            Self::TooManyArguments => "E0210",
+            Self::InvalidAssignmentTarget => "E0211",
+            Self::ThisOutsideMethod => "E0212",
+            Self::SuperOutsideSubclass => "E0213",
+            Self::Unsupported(..) => "E0214",
+            Self::UnsupportedPattern => "E0215",
+            Self::InvalidPlaceholder => "E0216",
+            Self::InvalidColumnShorthand(..) => "E0217",
+            Self::NotConstant(..) => "E0218",
+            Self::Internal(..) => "E0219",
+        }
+    }
+}
+
 impl fmt::Display for CompileErrorKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -174,6 +207,9 @@ impl fmt::Display for CompileErrorKind {
                     "Column shorthand '.{name}' can only be used as a function argument in DataFrame operations"
                 )
             }
+            CompileErrorKind::NotConstant(name) => {
+                write!(f, "Cannot evaluate const '{name}' at compile time")
+            }
             CompileErrorKind::Internal(msg) => {
                 write!(f, "Internal compiler error: {msg}")
             }
@@ -0,0 +1,174 @@
+//! Compile-time evaluation of operators on constants
+//!
+//! The compiler folds an operator whose operands are known at compile time
+//! into a single constant. Folding follows the VM's runtime semantics exactly;
+//! anything that would fail at runtime (division by zero, integer overflow,
+//! operands of the wrong type) is left unfolded so the VM reports it as usual.
+
+use super::Value;
+use crate::ast::{BinOp, UnaryOp};
+
+/// Fold `left op right`, or `None` when it must be evaluated at runtime
+pub(crate) fn binary(op: BinOp, left: &Value, right: &Value) -> Option<Value> {
+    if !is_primitive(left) || !is_primitive(right) {
+        return None;
+    }
+
+    match op {
+        BinOp::Add => match (left, right) {
+            (Value::String(x), Value::String(y)) => Some(Value::string(format!("{x}{y}"))),
+            (Value::String(x), other) => Some(Value::string(format!("{x}{other}"))),
+            (other, Value::String(y)) => Some(Value::string(format!("{other}{y}"))),
+            _ => arithmetic(left, right, i64::checked_add, |x, y| x + y),
+        },
+        BinOp::Sub => arithmetic(left, right, i64::checked_sub, |x, y| x - y),
+        BinOp::Mul => arithmetic(left, right, i64::checked_mul, |x, y| x * y),
+        BinOp::Div => match right {
+            Value::Int(0) => None,
+            Value::Float(y) if *y == 0.0 => None,
+            _ => arithmetic(left, right, i64::checked_div, |x, y| x / y),
+        },
+        BinOp::Mod => match (left, right) {
+            (Value::Int(_), Value::Int(0)) => None,
+            _ => arithmetic(left, right, i64::checked_rem, |x, y| x % y),
+        },
+        BinOp::Eq => Some(Value::Bool(left == right)),
+        BinOp::Ne => Some(Value::Bool(left != right)),
+        BinOp::Lt => compare(left, right, std::cmp::Ordering::is_lt),
+        BinOp::Le => compare(left, right, std::cmp::Ordering::is_le),
+        BinOp::Gt => compare(left, right, std::cmp::Ordering::is_gt),
+        BinOp::Ge => compare(left, right, std::cmp::Ordering::is_ge),
+        // `&&`, `||` and `??` short-circuit to one of their operands
+        BinOp::And => Some(if left.is_truthy() { right } else { left }.clone()),
+        BinOp::Or => Some(if left.is_truthy() { left } else { right }.clone()),
+        BinOp::NullCoalesce => Some(if left.is_null() { right } else { left }.clone()),
+        BinOp::Pipe | BinOp::Range | BinOp::RangeInclusive => None,
+    }
+}
+
+/// Fold `op value`, or `None` when it must be evaluated at runtime
+pub(crate) fn unary(op: UnaryOp, value: &Value) -> Option<Value> {
+    match (op, value) {
+        (UnaryOp::Neg, Value::Int(x)) => x.checked_neg().map(Value::Int),
+        (UnaryOp::Neg, Value::Float(x)) => Some(Value::Float(-x)),
+        (UnaryOp::Not, value) if is_primitive(value) => Some(Value::Bool(!value.is_truthy())),
+        _ => None,
+    }
+}
+
+/// Values that can appear in a folded expression
+fn is_primitive(value: &Value) -> bool {
+    matches!(
+        value,
+        Value::Null | Value::Bool(_) | Value::Int(_) | Value::Float(_) | Value::String(_)
+    )
+}
+
+/// Numeric operators: `Int op Int` stays an integer, anything with a `Float`
+/// is computed in floating point
+fn arithmetic(
+    left: &Value,
+    right: &Value,
+    int_op: fn(i64, i64) -> Option<i64>,
+    float_op: fn(f64, f64) -> f64,
+) -> Option<Value> {
+    #[allow(clippy::cast_precision_loss)]
+    match (left, right) {
+        (Value::Int(x), Value::Int(y)) => int_op(*x, *y).map(Value::Int),
+        (Value::Float(x), Value::Float(y)) => Some(Value::Float(float_op(*x, *y))),
+        (Value::Int(x), Value::Float(y)) => Some(Value::Float(float_op(*x as f64, *y))),
+        (Value::Float(x), Value::Int(y)) => Some(Value::Float(float_op(*x, *y as f64))),
+        _ => None,
+    }
+}
+
+/// Ordering comparisons between numbers or between strings
+fn compare(left: &Value, right: &Value, test: fn(std::cmp::Ordering) -> bool) -> Option<Value> {
+    #[allow(clippy::cast_precision_loss)]
+    let ordering = match (left, right) {
+        (Value::Int(x), Value::Int(y)) => Some(x.cmp(y)),
+        (Value::Float(x), Value::Float(y)) => x.partial_cmp(y),
+        (Value::Int(x), Value::Float(y)) => (*x as f64).partial_cmp(y),
+        (Value::Float(x), Value::Int(y)) => x.partial_cmp(&(*y as f64)),
+        (Value::String(x), Value::String(y)) => Some(x.cmp(y)),
+        _ => None,
+    };
+    // NaN compares false every way; leave it to the VM
+    ordering.map(|ordering| Value::Bool(test(ordering)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folds_arithmetic_like_the_vm() {
+        assert_eq!(
+            binary(BinOp::Add, &Value::Int(2), &Value::Int(3)),
+            Some(Value::Int(5))
+        );
+        assert_eq!(
+            binary(BinOp::Mul, &Value::Int(2), &Value::Float(1.5)),
+            Some(Value::Float(3.0))
+        );
+        assert_eq!(
+            binary(BinOp::Div, &Value::Int(7), &Value::Int(2)),
+            Some(Value::Int(3))
+        );
+        assert_eq!(
+            binary(BinOp::Add, &Value::string("n = "), &Value::Int(4)),
+            Some(Value::string("n = 4"))
+        );
+        assert_eq!(
+            unary(UnaryOp::Neg, &Value::Float(2.5)),
+            Some(Value::Float(-2.5))
+        );
+    }
+
+    #[test]
+    fn leaves_runtime_errors_unfolded() {
+        assert_eq!(binary(BinOp::Div, &Value::Int(1), &Value::Int(0)), None);
+        assert_eq!(
+            binary(BinOp::Div, &Value::Float(1.0), &Value::Float(0.0)),
+            None
+        );
+        assert_eq!(binary(BinOp::Mod, &Value::Int(1), &Value::Int(0)), None);
+        assert_eq!(
+            binary(BinOp::Add, &Value::Int(i64::MAX), &Value::Int(1)),
+            None
+        );
+        assert_eq!(unary(UnaryOp::Neg, &Value::Int(i64::MIN)), None);
+        assert_eq!(
+            binary(BinOp::Sub, &Value::string("a"), &Value::Int(1)),
+            None
+        );
+    }
+
+    #[test]
+    fn folds_comparisons_and_logic() {
+        assert_eq!(
+            binary(BinOp::Lt, &Value::Int(1), &Value::Float(1.5)),
+            Some(Value::Bool(true))
+        );
+        assert_eq!(
+            binary(BinOp::Ge, &Value::string("b"), &Value::string("a")),
+            Some(Value::Bool(true))
+        );
+        assert_eq!(
+            binary(BinOp::Eq, &Value::Int(1), &Value::Float(1.0)),
+            Some(Value::Bool(false))
+        );
+        assert_eq!(
+            binary(BinOp::Or, &Value::Null, &Value::Int(3)),
+            Some(Value::Int(3))
+        );
+        assert_eq!(
+            binary(BinOp::NullCoalesce, &Value::Int(0), &Value::Int(3)),
+            Some(Value::Int(0))
+        );
+        assert_eq!(
+            unary(UnaryOp::Not, &Value::Int(0)),
+            Some(Value::Bool(false))
+        );
+    }
+}
@@ -4,26 +4,37 @@
 //! - `OpCode`: The bytecode instruction set
 //! - `Value`: Runtime value representation
 //! - `Chunk`: A sequence of bytecode instructions
-//! - `Compiler`: AST to bytecode compilation
+//! - `CowCell`: Copy-on-write storage behind lists and maps
+//! - `Portable`: Deep copies of values that can move between threads
+//! - `Compiler`: AST to bytecode compilation, followed by peephole optimization
 //! - Disassembler utilities for debugging
 
 mod chunk;
 mod compiler;
+mod cow;
 mod debug;
 mod error;
+mod fold;
 mod opcode;
+mod optimize;
+mod portable;
 mod value;
 
-pub use chunk::Chunk;
+pub use chunk::{Chunk, LocalVariable};
 pub use compiler::Compiler;
-pub use debug::{disassemble_chunk, disassemble_instruction, trace_instruction};
+pub use cow::CowCell;
+pub use debug::{
+    disassemble_chunk, disassemble_function, disassemble_instruction, trace_instruction,
+};
 pub use error::{CompileError, CompileErrorKind, CompileResult};
 pub use opcode::OpCode;
+pub use portable::{Portable, PortableFunction};
 pub use value::{
-    BoundMethod, Closure, CoroutineState, CoroutineStatus, DbConnection, DbConnectionKind,
-    EnumVariantInstance, ExpectationState, Function, FutureState, FutureStatus, GuiValue,
-    HashableValue, ImageWrapper, NativeFunction, Range, SavedCallFrame, SavedExceptionHandler,
-    StructInstance, TcpListenerWrapper, TcpStreamWrapper, UdpSocketWrapper, Upvalue, Value,
-    WeakRefValue, WebSocketServerConnWrapper, WebSocketServerWrapper, WebSocketWrapper,
-    XmlDocumentWrapper,
+    BoundMethod, ChildProcessWrapper, Closure, CoroutineState, CoroutineStatus, CsvReaderWrapper,
+    CsvWriterWrapper, DbConnection, DbConnectionKind, EnumTypeInfo, EnumVariantInstance,
+    ExpectationState, FieldType, Function, FutureState, FutureStatus, GuiValue, HashableValue,
+    ImageWrapper, MockState, NamespaceName, NativeFunction, Range, SavedCallFrame,
+    SavedExceptionHandler, StructInstance, StructTypeInfo, TcpListenerWrapper, TcpStreamWrapper,
+    UdpSocketWrapper, Upvalue, Value, ValueMap, VariantFields, WeakRefValue,
+    WebSocketServerConnWrapper, WebSocketServerWrapper, WebSocketWrapper, XmlDocumentWrapper,
 };
@@ -275,6 +275,25 @@ pub enum OpCode {
     /// Operand: u16 constant index (field path as string)
     /// Pushes a StateBinding value onto the stack
     StateBinding,
+
+    // ===== Fused Instructions (emitted by the optimizer) =====
+    /// Compare the top two values and jump if the result is falsy
+    /// Operand 1: u8 comparison opcode (`Eq`, `Ne`, `Lt`, `Le`, `Gt` or `Ge`)
+    /// Operand 2: i16 offset
+    /// Pops both operands; replaces a comparison followed by `JumpIfFalse`
+    CompareJumpIfFalse,
+
+    /// Push the sum of a local variable and a constant
+    /// Operand 1: u16 local slot index
+    /// Operand 2: u16 constant index
+    /// Replaces `LoadLocal`, `Const`, `Add`
+    AddLocalConst,
+
+    /// Push two local variables, first operand first
+    /// Operand 1: u16 local slot index
+    /// Operand 2: u16 local slot index
+    /// Replaces two consecutive `LoadLocal`s
+    LoadLocalPair,
 }
 
 impl OpCode {
@@ -338,7 +357,6 @@ impl OpCode {
             | OpCode::NewList
             | OpCode::NewMap
             | OpCode::NewSet
-            | OpCode::NewStruct
             | OpCode::IterNext
             | OpCode::StringConcat
             | OpCode::IsInstance
@@ -348,11 +366,14 @@ impl OpCode {
             | OpCode::NullSafeGetIndex
             | OpCode::StateBinding => 3,
 
-            // u16 + u8 operand (4 bytes)
-            OpCode::Invoke => 4,
+            // u16 + u8 operand, or u8 + i16 operand (4 bytes)
+            OpCode::Invoke | OpCode::CompareJumpIfFalse => 4,
 
-            // i16 + i16 operand (5 bytes)
-            OpCode::PushHandler => 5,
+            // u16 + u16 operand, or i16 + i16 operand (5 bytes)
+            OpCode::NewStruct
+            | OpCode::PushHandler
+            | OpCode::AddLocalConst
+            | OpCode::LoadLocalPair => 5,
         }
     }
 
@@ -425,6 +446,9 @@ impl OpCode {
             OpCode::Await => "AWAIT",
             OpCode::Breakpoint => "BREAKPOINT",
             OpCode::StateBinding => "STATE_BINDING",
+            OpCode::CompareJumpIfFalse => "COMPARE_JUMP_IF_FALSE",
+            OpCode::AddLocalConst => "ADD_LOCAL_CONST",
+            OpCode::LoadLocalPair => "LOAD_LOCAL_PAIR",
         }
     }
 }
@@ -506,6 +530,9 @@ impl TryFrom<u8> for OpCode {
             62 => Ok(OpCode::Await),
             63 => Ok(OpCode::Breakpoint),
             64 => Ok(OpCode::StateBinding),
+            65 => Ok(OpCode::CompareJumpIfFalse),
+            66 => Ok(OpCode::AddLocalConst),
+            67 => Ok(OpCode::LoadLocalPair),
             _ => Err(value),
         }
     }
@@ -518,7 +545,7 @@ mod tests {
     #[test]
     fn opcode_size_consistency() {
         // Every opcode should have a valid size >= 1
-        for i in 0..=64 {
+        for i in 0..=67 {
             if let Ok(op) = OpCode::try_from(i) {
                 assert!(op.size() >= 1, "OpCode {:?} has invalid size", op);
             }
@@ -528,13 +555,22 @@ mod tests {
     #[test]
     fn opcode_roundtrip() {
         // All opcodes should round-trip through u8
-        for i in 0..=64 {
+        for i in 0..=67 {
             if let Ok(op) = OpCode::try_from(i) {
                 assert_eq!(op as u8, i, "OpCode {:?} has wrong discriminant", op);
             }
         }
     }
 
+    #[test]
+    fn opcode_sizes_include_all_operands() {
+        assert_eq!(OpCode::NewStruct.size(), 5);
+        assert_eq!(OpCode::CompareJumpIfFalse.size(), 4);
+        assert_eq!(OpCode::PushHandler.size(), 5);
+        assert_eq!(OpCode::AddLocalConst.size(), 5);
+        assert_eq!(OpCode::LoadLocalPair.size(), 5);
+    }
+
     #[test]
     fn opcode_names() {
         assert_eq!(OpCode::Add.name(), "ADD");
@@ -0,0 +1,699 @@
+//! Peephole optimization of compiled chunks
+//!
+//! Once a function is compiled, its chunk is decoded into a list of
+//! instructions whose jumps refer to instructions instead of byte offsets.
+//! The list is rewritten until no rule applies and then encoded again:
+//!
+//! - A jump to an unconditional jump goes straight to the final destination,
+//!   and an unconditional jump to the next instruction is dropped.
+//! - A constant condition followed by `JumpIfFalse` or `JumpIfTrue` becomes
+//!   an unconditional jump, or disappears when the jump is never taken.
+//! - A comparison followed by `JumpIfFalse` becomes `CompareJumpIfFalse`.
+//! - A store, `Pop`, and a reload of the same variable keep the stored value
+//!   on the stack; loading a variable only to store it back skips the store;
+//!   and a value pushed only to be popped is never pushed.
+//! - Instructions no path reaches (such as code after `Return`, `Throw` or an
+//!   unconditional jump) are removed.
+//!
+//! Once no rule applies, common sequences in loop bodies are fused into
+//! superinstructions so the interpreter dispatches once for them:
+//! `LoadLocal`, `Const`, `Add` becomes `AddLocalConst`, and two consecutive
+//! `LoadLocal`s become `LoadLocalPair`. This runs last so the rules above
+//! still see the separate loads.
+//!
+//! A rule never spans a jump target, so a path entering the middle of a
+//! pattern still runs the original instructions.
+
+use std::collections::HashMap;
+
+use super::chunk::Chunk;
+use super::opcode::OpCode;
+use super::value::Value;
+
+/// An instruction whose jump destinations are indices into the instruction
+/// list (the list's length stands for the end of the chunk)
+#[derive(Debug, Clone)]
+struct Instr {
+    op: OpCode,
+    /// Operand bytes other than jump offsets
+    operands: Vec<u8>,
+    /// Jump destinations
+    targets: Vec<usize>,
+    line: u32,
+}
+
+impl Instr {
+    fn jump(op: OpCode, target: usize, line: u32) -> Self {
+        Self {
+            op,
+            operands: Vec::new(),
+            targets: vec![target],
+            line,
+        }
+    }
+}
+
+/// Optimize a chunk in place
+///
+/// A chunk that can't be decoded, or whose rewritten jumps would no longer fit
+/// their operands, is left unchanged.
+pub(crate) fn optimize_chunk(chunk: &mut Chunk) {
+    let Some(mut instrs) = decode(chunk) else {
+        return;
+    };
+
+    loop {
+        let mut changed = thread_jumps(&mut instrs);
+        changed |= rewrite_patterns(&mut instrs, chunk.constants());
+        changed |= remove_unreachable(&mut instrs);
+        if !changed {
+            break;
+        }
+    }
+    fuse_locals(&mut instrs);
+
+    let Some(code) = encode(&instrs) else {
+        return;
+    };
+    chunk.clear_code();
+    for (byte, line) in code {
+        chunk.write_byte(byte, line);
+    }
+}
+
+/// Jumps with a single i16 offset as their only operand
+fn is_jump(op: OpCode) -> bool {
+    matches!(
+        op,
+        OpCode::Jump
+            | OpCode::JumpIfFalse
+            | OpCode::JumpIfTrue
+            | OpCode::JumpIfNull
+            | OpCode::JumpIfNotNull
+            | OpCode::PopJumpIfNull
+            | OpCode::Loop
+            | OpCode::IterNext
+    )
+}
+
+/// Instructions after which execution never falls through
+fn is_unconditional(op: OpCode) -> bool {
+    matches!(
+        op,
+        OpCode::Jump | OpCode::Loop | OpCode::Return | OpCode::Throw
+    )
+}
+
+fn is_comparison(op: OpCode) -> bool {
+    matches!(
+        op,
+        OpCode::Eq | OpCode::Ne | OpCode::Lt | OpCode::Le | OpCode::Gt | OpCode::Ge
+    )
+}
+
+/// The instruction's size in bytes, including a closure's upvalue descriptors
+fn instruction_size(chunk: &Chunk, offset: usize, op: OpCode) -> Option<usize> {
+    if op != OpCode::Closure {
+        return Some(op.size());
+    }
+    match chunk.get_constant(chunk.read_u16(offset + 1)?)? {
+        Value::Function(function) => Some(op.size() + 2 * function.upvalue_count as usize),
+        _ => None,
+    }
+}
+
+fn decode(chunk: &Chunk) -> Option<Vec<Instr>> {
+    let code = chunk.code();
+    let mut instrs = Vec::new();
+    let mut starts = HashMap::new();
+    let mut offset = 0;
+
+    while offset < code.len() {
+        let op = OpCode::try_from(code[offset]).ok()?;
+        let end = offset + instruction_size(chunk, offset, op)?;
+        let operands = code.get(offset + 1..end)?;
+        let target = |at: usize| {
+            let jump = i16::from_le_bytes([operands[at], operands[at + 1]]);
+            end.checked_add_signed(isize::from(jump))
+        };
+
+        let (operands, targets) = match op {
+            _ if is_jump(op) => (Vec::new(), vec![target(0)?]),
+            OpCode::CompareJumpIfFalse => (operands[..1].to_vec(), vec![target(1)?]),
+            OpCode::PushHandler => {
+                // A zero finally offset means there is no finally block
+                let mut targets = vec![target(0)?];
+                if operands[2..4] != [0, 0] {
+                    targets.push(target(2)?);
+                }
+                (Vec::new(), targets)
+            }
+            _ => (operands.to_vec(), Vec::new()),
+        };
+
+        starts.insert(offset, instrs.len());
+        instrs.push(Instr {
+            op,
+            operands,
+            targets,
+            line: chunk.get_line(offset),
+        });
+        offset = end;
+    }
+    starts.insert(code.len(), instrs.len());
+
+    // Jumps must land on an instruction boundary
+    for instr in &mut instrs {
+        for target in &mut instr.targets {
+            *target = *starts.get(target)?;
+        }
+    }
+    Some(instrs)
+}
+
+/// Encode the instructions as `(byte, line)` pairs, or `None` if a jump no
+/// longer fits in an i16
+fn encode(instrs: &[Instr]) -> Option<Vec<(u8, u32)>> {
+    let jump_slots = |instr: &Instr| {
+        if instr.op == OpCode::PushHandler {
+            2
+        } else {
+            instr.targets.len()
+        }
+    };
+
+    let mut starts = Vec::with_capacity(instrs.len() + 1);
+    let mut offset = 0;
+    for instr in instrs {
+        starts.push(offset);
+        offset += 1 + instr.operands.len() + 2 * jump_slots(instr);
+    }
+    starts.push(offset);
+
+    let mut code = Vec::with_capacity(offset);
+    for (index, instr) in instrs.iter().enumerate() {
+        let end = starts[index + 1];
+        code.push((instr.op as u8, instr.line));
+        code.extend(instr.operands.iter().map(|&byte| (byte, instr.line)));
+        for slot in 0..jump_slots(instr) {
+            let jump = match instr.targets.get(slot) {
+                Some(&target) => relative_offset(end, starts[target])?,
+                None => 0,
+            };
+            code.extend(jump.to_le_bytes().map(|byte| (byte, instr.line)));
+        }
+    }
+    Some(code)
+}
+
+/// The i16 jump offset from `from` to `to`, if it fits
+fn relative_offset(from: usize, to: usize) -> Option<i16> {
+    if to >= from {
+        i16::try_from(to - from).ok()
+    } else {
+        i16::try_from(from - to).ok().map(|distance| -distance)
+    }
+}
+
+/// Drop the instructions marked in `removed`; a jump to a removed instruction
+/// lands on the next instruction that is kept
+fn compact(instrs: &mut Vec<Instr>, removed: &[bool]) {
+    let mut new_index = Vec::with_capacity(instrs.len() + 1);
+    let mut kept = 0;
+    for &is_removed in removed {
+        new_index.push(kept);
+        if !is_removed {
+            kept += 1;
+        }
+    }
+    new_index.push(kept);
+
+    let mut index = 0;
+    instrs.retain(|_| {
+        index += 1;
+        !removed[index - 1]
+    });
+    for instr in instrs.iter_mut() {
+        for target in &mut instr.targets {
+            *target = new_index[*target];
+        }
+    }
+}
+
+/// Point jumps past chains of unconditional jumps and drop jumps to the next
+/// instruction
+fn thread_jumps(instrs: &mut Vec<Instr>) -> bool {
+    let mut changed = false;
+    for index in 0..instrs.len() {
+        let instr = &instrs[index];
+        if !is_jump(instr.op) && instr.op != OpCode::CompareJumpIfFalse {
+            continue;
+        }
+
+        let mut target = instr.targets[0];
+        // Bounded so a jump cycle (`loop {}`) can't spin forever
+        for _ in 0..instrs.len() {
+            match instrs.get(target) {
+                Some(next) if matches!(next.op, OpCode::Jump | OpCode::Loop) => {
+                    target = next.targets[0];
+                }
+                _ => break,
+            }
+        }
+        if target != instrs[index].targets[0] {
+            instrs[index].targets[0] = target;
+            changed = true;
+        }
+    }
+
+    let removed: Vec<bool> = instrs
+        .iter()
+        .enumerate()
+        .map(|(index, instr)| {
+            matches!(instr.op, OpCode::Jump | OpCode::Loop) && instr.targets[0] == index + 1
+        })
+        .collect();
+    if removed.contains(&true) {
+        compact(instrs, &removed);
+        changed = true;
+    }
+    changed
+}
+
+/// Apply the peephole rules once over the instruction list
+fn rewrite_patterns(instrs: &mut Vec<Instr>, constants: &[Value]) -> bool {
+    let mut is_target = vec![false; instrs.len() + 1];
+    for instr in instrs.iter() {
+        for &target in &instr.targets {
+            is_target[target] = true;
+        }
+    }
+
+    let mut removed = vec![false; instrs.len()];
+    let mut index = 0;
+    while index + 1 < instrs.len() {
+        let covered = rewrite_at(instrs, index, &is_target, constants, &mut removed);
+        index += covered.max(1);
+    }
+
+    if removed.contains(&true) {
+        compact(instrs, &removed);
+        true
+    } else {
+        false
+    }
+}
+
+/// Rewrite the pattern starting at `index`, returning how many instructions it
+/// covered (zero when no rule applies)
+fn rewrite_at(
+    instrs: &mut [Instr],
+    index: usize,
+    is_target: &[bool],
+    constants: &[Value],
+    removed: &mut [bool],
+) -> usize {
+    if is_target[index + 1] {
+        return 0;
+    }
+    let (first, second) = (&instrs[index], &instrs[index + 1]);
+
+    // `x = value` as a statement followed by a use of `x`: the stored value
+    // is still on the stack
+    if second.op == OpCode::Pop {
+        if let Some(third) = instrs.get(index + 2) {
+            if !is_target[index + 2]
+                && reload_of(first.op) == Some(third.op)
+                && first.operands == third.operands
+            {
+                removed[index + 1] = true;
+                removed[index + 2] = true;
+                return 3;
+            }
+        }
+    }
+
+    // A value pushed only to be popped
+    if second.op == OpCode::Pop
+        && matches!(
+            first.op,
+            OpCode::Const
+                | OpCode::Null
+                | OpCode::True
+                | OpCode::False
+                | OpCode::Dup
+                | OpCode::LoadLocal
+                | OpCode::LoadUpvalue
+        )
+    {
+        removed[index] = true;
+        removed[index + 1] = true;
+        return 2;
+    }
+
+    // Storing a variable's own value back into it
+    if reload_of(second.op) == Some(first.op) && first.operands == second.operands {
+        removed[index + 1] = true;
+        return 2;
+    }
+
+    if matches!(second.op, OpCode::JumpIfFalse | OpCode::JumpIfTrue) {
+        if let Some(truthy) = constant_truthiness(first, constants) {
+            if truthy == (second.op == OpCode::JumpIfTrue) {
+                instrs[index] = Instr::jump(OpCode::Jump, second.targets[0], second.line);
+            } else {
+                removed[index] = true;
+            }
+            removed[index + 1] = true;
+            return 2;
+        }
+    }
+
+    if is_comparison(first.op) && second.op == OpCode::JumpIfFalse {
+        instrs[index] = Instr {
+            op: OpCode::CompareJumpIfFalse,
+            operands: vec![first.op as u8],
+            targets: second.targets.clone(),
+            line: first.line,
+        };
+        removed[index + 1] = true;
+        return 2;
+    }
+
+    0
+}
+
+/// Fuse `LoadLocal`, `Const`, `Add` into `AddLocalConst` and pairs of
+/// `LoadLocal` into `LoadLocalPair`
+fn fuse_locals(instrs: &mut Vec<Instr>) {
+    let mut is_target = vec![false; instrs.len() + 1];
+    for instr in instrs.iter() {
+        for &target in &instr.targets {
+            is_target[target] = true;
+        }
+    }
+
+    let mut removed = vec![false; instrs.len()];
+    let mut index = 0;
+    while index + 1 < instrs.len() {
+        if instrs[index].op != OpCode::LoadLocal || is_target[index + 1] {
+            index += 1;
+            continue;
+        }
+
+        let second = &instrs[index + 1];
+        let adds_constant = second.op == OpCode::Const
+            && !is_target[index + 2]
+            && instrs
+                .get(index + 2)
+                .is_some_and(|third| third.op == OpCode::Add);
+        let (op, covered) = if adds_constant {
+            (OpCode::AddLocalConst, 3)
+        } else if second.op == OpCode::LoadLocal {
+            (OpCode::LoadLocalPair, 2)
+        } else {
+            index += 1;
+            continue;
+        };
+
+        let operands = [instrs[index].operands.clone(), second.operands.clone()].concat();
+        instrs[index].op = op;
+        instrs[index].operands = operands;
+        removed[index + 1..index + covered].fill(true);
+        index += covered;
+    }
+
+    if removed.contains(&true) {
+        compact(instrs, &removed);
+    }
+}
+
+/// The load that reads back what `store` wrote
+fn reload_of(store: OpCode) -> Option<OpCode> {
+    match store {
+        OpCode::StoreLocal => Some(OpCode::LoadLocal),
+        OpCode::StoreUpvalue => Some(OpCode::LoadUpvalue),
+        OpCode::StoreGlobal => Some(OpCode::LoadGlobal),
+        _ => None,
+    }
+}
+
+/// Whether the value pushed by `instr` is truthy, if known at compile time
+fn constant_truthiness(instr: &Instr, constants: &[Value]) -> Option<bool> {
+    match instr.op {
+        OpCode::True => Some(true),
+        OpCode::False | OpCode::Null => Some(false),
+        OpCode::Const => {
+            let index = u16::from_le_bytes([instr.operands[0], instr.operands[1]]);
+            constants.get(index as usize).map(Value::is_truthy)
+        }
+        _ => None,
+    }
+}
+
+/// Remove instructions that no path from the entry or an exception handler
+/// reaches
+fn remove_unreachable(instrs: &mut Vec<Instr>) -> bool {
+    let mut reachable = vec![false; instrs.len()];
+    let mut pending = vec![0];
+    while let Some(index) = pending.pop() {
+        if index >= instrs.len() || reachable[index] {
+            continue;
+        }
+        reachable[index] = true;
+        pending.extend(&instrs[index].targets);
+        if !is_unconditional(instrs[index].op) {
+            pending.push(index + 1);
+        }
+    }
+
+    if reachable.iter().all(|&reached| reached) {
+        return false;
+    }
+    let removed: Vec<bool> = reachable.iter().map(|&reached| !reached).collect();
+    compact(instrs, &removed);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn opcodes(chunk: &Chunk) -> Vec<OpCode> {
+        decode(chunk)
+            .expect("valid chunk")
+            .iter()
+            .map(|instr| instr.op)
+            .collect()
+    }
+
+    #[test]
+    fn fuses_comparison_and_jump() {
+        // while i < 10 { i = i + 1 }
+        let mut chunk = Chunk::new();
+        let start = chunk.current_offset();
+        chunk.write_op_u16(OpCode::LoadLocal, 1, 1);
+        chunk.emit_constant(Value::Int(10), 1);
+        chunk.write_op(OpCode::Lt, 1);
+        let exit = chunk.emit_jump(OpCode::JumpIfFalse, 1);
+        chunk.write_op_u16(OpCode::LoadLocal, 1, 2);
+        chunk.emit_constant(Value::Int(1), 2);
+        chunk.write_op(OpCode::Add, 2);
+        chunk.write_op_u16(OpCode::StoreLocal, 1, 2);
+        chunk.write_op(OpCode::Pop, 2);
+        chunk.emit_loop(start, 2);
+        chunk.patch_jump(exit);
+        chunk.write_op(OpCode::Null, 3);
+        chunk.write_op(OpCode::Return, 3);
+
+        optimize_chunk(&mut chunk);
+
+        assert_eq!(
+            opcodes(&chunk),
+            vec![
+                OpCode::LoadLocal,
+                OpCode::Const,
+                OpCode::CompareJumpIfFalse,
+                OpCode::AddLocalConst,
+                OpCode::StoreLocal,
+                OpCode::Pop,
+                OpCode::Loop,
+                OpCode::Null,
+                OpCode::Return,
+            ]
+        );
+        // The fused jump still exits to `Null`, the loop still restarts at 0
+        assert_eq!(chunk.read_byte(7), Some(OpCode::Lt as u8));
+        assert_eq!(chunk.read_i16(8), Some(12));
+        assert_eq!(chunk.read_i16(20), Some(-22));
+        assert_eq!(chunk.get_line(10), 2);
+    }
+
+    #[test]
+    fn fuses_local_loads() {
+        // a + b; c + 1
+        let mut chunk = Chunk::new();
+        chunk.write_op_u16(OpCode::LoadLocal, 1, 1);
+        chunk.write_op_u16(OpCode::LoadLocal, 2, 1);
+        chunk.write_op(OpCode::Add, 1);
+        chunk.write_op_u16(OpCode::LoadLocal, 3, 2);
+        chunk.emit_constant(Value::Int(1), 2);
+        chunk.write_op(OpCode::Add, 2);
+        chunk.write_op(OpCode::Return, 2);
+
+        optimize_chunk(&mut chunk);
+        assert_eq!(
+            opcodes(&chunk),
+            vec![
+                OpCode::LoadLocalPair,
+                OpCode::Add,
+                OpCode::AddLocalConst,
+                OpCode::Return
+            ]
+        );
+        assert_eq!(chunk.read_u16(1), Some(1));
+        assert_eq!(chunk.read_u16(3), Some(2));
+        assert_eq!(chunk.read_u16(7), Some(3));
+        assert_eq!(chunk.read_u16(9), Some(0));
+
+        // A load that is a jump target starts its own instruction
+        let mut chunk = Chunk::new();
+        chunk.write_op_u16(OpCode::LoadLocal, 1, 1);
+        let skip = chunk.emit_jump(OpCode::JumpIfTrue, 1);
+        chunk.write_op_u16(OpCode::LoadLocal, 1, 1);
+        chunk.write_op_u16(OpCode::LoadLocal, 2, 1);
+        chunk.patch_jump(skip);
+        chunk.write_op_u16(OpCode::LoadLocal, 3, 1);
+        chunk.emit_constant(Value::Int(1), 1);
+        chunk.write_op(OpCode::Add, 1);
+        chunk.write_op(OpCode::Return, 1);
+
+        optimize_chunk(&mut chunk);
+        assert_eq!(
+            opcodes(&chunk),
+            vec![
+                OpCode::LoadLocal,
+                OpCode::JumpIfTrue,
+                OpCode::LoadLocalPair,
+                OpCode::AddLocalConst,
+                OpCode::Return
+            ]
+        );
+    }
+
+    #[test]
+    fn removes_code_after_return_and_throw() {
+        let mut chunk = Chunk::new();
+        chunk.emit_constant(Value::Int(1), 1);
+        chunk.write_op(OpCode::Return, 1);
+        // Implicit return emitted after an explicit one
+        chunk.write_op(OpCode::Null, 2);
+        chunk.write_op(OpCode::Return, 2);
+
+        optimize_chunk(&mut chunk);
+        assert_eq!(opcodes(&chunk), vec![OpCode::Const, OpCode::Return]);
+
+        // A catch block is only reached through its handler
+        let mut chunk = Chunk::new();
+        chunk.write_op(OpCode::PushHandler, 1);
+        chunk.write_i16(7, 1);
+        chunk.write_i16(0, 1);
+        chunk.emit_constant(Value::string("boom"), 1);
+        chunk.write_op(OpCode::Throw, 1);
+        chunk.write_op_u16(OpCode::LoadGlobal, 0, 1);
+        // Catch block: discard the exception
+        chunk.write_op(OpCode::Pop, 2);
+        chunk.write_op(OpCode::Return, 2);
+
+        optimize_chunk(&mut chunk);
+        assert_eq!(
+            opcodes(&chunk),
+            vec![
+                OpCode::PushHandler,
+                OpCode::Const,
+                OpCode::Throw,
+                OpCode::Pop,
+                OpCode::Return
+            ]
+        );
+        assert_eq!(chunk.read_i16(1), Some(4));
+        assert_eq!(chunk.read_i16(3), Some(0));
+    }
+
+    #[test]
+    fn eliminates_redundant_loads_and_stores() {
+        let mut chunk = Chunk::new();
+        // x = 5; x
+        chunk.emit_constant(Value::Int(5), 1);
+        chunk.write_op_u16(OpCode::StoreLocal, 1, 1);
+        chunk.write_op(OpCode::Pop, 1);
+        chunk.write_op_u16(OpCode::LoadLocal, 1, 2);
+        // x = x
+        chunk.write_op_u16(OpCode::LoadLocal, 1, 3);
+        chunk.write_op_u16(OpCode::StoreLocal, 1, 3);
+        chunk.write_op(OpCode::Pop, 3);
+        chunk.write_op(OpCode::Return, 4);
+
+        optimize_chunk(&mut chunk);
+        assert_eq!(
+            opcodes(&chunk),
+            vec![OpCode::Const, OpCode::StoreLocal, OpCode::Return]
+        );
+    }
+
+    #[test]
+    fn collapses_constant_and_chained_jumps() {
+        // while true { ... } never tests its condition
+        let mut chunk = Chunk::new();
+        let start = chunk.current_offset();
+        chunk.write_op(OpCode::True, 1);
+        let exit = chunk.emit_jump(OpCode::JumpIfFalse, 1);
+        chunk.write_op_u16(OpCode::LoadGlobal, 0, 2);
+        chunk.write_op(OpCode::Pop, 2);
+        chunk.emit_loop(start, 2);
+        chunk.patch_jump(exit);
+        chunk.write_op(OpCode::Null, 3);
+        chunk.write_op(OpCode::Return, 3);
+
+        optimize_chunk(&mut chunk);
+        assert_eq!(
+            opcodes(&chunk),
+            vec![OpCode::LoadGlobal, OpCode::Pop, OpCode::Loop]
+        );
+
+        // if false { a } else { b } keeps only the else branch
+        let mut chunk = Chunk::new();
+        chunk.write_op(OpCode::False, 1);
+        let else_jump = chunk.emit_jump(OpCode::JumpIfFalse, 1);
+        chunk.write_op_u16(OpCode::LoadGlobal, 0, 1);
+        let end_jump = chunk.emit_jump(OpCode::Jump, 1);
+        chunk.patch_jump(else_jump);
+        chunk.write_op_u16(OpCode::LoadGlobal, 1, 1);
+        chunk.patch_jump(end_jump);
+        chunk.write_op(OpCode::Return, 1);
+
+        optimize_chunk(&mut chunk);
+        assert_eq!(opcodes(&chunk), vec![OpCode::LoadGlobal, OpCode::Return]);
+        assert_eq!(chunk.read_u16(1), Some(1));
+    }
+
+    #[test]
+    fn keeps_patterns_split_by_a_jump_target() {
+        // Another path jumps straight to the JumpIfFalse
+        let mut chunk = Chunk::new();
+        chunk.write_op_u16(OpCode::LoadLocal, 1, 1);
+        let skip = chunk.emit_jump(OpCode::JumpIfTrue, 1);
+        chunk.write_op(OpCode::Null, 1);
+        chunk.write_op(OpCode::Null, 1);
+        chunk.write_op(OpCode::Lt, 1);
+        chunk.patch_jump(skip);
+        let exit = chunk.emit_jump(OpCode::JumpIfFalse, 1);
+        chunk.write_op(OpCode::Null, 1);
+        chunk.patch_jump(exit);
+        chunk.write_op(OpCode::Return, 1);
+
+        optimize_chunk(&mut chunk);
+        assert!(opcodes(&chunk).contains(&OpCode::Lt));
+        assert!(!opcodes(&chunk).contains(&OpCode::CompareJumpIfFalse));
+    }
+}
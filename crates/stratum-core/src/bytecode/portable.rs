@@ -0,0 +1,274 @@
+//! Values that can move between threads
+//!
+//! Values share their contents through `Rc`, so they stay on the thread that
+//! made them. A [`Portable`] is a deep copy of a value that can be sent to
+//! another thread and made into a value again there. Plain data, data frames
+//! and series, and functions, natives and closures can be copied; a closure
+//! takes copies of the variables it captured, so changes to them on one
+//! thread aren't seen on the other.
+
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use super::chunk::{Chunk, ChunkCode};
+use super::value::{Closure, Function, HashableValue, NativeFunction, Upvalue, Value, ValueMap};
+use crate::ast::ExecutionMode;
+use crate::data::{DataFrame, Series};
+
+/// How deeply values may nest, which also stops a list that contains
+/// itself from being copied forever
+const MAX_DEPTH: usize = 256;
+
+/// A deep copy of a value that can be sent to another thread
+#[derive(Debug, Clone)]
+pub enum Portable {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    List(Vec<Portable>),
+    /// Entries in insertion order
+    Map(Vec<(Portable, Portable)>),
+    Set(Vec<Portable>),
+    Function(Arc<PortableFunction>),
+    /// A function and the values of the variables it captured
+    Closure(Arc<PortableFunction>, Vec<Portable>),
+    NativeFunction(NativeFunction),
+    DataFrame(Arc<DataFrame>),
+    Series(Arc<Series>),
+}
+
+/// A copy of a compiled function
+#[derive(Clone)]
+pub struct PortableFunction {
+    name: String,
+    arity: u8,
+    upvalue_count: u16,
+    execution_mode: ExecutionMode,
+    code: ChunkCode,
+    constants: Vec<Portable>,
+}
+
+impl fmt::Debug for PortableFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<fn {}>", self.name)
+    }
+}
+
+impl Portable {
+    /// Copy a value. Fails for values tied to their thread, such as
+    /// sockets, iterators and GUI elements, naming the value's type.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the value, or anything it contains, can't be copied.
+    pub fn from_value(value: &Value) -> Result<Self, String> {
+        Self::copy(value, 0)
+    }
+
+    fn copy(value: &Value, depth: usize) -> Result<Self, String> {
+        if depth > MAX_DEPTH {
+            return Err("value is nested too deeply to copy, or contains itself".to_string());
+        }
+        Ok(match value {
+            Value::Null => Self::Null,
+            Value::Bool(b) => Self::Bool(*b),
+            Value::Int(i) => Self::Int(*i),
+            Value::Float(f) => Self::Float(*f),
+            Value::String(s) => Self::String(s.to_string()),
+            Value::List(list) => Self::List(
+                list.borrow()
+                    .iter()
+                    .map(|item| Self::copy(item, depth + 1))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Map(map) => Self::Map(
+                map.borrow()
+                    .iter()
+                    .map(|(key, value)| {
+                        let key = Self::copy(&Value::from(key.clone()), depth + 1)?;
+                        Ok((key, Self::copy(value, depth + 1)?))
+                    })
+                    .collect::<Result<_, String>>()?,
+            ),
+            Value::Set(set) => Self::Set(
+                set.borrow()
+                    .iter()
+                    .map(|key| Self::copy(&Value::from(key.clone()), depth + 1))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Function(function) => {
+                Self::Function(Arc::new(PortableFunction::copy(function, depth)?))
+            }
+            Value::Closure(closure) => {
+                let upvalues = closure
+                    .upvalues
+                    .iter()
+                    .map(|upvalue| match &*upvalue.borrow() {
+                        Upvalue::Closed(value) => Self::copy(value, depth + 1),
+                        Upvalue::Open(_) => Err(format!(
+                            "{} captures a variable that is still in use",
+                            closure.function.name
+                        )),
+                    })
+                    .collect::<Result<_, _>>()?;
+                let function = PortableFunction::copy(&closure.function, depth)?;
+                Self::Closure(Arc::new(function), upvalues)
+            }
+            Value::NativeFunction(function) => Self::NativeFunction((**function).clone()),
+            Value::DataFrame(df) => Self::DataFrame(Arc::clone(df)),
+            Value::Series(series) => Self::Series(Arc::clone(series)),
+            other => {
+                return Err(format!(
+                    "{} values can't be moved to another thread",
+                    other.type_name()
+                ))
+            }
+        })
+    }
+
+    /// Make the copy into a value on the current thread
+    #[must_use]
+    pub fn into_value(self) -> Value {
+        match self {
+            Self::Null => Value::Null,
+            Self::Bool(b) => Value::Bool(b),
+            Self::Int(i) => Value::Int(i),
+            Self::Float(f) => Value::Float(f),
+            Self::String(s) => Value::string(s),
+            Self::List(items) => Value::list(items.into_iter().map(Self::into_value).collect()),
+            Self::Map(entries) => {
+                let mut map = ValueMap::new();
+                for (key, value) in entries {
+                    // Keys were hashable when copied
+                    if let Ok(key) = HashableValue::try_from(key.into_value()) {
+                        map.insert(key, value.into_value());
+                    }
+                }
+                Value::map(map)
+            }
+            Self::Set(items) => Value::set(
+                items
+                    .into_iter()
+                    .filter_map(|item| HashableValue::try_from(item.into_value()).ok())
+                    .collect::<HashSet<_>>(),
+            ),
+            Self::Function(function) => Value::Function(Rc::new(function.to_function())),
+            Self::Closure(function, upvalues) => {
+                let mut closure = Closure::new(Rc::new(function.to_function()));
+                closure.upvalues = upvalues
+                    .into_iter()
+                    .map(|value| Rc::new(RefCell::new(Upvalue::Closed(value.into_value()))))
+                    .collect();
+                Value::Closure(Rc::new(closure))
+            }
+            Self::NativeFunction(function) => Value::native(function),
+            Self::DataFrame(df) => Value::DataFrame(df),
+            Self::Series(series) => Value::Series(series),
+        }
+    }
+}
+
+impl PortableFunction {
+    fn copy(function: &Function, depth: usize) -> Result<Self, String> {
+        let (code, constants) = function.chunk.split();
+        let constants = constants
+            .iter()
+            .map(|constant| Portable::copy(constant, depth + 1))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            name: function.name.clone(),
+            arity: function.arity,
+            upvalue_count: function.upvalue_count,
+            execution_mode: function.execution_mode,
+            code,
+            constants,
+        })
+    }
+
+    fn to_function(&self) -> Function {
+        let constants = self
+            .constants
+            .iter()
+            .map(|constant| constant.clone().into_value())
+            .collect();
+        Function {
+            name: self.name.clone(),
+            arity: self.arity,
+            upvalue_count: self.upvalue_count,
+            chunk: Chunk::join(self.code.clone(), constants),
+            execution_mode: self.execution_mode,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bytecode::Compiler;
+    use crate::parser::Parser;
+    use crate::vm::VM;
+
+    fn send(value: &Value) -> Value {
+        let portable = Portable::from_value(value).unwrap();
+        std::thread::spawn(move || {
+            // Made into a value and copied again on the other thread
+            let value = portable.into_value();
+            Portable::from_value(&value).unwrap()
+        })
+        .join()
+        .unwrap()
+        .into_value()
+    }
+
+    #[test]
+    fn test_data_round_trips() {
+        let mut map = ValueMap::new();
+        map.insert(HashableValue::String(Rc::new("b".into())), Value::Int(1));
+        map.insert(
+            HashableValue::Int(7),
+            Value::list(vec![Value::Null, Value::Float(0.5)]),
+        );
+        let value = Value::list(vec![Value::string("a"), Value::Bool(true), Value::map(map)]);
+        let copy = send(&value);
+        assert_eq!(copy, value);
+        assert_eq!(format!("{copy}"), format!("{value}"));
+    }
+
+    #[test]
+    fn test_closure_runs_on_another_thread() {
+        let source =
+            "fx adder(offset) {\n    |x| x + offset + Math.abs(-1)\n}\nlet add = adder(40)";
+        let module = Parser::parse_module(source).unwrap();
+        let script = Compiler::new().compile_module(&module).unwrap();
+        let mut vm = VM::new();
+        vm.run(script).unwrap();
+
+        let add = Portable::from_value(&vm.globals()["add"]).unwrap();
+        let result = std::thread::spawn(move || {
+            let mut vm = VM::new();
+            let result = vm.invoke_callback(&add.into_value(), vec![Value::Int(1)]);
+            Portable::from_value(&result.unwrap()).unwrap()
+        })
+        .join()
+        .unwrap();
+        assert_eq!(result.into_value(), Value::Int(42));
+    }
+
+    #[test]
+    fn test_thread_bound_values_fail() {
+        let list = Value::list(vec![]);
+        if let Value::List(items) = &list {
+            items.borrow_mut().push(list.clone());
+        }
+        assert!(Portable::from_value(&list).is_err());
+        let error = Portable::from_value(&Value::StateBinding(Rc::new("count".into())));
+        assert!(error
+            .unwrap_err()
+            .contains("can't be moved to another thread"));
+    }
+}
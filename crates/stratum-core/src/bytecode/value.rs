@@ -9,6 +9,7 @@ use std::sync::{Arc, Mutex};
 
 use futures_util::stream::{SplitSink, SplitStream};
 use image::{DynamicImage, GenericImageView};
+use indexmap::IndexMap;
 use regex::Regex as CompiledRegex;
 use tokio::net::{
     TcpListener as TokioTcpListener, TcpStream as TokioTcpStream, UdpSocket as TokioUdpSocket,
@@ -16,8 +17,8 @@ use tokio::net::{
 use tokio_tungstenite::tungstenite::Message as WsMessage;
 use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
 
-use super::Chunk;
-use crate::ast::ExecutionMode;
+use super::{Chunk, CowCell};
+use crate::ast::{Derive, ExecutionMode};
 use crate::data::{
     AggSpec, Cube, CubeBuilder, CubeQuery, DataFrame, GroupedDataFrame, JoinSpec, Rolling, Series,
     SqlContext,
@@ -417,6 +418,92 @@ impl fmt::Debug for ImageWrapper {
     }
 }
 
+/// Child process wrapper for Stratum
+/// Holds a spawned process along with its piped stdio handles. Each handle sits
+/// behind its own mutex so a read blocked on stdout does not prevent writing to
+/// stdin or killing the process.
+pub struct ChildProcessWrapper {
+    /// OS process ID
+    pub pid: u32,
+    /// Program that was launched
+    pub program: String,
+    /// The underlying child process (used for wait/kill)
+    pub child: Arc<Mutex<std::process::Child>>,
+    /// Writable stdin pipe (None once closed)
+    pub stdin: Arc<Mutex<Option<std::process::ChildStdin>>>,
+    /// Buffered stdout pipe
+    pub stdout: Arc<Mutex<Option<std::io::BufReader<std::process::ChildStdout>>>>,
+    /// Buffered stderr pipe
+    pub stderr: Arc<Mutex<Option<std::io::BufReader<std::process::ChildStderr>>>>,
+}
+
+impl fmt::Debug for ChildProcessWrapper {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChildProcess")
+            .field("pid", &self.pid)
+            .field("program", &self.program)
+            .finish()
+    }
+}
+
+impl ChildProcessWrapper {
+    /// Wrap a spawned child, taking ownership of its piped stdio handles
+    #[must_use]
+    pub fn new(mut child: std::process::Child, program: String) -> Self {
+        let stdin = child.stdin.take();
+        let stdout = child.stdout.take().map(std::io::BufReader::new);
+        let stderr = child.stderr.take().map(std::io::BufReader::new);
+        Self {
+            pid: child.id(),
+            program,
+            child: Arc::new(Mutex::new(child)),
+            stdin: Arc::new(Mutex::new(stdin)),
+            stdout: Arc::new(Mutex::new(stdout)),
+            stderr: Arc::new(Mutex::new(stderr)),
+        }
+    }
+}
+
+/// Streaming CSV reader for Stratum
+/// Reads one record at a time from a file so large inputs are never loaded whole.
+pub struct CsvReaderWrapper {
+    /// Path of the file being read
+    pub path: String,
+    /// Header row, when the reader was opened with `headers: true`
+    pub headers: Option<Vec<String>>,
+    /// Underlying reader (None once closed)
+    pub reader: Mutex<Option<csv::Reader<std::fs::File>>>,
+}
+
+impl fmt::Debug for CsvReaderWrapper {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CsvReader")
+            .field("path", &self.path)
+            .field("headers", &self.headers)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Streaming CSV writer for Stratum
+pub struct CsvWriterWrapper {
+    /// Path of the file being written
+    pub path: String,
+    /// Column order used when writing Map rows (inferred from the first Map if not given)
+    pub headers: Mutex<Option<Vec<String>>>,
+    /// Whether the header row still needs to be written before the next Map row
+    pub header_pending: Mutex<bool>,
+    /// Underlying writer (None once closed)
+    pub writer: Mutex<Option<csv::Writer<std::fs::File>>>,
+}
+
+impl fmt::Debug for CsvWriterWrapper {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CsvWriter")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
 impl ImageWrapper {
     /// Create a new image wrapper
     #[must_use]
@@ -455,9 +542,9 @@ impl ImageWrapper {
 #[derive(Clone)]
 pub enum WeakRefValue {
     /// Weak reference to a list
-    List(Weak<RefCell<Vec<Value>>>),
+    List(Weak<CowCell<Vec<Value>>>),
     /// Weak reference to a map
-    Map(Weak<RefCell<HashMap<HashableValue, Value>>>),
+    Map(Weak<CowCell<ValueMap>>),
     /// Weak reference to a set
     Set(Weak<RefCell<HashSet<HashableValue>>>),
     /// Weak reference to a struct instance
@@ -520,6 +607,13 @@ impl fmt::Debug for WeakRefValue {
 }
 
 /// A runtime value in the Stratum VM
+///
+/// Values are copied on nearly every instruction, so each variant holds at
+/// most one machine word: immediates inline, everything else behind a thin
+/// pointer. That keeps a `Value` to a tag plus one word (16 bytes on 64-bit
+/// targets), so the stack and collections pack four values per cache line.
+/// Payloads that would be wider, such as trait objects, get an extra
+/// indirection, and namespace names are interned.
 #[derive(Clone)]
 pub enum Value {
     /// Null value
@@ -537,11 +631,11 @@ pub enum Value {
     /// String (reference-counted)
     String(Rc<String>),
 
-    /// List/array (reference-counted, mutable)
-    List(Rc<RefCell<Vec<Value>>>),
+    /// List/array (reference-counted, mutable, copy-on-write when cloned)
+    List(Rc<CowCell<Vec<Value>>>),
 
-    /// Map/dictionary (reference-counted, mutable)
-    Map(Rc<RefCell<HashMap<HashableValue, Value>>>),
+    /// Map/dictionary (reference-counted, mutable, copy-on-write when cloned)
+    Map(Rc<CowCell<ValueMap>>),
 
     /// Set (reference-counted, mutable)
     Set(Rc<RefCell<HashSet<HashableValue>>>),
@@ -553,7 +647,7 @@ pub enum Value {
     Closure(Rc<Closure>),
 
     /// Native/built-in function
-    NativeFunction(NativeFunction),
+    NativeFunction(Rc<NativeFunction>),
 
     /// Struct instance
     Struct(Rc<RefCell<StructInstance>>),
@@ -561,6 +655,12 @@ pub enum Value {
     /// Enum variant instance
     EnumVariant(Rc<EnumVariantInstance>),
 
+    /// Struct type descriptor (the value bound to a struct's name)
+    StructType(Rc<StructTypeInfo>),
+
+    /// Enum type descriptor (the value bound to an enum's name)
+    EnumType(Rc<EnumTypeInfo>),
+
     /// Range (start..end)
     Range(Rc<Range>),
 
@@ -571,7 +671,7 @@ pub enum Value {
     BoundMethod(Rc<BoundMethod>),
 
     /// Native namespace module (File, Dir, Path, Env, Args, Shell)
-    NativeNamespace(&'static str),
+    NativeNamespace(NamespaceName),
 
     /// Compiled regular expression
     Regex(Rc<CompiledRegex>),
@@ -635,26 +735,42 @@ pub enum Value {
 
     /// GUI element (opaque container for stratum-gui types)
     /// Stored as a type-erased Arc to allow cross-crate use
-    GuiElement(Arc<dyn GuiValue>),
+    GuiElement(Arc<Box<dyn GuiValue>>),
 
     /// State binding for reactive GUI updates (&state.field)
     /// Contains the dotted path to the bound field
-    StateBinding(String),
+    StateBinding(Rc<String>),
 
     /// Test expectation (from Test.expect(value))
     Expectation(Rc<RefCell<ExpectationState>>),
 
+    /// Test double standing in for a function (from Test.mock(target, fn))
+    Mock(Rc<RefCell<MockState>>),
+
     /// XML document (parsed XML with XPath support)
     XmlDocument(Arc<XmlDocumentWrapper>),
 
     /// Image (loaded image for processing)
     Image(Arc<ImageWrapper>),
 
+    /// Spawned child process with piped stdin/stdout/stderr
+    ChildProcess(Arc<ChildProcessWrapper>),
+
+    /// Streaming CSV file reader
+    CsvReader(Arc<CsvReaderWrapper>),
+
+    /// Streaming CSV file writer
+    CsvWriter(Arc<CsvWriterWrapper>),
+
     /// Weak reference to a container value
     /// Does not prevent garbage collection of the referenced value
-    WeakRef(WeakRefValue),
+    WeakRef(Rc<WeakRefValue>),
 }
 
+// Every variant must stay within one word; see the note on `Value`
+#[cfg(target_pointer_width = "64")]
+const _: () = assert!(std::mem::size_of::<Value>() == 16);
+
 /// Trait for GUI values that can be stored in the VM.
 /// Implemented by stratum-gui's GuiElement type.
 pub trait GuiValue: std::fmt::Debug + Send + Sync {
@@ -671,6 +787,10 @@ pub trait GuiValue: std::fmt::Debug + Send + Sync {
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any;
 }
 
+/// The entries of a map, which iterate in the order their keys were first
+/// inserted
+pub type ValueMap = IndexMap<HashableValue, Value>;
+
 /// A hashable wrapper for values that can be used as map keys
 #[derive(Clone, Debug)]
 pub enum HashableValue {
@@ -864,6 +984,62 @@ impl fmt::Debug for NativeFunction {
     }
 }
 
+/// The name of a native namespace, such as `File` or `Gui`
+///
+/// Names are interned: each distinct name is stored once for the life of the
+/// process, so a handle is one word, compares by pointer, and registering a
+/// namespace again, as every new VM does, stores nothing new.
+#[derive(Clone, Copy)]
+pub struct NamespaceName(&'static &'static str);
+
+impl NamespaceName {
+    /// The handle for `name`, storing the name on first use
+    pub fn intern(name: &str) -> Self {
+        static NAMES: Mutex<Vec<&'static &'static str>> = Mutex::new(Vec::new());
+
+        let mut names = NAMES.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(interned) = names.iter().find(|interned| ***interned == name) {
+            return Self(interned);
+        }
+        let name: &'static str = Box::leak(name.into());
+        let interned: &'static &'static str = Box::leak(Box::new(name));
+        names.push(interned);
+        Self(interned)
+    }
+
+    /// The name
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        self.0
+    }
+}
+
+impl PartialEq for NamespaceName {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.0, other.0)
+    }
+}
+
+impl Eq for NamespaceName {}
+
+impl Hash for NamespaceName {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::ptr::hash(self.0, state);
+    }
+}
+
+impl fmt::Debug for NamespaceName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
+impl fmt::Display for NamespaceName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
 /// A struct instance
 #[derive(Clone, Debug)]
 pub struct StructInstance {
@@ -885,6 +1061,93 @@ impl StructInstance {
     }
 }
 
+/// Runtime description of a struct declaration
+/// Emitted by the compiler for every `struct` item and bound to a global of
+/// the same name, so natives such as `Json.decode_as` can inspect field types.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StructTypeInfo {
+    /// The struct type name
+    pub name: String,
+
+    /// Fields in declaration order
+    pub fields: Vec<(String, FieldType)>,
+
+    /// Behaviours requested with `#[derive(...)]`
+    pub derives: Vec<Derive>,
+}
+
+/// Runtime description of an enum declaration
+/// Emitted by the compiler for every `enum` item and bound to a global of
+/// the same name, so derived methods can find variant shapes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnumTypeInfo {
+    /// The enum type name
+    pub name: String,
+
+    /// Variants in declaration order
+    pub variants: Vec<(String, VariantFields)>,
+
+    /// Behaviours requested with `#[derive(...)]`
+    pub derives: Vec<Derive>,
+}
+
+impl EnumTypeInfo {
+    /// Look up the declared payload of a variant
+    #[must_use]
+    pub fn variant(&self, name: &str) -> Option<&VariantFields> {
+        self.variants
+            .iter()
+            .find(|(variant, _)| variant == name)
+            .map(|(_, fields)| fields)
+    }
+}
+
+/// Declared payload of an enum variant
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VariantFields {
+    /// `Variant`
+    Unit,
+    /// `Variant(T)`
+    Tuple(FieldType),
+    /// `Variant { name: T, ... }` in declaration order
+    Struct(Vec<(String, FieldType)>),
+}
+
+/// Declared type of a struct field, as far as it can be checked at runtime
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FieldType {
+    /// Unchecked (generic parameters, functions, tuples, `_`)
+    Any,
+    Bool,
+    Int,
+    Float,
+    String,
+    /// List<T> or [T]
+    List(Box<FieldType>),
+    /// Map<K, V> (only the value type is checked; keys are strings in JSON)
+    Map(Box<FieldType>),
+    /// T?
+    Nullable(Box<FieldType>),
+    /// Another named type, resolved by name when needed
+    Named(String),
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldType::Any => write!(f, "Any"),
+            FieldType::Bool => write!(f, "Bool"),
+            FieldType::Int => write!(f, "Int"),
+            FieldType::Float => write!(f, "Float"),
+            FieldType::String => write!(f, "String"),
+            FieldType::List(inner) => write!(f, "List<{inner}>"),
+            FieldType::Map(inner) => write!(f, "Map<String, {inner}>"),
+            FieldType::Nullable(inner) => write!(f, "{inner}?"),
+            FieldType::Named(name) => write!(f, "{name}"),
+        }
+    }
+}
+
 /// An enum variant instance
 #[derive(Clone, Debug)]
 pub struct EnumVariantInstance {
@@ -984,6 +1247,35 @@ impl ExpectationState {
     }
 }
 
+/// State for a test double (Test.mock(target, replacement))
+#[derive(Clone, Debug)]
+pub struct MockState {
+    /// The mocked global function, or `Namespace.method` for a native
+    pub target: String,
+    /// The function called in place of the target
+    pub replacement: Value,
+    /// The global's value before it was mocked (`None` for namespace methods)
+    pub original: Option<Value>,
+    /// The arguments of each call, in order
+    pub calls: Vec<Vec<Value>>,
+    /// Whether the mock is still standing in for its target
+    pub active: bool,
+}
+
+impl MockState {
+    /// Create an active mock with no calls recorded
+    #[must_use]
+    pub fn new(target: String, replacement: Value, original: Option<Value>) -> Self {
+        Self {
+            target,
+            replacement,
+            original,
+            calls: Vec::new(),
+            active: true,
+        }
+    }
+}
+
 /// Status of a future/promise
 #[derive(Clone, Debug, PartialEq)]
 pub enum FutureStatus {
@@ -1100,6 +1392,11 @@ pub struct SavedCallFrame {
     pub ip: usize,
     /// Stack base offset (relative to coroutine stack)
     pub stack_base: usize,
+    /// Whether the frame's return value is negated (`!=` calling `__eq__`)
+    pub negate_result: bool,
+    /// Offset the caller jumps by when the frame returns a falsy value
+    /// (a fused comparison calling an impl method)
+    pub branch_if_false: Option<i16>,
 }
 
 /// Saved exception handler for coroutine suspension
@@ -1222,10 +1519,12 @@ impl Value {
             Value::NativeFunction(_) => "Function",
             Value::Struct(_) => "Struct",
             Value::EnumVariant(_) => "EnumVariant",
+            Value::StructType(_) => "StructType",
+            Value::EnumType(_) => "EnumType",
             Value::Range(_) => "Range",
             Value::Iterator(_) => "Iterator",
             Value::BoundMethod(_) => "Method",
-            Value::NativeNamespace(name) => name,
+            Value::NativeNamespace(name) => name.as_str(),
             Value::Regex(_) => "Regex",
             Value::DbConnection(_) => "DbConnection",
             Value::TcpStream(_) => "TcpStream",
@@ -1249,8 +1548,12 @@ impl Value {
             Value::GuiElement(e) => e.kind_name(),
             Value::StateBinding(_) => "StateBinding",
             Value::Expectation(_) => "Expectation",
+            Value::Mock(_) => "Mock",
             Value::XmlDocument(_) => "XmlDocument",
             Value::Image(_) => "Image",
+            Value::ChildProcess(_) => "ChildProcess",
+            Value::CsvReader(_) => "CsvReader",
+            Value::CsvWriter(_) => "CsvWriter",
             Value::WeakRef(_) => "WeakRef",
         }
     }
@@ -1282,19 +1585,25 @@ impl Value {
     /// Create an empty list
     #[must_use]
     pub fn empty_list() -> Self {
-        Value::List(Rc::new(RefCell::new(Vec::new())))
+        Value::list(Vec::new())
     }
 
     /// Create a list from values
     #[must_use]
     pub fn list(values: Vec<Value>) -> Self {
-        Value::List(Rc::new(RefCell::new(values)))
+        Value::List(Rc::new(CowCell::new(values)))
     }
 
     /// Create an empty map
     #[must_use]
     pub fn empty_map() -> Self {
-        Value::Map(Rc::new(RefCell::new(HashMap::new())))
+        Value::map(ValueMap::new())
+    }
+
+    /// Create a map from entries
+    #[must_use]
+    pub fn map(entries: ValueMap) -> Self {
+        Value::Map(Rc::new(CowCell::new(entries)))
     }
 
     /// Create an empty set
@@ -1309,6 +1618,36 @@ impl Value {
         Value::Set(Rc::new(RefCell::new(values)))
     }
 
+    /// Wrap a weak reference as a value
+    #[must_use]
+    pub fn weak(weak: WeakRefValue) -> Self {
+        Value::WeakRef(Rc::new(weak))
+    }
+
+    /// The native namespace called `name`
+    #[must_use]
+    pub fn namespace(name: &str) -> Self {
+        Value::NativeNamespace(NamespaceName::intern(name))
+    }
+
+    /// Wrap a native function as a value
+    #[must_use]
+    pub fn native(function: NativeFunction) -> Self {
+        Value::NativeFunction(Rc::new(function))
+    }
+
+    /// Wrap a GUI element as a value
+    #[must_use]
+    pub fn gui(element: impl GuiValue + 'static) -> Self {
+        Value::GuiElement(Arc::new(Box::new(element)))
+    }
+
+    /// Create a state binding to the dotted path of a state field
+    #[must_use]
+    pub fn state_binding(path: impl Into<String>) -> Self {
+        Value::StateBinding(Rc::new(path.into()))
+    }
+
     /// Create a regex value from a compiled regex
     #[must_use]
     pub fn regex(re: CompiledRegex) -> Self {
@@ -1333,10 +1672,10 @@ impl Value {
     #[must_use]
     pub fn weak_ref(&self) -> Option<Value> {
         match self {
-            Value::List(rc) => Some(Value::WeakRef(WeakRefValue::List(Rc::downgrade(rc)))),
-            Value::Map(rc) => Some(Value::WeakRef(WeakRefValue::Map(Rc::downgrade(rc)))),
-            Value::Set(rc) => Some(Value::WeakRef(WeakRefValue::Set(Rc::downgrade(rc)))),
-            Value::Struct(rc) => Some(Value::WeakRef(WeakRefValue::Struct(Rc::downgrade(rc)))),
+            Value::List(rc) => Some(Value::weak(WeakRefValue::List(Rc::downgrade(rc)))),
+            Value::Map(rc) => Some(Value::weak(WeakRefValue::Map(Rc::downgrade(rc)))),
+            Value::Set(rc) => Some(Value::weak(WeakRefValue::Set(Rc::downgrade(rc)))),
+            Value::Struct(rc) => Some(Value::weak(WeakRefValue::Struct(Rc::downgrade(rc)))),
             _ => None,
         }
     }
@@ -1387,6 +1726,8 @@ impl PartialEq for Value {
             (Value::EnumVariant(a), Value::EnumVariant(b)) => {
                 a.enum_name == b.enum_name && a.variant_name == b.variant_name && a.data == b.data
             }
+            (Value::StructType(a), Value::StructType(b)) => a.name == b.name,
+            (Value::EnumType(a), Value::EnumType(b)) => a.name == b.name,
             (Value::Range(a), Value::Range(b)) => {
                 a.start == b.start && a.end == b.end && a.inclusive == b.inclusive
             }
@@ -1411,8 +1752,12 @@ impl PartialEq for Value {
             (Value::GuiElement(a), Value::GuiElement(b)) => Arc::ptr_eq(a, b),
             (Value::StateBinding(a), Value::StateBinding(b)) => a == b,
             (Value::Expectation(a), Value::Expectation(b)) => Rc::ptr_eq(a, b),
+            (Value::Mock(a), Value::Mock(b)) => Rc::ptr_eq(a, b),
             (Value::XmlDocument(a), Value::XmlDocument(b)) => Arc::ptr_eq(a, b),
             (Value::Image(a), Value::Image(b)) => Arc::ptr_eq(a, b),
+            (Value::ChildProcess(a), Value::ChildProcess(b)) => Arc::ptr_eq(a, b),
+            (Value::CsvReader(a), Value::CsvReader(b)) => Arc::ptr_eq(a, b),
+            (Value::CsvWriter(a), Value::CsvWriter(b)) => Arc::ptr_eq(a, b),
             (Value::WeakRef(a), Value::WeakRef(b)) => a.ptr() == b.ptr(),
             _ => false,
         }
@@ -1451,6 +1796,8 @@ impl fmt::Debug for Value {
                     write!(f, "{}.{}", e.enum_name, e.variant_name)
                 }
             }
+            Value::StructType(t) => write!(f, "<struct {}>", t.name),
+            Value::EnumType(t) => write!(f, "<enum {}>", t.name),
             Value::Range(r) => {
                 if r.inclusive {
                     write!(f, "{}..={}", r.start, r.end)
@@ -1583,6 +1930,7 @@ impl fmt::Debug for Value {
                     write!(f, "<Expectation {:?}>", exp.actual)
                 }
             }
+            Value::Mock(mock) => write!(f, "<Mock {}>", mock.borrow().target),
             Value::XmlDocument(doc) => {
                 write!(
                     f,
@@ -1594,6 +1942,11 @@ impl fmt::Debug for Value {
             Value::Image(img) => {
                 write!(f, "<Image {}x{}>", img.width(), img.height())
             }
+            Value::ChildProcess(proc) => {
+                write!(f, "<ChildProcess pid={} '{}'>", proc.pid, proc.program)
+            }
+            Value::CsvReader(reader) => write!(f, "<CsvReader '{}'>", reader.path),
+            Value::CsvWriter(writer) => write!(f, "<CsvWriter '{}'>", writer.path),
             Value::WeakRef(weak) => {
                 let alive = if weak.is_alive() { "alive" } else { "dead" };
                 write!(f, "<weak {} ({})>", weak.target_type_name(), alive)
@@ -1665,6 +2018,8 @@ impl fmt::Display for Value {
                     write!(f, "{}", e.variant_name)
                 }
             }
+            Value::StructType(t) => write!(f, "<struct {}>", t.name),
+            Value::EnumType(t) => write!(f, "<enum {}>", t.name),
             Value::Range(r) => {
                 if r.inclusive {
                     write!(f, "{}..={}", r.start, r.end)
@@ -1758,12 +2113,16 @@ impl fmt::Display for Value {
                     write!(f, "<expect {}>", exp.actual)
                 }
             }
+            Value::Mock(mock) => write!(f, "<mock {}>", mock.borrow().target),
             Value::XmlDocument(doc) => {
                 write!(f, "<xml root='{}'>", doc.root_name)
             }
             Value::Image(img) => {
                 write!(f, "<image {}x{}>", img.width(), img.height())
             }
+            Value::ChildProcess(proc) => write!(f, "<process {}>", proc.pid),
+            Value::CsvReader(reader) => write!(f, "<csv reader {}>", reader.path),
+            Value::CsvWriter(writer) => write!(f, "<csv writer {}>", writer.path),
             Value::WeakRef(weak) => {
                 let alive = if weak.is_alive() { "alive" } else { "dead" };
                 write!(f, "<weak {} ({})>", weak.target_type_name(), alive)
@@ -1839,4 +2198,73 @@ mod tests {
         assert!(ready.is_ready());
         assert_eq!(ready.result, Some(Value::Int(42)));
     }
+
+    #[test]
+    fn boxed_payloads() {
+        let print = Value::native(NativeFunction::new("print", -1, |_| Ok(Value::Null)));
+        assert_eq!(format!("{print}"), "<native fn print>");
+
+        let module = Value::namespace("File");
+        assert_eq!(module.type_name(), "File");
+        assert_eq!(module, Value::namespace("File"));
+        assert_ne!(module, Value::namespace("Dir"));
+        assert_eq!(format!("{module}"), "<module File>");
+
+        let binding = Value::state_binding("form.name");
+        assert_eq!(binding, Value::state_binding("form.name"));
+        assert_eq!(format!("{binding}"), "<binding &form.name>");
+
+        let list = Value::list(vec![Value::Int(1)]);
+        let weak = list.weak_ref().unwrap();
+        assert_eq!(weak.upgrade_weak(), Some(list.clone()));
+        drop(list);
+        assert!(!weak.is_weak_ref_alive());
+    }
+
+    #[test]
+    fn boxed_payloads_round_trip() {
+        // Clones share the payload and compare equal
+        let list = Value::list(vec![]);
+        for value in [
+            Value::namespace("Path"),
+            Value::state_binding("form.email"),
+            list.weak_ref().unwrap(),
+        ] {
+            assert_eq!(value.clone(), value);
+        }
+
+        let print = Value::native(NativeFunction::new("print", -1, |_| Ok(Value::Null)));
+        let copy = crate::bytecode::Portable::from_value(&print)
+            .unwrap()
+            .into_value();
+        match copy {
+            Value::NativeFunction(function) => assert_eq!(function.name, "print"),
+            other => panic!("expected a native function, got {other}"),
+        }
+
+        let name = NamespaceName::intern("Path");
+        assert_eq!(NamespaceName::intern(name.as_str()), name);
+        assert_eq!(
+            Value::namespace(name.as_str()),
+            Value::NativeNamespace(name)
+        );
+    }
+
+    #[test]
+    fn namespace_names_are_interned() {
+        let first = NamespaceName::intern("Interned");
+        // Interning a name again, as each new VM does, stores nothing new
+        let name = String::from("Interned");
+        let again = NamespaceName::intern(&name);
+        assert!(std::ptr::eq(first.as_str(), again.as_str()));
+        assert_eq!(first, again);
+        assert_ne!(first, NamespaceName::intern("Other"));
+
+        let names: HashSet<NamespaceName> = ["Interned", "Other", "Interned"]
+            .into_iter()
+            .map(NamespaceName::intern)
+            .collect();
+        assert_eq!(names.len(), 2);
+        assert!(names.contains(&again));
+    }
 }
@@ -3,13 +3,48 @@
 //! This module provides bytecode-level coverage tracking with support for:
 //! - Line coverage: Track which source lines were executed
 //! - Branch coverage: Track which conditional branches were taken
-//! - Coverage reporting in multiple formats (summary, HTML, lcov)
+//! - Coverage reporting in multiple formats (summary, HTML, lcov, Cobertura)
+//!
+//! The VM reports each instruction it interprets, and the outcome of each
+//! conditional jump: both sides of `if`, `&&`/`||` and `??`, each `match`
+//! arm's test, and whether a `for` loop ran or exited. Functions are keyed by
+//! source file, name and first line, so the same function compiled in
+//! separate test VMs merges into one entry.
+//!
+//! A collector's data can be saved with [`CoverageCollector::to_data`] and
+//! loaded with [`CoverageCollector::from_data`], so runs in separate
+//! processes (such as sharded CI jobs) can be merged into one report.
 
 use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
 use crate::bytecode::{Chunk, Function, OpCode};
 
+/// Version of the coverage data format written by [`CoverageCollector::to_data`]
+pub const COVERAGE_DATA_VERSION: u32 = 1;
+
+/// Errors reading coverage data
+#[derive(Debug, Error)]
+pub enum CoverageDataError {
+    /// The data is not valid coverage JSON
+    #[error("invalid coverage data: {0}")]
+    Json(#[from] serde_json::Error),
+
+    /// The data was written in a format this version cannot read
+    #[error("unsupported coverage data version {0} (expected {COVERAGE_DATA_VERSION})")]
+    UnsupportedVersion(u32),
+}
+
+/// Coverage data as saved to disk
+#[derive(Debug, Serialize, Deserialize)]
+struct CoverageData {
+    version: u32,
+    functions: HashMap<String, FunctionCoverage>,
+}
+
 /// Identifies a branch point in the bytecode
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct BranchId {
@@ -20,7 +55,7 @@ pub struct BranchId {
 }
 
 /// Tracks coverage data for a single function/chunk
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct FunctionCoverage {
     /// Function name
     pub name: String,
@@ -34,8 +69,21 @@ pub struct FunctionCoverage {
     pub branches: HashMap<usize, BranchInfo>,
 }
 
+/// A line or branch outcome covered by a run
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CoveragePoint {
+    /// A line of a function was executed
+    Line { function: String, line: u32 },
+    /// The branch at an offset in a function went one way
+    Branch {
+        function: String,
+        offset: usize,
+        taken: bool,
+    },
+}
+
 /// Information about a branch point
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct BranchInfo {
     /// Source line number
     pub line: u32,
@@ -91,6 +139,7 @@ impl FunctionCoverage {
             // Track branch points
             match opcode {
                 OpCode::JumpIfFalse
+                | OpCode::CompareJumpIfFalse
                 | OpCode::JumpIfTrue
                 | OpCode::JumpIfNull
                 | OpCode::JumpIfNotNull
@@ -159,8 +208,10 @@ impl FunctionCoverage {
 /// Collects coverage data across multiple functions/files
 #[derive(Debug, Clone, Default)]
 pub struct CoverageCollector {
-    /// Coverage data per function (keyed by function pointer address as string)
+    /// Coverage data per function (keyed by source file, name and first line)
     functions: HashMap<String, FunctionCoverage>,
+    /// Keys of the functions seen so far, by the address of their bytecode
+    keys: HashMap<usize, String>,
     /// Currently active function for recording
     active_function: Option<String>,
     /// Map of source files to their total line counts (for reporting)
@@ -173,13 +224,57 @@ impl CoverageCollector {
         Self::default()
     }
 
-    /// Begin tracking a function
-    ///
-    /// This marks all executable lines in the function as executed since we're
-    /// entering the function. For more precise line-by-line tracking, use
-    /// `record_line` from the VM execution loop.
+    /// Begin tracking a function, making it the one `record_line` and the
+    /// `record_branch_*` methods apply to
     pub fn begin_function(&mut self, function: &Function) {
-        let key = format!("{}@{:p}", function.name, function);
+        self.coverage_for(function);
+        self.active_function = self.keys.get(&Self::address(function)).cloned();
+    }
+
+    /// Record that the instruction at `offset` in a function was executed
+    pub fn record_instruction(&mut self, function: &Function, offset: usize) {
+        let line = function.chunk.get_line(offset);
+        self.coverage_for(function).record_line(line);
+    }
+
+    /// Record whether the conditional jump at `offset` in a function jumped
+    pub fn record_branch(&mut self, function: &Function, offset: usize, taken: bool) {
+        let coverage = self.coverage_for(function);
+        if taken {
+            coverage.record_branch_taken(offset);
+        } else {
+            coverage.record_branch_not_taken(offset);
+        }
+    }
+
+    /// The coverage of a function, analyzing its bytecode the first time
+    /// it is seen
+    fn coverage_for(&mut self, function: &Function) -> &mut FunctionCoverage {
+        let address = Self::address(function);
+        // A freed function's address can be reused by another
+        let known = self.keys.get(&address).is_some_and(|key| {
+            self.functions
+                .get(key)
+                .is_some_and(|coverage| coverage.name == function.name)
+        });
+        if !known {
+            let key = self.register(function);
+            self.keys.insert(address, key);
+        }
+        let key = &self.keys[&address];
+        self.functions
+            .get_mut(key)
+            .expect("function registered above")
+    }
+
+    /// Add a function's executable lines and branch points, returning its key
+    fn register(&mut self, function: &Function) -> String {
+        let key = format!(
+            "{}:{}:{}",
+            function.chunk.source_name.as_deref().unwrap_or(""),
+            function.name,
+            function.chunk.get_line(0)
+        );
 
         if !self.functions.contains_key(&key) {
             let mut coverage =
@@ -196,14 +291,11 @@ impl CoverageCollector {
 
             self.functions.insert(key.clone(), coverage);
         }
+        key
+    }
 
-        // Mark all executable lines as executed since we're entering this function
-        // This is a simplification - true line coverage would track each line individually
-        if let Some(coverage) = self.functions.get_mut(&key) {
-            coverage.executed_lines = coverage.executable_lines.clone();
-        }
-
-        self.active_function = Some(key);
+    fn address(function: &Function) -> usize {
+        function as *const Function as usize
     }
 
     /// End tracking the current function
@@ -238,6 +330,63 @@ impl CoverageCollector {
         }
     }
 
+    /// Serialize the collected data as JSON, to be loaded with `from_data`
+    pub fn to_data(&self) -> String {
+        let data = CoverageData {
+            version: COVERAGE_DATA_VERSION,
+            functions: self.functions.clone(),
+        };
+        serde_json::to_string_pretty(&data).expect("coverage data serializes")
+    }
+
+    /// Load data saved by `to_data`
+    pub fn from_data(data: &str) -> Result<Self, CoverageDataError> {
+        let data: CoverageData = serde_json::from_str(data)?;
+        if data.version != COVERAGE_DATA_VERSION {
+            return Err(CoverageDataError::UnsupportedVersion(data.version));
+        }
+
+        let mut collector = Self::new();
+        for coverage in data.functions.values() {
+            if let (Some(source), Some(max_line)) = (
+                &coverage.source_file,
+                coverage.executable_lines.iter().max(),
+            ) {
+                let entry = collector.source_lines.entry(source.clone()).or_insert(0);
+                *entry = (*entry).max(*max_line);
+            }
+        }
+        collector.functions = data.functions;
+        Ok(collector)
+    }
+
+    /// Every line executed and branch outcome seen, across all functions
+    ///
+    /// Two runs covered the same code exactly when their point sets are
+    /// equal, which is what coverage-guided fuzzing compares.
+    pub fn points(&self) -> HashSet<CoveragePoint> {
+        let mut points = HashSet::new();
+        for (key, cov) in &self.functions {
+            points.extend(cov.executed_lines.iter().map(|&line| CoveragePoint::Line {
+                function: key.clone(),
+                line,
+            }));
+            for (&offset, branch) in &cov.branches {
+                for (taken, count) in [(true, branch.taken_count), (false, branch.not_taken_count)]
+                {
+                    if count > 0 {
+                        points.insert(CoveragePoint::Branch {
+                            function: key.clone(),
+                            offset,
+                            taken,
+                        });
+                    }
+                }
+            }
+        }
+        points
+    }
+
     /// Merge another collector's data into this one
     pub fn merge(&mut self, other: &CoverageCollector) {
         for (key, other_cov) in &other.functions {
@@ -266,7 +415,7 @@ impl CoverageCollector {
     pub fn by_source_file(&self) -> HashMap<String, FileCoverage> {
         let mut files: HashMap<String, FileCoverage> = HashMap::new();
 
-        for coverage in self.functions.values() {
+        for (key, coverage) in &self.functions {
             let source = coverage
                 .source_file
                 .clone()
@@ -280,8 +429,9 @@ impl CoverageCollector {
             file_cov.executed_lines.extend(&coverage.executed_lines);
 
             for (offset, branch) in &coverage.branches {
-                let key = (coverage.name.clone(), *offset);
-                file_cov.branches.insert(key, branch.clone());
+                file_cov
+                    .branches
+                    .insert((key.clone(), *offset), branch.clone());
             }
 
             file_cov.functions.push(coverage.name.clone());
@@ -339,7 +489,7 @@ pub struct FileCoverage {
     pub executable_lines: HashSet<u32>,
     /// Executed lines
     pub executed_lines: HashSet<u32>,
-    /// Branch points: (function_name, offset) -> BranchInfo
+    /// Branch points: (function key, offset) -> BranchInfo
     pub branches: HashMap<(String, usize), BranchInfo>,
     /// Functions in this file
     pub functions: Vec<String>,
@@ -457,6 +607,8 @@ pub enum CoverageFormat {
     Html,
     /// LCOV format for CI tooling
     Lcov,
+    /// Cobertura XML, as read by GitLab, Jenkins and Azure Pipelines
+    Cobertura,
 }
 
 impl std::str::FromStr for CoverageFormat {
@@ -467,6 +619,7 @@ impl std::str::FromStr for CoverageFormat {
             "summary" | "text" => Ok(CoverageFormat::Summary),
             "html" => Ok(CoverageFormat::Html),
             "lcov" => Ok(CoverageFormat::Lcov),
+            "cobertura" | "xml" => Ok(CoverageFormat::Cobertura),
             _ => Err(format!("Unknown coverage format: {}", s)),
         }
     }
@@ -482,6 +635,7 @@ pub fn generate_report(
         CoverageFormat::Summary => generate_summary_report(collector),
         CoverageFormat::Html => generate_html_report(collector, output_dir),
         CoverageFormat::Lcov => generate_lcov_report(collector),
+        CoverageFormat::Cobertura => generate_cobertura_report(collector, output_dir),
     }
 }
 
@@ -719,6 +873,110 @@ fn generate_lcov_report(collector: &CoverageCollector) -> String {
     lcov
 }
 
+/// Generate Cobertura XML report
+fn generate_cobertura_report(collector: &CoverageCollector, output_dir: Option<&Path>) -> String {
+    let summary = collector.generate_summary();
+    let mut files: Vec<FileCoverage> = collector.by_source_file().into_values().collect();
+    files.sort_by(|a, b| a.source_file.cmp(&b.source_file));
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(
+        "<!DOCTYPE coverage SYSTEM \"http://cobertura.sourceforge.net/xml/coverage-04.dtd\">\n",
+    );
+    xml.push_str(&format!(
+        "<coverage line-rate=\"{:.4}\" branch-rate=\"{:.4}\" lines-covered=\"{}\" lines-valid=\"{}\" branches-covered=\"{}\" branches-valid=\"{}\" complexity=\"0\" version=\"{}\" timestamp=\"{}\">\n",
+        summary.line_coverage_percent() / 100.0,
+        summary.branch_coverage_percent() / 100.0,
+        summary.covered_lines,
+        summary.total_lines,
+        summary.covered_branches,
+        summary.total_branches,
+        env!("CARGO_PKG_VERSION"),
+        timestamp
+    ));
+    xml.push_str("  <sources>\n    <source>.</source>\n  </sources>\n");
+    xml.push_str("  <packages>\n");
+    xml.push_str(&format!(
+        "    <package name=\"stratum\" line-rate=\"{:.4}\" branch-rate=\"{:.4}\" complexity=\"0\">\n",
+        summary.line_coverage_percent() / 100.0,
+        summary.branch_coverage_percent() / 100.0
+    ));
+    xml.push_str("      <classes>\n");
+
+    for file in &files {
+        let source = escape_xml(&file.source_file);
+        xml.push_str(&format!(
+            "        <class name=\"{}\" filename=\"{}\" line-rate=\"{:.4}\" branch-rate=\"{:.4}\" complexity=\"0\">\n",
+            source,
+            source,
+            file.line_coverage_percent() / 100.0,
+            file.branch_coverage_percent() / 100.0
+        ));
+        xml.push_str("          <methods/>\n");
+        xml.push_str("          <lines>\n");
+
+        // Branch outcomes (covered, total) per line
+        let mut line_branches: HashMap<u32, (usize, usize)> = HashMap::new();
+        for branch in file.branches.values() {
+            let entry = line_branches.entry(branch.line).or_default();
+            entry.0 +=
+                usize::from(branch.taken_count > 0) + usize::from(branch.not_taken_count > 0);
+            entry.1 += 2;
+        }
+
+        let mut lines: Vec<u32> = file.executable_lines.iter().copied().collect();
+        lines.sort_unstable();
+        for line in lines {
+            let hits = usize::from(file.executed_lines.contains(&line));
+            match line_branches.get(&line) {
+                Some(&(covered, total)) => xml.push_str(&format!(
+                    "            <line number=\"{}\" hits=\"{}\" branch=\"true\" condition-coverage=\"{}% ({}/{})\"/>\n",
+                    line,
+                    hits,
+                    covered * 100 / total,
+                    covered,
+                    total
+                )),
+                None => xml.push_str(&format!(
+                    "            <line number=\"{}\" hits=\"{}\" branch=\"false\"/>\n",
+                    line, hits
+                )),
+            }
+        }
+
+        xml.push_str("          </lines>\n");
+        xml.push_str("        </class>\n");
+    }
+
+    xml.push_str("      </classes>\n    </package>\n  </packages>\n</coverage>\n");
+
+    // Write to file if output_dir is provided
+    if let Some(dir) = output_dir {
+        let path = dir.join("coverage.xml");
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            eprintln!("Warning: Could not create output directory: {}", e);
+        } else if let Err(e) = std::fs::write(&path, &xml) {
+            eprintln!("Warning: Could not write Cobertura report: {}", e);
+        } else {
+            return format!("Cobertura report written to: {}", path.display());
+        }
+    }
+
+    xml
+}
+
+/// Escape text for an XML attribute
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
 /// Get CSS class based on coverage percentage
 fn coverage_class(percent: f64) -> &'static str {
     if percent >= 80.0 {
@@ -877,4 +1135,122 @@ mod tests {
         assert_eq!(summary.covered_lines, 3);
         assert_eq!(summary.total_functions, 1);
     }
+
+    fn run_with_coverage(source: &str) -> CoverageCollector {
+        let module = crate::Parser::parse_module(source).unwrap();
+        let function = crate::Compiler::with_source("cov.strat".to_string())
+            .compile_module(&module)
+            .unwrap();
+        let mut vm = crate::VM::new();
+        vm.enable_coverage();
+        vm.run(function).unwrap();
+        vm.take_coverage().unwrap()
+    }
+
+    #[test]
+    fn test_vm_records_lines_and_branches() {
+        let program = |call: &str| {
+            format!(
+                "fx sign(n: Int) -> Int {{\n    if n < 0 {{\n        return -1\n    }}\n    1\n}}\n{call}\n"
+            )
+        };
+
+        let positive = run_with_coverage(&program("sign(5)"));
+        let files = positive.by_source_file();
+        let file = &files["cov.strat"];
+        assert!(file.uncovered_lines().contains(&3));
+        assert!(!file.uncovered_lines().contains(&5));
+        let summary = positive.generate_summary();
+        assert_eq!((summary.covered_branches, summary.total_branches), (1, 2));
+
+        // The same function run in another VM merges into one entry
+        let mut merged = positive.clone();
+        merged.merge(&run_with_coverage(&program("sign(-1)")));
+        let summary = merged.generate_summary();
+        assert_eq!((summary.covered_branches, summary.total_branches), (2, 2));
+        assert!(!merged.by_source_file()["cov.strat"]
+            .uncovered_lines()
+            .contains(&3));
+    }
+
+    #[test]
+    fn test_cobertura_report() {
+        assert_eq!(
+            "cobertura".parse::<CoverageFormat>().unwrap(),
+            CoverageFormat::Cobertura
+        );
+
+        let mut collector = CoverageCollector::new();
+        let mut cov = FunctionCoverage::new("check".to_string(), Some("a&b.strat".to_string()));
+        cov.executable_lines = [1, 2, 3].into_iter().collect();
+        cov.executed_lines = [1, 2].into_iter().collect();
+        cov.branches.insert(
+            4,
+            BranchInfo {
+                line: 2,
+                taken_count: 3,
+                not_taken_count: 0,
+            },
+        );
+        collector.functions.insert("check".to_string(), cov);
+
+        let xml = generate_report(&collector, CoverageFormat::Cobertura, None);
+        assert!(xml.starts_with("<?xml"));
+        assert!(xml.contains("lines-covered=\"2\" lines-valid=\"3\""));
+        assert!(xml.contains("filename=\"a&amp;b.strat\""));
+        assert!(xml.contains(
+            "<line number=\"2\" hits=\"1\" branch=\"true\" condition-coverage=\"50% (1/2)\"/>"
+        ));
+        assert!(xml.contains("<line number=\"3\" hits=\"0\" branch=\"false\"/>"));
+    }
+
+    #[test]
+    fn test_coverage_data_round_trip() {
+        let mut collector = CoverageCollector::new();
+        let mut cov = FunctionCoverage::new("check".to_string(), Some("a.strat".to_string()));
+        cov.executable_lines = [1, 2, 3].into_iter().collect();
+        cov.executed_lines = [1, 2].into_iter().collect();
+        cov.branches.insert(
+            4,
+            BranchInfo {
+                line: 2,
+                taken_count: 3,
+                not_taken_count: 0,
+            },
+        );
+        collector
+            .functions
+            .insert("a.strat:check:1".to_string(), cov);
+
+        let mut other = CoverageCollector::new();
+        let mut cov = FunctionCoverage::new("check".to_string(), Some("a.strat".to_string()));
+        cov.executable_lines = [1, 2, 3].into_iter().collect();
+        cov.executed_lines = [1, 3].into_iter().collect();
+        cov.branches.insert(
+            4,
+            BranchInfo {
+                line: 2,
+                taken_count: 0,
+                not_taken_count: 1,
+            },
+        );
+        other.functions.insert("a.strat:check:1".to_string(), cov);
+
+        let mut merged = CoverageCollector::from_data(&collector.to_data()).unwrap();
+        merged.merge(&CoverageCollector::from_data(&other.to_data()).unwrap());
+        let summary = merged.generate_summary();
+        assert_eq!((summary.covered_lines, summary.total_lines), (3, 3));
+        assert_eq!((summary.covered_branches, summary.total_branches), (2, 2));
+        assert_eq!(merged.source_lines["a.strat"], 3);
+    }
+
+    #[test]
+    fn test_coverage_data_version_is_checked() {
+        let data = r#"{"version": 99, "functions": {}}"#;
+        assert!(matches!(
+            CoverageCollector::from_data(data),
+            Err(CoverageDataError::UnsupportedVersion(99))
+        ));
+        assert!(CoverageCollector::from_data("not json").is_err());
+    }
 }
@@ -3,15 +3,23 @@
 //! This module provides a Stratum-friendly wrapper around ElastiCube,
 //! enabling multi-dimensional analytical processing (OLAP) with dimensions,
 //! measures, hierarchies, and OLAP operations (slice, dice, drill-down, roll-up).
+//! Cubes built from a DataFrame remember their definition, so they can be saved
+//! to and loaded from Parquet and refreshed with appended rows.
 
 use std::fmt;
-use std::sync::Arc;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
 
 use arrow::datatypes::DataType;
+use arrow::record_batch::RecordBatch;
 use elasticube_core::{
     AggFunc, CacheStats, ElastiCube, ElastiCubeBuilder, QueryBuilder, QueryCache,
 };
 
+use super::cube_store::{
+    agg_func_name, data_type_name, read_cube, write_cube, CalculatedMeasureSpec, CubeAggregates,
+    CubeDefinition, MeasureSpec,
+};
 use super::{DataError, DataFrame, DataResult};
 
 /// OLAP Cube for multi-dimensional analytical processing
@@ -29,6 +37,10 @@ pub struct Cube {
     name: Option<String>,
     /// Shared query cache for improved performance
     cache: Option<Arc<QueryCache>>,
+    /// How the cube was built, for saving and refreshing it
+    definition: Option<Arc<CubeDefinition>>,
+    /// Measures aggregated by all dimensions, computed when first asked for
+    aggregates: Arc<OnceLock<CubeAggregates>>,
 }
 
 impl Cube {
@@ -38,6 +50,8 @@ impl Cube {
             inner: Arc::new(cube),
             name: None,
             cache: None,
+            definition: None,
+            aggregates: Arc::default(),
         }
     }
 
@@ -47,6 +61,8 @@ impl Cube {
             inner: Arc::new(cube),
             name: Some(name.into()),
             cache: None,
+            definition: None,
+            aggregates: Arc::default(),
         }
     }
 
@@ -56,6 +72,8 @@ impl Cube {
             inner: Arc::new(cube),
             name: None,
             cache: Some(Arc::new(QueryCache::new(cache_size))),
+            definition: None,
+            aggregates: Arc::default(),
         }
     }
 
@@ -69,6 +87,8 @@ impl Cube {
             inner: Arc::new(cube),
             name: Some(name.into()),
             cache: Some(Arc::new(QueryCache::new(cache_size))),
+            definition: None,
+            aggregates: Arc::default(),
         }
     }
 
@@ -78,6 +98,8 @@ impl Cube {
             inner: cube,
             name: None,
             cache: None,
+            definition: None,
+            aggregates: Arc::default(),
         }
     }
 
@@ -87,6 +109,8 @@ impl Cube {
             inner: cube,
             name: Some(name.into()),
             cache: None,
+            definition: None,
+            aggregates: Arc::default(),
         }
     }
 
@@ -365,6 +389,137 @@ impl Cube {
         })
     }
 
+    /// Get the definition the cube was built from
+    ///
+    /// Returns None for cubes wrapped directly around an ElastiCube rather
+    /// than built with a [`CubeBuilder`].
+    #[must_use]
+    pub fn definition(&self) -> Option<&CubeDefinition> {
+        self.definition.as_deref()
+    }
+
+    fn require_definition(&self, operation: &str) -> DataResult<&CubeDefinition> {
+        self.definition().ok_or_else(|| {
+            DataError::Cube(format!(
+                "{operation} needs a cube built from a DataFrame with a CubeBuilder"
+            ))
+        })
+    }
+
+    /// Save the cube's rows and definition to a Parquet file
+    ///
+    /// The file is a regular Parquet file that other tools can read; the
+    /// cube definition is stored in its key-value metadata.
+    ///
+    /// # Example
+    /// ```ignore
+    /// cube.save("sales.cube.parquet")
+    /// let cube = Cube.load("sales.cube.parquet")
+    /// ```
+    pub fn save(&self, path: impl AsRef<Path>) -> DataResult<()> {
+        let definition = self.require_definition("save")?;
+        write_cube(
+            path.as_ref(),
+            definition,
+            self.inner.arrow_schema().clone(),
+            self.inner.data(),
+        )
+    }
+
+    /// Load a cube saved with [`Cube::save`]
+    ///
+    /// The cube is built again from the saved rows with the saved dimensions,
+    /// measures, hierarchies, calculated measures and cache setting.
+    pub fn load(path: impl AsRef<Path>) -> DataResult<Cube> {
+        let (definition, df) = read_cube(path.as_ref())?;
+        let builder = CubeBuilder::from_batches(
+            definition.name.clone(),
+            df.schema().clone(),
+            df.batches().to_vec(),
+        )?;
+        definition.apply(builder)?.build()
+    }
+
+    /// Append new rows to the cube, returning the refreshed cube
+    ///
+    /// `df` must have the cube's columns with the same types. The cube's
+    /// existing record batches are shared with the refreshed cube rather
+    /// than copied. If the cube's aggregates have been computed, only the
+    /// appended rows are folded into them. The query cache starts empty,
+    /// since cached results no longer hold.
+    ///
+    /// The refreshed cube is built from all of its batches, so a refresh
+    /// costs time in proportion to the whole cube, not to the appended rows.
+    pub fn refresh(&self, df: &DataFrame) -> DataResult<Cube> {
+        let definition = self.require_definition("refresh")?;
+        let schema = self.inner.arrow_schema().clone();
+
+        let fields = schema.fields();
+        let new_fields = df.schema().fields();
+        let same_columns = fields.len() == new_fields.len()
+            && fields
+                .iter()
+                .zip(new_fields.iter())
+                .all(|(a, b)| a.name() == b.name() && a.data_type() == b.data_type());
+        if !same_columns {
+            let names = |fields: &arrow::datatypes::Fields| {
+                fields
+                    .iter()
+                    .map(|f| format!("{}: {}", f.name(), f.data_type()))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            return Err(DataError::SchemaMismatch(format!(
+                "refresh expects columns [{}], got [{}]",
+                names(fields),
+                names(new_fields)
+            )));
+        }
+
+        let appended = df
+            .batches()
+            .iter()
+            .map(|batch| RecordBatch::try_new(schema.clone(), batch.columns().to_vec()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut batches = self.inner.data().to_vec();
+        batches.extend(appended.iter().cloned());
+
+        let builder = CubeBuilder::from_batches(self.name.clone(), schema, batches)?;
+        let cube = definition.apply(builder)?.build()?;
+
+        if let Some(existing) = self.aggregates.get() {
+            let mut aggregates = existing.clone();
+            for batch in &appended {
+                aggregates.add_batch(batch)?;
+            }
+            let _ = cube.aggregates.set(aggregates);
+        }
+        Ok(cube)
+    }
+
+    /// Get every measure aggregated by all of the cube's dimensions
+    ///
+    /// Returns one row per combination of dimension values, in the order
+    /// the combinations first appear. Calculated measures are not included.
+    /// The aggregates are computed on first use and kept with the cube.
+    pub fn aggregates(&self) -> DataResult<DataFrame> {
+        if self.aggregates.get().is_none() {
+            let definition = self.require_definition("aggregates")?;
+            let aggregates = CubeAggregates::from_batches(definition, self.inner.data())?;
+            let _ = self.aggregates.set(aggregates);
+        }
+        self.aggregates
+            .get()
+            .ok_or(DataError::EmptyData)?
+            .to_dataframe()
+    }
+
+    /// Whether the cube's aggregates have been computed
+    #[must_use]
+    pub fn has_aggregates(&self) -> bool {
+        self.aggregates.get().is_some()
+    }
+
     /// Create a Cube from a DataFrame using a builder
     ///
     /// This starts the process of converting a DataFrame to a Cube.
@@ -428,42 +583,36 @@ pub struct CubeBuilder {
     name: Option<String>,
     /// Cache configuration (None = no caching, Some(size) = enable with size)
     cache_size: Option<usize>,
+    /// Everything added so far, kept with the built cube
+    definition: CubeDefinition,
 }
 
 impl CubeBuilder {
     /// Create a new CubeBuilder from a DataFrame
     pub fn from_dataframe(df: &DataFrame) -> DataResult<Self> {
-        let batches = df.batches().to_vec();
-        if batches.is_empty() {
-            return Err(DataError::EmptyData);
-        }
-
-        let schema = df.schema().clone();
-        let builder = ElastiCubeBuilder::new("cube");
-
-        // Load the data into the builder
-        let builder = builder
-            .load_record_batches(schema.clone(), batches)
-            .map_err(|e| DataError::Cube(e.to_string()))?;
-
-        Ok(Self {
-            builder,
-            schema,
-            name: None,
-            cache_size: None,
-        })
+        Self::from_batches(None, df.schema().clone(), df.batches().to_vec())
     }
 
     /// Create a new CubeBuilder from a DataFrame with a name
     pub fn from_dataframe_with_name(name: impl Into<String>, df: &DataFrame) -> DataResult<Self> {
-        let name_str = name.into();
-        let batches = df.batches().to_vec();
+        Self::from_batches(
+            Some(name.into()),
+            df.schema().clone(),
+            df.batches().to_vec(),
+        )
+    }
+
+    /// Create a new CubeBuilder over record batches sharing a schema
+    fn from_batches(
+        name: Option<String>,
+        schema: Arc<arrow::datatypes::Schema>,
+        batches: Vec<RecordBatch>,
+    ) -> DataResult<Self> {
         if batches.is_empty() {
             return Err(DataError::EmptyData);
         }
 
-        let schema = df.schema().clone();
-        let builder = ElastiCubeBuilder::new(&name_str);
+        let builder = ElastiCubeBuilder::new(name.as_deref().unwrap_or("cube"));
 
         // Load the data into the builder
         let builder = builder
@@ -473,7 +622,8 @@ impl CubeBuilder {
         Ok(Self {
             builder,
             schema,
-            name: Some(name_str),
+            definition: CubeDefinition::new(name.clone()),
+            name,
             cache_size: None,
         })
     }
@@ -489,8 +639,9 @@ impl CubeBuilder {
     /// Add a dimension to the cube by column name
     ///
     /// The column's data type is looked up from the DataFrame schema.
-    pub fn dimension(self, name: &str) -> DataResult<Self> {
+    pub fn dimension(mut self, name: &str) -> DataResult<Self> {
         let data_type = self.get_column_type(name)?;
+        self.definition.dimensions.push(name.to_string());
         let builder = self
             .builder
             .add_dimension(name, data_type)
@@ -500,14 +651,19 @@ impl CubeBuilder {
             schema: self.schema,
             name: self.name,
             cache_size: self.cache_size,
+            definition: self.definition,
         })
     }
 
     /// Add a measure to the cube with a specific aggregation function
     ///
     /// The column's data type is looked up from the DataFrame schema.
-    pub fn measure(self, name: &str, agg_func: AggFunc) -> DataResult<Self> {
+    pub fn measure(mut self, name: &str, agg_func: AggFunc) -> DataResult<Self> {
         let data_type = self.get_column_type(name)?;
+        self.definition.measures.push(MeasureSpec {
+            name: name.to_string(),
+            agg: agg_func_name(&agg_func)?.to_string(),
+        });
         let builder = self
             .builder
             .add_measure(name, data_type, agg_func)
@@ -517,6 +673,7 @@ impl CubeBuilder {
             schema: self.schema,
             name: self.name,
             cache_size: self.cache_size,
+            definition: self.definition,
         })
     }
 
@@ -524,8 +681,11 @@ impl CubeBuilder {
     ///
     /// A hierarchy defines a drill-down path through dimensions.
     /// For example: `["year", "quarter", "month"]` for time-based analysis.
-    pub fn hierarchy(self, name: &str, levels: &[&str]) -> DataResult<Self> {
+    pub fn hierarchy(mut self, name: &str, levels: &[&str]) -> DataResult<Self> {
         let levels_vec: Vec<String> = levels.iter().map(|s| (*s).to_string()).collect();
+        self.definition
+            .hierarchies
+            .push((name.to_string(), levels_vec.clone()));
         let builder = self
             .builder
             .add_hierarchy(name, levels_vec)
@@ -535,6 +695,7 @@ impl CubeBuilder {
             schema: self.schema,
             name: self.name,
             cache_size: self.cache_size,
+            definition: self.definition,
         })
     }
 
@@ -560,6 +721,10 @@ impl CubeBuilder {
             schema: self.schema,
             name: self.name,
             cache_size: Some(size),
+            definition: CubeDefinition {
+                cache_size: Some(size),
+                ..self.definition
+            },
         }
     }
 
@@ -601,12 +766,20 @@ impl CubeBuilder {
     ///     |> build()
     /// ```
     pub fn calculated_measure_with_type(
-        self,
+        mut self,
         name: &str,
         expression: &str,
         data_type: DataType,
         agg_func: AggFunc,
     ) -> DataResult<Self> {
+        self.definition
+            .calculated_measures
+            .push(CalculatedMeasureSpec {
+                name: name.to_string(),
+                expression: expression.to_string(),
+                data_type: data_type_name(&data_type)?.to_string(),
+                agg: agg_func_name(&agg_func)?.to_string(),
+            });
         let builder = self
             .builder
             .add_calculated_measure(name, expression, data_type, agg_func)
@@ -616,6 +789,7 @@ impl CubeBuilder {
             schema: self.schema,
             name: self.name,
             cache_size: self.cache_size,
+            definition: self.definition,
         })
     }
 
@@ -627,12 +801,13 @@ impl CubeBuilder {
             .map_err(|e| DataError::Cube(e.to_string()))?;
 
         // Create the cube with optional caching
-        let result = match (self.name, self.cache_size) {
+        let mut result = match (self.name, self.cache_size) {
             (Some(name), Some(size)) => Cube::with_name_and_cache(cube, name, size),
             (Some(name), None) => Cube::with_name(cube, name),
             (None, Some(size)) => Cube::with_cache(cube, size),
             (None, None) => Cube::new(cube),
         };
+        result.definition = Some(Arc::new(self.definition));
 
         Ok(result)
     }
@@ -2308,4 +2483,117 @@ mod tests {
         let cube_with_cache = cube_no_cache.enable_cache(50);
         assert!(cube_with_cache.has_cache());
     }
+
+    fn create_sales_cube(df: &DataFrame) -> Cube {
+        Cube::from_dataframe_with_name("sales", df)
+            .unwrap()
+            .cache_enabled(10)
+            .dimension("region")
+            .unwrap()
+            .measure("revenue", AggFunc::Sum)
+            .unwrap()
+            .hierarchy("geo", &["region"])
+            .unwrap()
+            .calculated_measure("double", "revenue * 2")
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    fn revenue_column(df: &DataFrame) -> Vec<f64> {
+        df.batches()
+            .iter()
+            .flat_map(|batch| {
+                batch
+                    .column_by_name("revenue")
+                    .unwrap()
+                    .as_any()
+                    .downcast_ref::<Float64Array>()
+                    .unwrap()
+                    .values()
+                    .to_vec()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_cube_records_definition() {
+        let cube = create_sales_cube(&create_test_dataframe());
+        let definition = cube.definition().unwrap();
+        assert_eq!(definition.name.as_deref(), Some("sales"));
+        assert_eq!(definition.dimensions, vec!["region"]);
+        assert_eq!(definition.measures[0].agg, "sum");
+        assert_eq!(definition.calculated_measures[0].data_type, "float64");
+        assert_eq!(definition.cache_size, Some(10));
+    }
+
+    #[test]
+    fn test_cube_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sales.cube.parquet");
+        let cube = create_sales_cube(&create_test_dataframe());
+        cube.save(&path).unwrap();
+
+        let loaded = Cube::load(&path).unwrap();
+        assert_eq!(loaded.name(), Some("sales"));
+        assert_eq!(loaded.row_count(), 4);
+        assert_eq!(loaded.dimension_names(), vec!["region"]);
+        assert!(loaded.has_measure("revenue"));
+        assert_eq!(loaded.hierarchy_names(), vec!["geo"]);
+        assert!(loaded.has_cache());
+        assert_eq!(loaded.definition(), cube.definition());
+
+        // The saved rows are a plain Parquet file
+        let rows = super::super::read_parquet(&path).unwrap();
+        assert_eq!(revenue_column(&rows), vec![100.0, 200.0, 150.0, 175.0]);
+
+        assert!(Cube::load(dir.path().join("missing.parquet")).is_err());
+    }
+
+    #[test]
+    fn test_cube_refresh_appends_rows() {
+        let cube = create_sales_cube(&create_test_dataframe());
+        let before = cube.aggregates().unwrap();
+        assert_eq!(revenue_column(&before), vec![100.0, 200.0, 150.0, 175.0]);
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("region", arrow::datatypes::DataType::Utf8, false),
+            Field::new("revenue", arrow::datatypes::DataType::Float64, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(StringArray::from(vec!["North", "Central"])),
+                Arc::new(Float64Array::from(vec![50.0, 80.0])),
+            ],
+        )
+        .unwrap();
+        let refreshed = cube.refresh(&DataFrame::from_batch(batch)).unwrap();
+
+        assert_eq!(refreshed.row_count(), 6);
+        assert_eq!(cube.row_count(), 4);
+        assert_eq!(refreshed.definition(), cube.definition());
+        // Aggregates carried over and updated with only the new rows
+        assert!(refreshed.has_aggregates());
+        assert_eq!(
+            revenue_column(&refreshed.aggregates().unwrap()),
+            vec![150.0, 200.0, 150.0, 175.0, 80.0]
+        );
+    }
+
+    #[test]
+    fn test_cube_refresh_rejects_other_columns() {
+        let cube = create_sales_cube(&create_test_dataframe());
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "region",
+            arrow::datatypes::DataType::Utf8,
+            false,
+        )]));
+        let batch =
+            RecordBatch::try_new(schema, vec![Arc::new(StringArray::from(vec!["North"]))]).unwrap();
+        let result = cube.refresh(&DataFrame::from_batch(batch));
+        assert!(matches!(result, Err(DataError::SchemaMismatch(_))));
+        // Not computed by a failed refresh
+        assert!(!cube.has_aggregates());
+    }
 }
@@ -0,0 +1,614 @@
+//! Cube persistence and incremental aggregation
+//!
+//! A saved cube is a Parquet file holding the cube's rows, with the cube's
+//! definition (dimensions, measures, hierarchies and calculated measures)
+//! stored as JSON in the file's key-value metadata. Loading reads the rows
+//! back and builds the cube again from that definition.
+//!
+//! [`CubeAggregates`] holds every measure aggregated by all of the cube's
+//! dimensions as partial states, so rows appended by `Cube::refresh` are
+//! folded into the existing totals instead of aggregating the whole cube
+//! again.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::{Array, ArrayRef, Float64Array, Int64Array, RecordBatch};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::util::display::{ArrayFormatter, FormatOptions};
+use elasticube_core::AggFunc;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use parquet::file::metadata::KeyValue;
+use parquet::file::properties::WriterProperties;
+use serde::{Deserialize, Serialize};
+
+use super::{CubeBuilder, DataError, DataFrame, DataResult};
+
+/// Parquet metadata key the cube definition is stored under
+pub const CUBE_METADATA_KEY: &str = "stratum.cube";
+
+/// Version of the saved cube format
+const FORMAT_VERSION: u32 = 1;
+
+/// A measure aggregated from a column
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MeasureSpec {
+    pub name: String,
+    /// Aggregation function name, as accepted by `measure(name, agg)`
+    pub agg: String,
+}
+
+/// A measure computed from an expression over other measures
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CalculatedMeasureSpec {
+    pub name: String,
+    pub expression: String,
+    pub data_type: String,
+    pub agg: String,
+}
+
+/// Everything needed to build a cube again from its rows
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CubeDefinition {
+    pub version: u32,
+    pub name: Option<String>,
+    pub dimensions: Vec<String>,
+    pub measures: Vec<MeasureSpec>,
+    pub calculated_measures: Vec<CalculatedMeasureSpec>,
+    pub hierarchies: Vec<(String, Vec<String>)>,
+    pub cache_size: Option<usize>,
+}
+
+impl CubeDefinition {
+    /// An empty definition for a cube with an optional name
+    #[must_use]
+    pub fn new(name: Option<String>) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            name,
+            ..Self::default()
+        }
+    }
+
+    /// Replay the definition onto a builder holding the cube's rows
+    pub fn apply(&self, mut builder: CubeBuilder) -> DataResult<CubeBuilder> {
+        for dimension in &self.dimensions {
+            builder = builder.dimension(dimension)?;
+        }
+        for measure in &self.measures {
+            builder = builder.measure(&measure.name, parse_agg_func(&measure.agg)?)?;
+        }
+        for (name, levels) in &self.hierarchies {
+            let levels: Vec<&str> = levels.iter().map(String::as_str).collect();
+            builder = builder.hierarchy(name, &levels)?;
+        }
+        for calculated in &self.calculated_measures {
+            builder = builder.calculated_measure_with_type(
+                &calculated.name,
+                &calculated.expression,
+                parse_data_type(&calculated.data_type)?,
+                parse_agg_func(&calculated.agg)?,
+            )?;
+        }
+        if let Some(size) = self.cache_size {
+            builder = builder.cache_enabled(size);
+        }
+        Ok(builder)
+    }
+
+    /// The measures with their parsed aggregation functions
+    fn aggregations(&self) -> DataResult<Vec<(String, AggFunc)>> {
+        self.measures
+            .iter()
+            .map(|m| Ok((m.name.clone(), parse_agg_func(&m.agg)?)))
+            .collect()
+    }
+}
+
+/// The name `measure(name, agg)` accepts for an aggregation function
+pub fn agg_func_name(agg: &AggFunc) -> DataResult<&'static str> {
+    Ok(match agg {
+        AggFunc::Sum => "sum",
+        AggFunc::Avg => "avg",
+        AggFunc::Min => "min",
+        AggFunc::Max => "max",
+        AggFunc::Count => "count",
+        AggFunc::CountDistinct => "count_distinct",
+        AggFunc::Median => "median",
+        AggFunc::StdDev => "stddev",
+        AggFunc::Variance => "variance",
+        AggFunc::First => "first",
+        AggFunc::Last => "last",
+        #[allow(unreachable_patterns)]
+        _ => return Err(DataError::Cube("aggregation can't be saved".to_string())),
+    })
+}
+
+/// Parse an aggregation function name written by [`agg_func_name`]
+pub fn parse_agg_func(name: &str) -> DataResult<AggFunc> {
+    Ok(match name {
+        "sum" => AggFunc::Sum,
+        "avg" => AggFunc::Avg,
+        "min" => AggFunc::Min,
+        "max" => AggFunc::Max,
+        "count" => AggFunc::Count,
+        "count_distinct" => AggFunc::CountDistinct,
+        "median" => AggFunc::Median,
+        "stddev" => AggFunc::StdDev,
+        "variance" => AggFunc::Variance,
+        "first" => AggFunc::First,
+        "last" => AggFunc::Last,
+        other => return Err(DataError::Cube(format!("unknown aggregation '{other}'"))),
+    })
+}
+
+/// The name of a calculated measure's data type
+pub fn data_type_name(data_type: &DataType) -> DataResult<&'static str> {
+    match data_type {
+        DataType::Float64 => Ok("float64"),
+        DataType::Float32 => Ok("float32"),
+        DataType::Int64 => Ok("int64"),
+        DataType::Int32 => Ok("int32"),
+        other => Err(DataError::Cube(format!(
+            "calculated measure type {other} can't be saved"
+        ))),
+    }
+}
+
+fn parse_data_type(name: &str) -> DataResult<DataType> {
+    match name {
+        "float64" => Ok(DataType::Float64),
+        "float32" => Ok(DataType::Float32),
+        "int64" => Ok(DataType::Int64),
+        "int32" => Ok(DataType::Int32),
+        other => Err(DataError::Cube(format!("unknown data type '{other}'"))),
+    }
+}
+
+/// Write a cube's rows and definition to a Parquet file
+pub fn write_cube(
+    path: &Path,
+    definition: &CubeDefinition,
+    schema: SchemaRef,
+    batches: &[RecordBatch],
+) -> DataResult<()> {
+    let json = serde_json::to_string(definition)
+        .map_err(|e| DataError::Cube(format!("failed to encode cube definition: {e}")))?;
+    let properties = WriterProperties::builder()
+        .set_key_value_metadata(Some(vec![KeyValue::new(
+            CUBE_METADATA_KEY.to_string(),
+            json,
+        )]))
+        .build();
+
+    let file = File::create(path)
+        .map_err(|e| DataError::Io(format!("failed to create file '{}': {e}", path.display())))?;
+    let mut writer = ArrowWriter::try_new(file, schema, Some(properties))
+        .map_err(|e| DataError::Parquet(format!("failed to create writer: {e}")))?;
+    for batch in batches {
+        writer
+            .write(batch)
+            .map_err(|e| DataError::Parquet(format!("failed to write batch: {e}")))?;
+    }
+    writer
+        .close()
+        .map_err(|e| DataError::Parquet(format!("failed to close writer: {e}")))?;
+    Ok(())
+}
+
+/// Read a cube's rows and definition from a file written by [`write_cube`]
+pub fn read_cube(path: &Path) -> DataResult<(CubeDefinition, DataFrame)> {
+    let file = File::open(path)
+        .map_err(|e| DataError::Io(format!("failed to open file '{}': {e}", path.display())))?;
+    let builder = ParquetRecordBatchReaderBuilder::try_new(file)
+        .map_err(|e| DataError::Parquet(format!("failed to read parquet: {e}")))?;
+
+    let json = builder
+        .metadata()
+        .file_metadata()
+        .key_value_metadata()
+        .and_then(|entries| entries.iter().find(|kv| kv.key == CUBE_METADATA_KEY))
+        .and_then(|kv| kv.value.clone())
+        .ok_or_else(|| DataError::Cube(format!("'{}' is not a saved cube", path.display())))?;
+    let definition: CubeDefinition = serde_json::from_str(&json)
+        .map_err(|e| DataError::Cube(format!("invalid cube definition: {e}")))?;
+    if definition.version > FORMAT_VERSION {
+        return Err(DataError::Cube(format!(
+            "'{}' was saved by a newer version of Stratum (format {})",
+            path.display(),
+            definition.version
+        )));
+    }
+
+    let schema = builder.schema().clone();
+    let batches = builder
+        .build()
+        .map_err(|e| DataError::Parquet(format!("failed to build reader: {e}")))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| DataError::Parquet(format!("failed to read batches: {e}")))?;
+    Ok((definition, DataFrame::from_batches(schema, batches)?))
+}
+
+/// Running state of one measure within one group
+#[derive(Debug, Clone, Default)]
+struct Partial {
+    count: u64,
+    sum: f64,
+    /// Running mean and sum of squared deviations (Welford)
+    mean: f64,
+    m2: f64,
+    min: Option<f64>,
+    max: Option<f64>,
+    first: Option<f64>,
+    last: Option<f64>,
+    /// All values, kept only for medians
+    values: Vec<f64>,
+    /// Distinct values by bit pattern, kept only for distinct counts
+    distinct: HashSet<u64>,
+}
+
+impl Partial {
+    #[allow(clippy::cast_precision_loss)]
+    fn add(&mut self, agg: &AggFunc, x: f64) {
+        self.count += 1;
+        self.sum += x;
+        let delta = x - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (x - self.mean);
+        self.min = Some(self.min.map_or(x, |m| m.min(x)));
+        self.max = Some(self.max.map_or(x, |m| m.max(x)));
+        self.first.get_or_insert(x);
+        self.last = Some(x);
+        match agg {
+            AggFunc::Median => self.values.push(x),
+            AggFunc::CountDistinct => {
+                // Treat -0.0 and 0.0 as the same value
+                self.distinct.insert((x + 0.0).to_bits());
+            }
+            _ => {}
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn finish(&self, agg: &AggFunc) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+        match agg {
+            AggFunc::Sum => Some(self.sum),
+            AggFunc::Avg => Some(self.mean),
+            AggFunc::Min => self.min,
+            AggFunc::Max => self.max,
+            AggFunc::First => self.first,
+            AggFunc::Last => self.last,
+            AggFunc::Variance | AggFunc::StdDev => {
+                if self.count < 2 {
+                    return None;
+                }
+                let variance = self.m2 / (self.count - 1) as f64;
+                Some(if matches!(agg, AggFunc::StdDev) {
+                    variance.sqrt()
+                } else {
+                    variance
+                })
+            }
+            AggFunc::Median => {
+                let mut values = self.values.clone();
+                values.sort_by(f64::total_cmp);
+                let mid = values.len() / 2;
+                Some(if values.len() % 2 == 0 {
+                    (values[mid - 1] + values[mid]) / 2.0
+                } else {
+                    values[mid]
+                })
+            }
+            _ => Some(self.sum),
+        }
+    }
+}
+
+/// Every measure of a cube aggregated by all of its dimensions
+///
+/// Groups keep their first-seen order. Each group keeps one row of its
+/// dimension values, so the result has the same dimension types as the cube.
+#[derive(Debug, Clone)]
+pub struct CubeAggregates {
+    dimensions: Vec<String>,
+    measures: Vec<(String, AggFunc)>,
+    index: HashMap<Vec<Option<String>>, usize>,
+    keys: Vec<Vec<ArrayRef>>,
+    states: Vec<Vec<Partial>>,
+    dimension_fields: Option<Vec<Field>>,
+}
+
+impl CubeAggregates {
+    /// Empty aggregates for a cube definition
+    pub fn new(definition: &CubeDefinition) -> DataResult<Self> {
+        Ok(Self {
+            dimensions: definition.dimensions.clone(),
+            measures: definition.aggregations()?,
+            index: HashMap::new(),
+            keys: Vec::new(),
+            states: Vec::new(),
+            dimension_fields: None,
+        })
+    }
+
+    /// Aggregate a set of rows
+    pub fn from_batches(definition: &CubeDefinition, batches: &[RecordBatch]) -> DataResult<Self> {
+        let mut aggregates = Self::new(definition)?;
+        for batch in batches {
+            aggregates.add_batch(batch)?;
+        }
+        Ok(aggregates)
+    }
+
+    /// Number of distinct dimension combinations seen
+    #[must_use]
+    pub fn group_count(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Fold a batch of rows into the aggregates
+    pub fn add_batch(&mut self, batch: &RecordBatch) -> DataResult<()> {
+        let column = |name: &str| {
+            batch
+                .column_by_name(name)
+                .cloned()
+                .ok_or_else(|| DataError::ColumnNotFound(name.to_string()))
+        };
+        let dimensions = self
+            .dimensions
+            .iter()
+            .map(|name| column(name))
+            .collect::<DataResult<Vec<_>>>()?;
+        let measures = self
+            .measures
+            .iter()
+            .map(|(name, _)| {
+                let values = arrow::compute::cast(&column(name)?, &DataType::Float64)
+                    .map_err(|_| DataError::Cube(format!("measure '{name}' is not numeric")))?;
+                values
+                    .as_any()
+                    .downcast_ref::<Float64Array>()
+                    .cloned()
+                    .ok_or_else(|| DataError::Cube(format!("measure '{name}' is not numeric")))
+            })
+            .collect::<DataResult<Vec<_>>>()?;
+
+        if self.dimension_fields.is_none() {
+            let schema = batch.schema();
+            let fields = self
+                .dimensions
+                .iter()
+                .map(|name| Ok(schema.field_with_name(name)?.clone()))
+                .collect::<DataResult<Vec<_>>>()?;
+            self.dimension_fields = Some(fields);
+        }
+
+        let options = FormatOptions::default();
+        let formatters = dimensions
+            .iter()
+            .map(|array| ArrayFormatter::try_new(array.as_ref(), &options))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for row in 0..batch.num_rows() {
+            let key: Vec<Option<String>> = dimensions
+                .iter()
+                .zip(&formatters)
+                .map(|(array, formatter)| {
+                    array
+                        .is_valid(row)
+                        .then(|| formatter.value(row).to_string())
+                })
+                .collect();
+            let group = match self.index.get(&key) {
+                Some(&group) => group,
+                None => {
+                    let group = self.keys.len();
+                    self.index.insert(key, group);
+                    self.keys
+                        .push(dimensions.iter().map(|array| array.slice(row, 1)).collect());
+                    self.states
+                        .push(vec![Partial::default(); self.measures.len()]);
+                    group
+                }
+            };
+            for ((values, (_, agg)), state) in measures
+                .iter()
+                .zip(&self.measures)
+                .zip(&mut self.states[group])
+            {
+                if values.is_valid(row) {
+                    state.add(agg, values.value(row));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The aggregates as a DataFrame, one row per dimension combination
+    pub fn to_dataframe(&self) -> DataResult<DataFrame> {
+        let Some(dimension_fields) = &self.dimension_fields else {
+            return Err(DataError::EmptyData);
+        };
+
+        let mut fields = dimension_fields.clone();
+        let mut columns: Vec<ArrayRef> = Vec::new();
+        for d in 0..self.dimensions.len() {
+            let parts: Vec<&dyn Array> = self.keys.iter().map(|key| key[d].as_ref()).collect();
+            columns.push(arrow::compute::concat(&parts)?);
+        }
+
+        for (m, (name, agg)) in self.measures.iter().enumerate() {
+            if matches!(agg, AggFunc::Count | AggFunc::CountDistinct) {
+                let counts: Int64Array = self
+                    .states
+                    .iter()
+                    .map(|states| {
+                        let state = &states[m];
+                        let count = if matches!(agg, AggFunc::Count) {
+                            i64::try_from(state.count)
+                        } else {
+                            i64::try_from(state.distinct.len())
+                        };
+                        Some(count.unwrap_or(i64::MAX))
+                    })
+                    .collect();
+                fields.push(Field::new(name, DataType::Int64, false));
+                columns.push(Arc::new(counts));
+            } else {
+                let values: Float64Array = self
+                    .states
+                    .iter()
+                    .map(|states| states[m].finish(agg))
+                    .collect();
+                fields.push(Field::new(name, DataType::Float64, true));
+                columns.push(Arc::new(values));
+            }
+        }
+
+        let schema = Arc::new(Schema::new(fields));
+        let batch = RecordBatch::try_new(schema.clone(), columns)?;
+        DataFrame::from_batches(schema, vec![batch])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::StringArray;
+
+    fn batch(regions: Vec<&str>, revenue: Vec<f64>) -> RecordBatch {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("region", DataType::Utf8, false),
+            Field::new("revenue", DataType::Float64, false),
+        ]));
+        RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(StringArray::from(regions)),
+                Arc::new(Float64Array::from(revenue)),
+            ],
+        )
+        .unwrap()
+    }
+
+    fn definition(aggs: &[&str]) -> CubeDefinition {
+        let mut definition = CubeDefinition::new(Some("sales".to_string()));
+        definition.dimensions = vec!["region".to_string()];
+        definition.measures = aggs
+            .iter()
+            .map(|agg| MeasureSpec {
+                name: "revenue".to_string(),
+                agg: (*agg).to_string(),
+            })
+            .collect();
+        definition
+    }
+
+    fn column(df: &DataFrame, index: usize) -> Vec<Option<f64>> {
+        let batch = &df.batches()[0];
+        let values = arrow::compute::cast(batch.column(index), &DataType::Float64).unwrap();
+        let values = values.as_any().downcast_ref::<Float64Array>().unwrap();
+        (0..values.len())
+            .map(|i| values.is_valid(i).then(|| values.value(i)))
+            .collect()
+    }
+
+    #[test]
+    fn test_agg_func_names_round_trip() {
+        for name in [
+            "sum",
+            "avg",
+            "min",
+            "max",
+            "count",
+            "count_distinct",
+            "median",
+            "stddev",
+            "variance",
+            "first",
+            "last",
+        ] {
+            let agg = parse_agg_func(name).unwrap();
+            assert_eq!(agg_func_name(&agg).unwrap(), name);
+        }
+        assert!(parse_agg_func("mode").is_err());
+        assert_eq!(data_type_name(&DataType::Int32).unwrap(), "int32");
+        assert!(data_type_name(&DataType::Utf8).is_err());
+    }
+
+    #[test]
+    fn test_definition_json_round_trip() {
+        let mut definition = definition(&["sum"]);
+        definition.hierarchies = vec![("geo".to_string(), vec!["region".to_string()])];
+        definition.calculated_measures = vec![CalculatedMeasureSpec {
+            name: "double".to_string(),
+            expression: "revenue * 2".to_string(),
+            data_type: "float64".to_string(),
+            agg: "sum".to_string(),
+        }];
+        definition.cache_size = Some(10);
+        let json = serde_json::to_string(&definition).unwrap();
+        assert_eq!(
+            serde_json::from_str::<CubeDefinition>(&json).unwrap(),
+            definition
+        );
+    }
+
+    #[test]
+    fn test_aggregates_fold_appended_rows() {
+        let definition = definition(&["sum"]);
+        let first = batch(vec!["North", "South", "North"], vec![100.0, 200.0, 50.0]);
+        let mut aggregates = CubeAggregates::from_batches(&definition, &[first.clone()]).unwrap();
+        assert_eq!(aggregates.group_count(), 2);
+
+        let appended = batch(vec!["East", "North"], vec![75.0, 25.0]);
+        aggregates.add_batch(&appended).unwrap();
+        assert_eq!(aggregates.group_count(), 3);
+
+        let df = aggregates.to_dataframe().unwrap();
+        assert_eq!(column(&df, 1), vec![Some(175.0), Some(200.0), Some(75.0)]);
+
+        // Same result as aggregating all rows at once
+        let rebuilt = CubeAggregates::from_batches(&definition, &[first, appended]).unwrap();
+        assert_eq!(column(&rebuilt.to_dataframe().unwrap(), 1), column(&df, 1));
+    }
+
+    #[test]
+    fn test_aggregate_functions() {
+        let rows = batch(vec!["A", "A", "A", "A", "B"], vec![4.0, 1.0, 4.0, 3.0, 9.0]);
+        for (agg, expected) in [
+            ("avg", [Some(3.0), Some(9.0)]),
+            ("min", [Some(1.0), Some(9.0)]),
+            ("max", [Some(4.0), Some(9.0)]),
+            ("count", [Some(4.0), Some(1.0)]),
+            ("count_distinct", [Some(3.0), Some(1.0)]),
+            ("median", [Some(3.5), Some(9.0)]),
+            // Sample variance of 4, 1, 4, 3 is 2; a single value has none
+            ("variance", [Some(2.0), None]),
+            ("first", [Some(4.0), Some(9.0)]),
+            ("last", [Some(3.0), Some(9.0)]),
+        ] {
+            let df = CubeAggregates::from_batches(&definition(&[agg]), &[rows.clone()])
+                .unwrap()
+                .to_dataframe()
+                .unwrap();
+            assert_eq!(column(&df, 1), expected.to_vec(), "{agg}");
+        }
+    }
+
+    #[test]
+    fn test_read_rejects_plain_parquet() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("plain.parquet");
+        let df = DataFrame::from_batch(batch(vec!["North"], vec![1.0]));
+        super::super::write_parquet(&df, &path).unwrap();
+        let err = read_cube(&path).unwrap_err();
+        assert!(err.to_string().contains("is not a saved cube"));
+    }
+}
@@ -5,11 +5,14 @@ use std::sync::Arc;
 
 use std::collections::HashSet;
 
-use arrow::array::{RecordBatch, UInt32Array};
+use arrow::array::{Array, RecordBatch, UInt32Array, UInt64Array};
+use arrow::compute::kernels::sort::LexicographicalComparator;
 use arrow::compute::{lexsort_to_indices, take, SortColumn, SortOptions};
 use arrow::datatypes::{Field, Schema, SchemaRef};
+use rayon::prelude::*;
 
 use super::error::{DataError, DataResult};
+use super::parallel::{install, should_parallelize};
 use super::series::Series;
 use crate::bytecode::Value;
 
@@ -311,18 +314,17 @@ impl DataFrame {
         let columns: Vec<_> = (0..num_cols).map(|i| self.column_by_index(i)).collect();
 
         (0..self.num_rows()).map(move |row_idx| {
-            use std::cell::RefCell;
-            use std::collections::HashMap;
+            use crate::bytecode::ValueMap;
             use std::rc::Rc;
 
-            let mut row_map = HashMap::new();
+            let mut row_map = ValueMap::new();
             for (col_idx, col_name) in col_names.iter().enumerate() {
                 let col = columns[col_idx].as_ref().map_err(|e| e.clone())?;
                 let val = col.get(row_idx)?;
                 let key = crate::bytecode::HashableValue::String(Rc::new(col_name.clone()));
                 row_map.insert(key, val);
             }
-            Ok(Value::Map(Rc::new(RefCell::new(row_map))))
+            Ok(Value::map(row_map))
         })
     }
 
@@ -441,18 +443,23 @@ impl DataFrame {
             });
         }
 
-        // Get sorted indices
-        let indices: UInt32Array = lexsort_to_indices(&sort_columns, None)?;
+        // Get sorted indices, with a parallel stable sort for large frames
+        let num_rows = self.num_rows();
+        let indices: UInt32Array = if should_parallelize(num_rows) {
+            let comparator = LexicographicalComparator::try_new(&sort_columns)?;
+            let len = u32::try_from(num_rows)
+                .map_err(|_| DataError::InvalidOperation(format!("cannot sort {num_rows} rows")))?;
+            let mut order: Vec<u32> = (0..len).collect();
+            install(|| {
+                order.par_sort_by(|&a, &b| comparator.compare(a as usize, b as usize));
+            });
+            UInt32Array::from(order)
+        } else {
+            lexsort_to_indices(&sort_columns, None)?
+        };
 
         // Use indices to reorder all columns
-        let mut new_columns = Vec::with_capacity(self.num_columns());
-        for col_idx in 0..self.num_columns() {
-            let series = self.column_by_index(col_idx)?;
-            let sorted_array = take(series.array(), &indices, None)?;
-            new_columns.push(Series::new(series.name(), sorted_array));
-        }
-
-        DataFrame::from_series(new_columns)
+        self.take_by(&indices)
     }
 
     /// Take the first n rows (alias for head)
@@ -566,8 +573,6 @@ impl DataFrame {
     /// # Errors
     /// Returns error if any index is out of bounds
     pub fn filter_by_indices(&self, indices: &[usize]) -> DataResult<Self> {
-        use super::parallel::should_parallelize;
-
         if indices.is_empty() {
             return Ok(Self::empty(self.schema.clone()));
         }
@@ -583,53 +588,35 @@ impl DataFrame {
             }
         }
 
-        // Use parallel processing if beneficial
-        if should_parallelize(indices.len() * self.num_columns()) {
-            self.filter_by_indices_parallel(indices)
-        } else {
-            self.filter_by_indices_sequential(indices)
-        }
-    }
-
-    /// Sequential filter implementation
-    fn filter_by_indices_sequential(&self, indices: &[usize]) -> DataResult<Self> {
-        let mut new_columns = Vec::new();
-        for col_idx in 0..self.num_columns() {
-            let col = self.column_by_index(col_idx)?;
-            let values: Vec<Value> = indices
-                .iter()
-                .map(|&idx| col.get(idx))
-                .collect::<DataResult<Vec<_>>>()?;
-
-            let new_series = Series::from_values(col.name(), &values)?;
-            new_columns.push(new_series);
-        }
-
-        DataFrame::from_series(new_columns)
+        let indices: UInt64Array = indices.iter().map(|&idx| idx as u64).collect();
+        self.take_by(&indices)
     }
 
-    /// Parallel filter implementation using Rayon
-    fn filter_by_indices_parallel(&self, indices: &[usize]) -> DataResult<Self> {
-        use rayon::prelude::*;
-
-        // Get all columns first
+    /// Gather the rows at `indices` from every column with Arrow's `take`
+    /// kernel, one column per thread for large DataFrames
+    fn take_by(&self, indices: &dyn Array) -> DataResult<Self> {
         let columns: Vec<Series> = (0..self.num_columns())
             .map(|i| self.column_by_index(i))
             .collect::<DataResult<Vec<_>>>()?;
 
-        // Process columns in parallel
-        let new_columns: Result<Vec<Series>, DataError> = columns
-            .into_par_iter()
-            .map(|col| {
-                let values: Vec<Value> = indices
-                    .iter()
-                    .map(|&idx| col.get(idx))
-                    .collect::<DataResult<Vec<_>>>()?;
-                Series::from_values(col.name(), &values)
-            })
-            .collect();
+        let take_column = |col: &Series| -> DataResult<Series> {
+            Ok(Series::new(col.name(), take(col.array(), indices, None)?))
+        };
+        let new_columns = if should_parallelize(indices.len() * columns.len()) {
+            install(|| {
+                columns
+                    .par_iter()
+                    .map(take_column)
+                    .collect::<DataResult<Vec<_>>>()
+            })?
+        } else {
+            columns
+                .iter()
+                .map(take_column)
+                .collect::<DataResult<Vec<_>>>()?
+        };
 
-        DataFrame::from_series(new_columns?)
+        DataFrame::from_series(new_columns)
     }
 
     /// Generate summary statistics for numeric columns
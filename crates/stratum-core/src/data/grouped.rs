@@ -7,6 +7,7 @@ use arrow::datatypes::{DataType, Field, Schema};
 
 use super::dataframe::DataFrame;
 use super::error::{DataError, DataResult};
+use super::parallel::map_row_chunks;
 use super::series::Series;
 use crate::bytecode::Value;
 
@@ -207,9 +208,12 @@ pub struct GroupedDataFrame {
     /// The columns to group by
     group_columns: Vec<String>,
     /// Map from group key to row indices belonging to that group
-    groups: HashMap<Vec<GroupKey>, Vec<usize>>,
+    groups: GroupMap,
 }
 
+/// Row indices of each group, by group key
+type GroupMap = HashMap<Vec<GroupKey>, Vec<usize>>;
+
 /// A value that can be used as a group key
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum GroupKey {
@@ -256,29 +260,36 @@ impl GroupedDataFrame {
     }
 
     /// Build the mapping from group keys to row indices
-    fn build_groups(
-        df: &DataFrame,
-        group_columns: &[String],
-    ) -> DataResult<HashMap<Vec<GroupKey>, Vec<usize>>> {
-        let mut groups: HashMap<Vec<GroupKey>, Vec<usize>> = HashMap::new();
-
+    fn build_groups(df: &DataFrame, group_columns: &[String]) -> DataResult<GroupMap> {
         // Get the group column series
         let group_series: Vec<Series> = group_columns
             .iter()
             .map(|name| df.column(name))
             .collect::<DataResult<Vec<_>>>()?;
 
-        // Iterate over all rows and assign to groups
-        for row_idx in 0..df.num_rows() {
-            let mut key = Vec::with_capacity(group_columns.len());
+        // Assign rows to groups, one chunk of rows per thread for large frames.
+        // Chunks are merged in row order, so each group's indices stay sorted.
+        let chunks = map_row_chunks(df.num_rows(), |rows| -> DataResult<GroupMap> {
+            let mut groups = GroupMap::new();
+            for row_idx in rows {
+                let mut key = Vec::with_capacity(group_series.len());
+
+                for series in &group_series {
+                    let val = series.get(row_idx)?;
+                    let group_key = value_to_group_key(&val)?;
+                    key.push(group_key);
+                }
 
-            for series in &group_series {
-                let val = series.get(row_idx)?;
-                let group_key = value_to_group_key(&val)?;
-                key.push(group_key);
+                groups.entry(key).or_default().push(row_idx);
             }
+            Ok(groups)
+        });
 
-            groups.entry(key).or_default().push(row_idx);
+        let mut groups = GroupMap::new();
+        for chunk in chunks {
+            for (key, rows) in chunk? {
+                groups.entry(key).or_default().extend(rows);
+            }
         }
 
         Ok(groups)
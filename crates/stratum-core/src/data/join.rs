@@ -2,8 +2,14 @@
 
 use std::collections::HashMap;
 
+use arrow::array::{Array, BooleanArray, UInt64Array};
+use arrow::compute::kernels::zip::zip;
+use arrow::compute::{cast, take};
+use rayon::prelude::*;
+
 use super::dataframe::DataFrame;
 use super::error::{DataError, DataResult};
+use super::parallel::{install, map_row_chunks, should_parallelize};
 use super::series::Series;
 use crate::bytecode::Value;
 
@@ -180,81 +186,72 @@ impl DataFrame {
         let left_col = self.column(&spec.left_column)?;
         let right_col = other.column(&spec.right_column)?;
 
-        // Build hash map from right DataFrame
-        let mut right_map: HashMap<JoinKey, Vec<usize>> = HashMap::new();
-        for idx in 0..other.num_rows() {
-            let val = right_col.get(idx)?;
-            let key = JoinKey::from_value(&val)?;
-            right_map.entry(key).or_default().push(idx);
+        // Build hash map from right DataFrame, hashing chunks of rows in
+        // parallel and merging them in row order
+        let right_chunks = map_row_chunks(other.num_rows(), |rows| -> DataResult<KeyMap> {
+            let mut map = KeyMap::new();
+            for idx in rows {
+                let key = JoinKey::from_value(&right_col.get(idx)?)?;
+                map.entry(key).or_default().push(idx);
+            }
+            Ok(map)
+        });
+        let mut right_map = KeyMap::new();
+        for chunk in right_chunks {
+            for (key, rows) in chunk? {
+                right_map.entry(key).or_default().extend(rows);
+            }
         }
 
-        // Track which right rows were matched (for right/outer joins)
-        let mut right_matched = vec![false; other.num_rows()];
-
-        // Collect matching row pairs
-        let mut left_indices: Vec<Option<usize>> = Vec::new();
-        let mut right_indices: Vec<Option<usize>> = Vec::new();
-
-        // Process left rows
-        for left_idx in 0..self.num_rows() {
-            let val = left_col.get(left_idx)?;
-            let key = JoinKey::from_value(&val)?;
-
-            if let Some(matching_right_indices) = right_map.get(&key) {
-                // Found matches
-                for &right_idx in matching_right_indices {
-                    left_indices.push(Some(left_idx));
-                    right_indices.push(Some(right_idx));
-                    right_matched[right_idx] = true;
+        // Probe with the left rows, in parallel chunks, collecting matching
+        // row pairs in left row order
+        let keep_unmatched_left = matches!(spec.join_type, JoinType::Left | JoinType::Outer);
+        let left_chunks = map_row_chunks(self.num_rows(), |rows| -> DataResult<RowPairs> {
+            let mut pairs = RowPairs::default();
+            for left_idx in rows {
+                let key = JoinKey::from_value(&left_col.get(left_idx)?)?;
+                if let Some(matching_right_indices) = right_map.get(&key) {
+                    // Found matches
+                    for &right_idx in matching_right_indices {
+                        pairs.push(Some(left_idx), Some(right_idx));
+                    }
+                } else if keep_unmatched_left {
+                    // No match, but include left row with null right
+                    pairs.push(Some(left_idx), None);
                 }
-            } else if spec.join_type == JoinType::Left || spec.join_type == JoinType::Outer {
-                // No match, but include left row with null right
-                left_indices.push(Some(left_idx));
-                right_indices.push(None);
+                // For inner/right joins, unmatched left rows are dropped
             }
-            // For inner/right joins, unmatched left rows are dropped
+            Ok(pairs)
+        });
+        let mut pairs = RowPairs::default();
+        for chunk in left_chunks {
+            let chunk = chunk?;
+            pairs.left.extend(chunk.left);
+            pairs.right.extend(chunk.right);
         }
 
         // For right/outer joins, add unmatched right rows
-        if spec.join_type == JoinType::Right || spec.join_type == JoinType::Outer {
+        if matches!(spec.join_type, JoinType::Right | JoinType::Outer) {
+            let mut right_matched = vec![false; other.num_rows()];
+            for &right_idx in pairs.right.iter().flatten() {
+                right_matched[right_idx] = true;
+            }
             for (right_idx, matched) in right_matched.iter().enumerate() {
                 if !*matched {
-                    left_indices.push(None);
-                    right_indices.push(Some(right_idx));
+                    pairs.push(None, Some(right_idx));
                 }
             }
         }
 
-        // Build result columns
-        let mut result_columns: Vec<Series> = Vec::new();
+        let left_indices: UInt64Array = pairs.left.iter().map(|i| i.map(|i| i as u64)).collect();
+        let right_indices: UInt64Array = pairs.right.iter().map(|i| i.map(|i| i as u64)).collect();
 
-        // Add all columns from left DataFrame
-        // Special handling for the join column: when left is null, use right's value
+        // Output columns, as (source column, indices, output name)
+        let mut outputs: Vec<(Series, &UInt64Array, String)> = Vec::new();
         for col_idx in 0..self.num_columns() {
             let col = self.column_by_index(col_idx)?;
-            let col_name = col.name();
-            let is_join_column = col_name == spec.left_column;
-
-            let values: Vec<Value> = left_indices
-                .iter()
-                .zip(right_indices.iter())
-                .map(|(left_opt, right_opt)| {
-                    if let Some(left_idx) = left_opt {
-                        col.get(*left_idx)
-                    } else if is_join_column && spec.left_column == spec.right_column {
-                        // For unmatched right rows, use right's join column value
-                        if let Some(right_idx) = right_opt {
-                            right_col.get(*right_idx)
-                        } else {
-                            Ok(Value::Null)
-                        }
-                    } else {
-                        Ok(Value::Null)
-                    }
-                })
-                .collect::<DataResult<Vec<_>>>()?;
-            let new_series = Series::from_values(col_name, &values)?;
-            result_columns.push(new_series);
+            let name = col.name().to_string();
+            outputs.push((col, &left_indices, name));
         }
 
         // Add columns from right DataFrame (excluding the join column if same name)
@@ -274,19 +271,57 @@ impl DataFrame {
             } else {
                 col_name.to_string()
             };
-
-            let values: Vec<Value> = right_indices
-                .iter()
-                .map(|opt_idx| opt_idx.map_or(Ok(Value::Null), |idx| col.get(idx)))
-                .collect::<DataResult<Vec<_>>>()?;
-            let new_series = Series::from_values(&output_name, &values)?;
-            result_columns.push(new_series);
+            outputs.push((col, &right_indices, output_name));
         }
 
+        // Gather rows with Arrow's take kernel, one column per thread for
+        // large results. Missing rows (None indices) become nulls.
+        let gather = |output: &(Series, &UInt64Array, String)| -> DataResult<Series> {
+            let (col, indices, name) = output;
+            let mut array = take(col.array(), *indices, None)?;
+
+            // Special handling for the join column: when left is null, use right's value
+            if name == &spec.left_column && spec.left_column == spec.right_column {
+                let from_right = take(right_col.array(), &right_indices, None)?;
+                let from_right = cast(&from_right, array.data_type())?;
+                let has_left =
+                    BooleanArray::from(pairs.left.iter().map(Option::is_some).collect::<Vec<_>>());
+                array = zip(&has_left, &array, &from_right)?;
+            }
+            Ok(Series::new(name.clone(), array))
+        };
+        let result_columns = if should_parallelize(left_indices.len() * outputs.len()) {
+            install(|| {
+                outputs
+                    .par_iter()
+                    .map(gather)
+                    .collect::<DataResult<Vec<_>>>()
+            })?
+        } else {
+            outputs.iter().map(gather).collect::<DataResult<Vec<_>>>()?
+        };
+
         DataFrame::from_series(result_columns)
     }
 }
 
+/// Row indices of the right DataFrame, by join key
+type KeyMap = HashMap<JoinKey, Vec<usize>>;
+
+/// Matching (left, right) row pairs; None stands for a missing row
+#[derive(Default)]
+struct RowPairs {
+    left: Vec<Option<usize>>,
+    right: Vec<Option<usize>>,
+}
+
+impl RowPairs {
+    fn push(&mut self, left: Option<usize>, right: Option<usize>) {
+        self.left.push(left);
+        self.right.push(right);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -14,6 +14,10 @@
 //! // ... run VM code ...
 //! println!("{}", profiler_summary());
 //! ```
+//!
+//! A VM with allocation profiling turned on (`VM::set_allocation_profiling`)
+//! reports the values its instructions allocate, with the function and line
+//! that allocated them, and reports each one as freed once it is dropped.
 
 use std::collections::HashMap;
 use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
@@ -130,6 +134,61 @@ pub struct CategoryStats {
     pub peak_bytes: usize,
 }
 
+/// Where a value was allocated
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AllocationSite {
+    /// Function that allocated the value
+    pub function: String,
+    /// Source file (if known)
+    pub file: Option<String>,
+    /// Line of the allocating instruction
+    pub line: u32,
+}
+
+impl std::fmt::Display for AllocationSite {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{} ({}:{})", self.function, file, self.line),
+            None => write!(f, "{} (line {})", self.function, self.line),
+        }
+    }
+}
+
+/// Allocations of one category made at one call site
+#[derive(Debug, Clone)]
+pub struct CallSiteStats {
+    /// Where the values were allocated
+    pub site: AllocationSite,
+    /// Allocation category
+    pub category: String,
+    /// Number of allocations
+    pub allocation_count: usize,
+    /// Total bytes allocated
+    pub total_bytes: usize,
+}
+
+/// Memory profile report format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MemoryReportFormat {
+    /// Plain text summary
+    #[default]
+    Summary,
+    /// Standalone HTML page
+    Html,
+}
+
+impl std::str::FromStr for MemoryReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "summary" | "text" => Ok(MemoryReportFormat::Summary),
+            "html" => Ok(MemoryReportFormat::Html),
+            _ => Err(format!("Unknown memory profile format: {}", s)),
+        }
+    }
+}
+
 /// Potential memory leak information
 #[derive(Debug, Clone)]
 pub struct LeakInfo {
@@ -152,6 +211,8 @@ pub struct MemoryProfiler {
     current_allocations: HashMap<String, usize>,
     /// Statistics per category
     category_stats: HashMap<String, CategoryStats>,
+    /// Allocation count and bytes per call site and category
+    call_sites: HashMap<(AllocationSite, String), (usize, usize)>,
     /// Start time for profiling session
     start_time: Instant,
     /// Peak memory usage
@@ -173,6 +234,9 @@ impl Default for MemoryProfiler {
 /// Default maximum events to keep
 const DEFAULT_MAX_EVENTS: usize = 10_000;
 
+/// Number of allocation sites listed in a report
+const MAX_REPORTED_SITES: usize = 20;
+
 impl MemoryProfiler {
     /// Create a new memory profiler
     #[must_use]
@@ -182,6 +246,7 @@ impl MemoryProfiler {
             events: Vec::new(),
             current_allocations: HashMap::new(),
             category_stats: HashMap::new(),
+            call_sites: HashMap::new(),
             start_time: Instant::now(),
             peak_bytes: 0,
             current_bytes: 0,
@@ -255,6 +320,21 @@ impl MemoryProfiler {
         }
     }
 
+    /// Record an allocation made at a known call site
+    pub fn record_allocation_at(&mut self, bytes: usize, description: &str, site: AllocationSite) {
+        if !self.enabled {
+            return;
+        }
+
+        self.record_allocation(bytes, description);
+        let entry = self
+            .call_sites
+            .entry((site, description.to_string()))
+            .or_insert((0, 0));
+        entry.0 += 1;
+        entry.1 += bytes;
+    }
+
     /// Record a deallocation
     pub fn record_deallocation(&mut self, bytes: usize, description: &str) {
         if !self.enabled {
@@ -326,6 +406,29 @@ impl MemoryProfiler {
         &self.category_stats
     }
 
+    /// Get allocations per call site and category, most bytes first
+    #[must_use]
+    pub fn call_sites(&self) -> Vec<CallSiteStats> {
+        let mut sites: Vec<CallSiteStats> = self
+            .call_sites
+            .iter()
+            .map(|((site, category), &(count, bytes))| CallSiteStats {
+                site: site.clone(),
+                category: category.clone(),
+                allocation_count: count,
+                total_bytes: bytes,
+            })
+            .collect();
+        sites.sort_by(|a, b| {
+            b.total_bytes
+                .cmp(&a.total_bytes)
+                .then(b.allocation_count.cmp(&a.allocation_count))
+                .then_with(|| a.site.function.cmp(&b.site.function))
+                .then(a.site.line.cmp(&b.site.line))
+        });
+        sites
+    }
+
     /// Set GC stats snapshot (called at report time)
     pub fn set_gc_stats(&mut self, stats: GcStats) {
         self.gc_stats = Some(stats);
@@ -342,6 +445,7 @@ impl MemoryProfiler {
         self.events.clear();
         self.current_allocations.clear();
         self.category_stats.clear();
+        self.call_sites.clear();
         self.peak_bytes = 0;
         self.current_bytes = 0;
         self.gc_stats = None;
@@ -406,6 +510,12 @@ impl MemoryProfiler {
             report.push_str(&format!("Cycles Broken:   {}\n", gc.cycles_broken));
             report.push_str(&format!("Objects Tracked: {}\n", gc.tracked_objects));
             report.push_str(&format!("Threshold:       {}\n", gc.threshold));
+            report.push_str(&format!(
+                "Pause Time:      {:.3}ms total, {:.3}ms avg, {:.3}ms max\n",
+                gc.total_pause.as_secs_f64() * 1000.0,
+                gc.average_pause().as_secs_f64() * 1000.0,
+                gc.max_pause.as_secs_f64() * 1000.0
+            ));
         }
 
         // Allocation breakdown by category
@@ -415,23 +525,43 @@ impl MemoryProfiler {
             sorted.sort_by(|a, b| b.1.total_allocated.cmp(&a.1.total_allocated));
 
             report.push_str(&format!(
-                "{:<12} {:>10} {:>10} {:>12} {:>12}\n",
-                "Category", "Allocs", "Deallocs", "Total", "Current"
+                "{:<12} {:>10} {:>10} {:>12} {:>12} {:>12}\n",
+                "Category", "Allocs", "Deallocs", "Total", "Peak", "Current"
             ));
-            report.push_str(&format!("{}\n", "-".repeat(58)));
+            report.push_str(&format!("{}\n", "-".repeat(71)));
 
             for (category, stats) in sorted {
                 report.push_str(&format!(
-                    "{:<12} {:>10} {:>10} {:>12} {:>12}\n",
+                    "{:<12} {:>10} {:>10} {:>12} {:>12} {:>12}\n",
                     truncate_str(category, 12),
                     stats.allocation_count,
                     stats.deallocation_count,
                     MemoryStats::format_bytes(stats.total_allocated),
+                    MemoryStats::format_bytes(stats.peak_bytes),
                     MemoryStats::format_bytes(stats.current_bytes),
                 ));
             }
         }
 
+        // Top allocation sites
+        let sites = self.call_sites();
+        if !sites.is_empty() {
+            report.push_str("\n--- Top Allocation Sites ---\n");
+            report.push_str(&format!(
+                "{:>12} {:>10}  {:<12} {}\n",
+                "Bytes", "Allocs", "Category", "Site"
+            ));
+            for site in sites.iter().take(MAX_REPORTED_SITES) {
+                report.push_str(&format!(
+                    "{:>12} {:>10}  {:<12} {}\n",
+                    MemoryStats::format_bytes(site.total_bytes),
+                    site.allocation_count,
+                    truncate_str(&site.category, 12),
+                    site.site
+                ));
+            }
+        }
+
         // Leak detection
         let leaks = self.detect_leaks();
         if !leaks.is_empty() {
@@ -451,6 +581,137 @@ impl MemoryProfiler {
 
         report
     }
+
+    /// Generate a standalone HTML report
+    #[must_use]
+    pub fn html_report(&self) -> String {
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n<html>\n<head>\n");
+        html.push_str("<meta charset=\"UTF-8\">\n");
+        html.push_str("<title>Stratum Memory Profile</title>\n");
+        html.push_str("<style>\n");
+        html.push_str(HTML_STYLES);
+        html.push_str("</style>\n");
+        html.push_str("</head>\n<body>\n");
+        html.push_str("<h1>Stratum Memory Profile</h1>\n");
+
+        // Overview
+        html.push_str("<h2>Overview</h2>\n<table>\n");
+        html.push_str(&format!(
+            "<tr><td>Execution time</td><td>{:.3}s</td></tr>\n",
+            self.elapsed().as_secs_f64()
+        ));
+        html.push_str(&format!(
+            "<tr><td>Peak memory</td><td>{}</td></tr>\n",
+            MemoryStats::format_bytes(self.peak_bytes)
+        ));
+        html.push_str(&format!(
+            "<tr><td>Final memory</td><td>{}</td></tr>\n",
+            MemoryStats::format_bytes(self.current_bytes)
+        ));
+        html.push_str("</table>\n");
+
+        // GC
+        if let Some(gc) = &self.gc_stats {
+            html.push_str("<h2>Garbage Collection</h2>\n<table>\n");
+            html.push_str(&format!(
+                "<tr><td>Collections</td><td>{}</td></tr>\n",
+                gc.collections
+            ));
+            html.push_str(&format!(
+                "<tr><td>Cycles broken</td><td>{}</td></tr>\n",
+                gc.cycles_broken
+            ));
+            html.push_str(&format!(
+                "<tr><td>Objects tracked</td><td>{}</td></tr>\n",
+                gc.tracked_objects
+            ));
+            html.push_str(&format!(
+                "<tr><td>Total pause</td><td>{:.3} ms</td></tr>\n",
+                gc.total_pause.as_secs_f64() * 1000.0
+            ));
+            html.push_str(&format!(
+                "<tr><td>Average pause</td><td>{:.3} ms</td></tr>\n",
+                gc.average_pause().as_secs_f64() * 1000.0
+            ));
+            html.push_str(&format!(
+                "<tr><td>Longest pause</td><td>{:.3} ms</td></tr>\n",
+                gc.max_pause.as_secs_f64() * 1000.0
+            ));
+            html.push_str("</table>\n");
+        }
+
+        // Types, with a bar for each type's share of the peak
+        let mut categories: Vec<_> = self.category_stats.iter().collect();
+        categories.sort_by(|a, b| b.1.peak_bytes.cmp(&a.1.peak_bytes).then(a.0.cmp(b.0)));
+        let largest_peak = categories.first().map_or(0, |(_, s)| s.peak_bytes).max(1);
+        html.push_str("<h2>Types</h2>\n<table>\n");
+        html.push_str(
+            "<tr><th>Type</th><th>Allocations</th><th>Frees</th><th>Allocated</th>\
+             <th>Peak live</th><th>Live at exit</th><th></th></tr>\n",
+        );
+        for (category, stats) in categories {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td>\
+                 <td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td>\
+                 <td><div class=\"bar\" style=\"width: {}px\"></div></td></tr>\n",
+                escape_html(category),
+                stats.allocation_count,
+                stats.deallocation_count,
+                MemoryStats::format_bytes(stats.total_allocated),
+                MemoryStats::format_bytes(stats.peak_bytes),
+                MemoryStats::format_bytes(stats.current_bytes),
+                stats.peak_bytes * 200 / largest_peak
+            ));
+        }
+        html.push_str("</table>\n");
+
+        // Call sites
+        html.push_str("<h2>Allocation Sites</h2>\n<table>\n");
+        html.push_str(
+            "<tr><th>Function</th><th>Location</th><th>Type</th>\
+             <th>Allocations</th><th>Bytes</th></tr>\n",
+        );
+        for site in self.call_sites().iter().take(MAX_REPORTED_SITES * 5) {
+            let location = match &site.site.file {
+                Some(file) => format!("{}:{}", file, site.site.line),
+                None => format!("line {}", site.site.line),
+            };
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td class=\"num\">{}</td>\
+                 <td class=\"num\">{}</td></tr>\n",
+                escape_html(&site.site.function),
+                escape_html(&location),
+                escape_html(&site.category),
+                site.allocation_count,
+                MemoryStats::format_bytes(site.total_bytes)
+            ));
+        }
+        html.push_str("</table>\n");
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+/// Styles for the HTML report
+const HTML_STYLES: &str = r#"
+body { font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, sans-serif; margin: 20px; background: #f5f5f5; }
+h1 { color: #333; }
+h2 { color: #555; margin-top: 30px; }
+table { border-collapse: collapse; background: white; box-shadow: 0 1px 3px rgba(0,0,0,0.1); }
+th, td { padding: 6px 12px; border-bottom: 1px solid #eee; text-align: left; }
+th { background: #fafafa; }
+td.num { text-align: right; font-family: monospace; }
+.bar { height: 10px; background: #4a90d9; }
+"#;
+
+/// Escape text for inclusion in HTML
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
 }
 
 /// Truncate a string to a maximum length, adding "..." if truncated
@@ -501,6 +762,11 @@ pub fn record_allocation(bytes: usize, description: &str) {
     global_profiler_mut().record_allocation(bytes, description);
 }
 
+/// Record a global allocation made at a known call site
+pub fn record_allocation_at(bytes: usize, description: &str, site: AllocationSite) {
+    global_profiler_mut().record_allocation_at(bytes, description, site);
+}
+
 /// Record a global deallocation
 pub fn record_deallocation(bytes: usize, description: &str) {
     global_profiler_mut().record_deallocation(bytes, description);
@@ -511,6 +777,11 @@ pub fn profiler_summary() -> String {
     global_profiler().summary()
 }
 
+/// Get the global profiler report as a standalone HTML page
+pub fn profiler_html_report() -> String {
+    global_profiler().html_report()
+}
+
 /// Reset the global profiler
 pub fn reset_profiler() {
     global_profiler_mut().reset();
@@ -641,6 +912,7 @@ mod tests {
             tracked_objects: 100,
             allocation_count: 50,
             threshold: 10000,
+            ..GcStats::default()
         };
         profiler.set_gc_stats(gc_stats.clone());
 
@@ -660,6 +932,7 @@ mod tests {
             tracked_objects: 50,
             allocation_count: 25,
             threshold: 10000,
+            ..GcStats::default()
         };
         profiler.set_gc_stats(gc_stats);
 
@@ -669,6 +942,89 @@ mod tests {
         assert!(summary.contains("Cycles Broken:"));
     }
 
+    fn site(function: &str, line: u32) -> AllocationSite {
+        AllocationSite {
+            function: function.to_string(),
+            file: Some("main.strat".to_string()),
+            line,
+        }
+    }
+
+    #[test]
+    fn test_call_site_stats() {
+        let mut profiler = MemoryProfiler::new();
+        profiler.enable();
+
+        profiler.record_allocation_at(100, categories::LIST, site("build", 3));
+        profiler.record_allocation_at(100, categories::LIST, site("build", 3));
+        profiler.record_allocation_at(500, categories::MAP, site("index", 7));
+        profiler.record_allocation_at(40, categories::STRING, site("build", 3));
+
+        let sites = profiler.call_sites();
+        assert_eq!(sites.len(), 3);
+        assert_eq!(sites[0].site, site("index", 7));
+        assert_eq!(sites[0].total_bytes, 500);
+        assert_eq!(sites[1].category, categories::LIST);
+        assert_eq!(sites[1].allocation_count, 2);
+        assert_eq!(sites[1].total_bytes, 200);
+
+        // Call site allocations count towards the category totals
+        assert_eq!(profiler.current_bytes(), 740);
+        assert_eq!(
+            profiler.category_stats()[categories::LIST].total_allocated,
+            200
+        );
+
+        let summary = profiler.summary();
+        assert!(summary.contains("Top Allocation Sites"));
+        assert!(summary.contains("index (main.strat:7)"));
+
+        profiler.reset();
+        assert!(profiler.call_sites().is_empty());
+    }
+
+    #[test]
+    fn test_peak_bytes_per_category() {
+        let mut profiler = MemoryProfiler::new();
+        profiler.enable();
+
+        profiler.record_allocation(300, categories::LIST);
+        profiler.record_allocation(300, categories::LIST);
+        profiler.record_deallocation(300, categories::LIST);
+        profiler.record_allocation(100, categories::LIST);
+
+        let stats = &profiler.category_stats()[categories::LIST];
+        assert_eq!(stats.peak_bytes, 600);
+        assert_eq!(stats.current_bytes, 400);
+    }
+
+    #[test]
+    fn test_html_report() {
+        let mut profiler = MemoryProfiler::new();
+        profiler.enable();
+        profiler.record_allocation_at(64, categories::STRUCT, site("<script>", 1));
+        profiler.set_gc_stats(GcStats {
+            collections: 2,
+            total_pause: Duration::from_millis(3),
+            max_pause: Duration::from_millis(2),
+            ..GcStats::default()
+        });
+
+        let html = profiler.html_report();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<td>Struct</td>"));
+        assert!(html.contains("&lt;script&gt;"));
+        assert!(html.contains("main.strat:1"));
+        assert!(html.contains("<tr><td>Average pause</td><td>1.500 ms</td></tr>"));
+    }
+
+    #[test]
+    fn test_memory_report_format_from_str() {
+        assert_eq!("html".parse(), Ok(MemoryReportFormat::Html));
+        assert_eq!("Summary".parse(), Ok(MemoryReportFormat::Summary));
+        assert!("json".parse::<MemoryReportFormat>().is_err());
+    }
+
     #[test]
     fn test_truncate_str() {
         assert_eq!(truncate_str("hello", 10), "hello");
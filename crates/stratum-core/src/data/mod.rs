@@ -6,10 +6,12 @@
 //! - GroupedDataFrame: DataFrame partitioned by key columns for aggregation
 //! - JoinSpec: Join specifications for DataFrame operations
 //! - Cube: OLAP cube for multi-dimensional analytical processing
+//! - Cube persistence to Parquet and incremental aggregation
 //! - Type mapping between Stratum and Arrow types
 //! - File I/O for Parquet, CSV, and JSON
 
 mod cube;
+mod cube_store;
 mod dataframe;
 mod error;
 mod grouped;
@@ -23,6 +25,7 @@ mod sql;
 mod types;
 
 pub use cube::{Cube, CubeBuilder, CubeQuery};
+pub use cube_store::{CalculatedMeasureSpec, CubeAggregates, CubeDefinition, MeasureSpec};
 pub use dataframe::DataFrame;
 pub use error::{DataError, DataResult};
 pub use grouped::{AggOp, AggSpec, GroupedDataFrame};
@@ -34,10 +37,14 @@ pub use join::{JoinSpec, JoinType};
 pub use lazy::{LazyFrame, LazyGroupBy};
 pub use memory::{
     categories as memory_categories, detect_leaks, disable_profiling, enable_profiling,
-    is_profiling_enabled, profiler_summary, record_allocation, record_deallocation, reset_profiler,
-    set_profiler_gc_stats, CategoryStats, LeakInfo, MemoryProfiler, MemoryStats,
+    global_profiler, is_profiling_enabled, profiler_html_report, profiler_summary,
+    record_allocation, record_allocation_at, record_deallocation, reset_profiler,
+    set_profiler_gc_stats, AllocationSite, CallSiteStats, CategoryStats, LeakInfo, MemoryProfiler,
+    MemoryReportFormat, MemoryStats,
+};
+pub use parallel::{
+    install, parallel_threshold, set_parallel_threshold, set_threads, threads, ParallelConfig,
 };
-pub use parallel::{parallel_threshold, set_parallel_threshold, ParallelConfig};
 pub use series::{Rolling, Series};
 pub use sql::{sql_query, sql_query_with_name, SqlContext};
 pub use types::{arrow_to_stratum_type, stratum_to_arrow_type};
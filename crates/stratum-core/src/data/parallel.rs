@@ -3,8 +3,19 @@
 //! This module provides automatic parallelization for large DataFrame operations
 //! using Rayon. Operations are parallelized when the row count exceeds a
 //! configurable threshold.
+//!
+//! Parallel work runs on a data thread pool. By default this is Rayon's global
+//! pool; `set_threads(n)` replaces it with a pool of `n` threads, which can be
+//! changed again at any time (unlike Rayon's global pool).
 
+use std::ops::Range;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+use super::error::{DataError, DataResult};
 
 /// Default threshold for automatic parallelization (10,000 rows)
 pub const DEFAULT_PARALLEL_THRESHOLD: usize = 10_000;
@@ -12,6 +23,9 @@ pub const DEFAULT_PARALLEL_THRESHOLD: usize = 10_000;
 /// Global parallel threshold - operations with more rows than this will be parallelized
 static PARALLEL_THRESHOLD: AtomicUsize = AtomicUsize::new(DEFAULT_PARALLEL_THRESHOLD);
 
+/// Thread pool set with `set_threads` (None = Rayon's global pool)
+static POOL: RwLock<Option<Arc<ThreadPool>>> = RwLock::new(None);
+
 /// Get the current parallel threshold
 #[must_use]
 pub fn parallel_threshold() -> usize {
@@ -29,7 +43,87 @@ pub fn set_parallel_threshold(threshold: usize) {
 /// Check if the given row count should trigger parallel execution
 #[must_use]
 pub fn should_parallelize(num_rows: usize) -> bool {
-    num_rows > parallel_threshold()
+    num_rows > parallel_threshold() && threads() > 1
+}
+
+/// Set the number of threads used for parallel DataFrame operations
+///
+/// Pass 0 to go back to Rayon's global pool, which uses one thread per CPU.
+///
+/// # Errors
+/// Returns error if the thread pool cannot be created
+pub fn set_threads(num_threads: usize) -> DataResult<()> {
+    let pool = if num_threads == 0 {
+        None
+    } else {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .thread_name(|i| format!("stratum-data-{i}"))
+            .build()
+            .map_err(|e| {
+                DataError::InvalidOperation(format!("failed to create thread pool: {e}"))
+            })?;
+        Some(Arc::new(pool))
+    };
+    *POOL
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = pool;
+    Ok(())
+}
+
+/// Get the number of threads used for parallel DataFrame operations
+#[must_use]
+pub fn threads() -> usize {
+    current_pool().map_or_else(rayon::current_num_threads, |pool| {
+        pool.current_num_threads()
+    })
+}
+
+fn current_pool() -> Option<Arc<ThreadPool>> {
+    POOL.read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .clone()
+}
+
+/// Run `op` on the data thread pool, so Rayon iterators inside it use the
+/// configured number of threads
+pub fn install<R, F>(op: F) -> R
+where
+    R: Send,
+    F: FnOnce() -> R + Send,
+{
+    match current_pool() {
+        Some(pool) => pool.install(op),
+        None => op(),
+    }
+}
+
+/// Split `len` rows into one contiguous range per thread, or a single range
+/// if `len` is below the parallel threshold
+#[must_use]
+pub fn row_chunks(len: usize) -> Vec<Range<usize>> {
+    if !should_parallelize(len) {
+        return vec![0..len];
+    }
+    let chunk = len.div_ceil(threads());
+    (0..len)
+        .step_by(chunk)
+        .map(|start| start..(start + chunk).min(len))
+        .collect()
+}
+
+/// Apply `op` to each range from [`row_chunks`], in parallel when there is
+/// more than one, returning the results in row order
+pub fn map_row_chunks<R, F>(len: usize, op: F) -> Vec<R>
+where
+    R: Send,
+    F: Fn(Range<usize>) -> R + Sync + Send,
+{
+    let chunks = row_chunks(len);
+    if chunks.len() == 1 {
+        return chunks.into_iter().map(op).collect();
+    }
+    install(|| chunks.into_par_iter().map(op).collect())
 }
 
 /// Configuration builder for parallel operations
@@ -75,10 +169,8 @@ impl ParallelConfig {
     pub fn apply(&self) {
         set_parallel_threshold(self.threshold);
         if let Some(threads) = self.num_threads {
-            // Configure Rayon thread pool if custom thread count specified
-            let _ = rayon::ThreadPoolBuilder::new()
-                .num_threads(threads)
-                .build_global();
+            // Configure the data thread pool if custom thread count specified
+            let _ = set_threads(threads);
         }
     }
 }
@@ -101,4 +193,28 @@ mod tests {
         // Reset to default
         set_parallel_threshold(DEFAULT_PARALLEL_THRESHOLD);
     }
+
+    #[test]
+    fn test_set_threads() {
+        set_threads(3).unwrap();
+        assert_eq!(threads(), 3);
+        assert_eq!(install(rayon::current_num_threads), 3);
+
+        set_threads(0).unwrap();
+        assert_eq!(threads(), rayon::current_num_threads());
+    }
+
+    #[test]
+    fn test_row_chunks() {
+        // Below the threshold everything is one chunk
+        assert_eq!(row_chunks(10), vec![0..10]);
+        assert_eq!(row_chunks(0), vec![0..0]);
+
+        // Chunks cover every row once, in order
+        let len = DEFAULT_PARALLEL_THRESHOLD * 3 + 7;
+        let chunks = map_row_chunks(len, |range| range);
+        assert_eq!(chunks.first().unwrap().start, 0);
+        assert_eq!(chunks.last().unwrap().end, len);
+        assert!(chunks.windows(2).all(|pair| pair[0].end == pair[1].start));
+    }
 }
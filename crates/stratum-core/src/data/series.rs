@@ -4,17 +4,70 @@ use std::fmt;
 use std::sync::Arc;
 
 use arrow::array::{
-    Array, ArrayRef, BooleanArray, Float64Array, Int32Array, Int64Array, Scalar, StringArray,
+    Array, ArrayRef, BooleanArray, Datum, Float64Array, Int32Array, Int64Array, Scalar, StringArray,
 };
 use arrow::compute;
 use arrow::compute::kernels::{boolean, cmp, length, numeric, substring};
 use arrow::datatypes::DataType;
+use arrow::error::ArrowError;
 
 use super::error::{DataError, DataResult};
+use super::parallel::map_row_chunks;
 use super::types::arrow_to_stratum_type;
 use crate::bytecode::Value;
 use crate::types::Type;
 
+/// An Arrow comparison kernel
+type CmpKernel = fn(&dyn Datum, &dyn Datum) -> Result<BooleanArray, ArrowError>;
+
+/// Run an element-wise kernel over two arrays of the same length. Large
+/// arrays are split into row chunks that are evaluated on the data thread
+/// pool and concatenated in order.
+fn chunked_binary<F>(left: &ArrayRef, right: &ArrayRef, kernel: F) -> Result<ArrayRef, ArrowError>
+where
+    F: Fn(&dyn Datum, &dyn Datum) -> Result<ArrayRef, ArrowError> + Sync,
+{
+    let chunks = map_row_chunks(left.len(), |rows| {
+        let left = left.slice(rows.start, rows.len());
+        let right = right.slice(rows.start, rows.len());
+        kernel(&left, &right)
+    });
+    concat_chunks(chunks)
+}
+
+/// Run an element-wise kernel between an array and a scalar, in row chunks
+/// like [`chunked_binary`]
+fn chunked_scalar<T, F>(
+    left: &ArrayRef,
+    scalar: &Scalar<T>,
+    kernel: F,
+) -> Result<ArrayRef, ArrowError>
+where
+    T: Array,
+    F: Fn(&dyn Datum, &dyn Datum) -> Result<ArrayRef, ArrowError> + Sync,
+{
+    let chunks = map_row_chunks(left.len(), |rows| {
+        kernel(&left.slice(rows.start, rows.len()), scalar)
+    });
+    concat_chunks(chunks)
+}
+
+/// Adapt a comparison kernel to return an [`ArrayRef`]
+fn boolean_kernel(
+    cmp_fn: CmpKernel,
+) -> impl Fn(&dyn Datum, &dyn Datum) -> Result<ArrayRef, ArrowError> + Sync {
+    move |left, right| cmp_fn(left, right).map(|result| Arc::new(result) as ArrayRef)
+}
+
+fn concat_chunks(chunks: Vec<Result<ArrayRef, ArrowError>>) -> Result<ArrayRef, ArrowError> {
+    let mut chunks = chunks.into_iter().collect::<Result<Vec<_>, _>>()?;
+    if chunks.len() == 1 {
+        return Ok(chunks.remove(0));
+    }
+    let arrays: Vec<&dyn Array> = chunks.iter().map(AsRef::as_ref).collect();
+    compute::concat(&arrays)
+}
+
 /// A single column of homogeneous data backed by an Arrow array
 #[derive(Clone)]
 pub struct Series {
@@ -819,7 +872,8 @@ impl Series {
     pub fn add(&self, other: &Series) -> DataResult<Self> {
         self.check_length(other)?;
         let (left, right) = self.coerce_numeric_pair(other)?;
-        let result = numeric::add(&left, &right).map_err(|e| DataError::Arrow(e.to_string()))?;
+        let result = chunked_binary(&left, &right, numeric::add)
+            .map_err(|e| DataError::Arrow(e.to_string()))?;
         Ok(Self::new(self.name.clone(), result))
     }
 
@@ -831,20 +885,24 @@ impl Series {
         let result = match (self.array.data_type(), value) {
             (DataType::Int64, Value::Int(v)) => {
                 let scalar = Scalar::new(Int64Array::from(vec![*v]));
-                numeric::add(&self.array, &scalar).map_err(|e| DataError::Arrow(e.to_string()))?
+                chunked_scalar(&self.array, &scalar, numeric::add)
+                    .map_err(|e| DataError::Arrow(e.to_string()))?
             }
             (DataType::Int64, Value::Float(v)) => {
                 let arr = self.cast_to_float()?;
                 let scalar = Scalar::new(Float64Array::from(vec![*v]));
-                numeric::add(&arr, &scalar).map_err(|e| DataError::Arrow(e.to_string()))?
+                chunked_scalar(&arr, &scalar, numeric::add)
+                    .map_err(|e| DataError::Arrow(e.to_string()))?
             }
             (DataType::Float64, Value::Int(v)) => {
                 let scalar = Scalar::new(Float64Array::from(vec![*v as f64]));
-                numeric::add(&self.array, &scalar).map_err(|e| DataError::Arrow(e.to_string()))?
+                chunked_scalar(&self.array, &scalar, numeric::add)
+                    .map_err(|e| DataError::Arrow(e.to_string()))?
             }
             (DataType::Float64, Value::Float(v)) => {
                 let scalar = Scalar::new(Float64Array::from(vec![*v]));
-                numeric::add(&self.array, &scalar).map_err(|e| DataError::Arrow(e.to_string()))?
+                chunked_scalar(&self.array, &scalar, numeric::add)
+                    .map_err(|e| DataError::Arrow(e.to_string()))?
             }
             _ => {
                 return Err(DataError::InvalidOperation(format!(
@@ -864,7 +922,8 @@ impl Series {
     pub fn sub(&self, other: &Series) -> DataResult<Self> {
         self.check_length(other)?;
         let (left, right) = self.coerce_numeric_pair(other)?;
-        let result = numeric::sub(&left, &right).map_err(|e| DataError::Arrow(e.to_string()))?;
+        let result = chunked_binary(&left, &right, numeric::sub)
+            .map_err(|e| DataError::Arrow(e.to_string()))?;
         Ok(Self::new(self.name.clone(), result))
     }
 
@@ -876,20 +935,24 @@ impl Series {
         let result = match (self.array.data_type(), value) {
             (DataType::Int64, Value::Int(v)) => {
                 let scalar = Scalar::new(Int64Array::from(vec![*v]));
-                numeric::sub(&self.array, &scalar).map_err(|e| DataError::Arrow(e.to_string()))?
+                chunked_scalar(&self.array, &scalar, numeric::sub)
+                    .map_err(|e| DataError::Arrow(e.to_string()))?
             }
             (DataType::Int64, Value::Float(v)) => {
                 let arr = self.cast_to_float()?;
                 let scalar = Scalar::new(Float64Array::from(vec![*v]));
-                numeric::sub(&arr, &scalar).map_err(|e| DataError::Arrow(e.to_string()))?
+                chunked_scalar(&arr, &scalar, numeric::sub)
+                    .map_err(|e| DataError::Arrow(e.to_string()))?
             }
             (DataType::Float64, Value::Int(v)) => {
                 let scalar = Scalar::new(Float64Array::from(vec![*v as f64]));
-                numeric::sub(&self.array, &scalar).map_err(|e| DataError::Arrow(e.to_string()))?
+                chunked_scalar(&self.array, &scalar, numeric::sub)
+                    .map_err(|e| DataError::Arrow(e.to_string()))?
             }
             (DataType::Float64, Value::Float(v)) => {
                 let scalar = Scalar::new(Float64Array::from(vec![*v]));
-                numeric::sub(&self.array, &scalar).map_err(|e| DataError::Arrow(e.to_string()))?
+                chunked_scalar(&self.array, &scalar, numeric::sub)
+                    .map_err(|e| DataError::Arrow(e.to_string()))?
             }
             _ => {
                 return Err(DataError::InvalidOperation(format!(
@@ -909,7 +972,8 @@ impl Series {
     pub fn mul(&self, other: &Series) -> DataResult<Self> {
         self.check_length(other)?;
         let (left, right) = self.coerce_numeric_pair(other)?;
-        let result = numeric::mul(&left, &right).map_err(|e| DataError::Arrow(e.to_string()))?;
+        let result = chunked_binary(&left, &right, numeric::mul)
+            .map_err(|e| DataError::Arrow(e.to_string()))?;
         Ok(Self::new(self.name.clone(), result))
     }
 
@@ -921,20 +985,24 @@ impl Series {
         let result = match (self.array.data_type(), value) {
             (DataType::Int64, Value::Int(v)) => {
                 let scalar = Scalar::new(Int64Array::from(vec![*v]));
-                numeric::mul(&self.array, &scalar).map_err(|e| DataError::Arrow(e.to_string()))?
+                chunked_scalar(&self.array, &scalar, numeric::mul)
+                    .map_err(|e| DataError::Arrow(e.to_string()))?
             }
             (DataType::Int64, Value::Float(v)) => {
                 let arr = self.cast_to_float()?;
                 let scalar = Scalar::new(Float64Array::from(vec![*v]));
-                numeric::mul(&arr, &scalar).map_err(|e| DataError::Arrow(e.to_string()))?
+                chunked_scalar(&arr, &scalar, numeric::mul)
+                    .map_err(|e| DataError::Arrow(e.to_string()))?
             }
             (DataType::Float64, Value::Int(v)) => {
                 let scalar = Scalar::new(Float64Array::from(vec![*v as f64]));
-                numeric::mul(&self.array, &scalar).map_err(|e| DataError::Arrow(e.to_string()))?
+                chunked_scalar(&self.array, &scalar, numeric::mul)
+                    .map_err(|e| DataError::Arrow(e.to_string()))?
             }
             (DataType::Float64, Value::Float(v)) => {
                 let scalar = Scalar::new(Float64Array::from(vec![*v]));
-                numeric::mul(&self.array, &scalar).map_err(|e| DataError::Arrow(e.to_string()))?
+                chunked_scalar(&self.array, &scalar, numeric::mul)
+                    .map_err(|e| DataError::Arrow(e.to_string()))?
             }
             _ => {
                 return Err(DataError::InvalidOperation(format!(
@@ -954,7 +1022,8 @@ impl Series {
     pub fn div(&self, other: &Series) -> DataResult<Self> {
         self.check_length(other)?;
         let (left, right) = self.coerce_numeric_pair(other)?;
-        let result = numeric::div(&left, &right).map_err(|e| DataError::Arrow(e.to_string()))?;
+        let result = chunked_binary(&left, &right, numeric::div)
+            .map_err(|e| DataError::Arrow(e.to_string()))?;
         Ok(Self::new(self.name.clone(), result))
     }
 
@@ -966,20 +1035,24 @@ impl Series {
         let result = match (self.array.data_type(), value) {
             (DataType::Int64, Value::Int(v)) => {
                 let scalar = Scalar::new(Int64Array::from(vec![*v]));
-                numeric::div(&self.array, &scalar).map_err(|e| DataError::Arrow(e.to_string()))?
+                chunked_scalar(&self.array, &scalar, numeric::div)
+                    .map_err(|e| DataError::Arrow(e.to_string()))?
             }
             (DataType::Int64, Value::Float(v)) => {
                 let arr = self.cast_to_float()?;
                 let scalar = Scalar::new(Float64Array::from(vec![*v]));
-                numeric::div(&arr, &scalar).map_err(|e| DataError::Arrow(e.to_string()))?
+                chunked_scalar(&arr, &scalar, numeric::div)
+                    .map_err(|e| DataError::Arrow(e.to_string()))?
             }
             (DataType::Float64, Value::Int(v)) => {
                 let scalar = Scalar::new(Float64Array::from(vec![*v as f64]));
-                numeric::div(&self.array, &scalar).map_err(|e| DataError::Arrow(e.to_string()))?
+                chunked_scalar(&self.array, &scalar, numeric::div)
+                    .map_err(|e| DataError::Arrow(e.to_string()))?
             }
             (DataType::Float64, Value::Float(v)) => {
                 let scalar = Scalar::new(Float64Array::from(vec![*v]));
-                numeric::div(&self.array, &scalar).map_err(|e| DataError::Arrow(e.to_string()))?
+                chunked_scalar(&self.array, &scalar, numeric::div)
+                    .map_err(|e| DataError::Arrow(e.to_string()))?
             }
             _ => {
                 return Err(DataError::InvalidOperation(format!(
@@ -1011,9 +1084,9 @@ impl Series {
     /// Returns error if types are incompatible or lengths don't match
     pub fn eq(&self, other: &Series) -> DataResult<Self> {
         self.check_length(other)?;
-        let result =
-            cmp::eq(&self.array, &other.array).map_err(|e| DataError::Arrow(e.to_string()))?;
-        Ok(Self::new(format!("{}_eq", self.name), Arc::new(result)))
+        let result = chunked_binary(&self.array, &other.array, boolean_kernel(cmp::eq))
+            .map_err(|e| DataError::Arrow(e.to_string()))?;
+        Ok(Self::new(format!("{}_eq", self.name), result))
     }
 
     /// Element-wise equality comparison with a scalar
@@ -1022,7 +1095,7 @@ impl Series {
     /// Returns error if types are incompatible
     pub fn eq_scalar(&self, value: &Value) -> DataResult<Self> {
         let result = self.compare_scalar(value, cmp::eq)?;
-        Ok(Self::new(format!("{}_eq", self.name), Arc::new(result)))
+        Ok(Self::new(format!("{}_eq", self.name), result))
     }
 
     /// Element-wise inequality comparison
@@ -1031,9 +1104,9 @@ impl Series {
     /// Returns error if types are incompatible or lengths don't match
     pub fn neq(&self, other: &Series) -> DataResult<Self> {
         self.check_length(other)?;
-        let result =
-            cmp::neq(&self.array, &other.array).map_err(|e| DataError::Arrow(e.to_string()))?;
-        Ok(Self::new(format!("{}_neq", self.name), Arc::new(result)))
+        let result = chunked_binary(&self.array, &other.array, boolean_kernel(cmp::neq))
+            .map_err(|e| DataError::Arrow(e.to_string()))?;
+        Ok(Self::new(format!("{}_neq", self.name), result))
     }
 
     /// Element-wise inequality comparison with a scalar
@@ -1042,7 +1115,7 @@ impl Series {
     /// Returns error if types are incompatible
     pub fn neq_scalar(&self, value: &Value) -> DataResult<Self> {
         let result = self.compare_scalar(value, cmp::neq)?;
-        Ok(Self::new(format!("{}_neq", self.name), Arc::new(result)))
+        Ok(Self::new(format!("{}_neq", self.name), result))
     }
 
     /// Element-wise less-than comparison
@@ -1051,9 +1124,9 @@ impl Series {
     /// Returns error if types are incompatible or lengths don't match
     pub fn lt(&self, other: &Series) -> DataResult<Self> {
         self.check_length(other)?;
-        let result =
-            cmp::lt(&self.array, &other.array).map_err(|e| DataError::Arrow(e.to_string()))?;
-        Ok(Self::new(format!("{}_lt", self.name), Arc::new(result)))
+        let result = chunked_binary(&self.array, &other.array, boolean_kernel(cmp::lt))
+            .map_err(|e| DataError::Arrow(e.to_string()))?;
+        Ok(Self::new(format!("{}_lt", self.name), result))
     }
 
     /// Element-wise less-than comparison with a scalar
@@ -1062,7 +1135,7 @@ impl Series {
     /// Returns error if types are incompatible
     pub fn lt_scalar(&self, value: &Value) -> DataResult<Self> {
         let result = self.compare_scalar(value, cmp::lt)?;
-        Ok(Self::new(format!("{}_lt", self.name), Arc::new(result)))
+        Ok(Self::new(format!("{}_lt", self.name), result))
     }
 
     /// Element-wise less-than-or-equal comparison
@@ -1071,9 +1144,9 @@ impl Series {
     /// Returns error if types are incompatible or lengths don't match
     pub fn le(&self, other: &Series) -> DataResult<Self> {
         self.check_length(other)?;
-        let result =
-            cmp::lt_eq(&self.array, &other.array).map_err(|e| DataError::Arrow(e.to_string()))?;
-        Ok(Self::new(format!("{}_le", self.name), Arc::new(result)))
+        let result = chunked_binary(&self.array, &other.array, boolean_kernel(cmp::lt_eq))
+            .map_err(|e| DataError::Arrow(e.to_string()))?;
+        Ok(Self::new(format!("{}_le", self.name), result))
     }
 
     /// Element-wise less-than-or-equal comparison with a scalar
@@ -1082,7 +1155,7 @@ impl Series {
     /// Returns error if types are incompatible
     pub fn le_scalar(&self, value: &Value) -> DataResult<Self> {
         let result = self.compare_scalar(value, cmp::lt_eq)?;
-        Ok(Self::new(format!("{}_le", self.name), Arc::new(result)))
+        Ok(Self::new(format!("{}_le", self.name), result))
     }
 
     /// Element-wise greater-than comparison
@@ -1091,9 +1164,9 @@ impl Series {
     /// Returns error if types are incompatible or lengths don't match
     pub fn gt(&self, other: &Series) -> DataResult<Self> {
         self.check_length(other)?;
-        let result =
-            cmp::gt(&self.array, &other.array).map_err(|e| DataError::Arrow(e.to_string()))?;
-        Ok(Self::new(format!("{}_gt", self.name), Arc::new(result)))
+        let result = chunked_binary(&self.array, &other.array, boolean_kernel(cmp::gt))
+            .map_err(|e| DataError::Arrow(e.to_string()))?;
+        Ok(Self::new(format!("{}_gt", self.name), result))
     }
 
     /// Element-wise greater-than comparison with a scalar
@@ -1102,7 +1175,7 @@ impl Series {
     /// Returns error if types are incompatible
     pub fn gt_scalar(&self, value: &Value) -> DataResult<Self> {
         let result = self.compare_scalar(value, cmp::gt)?;
-        Ok(Self::new(format!("{}_gt", self.name), Arc::new(result)))
+        Ok(Self::new(format!("{}_gt", self.name), result))
     }
 
     /// Element-wise greater-than-or-equal comparison
@@ -1111,9 +1184,9 @@ impl Series {
     /// Returns error if types are incompatible or lengths don't match
     pub fn ge(&self, other: &Series) -> DataResult<Self> {
         self.check_length(other)?;
-        let result =
-            cmp::gt_eq(&self.array, &other.array).map_err(|e| DataError::Arrow(e.to_string()))?;
-        Ok(Self::new(format!("{}_ge", self.name), Arc::new(result)))
+        let result = chunked_binary(&self.array, &other.array, boolean_kernel(cmp::gt_eq))
+            .map_err(|e| DataError::Arrow(e.to_string()))?;
+        Ok(Self::new(format!("{}_ge", self.name), result))
     }
 
     /// Element-wise greater-than-or-equal comparison with a scalar
@@ -1122,7 +1195,7 @@ impl Series {
     /// Returns error if types are incompatible
     pub fn ge_scalar(&self, value: &Value) -> DataResult<Self> {
         let result = self.compare_scalar(value, cmp::gt_eq)?;
-        Ok(Self::new(format!("{}_ge", self.name), Arc::new(result)))
+        Ok(Self::new(format!("{}_ge", self.name), result))
     }
 
     // ========================================================================
@@ -1861,38 +1934,38 @@ impl Series {
     }
 
     /// Compare series elements to a scalar value using the given comparison function
-    fn compare_scalar<F>(&self, value: &Value, cmp_fn: F) -> DataResult<BooleanArray>
-    where
-        F: Fn(
-            &dyn arrow::array::Datum,
-            &dyn arrow::array::Datum,
-        ) -> Result<BooleanArray, arrow::error::ArrowError>,
-    {
+    fn compare_scalar(&self, value: &Value, cmp_fn: CmpKernel) -> DataResult<ArrayRef> {
         let result = match (self.array.data_type(), value) {
             (DataType::Int64, Value::Int(v)) => {
                 let scalar = Scalar::new(Int64Array::from(vec![*v]));
-                cmp_fn(&self.array, &scalar).map_err(|e| DataError::Arrow(e.to_string()))?
+                chunked_scalar(&self.array, &scalar, boolean_kernel(cmp_fn))
+                    .map_err(|e| DataError::Arrow(e.to_string()))?
             }
             (DataType::Int64, Value::Float(v)) => {
                 let arr = self.cast_to_float()?;
                 let scalar = Scalar::new(Float64Array::from(vec![*v]));
-                cmp_fn(&arr, &scalar).map_err(|e| DataError::Arrow(e.to_string()))?
+                chunked_scalar(&arr, &scalar, boolean_kernel(cmp_fn))
+                    .map_err(|e| DataError::Arrow(e.to_string()))?
             }
             (DataType::Float64, Value::Int(v)) => {
                 let scalar = Scalar::new(Float64Array::from(vec![*v as f64]));
-                cmp_fn(&self.array, &scalar).map_err(|e| DataError::Arrow(e.to_string()))?
+                chunked_scalar(&self.array, &scalar, boolean_kernel(cmp_fn))
+                    .map_err(|e| DataError::Arrow(e.to_string()))?
             }
             (DataType::Float64, Value::Float(v)) => {
                 let scalar = Scalar::new(Float64Array::from(vec![*v]));
-                cmp_fn(&self.array, &scalar).map_err(|e| DataError::Arrow(e.to_string()))?
+                chunked_scalar(&self.array, &scalar, boolean_kernel(cmp_fn))
+                    .map_err(|e| DataError::Arrow(e.to_string()))?
             }
             (DataType::Boolean, Value::Bool(v)) => {
                 let scalar = Scalar::new(BooleanArray::from(vec![*v]));
-                cmp_fn(&self.array, &scalar).map_err(|e| DataError::Arrow(e.to_string()))?
+                chunked_scalar(&self.array, &scalar, boolean_kernel(cmp_fn))
+                    .map_err(|e| DataError::Arrow(e.to_string()))?
             }
             (DataType::Utf8, Value::String(v)) => {
                 let scalar = Scalar::new(StringArray::from(vec![v.as_str()]));
-                cmp_fn(&self.array, &scalar).map_err(|e| DataError::Arrow(e.to_string()))?
+                chunked_scalar(&self.array, &scalar, boolean_kernel(cmp_fn))
+                    .map_err(|e| DataError::Arrow(e.to_string()))?
             }
             _ => {
                 return Err(DataError::InvalidOperation(format!(
@@ -7,6 +7,7 @@ use std::sync::Arc;
 
 use arrow::datatypes::Schema;
 use datafusion::datasource::MemTable;
+use datafusion::execution::context::SQLOptions;
 use datafusion::prelude::*;
 
 use super::dataframe::DataFrame;
@@ -20,6 +21,16 @@ fn create_runtime() -> DataResult<tokio::runtime::Runtime> {
         .map_err(|e| DataError::Sql(e.to_string()))
 }
 
+/// What queries may do: read the registered tables, but not create tables
+/// over files, write files with `COPY` or `INSERT`, or change settings.
+/// Those would reach the file system without the VM's permissions.
+fn query_options() -> SQLOptions {
+    SQLOptions::new()
+        .with_allow_ddl(false)
+        .with_allow_dml(false)
+        .with_allow_statements(false)
+}
+
 /// Execute a SQL query against a single DataFrame
 ///
 /// The DataFrame is registered as a table named "df" by default.
@@ -52,7 +63,7 @@ pub fn sql_query_with_name(df: &DataFrame, table_name: &str, query: &str) -> Dat
         register_dataframe(&ctx, table_name, df).await?;
 
         // Execute the query
-        let df_result = ctx.sql(query).await?;
+        let df_result = ctx.sql_with_options(query, query_options()).await?;
 
         // Get schema before collecting (collect consumes the DataFrame)
         let df_schema = df_result.schema().clone();
@@ -102,7 +113,7 @@ impl SqlContext {
     /// Execute a SQL query and return the result as a DataFrame
     pub fn query(&self, sql: &str) -> DataResult<DataFrame> {
         self.runtime.block_on(async {
-            let df_result = self.session.sql(sql).await?;
+            let df_result = self.session.sql_with_options(sql, query_options()).await?;
 
             // Get schema before collecting (collect consumes the DataFrame)
             let df_schema = df_result.schema().clone();
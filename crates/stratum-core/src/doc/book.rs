@@ -0,0 +1,1018 @@
+//! Book generation: guides written as Markdown chapters, combined with the
+//! API documentation into one static site
+//!
+//! Chapters come from a directory such as `docs/`. A `SUMMARY.md` there
+//! gives their order and nesting, as in mdBook:
+//!
+//! ```text
+//! # Summary
+//!
+//! [Introduction](README.md)
+//!
+//! - [Getting started](getting-started.md)
+//!   - [Installing](install.md)
+//! - [Reading files](guide/files.md)
+//! ```
+//!
+//! Without one, every Markdown file is a chapter, `README.md` or `index.md`
+//! first and the rest in path order. `stratum` code blocks are runnable:
+//! each is run while the book is generated and its output shown beside it.
+
+use std::fmt::Write;
+use std::io;
+use std::path::Path;
+
+use super::html::{HtmlGenerator, HtmlOptions};
+use super::project::ProjectDoc;
+use super::search::{
+    entries_to_json, generate_search_css, generate_search_js, project_entries, SearchEntry,
+};
+
+/// A chapter of a book
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chapter {
+    /// Title shown in the table of contents
+    pub title: String,
+    /// Path of the Markdown file, relative to the book's directory and with
+    /// `/` separators
+    pub path: String,
+    /// The chapter's Markdown
+    pub content: String,
+    /// Nesting level in the table of contents, 0 for top-level chapters
+    pub depth: usize,
+}
+
+impl Chapter {
+    /// Path of the chapter's page in the generated site
+    pub fn html_path(&self) -> String {
+        markdown_to_html_path(&self.path)
+    }
+}
+
+/// A book of Markdown chapters
+#[derive(Debug, Clone, Default)]
+pub struct Book {
+    /// Title of the book
+    pub title: String,
+    /// Chapters in reading order
+    pub chapters: Vec<Chapter>,
+}
+
+impl Book {
+    /// Create an empty book
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            chapters: Vec::new(),
+        }
+    }
+
+    /// Load the chapters in `dir`, in the order its `SUMMARY.md` gives or,
+    /// without one, in path order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory or a chapter cannot be read.
+    pub fn load(dir: &Path, title: impl Into<String>) -> io::Result<Self> {
+        let mut book = Self::new(title);
+        let summary = dir.join("SUMMARY.md");
+        let entries = if summary.is_file() {
+            parse_summary(&std::fs::read_to_string(&summary)?)
+        } else {
+            let mut paths = Vec::new();
+            collect_markdown_files(dir, dir, &mut paths)?;
+            paths.sort_by_key(|path| (!is_introduction(path), path.clone()));
+            paths.into_iter().map(|path| (None, path, 0)).collect()
+        };
+
+        for (title, path, depth) in entries {
+            let content = std::fs::read_to_string(dir.join(&path)).map_err(|e| {
+                io::Error::new(e.kind(), format!("Failed to read chapter {path}: {e}"))
+            })?;
+            let title = title
+                .or_else(|| first_heading(&content))
+                .unwrap_or_else(|| file_stem(&path).to_string());
+            book.chapters.push(Chapter {
+                title,
+                path,
+                content,
+                depth,
+            });
+        }
+        Ok(book)
+    }
+}
+
+/// Runs a `stratum` example, returning its output or the error it failed
+/// with
+pub type ExampleRunner<'a> = &'a dyn Fn(&str) -> Result<String, String>;
+
+/// A file of the generated site
+#[derive(Debug, Clone, PartialEq)]
+pub struct BookPage {
+    /// Path relative to the site root, with `/` separators
+    pub path: String,
+    /// File contents
+    pub content: String,
+}
+
+/// Generates the static site for a book
+pub struct BookGenerator<'a> {
+    book: &'a Book,
+    api: Option<&'a ProjectDoc>,
+    runner: Option<ExampleRunner<'a>>,
+}
+
+impl<'a> BookGenerator<'a> {
+    /// Create a generator for `book`
+    pub fn new(book: &'a Book) -> Self {
+        Self {
+            book,
+            api: None,
+            runner: None,
+        }
+    }
+
+    /// Include API documentation, under `api/` in the site
+    #[must_use]
+    pub fn with_api(mut self, project: &'a ProjectDoc) -> Self {
+        self.api = Some(project);
+        self
+    }
+
+    /// Run `stratum` examples with `runner` and show their output
+    #[must_use]
+    pub fn with_runner(mut self, runner: ExampleRunner<'a>) -> Self {
+        self.runner = Some(runner);
+        self
+    }
+
+    /// Generate every page of the site
+    pub fn generate(&self) -> Vec<BookPage> {
+        let mut pages = Vec::new();
+        let mut search = Vec::new();
+
+        for (index, chapter) in self.book.chapters.iter().enumerate() {
+            let page = self.chapter_page(index, &mut search);
+            pages.push(BookPage {
+                path: chapter.html_path(),
+                content: page,
+            });
+        }
+
+        if let Some(project) = self.api {
+            let options = HtmlOptions {
+                enable_search: true,
+                enable_crosslinks: true,
+            };
+            for module in &project.modules {
+                pages.push(BookPage {
+                    path: format!("api/{}.html", module.name),
+                    content: HtmlGenerator::generate_with_project(module, project, &options),
+                });
+            }
+            pages.push(BookPage {
+                path: "api/index.html".to_string(),
+                content: HtmlGenerator::generate_index(project, &options),
+            });
+            pages.push(BookPage {
+                path: "api/search-index.json".to_string(),
+                content: entries_to_json(&project_entries(project)),
+            });
+            search.extend(project_entries(project).into_iter().map(|mut entry| {
+                entry.link = format!("api/{}", entry.link);
+                entry
+            }));
+        }
+
+        pages.push(BookPage {
+            path: "search-index.json".to_string(),
+            content: entries_to_json(&search),
+        });
+
+        // The site opens on the first chapter
+        let start = match (self.book.chapters.first(), self.api) {
+            (Some(chapter), _) => Some(chapter.html_path()),
+            (None, Some(_)) => Some("api/index.html".to_string()),
+            (None, None) => None,
+        };
+        if let Some(start) = start.filter(|start| start != "index.html") {
+            pages.push(BookPage {
+                path: "index.html".to_string(),
+                content: redirect_page(&start),
+            });
+        }
+
+        pages
+    }
+
+    /// The page of chapter `index`, adding its headings to `search`
+    fn chapter_page(&self, index: usize, search: &mut Vec<SearchEntry>) -> String {
+        let chapter = &self.book.chapters[index];
+        let root = "../".repeat(chapter.html_path().matches('/').count());
+        let rendered = MarkdownRenderer::new(self.runner).render(&chapter.content);
+
+        let html_path = chapter.html_path();
+        search.push(SearchEntry {
+            name: chapter.title.clone(),
+            kind: "chapter",
+            module: self.book.title.clone(),
+            description: first_paragraph(&chapter.content),
+            link: html_path.clone(),
+        });
+        for heading in rendered.headings.iter().filter(|h| h.level > 1) {
+            search.push(SearchEntry {
+                name: heading.text.clone(),
+                kind: "section",
+                module: chapter.title.clone(),
+                description: String::new(),
+                link: format!("{html_path}#{}", heading.anchor),
+            });
+        }
+
+        let mut output = String::new();
+        writeln!(output, "<!DOCTYPE html>").unwrap();
+        writeln!(output, "<html lang=\"en\">").unwrap();
+        writeln!(output, "<head>").unwrap();
+        writeln!(output, "  <meta charset=\"UTF-8\">").unwrap();
+        writeln!(
+            output,
+            "  <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">"
+        )
+        .unwrap();
+        writeln!(
+            output,
+            "  <title>{} - {}</title>",
+            escape_html(&chapter.title),
+            escape_html(&self.book.title)
+        )
+        .unwrap();
+        HtmlGenerator::write_styles(&mut output);
+        writeln!(
+            output,
+            "<style>{}{}</style>",
+            generate_search_css(),
+            BOOK_CSS
+        )
+        .unwrap();
+        writeln!(output, "</head>").unwrap();
+        writeln!(output, "<body data-root=\"{root}\">").unwrap();
+
+        // Table of contents
+        writeln!(output, "<nav class=\"sidebar\">").unwrap();
+        writeln!(output, "  <div class=\"sidebar-header\">").unwrap();
+        writeln!(
+            output,
+            "    <h2><a href=\"{root}index.html\">{}</a></h2>",
+            escape_html(&self.book.title)
+        )
+        .unwrap();
+        writeln!(output, "  </div>").unwrap();
+        writeln!(output, "  <div class=\"search-container\">").unwrap();
+        writeln!(output, "    <span class=\"search-icon\">&#128269;</span>").unwrap();
+        writeln!(
+            output,
+            "    <input type=\"text\" id=\"search-input\" placeholder=\"Search...\" autocomplete=\"off\">"
+        )
+        .unwrap();
+        writeln!(output, "    <span class=\"search-hint\">/</span>").unwrap();
+        writeln!(output, "    <div id=\"search-results\"></div>").unwrap();
+        writeln!(output, "  </div>").unwrap();
+        writeln!(output, "  <nav>").unwrap();
+        writeln!(output, "    <ul class=\"toc\">").unwrap();
+        for (i, entry) in self.book.chapters.iter().enumerate() {
+            let class = if i == index { " class=\"active\"" } else { "" };
+            writeln!(
+                output,
+                "      <li class=\"depth-{}\"><a href=\"{root}{}\"{class}>{}</a></li>",
+                entry.depth,
+                entry.html_path(),
+                escape_html(&entry.title)
+            )
+            .unwrap();
+        }
+        writeln!(output, "    </ul>").unwrap();
+        if let Some(project) = self.api {
+            writeln!(output, "    <h3>API Reference</h3>").unwrap();
+            writeln!(output, "    <ul class=\"toc\">").unwrap();
+            writeln!(
+                output,
+                "      <li><a href=\"{root}api/index.html\">{}</a></li>",
+                escape_html(&project.name)
+            )
+            .unwrap();
+            for module in &project.modules {
+                writeln!(
+                    output,
+                    "      <li class=\"depth-1\"><a href=\"{root}api/{0}.html\">{0}</a></li>",
+                    module.name
+                )
+                .unwrap();
+            }
+            writeln!(output, "    </ul>").unwrap();
+        }
+        writeln!(output, "  </nav>").unwrap();
+        writeln!(output, "</nav>").unwrap();
+
+        // Chapter
+        writeln!(output, "<main class=\"content chapter\">").unwrap();
+        output.push_str(&rendered.html);
+
+        // Previous and next chapters
+        let previous = index
+            .checked_sub(1)
+            .map(|i| &self.book.chapters[i])
+            .map(|c| (format!("{root}{}", c.html_path()), c.title.as_str()));
+        let next = match self.book.chapters.get(index + 1) {
+            Some(c) => Some((format!("{root}{}", c.html_path()), c.title.as_str())),
+            None => self
+                .api
+                .map(|_| (format!("{root}api/index.html"), "API Reference")),
+        };
+        writeln!(output, "<nav class=\"chapter-nav\">").unwrap();
+        if let Some((href, title)) = previous {
+            writeln!(
+                output,
+                "  <a class=\"previous\" href=\"{href}\">&larr; {}</a>",
+                escape_html(title)
+            )
+            .unwrap();
+        }
+        if let Some((href, title)) = next {
+            writeln!(
+                output,
+                "  <a class=\"next\" href=\"{href}\">{} &rarr;</a>",
+                escape_html(title)
+            )
+            .unwrap();
+        }
+        writeln!(output, "</nav>").unwrap();
+        writeln!(output, "</main>").unwrap();
+
+        writeln!(
+            output,
+            "<script>{}{}</script>",
+            generate_search_js(),
+            BOOK_JS
+        )
+        .unwrap();
+        writeln!(output, "</body>").unwrap();
+        writeln!(output, "</html>").unwrap();
+        output
+    }
+}
+
+/// A heading of a rendered chapter
+#[derive(Debug, Clone, PartialEq)]
+struct Heading {
+    level: usize,
+    text: String,
+    anchor: String,
+}
+
+/// A chapter rendered to HTML
+struct Rendered {
+    html: String,
+    headings: Vec<Heading>,
+}
+
+/// Renders the Markdown of a chapter to HTML.
+///
+/// Supports headings, paragraphs, fenced code blocks, lists, block quotes,
+/// tables, rules, and inline code, emphasis, links and images. Links to
+/// other chapters' `.md` files are rewritten to their pages.
+struct MarkdownRenderer<'a> {
+    runner: Option<ExampleRunner<'a>>,
+    headings: Vec<Heading>,
+}
+
+impl<'a> MarkdownRenderer<'a> {
+    fn new(runner: Option<ExampleRunner<'a>>) -> Self {
+        Self {
+            runner,
+            headings: Vec::new(),
+        }
+    }
+
+    fn render(mut self, markdown: &str) -> Rendered {
+        let lines: Vec<&str> = markdown.lines().collect();
+        let html = self.render_blocks(&lines);
+        Rendered {
+            html,
+            headings: self.headings,
+        }
+    }
+
+    fn render_blocks(&mut self, lines: &[&str]) -> String {
+        let mut output = String::new();
+        let mut i = 0;
+        while i < lines.len() {
+            let trimmed = lines[i].trim();
+            if trimmed.is_empty() {
+                i += 1;
+            } else if let Some(info) = trimmed.strip_prefix("```") {
+                let end = lines[i + 1..]
+                    .iter()
+                    .position(|line| line.trim().starts_with("```"))
+                    .map_or(lines.len(), |offset| i + 1 + offset);
+                self.write_code_block(&mut output, info.trim(), &lines[i + 1..end]);
+                i = end + 1;
+            } else if let Some((level, text)) = parse_heading(trimmed) {
+                let anchor = self.anchor_for(text);
+                writeln!(
+                    output,
+                    "<h{level} id=\"{anchor}\">{}</h{level}>",
+                    inline(text)
+                )
+                .unwrap();
+                self.headings.push(Heading {
+                    level,
+                    text: text.to_string(),
+                    anchor,
+                });
+                i += 1;
+            } else if matches!(trimmed, "---" | "***" | "___") {
+                writeln!(output, "<hr>").unwrap();
+                i += 1;
+            } else if trimmed.starts_with('>') {
+                let end = block_end(lines, i, |line| line.trim().starts_with('>'));
+                let quoted: Vec<&str> = lines[i..end]
+                    .iter()
+                    .map(|line| {
+                        let line = line.trim().trim_start_matches('>');
+                        line.strip_prefix(' ').unwrap_or(line)
+                    })
+                    .collect();
+                let inner = self.render_blocks(&quoted);
+                writeln!(output, "<blockquote>\n{inner}</blockquote>").unwrap();
+                i = end;
+            } else if is_table(lines, i) {
+                i = Self::write_table(&mut output, lines, i);
+            } else if list_marker(lines[i]).is_some() {
+                i = self.write_list(&mut output, lines, i);
+            } else {
+                let end = block_end(lines, i, |line| {
+                    let trimmed = line.trim();
+                    !trimmed.is_empty()
+                        && !trimmed.starts_with("```")
+                        && !trimmed.starts_with('>')
+                        && parse_heading(trimmed).is_none()
+                        && list_marker(line).is_none()
+                });
+                let text: Vec<&str> = lines[i..end].iter().map(|line| line.trim()).collect();
+                writeln!(output, "<p>{}</p>", inline(&text.join(" "))).unwrap();
+                i = end;
+            }
+        }
+        output
+    }
+
+    fn write_code_block(&mut self, output: &mut String, info: &str, lines: &[&str]) {
+        let code = lines.join("\n");
+        let mut attributes = info.split(|c: char| c == ',' || c.is_whitespace());
+        let language = attributes.next().unwrap_or("");
+        let runnable = language == "stratum"
+            && !attributes.any(|attribute| matches!(attribute, "ignore" | "no_run"));
+        let class = if language.is_empty() {
+            String::new()
+        } else {
+            format!(" class=\"language-{}\"", escape_html(language))
+        };
+        let block = format!("<pre><code{class}>{}</code></pre>", escape_html(&code));
+
+        match self.runner.filter(|_| runnable) {
+            Some(runner) => {
+                let (class, result) = match runner(&code) {
+                    Ok(output) => ("example-output", output),
+                    Err(error) => ("example-output error", error),
+                };
+                writeln!(
+                    output,
+                    "<div class=\"runnable\">\n{block}\n<div class=\"example-buttons\">\
+                     <button type=\"button\" class=\"run-example\">Run</button>\
+                     <button type=\"button\" class=\"copy-example\">Copy</button></div>\n\
+                     <pre class=\"{class}\" hidden>{}</pre>\n</div>",
+                    escape_html(&result)
+                )
+                .unwrap();
+            }
+            None => writeln!(output, "{block}").unwrap(),
+        }
+    }
+
+    /// Write the table starting at line `start`, returning the line after it
+    fn write_table(output: &mut String, lines: &[&str], start: usize) -> usize {
+        let end = block_end(lines, start, |line| line.trim().starts_with('|'));
+        writeln!(output, "<table>").unwrap();
+        writeln!(output, "<thead><tr>").unwrap();
+        for cell in table_cells(lines[start]) {
+            writeln!(output, "<th>{}</th>", inline(cell)).unwrap();
+        }
+        writeln!(output, "</tr></thead>").unwrap();
+        writeln!(output, "<tbody>").unwrap();
+        // The second line separates the header from the rows
+        for line in &lines[start + 2..end] {
+            write!(output, "<tr>").unwrap();
+            for cell in table_cells(line) {
+                write!(output, "<td>{}</td>", inline(cell)).unwrap();
+            }
+            writeln!(output, "</tr>").unwrap();
+        }
+        writeln!(output, "</tbody>").unwrap();
+        writeln!(output, "</table>").unwrap();
+        end
+    }
+
+    /// Write the list starting at line `start`, returning the line after it.
+    ///
+    /// Lines indented past an item's marker belong to the item, so nested
+    /// lists are rendered within it.
+    fn write_list(&mut self, output: &mut String, lines: &[&str], start: usize) -> usize {
+        let Some((ordered, indent, _)) = list_marker(lines[start]) else {
+            return start + 1;
+        };
+        let tag = if ordered { "ol" } else { "ul" };
+        writeln!(output, "<{tag}>").unwrap();
+
+        let mut i = start;
+        while i < lines.len() {
+            let Some((item_ordered, item_indent, text)) = list_marker(lines[i]) else {
+                break;
+            };
+            if item_indent != indent || item_ordered != ordered {
+                break;
+            }
+            let end = block_end(lines, i + 1, |line| {
+                line.trim().is_empty() || indentation(line) > indent
+            });
+            // Trailing blank lines end the item rather than belonging to it
+            let mut end_of_item = end;
+            while end_of_item > i + 1 && lines[end_of_item - 1].trim().is_empty() {
+                end_of_item -= 1;
+            }
+            let nested: Vec<&str> = lines[i + 1..end_of_item]
+                .iter()
+                .map(|line| line.get(indent + 2..).unwrap_or_else(|| line.trim_start()))
+                .collect();
+
+            write!(output, "<li>{}", inline(text)).unwrap();
+            if !nested.is_empty() {
+                write!(output, "\n{}", self.render_blocks(&nested)).unwrap();
+            }
+            writeln!(output, "</li>").unwrap();
+            i = end;
+        }
+
+        writeln!(output, "</{tag}>").unwrap();
+        i
+    }
+
+    /// A unique anchor for a heading
+    fn anchor_for(&self, text: &str) -> String {
+        let base: String = text
+            .to_lowercase()
+            .chars()
+            .filter_map(|c| match c {
+                c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+                ' ' => Some('-'),
+                _ => None,
+            })
+            .collect();
+        let mut anchor = base.clone();
+        let mut n = 1;
+        while self.headings.iter().any(|h| h.anchor == anchor) {
+            anchor = format!("{base}-{n}");
+            n += 1;
+        }
+        anchor
+    }
+}
+
+/// Render inline Markdown: code spans, emphasis, links and images
+fn inline(text: &str) -> String {
+    let mut output = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c == '`' {
+            if let Some(end) = rest[1..].find('`') {
+                write!(output, "<code>{}</code>", escape_html(&rest[1..=end])).unwrap();
+                rest = &rest[end + 2..];
+                continue;
+            }
+        } else if rest.starts_with("**") {
+            if let Some(end) = rest[2..].find("**") {
+                write!(output, "<strong>{}</strong>", inline(&rest[2..end + 2])).unwrap();
+                rest = &rest[end + 4..];
+                continue;
+            }
+        } else if c == '*' {
+            if let Some(end) = rest[1..].find('*').filter(|&end| end > 0) {
+                write!(output, "<em>{}</em>", inline(&rest[1..=end])).unwrap();
+                rest = &rest[end + 2..];
+                continue;
+            }
+        } else if c == '[' || rest.starts_with("![") {
+            let image = c == '!';
+            let label_start = if image { 2 } else { 1 };
+            if let Some((label, url, length)) = parse_link(&rest[label_start..]) {
+                let url = escape_html(&rewrite_link(url));
+                if image {
+                    write!(output, "<img src=\"{url}\" alt=\"{}\">", escape_html(label)).unwrap();
+                } else {
+                    write!(output, "<a href=\"{url}\">{}</a>", inline(label)).unwrap();
+                }
+                rest = &rest[label_start + length..];
+                continue;
+            }
+        }
+        output.push_str(&escape_html(&rest[..c.len_utf8()]));
+        rest = &rest[c.len_utf8()..];
+    }
+    output
+}
+
+/// Point links to other chapters at their pages
+fn rewrite_link(url: &str) -> String {
+    if url.contains("://") || url.starts_with('#') || url.starts_with('/') {
+        return url.to_string();
+    }
+    let (path, fragment) = match url.find('#') {
+        Some(i) => url.split_at(i),
+        None => (url, ""),
+    };
+    if Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("md"))
+    {
+        // Chapter pages keep the layout of the Markdown files
+        format!("{}{fragment}", markdown_to_html_path(path))
+    } else {
+        url.to_string()
+    }
+}
+
+/// `[label](url)` at the start of `text` (after the `[`), as the label, the
+/// URL and the length of the link after the `[`
+fn parse_link(text: &str) -> Option<(&str, &str, usize)> {
+    let label_end = text.find("](")?;
+    let url_end = label_end + 2 + text[label_end + 2..].find(')')?;
+    let label = &text[..label_end];
+    if label.contains('[') {
+        return None;
+    }
+    Some((label, &text[label_end + 2..url_end], url_end + 1))
+}
+
+/// The heading level and text of an ATX heading line
+fn parse_heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let text = line[level..].strip_prefix(' ')?;
+    Some((level, text.trim().trim_end_matches('#').trim_end()))
+}
+
+/// Whether a line is a list item, and if so whether the list is ordered, the
+/// item's indentation and its text
+fn list_marker(line: &str) -> Option<(bool, usize, &str)> {
+    let indent = indentation(line);
+    let trimmed = line.trim_start();
+    for marker in ["- ", "* ", "+ "] {
+        if let Some(text) = trimmed.strip_prefix(marker) {
+            return Some((false, indent, text.trim()));
+        }
+    }
+    let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+    if digits > 0 {
+        if let Some(text) = trimmed[digits..].strip_prefix(". ") {
+            return Some((true, indent, text.trim()));
+        }
+    }
+    None
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Whether a table, a header row followed by a `|---|` separator, starts at
+/// line `i`
+fn is_table(lines: &[&str], i: usize) -> bool {
+    lines[i].trim().starts_with('|')
+        && lines.get(i + 1).is_some_and(|line| {
+            let line = line.trim();
+            line.starts_with('|') && line.chars().all(|c| matches!(c, '|' | '-' | ':' | ' '))
+        })
+}
+
+fn table_cells(line: &str) -> impl Iterator<Item = &str> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = line.strip_suffix('|').unwrap_or(line);
+    line.split('|').map(str::trim)
+}
+
+/// The end of the block starting at line `start` whose lines all satisfy
+/// `continues`
+fn block_end(lines: &[&str], start: usize, continues: impl Fn(&str) -> bool) -> usize {
+    lines[start..]
+        .iter()
+        .position(|line| !continues(line))
+        .map_or(lines.len(), |offset| start + offset)
+}
+
+/// Parse the chapter entries of a `SUMMARY.md` as their title, path and
+/// depth
+fn parse_summary(summary: &str) -> Vec<(Option<String>, String, usize)> {
+    summary
+        .lines()
+        .filter_map(|line| {
+            let depth = indentation(line) / 2;
+            let trimmed = line.trim_start();
+            let trimmed = trimmed
+                .strip_prefix("- ")
+                .or_else(|| trimmed.strip_prefix("* "))
+                .unwrap_or(trimmed);
+            let (title, path, _) = parse_link(trimmed.strip_prefix('[')?)?;
+            (!path.is_empty()).then(|| (Some(title.to_string()), path.to_string(), depth))
+        })
+        .collect()
+}
+
+/// Add the Markdown files under `dir` to `paths`, relative to `root`
+fn collect_markdown_files(root: &Path, dir: &Path, paths: &mut Vec<String>) -> io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_markdown_files(root, &path, paths)?;
+        } else if path.extension().is_some_and(|ext| ext == "md") {
+            if let Ok(relative) = path.strip_prefix(root) {
+                let parts: Vec<_> = relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect();
+                paths.push(parts.join("/"));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn is_introduction(path: &str) -> bool {
+    matches!(path, "README.md" | "index.md")
+}
+
+fn first_heading(markdown: &str) -> Option<String> {
+    markdown
+        .lines()
+        .find_map(|line| parse_heading(line.trim()).filter(|(level, _)| *level == 1))
+        .map(|(_, text)| text.to_string())
+}
+
+/// The first paragraph of a chapter, for search results
+fn first_paragraph(markdown: &str) -> String {
+    markdown
+        .split("\n\n")
+        .map(str::trim)
+        .find(|block| {
+            !block.is_empty()
+                && !block.starts_with('#')
+                && !block.starts_with("```")
+                && !block.starts_with('|')
+        })
+        .map(|block| block.split_whitespace().collect::<Vec<_>>().join(" "))
+        .unwrap_or_default()
+}
+
+fn file_stem(path: &str) -> &str {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    name.strip_suffix(".md").unwrap_or(name)
+}
+
+fn markdown_to_html_path(path: &str) -> String {
+    let stem = path.len() - Path::new(path).extension().map_or(0, |ext| ext.len() + 1);
+    format!("{}.html", &path[..stem])
+}
+
+fn redirect_page(target: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n  <meta charset=\"UTF-8\">\n  \
+         <meta http-equiv=\"refresh\" content=\"0; url={target}\">\n  \
+         <title>Redirecting</title>\n</head>\n<body>\n  \
+         <a href=\"{target}\">{target}</a>\n</body>\n</html>\n"
+    )
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const BOOK_CSS: &str = r"
+.toc li.depth-1 { padding-left: 1rem; }
+.toc li.depth-2 { padding-left: 2rem; }
+.toc li.depth-3 { padding-left: 3rem; }
+.toc a.active { color: var(--accent-color); font-weight: 600; }
+
+.chapter h1 { font-size: 2.25rem; color: var(--accent-color); margin-bottom: 1rem; }
+.chapter h2 { font-size: 1.5rem; color: var(--accent-color); margin: 2rem 0 1rem; }
+.chapter h3, .chapter h4 { margin: 1.5rem 0 0.75rem; }
+.chapter p, .chapter ul, .chapter ol, .chapter table, .chapter blockquote { margin: 0.75rem 0; }
+.chapter ul, .chapter ol { padding-left: 1.5rem; }
+.chapter a { color: var(--accent-color); }
+.chapter code { background: var(--code-bg); padding: 0.1rem 0.3rem; border-radius: 3px; }
+.chapter pre { background: #0d1117; padding: 1rem; border-radius: 6px; overflow-x: auto; margin: 1rem 0; }
+.chapter pre code { background: none; padding: 0; }
+.chapter blockquote { border-left: 3px solid var(--accent-color); padding-left: 1rem; color: #bbb; }
+.chapter table { border-collapse: collapse; }
+.chapter th, .chapter td { border: 1px solid var(--border-color); padding: 0.4rem 0.75rem; text-align: left; }
+.chapter hr { border: none; border-top: 1px solid var(--border-color); margin: 2rem 0; }
+
+.runnable { position: relative; }
+.runnable pre:first-child { margin-bottom: 0; }
+.example-buttons { position: absolute; top: 0.5rem; right: 0.5rem; }
+.example-buttons button {
+  background: var(--code-bg);
+  color: var(--text-color);
+  border: 1px solid var(--border-color);
+  border-radius: 4px;
+  padding: 0.2rem 0.6rem;
+  margin-left: 0.25rem;
+  cursor: pointer;
+}
+.example-buttons button:hover { border-color: var(--accent-color); }
+.example-output { border-top: 1px solid var(--border-color); margin-top: 0; }
+.example-output.error { color: #f48771; }
+
+.chapter-nav { display: flex; justify-content: space-between; margin: 3rem 0 1rem; }
+.chapter-nav .next { margin-left: auto; }
+";
+
+const BOOK_JS: &str = r"
+// Runnable examples: show the output recorded when the book was generated
+document.querySelectorAll('.runnable').forEach(function(example) {
+    const code = example.querySelector('code').textContent;
+    const output = example.querySelector('.example-output');
+    example.querySelector('.run-example').addEventListener('click', function() {
+        output.hidden = !output.hidden;
+    });
+    example.querySelector('.copy-example').addEventListener('click', function() {
+        navigator.clipboard.writeText(code);
+    });
+});
+";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::doc::DocExtractor;
+    use crate::Parser;
+
+    fn chapter(title: &str, path: &str, content: &str, depth: usize) -> Chapter {
+        Chapter {
+            title: title.to_string(),
+            path: path.to_string(),
+            content: content.to_string(),
+            depth,
+        }
+    }
+
+    fn render(markdown: &str) -> String {
+        MarkdownRenderer::new(None).render(markdown).html
+    }
+
+    #[test]
+    fn test_parse_summary() {
+        let summary = "# Summary\n\n[Introduction](README.md)\n\n- [Getting started](start.md)\n  - [Installing](guide/install.md)\n- [Draft]()\n";
+        assert_eq!(
+            parse_summary(summary),
+            [
+                (Some("Introduction".to_string()), "README.md".to_string(), 0),
+                (
+                    Some("Getting started".to_string()),
+                    "start.md".to_string(),
+                    0
+                ),
+                (
+                    Some("Installing".to_string()),
+                    "guide/install.md".to_string(),
+                    1
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_load_without_summary() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("guide")).unwrap();
+        std::fs::write(dir.path().join("README.md"), "# Welcome\n\nHello.").unwrap();
+        std::fs::write(dir.path().join("guide/files.md"), "Reading files.").unwrap();
+        std::fs::write(dir.path().join("about.md"), "# About us\n").unwrap();
+
+        let book = Book::load(dir.path(), "Guide").unwrap();
+        let chapters: Vec<_> = book
+            .chapters
+            .iter()
+            .map(|c| (c.title.as_str(), c.path.as_str()))
+            .collect();
+        assert_eq!(
+            chapters,
+            [
+                ("Welcome", "README.md"),
+                ("About us", "about.md"),
+                ("files", "guide/files.md"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_blocks() {
+        let html = render(
+            "# Title\n\nSome *emphasis*, **strong** and `code <T>`.\nSame paragraph.\n\n- one\n- two\n  - nested\n\n1. first\n\n> quoted\n\n| A | B |\n|---|---|\n| 1 | 2 |\n\n---",
+        );
+        assert_eq!(
+            html,
+            "<h1 id=\"title\">Title</h1>\n\
+             <p>Some <em>emphasis</em>, <strong>strong</strong> and <code>code &lt;T&gt;</code>. Same paragraph.</p>\n\
+             <ul>\n<li>one</li>\n<li>two\n<ul>\n<li>nested</li>\n</ul>\n</li>\n</ul>\n\
+             <ol>\n<li>first</li>\n</ol>\n\
+             <blockquote>\n<p>quoted</p>\n</blockquote>\n\
+             <table>\n<thead><tr>\n<th>A</th>\n<th>B</th>\n</tr></thead>\n<tbody>\n<tr><td>1</td><td>2</td></tr>\n</tbody>\n</table>\n\
+             <hr>\n"
+        );
+    }
+
+    #[test]
+    fn test_render_links() {
+        assert_eq!(
+            inline("See [files](guide/files.md#reading), [site](https://x.dev) and ![logo](logo.png)"),
+            "See <a href=\"guide/files.html#reading\">files</a>, <a href=\"https://x.dev\">site</a> and <img src=\"logo.png\" alt=\"logo\">"
+        );
+        assert_eq!(inline("a [b] c"), "a [b] c");
+    }
+
+    #[test]
+    fn test_runnable_examples() {
+        let runner = |code: &str| {
+            if code.contains("fail") {
+                Err("error: failed".to_string())
+            } else {
+                Ok("3\n".to_string())
+            }
+        };
+        let html = MarkdownRenderer::new(Some(&runner)).render(
+            "```stratum\nprint(1 + 2)\n```\n\n```stratum\nfail()\n```\n\n```stratum,no_run\nserve()\n```\n\n```sh\nls\n```",
+        ).html;
+        assert!(html.contains("<pre class=\"example-output\" hidden>3\n</pre>"));
+        assert!(html.contains("<pre class=\"example-output error\" hidden>error: failed</pre>"));
+        assert_eq!(html.matches("class=\"runnable\"").count(), 2);
+        assert!(html.contains("<pre><code class=\"language-sh\">ls</code></pre>"));
+    }
+
+    #[test]
+    fn test_generate_book() {
+        let mut book = Book::new("Guide");
+        book.chapters.push(chapter(
+            "Introduction",
+            "README.md",
+            "# Introduction\n\nStart [here](guide/files.md).\n\n## Setup\n",
+            0,
+        ));
+        book.chapters
+            .push(chapter("Files", "guide/files.md", "# Files\n", 1));
+        let module = Parser::parse_module("/// Read a file.\nfx read(path: String) {}").unwrap();
+        let mut project = ProjectDoc::new("app");
+        project.add_module(DocExtractor::extract(&module, "io"));
+
+        let pages = BookGenerator::new(&book).with_api(&project).generate();
+        let page = |path: &str| {
+            &pages
+                .iter()
+                .find(|page| page.path == path)
+                .unwrap_or_else(|| panic!("no page {path}"))
+                .content
+        };
+
+        let intro = page("README.html");
+        assert!(intro.contains("<a href=\"README.html\" class=\"active\">Introduction</a>"));
+        assert!(intro.contains("<a class=\"next\" href=\"guide/files.html\">Files &rarr;</a>"));
+        assert!(intro.contains("<a href=\"api/io.html\">io</a>"));
+
+        let files = page("guide/files.html");
+        assert!(files.contains("<body data-root=\"../\">"));
+        assert!(files.contains("<a class=\"previous\" href=\"../README.html\">"));
+        assert!(files.contains("<a class=\"next\" href=\"../api/index.html\">"));
+
+        assert!(page("index.html").contains("url=README.html"));
+        assert!(page("api/io.html").contains("Read a file."));
+        let search = page("search-index.json");
+        assert!(search.contains(
+            r#""n":"Setup","k":"section","m":"Introduction","d":"","l":"README.html#setup""#
+        ));
+        assert!(search.contains(r#""l":"api/io.html#read""#));
+    }
+}
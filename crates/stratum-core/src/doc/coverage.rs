@@ -0,0 +1,183 @@
+//! Documentation coverage: how many public functions, structs and enums
+//! have doc comments
+
+use crate::ast::{ItemKind as AstItemKind, Module};
+use crate::lexer::Span;
+
+use super::project::ProjectDoc;
+use super::types::{DocumentedModule, ItemKind};
+use super::DocExtractor;
+
+/// Documentation coverage of one module
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleCoverage {
+    /// Module name
+    pub module: String,
+    /// Number of public functions, structs and enums
+    pub total: usize,
+    /// Names of those without a doc comment, in source order
+    pub undocumented: Vec<String>,
+}
+
+impl ModuleCoverage {
+    /// Measure the coverage of a documented module
+    pub fn of(module: &DocumentedModule) -> Self {
+        let counted: Vec<_> = module
+            .items
+            .iter()
+            .filter(|item| item.public && is_counted(item.kind))
+            .collect();
+        Self {
+            module: module.name.clone(),
+            total: counted.len(),
+            undocumented: counted
+                .iter()
+                .filter(|item| item.doc.is_none())
+                .map(|item| item.name.clone())
+                .collect(),
+        }
+    }
+
+    /// Number of items with a doc comment
+    pub fn documented(&self) -> usize {
+        self.total - self.undocumented.len()
+    }
+
+    /// Percentage of items with a doc comment; 100 when there are none
+    pub fn percentage(&self) -> f64 {
+        percentage(self.documented(), self.total)
+    }
+}
+
+/// Documentation coverage of a project
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DocCoverage {
+    /// Coverage of each module
+    pub modules: Vec<ModuleCoverage>,
+}
+
+impl DocCoverage {
+    /// Measure the coverage of every module of a project
+    pub fn of(project: &ProjectDoc) -> Self {
+        Self {
+            modules: project.modules.iter().map(ModuleCoverage::of).collect(),
+        }
+    }
+
+    /// Number of public functions, structs and enums across all modules
+    pub fn total(&self) -> usize {
+        self.modules.iter().map(|m| m.total).sum()
+    }
+
+    /// Number of those with a doc comment
+    pub fn documented(&self) -> usize {
+        self.modules.iter().map(ModuleCoverage::documented).sum()
+    }
+
+    /// Percentage of items with a doc comment across all modules
+    pub fn percentage(&self) -> f64 {
+        percentage(self.documented(), self.total())
+    }
+}
+
+/// A public function, struct or enum without a doc comment
+#[derive(Debug, Clone, PartialEq)]
+pub struct MissingDoc {
+    /// Name of the item
+    pub name: String,
+    /// Kind of item
+    pub kind: ItemKind,
+    /// Location of the item's name
+    pub span: Span,
+}
+
+/// Find the public functions, structs and enums of a module that have no
+/// doc comment
+pub fn missing_docs(module: &Module) -> Vec<MissingDoc> {
+    module
+        .items()
+        .into_iter()
+        .filter_map(|item| match &item.kind {
+            AstItemKind::Function(f) => Some((&f.name, f.trivia.doc.is_some(), ItemKind::Function)),
+            AstItemKind::Struct(s) => Some((&s.name, s.trivia.doc.is_some(), ItemKind::Struct)),
+            AstItemKind::Enum(e) => Some((&e.name, e.trivia.doc.is_some(), ItemKind::Enum)),
+            _ => None,
+        })
+        .filter(|(name, documented, _)| !documented && DocExtractor::is_public_name(&name.name))
+        .map(|(name, _, kind)| MissingDoc {
+            name: name.name.clone(),
+            kind,
+            span: name.span,
+        })
+        .collect()
+}
+
+fn is_counted(kind: ItemKind) -> bool {
+    matches!(kind, ItemKind::Function | ItemKind::Struct | ItemKind::Enum)
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn percentage(documented: usize, total: usize) -> f64 {
+    if total == 0 {
+        100.0
+    } else {
+        documented as f64 * 100.0 / total as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+
+    const SOURCE: &str = r"
+/// Read a file.
+fx read(path: String) {}
+
+fx write(path: String) {}
+
+fx _helper() {}
+
+struct Row { cells: List<String> }
+
+/// How a file is opened.
+enum Mode { Read, Write }
+
+interface Named { fx name(self) -> String }
+";
+
+    #[test]
+    fn test_module_coverage() {
+        let module = Parser::parse_module(SOURCE).unwrap();
+        let coverage = ModuleCoverage::of(&DocExtractor::extract(&module, "io"));
+        assert_eq!(coverage.total, 4);
+        assert_eq!(coverage.undocumented, ["write", "Row"]);
+        assert_eq!(coverage.documented(), 2);
+        assert!((coverage.percentage() - 50.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_project_coverage() {
+        let mut project = ProjectDoc::new("app");
+        for (name, source) in [("io", SOURCE), ("empty", "fx _private() {}")] {
+            let module = Parser::parse_module(source).unwrap();
+            project.add_module(DocExtractor::extract(&module, name));
+        }
+        let coverage = DocCoverage::of(&project);
+        assert_eq!((coverage.documented(), coverage.total()), (2, 4));
+        assert!((coverage.modules[1].percentage() - 100.0).abs() < f64::EPSILON);
+        assert!((DocCoverage::default().percentage() - 100.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_missing_docs() {
+        let module = Parser::parse_module(SOURCE).unwrap();
+        let missing = missing_docs(&module);
+        let names: Vec<_> = missing.iter().map(|m| (m.name.as_str(), m.kind)).collect();
+        assert_eq!(
+            names,
+            [("write", ItemKind::Function), ("Row", ItemKind::Struct)]
+        );
+        assert_eq!(missing[0].span, Span::from_range(47..52));
+    }
+}
@@ -6,6 +6,7 @@
 use std::collections::HashSet;
 
 use super::project::ProjectDoc;
+use crate::ast::intra_doc_links;
 
 /// Configuration for cross-linking
 #[derive(Debug, Clone)]
@@ -58,9 +59,35 @@ impl<'a> CrossLinker<'a> {
         self.add_links_to_text(signature)
     }
 
-    /// Process description text and add cross-links
+    /// Process description text, adding cross-links and resolving
+    /// intra-doc links such as `[parse]`
     pub fn link_description(&self, text: &str) -> String {
-        self.add_links_to_text(text)
+        let mut result = String::new();
+        let mut last = 0;
+        for link in intra_doc_links(text) {
+            result.push_str(&self.add_links_to_text(&text[last..link.range.start]));
+            result.push_str(&self.link_intra_doc(&link.target));
+            last = link.range.end;
+        }
+        result.push_str(&self.add_links_to_text(&text[last..]));
+        result
+    }
+
+    /// Render an intra-doc link, or just the name as code if it does not
+    /// resolve
+    fn link_intra_doc(&self, target: &str) -> String {
+        match self
+            .project
+            .resolve_link(target, &self.config.current_module)
+        {
+            Some(symbol) => format!(
+                "<a href=\"{}\" class=\"doc-link\"><code>{}</code></a>",
+                self.project
+                    .link_to_symbol(symbol, &self.config.current_module),
+                target
+            ),
+            None => format!("<code>{}</code>", target),
+        }
     }
 
     /// Add links to type names in text
@@ -157,6 +184,7 @@ fn is_keyword(word: &str) -> bool {
     matches!(
         word,
         "fx" | "let"
+            | "const"
             | "if"
             | "else"
             | "while"
@@ -270,6 +298,25 @@ mod tests {
         assert!(!result.contains("href=\"#string\""));
     }
 
+    #[test]
+    fn test_link_description_intra_doc_links() {
+        let project = create_test_project();
+        let linker = CrossLinker::new(
+            &project,
+            CrossLinkConfig {
+                current_module: "other".to_string(),
+                link_external: true,
+            },
+        );
+
+        let result = linker.link_description("Call [greet] or [`missing`]");
+        assert_eq!(
+            result,
+            "Call <a href=\"utils.html#greet\" class=\"doc-link\"><code>greet</code></a> \
+             or <code>missing</code>"
+        );
+    }
+
     #[test]
     fn test_extract_type_names() {
         let sig = "fx process(input: DataFrame, filter: Filter<T>) -> Result<Output>";
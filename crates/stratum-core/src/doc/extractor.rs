@@ -5,7 +5,7 @@ use crate::ast::{
     TopLevelItem, TypeAnnotation,
 };
 
-use super::types::{DocComment, DocumentedItem, DocumentedModule, ItemKind};
+use super::types::{DocumentedItem, DocumentedModule, ItemKind};
 
 /// Extracts documentation from a parsed AST
 pub struct DocExtractor;
@@ -16,9 +16,7 @@ impl DocExtractor {
         let mut doc_module = DocumentedModule::new(name.to_string());
 
         // Extract module-level documentation from leading trivia
-        if let Some(doc_text) = module.trivia.doc_text() {
-            doc_module.doc = Some(DocComment::parse(&doc_text));
-        }
+        doc_module.doc = module.trivia.doc.clone();
 
         // Process all top-level items
         for top_level in &module.top_level {
@@ -30,11 +28,13 @@ impl DocExtractor {
                 }
                 TopLevelItem::Let(let_decl) => {
                     // Extract documentation for top-level constants
-                    if let Some(doc_text) = let_decl.trivia.doc_text() {
+                    if let Some(doc) = &let_decl.trivia.doc {
                         let name = Self::pattern_to_name(&let_decl.pattern);
                         let sig = Self::format_let_signature(let_decl);
-                        let mut item = DocumentedItem::new(name, ItemKind::Constant, sig);
-                        item.doc = Some(DocComment::parse(&doc_text));
+                        let public = Self::is_public_name(&name);
+                        let mut item =
+                            DocumentedItem::new(name, ItemKind::Constant, sig).with_public(public);
+                        item.doc = Some(doc.clone());
                         doc_module.add_item(item);
                     }
                 }
@@ -57,22 +57,26 @@ impl DocExtractor {
     }
 
     fn extract_function(func: &Function) -> DocumentedItem {
-        let doc = func.trivia.doc_text().map(|t| DocComment::parse(&t));
+        let doc = func.trivia.doc.clone();
         let sig = Self::format_function_signature(func);
-        DocumentedItem::new(func.name.name.clone(), ItemKind::Function, sig).with_doc(doc)
+        DocumentedItem::new(func.name.name.clone(), ItemKind::Function, sig)
+            .with_doc(doc)
+            .with_public(Self::is_public_name(&func.name.name))
     }
 
     fn extract_struct(s: &StructDef) -> DocumentedItem {
-        let doc = s.trivia.doc_text().map(|t| DocComment::parse(&t));
+        let doc = s.trivia.doc.clone();
         let sig = Self::format_struct_signature(s);
-        let mut item =
-            DocumentedItem::new(s.name.name.clone(), ItemKind::Struct, sig).with_doc(doc);
+        let mut item = DocumentedItem::new(s.name.name.clone(), ItemKind::Struct, sig)
+            .with_doc(doc)
+            .with_public(Self::is_public_name(&s.name.name));
 
         // Add fields as children
         for field in &s.fields {
             let field_sig = format!("{}: {}", field.name.name, Self::format_type(&field.ty));
             let field_item =
-                DocumentedItem::new(field.name.name.clone(), ItemKind::Field, field_sig);
+                DocumentedItem::new(field.name.name.clone(), ItemKind::Field, field_sig)
+                    .with_public(field.is_public && Self::is_public_name(&field.name.name));
             item.add_child(field_item);
         }
 
@@ -80,9 +84,11 @@ impl DocExtractor {
     }
 
     fn extract_enum(e: &EnumDef) -> DocumentedItem {
-        let doc = e.trivia.doc_text().map(|t| DocComment::parse(&t));
+        let doc = e.trivia.doc.clone();
         let sig = Self::format_enum_signature(e);
-        let mut item = DocumentedItem::new(e.name.name.clone(), ItemKind::Enum, sig).with_doc(doc);
+        let mut item = DocumentedItem::new(e.name.name.clone(), ItemKind::Enum, sig)
+            .with_doc(doc)
+            .with_public(Self::is_public_name(&e.name.name));
 
         // Add variants as children
         for variant in &e.variants {
@@ -96,10 +102,11 @@ impl DocExtractor {
     }
 
     fn extract_interface(i: &InterfaceDef) -> DocumentedItem {
-        let doc = i.trivia.doc_text().map(|t| DocComment::parse(&t));
+        let doc = i.trivia.doc.clone();
         let sig = Self::format_interface_signature(i);
-        let mut item =
-            DocumentedItem::new(i.name.name.clone(), ItemKind::Interface, sig).with_doc(doc);
+        let mut item = DocumentedItem::new(i.name.name.clone(), ItemKind::Interface, sig)
+            .with_doc(doc)
+            .with_public(Self::is_public_name(&i.name.name));
 
         // Add methods as children
         for method in &i.methods {
@@ -113,7 +120,7 @@ impl DocExtractor {
     }
 
     fn extract_impl(i: &ImplDef) -> DocumentedItem {
-        let doc = i.trivia.doc_text().map(|t| DocComment::parse(&t));
+        let doc = i.trivia.doc.clone();
         let sig = Self::format_impl_signature(i);
         let name = Self::format_impl_name(i);
         let mut item = DocumentedItem::new(name, ItemKind::Impl, sig).with_doc(doc);
@@ -127,6 +134,11 @@ impl DocExtractor {
         item
     }
 
+    /// Items and fields named with a leading underscore are private
+    pub(super) fn is_public_name(name: &str) -> bool {
+        !name.starts_with('_')
+    }
+
     // Formatting helpers
 
     fn format_function_signature(func: &Function) -> String {
@@ -425,4 +437,37 @@ struct Point {
         assert_eq!(item.kind, ItemKind::Struct);
         assert_eq!(item.children.len(), 2);
     }
+
+    #[test]
+    fn test_extract_visibility() {
+        let source = r#"
+struct Config {
+    name: String,
+    _cache: Int,
+}
+
+fx _helper() {}
+
+fx run() {}
+"#;
+
+        let module = Parser::parse_module(source).unwrap();
+        let doc_module = DocExtractor::extract(&module, "test");
+
+        let visibility: Vec<_> = doc_module
+            .items
+            .iter()
+            .map(|item| (item.name.as_str(), item.public))
+            .collect();
+        assert_eq!(
+            visibility,
+            [("Config", true), ("_helper", false), ("run", true)]
+        );
+        let fields: Vec<_> = doc_module.items[0]
+            .children
+            .iter()
+            .map(|field| field.public)
+            .collect();
+        assert_eq!(fields, [true, false]);
+    }
 }
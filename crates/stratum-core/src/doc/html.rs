@@ -1,5 +1,6 @@
 //! HTML documentation generator
 
+use std::collections::BTreeMap;
 use std::fmt::Write;
 
 use super::crosslink::{CrossLinkConfig, CrossLinker};
@@ -209,8 +210,9 @@ impl HtmlGenerator {
         writeln!(output, "  <div class=\"sidebar-header\">").unwrap();
         writeln!(
             output,
-            "    <h2><a href=\"index.html\">{}</a></h2>",
-            project.name
+            "    <h2><a href=\"index.html\">{}</a>{}</h2>",
+            project.name,
+            Self::version_badge(project)
         )
         .unwrap();
         writeln!(output, "  </div>").unwrap();
@@ -229,25 +231,8 @@ impl HtmlGenerator {
             writeln!(output, "  </div>").unwrap();
         }
 
-        // Module list
-        writeln!(output, "  <div class=\"module-list\">").unwrap();
-        writeln!(output, "    <h3>Modules</h3>").unwrap();
-        writeln!(output, "    <ul>").unwrap();
-        for m in &project.modules {
-            let class = if m.name == module.name {
-                " class=\"active\""
-            } else {
-                ""
-            };
-            writeln!(
-                output,
-                "      <li><a href=\"{}.html\"{}>{}</a></li>",
-                m.name, class, m.name
-            )
-            .unwrap();
-        }
-        writeln!(output, "    </ul>").unwrap();
-        writeln!(output, "  </div>").unwrap();
+        Self::write_module_tree(&mut output, project, Some(&module.name));
+        Self::write_dependencies(&mut output, project);
 
         Self::write_nav(&mut output, module);
         writeln!(output, "</nav>").unwrap();
@@ -415,7 +400,13 @@ impl HtmlGenerator {
         // Sidebar
         writeln!(output, "<nav class=\"sidebar\">").unwrap();
         writeln!(output, "  <div class=\"sidebar-header\">").unwrap();
-        writeln!(output, "    <h2>{}</h2>", project.name).unwrap();
+        writeln!(
+            output,
+            "    <h2>{}{}</h2>",
+            project.name,
+            Self::version_badge(project)
+        )
+        .unwrap();
         writeln!(output, "  </div>").unwrap();
 
         if options.enable_search {
@@ -431,19 +422,8 @@ impl HtmlGenerator {
             writeln!(output, "  </div>").unwrap();
         }
 
-        writeln!(output, "  <div class=\"module-list\">").unwrap();
-        writeln!(output, "    <h3>Modules</h3>").unwrap();
-        writeln!(output, "    <ul>").unwrap();
-        for m in &project.modules {
-            writeln!(
-                output,
-                "      <li><a href=\"{}.html\">{}</a></li>",
-                m.name, m.name
-            )
-            .unwrap();
-        }
-        writeln!(output, "    </ul>").unwrap();
-        writeln!(output, "  </div>").unwrap();
+        Self::write_module_tree(&mut output, project, None);
+        Self::write_dependencies(&mut output, project);
         writeln!(output, "</nav>").unwrap();
 
         // Main content
@@ -555,7 +535,100 @@ impl HtmlGenerator {
         output
     }
 
-    fn write_styles(output: &mut String) {
+    /// The project's version, after its name in the sidebar
+    fn version_badge(project: &ProjectDoc) -> String {
+        project
+            .version
+            .as_ref()
+            .map(|version| {
+                format!(
+                    " <span class=\"version\">{}</span>",
+                    Self::escape_html(version)
+                )
+            })
+            .unwrap_or_default()
+    }
+
+    /// Write the sidebar's tree of modules, nested by the dotted segments of
+    /// their names, highlighting `current`
+    fn write_module_tree(output: &mut String, project: &ProjectDoc, current: Option<&str>) {
+        let mut root = ModuleTree::default();
+        for module in &project.modules {
+            let mut node = &mut root;
+            for segment in module.name.split('.') {
+                node = node.children.entry(segment).or_default();
+            }
+            node.module = Some(module.name.as_str());
+        }
+
+        writeln!(output, "  <div class=\"module-list\">").unwrap();
+        writeln!(output, "    <h3>Modules</h3>").unwrap();
+        Self::write_tree_level(output, &root, current, 2);
+        writeln!(output, "  </div>").unwrap();
+    }
+
+    fn write_tree_level(
+        output: &mut String,
+        node: &ModuleTree,
+        current: Option<&str>,
+        depth: usize,
+    ) {
+        let indent = "  ".repeat(depth);
+        writeln!(output, "{indent}<ul>").unwrap();
+        for (segment, child) in &node.children {
+            match child.module {
+                Some(name) => {
+                    let class = if current == Some(name) {
+                        " class=\"active\""
+                    } else {
+                        ""
+                    };
+                    write!(
+                        output,
+                        "{indent}  <li><a href=\"{name}.html\"{class} title=\"{name}\">{segment}</a>"
+                    )
+                    .unwrap();
+                }
+                None => write!(
+                    output,
+                    "{indent}  <li><span class=\"module-group\">{segment}</span>"
+                )
+                .unwrap(),
+            }
+            if child.children.is_empty() {
+                writeln!(output, "</li>").unwrap();
+            } else {
+                writeln!(output).unwrap();
+                Self::write_tree_level(output, child, current, depth + 2);
+                writeln!(output, "{indent}  </li>").unwrap();
+            }
+        }
+        writeln!(output, "{indent}</ul>").unwrap();
+    }
+
+    /// Write the sidebar's links to the documentation of dependencies
+    fn write_dependencies(output: &mut String, project: &ProjectDoc) {
+        if project.dependencies.is_empty() {
+            return;
+        }
+        writeln!(output, "  <div class=\"module-list\">").unwrap();
+        writeln!(output, "    <h3>Dependencies</h3>").unwrap();
+        writeln!(output, "    <ul>").unwrap();
+        for dependency in &project.dependencies {
+            writeln!(
+                output,
+                "      <li><a href=\"{}\">{}</a> <span class=\"version\">{}</span></li>",
+                dependency.href,
+                dependency.name,
+                Self::escape_html(&dependency.version)
+            )
+            .unwrap();
+        }
+        writeln!(output, "    </ul>").unwrap();
+        writeln!(output, "  </div>").unwrap();
+    }
+
+    pub(super) fn write_styles(output: &mut String) {
         writeln!(output, "<style>").unwrap();
         writeln!(
             output,
@@ -807,6 +880,20 @@ footer a {{
   color: var(--accent-color);
 }}
 
+.module-list ul ul {{
+  padding-left: 1rem;
+}}
+
+.module-group {{
+  color: #888;
+}}
+
+.version {{
+  font-size: 0.75rem;
+  font-weight: normal;
+  color: #888;
+}}
+
 /* Module grid on index page */
 .module-grid {{
   display: grid;
@@ -1301,10 +1388,18 @@ footer a {{
     }
 }
 
+/// A level of the sidebar's module tree
+#[derive(Default)]
+struct ModuleTree<'a> {
+    /// The module at this level, if there is one and not only submodules
+    module: Option<&'a str>,
+    children: BTreeMap<&'a str, ModuleTree<'a>>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::doc::DocExtractor;
+    use crate::doc::{DependencyDoc, DocExtractor};
     use crate::Parser;
 
     #[test]
@@ -1428,4 +1523,32 @@ struct Point {
         // Check search
         assert!(html.contains("search-input"));
     }
+
+    #[test]
+    fn test_module_tree_and_dependencies() {
+        let mut project = ProjectDoc::new("app").with_version("1.2.0");
+        for name in ["main", "net.http", "net", "util.text"] {
+            project.add_module(DocumentedModule::new(name.to_string()));
+        }
+        project.add_dependency(DependencyDoc {
+            name: "json".to_string(),
+            version: "2.0.1".to_string(),
+            href: "../../json/2.0.1/index.html".to_string(),
+        });
+        let options = HtmlOptions::default();
+
+        let html = HtmlGenerator::generate_with_project(&project.modules[1], &project, &options);
+        assert!(html.contains("<span class=\"version\">1.2.0</span>"));
+        assert!(html.contains(
+            "<li><a href=\"net.html\" title=\"net\">net</a>\n        <ul>\n          <li><a href=\"net.http.html\" class=\"active\" title=\"net.http\">http</a></li>"
+        ));
+        // util has no module of its own, only util.text
+        assert!(html.contains("<li><span class=\"module-group\">util</span>"));
+        assert!(html.contains("<a href=\"../../json/2.0.1/index.html\">json</a>"));
+
+        let index = HtmlGenerator::generate_index(&project, &options);
+        assert!(index.contains("<h2>app <span class=\"version\">1.2.0</span></h2>"));
+        assert!(index.contains("<h3>Dependencies</h3>"));
+        assert!(!index.contains("class=\"active\""));
+    }
 }
@@ -9,7 +9,11 @@
 //! - **Cross-linking**: Automatic linking between types and functions
 //! - **Search**: Client-side fuzzy search across all symbols
 //! - **Multiple formats**: HTML and Markdown output
+//! - **Books**: Markdown guides combined with the API documentation
+//! - **Coverage**: Which public items lack doc comments
 
+mod book;
+mod coverage;
 mod crosslink;
 mod extractor;
 mod html;
@@ -18,10 +22,12 @@ mod project;
 mod search;
 mod types;
 
+pub use book::{Book, BookGenerator, BookPage, Chapter, ExampleRunner};
+pub use coverage::{missing_docs, DocCoverage, MissingDoc, ModuleCoverage};
 pub use crosslink::{extract_type_names, CrossLinkConfig, CrossLinker};
 pub use extractor::DocExtractor;
 pub use html::{HtmlGenerator, HtmlOptions};
 pub use markdown::MarkdownGenerator;
-pub use project::{build_project_doc, ProjectDoc, SymbolInfo};
+pub use project::{build_project_doc, DependencyDoc, ProjectDoc, SymbolInfo};
 pub use search::{generate_search_css, generate_search_index, generate_search_js, SearchEntry};
 pub use types::{DocComment, DocumentedItem, DocumentedModule, ItemKind, ParamDoc};
@@ -25,15 +25,30 @@ pub struct SymbolInfo {
     pub signature: String,
 }
 
+/// A link to the documentation of a project's dependency
+#[derive(Debug, Clone, PartialEq)]
+pub struct DependencyDoc {
+    /// The dependency's name
+    pub name: String,
+    /// The documented version
+    pub version: String,
+    /// The dependency's index page, relative to the project's pages
+    pub href: String,
+}
+
 /// Project-wide documentation with cross-reference support
 #[derive(Debug, Clone)]
 pub struct ProjectDoc {
     /// Name of the project
     pub name: String,
+    /// Version of the project, for packages
+    pub version: Option<String>,
     /// All documented modules
     pub modules: Vec<DocumentedModule>,
     /// Symbol index for cross-referencing (name -> list of symbols with that name)
     pub symbol_index: HashMap<String, Vec<SymbolInfo>>,
+    /// Dependencies whose documentation is linked from the project's
+    pub dependencies: Vec<DependencyDoc>,
 }
 
 impl ProjectDoc {
@@ -41,11 +56,24 @@ impl ProjectDoc {
     pub fn new(name: impl Into<String>) -> Self {
         Self {
             name: name.into(),
+            version: None,
             modules: Vec::new(),
             symbol_index: HashMap::new(),
+            dependencies: Vec::new(),
         }
     }
 
+    /// Set the version of the project
+    pub fn with_version(mut self, version: impl Into<String>) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+
+    /// Link to the documentation of a dependency
+    pub fn add_dependency(&mut self, dependency: DependencyDoc) {
+        self.dependencies.push(dependency);
+    }
+
     /// Add a documented module and index its symbols
     pub fn add_module(&mut self, module: DocumentedModule) {
         self.index_module(&module);
@@ -128,6 +156,24 @@ impl ProjectDoc {
         })
     }
 
+    /// Resolve an intra-doc link target from a module: an item name,
+    /// `Type.member`, or `module.item`
+    pub fn resolve_link(&self, target: &str, from_module: &str) -> Option<&SymbolInfo> {
+        let Some((parent, name)) = target.rsplit_once('.') else {
+            return self.lookup_in_module(target, from_module);
+        };
+        let symbols = self.symbol_index.get(name)?;
+        let member_anchor = format!("{}-{}", Self::make_anchor(parent), Self::make_anchor(name));
+        symbols
+            .iter()
+            .find(|s| s.anchor == member_anchor)
+            .or_else(|| {
+                symbols
+                    .iter()
+                    .find(|s| s.module == parent && s.anchor == Self::make_anchor(name))
+            })
+    }
+
     /// Get all symbols sorted alphabetically
     pub fn all_symbols(&self) -> Vec<&SymbolInfo> {
         let mut symbols: Vec<_> = self.symbol_index.values().flatten().collect();
@@ -266,4 +312,37 @@ struct Gamma {}
         assert_eq!(symbols[1].name, "beta");
         assert_eq!(symbols[2].name, "Gamma");
     }
+
+    #[test]
+    fn test_resolve_link() {
+        let mut project = ProjectDoc::new("test");
+        for (name, source) in [
+            ("rows", "struct Row { cells: List<String> }\nfx parse() {}"),
+            ("util.text", "fx parse() {}\nfx cells() {}"),
+        ] {
+            let module = Parser::parse_module(source).unwrap();
+            project.add_module(DocExtractor::extract(&module, name));
+        }
+
+        let symbol = project.resolve_link("parse", "util.text").unwrap();
+        assert_eq!(symbol.module, "util.text");
+        assert_eq!(
+            project.resolve_link("parse", "rows").unwrap().module,
+            "rows"
+        );
+        assert_eq!(
+            project
+                .resolve_link("Row.cells", "util.text")
+                .unwrap()
+                .anchor,
+            "row-cells"
+        );
+        let symbol = project.resolve_link("util.text.cells", "rows").unwrap();
+        assert_eq!(
+            (symbol.module.as_str(), symbol.anchor.as_str()),
+            ("util.text", "cells")
+        );
+        assert!(project.resolve_link("missing", "rows").is_none());
+        assert!(project.resolve_link("Row.missing", "rows").is_none());
+    }
 }
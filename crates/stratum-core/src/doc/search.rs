@@ -23,19 +23,26 @@ pub struct SearchEntry {
 
 /// Generate a JSON search index from project documentation
 pub fn generate_search_index(project: &ProjectDoc) -> String {
-    let mut entries = Vec::new();
+    entries_to_json(&project_entries(project))
+}
 
-    for symbol in project.all_symbols() {
-        entries.push(SearchEntry {
+/// Search entries for every symbol of a project
+pub(super) fn project_entries(project: &ProjectDoc) -> Vec<SearchEntry> {
+    project
+        .all_symbols()
+        .into_iter()
+        .map(|symbol| SearchEntry {
             name: symbol.name.clone(),
             kind: kind_to_str(symbol.kind),
             module: symbol.module.clone(),
             description: symbol.description.clone(),
             link: format!("{}.html#{}", symbol.module, symbol.anchor),
-        });
-    }
+        })
+        .collect()
+}
 
-    // Generate JSON
+/// Serialize search entries in the format the search script loads
+pub(super) fn entries_to_json(entries: &[SearchEntry]) -> String {
     let mut json = String::from("[\n");
     for (i, entry) in entries.iter().enumerate() {
         if i > 0 {
@@ -64,10 +71,13 @@ pub fn generate_search_js() -> &'static str {
     let searchIndex = [];
     let searchInput = null;
     let searchResults = null;
+    // Pages below the site root give the path back to it, since index
+    // links are relative to the root
+    const root = document.body.dataset.root || '';
 
     // Load search index
     function loadSearchIndex() {
-        fetch('search-index.json')
+        fetch(root + 'search-index.json')
             .then(r => r.json())
             .then(data => { searchIndex = data; })
             .catch(e => console.warn('Search index not available:', e));
@@ -150,7 +160,7 @@ pub fn generate_search_js() -> &'static str {
             searchResults.innerHTML = topResults.map(r => {
                 const e = r.entry;
                 const kindClass = 'kind-' + e.k.toLowerCase();
-                return `<a href="${e.l}" class="search-result">
+                return `<a href="${root}${e.l}" class="search-result">
                     <span class="search-result-kind ${kindClass}">${e.k}</span>
                     <span class="search-result-name">${highlight(e.n, query)}</span>
                     <span class="search-result-module">${e.m}</span>
@@ -322,6 +332,8 @@ pub fn generate_search_css() -> &'static str {
 .kind-interface { background: #4a275a; color: #c476d4; }
 .kind-method { background: #275a4a; color: #56d4b4; }
 .kind-constant { background: #5a2727; color: #d47676; }
+.kind-chapter { background: #3a3a5a; color: #a9a0f0; }
+.kind-section { background: #33334a; color: #9a94c8; }
 
 .search-result-name {
     font-weight: 600;
@@ -0,0 +1,1897 @@
+//! Stable error codes and their explanations
+//!
+//! Every kind of parse, type, compile and runtime error has a code, such as
+//! `E0100`, that stays the same across releases even when the message
+//! wording changes. `stratum explain <code>` prints the explanation, and the
+//! language server links each diagnostic to it.
+//!
+//! Codes are grouped by the stage that reports them:
+//!
+//! | Range         | Stage        |
+//! |---------------|--------------|
+//! | `E0001–E0099` | Parser       |
+//! | `E0100–E0199` | Type checker |
+//! | `E0200–E0299` | Compiler     |
+//! | `E0300–E0399` | Runtime      |
+//!
+//! Codes are never reused: a removed error keeps its number retired.
+
+/// Online index of every error code, with an anchor per code
+pub const ERROR_INDEX_URL: &str =
+    "https://github.com/horizon-analytic-studios/stratum/blob/main/docs/errors.md";
+
+/// An error code and its extended description
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorCode {
+    /// The code, such as `E0100`
+    pub code: &'static str,
+    /// A stable kebab-case name, such as `type-mismatch`
+    pub name: &'static str,
+    /// One-line summary
+    pub summary: &'static str,
+    /// What causes the error, with an erroneous example and a fix
+    pub explanation: &'static str,
+}
+
+impl ErrorCode {
+    /// Link to this code in the online error index
+    #[must_use]
+    pub fn url(&self) -> String {
+        format!("{ERROR_INDEX_URL}#{}", self.code.to_lowercase())
+    }
+}
+
+/// Look up an error code, accepting `E0100`, `e0100` or `100`
+#[must_use]
+pub fn lookup(code: &str) -> Option<&'static ErrorCode> {
+    let code = code.trim();
+    let digits = code
+        .strip_prefix('E')
+        .or_else(|| code.strip_prefix('e'))
+        .unwrap_or(code);
+    let number: u32 = digits.parse().ok()?;
+    let code = format!("E{number:04}");
+    ERROR_CODES.iter().find(|entry| entry.code == code)
+}
+
+/// Every error code, in ascending order
+pub static ERROR_CODES: &[ErrorCode] = &[
+    // ==================== Parser ====================
+    ErrorCode {
+        code: "E0001",
+        name: "unexpected-token",
+        summary: "a token appeared where the grammar does not allow it",
+        explanation: r#"The parser found a token that cannot appear at this point, such as a
+missing operator between two values or a stray closing bracket.
+
+Erroneous example:
+
+    let total = price quantity
+
+Add the missing operator or punctuation:
+
+    let total = price * quantity
+"#,
+    },
+    ErrorCode {
+        code: "E0002",
+        name: "unexpected-eof",
+        summary: "the file ended in the middle of a construct",
+        explanation: r#"The file ended before a construct was complete, usually because a
+brace, bracket or parenthesis was never closed.
+
+Erroneous example:
+
+    fx main() {
+        println("hello")
+
+Close every opened delimiter:
+
+    fx main() {
+        println("hello")
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0003",
+        name: "expected-expression",
+        summary: "an expression was expected",
+        explanation: r#"A value was expected, such as on the right of `=` or after an operator,
+but none was written.
+
+Erroneous example:
+
+    let x =
+    let y = 1 +
+
+Supply the missing expression:
+
+    let x = 0
+    let y = 1 + x
+"#,
+    },
+    ErrorCode {
+        code: "E0004",
+        name: "expected-statement",
+        summary: "a statement was expected",
+        explanation: r#"The parser expected the start of a statement, such as `let`, a
+declaration or an expression, but found something that cannot begin one.
+
+Erroneous example:
+
+    fx main() {
+        => 1
+    }
+
+Write a complete statement:
+
+    fx main() {
+        return 1
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0005",
+        name: "expected-identifier",
+        summary: "a name was expected",
+        explanation: r#"A name was expected, for example after `let`, `fx` or `struct`, but a
+keyword, literal or symbol was found instead. Keywords cannot be used as
+names.
+
+Erroneous example:
+
+    let match = 1
+    fx 2nd() {}
+
+Choose a name that is not a keyword and does not start with a digit:
+
+    let matched = 1
+    fx second() {}
+"#,
+    },
+    ErrorCode {
+        code: "E0006",
+        name: "expected-type",
+        summary: "a type was expected",
+        explanation: r#"A type annotation was started with `:` or `->` but no type followed.
+
+Erroneous example:
+
+    fx double(x: ) -> Int { x * 2 }
+
+Write the type, or remove the annotation to let it be inferred:
+
+    fx double(x: Int) -> Int { x * 2 }
+"#,
+    },
+    ErrorCode {
+        code: "E0007",
+        name: "expected-pattern",
+        summary: "a pattern was expected",
+        explanation: r#"A `match` arm, `let` binding or `for` loop needs a pattern to match or
+bind, such as a name, a literal or a destructuring pattern.
+
+Erroneous example:
+
+    match value {
+        => "empty",
+    }
+
+Write a pattern before `=>`; `_` matches anything:
+
+    match value {
+        _ => "empty",
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0008",
+        name: "expected-after",
+        summary: "a required delimiter is missing",
+        explanation: r#"A construct is missing a token it requires, such as the `)` closing an
+argument list or the `{` opening a function body. The message names the
+missing token and what it should follow.
+
+Erroneous example:
+
+    fx greet(name: String {
+        println("hello " + name)
+    }
+
+Add the missing token:
+
+    fx greet(name: String) {
+        println("hello " + name)
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0009",
+        name: "invalid-assignment-target",
+        summary: "the left side of `=` cannot be assigned to",
+        explanation: r#"Only variables, fields and indexed elements can be assigned to. Calls,
+literals and other expressions cannot appear on the left of `=`.
+
+Erroneous example:
+
+    len(items) = 0
+    1 = x
+
+Assign to a variable, field or element:
+
+    items = []
+    x = 1
+"#,
+    },
+    ErrorCode {
+        code: "E0010",
+        name: "invalid-number",
+        summary: "a number literal is malformed or out of range",
+        explanation: r#"A number literal could not be read, usually because it is too large for
+an `Int` (a 64-bit signed integer) or has invalid digits for its base.
+
+Erroneous example:
+
+    let big = 99999999999999999999
+    let mask = 0xZZ
+
+Use a value in range, or a `Float` for very large magnitudes:
+
+    let big = 99999999999999999999.0
+    let mask = 0xFF
+"#,
+    },
+    ErrorCode {
+        code: "E0011",
+        name: "duplicate-parameter",
+        summary: "two parameters of a function have the same name",
+        explanation: r#"Each parameter of a function must have a distinct name.
+
+Erroneous example:
+
+    fx area(w: Int, w: Int) -> Int { w * w }
+
+Rename one of the parameters:
+
+    fx area(w: Int, h: Int) -> Int { w * h }
+"#,
+    },
+    ErrorCode {
+        code: "E0012",
+        name: "break-outside-loop",
+        summary: "`break` used outside a loop",
+        explanation: r#"`break` exits the innermost `for` or `while` loop, so it can only
+appear inside one. A closure body starts a new function, so `break` inside
+a closure cannot exit a loop around it.
+
+Erroneous example:
+
+    fx check(x: Int) {
+        if x > 10 {
+            break
+        }
+    }
+
+Use `return` to leave a function early:
+
+    fx check(x: Int) {
+        if x > 10 {
+            return
+        }
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0013",
+        name: "continue-outside-loop",
+        summary: "`continue` used outside a loop",
+        explanation: r#"`continue` skips to the next iteration of the innermost `for` or
+`while` loop, so it can only appear inside one.
+
+Erroneous example:
+
+    fx process(x: Int) {
+        if x < 0 {
+            continue
+        }
+        println(x)
+    }
+
+Use `return`, or move the check into the loop:
+
+    fx process(x: Int) {
+        if x < 0 {
+            return
+        }
+        println(x)
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0014",
+        name: "return-outside-function",
+        summary: "`return` used outside a function",
+        explanation: r#"`return` leaves the enclosing function, so it cannot appear at the top
+level of a file.
+
+Erroneous example:
+
+    let x = 1
+    return x
+
+Move the code into a function:
+
+    fx main() -> Int {
+        let x = 1
+        return x
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0015",
+        name: "positional-after-named",
+        summary: "a positional argument follows a named argument",
+        explanation: r#"In a call, every positional argument must come before the first named
+argument.
+
+Erroneous example:
+
+    connect(host: "localhost", 8080)
+
+Move positional arguments first, or name them too:
+
+    connect("localhost", port: 8080)
+    connect(host: "localhost", port: 8080)
+"#,
+    },
+    // ==================== Type checker ====================
+    ErrorCode {
+        code: "E0100",
+        name: "type-mismatch",
+        summary: "a value has a different type than expected",
+        explanation: r#"A value's type does not match the type required where it is used, such
+as a variable's annotation or a function parameter.
+
+Erroneous example:
+
+    let count: Int = "3"
+
+Convert the value, or change the annotation to match it:
+
+    let count: Int = 3
+    let label: String = "3"
+"#,
+    },
+    ErrorCode {
+        code: "E0101",
+        name: "undefined-variable",
+        summary: "a name is not defined in this scope",
+        explanation: r#"A variable was used that has not been declared, or is declared in a
+scope that has already ended. Check the spelling, and that the `let`
+comes before the use.
+
+Erroneous example:
+
+    fx main() {
+        if true {
+            let total = 10
+        }
+        println(total)
+    }
+
+Declare the variable in a scope that includes every use:
+
+    fx main() {
+        let total = 10
+        println(total)
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0102",
+        name: "undefined-type",
+        summary: "a type name is not defined",
+        explanation: r#"A type annotation names a type that is neither built in nor declared
+with `struct`, `enum` or `interface`. Built-in types are capitalised, such
+as `Int`, `Float`, `String`, `Bool` and `List<T>`.
+
+Erroneous example:
+
+    let n: int = 1
+    fx load() -> Config { ... }
+
+Fix the spelling, or declare the type:
+
+    let n: Int = 1
+    struct Config { path: String }
+"#,
+    },
+    ErrorCode {
+        code: "E0103",
+        name: "undefined-function",
+        summary: "a called function is not defined",
+        explanation: r#"A function was called that is not declared in this file, imported, or
+part of the standard library.
+
+Erroneous example:
+
+    fx main() {
+        greet("world")
+    }
+
+Declare or import the function:
+
+    fx greet(name: String) {
+        println("hello " + name)
+    }
+
+    fx main() {
+        greet("world")
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0104",
+        name: "undefined-struct",
+        summary: "a struct literal names an unknown struct",
+        explanation: r#"A struct literal uses a name that is not a declared struct.
+
+Erroneous example:
+
+    let p = Pont { x: 1, y: 2 }
+
+Fix the name, or declare the struct:
+
+    struct Point { x: Int, y: Int }
+    let p = Point { x: 1, y: 2 }
+"#,
+    },
+    ErrorCode {
+        code: "E0105",
+        name: "undefined-enum",
+        summary: "an enum name is not defined",
+        explanation: r#"An enum variant was referenced through an enum that is not declared.
+
+Erroneous example:
+
+    let c = Colour::Red
+
+Fix the name, or declare the enum:
+
+    enum Color { Red, Green, Blue }
+    let c = Color::Red
+"#,
+    },
+    ErrorCode {
+        code: "E0106",
+        name: "not-callable",
+        summary: "a value that is not a function was called",
+        explanation: r#"Only functions and closures can be called with `(...)`.
+
+Erroneous example:
+
+    let limit = 10
+    let x = limit(5)
+
+Call a function, or drop the parentheses:
+
+    let limit = 10
+    let x = limit
+"#,
+    },
+    ErrorCode {
+        code: "E0107",
+        name: "wrong-argument-count",
+        summary: "a function was called with the wrong number of arguments",
+        explanation: r#"A call passes more or fewer arguments than the function declares
+parameters.
+
+Erroneous example:
+
+    fx add(a: Int, b: Int) -> Int { a + b }
+    let x = add(1)
+
+Pass one argument per parameter:
+
+    let x = add(1, 2)
+"#,
+    },
+    ErrorCode {
+        code: "E0108",
+        name: "not-indexable",
+        summary: "a value that cannot be indexed was indexed",
+        explanation: r#"Indexing with `[...]` works on lists, maps, strings and DataFrames, but
+not on other values such as numbers or booleans.
+
+Erroneous example:
+
+    let n = 42
+    let first = n[0]
+
+Index a collection instead:
+
+    let digits = [4, 2]
+    let first = digits[0]
+"#,
+    },
+    ErrorCode {
+        code: "E0109",
+        name: "invalid-index-type",
+        summary: "an index has the wrong type for the collection",
+        explanation: r#"Lists and strings are indexed by `Int`, and maps by their key type.
+
+Erroneous example:
+
+    let items = ["a", "b"]
+    let x = items["0"]
+
+Use an index of the right type:
+
+    let x = items[0]
+"#,
+    },
+    ErrorCode {
+        code: "E0110",
+        name: "no-such-field",
+        summary: "a field does not exist on the type",
+        explanation: r#"A field was read or written that the struct does not declare.
+
+Erroneous example:
+
+    struct User { name: String }
+
+    fx show(u: User) {
+        println(u.email)
+    }
+
+Use a declared field, or add the field to the struct:
+
+    struct User { name: String, email: String }
+"#,
+    },
+    ErrorCode {
+        code: "E0111",
+        name: "unnecessary-null-safe",
+        summary: "a null-safe operator was used on a value that cannot be null",
+        explanation: r#"`?.`, `?[` and `??` only make sense on nullable values (types ending in
+`?`). On a non-nullable value they have no effect.
+
+Erroneous example:
+
+    let x: Int = 42
+    let y = x ?? 0
+
+Use the value directly, or make its type nullable if it can be null:
+
+    let y = x
+    let z: Int? = null
+    let w = z ?? 0
+"#,
+    },
+    ErrorCode {
+        code: "E0112",
+        name: "nullability-mismatch",
+        summary: "a nullable value was used where a value is required",
+        explanation: r#"A value of a nullable type (`T?`) may be `null`, so it cannot be used
+where a non-nullable `T` is required without first handling the `null`
+case.
+
+Erroneous example:
+
+    fx length(s: String?) -> Int {
+        len(s)
+    }
+
+Check for `null` first, or supply a default with `??`:
+
+    fx length(s: String?) -> Int {
+        len(s ?? "")
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0113",
+        name: "invalid-binary-op",
+        summary: "a binary operator does not support these operand types",
+        explanation: r#"The operator is not defined for the types of its operands, such as
+adding a number to a string or comparing values of unrelated types.
+
+Erroneous example:
+
+    let label = "count: " + 3
+
+Convert the operands to compatible types, or use string interpolation:
+
+    let label = "count: " + str(3)
+    let label = "count: {3}"
+"#,
+    },
+    ErrorCode {
+        code: "E0114",
+        name: "invalid-unary-op",
+        summary: "a unary operator does not support this operand type",
+        explanation: r#"`-` applies to numbers and `!` to `Bool`.
+
+Erroneous example:
+
+    let x = -"5"
+    let y = !0
+
+Apply the operator to a value of the right type:
+
+    let x = -5
+    let y = !false
+"#,
+    },
+    ErrorCode {
+        code: "E0115",
+        name: "return-type-mismatch",
+        summary: "a returned value does not match the declared return type",
+        explanation: r#"The value returned by a function, either with `return` or as the final
+expression of its body, has a different type than the `->` annotation.
+
+Erroneous example:
+
+    fx half(x: Int) -> Int {
+        x / 2.0
+    }
+
+Return a value of the declared type, or change the annotation:
+
+    fx half(x: Int) -> Float {
+        x / 2.0
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0116",
+        name: "invalid-assignment-target",
+        summary: "the left side of an assignment cannot be assigned to",
+        explanation: r#"The type checker found an assignment whose target is not a variable,
+field or indexed element.
+
+Erroneous example:
+
+    fx reset() {
+        get_count() = 0
+    }
+
+Assign to something that holds a value:
+
+    fx reset() {
+        count = 0
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0117",
+        name: "duplicate-field",
+        summary: "a struct literal sets the same field twice",
+        explanation: r#"Each field can be given only once in a struct literal.
+
+Erroneous example:
+
+    let p = Point { x: 1, x: 2 }
+
+Set each field once:
+
+    let p = Point { x: 1, y: 2 }
+"#,
+    },
+    ErrorCode {
+        code: "E0118",
+        name: "missing-field",
+        summary: "a struct literal leaves out a field",
+        explanation: r#"A struct literal must give a value for every field the struct declares.
+
+Erroneous example:
+
+    struct Point { x: Int, y: Int }
+    let p = Point { x: 1 }
+
+Provide every field:
+
+    let p = Point { x: 1, y: 0 }
+"#,
+    },
+    ErrorCode {
+        code: "E0119",
+        name: "extra-field",
+        summary: "a struct literal sets a field the struct does not have",
+        explanation: r#"A struct literal gives a value for a field that is not declared on the
+struct.
+
+Erroneous example:
+
+    struct Point { x: Int, y: Int }
+    let p = Point { x: 1, y: 2, z: 3 }
+
+Remove the field, or add it to the struct:
+
+    struct Point { x: Int, y: Int, z: Int }
+"#,
+    },
+    ErrorCode {
+        code: "E0120",
+        name: "cannot-infer",
+        summary: "a type could not be inferred",
+        explanation: r#"There is not enough information to work out a type, typically for an
+empty collection or `null` with no other uses.
+
+Erroneous example:
+
+    let items = []
+
+Add a type annotation:
+
+    let items: List<String> = []
+"#,
+    },
+    ErrorCode {
+        code: "E0121",
+        name: "recursive-type",
+        summary: "a type contains itself without indirection",
+        explanation: r#"A struct that directly contains a field of its own type would be
+infinitely large.
+
+Erroneous example:
+
+    struct Node { value: Int, next: Node }
+
+Make the recursive field nullable or put it in a collection:
+
+    struct Node { value: Int, next: Node? }
+    struct Tree { value: Int, children: List<Tree> }
+"#,
+    },
+    ErrorCode {
+        code: "E0122",
+        name: "duplicate-definition",
+        summary: "a name is defined twice in the same scope",
+        explanation: r#"Two functions, types or constants in the same module have the same
+name.
+
+Erroneous example:
+
+    fx load() {}
+    fx load() {}
+
+Rename or remove one definition:
+
+    fx load() {}
+    fx load_all() {}
+"#,
+    },
+    ErrorCode {
+        code: "E0123",
+        name: "break-outside-loop",
+        summary: "`break` used outside a loop",
+        explanation: r#"The type checker found a `break` that is not inside a `for` or `while`
+loop of the same function. A closure body is a separate function, so
+`break` inside a closure cannot exit a loop around it.
+
+Erroneous example:
+
+    for x in items {
+        items.map(|y| { if y == x { break } })
+    }
+
+Use a loop instead of the closure, so `break` exits it:
+
+    for x in items {
+        for y in items {
+            if y == x { break }
+        }
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0124",
+        name: "continue-outside-loop",
+        summary: "`continue` used outside a loop",
+        explanation: r#"The type checker found a `continue` that is not inside a `for` or
+`while` loop of the same function.
+
+Erroneous example:
+
+    fx skip_negative(x: Int) {
+        if x < 0 { continue }
+    }
+
+Use `return` to leave the function, or move the check into a loop:
+
+    fx skip_negative(x: Int) {
+        if x < 0 { return }
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0125",
+        name: "return-outside-function",
+        summary: "`return` used outside a function",
+        explanation: r#"The type checker found a `return` at the top level of a file.
+
+Erroneous example:
+
+    let ready = true
+    if !ready { return }
+
+Move the code into a function:
+
+    fx main() {
+        let ready = true
+        if !ready { return }
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0126",
+        name: "incompatible-branches",
+        summary: "the branches of an `if` or `match` have different types",
+        explanation: r#"When an `if` or `match` is used as a value, every branch must produce
+the same type.
+
+Erroneous example:
+
+    let label = if count > 0 { count } else { "none" }
+
+Make every branch produce the same type:
+
+    let label = if count > 0 { str(count) } else { "none" }
+"#,
+    },
+    ErrorCode {
+        code: "E0127",
+        name: "wrong-type-arg-count",
+        summary: "a generic type has the wrong number of type arguments",
+        explanation: r#"A generic type was given more or fewer type arguments than it declares.
+
+Erroneous example:
+
+    let scores: Map<String> = {}
+
+Give one type argument per type parameter:
+
+    let scores: Map<String, Int> = {}
+"#,
+    },
+    ErrorCode {
+        code: "E0128",
+        name: "occurs-check",
+        summary: "inference would produce an infinite type",
+        explanation: r#"Type inference found that a type would have to contain itself, such as
+a list that is its own element. This usually comes from passing a value
+to itself by mistake.
+
+Erroneous example:
+
+    fx wrap(x) { [x] }
+    let f = |x| wrap(f)
+
+Annotate the types involved to find the mistake, and pass the intended
+value:
+
+    let f = |x: Int| wrap(x)
+"#,
+    },
+    ErrorCode {
+        code: "E0129",
+        name: "cannot-unify",
+        summary: "two inferred types cannot be made the same",
+        explanation: r#"Inference required two types to be the same, for example the elements
+of a list or the uses of one unannotated parameter, but they conflict.
+
+Erroneous example:
+
+    let mixed = [1, "two", 3]
+
+Use values of one type, or convert them:
+
+    let numbers = [1, 2, 3]
+    let labels = ["1", "two", "3"]
+"#,
+    },
+    ErrorCode {
+        code: "E0130",
+        name: "undefined-interface",
+        summary: "an interface name is not defined",
+        explanation: r#"An `impl ... for` block names an interface that is not declared or
+imported.
+
+Erroneous example:
+
+    impl Printable for Point {
+        fx show(self) -> String { "point" }
+    }
+
+Declare or import the interface:
+
+    interface Printable {
+        fx show(self) -> String
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0131",
+        name: "impl-target-not-found",
+        summary: "an `impl` block names an unknown type",
+        explanation: r#"An `impl` block's target type is not a declared struct or enum.
+
+Erroneous example:
+
+    impl Pont {
+        fx norm(self) -> Float { 0.0 }
+    }
+
+Fix the name, or declare the type:
+
+    struct Point { x: Float, y: Float }
+    impl Point {
+        fx norm(self) -> Float { 0.0 }
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0132",
+        name: "missing-interface-method",
+        summary: "an implementation leaves out an interface method",
+        explanation: r#"An `impl Interface for Type` block must define every method of the
+interface that has no default body.
+
+Erroneous example:
+
+    interface Shape {
+        fx area(self) -> Float
+        fx name(self) -> String
+    }
+
+    impl Shape for Circle {
+        fx area(self) -> Float { 3.14 * self.r * self.r }
+    }
+
+Implement the missing method:
+
+    impl Shape for Circle {
+        fx area(self) -> Float { 3.14 * self.r * self.r }
+        fx name(self) -> String { "circle" }
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0133",
+        name: "method-signature-mismatch",
+        summary: "an implemented method's signature differs from the interface",
+        explanation: r#"A method in an `impl Interface for Type` block must take the same
+parameter types and return the same type as the interface declares.
+
+Erroneous example:
+
+    interface Shape {
+        fx area(self) -> Float
+    }
+
+    impl Shape for Square {
+        fx area(self) -> Int { self.side * self.side }
+    }
+
+Match the interface's signature:
+
+    impl Shape for Square {
+        fx area(self) -> Float { self.side * self.side }
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0134",
+        name: "duplicate-impl",
+        summary: "a type implements the same interface twice",
+        explanation: r#"A type can implement each interface only once.
+
+Erroneous example:
+
+    impl Shape for Circle { ... }
+    impl Shape for Circle { ... }
+
+Merge the methods into a single `impl` block.
+"#,
+    },
+    ErrorCode {
+        code: "E0135",
+        name: "method-not-found",
+        summary: "a method does not exist on the type",
+        explanation: r#"A method was called that the value's type does not provide, either
+built in, in an `impl` block or through `#[derive(...)]`.
+
+Erroneous example:
+
+    let name = "stratum"
+    let loud = name.upcase()
+
+Use a method the type has, or define it in an `impl` block:
+
+    let loud = name.to_upper()
+"#,
+    },
+    ErrorCode {
+        code: "E0136",
+        name: "instance-method-on-type",
+        summary: "a method taking `self` was called on the type",
+        explanation: r#"A method whose first parameter is `self` needs a value to be called on.
+Calling it through the type name, like a static method, leaves `self`
+unset.
+
+Erroneous example:
+
+    impl Counter {
+        fx value(self) -> Int { self.count }
+    }
+
+    let n = Counter.value()
+
+Call it on a value of the type:
+
+    let counter = Counter { count: 0 }
+    let n = counter.value()
+"#,
+    },
+    ErrorCode {
+        code: "E0137",
+        name: "await-outside-async",
+        summary: "`await` used outside an async function",
+        explanation: r#"`await` can only be used inside a function declared with `async fx`.
+
+Erroneous example:
+
+    fx main() {
+        await Async.sleep(100)
+    }
+
+Declare the function `async`:
+
+    async fx main() {
+        await Async.sleep(100)
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0138",
+        name: "await-non-future",
+        summary: "`await` applied to a value that is not a future",
+        explanation: r#"`await` waits for a `Future<T>`, returned by an async function. Other
+values are already available and need no `await`.
+
+Erroneous example:
+
+    async fx main() {
+        let n = await 42
+    }
+
+Use the value directly:
+
+    async fx main() {
+        let n = 42
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0139",
+        name: "placeholder-outside-pipeline",
+        summary: "the `_` placeholder was used outside a pipeline",
+        explanation: r#"In a pipeline step, `_` marks where the piped value goes. Outside the
+right-hand side of `|>` there is no piped value for it to stand for.
+
+Erroneous example:
+
+    let x = add(_, 1)
+
+Use `_` in a pipeline step, or pass the value directly:
+
+    let x = 5 |> add(_, 1)
+    let y = add(5, 1)
+"#,
+    },
+    ErrorCode {
+        code: "E0140",
+        name: "column-shorthand-outside-context",
+        summary: "a `.column` reference was used outside a DataFrame operation",
+        explanation: r#"The `.column` shorthand refers to a column of the DataFrame an
+operation such as `filter` or `select` runs on. Elsewhere there is no
+DataFrame for it to refer to.
+
+Erroneous example:
+
+    let big = .amount > 100
+
+Use the shorthand inside a DataFrame operation:
+
+    let big = sales.filter(.amount > 100)
+"#,
+    },
+    ErrorCode {
+        code: "E0141",
+        name: "invalid-attribute",
+        summary: "an attribute or derive is not recognised",
+        explanation: r#"An `#[...]` attribute, or a name in `#[derive(...)]`, is not one the
+compiler knows.
+
+Erroneous example:
+
+    #[derive(Equal)]
+    struct Point { x: Int, y: Int }
+
+Use a supported attribute or derive:
+
+    #[derive(Eq)]
+    struct Point { x: Int, y: Int }
+"#,
+    },
+    ErrorCode {
+        code: "E0142",
+        name: "not-constant",
+        summary: "a `const` initializer cannot be evaluated at compile time",
+        explanation: r#"A `const` must be computed from literals, other constants and operators
+on them. Function calls and other runtime values are not allowed.
+
+Erroneous example:
+
+    const SIZE = File.size("data.csv")
+
+Use `let` for values computed at runtime:
+
+    let size = File.size("data.csv")
+"#,
+    },
+    ErrorCode {
+        code: "E0143",
+        name: "assign-to-constant",
+        summary: "a `const` was assigned to",
+        explanation: r#"A name declared with `const` cannot be changed after its declaration.
+
+Erroneous example:
+
+    const LIMIT = 10
+    LIMIT = 20
+
+Declare it with `let` if it needs to change:
+
+    let limit = 10
+    limit = 20
+"#,
+    },
+    // ==================== Compiler ====================
+    ErrorCode {
+        code: "E0200",
+        name: "too-many-constants",
+        summary: "a function has more than 65535 constants",
+        explanation: r#"A single function uses more distinct constant values, such as literals
+and names, than the bytecode can address. This usually happens with very
+large generated literals.
+
+Split the function into smaller functions, or load large data from a file
+at runtime instead of embedding it in source.
+"#,
+    },
+    ErrorCode {
+        code: "E0201",
+        name: "too-many-locals",
+        summary: "a scope has more than 65535 local variables",
+        explanation: r#"A function declares more local variables than the bytecode can address.
+
+Split the function into smaller functions, or collect related values in
+a list, map or struct.
+"#,
+    },
+    ErrorCode {
+        code: "E0202",
+        name: "too-many-upvalues",
+        summary: "a closure captures more than 256 variables",
+        explanation: r#"A closure refers to more variables from enclosing functions than the
+bytecode can address.
+
+Collect the captured values in a struct or map and capture that instead.
+"#,
+    },
+    ErrorCode {
+        code: "E0203",
+        name: "undefined-variable",
+        summary: "the compiler found an undefined variable",
+        explanation: r#"The compiler found a variable that is not defined in any enclosing scope.
+This is normally reported by the type checker as E0101; it reaches the
+compiler when type checking is skipped.
+
+Erroneous example:
+
+    println(totl)
+
+Fix the name, or declare the variable before using it:
+
+    let total = 3
+    println(total)
+"#,
+    },
+    ErrorCode {
+        code: "E0204",
+        name: "duplicate-variable",
+        summary: "a variable is declared twice in the same scope",
+        explanation: r#"Two local variables in the same block have the same name where the
+compiler cannot tell them apart, such as two parameters of a closure.
+
+Erroneous example:
+
+    let pair = |a, a| a
+
+Give each variable its own name:
+
+    let pair = |a, b| a
+"#,
+    },
+    ErrorCode {
+        code: "E0205",
+        name: "break-outside-loop",
+        summary: "`break` used outside a loop",
+        explanation: r#"The compiler found a `break` with no enclosing loop to exit. See E0012.
+"#,
+    },
+    ErrorCode {
+        code: "E0206",
+        name: "continue-outside-loop",
+        summary: "`continue` used outside a loop",
+        explanation: r#"The compiler found a `continue` with no enclosing loop. See E0013.
+"#,
+    },
+    ErrorCode {
+        code: "E0207",
+        name: "return-outside-function",
+        summary: "`return` used outside a function",
+        explanation: r#"The compiler found a `return` with no enclosing function. See E0014.
+"#,
+    },
+    ErrorCode {
+        code: "E0208",
+        name: "jump-too-large",
+        summary: "a block is too large to jump over",
+        explanation: r#"The body of an `if`, loop or `match` arm compiles to more bytecode than
+a single jump can cross.
+
+Move parts of the large block into separate functions.
+"#,
+    },
+    ErrorCode {
+        code: "E0209",
+        name: "too-many-parameters",
+        summary: "a function declares more than 255 parameters",
+        explanation: r#"A function can take at most 255 parameters.
+
+Group related parameters in a struct:
+
+    struct Options { width: Int, height: Int, title: String }
+    fx open(options: Options) {}
+"#,
+    },
+    ErrorCode {
+        code: "E0210",
+        name: "too-many-arguments",
+        summary: "a call passes more than 255 arguments",
+        explanation: r#"A call can pass at most 255 arguments.
+
+Pass a list, map or struct instead of many separate arguments.
+"#,
+    },
+    ErrorCode {
+        code: "E0211",
+        name: "invalid-assignment-target",
+        summary: "the left side of an assignment cannot be assigned to",
+        explanation: r#"The compiler found an assignment to something that is not a variable,
+field or indexed element. See E0009.
+"#,
+    },
+    ErrorCode {
+        code: "E0212",
+        name: "this-outside-method",
+        summary: "`this` used outside a method",
+        explanation: r#"`this` only has a meaning inside a method. Methods in Stratum name their
+receiver `self` explicitly.
+
+Erroneous example:
+
+    impl Point {
+        fx norm(self) -> Float { this.x }
+    }
+
+Use the `self` parameter:
+
+    impl Point {
+        fx norm(self) -> Float { self.x }
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0213",
+        name: "super-outside-subclass",
+        summary: "`super` used outside a subclass",
+        explanation: r#"`super` refers to a parent class, but the enclosing type has none.
+Stratum types share behaviour through interfaces rather than inheritance.
+
+Implement an interface and call its methods on `self` instead.
+"#,
+    },
+    ErrorCode {
+        code: "E0214",
+        name: "unsupported",
+        summary: "a language feature is not supported by the compiler yet",
+        explanation: r#"The code uses a construct that parses and type checks but that the
+bytecode compiler does not implement yet. The message names the feature.
+
+Rewrite the code without the feature, for example with a loop in place of
+an unsupported expression form.
+"#,
+    },
+    ErrorCode {
+        code: "E0215",
+        name: "unsupported-pattern",
+        summary: "a pattern cannot be used in a binding position",
+        explanation: r#"`let` and `for` only accept patterns that always match, such as names
+and destructuring. Patterns that can fail, such as literals or enum
+variants, need a `match`.
+
+Erroneous example:
+
+    let Shape::Circle(r) = shape
+
+Use `match` to handle every case:
+
+    match shape {
+        Shape::Circle(r) => println(r),
+        _ => {},
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0216",
+        name: "invalid-placeholder",
+        summary: "the `_` placeholder was used outside a pipeline",
+        explanation: r#"The compiler found a `_` placeholder outside the right-hand side of
+`|>`. See E0139.
+"#,
+    },
+    ErrorCode {
+        code: "E0217",
+        name: "invalid-column-shorthand",
+        summary: "a `.column` reference was used outside a DataFrame operation",
+        explanation: r#"The compiler found a `.column` reference with no DataFrame operation
+around it. See E0140.
+"#,
+    },
+    ErrorCode {
+        code: "E0218",
+        name: "not-constant",
+        summary: "a `const` value cannot be computed at compile time",
+        explanation: r#"The compiler could not evaluate a `const` initializer, for example
+because it divides by zero or overflows. See E0142.
+
+Erroneous example:
+
+    const RATE = 10 / 0
+
+Fix the expression, or use `let`.
+"#,
+    },
+    ErrorCode {
+        code: "E0219",
+        name: "internal",
+        summary: "internal compiler error",
+        explanation: r#"The compiler reached a state it should never reach. This is a bug in
+Stratum, not in your code.
+
+Please report it with the code that triggers it at
+https://github.com/horizon-analytic-studios/stratum/issues
+"#,
+    },
+    // ==================== Runtime ====================
+    ErrorCode {
+        code: "E0300",
+        name: "type-error",
+        summary: "an operation received a value of the wrong type",
+        explanation: r#"At runtime, an operation was applied to a value of a type it does not
+support. The type checker catches most of these; the rest come from
+dynamically typed values such as parsed JSON.
+
+Erroneous example:
+
+    let data = Json.parse("{\"n\": \"3\"}")
+    let doubled = data["n"] * 2
+
+Convert the value to the type the operation needs:
+
+    let doubled = int(data["n"]) * 2
+"#,
+    },
+    ErrorCode {
+        code: "E0301",
+        name: "division-by-zero",
+        summary: "an integer was divided by zero",
+        explanation: r#"Integer division or remainder by zero has no result.
+
+Erroneous example:
+
+    fx average(total: Int, count: Int) -> Int {
+        total / count
+    }
+
+    average(10, 0)
+
+Check the divisor first:
+
+    fx average(total: Int, count: Int) -> Int {
+        if count == 0 { return 0 }
+        total / count
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0302",
+        name: "undefined-variable",
+        summary: "a global variable was read before it was defined",
+        explanation: r#"A global name was looked up at runtime and not found, typically
+because code ran before the definition it depends on, or the name comes
+from code evaluated dynamically.
+
+Define the name before the code that reads it runs.
+"#,
+    },
+    ErrorCode {
+        code: "E0303",
+        name: "undefined-field",
+        summary: "a field does not exist on the value",
+        explanation: r#"A field was read at runtime that the value does not have, such as a
+missing key on a map accessed with `.`.
+
+Erroneous example:
+
+    let user = Json.parse("{\"name\": \"ada\"}")
+    println(user.email)
+
+Check that the field exists, or look it up with a default:
+
+    println(user.get("email", "none"))
+"#,
+    },
+    ErrorCode {
+        code: "E0304",
+        name: "index-out-of-bounds",
+        summary: "a list or string index is out of range",
+        explanation: r#"An index was at or past the end of the list or string, or before its
+start.
+
+Erroneous example:
+
+    let items = [1, 2, 3]
+    let x = items[3]
+
+Check the length first; indexes run from `0` to `len(items) - 1`:
+
+    let x = if len(items) > 3 { items[3] } else { 0 }
+"#,
+    },
+    ErrorCode {
+        code: "E0305",
+        name: "invalid-index-type",
+        summary: "a value was indexed with the wrong type",
+        explanation: r#"At runtime, a list or string was indexed with something other than an
+`Int`, or a map with an unsupported key. See E0109.
+"#,
+    },
+    ErrorCode {
+        code: "E0306",
+        name: "not-callable",
+        summary: "a value that is not a function was called",
+        explanation: r#"At runtime, a call was made on a value that is not a function or
+closure, for example a map entry expected to hold a callback. See E0106.
+"#,
+    },
+    ErrorCode {
+        code: "E0307",
+        name: "arity-mismatch",
+        summary: "a function was called with the wrong number of arguments",
+        explanation: r#"At runtime, a function or closure received more or fewer arguments than
+it declares, typically when it was passed as a callback. See E0107.
+
+Erroneous example:
+
+    let pairs = [1, 2].map(|a, b| a + b)
+
+Give the callback the parameters its caller passes:
+
+    let doubled = [1, 2].map(|a| a * 2)
+"#,
+    },
+    ErrorCode {
+        code: "E0308",
+        name: "not-iterable",
+        summary: "a `for` loop was given a value that cannot be iterated",
+        explanation: r#"`for` iterates over lists, maps, sets, strings and ranges.
+
+Erroneous example:
+
+    for i in 10 {
+        println(i)
+    }
+
+Iterate over a range or collection:
+
+    for i in range(0, 10) {
+        println(i)
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0309",
+        name: "stack-underflow",
+        summary: "internal VM error: stack underflow",
+        explanation: r#"The virtual machine tried to pop from an empty stack. This is a bug in
+Stratum, not in your code.
+
+Please report it with the code that triggers it at
+https://github.com/horizon-analytic-studios/stratum/issues
+"#,
+    },
+    ErrorCode {
+        code: "E0310",
+        name: "stack-overflow",
+        summary: "too many nested function calls",
+        explanation: r#"The call stack grew past its limit, almost always because of recursion
+that never reaches its base case.
+
+Erroneous example:
+
+    fx countdown(n: Int) {
+        countdown(n - 1)
+    }
+
+Add a base case, or rewrite deep recursion as a loop:
+
+    fx countdown(n: Int) {
+        if n == 0 { return }
+        countdown(n - 1)
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0311",
+        name: "invalid-opcode",
+        summary: "internal VM error: invalid bytecode",
+        explanation: r#"The virtual machine found an instruction it does not recognise. This is
+a bug in Stratum, or a compiled binary built by a different version.
+
+Rebuild compiled binaries with the current `stratum build`. If the error
+persists, please report it at
+https://github.com/horizon-analytic-studios/stratum/issues
+"#,
+    },
+    ErrorCode {
+        code: "E0312",
+        name: "uncaught-exception",
+        summary: "a thrown value was not caught",
+        explanation: r#"A value was thrown with `throw` and no enclosing `try` caught it.
+
+Erroneous example:
+
+    fx parse_age(s: String) -> Int {
+        if s == "" { throw "empty age" }
+        int(s)
+    }
+
+    parse_age("")
+
+Catch the exception where it can be handled:
+
+    let age = 0
+    try {
+        age = parse_age("")
+    } catch {
+        println("invalid age")
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0313",
+        name: "user-error",
+        summary: "a standard library function reported an error",
+        explanation: r#"A built-in function failed, for example because a file does not exist
+or a string is not valid JSON. The message gives the reason.
+
+Erroneous example:
+
+    let text = File.read_text("missing.txt")
+
+Check the precondition first, or catch the error with `try`:
+
+    if File.exists("missing.txt") {
+        let text = File.read_text("missing.txt")
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0314",
+        name: "assertion-failed",
+        summary: "an assertion was false",
+        explanation: r#"An `assert` or `assert_eq` call found its condition false. In tests,
+this is how a failing expectation is reported.
+
+Erroneous example:
+
+    assert_eq(add(2, 2), 5)
+
+Fix the code under test, or the expectation if it is wrong.
+"#,
+    },
+    ErrorCode {
+        code: "E0315",
+        name: "invalid-operation",
+        summary: "an operation is not valid for these values",
+        explanation: r#"An operation was given values it cannot work with, such as an invalid
+argument to a standard library function. The message describes the
+problem.
+
+Check the arguments against the function's documentation.
+"#,
+    },
+    ErrorCode {
+        code: "E0316",
+        name: "key-not-found",
+        summary: "a map has no entry for the key",
+        explanation: r#"A map was indexed with `[...]` using a key it does not contain.
+
+Erroneous example:
+
+    let ages = {"ada": 36}
+    let age = ages["bob"]
+
+Check for the key first, or look it up with a default:
+
+    let age = ages.get("bob", 0)
+"#,
+    },
+    ErrorCode {
+        code: "E0317",
+        name: "unhashable-type",
+        summary: "a value cannot be used as a map key or set element",
+        explanation: r#"Map keys and set elements must be hashable: strings, integers,
+booleans, `null`, and lists or structs made of them. Floats, functions and
+maps cannot be keys.
+
+Erroneous example:
+
+    let seen = {[1.5]: true}
+
+Use a hashable value as the key, such as its string form:
+
+    let seen = {"1.5": true}
+"#,
+    },
+    ErrorCode {
+        code: "E0318",
+        name: "null-reference",
+        summary: "a `null` value was used as if it held a value",
+        explanation: r#"A field, method or index was accessed on `null`.
+
+Erroneous example:
+
+    let user: User? = find_user("ada")
+    println(user.name)
+
+Use the null-safe operators, or check for `null` first:
+
+    println(user?.name ?? "unknown")
+"#,
+    },
+    ErrorCode {
+        code: "E0319",
+        name: "break-outside-loop",
+        summary: "`break` executed outside a loop",
+        explanation: r#"The virtual machine executed a `break` with no enclosing loop. The
+compiler rejects this (see E0012), so reaching it at runtime is a bug in
+Stratum.
+
+Please report it at https://github.com/horizon-analytic-studios/stratum/issues
+"#,
+    },
+    ErrorCode {
+        code: "E0320",
+        name: "return-outside-function",
+        summary: "`return` executed outside a function",
+        explanation: r#"The virtual machine executed a `return` with no enclosing function. The
+compiler rejects this (see E0014), so reaching it at runtime is a bug in
+Stratum.
+
+Please report it at https://github.com/horizon-analytic-studios/stratum/issues
+"#,
+    },
+    ErrorCode {
+        code: "E0321",
+        name: "await-outside-async",
+        summary: "`await` executed outside an async context",
+        explanation: r#"A future was awaited where no async runtime is running, for example
+from a callback invoked synchronously. See E0137.
+
+Make the calling function `async fx`, or use the blocking form of the API.
+"#,
+    },
+    ErrorCode {
+        code: "E0322",
+        name: "async-error",
+        summary: "an async operation failed",
+        explanation: r#"An awaited operation, such as a network request or database query,
+failed. The message gives the underlying error.
+
+Catch the error with `try` where the program can recover, for example by
+retrying or reporting it:
+
+    try {
+        body = await fetch(url)
+    } catch {
+        println("request failed")
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0323",
+        name: "data-error",
+        summary: "a DataFrame or Series operation failed",
+        explanation: r#"A DataFrame, Series or Cube operation failed, commonly because a column
+does not exist or has a different type than the operation needs.
+
+Erroneous example:
+
+    let df = Data.read_csv("sales.csv")
+    let big = df.filter(.amonut > 100)
+
+Check column names and types, for example with `df.columns()`:
+
+    let big = df.filter(.amount > 100)
+"#,
+    },
+    ErrorCode {
+        code: "E0324",
+        name: "internal",
+        summary: "internal VM error",
+        explanation: r#"The virtual machine reached a state it should never reach. This is a bug
+in Stratum, not in your code.
+
+Please report it with the code that triggers it at
+https://github.com/horizon-analytic-studios/stratum/issues
+"#,
+    },
+    ErrorCode {
+        code: "E0325",
+        name: "timeout",
+        summary: "execution ran past its time limit",
+        explanation: r#"The program, or a single test, ran longer than the configured timeout,
+often because of a loop that never ends.
+
+Erroneous example:
+
+    let i = 0
+    while i < 10 {
+        println(i)
+    }
+
+Make sure loops make progress towards their exit condition, or raise the
+limit, such as `stratum test --timeout`, for work that is genuinely slow:
+
+    let i = 0
+    while i < 10 {
+        println(i)
+        i = i + 1
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0326",
+        name: "permission-denied",
+        summary: "a native needed a permission that was not granted",
+        explanation: r#"`stratum run` denies file, network, environment and process access unless
+it is granted on the command line. A call that needs access it was not
+granted fails, naming the flag that grants it.
+
+Erroneous example (run with `stratum run report.strat`):
+
+    let rows = Data.read_csv("data/sales.csv")
+
+Grant the access the program needs, as narrowly as it allows:
+
+    stratum run --allow-read=./data report.strat
+
+`--allow-read`, `--allow-write`, `--allow-net`, `--allow-env` and
+`--allow-run` each take an optional list of paths, hosts, variables or
+programs; `--allow-all` grants everything.
+"#,
+    },
+    ErrorCode {
+        code: "E0327",
+        name: "interrupted",
+        summary: "execution was stopped by a shutdown signal",
+        explanation: r#"`stratum run` stops a program gracefully on Ctrl-C (SIGINT) or SIGTERM.
+Between instructions, it runs the callbacks registered with `System.on_exit`,
+newest first, and then stops with this error. The process exits with status
+130 for SIGINT and 143 for SIGTERM, as if it had been killed by the signal.
+
+Example:
+
+    let db = Db.sqlite("app.db")
+    System.on_exit(|| db.close())
+    serve_forever(db)
+
+Release resources in an `on_exit` callback rather than after the loop, which
+never finishes. A second signal stops the program at once, without running
+the callbacks.
+"#,
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bytecode::CompileErrorKind;
+    use crate::parser::ParseErrorKind;
+    use crate::types::{Type, TypeErrorKind};
+    use crate::vm::RuntimeErrorKind;
+
+    #[test]
+    fn test_codes_sorted_and_unique() {
+        for pair in ERROR_CODES.windows(2) {
+            assert!(
+                pair[0].code < pair[1].code,
+                "{} >= {}",
+                pair[0].code,
+                pair[1].code
+            );
+        }
+        for entry in ERROR_CODES {
+            assert_eq!(entry.code.len(), 5);
+            assert!(entry.code.starts_with('E'));
+            assert!(!entry.summary.is_empty() && !entry.explanation.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(lookup("E0100").unwrap().name, "type-mismatch");
+        assert_eq!(lookup("e0100").unwrap().code, "E0100");
+        assert_eq!(lookup("100").unwrap().code, "E0100");
+        assert!(lookup("E9999").is_none());
+        assert!(lookup("type-mismatch").is_none());
+        assert_eq!(
+            lookup("E0001").unwrap().url(),
+            format!("{ERROR_INDEX_URL}#e0001")
+        );
+    }
+
+    #[test]
+    fn test_kinds_have_entries() {
+        let codes = [
+            ParseErrorKind::UnexpectedEof.code(),
+            ParseErrorKind::PositionalAfterNamed.code(),
+            TypeErrorKind::TypeMismatch {
+                expected: Type::Int,
+                found: Type::String,
+            }
+            .code(),
+            TypeErrorKind::AssignToConstant("X".to_string()).code(),
+            CompileErrorKind::TooManyConstants.code(),
+            CompileErrorKind::Internal(String::new()).code(),
+            RuntimeErrorKind::DivisionByZero.code(),
+            RuntimeErrorKind::Timeout(std::time::Duration::from_secs(1)).code(),
+            RuntimeErrorKind::PermissionDenied(String::new()).code(),
+            RuntimeErrorKind::Interrupted(2).code(),
+        ];
+        for code in codes {
+            assert!(lookup(code).is_some(), "no entry for {code}");
+        }
+        assert_eq!(TypeErrorKind::CannotInfer.code(), "E0120");
+        assert_eq!(
+            lookup(RuntimeErrorKind::NullReference.code()).unwrap().name,
+            "null-reference"
+        );
+    }
+}
@@ -0,0 +1,225 @@
+//! Import organization
+//!
+//! Sorts, groups and deduplicates the imports of a module and drops the names
+//! nothing reads. Standard library imports come first, then everything else,
+//! with a blank line between the two groups.
+
+use std::collections::{BTreeMap, HashSet};
+
+use super::{trim_span_end, Formatter, RangeEdit};
+use crate::ast::{Import, ImportItem, ImportKind, ItemKind};
+use crate::lexer::Span;
+use crate::lint;
+use crate::parser::Parser;
+
+impl Formatter {
+    /// Organize the imports of `source`
+    ///
+    /// The returned edit replaces the lines from the first import to the last
+    /// with the organized imports; anything written between them is kept and
+    /// moved below. Returns `None` if the source does not parse, has no
+    /// imports, or its imports are already organized.
+    #[must_use]
+    pub fn organize_imports(source: &str) -> Option<RangeEdit> {
+        let module = Parser::parse_module(source).ok()?;
+        let imports: Vec<&Import> = module
+            .items()
+            .into_iter()
+            .filter_map(|item| match &item.kind {
+                ItemKind::Import(import) => Some(import),
+                _ => None,
+            })
+            .collect();
+        let spans: Vec<Span> = imports
+            .iter()
+            .map(|import| trim_span_end(source, import.span))
+            .collect();
+        let (first, last) = (spans.first()?, spans.last()?);
+
+        let unused = lint::unused_imports(&module, source);
+        let organized = organize(&imports, &unused);
+
+        let mut formatter = Self::new();
+        for (i, import) in organized.iter().enumerate() {
+            if i > 0 {
+                formatter.writeln();
+                if is_std(&organized[i - 1]) && !is_std(import) {
+                    formatter.writeln();
+                }
+            }
+            formatter.write_import(import);
+        }
+        let mut text = formatter.output;
+
+        // Code between the imports stays, below the organized block
+        let between: Vec<&str> = spans
+            .windows(2)
+            .filter_map(|pair| source.get(pair[0].end as usize..pair[1].start as usize))
+            .map(str::trim)
+            .filter(|gap| !gap.is_empty())
+            .collect();
+        if !between.is_empty() {
+            if !text.is_empty() {
+                text.push_str("\n\n");
+            }
+            text.push_str(&between.join("\n\n"));
+        }
+
+        let mut span = Span::new(first.start, last.end);
+        if text.is_empty() {
+            // Everything was unused: take the line break along with the imports
+            let rest = source.get(span.end as usize..).unwrap_or_default();
+            let blank = rest.len() - rest.trim_start().len();
+            span.end += u32::try_from(blank).unwrap_or(0);
+        }
+
+        if source.get(span.start as usize..span.end as usize) == Some(text.as_str()) {
+            return None;
+        }
+        Some(RangeEdit { span, text })
+    }
+}
+
+/// The sorted, merged imports with unused names removed
+fn organize(imports: &[&Import], unused: &HashSet<String>) -> Vec<Import> {
+    let is_used = |name: &str| !unused.contains(name);
+
+    // Lists from the same module are merged into one
+    let mut lists: BTreeMap<Vec<String>, (Import, Vec<ImportItem>)> = BTreeMap::new();
+    let mut others: Vec<Import> = Vec::new();
+
+    for import in imports {
+        match &import.kind {
+            ImportKind::Item => {
+                if import.path.last().is_some_and(|ident| is_used(&ident.name)) {
+                    others.push((*import).clone());
+                }
+            }
+            ImportKind::Alias(alias) => {
+                if is_used(&alias.name) {
+                    others.push((*import).clone());
+                }
+            }
+            ImportKind::Glob => others.push((*import).clone()),
+            ImportKind::List(items) => {
+                let kept = items
+                    .iter()
+                    .filter(|item| is_used(&item.alias.as_ref().unwrap_or(&item.name).name))
+                    .cloned();
+                lists
+                    .entry(path_key(import))
+                    .or_insert_with(|| ((*import).clone(), Vec::new()))
+                    .1
+                    .extend(kept);
+            }
+        }
+    }
+
+    for (import, mut items) in lists.into_values() {
+        items.sort_by_key(item_key);
+        items.dedup_by(|a, b| item_key(a) == item_key(b));
+        if !items.is_empty() {
+            others.push(Import::new(
+                import.path,
+                ImportKind::List(items),
+                import.span,
+            ));
+        }
+    }
+
+    others.sort_by_key(sort_key);
+    others.dedup_by(|a, b| sort_key(a) == sort_key(b));
+    others
+}
+
+fn is_std(import: &Import) -> bool {
+    import.path.first().is_some_and(|ident| ident.name == "std")
+}
+
+fn path_key(import: &Import) -> Vec<String> {
+    import.path.iter().map(|ident| ident.name.clone()).collect()
+}
+
+fn item_key(item: &ImportItem) -> (String, Option<String>) {
+    (
+        item.name.name.clone(),
+        item.alias.as_ref().map(|alias| alias.name.clone()),
+    )
+}
+
+/// Standard library first, then by path; a plain import of a module sorts
+/// before a list or glob import from it
+fn sort_key(import: &Import) -> (bool, Vec<String>, u8, Option<String>) {
+    let (rank, alias) = match &import.kind {
+        ImportKind::Item => (0, None),
+        ImportKind::Alias(alias) => (1, Some(alias.name.clone())),
+        ImportKind::List(_) => (2, None),
+        ImportKind::Glob => (3, None),
+    };
+    (!is_std(import), path_key(import), rank, alias)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn organize_source(source: &str) -> String {
+        match Formatter::organize_imports(source) {
+            Some(edit) => {
+                let mut out = source.to_string();
+                out.replace_range(edit.span.start as usize..edit.span.end as usize, &edit.text);
+                out
+            }
+            None => source.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_organize_sorts_and_groups() {
+        let source = "import json\nimport std.math\nimport data.{Series, Frame}\nimport std.io\n\nio.println(json.encode(math.sqrt(Frame.new(Series.new()))))\n";
+        assert_eq!(
+            organize_source(source),
+            "import std.io\nimport std.math\n\nimport data.{Frame, Series}\nimport json\n\nio.println(json.encode(math.sqrt(Frame.new(Series.new()))))\n"
+        );
+    }
+
+    #[test]
+    fn test_organize_removes_unused() {
+        let source = "import std.math\nimport std.io\nimport data.{Frame, Series, read_csv}\nimport json.*\n\nio.println(read_csv(\"a\"))\n";
+        assert_eq!(
+            organize_source(source),
+            "import std.io\n\nimport data.{read_csv}\nimport json.*\n\nio.println(read_csv(\"a\"))\n"
+        );
+    }
+
+    #[test]
+    fn test_organize_merges_and_dedups() {
+        let source = "import data.{Frame}\nimport data.{Series, Frame}\nimport io\nimport io\n\nio.println(Frame.new(Series.new()))\n";
+        assert_eq!(
+            organize_source(source),
+            "import data.{Frame, Series}\nimport io\n\nio.println(Frame.new(Series.new()))\n"
+        );
+    }
+
+    #[test]
+    fn test_organize_keeps_code_between_imports() {
+        let source = "import io\nlet x = 1\nimport data\n\nio.println(data.load(x))\n";
+        assert_eq!(
+            organize_source(source),
+            "import data\nimport io\n\nlet x = 1\n\nio.println(data.load(x))\n"
+        );
+    }
+
+    #[test]
+    fn test_organize_all_unused() {
+        let source = "import std.math\nimport json\n\nfx main() {}\n";
+        assert_eq!(organize_source(source), "fx main() {}\n");
+    }
+
+    #[test]
+    fn test_organize_already_organized() {
+        let source = "import std.io\n\nimport data\n\nio.println(data.load())\n";
+        assert!(Formatter::organize_imports(source).is_none());
+        assert!(Formatter::organize_imports("fx main() {}\n").is_none());
+    }
+}
@@ -14,9 +14,12 @@ use crate::ast::{
     Param, Pattern, PatternKind, Stmt, StmtKind, StringPart, StructDef, StructField, TopLevelItem,
     TopLevelLet, Trivia, TypeAnnotation, TypeKind, TypeParam,
 };
+use crate::lexer::Span;
 
-/// Default indentation: 4 spaces
-const INDENT: &str = "    ";
+mod imports;
+mod range;
+
+pub use range::RangeEdit;
 
 /// Soft line length limit
 const LINE_LIMIT: usize = 100;
@@ -52,6 +55,9 @@ pub struct Formatter {
     config: FormatConfig,
     /// Whether we're at the start of a line
     at_line_start: bool,
+    /// Original source, used to keep the spelling of literals
+    /// (numeric separators, raw and multiline strings)
+    source: Option<String>,
 }
 
 impl Formatter {
@@ -69,27 +75,53 @@ impl Formatter {
             indent_level: 0,
             config,
             at_line_start: true,
+            source: None,
         }
     }
 
     /// Format a module and return the formatted source code
     #[must_use]
     pub fn format_module(module: &Module) -> String {
-        let mut formatter = Self::new();
-        formatter.write_module(module);
-        if formatter.config.trailing_newline && !formatter.output.ends_with('\n') {
-            formatter.output.push('\n');
-        }
-        formatter.output
+        Self::new().finish(module)
+    }
+
+    /// Format a module parsed from `source`
+    ///
+    /// Unlike [`Formatter::format_module`], literals keep their original
+    /// spelling, so `1_000_000`, `0xFF`, `r"\d+"` and indented `"""` strings
+    /// are written back as they appear in the source.
+    #[must_use]
+    pub fn format_source(source: &str, module: &Module) -> String {
+        Self::format_source_with_config(source, module, FormatConfig::default())
+    }
+
+    /// Format a module parsed from `source` with a custom config
+    #[must_use]
+    pub fn format_source_with_config(
+        source: &str,
+        module: &Module,
+        config: FormatConfig,
+    ) -> String {
+        let mut formatter = Self::with_config(config);
+        formatter.source = Some(source.to_string());
+        formatter.finish(module)
     }
 
     /// Check if formatting would change the source
     #[must_use]
     pub fn check_module(source: &str, module: &Module) -> bool {
-        let formatted = Self::format_module(module);
+        let formatted = Self::format_source(source, module);
         source == formatted
     }
 
+    fn finish(mut self, module: &Module) -> String {
+        self.write_module(module);
+        if self.config.trailing_newline && !self.output.ends_with('\n') {
+            self.output.push('\n');
+        }
+        self.output
+    }
+
     // ==================== Output Helpers ====================
 
     fn write(&mut self, s: &str) {
@@ -106,9 +138,8 @@ impl Formatter {
     }
 
     fn write_indent(&mut self) {
-        for _ in 0..self.indent_level {
-            self.output.push_str(INDENT);
-        }
+        let width = self.indent_level * self.config.indent_size;
+        self.output.push_str(&" ".repeat(width));
     }
 
     fn indent(&mut self) {
@@ -227,6 +258,7 @@ impl Formatter {
                     self.write(" = ");
                     self.write_expr(value);
                 }
+                AttributeArg::Value(value) => self.write_expr(value),
             }
         }
     }
@@ -243,7 +275,7 @@ impl Formatter {
 
     fn write_top_level_let(&mut self, let_decl: &TopLevelLet) {
         self.write_leading_trivia(&let_decl.trivia);
-        self.write("let ");
+        self.write(if let_decl.is_const { "const " } else { "let " });
         self.write_pattern(&let_decl.pattern);
         if let Some(ty) = &let_decl.ty {
             self.write(": ");
@@ -345,6 +377,10 @@ impl Formatter {
 
     fn write_struct(&mut self, s: &StructDef) {
         self.write_leading_trivia(&s.trivia);
+        for attr in &s.attributes {
+            self.write_attribute(attr);
+            self.writeln();
+        }
         self.write("struct ");
         self.write(&s.name.name);
 
@@ -380,6 +416,10 @@ impl Formatter {
 
     fn write_enum(&mut self, e: &EnumDef) {
         self.write_leading_trivia(&e.trivia);
+        for attr in &e.attributes {
+            self.write_attribute(attr);
+            self.writeln();
+        }
         self.write("enum ");
         self.write(&e.name.name);
 
@@ -528,15 +568,15 @@ impl Formatter {
         self.write("import ");
         for (i, seg) in imp.path.iter().enumerate() {
             if i > 0 {
-                self.write("::");
+                self.write(".");
             }
             self.write(&seg.name);
         }
         match &imp.kind {
             ImportKind::Item => {}
-            ImportKind::Glob => self.write("::*"),
+            ImportKind::Glob => self.write(".*"),
             ImportKind::List(items) => {
-                self.write("::{ ");
+                self.write(".{");
                 for (i, item) in items.iter().enumerate() {
                     if i > 0 {
                         self.write(", ");
@@ -547,7 +587,7 @@ impl Formatter {
                         self.write(&alias.name);
                     }
                 }
-                self.write(" }");
+                self.write("}");
             }
             ImportKind::Alias(alias) => {
                 self.write(" as ");
@@ -569,7 +609,7 @@ impl Formatter {
         self.indent();
 
         for stmt in &block.stmts {
-            self.write_stmt(stmt);
+            self.write_block_stmt(stmt);
             self.writeln();
         }
 
@@ -584,6 +624,15 @@ impl Formatter {
 
     // ==================== Statements ====================
 
+    /// Write a statement inside a block, where expression statements
+    /// need their `;` back
+    fn write_block_stmt(&mut self, stmt: &Stmt) {
+        self.write_stmt(stmt);
+        if matches!(stmt.kind, StmtKind::Expr(_)) {
+            self.write(";");
+        }
+    }
+
     fn write_stmt(&mut self, stmt: &Stmt) {
         self.write_leading_trivia(&stmt.trivia);
 
@@ -684,7 +733,7 @@ impl Formatter {
 
     fn write_expr(&mut self, expr: &Expr) {
         match &expr.kind {
-            ExprKind::Literal(lit) => self.write_literal(lit),
+            ExprKind::Literal(lit) => self.write_literal(lit, expr.span),
             ExprKind::Ident(name) => self.write(&name.name),
             ExprKind::Binary { left, op, right } => {
                 self.write_expr(left);
@@ -802,10 +851,14 @@ impl Formatter {
                 self.write("}");
             }
             ExprKind::StringInterp { parts } => {
+                if let Some(text) = self.source_text(expr.span, |t| t.starts_with("\"\"\"")) {
+                    self.write(&text);
+                    return;
+                }
                 self.write("\"");
                 for part in parts {
                     match part {
-                        StringPart::Literal(s) => self.write(s),
+                        StringPart::Literal(s) => self.write(&escape_string(s)),
                         StringPart::Expr(e) => {
                             self.write("{");
                             self.write_expr(e);
@@ -915,7 +968,21 @@ impl Formatter {
         }
     }
 
-    fn write_literal(&mut self, lit: &Literal) {
+    fn write_literal(&mut self, lit: &Literal, span: Span) {
+        let spelled = match lit {
+            Literal::Int(_) | Literal::Float(_) => {
+                self.source_text(span, |t| t.starts_with(|c: char| c.is_ascii_digit()))
+            }
+            Literal::String(_) => self.source_text(span, |t| {
+                t.starts_with("r\"") || t.starts_with("r#") || t.starts_with("\"\"\"")
+            }),
+            _ => None,
+        };
+        if let Some(text) = spelled {
+            self.write(&text);
+            return;
+        }
+
         match lit {
             Literal::Int(n) => self.write(&n.to_string()),
             Literal::Float(n) => {
@@ -927,7 +994,7 @@ impl Formatter {
             }
             Literal::String(s) => {
                 self.write("\"");
-                self.write(s);
+                self.write(&escape_string(s));
                 self.write("\"");
             }
             Literal::Bool(b) => self.write(if *b { "true" } else { "false" }),
@@ -935,13 +1002,22 @@ impl Formatter {
         }
     }
 
+    /// The source text of `span`, if formatting from source and `keep` accepts it
+    fn source_text(&self, span: Span, keep: impl Fn(&str) -> bool) -> Option<String> {
+        let text = self
+            .source
+            .as_deref()?
+            .get(span.start as usize..span.end as usize)?;
+        keep(text).then(|| text.to_string())
+    }
+
     // ==================== Patterns ====================
 
     fn write_pattern(&mut self, pattern: &Pattern) {
         match &pattern.kind {
             PatternKind::Wildcard => self.write("_"),
             PatternKind::Ident(name) => self.write(&name.name),
-            PatternKind::Literal(lit) => self.write_literal(lit),
+            PatternKind::Literal(lit) => self.write_literal(lit, pattern.span),
             PatternKind::Variant {
                 enum_name,
                 variant,
@@ -1059,6 +1135,36 @@ impl Formatter {
     }
 }
 
+/// Shrink `span` to end at its last non-whitespace byte
+///
+/// Statement and item spans run on over the whitespace that follows them.
+fn trim_span_end(source: &str, span: Span) -> Span {
+    let text = source
+        .get(span.start as usize..span.end as usize)
+        .unwrap_or_default();
+    let trailing = u32::try_from(text.len() - text.trim_end().len()).unwrap_or(0);
+    Span::new(span.start, span.end - trailing)
+}
+
+/// Escape a string's contents for a regular `"..."` literal
+fn escape_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '{' => escaped.push_str("\\{"),
+            '}' => escaped.push_str("\\}"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            '\0' => escaped.push_str("\\0"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 impl Default for Formatter {
     fn default() -> Self {
         Self::new()
@@ -1083,6 +1189,21 @@ mod tests {
         assert!(formatted.contains("a + b"));
     }
 
+    #[test]
+    fn test_format_indent_size() {
+        let source = "fx f() {\nif true {\nprintln(1)\n}\n}\n";
+        let module = Parser::parse_module(source).unwrap();
+        let config = FormatConfig {
+            indent_size: 2,
+            ..FormatConfig::default()
+        };
+        let formatted = Formatter::format_source_with_config(source, &module, config);
+        assert!(
+            formatted.contains("\n  if true {\n    println(1)\n  }"),
+            "{formatted}"
+        );
+    }
+
     #[test]
     fn test_format_struct() {
         let source = "struct Point{x:Int,y:Int}";
@@ -1105,6 +1226,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_format_derive_attributes() {
+        let source = "#[derive(Eq,Show)]\nstruct Point{x:Int}\n#[derive(Json)]\nenum Shape{Dot}";
+        let formatted = format_code(source);
+        assert!(
+            formatted.contains("#[derive(Eq, Show)]\nstruct Point {"),
+            "{formatted}"
+        );
+        assert!(
+            formatted.contains("#[derive(Json)]\nenum Shape {"),
+            "{formatted}"
+        );
+    }
+
+    #[test]
+    fn test_format_test_case_attributes() {
+        let source = "#[test_case(1,-2,name=\"neg\")]\nfx test_abs(a:Int,b:Int){}";
+        let formatted = format_code(source);
+        assert!(
+            formatted.contains("#[test_case(1, -2, name = \"neg\")]\nfx test_abs("),
+            "{formatted}"
+        );
+    }
+
     #[test]
     fn test_format_preserves_comments() {
         let source = "// This is a comment\nfx main() {}";
@@ -1124,4 +1269,38 @@ fx add(a: Int, b: Int) -> Int {
         let formatted2 = format_code(&formatted1);
         assert_eq!(formatted1, formatted2, "Formatting should be idempotent");
     }
+
+    fn format_with_source(source: &str) -> String {
+        let module = Parser::parse_module(source).expect("parse failed");
+        Formatter::format_source(source, &module)
+    }
+
+    #[test]
+    fn test_format_escapes_strings() {
+        let source = r#"let s = "say \"hi\"\n\t{name} \{literal\} \\ done""#;
+        let formatted = format_code(source);
+        assert!(
+            formatted.contains(r#""say \"hi\"\n\t{name} \{literal\} \\ done""#),
+            "escapes should round-trip: {formatted}"
+        );
+        println!("OUT<<{formatted}>>");
+        assert_eq!(format_code(&formatted), formatted);
+    }
+
+    #[test]
+    fn test_format_source_keeps_literal_spelling() {
+        let source = "let big = 1_000_000\nlet mask = 0xFF_00\nlet re = r\"\\d+\\.\\d*\"\n";
+        assert_eq!(format_with_source(source), source);
+    }
+
+    #[test]
+    fn test_format_source_keeps_multiline_strings() {
+        let source = "fx main() {\n    let q = \"\"\"\n        SELECT *\n        FROM {table}\n        \"\"\"\n}\n";
+        let formatted = format_with_source(source);
+        assert_eq!(formatted, source);
+        assert!(Formatter::check_module(
+            source,
+            &Parser::parse_module(source).unwrap()
+        ));
+    }
 }
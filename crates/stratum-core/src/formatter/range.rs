@@ -0,0 +1,361 @@
+//! Range formatting
+//!
+//! Reformats only the statements or items a selection touches, so editors
+//! can format a selection (or pasted code) without rewriting the whole file.
+
+use super::{trim_span_end, Formatter};
+use crate::ast::{Block, ExprKind, ItemKind, Stmt, StmtKind, TopLevelItem, Trivia};
+use crate::lexer::Span;
+use crate::parser::Parser;
+
+/// A replacement produced by [`Formatter::format_range`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeEdit {
+    /// The source region to replace, widened to the touched nodes
+    pub span: Span,
+    /// The formatted text for that region
+    pub text: String,
+}
+
+/// A node that can be reformatted on its own
+enum Node<'a> {
+    Top(&'a TopLevelItem),
+    Stmt(&'a Stmt),
+    Expr(&'a crate::ast::Expr),
+}
+
+/// The run of sibling nodes touched by the selection
+struct Selection<'a> {
+    nodes: Vec<Node<'a>>,
+    indent: usize,
+}
+
+impl Formatter {
+    /// Format only the statements or items touched by `span`
+    ///
+    /// The selection is narrowed to the innermost block that contains it, and
+    /// every statement in that block overlapping the selection is formatted
+    /// at its nesting depth. Returns `None` if the source does not parse or
+    /// the selection touches no statements.
+    #[must_use]
+    pub fn format_range(source: &str, span: Span) -> Option<RangeEdit> {
+        let module = Parser::parse_module(source).ok()?;
+        let selection = select_top_level(source, &module.top_level, span)?;
+
+        let first = selection.nodes.first()?;
+        let last = selection.nodes.last()?;
+        let mut start = node_span(source, first).start;
+        let end = node_span(source, last).end;
+
+        // Take over the indentation in front of the first node when it starts
+        // its line, so the formatter's own indentation replaces it
+        let before = source.get(..start as usize)?;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let starts_line = before[line_start..].trim().is_empty();
+        if starts_line {
+            start = u32::try_from(line_start).unwrap_or(start);
+        }
+
+        let mut formatter = Self::new();
+        formatter.source = Some(source.to_string());
+        formatter.indent_level = selection.indent;
+        formatter.write_nodes(&selection.nodes);
+
+        let mut text = formatter.output;
+        if !starts_line {
+            text = text.trim_start().to_string();
+        }
+
+        Some(RangeEdit {
+            span: Span::new(start, end),
+            text,
+        })
+    }
+
+    fn write_nodes(&mut self, nodes: &[Node<'_>]) {
+        for (i, node) in nodes.iter().enumerate() {
+            if i > 0 {
+                self.writeln();
+                // Mirror `write_module`: functions get a blank line around them
+                if let (Node::Top(prev), Node::Top(item)) = (&nodes[i - 1], node) {
+                    if is_function(prev) || is_function(item) {
+                        self.writeln();
+                    }
+                }
+            }
+            match node {
+                Node::Top(item) => self.write_top_level_item(item),
+                Node::Stmt(stmt) => self.write_block_stmt(stmt),
+                Node::Expr(expr) => self.write_expr(expr),
+            }
+        }
+    }
+}
+
+fn select_top_level<'a>(
+    source: &str,
+    items: &'a [TopLevelItem],
+    span: Span,
+) -> Option<Selection<'a>> {
+    let touched: Vec<&TopLevelItem> = items
+        .iter()
+        .filter(|item| touches(node_span(source, &Node::Top(item)), span))
+        .collect();
+
+    if let [item] = touched.as_slice() {
+        if let Some(selection) = select_inside_item(source, item, span) {
+            return Some(selection);
+        }
+    }
+
+    if touched.is_empty() {
+        return None;
+    }
+    Some(Selection {
+        nodes: touched.into_iter().map(Node::Top).collect(),
+        indent: 0,
+    })
+}
+
+/// Narrow a selection that sits entirely inside a function or method body
+fn select_inside_item<'a>(
+    source: &str,
+    item: &'a TopLevelItem,
+    span: Span,
+) -> Option<Selection<'a>> {
+    match item {
+        TopLevelItem::Item(item) => match &item.kind {
+            ItemKind::Function(func) => select_in_block(source, &func.body, span, 1),
+            ItemKind::Impl(imp) => imp
+                .methods
+                .iter()
+                .find(|method| inside(&method.body, span))
+                .and_then(|method| select_in_block(source, &method.body, span, 2)),
+            _ => None,
+        },
+        TopLevelItem::Statement(stmt) => select_in_stmt(source, stmt, span, 0),
+        TopLevelItem::Let(_) => None,
+    }
+}
+
+fn select_in_block<'a>(
+    source: &str,
+    block: &'a Block,
+    span: Span,
+    indent: usize,
+) -> Option<Selection<'a>> {
+    if !inside(block, span) {
+        return None;
+    }
+
+    let mut nodes: Vec<Node<'_>> = block
+        .stmts
+        .iter()
+        .filter(|stmt| touches(node_span(source, &Node::Stmt(stmt)), span))
+        .map(Node::Stmt)
+        .collect();
+    if let Some(expr) = &block.expr {
+        if touches(expr.span, span) {
+            nodes.push(Node::Expr(expr));
+        }
+    }
+
+    if let [Node::Stmt(stmt)] = nodes.as_slice() {
+        if let Some(selection) = select_in_stmt(source, stmt, span, indent) {
+            return Some(selection);
+        }
+    }
+
+    // An empty selection still claims the block, so a cursor on a blank
+    // line formats nothing rather than the whole enclosing function
+    Some(Selection { nodes, indent })
+}
+
+/// Descend into the blocks a statement owns
+fn select_in_stmt<'a>(
+    source: &str,
+    stmt: &'a Stmt,
+    span: Span,
+    indent: usize,
+) -> Option<Selection<'a>> {
+    let nested = indent + 1;
+    match &stmt.kind {
+        StmtKind::For { body, .. } | StmtKind::While { body, .. } | StmtKind::Loop { body } => {
+            select_in_block(source, body, span, nested)
+        }
+        StmtKind::TryCatch {
+            try_block,
+            catches,
+            finally,
+        } => std::iter::once(try_block)
+            .chain(catches.iter().map(|catch| &catch.body))
+            .chain(finally.iter())
+            .find_map(|block| select_in_block(source, block, span, nested)),
+        StmtKind::Expr(expr) => match &expr.kind {
+            ExprKind::If {
+                then_branch,
+                else_branch,
+                ..
+            } => select_in_block(source, then_branch, span, nested).or_else(|| match else_branch {
+                Some(crate::ast::ElseBranch::Block(block)) => {
+                    select_in_block(source, block, span, nested)
+                }
+                _ => None,
+            }),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The span of a node, including its leading comments and attributes
+fn node_span(source: &str, node: &Node<'_>) -> Span {
+    trim_span_end(source, raw_node_span(node))
+}
+
+fn raw_node_span(node: &Node<'_>) -> Span {
+    match node {
+        Node::Top(TopLevelItem::Item(item)) => {
+            let (trivia, attr_start) = match &item.kind {
+                ItemKind::Function(func) => {
+                    (&func.trivia, func.attributes.first().map(|a| a.span.start))
+                }
+                ItemKind::Struct(s) => (&s.trivia, s.attributes.first().map(|a| a.span.start)),
+                ItemKind::Enum(e) => (&e.trivia, e.attributes.first().map(|a| a.span.start)),
+                ItemKind::Interface(i) => (&i.trivia, None),
+                ItemKind::Impl(i) => (&i.trivia, None),
+                ItemKind::Import(_) => return item.span,
+            };
+            let start = attr_start.map_or(item.span.start, |s| s.min(item.span.start));
+            with_trivia(Span::new(start, item.span.end), trivia)
+        }
+        Node::Top(TopLevelItem::Let(let_decl)) => with_trivia(let_decl.span, &let_decl.trivia),
+        Node::Top(TopLevelItem::Statement(stmt)) => with_trivia(stmt_span(stmt), &stmt.trivia),
+        Node::Stmt(stmt) => with_trivia(stmt_span(stmt), &stmt.trivia),
+        Node::Expr(expr) => expr.span,
+    }
+}
+
+/// Expression statements are spanned from the start of their enclosing
+/// block, so take the start from the expression itself
+fn stmt_span(stmt: &Stmt) -> Span {
+    match &stmt.kind {
+        StmtKind::Expr(expr) => Span::new(expr.span.start, stmt.span.end.max(expr.span.end)),
+        _ => stmt.span,
+    }
+}
+
+fn with_trivia(span: Span, trivia: &Trivia) -> Span {
+    match trivia.leading.first() {
+        Some(comment) => Span::new(comment.span.start.min(span.start), span.end),
+        None => span,
+    }
+}
+
+fn is_function(item: &TopLevelItem) -> bool {
+    matches!(
+        item,
+        TopLevelItem::Item(crate::ast::Item {
+            kind: ItemKind::Function(_),
+            ..
+        })
+    )
+}
+
+/// Whether `span` lies strictly between the braces of `block`
+fn inside(block: &Block, span: Span) -> bool {
+    block.span.start < span.start && span.end < block.span.end
+}
+
+/// Whether a node overlaps the selection; an empty selection (a cursor)
+/// touches the node it sits in
+fn touches(node: Span, span: Span) -> bool {
+    if span.start == span.end {
+        node.start <= span.start && span.start <= node.end
+    } else {
+        node.start < span.end && span.start < node.end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(source: &str, edit: &RangeEdit) -> String {
+        let mut out = source.to_string();
+        out.replace_range(edit.span.start as usize..edit.span.end as usize, &edit.text);
+        out
+    }
+
+    fn offset(source: &str, needle: &str) -> u32 {
+        u32::try_from(source.find(needle).expect("needle not found")).unwrap()
+    }
+
+    fn span_of(source: &str, needle: &str) -> Span {
+        let start = offset(source, needle);
+        Span::new(start, start + u32::try_from(needle.len()).unwrap())
+    }
+
+    #[test]
+    fn test_format_range_single_statement() {
+        let source = "fx main() {\n    let a=1+2\n    let   b = 3\n    a\n}\n";
+        let edit = Formatter::format_range(source, span_of(source, "let a=1+2")).unwrap();
+        assert_eq!(
+            apply(source, &edit),
+            "fx main() {\n    let a = 1 + 2\n    let   b = 3\n    a\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_format_range_leaves_other_functions_untouched() {
+        let source = "fx one()->Int{1+1}\n\nfx two()  ->  Int {\n    2\n}\n";
+        let edit = Formatter::format_range(source, span_of(source, "fx one")).unwrap();
+        assert_eq!(
+            apply(source, &edit),
+            "fx one() -> Int {\n    1 + 1\n}\n\nfx two()  ->  Int {\n    2\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_format_range_nested_block() {
+        let source =
+            "fx main() {\n    for x in xs {\n        print(x+1);\n        print( x )\n    }\n}\n";
+        let edit = Formatter::format_range(source, span_of(source, "print(x+1)")).unwrap();
+        assert_eq!(edit.text, "        print(x + 1);");
+        assert_eq!(
+            apply(source, &edit),
+            "fx main() {\n    for x in xs {\n        print(x + 1);\n        print( x )\n    }\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_format_range_keeps_leading_comments() {
+        let source = "fx main() {\n    // note\n    let a=1\n    a\n}\n";
+        let edit = Formatter::format_range(source, span_of(source, "let a=1")).unwrap();
+        assert_eq!(
+            apply(source, &edit),
+            "fx main() {\n    // note\n    let a = 1\n    a\n}\n"
+        );
+
+        let source = "// doc\nfx one(){1}\n";
+        let edit = Formatter::format_range(source, span_of(source, "one")).unwrap();
+        assert_eq!(apply(source, &edit), "// doc\nfx one() {\n    1\n}\n");
+    }
+
+    #[test]
+    fn test_format_range_cursor_and_empty_selection() {
+        let source = "fx main() {\n    let a=1\n\n    a\n}\n";
+        let cursor = offset(source, "a=1");
+        let edit = Formatter::format_range(source, Span::new(cursor, cursor)).unwrap();
+        assert_eq!(edit.text, "    let a = 1");
+
+        let blank = offset(source, "\n\n") + 1;
+        assert!(Formatter::format_range(source, Span::new(blank, blank)).is_none());
+    }
+
+    #[test]
+    fn test_format_range_rejects_invalid_source() {
+        let source = "fx main( {";
+        assert!(Formatter::format_range(source, Span::new(0, 4)).is_none());
+    }
+}
@@ -0,0 +1,420 @@
+//! Coverage-guided fuzzing of Stratum functions
+//!
+//! The fuzzer calls a one-parameter function over and over with mutated
+//! inputs. A `List` parameter receives the input as a list of byte values;
+//! any other parameter receives it as a (lossily decoded) string.
+//!
+//! Each call runs in a fresh VM with coverage enabled. An input that covers
+//! a line or branch outcome no earlier input covered joins the corpus that
+//! later inputs are mutated from, so the search works its way deeper into
+//! the target. The first input that makes the target fail is shrunk to a
+//! smaller input failing with the same kind of error.
+
+use std::collections::HashSet;
+use std::mem::discriminant;
+use std::rc::Rc;
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use thiserror::Error;
+
+use crate::ast::{ItemKind, Module, TopLevelItem, TypeKind};
+use crate::bytecode::{Compiler, Function, Value};
+use crate::coverage::CoveragePoint;
+use crate::parser::Parser;
+use crate::vm::{with_output_capture, RuntimeError, VM};
+
+/// The global holding the input while the target runs
+const INPUT_GLOBAL: &str = "__fuzz_input";
+
+/// Bytes that often reach edge cases in parsers and string handling
+const INTERESTING_BYTES: &[u8] = b"\0\x7f\xff \t\n\"'\\/{}[]()<>=-+.,:;0189aAzZ";
+
+/// Errors setting up a fuzzing run
+#[derive(Debug, Error)]
+pub enum FuzzError {
+    /// The module has no function with the target's name
+    #[error("no function named '{0}'")]
+    UnknownTarget(String),
+
+    /// The target does not take exactly one parameter
+    #[error("'{name}' takes {arity} parameters, but a fuzz target takes one")]
+    TargetArity { name: String, arity: usize },
+
+    /// The module failed to compile
+    #[error("compile errors:\n{}", .0.join("\n"))]
+    Compile(Vec<String>),
+
+    /// The module's definitions failed before the target could be called
+    #[error("setup failed: {0}")]
+    Setup(String),
+}
+
+/// How the fuzzer passes an input to the target
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputKind {
+    /// A string, decoded from the input bytes
+    String,
+    /// A list of the input's byte values
+    Bytes,
+}
+
+/// Options for a fuzzing run
+#[derive(Debug, Clone)]
+pub struct FuzzOptions {
+    /// Inputs to try after the seeds
+    pub runs: u64,
+    /// Longest input to generate, in bytes
+    pub max_len: usize,
+    /// Seed for the mutation RNG, so runs can be repeated
+    pub seed: u64,
+    /// Fail an input that runs longer than this
+    pub timeout: Option<Duration>,
+}
+
+impl Default for FuzzOptions {
+    fn default() -> Self {
+        Self {
+            runs: 10_000,
+            max_len: 256,
+            seed: 0,
+            timeout: Some(Duration::from_secs(1)),
+        }
+    }
+}
+
+/// An input that made the target fail
+#[derive(Debug, Clone)]
+pub struct FuzzCrash {
+    /// The input as generated
+    pub input: Vec<u8>,
+    /// The smallest input found that fails with the same kind of error
+    pub minimized: Vec<u8>,
+    /// The error the minimized input raises
+    pub error: String,
+}
+
+/// The outcome of a fuzzing run
+#[derive(Debug, Clone, Default)]
+pub struct FuzzReport {
+    /// Inputs run, including seeds
+    pub runs: u64,
+    /// Generated inputs that reached new coverage, in the order found
+    pub new_inputs: Vec<Vec<u8>>,
+    /// Lines and branch outcomes covered across all inputs
+    pub coverage_points: usize,
+    /// The first failing input, if any (fuzzing stops there)
+    pub crash: Option<FuzzCrash>,
+}
+
+/// A coverage-guided fuzzer for one function
+pub struct Fuzzer {
+    /// The module's definitions, run before each call
+    definitions: Rc<Function>,
+    /// A call of the target with the input global
+    call: Rc<Function>,
+    kind: InputKind,
+    options: FuzzOptions,
+    rng: StdRng,
+    corpus: Vec<Vec<u8>>,
+    seen: HashSet<CoveragePoint>,
+}
+
+impl Fuzzer {
+    /// Prepare to fuzz the function `target` of a module
+    pub fn new(
+        module: &Module,
+        target: &str,
+        source_name: &str,
+        options: FuzzOptions,
+    ) -> Result<Self, FuzzError> {
+        let function = module
+            .items()
+            .into_iter()
+            .find_map(|item| match &item.kind {
+                ItemKind::Function(f) if f.name.name == target => Some(f),
+                _ => None,
+            })
+            .ok_or_else(|| FuzzError::UnknownTarget(target.to_string()))?;
+        let [param] = function.params.as_slice() else {
+            return Err(FuzzError::TargetArity {
+                name: target.to_string(),
+                arity: function.params.len(),
+            });
+        };
+        let kind = match param.ty.as_ref().map(|ty| &ty.kind) {
+            Some(TypeKind::List(_)) => InputKind::Bytes,
+            Some(TypeKind::Named { name, .. }) if name.name == "List" => InputKind::Bytes,
+            _ => InputKind::String,
+        };
+
+        let definitions = Module::new(
+            module.inner_attributes.clone(),
+            module
+                .top_level
+                .iter()
+                .filter(|tl_item| match tl_item {
+                    TopLevelItem::Item(_) => true,
+                    TopLevelItem::Let(let_decl) => let_decl.is_const,
+                    TopLevelItem::Statement(_) => false,
+                })
+                .cloned()
+                .collect(),
+            module.span,
+        );
+        let call =
+            Parser::parse_module(&format!("{target}({INPUT_GLOBAL})")).map_err(|errors| {
+                FuzzError::Compile(errors.iter().map(ToString::to_string).collect())
+            })?;
+        let compile = |module: &Module| {
+            Compiler::with_source(source_name.to_string())
+                .compile_module(module)
+                .map_err(|errors| {
+                    FuzzError::Compile(errors.iter().map(ToString::to_string).collect())
+                })
+        };
+
+        let fuzzer = Self {
+            definitions: compile(&definitions)?,
+            call: compile(&call)?,
+            kind,
+            rng: StdRng::seed_from_u64(options.seed),
+            options,
+            corpus: Vec::new(),
+            seen: HashSet::new(),
+        };
+
+        // Definitions that fail would fail every input
+        let mut vm = VM::new();
+        let (result, _) = with_output_capture(|| vm.run(Rc::clone(&fuzzer.definitions)));
+        result.map_err(|e| FuzzError::Setup(e.to_string()))?;
+
+        Ok(fuzzer)
+    }
+
+    /// How the target is passed its input
+    #[must_use]
+    pub fn input_kind(&self) -> InputKind {
+        self.kind
+    }
+
+    /// Add an input to start mutating from, such as one saved by an
+    /// earlier run
+    pub fn add_seed(&mut self, input: Vec<u8>) {
+        self.corpus.push(input);
+    }
+
+    /// Run the seeds, then mutated inputs until the run count is reached or
+    /// the target fails
+    pub fn run(&mut self) -> FuzzReport {
+        let mut report = FuzzReport::default();
+        if self.corpus.is_empty() {
+            self.corpus.push(Vec::new());
+        }
+
+        let seeds = self.corpus.clone();
+        let generated = (0..self.options.runs).map(|_| None);
+        for seed in seeds.into_iter().map(Some).chain(generated) {
+            let is_seed = seed.is_some();
+            let input = seed.unwrap_or_else(|| self.mutate());
+            report.runs += 1;
+
+            match self.execute(&input) {
+                Ok(points) => {
+                    let before = self.seen.len();
+                    self.seen.extend(points);
+                    if self.seen.len() > before && !is_seed {
+                        self.corpus.push(input.clone());
+                        report.new_inputs.push(input);
+                    }
+                }
+                Err(error) => {
+                    let (minimized, error) = self.minimize(input.clone(), error);
+                    report.crash = Some(FuzzCrash {
+                        input,
+                        minimized,
+                        error: error.to_string(),
+                    });
+                    break;
+                }
+            }
+        }
+
+        report.coverage_points = self.seen.len();
+        report
+    }
+
+    /// Call the target with an input in a fresh VM, returning what it
+    /// covered
+    fn execute(&self, input: &[u8]) -> Result<HashSet<CoveragePoint>, RuntimeError> {
+        let mut vm = VM::new();
+        vm.enable_coverage();
+        vm.set_timeout(self.options.timeout);
+        let value = match self.kind {
+            InputKind::String => Value::string(String::from_utf8_lossy(input).into_owned()),
+            InputKind::Bytes => Value::list(
+                input
+                    .iter()
+                    .map(|&byte| Value::Int(i64::from(byte)))
+                    .collect(),
+            ),
+        };
+
+        let (result, _) = with_output_capture(|| {
+            vm.run(Rc::clone(&self.definitions))?;
+            vm.globals_mut().insert(INPUT_GLOBAL.to_string(), value);
+            vm.run(Rc::clone(&self.call))
+        });
+        result?;
+        Ok(vm.take_coverage().map(|c| c.points()).unwrap_or_default())
+    }
+
+    /// A mutation of a corpus input
+    fn mutate(&mut self) -> Vec<u8> {
+        let mut data = self.corpus[self.rng.gen_range(0..self.corpus.len())].clone();
+        for _ in 0..self.rng.gen_range(1..=4) {
+            let len = data.len();
+            match self.rng.gen_range(0..8) {
+                0 if len > 0 => {
+                    let i = self.rng.gen_range(0..len);
+                    data[i] ^= 1 << self.rng.gen_range(0..8);
+                }
+                1 if len > 0 => {
+                    let i = self.rng.gen_range(0..len);
+                    data[i] = self.rng.gen();
+                }
+                2 => {
+                    let i = self.rng.gen_range(0..=len);
+                    let byte = INTERESTING_BYTES[self.rng.gen_range(0..INTERESTING_BYTES.len())];
+                    data.insert(i, byte);
+                }
+                3 if len > 0 => {
+                    data.remove(self.rng.gen_range(0..len));
+                }
+                4 if len > 0 => {
+                    // Repeat a short run of bytes
+                    let start = self.rng.gen_range(0..len);
+                    let end = (start + self.rng.gen_range(1..=8)).min(len);
+                    let at = self.rng.gen_range(0..=len);
+                    let chunk = data[start..end].to_vec();
+                    data.splice(at..at, chunk);
+                }
+                5 => {
+                    // Splice in the tail of another corpus input
+                    let other = &self.corpus[self.rng.gen_range(0..self.corpus.len())];
+                    let at = self.rng.gen_range(0..=len);
+                    let from = self.rng.gen_range(0..=other.len());
+                    data.truncate(at);
+                    data.extend_from_slice(&other[from..]);
+                }
+                6 if len > 0 => {
+                    data.truncate(self.rng.gen_range(0..len));
+                }
+                _ => {
+                    let i = self.rng.gen_range(0..=len);
+                    data.insert(i, self.rng.gen());
+                }
+            }
+        }
+        data.truncate(self.options.max_len);
+        data
+    }
+
+    /// Remove ever smaller chunks of a failing input while it keeps failing
+    /// with the same kind of error
+    fn minimize(&self, input: Vec<u8>, error: RuntimeError) -> (Vec<u8>, RuntimeError) {
+        let kind = discriminant(&error.kind);
+        let mut best = (input, error);
+        let mut chunk = best.0.len() / 2;
+        while chunk > 0 {
+            let mut start = 0;
+            while start < best.0.len() {
+                let end = (start + chunk).min(best.0.len());
+                let mut candidate = best.0[..start].to_vec();
+                candidate.extend_from_slice(&best.0[end..]);
+                match self.execute(&candidate) {
+                    Err(error) if discriminant(&error.kind) == kind => best = (candidate, error),
+                    _ => start += chunk,
+                }
+            }
+            chunk /= 2;
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fuzzer(source: &str, target: &str, options: FuzzOptions) -> Result<Fuzzer, FuzzError> {
+        let module = Parser::parse_module(source).unwrap();
+        Fuzzer::new(&module, target, "fuzz.strat", options)
+    }
+
+    #[test]
+    fn test_finds_and_minimizes_crash() {
+        let source = r#"
+            fx check(s: String) {
+                if s.len() >= 4 {
+                    assert(false)
+                }
+            }
+        "#;
+        let options = FuzzOptions {
+            runs: 2_000,
+            seed: 7,
+            ..FuzzOptions::default()
+        };
+        let mut fuzzer = fuzzer(source, "check", options).unwrap();
+        fuzzer.add_seed(b"hello world".to_vec());
+
+        let report = fuzzer.run();
+        let crash = report.crash.expect("the seed fails");
+        assert_eq!(crash.input, b"hello world");
+        assert_eq!(crash.minimized.len(), 4);
+        assert!(crash.error.contains("ssertion"), "{}", crash.error);
+    }
+
+    #[test]
+    fn test_new_coverage_grows_corpus() {
+        let source = r#"
+            fx classify(bytes: List<Int>) -> Int {
+                if bytes.len() == 0 {
+                    return 0
+                }
+                if bytes[0] > 127 {
+                    return 2
+                }
+                1
+            }
+        "#;
+        let options = FuzzOptions {
+            runs: 500,
+            seed: 1,
+            ..FuzzOptions::default()
+        };
+        let mut fuzzer = fuzzer(source, "classify", options).unwrap();
+        assert_eq!(fuzzer.input_kind(), InputKind::Bytes);
+
+        let report = fuzzer.run();
+        assert!(report.crash.is_none());
+        assert_eq!(report.runs, 501);
+        // Non-empty inputs below and above 128 each reached a new branch
+        assert_eq!(report.new_inputs.len(), 2, "{:?}", report.new_inputs);
+    }
+
+    #[test]
+    fn test_target_must_take_one_parameter() {
+        let source = "fx add(a: Int, b: Int) -> Int { a + b }";
+        assert!(matches!(
+            fuzzer(source, "add", FuzzOptions::default()),
+            Err(FuzzError::TargetArity { arity: 2, .. })
+        ));
+        assert!(matches!(
+            fuzzer(source, "missing", FuzzOptions::default()),
+            Err(FuzzError::UnknownTarget(_))
+        ));
+    }
+}
@@ -13,9 +13,11 @@
 use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 use std::rc::{Rc, Weak};
+use std::time::{Duration, Instant};
 
 use crate::bytecode::{
-    Closure, CoroutineState, FutureState, HashableValue, StructInstance, Upvalue, Value,
+    Closure, CoroutineState, CowCell, FutureState, HashableValue, StructInstance, Upvalue, Value,
+    ValueMap,
 };
 
 /// Default allocation threshold before triggering collection
@@ -28,9 +30,9 @@ const MIN_THRESHOLD: usize = 100;
 #[derive(Clone)]
 pub enum TrackedContainer {
     /// A list value
-    List(Weak<RefCell<Vec<Value>>>),
+    List(Weak<CowCell<Vec<Value>>>),
     /// A map value
-    Map(Weak<RefCell<HashMap<HashableValue, Value>>>),
+    Map(Weak<CowCell<ValueMap>>),
     /// A struct instance
     Struct(Weak<RefCell<StructInstance>>),
     /// A closure with upvalues
@@ -143,6 +145,22 @@ pub struct GcStats {
     pub allocation_count: usize,
     /// Current collection threshold
     pub threshold: usize,
+    /// Total time spent in collections
+    pub total_pause: Duration,
+    /// Longest single collection
+    pub max_pause: Duration,
+}
+
+impl GcStats {
+    /// Average time spent in a collection
+    #[must_use]
+    pub fn average_pause(&self) -> Duration {
+        if self.collections == 0 {
+            Duration::ZERO
+        } else {
+            self.total_pause / self.collections as u32
+        }
+    }
 }
 
 /// The cycle collector for Stratum's memory management
@@ -158,6 +176,8 @@ pub struct CycleCollector {
     /// Statistics
     collections: usize,
     cycles_broken: usize,
+    total_pause: Duration,
+    max_pause: Duration,
 }
 
 impl Default for CycleCollector {
@@ -177,6 +197,8 @@ impl CycleCollector {
             auto_collect: true,
             collections: 0,
             cycles_broken: 0,
+            total_pause: Duration::ZERO,
+            max_pause: Duration::ZERO,
         }
     }
 
@@ -220,6 +242,8 @@ impl CycleCollector {
             tracked_objects: self.tracked.len(),
             allocation_count: self.allocation_count,
             threshold: self.threshold,
+            total_pause: self.total_pause,
+            max_pause: self.max_pause,
         }
     }
 
@@ -304,6 +328,14 @@ impl CycleCollector {
         globals: &HashMap<String, Value>,
         open_upvalues: &[Rc<RefCell<Upvalue>>],
     ) -> usize {
+        let start = Instant::now();
+        let span = tracing::debug_span!(
+            "gc_collect",
+            tracked = self.tracked.len(),
+            cycles_broken = tracing::field::Empty
+        );
+        let _entered = span.enter();
+
         // Step 1: Clean up dead weak references
         self.tracked.retain(|_, container| container.is_alive());
 
@@ -353,6 +385,10 @@ impl CycleCollector {
         self.allocation_count = 0;
         self.collections += 1;
         self.cycles_broken += broken;
+        let pause = start.elapsed();
+        self.total_pause += pause;
+        self.max_pause = self.max_pause.max(pause);
+        span.record("cycles_broken", broken);
 
         broken
     }
@@ -526,7 +562,12 @@ impl CycleCollector {
             | Value::GuiElement(_)
             | Value::StateBinding(_)
             | Value::XmlDocument(_)
-            | Value::Image(_) => {}
+            | Value::Image(_)
+            | Value::ChildProcess(_)
+            | Value::StructType(_)
+            | Value::EnumType(_)
+            | Value::CsvReader(_)
+            | Value::CsvWriter(_) => {}
             // Weak references are intentionally NOT followed during marking.
             // This is the key behavior that allows them to break cycles -
             // the referenced object can be collected even if a weak ref exists.
@@ -538,6 +579,19 @@ impl CycleCollector {
                     self.mark(&rc.borrow().actual, reachable);
                 }
             }
+            Value::Mock(rc) => {
+                let ptr = Rc::as_ptr(rc) as usize;
+                if reachable.insert(ptr) {
+                    let mock = rc.borrow();
+                    self.mark(&mock.replacement, reachable);
+                    if let Some(original) = &mock.original {
+                        self.mark(original, reachable);
+                    }
+                    for arg in mock.calls.iter().flatten() {
+                        self.mark(arg, reachable);
+                    }
+                }
+            }
         }
     }
 
@@ -705,7 +759,7 @@ mod tests {
         let mut gc = CycleCollector::with_threshold(MIN_THRESHOLD);
 
         // Create a self-referencing list (a cycle)
-        let list: Rc<RefCell<Vec<Value>>> = Rc::new(RefCell::new(vec![]));
+        let list: Rc<CowCell<Vec<Value>>> = Rc::new(CowCell::new(vec![]));
         let list_value = Value::List(Rc::clone(&list));
 
         // Add reference to itself
@@ -764,6 +818,8 @@ mod tests {
 
         let stats = gc.stats();
         assert_eq!(stats.collections, 2);
+        assert!(stats.max_pause <= stats.total_pause);
+        assert!(stats.average_pause() <= stats.max_pause);
     }
 
     #[test]
@@ -783,10 +839,8 @@ mod tests {
         let mut gc = CycleCollector::with_threshold(MIN_THRESHOLD);
 
         // Create two maps that reference each other
-        let map1: Rc<RefCell<HashMap<HashableValue, Value>>> =
-            Rc::new(RefCell::new(HashMap::new()));
-        let map2: Rc<RefCell<HashMap<HashableValue, Value>>> =
-            Rc::new(RefCell::new(HashMap::new()));
+        let map1: Rc<CowCell<ValueMap>> = Rc::new(CowCell::new(ValueMap::new()));
+        let map2: Rc<CowCell<ValueMap>> = Rc::new(CowCell::new(ValueMap::new()));
 
         let map1_value = Value::Map(Rc::clone(&map1));
         let map2_value = Value::Map(Rc::clone(&map2));
@@ -892,7 +946,7 @@ mod tests {
         let mut gc = CycleCollector::with_threshold(MIN_THRESHOLD);
 
         // Create a struct that would form a cycle using a weak ref
-        let outer: Rc<RefCell<Vec<Value>>> = Rc::new(RefCell::new(vec![]));
+        let outer: Rc<CowCell<Vec<Value>>> = Rc::new(CowCell::new(vec![]));
         let outer_value = Value::List(Rc::clone(&outer));
 
         // Create a weak reference instead of a strong one
@@ -504,6 +504,11 @@ impl<'a, 'b> FunctionCompiler<'a, 'b> {
                     let target = ((ip as isize + 3) + offset as isize) as usize;
                     self.get_block(target);
                 }
+                OpCode::CompareJumpIfFalse => {
+                    let offset = self.read_i16(ip + 2);
+                    let target = ((ip as isize + 4) + offset as isize) as usize;
+                    self.get_block(target);
+                }
                 _ => {}
             }
 
@@ -566,17 +571,25 @@ impl<'a, 'b> FunctionCompiler<'a, 'b> {
             OpCode::LoadLocal => {
                 let slot = self.read_u16(start_ip + 1) as usize;
                 self.ip += 2;
+                self.compile_load_local(slot);
+            }
 
-                // Ensure we have enough locals
-                while self.locals.len() <= slot {
-                    let vars = self.alloc_value_vars();
-                    self.locals.push(vars);
-                }
+            OpCode::LoadLocalPair => {
+                let first = self.read_u16(start_ip + 1) as usize;
+                let second = self.read_u16(start_ip + 3) as usize;
+                self.ip += 4;
+                self.compile_load_local(first);
+                self.compile_load_local(second);
+            }
 
-                let (tag_var, data_var) = self.locals[slot];
-                let tag = self.builder.use_var(tag_var);
-                let data = self.builder.use_var(data_var);
-                self.push(tag, data);
+            OpCode::AddLocalConst => {
+                let slot = self.read_u16(start_ip + 1) as usize;
+                let index = self.read_u16(start_ip + 3);
+                self.ip += 4;
+                self.compile_load_local(slot);
+                let constant = &self.chunk.constants()[index as usize];
+                self.compile_constant(constant)?;
+                self.compile_binary_op(BinaryOp::Add)?;
             }
 
             OpCode::StoreLocal => {
@@ -625,29 +638,31 @@ impl<'a, 'b> FunctionCompiler<'a, 'b> {
                 let offset = self.read_i16(start_ip + 1);
                 self.ip += 2;
                 let target = ((start_ip as isize + 3) + offset as isize) as usize;
+                self.compile_jump_if_false(target);
+            }
 
-                let (tag, data) = self.pop();
-                let target_block = self.get_block(target);
-                let fallthrough = self.builder.create_block();
-
-                // Check if value is falsy (null or false)
-                let is_null = self
-                    .builder
-                    .ins()
-                    .icmp_imm(IntCC::Equal, tag, ValueTag::Null as i64);
-                let is_bool = self
-                    .builder
-                    .ins()
-                    .icmp_imm(IntCC::Equal, tag, ValueTag::Bool as i64);
-                let is_false = self.builder.ins().icmp_imm(IntCC::Equal, data, 0);
-                let bool_and_false = self.builder.ins().band(is_bool, is_false);
-                let is_falsy = self.builder.ins().bor(is_null, bool_and_false);
-
-                self.builder
-                    .ins()
-                    .brif(is_falsy, target_block, &[], fallthrough, &[]);
-                self.builder.switch_to_block(fallthrough);
-                self.builder.seal_block(fallthrough);
+            OpCode::CompareJumpIfFalse => {
+                let comparison = self.read_u8(start_ip + 1);
+                let offset = self.read_i16(start_ip + 2);
+                self.ip += 3;
+                let target = ((start_ip as isize + 4) + offset as isize) as usize;
+
+                let cc = match OpCode::try_from(comparison) {
+                    Ok(OpCode::Eq) => IntCC::Equal,
+                    Ok(OpCode::Ne) => IntCC::NotEqual,
+                    Ok(OpCode::Lt) => IntCC::SignedLessThan,
+                    Ok(OpCode::Le) => IntCC::SignedLessThanOrEqual,
+                    Ok(OpCode::Gt) => IntCC::SignedGreaterThan,
+                    Ok(OpCode::Ge) => IntCC::SignedGreaterThanOrEqual,
+                    _ => {
+                        return Err(JitError::UnsupportedInstruction(format!(
+                            "{:?} with comparison {}",
+                            op, comparison
+                        )));
+                    }
+                };
+                self.compile_comparison(cc)?;
+                self.compile_jump_if_false(target);
             }
 
             OpCode::JumpIfTrue => {
@@ -794,6 +809,20 @@ impl<'a, 'b> FunctionCompiler<'a, 'b> {
         Ok(())
     }
 
+    /// Push a local variable
+    fn compile_load_local(&mut self, slot: usize) {
+        // Ensure we have enough locals
+        while self.locals.len() <= slot {
+            let vars = self.alloc_value_vars();
+            self.locals.push(vars);
+        }
+
+        let (tag_var, data_var) = self.locals[slot];
+        let tag = self.builder.use_var(tag_var);
+        let data = self.builder.use_var(data_var);
+        self.push(tag, data);
+    }
+
     /// Compile a constant value
     fn compile_constant(&mut self, value: &Value) -> JitResult<()> {
         match value {
@@ -1004,6 +1033,32 @@ impl<'a, 'b> FunctionCompiler<'a, 'b> {
         Ok(())
     }
 
+    /// Pop a value and branch to the block at `target` if it is falsy
+    fn compile_jump_if_false(&mut self, target: usize) {
+        let (tag, data) = self.pop();
+        let target_block = self.get_block(target);
+        let fallthrough = self.builder.create_block();
+
+        // Check if value is falsy (null or false)
+        let is_null = self
+            .builder
+            .ins()
+            .icmp_imm(IntCC::Equal, tag, ValueTag::Null as i64);
+        let is_bool = self
+            .builder
+            .ins()
+            .icmp_imm(IntCC::Equal, tag, ValueTag::Bool as i64);
+        let is_false = self.builder.ins().icmp_imm(IntCC::Equal, data, 0);
+        let bool_and_false = self.builder.ins().band(is_bool, is_false);
+        let is_falsy = self.builder.ins().bor(is_null, bool_and_false);
+
+        self.builder
+            .ins()
+            .brif(is_falsy, target_block, &[], fallthrough, &[]);
+        self.builder.switch_to_block(fallthrough);
+        self.builder.seal_block(fallthrough);
+    }
+
     /// Compile a comparison operation
     fn compile_comparison(&mut self, cc: IntCC) -> JitResult<()> {
         let (_right_tag, right_data) = self.pop();
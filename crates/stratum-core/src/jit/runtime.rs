@@ -7,11 +7,10 @@
 //! - Type checking
 //! - Error handling
 
-use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
 
-use crate::bytecode::Value;
+use crate::bytecode::{CowCell, Value};
 
 use super::types::ValueTag;
 
@@ -197,13 +196,13 @@ pub unsafe fn packed_to_value(packed: PackedValue) -> Value {
             Value::String(Rc::from_raw(ptr))
         }
         t if t == ValueTag::List as u8 => {
-            let ptr = packed.data as *const RefCell<Vec<Value>>;
+            let ptr = packed.data as *const CowCell<Vec<Value>>;
             Rc::increment_strong_count(ptr);
             Value::List(Rc::from_raw(ptr))
         }
         t if t == ValueTag::Map as u8 => {
-            use crate::bytecode::HashableValue;
-            let ptr = packed.data as *const RefCell<HashMap<HashableValue, Value>>;
+            use crate::bytecode::ValueMap;
+            let ptr = packed.data as *const CowCell<ValueMap>;
             Rc::increment_strong_count(ptr);
             Value::Map(Rc::from_raw(ptr))
         }
@@ -253,7 +252,7 @@ pub unsafe extern "C" fn stratum_rc_dec(ptr: *const (), tag: u8) {
             Rc::decrement_strong_count(ptr);
         }
         t if t == ValueTag::List as u8 => {
-            let ptr = ptr as *const RefCell<Vec<Value>>;
+            let ptr = ptr as *const CowCell<Vec<Value>>;
             Rc::decrement_strong_count(ptr);
         }
         _ => {
@@ -293,17 +292,17 @@ pub unsafe extern "C" fn stratum_concat_strings(
 
 /// Create a new empty list
 #[no_mangle]
-pub extern "C" fn stratum_new_list() -> *const RefCell<Vec<Value>> {
-    let list = Rc::new(RefCell::new(Vec::new()));
+pub extern "C" fn stratum_new_list() -> *const CowCell<Vec<Value>> {
+    let list = Rc::new(CowCell::new(Vec::new()));
     Rc::into_raw(list)
 }
 
 /// Get the length of a list
 ///
 /// # Safety
-/// ptr must be a valid pointer to a list's RefCell
+/// ptr must be a valid pointer to a list's CowCell
 #[no_mangle]
-pub unsafe extern "C" fn stratum_list_len(ptr: *const RefCell<Vec<Value>>) -> i64 {
+pub unsafe extern "C" fn stratum_list_len(ptr: *const CowCell<Vec<Value>>) -> i64 {
     let list = &*ptr;
     list.borrow().len() as i64
 }
@@ -452,6 +451,11 @@ impl JitContext {
     pub fn is_compiled(&self, name: &str) -> bool {
         self.functions.contains_key(name)
     }
+
+    /// Drop a compiled function, so its next call compiles its current code
+    pub fn invalidate(&mut self, name: &str) {
+        self.functions.remove(name);
+    }
 }
 
 impl Default for JitContext {
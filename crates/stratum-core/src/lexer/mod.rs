@@ -3,8 +3,10 @@
 //! The lexer converts source code into a stream of tokens, handling:
 //! - Keywords, identifiers, and operators
 //! - Numeric literals (int, float, hex, binary, octal)
-//! - String literals with interpolation support
+//! - String literals with interpolation support, raw strings, and
+//!   indentation-trimmed multiline strings
 //! - Comments (line and block)
+//! - A leading `#!` shebang line, which is skipped
 //! - Source location tracking
 
 #![allow(clippy::cast_possible_truncation)] // We intentionally use u32 for spans; files > 4GB are unsupported
@@ -13,7 +15,7 @@ mod span;
 mod token;
 
 pub use span::{LineIndex, Location, Span};
-pub use token::TokenKind;
+pub use token::{raw_string_contents, TokenKind};
 
 use logos::Logos;
 use thiserror::Error;
@@ -98,8 +100,10 @@ enum LexerMode {
     Normal,
     /// Inside a string literal
     String,
-    /// Inside a multiline string literal (triple-quoted)
-    MultiLineString,
+    /// Inside a multiline string literal (triple-quoted). `indent` is the
+    /// common indentation stripped from each line, or `None` when the string
+    /// does not start on its own line and is kept verbatim.
+    MultiLineString { indent: Option<usize> },
     /// Inside an interpolation expression within a string
     Interpolation { depth: u32 },
     /// Inside an interpolation expression within a multiline string
@@ -121,11 +125,19 @@ pub struct Lexer<'source> {
 
 impl<'source> Lexer<'source> {
     /// Create a new lexer for the given source code
+    ///
+    /// A `#!` line at the very start of the source is skipped so scripts can
+    /// be run directly; spans still refer to the original source.
     #[must_use]
     pub fn new(source: &'source str) -> Self {
+        let position = if source.starts_with("#!") && !source.starts_with("#![") {
+            source.find('\n').unwrap_or(source.len())
+        } else {
+            0
+        };
         Self {
             source,
-            position: 0,
+            position,
             mode: LexerMode::Normal,
             mode_stack: Vec::new(),
             errors: Vec::new(),
@@ -158,7 +170,7 @@ impl<'source> Lexer<'source> {
         match self.mode {
             LexerMode::Normal => self.lex_normal(),
             LexerMode::String => self.lex_string(),
-            LexerMode::MultiLineString => self.lex_multiline_string(),
+            LexerMode::MultiLineString { indent } => self.lex_multiline_string(indent),
             LexerMode::Interpolation { .. } => self.lex_interpolation(),
             LexerMode::MultiLineInterpolation { .. } => self.lex_multiline_interpolation(),
         }
@@ -190,7 +202,17 @@ impl<'source> Lexer<'source> {
                 if kind == TokenKind::StringStart {
                     self.mode = LexerMode::String;
                 } else if kind == TokenKind::MultiLineStringStart {
-                    self.mode = LexerMode::MultiLineString;
+                    self.mode = self.enter_multiline_string();
+                } else if kind == TokenKind::RawString && raw_string_contents(lexeme).is_none() {
+                    self.errors.push(SpannedError::new(
+                        LexError::UnterminatedString,
+                        Span::new(start as u32, end as u32),
+                    ));
+                    return Some(Token::new(
+                        TokenKind::Error,
+                        Span::new(start as u32, end as u32),
+                        lexeme,
+                    ));
                 }
 
                 Some(Token::new(
@@ -200,11 +222,12 @@ impl<'source> Lexer<'source> {
                 ))
             }
             Some(Err(())) => {
-                // Error recovery: skip the invalid character
-                let start = self.position;
-                let invalid_char = remaining.chars().next()?;
+                // Error recovery: skip the invalid character. The logos span
+                // accounts for any whitespace skipped before it.
+                let start = self.position + logos_lexer.span().start;
+                let invalid_char = self.source[start..].chars().next()?;
                 let char_len = invalid_char.len_utf8();
-                self.position += char_len;
+                self.position = start + char_len;
 
                 self.errors.push(SpannedError::new(
                     LexError::UnexpectedChar,
@@ -445,11 +468,11 @@ impl<'source> Lexer<'source> {
                 ))
             }
             Some(Err(())) => {
-                // Error recovery
-                let start = self.position;
-                let invalid_char = remaining.chars().next()?;
+                // Error recovery (skipping any leading whitespace, as above)
+                let start = self.position + logos_lexer.span().start;
+                let invalid_char = self.source[start..].chars().next()?;
                 let char_len = invalid_char.len_utf8();
-                self.position += char_len;
+                self.position = start + char_len;
 
                 self.errors.push(SpannedError::new(
                     LexError::UnexpectedChar,
@@ -473,7 +496,7 @@ impl<'source> Lexer<'source> {
     /// Lex inside a multiline string literal (triple-quoted)
     #[allow(clippy::too_many_lines)]
     #[allow(clippy::unnecessary_wraps)]
-    fn lex_multiline_string(&mut self) -> Option<Token> {
+    fn lex_multiline_string(&mut self, indent: Option<usize>) -> Option<Token> {
         let start = self.position;
         let mut content = String::new();
         let chars: Vec<char> = self.source[self.position..].chars().collect();
@@ -485,7 +508,11 @@ impl<'source> Lexer<'source> {
                 '"' => {
                     // Check for end of multiline string (""")
                     if i + 2 < chars.len() && chars[i + 1] == '"' && chars[i + 2] == '"' {
-                        // End of multiline string
+                        // End of multiline string. A closing delimiter on its
+                        // own line doesn't contribute its line break.
+                        if let Some(indent) = indent {
+                            self.trim_closing_line(&mut content, start, indent);
+                        }
                         if !content.is_empty() {
                             // First emit the string part
                             let token = Token::new(
@@ -523,7 +550,7 @@ impl<'source> Lexer<'source> {
                     // Emit interpolation start
                     let interp_start = self.position;
                     self.position += 1;
-                    self.mode_stack.push(LexerMode::MultiLineString);
+                    self.mode_stack.push(LexerMode::MultiLineString { indent });
                     self.mode = LexerMode::MultiLineInterpolation { depth: 1 };
                     return Some(Token::new(
                         TokenKind::InterpolationStart,
@@ -576,6 +603,16 @@ impl<'source> Lexer<'source> {
                     content.push(c);
                     self.position += 1;
                     i += 1;
+                    // Strip the common indentation from the next line
+                    let mut skipped = 0;
+                    while skipped < indent.unwrap_or(0)
+                        && i < chars.len()
+                        && matches!(chars[i], ' ' | '\t')
+                    {
+                        self.position += 1;
+                        i += 1;
+                        skipped += 1;
+                    }
                 }
                 _ => {
                     content.push(c);
@@ -606,6 +643,47 @@ impl<'source> Lexer<'source> {
         }
     }
 
+    /// Enter multiline string mode after an opening `"""`
+    ///
+    /// When the opening delimiter ends its line, that line break is dropped
+    /// and the common indentation of the body is stripped from every line,
+    /// so a multiline string can be indented along with the surrounding code.
+    fn enter_multiline_string(&mut self) -> LexerMode {
+        let rest = &self.source[self.position..];
+        let Some(body) = rest
+            .strip_prefix('\n')
+            .or_else(|| rest.strip_prefix("\r\n"))
+        else {
+            return LexerMode::MultiLineString { indent: None };
+        };
+
+        let indent = multiline_indent(body);
+        let first_line = body.len() - body.trim_start_matches([' ', '\t']).len();
+        self.position = self.source.len() - body.len() + first_line.min(indent);
+        LexerMode::MultiLineString {
+            indent: Some(indent),
+        }
+    }
+
+    /// Drop the trailing line break and indentation when the closing `"""`
+    /// of an indentation-trimmed string sits on its own line
+    fn trim_closing_line(&self, content: &mut String, part_start: usize, indent: usize) {
+        let before = &self.source[..self.position];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        if line_start < part_start || !before[line_start..].trim_matches([' ', '\t']).is_empty() {
+            return;
+        }
+        // Whitespace past the stripped indentation ended up in the content
+        let kept = (self.position - line_start).saturating_sub(indent);
+        content.truncate(content.len().saturating_sub(kept));
+        if line_start > part_start && content.ends_with('\n') {
+            content.pop();
+            if content.ends_with('\r') {
+                content.pop();
+            }
+        }
+    }
+
     /// Lex inside an interpolation expression within a multiline string
     fn lex_multiline_interpolation(&mut self) -> Option<Token> {
         if self.position >= self.source.len() {
@@ -673,7 +751,7 @@ impl<'source> Lexer<'source> {
                         TokenKind::MultiLineStringStart => {
                             // Nested multiline string in interpolation
                             self.mode_stack.push(self.mode);
-                            self.mode = LexerMode::MultiLineString;
+                            self.mode = self.enter_multiline_string();
                         }
                         _ => {}
                     }
@@ -686,11 +764,11 @@ impl<'source> Lexer<'source> {
                 ))
             }
             Some(Err(())) => {
-                // Error recovery
-                let start = self.position;
-                let invalid_char = remaining.chars().next()?;
+                // Error recovery (skipping any leading whitespace, as above)
+                let start = self.position + logos_lexer.span().start;
+                let invalid_char = self.source[start..].chars().next()?;
                 let char_len = invalid_char.len_utf8();
-                self.position += char_len;
+                self.position = start + char_len;
 
                 self.errors.push(SpannedError::new(
                     LexError::UnexpectedChar,
@@ -724,6 +802,38 @@ impl<'source> Lexer<'source> {
     }
 }
 
+/// Compute the indentation shared by the lines of a multiline string body
+///
+/// Blank lines are ignored, but the line holding the closing `"""` always
+/// counts, so its indentation caps how much is stripped.
+fn multiline_indent(body: &str) -> usize {
+    let mut end = body.len();
+    let mut chars = body.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '"' if body[i..].starts_with("\"\"\"") => {
+                end = i;
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    let mut lines = body[..end].split('\n').peekable();
+    let mut indent = usize::MAX;
+    while let Some(line) = lines.next() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        let text = line.trim_start_matches([' ', '\t']);
+        if !text.is_empty() || lines.peek().is_none() {
+            indent = indent.min(line.len() - text.len());
+        }
+    }
+    indent
+}
+
 impl Iterator for Lexer<'_> {
     type Item = Token;
 
@@ -890,6 +1000,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn lex_shebang() {
+        let tokens = lex("#!/usr/bin/env stratum\nfoo");
+        let kinds: Vec<_> = tokens.iter().map(|t| t.kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            vec![TokenKind::Newline, TokenKind::Ident, TokenKind::Eof]
+        );
+        assert_eq!(tokens[1].span.start, 23);
+
+        assert_eq!(lex_kinds("#!"), vec![TokenKind::Eof]);
+        assert_eq!(lex_kinds("foo\n#!bar")[2], TokenKind::Hash);
+    }
+
     #[test]
     fn lex_simple_string() {
         let tokens = lex(r#""hello""#);
@@ -959,6 +1083,15 @@ mod tests {
         assert_eq!(tokens[3].span, Span::new(8, 10)); // "42"
     }
 
+    #[test]
+    fn error_span_skips_leading_whitespace() {
+        let (tokens, errors) = Lexer::tokenize("let a = 1 @ 2");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].span, Span::new(10, 11));
+        let error_token = tokens.iter().find(|t| t.kind == TokenKind::Error).unwrap();
+        assert_eq!(error_token.lexeme, "@");
+    }
+
     #[test]
     fn error_recovery_continues() {
         let (tokens, errors) = Lexer::tokenize("let @ x = 5");
@@ -1015,12 +1148,13 @@ mod tests {
 
     #[test]
     fn lex_all_keywords() {
-        let source = "fx let if else for while match return import struct enum interface impl async await try catch break continue in true false null";
+        let source = "fx let const if else for while match return import struct enum interface impl async await try catch break continue in true false null";
         let tokens = lex(source);
         let kinds: Vec<_> = tokens.iter().map(|t| t.kind.clone()).collect();
 
         assert!(kinds.contains(&TokenKind::Fx));
         assert!(kinds.contains(&TokenKind::Let));
+        assert!(kinds.contains(&TokenKind::Const));
         assert!(kinds.contains(&TokenKind::If));
         assert!(kinds.contains(&TokenKind::Else));
         assert!(kinds.contains(&TokenKind::For));
@@ -1139,4 +1273,93 @@ world""""#);
         assert_eq!(tokens[0].kind, TokenKind::MultiLineStringStart);
         assert_eq!(tokens[1].kind, TokenKind::MultiLineStringEnd);
     }
+
+    /// Concatenate the string parts of a lexed string
+    fn string_value(source: &str) -> String {
+        lex(source)
+            .into_iter()
+            .filter(|t| t.kind == TokenKind::StringPart)
+            .map(|t| t.lexeme)
+            .collect()
+    }
+
+    #[test]
+    fn lex_multiline_string_trims_indentation() {
+        let source = "    let sql = \"\"\"\n        SELECT *\n          FROM t\n\n        WHERE x = 1\n        \"\"\"";
+        assert_eq!(string_value(source), "SELECT *\n  FROM t\n\nWHERE x = 1");
+    }
+
+    #[test]
+    fn lex_multiline_string_closing_line_caps_indentation() {
+        let source = "\"\"\"\n        a\n          b\n    \"\"\"";
+        assert_eq!(string_value(source), "    a\n      b");
+    }
+
+    #[test]
+    fn lex_multiline_string_keeps_trailing_text_line() {
+        let source = "\"\"\"\n    one\n    two\"\"\"";
+        assert_eq!(string_value(source), "one\ntwo");
+        let source = "\"\"\"\r\n    one\r\n    \"\"\"";
+        assert_eq!(string_value(source), "one");
+    }
+
+    #[test]
+    fn lex_multiline_string_trims_around_interpolation() {
+        let source = "\"\"\"\n    Hello {name}\n      bye\n    \"\"\"";
+        let tokens = lex(source);
+        assert_eq!(tokens[1].kind, TokenKind::StringPart);
+        assert_eq!(tokens[1].lexeme, "Hello ");
+        assert_eq!(tokens[3].lexeme, "name");
+        assert_eq!(tokens[5].kind, TokenKind::StringPart);
+        assert_eq!(tokens[5].lexeme, "\n  bye");
+        assert_eq!(tokens[6].kind, TokenKind::MultiLineStringEnd);
+    }
+
+    #[test]
+    fn lex_multiline_string_escapes_are_not_indentation() {
+        let source = "\"\"\"\n    \\tindented\n    \"\"\"";
+        assert_eq!(string_value(source), "\tindented");
+    }
+
+    #[test]
+    fn lex_raw_strings() {
+        let tokens = lex(r##"r"\d+\.\d*" r#"say "hi" {name}"#"##);
+        assert_eq!(tokens[0].kind, TokenKind::RawString);
+        assert_eq!(raw_string_contents(&tokens[0].lexeme), Some(r"\d+\.\d*"));
+        assert_eq!(tokens[1].kind, TokenKind::RawString);
+        assert_eq!(
+            raw_string_contents(&tokens[1].lexeme),
+            Some(r#"say "hi" {name}"#)
+        );
+        assert_eq!(tokens[2].kind, TokenKind::Eof);
+    }
+
+    #[test]
+    fn lex_raw_string_spans_lines() {
+        let tokens = lex("r\"a\nb\" x");
+        assert_eq!(tokens[0].kind, TokenKind::RawString);
+        assert_eq!(raw_string_contents(&tokens[0].lexeme), Some("a\nb"));
+        assert_eq!(tokens[1].kind, TokenKind::Ident);
+    }
+
+    #[test]
+    fn lex_r_identifier_is_not_raw_string() {
+        assert_eq!(
+            lex_kinds("r + rx"),
+            vec![
+                TokenKind::Ident,
+                TokenKind::Plus,
+                TokenKind::Ident,
+                TokenKind::Eof
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_unterminated_raw_string() {
+        let (tokens, errors) = Lexer::tokenize(r#"r#"never "closed"#);
+        assert_eq!(tokens[0].kind, TokenKind::Error);
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0].error, LexError::UnterminatedString));
+    }
 }
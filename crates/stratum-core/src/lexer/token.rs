@@ -11,6 +11,8 @@ pub enum TokenKind {
     Fx,
     #[token("let")]
     Let,
+    #[token("const")]
+    Const,
     #[token("if")]
     If,
     #[token("else")]
@@ -96,6 +98,11 @@ pub enum TokenKind {
     #[token("\"")]
     StringStart,
 
+    /// Raw string: r"..." or r#"..."# (no escapes, no interpolation)
+    /// The lexeme is the full source text including delimiters
+    #[regex(r#"r#*""#, lex_raw_string)]
+    RawString,
+
     /// Part of a string literal (between interpolations)
     /// Not matched by logos - produced by the lexer's string mode
     StringPart,
@@ -237,6 +244,7 @@ impl TokenKind {
             self,
             Self::Fx
                 | Self::Let
+                | Self::Const
                 | Self::If
                 | Self::Else
                 | Self::For
@@ -274,6 +282,7 @@ impl TokenKind {
                 | Self::True
                 | Self::False
                 | Self::Null
+                | Self::RawString
         )
     }
 
@@ -315,12 +324,44 @@ impl TokenKind {
     }
 }
 
+/// Consume the body of a raw string after its opening `r#*"`
+///
+/// The string ends at the first `"` followed by as many `#` as the opening
+/// delimiter. An unterminated raw string consumes the rest of the input; the
+/// lexer reports it as an error.
+fn lex_raw_string(lex: &mut logos::Lexer<TokenKind>) {
+    let hashes = lex.slice().len() - 2;
+    let rest = lex.remainder();
+    let mut offset = 0;
+    while let Some(quote) = rest[offset..].find('"') {
+        let after = offset + quote + 1;
+        if rest[after..].bytes().take_while(|&b| b == b'#').count() >= hashes {
+            lex.bump(after + hashes);
+            return;
+        }
+        offset = after;
+    }
+    lex.bump(rest.len());
+}
+
+/// Returns the contents of a raw string lexeme, or `None` if it is unterminated
+#[must_use]
+pub fn raw_string_contents(lexeme: &str) -> Option<&str> {
+    let body = lexeme.strip_prefix('r')?;
+    let hashes = body.len() - body.trim_start_matches('#').len();
+    let closing = format!("\"{}", "#".repeat(hashes));
+    body[hashes..]
+        .strip_prefix('"')?
+        .strip_suffix(closing.as_str())
+}
+
 impl std::fmt::Display for TokenKind {
     #[allow(clippy::match_same_arms)] // Each token type is intentionally separate for clarity
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Fx => write!(f, "fx"),
             Self::Let => write!(f, "let"),
+            Self::Const => write!(f, "const"),
             Self::If => write!(f, "if"),
             Self::Else => write!(f, "else"),
             Self::For => write!(f, "for"),
@@ -352,6 +393,7 @@ impl std::fmt::Display for TokenKind {
             Self::MultiLineStringEnd => write!(f, "\"\"\""),
             Self::StringStart => write!(f, "\""),
             Self::StringPart => write!(f, "string"),
+            Self::RawString => write!(f, "raw string"),
             Self::StringEnd => write!(f, "\""),
             Self::InterpolationStart => write!(f, "{{"),
             Self::InterpolationEnd => write!(f, "}}"),
@@ -8,6 +8,8 @@
 //! - Bytecode: Instruction set and compiler
 //! - VM: Bytecode execution
 //! - Formatter: Source code formatting
+//! - Lint: Static analysis with configurable rules
+//! - Error codes: Stable codes and explanations for every diagnostic
 
 /// Library version
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -33,9 +35,15 @@ pub mod vm;
 /// Testing framework - test discovery and execution
 pub mod testing;
 
+/// Error codes - stable codes and extended explanations for diagnostics
+pub mod error_codes;
+
 /// Source code formatter
 pub mod formatter;
 
+/// Static analysis - configurable lint rules and safe autofixes
+pub mod lint;
+
 /// JIT compilation module (Cranelift-based)
 /// JIT requires unsafe code for memory management and function pointers
 #[allow(unsafe_code, clippy::missing_safety_doc)]
@@ -58,6 +66,12 @@ pub mod gc;
 /// Code coverage module - line and branch coverage tracking
 pub mod coverage;
 
+/// CPU profiling - call stack sampling and flamegraph output
+pub mod profile;
+
+/// Fuzzing - coverage-guided fuzzing of functions
+pub mod fuzz;
+
 /// Test utilities - helpers for testing Stratum code
 pub mod testutil;
 
@@ -77,19 +91,30 @@ pub use bytecode::Compiler;
 pub use vm::VM;
 
 /// Convenience re-export of namespace handler types for external registration
-pub use vm::{NamespaceHandler, ValueMethodHandler, VmMethodHandler};
+pub use vm::{
+    NamespaceAccess, NamespaceHandler, ValueMethodAccess, ValueMethodHandler, VmMethodHandler,
+};
 
 /// Convenience re-export of output capture utilities
 pub use vm::{with_output_capture, OutputCapture};
 
+/// Convenience re-export of the `Args` namespace's program arguments
+pub use vm::set_program_args;
+
+/// Convenience re-export of the capability permissions natives are checked against
+pub use vm::{Access, Capability, Grant, Permissions};
+
 /// Convenience re-export of debug types
 pub use vm::{
     DebugAction, DebugContext, DebugLocation, DebugStackFrame, DebugState, DebugStepResult,
-    DebugVariable, PauseReason,
+    DebugVariable, HitCondition, HotReload, PauseReason, TraceEntry,
 };
 
 /// Convenience re-export of formatter
-pub use formatter::Formatter;
+pub use formatter::{FormatConfig, Formatter};
+
+/// Convenience re-export of linter
+pub use lint::Linter;
 
 /// Convenience re-export of JIT compiler
 pub use jit::JitCompiler;
@@ -106,16 +131,21 @@ pub use gc::CycleCollector;
 /// Convenience re-export of memory profiling types and functions
 pub use data::{
     detect_leaks, disable_profiling, enable_profiling, is_profiling_enabled, memory_categories,
-    profiler_summary, record_allocation, record_deallocation, reset_profiler,
-    set_profiler_gc_stats, CategoryStats, LeakInfo, MemoryProfiler, MemoryStats,
+    profiler_html_report, profiler_summary, record_allocation, record_allocation_at,
+    record_deallocation, reset_profiler, set_profiler_gc_stats, AllocationSite, CallSiteStats,
+    CategoryStats, LeakInfo, MemoryProfiler, MemoryReportFormat, MemoryStats,
 };
 
 /// Convenience re-export of coverage types
 pub use coverage::{
-    generate_report, BranchInfo, CoverageCollector, CoverageFormat, CoverageSummary, FileCoverage,
-    FileCoverageSummary, FunctionCoverage,
+    generate_report, BranchInfo, CoverageCollector, CoverageDataError, CoverageFormat,
+    CoveragePoint, CoverageSummary, FileCoverage, FileCoverageSummary, FunctionCoverage,
+    COVERAGE_DATA_VERSION,
 };
 
+/// Convenience re-export of CPU profiling types
+pub use profile::{CpuProfiler, ProfileFormat, ProfileFrame, DEFAULT_SAMPLE_INTERVAL};
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -444,6 +474,43 @@ mod tests {
         assert_eq!(result, bytecode::Value::Int(123));
     }
 
+    #[test]
+    fn test_jit_fused_locals() {
+        use crate::ast::ExecutionMode;
+
+        // (a + b) + 1 with the loads fused as the optimizer emits them
+        let mut chunk = bytecode::Chunk::new();
+        let one = chunk.add_constant(bytecode::Value::Int(1)).unwrap();
+        chunk.write_op_u16(bytecode::OpCode::LoadLocalPair, 0, 1);
+        chunk.write_u16(1, 1);
+        chunk.write_op(bytecode::OpCode::Add, 1);
+        chunk.write_op_u16(bytecode::OpCode::StoreLocal, 2, 1);
+        chunk.write_op(bytecode::OpCode::Pop, 1);
+        chunk.write_op_u16(bytecode::OpCode::AddLocalConst, 2, 1);
+        chunk.write_u16(one, 1);
+        chunk.write_op(bytecode::OpCode::Return, 1);
+
+        let mut function = bytecode::Function::new("add_one".to_string(), 2);
+        function.chunk = chunk;
+        function.execution_mode = ExecutionMode::Compile;
+
+        let mut jit = jit::JitCompiler::new();
+        let func_ptr = jit
+            .compile_function(&function)
+            .expect("JIT compilation should succeed");
+        let compiled = jit::CompiledFunction {
+            ptr: func_ptr,
+            arity: 2,
+            name: "add_one".to_string(),
+        };
+
+        let args = vec![bytecode::Value::Int(40), bytecode::Value::Int(1)];
+        assert_eq!(
+            jit::call_jit_function(&compiled, &args),
+            bytecode::Value::Int(42)
+        );
+    }
+
     #[test]
     fn test_jit_comparison() {
         use crate::ast::ExecutionMode;
@@ -0,0 +1,1129 @@
+//! Scope-aware walk over a module that produces the raw lint findings
+//!
+//! The analyzer reports every finding regardless of configuration; the
+//! [`Linter`](super::Linter) applies the rule levels afterwards.
+
+use std::collections::HashSet;
+
+use super::{LintFix, LintRule, TextEdit};
+use crate::ast::{
+    BinOp, Block, ElseBranch, Expr, ExprKind, Function, Ident, Import, ImportKind, Item, ItemKind,
+    Literal, Module, Param, Pattern, PatternKind, Stmt, StmtKind, StringPart, TopLevelItem,
+    TypeAnnotation, TypeKind,
+};
+use crate::lexer::{LineIndex, Span};
+
+/// A finding before the configured level is applied
+pub(super) struct Finding {
+    pub rule: LintRule,
+    pub message: String,
+    pub span: Span,
+    pub fix: Option<LintFix>,
+}
+
+/// Run every rule over `module`
+pub(super) fn analyze(module: &Module, source: &str) -> Vec<Finding> {
+    let mut analyzer = Analyzer::new(source);
+    analyzer.module(module);
+    analyzer.findings
+}
+
+/// Names bound by the imports of `module` that nothing reads
+pub(super) fn unused_imports(module: &Module, source: &str) -> HashSet<String> {
+    let mut analyzer = Analyzer::new(source);
+    analyzer.module(module);
+    module
+        .items()
+        .into_iter()
+        .filter_map(|item| match &item.kind {
+            ItemKind::Import(import) => Some(import_bindings(import)),
+            _ => None,
+        })
+        .flatten()
+        .filter(|(ident, _)| analyzer.is_unused_import(ident))
+        .map(|(ident, _)| ident.name.clone())
+        .collect()
+}
+
+/// The name each import binds, with the span of its list entry
+fn import_bindings(import: &Import) -> Vec<(&Ident, Span)> {
+    match &import.kind {
+        ImportKind::Item => import
+            .path
+            .last()
+            .map(|ident| (ident, import.span))
+            .into_iter()
+            .collect(),
+        ImportKind::Alias(alias) => vec![(alias, import.span)],
+        ImportKind::List(items) => items
+            .iter()
+            .map(|item| (item.alias.as_ref().unwrap_or(&item.name), item.span))
+            .collect(),
+        ImportKind::Glob => Vec::new(),
+    }
+}
+
+/// How a binding was introduced, which decides how it is reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BindingKind {
+    /// `let`, `for`, `match`, `catch` and lambda bindings; renaming to
+    /// `_name` is safe
+    Local,
+    /// Shorthand field in a struct pattern (`Point { x }`); the name is also
+    /// the field being matched, so it cannot simply be renamed
+    FieldShorthand,
+    /// Function parameter; renaming would break callers using named arguments
+    Param,
+    /// Never reported: `self`, and parameters whose names an interface fixes
+    Silent,
+}
+
+/// A name introduced in a local scope
+#[derive(Debug)]
+struct Binding {
+    name: String,
+    span: Span,
+    kind: BindingKind,
+    read: bool,
+    /// Plain assignments to the binding after its declaration
+    writes: Vec<Span>,
+}
+
+/// A lexical scope of local bindings
+#[derive(Debug, Default)]
+struct Scope {
+    bindings: Vec<Binding>,
+    /// Outermost scope of a function or lambda; shadowing is only checked
+    /// up to here
+    boundary: bool,
+}
+
+struct Analyzer<'a> {
+    source: &'a str,
+    lines: LineIndex,
+    scopes: Vec<Scope>,
+    /// Names that resolved to module level: functions, types, imports
+    globals_read: HashSet<String>,
+    /// The top-level function being walked, so recursion does not count as a use
+    current_function: Option<String>,
+    findings: Vec<Finding>,
+}
+
+impl<'a> Analyzer<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            lines: LineIndex::new(source),
+            scopes: Vec::new(),
+            globals_read: HashSet::new(),
+            current_function: None,
+            findings: Vec::new(),
+        }
+    }
+
+    fn report(&mut self, rule: LintRule, message: String, span: Span, fix: Option<LintFix>) {
+        self.findings.push(Finding {
+            rule,
+            message,
+            span,
+            fix,
+        });
+    }
+
+    /// Source text of a span, or an empty string for synthesized nodes
+    fn text(&self, span: Span) -> &'a str {
+        self.source.get(span.as_range()).unwrap_or_default()
+    }
+
+    // ==================== Module ====================
+
+    fn module(&mut self, module: &Module) {
+        // Top-level statements share one scope, like the body of a script
+        self.push_scope(true);
+        for top in &module.top_level {
+            match top {
+                TopLevelItem::Item(item) => self.item(item),
+                TopLevelItem::Let(decl) => {
+                    if let Some(ty) = &decl.ty {
+                        self.type_annotation(ty);
+                    }
+                    self.expr(&decl.value);
+                    self.pattern_references(&decl.pattern);
+                }
+                TopLevelItem::Statement(stmt) => self.stmt(stmt),
+            }
+        }
+        self.pop_scope();
+
+        self.unused_functions(module);
+        self.unused_imports(module);
+    }
+
+    fn item(&mut self, item: &Item) {
+        match &item.kind {
+            ItemKind::Function(function) => {
+                self.current_function = Some(function.name.name.clone());
+                self.function(function, true);
+                self.current_function = None;
+            }
+            ItemKind::Struct(def) => {
+                for field in &def.fields {
+                    self.type_annotation(&field.ty);
+                }
+            }
+            ItemKind::Enum(def) => {
+                for variant in &def.variants {
+                    match &variant.data {
+                        Some(crate::ast::EnumVariantData::Tuple(types)) => {
+                            for ty in types {
+                                self.type_annotation(ty);
+                            }
+                        }
+                        Some(crate::ast::EnumVariantData::Struct(fields)) => {
+                            for field in fields {
+                                self.type_annotation(&field.ty);
+                            }
+                        }
+                        None => {}
+                    }
+                }
+            }
+            ItemKind::Interface(def) => {
+                for method in &def.methods {
+                    self.signature(&method.params, method.return_type.as_ref());
+                }
+                for method in def.default_methods() {
+                    self.function(&method, false);
+                }
+            }
+            ItemKind::Impl(def) => {
+                self.type_annotation(&def.target);
+                if let Some(interface) = &def.interface {
+                    self.type_annotation(interface);
+                }
+                // An interface decides the parameter names of its methods
+                let report_params = def.interface.is_none();
+                for method in &def.methods {
+                    self.function(method, report_params);
+                }
+            }
+            ItemKind::Import(_) => {}
+        }
+    }
+
+    fn function(&mut self, function: &Function, report_params: bool) {
+        self.signature(&function.params, function.return_type.as_ref());
+
+        self.push_scope(true);
+        for param in &function.params {
+            let kind = if report_params && param.name.name != "self" {
+                BindingKind::Param
+            } else {
+                BindingKind::Silent
+            };
+            self.declare(&param.name, kind);
+        }
+        self.block(&function.body);
+        self.pop_scope();
+    }
+
+    /// Types and default values of a parameter list
+    fn signature(&mut self, params: &[Param], return_type: Option<&TypeAnnotation>) {
+        for param in params {
+            if let Some(ty) = &param.ty {
+                self.type_annotation(ty);
+            }
+            if let Some(default) = &param.default {
+                self.expr(default);
+            }
+        }
+        if let Some(ty) = return_type {
+            self.type_annotation(ty);
+        }
+    }
+
+    fn type_annotation(&mut self, ty: &TypeAnnotation) {
+        match &ty.kind {
+            TypeKind::Named { name, args } => {
+                self.globals_read.insert(name.name.clone());
+                for arg in args {
+                    self.type_annotation(arg);
+                }
+            }
+            TypeKind::Nullable(inner) | TypeKind::List(inner) => self.type_annotation(inner),
+            TypeKind::Function { params, ret } => {
+                for param in params {
+                    self.type_annotation(param);
+                }
+                self.type_annotation(ret);
+            }
+            TypeKind::Tuple(types) => {
+                for ty in types {
+                    self.type_annotation(ty);
+                }
+            }
+            TypeKind::Unit | TypeKind::Never | TypeKind::Inferred => {}
+        }
+    }
+
+    // ==================== Scopes ====================
+
+    fn push_scope(&mut self, boundary: bool) {
+        self.scopes.push(Scope {
+            bindings: Vec::new(),
+            boundary,
+        });
+    }
+
+    /// Close the innermost scope, reporting the bindings it never read
+    fn pop_scope(&mut self) {
+        let Some(scope) = self.scopes.pop() else {
+            return;
+        };
+        for binding in scope.bindings {
+            if binding.read || binding.name.starts_with('_') {
+                continue;
+            }
+            let message = match binding.kind {
+                BindingKind::Silent => continue,
+                BindingKind::Param => format!("unused parameter `{}`", binding.name),
+                _ if binding.writes.is_empty() => format!("unused variable `{}`", binding.name),
+                _ => format!("variable `{}` is assigned but never read", binding.name),
+            };
+            let fix = (binding.kind == BindingKind::Local).then(|| {
+                let renamed = format!("_{}", binding.name);
+                LintFix {
+                    description: format!("rename to `{renamed}`"),
+                    edits: std::iter::once(binding.span)
+                        .chain(binding.writes)
+                        .map(|span| TextEdit {
+                            span,
+                            replacement: renamed.clone(),
+                        })
+                        .collect(),
+                }
+            });
+            self.report(LintRule::UnusedVariables, message, binding.span, fix);
+        }
+    }
+
+    fn declare(&mut self, ident: &Ident, kind: BindingKind) {
+        if ident.name == "_" {
+            return;
+        }
+        if kind != BindingKind::Silent && !ident.name.starts_with('_') {
+            if let Some(previous) = self.visible_in_function(&ident.name) {
+                let line = self.lines.location(previous.start).line;
+                self.report(
+                    LintRule::Shadowing,
+                    format!(
+                        "`{}` shadows the binding declared on line {line}",
+                        ident.name
+                    ),
+                    ident.span,
+                    None,
+                );
+            }
+        }
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.bindings.push(Binding {
+                name: ident.name.clone(),
+                span: ident.span,
+                kind,
+                read: false,
+                writes: Vec::new(),
+            });
+        }
+    }
+
+    /// Span of a binding named `name` in the current function, if any
+    fn visible_in_function(&self, name: &str) -> Option<Span> {
+        for scope in self.scopes.iter().rev() {
+            if let Some(binding) = scope.bindings.iter().rev().find(|b| b.name == name) {
+                return Some(binding.span);
+            }
+            if scope.boundary {
+                break;
+            }
+        }
+        None
+    }
+
+    fn lookup(&mut self, name: &str) -> Option<&mut Binding> {
+        self.scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.bindings.iter_mut().rev().find(|b| b.name == name))
+    }
+
+    fn read(&mut self, name: &str) {
+        if let Some(binding) = self.lookup(name) {
+            binding.read = true;
+        } else if self.current_function.as_deref() != Some(name) {
+            self.globals_read.insert(name.to_string());
+        }
+    }
+
+    fn write(&mut self, ident: &Ident) {
+        if let Some(binding) = self.lookup(&ident.name) {
+            binding.writes.push(ident.span);
+        }
+    }
+
+    // ==================== Patterns ====================
+
+    /// Declare the names bound by a pattern
+    fn declare_pattern(&mut self, pattern: &Pattern, kind: BindingKind, in_match: bool) {
+        match &pattern.kind {
+            PatternKind::Wildcard | PatternKind::Literal(_) => {}
+            // A capitalized name in a match arm reads as a unit variant
+            PatternKind::Ident(ident)
+                if in_match && ident.name.starts_with(|c: char| c.is_uppercase()) =>
+            {
+                self.read(&ident.name);
+            }
+            PatternKind::Ident(ident) => self.declare(ident, kind),
+            PatternKind::Variant {
+                enum_name, data, ..
+            } => {
+                if let Some(name) = enum_name {
+                    self.read(&name.name);
+                }
+                if let Some(data) = data {
+                    self.declare_pattern(data, kind, in_match);
+                }
+            }
+            PatternKind::Struct { name, fields } => {
+                self.read(&name.name);
+                for field in fields {
+                    match &field.pattern {
+                        Some(pattern) => self.declare_pattern(pattern, kind, in_match),
+                        None if kind == BindingKind::Local => {
+                            self.declare(&field.name, BindingKind::FieldShorthand);
+                        }
+                        None => self.declare(&field.name, kind),
+                    }
+                }
+            }
+            PatternKind::List { elements, rest } => {
+                for element in elements {
+                    self.declare_pattern(element, kind, in_match);
+                }
+                if let Some(rest) = rest {
+                    self.declare_pattern(rest, kind, in_match);
+                }
+            }
+            // Every alternative binds the same names
+            PatternKind::Or(alternatives) => {
+                if let Some((first, others)) = alternatives.split_first() {
+                    self.declare_pattern(first, kind, in_match);
+                    for other in others {
+                        self.pattern_references(other);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Record the types a pattern names without binding anything
+    fn pattern_references(&mut self, pattern: &Pattern) {
+        match &pattern.kind {
+            PatternKind::Wildcard | PatternKind::Literal(_) | PatternKind::Ident(_) => {}
+            PatternKind::Variant {
+                enum_name, data, ..
+            } => {
+                if let Some(name) = enum_name {
+                    self.read(&name.name);
+                }
+                if let Some(data) = data {
+                    self.pattern_references(data);
+                }
+            }
+            PatternKind::Struct { name, fields } => {
+                self.read(&name.name);
+                for pattern in fields.iter().filter_map(|field| field.pattern.as_ref()) {
+                    self.pattern_references(pattern);
+                }
+            }
+            PatternKind::List { elements, rest } => {
+                for element in elements.iter().chain(rest.as_deref()) {
+                    self.pattern_references(element);
+                }
+            }
+            PatternKind::Or(alternatives) => {
+                for alternative in alternatives {
+                    self.pattern_references(alternative);
+                }
+            }
+        }
+    }
+
+    // ==================== Statements ====================
+
+    fn block(&mut self, block: &Block) {
+        self.push_scope(false);
+
+        let mut diverged: Option<(&'static str, usize)> = None;
+        for (index, stmt) in block.stmts.iter().enumerate() {
+            self.stmt(stmt);
+            if diverged.is_none() {
+                diverged = stmt_diverges(stmt).map(|keyword| (keyword, index));
+            }
+        }
+        if let Some(expr) = &block.expr {
+            self.expr(expr);
+        }
+
+        if let Some((keyword, index)) = diverged {
+            let unreachable = block.stmts[index + 1..]
+                .iter()
+                .map(stmt_span)
+                .chain(block.expr.as_ref().map(|expr| expr.span))
+                .reduce(Span::merge);
+            if let Some(span) = unreachable {
+                self.report(
+                    LintRule::UnreachableCode,
+                    format!("unreachable code after `{keyword}`"),
+                    span,
+                    None,
+                );
+            }
+        }
+
+        self.pop_scope();
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::Let { pattern, ty, value } => {
+                if let Some(ty) = ty {
+                    self.type_annotation(ty);
+                }
+                self.expr(value);
+                self.declare_pattern(pattern, BindingKind::Local, false);
+            }
+            StmtKind::Expr(expr) | StmtKind::Throw(expr) | StmtKind::Return(Some(expr)) => {
+                self.expr(expr);
+            }
+            StmtKind::Assign { target, value } | StmtKind::CompoundAssign { target, value, .. } => {
+                self.expr(value);
+                match &target.kind {
+                    ExprKind::Ident(ident) => self.write(ident),
+                    _ => self.expr(target),
+                }
+            }
+            StmtKind::For {
+                pattern,
+                iter,
+                body,
+            } => {
+                self.expr(iter);
+                self.push_scope(false);
+                self.declare_pattern(pattern, BindingKind::Local, false);
+                self.block(body);
+                self.pop_scope();
+            }
+            StmtKind::While { cond, body } => {
+                self.expr(cond);
+                self.block(body);
+            }
+            StmtKind::Loop { body } => self.block(body),
+            StmtKind::TryCatch {
+                try_block,
+                catches,
+                finally,
+            } => {
+                self.block(try_block);
+                for catch in catches {
+                    if let Some(ty) = &catch.exception_type {
+                        self.type_annotation(ty);
+                    }
+                    self.push_scope(false);
+                    if let Some(binding) = &catch.binding {
+                        self.declare(binding, BindingKind::Local);
+                    }
+                    self.block(&catch.body);
+                    self.pop_scope();
+                }
+                if let Some(finally) = finally {
+                    self.block(finally);
+                }
+            }
+            StmtKind::Return(None) | StmtKind::Break | StmtKind::Continue => {}
+        }
+    }
+
+    // ==================== Expressions ====================
+
+    fn expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Literal(_) | ExprKind::Placeholder | ExprKind::ColumnShorthand(_) => {}
+            ExprKind::Ident(ident) => self.read(&ident.name),
+            ExprKind::Binary { left, op, right } => {
+                self.comparison(left, *op, right, expr.span);
+                self.expr(left);
+                self.expr(right);
+            }
+            ExprKind::Unary { expr: inner, .. }
+            | ExprKind::Paren(inner)
+            | ExprKind::Await(inner)
+            | ExprKind::Try(inner)
+            | ExprKind::StateBinding(inner)
+            | ExprKind::Field { expr: inner, .. }
+            | ExprKind::NullSafeField { expr: inner, .. } => self.expr(inner),
+            ExprKind::Call {
+                callee,
+                args,
+                trailing_closure,
+            } => {
+                self.expr(callee);
+                for arg in args {
+                    self.expr(arg.value());
+                }
+                if let Some(closure) = trailing_closure {
+                    self.expr(closure);
+                }
+            }
+            ExprKind::Index { expr: inner, index }
+            | ExprKind::NullSafeIndex { expr: inner, index } => {
+                self.expr(inner);
+                self.expr(index);
+            }
+            ExprKind::If {
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                self.expr(cond);
+                self.block(then_branch);
+                match else_branch {
+                    Some(ElseBranch::Block(block)) => self.block(block),
+                    Some(ElseBranch::ElseIf(else_if)) => self.expr(else_if),
+                    None => {}
+                }
+            }
+            ExprKind::Match {
+                expr: scrutinee,
+                arms,
+            } => {
+                self.expr(scrutinee);
+                for arm in arms {
+                    self.push_scope(false);
+                    self.declare_pattern(&arm.pattern, BindingKind::Local, true);
+                    if let Some(guard) = &arm.guard {
+                        self.expr(guard);
+                    }
+                    self.expr(&arm.body);
+                    self.pop_scope();
+                }
+            }
+            ExprKind::Lambda {
+                params,
+                return_type,
+                body,
+            } => {
+                self.signature(params, return_type.as_ref());
+                self.push_scope(true);
+                for param in params {
+                    self.declare(&param.name, BindingKind::Local);
+                }
+                self.expr(body);
+                self.pop_scope();
+            }
+            ExprKind::Block(block) => self.block(block),
+            ExprKind::List(items) => {
+                for item in items {
+                    self.expr(item);
+                }
+            }
+            ExprKind::Map(entries) => {
+                for (key, value) in entries {
+                    self.expr(key);
+                    self.expr(value);
+                }
+            }
+            ExprKind::StringInterp { parts } => {
+                for part in parts {
+                    if let StringPart::Expr(expr) = part {
+                        self.expr(expr);
+                    }
+                }
+            }
+            ExprKind::StructInit { name, fields } => {
+                self.read(&name.name);
+                for field in fields {
+                    match &field.value {
+                        Some(value) => self.expr(value),
+                        None => self.read(&field.name.name),
+                    }
+                }
+            }
+            ExprKind::EnumVariant {
+                enum_name, data, ..
+            } => {
+                if let Some(name) = enum_name {
+                    self.read(&name.name);
+                }
+                if let Some(data) = data {
+                    self.expr(data);
+                }
+            }
+        }
+    }
+
+    /// Check a binary expression for comparisons that are likely mistakes
+    fn comparison(&mut self, left: &Expr, op: BinOp, right: &Expr, span: Span) {
+        if !is_comparison(op) {
+            return;
+        }
+
+        let message = if matches!(&left.kind, ExprKind::Binary { op, .. } if is_comparison(*op)) {
+            format!(
+                "`{}` compares the boolean result of `{}`; combine comparisons with `&&`",
+                self.text(span),
+                self.text(left.span)
+            )
+        } else if is_plain_operand(left) && self.text(left.span) == self.text(right.span) {
+            let always = matches!(op, BinOp::Eq | BinOp::Le | BinOp::Ge);
+            format!(
+                "comparing `{}` with itself is always {always}",
+                self.text(left.span)
+            )
+        } else if let Some(value) = bool_literal(right).or_else(|| bool_literal(left)) {
+            if !matches!(op, BinOp::Eq | BinOp::Ne) {
+                return;
+            }
+            let other = if bool_literal(right).is_some() {
+                left
+            } else {
+                right
+            };
+            let negated = (op == BinOp::Eq) != value;
+            format!(
+                "comparison with `{value}` is redundant; use `{}{}` instead",
+                if negated { "!" } else { "" },
+                self.text(other.span)
+            )
+        } else {
+            return;
+        };
+
+        self.report(LintRule::SuspiciousComparisons, message, span, None);
+    }
+
+    // ==================== Module-level rules ====================
+
+    /// Functions never called from an entry file
+    ///
+    /// Every function of a library module can be imported elsewhere, so only
+    /// files that run on their own (top-level statements or `fx main`) are
+    /// checked.
+    fn unused_functions(&mut self, module: &Module) {
+        let functions: Vec<&Function> = module
+            .items()
+            .into_iter()
+            .filter_map(|item| match &item.kind {
+                ItemKind::Function(function) => Some(function),
+                _ => None,
+            })
+            .collect();
+        let is_entry = module.has_top_level_statements()
+            || functions
+                .iter()
+                .any(|function| function.name.name == "main");
+        if !is_entry {
+            return;
+        }
+
+        for function in functions {
+            let name = &function.name.name;
+            if name == "main"
+                || name.starts_with('_')
+                || function.is_test()
+                || function.is_fixture()
+                || self.globals_read.contains(name)
+            {
+                continue;
+            }
+            self.report(
+                LintRule::UnusedFunctions,
+                format!("function `{name}` is never called"),
+                function.name.span,
+                None,
+            );
+        }
+    }
+
+    fn unused_imports(&mut self, module: &Module) {
+        for item in module.items() {
+            let ItemKind::Import(import) = &item.kind else {
+                continue;
+            };
+
+            let (unused, used): (Vec<_>, Vec<_>) = import_bindings(import)
+                .into_iter()
+                .partition(|(ident, _)| self.is_unused_import(ident));
+            if unused.is_empty() {
+                continue;
+            }
+
+            let names = unused
+                .iter()
+                .map(|(ident, _)| format!("`{}`", ident.name))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let (message, fix) = if used.is_empty() {
+                let message = if unused.len() == 1 {
+                    format!("unused import {names}")
+                } else {
+                    format!("unused imports {names}")
+                };
+                let fix = LintFix {
+                    description: "remove the import".to_string(),
+                    edits: vec![TextEdit {
+                        span: self.whole_lines(import.span),
+                        replacement: String::new(),
+                    }],
+                };
+                (message, fix)
+            } else {
+                let ImportKind::List(items) = &import.kind else {
+                    continue;
+                };
+                let (Some(first), Some(last)) = (items.first(), items.last()) else {
+                    continue;
+                };
+                let kept = used
+                    .iter()
+                    .map(|(_, span)| self.text(*span))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let fix = LintFix {
+                    description: "remove the unused names".to_string(),
+                    edits: vec![TextEdit {
+                        span: first.span.merge(last.span),
+                        replacement: kept,
+                    }],
+                };
+                (format!("unused imports {names}"), fix)
+            };
+            self.report(LintRule::UnusedImports, message, import.span, Some(fix));
+        }
+    }
+
+    fn is_unused_import(&self, ident: &Ident) -> bool {
+        !ident.name.starts_with('_') && !self.globals_read.contains(&ident.name)
+    }
+
+    /// Widen `span` to the full lines it covers when nothing else is on them,
+    /// so deleting it does not leave a blank line behind
+    fn whole_lines(&self, span: Span) -> Span {
+        let (start, end) = (span.start as usize, span.end as usize);
+        let (Some(before), Some(after)) = (self.source.get(..start), self.source.get(end..)) else {
+            return span;
+        };
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let line_end = after.find('\n').map_or(self.source.len(), |i| end + i + 1);
+        if before[line_start..].trim().is_empty() && self.source[end..line_end].trim().is_empty() {
+            Span::from_range(line_start..line_end)
+        } else {
+            span
+        }
+    }
+}
+
+/// Span of a statement's own text
+///
+/// The span of an expression statement starts at its enclosing block, so
+/// take the start from the expression instead.
+fn stmt_span(stmt: &Stmt) -> Span {
+    match &stmt.kind {
+        StmtKind::Expr(expr) => Span::new(expr.span.start, stmt.span.end),
+        _ => stmt.span,
+    }
+}
+
+/// Whether control never continues past `stmt`, naming the keyword responsible
+fn stmt_diverges(stmt: &Stmt) -> Option<&'static str> {
+    match &stmt.kind {
+        StmtKind::Return(_) => Some("return"),
+        StmtKind::Break => Some("break"),
+        StmtKind::Continue => Some("continue"),
+        StmtKind::Throw(_) => Some("throw"),
+        StmtKind::Expr(expr) => expr_diverges(expr),
+        _ => None,
+    }
+}
+
+fn expr_diverges(expr: &Expr) -> Option<&'static str> {
+    match &expr.kind {
+        ExprKind::Block(block) => block_diverges(block),
+        // Only an `if` whose every branch diverges
+        ExprKind::If {
+            then_branch,
+            else_branch: Some(else_branch),
+            ..
+        } => {
+            let keyword = block_diverges(then_branch)?;
+            match else_branch {
+                ElseBranch::Block(block) => block_diverges(block),
+                ElseBranch::ElseIf(else_if) => expr_diverges(else_if),
+            }
+            .map(|_| keyword)
+        }
+        _ => None,
+    }
+}
+
+fn block_diverges(block: &Block) -> Option<&'static str> {
+    block.stmts.iter().find_map(stmt_diverges)
+}
+
+const fn is_comparison(op: BinOp) -> bool {
+    matches!(
+        op,
+        BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge
+    )
+}
+
+/// Operands whose value cannot change between the two sides of a comparison
+fn is_plain_operand(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Ident(_) | ExprKind::Literal(_) => true,
+        ExprKind::Field { expr, .. } | ExprKind::Paren(expr) => is_plain_operand(expr),
+        _ => false,
+    }
+}
+
+fn bool_literal(expr: &Expr) -> Option<bool> {
+    match &expr.kind {
+        ExprKind::Literal(Literal::Bool(value)) => Some(*value),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    fn findings(source: &str) -> Vec<(LintRule, String)> {
+        let module = Parser::parse_module(source).unwrap();
+        let mut findings = analyze(&module, source);
+        findings.sort_by_key(|finding| finding.span.start);
+        findings
+            .into_iter()
+            .map(|finding| (finding.rule, finding.message))
+            .collect()
+    }
+
+    fn messages(source: &str, rule: LintRule) -> Vec<String> {
+        findings(source)
+            .into_iter()
+            .filter(|(r, _)| *r == rule)
+            .map(|(_, message)| message)
+            .collect()
+    }
+
+    #[test]
+    fn unused_variables() {
+        let source = r"
+fx main() {
+    let used = 1
+    let unused = 2
+    let _ignored = 3
+    let assigned = 0
+    assigned = used
+    for item in [1, 2] {}
+    match used {
+        Some(inner) => 1,
+        None => 2,
+        _ => 3,
+    };
+    let f = |x, y| x
+    f(1, 2)
+}
+";
+        assert_eq!(
+            messages(source, LintRule::UnusedVariables),
+            vec![
+                "unused variable `unused`",
+                "variable `assigned` is assigned but never read",
+                "unused variable `item`",
+                "unused variable `inner`",
+                "unused variable `y`",
+            ]
+        );
+    }
+
+    #[test]
+    fn unused_parameters_outside_interface_impls() {
+        let source = r#"
+interface Greeter {
+    fx greet(self, name: String) -> String
+}
+
+struct Quiet {}
+
+impl Greeter for Quiet {
+    fx greet(self, name: String) -> String { "" }
+}
+
+fx scale(value: Int, factor: Int) -> Int { value }
+"#;
+        assert_eq!(
+            messages(source, LintRule::UnusedVariables),
+            vec!["unused parameter `factor`"]
+        );
+    }
+
+    #[test]
+    fn closures_and_interpolation_read_captured_variables() {
+        let source = r#"
+fx main() {
+    let name = "world"
+    let count = 2
+    let greet = |greeting| "{greeting} {name}"
+    greet("hello");
+    Point { count }
+}
+"#;
+        assert!(messages(source, LintRule::UnusedVariables).is_empty());
+    }
+
+    #[test]
+    fn unused_functions_only_in_entry_files() {
+        let script = r"
+fx helper() { 1 }
+fx recursive(n: Int) -> Int { recursive(n - 1) }
+fx called() { 2 }
+fx _private() { 3 }
+
+#[test]
+fx test_helper() {}
+
+fx main() { called() }
+";
+        assert_eq!(
+            messages(script, LintRule::UnusedFunctions),
+            vec![
+                "function `helper` is never called",
+                "function `recursive` is never called",
+            ]
+        );
+
+        let library = "fx helper() { 1 }\n";
+        assert!(messages(library, LintRule::UnusedFunctions).is_empty());
+    }
+
+    #[test]
+    fn unused_imports() {
+        let source = r"
+import std.math
+import std.io
+import data.{Frame, Series, read_csv}
+import json.*
+
+fx load(path: String) -> Frame {
+    io.println(path);
+    read_csv(path)
+}
+";
+        assert_eq!(
+            messages(source, LintRule::UnusedImports),
+            vec!["unused import `math`", "unused imports `Series`"]
+        );
+    }
+
+    #[test]
+    fn import_fixes() {
+        let source = "import std.math\nimport data.{Frame, Series, read_csv}\n\nread_csv(\"a\")\n";
+        let module = Parser::parse_module(source).unwrap();
+        let fixes: Vec<LintFix> = analyze(&module, source)
+            .into_iter()
+            .filter_map(|finding| finding.fix)
+            .collect();
+        assert_eq!(fixes.len(), 2);
+        assert_eq!(fixes[0].edits[0].span.as_range(), 0..16);
+        assert_eq!(fixes[1].edits[0].replacement, "read_csv");
+    }
+
+    #[test]
+    fn shadowing() {
+        let source = r"
+fx main(value: Int) {
+    let value = value + 1
+    let total = 0
+    if value > 0 {
+        let total = 1
+        println(total)
+    };
+    let double = |total| total * 2
+    let _value = 2
+    println(double(total))
+}
+";
+        assert_eq!(
+            messages(source, LintRule::Shadowing),
+            vec![
+                "`value` shadows the binding declared on line 2",
+                "`total` shadows the binding declared on line 4",
+            ]
+        );
+    }
+
+    #[test]
+    fn unreachable_code() {
+        let source = r#"
+fx check(n: Int) -> Int {
+    if n > 0 {
+        return 1
+    } else {
+        throw "negative"
+    };
+    println(n);
+    n
+}
+
+fx loop_body() {
+    while true {
+        break
+        println("never")
+    }
+    println("after")
+}
+"#;
+        assert_eq!(
+            messages(source, LintRule::UnreachableCode),
+            vec![
+                "unreachable code after `return`",
+                "unreachable code after `break`"
+            ]
+        );
+    }
+
+    #[test]
+    fn suspicious_comparisons() {
+        let source = r"
+fx check(a: Int, b: Int, c: Int, flag: Bool) {
+    if a < b < c {};
+    if a.size == a.size {};
+    if a != a {};
+    if flag == true {};
+    if false == flag {};
+    if (a < b) == flag {};
+    if a == b {}
+}
+";
+        assert_eq!(
+            messages(source, LintRule::SuspiciousComparisons),
+            vec![
+                "`a < b < c` compares the boolean result of `a < b`; combine comparisons with `&&`",
+                "comparing `a.size` with itself is always true",
+                "comparing `a` with itself is always false",
+                "comparison with `true` is redundant; use `flag` instead",
+                "comparison with `false` is redundant; use `!flag` instead",
+            ]
+        );
+    }
+}
@@ -0,0 +1,430 @@
+//! Static analysis of Stratum source code
+//!
+//! The linter walks a parsed module looking for code that is legal but most
+//! likely a mistake: bindings that are never read, functions and imports that
+//! are never used, code after a `return`, and comparisons whose result is
+//! fixed or misleading.
+//!
+//! Every check is a [`LintRule`] with a default [`LintLevel`]. Projects adjust
+//! the levels in the `[lints]` table of `stratum.toml`:
+//!
+//! ```toml
+//! [lints]
+//! shadowing = "warn"
+//! unused_variables = "deny"
+//! unreachable_code = "allow"
+//! ```
+//!
+//! Diagnostics carry a [`LintFix`] when the problem can be corrected without
+//! changing what the program does; [`apply_fixes`] rewrites the source with
+//! them.
+
+mod analyzer;
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use thiserror::Error;
+
+use crate::ast::Module;
+use crate::lexer::Span;
+use crate::parser::{ParseError, Parser};
+
+/// A check performed by the linter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum LintRule {
+    /// A local variable, parameter, or pattern binding that is never read
+    UnusedVariables,
+    /// A function in an entry file that is never called
+    UnusedFunctions,
+    /// An import whose names are never referenced
+    UnusedImports,
+    /// A binding that hides another binding of the same name
+    Shadowing,
+    /// Statements after `return`, `break`, `continue`, or `throw`
+    UnreachableCode,
+    /// Comparisons that are always true or false, or compare a comparison
+    SuspiciousComparisons,
+}
+
+impl LintRule {
+    /// Every registered rule
+    pub const ALL: [LintRule; 6] = [
+        LintRule::UnusedVariables,
+        LintRule::UnusedFunctions,
+        LintRule::UnusedImports,
+        LintRule::Shadowing,
+        LintRule::UnreachableCode,
+        LintRule::SuspiciousComparisons,
+    ];
+
+    /// Look up a rule by the name used in `[lints]`
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|rule| rule.name() == name)
+    }
+
+    /// The name used in `[lints]` and in reported diagnostics
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            LintRule::UnusedVariables => "unused_variables",
+            LintRule::UnusedFunctions => "unused_functions",
+            LintRule::UnusedImports => "unused_imports",
+            LintRule::Shadowing => "shadowing",
+            LintRule::UnreachableCode => "unreachable_code",
+            LintRule::SuspiciousComparisons => "suspicious_comparisons",
+        }
+    }
+
+    /// One-line description of what the rule reports
+    #[must_use]
+    pub const fn description(self) -> &'static str {
+        match self {
+            LintRule::UnusedVariables => "variables, parameters and pattern bindings never read",
+            LintRule::UnusedFunctions => "functions in an entry file that are never called",
+            LintRule::UnusedImports => "imported names that are never referenced",
+            LintRule::Shadowing => "bindings that hide an earlier binding of the same name",
+            LintRule::UnreachableCode => "statements that can never execute",
+            LintRule::SuspiciousComparisons => {
+                "comparisons with a fixed result or a comparison as an operand"
+            }
+        }
+    }
+
+    /// The level used when `[lints]` does not mention the rule
+    ///
+    /// Shadowing is allowed by default: rebinding a name while transforming
+    /// it (`let name = name.trim()`) is idiomatic Stratum.
+    #[must_use]
+    pub const fn default_level(self) -> LintLevel {
+        match self {
+            LintRule::Shadowing => LintLevel::Allow,
+            _ => LintLevel::Warn,
+        }
+    }
+}
+
+impl fmt::Display for LintRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// How a rule's findings are reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum LintLevel {
+    /// The rule is disabled
+    Allow,
+    /// Findings are reported but do not fail the lint run
+    Warn,
+    /// Findings are reported and fail the lint run
+    Deny,
+}
+
+impl LintLevel {
+    /// Parse a level as written in `[lints]`
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "allow" => Some(LintLevel::Allow),
+            "warn" => Some(LintLevel::Warn),
+            "deny" => Some(LintLevel::Deny),
+            _ => None,
+        }
+    }
+
+    /// The name used in `[lints]`
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            LintLevel::Allow => "allow",
+            LintLevel::Warn => "warn",
+            LintLevel::Deny => "deny",
+        }
+    }
+}
+
+impl fmt::Display for LintLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// An invalid entry in a `[lints]` table
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum LintConfigError {
+    #[error("unknown lint rule '{0}'")]
+    UnknownRule(String),
+
+    #[error("invalid level '{level}' for lint '{rule}', expected allow, warn, or deny")]
+    UnknownLevel { rule: String, level: String },
+}
+
+/// The level of every rule for a lint run
+#[derive(Debug, Clone, Default)]
+pub struct LintConfig {
+    /// Levels that differ from the rule defaults
+    levels: HashMap<LintRule, LintLevel>,
+}
+
+impl LintConfig {
+    /// Build a configuration from `rule = "level"` entries of a `[lints]` table
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for an unknown rule name or level.
+    pub fn from_table<'a>(
+        entries: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> Result<Self, LintConfigError> {
+        let mut config = Self::default();
+        for (rule_name, level_name) in entries {
+            let rule = LintRule::from_name(rule_name)
+                .ok_or_else(|| LintConfigError::UnknownRule(rule_name.to_string()))?;
+            let level =
+                LintLevel::from_name(level_name).ok_or_else(|| LintConfigError::UnknownLevel {
+                    rule: rule_name.to_string(),
+                    level: level_name.to_string(),
+                })?;
+            config.set(rule, level);
+        }
+        Ok(config)
+    }
+
+    /// Override the level of a rule
+    pub fn set(&mut self, rule: LintRule, level: LintLevel) {
+        self.levels.insert(rule, level);
+    }
+
+    /// The effective level of a rule
+    #[must_use]
+    pub fn level(&self, rule: LintRule) -> LintLevel {
+        self.levels
+            .get(&rule)
+            .copied()
+            .unwrap_or_else(|| rule.default_level())
+    }
+}
+
+/// A replacement of one source range
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    /// The range to replace
+    pub span: Span,
+    /// The text to put in its place
+    pub replacement: String,
+}
+
+/// An automatic correction that does not change program behaviour
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintFix {
+    /// What the fix does, e.g. "remove the import"
+    pub description: String,
+    /// The edits making up the fix, applied together or not at all
+    pub edits: Vec<TextEdit>,
+}
+
+/// A single finding of the linter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintDiagnostic {
+    /// The rule that produced the finding
+    pub rule: LintRule,
+    /// The configured level of the rule
+    pub level: LintLevel,
+    /// What is wrong
+    pub message: String,
+    /// The offending source range
+    pub span: Span,
+    /// A safe correction, when one exists
+    pub fix: Option<LintFix>,
+}
+
+/// Runs the lint rules over modules with a fixed configuration
+#[derive(Debug, Clone, Default)]
+pub struct Linter {
+    config: LintConfig,
+}
+
+impl Linter {
+    /// Create a linter with the given rule levels
+    #[must_use]
+    pub fn new(config: LintConfig) -> Self {
+        Self { config }
+    }
+
+    /// Parse and lint a source file
+    ///
+    /// # Errors
+    ///
+    /// Returns the parse errors if the source is not valid Stratum.
+    pub fn lint_source(&self, source: &str) -> Result<Vec<LintDiagnostic>, Vec<ParseError>> {
+        let module = Parser::parse_module(source)?;
+        Ok(self.lint_module(&module, source))
+    }
+
+    /// Lint a parsed module; `source` is the text it was parsed from
+    ///
+    /// Diagnostics of allowed rules are dropped; the rest are ordered by
+    /// position.
+    #[must_use]
+    pub fn lint_module(&self, module: &Module, source: &str) -> Vec<LintDiagnostic> {
+        let mut diagnostics: Vec<LintDiagnostic> = analyzer::analyze(module, source)
+            .into_iter()
+            .filter_map(|finding| {
+                let level = self.config.level(finding.rule);
+                (level != LintLevel::Allow).then_some(LintDiagnostic {
+                    rule: finding.rule,
+                    level,
+                    message: finding.message,
+                    span: finding.span,
+                    fix: finding.fix,
+                })
+            })
+            .collect();
+        diagnostics.sort_by_key(|diagnostic| (diagnostic.span.start, diagnostic.rule));
+        diagnostics
+    }
+}
+
+/// Names bound by the imports of `module` that nothing in it reads
+///
+/// `source` is the text the module was parsed from. Names starting with `_`
+/// are never reported.
+#[must_use]
+pub fn unused_imports(module: &Module, source: &str) -> HashSet<String> {
+    analyzer::unused_imports(module, source)
+}
+
+/// Apply the fixes of `diagnostics` to `source`
+///
+/// A fix whose edits overlap an earlier fix is skipped; running the linter
+/// again picks it up. Returns the new source and the number of fixes applied.
+#[must_use]
+pub fn apply_fixes(source: &str, diagnostics: &[LintDiagnostic]) -> (String, usize) {
+    let mut accepted: Vec<&TextEdit> = Vec::new();
+    let mut applied = 0;
+
+    for fix in diagnostics.iter().filter_map(|d| d.fix.as_ref()) {
+        let overlaps = fix.edits.iter().any(|edit| {
+            accepted.iter().any(|other| {
+                edit.span.start < other.span.end && other.span.start < edit.span.end
+                    || edit.span == other.span
+            })
+        });
+        if !overlaps {
+            accepted.extend(&fix.edits);
+            applied += 1;
+        }
+    }
+
+    accepted.sort_by_key(|edit| std::cmp::Reverse(edit.span.start));
+    let mut fixed = source.to_string();
+    for edit in accepted {
+        fixed.replace_range(edit.span.as_range(), &edit.replacement);
+    }
+    (fixed, applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint(source: &str, config: LintConfig) -> Vec<LintDiagnostic> {
+        Linter::new(config).lint_source(source).unwrap()
+    }
+
+    #[test]
+    fn reads_levels_from_lints_table() {
+        let config =
+            LintConfig::from_table([("shadowing", "deny"), ("unused_imports", "allow")]).unwrap();
+        assert_eq!(config.level(LintRule::Shadowing), LintLevel::Deny);
+        assert_eq!(config.level(LintRule::UnusedImports), LintLevel::Allow);
+        assert_eq!(config.level(LintRule::UnusedVariables), LintLevel::Warn);
+
+        assert_eq!(
+            LintConfig::from_table([("unused_everything", "warn")]).unwrap_err(),
+            LintConfigError::UnknownRule("unused_everything".to_string())
+        );
+        assert!(matches!(
+            LintConfig::from_table([("shadowing", "loud")]),
+            Err(LintConfigError::UnknownLevel { .. })
+        ));
+    }
+
+    #[test]
+    fn allowed_rules_are_not_reported() {
+        let source = "fx main() {\n    let x = 1\n    let x = 2\n}\n";
+        let rules = |config| {
+            lint(source, config)
+                .into_iter()
+                .map(|d| d.rule)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            rules(LintConfig::default()),
+            vec![LintRule::UnusedVariables, LintRule::UnusedVariables]
+        );
+
+        let config =
+            LintConfig::from_table([("shadowing", "warn"), ("unused_variables", "allow")]).unwrap();
+        assert_eq!(rules(config), vec![LintRule::Shadowing]);
+    }
+
+    #[test]
+    fn reports_configured_level() {
+        let config = LintConfig::from_table([("unreachable_code", "deny")]).unwrap();
+        let diagnostics = lint("fx main() {\n    return 1\n    println(2)\n}\n", config);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].rule, LintRule::UnreachableCode);
+        assert_eq!(diagnostics[0].level, LintLevel::Deny);
+    }
+
+    #[test]
+    fn applies_safe_fixes() {
+        let source = "import std.math\nimport std.io\n\nfx main() {\n    let total = 1\n    io.println(\"hi\")\n}\n";
+        let diagnostics = lint(source, LintConfig::default());
+        let (fixed, applied) = apply_fixes(source, &diagnostics);
+        assert_eq!(applied, 2);
+        assert_eq!(
+            fixed,
+            "import std.io\n\nfx main() {\n    let _total = 1\n    io.println(\"hi\")\n}\n"
+        );
+        assert!(lint(&fixed, LintConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn skips_overlapping_fixes() {
+        let edit = |start, end, text: &str| TextEdit {
+            span: Span::new(start, end),
+            replacement: text.to_string(),
+        };
+        let diagnostic = |edits| LintDiagnostic {
+            rule: LintRule::UnusedVariables,
+            level: LintLevel::Warn,
+            message: String::new(),
+            span: Span::new(0, 1),
+            fix: Some(LintFix {
+                description: String::new(),
+                edits,
+            }),
+        };
+        let (fixed, applied) = apply_fixes(
+            "abcdef",
+            &[
+                diagnostic(vec![edit(0, 2, "X"), edit(4, 5, "Y")]),
+                diagnostic(vec![edit(1, 3, "Z")]),
+            ],
+        );
+        assert_eq!(applied, 1);
+        assert_eq!(fixed, "XcdYf");
+    }
+
+    #[test]
+    fn lists_unused_import_names() {
+        let source = "import std.math\nimport data.{Frame, Series as S, _private}\n\nFrame.new()\n";
+        let module = Parser::parse_module(source).unwrap();
+        let mut names: Vec<String> = unused_imports(&module, source).into_iter().collect();
+        names.sort();
+        assert_eq!(names, vec!["S", "math"]);
+    }
+}
@@ -100,6 +100,31 @@ pub enum ParseErrorKind {
     PositionalAfterNamed,
 }
 
+impl ParseErrorKind {
+    /// The stable error code for this kind of error, such as `E0100`,
+    /// explained in [`crate::error_codes`]
+    #[must_use]
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::UnexpectedToken { .. } => "E0001",
+            Self::UnexpectedEof => "E0002",
+            Self::ExpectedExpression => "E0003",
+            Self::ExpectedStatement => "E0004",
+            Self::ExpectedIdentifier => "E0005",
+            Self::ExpectedType => "E0006",
+            Self::ExpectedPattern => "E0007",
+            Self::ExpectedAfter { .. } => "E0008",
+            Self::InvalidAssignmentTarget => "E0009",
+            Self::InvalidNumber(..) => "E0010",
+            Self::DuplicateParameter(..) => "E0011",
+            Self::BreakOutsideLoop => "E0012",
+            Self::ContinueOutsideLoop => "E0013",
+            Self::ReturnOutsideFunction => "E0014",
+            Self::PositionalAfterNamed => "E0015",
+        }
+    }
+}
+
 /// What token was expected
 #[derive(Debug, Clone, PartialEq)]
 pub enum ExpectedToken {
@@ -0,0 +1,998 @@
+//! Incremental reparsing for editor tooling
+//!
+//! [`IncrementalParser`] keeps the tokens and AST from the previous parse. When
+//! the source changes, the old and new text are diffed into a single
+//! [`TextEdit`]; top-level items before the edit are reused as-is, items after
+//! it are reused with their spans shifted, and only the region in between is
+//! run through the parser again.
+
+#![allow(clippy::cast_possible_truncation)] // Spans are u32; files > 4GB are unsupported
+
+use std::sync::Arc;
+
+use crate::ast::{
+    Attribute, AttributeArg, Block, CallArg, CatchClause, Comment, ElseBranch, EnumVariant,
+    EnumVariantData, Expr, ExprKind, FieldInit, FieldPattern, Function, Ident, ImportItem,
+    ImportKind, InterfaceMethod, Item, ItemKind, MatchArm, Module, Param, Pattern, PatternKind,
+    Stmt, StmtKind, StringPart, StructField, TopLevelItem, Trivia, TypeAnnotation, TypeKind,
+    TypeParam,
+};
+use crate::lexer::{Span, Token};
+
+use super::{ParseError, Parser};
+
+/// Number of tokens the parser may look at past the end of an item
+///
+/// An item is only reused when this many tokens after its start are untouched
+/// by the edit, so its parse can't have depended on the edited text.
+const LOOKAHEAD: usize = 3;
+
+/// A single contiguous text replacement
+///
+/// `start..old_end` is the replaced byte range in the old source, and
+/// `start..new_end` is the text that replaced it in the new source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextEdit {
+    /// Byte offset where the edit begins (same in both sources)
+    pub start: u32,
+    /// End of the replaced range in the old source
+    pub old_end: u32,
+    /// End of the inserted text in the new source
+    pub new_end: u32,
+}
+
+impl TextEdit {
+    /// Compute the smallest edit that turns `old` into `new`
+    ///
+    /// Trims the common prefix and suffix of both strings. Returns `None` if
+    /// they are identical.
+    #[must_use]
+    pub fn between(old: &str, new: &str) -> Option<Self> {
+        if old == new {
+            return None;
+        }
+
+        let mut prefix = old
+            .bytes()
+            .zip(new.bytes())
+            .take_while(|(a, b)| a == b)
+            .count();
+        while !old.is_char_boundary(prefix) || !new.is_char_boundary(prefix) {
+            prefix -= 1;
+        }
+
+        let max_suffix = old.len().min(new.len()) - prefix;
+        let mut suffix = old
+            .bytes()
+            .rev()
+            .zip(new.bytes().rev())
+            .take(max_suffix)
+            .take_while(|(a, b)| a == b)
+            .count();
+        while !old.is_char_boundary(old.len() - suffix) || !new.is_char_boundary(new.len() - suffix)
+        {
+            suffix -= 1;
+        }
+
+        Some(Self {
+            start: prefix as u32,
+            old_end: (old.len() - suffix) as u32,
+            new_end: (new.len() - suffix) as u32,
+        })
+    }
+
+    /// Map an offset in the old source that lies at or after `old_end`
+    fn shift(&self, offset: u32) -> u32 {
+        if self.new_end >= self.old_end {
+            offset + (self.new_end - self.old_end)
+        } else {
+            offset - (self.old_end - self.new_end)
+        }
+    }
+
+    /// Map an offset in the old source onto the new source
+    ///
+    /// Offsets before the edit are unchanged, offsets after it are shifted, and
+    /// offsets inside the replaced range collapse to the start of the edit.
+    #[must_use]
+    pub fn map_offset(&self, offset: u32) -> u32 {
+        if offset <= self.start {
+            offset
+        } else if offset >= self.old_end {
+            self.shift(offset)
+        } else {
+            self.start
+        }
+    }
+
+    /// Map a span in the old source onto the new source
+    #[must_use]
+    pub fn map_span(&self, span: Span) -> Span {
+        let start = self.map_offset(span.start);
+        Span::new(start, self.map_offset(span.end).max(start))
+    }
+}
+
+/// Summary of what an [`IncrementalParser::update`] call did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reparse {
+    /// The edit that was applied, or `None` if the source was unchanged
+    pub edit: Option<TextEdit>,
+    /// Top-level items carried over from the previous parse
+    pub reused_items: usize,
+    /// Top-level items produced by running the parser again
+    pub reparsed_items: usize,
+    /// Whether any token other than whitespace and comments changed
+    ///
+    /// When this is `false` the new AST is the old one with shifted spans, so
+    /// results derived from it (such as type errors) can be remapped through
+    /// `edit` instead of recomputed.
+    pub tokens_changed: bool,
+}
+
+/// A parser that reuses unchanged top-level items between edits
+#[derive(Debug, Clone)]
+pub struct IncrementalParser {
+    /// Source of the last parse
+    source: String,
+    /// Parser state of the last parse (tokens and diagnostics)
+    parser: Parser,
+    /// AST of the last parse, shared so callers can hold on to it across edits
+    module: Arc<Module>,
+}
+
+impl IncrementalParser {
+    /// Parse `source` from scratch
+    #[must_use]
+    pub fn new(source: &str) -> Self {
+        let mut parser = Parser::new(source);
+        let module = parser.module();
+        Self {
+            source: source.to_string(),
+            parser,
+            module: Arc::new(module),
+        }
+    }
+
+    /// The source text of the last parse
+    #[must_use]
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The (possibly partially recovered) AST of the last parse
+    #[must_use]
+    pub fn module(&self) -> &Arc<Module> {
+        &self.module
+    }
+
+    /// All lexer and parser diagnostics of the last parse, ordered by position
+    #[must_use]
+    pub fn errors(&self) -> Vec<ParseError> {
+        self.parser.sorted_errors()
+    }
+
+    /// Bring the parse up to date with `source`
+    ///
+    /// The result is identical to parsing `source` from scratch; only the work
+    /// needed to get there differs.
+    pub fn update(&mut self, source: &str) -> Reparse {
+        let Some(edit) = TextEdit::between(&self.source, source) else {
+            return Reparse {
+                edit: None,
+                reused_items: self.module.top_level.len(),
+                reparsed_items: 0,
+                tokens_changed: false,
+            };
+        };
+
+        let mut parser = Parser::new(source);
+        let tokens_changed = !same_significant_tokens(&self.parser.tokens, &parser.tokens);
+        let old_module = std::mem::replace(
+            &mut self.module,
+            Arc::new(Module::new(Vec::new(), Vec::new(), Span::dummy())),
+        );
+        let mut old_module = Arc::try_unwrap(old_module).unwrap_or_else(|shared| (*shared).clone());
+
+        let (module, reused_items) = match self.reparse(&mut parser, &mut old_module, edit) {
+            Some(result) => result,
+            None => (parser.module(), 0),
+        };
+
+        let reparsed_items = module.top_level.len() - reused_items;
+        self.source = source.to_string();
+        self.parser = parser;
+        self.module = Arc::new(module);
+
+        Reparse {
+            edit: Some(edit),
+            reused_items,
+            reparsed_items,
+            tokens_changed,
+        }
+    }
+
+    /// Reparse only the region affected by `edit`
+    ///
+    /// Returns `None` when nothing before the edit can be reused, in which case
+    /// the caller falls back to a full parse.
+    fn reparse(
+        &self,
+        parser: &mut Parser,
+        old: &mut Module,
+        edit: TextEdit,
+    ) -> Option<(Module, usize)> {
+        let old_tokens = &self.parser.tokens;
+
+        // The last item whose first few tokens precede the edit is where we
+        // resume: it and everything after it are parsed again
+        let resume = old
+            .top_level
+            .iter()
+            .rposition(|item| tokens_before(old_tokens, item.span().start, edit.start))?;
+        let resume_offset = old.top_level[resume].span().start;
+        let position = parser
+            .tokens
+            .iter()
+            .position(|token| token.span.start == resume_offset)?;
+
+        // Restore the parser state the full parse had at this point: comments
+        // collected since the previous item, and errors reported so far. An
+        // error can sit exactly on the item's first token when recovery stopped
+        // there; the item itself never reports one at that position.
+        parser.position = position;
+        parser.pending_comments = leading_comments(&old.top_level[resume])
+            .map(<[Comment]>::to_vec)
+            .unwrap_or_default();
+        parser.errors = self
+            .parser
+            .errors
+            .iter()
+            .filter(|err| err.span.start <= resume_offset)
+            .cloned()
+            .collect();
+
+        let mut suffix = old.top_level.split_off(resume);
+        let mut top_level = std::mem::take(&mut old.top_level);
+        let reused_prefix = top_level.len();
+        let mut reused_suffix = 0;
+        let mut tokens_diverged = false;
+
+        while !parser.is_eof() {
+            parser.collect_trivia();
+            if parser.is_eof() {
+                break;
+            }
+
+            let offset = parser.current().span.start;
+            if !tokens_diverged && offset >= edit.new_end {
+                match self.reusable_suffix_item(parser, &suffix, edit) {
+                    SuffixMatch::Item(index) => {
+                        let old_start = suffix[index].span().start;
+                        for mut item in suffix.drain(index..) {
+                            item.shift_spans(&edit);
+                            top_level.push(item);
+                            reused_suffix += 1;
+                        }
+                        parser.errors.extend(
+                            self.parser
+                                .errors
+                                .iter()
+                                // Errors on the item's first token came from
+                                // recovery before it, which was just redone
+                                .filter(|err| err.span.start > old_start)
+                                .map(|err| {
+                                    let mut err = err.clone();
+                                    err.span.shift_spans(&edit);
+                                    err
+                                }),
+                        );
+                        parser.position = parser.tokens.len() - 1;
+                        break;
+                    }
+                    SuffixMatch::Diverged => tokens_diverged = true,
+                    SuffixMatch::None => {}
+                }
+            }
+
+            parser.top_level_item_recovering(&mut top_level);
+        }
+
+        let end = parser.current().span.end;
+        let module = Module::with_trivia(
+            std::mem::take(&mut old.inner_attributes),
+            top_level,
+            Span::new(old.span.start, end),
+            Trivia::empty(),
+        );
+        Some((module, reused_prefix + reused_suffix))
+    }
+
+    /// Find an old item after the edit that starts at the parser's position
+    /// and would parse identically
+    fn reusable_suffix_item(
+        &self,
+        parser: &Parser,
+        suffix: &[TopLevelItem],
+        edit: TextEdit,
+    ) -> SuffixMatch {
+        let offset = parser.current().span.start;
+        let Ok(index) =
+            suffix.binary_search_by_key(&offset, |item| edit.map_offset(item.span().start))
+        else {
+            return SuffixMatch::None;
+        };
+        let item = &suffix[index];
+        if item.span().start < edit.old_end {
+            return SuffixMatch::None;
+        }
+
+        // The item's leading comments must match what the parser has collected
+        if let Some(comments) = leading_comments(item) {
+            let same_comments = comments.len() == parser.pending_comments.len()
+                && comments
+                    .iter()
+                    .zip(&parser.pending_comments)
+                    .all(|(old, new)| old.text == new.text && edit.map_span(old.span) == new.span);
+            if !same_comments {
+                return SuffixMatch::None;
+            }
+        }
+
+        // The text after the edit is unchanged, but the lexer may not have been
+        // in the same state when it got there (e.g. an unterminated string)
+        let old_tokens = &self.parser.tokens;
+        let old_position = old_tokens.partition_point(|t| t.span.start < item.span().start);
+        let new_tokens = &parser.tokens[parser.position..];
+        let old_tokens = &old_tokens[old_position..];
+        let same_tokens = new_tokens.len() == old_tokens.len()
+            && new_tokens.iter().zip(old_tokens).all(|(new, old)| {
+                new.kind == old.kind
+                    && new.lexeme == old.lexeme
+                    && new.span.start == edit.shift(old.span.start)
+            });
+        if same_tokens {
+            SuffixMatch::Item(index)
+        } else {
+            SuffixMatch::Diverged
+        }
+    }
+}
+
+/// Outcome of looking for a reusable item after the edit
+enum SuffixMatch {
+    /// The old item at this index (and everything after it) can be reused
+    Item(usize),
+    /// No old item starts here
+    None,
+    /// The token streams differ from here on, so no later item can match
+    Diverged,
+}
+
+/// Check that the first [`LOOKAHEAD`] non-trivia tokens at `offset` end
+/// strictly before `limit`
+///
+/// Strictly, because an edit right after a token can extend it (typing more
+/// characters of an identifier, for example).
+fn tokens_before(tokens: &[Token], offset: u32, limit: u32) -> bool {
+    let position = tokens.partition_point(|t| t.span.start < offset);
+    let mut significant = tokens[position..]
+        .iter()
+        .filter(|t| !t.kind.is_trivia())
+        .take(LOOKAHEAD)
+        .peekable();
+    significant.peek().is_some() && significant.all(|t| t.span.end < limit)
+}
+
+/// Compare two token streams ignoring whitespace, comments and positions
+fn same_significant_tokens(old: &[Token], new: &[Token]) -> bool {
+    let old = old.iter().filter(|t| !t.kind.is_trivia());
+    let mut new = new.iter().filter(|t| !t.kind.is_trivia());
+    for a in old {
+        match new.next() {
+            Some(b) if a.kind == b.kind && a.lexeme == b.lexeme => {}
+            _ => return false,
+        }
+    }
+    new.next().is_none()
+}
+
+/// Leading comments attached to a top-level item
+///
+/// Returns `None` for items that don't keep their comments (imports).
+fn leading_comments(item: &TopLevelItem) -> Option<&[Comment]> {
+    let trivia = match item {
+        TopLevelItem::Item(item) => match &item.kind {
+            ItemKind::Function(f) => &f.trivia,
+            ItemKind::Struct(s) => &s.trivia,
+            ItemKind::Enum(e) => &e.trivia,
+            ItemKind::Interface(i) => &i.trivia,
+            ItemKind::Impl(i) => &i.trivia,
+            ItemKind::Import(_) => return None,
+        },
+        TopLevelItem::Let(let_decl) => &let_decl.trivia,
+        TopLevelItem::Statement(stmt) => &stmt.trivia,
+    };
+    Some(&trivia.leading)
+}
+
+// ==================== Span Shifting ====================
+
+/// Move every span in a reused node to its position in the new source
+///
+/// Nearly everything in an item reused after the edit simply shifts, but its
+/// leading comments may have been collected from before the edit.
+trait ShiftSpans {
+    fn shift_spans(&mut self, edit: &TextEdit);
+}
+
+impl ShiftSpans for Span {
+    fn shift_spans(&mut self, edit: &TextEdit) {
+        *self = edit.map_span(*self);
+    }
+}
+
+impl<T: ShiftSpans> ShiftSpans for Vec<T> {
+    fn shift_spans(&mut self, edit: &TextEdit) {
+        for node in self {
+            node.shift_spans(edit);
+        }
+    }
+}
+
+impl<T: ShiftSpans> ShiftSpans for Option<T> {
+    fn shift_spans(&mut self, edit: &TextEdit) {
+        if let Some(node) = self {
+            node.shift_spans(edit);
+        }
+    }
+}
+
+impl<T: ShiftSpans> ShiftSpans for Box<T> {
+    fn shift_spans(&mut self, edit: &TextEdit) {
+        (**self).shift_spans(edit);
+    }
+}
+
+impl ShiftSpans for Ident {
+    fn shift_spans(&mut self, edit: &TextEdit) {
+        self.span.shift_spans(edit);
+    }
+}
+
+impl ShiftSpans for Comment {
+    fn shift_spans(&mut self, edit: &TextEdit) {
+        self.span.shift_spans(edit);
+    }
+}
+
+impl ShiftSpans for Trivia {
+    fn shift_spans(&mut self, edit: &TextEdit) {
+        self.leading.shift_spans(edit);
+        self.trailing.shift_spans(edit);
+    }
+}
+
+impl ShiftSpans for TopLevelItem {
+    fn shift_spans(&mut self, edit: &TextEdit) {
+        match self {
+            TopLevelItem::Item(item) => item.shift_spans(edit),
+            TopLevelItem::Let(let_decl) => {
+                let_decl.pattern.shift_spans(edit);
+                let_decl.ty.shift_spans(edit);
+                let_decl.value.shift_spans(edit);
+                let_decl.span.shift_spans(edit);
+                let_decl.trivia.shift_spans(edit);
+            }
+            TopLevelItem::Statement(stmt) => stmt.shift_spans(edit),
+        }
+    }
+}
+
+impl ShiftSpans for Item {
+    fn shift_spans(&mut self, edit: &TextEdit) {
+        self.span.shift_spans(edit);
+        match &mut self.kind {
+            ItemKind::Function(f) => f.shift_spans(edit),
+            ItemKind::Struct(s) => {
+                s.name.shift_spans(edit);
+                s.type_params.shift_spans(edit);
+                s.fields.shift_spans(edit);
+                s.attributes.shift_spans(edit);
+                s.span.shift_spans(edit);
+                s.trivia.shift_spans(edit);
+            }
+            ItemKind::Enum(e) => {
+                e.name.shift_spans(edit);
+                e.type_params.shift_spans(edit);
+                e.variants.shift_spans(edit);
+                e.attributes.shift_spans(edit);
+                e.span.shift_spans(edit);
+                e.trivia.shift_spans(edit);
+            }
+            ItemKind::Interface(i) => {
+                i.name.shift_spans(edit);
+                i.type_params.shift_spans(edit);
+                i.methods.shift_spans(edit);
+                i.span.shift_spans(edit);
+                i.trivia.shift_spans(edit);
+            }
+            ItemKind::Impl(i) => {
+                i.type_params.shift_spans(edit);
+                i.interface.shift_spans(edit);
+                i.target.shift_spans(edit);
+                i.methods.shift_spans(edit);
+                i.span.shift_spans(edit);
+                i.trivia.shift_spans(edit);
+            }
+            ItemKind::Import(i) => {
+                i.path.shift_spans(edit);
+                match &mut i.kind {
+                    ImportKind::Item | ImportKind::Glob => {}
+                    ImportKind::List(items) => items.shift_spans(edit),
+                    ImportKind::Alias(alias) => alias.shift_spans(edit),
+                }
+                i.span.shift_spans(edit);
+            }
+        }
+    }
+}
+
+impl ShiftSpans for Function {
+    fn shift_spans(&mut self, edit: &TextEdit) {
+        self.name.shift_spans(edit);
+        self.type_params.shift_spans(edit);
+        self.params.shift_spans(edit);
+        self.return_type.shift_spans(edit);
+        self.body.shift_spans(edit);
+        self.attributes.shift_spans(edit);
+        self.span.shift_spans(edit);
+        self.trivia.shift_spans(edit);
+    }
+}
+
+impl ShiftSpans for Attribute {
+    fn shift_spans(&mut self, edit: &TextEdit) {
+        self.name.shift_spans(edit);
+        for arg in &mut self.args {
+            match arg {
+                AttributeArg::Ident(ident) => ident.shift_spans(edit),
+                AttributeArg::NameValue { name, value } => {
+                    name.shift_spans(edit);
+                    value.shift_spans(edit);
+                }
+                AttributeArg::Value(value) => value.shift_spans(edit),
+            }
+        }
+        self.span.shift_spans(edit);
+    }
+}
+
+impl ShiftSpans for StructField {
+    fn shift_spans(&mut self, edit: &TextEdit) {
+        self.name.shift_spans(edit);
+        self.ty.shift_spans(edit);
+        self.span.shift_spans(edit);
+    }
+}
+
+impl ShiftSpans for EnumVariant {
+    fn shift_spans(&mut self, edit: &TextEdit) {
+        self.name.shift_spans(edit);
+        match &mut self.data {
+            Some(EnumVariantData::Tuple(types)) => types.shift_spans(edit),
+            Some(EnumVariantData::Struct(fields)) => fields.shift_spans(edit),
+            None => {}
+        }
+        self.span.shift_spans(edit);
+    }
+}
+
+impl ShiftSpans for InterfaceMethod {
+    fn shift_spans(&mut self, edit: &TextEdit) {
+        self.name.shift_spans(edit);
+        self.type_params.shift_spans(edit);
+        self.params.shift_spans(edit);
+        self.return_type.shift_spans(edit);
+        self.default_body.shift_spans(edit);
+        self.span.shift_spans(edit);
+    }
+}
+
+impl ShiftSpans for ImportItem {
+    fn shift_spans(&mut self, edit: &TextEdit) {
+        self.name.shift_spans(edit);
+        self.alias.shift_spans(edit);
+        self.span.shift_spans(edit);
+    }
+}
+
+impl ShiftSpans for TypeParam {
+    fn shift_spans(&mut self, edit: &TextEdit) {
+        self.name.shift_spans(edit);
+        self.bounds.shift_spans(edit);
+        self.span.shift_spans(edit);
+    }
+}
+
+impl ShiftSpans for TypeAnnotation {
+    fn shift_spans(&mut self, edit: &TextEdit) {
+        match &mut self.kind {
+            TypeKind::Named { name, args } => {
+                name.shift_spans(edit);
+                args.shift_spans(edit);
+            }
+            TypeKind::Nullable(inner) | TypeKind::List(inner) => inner.shift_spans(edit),
+            TypeKind::Function { params, ret } => {
+                params.shift_spans(edit);
+                ret.shift_spans(edit);
+            }
+            TypeKind::Tuple(types) => types.shift_spans(edit),
+            TypeKind::Unit | TypeKind::Never | TypeKind::Inferred => {}
+        }
+        self.span.shift_spans(edit);
+    }
+}
+
+impl ShiftSpans for Block {
+    fn shift_spans(&mut self, edit: &TextEdit) {
+        self.stmts.shift_spans(edit);
+        self.expr.shift_spans(edit);
+        self.span.shift_spans(edit);
+    }
+}
+
+impl ShiftSpans for Stmt {
+    fn shift_spans(&mut self, edit: &TextEdit) {
+        match &mut self.kind {
+            StmtKind::Let { pattern, ty, value } => {
+                pattern.shift_spans(edit);
+                ty.shift_spans(edit);
+                value.shift_spans(edit);
+            }
+            StmtKind::Expr(expr) | StmtKind::Throw(expr) => expr.shift_spans(edit),
+            StmtKind::Assign { target, value } | StmtKind::CompoundAssign { target, value, .. } => {
+                target.shift_spans(edit);
+                value.shift_spans(edit);
+            }
+            StmtKind::Return(value) => value.shift_spans(edit),
+            StmtKind::For {
+                pattern,
+                iter,
+                body,
+            } => {
+                pattern.shift_spans(edit);
+                iter.shift_spans(edit);
+                body.shift_spans(edit);
+            }
+            StmtKind::While { cond, body } => {
+                cond.shift_spans(edit);
+                body.shift_spans(edit);
+            }
+            StmtKind::Loop { body } => body.shift_spans(edit),
+            StmtKind::Break | StmtKind::Continue => {}
+            StmtKind::TryCatch {
+                try_block,
+                catches,
+                finally,
+            } => {
+                try_block.shift_spans(edit);
+                catches.shift_spans(edit);
+                finally.shift_spans(edit);
+            }
+        }
+        self.span.shift_spans(edit);
+        self.trivia.shift_spans(edit);
+    }
+}
+
+impl ShiftSpans for CatchClause {
+    fn shift_spans(&mut self, edit: &TextEdit) {
+        self.exception_type.shift_spans(edit);
+        self.binding.shift_spans(edit);
+        self.body.shift_spans(edit);
+        self.span.shift_spans(edit);
+    }
+}
+
+impl ShiftSpans for Expr {
+    fn shift_spans(&mut self, edit: &TextEdit) {
+        match &mut self.kind {
+            ExprKind::Literal(_) | ExprKind::Placeholder => {}
+            ExprKind::Ident(ident) | ExprKind::ColumnShorthand(ident) => ident.shift_spans(edit),
+            ExprKind::Binary { left, right, .. } => {
+                left.shift_spans(edit);
+                right.shift_spans(edit);
+            }
+            ExprKind::Unary { expr, .. }
+            | ExprKind::Paren(expr)
+            | ExprKind::Await(expr)
+            | ExprKind::Try(expr)
+            | ExprKind::StateBinding(expr) => expr.shift_spans(edit),
+            ExprKind::Call {
+                callee,
+                args,
+                trailing_closure,
+            } => {
+                callee.shift_spans(edit);
+                for arg in args {
+                    match arg {
+                        CallArg::Positional(value) => value.shift_spans(edit),
+                        CallArg::Named { name, value, span } => {
+                            name.shift_spans(edit);
+                            value.shift_spans(edit);
+                            span.shift_spans(edit);
+                        }
+                    }
+                }
+                trailing_closure.shift_spans(edit);
+            }
+            ExprKind::Index { expr, index } | ExprKind::NullSafeIndex { expr, index } => {
+                expr.shift_spans(edit);
+                index.shift_spans(edit);
+            }
+            ExprKind::Field { expr, field } | ExprKind::NullSafeField { expr, field } => {
+                expr.shift_spans(edit);
+                field.shift_spans(edit);
+            }
+            ExprKind::If {
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                cond.shift_spans(edit);
+                then_branch.shift_spans(edit);
+                match else_branch {
+                    Some(ElseBranch::Block(block)) => block.shift_spans(edit),
+                    Some(ElseBranch::ElseIf(expr)) => expr.shift_spans(edit),
+                    None => {}
+                }
+            }
+            ExprKind::Match { expr, arms } => {
+                expr.shift_spans(edit);
+                arms.shift_spans(edit);
+            }
+            ExprKind::Lambda {
+                params,
+                return_type,
+                body,
+            } => {
+                params.shift_spans(edit);
+                return_type.shift_spans(edit);
+                body.shift_spans(edit);
+            }
+            ExprKind::Block(block) => block.shift_spans(edit),
+            ExprKind::List(elements) => elements.shift_spans(edit),
+            ExprKind::Map(entries) => {
+                for (key, value) in entries {
+                    key.shift_spans(edit);
+                    value.shift_spans(edit);
+                }
+            }
+            ExprKind::StringInterp { parts } => {
+                for part in parts {
+                    if let StringPart::Expr(expr) = part {
+                        expr.shift_spans(edit);
+                    }
+                }
+            }
+            ExprKind::StructInit { name, fields } => {
+                name.shift_spans(edit);
+                fields.shift_spans(edit);
+            }
+            ExprKind::EnumVariant {
+                enum_name,
+                variant,
+                data,
+            } => {
+                enum_name.shift_spans(edit);
+                variant.shift_spans(edit);
+                data.shift_spans(edit);
+            }
+        }
+        self.span.shift_spans(edit);
+    }
+}
+
+impl ShiftSpans for MatchArm {
+    fn shift_spans(&mut self, edit: &TextEdit) {
+        self.pattern.shift_spans(edit);
+        self.guard.shift_spans(edit);
+        self.body.shift_spans(edit);
+        self.span.shift_spans(edit);
+    }
+}
+
+impl ShiftSpans for Param {
+    fn shift_spans(&mut self, edit: &TextEdit) {
+        self.name.shift_spans(edit);
+        self.ty.shift_spans(edit);
+        self.default.shift_spans(edit);
+        self.span.shift_spans(edit);
+    }
+}
+
+impl ShiftSpans for FieldInit {
+    fn shift_spans(&mut self, edit: &TextEdit) {
+        self.name.shift_spans(edit);
+        self.value.shift_spans(edit);
+        self.span.shift_spans(edit);
+    }
+}
+
+impl ShiftSpans for Pattern {
+    fn shift_spans(&mut self, edit: &TextEdit) {
+        match &mut self.kind {
+            PatternKind::Wildcard | PatternKind::Literal(_) => {}
+            PatternKind::Ident(ident) => ident.shift_spans(edit),
+            PatternKind::Variant {
+                enum_name,
+                variant,
+                data,
+            } => {
+                enum_name.shift_spans(edit);
+                variant.shift_spans(edit);
+                data.shift_spans(edit);
+            }
+            PatternKind::Struct { name, fields } => {
+                name.shift_spans(edit);
+                fields.shift_spans(edit);
+            }
+            PatternKind::List { elements, rest } => {
+                elements.shift_spans(edit);
+                rest.shift_spans(edit);
+            }
+            PatternKind::Or(patterns) => patterns.shift_spans(edit),
+        }
+        self.span.shift_spans(edit);
+    }
+}
+
+impl ShiftSpans for FieldPattern {
+    fn shift_spans(&mut self, edit: &TextEdit) {
+        self.name.shift_spans(edit);
+        self.pattern.shift_spans(edit);
+        self.span.shift_spans(edit);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Apply `new` to an incremental parser built from `old` and check the
+    /// result against a parse from scratch
+    fn check_update(old: &str, new: &str) -> Reparse {
+        let mut incremental = IncrementalParser::new(old);
+        let reparse = incremental.update(new);
+
+        let mut full = Parser::new(new);
+        let expected = full.module();
+        assert_eq!(**incremental.module(), expected, "AST differs for {new:?}");
+
+        let errors: Vec<(String, Span)> = incremental
+            .errors()
+            .iter()
+            .map(|e| (e.to_string(), e.span))
+            .collect();
+        let expected_errors: Vec<(String, Span)> = full
+            .sorted_errors()
+            .iter()
+            .map(|e| (e.to_string(), e.span))
+            .collect();
+        assert_eq!(errors, expected_errors, "errors differ for {new:?}");
+        reparse
+    }
+
+    const SOURCE: &str = "// Math helpers\n\
+        fx add(a: Int, b: Int) -> Int { a + b }\n\
+        \n\
+        fx sub(a: Int, b: Int) -> Int {\n    // difference\n    a - b\n}\n\
+        \n\
+        struct Point { x: Int, y: Int }\n\
+        \n\
+        /// Entry point\n\
+        fx main() {\n    let p = Point { x: add(1, 2), y: sub(3, 4) }\n    println(p.x)\n}\n";
+
+    #[test]
+    fn text_edit_between() {
+        assert_eq!(TextEdit::between("abc", "abc"), None);
+        assert_eq!(
+            TextEdit::between("let x = 1", "let x = 42"),
+            Some(TextEdit {
+                start: 8,
+                old_end: 9,
+                new_end: 10
+            })
+        );
+        assert_eq!(
+            TextEdit::between("aaa", "aa"),
+            Some(TextEdit {
+                start: 2,
+                old_end: 3,
+                new_end: 2
+            })
+        );
+        // Never splits a multi-byte character
+        let edit = TextEdit::between("é", "è").unwrap();
+        assert_eq!((edit.start, edit.old_end, edit.new_end), (0, 2, 2));
+    }
+
+    #[test]
+    fn text_edit_map_span() {
+        let edit = TextEdit {
+            start: 10,
+            old_end: 12,
+            new_end: 15,
+        };
+        assert_eq!(edit.map_span(Span::new(2, 5)), Span::new(2, 5));
+        assert_eq!(edit.map_span(Span::new(20, 25)), Span::new(23, 28));
+        assert_eq!(edit.map_span(Span::new(5, 20)), Span::new(5, 23));
+        assert_eq!(edit.map_span(Span::new(11, 11)), Span::new(10, 10));
+    }
+
+    #[test]
+    fn edit_inside_function_body_reuses_other_items() {
+        let new = SOURCE.replace("a - b", "a - b - 1");
+        let reparse = check_update(SOURCE, &new);
+        assert!(reparse.tokens_changed);
+        assert_eq!(reparse.reparsed_items, 1);
+        assert_eq!(reparse.reused_items, 3);
+    }
+
+    #[test]
+    fn edit_in_first_item_falls_back_to_full_parse() {
+        let new = SOURCE.replace("fx add", "fx plus");
+        let reparse = check_update(SOURCE, &new);
+        assert_eq!(reparse.reused_items, 0);
+    }
+
+    #[test]
+    fn whitespace_and_comment_edits_keep_tokens() {
+        let new = SOURCE.replace("// difference", "// the difference of a and b");
+        let reparse = check_update(SOURCE, &new);
+        assert!(!reparse.tokens_changed);
+
+        let new = SOURCE.replace("struct Point", "\n\nstruct Point");
+        assert!(!check_update(SOURCE, &new).tokens_changed);
+    }
+
+    #[test]
+    fn edit_changing_leading_comment_of_next_item() {
+        let new = SOURCE.replace("/// Entry point", "/// Program entry point");
+        check_update(SOURCE, &new);
+    }
+
+    #[test]
+    fn inserting_and_removing_items() {
+        let new = SOURCE.replace("struct Point", "fx extra() { 1 }\n\nstruct Point");
+        let reparse = check_update(SOURCE, &new);
+        assert_eq!(reparse.reused_items + reparse.reparsed_items, 5);
+        check_update(&new, SOURCE);
+    }
+
+    #[test]
+    fn unterminated_string_reparses_rest_of_file() {
+        let new = SOURCE.replace("struct Point", "let s = \"oops\nstruct Point");
+        let reparse = check_update(SOURCE, &new);
+        assert!(reparse.tokens_changed);
+        check_update(&new, SOURCE);
+    }
+
+    #[test]
+    fn errors_before_and_after_edit_are_kept() {
+        let old = "fx a() { 1 }\nfx b( { }\nfx c() { 2 }\nfx d( { }\nfx e() { 3 }\n";
+        let new = old.replace("{ 2 }", "{ 2 + }");
+        check_update(old, &new);
+        check_update(&new, old);
+    }
+
+    #[test]
+    fn every_single_character_deletion_matches_full_parse() {
+        for (i, _) in SOURCE.char_indices() {
+            let mut new = SOURCE.to_string();
+            new.remove(i);
+            check_update(SOURCE, &new);
+        }
+    }
+}
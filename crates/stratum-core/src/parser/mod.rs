@@ -19,8 +19,10 @@
 //! ```
 
 mod error;
+mod incremental;
 
 pub use error::{ExpectedToken, ParseError, ParseErrorKind};
+pub use incremental::{IncrementalParser, Reparse, TextEdit};
 
 use crate::ast::{
     Attribute, AttributeArg, BinOp, Block, CallArg, CatchClause, Comment, CompoundOp, ElseBranch,
@@ -29,7 +31,7 @@ use crate::ast::{
     Literal, MatchArm, Module, Param, Pattern, PatternKind, Stmt, StmtKind, StringPart, StructDef,
     StructField, TopLevelItem, TopLevelLet, Trivia, TypeAnnotation, TypeKind, TypeParam, UnaryOp,
 };
-use crate::lexer::{Lexer, Span, SpannedError, Token, TokenKind};
+use crate::lexer::{raw_string_contents, Lexer, Span, SpannedError, Token, TokenKind};
 
 /// Result type for parsing operations
 pub type ParseResult<T> = Result<T, ParseError>;
@@ -55,6 +57,7 @@ pub enum ReplInput {
 }
 
 /// The Stratum parser
+#[derive(Debug, Clone)]
 pub struct Parser {
     /// All tokens from the source
     tokens: Vec<Token>,
@@ -89,16 +92,31 @@ impl Parser {
     }
 
     /// Parse an entire module (source file)
+    ///
+    /// The parser recovers at statement and item boundaries after an error, so
+    /// the returned error list contains every problem found in the file (lexer
+    /// errors included), ordered by source position.
     pub fn parse_module(source: &str) -> Result<Module, Vec<ParseError>> {
-        let mut parser = Parser::new(source);
-        let module = parser.module();
-        if parser.errors.is_empty() {
+        let (module, errors) = Self::parse_module_recovering(source);
+        if errors.is_empty() {
             Ok(module)
         } else {
-            Err(parser.errors)
+            Err(errors)
         }
     }
 
+    /// Parse an entire module, returning the partially recovered AST together
+    /// with all diagnostics
+    ///
+    /// Items and statements that failed to parse are omitted from the module;
+    /// everything around them is still available. This is what editor tooling
+    /// uses to keep symbols and completions working while a file is broken.
+    pub fn parse_module_recovering(source: &str) -> (Module, Vec<ParseError>) {
+        let mut parser = Parser::new(source);
+        let module = parser.module();
+        (module, parser.sorted_errors())
+    }
+
     /// Parse a single expression (useful for REPL)
     pub fn parse_expression(source: &str) -> Result<Expr, Vec<ParseError>> {
         let mut parser = Parser::new(source);
@@ -224,6 +242,35 @@ impl Parser {
         }
     }
 
+    /// Merge lexer and parser errors into a single position-ordered list
+    ///
+    /// Parse errors reported at the position of a lexer error are dropped in
+    /// favour of the lexer error, which carries the real cause.
+    fn sorted_errors(&self) -> Vec<ParseError> {
+        let mut errors: Vec<ParseError> = self
+            .errors
+            .iter()
+            .filter(|err| {
+                !self
+                    .lex_errors
+                    .iter()
+                    .any(|lex| lex.span.start == err.span.start)
+            })
+            .cloned()
+            .collect();
+        errors.extend(self.lex_errors.iter().map(|lex_err| {
+            ParseError::new(
+                ParseErrorKind::UnexpectedToken {
+                    found: TokenKind::Error,
+                    expected: ExpectedToken::Description(lex_err.error.to_string()),
+                },
+                lex_err.span,
+            )
+        }));
+        errors.sort_by_key(|err| err.span.start);
+        errors
+    }
+
     /// Get all errors (both lex and parse errors)
     #[must_use]
     pub fn all_errors(&self) -> Vec<ParseError> {
@@ -272,20 +319,9 @@ impl Parser {
     /// Collect trivia tokens (comments, newlines) into pending_comments
     fn collect_trivia(&mut self) {
         while self.position < self.tokens.len() && self.current().kind.is_trivia() {
-            let token = &self.tokens[self.position];
-            match &token.kind {
-                TokenKind::LineComment => {
-                    self.pending_comments
-                        .push(Comment::line(token.lexeme.clone(), token.span));
-                }
-                TokenKind::BlockComment => {
-                    self.pending_comments
-                        .push(Comment::block(token.lexeme.clone(), token.span));
-                }
-                TokenKind::Newline => {
-                    // Just skip newlines - they're handled implicitly
-                }
-                _ => {}
+            // Newlines are just skipped - they're handled implicitly
+            if let Some(comment) = comment_from_token(&self.tokens[self.position]) {
+                self.pending_comments.push(comment);
             }
             self.position += 1;
         }
@@ -368,7 +404,17 @@ impl Parser {
     }
 
     /// Record an error but continue parsing
+    ///
+    /// A second error at the same position is almost always a cascade of the
+    /// first, so it is not reported.
     fn error(&mut self, error: ParseError) {
+        if self
+            .errors
+            .last()
+            .is_some_and(|last| last.span.start == error.span.start)
+        {
+            return;
+        }
         self.errors.push(error);
     }
 
@@ -386,13 +432,7 @@ impl Parser {
 
         let mut top_level = Vec::new();
         while !self.is_eof() {
-            match self.top_level_item() {
-                Ok(tl_item) => top_level.push(tl_item),
-                Err(e) => {
-                    self.error(e);
-                    self.synchronize();
-                }
-            }
+            self.top_level_item_recovering(&mut top_level);
         }
 
         let end = self.current().span.end;
@@ -404,6 +444,19 @@ impl Parser {
         )
     }
 
+    /// Parse the next top-level item into `out`, recovering from any error
+    fn top_level_item_recovering(&mut self, out: &mut Vec<TopLevelItem>) {
+        let before = self.position;
+        match self.top_level_item() {
+            Ok(tl_item) => out.push(tl_item),
+            Err(e) => {
+                self.error(e);
+                self.skip_if_stalled(before);
+                self.synchronize();
+            }
+        }
+    }
+
     /// Parse inner attributes: #![attr] #![attr(args)]
     ///
     /// Inner attributes apply to the enclosing module, not to the following item.
@@ -467,8 +520,8 @@ impl Parser {
         self.collect_trivia();
         let trivia = self.take_trivia();
 
-        // Check for `let` at top level
-        if self.check(TokenKind::Let) {
+        // Check for `let` or `const` at top level
+        if self.check(TokenKind::Let) || self.check(TokenKind::Const) {
             return self.top_level_let_with_trivia(trivia);
         }
 
@@ -498,12 +551,19 @@ impl Parser {
         )
     }
 
-    /// Parse a top-level let declaration with trivia
+    /// Parse a top-level let or const declaration with trivia
     fn top_level_let_with_trivia(&mut self, trivia: Trivia) -> ParseResult<TopLevelItem> {
         let start = self.current().span.start;
-        self.expect(TokenKind::Let)?;
+        let is_const = self.eat(TokenKind::Const).is_some();
 
-        let pattern = self.pattern()?;
+        // A const binds a single name; let accepts any pattern
+        let pattern = if is_const {
+            let ident = self.expect_ident()?;
+            Pattern::new(PatternKind::Ident(ident.clone()), ident.span)
+        } else {
+            self.expect(TokenKind::Let)?;
+            self.pattern()?
+        };
 
         // Optional type annotation
         let ty = if self.eat(TokenKind::Colon).is_some() {
@@ -518,13 +578,12 @@ impl Parser {
         let end = value.span.end;
         self.eat(TokenKind::Semicolon);
 
-        Ok(TopLevelItem::Let(TopLevelLet::with_trivia(
-            pattern,
-            ty,
-            value,
-            Span::new(start, end),
-            trivia,
-        )))
+        let let_decl = TopLevelLet::with_trivia(pattern, ty, value, Span::new(start, end), trivia);
+        Ok(TopLevelItem::Let(if is_const {
+            let_decl.into_const()
+        } else {
+            let_decl
+        }))
     }
 
     /// Parse a top-level statement (expression statements, etc.) with trivia
@@ -577,34 +636,8 @@ impl Parser {
 
         let kind = match self.current_kind() {
             TokenKind::Fx | TokenKind::Async => self.function_item(attributes)?,
-            TokenKind::Struct => {
-                if !attributes.is_empty() {
-                    return Err(ParseError::new(
-                        ParseErrorKind::UnexpectedToken {
-                            found: TokenKind::Hash,
-                            expected: ExpectedToken::Description(
-                                "attributes not supported on structs yet".to_string(),
-                            ),
-                        },
-                        attributes[0].span,
-                    ));
-                }
-                self.struct_item()?
-            }
-            TokenKind::Enum => {
-                if !attributes.is_empty() {
-                    return Err(ParseError::new(
-                        ParseErrorKind::UnexpectedToken {
-                            found: TokenKind::Hash,
-                            expected: ExpectedToken::Description(
-                                "attributes not supported on enums yet".to_string(),
-                            ),
-                        },
-                        attributes[0].span,
-                    ));
-                }
-                self.enum_item()?
-            }
+            TokenKind::Struct => self.struct_item(attributes)?,
+            TokenKind::Enum => self.enum_item(attributes)?,
             TokenKind::Interface => {
                 if !attributes.is_empty() {
                     return Err(ParseError::new(
@@ -717,11 +750,29 @@ impl Parser {
         Ok(Attribute::new(name, args, Span::new(start, end)))
     }
 
-    /// Parse attribute arguments: ident, ident = expr, ...
+    /// Parse attribute arguments: ident, ident = expr, expr, ...
     fn attribute_args(&mut self) -> ParseResult<Vec<AttributeArg>> {
         let mut args = Vec::new();
 
         while !self.check(TokenKind::RParen) && !self.is_eof() {
+            // Anything but a lone identifier or `name =` is a positional value
+            let is_named = matches!(
+                self.current().kind,
+                TokenKind::Ident | TokenKind::UnicodeIdent
+            ) && self.peek().map_or(true, |next| {
+                matches!(
+                    next.kind,
+                    TokenKind::Eq | TokenKind::Comma | TokenKind::RParen
+                )
+            });
+            if !is_named {
+                args.push(AttributeArg::Value(Box::new(self.expression()?)));
+                if !self.eat(TokenKind::Comma).is_some() {
+                    break;
+                }
+                continue;
+            }
+
             let name = self.expect_ident()?;
 
             let arg = if self.eat(TokenKind::Eq).is_some() {
@@ -848,7 +899,7 @@ impl Parser {
     }
 
     /// Parse a struct definition
-    fn struct_item(&mut self) -> ParseResult<ItemKind> {
+    fn struct_item(&mut self, attributes: Vec<Attribute>) -> ParseResult<ItemKind> {
         self.expect(TokenKind::Struct)?;
         let name = self.expect_ident()?;
 
@@ -872,12 +923,9 @@ impl Parser {
                 .unwrap_or(name.span.start),
         );
 
-        Ok(ItemKind::Struct(StructDef::new(
-            name,
-            type_params,
-            fields,
-            span,
-        )))
+        let mut def = StructDef::new(name, type_params, fields, span);
+        def.attributes = attributes;
+        Ok(ItemKind::Struct(def))
     }
 
     /// Parse struct fields
@@ -901,7 +949,7 @@ impl Parser {
     }
 
     /// Parse an enum definition
-    fn enum_item(&mut self) -> ParseResult<ItemKind> {
+    fn enum_item(&mut self, attributes: Vec<Attribute>) -> ParseResult<ItemKind> {
         let start = self.current().span.start;
         self.expect(TokenKind::Enum)?;
         let name = self.expect_ident()?;
@@ -924,12 +972,9 @@ impl Parser {
             .map(|t| t.span.end)
             .unwrap_or(start);
 
-        Ok(ItemKind::Enum(EnumDef::new(
-            name,
-            type_params,
-            variants,
-            Span::new(start, end),
-        )))
+        let mut def = EnumDef::new(name, type_params, variants, Span::new(start, end));
+        def.attributes = attributes;
+        Ok(ItemKind::Enum(def))
     }
 
     /// Parse enum variants
@@ -1382,6 +1427,7 @@ impl Parser {
         let mut trailing_expr = None;
 
         while !self.check(TokenKind::RBrace) && !self.is_eof() {
+            let before = self.position;
             match self.statement_or_expr() {
                 Ok(StmtOrExpr::Stmt(stmt)) => stmts.push(stmt),
                 Ok(StmtOrExpr::Expr(expr)) => {
@@ -1389,14 +1435,15 @@ impl Parser {
                     if self.check(TokenKind::RBrace) {
                         trailing_expr = Some(expr);
                     } else if self.eat(TokenKind::Semicolon).is_some() {
+                        let expr_start = expr.span.start;
                         stmts.push(Stmt::new(
                             StmtKind::Expr(expr),
                             Span::new(
-                                start,
+                                expr_start,
                                 self.tokens
                                     .get(self.position.saturating_sub(1))
                                     .map(|t| t.span.end)
-                                    .unwrap_or(start),
+                                    .unwrap_or(expr_start),
                             ),
                         ));
                     } else {
@@ -1414,6 +1461,7 @@ impl Parser {
                 }
                 Err(e) => {
                     self.error(e);
+                    self.skip_if_stalled(before);
                     self.synchronize_in_block();
                 }
             }
@@ -1835,9 +1883,9 @@ impl Parser {
                     Err(ParseError::new(ParseErrorKind::ExpectedPattern, span))
                 }
             }
-            TokenKind::StringStart | TokenKind::MultiLineStringStart => {
-                // String literal pattern (regular or multiline)
-                let lit = self.string_literal()?;
+            TokenKind::StringStart | TokenKind::MultiLineStringStart | TokenKind::RawString => {
+                // String literal pattern (regular, multiline, or raw)
+                let lit = self.literal()?;
                 let span = lit.span;
                 if let ExprKind::Literal(l) = lit.kind {
                     Ok(Pattern::new(PatternKind::Literal(l), span))
@@ -2235,6 +2283,7 @@ impl Parser {
             TokenKind::True | TokenKind::False => self.bool_literal(),
             TokenKind::Null => self.null_literal(),
             TokenKind::StringStart | TokenKind::MultiLineStringStart => self.string_literal(),
+            TokenKind::RawString => self.raw_string_literal(),
 
             // Identifiers and struct init
             TokenKind::Ident | TokenKind::UnicodeIdent => self.ident_or_struct_init(),
@@ -2320,6 +2369,7 @@ impl Parser {
             TokenKind::True | TokenKind::False => self.bool_literal(),
             TokenKind::Null => self.null_literal(),
             TokenKind::StringStart | TokenKind::MultiLineStringStart => self.string_literal(),
+            TokenKind::RawString => self.raw_string_literal(),
             _ => Err(ParseError::new(
                 ParseErrorKind::ExpectedExpression,
                 self.current().span,
@@ -2402,6 +2452,16 @@ impl Parser {
         }
     }
 
+    /// Parse a raw string literal (r"..." or r#"..."#)
+    fn raw_string_literal(&mut self) -> ParseResult<Expr> {
+        let token = self.expect(TokenKind::RawString)?;
+        let contents = raw_string_contents(&token.lexeme).unwrap_or_default();
+        Ok(Expr::new(
+            ExprKind::Literal(Literal::String(contents.to_string())),
+            token.span,
+        ))
+    }
+
     /// Parse column shorthand (.column_name)
     /// This is syntactic sugar for accessing columns in DataFrame operations
     fn column_shorthand(&mut self) -> ParseResult<Expr> {
@@ -2822,7 +2882,10 @@ impl Parser {
 
         // Try to detect if this is a map literal
         // Map literals have the form { key: value, ... } where key is usually a string
-        if self.check(TokenKind::StringStart) || self.check(TokenKind::MultiLineStringStart) {
+        if self.check(TokenKind::StringStart)
+            || self.check(TokenKind::MultiLineStringStart)
+            || self.check(TokenKind::RawString)
+        {
             // Likely a map literal
             return self.map_literal_after_brace(start);
         }
@@ -2836,6 +2899,7 @@ impl Parser {
         let mut trailing_expr = None;
 
         while !self.check(TokenKind::RBrace) && !self.is_eof() {
+            let before = self.position;
             match self.statement_or_expr() {
                 Ok(StmtOrExpr::Stmt(stmt)) => stmts.push(stmt),
                 Ok(StmtOrExpr::Expr(expr)) => {
@@ -2860,6 +2924,7 @@ impl Parser {
                 }
                 Err(e) => {
                     self.error(e);
+                    self.skip_if_stalled(before);
                     self.synchronize_in_block();
                 }
             }
@@ -3021,30 +3086,61 @@ impl Parser {
 
     // ==================== Error Recovery ====================
 
-    /// Synchronize parser state after an error
+    /// Make sure a failed parse consumed at least one token
+    ///
+    /// Errors raised on a token that also starts a statement (e.g. `for` at the
+    /// top level) would otherwise make synchronization stop in place forever.
+    fn skip_if_stalled(&mut self, before: usize) {
+        if self.position == before && !self.is_eof() {
+            self.advance();
+        }
+    }
+
+    /// Synchronize parser state after an error at the top level
+    ///
+    /// Skips to the next item or statement boundary. Braced bodies that follow
+    /// the error are skipped as a whole, so a broken function signature does not
+    /// cause its body to be reparsed as top-level code.
     fn synchronize(&mut self) {
+        let mut depth = 0usize;
         while !self.is_eof() {
-            // Stop at statement boundaries
-            if self.current_kind() == TokenKind::Semicolon {
-                self.advance();
-                return;
-            }
-
-            // Stop at keywords that start new statements/items
             match self.current_kind() {
+                TokenKind::LBrace => depth += 1,
+                TokenKind::RBrace => {
+                    self.advance();
+                    // Either the end of a body we skipped, or the closing brace of
+                    // the item the error occurred in
+                    if depth <= 1 {
+                        return;
+                    }
+                    depth -= 1;
+                    continue;
+                }
+                TokenKind::Semicolon if depth == 0 => {
+                    self.advance();
+                    return;
+                }
+                // Stop at keywords that start new statements/items
                 TokenKind::Fx
+                | TokenKind::Async
+                | TokenKind::Hash
                 | TokenKind::Struct
                 | TokenKind::Enum
                 | TokenKind::Interface
                 | TokenKind::Impl
                 | TokenKind::Import
                 | TokenKind::Let
+                | TokenKind::Const
                 | TokenKind::For
                 | TokenKind::While
                 | TokenKind::If
                 | TokenKind::Return
                 | TokenKind::Try
-                | TokenKind::Throw => return,
+                | TokenKind::Throw
+                    if depth == 0 =>
+                {
+                    return;
+                }
                 _ => {}
             }
 
@@ -3053,14 +3149,24 @@ impl Parser {
     }
 
     /// Synchronize within a block
+    ///
+    /// Stops before the block's closing brace; braces opened after the error
+    /// are balanced so a broken nested expression does not end the block early.
     fn synchronize_in_block(&mut self) {
-        while !self.is_eof() && !self.check(TokenKind::RBrace) {
-            if self.current_kind() == TokenKind::Semicolon {
-                self.advance();
-                return;
-            }
-
+        let mut depth = 0usize;
+        while !self.is_eof() {
             match self.current_kind() {
+                TokenKind::LBrace => depth += 1,
+                TokenKind::RBrace => {
+                    if depth == 0 {
+                        return;
+                    }
+                    depth -= 1;
+                }
+                TokenKind::Semicolon if depth == 0 => {
+                    self.advance();
+                    return;
+                }
                 TokenKind::Let
                 | TokenKind::For
                 | TokenKind::While
@@ -3069,7 +3175,11 @@ impl Parser {
                 | TokenKind::Break
                 | TokenKind::Continue
                 | TokenKind::Try
-                | TokenKind::Throw => return,
+                | TokenKind::Throw
+                    if depth == 0 =>
+                {
+                    return;
+                }
                 _ => {}
             }
 
@@ -3080,6 +3190,15 @@ impl Parser {
 
 // ==================== Helper Functions ====================
 
+/// Convert a comment token into an AST comment
+fn comment_from_token(token: &Token) -> Option<Comment> {
+    match token.kind {
+        TokenKind::LineComment => Some(Comment::line(token.lexeme.clone(), token.span)),
+        TokenKind::BlockComment => Some(Comment::block(token.lexeme.clone(), token.span)),
+        _ => None,
+    }
+}
+
 /// Parse an integer from a lexeme
 fn parse_int(lexeme: &str, kind: &TokenKind) -> Result<i64, String> {
     let clean = lexeme.replace('_', "");
@@ -3106,6 +3225,7 @@ fn parse_int(lexeme: &str, kind: &TokenKind) -> Result<i64, String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ast::Derive;
 
     fn parse_expr(source: &str) -> Result<Expr, Vec<ParseError>> {
         Parser::parse_expression(source)
@@ -3143,6 +3263,46 @@ mod tests {
         assert!(matches!(expr.kind, ExprKind::Literal(Literal::String(s)) if s == "hello"));
     }
 
+    #[test]
+    fn parse_raw_string_literals() {
+        let expr = parse_expr(r#"r"C:\temp\{x}""#).unwrap();
+        assert!(matches!(expr.kind, ExprKind::Literal(Literal::String(s)) if s == r"C:\temp\{x}"));
+
+        let expr = parse_expr(r##"r#"a "quoted" word"#"##).unwrap();
+        assert!(
+            matches!(expr.kind, ExprKind::Literal(Literal::String(s)) if s == r#"a "quoted" word"#)
+        );
+    }
+
+    #[test]
+    fn parse_raw_string_pattern() {
+        let module = Parser::parse_module(r#"fx f(s) { match s { r"\n" => 1, _ => 0 } }"#).unwrap();
+        assert_eq!(module.items().len(), 1);
+    }
+
+    #[test]
+    fn parse_indented_multiline_string() {
+        let expr = parse_expr("\"\"\"\n    first\n      second\n    \"\"\"").unwrap();
+        assert!(
+            matches!(expr.kind, ExprKind::Literal(Literal::String(s)) if s == "first\n  second")
+        );
+    }
+
+    #[test]
+    fn parse_numeric_separators() {
+        let expr = parse_expr("1_000_000").unwrap();
+        assert!(matches!(
+            expr.kind,
+            ExprKind::Literal(Literal::Int(1_000_000))
+        ));
+        let expr = parse_expr("0xFF_FF").unwrap();
+        assert!(matches!(expr.kind, ExprKind::Literal(Literal::Int(0xFFFF))));
+        let expr = parse_expr("1_000.000_5").unwrap();
+        assert!(
+            matches!(expr.kind, ExprKind::Literal(Literal::Float(f)) if (f - 1000.0005).abs() < 1e-9)
+        );
+    }
+
     #[test]
     fn parse_binary_expressions() {
         let expr = parse_expr("1 + 2").unwrap();
@@ -3250,6 +3410,20 @@ mod tests {
         assert!(matches!(module.top_level[0], TopLevelItem::Let(_)));
     }
 
+    #[test]
+    fn parse_top_level_const() {
+        let module = parse_module("const LIMIT: Int = 10 * 2").unwrap();
+        let TopLevelItem::Let(decl) = &module.top_level[0] else {
+            panic!("expected a top-level declaration");
+        };
+        assert!(decl.is_const);
+        assert!(decl.ty.is_some());
+        assert_eq!(decl.name().map(|n| n.name.as_str()), Some("LIMIT"));
+
+        // A const binds a single name, not a pattern
+        assert!(parse_module("const (a, b) = (1, 2)").is_err());
+    }
+
     #[test]
     fn parse_top_level_statement() {
         let module = parse_module("println(\"Hello\")").unwrap();
@@ -3471,6 +3645,23 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_derive_attributes_on_struct_and_enum() {
+        let module =
+            parse_module("#[derive(Eq, Show)]\nstruct P { x: Int }\n#[derive(Json)]\nenum E { A }")
+                .unwrap();
+        let items = module.items();
+        let ItemKind::Struct(s) = &items[0].kind else {
+            panic!("expected struct");
+        };
+        assert_eq!(s.derives(), vec![Derive::Eq, Derive::Show]);
+        let ItemKind::Enum(e) = &items[1].kind else {
+            panic!("expected enum");
+        };
+        assert_eq!(e.attributes[0].name.name, "derive");
+        assert_eq!(e.derives(), vec![Derive::Json]);
+    }
+
     #[test]
     fn parse_mixed_inner_and_outer_attributes() {
         let source = "#![interpret]\n#[compile]\nfx main() {}";
@@ -3628,6 +3819,70 @@ mod tests {
         assert!(result.is_err());
     }
 
+    // ==================== Error Recovery Tests ====================
+
+    fn function_names(module: &Module) -> Vec<String> {
+        module
+            .top_level
+            .iter()
+            .filter_map(|tl| match tl {
+                TopLevelItem::Item(Item {
+                    kind: ItemKind::Function(func),
+                    ..
+                }) => Some(func.name.name.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn recover_reports_every_broken_item() {
+        let source = "fx a() { let x = }\nfx b() { 1 }\nfx c() { let y = }\n";
+        let errors = parse_module(source).unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].span.start < errors[1].span.start);
+
+        let (module, errors) = Parser::parse_module_recovering(source);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(function_names(&module), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn recover_multiple_statements_in_block() {
+        let errors = parse_module("fx main() { let a = ; let b = 2; let c = ; }").unwrap_err();
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn recover_broken_signature_skips_body() {
+        let source = "fx bad(a: ) {\n    let x = 1\n    x\n}\nfx good() { 1 }\n";
+        let (module, errors) = Parser::parse_module_recovering(source);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(function_names(&module), vec!["good"]);
+    }
+
+    #[test]
+    fn recover_from_statement_keyword_at_top_level() {
+        // `for` can't start a top-level expression; recovery must still make progress
+        let (module, errors) = Parser::parse_module_recovering("for\nfx ok() {}\n");
+        assert!(!errors.is_empty());
+        assert_eq!(function_names(&module), vec!["ok"]);
+    }
+
+    #[test]
+    fn recover_includes_lex_errors_in_order() {
+        let errors = parse_module("let a = 1 @ 2\nlet b = ").unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(
+            &errors[0].kind,
+            ParseErrorKind::UnexpectedToken {
+                found: TokenKind::Error,
+                expected: ExpectedToken::Description(_),
+            }
+        ));
+        assert!(errors[0].span.start < errors[1].span.start);
+    }
+
     // ==================== Trailing Closure Tests ====================
 
     #[test]
@@ -3742,4 +3997,29 @@ mod tests {
             panic!("expected Call");
         }
     }
+
+    #[test]
+    fn parse_doc_comment_attached_to_item() {
+        let module = Parser::parse_module(
+            "// not documentation\n/// Add two numbers.\n///\n/// See [sub].\nfx add(a: Int, b: Int) -> Int { a + b }\n\nfx sub(a: Int, b: Int) -> Int { a - b }",
+        )
+        .unwrap();
+        let TopLevelItem::Item(item) = &module.top_level[0] else {
+            panic!("expected item");
+        };
+        let ItemKind::Function(func) = &item.kind else {
+            panic!("expected function");
+        };
+        let doc = func.trivia.doc.as_ref().unwrap();
+        assert_eq!(doc.summary, "Add two numbers.");
+        assert_eq!(doc.links(), ["sub"]);
+
+        let TopLevelItem::Item(item) = &module.top_level[1] else {
+            panic!("expected item");
+        };
+        let ItemKind::Function(func) = &item.kind else {
+            panic!("expected function");
+        };
+        assert!(func.trivia.doc.is_none());
+    }
 }
@@ -0,0 +1,436 @@
+//! CPU profiling for the Stratum programming language
+//!
+//! The profiler counts the instructions the VM executes and, every
+//! `interval` instructions, samples the call stack. Each sample is
+//! attributed to the functions and lines on the stack, so a report shows
+//! where execution spends its time in Stratum code rather than in the VM.
+//!
+//! Profiles can be written as:
+//! - A plain text summary of the hottest functions and lines
+//! - Folded stacks, the input of `flamegraph.pl` and `inferno`
+//! - Speedscope JSON, for <https://www.speedscope.app>
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+/// Instructions executed between samples by default
+pub const DEFAULT_SAMPLE_INTERVAL: u64 = 1000;
+
+/// Number of functions and lines listed in a summary
+const SUMMARY_ROWS: usize = 15;
+
+/// A function and line on a sampled call stack
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ProfileFrame {
+    /// Function name
+    pub function: String,
+    /// Source file (if known)
+    pub file: Option<String>,
+    /// Line executing in the function
+    pub line: u32,
+}
+
+impl ProfileFrame {
+    /// Name of the frame in folded stacks and flamegraphs
+    fn label(&self, with_lines: bool) -> String {
+        if with_lines {
+            format!("{}:{}", self.function, self.line)
+        } else {
+            self.function.clone()
+        }
+    }
+}
+
+/// Samples the VM's call stack every `interval` instructions
+#[derive(Debug, Clone)]
+pub struct CpuProfiler {
+    /// Instructions executed between samples
+    interval: u64,
+    /// Instructions left until the next sample
+    countdown: u64,
+    /// Instructions executed in total
+    instructions: u64,
+    /// Distinct frames, indexed by the stacks in `samples`
+    frames: Vec<ProfileFrame>,
+    /// Index of each frame in `frames`
+    frame_ids: HashMap<ProfileFrame, usize>,
+    /// Sample count of each distinct stack (outermost frame first)
+    samples: HashMap<Vec<usize>, u64>,
+}
+
+impl Default for CpuProfiler {
+    fn default() -> Self {
+        Self::new(DEFAULT_SAMPLE_INTERVAL)
+    }
+}
+
+impl CpuProfiler {
+    /// Create a profiler sampling every `interval` instructions (at least 1)
+    pub fn new(interval: u64) -> Self {
+        let interval = interval.max(1);
+        Self {
+            interval,
+            countdown: interval,
+            instructions: 0,
+            frames: Vec::new(),
+            frame_ids: HashMap::new(),
+            samples: HashMap::new(),
+        }
+    }
+
+    /// Instructions executed between samples
+    pub fn interval(&self) -> u64 {
+        self.interval
+    }
+
+    /// Instructions executed while profiling
+    pub fn instructions(&self) -> u64 {
+        self.instructions
+    }
+
+    /// Number of samples taken
+    pub fn sample_count(&self) -> u64 {
+        self.samples.values().sum()
+    }
+
+    /// Count an instruction; returns true when the stack should be sampled
+    #[inline]
+    pub fn tick(&mut self) -> bool {
+        self.instructions += 1;
+        self.countdown -= 1;
+        if self.countdown == 0 {
+            self.countdown = self.interval;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Record a sampled call stack, outermost frame first
+    pub fn record(&mut self, stack: impl IntoIterator<Item = ProfileFrame>) {
+        let ids: Vec<usize> = stack
+            .into_iter()
+            .map(|frame| match self.frame_ids.get(&frame) {
+                Some(&id) => id,
+                None => {
+                    let id = self.frames.len();
+                    self.frame_ids.insert(frame.clone(), id);
+                    self.frames.push(frame);
+                    id
+                }
+            })
+            .collect();
+        if !ids.is_empty() {
+            *self.samples.entry(ids).or_insert(0) += 1;
+        }
+    }
+
+    /// Sampled stacks with their counts, most frequent first and then by
+    /// the stack itself so output is stable
+    fn sorted_samples(&self) -> Vec<(&Vec<usize>, u64)> {
+        let mut samples: Vec<_> = self.samples.iter().map(|(s, &n)| (s, n)).collect();
+        samples.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        samples
+    }
+
+    /// Sample counts per function: (name, self, total), hottest first
+    pub fn function_totals(&self) -> Vec<(String, u64, u64)> {
+        let mut totals: HashMap<&str, (u64, u64)> = HashMap::new();
+        for (stack, count) in &self.samples {
+            let mut seen: Vec<&str> = Vec::new();
+            for &id in stack {
+                let name = self.frames[id].function.as_str();
+                // Recursive calls count once towards the total
+                if !seen.contains(&name) {
+                    seen.push(name);
+                    totals.entry(name).or_default().1 += count;
+                }
+            }
+            if let Some(&leaf) = stack.last() {
+                totals
+                    .entry(self.frames[leaf].function.as_str())
+                    .or_default()
+                    .0 += count;
+            }
+        }
+
+        let mut totals: Vec<(String, u64, u64)> = totals
+            .into_iter()
+            .map(|(name, (own, total))| (name.to_string(), own, total))
+            .collect();
+        totals.sort_by(|a, b| b.1.cmp(&a.1).then(b.2.cmp(&a.2)).then(a.0.cmp(&b.0)));
+        totals
+    }
+
+    /// Sample counts of the lines executing when sampled, hottest first
+    pub fn line_totals(&self) -> Vec<(ProfileFrame, u64)> {
+        let mut totals: HashMap<usize, u64> = HashMap::new();
+        for (stack, count) in &self.samples {
+            if let Some(&leaf) = stack.last() {
+                *totals.entry(leaf).or_insert(0) += count;
+            }
+        }
+
+        let mut totals: Vec<(ProfileFrame, u64)> = totals
+            .into_iter()
+            .map(|(id, count)| (self.frames[id].clone(), count))
+            .collect();
+        totals.sort_by(|a, b| {
+            b.1.cmp(&a.1)
+                .then_with(|| a.0.function.cmp(&b.0.function))
+                .then(a.0.line.cmp(&b.0.line))
+        });
+        totals
+    }
+
+    /// Folded stacks, one `outer;inner count` line per distinct stack
+    ///
+    /// With `with_lines`, frames are named `function:line`, giving a
+    /// flamegraph with one box per line instead of per function.
+    pub fn to_folded(&self, with_lines: bool) -> String {
+        let mut counts: HashMap<String, u64> = HashMap::new();
+        for (stack, count) in &self.samples {
+            let labels: Vec<String> = stack
+                .iter()
+                .map(|&id| self.frames[id].label(with_lines))
+                .collect();
+            *counts.entry(labels.join(";")).or_insert(0) += count;
+        }
+
+        let mut lines: Vec<(String, u64)> = counts.into_iter().collect();
+        lines.sort();
+        let mut output = String::new();
+        for (stack, count) in lines {
+            let _ = writeln!(output, "{} {}", stack, count);
+        }
+        output
+    }
+
+    /// A speedscope sampled profile, weighted by instructions executed
+    pub fn to_speedscope(&self, name: &str) -> String {
+        let frames: Vec<serde_json::Value> = self
+            .frames
+            .iter()
+            .map(|frame| {
+                let mut value = serde_json::json!({
+                    "name": frame.function,
+                    "line": frame.line,
+                });
+                if let Some(ref file) = frame.file {
+                    value["file"] = serde_json::Value::String(file.clone());
+                }
+                value
+            })
+            .collect();
+
+        let samples = self.sorted_samples();
+        let stacks: Vec<&Vec<usize>> = samples.iter().map(|(stack, _)| *stack).collect();
+        let weights: Vec<u64> = samples
+            .iter()
+            .map(|(_, count)| count * self.interval)
+            .collect();
+
+        let profile = serde_json::json!({
+            "$schema": "https://www.speedscope.app/file-format-schema.json",
+            "exporter": format!("stratum {}", env!("CARGO_PKG_VERSION")),
+            "name": name,
+            "activeProfileIndex": 0,
+            "shared": { "frames": frames },
+            "profiles": [{
+                "type": "sampled",
+                "name": name,
+                "unit": "none",
+                "startValue": 0,
+                "endValue": self.instructions,
+                "samples": stacks,
+                "weights": weights,
+            }],
+        });
+        serde_json::to_string_pretty(&profile).unwrap_or_default()
+    }
+
+    /// A plain text report of the hottest functions and lines
+    pub fn summary(&self) -> String {
+        let total = self.sample_count();
+        let mut output = String::new();
+
+        output.push_str("CPU Profile\n");
+        output.push_str("===========\n\n");
+        let _ = writeln!(
+            output,
+            "{} instructions, {} samples (1 per {} instructions)",
+            self.instructions, total, self.interval
+        );
+
+        if total == 0 {
+            output
+                .push_str("\nNo samples recorded; lower the interval to profile short programs.\n");
+            return output;
+        }
+
+        let percent = |count: u64| count as f64 * 100.0 / total as f64;
+
+        output.push_str("\nFunctions:\n");
+        output.push_str("     Self    Total  Function\n");
+        for (name, own, inclusive) in self.function_totals().into_iter().take(SUMMARY_ROWS) {
+            let _ = writeln!(
+                output,
+                "  {:>6.1}%  {:>6.1}%  {}",
+                percent(own),
+                percent(inclusive),
+                name
+            );
+        }
+
+        output.push_str("\nLines:\n");
+        output.push_str("     Self  Location\n");
+        for (frame, count) in self.line_totals().into_iter().take(SUMMARY_ROWS) {
+            let location = match frame.file {
+                Some(ref file) => format!("{}:{}", file, frame.line),
+                None => format!("line {}", frame.line),
+            };
+            let _ = writeln!(
+                output,
+                "  {:>6.1}%  {} ({})",
+                percent(count),
+                frame.function,
+                location
+            );
+        }
+
+        output
+    }
+}
+
+/// CPU profile output format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProfileFormat {
+    /// Plain text summary
+    #[default]
+    Summary,
+    /// Folded stacks for flamegraph tooling
+    Folded,
+    /// Speedscope JSON
+    Speedscope,
+}
+
+impl std::str::FromStr for ProfileFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "summary" | "text" => Ok(ProfileFormat::Summary),
+            "folded" | "flamegraph" => Ok(ProfileFormat::Folded),
+            "speedscope" | "json" => Ok(ProfileFormat::Speedscope),
+            _ => Err(format!("Unknown profile format: {}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(function: &str, line: u32) -> ProfileFrame {
+        ProfileFrame {
+            function: function.to_string(),
+            file: Some("main.strat".to_string()),
+            line,
+        }
+    }
+
+    fn sample_profile() -> CpuProfiler {
+        let mut profiler = CpuProfiler::new(10);
+        for _ in 0..3 {
+            profiler.record([frame("<script>", 9), frame("main", 5), frame("fib", 2)]);
+        }
+        profiler.record([frame("<script>", 9), frame("main", 6)]);
+        profiler
+    }
+
+    #[test]
+    fn test_tick_samples_every_interval() {
+        let mut profiler = CpuProfiler::new(3);
+        let sampled: Vec<bool> = (0..7).map(|_| profiler.tick()).collect();
+        assert_eq!(sampled, vec![false, false, true, false, false, true, false]);
+        assert_eq!(profiler.instructions(), 7);
+
+        // An interval of 0 would never sample
+        assert_eq!(CpuProfiler::new(0).interval(), 1);
+    }
+
+    #[test]
+    fn test_function_totals() {
+        let profiler = sample_profile();
+        assert_eq!(profiler.sample_count(), 4);
+
+        let totals = profiler.function_totals();
+        assert_eq!(totals[0], ("fib".to_string(), 3, 3));
+        assert!(totals.contains(&("main".to_string(), 1, 4)));
+        assert!(totals.contains(&("<script>".to_string(), 0, 4)));
+    }
+
+    #[test]
+    fn test_recursion_counts_once_in_total() {
+        let mut profiler = CpuProfiler::new(1);
+        profiler.record([frame("fib", 2), frame("fib", 3), frame("fib", 2)]);
+        assert_eq!(profiler.function_totals(), vec![("fib".to_string(), 1, 1)]);
+    }
+
+    #[test]
+    fn test_line_totals() {
+        let profiler = sample_profile();
+        let lines = profiler.line_totals();
+        assert_eq!(lines[0], (frame("fib", 2), 3));
+        assert_eq!(lines[1], (frame("main", 6), 1));
+    }
+
+    #[test]
+    fn test_folded_output() {
+        let profiler = sample_profile();
+        assert_eq!(
+            profiler.to_folded(false),
+            "<script>;main 1\n<script>;main;fib 3\n"
+        );
+        assert_eq!(
+            profiler.to_folded(true),
+            "<script>:9;main:5;fib:2 3\n<script>:9;main:6 1\n"
+        );
+    }
+
+    #[test]
+    fn test_speedscope_output() {
+        let profiler = sample_profile();
+        let json: serde_json::Value =
+            serde_json::from_str(&profiler.to_speedscope("main.strat")).unwrap();
+
+        let frames = json["shared"]["frames"].as_array().unwrap();
+        assert_eq!(frames.len(), 4);
+        assert_eq!(frames[2]["name"], "fib");
+        assert_eq!(frames[2]["file"], "main.strat");
+
+        let profile = &json["profiles"][0];
+        assert_eq!(profile["type"], "sampled");
+        assert_eq!(profile["samples"][0], serde_json::json!([0, 1, 2]));
+        assert_eq!(profile["weights"], serde_json::json!([30, 10]));
+    }
+
+    #[test]
+    fn test_summary() {
+        let summary = sample_profile().summary();
+        assert!(summary.contains("4 samples (1 per 10 instructions)"));
+        assert!(summary.contains("75.0%  fib"));
+        assert!(summary.contains("fib (main.strat:2)"));
+
+        let empty = CpuProfiler::new(10).summary();
+        assert!(empty.contains("No samples recorded"));
+    }
+
+    #[test]
+    fn test_profile_format_from_str() {
+        assert_eq!("folded".parse(), Ok(ProfileFormat::Folded));
+        assert_eq!("Speedscope".parse(), Ok(ProfileFormat::Speedscope));
+        assert_eq!("summary".parse(), Ok(ProfileFormat::Summary));
+        assert!("svg".parse::<ProfileFormat>().is_err());
+    }
+}
@@ -2,11 +2,31 @@
 //!
 //! This module provides functionality for discovering and running test functions
 //! marked with the `#[test]` attribute.
+//!
+//! A function with `#[test_case(args...)]` attributes runs once per attribute,
+//! with the attribute's values as its leading arguments. Any parameters after
+//! those are filled by the `#[fixture]` function of the same name, which runs
+//! before the test; `#[fixture(teardown = name)]` names a function that is
+//! passed the fixture's value once the test has finished, pass or fail.
+//!
+//! Each test runs in its own VM with its print output captured, so tests can
+//! run in parallel across worker threads and still be reported in the order
+//! they were discovered.
+//!
+//! Code examples in doc comments run as doctests: each example is appended
+//! to its module's definitions and passes if it runs without an error.
 
-use crate::ast::{Function, Module};
+use crate::ast::{
+    AttributeArg, Block, CallArg, Expr, ExprKind, Function, Ident, Item, ItemKind, Literal, Module,
+    Pattern, PatternKind, Span, Stmt, StmtKind, TopLevelItem, TopLevelLet, Trivia,
+};
 use crate::bytecode::Compiler;
 use crate::coverage::CoverageCollector;
-use crate::vm::VM;
+use crate::parser::Parser;
+use crate::vm::{with_output_capture, RuntimeErrorKind, VM};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
 use std::time::{Duration, Instant};
 
 /// Result of running a single test
@@ -22,6 +42,8 @@ pub struct TestResult {
     pub error: Option<String>,
     /// Whether this test was expected to panic
     pub should_panic: bool,
+    /// Lines the test printed
+    pub output: Vec<String>,
 }
 
 impl TestResult {
@@ -34,6 +56,7 @@ impl TestResult {
             duration,
             error: None,
             should_panic: false,
+            output: Vec::new(),
         }
     }
 
@@ -46,6 +69,7 @@ impl TestResult {
             duration,
             error: Some(error),
             should_panic: false,
+            output: Vec::new(),
         }
     }
 }
@@ -86,6 +110,18 @@ impl TestSummary {
         self.results.push(result);
     }
 
+    /// Add the results and coverage of another run, such as another file's
+    pub fn merge(&mut self, other: TestSummary) {
+        for result in other.results {
+            self.add(result);
+        }
+        match (&mut self.coverage, other.coverage) {
+            (Some(coverage), Some(other)) => coverage.merge(&other),
+            (coverage @ None, other) => *coverage = other,
+            (Some(_), None) => {}
+        }
+    }
+
     /// Check if all tests passed
     #[must_use]
     pub fn all_passed(&self) -> bool {
@@ -102,27 +138,205 @@ pub struct TestCase {
     pub function: Function,
     /// Whether the test should expect a panic
     pub should_panic: bool,
+    /// Values for the leading parameters (from `#[test_case(...)]`)
+    pub args: Vec<Expr>,
+    /// Fixtures in the module, for the parameters after `args`
+    pub fixtures: Vec<Fixture>,
+    /// The doc comment example this test runs, for doctests
+    pub doctest: Option<Doctest>,
+}
+
+/// A code example from a doc comment, run as a test
+#[derive(Debug, Clone)]
+pub struct Doctest {
+    /// The example's source
+    pub source: String,
+    /// The module's definitions, which the example runs after
+    pub definitions: Arc<[TopLevelItem]>,
+}
+
+/// A `#[fixture]` function, providing the argument of the same name to tests
+#[derive(Debug, Clone)]
+pub struct Fixture {
+    /// The fixture function
+    pub function: Function,
+    /// The function passed the fixture's value after the test, if any
+    pub teardown: Option<Function>,
+}
+
+impl Fixture {
+    /// The parameter name this fixture provides
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.function.name.name
+    }
 }
 
 /// Discovers test functions from a module
+///
+/// A function with `#[test_case(...)]` attributes gives one test per case,
+/// named `test_name[label]`, where the label is the case's `name = "..."`
+/// argument or else its values.
 pub fn discover_tests(module: &Module) -> Vec<TestCase> {
+    let functions: Vec<&Function> = module
+        .items()
+        .into_iter()
+        .filter_map(|item| match &item.kind {
+            ItemKind::Function(func) => Some(func),
+            _ => None,
+        })
+        .collect();
+    let fixtures = discover_fixtures(&functions);
+
     let mut tests = Vec::new();
+    for func in functions.iter().filter(|func| func.is_test()) {
+        if func.test_cases().next().is_none() {
+            tests.push(TestCase {
+                name: func.name.name.clone(),
+                function: (*func).clone(),
+                should_panic: func.should_panic(),
+                args: Vec::new(),
+                fixtures: fixtures_for(func, 0, &fixtures),
+                doctest: None,
+            });
+            continue;
+        }
+
+        for case in func.test_cases() {
+            // A bare `should_panic` marks the case; other arguments are values
+            let mut args = Vec::new();
+            let mut should_panic = func.should_panic();
+            for arg in &case.args {
+                match arg {
+                    AttributeArg::Value(value) => args.push(value.as_ref().clone()),
+                    AttributeArg::Ident(ident) if ident.name == "should_panic" => {
+                        should_panic = true;
+                    }
+                    AttributeArg::Ident(ident) => args.push(Expr::ident(&ident.name, ident.span)),
+                    AttributeArg::NameValue { .. } => {}
+                }
+            }
+            let label = match case.named_value("name").map(|value| &value.kind) {
+                Some(ExprKind::Literal(Literal::String(label))) => label.clone(),
+                _ => args
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            };
+            tests.push(TestCase {
+                name: format!("{}[{label}]", func.name.name),
+                function: (*func).clone(),
+                should_panic,
+                fixtures: fixtures_for(func, args.len(), &fixtures),
+                args,
+                doctest: None,
+            });
+        }
+    }
+
+    tests
+}
 
+/// Discovers the code examples in a module's doc comments
+///
+/// Each example gives a test named `item[doctest N]`, where `item` is the
+/// documented function or type (an impl block's examples use its type).
+pub fn discover_doctests(module: &Module) -> Vec<TestCase> {
+    let definitions: Arc<[TopLevelItem]> = module
+        .top_level
+        .iter()
+        .filter(|tl_item| match tl_item {
+            TopLevelItem::Item(_) => true,
+            TopLevelItem::Let(let_decl) => let_decl.is_const,
+            TopLevelItem::Statement(_) => false,
+        })
+        .cloned()
+        .collect();
+
+    let mut documented: Vec<(String, &Trivia, Span)> = Vec::new();
     for item in module.items() {
-        if let crate::ast::ItemKind::Function(func) = &item.kind {
-            if func.is_test() {
-                tests.push(TestCase {
-                    name: func.name.name.clone(),
-                    function: func.clone(),
-                    should_panic: func.should_panic(),
-                });
+        match &item.kind {
+            ItemKind::Function(f) => documented.push((f.name.name.clone(), &f.trivia, f.span)),
+            ItemKind::Struct(s) => documented.push((s.name.name.clone(), &s.trivia, s.span)),
+            ItemKind::Enum(e) => documented.push((e.name.name.clone(), &e.trivia, e.span)),
+            ItemKind::Interface(i) => documented.push((i.name.name.clone(), &i.trivia, i.span)),
+            ItemKind::Impl(def) => {
+                documented.push((def.target.to_string(), &def.trivia, def.span));
             }
+            ItemKind::Import(_) => {}
+        }
+    }
+
+    let mut tests = Vec::new();
+    for (item_name, trivia, span) in documented {
+        let Some(doc) = &trivia.doc else {
+            continue;
+        };
+        for (index, source) in doc.examples.iter().cloned().enumerate() {
+            let name = format!("{item_name}[doctest {}]", index + 1);
+            // Doctests have no function of their own; this one stands in for
+            // the example where a test function is expected
+            let function = Function::new(
+                Ident::new(&name, span),
+                Vec::new(),
+                Vec::new(),
+                None,
+                Block::empty(span),
+                false,
+                Vec::new(),
+                span,
+            );
+            tests.push(TestCase {
+                name,
+                function,
+                should_panic: false,
+                args: Vec::new(),
+                fixtures: Vec::new(),
+                doctest: Some(Doctest {
+                    source,
+                    definitions: Arc::clone(&definitions),
+                }),
+            });
         }
     }
 
     tests
 }
 
+/// The fixtures for a test's parameters after its first `skip`
+fn fixtures_for(func: &Function, skip: usize, fixtures: &[Fixture]) -> Vec<Fixture> {
+    func.params
+        .iter()
+        .skip(skip)
+        .filter_map(|param| fixtures.iter().find(|f| f.name() == param.name.name))
+        .cloned()
+        .collect()
+}
+
+/// Collect the `#[fixture]` functions and their teardowns
+fn discover_fixtures(functions: &[&Function]) -> Vec<Fixture> {
+    functions
+        .iter()
+        .filter(|func| func.is_fixture())
+        .map(|func| {
+            let teardown = func
+                .attributes
+                .iter()
+                .filter(|a| a.is_fixture())
+                .find_map(|a| match &a.named_value("teardown")?.kind {
+                    ExprKind::Ident(name) => Some(name.name.as_str()),
+                    _ => None,
+                })
+                .and_then(|name| functions.iter().find(|f| f.name.name == name));
+            Fixture {
+                function: (*func).clone(),
+                teardown: teardown.map(|f| (*f).clone()),
+            }
+        })
+        .collect()
+}
+
 /// Filters tests by a pattern
 pub fn filter_tests(tests: Vec<TestCase>, filter: Option<&str>) -> Vec<TestCase> {
     match filter {
@@ -135,17 +349,22 @@ pub fn filter_tests(tests: Vec<TestCase>, filter: Option<&str>) -> Vec<TestCase>
 }
 
 /// Run a single test function
+///
+/// Fixture teardowns run after the test whether or not it passed; a failing
+/// teardown fails the test.
 pub fn run_test(test: &TestCase, source_name: &str, vm: &mut VM) -> TestResult {
     let start = Instant::now();
 
-    // Compile the test function
-    let compile_result =
-        Compiler::with_source(source_name.to_string()).compile_test_function(&test.function);
+    // Compile the test function, its fixtures and the call to it
+    let compile_result = test_program(test).and_then(|program| {
+        Compiler::with_source(source_name.to_string())
+            .compile_module(&program)
+            .map_err(|errors| errors.iter().map(|e| format!("{e}")).collect())
+    });
 
     let function = match compile_result {
         Ok(f) => f,
-        Err(errors) => {
-            let error_msgs: Vec<String> = errors.iter().map(|e| format!("{e}")).collect();
+        Err(error_msgs) => {
             return TestResult::failed(
                 test.name.clone(),
                 start.elapsed(),
@@ -154,11 +373,18 @@ pub fn run_test(test: &TestCase, source_name: &str, vm: &mut VM) -> TestResult {
         }
     };
 
-    // Run the test
+    // Run the test, undo its mocks, then tear down whichever fixtures were
+    // set up
     let result = vm.run(function);
+    vm.restore_mocks();
+    let teardown = run_teardowns(test, source_name, vm);
     let duration = start.elapsed();
 
-    match (result, test.should_panic) {
+    let result = match (result, test.should_panic) {
+        // A timeout is never the panic a test expects
+        (Err(e), _) if matches!(e.kind, RuntimeErrorKind::Timeout(_)) => {
+            TestResult::failed(test.name.clone(), duration, e.kind.to_string())
+        }
         (Ok(_), false) => TestResult::passed(test.name.clone(), duration),
         (Ok(_), true) => TestResult::failed(
             test.name.clone(),
@@ -172,7 +398,151 @@ pub fn run_test(test: &TestCase, source_name: &str, vm: &mut VM) -> TestResult {
             result
         }
         (Err(e), false) => TestResult::failed(test.name.clone(), duration, e.to_string()),
+    };
+
+    match teardown {
+        Err(error) if result.passed => TestResult::failed(
+            test.name.clone(),
+            duration,
+            format!("Teardown failed: {error}"),
+        ),
+        _ => result,
+    }
+}
+
+/// The global holding a fixture's value while its test runs
+fn fixture_global(fixture: &Fixture) -> String {
+    format!("__fixture_{}", fixture.name())
+}
+
+/// Build the program that runs a test: the test and fixture definitions,
+/// a global per fixture holding its value, and the call to the test
+fn test_program(test: &TestCase) -> Result<Module, Vec<String>> {
+    if let Some(doctest) = &test.doctest {
+        return doctest_program(doctest);
+    }
+
+    let func = &test.function;
+    let span = func.span;
+    let mut top_level = Vec::new();
+    let mut define = |function: &Function| {
+        let defined = top_level.iter().any(|item| {
+            matches!(item, TopLevelItem::Item(Item { kind: ItemKind::Function(f), .. })
+                if f.name.name == function.name.name)
+        });
+        if !defined {
+            top_level.push(TopLevelItem::Item(Item::new(
+                ItemKind::Function(function.clone()),
+                function.span,
+            )));
+        }
+    };
+    define(func);
+    for fixture in &test.fixtures {
+        define(&fixture.function);
+        if let Some(teardown) = &fixture.teardown {
+            define(teardown);
+        }
     }
+
+    if test.args.len() > func.params.len() {
+        return Err(vec![format!(
+            "test case has {} values but `{}` takes {} parameters",
+            test.args.len(),
+            func.name.name,
+            func.params.len()
+        )]);
+    }
+
+    let mut args = test.args.clone();
+    for param in &func.params[test.args.len()..] {
+        let Some(fixture) = test.fixtures.iter().find(|f| f.name() == param.name.name) else {
+            return Err(vec![format!(
+                "no fixture provides parameter `{}`",
+                param.name.name
+            )]);
+        };
+        let global = fixture_global(fixture);
+        top_level.push(TopLevelItem::Let(TopLevelLet::new(
+            Pattern::new(PatternKind::Ident(Ident::new(&global, span)), span),
+            None,
+            call(fixture.name(), Vec::new(), span),
+            span,
+        )));
+        args.push(Expr::ident(global, span));
+    }
+
+    top_level.push(TopLevelItem::Statement(Stmt::new(
+        StmtKind::Expr(call(&func.name.name, args, span)),
+        span,
+    )));
+    Ok(Module::new(Vec::new(), top_level, span))
+}
+
+/// Build the program that runs a doctest: the module's definitions, then
+/// the example
+fn doctest_program(doctest: &Doctest) -> Result<Module, Vec<String>> {
+    let example = Parser::parse_module(&doctest.source)
+        .map_err(|errors| errors.iter().map(ToString::to_string).collect::<Vec<_>>())?;
+    let mut top_level = doctest.definitions.to_vec();
+    top_level.extend(example.top_level);
+    Ok(Module::new(
+        example.inner_attributes,
+        top_level,
+        example.span,
+    ))
+}
+
+/// Pass each set-up fixture's value to its teardown, last fixture first
+fn run_teardowns(test: &TestCase, source_name: &str, vm: &mut VM) -> Result<(), String> {
+    let mut first_error = None;
+    for fixture in test.fixtures.iter().rev() {
+        let Some(teardown) = &fixture.teardown else {
+            continue;
+        };
+        let global = fixture_global(fixture);
+        if !vm.globals().contains_key(&global) {
+            continue;
+        }
+
+        let span = teardown.span;
+        let program = Module::new(
+            Vec::new(),
+            vec![TopLevelItem::Statement(Stmt::new(
+                StmtKind::Expr(call(
+                    &teardown.name.name,
+                    vec![Expr::ident(global, span)],
+                    span,
+                )),
+                span,
+            ))],
+            span,
+        );
+        let result = match Compiler::with_source(source_name.to_string()).compile_module(&program) {
+            Ok(function) => vm.run(function).map(drop).map_err(|e| e.to_string()),
+            Err(errors) => Err(errors
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")),
+        };
+        if let Err(error) = result {
+            first_error.get_or_insert(error);
+        }
+    }
+    first_error.map_or(Ok(()), Err)
+}
+
+/// A call to the named function
+fn call(name: &str, args: Vec<Expr>, span: Span) -> Expr {
+    Expr::new(
+        ExprKind::Call {
+            callee: Box::new(Expr::ident(name, span)),
+            args: args.into_iter().map(CallArg::Positional).collect(),
+            trailing_closure: None,
+        },
+        span,
+    )
 }
 
 /// Test runner that executes tests and collects results
@@ -183,6 +553,10 @@ pub struct TestRunner {
     verbose: bool,
     /// Whether to collect coverage data
     coverage: bool,
+    /// Number of worker threads running tests
+    jobs: usize,
+    /// Time each test may run before it fails
+    timeout: Option<Duration>,
 }
 
 impl TestRunner {
@@ -193,6 +567,8 @@ impl TestRunner {
             filter: None,
             verbose: false,
             coverage: false,
+            jobs: 1,
+            timeout: None,
         }
     }
 
@@ -217,6 +593,20 @@ impl TestRunner {
         self
     }
 
+    /// Run tests on `jobs` worker threads (at least one)
+    #[must_use]
+    pub fn with_jobs(mut self, jobs: usize) -> Self {
+        self.jobs = jobs.max(1);
+        self
+    }
+
+    /// Fail any test still running after `timeout`
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
     /// Run all tests in a module
     pub fn run_module(&self, module: &Module, source_name: &str) -> TestSummary {
         let tests = discover_tests(module);
@@ -225,42 +615,87 @@ impl TestRunner {
     }
 
     /// Run a list of test cases
+    ///
+    /// Results are in the order of `tests`, however many workers ran them.
     pub fn run_tests(&self, tests: &[TestCase], source_name: &str) -> TestSummary {
+        let workers = self.jobs.min(tests.len());
+        let outcomes = if workers <= 1 {
+            tests
+                .iter()
+                .map(|test| self.run_isolated(test, source_name))
+                .collect()
+        } else {
+            self.run_parallel(tests, source_name, workers)
+        };
+
         let mut summary = TestSummary::new();
         let mut aggregated_coverage = if self.coverage {
             Some(CoverageCollector::new())
         } else {
             None
         };
-
-        // First compile and run the module to register all functions
-        // This is needed so test functions can call helper functions
-
-        for test in tests {
-            // Create a fresh VM for each test
-            let mut vm = VM::new();
-
-            // Enable coverage if requested
-            if self.coverage {
-                vm.enable_coverage();
-            }
-
-            let result = run_test(test, source_name, &mut vm);
+        for (result, test_coverage) in outcomes {
             summary.add(result);
-
-            // Collect coverage data from this test
-            if self.coverage {
-                if let Some(test_coverage) = vm.take_coverage() {
-                    if let Some(ref mut agg) = aggregated_coverage {
-                        agg.merge(&test_coverage);
-                    }
-                }
+            if let (Some(agg), Some(test_coverage)) = (&mut aggregated_coverage, test_coverage) {
+                agg.merge(&test_coverage);
             }
         }
 
         summary.coverage = aggregated_coverage;
         summary
     }
+
+    /// Run tests on `workers` threads, each taking the next unstarted test,
+    /// and return the outcomes in test order
+    fn run_parallel(
+        &self,
+        tests: &[TestCase],
+        source_name: &str,
+        workers: usize,
+    ) -> Vec<(TestResult, Option<CoverageCollector>)> {
+        let next = AtomicUsize::new(0);
+        let mut outcomes: Vec<_> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..workers)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut finished = Vec::new();
+                        loop {
+                            let index = next.fetch_add(1, Ordering::Relaxed);
+                            let Some(test) = tests.get(index) else {
+                                break;
+                            };
+                            finished.push((index, self.run_isolated(test, source_name)));
+                        }
+                        finished
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap_or_else(std::panic::resume_unwind))
+                .collect()
+        });
+
+        outcomes.sort_by_key(|(index, _)| *index);
+        outcomes.into_iter().map(|(_, outcome)| outcome).collect()
+    }
+
+    /// Run one test in a fresh VM, capturing its output and coverage
+    fn run_isolated(
+        &self,
+        test: &TestCase,
+        source_name: &str,
+    ) -> (TestResult, Option<CoverageCollector>) {
+        let mut vm = VM::new();
+        if self.coverage {
+            vm.enable_coverage();
+        }
+        vm.set_timeout(self.timeout);
+
+        let (mut result, output) = with_output_capture(|| run_test(test, source_name, &mut vm));
+        result.output = output.stdout;
+        (result, vm.take_coverage())
+    }
 }
 
 impl Default for TestRunner {
@@ -269,10 +704,167 @@ impl Default for TestRunner {
     }
 }
 
+/// Machine-readable test report format, for CI systems
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestReportFormat {
+    /// JUnit XML
+    Junit,
+    /// JSON document with one entry per test
+    Json,
+    /// Test Anything Protocol, version 13
+    Tap,
+}
+
+impl std::str::FromStr for TestReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "junit" | "xml" => Ok(TestReportFormat::Junit),
+            "json" => Ok(TestReportFormat::Json),
+            "tap" => Ok(TestReportFormat::Tap),
+            _ => Err(format!("Unknown test report format: {}", s)),
+        }
+    }
+}
+
+/// Generate a report of a test run, naming the suite `suite` (usually the
+/// test file)
+pub fn generate_test_report(
+    summary: &TestSummary,
+    suite: &str,
+    format: TestReportFormat,
+) -> String {
+    match format {
+        TestReportFormat::Junit => generate_junit_report(summary, suite),
+        TestReportFormat::Json => generate_json_report(summary, suite),
+        TestReportFormat::Tap => generate_tap_report(summary),
+    }
+}
+
+/// Generate a JUnit XML report
+fn generate_junit_report(summary: &TestSummary, suite: &str) -> String {
+    let suite = escape_xml(suite);
+    let seconds = summary.duration.as_secs_f64();
+    let mut output = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    output.push_str(&format!(
+        "<testsuites tests=\"{}\" failures=\"{}\" time=\"{seconds:.6}\">\n",
+        summary.total, summary.failed
+    ));
+    output.push_str(&format!(
+        "  <testsuite name=\"{suite}\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"0\" time=\"{seconds:.6}\">\n",
+        summary.total, summary.failed
+    ));
+
+    for result in &summary.results {
+        output.push_str(&format!(
+            "    <testcase name=\"{}\" classname=\"{suite}\" time=\"{:.6}\"",
+            escape_xml(&result.name),
+            result.duration.as_secs_f64()
+        ));
+        if result.passed && result.output.is_empty() {
+            output.push_str("/>\n");
+            continue;
+        }
+        output.push_str(">\n");
+        if !result.passed {
+            let message = escape_xml(result.error.as_deref().unwrap_or("Test failed"));
+            output.push_str(&format!(
+                "      <failure message=\"{message}\">{message}</failure>\n"
+            ));
+        }
+        if !result.output.is_empty() {
+            output.push_str(&format!(
+                "      <system-out>{}</system-out>\n",
+                escape_xml(&result.output.join("\n"))
+            ));
+        }
+        output.push_str("    </testcase>\n");
+    }
+
+    output.push_str("  </testsuite>\n</testsuites>\n");
+    output
+}
+
+/// Escape text for an XML attribute or element
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Generate a JSON report
+fn generate_json_report(summary: &TestSummary, suite: &str) -> String {
+    let tests: Vec<serde_json::Value> = summary
+        .results
+        .iter()
+        .map(|result| {
+            serde_json::json!({
+                "name": result.name,
+                "status": if result.passed { "passed" } else { "failed" },
+                "duration_ms": result.duration.as_secs_f64() * 1000.0,
+                "should_panic": result.should_panic,
+                "error": result.error,
+                "output": result.output,
+            })
+        })
+        .collect();
+    let report = serde_json::json!({
+        "suite": suite,
+        "total": summary.total,
+        "passed": summary.passed,
+        "failed": summary.failed,
+        "duration_ms": summary.duration.as_secs_f64() * 1000.0,
+        "tests": tests,
+    });
+    serde_json::to_string_pretty(&report).unwrap_or_default()
+}
+
+/// Generate a TAP version 13 report, with each test's details in a YAML block
+fn generate_tap_report(summary: &TestSummary) -> String {
+    let mut output = format!("TAP version 13\n1..{}\n", summary.total);
+    for (number, result) in summary.results.iter().enumerate() {
+        let status = if result.passed { "ok" } else { "not ok" };
+        output.push_str(&format!("{status} {} - {}\n", number + 1, result.name));
+        output.push_str("  ---\n");
+        output.push_str(&format!(
+            "  duration_ms: {:.3}\n",
+            result.duration.as_secs_f64() * 1000.0
+        ));
+        // JSON strings are valid YAML scalars and keep the block on one line
+        if let Some(error) = result.error.as_ref().filter(|_| !result.passed) {
+            output.push_str(&format!(
+                "  message: {}\n",
+                serde_json::Value::from(error.as_str())
+            ));
+        }
+        if !result.output.is_empty() {
+            output.push_str("  output:\n");
+            for line in &result.output {
+                output.push_str(&format!(
+                    "    - {}\n",
+                    serde_json::Value::from(line.as_str())
+                ));
+            }
+        }
+        output.push_str("  ...\n");
+    }
+    output
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::parser::Parser;
 
     #[test]
     fn test_discover_tests() {
@@ -337,4 +929,249 @@ mod tests {
         assert_eq!(tests.len(), 1);
         assert!(tests[0].should_panic);
     }
+
+    #[test]
+    fn test_parallel_results_keep_test_order() {
+        let source = r#"
+            #[test]
+            fx test_a() { println("a") }
+
+            #[test]
+            fx test_b() { assert(false) }
+
+            #[test]
+            fx test_c() { println("c") }
+
+            #[test]
+            fx test_d() { println("d") }
+
+            #[test]
+            fx test_e() { println("e") }
+        "#;
+
+        let module = Parser::parse_module(source).unwrap();
+        let summary = TestRunner::new()
+            .with_jobs(3)
+            .run_module(&module, "parallel.strat");
+
+        let names: Vec<&str> = summary.results.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["test_a", "test_b", "test_c", "test_d", "test_e"]);
+        assert_eq!(summary.passed, 4);
+        assert_eq!(summary.failed, 1);
+        assert!(!summary.results[1].passed);
+        for result in summary.results.iter().filter(|r| r.passed) {
+            assert_eq!(result.output, [result.name["test_".len()..].to_string()]);
+        }
+    }
+
+    #[test]
+    fn test_timeout_fails_long_running_test() {
+        let source = r#"
+            #[test(should_panic)]
+            fx test_forever() {
+                let i = 0
+                while true {
+                    i = i + 1
+                }
+            }
+
+            #[test]
+            fx test_quick() {
+                assert(true)
+            }
+        "#;
+
+        let module = Parser::parse_module(source).unwrap();
+        let summary = TestRunner::new()
+            .with_jobs(2)
+            .with_timeout(Some(Duration::from_millis(50)))
+            .run_module(&module, "timeout.strat");
+
+        assert!(!summary.results[0].passed);
+        let error = summary.results[0].error.as_deref().unwrap();
+        assert!(error.contains("timed out after 50ms"), "{error}");
+        assert!(summary.results[1].passed);
+    }
+
+    #[test]
+    fn test_discover_test_cases() {
+        let source = r#"
+            #[test_case(1, 2, 3)]
+            #[test_case(2, 2, 4, name = "twos")]
+            #[test_case(1, 1, 3, should_panic)]
+            fx test_add(a: Int, b: Int, expected: Int) {
+                assert_eq(a + b, expected)
+            }
+        "#;
+
+        let module = Parser::parse_module(source).unwrap();
+        let tests = discover_tests(&module);
+
+        let names: Vec<&str> = tests.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["test_add[1, 2, 3]", "test_add[twos]", "test_add[1, 1, 3]"]
+        );
+        assert_eq!(tests[0].args.len(), 3);
+        assert!(!tests[1].should_panic);
+        assert!(tests[2].should_panic);
+
+        let summary = TestRunner::new().run_tests(&tests, "cases.strat");
+        assert_eq!(summary.passed, 3, "{:?}", summary.results);
+    }
+
+    #[test]
+    fn test_doctests() {
+        let source = r#"
+            /// Add two numbers
+            ///
+            /// ## Example
+            /// ```stratum
+            /// assert_eq(add(1, 2), 3)
+            /// ```
+            ///
+            /// ```stratum
+            /// assert_eq(add(1, 2), 4)
+            /// ```
+            fx add(a: Int, b: Int) -> Int {
+                a + b
+            }
+
+            /// ```stratum
+            /// let p = Point { x: 3, y: 4 }
+            /// assert_eq(p.x + p.y, 7)
+            /// ```
+            struct Point { x: Int, y: Int }
+
+            #[test]
+            fx test_add() {
+                assert_eq(add(2, 2), 4)
+            }
+        "#;
+
+        let module = Parser::parse_module(source).unwrap();
+        let tests = discover_doctests(&module);
+
+        let names: Vec<&str> = tests.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["add[doctest 1]", "add[doctest 2]", "Point[doctest 1]"]
+        );
+
+        let summary = TestRunner::new().run_tests(&tests, "lib.strat");
+        let passed: Vec<bool> = summary.results.iter().map(|r| r.passed).collect();
+        assert_eq!(passed, [true, false, true], "{:?}", summary.results);
+    }
+
+    #[test]
+    fn test_summary_merge() {
+        let mut summary = TestSummary::new();
+        summary.add(TestResult::passed("a".to_string(), Duration::ZERO));
+        let mut other = TestSummary::new();
+        other.add(TestResult::failed(
+            "b".to_string(),
+            Duration::ZERO,
+            "boom".to_string(),
+        ));
+
+        summary.merge(other);
+        assert_eq!((summary.total, summary.passed, summary.failed), (2, 1, 1));
+        assert_eq!(summary.results[1].name, "b");
+    }
+
+    #[test]
+    fn test_fixtures_are_injected_and_torn_down() {
+        let source = r#"
+            #[fixture(teardown = close_conn)]
+            fx conn() {
+                println("open");
+                42
+            }
+
+            fx close_conn(conn) {
+                println("close")
+            }
+
+            #[test]
+            fx test_uses_conn(conn) {
+                assert_eq(conn, 42)
+            }
+
+            #[test_case(2)]
+            fx test_fails_with_conn(n: Int, conn) {
+                assert_eq(n, 3)
+            }
+
+            #[test]
+            fx test_missing(other) { }
+        "#;
+
+        let module = Parser::parse_module(source).unwrap();
+        let summary = TestRunner::new().run_module(&module, "fixtures.strat");
+
+        assert!(summary.results[0].passed, "{:?}", summary.results[0]);
+        assert_eq!(summary.results[0].output, ["open", "close"]);
+
+        // Teardown still runs when the test fails
+        assert!(!summary.results[1].passed);
+        assert_eq!(summary.results[1].output, ["open", "close"]);
+
+        let error = summary.results[2].error.as_deref().unwrap();
+        assert!(
+            error.contains("no fixture provides parameter `other`"),
+            "{error}"
+        );
+    }
+
+    fn sample_summary() -> TestSummary {
+        let mut summary = TestSummary::new();
+        let mut passed = TestResult::passed("test_ok".to_string(), Duration::from_millis(2));
+        passed.output = vec!["hello".to_string()];
+        summary.add(passed);
+        summary.add(TestResult::failed(
+            "test_bad[<a & b>]".to_string(),
+            Duration::from_millis(5),
+            "expected 1, got \"2\"".to_string(),
+        ));
+        summary
+    }
+
+    #[test]
+    fn test_junit_report() {
+        let report = generate_test_report(&sample_summary(), "math.strat", TestReportFormat::Junit);
+        assert!(report.starts_with("<?xml"));
+        assert!(report.contains(r#"<testsuite name="math.strat" tests="2" failures="1""#));
+        assert!(
+            report.contains(r#"<testcase name="test_ok" classname="math.strat" time="0.002000">"#)
+        );
+        assert!(report.contains("<system-out>hello</system-out>"));
+        assert!(report.contains(r#"name="test_bad[&lt;a &amp; b&gt;]""#));
+        assert!(report.contains(r#"<failure message="expected 1, got &quot;2&quot;">"#));
+    }
+
+    #[test]
+    fn test_json_report() {
+        let report = generate_test_report(&sample_summary(), "math.strat", TestReportFormat::Json);
+        let json: serde_json::Value = serde_json::from_str(&report).unwrap();
+        assert_eq!(json["suite"], "math.strat");
+        assert_eq!(json["failed"], 1);
+        assert_eq!(json["tests"][0]["status"], "passed");
+        assert_eq!(json["tests"][0]["output"][0], "hello");
+        assert_eq!(json["tests"][1]["error"], "expected 1, got \"2\"");
+        assert_eq!(json["tests"][1]["duration_ms"], 5.0);
+    }
+
+    #[test]
+    fn test_tap_report() {
+        let report = generate_test_report(&sample_summary(), "math.strat", TestReportFormat::Tap);
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines[0], "TAP version 13");
+        assert_eq!(lines[1], "1..2");
+        assert_eq!(lines[2], "ok 1 - test_ok");
+        assert!(lines.contains(&"    - \"hello\""));
+        assert!(lines.contains(&"not ok 2 - test_bad[<a & b>]"));
+        assert!(lines.contains(&r#"  message: "expected 1, got \"2\"""#));
+        assert_eq!("tap".parse(), Ok(TestReportFormat::Tap));
+        assert!("yaml".parse::<TestReportFormat>().is_err());
+    }
 }
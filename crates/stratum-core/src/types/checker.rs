@@ -6,10 +6,10 @@
 use std::collections::HashMap;
 
 use crate::ast::{
-    BinOp, Block, CompoundOp, ElseBranch, EnumDef, EnumVariant, EnumVariantData, Expr, ExprKind,
-    FieldInit, Function, Ident, ImplDef, InterfaceDef, Item, ItemKind, Literal, Module, Param,
-    Pattern, PatternKind, Stmt, StmtKind, StringPart, StructDef, TopLevelItem, TopLevelLet,
-    TypeAnnotation, TypeKind, UnaryOp,
+    resolve_derives, Attribute, BinOp, Block, CompoundOp, Derive, ElseBranch, EnumDef, EnumVariant,
+    EnumVariantData, Expr, ExprKind, FieldInit, Function, Ident, ImplDef, InterfaceDef, Item,
+    ItemKind, Literal, Module, Param, Pattern, PatternKind, Stmt, StmtKind, StringPart, StructDef,
+    TopLevelItem, TopLevelLet, TypeAnnotation, TypeKind, UnaryOp, INDEX_METHOD,
 };
 use crate::lexer::Span;
 
@@ -39,6 +39,24 @@ pub struct TypeChecker {
 
     /// Whether we are currently inside an async function
     in_async_context: bool,
+
+    /// Types of the names bound by patterns and parameters, keyed by the
+    /// span of the name
+    binding_types: Vec<(Span, Type)>,
+
+    /// Names read in expressions and the bindings they resolved to
+    resolved_names: Vec<ResolvedName>,
+}
+
+/// A name read in an expression and what the type checker resolved it to
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedName {
+    /// Span of the identifier
+    pub span: Span,
+    /// Type of the binding the name refers to
+    pub ty: Type,
+    /// Whether the binding is a constant
+    pub is_const: bool,
 }
 
 /// Result of type checking
@@ -49,6 +67,14 @@ pub struct TypeCheckResult {
 
     /// Whether type checking succeeded (no errors)
     pub success: bool,
+
+    /// Inferred type of every name bound by a `let`, pattern or parameter,
+    /// keyed by the span of the name and ordered by position
+    pub binding_types: Vec<(Span, Type)>,
+
+    /// Every name read in an expression that resolved to a binding, ordered
+    /// by position
+    pub resolved_names: Vec<ResolvedName>,
 }
 
 impl Default for TypeChecker {
@@ -67,6 +93,8 @@ impl TypeChecker {
             errors: Vec::new(),
             type_params_in_scope: HashMap::new(),
             in_async_context: false,
+            binding_types: Vec::new(),
+            resolved_names: Vec::new(),
         };
         checker.register_builtins();
         checker
@@ -176,14 +204,18 @@ impl TypeChecker {
             "Json",
             "Toml",
             "Yaml",
+            "Csv",
             "Base64",
             "Url",
             "Gzip",
             "Zip",
+            "Zstd",
+            "Tar",
             "DateTime",
             "Duration",
             "Time",
             "Regex",
+            "Template",
             "Hash",
             "Uuid",
             "Random",
@@ -191,6 +223,7 @@ impl TypeChecker {
             "Math",
             "Input",
             "Log",
+            "I18n",
             "System",
             "Db",
             "Tcp",
@@ -211,26 +244,96 @@ impl TypeChecker {
 
     /// Type check a complete module
     pub fn check_module(&mut self, module: &Module) -> TypeCheckResult {
+        self.check_module_in_package(module, &[])
+    }
+
+    /// Type check a module together with the other modules of its package
+    ///
+    /// The functions, types and impls declared in `siblings` are visible to
+    /// `module`, with the module's own declarations taking precedence. Only
+    /// `module` is checked: problems inside the siblings are left for when
+    /// they are checked themselves.
+    pub fn check_module_in_package(
+        &mut self,
+        module: &Module,
+        siblings: &[&Module],
+    ) -> TypeCheckResult {
         // First pass: collect all type definitions (functions, structs, enums, interfaces)
         // We hoist these so they're available throughout the module
+        for sibling in siblings {
+            let errors = self.errors.len();
+            for item in sibling.items() {
+                self.register_item(item);
+            }
+            self.errors.truncate(errors);
+        }
         for tl_item in &module.top_level {
             if let TopLevelItem::Item(item) = tl_item {
                 self.register_item(item);
             }
         }
 
+        // Impl signatures refer to the types registered above; registering them
+        // before any body is checked lets methods be called from anywhere
+        for sibling in siblings {
+            let errors = self.errors.len();
+            for item in sibling.items() {
+                if let ItemKind::Impl(imp) = &item.kind {
+                    self.register_impl(imp);
+                }
+            }
+            self.errors.truncate(errors);
+        }
+        for tl_item in &module.top_level {
+            if let TopLevelItem::Item(item) = tl_item {
+                if let ItemKind::Impl(imp) = &item.kind {
+                    self.register_impl(imp);
+                }
+            }
+        }
+
+        // Constants are evaluated at compile time, so they are visible to every
+        // function body; each may only refer to constants declared above it
+        for tl_item in &module.top_level {
+            if let TopLevelItem::Let(let_decl) = tl_item {
+                if let_decl.is_const {
+                    self.check_top_level_let(let_decl);
+                }
+            }
+        }
+
         // Second pass: type check all top-level items in order
         // This ensures top-to-bottom evaluation for lets and statements
         for tl_item in &module.top_level {
-            self.check_top_level_item(tl_item);
+            if !matches!(tl_item, TopLevelItem::Let(let_decl) if let_decl.is_const) {
+                self.check_top_level_item(tl_item);
+            }
         }
 
         // Collect inference errors
         self.errors.extend(self.inference.take_errors());
 
+        // Bindings are recorded as they are checked; later uses may have
+        // pinned down their type variables since
+        let mut binding_types: Vec<(Span, Type)> = std::mem::take(&mut self.binding_types)
+            .into_iter()
+            .map(|(span, ty)| (span, self.inference.apply(&ty)))
+            .collect();
+        binding_types.sort_by_key(|(span, _)| span.start);
+        let mut resolved_names: Vec<ResolvedName> = std::mem::take(&mut self.resolved_names)
+            .into_iter()
+            .map(|name| ResolvedName {
+                ty: self.inference.apply(&name.ty),
+                ..name
+            })
+            .collect();
+        resolved_names.sort_by_key(|name| name.span.start);
+
         TypeCheckResult {
             success: self.errors.is_empty(),
             errors: std::mem::take(&mut self.errors),
+            binding_types,
+            resolved_names,
         }
     }
 
@@ -273,7 +376,58 @@ impl TypeChecker {
 
         // Bind the pattern to the type
         let ty = declared_type.unwrap_or(value_type);
-        self.check_pattern(&let_decl.pattern, &ty);
+        match let_decl.name() {
+            Some(name) if let_decl.is_const => {
+                if !self.is_const_expr(&let_decl.value) {
+                    self.errors.push(TypeError::new(
+                        TypeErrorKind::NotConstant {
+                            name: name.name.clone(),
+                        },
+                        let_decl.value.span,
+                    ));
+                }
+                self.env.define_const(&name.name, ty);
+            }
+            _ => self.check_pattern(&let_decl.pattern, &ty),
+        }
+    }
+
+    /// Whether `expr` can be evaluated at compile time: literals, earlier
+    /// constants, operators on them, and `if` with constant branches
+    fn is_const_expr(&self, expr: &Expr) -> bool {
+        match &expr.kind {
+            ExprKind::Literal(_) => true,
+            ExprKind::Ident(name) => self
+                .env
+                .lookup_var(&name.name)
+                .is_some_and(|info| info.is_const),
+            ExprKind::Paren(inner) | ExprKind::Unary { expr: inner, .. } => {
+                self.is_const_expr(inner)
+            }
+            ExprKind::Binary { left, op, right } => {
+                !matches!(op, BinOp::Pipe | BinOp::Range | BinOp::RangeInclusive)
+                    && self.is_const_expr(left)
+                    && self.is_const_expr(right)
+            }
+            ExprKind::If {
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                let const_block = |block: &Block| {
+                    block.stmts.is_empty()
+                        && block.expr.as_ref().is_some_and(|e| self.is_const_expr(e))
+                };
+                self.is_const_expr(cond)
+                    && const_block(then_branch)
+                    && match else_branch {
+                        Some(ElseBranch::Block(block)) => const_block(block),
+                        Some(ElseBranch::ElseIf(else_if)) => self.is_const_expr(else_if),
+                        None => false,
+                    }
+            }
+            _ => false,
+        }
     }
 
     /// Register an item's type (first pass)
@@ -282,9 +436,15 @@ impl TypeChecker {
             ItemKind::Function(func) => self.register_function(func),
             ItemKind::Struct(s) => {
                 self.register_struct(s);
+                // The struct name is also bound to a runtime type descriptor
+                // (passed to natives such as Json.decode_as)
+                self.env.define_var(s.name.name.clone(), Type::Any, false);
             }
             ItemKind::Enum(e) => {
                 self.register_enum(e);
+                // Like structs, the enum name is bound to a runtime descriptor
+                // (used for derived static methods such as `from_json`)
+                self.env.define_var(e.name.name.clone(), Type::Any, false);
             }
             ItemKind::Interface(i) => {
                 self.register_interface(i);
@@ -361,6 +521,7 @@ impl TypeChecker {
             type_param_vars,
             fields,
             field_order,
+            derives: self.resolve_derives(&s.attributes),
         };
 
         self.env.define_struct(info)
@@ -403,11 +564,24 @@ impl TypeChecker {
             type_params,
             type_param_vars,
             variants,
+            derives: self.resolve_derives(&e.attributes),
         };
 
         self.env.define_enum(info)
     }
 
+    /// Resolve the derives requested on a struct or enum, reporting invalid attributes
+    fn resolve_derives(&mut self, attributes: &[Attribute]) -> Vec<Derive> {
+        let (derives, errors) = resolve_derives(attributes);
+        for error in errors {
+            self.errors.push(TypeError::new(
+                TypeErrorKind::InvalidAttribute(error.message),
+                error.span,
+            ));
+        }
+        derives
+    }
+
     /// Resolve enum variant data
     fn resolve_variant_data(&mut self, variant: &EnumVariant) -> Option<VariantData> {
         match &variant.data {
@@ -440,6 +614,7 @@ impl TypeChecker {
             let param_types: Vec<Type> = method
                 .params
                 .iter()
+                .filter(|p| !Self::is_self_param(p))
                 .map(|p| {
                     p.ty.as_ref()
                         .map_or(Type::Error, |t| self.resolve_type_annotation(t))
@@ -531,8 +706,8 @@ impl TypeChecker {
         self.env.exit_scope();
     }
 
-    /// Check an impl block
-    fn check_impl(&mut self, imp: &ImplDef) {
+    /// Register an impl block's methods, validating interface compliance
+    fn register_impl(&mut self, imp: &ImplDef) {
         // 1. Resolve the target type
         let target_type_name = self.extract_type_name(&imp.target);
         let target_type = self.resolve_type_annotation(&imp.target);
@@ -552,6 +727,7 @@ impl TypeChecker {
             let param_types: Vec<Type> = method
                 .params
                 .iter()
+                .filter(|p| !Self::is_self_param(p))
                 .map(|p| self.resolve_param_type(&p.ty))
                 .collect();
 
@@ -566,6 +742,7 @@ impl TypeChecker {
                     params: param_types,
                     ret: ret_type,
                     is_async: method.is_async,
+                    takes_self: method.params.first().is_some_and(Self::is_self_param),
                 },
             );
         }
@@ -614,6 +791,21 @@ impl TypeChecker {
                         ));
                     }
                 }
+
+                // Default methods that aren't overridden become methods of the target
+                for (method_name, method_info) in &interface_info.methods {
+                    if method_info.has_default && !impl_methods.contains_key(method_name) {
+                        impl_methods.insert(
+                            method_name.clone(),
+                            ImplMethodInfo {
+                                params: method_info.params.clone(),
+                                ret: method_info.ret.clone(),
+                                is_async: false,
+                                takes_self: true,
+                            },
+                        );
+                    }
+                }
                 Some(iface_name)
             } else {
                 self.errors.push(TypeError::new(
@@ -642,13 +834,29 @@ impl TypeChecker {
                 imp.span,
             ));
         }
+    }
 
-        // 5. Type check each method with `self` bound to the target type
+    /// Check the method bodies of an impl block
+    fn check_impl(&mut self, imp: &ImplDef) {
+        // Errors for the target were reported when the impl was registered
+        let error_count = self.errors.len();
+        let target_type = self.resolve_type_annotation(&imp.target);
+        self.errors.truncate(error_count);
+        if matches!(target_type, Type::Error) {
+            return;
+        }
+
+        // Type check each method with `self` bound to the target type
         for method in &imp.methods {
             self.check_impl_method(method, &target_type);
         }
     }
 
+    /// Whether a parameter is the receiver of an instance method
+    fn is_self_param(param: &Param) -> bool {
+        param.name.name == "self"
+    }
+
     /// Type check a method within an impl block
     fn check_impl_method(&mut self, func: &Function, self_type: &Type) {
         self.env.enter_scope();
@@ -662,7 +870,7 @@ impl TypeChecker {
             .map_or(Type::Unit, |t| self.resolve_type_annotation(t));
         self.env.set_return_type(Some(ret_type.clone()));
 
-        for param in &func.params {
+        for param in func.params.iter().filter(|p| !Self::is_self_param(p)) {
             let param_type = self.resolve_param_type(&param.ty);
             self.env.define_var(&param.name.name, param_type, false);
         }
@@ -792,6 +1000,22 @@ impl TypeChecker {
         result
     }
 
+    /// Report an assignment whose target is a `const`
+    fn check_not_constant(&mut self, target: &Expr) {
+        if let ExprKind::Ident(name) = &target.kind {
+            if self
+                .env
+                .lookup_var(&name.name)
+                .is_some_and(|info| info.is_const)
+            {
+                self.errors.push(TypeError::new(
+                    TypeErrorKind::AssignToConstant(name.name.clone()),
+                    target.span,
+                ));
+            }
+        }
+    }
+
     /// Apply type narrowing by shadowing variables with their narrowed types
     fn apply_narrowing(&mut self, narrowings: &HashMap<String, Narrowing>) {
         for (name, narrowing) in narrowings {
@@ -840,6 +1064,7 @@ impl TypeChecker {
             }
 
             StmtKind::Assign { target, value } => {
+                self.check_not_constant(target);
                 let target_type = self.check_expr(target);
                 let value_type = self.check_expr(value);
 
@@ -850,6 +1075,7 @@ impl TypeChecker {
             }
 
             StmtKind::CompoundAssign { target, op, value } => {
+                self.check_not_constant(target);
                 let target_type = self.check_expr(target);
                 let value_type = self.check_expr(value);
                 let result_type = self.check_compound_op(*op, &target_type, &value_type, stmt.span);
@@ -966,7 +1192,13 @@ impl TypeChecker {
 
             ExprKind::Ident(name) => {
                 if let Some(info) = self.env.lookup_var(&name.name) {
-                    info.ty.clone()
+                    let ty = info.ty.clone();
+                    self.resolved_names.push(ResolvedName {
+                        span: name.span,
+                        ty: ty.clone(),
+                        is_const: info.is_const,
+                    });
+                    ty
                 } else {
                     self.errors
                         .push(TypeError::undefined_variable(&name.name, expr.span));
@@ -1017,6 +1249,9 @@ impl TypeChecker {
             }
 
             ExprKind::Field { expr: obj, field } => {
+                if let Some(ty) = self.check_static_method(obj, &field.name) {
+                    return ty;
+                }
                 let obj_type = self.check_expr(obj);
                 self.check_field_access(&obj_type, &field.name, expr.span)
             }
@@ -1331,6 +1566,18 @@ impl TypeChecker {
         let left = self.inference.apply(left);
         let right = self.inference.apply(right);
 
+        if let Some(method) = op.method_name() {
+            if let Some(ty) =
+                self.check_operator_method(&left, method, std::slice::from_ref(&right), span)
+            {
+                return if matches!(op, BinOp::Eq | BinOp::Ne) {
+                    Type::Bool
+                } else {
+                    ty
+                };
+            }
+        }
+
         match op {
             BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Mod => {
                 // Try to unify with numeric types for type inference
@@ -1512,6 +1759,12 @@ impl TypeChecker {
     fn check_unary_op(&mut self, op: UnaryOp, operand: &Type, span: Span) -> Type {
         let operand = self.inference.apply(operand);
 
+        if let Some(method) = op.method_name() {
+            if let Some(ty) = self.check_operator_method(&operand, method, &[], span) {
+                return ty;
+            }
+        }
+
         match op {
             UnaryOp::Neg => {
                 if operand.is_numeric() {
@@ -1610,6 +1863,12 @@ impl TypeChecker {
         let container = self.inference.apply(container);
         let index = self.inference.apply(index);
 
+        if let Some(ty) =
+            self.check_operator_method(&container, INDEX_METHOD, std::slice::from_ref(&index), span)
+        {
+            return ty;
+        }
+
         match &container {
             Type::List(elem) => {
                 if !self.inference.unify(&index, &Type::Int, span) {
@@ -1669,7 +1928,7 @@ impl TypeChecker {
         let obj = self.inference.apply(obj);
 
         match &obj {
-            Type::Struct { id, name: _, .. } => {
+            Type::Struct { id, name, .. } => {
                 // Clone the field type to avoid borrow issues
                 let field_type = self
                     .env
@@ -1677,8 +1936,35 @@ impl TypeChecker {
                     .and_then(|info| info.fields.get(field))
                     .map(|f| f.ty.clone());
 
+                let derives = self
+                    .env
+                    .get_struct(*id)
+                    .map(|info| info.derives.clone())
+                    .unwrap_or_default();
+
                 if let Some(ty) = field_type {
                     ty
+                } else if let Some(ty) = self.impl_method_type(name, field) {
+                    ty
+                } else if let Some(ty) = Self::derived_method_type(&obj, &derives, field) {
+                    ty
+                } else {
+                    self.errors
+                        .push(TypeError::no_such_field(obj.clone(), field, span));
+                    Type::Error
+                }
+            }
+            Type::Enum { id, name, .. } => {
+                let derives = self
+                    .env
+                    .get_enum(*id)
+                    .map(|info| info.derives.clone())
+                    .unwrap_or_default();
+
+                if let Some(ty) = self.impl_method_type(name, field) {
+                    ty
+                } else if let Some(ty) = Self::derived_method_type(&obj, &derives, field) {
+                    ty
                 } else {
                     self.errors
                         .push(TypeError::no_such_field(obj.clone(), field, span));
@@ -1724,6 +2010,94 @@ impl TypeChecker {
         }
     }
 
+    /// Get the type of a method defined in an impl block for `type_name`
+    fn impl_method_type(&self, type_name: &str, method: &str) -> Option<Type> {
+        let info = self.env.lookup_method(type_name, method)?;
+        let ret = if info.is_async {
+            Type::future(info.ret.clone())
+        } else {
+            info.ret.clone()
+        };
+        Some(Type::function(info.params.clone(), ret))
+    }
+
+    /// Check an operator applied to a struct or enum that overloads it with an
+    /// impl method such as `__add__`, returning `None` when it doesn't
+    fn check_operator_method(
+        &mut self,
+        operand: &Type,
+        method: &str,
+        args: &[Type],
+        span: Span,
+    ) -> Option<Type> {
+        let (Type::Struct { name, .. } | Type::Enum { name, .. }) = operand else {
+            return None;
+        };
+        let method_type = self.impl_method_type(name, method)?;
+        Some(self.check_call(&method_type, args, span))
+    }
+
+    /// Get the type of a method generated by `#[derive(...)]` on `self_type`
+    fn derived_method_type(self_type: &Type, derives: &[Derive], method: &str) -> Option<Type> {
+        match Derive::providing_method(derives, method)? {
+            Derive::Eq => Some(Type::function(vec![self_type.clone()], Type::Bool)),
+            Derive::Show | Derive::Json => Some(Type::function(vec![], Type::String)),
+        }
+    }
+
+    /// Check `Type.method` where `method` is a static method, either defined
+    /// in an impl block (`Point.new`) or generated by `#[derive(...)]`
+    /// (`Point.from_json`)
+    fn check_static_method(&mut self, obj: &Expr, method: &str) -> Option<Type> {
+        let ExprKind::Ident(name) = &obj.kind else {
+            return None;
+        };
+
+        let is_static = self
+            .env
+            .lookup_method(&name.name, method)
+            .map(|info| !info.takes_self);
+        match is_static {
+            Some(true) => return self.impl_method_type(&name.name, method),
+            Some(false) => {
+                self.errors.push(TypeError::new(
+                    TypeErrorKind::InstanceMethodOnType {
+                        type_name: name.name.clone(),
+                        method_name: method.to_string(),
+                    },
+                    obj.span,
+                ));
+                return Some(Type::Error);
+            }
+            None => {}
+        }
+
+        let struct_info = self
+            .env
+            .lookup_struct(&name.name)
+            .map(|(id, info)| (Ok(id), info.type_params.len(), info.derives.clone()));
+        let (id, type_params, derives) = struct_info.or_else(|| {
+            self.env
+                .lookup_enum(&name.name)
+                .map(|(id, info)| (Err(id), info.type_params.len(), info.derives.clone()))
+        })?;
+
+        match Derive::providing_static_method(&derives, method)? {
+            Derive::Json => {
+                // Generic types are instantiated with fresh variables
+                let type_args = (0..type_params)
+                    .map(|_| self.inference.fresh_var())
+                    .collect();
+                let self_type = match id {
+                    Ok(id) => Type::struct_type(id, &name.name, type_args),
+                    Err(id) => Type::enum_type(id, &name.name, type_args),
+                };
+                Some(Type::function(vec![Type::String], self_type))
+            }
+            Derive::Eq | Derive::Show => None,
+        }
+    }
+
     /// Get the type of a String method (returns function type for methods)
     fn check_string_method(&mut self, method: &str, span: Span) -> Type {
         match method {
@@ -1766,6 +2140,7 @@ impl TypeChecker {
             "sort" => Type::function(vec![], Type::list(elem.clone())),
             "join" => Type::function(vec![Type::String], Type::String),
             "get" => Type::function(vec![Type::Int], Type::nullable(elem.clone())),
+            "clone" => Type::function(vec![], Type::list(elem.clone())),
             // Higher-order methods - use type variables for flexibility
             "map" => {
                 let result_type = self.inference.fresh_var();
@@ -1819,6 +2194,10 @@ impl TypeChecker {
                 Type::list(Type::Tuple(vec![key.clone(), value.clone()])),
             ),
             "clear" => Type::function(vec![], Type::Unit),
+            "clone" => Type::function(
+                vec![],
+                Type::Map(Box::new(key.clone()), Box::new(value.clone())),
+            ),
             _ => {
                 self.errors.push(TypeError::no_such_field(
                     Type::Map(Box::new(key), Box::new(value)),
@@ -1884,6 +2263,7 @@ impl TypeChecker {
             .iter()
             .map(|p| {
                 let ty = self.resolve_param_type(&p.ty);
+                self.binding_types.push((p.name.span, ty.clone()));
                 self.env.define_var(&p.name.name, ty.clone(), false);
                 ty
             })
@@ -2105,6 +2485,7 @@ impl TypeChecker {
         match &pattern.kind {
             PatternKind::Wildcard => {}
             PatternKind::Ident(name) => {
+                self.binding_types.push((name.span, expected.clone()));
                 self.env.define_var(&name.name, expected.clone(), false);
             }
             PatternKind::Literal(lit) => {
@@ -2178,6 +2559,7 @@ impl TypeChecker {
         match &pattern.kind {
             PatternKind::Wildcard => {}
             PatternKind::Ident(name) => {
+                self.binding_types.push((name.span, ty.clone()));
                 self.env.define_var(&name.name, ty.clone(), false);
             }
             PatternKind::Literal(_) => {}
@@ -2483,6 +2865,74 @@ mod tests {
         assert!(result.success, "errors: {:?}", result.errors);
     }
 
+    #[test]
+    fn test_records_binding_types() {
+        let source =
+            "fx main() {\n    let x = 42\n    let names = [\"a\"]\n    let inc = |n: Int| n + x\n}";
+        let result = check(source);
+        assert!(result.success, "errors: {:?}", result.errors);
+
+        let types: Vec<(&str, String)> = result
+            .binding_types
+            .iter()
+            .map(|(span, ty)| {
+                (
+                    &source[span.start as usize..span.end as usize],
+                    ty.to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(types[0], ("x", "Int".to_string()));
+        assert_eq!(types[1], ("names", "List<String>".to_string()));
+        assert!(types.contains(&("n", "Int".to_string())), "{types:?}");
+        assert!(types.iter().any(|(name, _)| *name == "inc"), "{types:?}");
+    }
+
+    #[test]
+    fn test_records_resolved_names() {
+        let source = "const LIMIT = 10\nfx main() {\n    let x = LIMIT\n    println(x + File.size(\"a\"))\n}";
+        let result = check(source);
+
+        let names: Vec<(&str, String, bool)> = result
+            .resolved_names
+            .iter()
+            .map(|name| {
+                let text = &source[name.span.start as usize..name.span.end as usize];
+                (text, name.ty.to_string(), name.is_const)
+            })
+            .collect();
+        assert_eq!(names[0], ("LIMIT", "Int".to_string(), true));
+        assert_eq!(names[2], ("x", "Int".to_string(), false));
+        assert!(
+            names
+                .iter()
+                .any(|(text, ty, _)| *text == "File" && ty == "File"),
+            "{names:?}"
+        );
+    }
+
+    #[test]
+    fn test_check_module_in_package() {
+        let lib = Parser::parse_module(
+            "struct Point { x: Int, y: Int }\nimpl Point {\n    fx sum(self) -> Int { self.x + self.y }\n}\nfx origin() -> Point { Point { x: 0, y: 0 } }\nfx broken() -> Int { \"no\" }",
+        )
+        .unwrap();
+        let main = Parser::parse_module("fx main() -> Int {\n    let p = origin()\n    p.sum()\n}")
+            .unwrap();
+
+        // On its own, `main` cannot see the library
+        assert!(!TypeChecker::new().check_module(&main).success);
+
+        // The sibling's bodies are not checked, so `broken` goes unreported
+        let result = TypeChecker::new().check_module_in_package(&main, &[&lib]);
+        assert!(result.success, "errors: {:?}", result.errors);
+
+        // A call that no longer matches the sibling's signature is
+        let main = Parser::parse_module("fx main() -> Int { origin(1).sum() }").unwrap();
+        let result = TypeChecker::new().check_module_in_package(&main, &[&lib]);
+        assert!(!result.success);
+    }
+
     #[test]
     fn test_type_annotation() {
         let result = check("fx main() { let x: Int = 42 }");
@@ -3194,6 +3644,75 @@ mod tests {
         assert!(result.success, "errors: {:?}", result.errors);
     }
 
+    #[test]
+    fn test_derived_struct_methods() {
+        let result = check(
+            r"
+            #[derive(Eq, Show, Json)]
+            struct Point { x: Int, y: Int }
+            fx main() {
+                let p = Point { x: 1, y: 2 }
+                let same: Bool = p.equals(Point { x: 1, y: 2 })
+                let text: String = p.to_string()
+                let json: String = p.to_json()
+                let back: Point = Point.from_json(json)
+            }
+        ",
+        );
+        assert!(result.success, "errors: {:?}", result.errors);
+    }
+
+    #[test]
+    fn test_derived_enum_methods() {
+        let result = check(
+            r"
+            #[derive(Show, Json)]
+            enum Shape { Dot, Circle(Float) }
+            fx main() {
+                let s = Shape::Circle(1.5)
+                let text: String = s.to_string()
+                let back: Shape = Shape.from_json(s.to_json())
+            }
+        ",
+        );
+        assert!(result.success, "errors: {:?}", result.errors);
+    }
+
+    #[test]
+    fn test_underived_method_is_missing() {
+        let result = check(
+            r"
+            #[derive(Show)]
+            struct Point { x: Int, y: Int }
+            fx main() {
+                let p = Point { x: 1, y: 2 }
+                let json = p.to_json()
+            }
+        ",
+        );
+        assert!(!result.success);
+        assert!(result
+            .errors
+            .iter()
+            .any(|e| matches!(e.kind, TypeErrorKind::NoSuchField { .. })));
+    }
+
+    #[test]
+    fn test_unknown_derive() {
+        let result = check(
+            r"
+            #[derive(Eq, Hash)]
+            struct Point { x: Int, y: Int }
+            fx main() {}
+        ",
+        );
+        assert!(!result.success);
+        assert!(result.errors.iter().any(|e| matches!(
+            &e.kind,
+            TypeErrorKind::InvalidAttribute(msg) if msg.contains("`Hash`")
+        )));
+    }
+
     #[test]
     fn test_struct_pattern_basic() {
         let result = check(
@@ -3554,6 +4073,228 @@ mod tests {
         assert!(result.success, "errors: {:?}", result.errors);
     }
 
+    #[test]
+    fn test_impl_methods_with_self_and_static() {
+        let result = check(
+            r"
+            fx main() {
+                let p = Point.new(3, 4)
+                let n: Int = p.manhattan()
+                let q: Point = p.shifted(1)
+            }
+
+            struct Point { x: Int, y: Int }
+
+            impl Point {
+                fx new(x: Int, y: Int) -> Point {
+                    Point { x: x, y: y }
+                }
+                fx manhattan(self) -> Int {
+                    self.x + self.y
+                }
+                fx shifted(self, by: Int) -> Point {
+                    Point.new(self.x + by, self.y + by)
+                }
+            }
+        ",
+        );
+        assert!(result.success, "errors: {:?}", result.errors);
+    }
+
+    #[test]
+    fn test_impl_methods_on_enum() {
+        let result = check(
+            r"
+            enum Shape { Dot, Circle(Float) }
+
+            impl Shape {
+                fx unit() -> Shape {
+                    Shape::Circle(1.0)
+                }
+                fx area(self) -> Float {
+                    match self {
+                        Shape::Dot => 0.0,
+                        Shape::Circle(r) => 3.14 * r * r
+                    }
+                }
+            }
+
+            fx main() {
+                let a: Float = Shape.unit().area()
+            }
+        ",
+        );
+        assert!(result.success, "errors: {:?}", result.errors);
+    }
+
+    #[test]
+    fn test_instance_method_called_on_type() {
+        let result = check(
+            r"
+            struct Counter { count: Int }
+
+            impl Counter {
+                fx value(self) -> Int {
+                    self.count
+                }
+            }
+
+            fx main() {
+                let n = Counter.value()
+            }
+        ",
+        );
+        assert!(!result.success);
+        assert!(result.errors.iter().any(|e| matches!(
+            &e.kind,
+            TypeErrorKind::InstanceMethodOnType { method_name, .. } if method_name == "value"
+        )));
+    }
+
+    #[test]
+    fn test_operator_overloading_methods() {
+        let result = check(
+            r"
+            struct Vec2 { x: Float, y: Float }
+
+            impl Vec2 {
+                fx __add__(self, other: Vec2) -> Vec2 {
+                    Vec2 { x: self.x + other.x, y: self.y + other.y }
+                }
+                fx __mul__(self, k: Float) -> Vec2 {
+                    Vec2 { x: self.x * k, y: self.y * k }
+                }
+                fx __eq__(self, other: Vec2) -> Bool {
+                    self.x == other.x && self.y == other.y
+                }
+                fx __neg__(self) -> Vec2 {
+                    Vec2 { x: 0.0 - self.x, y: 0.0 - self.y }
+                }
+                fx __index__(self, i: Int) -> Float {
+                    if i == 0 { self.x } else { self.y }
+                }
+            }
+
+            fx main() {
+                let a = Vec2 { x: 1.0, y: 2.0 }
+                let sum: Vec2 = a + a * 2.0
+                let same: Bool = a == -a
+                let differs: Bool = a != sum
+                let x: Float = a[0]
+            }
+        ",
+        );
+        assert!(result.success, "errors: {:?}", result.errors);
+    }
+
+    #[test]
+    fn test_operator_overloading_checks_operands() {
+        let result = check(
+            r#"
+            struct Vec2 { x: Float, y: Float }
+
+            impl Vec2 {
+                fx __add__(self, other: Vec2) -> Vec2 {
+                    Vec2 { x: self.x + other.x, y: self.y + other.y }
+                }
+            }
+
+            fx main() {
+                let a = Vec2 { x: 1.0, y: 2.0 }
+                let bad = a + "text"
+                let missing = a - a
+            }
+        "#,
+        );
+        assert!(!result.success);
+        assert!(result
+            .errors
+            .iter()
+            .any(|e| matches!(&e.kind, TypeErrorKind::InvalidBinaryOp { op, .. } if op == "-")));
+    }
+
+    #[test]
+    fn test_impl_method_argument_types_exclude_self() {
+        let result = check(
+            r#"
+            struct Point { x: Int, y: Int }
+
+            impl Point {
+                fx shifted(self, by: Int) -> Point {
+                    Point { x: self.x + by, y: self.y + by }
+                }
+            }
+
+            fx main() {
+                let p = Point { x: 1, y: 2 }
+                let q = p.shifted("far")
+            }
+        "#,
+        );
+        assert!(!result.success);
+    }
+
+    // ========== Const Tests ==========
+
+    #[test]
+    fn test_const_declarations() {
+        let result = check(
+            r#"
+            const SCALE = 2 * 1.5
+            const UNIT = "cm"
+            const LABEL: String = "size in " + UNIT
+            const DEBUG = if SCALE > 1.0 { true } else { false }
+
+            fx area(side: Float) -> Float {
+                side * side * SCALE
+            }
+        "#,
+        );
+        assert!(result.success, "errors: {:?}", result.errors);
+    }
+
+    #[test]
+    fn test_const_requires_compile_time_value() {
+        let result = check(
+            r"
+            fx compute() -> Int { 42 }
+
+            const ANSWER = compute()
+            const LATER = EARLY + 1
+            const EARLY = 1
+        ",
+        );
+        let not_constant: Vec<_> = result
+            .errors
+            .iter()
+            .filter_map(|e| match &e.kind {
+                TypeErrorKind::NotConstant { name } => Some(name.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(not_constant, ["ANSWER", "LATER"]);
+    }
+
+    #[test]
+    fn test_assign_to_const() {
+        let result = check(
+            r"
+            const LIMIT = 10
+
+            fx main() {
+                LIMIT = 20
+                LIMIT = LIMIT + 1
+            }
+        ",
+        );
+        let assignments = result
+            .errors
+            .iter()
+            .filter(|e| matches!(&e.kind, TypeErrorKind::AssignToConstant(name) if name == "LIMIT"))
+            .count();
+        assert_eq!(assignments, 2);
+    }
+
     // ========== Generic Type Instantiation Tests ==========
 
     #[test]
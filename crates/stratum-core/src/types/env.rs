@@ -4,6 +4,8 @@
 
 use std::collections::HashMap;
 
+use crate::ast::Derive;
+
 use super::{EnumId, InterfaceId, StructId, Type};
 
 /// Type environment managing scopes and type definitions
@@ -72,6 +74,9 @@ pub struct VarInfo {
 
     /// Whether this variable is mutable
     pub mutable: bool,
+
+    /// Whether this name was declared with `const`
+    pub is_const: bool,
 }
 
 /// Information about a struct definition
@@ -92,6 +97,9 @@ pub struct StructInfo {
 
     /// Order of fields (for construction)
     pub field_order: Vec<String>,
+
+    /// Behaviours requested with `#[derive(...)]`
+    pub derives: Vec<Derive>,
 }
 
 /// Information about a struct field
@@ -118,6 +126,9 @@ pub struct EnumInfo {
 
     /// Variants
     pub variants: HashMap<String, VariantInfo>,
+
+    /// Behaviours requested with `#[derive(...)]`
+    pub derives: Vec<Derive>,
 }
 
 /// Information about an enum variant
@@ -190,6 +201,10 @@ pub struct ImplMethodInfo {
 
     /// Whether the method is async
     pub is_async: bool,
+
+    /// Whether the method declares `self` (an instance method) rather than
+    /// being static
+    pub takes_self: bool,
 }
 
 impl Default for TypeEnv {
@@ -250,7 +265,28 @@ impl TypeEnv {
     /// Define a variable in the current scope
     pub fn define_var(&mut self, name: impl Into<String>, ty: Type, mutable: bool) {
         if let Some(scope) = self.scopes.last_mut() {
-            scope.variables.insert(name.into(), VarInfo { ty, mutable });
+            scope.variables.insert(
+                name.into(),
+                VarInfo {
+                    ty,
+                    mutable,
+                    is_const: false,
+                },
+            );
+        }
+    }
+
+    /// Define a compile-time constant in the current scope
+    pub fn define_const(&mut self, name: impl Into<String>, ty: Type) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.variables.insert(
+                name.into(),
+                VarInfo {
+                    ty,
+                    mutable: false,
+                    is_const: true,
+                },
+            );
         }
     }
 
@@ -569,6 +605,7 @@ mod tests {
                 f
             },
             field_order: vec!["x".into(), "y".into()],
+            derives: Vec::new(),
         };
 
         let id = env.define_struct(info);
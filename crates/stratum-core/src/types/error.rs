@@ -317,6 +317,14 @@ pub enum TypeErrorKind {
         method_name: String,
     },
 
+    /// Method taking `self` called on the type instead of a value
+    InstanceMethodOnType {
+        /// The type the method was called on
+        type_name: String,
+        /// The method name
+        method_name: String,
+    },
+
     /// Await used outside of async function
     AwaitOutsideAsync,
 
@@ -328,6 +336,72 @@ pub enum TypeErrorKind {
 
     /// Column shorthand (.column) used outside of DataFrame context
     ColumnShorthandOutsideContext,
+
+    /// Attribute or derive the compiler does not recognise
+    InvalidAttribute(String),
+
+    /// A `const` initializer that cannot be evaluated at compile time
+    NotConstant {
+        /// The constant being declared
+        name: String,
+    },
+
+    /// Assignment to a name declared with `const`
+    AssignToConstant(String),
+}
+
+impl TypeErrorKind {
+    /// The stable error code for this kind of error, such as `E0100`,
+    /// explained in [`crate::error_codes`]
+    #[must_use]
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::TypeMismatch { .. } => "E0100",
+            Self::UndefinedVariable(..) => "E0101",
+            Self::UndefinedType(..) => "E0102",
+            Self::UndefinedFunction(..) => "E0103",
+            Self::UndefinedStruct(..) => "E0104",
+            Self::UndefinedEnum(..) => "E0105",
+            Self::NotCallable(..) => "E0106",
+            Self::WrongArgumentCount { .. } => "E0107",
+            Self::NotIndexable(..) => "E0108",
+            Self::InvalidIndexType { .. } => "E0109",
+            Self::NoSuchField { .. } => "E0110",
+            Self::UnnecessaryNullSafe(..) => "E0111",
+            Self::NullabilityMismatch { .. } => "E0112",
+            Self::InvalidBinaryOp { .. } => "E0113",
+            Self::InvalidUnaryOp { .. } => "E0114",
+            Self::ReturnTypeMismatch { .. } => "E0115",
+            Self::InvalidAssignmentTarget => "E0116",
+            Self::DuplicateField(..) => "E0117",
+            Self::MissingField { .. } => "E0118",
+            Self::ExtraField { .. } => "E0119",
+            Self::CannotInfer => "E0120",
+            Self::RecursiveType(..) => "E0121",
+            Self::DuplicateDefinition(..) => "E0122",
+            Self::BreakOutsideLoop => "E0123",
+            Self::ContinueOutsideLoop => "E0124",
+            Self::ReturnOutsideFunction => "E0125",
+            Self::IncompatibleBranches { .. } => "E0126",
+            Self::WrongTypeArgCount { .. } => "E0127",
+            Self::OccursCheck { .. } => "E0128",
+            Self::CannotUnify { .. } => "E0129",
+            Self::UndefinedInterface(..) => "E0130",
+            Self::ImplTargetNotFound(..) => "E0131",
+            Self::MissingInterfaceMethod { .. } => "E0132",
+            Self::MethodSignatureMismatch { .. } => "E0133",
+            Self::DuplicateImpl { .. } => "E0134",
+            Self::MethodNotFound { .. } => "E0135",
+            Self::InstanceMethodOnType { .. } => "E0136",
+            Self::AwaitOutsideAsync => "E0137",
+            Self::AwaitNonFuture(..) => "E0138",
+            Self::PlaceholderOutsidePipeline => "E0139",
+            Self::ColumnShorthandOutsideContext => "E0140",
+            Self::InvalidAttribute(..) => "E0141",
+            Self::NotConstant { .. } => "E0142",
+            Self::AssignToConstant(..) => "E0143",
+        }
+    }
 }
 
 impl fmt::Display for TypeErrorKind {
@@ -501,6 +575,15 @@ impl fmt::Display for TypeErrorKind {
             TypeErrorKind::MethodNotFound { ty, method_name } => {
                 write!(f, "no method `{method_name}` found for type `{ty}`")
             }
+            TypeErrorKind::InstanceMethodOnType {
+                type_name,
+                method_name,
+            } => {
+                write!(
+                    f,
+                    "method `{method_name}` takes `self`; call it on a `{type_name}` value instead of the type"
+                )
+            }
             TypeErrorKind::AwaitOutsideAsync => {
                 write!(f, "`await` can only be used inside async functions")
             }
@@ -519,6 +602,16 @@ impl fmt::Display for TypeErrorKind {
                     "column shorthand `.column` can only be used inside DataFrame operations like filter, select, etc."
                 )
             }
+            TypeErrorKind::InvalidAttribute(message) => write!(f, "{message}"),
+            TypeErrorKind::NotConstant { name } => {
+                write!(
+                    f,
+                    "value of `const {name}` must be built from literals, other constants, and operators"
+                )
+            }
+            TypeErrorKind::AssignToConstant(name) => {
+                write!(f, "cannot assign to constant `{name}`")
+            }
         }
     }
 }
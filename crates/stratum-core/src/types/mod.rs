@@ -12,7 +12,7 @@ mod error;
 mod inference;
 mod narrowing;
 
-pub use checker::{TypeCheckResult, TypeChecker};
+pub use checker::{ResolvedName, TypeCheckResult, TypeChecker};
 pub use env::TypeEnv;
 pub use error::{TypeError, TypeErrorKind};
 pub use inference::TypeInference;
@@ -0,0 +1,203 @@
+//! Allocation tracking for the memory profiler
+//!
+//! With allocation profiling on, the VM reports each list, map, set,
+//! struct, closure and string its instructions create to the global memory
+//! profiler, along with the function and line that created it. A weak
+//! reference to the value is kept so the profiler can be told when it is
+//! freed; dropped values are found by sweeping the weak references as the
+//! set of tracked values grows, and once more when the profile is read.
+//!
+//! Sizes are estimates taken when a value is created: the value's own
+//! allocation plus its elements' slots, not the values the elements point to.
+
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::mem::size_of;
+use std::rc::{Rc, Weak};
+
+use crate::bytecode::{Closure, CowCell, HashableValue, StructInstance, Upvalue, Value, ValueMap};
+use crate::data::{
+    memory_categories as categories, record_allocation_at, record_deallocation, AllocationSite,
+};
+
+/// Tracked values before the first sweep for freed ones
+const MIN_SWEEP_THRESHOLD: usize = 1024;
+
+/// Bytes an `Rc` allocation adds for its reference counts
+const RC_HEADER: usize = 2 * size_of::<usize>();
+
+/// A weak reference to a tracked value
+enum TrackedValue {
+    List(Weak<CowCell<Vec<Value>>>),
+    Map(Weak<CowCell<ValueMap>>),
+    Set(Weak<RefCell<HashSet<HashableValue>>>),
+    Struct(Weak<RefCell<StructInstance>>),
+    Closure(Weak<Closure>),
+    String(Weak<String>),
+}
+
+impl TrackedValue {
+    fn is_alive(&self) -> bool {
+        match self {
+            TrackedValue::List(weak) => weak.strong_count() > 0,
+            TrackedValue::Map(weak) => weak.strong_count() > 0,
+            TrackedValue::Set(weak) => weak.strong_count() > 0,
+            TrackedValue::Struct(weak) => weak.strong_count() > 0,
+            TrackedValue::Closure(weak) => weak.strong_count() > 0,
+            TrackedValue::String(weak) => weak.strong_count() > 0,
+        }
+    }
+}
+
+/// A live value reported to the profiler
+struct Allocation {
+    value: TrackedValue,
+    bytes: usize,
+    category: &'static str,
+}
+
+/// The values reported to the memory profiler that may still be alive
+pub(super) struct AllocationTracker {
+    live: Vec<Allocation>,
+    sweep_threshold: usize,
+}
+
+impl Default for AllocationTracker {
+    fn default() -> Self {
+        Self {
+            live: Vec::new(),
+            sweep_threshold: MIN_SWEEP_THRESHOLD,
+        }
+    }
+}
+
+impl AllocationTracker {
+    /// Report a newly created value, if it is a heap value the profiler
+    /// tracks
+    pub(super) fn track(&mut self, value: &Value, site: impl FnOnce() -> AllocationSite) {
+        let Some((tracked, bytes, category)) = describe(value) else {
+            return;
+        };
+        record_allocation_at(bytes, category, site());
+        self.live.push(Allocation {
+            value: tracked,
+            bytes,
+            category,
+        });
+
+        // Sweep when the tracked set doubles, so sweeping stays amortized
+        // constant time per allocation
+        if self.live.len() >= self.sweep_threshold {
+            self.sweep();
+            self.sweep_threshold = (self.live.len() * 2).max(MIN_SWEEP_THRESHOLD);
+        }
+    }
+
+    /// Report the tracked values that have been dropped as freed
+    pub(super) fn sweep(&mut self) {
+        self.live.retain(|allocation| {
+            let alive = allocation.value.is_alive();
+            if !alive {
+                record_deallocation(allocation.bytes, allocation.category);
+            }
+            alive
+        });
+    }
+
+    /// Number of tracked values not yet seen to be freed
+    #[cfg(test)]
+    pub(super) fn len(&self) -> usize {
+        self.live.len()
+    }
+}
+
+/// A weak reference, estimated size and category for a heap value
+fn describe(value: &Value) -> Option<(TrackedValue, usize, &'static str)> {
+    let described = match value {
+        Value::List(rc) => (
+            TrackedValue::List(Rc::downgrade(rc)),
+            size_of::<CowCell<Vec<Value>>>()
+                + size_of::<Vec<Value>>()
+                + rc.borrow().capacity() * size_of::<Value>(),
+            categories::LIST,
+        ),
+        Value::Map(rc) => (
+            TrackedValue::Map(Rc::downgrade(rc)),
+            size_of::<CowCell<ValueMap>>()
+                + size_of::<ValueMap>()
+                + rc.borrow().capacity() * (size_of::<HashableValue>() + size_of::<Value>()),
+            categories::MAP,
+        ),
+        Value::Set(rc) => (
+            TrackedValue::Set(Rc::downgrade(rc)),
+            size_of::<RefCell<HashSet<HashableValue>>>()
+                + rc.borrow().capacity() * size_of::<HashableValue>(),
+            categories::SET,
+        ),
+        Value::Struct(rc) => {
+            let instance = rc.borrow();
+            let fields: usize = instance
+                .fields
+                .keys()
+                .map(|name| size_of::<String>() + name.len() + size_of::<Value>())
+                .sum();
+            (
+                TrackedValue::Struct(Rc::downgrade(rc)),
+                size_of::<RefCell<StructInstance>>() + instance.type_name.len() + fields,
+                categories::STRUCT,
+            )
+        }
+        Value::Closure(rc) => (
+            TrackedValue::Closure(Rc::downgrade(rc)),
+            size_of::<Closure>() + rc.upvalues.len() * size_of::<Rc<RefCell<Upvalue>>>(),
+            categories::CLOSURE,
+        ),
+        Value::String(rc) => (
+            TrackedValue::String(Rc::downgrade(rc)),
+            size_of::<String>() + rc.capacity(),
+            categories::STRING,
+        ),
+        _ => return None,
+    };
+    Some((described.0, described.1 + RC_HEADER, described.2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_sizes() {
+        let (_, small, category) = describe(&Value::list(vec![Value::Int(1)])).unwrap();
+        assert_eq!(category, categories::LIST);
+        let (_, large, _) = describe(&Value::list(vec![Value::Int(1); 10])).unwrap();
+        assert_eq!(large - small, 9 * size_of::<Value>());
+
+        let (_, bytes, category) = describe(&Value::string("hello")).unwrap();
+        assert_eq!(category, categories::STRING);
+        assert!(bytes >= 5 + RC_HEADER);
+
+        assert!(describe(&Value::Int(3)).is_none());
+    }
+
+    #[test]
+    fn test_sweep_forgets_dropped_values() {
+        let site = || AllocationSite {
+            function: "main".to_string(),
+            file: None,
+            line: 1,
+        };
+        let mut tracker = AllocationTracker::default();
+        let kept = Value::list(vec![]);
+        tracker.track(&kept, site);
+        tracker.track(&Value::list(vec![]), site);
+        tracker.track(&Value::Null, site);
+        assert_eq!(tracker.len(), 2);
+
+        tracker.sweep();
+        assert_eq!(tracker.len(), 1);
+        drop(kept);
+        tracker.sweep();
+        assert_eq!(tracker.len(), 0);
+    }
+}
@@ -6,7 +6,7 @@
 use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 
-use crate::bytecode::Value;
+use crate::bytecode::{HashableValue, Value};
 
 /// Represents a debug location in source code
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -38,8 +38,15 @@ pub struct Breakpoint {
     pub location: DebugLocation,
     /// Whether the breakpoint is enabled
     pub enabled: bool,
-    /// Optional condition expression (not yet implemented)
+    /// Optional condition expression, evaluated in the paused frame; the
+    /// breakpoint only triggers when it is truthy
     pub condition: Option<String>,
+    /// Optional hit count filter (see [`HitCondition`])
+    pub hit_condition: Option<String>,
+    /// Message to log instead of stopping, with `{expr}` interpolation
+    pub log_message: Option<String>,
+    /// Number of times the breakpoint triggered with its condition holding
+    pub hit_count: u32,
 }
 
 impl Breakpoint {
@@ -50,6 +57,64 @@ impl Breakpoint {
             location,
             enabled: true,
             condition: None,
+            hit_condition: None,
+            log_message: None,
+            hit_count: 0,
+        }
+    }
+
+    /// Whether this is a logpoint, which logs a message and never stops
+    pub fn is_logpoint(&self) -> bool {
+        self.log_message.is_some()
+    }
+}
+
+/// A filter on a breakpoint's hit count
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HitCondition {
+    /// `N` or `== N`: only the Nth hit
+    Equal(u32),
+    /// `> N`
+    Greater(u32),
+    /// `>= N`
+    GreaterOrEqual(u32),
+    /// `< N`
+    Less(u32),
+    /// `<= N`
+    LessOrEqual(u32),
+    /// `% N`: every Nth hit
+    Multiple(u32),
+}
+
+impl HitCondition {
+    /// Parse a hit condition such as `5`, `>= 5` or `% 2`
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let (make, count): (fn(u32) -> Self, &str) = match text {
+            _ if text.starts_with(">=") => (Self::GreaterOrEqual, &text[2..]),
+            _ if text.starts_with("<=") => (Self::LessOrEqual, &text[2..]),
+            _ if text.starts_with("==") => (Self::Equal, &text[2..]),
+            _ if text.starts_with('>') => (Self::Greater, &text[1..]),
+            _ if text.starts_with('<') => (Self::Less, &text[1..]),
+            _ if text.starts_with('%') => (Self::Multiple, &text[1..]),
+            _ => (Self::Equal, text),
+        };
+
+        match make(count.trim().parse().ok()?) {
+            Self::Multiple(0) => None,
+            condition => Some(condition),
+        }
+    }
+
+    /// Whether the breakpoint triggers on hit number `hit_count`
+    pub fn matches(self, hit_count: u32) -> bool {
+        match self {
+            Self::Equal(n) => hit_count == n,
+            Self::Greater(n) => hit_count > n,
+            Self::GreaterOrEqual(n) => hit_count >= n,
+            Self::Less(n) => hit_count < n,
+            Self::LessOrEqual(n) => hit_count <= n,
+            Self::Multiple(n) => hit_count % n == 0,
         }
     }
 }
@@ -97,6 +162,19 @@ pub struct DebugState {
     pub pause_reason: PauseReason,
 }
 
+/// What a hot reload changed in the running program
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HotReload {
+    /// Functions whose code changed, now swapped in
+    pub reloaded: Vec<String>,
+    /// Functions new to the program
+    pub added: Vec<String>,
+    /// Functions that could not be swapped in, with the reason
+    pub skipped: Vec<(String, String)>,
+    /// Breakpoints that moved with their function, as (ID, new line)
+    pub moved_breakpoints: Vec<(u32, u32)>,
+}
+
 /// Reason for pausing execution
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PauseReason {
@@ -130,6 +208,8 @@ pub struct DebugVariable {
     pub value: String,
     /// Type name
     pub type_name: String,
+    /// The value itself, so containers can be expanded
+    pub raw: Value,
 }
 
 impl DebugVariable {
@@ -139,6 +219,73 @@ impl DebugVariable {
             name,
             value: format!("{}", value),
             type_name: value.type_name().to_string(),
+            raw: value.clone(),
+        }
+    }
+
+    /// Whether the value has elements or fields to expand
+    pub fn has_children(&self) -> bool {
+        match &self.raw {
+            Value::List(items) => !items.borrow().is_empty(),
+            Value::Map(entries) => !entries.borrow().is_empty(),
+            Value::Set(items) => !items.borrow().is_empty(),
+            Value::Struct(instance) => !instance.borrow().fields.is_empty(),
+            Value::EnumVariant(variant) => variant.data.is_some(),
+            _ => false,
+        }
+    }
+
+    /// The elements of a list or set, the entries of a map, the fields of a
+    /// struct, or the data of an enum variant
+    pub fn children(&self) -> Vec<DebugVariable> {
+        match &self.raw {
+            Value::List(items) => items
+                .borrow()
+                .iter()
+                .enumerate()
+                .map(|(i, item)| Self::from_value(format!("[{i}]"), item))
+                .collect(),
+            Value::Map(entries) => {
+                let mut children: Vec<DebugVariable> = entries
+                    .borrow()
+                    .iter()
+                    .map(|(key, value)| {
+                        let name = match key {
+                            HashableValue::String(s) => format!("{s:?}"),
+                            _ => Value::from(key.clone()).to_string(),
+                        };
+                        Self::from_value(name, value)
+                    })
+                    .collect();
+                children.sort_by(|a, b| a.name.cmp(&b.name));
+                children
+            }
+            Value::Set(items) => {
+                let mut values: Vec<Value> =
+                    items.borrow().iter().cloned().map(Value::from).collect();
+                values.sort_by_key(ToString::to_string);
+                values
+                    .iter()
+                    .enumerate()
+                    .map(|(i, item)| Self::from_value(format!("[{i}]"), item))
+                    .collect()
+            }
+            Value::Struct(instance) => {
+                let mut children: Vec<DebugVariable> = instance
+                    .borrow()
+                    .fields
+                    .iter()
+                    .map(|(name, value)| Self::from_value(name.clone(), value))
+                    .collect();
+                children.sort_by(|a, b| a.name.cmp(&b.name));
+                children
+            }
+            Value::EnumVariant(variant) => variant
+                .data
+                .iter()
+                .map(|data| Self::from_value("0".to_string(), data))
+                .collect(),
+            _ => Vec::new(),
         }
     }
 }
@@ -160,6 +307,10 @@ pub struct DebugContext {
     step_frame_depth: usize,
     /// Line when step was initiated (to detect line changes)
     step_line: u32,
+    /// Last line executed in each active frame, outermost first
+    frame_lines: Vec<u32>,
+    /// Messages from logpoints not yet collected
+    log_messages: Vec<String>,
 }
 
 /// Internal stepping mode
@@ -196,8 +347,14 @@ impl DebugContext {
     /// Remove a breakpoint by ID
     pub fn remove_breakpoint(&mut self, id: u32) -> bool {
         if let Some(bp) = self.breakpoints_by_id.remove(&id) {
+            let shared = self
+                .breakpoints_by_id
+                .values()
+                .any(|other| other.location == bp.location);
             if let Some(lines) = self.breakpoints.get_mut(&bp.location.file) {
-                lines.remove(&bp.location.line);
+                if !shared {
+                    lines.remove(&bp.location.line);
+                }
             }
             true
         } else {
@@ -205,6 +362,129 @@ impl DebugContext {
         }
     }
 
+    /// Get a breakpoint by ID
+    pub fn breakpoint(&self, id: u32) -> Option<&Breakpoint> {
+        self.breakpoints_by_id.get(&id)
+    }
+
+    /// Get a breakpoint by ID to set its condition, hit condition or log message
+    pub fn breakpoint_mut(&mut self, id: u32) -> Option<&mut Breakpoint> {
+        self.breakpoints_by_id.get_mut(&id)
+    }
+
+    /// IDs of the enabled breakpoints at the given location, in creation order
+    pub fn breakpoints_at(&self, file: Option<&PathBuf>, line: u32) -> Vec<u32> {
+        if !self.has_breakpoint(file, line) {
+            return Vec::new();
+        }
+        let mut ids: Vec<u32> = self
+            .breakpoints_by_id
+            .values()
+            .filter(|bp| bp.enabled && bp.location.line == line)
+            .filter(|bp| bp.location.file.as_ref() == file)
+            .map(|bp| bp.id)
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Count a hit of a breakpoint whose condition holds, returning whether
+    /// its hit condition lets it trigger
+    ///
+    /// A hit condition that does not parse never filters.
+    pub fn record_hit(&mut self, id: u32) -> bool {
+        let Some(bp) = self.breakpoints_by_id.get_mut(&id) else {
+            return false;
+        };
+        bp.hit_count += 1;
+        bp.hit_condition
+            .as_deref()
+            .and_then(HitCondition::parse)
+            .map_or(true, |condition| condition.matches(bp.hit_count))
+    }
+
+    /// Move the breakpoints of `file` that fall in one of the line ranges
+    /// `(first, last, delta)` by that range's delta, returning the IDs and
+    /// new lines of the moved breakpoints
+    ///
+    /// Every breakpoint is matched against its line before any move, so
+    /// ranges shifting into each other don't move a breakpoint twice.
+    pub fn shift_breakpoints(
+        &mut self,
+        file: Option<&PathBuf>,
+        shifts: &[(u32, u32, i64)],
+    ) -> Vec<(u32, u32)> {
+        let mut moved = Vec::new();
+        for bp in self.breakpoints_by_id.values_mut() {
+            if bp.location.file.as_ref() != file {
+                continue;
+            }
+            let line = bp.location.line;
+            let Some((_, _, delta)) = shifts
+                .iter()
+                .find(|(first, last, delta)| *delta != 0 && (*first..=*last).contains(&line))
+            else {
+                continue;
+            };
+            if let Ok(new_line) = u32::try_from(i64::from(line) + delta) {
+                bp.location.line = new_line;
+                moved.push((bp.id, new_line));
+            }
+        }
+
+        if !moved.is_empty() {
+            let lines = self
+                .breakpoints_by_id
+                .values()
+                .filter(|bp| bp.location.file.as_ref() == file)
+                .map(|bp| bp.location.line)
+                .collect();
+            self.breakpoints.insert(file.cloned(), lines);
+        }
+        moved.sort_unstable();
+        moved
+    }
+
+    /// Queue a message from a logpoint
+    pub fn log(&mut self, message: String) {
+        self.log_messages.push(message);
+    }
+
+    /// Take the logpoint messages queued since the last call
+    pub fn take_log_messages(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.log_messages)
+    }
+
+    /// Record that the frame at `depth` (1 = outermost) is about to execute
+    /// `line`, returning whether it just moved onto that line
+    ///
+    /// Breakpoints trigger only when a line is entered, not on every
+    /// instruction of it, and not again when a call made from it returns.
+    pub fn enter_line(&mut self, depth: usize, line: u32) -> bool {
+        self.frame_lines.truncate(depth);
+        let entered = depth > 0 && self.frame_lines.get(depth - 1) != Some(&line);
+        if depth > 0 {
+            self.frame_lines.resize(depth, 0);
+            self.frame_lines[depth - 1] = line;
+        }
+        entered
+    }
+
+    /// Forget the lines recorded by [`Self::enter_line`], for a new run
+    pub fn reset_lines(&mut self) {
+        self.frame_lines.clear();
+    }
+
+    /// The lines recorded by [`Self::enter_line`], outermost frame first
+    pub(super) fn frame_lines(&self) -> &[u32] {
+        &self.frame_lines
+    }
+
+    /// Replace the recorded lines, when the VM steps back to a checkpoint
+    pub(super) fn set_frame_lines(&mut self, lines: Vec<u32>) {
+        self.frame_lines = lines;
+    }
+
     /// Check if there's a breakpoint at the given location
     pub fn has_breakpoint(&self, file: Option<&PathBuf>, line: u32) -> bool {
         self.breakpoints
@@ -308,6 +588,114 @@ mod tests {
         assert!(!ctx.has_breakpoint(Some(&PathBuf::from("other.strat")), 5));
     }
 
+    #[test]
+    fn test_breakpoints_at_shared_line() {
+        let mut ctx = DebugContext::new();
+        let id1 = ctx.add_breakpoint(None, 10);
+        let id2 = ctx.add_breakpoint(None, 10);
+        assert_eq!(ctx.breakpoints_at(None, 10), vec![id1, id2]);
+
+        // The line stays a breakpoint while another breakpoint is on it
+        assert!(ctx.remove_breakpoint(id1));
+        assert!(ctx.has_breakpoint(None, 10));
+        assert_eq!(ctx.breakpoints_at(None, 10), vec![id2]);
+
+        ctx.breakpoint_mut(id2).unwrap().enabled = false;
+        assert!(ctx.breakpoints_at(None, 10).is_empty());
+    }
+
+    #[test]
+    fn test_hit_condition_parse() {
+        assert_eq!(HitCondition::parse("3"), Some(HitCondition::Equal(3)));
+        assert_eq!(HitCondition::parse("== 3"), Some(HitCondition::Equal(3)));
+        assert_eq!(HitCondition::parse(">3"), Some(HitCondition::Greater(3)));
+        assert_eq!(
+            HitCondition::parse(" >= 3 "),
+            Some(HitCondition::GreaterOrEqual(3))
+        );
+        assert_eq!(HitCondition::parse("<3"), Some(HitCondition::Less(3)));
+        assert_eq!(
+            HitCondition::parse("<=3"),
+            Some(HitCondition::LessOrEqual(3))
+        );
+        assert_eq!(HitCondition::parse("% 2"), Some(HitCondition::Multiple(2)));
+        assert_eq!(HitCondition::parse("% 0"), None);
+        assert_eq!(HitCondition::parse("often"), None);
+    }
+
+    #[test]
+    fn test_record_hit_with_hit_condition() {
+        let mut ctx = DebugContext::new();
+        let id = ctx.add_breakpoint(None, 10);
+        ctx.breakpoint_mut(id).unwrap().hit_condition = Some("% 2".to_string());
+
+        let triggered: Vec<bool> = (0..4).map(|_| ctx.record_hit(id)).collect();
+        assert_eq!(triggered, vec![false, true, false, true]);
+        assert_eq!(ctx.breakpoint(id).unwrap().hit_count, 4);
+    }
+
+    #[test]
+    fn test_enter_line() {
+        let mut ctx = DebugContext::new();
+        assert!(ctx.enter_line(1, 10));
+        // Later instructions on the same line
+        assert!(!ctx.enter_line(1, 10));
+
+        // A call from line 10 and the return to it
+        assert!(ctx.enter_line(2, 3));
+        assert!(!ctx.enter_line(2, 3));
+        assert!(!ctx.enter_line(1, 10));
+
+        assert!(ctx.enter_line(1, 11));
+
+        ctx.reset_lines();
+        assert!(ctx.enter_line(1, 11));
+    }
+
+    #[test]
+    fn test_shift_breakpoints() {
+        let mut ctx = DebugContext::new();
+        let a = ctx.add_breakpoint(None, 3);
+        let b = ctx.add_breakpoint(None, 8);
+        let c = ctx.add_breakpoint(None, 20);
+
+        // Lines 2-5 move down into 6-9, whose own code moves down too
+        let moved = ctx.shift_breakpoints(None, &[(2, 5, 4), (6, 9, 2)]);
+        assert_eq!(moved, vec![(a, 7), (b, 10)]);
+        assert_eq!(ctx.breakpoints_at(None, 7), vec![a]);
+        assert_eq!(ctx.breakpoints_at(None, 10), vec![b]);
+        assert!(!ctx.has_breakpoint(None, 3));
+        assert_eq!(ctx.breakpoint(c).unwrap().location.line, 20);
+    }
+
+    #[test]
+    fn test_log_messages() {
+        let mut ctx = DebugContext::new();
+        ctx.log("x = 1".to_string());
+        ctx.log("x = 2".to_string());
+        assert_eq!(ctx.take_log_messages(), vec!["x = 1", "x = 2"]);
+        assert!(ctx.take_log_messages().is_empty());
+    }
+
+    #[test]
+    fn test_variable_children() {
+        let list = Value::list(vec![Value::Int(1), Value::list(vec![])]);
+        let variable = DebugVariable::from_value("xs".to_string(), &list);
+        assert!(variable.has_children());
+
+        let children = variable.children();
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[0].name, "[0]");
+        assert_eq!(children[0].value, "1");
+        assert!(!children[0].has_children());
+        // An empty list has nothing to expand
+        assert!(!children[1].has_children());
+
+        let scalar = DebugVariable::from_value("n".to_string(), &Value::Int(3));
+        assert!(!scalar.has_children());
+        assert!(scalar.children().is_empty());
+    }
+
     #[test]
     fn test_step_into() {
         let mut ctx = DebugContext::new();
@@ -0,0 +1,181 @@
+//! Deterministic execution
+//!
+//! A VM made with [`VM::deterministic`](super::VM::deterministic) gives the
+//! same results on every run and platform. It owns a [`Determinism`]: a
+//! seeded random number generator for `Random` and `Uuid`, and a virtual
+//! clock for `Time`, `DateTime` and `Uuid.v7`. The clock starts at
+//! 2000-01-01T00:00:00Z and moves only when the program sleeps, which returns
+//! at once instead of waiting. Maps always iterate in insertion order, so they
+//! need nothing here.
+//!
+//! Natives are plain functions of their arguments, so they find the state of
+//! the VM calling them through the call context: while a VM runs code it
+//! [enters](enter) its state, and natives read whatever state was entered
+//! last on their thread. Each VM keeps its own generator and clock however
+//! many run on a thread, and nested runs put back the caller's state when
+//! they return.
+//!
+//! The generator is ChaCha8, whose output is fixed by its algorithm, so a seed
+//! gives the same values whichever version of `rand` is in use.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// Where the virtual clock starts: 2000-01-01T00:00:00Z, in Unix milliseconds
+pub const EPOCH_MILLIS: i64 = 946_684_800_000;
+
+/// The seeded generator and virtual clock of one deterministic VM
+#[derive(Debug)]
+pub(crate) struct Determinism {
+    rng: ChaCha8Rng,
+    clock_millis: i64,
+}
+
+impl Determinism {
+    /// A generator seeded with `seed` and a clock at [`EPOCH_MILLIS`]
+    pub(crate) fn new(seed: u64) -> Self {
+        Self {
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            clock_millis: EPOCH_MILLIS,
+        }
+    }
+
+    /// Move the clock forward by `millis`
+    pub(crate) fn advance(&mut self, millis: i64) {
+        self.clock_millis = self.clock_millis.saturating_add(millis);
+    }
+}
+
+thread_local! {
+    /// The state of the VM whose code is running on this thread, if that VM
+    /// is deterministic
+    static CURRENT: RefCell<Option<Rc<RefCell<Determinism>>>> = const { RefCell::new(None) };
+}
+
+/// A VM's state entered as the call context; the previous one comes back
+/// when it is dropped
+#[must_use]
+pub(crate) struct Entered {
+    previous: Option<Rc<RefCell<Determinism>>>,
+}
+
+impl Drop for Entered {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        CURRENT.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+/// Make `state` the one natives on this thread see, until the returned
+/// guard is dropped. A VM that isn't deterministic enters `None`, so it
+/// doesn't see the state of a VM it was called from.
+pub(crate) fn enter(state: Option<&Rc<RefCell<Determinism>>>) -> Entered {
+    let previous = CURRENT.with(|current| current.replace(state.cloned()));
+    Entered { previous }
+}
+
+/// Whether the running VM is deterministic
+#[must_use]
+pub(crate) fn is_enabled() -> bool {
+    CURRENT.with(|current| current.borrow().is_some())
+}
+
+/// Run `f` with the running VM's seeded generator, or the thread's own
+/// generator when it isn't deterministic
+pub(crate) fn with_rng<R>(f: impl FnOnce(&mut dyn RngCore) -> R) -> R {
+    let state = CURRENT.with(|current| current.borrow().clone());
+    match state {
+        Some(state) => f(&mut state.borrow_mut().rng),
+        None => f(&mut rand::thread_rng()),
+    }
+}
+
+/// The running VM's virtual clock in Unix milliseconds, if it is
+/// deterministic
+#[must_use]
+pub(crate) fn now_millis() -> Option<i64> {
+    CURRENT.with(|current| {
+        current
+            .borrow()
+            .as_ref()
+            .map(|state| state.borrow().clock_millis)
+    })
+}
+
+/// Move the running VM's virtual clock forward by `millis`. Returns false,
+/// leaving the caller to really wait, if it isn't deterministic.
+pub(crate) fn advance(millis: i64) -> bool {
+    CURRENT.with(|current| match current.borrow().as_ref() {
+        Some(state) => {
+            state.borrow_mut().advance(millis);
+            true
+        }
+        None => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    fn state(seed: u64) -> Rc<RefCell<Determinism>> {
+        Rc::new(RefCell::new(Determinism::new(seed)))
+    }
+
+    #[test]
+    fn test_seeded_rng_repeats() {
+        let draw = |state: &Rc<RefCell<Determinism>>| {
+            let _entered = enter(Some(state));
+            (0..4)
+                .map(|_| with_rng(|rng| rng.gen()))
+                .collect::<Vec<u32>>()
+        };
+        assert_eq!(draw(&state(42)), draw(&state(42)));
+        // ChaCha8 is fixed, so seeds give these values on every platform
+        assert_eq!(
+            draw(&state(42)),
+            [962_419_617, 2_928_721_845, 628_724_104, 4_081_401_798]
+        );
+    }
+
+    #[test]
+    fn test_virtual_clock() {
+        assert!(!is_enabled());
+        assert_eq!(now_millis(), None);
+        assert!(!advance(10));
+
+        let state = state(1);
+        {
+            let _entered = enter(Some(&state));
+            assert_eq!(now_millis(), Some(EPOCH_MILLIS));
+            assert!(advance(1500));
+            assert_eq!(now_millis(), Some(EPOCH_MILLIS + 1500));
+        }
+        assert!(!is_enabled());
+        assert_eq!(state.borrow().clock_millis, EPOCH_MILLIS + 1500);
+    }
+
+    #[test]
+    fn test_nested_states_are_separate() {
+        let outer = state(1);
+        let inner = state(1);
+        let _outer = enter(Some(&outer));
+        advance(100);
+        {
+            let _inner = enter(Some(&inner));
+            assert_eq!(now_millis(), Some(EPOCH_MILLIS));
+            {
+                // A VM that isn't deterministic sees the system clock
+                let _plain = enter(None);
+                assert!(!is_enabled());
+            }
+            advance(5);
+        }
+        assert_eq!(now_millis(), Some(EPOCH_MILLIS + 100));
+        assert_eq!(inner.borrow().clock_millis, EPOCH_MILLIS + 5);
+    }
+}
@@ -1,6 +1,7 @@
 //! Runtime errors for the Stratum virtual machine
 
 use std::fmt;
+use std::time::Duration;
 
 use crate::bytecode::Value;
 
@@ -165,6 +166,53 @@ pub enum RuntimeErrorKind {
 
     /// Internal VM error
     Internal(String),
+
+    /// Execution ran past the VM's timeout
+    Timeout(Duration),
+
+    /// A native needed a capability the VM was not granted
+    PermissionDenied(String),
+
+    /// Execution stopped for a shutdown signal, such as SIGINT from Ctrl-C
+    Interrupted(i32),
+}
+
+impl RuntimeErrorKind {
+    /// The stable error code for this kind of error, such as `E0100`,
+    /// explained in [`crate::error_codes`]
+    #[must_use]
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::TypeError { .. } => "E0300",
+            Self::DivisionByZero => "E0301",
+            Self::UndefinedVariable(..) => "E0302",
+            Self::UndefinedField { .. } => "E0303",
+            Self::IndexOutOfBounds { .. } => "E0304",
+            Self::InvalidIndexType { .. } => "E0305",
+            Self::NotCallable(..) => "E0306",
+            Self::ArityMismatch { .. } => "E0307",
+            Self::NotIterable(..) => "E0308",
+            Self::StackUnderflow => "E0309",
+            Self::StackOverflow => "E0310",
+            Self::InvalidOpcode(..) => "E0311",
+            Self::UncaughtException(..) => "E0312",
+            Self::UserError(..) => "E0313",
+            Self::AssertionFailed(..) => "E0314",
+            Self::InvalidOperation(..) => "E0315",
+            Self::KeyNotFound(..) => "E0316",
+            Self::UnhashableType(..) => "E0317",
+            Self::NullReference => "E0318",
+            Self::BreakOutsideLoop => "E0319",
+            Self::ReturnOutsideFunction => "E0320",
+            Self::AwaitOutsideAsync => "E0321",
+            Self::AsyncError(..) => "E0322",
+            Self::DataError(..) => "E0323",
+            Self::Internal(..) => "E0324",
+            Self::Timeout(..) => "E0325",
+            Self::PermissionDenied(..) => "E0326",
+            Self::Interrupted(..) => "E0327",
+        }
+    }
 }
 
 impl fmt::Display for RuntimeErrorKind {
@@ -231,6 +279,17 @@ impl fmt::Display for RuntimeErrorKind {
             RuntimeErrorKind::AsyncError(msg) => write!(f, "async error: {msg}"),
             RuntimeErrorKind::DataError(msg) => write!(f, "data error: {msg}"),
             RuntimeErrorKind::Internal(msg) => write!(f, "internal error: {msg}"),
+            RuntimeErrorKind::Timeout(timeout) => {
+                write!(f, "timed out after {}ms", timeout.as_millis())
+            }
+            RuntimeErrorKind::PermissionDenied(msg) => write!(f, "permission denied: {msg}"),
+            RuntimeErrorKind::Interrupted(signal) => {
+                write!(
+                    f,
+                    "interrupted by {}",
+                    super::shutdown::signal_name(*signal)
+                )
+            }
         }
     }
 }
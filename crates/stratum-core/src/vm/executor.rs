@@ -18,11 +18,11 @@ use futures_util::{SinkExt, StreamExt};
 use tokio_tungstenite::connect_async;
 use tokio_tungstenite::tungstenite::Message as WsMessage;
 
-use super::{RuntimeError, RuntimeErrorKind, RuntimeResult, VM};
+use super::{determinism, RuntimeError, RuntimeErrorKind, RuntimeResult, VM};
 use crate::bytecode::{
-    CoroutineState, CoroutineStatus, FutureState, FutureStatus, HashableValue, TcpListenerWrapper,
-    TcpStreamWrapper, UdpSocketWrapper, Value, WebSocketServerConnWrapper, WebSocketServerWrapper,
-    WebSocketWrapper,
+    ChildProcessWrapper, CoroutineState, CoroutineStatus, FutureState, FutureStatus, HashableValue,
+    TcpListenerWrapper, TcpStreamWrapper, UdpSocketWrapper, Value, ValueMap,
+    WebSocketServerConnWrapper, WebSocketServerWrapper, WebSocketWrapper,
 };
 use std::sync::Arc;
 
@@ -208,8 +208,11 @@ impl AsyncExecutor {
                         "sleep" => {
                             // Get sleep duration from metadata
                             if let Some(Value::Int(ms)) = &metadata {
-                                let duration = std::time::Duration::from_millis(*ms as u64);
-                                tokio::time::sleep(duration).await;
+                                // Deterministic VMs only move their virtual clock
+                                if !determinism::advance(*ms) {
+                                    let duration = std::time::Duration::from_millis(*ms as u64);
+                                    tokio::time::sleep(duration).await;
+                                }
                                 Ok(Value::Null)
                             } else {
                                 Err("sleep: invalid duration metadata".to_string())
@@ -430,8 +433,8 @@ impl AsyncExecutor {
                                             ),
                                         };
                                         // Return a map with data, host, port
-                                        let result = Value::Map(Rc::new(RefCell::new({
-                                            let mut m = std::collections::HashMap::new();
+                                        let result = Value::map({
+                                            let mut m = ValueMap::new();
                                             m.insert(
                                                 HashableValue::String(Rc::new("data".into())),
                                                 data,
@@ -445,7 +448,7 @@ impl AsyncExecutor {
                                                 Value::Int(addr.port() as i64),
                                             );
                                             m
-                                        })));
+                                        });
                                         Ok(result)
                                     }
                                     Err(e) => Err(format!("udp_recv_from: {e}")),
@@ -570,7 +573,7 @@ impl AsyncExecutor {
                                     Some(Ok(msg)) => {
                                         let result = match msg {
                                             WsMessage::Text(text) => {
-                                                let mut m = std::collections::HashMap::new();
+                                                let mut m = ValueMap::new();
                                                 m.insert(
                                                     HashableValue::String(Rc::new("type".into())),
                                                     Value::string("text"),
@@ -579,14 +582,14 @@ impl AsyncExecutor {
                                                     HashableValue::String(Rc::new("data".into())),
                                                     Value::string(text.to_string()),
                                                 );
-                                                Value::Map(Rc::new(RefCell::new(m)))
+                                                Value::map(m)
                                             }
                                             WsMessage::Binary(data) => {
                                                 let bytes: Vec<Value> = data
                                                     .iter()
                                                     .map(|b| Value::Int(*b as i64))
                                                     .collect();
-                                                let mut m = std::collections::HashMap::new();
+                                                let mut m = ValueMap::new();
                                                 m.insert(
                                                     HashableValue::String(Rc::new("type".into())),
                                                     Value::string("binary"),
@@ -595,13 +598,13 @@ impl AsyncExecutor {
                                                     HashableValue::String(Rc::new("data".into())),
                                                     Value::list(bytes),
                                                 );
-                                                Value::Map(Rc::new(RefCell::new(m)))
+                                                Value::map(m)
                                             }
                                             WsMessage::Ping(_)
                                             | WsMessage::Pong(_)
                                             | WsMessage::Frame(_) => {
                                                 // Control frames - return empty with type
-                                                let mut m = std::collections::HashMap::new();
+                                                let mut m = ValueMap::new();
                                                 m.insert(
                                                     HashableValue::String(Rc::new("type".into())),
                                                     Value::string("control"),
@@ -610,11 +613,11 @@ impl AsyncExecutor {
                                                     HashableValue::String(Rc::new("data".into())),
                                                     Value::Null,
                                                 );
-                                                Value::Map(Rc::new(RefCell::new(m)))
+                                                Value::map(m)
                                             }
                                             WsMessage::Close(_) => {
                                                 ws_wrapper.set_closed();
-                                                let mut m = std::collections::HashMap::new();
+                                                let mut m = ValueMap::new();
                                                 m.insert(
                                                     HashableValue::String(Rc::new("type".into())),
                                                     Value::string("close"),
@@ -623,7 +626,7 @@ impl AsyncExecutor {
                                                     HashableValue::String(Rc::new("data".into())),
                                                     Value::Null,
                                                 );
-                                                Value::Map(Rc::new(RefCell::new(m)))
+                                                Value::map(m)
                                             }
                                         };
                                         Ok(result)
@@ -634,7 +637,7 @@ impl AsyncExecutor {
                                     }
                                     None => {
                                         ws_wrapper.set_closed();
-                                        let mut m = std::collections::HashMap::new();
+                                        let mut m = ValueMap::new();
                                         m.insert(
                                             HashableValue::String(Rc::new("type".into())),
                                             Value::string("close"),
@@ -643,7 +646,7 @@ impl AsyncExecutor {
                                             HashableValue::String(Rc::new("data".into())),
                                             Value::Null,
                                         );
-                                        Ok(Value::Map(Rc::new(RefCell::new(m))))
+                                        Ok(Value::map(m))
                                     }
                                 }
                             } else {
@@ -718,7 +721,7 @@ impl AsyncExecutor {
                                     Some(Ok(msg)) => {
                                         let result = match msg {
                                             WsMessage::Text(text) => {
-                                                let mut m = std::collections::HashMap::new();
+                                                let mut m = ValueMap::new();
                                                 m.insert(
                                                     HashableValue::String(Rc::new("type".into())),
                                                     Value::string("text"),
@@ -727,14 +730,14 @@ impl AsyncExecutor {
                                                     HashableValue::String(Rc::new("data".into())),
                                                     Value::string(text.to_string()),
                                                 );
-                                                Value::Map(Rc::new(RefCell::new(m)))
+                                                Value::map(m)
                                             }
                                             WsMessage::Binary(data) => {
                                                 let bytes: Vec<Value> = data
                                                     .iter()
                                                     .map(|b| Value::Int(*b as i64))
                                                     .collect();
-                                                let mut m = std::collections::HashMap::new();
+                                                let mut m = ValueMap::new();
                                                 m.insert(
                                                     HashableValue::String(Rc::new("type".into())),
                                                     Value::string("binary"),
@@ -743,12 +746,12 @@ impl AsyncExecutor {
                                                     HashableValue::String(Rc::new("data".into())),
                                                     Value::list(bytes),
                                                 );
-                                                Value::Map(Rc::new(RefCell::new(m)))
+                                                Value::map(m)
                                             }
                                             WsMessage::Ping(_)
                                             | WsMessage::Pong(_)
                                             | WsMessage::Frame(_) => {
-                                                let mut m = std::collections::HashMap::new();
+                                                let mut m = ValueMap::new();
                                                 m.insert(
                                                     HashableValue::String(Rc::new("type".into())),
                                                     Value::string("control"),
@@ -757,11 +760,11 @@ impl AsyncExecutor {
                                                     HashableValue::String(Rc::new("data".into())),
                                                     Value::Null,
                                                 );
-                                                Value::Map(Rc::new(RefCell::new(m)))
+                                                Value::map(m)
                                             }
                                             WsMessage::Close(_) => {
                                                 conn_wrapper.set_closed();
-                                                let mut m = std::collections::HashMap::new();
+                                                let mut m = ValueMap::new();
                                                 m.insert(
                                                     HashableValue::String(Rc::new("type".into())),
                                                     Value::string("close"),
@@ -770,7 +773,7 @@ impl AsyncExecutor {
                                                     HashableValue::String(Rc::new("data".into())),
                                                     Value::Null,
                                                 );
-                                                Value::Map(Rc::new(RefCell::new(m)))
+                                                Value::map(m)
                                             }
                                         };
                                         Ok(result)
@@ -781,7 +784,7 @@ impl AsyncExecutor {
                                     }
                                     None => {
                                         conn_wrapper.set_closed();
-                                        let mut m = std::collections::HashMap::new();
+                                        let mut m = ValueMap::new();
                                         m.insert(
                                             HashableValue::String(Rc::new("type".into())),
                                             Value::string("close"),
@@ -790,7 +793,7 @@ impl AsyncExecutor {
                                             HashableValue::String(Rc::new("data".into())),
                                             Value::Null,
                                         );
-                                        Ok(Value::Map(Rc::new(RefCell::new(m))))
+                                        Ok(Value::map(m))
                                     }
                                 }
                             } else {
@@ -939,6 +942,125 @@ impl AsyncExecutor {
                                 Err("Async.timeout: invalid metadata".to_string())
                             }
                         }
+                        "process_read" => {
+                            // Read a line or chunk from a child process's stdout/stderr.
+                            // Pipe reads block, so they run on tokio's blocking pool.
+                            let request = match &metadata {
+                                Some(Value::Map(map_ref)) => {
+                                    let map = map_ref.borrow();
+                                    let get = |key: &str| {
+                                        map.get(&HashableValue::String(Rc::new(key.into())))
+                                            .cloned()
+                                    };
+                                    match (
+                                        get("process"),
+                                        get("stream"),
+                                        get("line"),
+                                        get("max_bytes"),
+                                    ) {
+                                        (
+                                            Some(Value::ChildProcess(proc)),
+                                            Some(Value::String(stream)),
+                                            Some(Value::Bool(line)),
+                                            Some(Value::Int(max_bytes)),
+                                        ) => Some((
+                                            proc,
+                                            stream.as_str() == "stderr",
+                                            line,
+                                            max_bytes,
+                                        )),
+                                        _ => None,
+                                    }
+                                }
+                                _ => None,
+                            };
+                            match request {
+                                Some((proc, is_stderr, line, max_bytes)) => {
+                                    let task = tokio::task::spawn_blocking(move || {
+                                        read_process_pipe(
+                                            &proc,
+                                            is_stderr,
+                                            line,
+                                            max_bytes as usize,
+                                        )
+                                    });
+                                    match task.await {
+                                        Ok(Ok(Some(bytes))) => match String::from_utf8(bytes) {
+                                            Ok(s) => Ok(Value::string(s)),
+                                            Err(e) => Ok(Value::list(
+                                                e.into_bytes()
+                                                    .into_iter()
+                                                    .map(|b| Value::Int(i64::from(b)))
+                                                    .collect(),
+                                            )),
+                                        },
+                                        Ok(Ok(None)) => Ok(Value::Null),
+                                        Ok(Err(e)) => Err(format!("process_read: {e}")),
+                                        Err(e) => Err(format!("process_read: {e}")),
+                                    }
+                                }
+                                None => Err("process_read: invalid metadata".to_string()),
+                            }
+                        }
+                        "process_wait" => {
+                            // Poll the child for exit, killing it if the timeout elapses
+                            let request = match &metadata {
+                                Some(Value::Map(map_ref)) => {
+                                    let map = map_ref.borrow();
+                                    let proc =
+                                        map.get(&HashableValue::String(Rc::new("process".into())));
+                                    let timeout = map
+                                        .get(&HashableValue::String(Rc::new("timeout_ms".into())));
+                                    match (proc, timeout) {
+                                        (Some(Value::ChildProcess(proc)), Some(Value::Int(ms))) => {
+                                            Some((Arc::clone(proc), Some(*ms as u64)))
+                                        }
+                                        (Some(Value::ChildProcess(proc)), _) => {
+                                            Some((Arc::clone(proc), None))
+                                        }
+                                        _ => None,
+                                    }
+                                }
+                                _ => None,
+                            };
+                            match request {
+                                Some((proc, timeout_ms)) => {
+                                    let started = std::time::Instant::now();
+                                    loop {
+                                        let status = match proc.child.lock() {
+                                            Ok(mut child) => child.try_wait(),
+                                            Err(_) => {
+                                                break Err("process_wait: lock poisoned".to_string())
+                                            }
+                                        };
+                                        match status {
+                                            Ok(Some(status)) => {
+                                                break Ok(Value::Int(
+                                                    super::natives::exit_status_code(status),
+                                                ))
+                                            }
+                                            Ok(None) => {}
+                                            Err(e) => break Err(format!("process_wait: {e}")),
+                                        }
+                                        if let Some(ms) = timeout_ms {
+                                            if started.elapsed().as_millis() >= u128::from(ms) {
+                                                if let Ok(mut child) = proc.child.lock() {
+                                                    let _ = child.kill();
+                                                    let _ = child.wait();
+                                                }
+                                                break Err(format!(
+                                                    "process {} timed out after {ms}ms and was killed",
+                                                    proc.pid
+                                                ));
+                                            }
+                                        }
+                                        tokio::time::sleep(std::time::Duration::from_millis(10))
+                                            .await;
+                                    }
+                                }
+                                None => Err("process_wait: invalid metadata".to_string()),
+                            }
+                        }
                         "spawn" => {
                             // Async.spawn - run closure on separate OS thread
                             // For true parallelism, we need to handle closures specially:
@@ -1052,6 +1174,59 @@ impl AsyncExecutor {
     }
 }
 
+/// Blocking read from a child process pipe.
+/// Returns `None` at end-of-file. In line mode the trailing newline is stripped.
+fn read_process_pipe(
+    proc: &ChildProcessWrapper,
+    is_stderr: bool,
+    line: bool,
+    max_bytes: usize,
+) -> Result<Option<Vec<u8>>, String> {
+    use std::io::{BufRead, Read};
+
+    fn read_from<R: BufRead>(
+        reader: &mut R,
+        line: bool,
+        max_bytes: usize,
+    ) -> std::io::Result<Option<Vec<u8>>> {
+        let mut buf = Vec::new();
+        if line {
+            if reader.read_until(b'\n', &mut buf)? == 0 {
+                return Ok(None);
+            }
+            if buf.last() == Some(&b'\n') {
+                buf.pop();
+                if buf.last() == Some(&b'\r') {
+                    buf.pop();
+                }
+            }
+        } else {
+            buf.resize(max_bytes, 0);
+            let n = reader.read(&mut buf)?;
+            if n == 0 {
+                return Ok(None);
+            }
+            buf.truncate(n);
+        }
+        Ok(Some(buf))
+    }
+
+    let result = if is_stderr {
+        let mut pipe = proc.stderr.lock().map_err(|_| "stderr lock poisoned")?;
+        match pipe.as_mut() {
+            Some(reader) => read_from(reader, line, max_bytes),
+            None => return Ok(None),
+        }
+    } else {
+        let mut pipe = proc.stdout.lock().map_err(|_| "stdout lock poisoned")?;
+        match pipe.as_mut() {
+            Some(reader) => read_from(reader, line, max_bytes),
+            None => return Ok(None),
+        }
+    };
+    result.map_err(|e| e.to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1128,4 +1303,61 @@ mod tests {
             _ => panic!("Expected Suspended"),
         }
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_process_streaming_read_and_wait() {
+        use crate::vm::natives::{child_process_method, process_method};
+
+        let proc = process_method("start", &[Value::string("cat")]).unwrap();
+        let Value::ChildProcess(proc) = proc else {
+            panic!("Expected ChildProcess");
+        };
+        child_process_method(&proc, "write_line", &[Value::string("first")]).unwrap();
+        child_process_method(&proc, "write", &[Value::string("second\n")]).unwrap();
+        child_process_method(&proc, "close_stdin", &[]).unwrap();
+
+        let executor = AsyncExecutor::new();
+        let local_set = LocalSet::new();
+        let await_method = |method: &str| {
+            let future = child_process_method(&proc, method, &[]).unwrap();
+            executor
+                .runtime
+                .block_on(local_set.run_until(executor.wait_for_future(&future)))
+        };
+
+        assert_eq!(await_method("read_line"), Value::string("first"));
+        assert_eq!(await_method("read_line"), Value::string("second"));
+        assert_eq!(await_method("read_line"), Value::Null);
+        assert_eq!(await_method("wait"), Value::Int(0));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_process_wait_timeout_kills() {
+        use crate::vm::natives::{child_process_method, process_method};
+
+        let proc = process_method(
+            "start",
+            &[
+                Value::string("sleep"),
+                Value::list(vec![Value::string("10")]),
+            ],
+        )
+        .unwrap();
+        let Value::ChildProcess(proc) = proc else {
+            panic!("Expected ChildProcess");
+        };
+
+        let executor = AsyncExecutor::new();
+        let future = child_process_method(&proc, "wait", &[Value::Int(50)]).unwrap();
+        let result = executor
+            .runtime
+            .block_on(LocalSet::new().run_until(executor.wait_for_future(&future)));
+        assert!(matches!(result, Value::String(s) if s.contains("timed out")));
+        assert_ne!(
+            child_process_method(&proc, "try_wait", &[]).unwrap(),
+            Value::Null
+        );
+    }
 }
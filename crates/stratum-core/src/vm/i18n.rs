@@ -0,0 +1,591 @@
+//! Translation catalogs and locale-aware formatting for the `I18n` namespace
+//!
+//! Catalogs are Fluent (`.ftl`) resources, one bundle per locale. Messages
+//! are looked up through a chain of locales negotiated from the requested
+//! ones (the OS preferences unless `I18n.set_locale` was called) against the
+//! locales that have catalogs, ending with the fallback locale. Fluent's
+//! selectors pick plural forms by the CLDR rules of the message's locale.
+//!
+//! Numbers and dates are formatted for the current locale: the first in the
+//! chain, or the first requested locale when no catalogs are loaded. In
+//! deterministic mode the OS preferences read as `en-US`, so runs repeat on
+//! every machine.
+
+use std::cell::RefCell;
+use std::fs;
+use std::path::Path;
+
+use chrono::{TimeZone, Utc};
+use fluent_bundle::{FluentArgs, FluentBundle, FluentResource, FluentValue};
+use fluent_langneg::{negotiate_languages, NegotiationStrategy};
+use intl_pluralrules::{PluralCategory, PluralRuleType, PluralRules};
+use unic_langid::LanguageIdentifier;
+
+use super::determinism;
+use crate::bytecode::Value;
+
+/// Locale messages fall back to unless `I18n.set_fallback` says otherwise
+const DEFAULT_FALLBACK: &str = "en-US";
+
+struct Catalog {
+    locale: LanguageIdentifier,
+    bundle: FluentBundle<FluentResource>,
+}
+
+struct State {
+    catalogs: Vec<Catalog>,
+    /// Locales asked for with `I18n.set_locale`, or `None` to follow the OS
+    requested: Option<Vec<LanguageIdentifier>>,
+    fallback: LanguageIdentifier,
+    /// Locales messages are looked up in, best first
+    chain: Vec<LanguageIdentifier>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            catalogs: Vec::new(),
+            requested: None,
+            fallback: DEFAULT_FALLBACK.parse().unwrap_or_default(),
+            chain: Vec::new(),
+        }
+    }
+}
+
+impl State {
+    fn requested(&self) -> Vec<LanguageIdentifier> {
+        self.requested.clone().unwrap_or_else(system_locales)
+    }
+
+    /// Negotiate the lookup chain again after the catalogs or the requested
+    /// locales change
+    fn resolve(&mut self) {
+        let requested = self.requested();
+        let available: Vec<LanguageIdentifier> =
+            self.catalogs.iter().map(|c| c.locale.clone()).collect();
+        let default = available
+            .iter()
+            .find(|locale| **locale == self.fallback)
+            .or_else(|| {
+                available
+                    .iter()
+                    .find(|locale| locale.language == self.fallback.language)
+            });
+        self.chain = negotiate_languages(
+            &requested,
+            &available,
+            default,
+            NegotiationStrategy::Filtering,
+        )
+        .into_iter()
+        .cloned()
+        .collect();
+    }
+
+    fn locale(&self) -> LanguageIdentifier {
+        self.chain
+            .first()
+            .cloned()
+            .or_else(|| self.requested().into_iter().next())
+            .unwrap_or_else(|| self.fallback.clone())
+    }
+
+    fn catalog(&self, locale: &LanguageIdentifier) -> Option<&Catalog> {
+        self.catalogs.iter().find(|c| c.locale == *locale)
+    }
+}
+
+thread_local! {
+    static STATE: RefCell<State> = RefCell::new(State::default());
+
+    /// Locale of the message being formatted, for `format_fluent_number`
+    static FORMATTING: RefCell<Option<LanguageIdentifier>> = const { RefCell::new(None) };
+}
+
+/// Parse a locale such as `de-AT` or `pt_BR`
+pub(crate) fn parse_locale(locale: &str) -> Result<LanguageIdentifier, String> {
+    // POSIX names such as "de_AT.UTF-8" carry an encoding the tag can't
+    let tag = locale.split(['.', '@']).next().unwrap_or_default();
+    tag.replace('_', "-")
+        .parse()
+        .map_err(|_| format!("invalid locale '{locale}'"))
+}
+
+/// The user's preferred locales, from the OS
+pub(crate) fn system_locales() -> Vec<LanguageIdentifier> {
+    if determinism::is_enabled() {
+        return DEFAULT_FALLBACK.parse().into_iter().collect();
+    }
+    sys_locale::get_locales()
+        .filter_map(|locale| parse_locale(&locale).ok())
+        // "C" and "POSIX" parse as languages but name none
+        .filter(|locale| !matches!(locale.language.as_str(), "c" | "posix"))
+        .collect()
+}
+
+/// Add Fluent `source` to the catalog for `locale`. Messages already in the
+/// catalog are replaced by ones with the same name.
+pub(crate) fn add_source(locale: &str, source: String) -> Result<(), String> {
+    let locale = parse_locale(locale)?;
+    let resource = FluentResource::try_new(source).map_err(|(_, errors)| {
+        let first = errors
+            .first()
+            .map(|e| format!("{:?}", e.kind))
+            .unwrap_or_default();
+        format!("invalid Fluent catalog for {locale}: {first}")
+    })?;
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        if state.catalog(&locale).is_none() {
+            let mut bundle = FluentBundle::new(vec![locale.clone()]);
+            // Isolation marks suit bidirectional GUI text but litter
+            // terminal output, so they are left out
+            bundle.set_use_isolating(false);
+            bundle.set_formatter(Some(format_fluent_number));
+            state.catalogs.push(Catalog {
+                locale: locale.clone(),
+                bundle,
+            });
+        }
+        if let Some(catalog) = state.catalogs.iter_mut().find(|c| c.locale == locale) {
+            catalog.bundle.add_resource_overriding(resource);
+        }
+        state.resolve();
+    });
+    Ok(())
+}
+
+/// Load the catalogs at `path` and return their locales. A `.ftl` file is
+/// the catalog for the locale it is named after, as in `de-AT.ftl`; a
+/// directory holds such files, or a directory per locale of `.ftl` files.
+pub(crate) fn load(path: &Path) -> Result<Vec<String>, String> {
+    let read = |path: &Path| {
+        fs::read_to_string(path)
+            .map_err(|e| format!("failed to read catalog '{}': {}", path.display(), e))
+    };
+    let locale_of = |path: &Path| {
+        path.file_stem()
+            .and_then(|stem| stem.to_str())
+            .map(str::to_string)
+            .unwrap_or_default()
+    };
+    let is_ftl = |path: &Path| path.extension().is_some_and(|ext| ext == "ftl");
+    let sorted_entries = |dir: &Path| -> Result<Vec<std::path::PathBuf>, String> {
+        let mut entries: Vec<_> = fs::read_dir(dir)
+            .map_err(|e| format!("failed to read directory '{}': {}", dir.display(), e))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .collect();
+        entries.sort();
+        Ok(entries)
+    };
+
+    let mut locales = Vec::new();
+    if path.is_file() {
+        let locale = locale_of(path);
+        add_source(&locale, read(path)?)?;
+        locales.push(locale);
+    } else {
+        for entry in sorted_entries(path)? {
+            if entry.is_dir() {
+                let locale = entry
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .unwrap_or_default()
+                    .to_string();
+                for file in sorted_entries(entry.as_path())?
+                    .into_iter()
+                    .filter(|file| is_ftl(file.as_path()))
+                {
+                    add_source(&locale, read(file.as_path())?)?;
+                }
+                locales.push(locale);
+            } else if is_ftl(entry.as_path()) {
+                let locale = locale_of(entry.as_path());
+                add_source(&locale, read(entry.as_path())?)?;
+                locales.push(locale);
+            }
+        }
+    }
+    locales.dedup();
+    Ok(locales)
+}
+
+/// Ask for `locales`, best first, and return the locale now in use
+pub(crate) fn set_locales(locales: &[String]) -> Result<String, String> {
+    let requested = locales
+        .iter()
+        .map(|locale| parse_locale(locale))
+        .collect::<Result<Vec<_>, _>>()?;
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        state.requested = Some(requested);
+        state.resolve();
+        Ok(state.locale().to_string())
+    })
+}
+
+/// Fall back to `locale` for messages no requested locale has
+pub(crate) fn set_fallback(locale: &str) -> Result<(), String> {
+    let fallback = parse_locale(locale)?;
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        state.fallback = fallback;
+        state.resolve();
+    });
+    Ok(())
+}
+
+/// The locale messages, numbers and dates are shown in
+pub(crate) fn current_locale() -> LanguageIdentifier {
+    STATE.with(|state| state.borrow().locale())
+}
+
+/// The locales that have catalogs, in the order they were loaded
+pub(crate) fn available_locales() -> Vec<String> {
+    STATE.with(|state| {
+        state
+            .borrow()
+            .catalogs
+            .iter()
+            .map(|c| c.locale.to_string())
+            .collect()
+    })
+}
+
+/// Format the message `key`, or its attribute for `message.attribute`, in
+/// the first locale of the chain that has it
+pub(crate) fn translate(key: &str, args: &[(String, Value)]) -> Option<String> {
+    let (id, attribute) = match key.split_once('.') {
+        Some((id, attribute)) => (id, Some(attribute)),
+        None => (key, None),
+    };
+    let mut fluent_args = FluentArgs::new();
+    for (name, value) in args {
+        let value = match value {
+            Value::Int(i) => FluentValue::from(*i),
+            Value::Float(f) => FluentValue::from(*f),
+            other => FluentValue::from(other.to_string()),
+        };
+        fluent_args.set(name.clone(), value);
+    }
+
+    STATE.with(|state| {
+        let state = state.borrow();
+        state.chain.iter().find_map(|locale| {
+            let catalog = state.catalog(locale)?;
+            let message = catalog.bundle.get_message(id)?;
+            let pattern = match attribute {
+                Some(attribute) => message.get_attribute(attribute)?.value(),
+                None => message.value()?,
+            };
+            FORMATTING.with(|formatting| *formatting.borrow_mut() = Some(locale.clone()));
+            // A missing argument shows as its name in braces, as Fluent
+            // intends, rather than failing the whole message
+            let mut errors = Vec::new();
+            let text = catalog
+                .bundle
+                .format_pattern(pattern, Some(&fluent_args), &mut errors)
+                .into_owned();
+            FORMATTING.with(|formatting| formatting.borrow_mut().take());
+            Some(text)
+        })
+    })
+}
+
+/// Show numbers in messages with the separators of the message's locale
+fn format_fluent_number<M>(value: &FluentValue<'_>, _memoizer: &M) -> Option<String> {
+    let FluentValue::Number(number) = value else {
+        return None;
+    };
+    let locale = FORMATTING.with(|formatting| formatting.borrow().clone())?;
+    let text = match number.options.minimum_fraction_digits {
+        Some(digits) => format!("{:.*}", digits, number.value),
+        None => number.value.to_string(),
+    };
+    Some(format_number(&text, &locale, number.options.use_grouping))
+}
+
+/// The CLDR plural category of `number` in `locale`: zero, one, two, few,
+/// many or other. `number` is written out, so "1.0" and "1" can differ as
+/// the rules require.
+pub(crate) fn plural_category(number: &str, locale: &LanguageIdentifier) -> &'static str {
+    let category = PluralRules::create(locale.clone(), PluralRuleType::CARDINAL)
+        .and_then(|rules| rules.select(number))
+        .unwrap_or(PluralCategory::OTHER);
+    match category {
+        PluralCategory::ZERO => "zero",
+        PluralCategory::ONE => "one",
+        PluralCategory::TWO => "two",
+        PluralCategory::FEW => "few",
+        PluralCategory::MANY => "many",
+        PluralCategory::OTHER => "other",
+    }
+}
+
+/// Separators a locale writes numbers with
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct NumberStyle {
+    decimal: char,
+    group: char,
+    /// Groups of two digits above the thousands, as in 12,34,567
+    indian: bool,
+}
+
+fn number_style(locale: &LanguageIdentifier) -> NumberStyle {
+    let style = |decimal, group| NumberStyle {
+        decimal,
+        group,
+        indian: false,
+    };
+    let region = locale.region.as_ref().map(|region| region.as_str());
+    match (locale.language.as_str(), region) {
+        ("de" | "it", Some("CH" | "LI")) => style('.', '\u{2019}'),
+        ("es", Some("MX" | "US")) => style('.', ','),
+        ("pt", Some("PT")) => style(',', '\u{a0}'),
+        ("en", Some("IN")) | ("hi" | "bn" | "mr" | "gu" | "ta" | "te" | "kn" | "ml", _) => {
+            NumberStyle {
+                indian: true,
+                ..style('.', ',')
+            }
+        }
+        ("fr", _) => style(',', '\u{202f}'),
+        (
+            "de" | "es" | "it" | "nl" | "pt" | "id" | "tr" | "da" | "el" | "ro" | "hr" | "sl"
+            | "sr" | "vi" | "ca",
+            _,
+        ) => style(',', '.'),
+        (
+            "ru" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "nn" | "no" | "uk" | "hu" | "bg"
+            | "lt" | "lv" | "et" | "be" | "kk",
+            _,
+        ) => style(',', '\u{a0}'),
+        _ => style('.', ','),
+    }
+}
+
+/// Rewrite `number`, written the way Rust writes numbers ("-1234.5"), with
+/// the separators of `locale`
+pub(crate) fn format_number(number: &str, locale: &LanguageIdentifier, grouping: bool) -> String {
+    let style = number_style(locale);
+    let (sign, unsigned) = match number.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", number),
+    };
+    // "inf" and "NaN" have no digits to separate
+    if !unsigned.starts_with(|c: char| c.is_ascii_digit()) {
+        return number.to_string();
+    }
+    let (whole, fraction) = match unsigned.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (unsigned, None),
+    };
+
+    let mut result = sign.to_string();
+    let len = whole.len();
+    for (i, digit) in whole.chars().enumerate() {
+        let left = len - i;
+        let boundary = if style.indian {
+            left == 3 || (left > 3 && (left - 3) % 2 == 0)
+        } else {
+            left % 3 == 0
+        };
+        if grouping && i > 0 && boundary {
+            result.push(style.group);
+        }
+        result.push(digit);
+    }
+    if let Some(fraction) = fraction {
+        result.push(style.decimal);
+        result.push_str(fraction);
+    }
+    result
+}
+
+/// Format the UTC time `millis` with strftime `format`, naming months and
+/// days and ordering `%x`, `%X` and `%c` the way `locale` does
+pub(crate) fn format_date(
+    millis: i64,
+    format: &str,
+    locale: &LanguageIdentifier,
+) -> Result<String, String> {
+    let time = Utc
+        .timestamp_millis_opt(millis)
+        .single()
+        .ok_or_else(|| format!("invalid timestamp: {millis}"))?;
+    Ok(time
+        .format_localized(format, date_locale(locale))
+        .to_string())
+}
+
+/// The glibc locale whose date formats `locale` uses
+fn date_locale(locale: &LanguageIdentifier) -> chrono::Locale {
+    let language = locale.language.as_str();
+    // Locales without a region take the one their language is mostly
+    // written in
+    let likely = match language {
+        "en" => "US",
+        "ja" => "JP",
+        "zh" => "CN",
+        "ko" => "KR",
+        "sv" => "SE",
+        "da" => "DK",
+        "nb" | "nn" | "no" => "NO",
+        "cs" => "CZ",
+        "uk" => "UA",
+        "el" => "GR",
+        "hi" => "IN",
+        "vi" => "VN",
+        "ar" => "SA",
+        "he" => "IL",
+        "fa" => "IR",
+        "ca" => "ES",
+        "et" => "EE",
+        "sl" => "SI",
+        "sr" => "RS",
+        _ => "",
+    };
+    let mut candidates = Vec::new();
+    if let Some(region) = &locale.region {
+        candidates.push(format!("{language}_{}", region.as_str()));
+    }
+    if !likely.is_empty() {
+        candidates.push(format!("{language}_{likely}"));
+    }
+    candidates.push(format!("{language}_{}", language.to_uppercase()));
+    candidates
+        .iter()
+        .find_map(|name| chrono::Locale::try_from(name.as_str()).ok())
+        .unwrap_or(chrono::Locale::POSIX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EN: &str = "
+items = { $count ->
+    [one] One item
+   *[other] { $count } items
+}
+greeting = Hello, { $name }!
+    .title = Welcome
+only-english = Only in English
+";
+
+    const PL: &str = "
+items = { $count ->
+    [one] Jeden element
+    [few] { $count } elementy
+   *[many] { $count } elementów
+}
+greeting = Cześć, { $name }!
+";
+
+    fn args(pairs: &[(&str, Value)]) -> Vec<(String, Value)> {
+        pairs
+            .iter()
+            .map(|(name, value)| ((*name).to_string(), value.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_translate_with_plurals_and_fallback() {
+        add_source("en-US", EN.to_string()).unwrap();
+        add_source("pl", PL.to_string()).unwrap();
+        assert_eq!(set_locales(&["pl-PL".to_string()]).unwrap(), "pl");
+
+        let count = |n| translate("items", &args(&[("count", Value::Int(n))])).unwrap();
+        assert_eq!(count(1), "Jeden element");
+        assert_eq!(count(3), "3 elementy");
+        assert_eq!(count(5), "5 elementów");
+        // Numbers in messages take the locale's separators
+        assert_eq!(count(1000), "1\u{a0}000 elementów");
+
+        let name = args(&[("name", Value::string("Ada"))]);
+        assert_eq!(translate("greeting", &name).unwrap(), "Cześć, Ada!");
+        assert_eq!(translate("greeting.title", &name).unwrap(), "Welcome");
+        assert_eq!(translate("only-english", &[]).unwrap(), "Only in English");
+        assert_eq!(translate("missing", &[]), None);
+
+        set_locales(&["en".to_string()]).unwrap();
+        assert_eq!(count(1), "One item");
+        assert_eq!(count(2), "2 items");
+        assert_eq!(available_locales(), vec!["en-US", "pl"]);
+    }
+
+    #[test]
+    fn test_invalid_catalogs_and_locales() {
+        assert!(add_source("en", "greeting = { $name".to_string())
+            .unwrap_err()
+            .contains("invalid Fluent catalog"));
+        assert!(parse_locale("not a locale").is_err());
+        assert_eq!(parse_locale("de_AT.UTF-8").unwrap().to_string(), "de-AT");
+    }
+
+    #[test]
+    fn test_load_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("en.ftl"), "hello = Hello").unwrap();
+        fs::create_dir(dir.path().join("fr")).unwrap();
+        fs::write(dir.path().join("fr").join("main.ftl"), "hello = Bonjour").unwrap();
+        fs::write(dir.path().join("README.md"), "not a catalog").unwrap();
+
+        assert_eq!(load(dir.path()).unwrap(), vec!["en", "fr"]);
+        set_locales(&["fr-CA".to_string()]).unwrap();
+        assert_eq!(translate("hello", &[]).unwrap(), "Bonjour");
+    }
+
+    #[test]
+    fn test_plural_categories() {
+        let en = parse_locale("en").unwrap();
+        let pl = parse_locale("pl").unwrap();
+        assert_eq!(plural_category("1", &en), "one");
+        assert_eq!(plural_category("1.0", &en), "other");
+        assert_eq!(plural_category("22", &pl), "few");
+        assert_eq!(plural_category("25", &pl), "many");
+    }
+
+    #[test]
+    fn test_format_number() {
+        let locale = |tag| parse_locale(tag).unwrap();
+        assert_eq!(
+            format_number("1234567.5", &locale("en-US"), true),
+            "1,234,567.5"
+        );
+        assert_eq!(
+            format_number("-1234567.5", &locale("de"), true),
+            "-1.234.567,5"
+        );
+        assert_eq!(
+            format_number("1234.5", &locale("fr"), true),
+            "1\u{202f}234,5"
+        );
+        assert_eq!(
+            format_number("1234567", &locale("en-IN"), true),
+            "12,34,567"
+        );
+        assert_eq!(
+            format_number("1234.5", &locale("de-CH"), true),
+            "1\u{2019}234.5"
+        );
+        assert_eq!(format_number("2026", &locale("en"), false), "2026");
+        assert_eq!(format_number("NaN", &locale("de"), true), "NaN");
+    }
+
+    #[test]
+    fn test_format_date() {
+        // 2024-03-05T00:00:00Z
+        let millis = 1_709_596_800_000;
+        let date = |tag, format| format_date(millis, format, &parse_locale(tag).unwrap()).unwrap();
+        assert_eq!(date("de", "%x"), "05.03.2024");
+        assert_eq!(date("en-US", "%x"), "03/05/2024");
+        assert_eq!(date("fr", "%e %B %Y"), " 5 mars 2024");
+    }
+
+    #[test]
+    fn test_deterministic_system_locale() {
+        let state = std::rc::Rc::new(RefCell::new(determinism::Determinism::new(1)));
+        let _entered = determinism::enter(Some(&state));
+        assert_eq!(system_locales(), vec![parse_locale("en-US").unwrap()]);
+    }
+}
@@ -3,34 +3,50 @@
 //! This module provides a stack-based bytecode interpreter that executes
 //! compiled Stratum code.
 
+mod allocations;
 mod debug;
+mod determinism;
 mod error;
 mod executor;
+mod i18n;
 mod natives;
 mod output;
+mod permissions;
+mod shutdown;
+mod trace;
 
 pub use debug::{
     Breakpoint, DebugAction, DebugContext, DebugLocation, DebugStackFrame, DebugState,
-    DebugStepResult, DebugVariable, PauseReason,
+    DebugStepResult, DebugVariable, HitCondition, HotReload, PauseReason,
 };
 pub use error::{RuntimeError, RuntimeErrorKind, RuntimeResult, StackFrame};
 pub use executor::{AsyncExecutor, CoroutineResult};
+pub use natives::set_program_args;
 pub use output::{with_output_capture, OutputCapture};
+pub use permissions::{Access, Capability, Grant, Permissions};
+pub use trace::{TraceEntry, DEFAULT_TRACE_CAPACITY};
 
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 
-use crate::ast::ExecutionMode;
+use indexmap::IndexMap;
+
+use crate::ast::{BinOp, Derive, ExecutionMode, UnaryOp, INDEX_METHOD};
 use crate::bytecode::{
-    Chunk, Closure, CoroutineState, EnumVariantInstance, ExpectationState, Function, FutureStatus,
-    HashableValue, NativeFunction, OpCode, Range, SavedCallFrame, SavedExceptionHandler,
-    StructInstance, Upvalue, Value,
+    Chunk, Closure, CoroutineState, CowCell, EnumVariantInstance, ExpectationState, FieldType,
+    Function, FutureStatus, HashableValue, MockState, NativeFunction, OpCode, Range,
+    SavedCallFrame, SavedExceptionHandler, StructInstance, Upvalue, Value, ValueMap, VariantFields,
 };
 use crate::coverage::CoverageCollector;
-use crate::data::{AggSpec, DataFrame, GroupedDataFrame, Rolling, Series};
+use crate::data::{AggSpec, AllocationSite, DataFrame, GroupedDataFrame, Rolling, Series};
 use crate::gc::CycleCollector;
 use crate::jit::{call_jit_function, CompiledFunction, JitCompiler, JitContext};
+use crate::profile::{CpuProfiler, ProfileFrame};
+use allocations::AllocationTracker;
+use determinism::Determinism;
+use trace::{Checkpoint, ExecutionTrace, Undo};
 
 /// Maximum call stack depth
 const MAX_FRAMES: usize = 256;
@@ -38,6 +54,10 @@ const MAX_FRAMES: usize = 256;
 /// Maximum value stack size
 const MAX_STACK: usize = 65536;
 
+/// Instructions executed between checks of the timeout deadline and for
+/// shutdown signals
+const DEADLINE_CHECK_INTERVAL: u32 = 1024;
+
 /// A call frame on the call stack
 #[derive(Clone)]
 struct CallFrame {
@@ -50,6 +70,16 @@ struct CallFrame {
     /// Base of this frame's locals on the value stack
     /// (slot 0 is the function itself for methods, or first local)
     stack_base: usize,
+
+    /// Negate the return value (`!=` dispatched to a struct's `__eq__`)
+    negate_result: bool,
+
+    /// Instead of pushing the return value, jump the caller by this offset
+    /// when it is falsy (`CompareJumpIfFalse` dispatched to an impl method)
+    branch_if_false: Option<i16>,
+
+    /// Tracing span covering the call, closed when the frame is dropped
+    span: tracing::Span,
 }
 
 impl CallFrame {
@@ -58,6 +88,18 @@ impl CallFrame {
             closure,
             ip: 0,
             stack_base,
+            negate_result: false,
+            branch_if_false: None,
+            span: tracing::Span::none(),
+        }
+    }
+
+    /// The value handed back to the caller when this frame returns `result`
+    fn return_value(&self, result: Value) -> Value {
+        if self.negate_result {
+            Value::Bool(!result.is_truthy())
+        } else {
+            result
         }
     }
 
@@ -99,6 +141,16 @@ pub type VmMethodHandler = fn(&mut VM, &str, &[Value]) -> RuntimeResult<Value>;
 /// Used to enable method chaining like `element.bold().color(255, 0, 0)`
 pub type ValueMethodHandler = fn(&Value, &str, &[Value]) -> Result<Value, String>;
 
+/// Type for declaring what an external namespace's methods need from the
+/// VM's permissions. Takes the method name and arguments, and returns the
+/// accesses the call needs, or `None` for a method the namespace doesn't know,
+/// which then only runs when every capability is granted
+pub type NamespaceAccess = fn(&str, &[Value]) -> Option<Vec<Access>>;
+
+/// Type for declaring what methods on an external value type need, as
+/// [`NamespaceAccess`] with the receiver first
+pub type ValueMethodAccess = fn(&Value, &str, &[Value]) -> Option<Vec<Access>>;
+
 /// The Stratum Virtual Machine
 pub struct VM {
     /// Value stack
@@ -140,6 +192,14 @@ pub struct VM {
     /// Debug context for breakpoints and stepping
     debug_context: DebugContext,
 
+    /// Functions swapped out by a hot reload, keyed by the old function's
+    /// pointer, with the closure that replaced them (the old function is
+    /// kept so the pointer stays unique)
+    replaced_functions: HashMap<*const Function, (Rc<Function>, Rc<Closure>)>,
+
+    /// Checkpoints taken on entering lines in debug mode, for stepping back
+    trace: ExecutionTrace,
+
     /// Current source file being executed (for debug location tracking)
     current_source: Option<std::path::PathBuf>,
 
@@ -152,17 +212,51 @@ pub struct VM {
     /// Coverage collector (if coverage tracking is enabled)
     coverage: Option<CoverageCollector>,
 
+    /// CPU profiler (if CPU profiling is enabled)
+    cpu_profiler: Option<CpuProfiler>,
+
+    /// Values reported to the memory profiler (if allocation profiling is
+    /// enabled)
+    allocations: Option<AllocationTracker>,
+
+    /// Deadline after which execution fails with a timeout, and the timeout
+    /// it was set from
+    deadline: Option<(Instant, Duration)>,
+
+    /// Instructions left before the deadline is next checked
+    deadline_countdown: u32,
+
+    /// Whether SIGINT and SIGTERM stop execution gracefully
+    watch_signals: bool,
+
+    /// Callbacks from System.on_exit, oldest first
+    exit_hooks: Vec<Value>,
+
+    /// Seed of the random number generator, if this VM runs deterministically
+    seed: Option<u64>,
+
+    /// Seeded generator and virtual clock natives use while this VM runs, if
+    /// it runs deterministically
+    determinism: Option<Rc<RefCell<Determinism>>>,
+
+    /// Capabilities natives may use
+    permissions: Permissions,
+
+    /// Mocks standing in for functions (from Test.mock), oldest first
+    mocks: Vec<Rc<RefCell<MockState>>>,
+
     /// Registry for external namespace handlers (e.g., Gui namespace from stratum-gui)
-    /// Maps namespace name to handler function
-    external_namespaces: HashMap<String, NamespaceHandler>,
+    /// Maps namespace name to handler function and its access declaration
+    external_namespaces: HashMap<String, (NamespaceHandler, NamespaceAccess)>,
 
     /// Registry for special methods that need VM access
     /// Maps (namespace, method) to handler function
     vm_method_handlers: HashMap<(String, String), VmMethodHandler>,
 
     /// Registry for value-type method handlers (e.g., GuiElement methods)
-    /// Maps type name to handler function for method chaining support
-    value_method_handlers: HashMap<String, ValueMethodHandler>,
+    /// Maps type name to handler function for method chaining support, and
+    /// its access declaration
+    value_method_handlers: HashMap<String, (ValueMethodHandler, ValueMethodAccess)>,
 }
 
 impl Default for VM {
@@ -189,10 +283,22 @@ impl VM {
             call_counts: HashMap::new(),
             hot_threshold: DEFAULT_HOT_THRESHOLD,
             debug_context: DebugContext::new(),
+            replaced_functions: HashMap::new(),
+            trace: ExecutionTrace::default(),
             current_source: None,
             gc: CycleCollector::new(),
             pending_spawn: false,
             coverage: None,
+            cpu_profiler: None,
+            allocations: None,
+            deadline: None,
+            deadline_countdown: DEADLINE_CHECK_INTERVAL,
+            watch_signals: false,
+            exit_hooks: Vec::new(),
+            seed: None,
+            determinism: None,
+            permissions: Permissions::allow_all(),
+            mocks: Vec::new(),
             external_namespaces: HashMap::new(),
             vm_method_handlers: HashMap::new(),
             value_method_handlers: HashMap::new(),
@@ -212,6 +318,49 @@ impl VM {
         vm
     }
 
+    /// Create a VM whose runs repeat exactly, for tests and simulations
+    ///
+    /// `Random` and `Uuid` draw from a generator seeded with `seed`. `Time`,
+    /// `DateTime` and `Uuid.v7` read a virtual clock that starts at
+    /// 2000-01-01T00:00:00Z in UTC and advances only when the program
+    /// sleeps, which returns without waiting. Maps iterate in insertion
+    /// order, as they always do. The generator and clock belong to this VM,
+    /// so other VMs on the same thread don't disturb them.
+    #[must_use]
+    pub fn deterministic(seed: u64) -> Self {
+        let mut vm = Self::new();
+        vm.seed = Some(seed);
+        vm.determinism = Some(Rc::new(RefCell::new(Determinism::new(seed))));
+        vm
+    }
+
+    /// The seed this VM was made with by [`VM::deterministic`], if any
+    #[must_use]
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// Move the virtual clock of a deterministic VM forward, as if the
+    /// program had slept for `duration`. Does nothing for other VMs.
+    pub fn advance_clock(&mut self, duration: Duration) {
+        if let Some(state) = &self.determinism {
+            let millis = i64::try_from(duration.as_millis()).unwrap_or(i64::MAX);
+            state.borrow_mut().advance(millis);
+        }
+    }
+
+    /// Limit the files, hosts, environment variables and processes natives
+    /// may use; a new VM may use all of them
+    pub fn set_permissions(&mut self, permissions: Permissions) {
+        self.permissions = permissions;
+    }
+
+    /// The capabilities natives may use
+    #[must_use]
+    pub fn permissions(&self) -> &Permissions {
+        &self.permissions
+    }
+
     /// Enable or disable JIT compilation
     pub fn set_jit_enabled(&mut self, enabled: bool) {
         self.jit_enabled = enabled;
@@ -264,6 +413,286 @@ impl VM {
         self.coverage.as_ref()
     }
 
+    /// Enable CPU profiling, sampling the call stack every `interval`
+    /// instructions
+    ///
+    /// Only interpreted code is sampled, so disable the JIT to profile
+    /// functions that would otherwise be compiled.
+    pub fn enable_cpu_profiling(&mut self, interval: u64) {
+        self.cpu_profiler = Some(CpuProfiler::new(interval));
+    }
+
+    /// Take the CPU profiler (transferring ownership)
+    pub fn take_cpu_profile(&mut self) -> Option<CpuProfiler> {
+        self.cpu_profiler.take()
+    }
+
+    /// Get a reference to the CPU profiler
+    #[must_use]
+    pub fn cpu_profile(&self) -> Option<&CpuProfiler> {
+        self.cpu_profiler.as_ref()
+    }
+
+    /// Report the values this VM's instructions allocate, and free, to the
+    /// global memory profiler
+    ///
+    /// The memory profiler must also be enabled for anything to be recorded.
+    pub fn set_allocation_profiling(&mut self, enabled: bool) {
+        self.allocations = enabled.then(AllocationTracker::default);
+    }
+
+    /// Report the profiled values dropped since the last sweep as freed, so
+    /// the memory profiler's live byte counts are current
+    pub fn sweep_allocations(&mut self) {
+        if let Some(tracker) = self.allocations.as_mut() {
+            tracker.sweep();
+        }
+    }
+
+    /// Fail execution with a timeout error once `timeout` has passed from
+    /// now, or remove the limit with `None`
+    ///
+    /// The deadline is checked between bytecode instructions, so a single
+    /// long-running native or JIT-compiled call is not interrupted.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.deadline = timeout.map(|timeout| (Instant::now() + timeout, timeout));
+        self.deadline_countdown = DEADLINE_CHECK_INTERVAL;
+    }
+
+    /// Stop gracefully on SIGINT or SIGTERM instead of being killed
+    ///
+    /// The signal is noticed between bytecode instructions. The `finally`
+    /// blocks of the running try statements run, innermost first, then the
+    /// callbacks registered with `System.on_exit`, newest first, and
+    /// execution then fails with an interrupted error. A second signal
+    /// exits at once.
+    ///
+    /// # Errors
+    /// Returns an error if the signal handlers can't be installed
+    pub fn handle_shutdown_signals(&mut self) -> std::io::Result<()> {
+        shutdown::install()?;
+        self.watch_signals = true;
+        self.deadline_countdown = DEADLINE_CHECK_INTERVAL;
+        Ok(())
+    }
+
+    /// Run the callbacks registered with `System.on_exit`, newest first, as
+    /// the program ends. Each runs once, even if an earlier one fails.
+    ///
+    /// # Errors
+    /// Returns the first error a callback fails with
+    pub fn run_exit_hooks(&mut self) -> RuntimeResult<()> {
+        let mut first_error = None;
+        // Callbacks may register more callbacks, which run too
+        while let Some(hook) = self.exit_hooks.pop() {
+            if let Err(e) = self.invoke_callback(&hook, Vec::new()) {
+                first_error.get_or_insert(e);
+            }
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+
+    /// Fail if the timeout deadline has passed or a shutdown signal arrived,
+    /// reading the clock and signals only every `DEADLINE_CHECK_INTERVAL`
+    /// instructions
+    #[inline]
+    fn check_deadline(&mut self) -> RuntimeResult<()> {
+        if self.deadline.is_none() && !self.watch_signals {
+            return Ok(());
+        }
+        self.deadline_countdown -= 1;
+        if self.deadline_countdown > 0 {
+            return Ok(());
+        }
+        self.deadline_countdown = DEADLINE_CHECK_INTERVAL;
+        if self.watch_signals {
+            if let Some(signal) = shutdown::take_pending() {
+                return Err(self.shut_down(signal));
+            }
+        }
+        if let Some((deadline, timeout)) = self.deadline {
+            if Instant::now() >= deadline {
+                return Err(self.runtime_error(RuntimeErrorKind::Timeout(timeout)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Run the `finally` blocks of the running try statements and then the
+    /// exit callbacks for a shutdown signal, and make the error that stops
+    /// execution
+    #[cold]
+    fn shut_down(&mut self, signal: i32) -> RuntimeError {
+        let interrupted = self.runtime_error(RuntimeErrorKind::Interrupted(signal));
+        // The program is stopping either way, so a failed finally block or
+        // callback is only reported
+        self.run_finally_blocks();
+        if let Err(e) = self.run_exit_hooks() {
+            eprintln!("error in exit callback: {e}");
+        }
+        interrupted
+    }
+
+    /// Unwind the handler stack, innermost first, running the `finally`
+    /// block of each try statement in its frame once the frames above it
+    /// are popped
+    fn run_finally_blocks(&mut self) {
+        while let Some(handler) = self.handlers.pop() {
+            if handler.finally_ip == 0 || handler.frame_index >= self.frames.len() {
+                continue;
+            }
+            while self.frames.len() > handler.frame_index + 1 {
+                let frame = self.frames.pop().unwrap();
+                self.close_upvalues(frame.stack_base);
+            }
+            self.stack.truncate(handler.stack_depth);
+            self.current_frame_mut().ip = handler.finally_ip;
+
+            // Try statements inside the block catch its exceptions; the
+            // outer ones are only unwound
+            let outer = std::mem::take(&mut self.handlers);
+            if let Err(e) = self.run_finally_block() {
+                eprintln!("error in finally block: {e}");
+            }
+            self.handlers = outer;
+        }
+    }
+
+    /// Run the current frame's copy of a `finally` block, which ends by
+    /// returning from the frame
+    fn run_finally_block(&mut self) -> RuntimeResult<()> {
+        let frame_count = self.frames.len();
+        loop {
+            if let Some(exception) = self.current_exception.take() {
+                if !self.handle_exception(exception.clone())? {
+                    return Err(self.runtime_error(RuntimeErrorKind::UncaughtException(exception)));
+                }
+                continue;
+            }
+
+            let frame = self.current_frame();
+            let chunk = frame.chunk();
+            let Some(instruction) = chunk.read_byte(frame.ip) else {
+                return Ok(());
+            };
+            let opcode = OpCode::try_from(instruction)
+                .map_err(|op| self.runtime_error(RuntimeErrorKind::InvalidOpcode(op)))?;
+            self.current_frame_mut().ip += 1;
+
+            if opcode == OpCode::Return {
+                // The block's own frame is left for the next handler to unwind
+                if self.frames.len() == frame_count {
+                    return Ok(());
+                }
+                let result = self.pop()?;
+                let frame = &self.frames[self.frames.len() - 1];
+                self.close_upvalues(frame.stack_base);
+                let frame = self.frames.pop().unwrap();
+                let result = frame.return_value(result);
+                self.stack.truncate(frame.stack_base);
+                self.return_to_caller(&frame, result)?;
+                continue;
+            }
+
+            self.execute_opcode(opcode)?;
+            if self.suspended_coroutine.take().is_some() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Report a value created by the current instruction to the memory
+    /// profiler
+    #[inline]
+    fn profile_allocation(&mut self, value: &Value) {
+        let Some(tracker) = self.allocations.as_mut() else {
+            return;
+        };
+        let frames = &self.frames;
+        tracker.track(value, || {
+            let frame = &frames[frames.len() - 1];
+            let chunk = frame.chunk();
+            AllocationSite {
+                function: frame.closure.function.name.clone(),
+                file: chunk.source_name.clone(),
+                line: chunk.get_line(frame.ip.saturating_sub(1)),
+            }
+        });
+    }
+
+    /// Count an instruction for the CPU profiler, sampling the call stack
+    /// when the profiler asks for it
+    #[inline]
+    fn profile_instruction(&mut self) {
+        let Some(profiler) = self.cpu_profiler.as_mut() else {
+            return;
+        };
+        if !profiler.tick() {
+            return;
+        }
+
+        // The innermost frame is about to execute the instruction at its
+        // ip; callers are inside the call instruction before theirs
+        let innermost = self.frames.len() - 1;
+        let stack: Vec<ProfileFrame> = self
+            .frames
+            .iter()
+            .enumerate()
+            .map(|(i, frame)| {
+                let chunk = frame.chunk();
+                let offset = if i == innermost {
+                    frame.ip
+                } else {
+                    frame.ip.saturating_sub(1)
+                };
+                ProfileFrame {
+                    function: frame.closure.function.name.clone(),
+                    file: chunk.source_name.clone(),
+                    line: chunk.get_line(offset),
+                }
+            })
+            .collect();
+        if let Some(profiler) = self.cpu_profiler.as_mut() {
+            profiler.record(stack);
+        }
+    }
+
+    /// Record the instruction about to execute for coverage, returning the
+    /// frame depth, offset and fall-through offset of a conditional jump so
+    /// its outcome can be recorded once it has executed
+    #[inline]
+    fn cover_instruction(&mut self, opcode: OpCode) -> Option<(usize, usize, usize)> {
+        let coverage = self.coverage.as_mut()?;
+        let frame = self.frames.last()?;
+        coverage.record_instruction(&frame.closure.function, frame.ip);
+        let is_branch = matches!(
+            opcode,
+            OpCode::JumpIfFalse
+                | OpCode::CompareJumpIfFalse
+                | OpCode::JumpIfTrue
+                | OpCode::JumpIfNull
+                | OpCode::JumpIfNotNull
+                | OpCode::PopJumpIfNull
+                | OpCode::IterNext
+        );
+        is_branch.then_some((self.frames.len(), frame.ip, frame.ip + opcode.size()))
+    }
+
+    /// Record whether the conditional jump at `offset` jumped, judging by
+    /// where its frame's ip ended up
+    fn cover_branch(&mut self, (depth, offset, fall_through): (usize, usize, usize)) {
+        // An overloaded comparison calls into an impl method and branches
+        // when it returns, so its outcome isn't known yet
+        if self.frames.len() != depth {
+            return;
+        }
+        let Some(coverage) = self.coverage.as_mut() else {
+            return;
+        };
+        let frame = &self.frames[depth - 1];
+        coverage.record_branch(&frame.closure.function, offset, frame.ip != fall_through);
+    }
+
     // ============================================================================
     // External Namespace Registration
     // ============================================================================
@@ -276,19 +705,25 @@ impl VM {
     /// # Arguments
     /// * `namespace` - The namespace name (e.g., "Gui")
     /// * `handler` - A function that dispatches method calls for this namespace
+    /// * `access` - A function that declares what each method needs from the
+    ///   VM's permissions, including methods registered with
+    ///   [`register_vm_method`](Self::register_vm_method)
     ///
     /// # Example
     /// ```ignore
-    /// vm.register_namespace("Gui", gui_method);
+    /// vm.register_namespace("Gui", gui_method, gui_access);
     /// ```
-    pub fn register_namespace(&mut self, namespace: &str, handler: NamespaceHandler) {
+    pub fn register_namespace(
+        &mut self,
+        namespace: &str,
+        handler: NamespaceHandler,
+        access: NamespaceAccess,
+    ) {
         // Also register the namespace as a global
-        self.globals.insert(
-            namespace.to_string(),
-            Value::NativeNamespace(Box::leak(namespace.to_string().into_boxed_str())),
-        );
+        self.globals
+            .insert(namespace.to_string(), Value::namespace(namespace));
         self.external_namespaces
-            .insert(namespace.to_string(), handler);
+            .insert(namespace.to_string(), (handler, access));
     }
 
     /// Register a VM method handler for a specific namespace method
@@ -313,15 +748,22 @@ impl VM {
     /// # Arguments
     /// * `type_name` - The type name (e.g., "GuiElement")
     /// * `handler` - A function that handles method calls on this value type
+    /// * `access` - A function that declares what each method needs from the
+    ///   VM's permissions
     ///
     /// # Example
     /// ```ignore
-    /// vm.register_value_method_handler("GuiElement", gui_element_method);
+    /// vm.register_value_method_handler("GuiElement", gui_element_method, gui_element_access);
     /// // Now Stratum code can use: element.bold().width(100)
     /// ```
-    pub fn register_value_method_handler(&mut self, type_name: &str, handler: ValueMethodHandler) {
+    pub fn register_value_method_handler(
+        &mut self,
+        type_name: &str,
+        handler: ValueMethodHandler,
+        access: ValueMethodAccess,
+    ) {
         self.value_method_handlers
-            .insert(type_name.to_string(), handler);
+            .insert(type_name.to_string(), (handler, access));
     }
 
     /// Check if an external namespace is registered
@@ -349,6 +791,7 @@ impl VM {
         }
 
         // Compile the function
+        let _span = tracing::debug_span!("jit_compile", function = %name, arity).entered();
         let compiler = self.get_jit_compiler();
         match compiler.compile_function(function) {
             Ok(ptr) => {
@@ -360,7 +803,10 @@ impl VM {
                 self.jit_context.register(name, ptr, arity);
                 Ok(compiled)
             }
-            Err(e) => Err(format!("JIT compilation failed: {}", e)),
+            Err(e) => {
+                tracing::debug!(error = %e, "JIT compilation failed");
+                Err(format!("JIT compilation failed: {}", e))
+            }
         }
     }
 
@@ -1947,121 +2393,137 @@ impl VM {
 
         // Register native namespace modules
         self.globals
-            .insert("File".to_string(), Value::NativeNamespace("File"));
+            .insert("File".to_string(), Value::namespace("File"));
         self.globals
-            .insert("Dir".to_string(), Value::NativeNamespace("Dir"));
+            .insert("Dir".to_string(), Value::namespace("Dir"));
         self.globals
-            .insert("Path".to_string(), Value::NativeNamespace("Path"));
+            .insert("Path".to_string(), Value::namespace("Path"));
         self.globals
-            .insert("Env".to_string(), Value::NativeNamespace("Env"));
+            .insert("Env".to_string(), Value::namespace("Env"));
         self.globals
-            .insert("Args".to_string(), Value::NativeNamespace("Args"));
+            .insert("Args".to_string(), Value::namespace("Args"));
         self.globals
-            .insert("Shell".to_string(), Value::NativeNamespace("Shell"));
+            .insert("Shell".to_string(), Value::namespace("Shell"));
         self.globals
-            .insert("Http".to_string(), Value::NativeNamespace("Http"));
+            .insert("Http".to_string(), Value::namespace("Http"));
 
         // Data encoding modules
         self.globals
-            .insert("Json".to_string(), Value::NativeNamespace("Json"));
+            .insert("Json".to_string(), Value::namespace("Json"));
         self.globals
-            .insert("Toml".to_string(), Value::NativeNamespace("Toml"));
+            .insert("Toml".to_string(), Value::namespace("Toml"));
         self.globals
-            .insert("Yaml".to_string(), Value::NativeNamespace("Yaml"));
+            .insert("Yaml".to_string(), Value::namespace("Yaml"));
         self.globals
-            .insert("Base64".to_string(), Value::NativeNamespace("Base64"));
+            .insert("Csv".to_string(), Value::namespace("Csv"));
         self.globals
-            .insert("Url".to_string(), Value::NativeNamespace("Url"));
+            .insert("Base64".to_string(), Value::namespace("Base64"));
+        self.globals
+            .insert("Url".to_string(), Value::namespace("Url"));
 
         // Compression modules
         self.globals
-            .insert("Gzip".to_string(), Value::NativeNamespace("Gzip"));
+            .insert("Gzip".to_string(), Value::namespace("Gzip"));
+        self.globals
+            .insert("Zip".to_string(), Value::namespace("Zip"));
         self.globals
-            .insert("Zip".to_string(), Value::NativeNamespace("Zip"));
+            .insert("Zstd".to_string(), Value::namespace("Zstd"));
+        self.globals
+            .insert("Tar".to_string(), Value::namespace("Tar"));
 
         // DateTime and Time modules
         self.globals
-            .insert("DateTime".to_string(), Value::NativeNamespace("DateTime"));
+            .insert("DateTime".to_string(), Value::namespace("DateTime"));
         self.globals
-            .insert("Duration".to_string(), Value::NativeNamespace("Duration"));
+            .insert("Duration".to_string(), Value::namespace("Duration"));
         self.globals
-            .insert("Time".to_string(), Value::NativeNamespace("Time"));
+            .insert("Time".to_string(), Value::namespace("Time"));
 
         // Regex module
         self.globals
-            .insert("Regex".to_string(), Value::NativeNamespace("Regex"));
+            .insert("Regex".to_string(), Value::namespace("Regex"));
+
+        // Template module
+        self.globals
+            .insert("Template".to_string(), Value::namespace("Template"));
 
-        // Hashing, UUID, and Random modules
+        // Hashing, Crypto, UUID, and Random modules
+        self.globals
+            .insert("Hash".to_string(), Value::namespace("Hash"));
         self.globals
-            .insert("Hash".to_string(), Value::NativeNamespace("Hash"));
+            .insert("Crypto".to_string(), Value::namespace("Crypto"));
         self.globals
-            .insert("Uuid".to_string(), Value::NativeNamespace("Uuid"));
+            .insert("Uuid".to_string(), Value::namespace("Uuid"));
         self.globals
-            .insert("Random".to_string(), Value::NativeNamespace("Random"));
+            .insert("Random".to_string(), Value::namespace("Random"));
 
         // Math module (constants and functions)
         self.globals
-            .insert("Math".to_string(), Value::NativeNamespace("Math"));
+            .insert("Math".to_string(), Value::namespace("Math"));
 
         // User Input module
         self.globals
-            .insert("Input".to_string(), Value::NativeNamespace("Input"));
+            .insert("Input".to_string(), Value::namespace("Input"));
 
         // Logging module
         self.globals
-            .insert("Log".to_string(), Value::NativeNamespace("Log"));
+            .insert("Log".to_string(), Value::namespace("Log"));
+
+        // Translation and locale formatting module
+        self.globals
+            .insert("I18n".to_string(), Value::namespace("I18n"));
 
         // System info module
         self.globals
-            .insert("System".to_string(), Value::NativeNamespace("System"));
+            .insert("System".to_string(), Value::namespace("System"));
 
         // Process module (non-blocking process spawn and control)
         self.globals
-            .insert("Process".to_string(), Value::NativeNamespace("Process"));
+            .insert("Process".to_string(), Value::namespace("Process"));
 
         // Signal module (signal handling)
         self.globals
-            .insert("Signal".to_string(), Value::NativeNamespace("Signal"));
+            .insert("Signal".to_string(), Value::namespace("Signal"));
 
         // Database module
         self.globals
-            .insert("Db".to_string(), Value::NativeNamespace("Db"));
+            .insert("Db".to_string(), Value::namespace("Db"));
 
         // Network modules (TCP/UDP/WebSocket)
         self.globals
-            .insert("Tcp".to_string(), Value::NativeNamespace("Tcp"));
+            .insert("Tcp".to_string(), Value::namespace("Tcp"));
         self.globals
-            .insert("Udp".to_string(), Value::NativeNamespace("Udp"));
+            .insert("Udp".to_string(), Value::namespace("Udp"));
         self.globals
-            .insert("WebSocket".to_string(), Value::NativeNamespace("WebSocket"));
+            .insert("WebSocket".to_string(), Value::namespace("WebSocket"));
 
         // Data operations module (DataFrame, Series)
         self.globals
-            .insert("Data".to_string(), Value::NativeNamespace("Data"));
+            .insert("Data".to_string(), Value::namespace("Data"));
 
         // Aggregation builder module (for group_by + aggregate)
         self.globals
-            .insert("Agg".to_string(), Value::NativeNamespace("Agg"));
+            .insert("Agg".to_string(), Value::namespace("Agg"));
 
         // Join builder module (for DataFrame joins)
         self.globals
-            .insert("Join".to_string(), Value::NativeNamespace("Join"));
+            .insert("Join".to_string(), Value::namespace("Join"));
 
         // Cube module (OLAP cube for multi-dimensional analysis)
         self.globals
-            .insert("Cube".to_string(), Value::NativeNamespace("Cube"));
+            .insert("Cube".to_string(), Value::namespace("Cube"));
 
         // Set module for creating sets
         self.globals
-            .insert("Set".to_string(), Value::NativeNamespace("Set"));
+            .insert("Set".to_string(), Value::namespace("Set"));
 
         // Test module for testing framework
         self.globals
-            .insert("Test".to_string(), Value::NativeNamespace("Test"));
+            .insert("Test".to_string(), Value::namespace("Test"));
 
         // Ref module for weak references
         self.globals
-            .insert("Ref".to_string(), Value::NativeNamespace("Ref"));
+            .insert("Ref".to_string(), Value::namespace("Ref"));
 
         // Note: GUI module is registered at runtime via register_namespace()
         // This allows stratum-gui to register itself without circular dependencies
@@ -2075,8 +2537,7 @@ impl VM {
         function: fn(&[Value]) -> Result<Value, String>,
     ) {
         let native = NativeFunction::new(name, arity, function);
-        self.globals
-            .insert(name.to_string(), Value::NativeFunction(native));
+        self.globals.insert(name.to_string(), Value::native(native));
     }
 
     /// Execute a compiled function
@@ -2097,7 +2558,7 @@ impl VM {
         self.stack.push(Value::Closure(closure.clone()));
 
         // Create the initial frame
-        self.frames.push(CallFrame::new(closure.clone(), 0));
+        self.push_frame(closure.clone(), 0);
 
         // Track function coverage if enabled
         if let Some(ref mut coverage) = self.coverage {
@@ -2110,6 +2571,7 @@ impl VM {
 
     /// Main execution loop
     fn execute(&mut self) -> RuntimeResult<Value> {
+        let _entered = determinism::enter(self.determinism.as_ref());
         loop {
             // Check for exception propagation
             if let Some(exception) = self.current_exception.take() {
@@ -2138,6 +2600,10 @@ impl VM {
             let opcode = OpCode::try_from(instruction)
                 .map_err(|op| self.runtime_error(RuntimeErrorKind::InvalidOpcode(op)))?;
 
+            self.profile_instruction();
+            self.check_deadline()?;
+            let branch = self.cover_instruction(opcode);
+
             // Advance IP past the opcode
             self.current_frame_mut().ip += 1;
 
@@ -2151,6 +2617,7 @@ impl VM {
 
                 // Pop the frame
                 let frame = self.frames.pop().unwrap();
+                let result = frame.return_value(result);
 
                 // If this was the last frame, we're done
                 if self.frames.is_empty() {
@@ -2160,13 +2627,16 @@ impl VM {
                 // Pop locals and the function itself
                 self.stack.truncate(frame.stack_base);
 
-                // Push the return value
-                self.push(result)?;
+                // Hand the return value to the caller
+                self.return_to_caller(&frame, result)?;
                 continue;
             }
 
             // Execute all other opcodes
             self.execute_opcode(opcode)?;
+            if let Some(branch) = branch {
+                self.cover_branch(branch);
+            }
 
             // Check if execution was suspended (e.g., by await)
             if let Some(coroutine) = self.suspended_coroutine.take() {
@@ -2203,6 +2673,18 @@ impl VM {
     // ===== Frame operations =====
 
     #[inline]
+    /// Push a call frame, opening a tracing span for the call under the
+    /// caller's span
+    fn push_frame(&mut self, closure: Rc<Closure>, stack_base: usize) {
+        let mut frame = CallFrame::new(closure, stack_base);
+        let function = frame.closure.function.name.as_str();
+        frame.span = match self.frames.last() {
+            Some(caller) => tracing::trace_span!(parent: &caller.span, "call", function),
+            None => tracing::trace_span!(parent: None, "call", function),
+        };
+        self.frames.push(frame);
+    }
+
     fn current_frame(&self) -> &CallFrame {
         &self.frames[self.frames.len() - 1]
     }
@@ -2241,6 +2723,19 @@ impl VM {
         frame.ip = (frame.ip as isize + offset as isize) as usize;
     }
 
+    /// Hand the value returned by `frame` to the frame now on top
+    fn return_to_caller(&mut self, frame: &CallFrame, result: Value) -> RuntimeResult<()> {
+        match frame.branch_if_false {
+            Some(offset) => {
+                if !result.is_truthy() {
+                    self.jump(offset);
+                }
+                Ok(())
+            }
+            None => self.push(result),
+        }
+    }
+
     // ===== Constant pool access =====
 
     fn get_constant(&self, index: usize) -> &Value {
@@ -2368,6 +2863,8 @@ impl VM {
                 closure: f.closure.clone(),
                 ip: f.ip,
                 stack_base: f.stack_base,
+                negate_result: f.negate_result,
+                branch_if_false: f.branch_if_false,
             })
             .collect();
 
@@ -2414,6 +2911,9 @@ impl VM {
                 closure: f.closure.clone(),
                 ip: f.ip,
                 stack_base: f.stack_base,
+                negate_result: f.negate_result,
+                branch_if_false: f.branch_if_false,
+                span: tracing::Span::none(),
             })
             .collect();
 
@@ -2548,17 +3048,30 @@ impl VM {
 
         match callee {
             Value::Closure(closure) => self.call_closure(closure, arg_count),
-            Value::NativeFunction(native) => self.call_native(native, arg_count),
+            Value::NativeFunction(native) => self.call_native(&native, arg_count),
             Value::BoundMethod(method) => {
                 // Replace the method on the stack with the receiver
                 let slot = self.stack.len() - 1 - arg_count as usize;
                 self.stack[slot] = method.receiver.clone();
                 self.call_closure(method.method.clone(), arg_count)
             }
+            Value::Mock(mock) => self.call_mock(&mock, arg_count),
             _ => Err(self.runtime_error(RuntimeErrorKind::NotCallable(callee.type_name()))),
         }
     }
 
+    /// Record a call to a mock and call its replacement in the callee's slot
+    fn call_mock(&mut self, mock: &Rc<RefCell<MockState>>, arg_count: u8) -> RuntimeResult<()> {
+        let args_start = self.stack.len() - arg_count as usize;
+        let replacement = {
+            let mut mock = mock.borrow_mut();
+            mock.calls.push(self.stack[args_start..].to_vec());
+            mock.replacement.clone()
+        };
+        self.stack[args_start - 1] = replacement;
+        self.call_value(arg_count)
+    }
+
     fn call_closure(&mut self, closure: Rc<Closure>, arg_count: u8) -> RuntimeResult<()> {
         if arg_count != closure.function.arity {
             return Err(self.runtime_error(RuntimeErrorKind::ArityMismatch {
@@ -2571,8 +3084,9 @@ impl VM {
             return Err(self.runtime_error(RuntimeErrorKind::StackOverflow));
         }
 
-        // Check if we can use JIT (requires JIT enabled and no upvalues)
-        let can_jit = self.jit_enabled && closure.upvalues.is_empty();
+        // Check if we can use JIT (requires JIT enabled and no upvalues);
+        // coverage is only recorded by the interpreter
+        let can_jit = self.jit_enabled && closure.upvalues.is_empty() && self.coverage.is_none();
 
         if can_jit {
             // Determine if we should use JIT based on execution mode
@@ -2616,7 +3130,7 @@ impl VM {
         // Stack layout: [..., closure, arg0, arg1, ...]
         // stack_base points to closure (slot 0 of the frame)
         let stack_base = self.stack.len() - arg_count as usize - 1;
-        self.frames.push(CallFrame::new(closure, stack_base));
+        self.push_frame(closure, stack_base);
 
         Ok(())
     }
@@ -2636,7 +3150,7 @@ impl VM {
         Ok(result)
     }
 
-    fn call_native(&mut self, native: NativeFunction, arg_count: u8) -> RuntimeResult<()> {
+    fn call_native(&mut self, native: &NativeFunction, arg_count: u8) -> RuntimeResult<()> {
         // Check arity
         if native.arity >= 0 && arg_count != native.arity as u8 {
             return Err(self.runtime_error(RuntimeErrorKind::ArityMismatch {
@@ -2689,7 +3203,7 @@ impl VM {
 
         // Set up the call frame
         let stack_base = self.stack.len() - arity as usize - 1;
-        self.frames.push(CallFrame::new(closure, stack_base));
+        self.push_frame(closure, stack_base);
 
         let mut iterations = 0;
         const MAX_ITERATIONS: usize = 10000;
@@ -2738,6 +3252,9 @@ impl VM {
             let opcode = OpCode::try_from(instruction)
                 .map_err(|op| self.runtime_error(RuntimeErrorKind::InvalidOpcode(op)))?;
 
+            self.profile_instruction();
+            self.check_deadline()?;
+            let branch = self.cover_instruction(opcode);
             self.current_frame_mut().ip += 1;
 
             // Handle Return specially to detect when closure is done
@@ -2750,6 +3267,7 @@ impl VM {
 
                 // Pop the frame
                 let frame = self.frames.pop().unwrap();
+                let result = frame.return_value(result);
 
                 // If we're back to starting frame count (or less), the closure is done
                 if self.frames.len() <= starting_frame_count {
@@ -2760,17 +3278,27 @@ impl VM {
 
                 // Otherwise, push result and continue (nested call within closure)
                 self.stack.truncate(frame.stack_base);
-                self.push(result)?;
+                self.return_to_caller(&frame, result)?;
                 continue;
             }
 
             // Execute other opcodes normally
             self.execute_opcode(opcode)?;
+            if let Some(branch) = branch {
+                self.cover_branch(branch);
+            }
         }
     }
 
     /// Execute a single opcode (extracted from the main loop for reuse)
     fn execute_opcode(&mut self, opcode: OpCode) -> RuntimeResult<()> {
+        // Structs and enums can overload operators with impl methods
+        if let Some((method_name, operand_count)) = Self::operator_method(opcode) {
+            if self.invoke_operator_method(method_name, operand_count, opcode == OpCode::Ne)? {
+                return Ok(());
+            }
+        }
+
         match opcode {
             OpCode::Const => {
                 let index = self.read_u16() as usize;
@@ -2814,6 +3342,36 @@ impl VM {
                 self.set_local(slot, value);
             }
 
+            OpCode::LoadLocalPair => {
+                let first = self.read_u16() as usize;
+                let second = self.read_u16() as usize;
+                let value = self.get_local(first).clone();
+                self.push(value)?;
+                let value = self.get_local(second).clone();
+                self.push(value)?;
+            }
+
+            OpCode::AddLocalConst => {
+                let slot = self.read_u16() as usize;
+                let index = self.read_u16() as usize;
+                let sum = match (self.get_local(slot), self.get_constant(index)) {
+                    (Value::Int(x), Value::Int(y)) => x.checked_add(*y).map(Value::Int),
+                    (Value::Float(x), Value::Float(y)) => Some(Value::Float(x + y)),
+                    _ => None,
+                };
+                if let Some(sum) = sum {
+                    self.push(sum)?;
+                } else {
+                    // Anything but plain numbers, including an overloaded `+`,
+                    // goes through `Add`
+                    let left = self.get_local(slot).clone();
+                    let right = self.get_constant(index).clone();
+                    self.push(left)?;
+                    self.push(right)?;
+                    self.execute_opcode(OpCode::Add)?;
+                }
+            }
+
             OpCode::LoadGlobal => {
                 let name_index = self.read_u16() as usize;
                 let name = self.get_constant_string(name_index)?;
@@ -3092,7 +3650,7 @@ impl VM {
                             })?;
                         self.push(result)?;
                     }
-                    _ => self.push(Value::Bool(left == right))?,
+                    _ => self.push(Value::Bool(self.values_equal(&left, &right)))?,
                 }
             }
 
@@ -3128,7 +3686,7 @@ impl VM {
                             })?;
                         self.push(result)?;
                     }
-                    _ => self.push(Value::Bool(left != right))?,
+                    _ => self.push(Value::Bool(!self.values_equal(&left, &right)))?,
                 }
             }
 
@@ -3203,6 +3761,22 @@ impl VM {
                 }
             }
 
+            OpCode::CompareJumpIfFalse => {
+                let comparison = self.read_u8();
+                let offset = self.read_i16();
+                let comparison = OpCode::try_from(comparison)
+                    .map_err(|op| self.runtime_error(RuntimeErrorKind::InvalidOpcode(op)))?;
+
+                let frame_count = self.frames.len();
+                self.execute_opcode(comparison)?;
+                if self.frames.len() > frame_count {
+                    // An impl method overloads the comparison; branch once it returns
+                    self.current_frame_mut().branch_if_false = Some(offset);
+                } else if !self.pop()?.is_truthy() {
+                    self.jump(offset);
+                }
+            }
+
             OpCode::JumpIfNull => {
                 let offset = self.read_i16();
                 let value = self.peek(0)?;
@@ -3268,7 +3842,9 @@ impl VM {
                     closure.upvalues.push(upvalue);
                 }
 
-                self.push(Value::Closure(Rc::new(closure)))?;
+                let closure = Value::Closure(Rc::new(closure));
+                self.profile_allocation(&closure);
+                self.push(closure)?;
             }
 
             // Object operations
@@ -3320,12 +3896,14 @@ impl VM {
                     items.push(self.pop()?);
                 }
                 items.reverse();
-                self.push(Value::list(items))?;
+                let list = Value::list(items);
+                self.profile_allocation(&list);
+                self.push(list)?;
             }
 
             OpCode::NewMap => {
                 let count = self.read_u16() as usize;
-                let mut map = HashMap::new();
+                let mut map = ValueMap::new();
                 for _ in 0..count {
                     let value = self.pop()?;
                     let key = self.pop()?;
@@ -3334,7 +3912,9 @@ impl VM {
                     })?;
                     map.insert(hashable, value);
                 }
-                self.push(Value::Map(Rc::new(RefCell::new(map))))?;
+                let map = Value::map(map);
+                self.profile_allocation(&map);
+                self.push(map)?;
             }
 
             OpCode::NewSet => {
@@ -3347,7 +3927,9 @@ impl VM {
                     })?;
                     set.insert(hashable);
                 }
-                self.push(Value::Set(Rc::new(RefCell::new(set))))?;
+                let set = Value::Set(Rc::new(RefCell::new(set)));
+                self.profile_allocation(&set);
+                self.push(set)?;
             }
 
             OpCode::NewStruct => {
@@ -3371,7 +3953,9 @@ impl VM {
                     };
                     instance.fields.insert(field_name, value);
                 }
-                self.push(Value::Struct(Rc::new(RefCell::new(instance))))?;
+                let instance = Value::Struct(Rc::new(RefCell::new(instance)));
+                self.profile_allocation(&instance);
+                self.push(instance)?;
             }
 
             // Iteration
@@ -3435,7 +4019,9 @@ impl VM {
                     parts.push(format!("{}", self.pop()?));
                 }
                 parts.reverse();
-                self.push(Value::string(parts.join("")))?;
+                let string = Value::string(parts.join(""));
+                self.profile_allocation(&string);
+                self.push(string)?;
             }
 
             // Range operations
@@ -3619,7 +4205,7 @@ impl VM {
                 let path = self.get_constant_string(path_index)?;
                 // Push a StateBinding value - for now represented as a tagged String
                 // The GUI runtime will interpret this as a binding path
-                self.push(Value::StateBinding(path))?;
+                self.push(Value::state_binding(path))?;
             }
         }
         Ok(())
@@ -3628,6 +4214,13 @@ impl VM {
     fn invoke(&mut self, method_name: String, arg_count: u8) -> RuntimeResult<()> {
         let receiver = self.peek(arg_count as usize)?.clone();
 
+        // A mocked native method is called like the mock's replacement
+        if let Value::NativeNamespace(ns) = &receiver {
+            if let Some(mock) = self.namespace_mock(ns.as_str(), &method_name) {
+                return self.call_mock(&mock, arg_count);
+            }
+        }
+
         match &receiver {
             Value::Struct(instance) => {
                 // Check if there's a method with this name
@@ -3637,9 +4230,40 @@ impl VM {
                         return self.call_closure(closure.clone(), arg_count);
                     }
                 }
+                let type_name = instance.borrow().type_name.clone();
+                // Methods from impl blocks (the receiver becomes `self`)
+                if let Some(method) = self.find_method(&type_name, &method_name) {
+                    return self.call_closure(method, arg_count);
+                }
+                // Methods generated by #[derive(...)] on the struct type
+                if let Some(derive) =
+                    Derive::providing_method(self.type_derives(&type_name), &method_name)
+                {
+                    return self.invoke_derived_method(derive, &receiver, arg_count);
+                }
                 // Try built-in struct methods
                 self.invoke_builtin_method(&receiver, &method_name, arg_count)
             }
+            Value::EnumVariant(variant) => {
+                if let Some(method) = self.find_method(&variant.enum_name, &method_name) {
+                    return self.call_closure(method, arg_count);
+                }
+                if let Some(derive) =
+                    Derive::providing_method(self.type_derives(&variant.enum_name), &method_name)
+                {
+                    return self.invoke_derived_method(derive, &receiver, arg_count);
+                }
+                Err(self.runtime_error(RuntimeErrorKind::UndefinedField {
+                    type_name: variant.enum_name.clone(),
+                    field: method_name,
+                }))
+            }
+            Value::StructType(info) => {
+                self.invoke_static_method(&receiver, &info.name, &method_name, arg_count)
+            }
+            Value::EnumType(info) => {
+                self.invoke_static_method(&receiver, &info.name, &method_name, arg_count)
+            }
             Value::String(_)
             | Value::List(_)
             | Value::Map(_)
@@ -3654,6 +4278,7 @@ impl VM {
             | Value::Cube(_)
             | Value::CubeBuilder(_)
             | Value::CubeQuery(_)
+            | Value::Mock(_)
             | Value::GuiElement(_) => {
                 self.invoke_builtin_method(&receiver, &method_name, arg_count)
             }
@@ -3665,41 +4290,308 @@ impl VM {
         }
     }
 
-    fn invoke_builtin_method(
+    /// Call a static method on a struct or enum type
+    ///
+    /// Methods from impl blocks receive the type itself as `self`; otherwise
+    /// the method must be generated by one of the type's derives.
+    fn invoke_static_method(
         &mut self,
         receiver: &Value,
+        type_name: &str,
         method_name: &str,
         arg_count: u8,
     ) -> RuntimeResult<()> {
-        // Collect arguments
-        let args: Vec<Value> = (0..arg_count)
-            .map(|_| self.pop())
-            .collect::<RuntimeResult<Vec<_>>>()?
-            .into_iter()
-            .rev()
-            .collect();
+        if let Some(method) = self.find_method(type_name, method_name) {
+            return self.call_closure(method, arg_count);
+        }
+        self.invoke_derived_static_method(receiver, method_name, arg_count)
+    }
+
+    /// The impl method that overloads `opcode` and the number of stack
+    /// operands it consumes (the receiver included)
+    fn operator_method(opcode: OpCode) -> Option<(&'static str, u8)> {
+        let op = match opcode {
+            OpCode::Add => BinOp::Add,
+            OpCode::Sub => BinOp::Sub,
+            OpCode::Mul => BinOp::Mul,
+            OpCode::Div => BinOp::Div,
+            OpCode::Mod => BinOp::Mod,
+            OpCode::Eq => BinOp::Eq,
+            OpCode::Ne => BinOp::Ne,
+            OpCode::Lt => BinOp::Lt,
+            OpCode::Le => BinOp::Le,
+            OpCode::Gt => BinOp::Gt,
+            OpCode::Ge => BinOp::Ge,
+            OpCode::Neg => return UnaryOp::Neg.method_name().map(|name| (name, 1)),
+            OpCode::GetIndex => return Some((INDEX_METHOD, 2)),
+            _ => return None,
+        };
+        op.method_name().map(|name| (name, 2))
+    }
 
-        // Pop the receiver
-        self.pop()?;
+    /// Call the impl method overloading an operator when the receiver (the
+    /// deepest of the `operand_count` values on top of the stack) is a struct
+    /// or enum that defines it
+    ///
+    /// Returns `false`, leaving the stack untouched, when the operator isn't
+    /// overloaded so the built-in behaviour applies.
+    fn invoke_operator_method(
+        &mut self,
+        method_name: &str,
+        operand_count: u8,
+        negate: bool,
+    ) -> RuntimeResult<bool> {
+        let type_name = match self.peek(operand_count as usize - 1)? {
+            Value::Struct(instance) => instance.borrow().type_name.clone(),
+            Value::EnumVariant(variant) => variant.enum_name.clone(),
+            _ => return Ok(false),
+        };
+        let Some(method) = self.find_method(&type_name, method_name) else {
+            return Ok(false);
+        };
 
-        let result = match receiver {
-            Value::String(s) => self.string_method(s, method_name, &args)?,
-            Value::List(l) => self.list_method(l, method_name, &args)?,
-            Value::Map(m) => self.map_method(m, method_name, &args)?,
-            Value::Set(s) => self.set_method(s, method_name, &args)?,
-            Value::NativeNamespace(ns) => self.namespace_method_dispatch(ns, method_name, &args)?,
-            Value::DbConnection(conn) => natives::db_connection_method(conn, method_name, &args)
-                .map_err(|msg| self.runtime_error(RuntimeErrorKind::UserError(msg)))?,
-            Value::TcpStream(stream) => natives::tcp_stream_method(stream, method_name, &args)
-                .map_err(|msg| self.runtime_error(RuntimeErrorKind::UserError(msg)))?,
-            Value::TcpListener(listener) => {
-                natives::tcp_listener_method(listener, method_name, &args)
-                    .map_err(|msg| self.runtime_error(RuntimeErrorKind::UserError(msg)))?
+        let frame_count = self.frames.len();
+        self.call_closure(method, operand_count - 1)?;
+        if negate {
+            if self.frames.len() > frame_count {
+                self.current_frame_mut().negate_result = true;
+            } else {
+                // The JIT already ran the method and pushed its result
+                let result = self.pop()?;
+                self.push(Value::Bool(!result.is_truthy()))?;
             }
-            Value::UdpSocket(socket) => natives::udp_socket_method(socket, method_name, &args)
-                .map_err(|msg| self.runtime_error(RuntimeErrorKind::UserError(msg)))?,
-            Value::WebSocket(ws) => natives::websocket_method(ws, method_name, &args)
-                .map_err(|msg| self.runtime_error(RuntimeErrorKind::UserError(msg)))?,
+        }
+        Ok(true)
+    }
+
+    /// Look up a method defined in an impl block (compiled as the global `Type.method`)
+    fn find_method(&self, type_name: &str, method_name: &str) -> Option<Rc<Closure>> {
+        match self.globals.get(&format!("{type_name}.{method_name}")) {
+            Some(Value::Closure(closure)) => Some(closure.clone()),
+            _ => None,
+        }
+    }
+
+    /// Look up the type descriptor bound to a struct or enum name
+    fn type_descriptor(&self, type_name: &str) -> Option<&Value> {
+        self.globals
+            .get(type_name)
+            .filter(|value| matches!(value, Value::StructType(_) | Value::EnumType(_)))
+    }
+
+    /// Behaviours derived by the struct or enum named `type_name`
+    fn type_derives(&self, type_name: &str) -> &[Derive] {
+        match self.type_descriptor(type_name) {
+            Some(Value::StructType(info)) => &info.derives,
+            Some(Value::EnumType(info)) => &info.derives,
+            _ => &[],
+        }
+    }
+
+    /// Call an instance method generated by `#[derive(...)]`
+    fn invoke_derived_method(
+        &mut self,
+        derive: Derive,
+        receiver: &Value,
+        arg_count: u8,
+    ) -> RuntimeResult<()> {
+        let expected = match derive {
+            Derive::Eq => 1,
+            Derive::Show | Derive::Json => 0,
+        };
+        if arg_count != expected {
+            return Err(self.runtime_error(RuntimeErrorKind::ArityMismatch {
+                expected,
+                got: arg_count,
+            }));
+        }
+
+        let args: Vec<Value> = (0..arg_count)
+            .map(|_| self.pop())
+            .collect::<RuntimeResult<Vec<_>>>()?;
+        // Pop the receiver
+        self.pop()?;
+
+        let result = match derive {
+            Derive::Eq => Value::Bool(self.values_equal(receiver, &args[0])),
+            Derive::Show => Value::string(self.show_value(receiver)),
+            Derive::Json => natives::json_method("encode", std::slice::from_ref(receiver))
+                .map_err(|msg| self.runtime_error(RuntimeErrorKind::UserError(msg)))?,
+        };
+        self.push(result)
+    }
+
+    /// Call a static method generated by `#[derive(...)]`, e.g. `Point.from_json(text)`
+    fn invoke_derived_static_method(
+        &mut self,
+        receiver: &Value,
+        method_name: &str,
+        arg_count: u8,
+    ) -> RuntimeResult<()> {
+        let (type_name, derives) = match receiver {
+            Value::StructType(info) => (&info.name, &info.derives),
+            Value::EnumType(info) => (&info.name, &info.derives),
+            _ => unreachable!("receiver is a type descriptor"),
+        };
+        let Some(Derive::Json) = Derive::providing_static_method(derives, method_name) else {
+            return Err(self.runtime_error(RuntimeErrorKind::UndefinedField {
+                type_name: type_name.clone(),
+                field: method_name.to_string(),
+            }));
+        };
+
+        let args: Vec<Value> = (0..arg_count)
+            .map(|_| self.pop())
+            .collect::<RuntimeResult<Vec<_>>>()?
+            .into_iter()
+            .rev()
+            .collect();
+        // Pop the receiver
+        self.pop()?;
+
+        let resolve = |name: &str| self.type_descriptor(name).cloned();
+        let result = natives::json_decode_type(receiver, &args, &resolve)
+            .map_err(|msg| self.runtime_error(RuntimeErrorKind::UserError(msg)))?;
+        self.push(result)
+    }
+
+    /// Equality used by `==` and `!=`: instances of types that derive `Eq`
+    /// compare field by field, everything else uses `Value` equality
+    fn values_equal(&self, left: &Value, right: &Value) -> bool {
+        match (left, right) {
+            (Value::Struct(a), Value::Struct(b)) if !Rc::ptr_eq(a, b) => {
+                let (a, b) = (a.borrow(), b.borrow());
+                a.type_name == b.type_name
+                    && self.type_derives(&a.type_name).contains(&Derive::Eq)
+                    && self.fields_equal(&a, &b)
+            }
+            (Value::EnumVariant(a), Value::EnumVariant(b)) => {
+                a.enum_name == b.enum_name
+                    && a.variant_name == b.variant_name
+                    && match (&a.data, &b.data) {
+                        // Struct-variant payloads follow the enum's derives
+                        (Some(Value::Struct(x)), Some(Value::Struct(y)))
+                            if self.type_derives(&a.enum_name).contains(&Derive::Eq) =>
+                        {
+                            self.fields_equal(&x.borrow(), &y.borrow())
+                        }
+                        (Some(x), Some(y)) => self.values_equal(x, y),
+                        (None, None) => true,
+                        _ => false,
+                    }
+            }
+            (Value::List(a), Value::List(b)) if !Rc::ptr_eq(a, b) => {
+                let (a, b) = (a.borrow(), b.borrow());
+                a.len() == b.len() && a.iter().zip(b.iter()).all(|(x, y)| self.values_equal(x, y))
+            }
+            _ => left == right,
+        }
+    }
+
+    fn fields_equal(&self, a: &StructInstance, b: &StructInstance) -> bool {
+        a.fields.len() == b.fields.len()
+            && a.fields.iter().all(|(name, value)| {
+                b.fields
+                    .get(name)
+                    .is_some_and(|other| self.values_equal(value, other))
+            })
+    }
+
+    /// Render a value for a derived `to_string()`: types that derive `Show`
+    /// print as `Point { x: 1, y: 2 }` or `Shape::Circle(1.5)` with fields in
+    /// declaration order, strings are quoted, everything else uses `Display`
+    fn show_value(&self, value: &Value) -> String {
+        match value {
+            Value::String(s) => format!("{:?}", s.as_str()),
+            Value::List(items) => {
+                let items: Vec<String> =
+                    items.borrow().iter().map(|v| self.show_value(v)).collect();
+                format!("[{}]", items.join(", "))
+            }
+            Value::Struct(instance) => match self.type_descriptor(&instance.borrow().type_name) {
+                Some(Value::StructType(info)) if info.derives.contains(&Derive::Show) => {
+                    self.show_fields(&info.name, &info.fields, &instance.borrow())
+                }
+                _ => value.to_string(),
+            },
+            Value::EnumVariant(variant) => match self.type_descriptor(&variant.enum_name) {
+                Some(Value::EnumType(info)) if info.derives.contains(&Derive::Show) => {
+                    let name = format!("{}::{}", info.name, variant.variant_name);
+                    match (&variant.data, info.variant(&variant.variant_name)) {
+                        (None, _) => name,
+                        (Some(Value::Struct(data)), Some(VariantFields::Struct(fields))) => {
+                            self.show_fields(&name, fields, &data.borrow())
+                        }
+                        (Some(data), _) => format!("{name}({})", self.show_value(data)),
+                    }
+                }
+                _ => value.to_string(),
+            },
+            other => other.to_string(),
+        }
+    }
+
+    fn show_fields(
+        &self,
+        name: &str,
+        fields: &[(String, FieldType)],
+        instance: &StructInstance,
+    ) -> String {
+        let fields: Vec<String> = fields
+            .iter()
+            .filter_map(|(field, _)| {
+                let value = instance.fields.get(field)?;
+                Some(format!("{field}: {}", self.show_value(value)))
+            })
+            .collect();
+        if fields.is_empty() {
+            name.to_string()
+        } else {
+            format!("{name} {{ {} }}", fields.join(", "))
+        }
+    }
+
+    fn invoke_builtin_method(
+        &mut self,
+        receiver: &Value,
+        method_name: &str,
+        arg_count: u8,
+    ) -> RuntimeResult<()> {
+        // Collect arguments
+        let args: Vec<Value> = (0..arg_count)
+            .map(|_| self.pop())
+            .collect::<RuntimeResult<Vec<_>>>()?
+            .into_iter()
+            .rev()
+            .collect();
+
+        // Pop the receiver
+        self.pop()?;
+
+        if !self.permissions.allows_all() {
+            self.check_permissions(receiver, method_name, &args)?;
+        }
+
+        let result = match receiver {
+            Value::String(s) => self.string_method(s, method_name, &args)?,
+            Value::List(l) => self.list_method(l, method_name, &args)?,
+            Value::Map(m) => self.map_method(m, method_name, &args)?,
+            Value::Set(s) => self.set_method(s, method_name, &args)?,
+            Value::NativeNamespace(ns) => {
+                self.namespace_method_dispatch(ns.as_str(), method_name, &args)?
+            }
+            Value::DbConnection(conn) => natives::db_connection_method(conn, method_name, &args)
+                .map_err(|msg| self.runtime_error(RuntimeErrorKind::UserError(msg)))?,
+            Value::TcpStream(stream) => natives::tcp_stream_method(stream, method_name, &args)
+                .map_err(|msg| self.runtime_error(RuntimeErrorKind::UserError(msg)))?,
+            Value::TcpListener(listener) => {
+                natives::tcp_listener_method(listener, method_name, &args)
+                    .map_err(|msg| self.runtime_error(RuntimeErrorKind::UserError(msg)))?
+            }
+            Value::UdpSocket(socket) => natives::udp_socket_method(socket, method_name, &args)
+                .map_err(|msg| self.runtime_error(RuntimeErrorKind::UserError(msg)))?,
+            Value::WebSocket(ws) => natives::websocket_method(ws, method_name, &args)
+                .map_err(|msg| self.runtime_error(RuntimeErrorKind::UserError(msg)))?,
             Value::WebSocketServer(server) => {
                 natives::websocket_server_method(server, method_name, &args)
                     .map_err(|msg| self.runtime_error(RuntimeErrorKind::UserError(msg)))?
@@ -3720,15 +4612,22 @@ impl VM {
             Value::CubeBuilder(builder) => self.cubebuilder_method(builder, method_name, &args)?,
             Value::CubeQuery(query) => self.cubequery_method(query, method_name, &args)?,
             Value::Expectation(exp) => self.expectation_method(exp, method_name, &args)?,
+            Value::Mock(mock) => self.mock_method(mock, method_name, &args)?,
             Value::XmlDocument(doc) => natives::xml_document_method(doc, method_name, &args)
                 .map_err(|msg| self.runtime_error(RuntimeErrorKind::UserError(msg)))?,
             Value::Image(img) => natives::image_method(img, method_name, &args)
                 .map_err(|msg| self.runtime_error(RuntimeErrorKind::UserError(msg)))?,
+            Value::ChildProcess(proc) => natives::child_process_method(proc, method_name, &args)
+                .map_err(|msg| self.runtime_error(RuntimeErrorKind::UserError(msg)))?,
+            Value::CsvReader(reader) => natives::csv_reader_method(reader, method_name, &args)
+                .map_err(|msg| self.runtime_error(RuntimeErrorKind::UserError(msg)))?,
+            Value::CsvWriter(writer) => natives::csv_writer_method(writer, method_name, &args)
+                .map_err(|msg| self.runtime_error(RuntimeErrorKind::UserError(msg)))?,
             Value::WeakRef(weak) => natives::weak_ref_method(method_name, &args, weak)
                 .map_err(|msg| self.runtime_error(RuntimeErrorKind::UserError(msg)))?,
             Value::GuiElement(_) => {
                 // Check if a handler is registered for GuiElement
-                if let Some(handler) = self.value_method_handlers.get("GuiElement") {
+                if let Some((handler, _)) = self.value_method_handlers.get("GuiElement") {
                     handler(receiver, method_name, &args)
                         .map_err(|msg| self.runtime_error(RuntimeErrorKind::UserError(msg)))?
                 } else {
@@ -3749,6 +4648,46 @@ impl VM {
         self.push(result)
     }
 
+    /// Fail if a call to a native needs a capability this VM was not granted
+    fn check_permissions(
+        &self,
+        receiver: &Value,
+        method: &str,
+        args: &[Value],
+    ) -> RuntimeResult<()> {
+        let (operation, accesses) = match receiver {
+            Value::NativeNamespace(ns) => (
+                format!("{ns}.{method}"),
+                match self.external_namespaces.get(ns.as_str()) {
+                    Some(&(_, access)) => access(method, args),
+                    None => permissions::namespace_access(ns.as_str(), method, args),
+                },
+            ),
+            Value::GuiElement(_) => (
+                format!("GuiElement.{method}"),
+                match self.value_method_handlers.get("GuiElement") {
+                    Some(&(_, access)) => access(receiver, method, args),
+                    None => Some(Vec::new()),
+                },
+            ),
+            _ => (
+                format!("{}.{method}", receiver.type_name()),
+                Some(permissions::value_method_access(receiver, method, args)),
+            ),
+        };
+        let Some(accesses) = accesses else {
+            return Err(self.runtime_error(RuntimeErrorKind::PermissionDenied(
+                permissions::undeclared(&operation),
+            )));
+        };
+        for access in &accesses {
+            self.permissions
+                .check(&operation, access)
+                .map_err(|msg| self.runtime_error(RuntimeErrorKind::PermissionDenied(msg)))?;
+        }
+        Ok(())
+    }
+
     fn string_method(&self, s: &Rc<String>, method: &str, args: &[Value]) -> RuntimeResult<Value> {
         match method {
             "length" | "len" => Ok(Value::Int(s.len() as i64)),
@@ -3908,13 +4847,15 @@ impl VM {
 
     fn list_method(
         &mut self,
-        list: &Rc<RefCell<Vec<Value>>>,
+        list: &Rc<CowCell<Vec<Value>>>,
         method: &str,
         args: &[Value],
     ) -> RuntimeResult<Value> {
         match method {
             "length" | "len" => Ok(Value::Int(list.borrow().len() as i64)),
             "is_empty" => Ok(Value::Bool(list.borrow().is_empty())),
+            // An independent list sharing elements until either is mutated
+            "clone" => Ok(Value::List(Rc::new(list.snapshot()))),
             "push" => {
                 if args.len() != 1 {
                     return Err(self.runtime_error(RuntimeErrorKind::ArityMismatch {
@@ -4280,7 +5221,7 @@ impl VM {
                         }));
                     }
                 };
-                let mut groups: HashMap<HashableValue, Vec<Value>> = HashMap::new();
+                let mut groups: IndexMap<HashableValue, Vec<Value>> = IndexMap::new();
                 let input = list.borrow().clone();
                 for item in input {
                     let key = self.call_closure_sync(closure.clone(), vec![item.clone()])?;
@@ -4290,11 +5231,11 @@ impl VM {
                     groups.entry(hashable_key).or_default().push(item);
                 }
                 // Convert to Map<HashableValue, Value::List>
-                let result: HashMap<HashableValue, Value> = groups
+                let result: ValueMap = groups
                     .into_iter()
                     .map(|(k, v)| (k, Value::list(v)))
                     .collect();
-                Ok(Value::Map(Rc::new(RefCell::new(result))))
+                Ok(Value::map(result))
             }
             _ => Err(self.runtime_error(RuntimeErrorKind::UndefinedField {
                 type_name: "List".to_string(),
@@ -4305,13 +5246,15 @@ impl VM {
 
     fn map_method(
         &mut self,
-        map: &Rc<RefCell<HashMap<HashableValue, Value>>>,
+        map: &Rc<CowCell<ValueMap>>,
         method: &str,
         args: &[Value],
     ) -> RuntimeResult<Value> {
         match method {
             "length" | "len" => Ok(Value::Int(map.borrow().len() as i64)),
             "is_empty" => Ok(Value::Bool(map.borrow().is_empty())),
+            // An independent map sharing entries until either is mutated
+            "clone" => Ok(Value::Map(Rc::new(map.snapshot()))),
             "contains_key" | "has" => {
                 if args.len() != 1 {
                     return Err(self.runtime_error(RuntimeErrorKind::ArityMismatch {
@@ -4365,7 +5308,8 @@ impl VM {
                 let key = HashableValue::try_from(args[0].clone()).map_err(|_| {
                     self.runtime_error(RuntimeErrorKind::UnhashableType(args[0].type_name()))
                 })?;
-                Ok(map.borrow_mut().remove(&key).unwrap_or(Value::Null))
+                // O(n), but keeps insertion order; `swap_remove` would not
+                Ok(map.borrow_mut().shift_remove(&key).unwrap_or(Value::Null))
             }
             "keys" => {
                 let keys: Vec<Value> = map
@@ -4598,7 +5542,7 @@ impl VM {
             "is_empty" => Ok(Value::Bool(df.is_empty())),
             "memory_usage" => {
                 let stats = df.memory_usage();
-                let mut map = HashMap::new();
+                let mut map = ValueMap::new();
                 map.insert(
                     HashableValue::String(Rc::new("num_rows".to_string())),
                     Value::Int(stats.num_rows as i64),
@@ -4623,17 +5567,17 @@ impl VM {
                     HashableValue::String(Rc::new("total_formatted".to_string())),
                     Value::string(stats.total_formatted()),
                 );
-                Ok(Value::Map(Rc::new(RefCell::new(map))))
+                Ok(Value::map(map))
             }
             "schema" => {
                 // Return schema as a map of column name -> type string
-                let mut schema_map = HashMap::new();
+                let mut schema_map = ValueMap::new();
                 for field in df.schema().fields() {
                     let key = HashableValue::String(Rc::new(field.name().clone()));
                     let type_str = Value::string(format!("{:?}", field.data_type()));
                     schema_map.insert(key, type_str);
                 }
-                Ok(Value::Map(Rc::new(RefCell::new(schema_map))))
+                Ok(Value::map(schema_map))
             }
 
             // Row operations
@@ -5783,7 +6727,7 @@ impl VM {
             "count" => Ok(Value::Int(series.count() as i64)),
             "memory_usage" => {
                 let stats = series.memory_usage();
-                let mut map = HashMap::new();
+                let mut map = ValueMap::new();
                 map.insert(
                     HashableValue::String(Rc::new("num_rows".to_string())),
                     Value::Int(stats.num_rows as i64),
@@ -5804,7 +6748,7 @@ impl VM {
                     HashableValue::String(Rc::new("total_formatted".to_string())),
                     Value::string(stats.total_formatted()),
                 );
-                Ok(Value::Map(Rc::new(RefCell::new(map))))
+                Ok(Value::map(map))
             }
 
             // Element access
@@ -6810,18 +7754,16 @@ impl VM {
             "hierarchies" => {
                 // Return a Map of hierarchy_name -> [level1, level2, ...]
                 use crate::bytecode::HashableValue;
-                use std::cell::RefCell;
-                use std::collections::HashMap;
                 use std::rc::Rc;
 
                 let hierarchies = cube.hierarchies_with_levels();
-                let mut map = HashMap::new();
+                let mut map = ValueMap::new();
                 for (name, levels) in hierarchies {
                     let key = HashableValue::String(Rc::new(name));
                     let levels_list: Vec<Value> = levels.into_iter().map(Value::string).collect();
                     map.insert(key, Value::list(levels_list));
                 }
-                Ok(Value::Map(Rc::new(RefCell::new(map))))
+                Ok(Value::map(map))
             }
             "dimension_values" => {
                 // dimension_values(dim_name) -> List of unique values
@@ -6890,13 +7832,11 @@ impl VM {
             "cache_stats" => {
                 // cache_stats() -> Map { hits, misses, hit_rate, entries } or Null if no cache
                 use crate::bytecode::HashableValue;
-                use std::cell::RefCell;
-                use std::collections::HashMap;
                 use std::rc::Rc;
 
                 match cube.cache_stats() {
                     Some(stats) => {
-                        let mut map = HashMap::new();
+                        let mut map = ValueMap::new();
                         map.insert(
                             HashableValue::String(Rc::new("hits".to_string())),
                             Value::Int(stats.hits as i64),
@@ -6913,7 +7853,7 @@ impl VM {
                             HashableValue::String(Rc::new("entries".to_string())),
                             Value::Int(stats.entries as i64),
                         );
-                        Ok(Value::Map(Rc::new(RefCell::new(map))))
+                        Ok(Value::map(map))
                     }
                     None => Ok(Value::Null),
                 }
@@ -6970,6 +7910,60 @@ impl VM {
                 Ok(Value::CubeQuery(Arc::new(Mutex::new(Some(query)))))
             }
 
+            // Persistence and refresh
+            "save" => {
+                // save(path) -> Null (write rows and definition to a Parquet file)
+                let path = match _args.first() {
+                    Some(Value::String(s)) => (**s).clone(),
+                    Some(other) => {
+                        return Err(self.runtime_error(RuntimeErrorKind::TypeError {
+                            expected: "String",
+                            got: other.type_name(),
+                            operation: "save",
+                        }))
+                    }
+                    None => {
+                        return Err(self.runtime_error(RuntimeErrorKind::UserError(
+                            "save requires a file path".to_string(),
+                        )))
+                    }
+                };
+                cube.save(&path)
+                    .map_err(|e| self.runtime_error(RuntimeErrorKind::UserError(e.to_string())))?;
+                Ok(Value::Null)
+            }
+
+            "refresh" => {
+                // refresh(df) -> Cube (a new cube with the DataFrame's rows appended)
+                let df = match _args.first() {
+                    Some(Value::DataFrame(df)) => df,
+                    Some(other) => {
+                        return Err(self.runtime_error(RuntimeErrorKind::TypeError {
+                            expected: "DataFrame",
+                            got: other.type_name(),
+                            operation: "refresh",
+                        }))
+                    }
+                    None => {
+                        return Err(self.runtime_error(RuntimeErrorKind::UserError(
+                            "refresh requires a DataFrame of new rows".to_string(),
+                        )))
+                    }
+                };
+                let refreshed = cube
+                    .refresh(df)
+                    .map_err(|e| self.runtime_error(RuntimeErrorKind::UserError(e.to_string())))?;
+                Ok(Value::Cube(std::sync::Arc::new(refreshed)))
+            }
+
+            "aggregates" => {
+                // aggregates() -> DataFrame (every measure by all dimensions)
+                let df = cube
+                    .aggregates()
+                    .map_err(|e| self.runtime_error(RuntimeErrorKind::UserError(e.to_string())))?;
+                Ok(Value::DataFrame(std::sync::Arc::new(df)))
+            }
+
             "to_dataframe" => {
                 // to_dataframe() -> DataFrame (convert cube data to DataFrame)
                 // Create a query that selects all and execute
@@ -8039,6 +9033,31 @@ impl VM {
             return self.test_suite_method(method, args);
         }
 
+        // Test.mock(target, replacement) swaps a function out of this VM
+        if ns == "Test" && method == "mock" && args.len() == 2 {
+            if let Value::String(target) = &args[0] {
+                return self.install_mock(target, args[1].clone());
+            }
+        }
+
+        // System.on_exit() keeps a callback for the VM to run as the program
+        // ends, and System.exit() runs them before exiting
+        if ns == "System" && method == "on_exit" {
+            return self.register_exit_hook(args);
+        }
+        if ns == "System" && method == "exit" && args.len() <= 1 {
+            if let Err(e) = self.run_exit_hooks() {
+                eprintln!("error in exit callback: {e}");
+            }
+        }
+
+        // Json.decode_as() resolves nested struct and enum types through globals
+        if ns == "Json" && method == "decode_as" {
+            let resolve = |name: &str| self.type_descriptor(name).cloned();
+            return natives::json_decode_as(args, &resolve)
+                .map_err(|msg| self.runtime_error(RuntimeErrorKind::UserError(msg)));
+        }
+
         // Check for registered VM method handlers (methods that need VM access)
         let key = (ns.to_string(), method.to_string());
         if let Some(handler) = self.vm_method_handlers.get(&key).copied() {
@@ -8046,7 +9065,7 @@ impl VM {
         }
 
         // Check for registered external namespace handlers
-        if let Some(handler) = self.external_namespaces.get(ns).copied() {
+        if let Some((handler, _)) = self.external_namespaces.get(ns).copied() {
             return handler(method, args)
                 .map_err(|msg| self.runtime_error(RuntimeErrorKind::UserError(msg)));
         }
@@ -8056,6 +9075,156 @@ impl VM {
             .map_err(|msg| self.runtime_error(RuntimeErrorKind::UserError(msg)))
     }
 
+    /// Keep `System.on_exit(callback)`'s callback to run as the program ends
+    fn register_exit_hook(&mut self, args: &[Value]) -> RuntimeResult<Value> {
+        if args.len() != 1 {
+            return Err(self.runtime_error(RuntimeErrorKind::ArityMismatch {
+                expected: 1,
+                got: args.len() as u8,
+            }));
+        }
+        if !matches!(args[0], Value::Closure(_) | Value::NativeFunction(_)) {
+            return Err(self.runtime_error(RuntimeErrorKind::TypeError {
+                expected: "Function",
+                got: args[0].type_name(),
+                operation: "System.on_exit",
+            }));
+        }
+        self.exit_hooks.push(args[0].clone());
+        Ok(Value::Null)
+    }
+
+    // ============================================================================
+    // Mocks (Test.mock("Http.get", |url| ...))
+    // ============================================================================
+
+    /// Stand `replacement` in for a global function, or for a native
+    /// namespace method named `Namespace.method`
+    fn install_mock(&mut self, target: &str, replacement: Value) -> RuntimeResult<Value> {
+        if !matches!(
+            replacement,
+            Value::Closure(_) | Value::NativeFunction(_) | Value::BoundMethod(_) | Value::Mock(_)
+        ) {
+            return Err(self.runtime_error(RuntimeErrorKind::TypeError {
+                expected: "Function",
+                got: replacement.type_name(),
+                operation: "Test.mock",
+            }));
+        }
+
+        let mock = match target.split_once('.') {
+            Some((ns, _)) => {
+                if !matches!(self.globals.get(ns), Some(Value::NativeNamespace(_))) {
+                    return Err(self.runtime_error(RuntimeErrorKind::UserError(format!(
+                        "Test.mock: '{ns}' is not a native namespace"
+                    ))));
+                }
+                Rc::new(RefCell::new(MockState::new(
+                    target.to_string(),
+                    replacement,
+                    None,
+                )))
+            }
+            None => {
+                let Some(original) = self.globals.get(target).cloned() else {
+                    return Err(
+                        self.runtime_error(RuntimeErrorKind::UndefinedVariable(target.to_string()))
+                    );
+                };
+                let mock = Rc::new(RefCell::new(MockState::new(
+                    target.to_string(),
+                    replacement,
+                    Some(original),
+                )));
+                self.globals
+                    .insert(target.to_string(), Value::Mock(mock.clone()));
+                mock
+            }
+        };
+        self.mocks.push(mock.clone());
+        Ok(Value::Mock(mock))
+    }
+
+    /// The newest active mock of a native namespace method
+    fn namespace_mock(&self, ns: &str, method: &str) -> Option<Rc<RefCell<MockState>>> {
+        self.mocks
+            .iter()
+            .rev()
+            .find(|mock| {
+                mock.borrow()
+                    .target
+                    .split_once('.')
+                    .is_some_and(|(mock_ns, mock_method)| mock_ns == ns && mock_method == method)
+            })
+            .cloned()
+    }
+
+    /// Put a mock's target back the way it was
+    fn restore_mock(&mut self, mock: &Rc<RefCell<MockState>>) {
+        let Some(index) = self.mocks.iter().position(|m| Rc::ptr_eq(m, mock)) else {
+            return;
+        };
+        self.mocks.remove(index);
+
+        let mut state = mock.borrow_mut();
+        state.active = false;
+        let Some(original) = state.original.take() else {
+            return;
+        };
+        // A newer mock of the same global now wraps this one: hand it the
+        // original instead
+        let newer = self.mocks[index..].iter().find(
+            |m| matches!(&m.borrow().original, Some(Value::Mock(inner)) if Rc::ptr_eq(inner, mock)),
+        );
+        match newer {
+            Some(newer) => newer.borrow_mut().original = Some(original),
+            None => {
+                self.globals.insert(state.target.clone(), original);
+            }
+        }
+    }
+
+    /// Restore every function replaced by `Test.mock`, newest mock first
+    ///
+    /// The test runner calls this when a test finishes.
+    pub fn restore_mocks(&mut self) {
+        while let Some(mock) = self.mocks.last().cloned() {
+            self.restore_mock(&mock);
+        }
+    }
+
+    fn mock_method(
+        &mut self,
+        mock: &Rc<RefCell<MockState>>,
+        method: &str,
+        args: &[Value],
+    ) -> RuntimeResult<Value> {
+        match method {
+            "times_called" => Ok(Value::Int(mock.borrow().calls.len() as i64)),
+            "called_with" => Ok(Value::Bool(
+                mock.borrow()
+                    .calls
+                    .iter()
+                    .any(|call| call.as_slice() == args),
+            )),
+            "calls" => Ok(Value::list(
+                mock.borrow()
+                    .calls
+                    .iter()
+                    .map(|call| Value::list(call.clone()))
+                    .collect(),
+            )),
+            "restore" => {
+                self.restore_mock(mock);
+                Ok(Value::Null)
+            }
+            _ => Err(self.runtime_error(RuntimeErrorKind::UndefinedField {
+                type_name: "Mock".to_string(),
+                field: method.to_string(),
+            })),
+        }
+    }
+
     // ============================================================================
     // Test suite methods (Test.describe(), Test.it())
     // ============================================================================
@@ -8517,6 +9686,7 @@ impl VM {
     /// - Argument count doesn't match arity
     /// - The closure throws an exception
     pub fn invoke_callback(&mut self, closure: &Value, args: Vec<Value>) -> RuntimeResult<Value> {
+        let _entered = determinism::enter(self.determinism.as_ref());
         match closure {
             Value::Closure(c) => self.call_closure_sync(c.clone(), args),
             Value::NativeFunction(nf) => {
@@ -8575,38 +9745,524 @@ impl VM {
         self.debug_context.get_breakpoint_lines(file)
     }
 
-    /// Get the current debug state (call stack, locals, location)
-    pub fn get_debug_state(&self, pause_reason: PauseReason) -> DebugState {
-        let (location, function_name) = if !self.frames.is_empty() {
-            let frame = &self.frames[self.frames.len() - 1];
-            let line = frame.chunk().get_line(frame.ip.saturating_sub(1));
-            let func_name = frame.closure.function.name.clone();
-            let file = self.current_source.clone();
-            (DebugLocation::new(file, line), func_name)
-        } else {
-            (DebugLocation::line(0), "<script>".to_string())
-        };
-
-        let call_stack = self.get_call_stack();
-        let locals = self.get_local_variables();
+    /// Get a breakpoint by ID to set its condition, hit condition or log message
+    pub fn breakpoint_mut(&mut self, id: u32) -> Option<&mut Breakpoint> {
+        self.debug_context.breakpoint_mut(id)
+    }
 
-        DebugState {
-            location,
-            function_name,
-            call_stack,
-            locals,
-            pause_reason,
-        }
+    /// Take the messages logged by logpoints since the last call
+    pub fn take_log_messages(&mut self) -> Vec<String> {
+        self.debug_context.take_log_messages()
     }
 
-    /// Get the current call stack
-    pub fn get_call_stack(&self) -> Vec<DebugStackFrame> {
-        self.frames
-            .iter()
+    /// Evaluate an expression in a paused frame (0 = innermost)
+    ///
+    /// The expression can refer to the frame's named locals and upvalues and
+    /// to globals.
+    /// It runs to completion without stopping at breakpoints; if it fails,
+    /// the VM is left as it was.
+    pub fn evaluate_in_frame(&mut self, frame_index: usize, source: &str) -> Result<Value, String> {
+        let expr = crate::parser::Parser::parse_expression(source).map_err(|errors| {
+            errors
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("; ")
+        })?;
+
+        // Bind the frame's variables as parameters of a lambda around the
+        // expression
+        let Some(bindings) = self.frame_bindings(frame_index) else {
+            return Err(format!("No frame {frame_index}"));
+        };
+
+        let span = expr.span;
+        let params = bindings
+            .iter()
+            .map(|(name, _)| crate::ast::Param::simple(name.clone(), span))
+            .collect();
+        let lambda = crate::ast::Expr::new(
+            crate::ast::ExprKind::Lambda {
+                params,
+                return_type: None,
+                body: Box::new(expr),
+            },
+            span,
+        );
+        let script = crate::bytecode::Compiler::new()
+            .compile_expression(&lambda)
+            .map_err(|errors| {
+                errors
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            })?;
+
+        let frame_count = self.frames.len();
+        let stack_len = self.stack.len();
+        let handler_count = self.handlers.len();
+        let values = bindings.into_iter().map(|(_, value)| value).collect();
+        let result = self
+            .call_closure_sync(Rc::new(Closure::new(script)), Vec::new())
+            .and_then(|closure| self.invoke_callback(&closure, values));
+
+        if result.is_err() {
+            self.close_upvalues(stack_len);
+            self.frames.truncate(frame_count);
+            self.stack.truncate(stack_len);
+            self.handlers.truncate(handler_count);
+            self.current_exception = None;
+        }
+        result.map_err(|e| e.to_string())
+    }
+
+    /// Swap edited top-level functions and methods into the running program
+    ///
+    /// `definitions` come from [`Compiler::compile_definitions`](crate::bytecode::Compiler::compile_definitions)
+    /// on the edited source. A function whose code changed replaces its
+    /// global, so later calls run the new code; frames already running the
+    /// old code finish with it unless restarted with [`Self::restart_frame`].
+    /// A function whose parameter count changed is skipped, since its
+    /// callers still pass the old count. Breakpoints in the current file
+    /// move with the functions they are in.
+    pub fn hot_reload(&mut self, definitions: Vec<(String, Rc<Function>)>) -> HotReload {
+        let mut report = HotReload::default();
+        let mut shifts = Vec::new();
+
+        for (name, function) in definitions {
+            let old = match self.globals.get(&name) {
+                Some(Value::Closure(old)) => old.clone(),
+                Some(_) => {
+                    report
+                        .skipped
+                        .push((name, "it is no longer a function".to_string()));
+                    continue;
+                }
+                None => {
+                    let closure = Rc::new(Closure::new(function));
+                    self.globals.insert(name.clone(), Value::Closure(closure));
+                    report.added.push(name);
+                    continue;
+                }
+            };
+
+            if old.function.arity != function.arity {
+                report
+                    .skipped
+                    .push((name, "its parameter count changed".to_string()));
+                continue;
+            }
+            if old.function.chunk.same_code(&function.chunk) {
+                continue;
+            }
+
+            if let (Some((first, last)), Some((new_first, _))) =
+                (old.function.chunk.line_range(), function.chunk.line_range())
+            {
+                shifts.push((first, last, i64::from(new_first) - i64::from(first)));
+            }
+
+            self.jit_context.invalidate(&old.function.name);
+            self.call_counts.remove(&Rc::as_ptr(&old.function));
+            let closure = Rc::new(Closure::new(function));
+            self.replaced_functions.insert(
+                Rc::as_ptr(&old.function),
+                (old.function.clone(), closure.clone()),
+            );
+            self.globals.insert(name.clone(), Value::Closure(closure));
+            report.reloaded.push(name);
+        }
+
+        report.moved_breakpoints = self
+            .debug_context
+            .shift_breakpoints(self.current_source.as_ref(), &shifts);
+        report
+    }
+
+    /// Restart a paused frame (0 = innermost) from its first instruction,
+    /// dropping the frames it called
+    ///
+    /// A function swapped out by [`Self::hot_reload`] restarts with its new
+    /// code. Parameters keep their current values, so a function that
+    /// reassigned one restarts with the reassigned value.
+    pub fn restart_frame(&mut self, frame_index: usize) -> Result<DebugState, String> {
+        let Some(position) = self.frames.len().checked_sub(frame_index + 1) else {
+            return Err(format!("No frame {frame_index}"));
+        };
+        if position == 0 {
+            return Err("The top-level script cannot be restarted".to_string());
+        }
+
+        let stack_base = self.frames[position].stack_base;
+        let mut closure = self.frames[position].closure.clone();
+        while let Some((_, newer)) = self.replaced_functions.get(&Rc::as_ptr(&closure.function)) {
+            closure = newer.clone();
+        }
+
+        // Keep the callee slot and the arguments
+        let keep = stack_base + 1 + closure.function.arity as usize;
+        self.close_upvalues(keep);
+        self.stack.truncate(keep);
+        self.frames.truncate(position + 1);
+        self.handlers
+            .retain(|handler| handler.frame_index < position);
+        self.current_exception = None;
+        if matches!(self.stack.get(stack_base), Some(Value::Closure(_))) {
+            self.stack[stack_base] = Value::Closure(closure.clone());
+        }
+
+        let frame = &mut self.frames[position];
+        frame.closure = closure;
+        frame.ip = 0;
+
+        // The restarted frame pauses on its first line, so continuing must
+        // not stop there again
+        let line = self.frames[position].chunk().get_line(0);
+        self.debug_context.enter_line(position + 1, line);
+        self.debug_context.clear_step();
+
+        Ok(self.get_debug_state(PauseReason::Step))
+    }
+
+    /// Keep up to `capacity` line entries for stepping back; 0 turns the
+    /// execution trace off
+    pub fn set_trace_capacity(&mut self, capacity: usize) {
+        self.trace.set_capacity(capacity);
+    }
+
+    /// The line entries recorded for stepping back, oldest first
+    pub fn execution_trace(&self) -> Vec<TraceEntry> {
+        self.trace.entries()
+    }
+
+    /// Whether there is an earlier point in the trace to step back to
+    pub fn can_step_back(&self) -> bool {
+        self.trace.len() > 1 || (self.trace.len() == 1 && !self.at_latest_checkpoint())
+    }
+
+    /// Step back to the start of the previous line entered, undoing the
+    /// variable writes made since
+    ///
+    /// Returns the paused state, or `None` at the start of the trace.
+    /// Running forward again re-executes the code, side effects included.
+    pub fn step_back(&mut self) -> Option<DebugState> {
+        if self.at_latest_checkpoint() {
+            if self.trace.len() < 2 {
+                return None;
+            }
+            let mut latest = self.trace.pop()?;
+            latest.undo_writes(&mut self.globals);
+        }
+        self.restore_latest_checkpoint()?;
+        Some(self.get_debug_state(PauseReason::Step))
+    }
+
+    /// Step back until a line with an enabled breakpoint whose condition
+    /// holds, or to the start of the trace
+    ///
+    /// Returns `None` if there is nothing to step back to. Hit counts and
+    /// logpoints only apply when running forward.
+    pub fn reverse_continue(&mut self) -> Option<DebugState> {
+        let mut state = self.step_back()?;
+        loop {
+            let line = self.get_current_line();
+            let ids = self
+                .debug_context
+                .breakpoints_at(self.current_source.as_ref(), line);
+            for id in ids {
+                let Some(breakpoint) = self.debug_context.breakpoint(id).cloned() else {
+                    continue;
+                };
+                if breakpoint.is_logpoint() {
+                    continue;
+                }
+                let holds = match &breakpoint.condition {
+                    Some(condition) => self
+                        .evaluate_in_frame(0, condition)
+                        .map_or(true, |value| value.is_truthy()),
+                    None => true,
+                };
+                if holds {
+                    return Some(self.get_debug_state(PauseReason::Breakpoint(id)));
+                }
+            }
+
+            match self.step_back() {
+                Some(earlier) => state = earlier,
+                None => return Some(state),
+            }
+        }
+    }
+
+    /// Whether the VM is where the most recent checkpoint was taken
+    fn at_latest_checkpoint(&self) -> bool {
+        self.trace.latest().is_some_and(|checkpoint| {
+            checkpoint.frames.len() == self.frames.len()
+                && checkpoint.frames.last().map(|f| f.ip) == self.frames.last().map(|f| f.ip)
+        })
+    }
+
+    /// Return to the most recent checkpoint, undoing the writes made since
+    fn restore_latest_checkpoint(&mut self) -> Option<()> {
+        let checkpoint = self.trace.latest_mut()?;
+        checkpoint.undo_writes(&mut self.globals);
+        checkpoint.entry.op_count = 0;
+        checkpoint.entry.writes.clear();
+
+        self.frames = checkpoint.frames.clone();
+        self.stack = checkpoint.stack.clone();
+        self.handlers = checkpoint.handlers.clone();
+        for (upvalue, slot) in &checkpoint.open_upvalues {
+            *upvalue.borrow_mut() = Upvalue::Open(*slot);
+        }
+        self.open_upvalues = checkpoint
+            .open_upvalues
+            .iter()
+            .map(|(upvalue, _)| upvalue.clone())
+            .collect();
+        self.debug_context
+            .set_frame_lines(checkpoint.frame_lines.clone());
+        self.debug_context.clear_step();
+        self.current_exception = None;
+        Some(())
+    }
+
+    /// Checkpoint the VM on entering `line`, for stepping back
+    fn record_checkpoint(&mut self, line: u32) {
+        if !self.trace.is_enabled() {
+            return;
+        }
+
+        let entry = TraceEntry {
+            line,
+            function_name: self.current_frame().closure.function.name.clone(),
+            depth: self.frames.len(),
+            stack_len: self.stack.len(),
+            op_count: 0,
+            writes: Vec::new(),
+        };
+        let open_upvalues = self
+            .open_upvalues
+            .iter()
+            .filter_map(|upvalue| match *upvalue.borrow() {
+                Upvalue::Open(slot) => Some((upvalue.clone(), slot)),
+                Upvalue::Closed(_) => None,
+            })
+            .collect();
+
+        self.trace.push(Checkpoint {
+            entry,
+            frames: self.frames.clone(),
+            stack: self.stack.clone(),
+            handlers: self.handlers.clone(),
+            open_upvalues,
+            frame_lines: self.debug_context.frame_lines().to_vec(),
+            undo: Vec::new(),
+        });
+    }
+
+    /// Log the value the instruction about to run overwrites, if it writes
+    /// a global or a captured variable, so stepping back can undo it
+    fn record_undo(&mut self, opcode: OpCode) {
+        if !self.trace.is_enabled() {
+            return;
+        }
+
+        let frame = self.current_frame();
+        let undo = match opcode {
+            OpCode::StoreGlobal | OpCode::DefineGlobal => {
+                let Some(Value::String(name)) = frame
+                    .chunk()
+                    .read_u16(frame.ip)
+                    .and_then(|index| frame.chunk().get_constant(index))
+                else {
+                    return;
+                };
+                Undo::Global(name.to_string(), self.globals.get(name.as_str()).cloned())
+            }
+            OpCode::StoreUpvalue => {
+                let Some(upvalue) = frame
+                    .chunk()
+                    .read_byte(frame.ip)
+                    .and_then(|index| frame.closure.upvalues.get(index as usize))
+                else {
+                    return;
+                };
+                let state = upvalue.borrow().clone();
+                Undo::Upvalue(upvalue.clone(), state)
+            }
+            _ => return,
+        };
+
+        if let Some(checkpoint) = self.trace.latest_mut() {
+            checkpoint.undo.push(undo);
+        }
+    }
+
+    /// Count an executed instruction and record the variables it assigned
+    ///
+    /// `depth` and `operand` are the frame depth and operand offset from
+    /// before the instruction ran. Writes are attributed to the line the
+    /// writing frame most recently entered.
+    fn record_step(&mut self, opcode: OpCode, depth: usize, operand: usize) {
+        if !self.trace.is_enabled() || self.frames.is_empty() {
+            return;
+        }
+
+        let frame = self.current_frame();
+        let chunk = frame.chunk();
+        let mut writes = Vec::new();
+        if self.frames.len() == depth {
+            match opcode {
+                OpCode::StoreLocal => {
+                    let slot = chunk.read_u16(operand);
+                    let local = chunk
+                        .locals_at(frame.ip)
+                        .into_iter()
+                        .find(|local| Some(local.slot) == slot);
+                    if let Some(local) = local {
+                        if let Some(value) = self.stack.get(frame.stack_base + local.slot as usize)
+                        {
+                            writes.push(DebugVariable::from_value(local.name.clone(), value));
+                        }
+                    }
+                }
+                OpCode::StoreGlobal | OpCode::DefineGlobal => {
+                    let name = chunk
+                        .read_u16(operand)
+                        .and_then(|index| chunk.get_constant(index));
+                    if let Some(Value::String(name)) = name {
+                        if let Some(value) = self.globals.get(name.as_str()) {
+                            writes.push(DebugVariable::from_value(name.to_string(), value));
+                        }
+                    }
+                }
+                OpCode::StoreUpvalue => {
+                    let index = chunk.read_byte(operand).map(usize::from);
+                    let name = index.and_then(|index| chunk.upvalue_names().get(index));
+                    if let (Some(index), Some(name)) = (index, name) {
+                        if let Ok(value) = self.get_upvalue(index) {
+                            writes.push(DebugVariable::from_value(name.clone(), &value));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Locals declared by the instruction (or by the call it returned
+        // from) start holding their value here
+        if frame.ip > 0 {
+            for local in chunk.local_variables() {
+                if local.start == frame.ip {
+                    if let Some(value) = self.stack.get(frame.stack_base + local.slot as usize) {
+                        writes.push(DebugVariable::from_value(local.name.clone(), value));
+                    }
+                }
+            }
+        }
+
+        let current_depth = self.frames.len();
+        if let Some(checkpoint) = self.trace.latest_mut() {
+            checkpoint.entry.op_count += 1;
+        }
+        if !writes.is_empty() {
+            if let Some(checkpoint) = self.trace.latest_at_depth_mut(current_depth) {
+                checkpoint.entry.writes.extend(writes);
+            }
+        }
+    }
+
+    /// The breakpoint to stop at on entering `line`, if any
+    ///
+    /// A breakpoint triggers when its condition is truthy (a condition that
+    /// fails to evaluate triggers it too, with the error logged) and its hit
+    /// condition allows; logpoints log their message and never stop.
+    fn triggered_breakpoint(&mut self, line: u32) -> Option<u32> {
+        let ids = self
+            .debug_context
+            .breakpoints_at(self.current_source.as_ref(), line);
+
+        let mut stop = None;
+        for id in ids {
+            let Some(breakpoint) = self.debug_context.breakpoint(id).cloned() else {
+                continue;
+            };
+
+            if let Some(condition) = &breakpoint.condition {
+                match self.evaluate_in_frame(0, condition) {
+                    Ok(value) if value.is_truthy() => {}
+                    Ok(_) => continue,
+                    Err(e) => self
+                        .debug_context
+                        .log(format!("Breakpoint condition `{condition}` failed: {e}")),
+                }
+            }
+            if !self.debug_context.record_hit(id) {
+                continue;
+            }
+
+            match &breakpoint.log_message {
+                Some(message) => {
+                    // Evaluate the message as a string literal, so `{expr}`
+                    // parts interpolate like in any Stratum string
+                    let literal =
+                        format!("\"{}\"", message.replace('\\', "\\\\").replace('"', "\\\""));
+                    let text = match self.evaluate_in_frame(0, &literal) {
+                        Ok(Value::String(text)) => text.to_string(),
+                        Ok(value) => value.to_string(),
+                        Err(e) => format!("{message} (error: {e})"),
+                    };
+                    self.debug_context.log(text);
+                }
+                None => {
+                    stop.get_or_insert(id);
+                }
+            }
+        }
+        stop
+    }
+
+    /// Get the current debug state (call stack, locals, location)
+    pub fn get_debug_state(&self, pause_reason: PauseReason) -> DebugState {
+        let (location, function_name) = if !self.frames.is_empty() {
+            let frame = &self.frames[self.frames.len() - 1];
+            let line = frame.chunk().get_line(frame.ip);
+            let func_name = frame.closure.function.name.clone();
+            let file = self.current_source.clone();
+            (DebugLocation::new(file, line), func_name)
+        } else {
+            (DebugLocation::line(0), "<script>".to_string())
+        };
+
+        let call_stack = self.get_call_stack();
+        let locals = self.get_local_variables();
+
+        DebugState {
+            location,
+            function_name,
+            call_stack,
+            locals,
+            pause_reason,
+        }
+    }
+
+    /// Get the current call stack
+    pub fn get_call_stack(&self) -> Vec<DebugStackFrame> {
+        self.frames
+            .iter()
             .rev()
             .enumerate()
             .map(|(idx, frame)| {
-                let line = frame.chunk().get_line(frame.ip.saturating_sub(1));
+                // The innermost frame is paused before the instruction at
+                // `ip`; the others are inside the call just before it
+                let offset = if idx == 0 {
+                    frame.ip
+                } else {
+                    frame.ip.saturating_sub(1)
+                };
+                let line = frame.chunk().get_line(offset);
                 let source = frame.closure.function.chunk.source_name.clone();
                 DebugStackFrame {
                     function_name: frame.closure.function.name.clone(),
@@ -8620,47 +10276,56 @@ impl VM {
 
     /// Get local variables in the current frame
     pub fn get_local_variables(&self) -> Vec<DebugVariable> {
-        if self.frames.is_empty() {
-            return Vec::new();
-        }
+        self.get_frame_variables(0)
+    }
 
-        let frame = &self.frames[self.frames.len() - 1];
-        let func = &frame.closure.function;
-        let mut locals = Vec::new();
-
-        // Calculate the number of locals from the stack layout
-        // Locals are stored from stack_base up to the current stack position
-        let stack_end = if self.frames.len() > 1 {
-            // If we have multiple frames, locals end at the next frame's base
-            self.stack.len()
-        } else {
-            self.stack.len()
+    /// Get the named variables of a frame (0 = innermost): its live locals
+    /// and the upvalues its closure captured
+    pub fn get_frame_variables(&self, frame_index: usize) -> Vec<DebugVariable> {
+        self.frame_bindings(frame_index)
+            .unwrap_or_default()
+            .iter()
+            .map(|(name, value)| DebugVariable::from_value(name.clone(), value))
+            .collect()
+    }
+
+    /// The names visible in a frame and their values, upvalues first; a
+    /// shadowing local replaces what it shadows
+    fn frame_bindings(&self, frame_index: usize) -> Option<Vec<(String, Value)>> {
+        let position = self.frames.len().checked_sub(frame_index + 1)?;
+        let frame = &self.frames[position];
+        let mut bindings: Vec<(String, Value)> = Vec::new();
+        let mut bind = |name: &str, value: Value| {
+            bindings.retain(|(bound, _)| bound != name);
+            bindings.push((name.to_string(), value));
         };
 
-        let local_count = stack_end.saturating_sub(frame.stack_base);
-        for i in 0..local_count {
-            let slot = frame.stack_base + i;
-            if slot < self.stack.len() {
-                let value = &self.stack[slot];
-                let name = if i < func.arity as usize {
-                    format!("arg{}", i)
-                } else {
-                    format!("local{}", i - func.arity as usize)
-                };
-                locals.push(DebugVariable::from_value(name, value));
+        let names = frame.chunk().upvalue_names();
+        for (name, upvalue) in names.iter().zip(&frame.closure.upvalues) {
+            let value = match &*upvalue.borrow() {
+                Upvalue::Open(slot) => self.stack.get(*slot).cloned(),
+                Upvalue::Closed(value) => Some(value.clone()),
+            };
+            if let Some(value) = value {
+                bind(name, value);
+            }
+        }
+        for local in frame.chunk().locals_at(frame.ip) {
+            if let Some(value) = self.stack.get(frame.stack_base + local.slot as usize) {
+                bind(&local.name, value.clone());
             }
         }
 
-        locals
+        Some(bindings)
     }
 
-    /// Get the current line number
+    /// Get the line the innermost frame is paused on
     pub fn get_current_line(&self) -> u32 {
         if self.frames.is_empty() {
             return 0;
         }
         let frame = &self.frames[self.frames.len() - 1];
-        frame.chunk().get_line(frame.ip.saturating_sub(1))
+        frame.chunk().get_line(frame.ip)
     }
 
     /// Get the current frame depth
@@ -8693,6 +10358,9 @@ impl VM {
     pub fn run_debug(&mut self, function: Rc<Function>) -> DebugStepResult {
         // Set up for debug execution
         self.debug_context.debug_mode = true;
+        self.debug_context.reset_lines();
+        self.replaced_functions.clear();
+        self.trace.clear();
 
         // Clear any leftover state from previous runs
         self.stack.clear();
@@ -8709,7 +10377,7 @@ impl VM {
         self.stack.push(Value::Closure(closure.clone()));
 
         // Create the initial frame
-        self.frames.push(CallFrame::new(closure, 0));
+        self.push_frame(closure, 0);
 
         // Run the debug execution loop
         self.execute_debug()
@@ -8723,6 +10391,7 @@ impl VM {
 
     /// Execute with debug support (checking breakpoints and steps)
     fn execute_debug(&mut self) -> DebugStepResult {
+        let _entered = determinism::enter(self.determinism.as_ref());
         loop {
             // Check for exception propagation
             if let Some(exception) = self.current_exception.take() {
@@ -8756,17 +10425,15 @@ impl VM {
             let current_line = chunk.get_line(frame.ip);
             let frame_depth = self.frames.len();
 
-            // Check breakpoint
-            if self
-                .debug_context
-                .has_breakpoint(self.current_source.as_ref(), current_line)
-            {
-                // Find breakpoint ID
-                let bp_id = 0; // Simplified - would need to look up actual ID
-                self.debug_context.clear_step();
-                return DebugStepResult::Paused(
-                    self.get_debug_state(PauseReason::Breakpoint(bp_id)),
-                );
+            // Check breakpoints when a line is entered
+            if self.debug_context.enter_line(frame_depth, current_line) {
+                self.record_checkpoint(current_line);
+                if let Some(bp_id) = self.triggered_breakpoint(current_line) {
+                    self.debug_context.clear_step();
+                    return DebugStepResult::Paused(
+                        self.get_debug_state(PauseReason::Breakpoint(bp_id)),
+                    );
+                }
             }
 
             // Check stepping
@@ -8778,8 +10445,9 @@ impl VM {
                 return DebugStepResult::Paused(self.get_debug_state(PauseReason::Step));
             }
 
-            // Execute instruction
-            let instruction = match chunk.read_byte(frame.ip) {
+            // Execute instruction (breakpoint conditions may have run code)
+            let frame = self.current_frame();
+            let instruction = match frame.chunk().read_byte(frame.ip) {
                 Some(b) => b,
                 None => return DebugStepResult::Error("Unexpected end of bytecode".to_string()),
             };
@@ -8791,6 +10459,8 @@ impl VM {
 
             // Advance IP
             self.current_frame_mut().ip += 1;
+            let operand = self.current_frame().ip;
+            self.record_undo(opcode);
 
             // Handle Return specially
             if opcode == OpCode::Return {
@@ -8803,15 +10473,17 @@ impl VM {
                 self.close_upvalues(frame.stack_base);
 
                 let frame = self.frames.pop().unwrap();
+                let result = frame.return_value(result);
 
                 if self.frames.is_empty() {
                     return DebugStepResult::Completed(result);
                 }
 
                 self.stack.truncate(frame.stack_base);
-                if let Err(e) = self.push(result) {
+                if let Err(e) = self.return_to_caller(&frame, result) {
                     return DebugStepResult::Error(format!("{}", e));
                 }
+                self.record_step(opcode, frame_depth, operand);
                 continue;
             }
 
@@ -8819,6 +10491,7 @@ impl VM {
             if let Err(e) = self.execute_opcode(opcode) {
                 return DebugStepResult::Error(format!("{}", e));
             }
+            self.record_step(opcode, frame_depth, operand);
 
             // Check if execution was suspended
             if let Some(coroutine) = self.suspended_coroutine.take() {
@@ -9169,4 +10842,834 @@ mod tests {
         let result = vm.run(make_function(chunk)).unwrap();
         assert_eq!(result, Value::Int(3));
     }
+
+    #[test]
+    fn test_operators_dispatch_to_impl_methods() {
+        let module = crate::parser::Parser::parse_module(
+            r"
+            struct Money { cents: Int }
+            impl Money {
+                fx __add__(self, other) { Money { cents: self.cents + other.cents } }
+                fx __eq__(self, other) { self.cents == other.cents }
+                fx __neg__(self) { Money { cents: 0 - self.cents } }
+                fx __index__(self, times) { self.cents * times }
+            }
+            let a = Money { cents: 150 }
+            let b = Money { cents: 250 }
+            let total = (a + b).cents
+            let same = a == a
+            let different = a != b
+            let negated = (-a).cents
+            let indexed = a[2]
+            ",
+        )
+        .unwrap();
+        let script = crate::bytecode::Compiler::new()
+            .compile_module(&module)
+            .unwrap();
+
+        let mut vm = VM::new();
+        vm.run(script).unwrap();
+        assert_eq!(vm.globals["total"], Value::Int(400));
+        assert_eq!(vm.globals["same"], Value::Bool(true));
+        assert_eq!(vm.globals["different"], Value::Bool(true));
+        assert_eq!(vm.globals["negated"], Value::Int(-150));
+        assert_eq!(vm.globals["indexed"], Value::Int(300));
+    }
+
+    #[test]
+    fn test_fused_comparison_jumps() {
+        let module = crate::parser::Parser::parse_module(
+            r#"
+            struct Money { cents: Int }
+            impl Money {
+                fx __lt__(self, other) { self.cents < other.cents }
+                fx __eq__(self, other) { self.cents == other.cents }
+            }
+            let limit = Money { cents: 500 }
+            let spent = Money { cents: 0 }
+            let rounds = 0
+            while spent < limit {
+                spent = Money { cents: spent.cents + 200 }
+                rounds = rounds + 1
+            }
+            let outcome = if spent != limit { "over" } else { "exact" }
+            let i = 0
+            let total = 0
+            while i < 10 {
+                i = i + 1
+                total = total + i
+            }
+            "#,
+        )
+        .unwrap();
+        let script = crate::bytecode::Compiler::new()
+            .compile_module(&module)
+            .unwrap();
+        let listing = crate::bytecode::disassemble_chunk(&script.chunk, "<script>");
+        assert!(listing.contains("COMPARE_JUMP_IF_FALSE"));
+
+        let mut vm = VM::new();
+        vm.run(script).unwrap();
+        // Overloaded comparisons branch once the impl method returns
+        assert_eq!(vm.globals["rounds"], Value::Int(3));
+        assert_eq!(vm.globals["outcome"], Value::string("over"));
+        assert_eq!(vm.globals["total"], Value::Int(55));
+    }
+
+    #[test]
+    fn test_fused_local_instructions() {
+        let module = crate::parser::Parser::parse_module(
+            r#"
+            struct Money { cents: Int }
+            impl Money {
+                fx __add__(self, other) { Money { cents: self.cents + other } }
+            }
+            fx sum_to(n) {
+                let i = 0
+                let total = 0
+                while i < n {
+                    i = i + 1
+                    total = total + i
+                }
+                total
+            }
+            fx grow(wallet) { (wallet + 5).cents }
+            fx halve(x) { x + 0.5 }
+            let total = sum_to(10)
+            let grown = grow(Money { cents: 10 })
+            let float_sum = halve(1.5)
+            let mixed_sum = halve(1)
+            let joined = halve("a")
+            "#,
+        )
+        .unwrap();
+        let script = crate::bytecode::Compiler::new()
+            .compile_module(&module)
+            .unwrap();
+        let listing = crate::bytecode::disassemble_function(&script);
+        assert!(listing.contains("ADD_LOCAL_CONST"));
+        assert!(listing.contains("LOAD_LOCAL_PAIR"));
+
+        let mut vm = VM::new();
+        vm.run(script).unwrap();
+        assert_eq!(vm.globals["total"], Value::Int(55));
+        // Values other than two Ints or two Floats fall back to `Add`
+        assert_eq!(vm.globals["grown"], Value::Int(15));
+        assert_eq!(vm.globals["float_sum"], Value::Float(2.0));
+        assert_eq!(vm.globals["mixed_sum"], Value::Float(1.5));
+        assert_eq!(vm.globals["joined"], Value::string("a0.5"));
+    }
+
+    #[test]
+    fn test_deterministic_runs_repeat() {
+        let source = r#"
+            let rolls = [Random.int(1, 6), Random.int(1, 6), Random.int(1, 6)]
+            let deck = Random.shuffle([1, 2, 3, 4, 5, 6, 7, 8])
+            let timer = Time.start()
+            let start = DateTime.format(DateTime.now(), "%Y-%m-%d %H:%M:%S")
+            Time.sleep_ms(1500)
+            let later = DateTime.format(DateTime.now(), "%Y-%m-%d %H:%M:%S")
+            let elapsed = str(Time.elapsed(timer))
+            let scores = {"zed": 1, "amy": 2, "kim": 3}
+            scores["bob"] = 4
+            scores.remove("amy")
+            let order = scores.keys()
+            let ids = [Uuid.v4(), Uuid.v7()]
+            "#;
+        let run = |seed| {
+            let module = crate::parser::Parser::parse_module(source).unwrap();
+            let script = crate::bytecode::Compiler::new()
+                .compile_module(&module)
+                .unwrap();
+            let mut vm = VM::deterministic(seed);
+            assert_eq!(vm.seed(), Some(seed));
+            let started = Instant::now();
+            vm.run(script).unwrap();
+            // Sleeping moves the virtual clock without waiting
+            assert!(started.elapsed() < Duration::from_millis(1500));
+            ["rolls", "deck", "start", "later", "elapsed", "order", "ids"]
+                .map(|name| format!("{}", vm.globals[name]))
+        };
+
+        let first = run(7);
+        assert_eq!(first, run(7));
+        assert_ne!(first[1], run(8)[1]);
+        assert_eq!(first[2], "2000-01-01 00:00:00");
+        assert_eq!(first[3], "2000-01-01 00:00:01");
+        assert_eq!(first[5], "[zed, kim, bob]");
+        assert_ne!(first[6], run(8)[6]);
+        // The v7 timestamp is the virtual clock, 1.5s after 2000-01-01
+        assert!(first[6].contains(", 00dc6acf-b1dc-7"), "{}", first[6]);
+        assert!(!determinism::is_enabled());
+    }
+
+    #[test]
+    fn test_deterministic_vms_keep_their_own_state() {
+        let run = |vm: &mut VM| {
+            let module =
+                crate::parser::Parser::parse_module("let id = Uuid.v7()\nTime.sleep_ms(250)")
+                    .unwrap();
+            let script = crate::bytecode::Compiler::new()
+                .compile_module(&module)
+                .unwrap();
+            vm.run(script).unwrap();
+            format!("{}", vm.globals["id"])
+        };
+
+        let mut alone = VM::deterministic(3);
+        let expected = [run(&mut alone), run(&mut alone)];
+
+        // Other VMs on the thread, deterministic or not, draw from and move
+        // their own generator and clock
+        let mut first = VM::deterministic(3);
+        let mut ids = vec![run(&mut first)];
+        {
+            let mut other = VM::deterministic(3);
+            assert_eq!(run(&mut other), expected[0]);
+            other.advance_clock(Duration::from_secs(3600));
+            run(&mut other);
+            let mut plain = VM::new();
+            run(&mut plain);
+        }
+        ids.push(run(&mut first));
+        assert_eq!(ids, expected);
+        assert!(!determinism::is_enabled());
+    }
+
+    #[test]
+    fn test_permissions_checked_at_dispatch() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = dir.path().join("data.txt");
+        std::fs::write(&data, "granted").unwrap();
+        let run = |vm: &mut VM, source: &str| {
+            let module = crate::parser::Parser::parse_module(source).unwrap();
+            let script = crate::bytecode::Compiler::new()
+                .compile_module(&module)
+                .unwrap();
+            vm.run(script)
+        };
+
+        let mut vm = VM::new();
+        vm.set_permissions(Permissions::deny_all().with(
+            Capability::Read,
+            Grant::Only(vec![dir.path().display().to_string()]),
+        ));
+        let read = format!(
+            "let text = File.read_text({:?})",
+            data.display().to_string()
+        );
+        run(&mut vm, &read).unwrap();
+        assert_eq!(vm.globals["text"], Value::string("granted"));
+        // Pure namespaces need nothing
+        assert!(run(&mut vm, "Json.encode([1, 2])").is_ok());
+
+        let write = format!("File.write_text({:?}, \"x\")", data.display().to_string());
+        let err = run(&mut vm, &write).unwrap_err();
+        assert!(matches!(err.kind, RuntimeErrorKind::PermissionDenied(_)));
+        assert!(err
+            .to_string()
+            .contains("File.write_text needs write access"));
+        assert_eq!(std::fs::read_to_string(&data).unwrap(), "granted");
+
+        let err = run(&mut vm, r#"Env.get("HOME")"#).unwrap_err();
+        assert!(err.to_string().contains("--allow-env=HOME"));
+        let err = run(&mut vm, r#"Shell.run("echo hi")"#).unwrap_err();
+        assert!(err.to_string().contains("--allow-run to grant it"));
+
+        // External namespaces fail closed for methods they don't declare
+        vm.register_namespace("Probe", |_, _| Ok(Value::Null), |_, _| None);
+        let err = run(&mut vm, "Probe.scan()").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Probe.scan doesn't declare the access it needs"));
+
+        vm.set_permissions(Permissions::allow_all());
+        assert!(run(&mut vm, &write).is_ok());
+    }
+
+    #[test]
+    fn test_registered_namespaces_share_their_name() {
+        let mut first = VM::new();
+        let mut second = VM::new();
+        first.register_namespace("Probe", |_, _| Ok(Value::Null), |_, _| None);
+        second.register_namespace("Probe", |_, _| Ok(Value::Null), |_, _| None);
+        let (Value::NativeNamespace(a), Value::NativeNamespace(b)) =
+            (&first.globals()["Probe"], &second.globals()["Probe"])
+        else {
+            panic!("expected namespaces");
+        };
+        assert!(std::ptr::eq(a.as_str(), b.as_str()));
+        assert_eq!(a.as_str(), "Probe");
+    }
+
+    #[test]
+    fn test_in_memory_database_needs_write_access() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out.csv");
+        let source = format!(
+            "let db = Db.duckdb(\":memory:\")\ndb.execute(\"COPY (SELECT 1 AS n) TO '{}'\")",
+            out.display()
+        );
+        let run = |vm: &mut VM| {
+            let module = crate::parser::Parser::parse_module(&source).unwrap();
+            let script = crate::bytecode::Compiler::new()
+                .compile_module(&module)
+                .unwrap();
+            vm.run(script)
+        };
+
+        let mut vm = VM::new();
+        vm.set_permissions(
+            Permissions::deny_all()
+                .with(Capability::Read, Grant::All)
+                .with(Capability::Net, Grant::All),
+        );
+        let err = run(&mut vm).unwrap_err();
+        assert!(matches!(err.kind, RuntimeErrorKind::PermissionDenied(_)));
+        assert!(err.to_string().contains("--allow-write to grant it"));
+        assert!(!out.exists());
+
+        vm.set_permissions(Permissions::allow_all());
+        run(&mut vm).unwrap();
+        assert!(out.exists());
+    }
+
+    #[test]
+    fn test_sql_on_dataframes_cannot_reach_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out.csv");
+        let secret = dir.path().join("secret.csv");
+        std::fs::write(&secret, "token\nhunter2\n").unwrap();
+        let run = |vm: &mut VM, source: &str| {
+            let module = crate::parser::Parser::parse_module(source).unwrap();
+            let script = crate::bytecode::Compiler::new()
+                .compile_module(&module)
+                .unwrap();
+            vm.run(script)
+        };
+
+        let mut vm = VM::new();
+        vm.set_permissions(Permissions::deny_all());
+        let copy = format!(
+            "let df = Data.frame([{{\"n\": 1}}])\nData.sql(df, \"COPY (SELECT n FROM df) TO '{}'\")",
+            out.display()
+        );
+        let err = run(&mut vm, &copy).unwrap_err();
+        assert!(err.to_string().contains("not supported"));
+        assert!(!out.exists());
+
+        let create = format!(
+            "let ctx = Data.sql_context()\nctx.query(\"CREATE EXTERNAL TABLE t STORED AS CSV LOCATION '{}'\")",
+            secret.display()
+        );
+        let err = run(&mut vm, &create).unwrap_err();
+        assert!(err.to_string().contains("not supported"));
+
+        // Queries that only read the registered tables still run
+        let select =
+            "let df = Data.frame([{\"n\": 1}])\nData.sql(df, \"SELECT n + 1 AS m FROM df\")";
+        assert!(run(&mut vm, select).is_ok());
+    }
+
+    /// Held while a test simulates a signal, since the first VM to notice
+    /// it takes it
+    static SIGNALS: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    /// A VM that watches for shutdown signals without installing the
+    /// process's signal handlers, which would outlive the test
+    fn vm_watching_signals() -> VM {
+        let mut vm = VM::new();
+        vm.watch_signals = true;
+        vm
+    }
+
+    #[test]
+    fn test_exit_hooks_run_on_signal() {
+        let module = crate::parser::Parser::parse_module(
+            r#"
+            let log = []
+            fx first() {
+                log.push("first")
+            }
+            fx second() {
+                log.push("second")
+            }
+            fx main() {
+                System.on_exit(first);
+                System.on_exit(second);
+                let i = 0
+                while true {
+                    i = i + 1
+                }
+            }
+            main()
+            "#,
+        )
+        .unwrap();
+        let script = crate::bytecode::Compiler::new()
+            .compile_module(&module)
+            .unwrap();
+
+        let _signals = SIGNALS.lock().unwrap_or_else(|e| e.into_inner());
+        let mut vm = vm_watching_signals();
+        shutdown::simulate(signal_hook::consts::SIGINT);
+        let err = vm.run(script).unwrap_err();
+        assert!(matches!(
+            err.kind,
+            RuntimeErrorKind::Interrupted(signal_hook::consts::SIGINT)
+        ));
+        assert!(err.to_string().contains("interrupted by SIGINT"));
+        // Newest first, and each only once
+        assert_eq!(vm.globals["log"].to_string(), "[second, first]");
+        assert!(vm.run_exit_hooks().is_ok());
+        assert_eq!(vm.globals["log"].to_string(), "[second, first]");
+    }
+
+    #[test]
+    fn test_finally_blocks_run_on_signal() {
+        let module = crate::parser::Parser::parse_module(
+            r#"
+            let log = []
+            fx cleanup() {
+                log.push("exit")
+            }
+            fx count() {
+                let i = 0
+                try {
+                    while true {
+                        i = i + 1
+                    }
+                } finally {
+                    log.push("inner")
+                }
+            }
+            fx main() {
+                System.on_exit(cleanup);
+                try {
+                    count()
+                } finally {
+                    log.push("outer")
+                }
+                log.push("after")
+            }
+            main()
+            "#,
+        )
+        .unwrap();
+        let script = crate::bytecode::Compiler::new()
+            .compile_module(&module)
+            .unwrap();
+
+        let _signals = SIGNALS.lock().unwrap_or_else(|e| e.into_inner());
+        let mut vm = vm_watching_signals();
+        shutdown::simulate(signal_hook::consts::SIGINT);
+        let err = vm.run(script).unwrap_err();
+        assert!(matches!(err.kind, RuntimeErrorKind::Interrupted(_)));
+        // Innermost first, then the exit callbacks, and nothing after
+        assert_eq!(vm.globals["log"].to_string(), "[inner, outer, exit]");
+    }
+
+    #[test]
+    fn test_on_exit_rejects_non_functions() {
+        let module = crate::parser::Parser::parse_module("System.on_exit(42)").unwrap();
+        let script = crate::bytecode::Compiler::new()
+            .compile_module(&module)
+            .unwrap();
+        let err = VM::new().run(script).unwrap_err();
+        assert!(matches!(err.kind, RuntimeErrorKind::TypeError { .. }));
+    }
+
+    /// A loop whose body is line 4, run under the debugger
+    fn debug_loop() -> (VM, Rc<Function>) {
+        let module = crate::parser::Parser::parse_module(
+            "fx main() {\n    let total = 0\n    for i in [0, 1, 2, 3, 4] {\n        total = total + i\n    }\n    total\n}\nmain()\n",
+        )
+        .unwrap();
+        let script = crate::bytecode::Compiler::new()
+            .with_optimization(false)
+            .compile_module(&module)
+            .unwrap();
+        let mut vm = VM::new();
+        vm.set_debug_mode(true);
+        (vm, script)
+    }
+
+    fn paused_at(result: DebugStepResult) -> u32 {
+        match result {
+            DebugStepResult::Paused(state) => state.location.line,
+            other => panic!("expected a pause, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_conditional_breakpoint() {
+        let (mut vm, script) = debug_loop();
+        let id = vm.add_breakpoint(None, 4);
+        vm.breakpoint_mut(id).unwrap().condition = Some("i == 3".to_string());
+
+        assert_eq!(paused_at(vm.run_debug(script)), 4);
+        assert_eq!(vm.evaluate_in_frame(0, "i"), Ok(Value::Int(3)));
+        assert_eq!(vm.evaluate_in_frame(0, "total * 10"), Ok(Value::Int(30)));
+        assert!(vm.evaluate_in_frame(0, "total +").is_err());
+
+        assert!(matches!(vm.continue_debug(), DebugStepResult::Completed(_)));
+    }
+
+    #[test]
+    fn test_hit_count_breakpoint() {
+        let (mut vm, script) = debug_loop();
+        let id = vm.add_breakpoint(None, 4);
+        vm.breakpoint_mut(id).unwrap().hit_condition = Some("% 2".to_string());
+
+        paused_at(vm.run_debug(script));
+        assert_eq!(vm.evaluate_in_frame(0, "i"), Ok(Value::Int(1)));
+        paused_at(vm.continue_debug());
+        assert_eq!(vm.evaluate_in_frame(0, "i"), Ok(Value::Int(3)));
+        assert!(matches!(vm.continue_debug(), DebugStepResult::Completed(_)));
+    }
+
+    #[test]
+    fn test_logpoint() {
+        let (mut vm, script) = debug_loop();
+        let id = vm.add_breakpoint(None, 4);
+        vm.breakpoint_mut(id).unwrap().log_message = Some("i = {i}, total = {total}".to_string());
+
+        assert!(matches!(
+            vm.run_debug(script),
+            DebugStepResult::Completed(_)
+        ));
+        assert_eq!(
+            vm.take_log_messages(),
+            vec![
+                "i = 0, total = 0",
+                "i = 1, total = 0",
+                "i = 2, total = 1",
+                "i = 3, total = 3",
+                "i = 4, total = 6",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pause_line_of_each_frame() {
+        let module = crate::parser::Parser::parse_module(
+            "fx double(x) {\n    x * 2\n}\nfx main() {\n    double(5)\n}\nmain()\n",
+        )
+        .unwrap();
+        let script = crate::bytecode::Compiler::new()
+            .with_optimization(false)
+            .compile_module(&module)
+            .unwrap();
+        let mut vm = VM::new();
+        vm.set_debug_mode(true);
+        vm.add_breakpoint(None, 2);
+
+        // The innermost frame reports the line it is paused on, the outer
+        // frames the line of the call they are waiting in
+        let state = match vm.run_debug(script) {
+            DebugStepResult::Paused(state) => state,
+            other => panic!("expected a pause, got {other:?}"),
+        };
+        assert_eq!(state.location.line, 2);
+        assert_eq!(vm.get_current_line(), 2);
+        let lines: Vec<u32> = state.call_stack.iter().map(|frame| frame.line).collect();
+        assert_eq!(lines, [2, 5, 7]);
+
+        assert!(matches!(vm.continue_debug(), DebugStepResult::Completed(_)));
+    }
+
+    #[test]
+    fn test_frame_variables_and_upvalues() {
+        let module = crate::parser::Parser::parse_module(
+            "fx main() {\n    let base = 10\n    let add = |x| {\n        x + base\n    }\n    add(5)\n}\nmain()\n",
+        )
+        .unwrap();
+        let script = crate::bytecode::Compiler::new()
+            .with_optimization(false)
+            .compile_module(&module)
+            .unwrap();
+        let mut vm = VM::new();
+        vm.set_debug_mode(true);
+        vm.add_breakpoint(None, 4);
+        assert_eq!(paused_at(vm.run_debug(script)), 4);
+
+        // Inside the lambda, `base` is an upvalue
+        let names = |variables: Vec<DebugVariable>| -> Vec<String> {
+            variables.into_iter().map(|v| v.name).collect()
+        };
+        assert_eq!(names(vm.get_frame_variables(0)), ["base", "x"]);
+        assert_eq!(vm.evaluate_in_frame(0, "x + base"), Ok(Value::Int(15)));
+
+        // The caller's frame
+        assert_eq!(names(vm.get_frame_variables(1)), ["base", "add"]);
+        assert_eq!(vm.evaluate_in_frame(1, "base * 2"), Ok(Value::Int(20)));
+        assert!(vm.evaluate_in_frame(5, "base").is_err());
+
+        assert!(matches!(vm.continue_debug(), DebugStepResult::Completed(_)));
+    }
+
+    /// A function called from `main`, whose result ends up in `result`
+    const DOUBLE: &str =
+        "fx double(x) {\n    x * 2\n}\nfx main() {\n    double(5)\n}\nlet result = main()\n";
+
+    fn debug_program(source: &str, breakpoint: u32) -> (VM, u32) {
+        let module = crate::parser::Parser::parse_module(source).unwrap();
+        let script = crate::bytecode::Compiler::new()
+            .with_optimization(false)
+            .compile_module(&module)
+            .unwrap();
+        let mut vm = VM::new();
+        vm.set_debug_mode(true);
+        let id = vm.add_breakpoint(None, breakpoint);
+        assert_eq!(paused_at(vm.run_debug(script)), breakpoint);
+        (vm, id)
+    }
+
+    fn definitions(source: &str) -> Vec<(String, Rc<Function>)> {
+        let module = crate::parser::Parser::parse_module(source).unwrap();
+        crate::bytecode::Compiler::new()
+            .with_optimization(false)
+            .compile_definitions(&module)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_hot_reload() {
+        let (mut vm, _) = debug_program(DOUBLE, 5);
+
+        let edited = format!(
+            "{}fx triple(x) {{\n    x * 3\n}}\n",
+            DOUBLE.replace("double(x)", "double(x, y)")
+        );
+        let report = vm.hot_reload(definitions(&edited));
+        assert_eq!(report.added, ["triple"]);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].0, "double");
+        assert!(report.reloaded.is_empty());
+
+        // Only the changed function is swapped in
+        let report = vm.hot_reload(definitions(&DOUBLE.replace("x * 2", "x * 3")));
+        assert_eq!(report.reloaded, ["double"]);
+        assert!(report.moved_breakpoints.is_empty());
+
+        assert!(matches!(vm.continue_debug(), DebugStepResult::Completed(_)));
+        assert_eq!(vm.globals["result"], Value::Int(15));
+    }
+
+    #[test]
+    fn test_hot_reload_and_restart_frame() {
+        let (mut vm, id) = debug_program(DOUBLE, 2);
+
+        // Both functions move down two lines, taking the breakpoint along
+        let edited = format!("\n\n{}", DOUBLE.replace("x * 2", "x * 3"));
+        let report = vm.hot_reload(definitions(&edited));
+        assert_eq!(report.reloaded, ["double", "main"]);
+        assert_eq!(report.moved_breakpoints, [(id, 4)]);
+
+        // Paused frames run the old code until they are restarted;
+        // restarting `main` drops `double`, which it then calls anew
+        assert!(vm.restart_frame(2).is_err());
+        let state = vm.restart_frame(1).unwrap();
+        assert_eq!(state.location.line, 7);
+        assert_eq!(state.call_stack.len(), 2);
+        assert_eq!(paused_at(vm.continue_debug()), 4);
+
+        assert!(matches!(vm.continue_debug(), DebugStepResult::Completed(_)));
+        assert_eq!(vm.globals["result"], Value::Int(15));
+    }
+
+    #[test]
+    fn test_step_back_undoes_global_writes() {
+        let (mut vm, _) = debug_program(
+            "let count = 0\nfx bump() {\n    count = count + 1\n}\nbump()\nbump()\nbump()\n",
+            7,
+        );
+        assert_eq!(vm.globals["count"], Value::Int(2));
+        assert!(vm.can_step_back());
+
+        // Back to the start of the second call's body
+        let state = vm.step_back().unwrap();
+        assert_eq!(state.location.line, 3);
+        assert_eq!(state.call_stack.len(), 2);
+        assert_eq!(vm.globals["count"], Value::Int(1));
+
+        let trace = vm.execution_trace();
+        let first_call = trace.iter().find(|entry| entry.line == 3).unwrap();
+        assert_eq!(first_call.function_name, "bump");
+        assert_eq!(first_call.writes[0].name, "count");
+        assert_eq!(first_call.writes[0].value, "1");
+
+        // Running forward again re-executes the write
+        assert_eq!(paused_at(vm.continue_debug()), 7);
+        assert_eq!(vm.globals["count"], Value::Int(2));
+        assert!(matches!(vm.continue_debug(), DebugStepResult::Completed(_)));
+        assert_eq!(vm.globals["count"], Value::Int(3));
+    }
+
+    #[test]
+    fn test_reverse_continue() {
+        let (mut vm, script) = debug_loop();
+        let id = vm.add_breakpoint(None, 4);
+        vm.breakpoint_mut(id).unwrap().condition = Some("i == 3".to_string());
+        assert_eq!(paused_at(vm.run_debug(script)), 4);
+        assert_eq!(vm.evaluate_in_frame(0, "total"), Ok(Value::Int(3)));
+
+        vm.breakpoint_mut(id).unwrap().condition = Some("i == 1".to_string());
+        let state = vm.reverse_continue().unwrap();
+        assert_eq!(state.pause_reason, PauseReason::Breakpoint(id));
+        assert_eq!(state.location.line, 4);
+        assert_eq!(vm.evaluate_in_frame(0, "i"), Ok(Value::Int(1)));
+        assert_eq!(vm.evaluate_in_frame(0, "total"), Ok(Value::Int(0)));
+
+        // Without a breakpoint to stop at, it runs back to where the script
+        // defines `main`
+        vm.remove_breakpoint(id);
+        let state = vm.reverse_continue().unwrap();
+        assert_eq!(state.location.line, 1);
+        assert_eq!(state.call_stack.len(), 1);
+        assert!(!vm.can_step_back());
+        assert!(vm.step_back().is_none());
+
+        assert!(matches!(vm.continue_debug(), DebugStepResult::Completed(_)));
+    }
+
+    #[test]
+    fn test_cpu_profile_attributes_samples_to_functions() {
+        let source = "fx fib(n) {\n    if n < 2 {\n        return n\n    }\n    fib(n - 1) + fib(n - 2)\n}\nfx main() {\n    fib(12)\n}\nlet result = main()\n";
+        let module = crate::parser::Parser::parse_module(source).unwrap();
+        let script = crate::bytecode::Compiler::with_source("fib.strat")
+            .compile_module(&module)
+            .unwrap();
+
+        let mut vm = VM::new_without_jit();
+        vm.enable_cpu_profiling(1);
+        vm.run(script).unwrap();
+        let profile = vm.take_cpu_profile().unwrap();
+
+        assert_eq!(profile.sample_count(), profile.instructions());
+        let totals = profile.function_totals();
+        let (name, own, _) = &totals[0];
+        assert_eq!(name, "fib");
+        assert!(*own > profile.sample_count() / 2);
+        assert!(totals
+            .iter()
+            .any(|(name, _, total)| name == "main" && *total > *own));
+
+        let (frame, _) = &profile.line_totals()[0];
+        assert_eq!(frame.file.as_deref(), Some("fib.strat"));
+        assert!((2..=5).contains(&frame.line));
+        assert!(profile.to_folded(false).contains("main;fib;fib"));
+    }
+
+    #[test]
+    fn test_allocation_profiling_reports_call_sites() {
+        use crate::data::{global_profiler, memory_categories};
+
+        let source = "fx build(n) {\n    [n, n + 1]\n}\nlet kept = build(0)\nfor i in 0..10 {\n    build(i)\n}\n";
+        let module = crate::parser::Parser::parse_module(source).unwrap();
+        let script = crate::bytecode::Compiler::with_source("alloc.strat")
+            .compile_module(&module)
+            .unwrap();
+
+        crate::data::reset_profiler();
+        crate::data::enable_profiling();
+        let mut vm = VM::new_without_jit();
+        vm.set_allocation_profiling(true);
+        vm.run(script).unwrap();
+        vm.sweep_allocations();
+        crate::data::disable_profiling();
+
+        let profiler = global_profiler();
+        let site = profiler
+            .call_sites()
+            .into_iter()
+            .find(|site| site.site.function == "build")
+            .unwrap();
+        assert_eq!(site.category, memory_categories::LIST);
+        assert_eq!(site.allocation_count, 11);
+        assert_eq!(site.site.file.as_deref(), Some("alloc.strat"));
+        assert_eq!(site.site.line, 2);
+
+        // Only the list kept in a global is still alive
+        let lists = &profiler.category_stats()[memory_categories::LIST];
+        assert_eq!(lists.deallocation_count, 10);
+        assert_eq!(lists.current_bytes, site.total_bytes / 11);
+    }
+
+    #[test]
+    fn test_mock_replaces_functions_and_records_calls() {
+        let source = r#"
+            fx greet(name) { "hello " + name }
+            fx fetch(url) { Http.get(url) }
+            let http = Test.mock("Http.get", |url| "stub:" + url)
+            let hi = Test.mock("greet", |name| "hi " + name)
+            let fetched = fetch("example.com")
+            let greeted = greet("bob")
+            let times = http.times_called()
+            let with_url = http.called_with("example.com")
+            let with_other = http.called_with("other.com")
+            hi.restore()
+            let restored = greet("bob")
+            Test.mock("greet", |name| "hey " + name)
+        "#;
+        let module = crate::parser::Parser::parse_module(source).unwrap();
+        let script = crate::bytecode::Compiler::new()
+            .compile_module(&module)
+            .unwrap();
+
+        let mut vm = VM::new();
+        vm.run(script).unwrap();
+        let global = |vm: &VM, name: &str| vm.globals()[name].clone();
+        assert_eq!(global(&vm, "fetched"), Value::string("stub:example.com"));
+        assert_eq!(global(&vm, "greeted"), Value::string("hi bob"));
+        assert_eq!(global(&vm, "times"), Value::Int(1));
+        assert_eq!(global(&vm, "with_url"), Value::Bool(true));
+        assert_eq!(global(&vm, "with_other"), Value::Bool(false));
+        assert_eq!(global(&vm, "restored"), Value::string("hello bob"));
+
+        // The last mock is still in place until the mocks are restored
+        assert!(matches!(global(&vm, "greet"), Value::Mock(_)));
+        vm.restore_mocks();
+        assert!(matches!(global(&vm, "greet"), Value::Closure(_)));
+        assert!(vm.namespace_mock("Http", "get").is_none());
+    }
+
+    #[test]
+    fn test_clone_copies_lists_and_maps_on_write() {
+        let source = r#"
+            let xs = [1, 2, 3]
+            let alias = xs
+            let copy = xs.clone()
+            copy.push(4)
+            alias.push(5)
+            let scores = {"a": 1}
+            let snapshot = scores.clone()
+            scores.set("b", 2)
+        "#;
+        let module = crate::parser::Parser::parse_module(source).unwrap();
+        let script = crate::bytecode::Compiler::new()
+            .compile_module(&module)
+            .unwrap();
+
+        let mut vm = VM::new();
+        vm.run(script).unwrap();
+        let global = |vm: &VM, name: &str| vm.globals()[name].clone();
+        let ints = |values: &[i64]| Value::list(values.iter().copied().map(Value::Int).collect());
+
+        // Assignment still aliases; clone() makes an independent list
+        assert_eq!(global(&vm, "xs"), ints(&[1, 2, 3, 5]));
+        assert_eq!(global(&vm, "alias"), ints(&[1, 2, 3, 5]));
+        assert_eq!(global(&vm, "copy"), ints(&[1, 2, 3, 4]));
+
+        let Value::Map(scores) = global(&vm, "scores") else {
+            panic!("Expected map");
+        };
+        let Value::Map(snapshot) = global(&vm, "snapshot") else {
+            panic!("Expected map");
+        };
+        assert_eq!(scores.borrow().len(), 2);
+        assert_eq!(snapshot.borrow().len(), 1);
+        assert!(!scores.is_shared());
+    }
 }
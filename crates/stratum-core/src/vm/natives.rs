@@ -1,6 +1,6 @@
 //! Native namespace implementations for File, Dir, Path, Env, Args, Shell, Http,
-//! Json, Toml, Yaml, Base64, Url, DateTime, Duration, Time, Regex, Gzip, Zip,
-//! Hash, Uuid, Random, Crypto, Gui
+//! Json, Toml, Yaml, Csv, Base64, Url, DateTime, Duration, Time, Regex, Template,
+//! Gzip, Zip, Zstd, Tar, Hash, Uuid, Random, Crypto, Gui
 
 use std::cell::RefCell;
 use std::collections::HashMap;
@@ -17,7 +17,7 @@ use flate2::write::GzEncoder;
 use flate2::Compression;
 
 use aes_gcm::{
-    aead::{Aead, AeadCore, KeyInit, OsRng},
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
     Aes256Gcm, Nonce,
 };
 use base64::Engine;
@@ -34,17 +34,19 @@ use serde_json;
 use sha2::{Digest, Sha256, Sha512};
 use uuid::Uuid;
 
+use super::{determinism, i18n};
 use crate::bytecode::{
-    FutureState, HashableValue, ImageWrapper, TcpListenerWrapper, TcpStreamWrapper,
-    UdpSocketWrapper, Value, WeakRefValue, WebSocketServerConnWrapper, WebSocketServerWrapper,
-    WebSocketWrapper, XmlDocumentWrapper,
+    ChildProcessWrapper, CsvReaderWrapper, CsvWriterWrapper, EnumTypeInfo, EnumVariantInstance,
+    FieldType, FutureState, HashableValue, ImageWrapper, StructInstance, TcpListenerWrapper,
+    TcpStreamWrapper, UdpSocketWrapper, Value, ValueMap, VariantFields, WeakRefValue,
+    WebSocketServerConnWrapper, WebSocketServerWrapper, WebSocketWrapper, XmlDocumentWrapper,
 };
 use crate::data::{
     read_csv_with_options, read_json, read_parquet, sql_query, write_csv, write_json,
     write_parquet, AggOp, AggSpec, CubeBuilder, DataFrame, JoinSpec, Series, SqlContext,
 };
 use image::{imageops::FilterType, DynamicImage, ImageFormat};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use sxd_document::parser as xml_parser;
 use sxd_xpath::{evaluate_xpath, Context, Factory, Value as XPathValue};
 
@@ -538,13 +540,13 @@ fn env_all(args: &[Value]) -> NativeResult {
     if !args.is_empty() {
         return Err(format!("Env.all() expects 0 arguments, got {}", args.len()));
     }
-    let mut map = HashMap::new();
+    let mut map = ValueMap::new();
     for (key, value) in env::vars() {
         let k = HashableValue::String(Rc::new(key));
         let v = Value::string(value);
         map.insert(k, v);
     }
-    Ok(Value::Map(Rc::new(std::cell::RefCell::new(map))))
+    Ok(Value::map(map))
 }
 
 fn env_has(args: &[Value]) -> NativeResult {
@@ -559,6 +561,28 @@ fn env_has(args: &[Value]) -> NativeResult {
 // Args Module
 // ============================================================================
 
+/// Program arguments set by the host, used by `Args` instead of the
+/// process's own arguments
+static PROGRAM_ARGS: RwLock<Option<Vec<String>>> = RwLock::new(None);
+
+/// Set the arguments the `Args` namespace reports
+///
+/// By default `Args` reports the arguments of the process itself; a host
+/// running a script (such as `stratum run file.strat -- a b`) sets them to the
+/// script's own arguments.
+pub fn set_program_args(args: Vec<String>) {
+    *PROGRAM_ARGS.write().unwrap() = Some(args);
+}
+
+/// The arguments reported by `Args`
+fn program_args() -> Vec<String> {
+    PROGRAM_ARGS
+        .read()
+        .unwrap()
+        .clone()
+        .unwrap_or_else(|| env::args().collect())
+}
+
 pub fn args_method(method: &str, args: &[Value]) -> NativeResult {
     match method {
         "all" | "list" => args_all(args),
@@ -569,7 +593,7 @@ pub fn args_method(method: &str, args: &[Value]) -> NativeResult {
 }
 
 fn args_all(_args: &[Value]) -> NativeResult {
-    let args: Vec<Value> = env::args().map(Value::string).collect();
+    let args: Vec<Value> = program_args().into_iter().map(Value::string).collect();
     Ok(Value::list(args))
 }
 
@@ -586,7 +610,7 @@ fn args_get(args: &[Value]) -> NativeResult {
             ))
         }
     };
-    let cli_args: Vec<String> = env::args().collect();
+    let cli_args = program_args();
     if index < cli_args.len() {
         Ok(Value::string(&cli_args[index]))
     } else {
@@ -595,7 +619,7 @@ fn args_get(args: &[Value]) -> NativeResult {
 }
 
 fn args_count(_args: &[Value]) -> NativeResult {
-    Ok(Value::Int(env::args().count() as i64))
+    Ok(Value::Int(program_args().len() as i64))
 }
 
 // ============================================================================
@@ -650,7 +674,7 @@ fn shell_run(args: &[Value]) -> NativeResult {
         .map_err(|e| format!("failed to execute '{}': {}", program, e))?;
 
     // Create result map with stdout, stderr, exit_code
-    let mut result = HashMap::new();
+    let mut result = ValueMap::new();
     result.insert(
         HashableValue::String(Rc::new("stdout".to_string())),
         Value::string(String::from_utf8_lossy(&output.stdout)),
@@ -668,7 +692,7 @@ fn shell_run(args: &[Value]) -> NativeResult {
         Value::Bool(output.status.success()),
     );
 
-    Ok(Value::Map(Rc::new(std::cell::RefCell::new(result))))
+    Ok(Value::map(result))
 }
 
 fn shell_exec(args: &[Value]) -> NativeResult {
@@ -771,7 +795,7 @@ fn response_to_value(response: reqwest::blocking::Response) -> NativeResult {
     let ok = response.status().is_success();
 
     // Collect response headers
-    let mut resp_headers = HashMap::new();
+    let mut resp_headers = ValueMap::new();
     for (name, value) in response.headers().iter() {
         if let Ok(v) = value.to_str() {
             resp_headers.insert(
@@ -787,7 +811,7 @@ fn response_to_value(response: reqwest::blocking::Response) -> NativeResult {
         .map_err(|e| format!("failed to read response body: {}", e))?;
 
     // Build result map
-    let mut result = HashMap::new();
+    let mut result = ValueMap::new();
     result.insert(
         HashableValue::String(Rc::new("status".to_string())),
         Value::Int(status),
@@ -798,14 +822,14 @@ fn response_to_value(response: reqwest::blocking::Response) -> NativeResult {
     );
     result.insert(
         HashableValue::String(Rc::new("headers".to_string())),
-        Value::Map(Rc::new(RefCell::new(resp_headers))),
+        Value::map(resp_headers),
     );
     result.insert(
         HashableValue::String(Rc::new("ok".to_string())),
         Value::Bool(ok),
     );
 
-    Ok(Value::Map(Rc::new(RefCell::new(result))))
+    Ok(Value::map(result))
 }
 
 fn http_get(args: &[Value]) -> NativeResult {
@@ -998,7 +1022,7 @@ fn http_head(args: &[Value]) -> NativeResult {
     let status = response.status().as_u16() as i64;
     let ok = response.status().is_success();
 
-    let mut resp_headers = HashMap::new();
+    let mut resp_headers = ValueMap::new();
     for (name, value) in response.headers().iter() {
         if let Ok(v) = value.to_str() {
             resp_headers.insert(
@@ -1008,7 +1032,7 @@ fn http_head(args: &[Value]) -> NativeResult {
         }
     }
 
-    let mut result = HashMap::new();
+    let mut result = ValueMap::new();
     result.insert(
         HashableValue::String(Rc::new("status".to_string())),
         Value::Int(status),
@@ -1019,14 +1043,14 @@ fn http_head(args: &[Value]) -> NativeResult {
     );
     result.insert(
         HashableValue::String(Rc::new("headers".to_string())),
-        Value::Map(Rc::new(RefCell::new(resp_headers))),
+        Value::map(resp_headers),
     );
     result.insert(
         HashableValue::String(Rc::new("ok".to_string())),
         Value::Bool(ok),
     );
 
-    Ok(Value::Map(Rc::new(RefCell::new(result))))
+    Ok(Value::map(result))
 }
 
 // ============================================================================
@@ -1037,6 +1061,9 @@ pub fn json_method(method: &str, args: &[Value]) -> NativeResult {
     match method {
         "encode" | "stringify" => json_encode(args),
         "decode" | "parse" => json_decode(args),
+        "validate" => json_validate(args),
+        // Nested struct types can only be resolved with VM access (see VM::namespace_method_dispatch)
+        "decode_as" => json_decode_as(args, &|_| None),
         _ => Err(format!("Json has no method '{method}'")),
     }
 }
@@ -1101,6 +1128,17 @@ fn value_to_json(value: &Value) -> Result<serde_json::Value, String> {
             }
             Ok(serde_json::Value::Object(obj))
         }
+        Value::EnumVariant(e) => {
+            let variant = e.variant_name.clone();
+            match &e.data {
+                None => Ok(serde_json::Value::String(variant)),
+                Some(data) => {
+                    let mut obj = serde_json::Map::new();
+                    obj.insert(variant, value_to_json(data)?);
+                    Ok(serde_json::Value::Object(obj))
+                }
+            }
+        }
         other => Err(format!("cannot convert {} to JSON", other.type_name())),
     }
 }
@@ -1125,13 +1163,616 @@ fn json_to_value(json: &serde_json::Value) -> NativeResult {
             Ok(Value::list(items?))
         }
         serde_json::Value::Object(obj) => {
-            let mut map = HashMap::new();
+            let mut map = ValueMap::new();
             for (k, v) in obj.iter() {
                 let key = HashableValue::String(Rc::new(k.clone()));
                 map.insert(key, json_to_value(v)?);
             }
-            Ok(Value::Map(Rc::new(RefCell::new(map))))
+            Ok(Value::map(map))
+        }
+    }
+}
+
+/// Path segment for an object key in error paths (`$.name` or `$["odd key"]`)
+fn json_path_key(path: &str, key: &str) -> String {
+    let is_ident = !key.is_empty()
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !key.starts_with(|c: char| c.is_ascii_digit());
+    if is_ident {
+        format!("{path}.{key}")
+    } else {
+        format!("{path}[{key:?}]")
+    }
+}
+
+/// JSON type name used in validation messages
+fn json_type_name(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "boolean",
+        serde_json::Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        serde_json::Value::Number(_) => "number",
+        serde_json::Value::String(_) => "string",
+        serde_json::Value::Array(_) => "array",
+        serde_json::Value::Object(_) => "object",
+    }
+}
+
+/// Accept either a JSON string or an already-decoded Stratum value
+fn json_arg_to_json(value: &Value, name: &str) -> Result<serde_json::Value, String> {
+    match value {
+        Value::String(s) => {
+            serde_json::from_str(s).map_err(|e| format!("failed to parse {name} as JSON: {}", e))
+        }
+        other => value_to_json(other),
+    }
+}
+
+/// Json.validate(value: Any, schema: Map|String) -> Map
+/// Validates a value against a JSON Schema. Returns
+/// `{valid: Bool, errors: List<{path, message}>}`. Supports the commonly used
+/// keywords: type, enum, const, properties, required, additionalProperties,
+/// patternProperties, items, prefixItems, contains, min/max constraints,
+/// pattern, format, allOf/anyOf/oneOf/not, if/then/else, and local `$ref`s.
+fn json_validate(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err(format!(
+            "Json.validate() expects 2 arguments, got {}",
+            args.len()
+        ));
+    }
+    let instance = value_to_json(&args[0])?;
+    let schema = json_arg_to_json(&args[1], "schema")?;
+
+    let mut errors = Vec::new();
+    let validator = JsonSchemaValidator { root: &schema };
+    validator.validate(&instance, &schema, "$", &mut errors)?;
+
+    let errors: Vec<Value> = errors
+        .into_iter()
+        .map(|(path, message)| {
+            let mut map = ValueMap::new();
+            map.insert(
+                HashableValue::String(Rc::new("path".to_string())),
+                Value::string(path),
+            );
+            map.insert(
+                HashableValue::String(Rc::new("message".to_string())),
+                Value::string(message),
+            );
+            Value::map(map)
+        })
+        .collect();
+
+    let mut result = ValueMap::new();
+    result.insert(
+        HashableValue::String(Rc::new("valid".to_string())),
+        Value::Bool(errors.is_empty()),
+    );
+    result.insert(
+        HashableValue::String(Rc::new("errors".to_string())),
+        Value::list(errors),
+    );
+    Ok(Value::map(result))
+}
+
+/// Minimal JSON Schema validator (draft 2020-12 keyword subset)
+struct JsonSchemaValidator<'a> {
+    root: &'a serde_json::Value,
+}
+
+impl JsonSchemaValidator<'_> {
+    /// Maximum `$ref` nesting, guarding against self-referential schemas
+    const MAX_DEPTH: usize = 64;
+
+    fn validate(
+        &self,
+        instance: &serde_json::Value,
+        schema: &serde_json::Value,
+        path: &str,
+        errors: &mut Vec<(String, String)>,
+    ) -> Result<(), String> {
+        self.validate_at(instance, schema, path, errors, 0)
+    }
+
+    fn is_valid(
+        &self,
+        instance: &serde_json::Value,
+        schema: &serde_json::Value,
+        depth: usize,
+    ) -> Result<bool, String> {
+        let mut errors = Vec::new();
+        self.validate_at(instance, schema, "$", &mut errors, depth)?;
+        Ok(errors.is_empty())
+    }
+
+    fn validate_at(
+        &self,
+        instance: &serde_json::Value,
+        schema: &serde_json::Value,
+        path: &str,
+        errors: &mut Vec<(String, String)>,
+        depth: usize,
+    ) -> Result<(), String> {
+        use serde_json::Value as J;
+
+        if depth > Self::MAX_DEPTH {
+            return Err("JSON schema is too deeply nested (recursive $ref?)".to_string());
+        }
+        let schema = match schema {
+            J::Bool(true) => return Ok(()),
+            J::Bool(false) => {
+                errors.push((path.to_string(), "value is not allowed here".to_string()));
+                return Ok(());
+            }
+            J::Object(obj) => obj,
+            _ => return Err("JSON schema must be an object or boolean".to_string()),
+        };
+        let mut fail = |message: String| errors.push((path.to_string(), message));
+
+        if let Some(J::String(reference)) = schema.get("$ref") {
+            let target = reference
+                .strip_prefix('#')
+                .and_then(|pointer| self.root.pointer(pointer))
+                .ok_or_else(|| format!("unsupported or unresolved $ref '{reference}'"))?;
+            self.validate_at(instance, target, path, errors, depth + 1)?;
+            return Ok(());
+        }
+
+        if let Some(expected) = schema.get("type") {
+            let types: Vec<&str> = match expected {
+                J::String(t) => vec![t.as_str()],
+                J::Array(ts) => ts.iter().filter_map(J::as_str).collect(),
+                _ => Vec::new(),
+            };
+            let actual = json_type_name(instance);
+            let matches = types.iter().any(|t| {
+                *t == actual
+                    || (*t == "number" && actual == "integer")
+                    || (*t == "integer" && instance.as_f64().is_some_and(|f| f.fract() == 0.0))
+            });
+            if !types.is_empty() && !matches {
+                fail(format!("expected {}, got {actual}", types.join(" or ")));
+                return Ok(());
+            }
+        }
+        if let Some(J::Array(options)) = schema.get("enum") {
+            if !options.contains(instance) {
+                let allowed: Vec<String> = options.iter().map(ToString::to_string).collect();
+                fail(format!("value must be one of {}", allowed.join(", ")));
+            }
+        }
+        if let Some(expected) = schema.get("const") {
+            if expected != instance {
+                fail(format!("value must be {expected}"));
+            }
+        }
+
+        // Numbers
+        if let Some(n) = instance.as_f64() {
+            let bound = |key: &str| schema.get(key).and_then(J::as_f64);
+            if let Some(min) = bound("minimum").filter(|min| n < *min) {
+                fail(format!("value {n} is less than minimum {min}"));
+            }
+            if let Some(max) = bound("maximum").filter(|max| n > *max) {
+                fail(format!("value {n} is greater than maximum {max}"));
+            }
+            if let Some(min) = bound("exclusiveMinimum").filter(|min| n <= *min) {
+                fail(format!("value {n} must be greater than {min}"));
+            }
+            if let Some(max) = bound("exclusiveMaximum").filter(|max| n >= *max) {
+                fail(format!("value {n} must be less than {max}"));
+            }
+            if let Some(step) = bound("multipleOf").filter(|s| *s > 0.0) {
+                if ((n / step) - (n / step).round()).abs() > 1e-9 {
+                    fail(format!("value {n} is not a multiple of {step}"));
+                }
+            }
+        }
+
+        // Strings
+        if let J::String(s) = instance {
+            let len = s.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(J::as_u64) {
+                if len < min {
+                    fail(format!("string is shorter than {min} characters"));
+                }
+            }
+            if let Some(max) = schema.get("maxLength").and_then(J::as_u64) {
+                if len > max {
+                    fail(format!("string is longer than {max} characters"));
+                }
+            }
+            if let Some(J::String(pattern)) = schema.get("pattern") {
+                let re = Regex::new(pattern)
+                    .map_err(|e| format!("invalid pattern '{pattern}' in schema: {e}"))?;
+                if !re.is_match(s) {
+                    fail(format!("string does not match pattern '{pattern}'"));
+                }
+            }
+            if let Some(J::String(format)) = schema.get("format") {
+                let ok = match format.as_str() {
+                    "date-time" => ChronoDateTime::parse_from_rfc3339(s).is_ok(),
+                    "date" => chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").is_ok(),
+                    "email" => s
+                        .split_once('@')
+                        .is_some_and(|(user, host)| !user.is_empty() && host.contains('.')),
+                    "uuid" => Uuid::parse_str(s).is_ok(),
+                    // Unknown formats are annotations only
+                    _ => true,
+                };
+                if !ok {
+                    fail(format!("string is not a valid {format}"));
+                }
+            }
+        }
+
+        // Arrays
+        if let J::Array(items) = instance {
+            let count = items.len() as u64;
+            if let Some(min) = schema.get("minItems").and_then(J::as_u64) {
+                if count < min {
+                    fail(format!("array has fewer than {min} items"));
+                }
+            }
+            if let Some(max) = schema.get("maxItems").and_then(J::as_u64) {
+                if count > max {
+                    fail(format!("array has more than {max} items"));
+                }
+            }
+            if schema.get("uniqueItems") == Some(&J::Bool(true)) {
+                let duplicate = items
+                    .iter()
+                    .enumerate()
+                    .any(|(i, a)| items[i + 1..].contains(a));
+                if duplicate {
+                    fail("array items must be unique".to_string());
+                }
+            }
+            let prefix = match schema.get("prefixItems") {
+                Some(J::Array(prefix)) => prefix.as_slice(),
+                _ => &[],
+            };
+            for (i, (item, item_schema)) in items.iter().zip(prefix).enumerate() {
+                self.validate_at(
+                    item,
+                    item_schema,
+                    &format!("{path}[{i}]"),
+                    errors,
+                    depth + 1,
+                )?;
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate().skip(prefix.len()) {
+                    self.validate_at(
+                        item,
+                        item_schema,
+                        &format!("{path}[{i}]"),
+                        errors,
+                        depth + 1,
+                    )?;
+                }
+            }
+            if let Some(contains) = schema.get("contains") {
+                let mut found = false;
+                for item in items {
+                    if self.is_valid(item, contains, depth + 1)? {
+                        found = true;
+                        break;
+                    }
+                }
+                if !found {
+                    errors.push((
+                        path.to_string(),
+                        "array does not contain a matching item".to_string(),
+                    ));
+                }
+            }
+        }
+
+        // Objects
+        if let J::Object(obj) = instance {
+            if let Some(J::Array(required)) = schema.get("required") {
+                for key in required.iter().filter_map(J::as_str) {
+                    if !obj.contains_key(key) {
+                        errors.push((
+                            json_path_key(path, key),
+                            "required property is missing".to_string(),
+                        ));
+                    }
+                }
+            }
+            let count = obj.len() as u64;
+            if let Some(min) = schema.get("minProperties").and_then(J::as_u64) {
+                if count < min {
+                    errors.push((
+                        path.to_string(),
+                        format!("object has fewer than {min} properties"),
+                    ));
+                }
+            }
+            if let Some(max) = schema.get("maxProperties").and_then(J::as_u64) {
+                if count > max {
+                    errors.push((
+                        path.to_string(),
+                        format!("object has more than {max} properties"),
+                    ));
+                }
+            }
+
+            let properties = schema.get("properties").and_then(J::as_object);
+            let pattern_properties = match schema.get("patternProperties").and_then(J::as_object) {
+                Some(patterns) => patterns
+                    .iter()
+                    .map(|(pattern, sub)| {
+                        Regex::new(pattern)
+                            .map(|re| (re, sub))
+                            .map_err(|e| format!("invalid pattern '{pattern}' in schema: {e}"))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?,
+                None => Vec::new(),
+            };
+
+            let mut keys: Vec<&String> = obj.keys().collect();
+            keys.sort();
+            for key in keys {
+                let value = &obj[key];
+                let key_path = json_path_key(path, key);
+                let mut matched = false;
+                if let Some(sub) = properties.and_then(|p| p.get(key)) {
+                    matched = true;
+                    self.validate_at(value, sub, &key_path, errors, depth + 1)?;
+                }
+                for (re, sub) in &pattern_properties {
+                    if re.is_match(key) {
+                        matched = true;
+                        self.validate_at(value, sub, &key_path, errors, depth + 1)?;
+                    }
+                }
+                if !matched {
+                    match schema.get("additionalProperties") {
+                        Some(J::Bool(false)) => errors
+                            .push((key_path, "additional property is not allowed".to_string())),
+                        Some(sub @ J::Object(_)) => {
+                            self.validate_at(value, sub, &key_path, errors, depth + 1)?;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        // Combinators
+        if let Some(J::Array(all)) = schema.get("allOf") {
+            for sub in all {
+                self.validate_at(instance, sub, path, errors, depth + 1)?;
+            }
+        }
+        if let Some(J::Array(any)) = schema.get("anyOf") {
+            let mut matched = false;
+            for sub in any {
+                if self.is_valid(instance, sub, depth + 1)? {
+                    matched = true;
+                    break;
+                }
+            }
+            if !matched {
+                errors.push((
+                    path.to_string(),
+                    "value does not match any of the allowed schemas".to_string(),
+                ));
+            }
+        }
+        if let Some(J::Array(one)) = schema.get("oneOf") {
+            let mut matches = 0;
+            for sub in one {
+                if self.is_valid(instance, sub, depth + 1)? {
+                    matches += 1;
+                }
+            }
+            if matches != 1 {
+                errors.push((
+                    path.to_string(),
+                    format!("value must match exactly one schema, matched {matches}"),
+                ));
+            }
+        }
+        if let Some(not) = schema.get("not") {
+            if self.is_valid(instance, not, depth + 1)? {
+                errors.push((
+                    path.to_string(),
+                    "value must not match the schema in 'not'".to_string(),
+                ));
+            }
+        }
+        if let Some(condition) = schema.get("if") {
+            let branch = if self.is_valid(instance, condition, depth + 1)? {
+                schema.get("then")
+            } else {
+                schema.get("else")
+            };
+            if let Some(branch) = branch {
+                self.validate_at(instance, branch, path, errors, depth + 1)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Looks up the type descriptor (`Value::StructType` or `Value::EnumType`)
+/// bound to a type name
+pub type TypeResolver<'a> = &'a dyn Fn(&str) -> Option<Value>;
+
+/// Json.decode_as(json: String|Map, type: StructType) -> Struct
+/// Decodes JSON into an instance of a declared struct, checking each field
+/// against its declared type. Nested struct and enum types are looked up
+/// with `resolve`; fields typed with other named types (aliases) are passed
+/// through unchecked. Errors name the offending path, e.g.
+/// `$.address.zip: expected Int, got String`.
+pub fn json_decode_as(args: &[Value], resolve: TypeResolver<'_>) -> NativeResult {
+    if args.len() != 2 {
+        return Err(format!(
+            "Json.decode_as() expects 2 arguments, got {}",
+            args.len()
+        ));
+    }
+    let json = json_arg_to_json(&args[0], "value")?;
+    let Value::StructType(info) = &args[1] else {
+        return Err(format!(
+            "Json.decode_as() expects a struct type as second argument, got {}",
+            args[1].type_name()
+        ));
+    };
+    decode_json_struct(&json, &info.name, &info.fields, "$", resolve)
+}
+
+/// `Type.from_json(json: String|Map)` for structs and enums that derive Json
+pub fn json_decode_type(ty: &Value, args: &[Value], resolve: TypeResolver<'_>) -> NativeResult {
+    let name = match ty {
+        Value::StructType(info) => &info.name,
+        Value::EnumType(info) => &info.name,
+        other => return Err(format!("cannot decode JSON into {}", other.type_name())),
+    };
+    if args.len() != 1 {
+        return Err(format!(
+            "{name}.from_json() expects 1 argument, got {}",
+            args.len()
+        ));
+    }
+    let json = json_arg_to_json(&args[0], "json")?;
+    decode_json_type(&json, ty, "$", resolve)
+}
+
+fn decode_json_type(
+    json: &serde_json::Value,
+    ty: &Value,
+    path: &str,
+    resolve: TypeResolver<'_>,
+) -> NativeResult {
+    match ty {
+        Value::StructType(info) => {
+            decode_json_struct(json, &info.name, &info.fields, path, resolve)
+        }
+        Value::EnumType(info) => decode_json_enum(json, info, path, resolve),
+        _ => json_to_value(json),
+    }
+}
+
+fn decode_json_struct(
+    json: &serde_json::Value,
+    type_name: &str,
+    fields: &[(String, FieldType)],
+    path: &str,
+    resolve: TypeResolver<'_>,
+) -> NativeResult {
+    let serde_json::Value::Object(obj) = json else {
+        return Err(format!(
+            "{path}: expected {type_name} object, got {}",
+            json_type_name(json)
+        ));
+    };
+
+    let mut instance = StructInstance::new(type_name.to_string());
+    for (name, ty) in fields {
+        let field_path = json_path_key(path, name);
+        let value = match obj.get(name) {
+            Some(value) => decode_json_field(value, ty, &field_path, resolve)?,
+            None if matches!(ty, FieldType::Nullable(_) | FieldType::Any) => Value::Null,
+            None => return Err(format!("{field_path}: missing required field of type {ty}")),
+        };
+        instance.fields.insert(name.clone(), value);
+    }
+    Ok(Value::Struct(Rc::new(RefCell::new(instance))))
+}
+
+/// Unit variants are encoded as `"Variant"`, variants with data as
+/// `{"Variant": data}` (see `value_to_json`)
+fn decode_json_enum(
+    json: &serde_json::Value,
+    info: &EnumTypeInfo,
+    path: &str,
+    resolve: TypeResolver<'_>,
+) -> NativeResult {
+    use serde_json::Value as J;
+
+    let (variant, payload) = match json {
+        J::String(variant) => (variant, None),
+        J::Object(obj) if obj.len() == 1 => {
+            let (variant, payload) = obj.iter().next().expect("object has one entry");
+            (variant, Some(payload))
+        }
+        _ => {
+            return Err(format!(
+                "{path}: expected {} variant, got {}",
+                info.name,
+                json_type_name(json)
+            ))
+        }
+    };
+    let Some(fields) = info.variant(variant) else {
+        return Err(format!("{path}: unknown {} variant '{variant}'", info.name));
+    };
+
+    let data_path = json_path_key(path, variant);
+    let data = match (fields, payload) {
+        (VariantFields::Unit, None) => None,
+        (VariantFields::Tuple(ty), Some(payload)) => {
+            Some(decode_json_field(payload, ty, &data_path, resolve)?)
+        }
+        (VariantFields::Struct(fields), Some(payload)) => Some(decode_json_struct(
+            payload, variant, fields, &data_path, resolve,
+        )?),
+        (VariantFields::Unit, Some(_)) => {
+            return Err(format!("{data_path}: unit variant takes no data"));
+        }
+        (_, None) => return Err(format!("{path}: variant '{variant}' expects data")),
+    };
+    Ok(Value::EnumVariant(Rc::new(EnumVariantInstance::new(
+        info.name.clone(),
+        variant.clone(),
+        data,
+    ))))
+}
+
+fn decode_json_field(
+    json: &serde_json::Value,
+    ty: &FieldType,
+    path: &str,
+    resolve: TypeResolver<'_>,
+) -> NativeResult {
+    use serde_json::Value as J;
+
+    let mismatch = || format!("{path}: expected {ty}, got {}", json_type_name(json));
+    match (ty, json) {
+        (FieldType::Any, _) => json_to_value(json),
+        (FieldType::Nullable(_), J::Null) => Ok(Value::Null),
+        (FieldType::Nullable(inner), _) => decode_json_field(json, inner, path, resolve),
+        (FieldType::Bool, J::Bool(b)) => Ok(Value::Bool(*b)),
+        (FieldType::Int, J::Number(n)) => n.as_i64().map(Value::Int).ok_or_else(mismatch),
+        (FieldType::Float, J::Number(n)) => n.as_f64().map(Value::Float).ok_or_else(mismatch),
+        (FieldType::String, J::String(s)) => Ok(Value::string(s.clone())),
+        (FieldType::List(inner), J::Array(items)) => {
+            let values = items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| decode_json_field(item, inner, &format!("{path}[{i}]"), resolve))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Value::list(values))
+        }
+        (FieldType::Map(inner), J::Object(obj)) => {
+            let mut map = ValueMap::new();
+            for (key, value) in obj {
+                let decoded = decode_json_field(value, inner, &json_path_key(path, key), resolve)?;
+                map.insert(HashableValue::String(Rc::new(key.clone())), decoded);
+            }
+            Ok(Value::map(map))
         }
+        (FieldType::Named(name), _) => match resolve(name) {
+            Some(ty) => decode_json_type(json, &ty, path, resolve),
+            None => json_to_value(json),
+        },
+        _ => Err(mismatch()),
     }
 }
 
@@ -1219,12 +1860,12 @@ fn toml_to_value(toml: &toml::Value) -> NativeResult {
             Ok(Value::list(items?))
         }
         toml::Value::Table(table) => {
-            let mut map = HashMap::new();
+            let mut map = ValueMap::new();
             for (k, v) in table.iter() {
                 let key = HashableValue::String(Rc::new(k.clone()));
                 map.insert(key, toml_to_value(v)?);
             }
-            Ok(Value::Map(Rc::new(RefCell::new(map))))
+            Ok(Value::map(map))
         }
         toml::Value::Datetime(dt) => Ok(Value::string(dt.to_string())),
     }
@@ -1326,7 +1967,7 @@ fn yaml_to_value(yaml: &serde_yaml::Value) -> NativeResult {
             Ok(Value::list(items?))
         }
         serde_yaml::Value::Mapping(mapping) => {
-            let mut map = HashMap::new();
+            let mut map = ValueMap::new();
             for (k, v) in mapping.iter() {
                 let key = match k {
                     serde_yaml::Value::String(s) => HashableValue::String(Rc::new(s.clone())),
@@ -1343,71 +1984,623 @@ fn yaml_to_value(yaml: &serde_yaml::Value) -> NativeResult {
                 };
                 map.insert(key, yaml_to_value(v)?);
             }
-            Ok(Value::Map(Rc::new(RefCell::new(map))))
+            Ok(Value::map(map))
         }
         serde_yaml::Value::Tagged(tagged) => yaml_to_value(&tagged.value),
     }
 }
 
 // ============================================================================
-// Base64 Module
+// Csv Module
 // ============================================================================
 
-pub fn base64_method(method: &str, args: &[Value]) -> NativeResult {
+/// Csv module entry point - row-level CSV parsing and writing
+pub fn csv_method(method: &str, args: &[Value]) -> NativeResult {
     match method {
-        "encode" => base64_encode(args),
-        "decode" => base64_decode(args),
-        _ => Err(format!("Base64 has no method '{method}'")),
+        "parse" => csv_parse(args),
+        "parse_line" => csv_parse_line(args),
+        "stringify" => csv_stringify(args),
+        "open" => csv_open(args),
+        "create" => csv_create(args),
+        _ => Err(format!("Csv has no method '{method}'")),
+    }
+}
+
+/// Options shared by the Csv readers and writers
+struct CsvOptions {
+    delimiter: u8,
+    quote: u8,
+    /// Reading: treat the first row as column names. Writing: emit a header row
+    headers: bool,
+    trim: bool,
+    flexible: bool,
+    comment: Option<u8>,
+    quote_style: csv::QuoteStyle,
+    terminator: csv::Terminator,
+    /// Column order for writing Map rows
+    columns: Option<Vec<String>>,
+    append: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            quote: b'"',
+            headers: true,
+            trim: false,
+            flexible: false,
+            comment: None,
+            quote_style: csv::QuoteStyle::Necessary,
+            terminator: csv::Terminator::Any(b'\n'),
+            columns: None,
+            append: false,
+        }
     }
 }
 
-fn base64_encode(args: &[Value]) -> NativeResult {
-    if args.len() != 1 {
-        return Err(format!(
-            "Base64.encode() expects 1 argument, got {}",
-            args.len()
-        ));
+/// Extract a single-byte option such as `delimiter` or `quote`
+fn get_csv_byte_option(map: &ValueMap, key: &str) -> Result<Option<u8>, String> {
+    let Some(text) = get_map_string(map, key) else {
+        return Ok(None);
+    };
+    match text.as_bytes() {
+        [byte] => Ok(Some(*byte)),
+        _ => Err(format!(
+            "Csv option '{key}' must be a single ASCII character, got '{text}'"
+        )),
     }
+}
 
-    let bytes = match &args[0] {
-        Value::String(s) => s.as_bytes().to_vec(),
-        Value::List(_) => get_bytes_arg(&args[0])?,
-        _ => {
+/// Parse the optional options Map for a Csv function
+fn get_csv_options(value: Option<&Value>, fn_name: &str) -> Result<CsvOptions, String> {
+    let mut opts = CsvOptions::default();
+    let map = match value {
+        None | Some(Value::Null) => return Ok(opts),
+        Some(Value::Map(map)) => map.borrow(),
+        Some(other) => {
             return Err(format!(
-                "Base64.encode() expects String or List<Int>, got {}",
-                args[0].type_name()
+                "Csv.{fn_name}() options must be Map, got {}",
+                other.type_name()
             ))
         }
     };
 
-    let encoded = base64::engine::general_purpose::STANDARD.encode(&bytes);
-    Ok(Value::string(encoded))
-}
-
-fn base64_decode(args: &[Value]) -> NativeResult {
-    if args.len() != 1 {
-        return Err(format!(
-            "Base64.decode() expects 1 argument, got {}",
-            args.len()
-        ));
+    if let Some(delimiter) = get_csv_byte_option(&map, "delimiter")? {
+        opts.delimiter = delimiter;
+    }
+    if let Some(quote) = get_csv_byte_option(&map, "quote")? {
+        opts.quote = quote;
+    }
+    opts.comment = get_csv_byte_option(&map, "comment")?;
+    if let Some(headers) = get_map_bool(&map, "headers") {
+        opts.headers = headers;
+    }
+    if let Some(trim) = get_map_bool(&map, "trim") {
+        opts.trim = trim;
+    }
+    if let Some(flexible) = get_map_bool(&map, "flexible") {
+        opts.flexible = flexible;
+    }
+    if let Some(append) = get_map_bool(&map, "append") {
+        opts.append = append;
+    }
+    if let Some(style) = get_map_string(&map, "quote_style") {
+        opts.quote_style = match style.as_str() {
+            "necessary" => csv::QuoteStyle::Necessary,
+            "always" => csv::QuoteStyle::Always,
+            "never" => csv::QuoteStyle::Never,
+            "non_numeric" => csv::QuoteStyle::NonNumeric,
+            other => {
+                return Err(format!(
+                "unknown quote_style '{other}' (expected necessary, always, never, or non_numeric)"
+            ))
+            }
+        };
+    }
+    if let Some(terminator) = get_map_string(&map, "line_terminator") {
+        opts.terminator = match terminator.as_str() {
+            "\n" => csv::Terminator::Any(b'\n'),
+            "\r\n" => csv::Terminator::CRLF,
+            other => {
+                return Err(format!(
+                    "line_terminator must be \"\\n\" or \"\\r\\n\", got {other:?}"
+                ))
+            }
+        };
+    }
+    let key = HashableValue::String(Rc::new("columns".to_string()));
+    if let Some(columns) = map.get(&key) {
+        let Value::List(list) = columns else {
+            return Err(format!(
+                "Csv option 'columns' must be List, got {}",
+                columns.type_name()
+            ));
+        };
+        let names = list
+            .borrow()
+            .iter()
+            .map(|v| get_string_arg(v, "column"))
+            .collect::<Result<Vec<_>, _>>()?;
+        opts.columns = Some(names);
     }
 
-    let encoded = get_string_arg(&args[0], "base64 string")?;
-    let bytes = base64::engine::general_purpose::STANDARD
-        .decode(&encoded)
-        .map_err(|e| format!("failed to decode base64: {}", e))?;
+    Ok(opts)
+}
 
-    // Try to decode as UTF-8 string, otherwise return as byte list
-    match String::from_utf8(bytes.clone()) {
-        Ok(s) => Ok(Value::string(s)),
-        Err(_) => {
-            let values: Vec<Value> = bytes.into_iter().map(|b| Value::Int(b as i64)).collect();
-            Ok(Value::list(values))
-        }
-    }
+fn csv_reader_builder(opts: &CsvOptions) -> csv::ReaderBuilder {
+    let mut builder = csv::ReaderBuilder::new();
+    builder
+        .delimiter(opts.delimiter)
+        .quote(opts.quote)
+        .has_headers(opts.headers)
+        .flexible(opts.flexible)
+        .comment(opts.comment)
+        .trim(if opts.trim {
+            csv::Trim::All
+        } else {
+            csv::Trim::None
+        });
+    builder
 }
 
-// ============================================================================
+fn csv_writer_builder(opts: &CsvOptions) -> csv::WriterBuilder {
+    let mut builder = csv::WriterBuilder::new();
+    builder
+        .delimiter(opts.delimiter)
+        .quote(opts.quote)
+        .quote_style(opts.quote_style)
+        .terminator(opts.terminator)
+        .flexible(true)
+        .has_headers(false);
+    builder
+}
+
+/// Convert a parsed record to a List of strings, or a Map keyed by header.
+/// Columns missing from a short row (only possible with `flexible`) are null.
+fn csv_record_to_value(record: &csv::StringRecord, headers: Option<&[String]>) -> Value {
+    match headers {
+        Some(headers) => {
+            let mut map = ValueMap::new();
+            for (i, name) in headers.iter().enumerate() {
+                let cell = record.get(i).map_or(Value::Null, Value::string);
+                map.insert(HashableValue::String(Rc::new(name.clone())), cell);
+            }
+            Value::map(map)
+        }
+        None => Value::list(record.iter().map(Value::string).collect()),
+    }
+}
+
+/// Render a cell for output: null becomes an empty field
+fn csv_cell_string(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Write a List or Map row, emitting the header row first when it is pending.
+/// Map rows use `columns` for ordering, inferring sorted keys from the first Map.
+fn write_csv_row<W: Write>(
+    writer: &mut csv::Writer<W>,
+    row: &Value,
+    columns: &mut Option<Vec<String>>,
+    header_pending: &mut bool,
+) -> Result<(), String> {
+    let write_err = |e: csv::Error| format!("failed to write CSV row: {}", e);
+
+    let cells: Vec<String> = match row {
+        Value::List(list) => list.borrow().iter().map(csv_cell_string).collect(),
+        Value::Map(map) => {
+            let map = map.borrow();
+            let names = columns.get_or_insert_with(|| {
+                let mut keys: Vec<String> = map
+                    .keys()
+                    .map(|k| csv_cell_string(&Value::from(k.clone())))
+                    .collect();
+                keys.sort();
+                keys
+            });
+            names
+                .iter()
+                .map(|name| {
+                    map.get(&HashableValue::String(Rc::new(name.clone())))
+                        .map(csv_cell_string)
+                        .unwrap_or_default()
+                })
+                .collect()
+        }
+        other => {
+            return Err(format!(
+                "CSV row must be List or Map, got {}",
+                other.type_name()
+            ))
+        }
+    };
+
+    if *header_pending {
+        if let Some(names) = columns.as_ref() {
+            writer.write_record(names).map_err(write_err)?;
+        }
+        *header_pending = false;
+    }
+    writer.write_record(&cells).map_err(write_err)
+}
+
+/// Csv.parse(text: String, options?: Map) -> List
+/// Parses CSV text into a List of Maps (with headers) or a List of Lists
+fn csv_parse(args: &[Value]) -> NativeResult {
+    if args.is_empty() || args.len() > 2 {
+        return Err(format!(
+            "Csv.parse() expects 1-2 arguments, got {}",
+            args.len()
+        ));
+    }
+    let text = get_string_arg(&args[0], "text")?;
+    let opts = get_csv_options(args.get(1), "parse")?;
+
+    let mut reader = csv_reader_builder(&opts).from_reader(text.as_bytes());
+    let headers = if opts.headers {
+        let record = reader
+            .headers()
+            .map_err(|e| format!("failed to parse CSV: {}", e))?;
+        Some(record.iter().map(str::to_string).collect::<Vec<_>>())
+    } else {
+        None
+    };
+
+    let mut rows = Vec::new();
+    for result in reader.records() {
+        let record = result.map_err(|e| format!("failed to parse CSV: {}", e))?;
+        rows.push(csv_record_to_value(&record, headers.as_deref()));
+    }
+    Ok(Value::list(rows))
+}
+
+/// Csv.parse_line(line: String, options?: Map) -> List<String>
+/// Parses a single CSV record, ignoring the `headers` option
+fn csv_parse_line(args: &[Value]) -> NativeResult {
+    if args.is_empty() || args.len() > 2 {
+        return Err(format!(
+            "Csv.parse_line() expects 1-2 arguments, got {}",
+            args.len()
+        ));
+    }
+    let line = get_string_arg(&args[0], "line")?;
+    let mut opts = get_csv_options(args.get(1), "parse_line")?;
+    opts.headers = false;
+
+    let mut reader = csv_reader_builder(&opts).from_reader(line.as_bytes());
+    match reader.records().next() {
+        Some(result) => {
+            let record = result.map_err(|e| format!("failed to parse CSV: {}", e))?;
+            Ok(csv_record_to_value(&record, None))
+        }
+        None => Ok(Value::list(Vec::new())),
+    }
+}
+
+/// Csv.stringify(rows: List, options?: Map) -> String
+/// Formats a List of Lists or Maps as CSV text
+fn csv_stringify(args: &[Value]) -> NativeResult {
+    if args.is_empty() || args.len() > 2 {
+        return Err(format!(
+            "Csv.stringify() expects 1-2 arguments, got {}",
+            args.len()
+        ));
+    }
+    let rows = match &args[0] {
+        Value::List(list) => list.borrow().clone(),
+        other => return Err(format!("rows must be List, got {}", other.type_name())),
+    };
+    let mut opts = get_csv_options(args.get(1), "stringify")?;
+
+    let mut writer = csv_writer_builder(&opts).from_writer(Vec::new());
+    let mut header_pending = opts.headers;
+    for row in &rows {
+        write_csv_row(&mut writer, row, &mut opts.columns, &mut header_pending)?;
+    }
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| format!("failed to write CSV: {}", e))?;
+    String::from_utf8(bytes)
+        .map(Value::string)
+        .map_err(|e| format!("CSV output is not valid UTF-8: {}", e))
+}
+
+/// Csv.open(path: String, options?: Map) -> CsvReader
+/// Opens a file for streaming, row-at-a-time reading
+fn csv_open(args: &[Value]) -> NativeResult {
+    if args.is_empty() || args.len() > 2 {
+        return Err(format!(
+            "Csv.open() expects 1-2 arguments, got {}",
+            args.len()
+        ));
+    }
+    let path = get_string_arg(&args[0], "path")?;
+    let opts = get_csv_options(args.get(1), "open")?;
+
+    let file = File::open(&path).map_err(|e| format!("failed to open '{}': {}", path, e))?;
+    let mut reader = csv_reader_builder(&opts).from_reader(file);
+    let headers = if opts.headers {
+        let record = reader
+            .headers()
+            .map_err(|e| format!("failed to read CSV headers from '{}': {}", path, e))?;
+        Some(record.iter().map(str::to_string).collect())
+    } else {
+        None
+    };
+
+    Ok(Value::CsvReader(Arc::new(CsvReaderWrapper {
+        path,
+        headers,
+        reader: Mutex::new(Some(reader)),
+    })))
+}
+
+/// Csv.create(path: String, options?: Map) -> CsvWriter
+/// Opens a file for streaming, row-at-a-time writing. With `append: true`
+/// rows are added to an existing file and the header row is only written
+/// if the file is empty.
+fn csv_create(args: &[Value]) -> NativeResult {
+    if args.is_empty() || args.len() > 2 {
+        return Err(format!(
+            "Csv.create() expects 1-2 arguments, got {}",
+            args.len()
+        ));
+    }
+    let path = get_string_arg(&args[0], "path")?;
+    let opts = get_csv_options(args.get(1), "create")?;
+
+    let file = if opts.append {
+        fs::OpenOptions::new().create(true).append(true).open(&path)
+    } else {
+        File::create(&path)
+    }
+    .map_err(|e| format!("failed to create '{}': {}", path, e))?;
+    let is_empty = file.metadata().map(|m| m.len() == 0).unwrap_or(true);
+
+    let writer = csv_writer_builder(&opts).from_writer(file);
+    Ok(Value::CsvWriter(Arc::new(CsvWriterWrapper {
+        path,
+        headers: Mutex::new(opts.columns),
+        header_pending: Mutex::new(opts.headers && is_empty),
+        writer: Mutex::new(Some(writer)),
+    })))
+}
+
+/// Dispatch a method call on a CsvReader
+pub fn csv_reader_method(
+    reader: &Arc<CsvReaderWrapper>,
+    method: &str,
+    args: &[Value],
+) -> NativeResult {
+    let expects_none = |name: &str| {
+        if args.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "CsvReader.{name}() expects 0 arguments, got {}",
+                args.len()
+            ))
+        }
+    };
+
+    match method {
+        "path" => {
+            expects_none("path")?;
+            Ok(Value::string(&reader.path))
+        }
+        "headers" => {
+            expects_none("headers")?;
+            Ok(reader.headers.as_ref().map_or(Value::Null, |headers| {
+                Value::list(headers.iter().map(Value::string).collect())
+            }))
+        }
+        "next" => {
+            expects_none("next")?;
+            csv_reader_next(reader)
+        }
+        "read_all" => {
+            expects_none("read_all")?;
+            let mut rows = Vec::new();
+            loop {
+                match csv_reader_next(reader)? {
+                    Value::Null => break,
+                    row => rows.push(row),
+                }
+            }
+            Ok(Value::list(rows))
+        }
+        "line" => {
+            expects_none("line")?;
+            let guard = reader
+                .reader
+                .lock()
+                .map_err(|_| "CsvReader lock poisoned".to_string())?;
+            let inner = guard.as_ref().ok_or("CsvReader is closed")?;
+            Ok(Value::Int(inner.position().line() as i64))
+        }
+        "close" => {
+            expects_none("close")?;
+            reader
+                .reader
+                .lock()
+                .map_err(|_| "CsvReader lock poisoned".to_string())?
+                .take();
+            Ok(Value::Null)
+        }
+        _ => Err(format!("CsvReader has no method '{method}'")),
+    }
+}
+
+/// reader.next() - Read the next row, or null at end of file
+fn csv_reader_next(reader: &Arc<CsvReaderWrapper>) -> NativeResult {
+    let mut guard = reader
+        .reader
+        .lock()
+        .map_err(|_| "CsvReader lock poisoned".to_string())?;
+    let inner = guard.as_mut().ok_or("CsvReader is closed")?;
+
+    let mut record = csv::StringRecord::new();
+    let has_row = inner
+        .read_record(&mut record)
+        .map_err(|e| format!("failed to parse CSV '{}': {}", reader.path, e))?;
+    if has_row {
+        Ok(csv_record_to_value(&record, reader.headers.as_deref()))
+    } else {
+        Ok(Value::Null)
+    }
+}
+
+/// Dispatch a method call on a CsvWriter
+pub fn csv_writer_method(
+    writer: &Arc<CsvWriterWrapper>,
+    method: &str,
+    args: &[Value],
+) -> NativeResult {
+    let expect_args = |name: &str, count: usize| {
+        if args.len() == count {
+            Ok(())
+        } else {
+            Err(format!(
+                "CsvWriter.{name}() expects {count} argument{}, got {}",
+                if count == 1 { "" } else { "s" },
+                args.len()
+            ))
+        }
+    };
+
+    match method {
+        "path" => {
+            expect_args("path", 0)?;
+            Ok(Value::string(&writer.path))
+        }
+        "write" => {
+            expect_args("write", 1)?;
+            csv_writer_write(writer, std::slice::from_ref(&args[0]))?;
+            Ok(Value::Null)
+        }
+        "write_all" => {
+            expect_args("write_all", 1)?;
+            let rows = match &args[0] {
+                Value::List(list) => list.borrow().clone(),
+                other => return Err(format!("rows must be List, got {}", other.type_name())),
+            };
+            csv_writer_write(writer, &rows)?;
+            Ok(Value::Int(rows.len() as i64))
+        }
+        "flush" => {
+            expect_args("flush", 0)?;
+            let mut guard = writer
+                .writer
+                .lock()
+                .map_err(|_| "CsvWriter lock poisoned".to_string())?;
+            let inner = guard.as_mut().ok_or("CsvWriter is closed")?;
+            inner
+                .flush()
+                .map_err(|e| format!("failed to flush '{}': {}", writer.path, e))?;
+            Ok(Value::Null)
+        }
+        "close" => {
+            expect_args("close", 0)?;
+            let mut guard = writer
+                .writer
+                .lock()
+                .map_err(|_| "CsvWriter lock poisoned".to_string())?;
+            if let Some(mut inner) = guard.take() {
+                inner
+                    .flush()
+                    .map_err(|e| format!("failed to flush '{}': {}", writer.path, e))?;
+            }
+            Ok(Value::Null)
+        }
+        _ => Err(format!("CsvWriter has no method '{method}'")),
+    }
+}
+
+fn csv_writer_write(writer: &Arc<CsvWriterWrapper>, rows: &[Value]) -> Result<(), String> {
+    let mut guard = writer
+        .writer
+        .lock()
+        .map_err(|_| "CsvWriter lock poisoned".to_string())?;
+    let inner = guard.as_mut().ok_or("CsvWriter is closed")?;
+    let mut columns = writer
+        .headers
+        .lock()
+        .map_err(|_| "CsvWriter lock poisoned".to_string())?;
+    let mut header_pending = writer
+        .header_pending
+        .lock()
+        .map_err(|_| "CsvWriter lock poisoned".to_string())?;
+
+    for row in rows {
+        write_csv_row(inner, row, &mut columns, &mut header_pending)?;
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Base64 Module
+// ============================================================================
+
+pub fn base64_method(method: &str, args: &[Value]) -> NativeResult {
+    match method {
+        "encode" => base64_encode(args),
+        "decode" => base64_decode(args),
+        _ => Err(format!("Base64 has no method '{method}'")),
+    }
+}
+
+fn base64_encode(args: &[Value]) -> NativeResult {
+    if args.len() != 1 {
+        return Err(format!(
+            "Base64.encode() expects 1 argument, got {}",
+            args.len()
+        ));
+    }
+
+    let bytes = match &args[0] {
+        Value::String(s) => s.as_bytes().to_vec(),
+        Value::List(_) => get_bytes_arg(&args[0])?,
+        _ => {
+            return Err(format!(
+                "Base64.encode() expects String or List<Int>, got {}",
+                args[0].type_name()
+            ))
+        }
+    };
+
+    let encoded = base64::engine::general_purpose::STANDARD.encode(&bytes);
+    Ok(Value::string(encoded))
+}
+
+fn base64_decode(args: &[Value]) -> NativeResult {
+    if args.len() != 1 {
+        return Err(format!(
+            "Base64.decode() expects 1 argument, got {}",
+            args.len()
+        ));
+    }
+
+    let encoded = get_string_arg(&args[0], "base64 string")?;
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(&encoded)
+        .map_err(|e| format!("failed to decode base64: {}", e))?;
+
+    // Try to decode as UTF-8 string, otherwise return as byte list
+    match String::from_utf8(bytes.clone()) {
+        Ok(s) => Ok(Value::string(s)),
+        Err(_) => {
+            let values: Vec<Value> = bytes.into_iter().map(|b| Value::Int(b as i64)).collect();
+            Ok(Value::list(values))
+        }
+    }
+}
+
+// ============================================================================
 // Url Module
 // ============================================================================
 
@@ -1480,7 +2673,7 @@ pub fn datetime_method(method: &str, args: &[Value]) -> NativeResult {
 
 /// Create a datetime map from chrono DateTime
 fn chrono_to_value<Tz: TimeZone>(dt: &ChronoDateTime<Tz>, tz_name: &str) -> Value {
-    let mut map = HashMap::new();
+    let mut map = ValueMap::new();
     map.insert(
         HashableValue::String(Rc::new("year".to_string())),
         Value::Int(i64::from(dt.year())),
@@ -1517,7 +2710,7 @@ fn chrono_to_value<Tz: TimeZone>(dt: &ChronoDateTime<Tz>, tz_name: &str) -> Valu
         HashableValue::String(Rc::new("timezone".to_string())),
         Value::string(tz_name),
     );
-    Value::Map(Rc::new(RefCell::new(map)))
+    Value::map(map)
 }
 
 /// Extract timestamp from a datetime map
@@ -1557,6 +2750,13 @@ fn datetime_now(args: &[Value]) -> NativeResult {
             args.len()
         ));
     }
+    if let Some(millis) = determinism::now_millis() {
+        let now = Utc
+            .timestamp_millis_opt(millis)
+            .single()
+            .ok_or_else(|| format!("invalid timestamp: {millis}"))?;
+        return Ok(chrono_to_value(&now, "UTC"));
+    }
     let now = Local::now();
     Ok(chrono_to_value(&now, "Local"))
 }
@@ -1798,6 +2998,14 @@ fn datetime_to_local(args: &[Value]) -> NativeResult {
         ));
     }
     let ts = get_datetime_timestamp(&args[0])?;
+    // The host's time zone would make results differ between machines
+    if determinism::is_enabled() {
+        let dt = Utc
+            .timestamp_millis_opt(ts)
+            .single()
+            .ok_or_else(|| format!("invalid timestamp: {}", ts))?;
+        return Ok(chrono_to_value(&dt, "UTC"));
+    }
     let dt = Local
         .timestamp_millis_opt(ts)
         .single()
@@ -1852,12 +3060,12 @@ pub fn duration_method(method: &str, args: &[Value]) -> NativeResult {
 
 /// Create a duration value (map with millis field)
 fn duration_to_value(millis: i64) -> Value {
-    let mut map = HashMap::new();
+    let mut map = ValueMap::new();
     map.insert(
         HashableValue::String(Rc::new("millis".to_string())),
         Value::Int(millis),
     );
-    Value::Map(Rc::new(RefCell::new(map)))
+    Value::map(map)
 }
 
 /// Extract milliseconds from a duration map
@@ -2026,7 +3234,11 @@ pub fn time_method(method: &str, args: &[Value]) -> NativeResult {
 
 /// Global start time for elapsed time calculations
 /// We use Instant to measure elapsed time, but store as millis since program start
+/// (or the virtual clock, in deterministic execution)
 fn get_instant_millis() -> i64 {
+    if let Some(millis) = determinism::now_millis() {
+        return millis - determinism::EPOCH_MILLIS;
+    }
     use std::sync::OnceLock;
     static START: OnceLock<Instant> = OnceLock::new();
     let start = START.get_or_init(Instant::now);
@@ -2044,7 +3256,9 @@ fn time_sleep(args: &[Value]) -> NativeResult {
     if millis < 0 {
         return Err("sleep duration cannot be negative".to_string());
     }
-    std::thread::sleep(StdDuration::from_millis(millis as u64));
+    if !determinism::advance(millis) {
+        std::thread::sleep(StdDuration::from_millis(millis as u64));
+    }
     Ok(Value::Null)
 }
 
@@ -2059,7 +3273,9 @@ fn time_sleep_ms(args: &[Value]) -> NativeResult {
     if ms < 0 {
         return Err("sleep duration cannot be negative".to_string());
     }
-    std::thread::sleep(StdDuration::from_millis(ms as u64));
+    if !determinism::advance(ms) {
+        std::thread::sleep(StdDuration::from_millis(ms as u64));
+    }
     Ok(Value::Null)
 }
 
@@ -2071,12 +3287,12 @@ fn time_start(args: &[Value]) -> NativeResult {
         ));
     }
     // Return a timer value with the current instant millis
-    let mut map = HashMap::new();
+    let mut map = ValueMap::new();
     map.insert(
         HashableValue::String(Rc::new("_start_millis".to_string())),
         Value::Int(get_instant_millis()),
     );
-    Ok(Value::Map(Rc::new(RefCell::new(map))))
+    Ok(Value::map(map))
 }
 
 fn time_elapsed(args: &[Value]) -> NativeResult {
@@ -2199,7 +3415,7 @@ fn get_regex_arg(args: &[Value]) -> Result<(Rc<Regex>, usize), String> {
 
 /// Create a match result map from a regex match
 fn match_to_value(m: &regex::Match, text: &str) -> Value {
-    let mut map = HashMap::new();
+    let mut map = ValueMap::new();
 
     // The matched text
     map.insert(
@@ -2222,7 +3438,7 @@ fn match_to_value(m: &regex::Match, text: &str) -> Value {
     // Also include the full input text for context
     let _ = text; // Silence unused warning - kept for potential future use
 
-    Value::Map(Rc::new(RefCell::new(map)))
+    Value::map(map)
 }
 
 /// Regex.is_match(regex, text) or Regex.is_match(pattern, text) or Regex.is_match(pattern, options, text)
@@ -2423,6 +3639,19 @@ fn get_string_arg(value: &Value, name: &str) -> Result<String, String> {
     }
 }
 
+/// Accept either a String (UTF-8 bytes) or a List<Int> of raw bytes
+fn get_string_or_bytes_arg(value: &Value, name: &str) -> Result<Vec<u8>, String> {
+    match value {
+        Value::String(s) => Ok(s.as_bytes().to_vec()),
+        Value::List(_) => get_bytes_arg(value),
+        _ => Err(format!(
+            "{} must be String or List<Int>, got {}",
+            name,
+            value.type_name()
+        )),
+    }
+}
+
 fn get_bytes_arg(value: &Value) -> Result<Vec<u8>, String> {
     match value {
         Value::List(list) => list
@@ -2439,32 +3668,204 @@ fn get_bytes_arg(value: &Value) -> Result<Vec<u8>, String> {
 }
 
 // ============================================================================
-// Gzip Module
+// Template Module
 // ============================================================================
 
-/// Gzip module entry point - compression and decompression
-pub fn gzip_method(method: &str, args: &[Value]) -> NativeResult {
+/// Name under which inline template sources are registered
+const INLINE_TEMPLATE_NAME: &str = "<template>";
+
+/// Template module entry point - Jinja-style template rendering
+pub fn template_method(method: &str, args: &[Value]) -> NativeResult {
     match method {
-        "compress" => gzip_compress(args),
-        "decompress" => gzip_decompress(args),
-        "compress_text" => gzip_compress_text(args),
-        "decompress_text" => gzip_decompress_text(args),
-        _ => Err(format!("Gzip has no method '{method}'")),
+        "render" => template_render(args),
+        "render_file" => template_render_file(args),
+        "validate" => template_validate(args),
+        _ => Err(format!("Template has no method '{method}'")),
+    }
+}
+
+/// Build a template environment from the optional options Map.
+/// Options: `escape` ("html", "json", "none"; by default inferred from the
+/// template's file extension), `partials` (Map of name -> source),
+/// `strict` (error on undefined variables), `trim_blocks` (strip the newline
+/// and leading whitespace around block tags).
+fn template_environment(
+    options: Option<&Value>,
+    fn_name: &str,
+) -> Result<minijinja::Environment<'static>, String> {
+    use minijinja::{AutoEscape, UndefinedBehavior};
+
+    let mut env = minijinja::Environment::new();
+    env.set_keep_trailing_newline(true);
+
+    let map = match options {
+        None | Some(Value::Null) => return Ok(env),
+        Some(Value::Map(map)) => map.borrow(),
+        Some(other) => {
+            return Err(format!(
+                "Template.{fn_name}() options must be Map, got {}",
+                other.type_name()
+            ))
+        }
+    };
+
+    if let Some(escape) = get_map_string(&map, "escape") {
+        let mode = match escape.as_str() {
+            "html" => AutoEscape::Html,
+            "json" => AutoEscape::Json,
+            "none" => AutoEscape::None,
+            other => {
+                return Err(format!(
+                    "unknown escape mode '{other}' (expected html, json, or none)"
+                ))
+            }
+        };
+        env.set_auto_escape_callback(move |_| mode.clone());
+    }
+    if get_map_bool(&map, "strict") == Some(true) {
+        env.set_undefined_behavior(UndefinedBehavior::Strict);
+    }
+    if get_map_bool(&map, "trim_blocks") == Some(true) {
+        env.set_trim_blocks(true);
+        env.set_lstrip_blocks(true);
+    }
+
+    let key = HashableValue::String(Rc::new("partials".to_string()));
+    if let Some(partials) = map.get(&key) {
+        let Value::Map(partials) = partials else {
+            return Err(format!(
+                "Template option 'partials' must be Map, got {}",
+                partials.type_name()
+            ));
+        };
+        for (name, source) in partials.borrow().iter() {
+            let HashableValue::String(name) = name else {
+                return Err("Template partial names must be strings".to_string());
+            };
+            let source = get_string_arg(source, "partial")?;
+            env.add_template_owned(name.to_string(), source)
+                .map_err(|e| format!("template error: {}", e))?;
+        }
     }
+
+    Ok(env)
 }
 
-/// Gzip.compress(bytes: List<Int>) -> List<Int>
-/// Compresses bytes using gzip
-fn gzip_compress(args: &[Value]) -> NativeResult {
-    if args.len() != 1 {
+/// Convert the render data (Map, Struct, or null) into a template context
+fn template_context(data: &Value, fn_name: &str) -> Result<minijinja::Value, String> {
+    match data {
+        Value::Null => Ok(minijinja::context! {}),
+        Value::Map(_) | Value::Struct(_) => {
+            Ok(minijinja::Value::from_serialize(value_to_json(data)?))
+        }
+        other => Err(format!(
+            "Template.{fn_name}() data must be Map or Struct, got {}",
+            other.type_name()
+        )),
+    }
+}
+
+/// Template.render(source: String, data: Map|Struct, options?: Map) -> String
+/// Renders an inline template. Supports `{{ expr }}`, `{% for %}`, `{% if %}`,
+/// `{% include "partial" %}`, macros, and filters.
+fn template_render(args: &[Value]) -> NativeResult {
+    if args.len() < 2 || args.len() > 3 {
         return Err(format!(
-            "Gzip.compress() expects 1 argument, got {}",
+            "Template.render() expects 2-3 arguments, got {}",
             args.len()
         ));
     }
-    let bytes = get_bytes_arg(&args[0])?;
+    let source = get_string_arg(&args[0], "source")?;
+    let ctx = template_context(&args[1], "render")?;
+    let mut env = template_environment(args.get(2), "render")?;
 
-    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    env.add_template_owned(INLINE_TEMPLATE_NAME, source)
+        .map_err(|e| format!("template error: {}", e))?;
+    env.get_template(INLINE_TEMPLATE_NAME)
+        .and_then(|tmpl| tmpl.render(ctx))
+        .map(Value::string)
+        .map_err(|e| format!("template error: {}", e))
+}
+
+/// Template.render_file(path: String, data: Map|Struct, options?: Map) -> String
+/// Renders a template file. Includes are resolved relative to the file's
+/// directory, and escaping defaults to HTML for .html/.htm/.xml files.
+fn template_render_file(args: &[Value]) -> NativeResult {
+    if args.len() < 2 || args.len() > 3 {
+        return Err(format!(
+            "Template.render_file() expects 2-3 arguments, got {}",
+            args.len()
+        ));
+    }
+    let path = get_string_arg(&args[0], "path")?;
+    let ctx = template_context(&args[1], "render_file")?;
+    let mut env = template_environment(args.get(2), "render_file")?;
+
+    let file_path = Path::new(&path);
+    if !file_path.is_file() {
+        return Err(format!("template file not found: '{}'", path));
+    }
+    let dir = file_path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let name = file_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| format!("invalid template path '{}'", path))?;
+    env.set_loader(minijinja::path_loader(dir));
+
+    env.get_template(&name)
+        .and_then(|tmpl| tmpl.render(ctx))
+        .map(Value::string)
+        .map_err(|e| format!("template error: {}", e))
+}
+
+/// Template.validate(source: String) -> nil
+/// Checks template syntax without rendering, throwing on the first error
+fn template_validate(args: &[Value]) -> NativeResult {
+    if args.len() != 1 {
+        return Err(format!(
+            "Template.validate() expects 1 argument, got {}",
+            args.len()
+        ));
+    }
+    let source = get_string_arg(&args[0], "source")?;
+    let mut env = minijinja::Environment::new();
+    env.add_template_owned(INLINE_TEMPLATE_NAME, source)
+        .map_err(|e| format!("template error: {}", e))?;
+    Ok(Value::Null)
+}
+
+// ============================================================================
+// Gzip Module
+// ============================================================================
+
+/// Gzip module entry point - compression and decompression
+pub fn gzip_method(method: &str, args: &[Value]) -> NativeResult {
+    match method {
+        "compress" => gzip_compress(args),
+        "decompress" => gzip_decompress(args),
+        "compress_text" => gzip_compress_text(args),
+        "decompress_text" => gzip_decompress_text(args),
+        "compress_file" => gzip_compress_file(args),
+        "decompress_file" => gzip_decompress_file(args),
+        _ => Err(format!("Gzip has no method '{method}'")),
+    }
+}
+
+/// Gzip.compress(bytes: List<Int>) -> List<Int>
+/// Compresses bytes using gzip
+fn gzip_compress(args: &[Value]) -> NativeResult {
+    if args.len() != 1 {
+        return Err(format!(
+            "Gzip.compress() expects 1 argument, got {}",
+            args.len()
+        ));
+    }
+    let bytes = get_bytes_arg(&args[0])?;
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
     encoder
         .write_all(&bytes)
         .map_err(|e| format!("gzip compression failed: {}", e))?;
@@ -2539,6 +3940,55 @@ fn gzip_decompress_text(args: &[Value]) -> NativeResult {
         .map_err(|e| format!("decompressed data is not valid UTF-8: {}", e))
 }
 
+/// Gzip.compress_file(input_path: String, output_path: String) -> nil
+/// Streams a file through the gzip encoder without loading it into memory
+fn gzip_compress_file(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err(format!(
+            "Gzip.compress_file() expects 2 arguments, got {}",
+            args.len()
+        ));
+    }
+    let input_path = get_string_arg(&args[0], "input_path")?;
+    let output_path = get_string_arg(&args[1], "output_path")?;
+
+    let mut input =
+        File::open(&input_path).map_err(|e| format!("failed to open '{}': {}", input_path, e))?;
+    let output = File::create(&output_path)
+        .map_err(|e| format!("failed to create '{}': {}", output_path, e))?;
+    let mut encoder = GzEncoder::new(output, Compression::default());
+    std::io::copy(&mut input, &mut encoder)
+        .map_err(|e| format!("gzip compression failed: {}", e))?;
+    encoder
+        .finish()
+        .map_err(|e| format!("gzip compression failed: {}", e))?;
+
+    Ok(Value::Null)
+}
+
+/// Gzip.decompress_file(input_path: String, output_path: String) -> nil
+/// Streams a gzip file through the decoder without loading it into memory
+fn gzip_decompress_file(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err(format!(
+            "Gzip.decompress_file() expects 2 arguments, got {}",
+            args.len()
+        ));
+    }
+    let input_path = get_string_arg(&args[0], "input_path")?;
+    let output_path = get_string_arg(&args[1], "output_path")?;
+
+    let input =
+        File::open(&input_path).map_err(|e| format!("failed to open '{}': {}", input_path, e))?;
+    let mut output = File::create(&output_path)
+        .map_err(|e| format!("failed to create '{}': {}", output_path, e))?;
+    let mut decoder = GzDecoder::new(input);
+    std::io::copy(&mut decoder, &mut output)
+        .map_err(|e| format!("gzip decompression failed: {}", e))?;
+
+    Ok(Value::Null)
+}
+
 /// Helper to convert bytes to Value::List
 fn bytes_to_list(bytes: &[u8]) -> Value {
     let values: Vec<Value> = bytes.iter().map(|b| Value::Int(i64::from(*b))).collect();
@@ -2581,7 +4031,7 @@ fn zip_list(args: &[Value]) -> NativeResult {
             .by_index(i)
             .map_err(|e| format!("failed to read entry {}: {}", i, e))?;
 
-        let mut map = HashMap::new();
+        let mut map = ValueMap::new();
         map.insert(
             HashableValue::String(Rc::new("name".to_string())),
             Value::string(entry.name()),
@@ -2599,7 +4049,7 @@ fn zip_list(args: &[Value]) -> NativeResult {
             Value::Bool(entry.is_dir()),
         );
 
-        entries.push(Value::Map(Rc::new(RefCell::new(map))));
+        entries.push(Value::map(map));
     }
 
     Ok(Value::list(entries))
@@ -2631,7 +4081,16 @@ fn zip_extract(args: &[Value]) -> NativeResult {
             .by_index(i)
             .map_err(|e| format!("failed to read entry {}: {}", i, e))?;
 
-        let entry_path = output_path.join(entry.name());
+        // Reject entries like "../evil" or "/etc/passwd" that would escape output_dir
+        let entry_path = match entry.enclosed_name() {
+            Some(name) => output_path.join(name),
+            None => {
+                return Err(format!(
+                    "refusing to extract '{}': path escapes the output directory",
+                    entry.name()
+                ))
+            }
+        };
 
         if entry.is_dir() {
             fs::create_dir_all(&entry_path)
@@ -2801,185 +4260,687 @@ fn zip_read_bytes(args: &[Value]) -> NativeResult {
 }
 
 // ============================================================================
-// Hash Module
+// Zstd Module
 // ============================================================================
 
-/// Hash module entry point
-pub fn hash_method(method: &str, args: &[Value]) -> NativeResult {
+/// Zstd module entry point - Zstandard compression and decompression
+pub fn zstd_method(method: &str, args: &[Value]) -> NativeResult {
     match method {
-        "sha256" => hash_sha256(args),
-        "sha256_bytes" => hash_sha256_bytes(args),
-        "sha512" => hash_sha512(args),
-        "sha512_bytes" => hash_sha512_bytes(args),
-        "md5" => hash_md5(args),
-        "md5_bytes" => hash_md5_bytes(args),
-        "hmac_sha256" => hash_hmac_sha256(args),
-        "hmac_sha512" => hash_hmac_sha512(args),
-        _ => Err(format!("Hash has no method '{method}'")),
+        "compress" => zstd_compress(args),
+        "decompress" => zstd_decompress(args),
+        "compress_text" => zstd_compress_text(args),
+        "decompress_text" => zstd_decompress_text(args),
+        "compress_file" => zstd_compress_file(args),
+        "decompress_file" => zstd_decompress_file(args),
+        _ => Err(format!("Zstd has no method '{method}'")),
+    }
+}
+
+/// Extract an optional zstd compression level (1-22, default 3)
+fn get_zstd_level(args: &[Value], index: usize, fn_name: &str) -> Result<i32, String> {
+    match args.get(index) {
+        None => Ok(zstd::DEFAULT_COMPRESSION_LEVEL),
+        Some(value) => {
+            let level = get_int_arg(value, "level")?;
+            if (1..=22).contains(&level) {
+                Ok(level as i32)
+            } else {
+                Err(format!(
+                    "Zstd.{fn_name}(): level must be between 1 and 22, got {level}"
+                ))
+            }
+        }
     }
 }
 
-/// Hash.sha256(data: String) -> String
-/// Returns hex-encoded SHA-256 hash
-fn hash_sha256(args: &[Value]) -> NativeResult {
-    if args.len() != 1 {
+/// Zstd.compress(bytes: List<Int>, level?: Int) -> List<Int>
+/// Compresses bytes using Zstandard
+fn zstd_compress(args: &[Value]) -> NativeResult {
+    if args.is_empty() || args.len() > 2 {
         return Err(format!(
-            "Hash.sha256() expects 1 argument, got {}",
+            "Zstd.compress() expects 1-2 arguments, got {}",
             args.len()
         ));
     }
-    let data = get_string_arg(&args[0], "data")?;
-    let mut hasher = Sha256::new();
-    hasher.update(data.as_bytes());
-    let result = hasher.finalize();
-    Ok(Value::string(hex::encode(result)))
+    let bytes = get_bytes_arg(&args[0])?;
+    let level = get_zstd_level(args, 1, "compress")?;
+
+    let compressed = zstd::encode_all(&bytes[..], level)
+        .map_err(|e| format!("zstd compression failed: {}", e))?;
+    Ok(bytes_to_list(&compressed))
 }
 
-/// Hash.sha256_bytes(data: List<Int>) -> String
-/// Hash raw bytes and return hex-encoded result
-fn hash_sha256_bytes(args: &[Value]) -> NativeResult {
+/// Zstd.decompress(bytes: List<Int>) -> List<Int>
+/// Decompresses Zstandard-encoded bytes
+fn zstd_decompress(args: &[Value]) -> NativeResult {
     if args.len() != 1 {
         return Err(format!(
-            "Hash.sha256_bytes() expects 1 argument, got {}",
+            "Zstd.decompress() expects 1 argument, got {}",
             args.len()
         ));
     }
     let bytes = get_bytes_arg(&args[0])?;
-    let mut hasher = Sha256::new();
-    hasher.update(&bytes);
-    let result = hasher.finalize();
-    Ok(Value::string(hex::encode(result)))
-}
 
-/// Hash.sha512(data: String) -> String
-fn hash_sha512(args: &[Value]) -> NativeResult {
-    if args.len() != 1 {
-        return Err(format!(
-            "Hash.sha512() expects 1 argument, got {}",
-            args.len()
-        ));
-    }
-    let data = get_string_arg(&args[0], "data")?;
-    let mut hasher = Sha512::new();
-    hasher.update(data.as_bytes());
-    let result = hasher.finalize();
-    Ok(Value::string(hex::encode(result)))
+    let decompressed =
+        zstd::decode_all(&bytes[..]).map_err(|e| format!("zstd decompression failed: {}", e))?;
+    Ok(bytes_to_list(&decompressed))
 }
 
-/// Hash.sha512_bytes(data: List<Int>) -> String
-fn hash_sha512_bytes(args: &[Value]) -> NativeResult {
-    if args.len() != 1 {
+/// Zstd.compress_text(text: String, level?: Int) -> List<Int>
+/// Compresses a string using Zstandard
+fn zstd_compress_text(args: &[Value]) -> NativeResult {
+    if args.is_empty() || args.len() > 2 {
         return Err(format!(
-            "Hash.sha512_bytes() expects 1 argument, got {}",
+            "Zstd.compress_text() expects 1-2 arguments, got {}",
             args.len()
         ));
     }
-    let bytes = get_bytes_arg(&args[0])?;
-    let mut hasher = Sha512::new();
-    hasher.update(&bytes);
-    let result = hasher.finalize();
-    Ok(Value::string(hex::encode(result)))
-}
+    let text = get_string_arg(&args[0], "text")?;
+    let level = get_zstd_level(args, 1, "compress_text")?;
 
-/// Hash.md5(data: String) -> String
-/// Note: MD5 is for compatibility, not security
-fn hash_md5(args: &[Value]) -> NativeResult {
-    if args.len() != 1 {
-        return Err(format!("Hash.md5() expects 1 argument, got {}", args.len()));
-    }
-    let data = get_string_arg(&args[0], "data")?;
-    let mut hasher = Md5::new();
-    hasher.update(data.as_bytes());
-    let result = hasher.finalize();
-    Ok(Value::string(hex::encode(result)))
+    let compressed = zstd::encode_all(text.as_bytes(), level)
+        .map_err(|e| format!("zstd compression failed: {}", e))?;
+    Ok(bytes_to_list(&compressed))
 }
 
-/// Hash.md5_bytes(data: List<Int>) -> String
-fn hash_md5_bytes(args: &[Value]) -> NativeResult {
+/// Zstd.decompress_text(bytes: List<Int>) -> String
+/// Decompresses Zstandard-encoded bytes to a string
+fn zstd_decompress_text(args: &[Value]) -> NativeResult {
     if args.len() != 1 {
         return Err(format!(
-            "Hash.md5_bytes() expects 1 argument, got {}",
+            "Zstd.decompress_text() expects 1 argument, got {}",
             args.len()
         ));
     }
     let bytes = get_bytes_arg(&args[0])?;
-    let mut hasher = Md5::new();
-    hasher.update(&bytes);
-    let result = hasher.finalize();
-    Ok(Value::string(hex::encode(result)))
+
+    let decompressed =
+        zstd::decode_all(&bytes[..]).map_err(|e| format!("zstd decompression failed: {}", e))?;
+    String::from_utf8(decompressed)
+        .map(Value::string)
+        .map_err(|e| format!("decompressed data is not valid UTF-8: {}", e))
 }
 
-/// Hash.hmac_sha256(key: String, message: String) -> String
-fn hash_hmac_sha256(args: &[Value]) -> NativeResult {
-    if args.len() != 2 {
+/// Zstd.compress_file(input_path: String, output_path: String, level?: Int) -> nil
+/// Streams a file through the zstd encoder without loading it into memory
+fn zstd_compress_file(args: &[Value]) -> NativeResult {
+    if args.len() != 2 && args.len() != 3 {
         return Err(format!(
-            "Hash.hmac_sha256() expects 2 arguments, got {}",
+            "Zstd.compress_file() expects 2-3 arguments, got {}",
             args.len()
         ));
     }
-    let key = get_string_arg(&args[0], "key")?;
-    let message = get_string_arg(&args[1], "message")?;
+    let input_path = get_string_arg(&args[0], "input_path")?;
+    let output_path = get_string_arg(&args[1], "output_path")?;
+    let level = get_zstd_level(args, 2, "compress_file")?;
 
-    type HmacSha256 = Hmac<Sha256>;
-    let mut mac = <HmacSha256 as Mac>::new_from_slice(key.as_bytes())
-        .map_err(|e| format!("HMAC key error: {e}"))?;
-    Mac::update(&mut mac, message.as_bytes());
-    let result = mac.finalize();
-    Ok(Value::string(hex::encode(result.into_bytes())))
+    let input =
+        File::open(&input_path).map_err(|e| format!("failed to open '{}': {}", input_path, e))?;
+    let output = File::create(&output_path)
+        .map_err(|e| format!("failed to create '{}': {}", output_path, e))?;
+    zstd::stream::copy_encode(input, output, level)
+        .map_err(|e| format!("zstd compression failed: {}", e))?;
+
+    Ok(Value::Null)
 }
 
-/// Hash.hmac_sha512(key: String, message: String) -> String
-fn hash_hmac_sha512(args: &[Value]) -> NativeResult {
+/// Zstd.decompress_file(input_path: String, output_path: String) -> nil
+/// Streams a zstd file through the decoder without loading it into memory
+fn zstd_decompress_file(args: &[Value]) -> NativeResult {
     if args.len() != 2 {
         return Err(format!(
-            "Hash.hmac_sha512() expects 2 arguments, got {}",
+            "Zstd.decompress_file() expects 2 arguments, got {}",
             args.len()
         ));
     }
-    let key = get_string_arg(&args[0], "key")?;
-    let message = get_string_arg(&args[1], "message")?;
+    let input_path = get_string_arg(&args[0], "input_path")?;
+    let output_path = get_string_arg(&args[1], "output_path")?;
 
-    type HmacSha512 = Hmac<Sha512>;
-    let mut mac = <HmacSha512 as Mac>::new_from_slice(key.as_bytes())
-        .map_err(|e| format!("HMAC key error: {e}"))?;
-    Mac::update(&mut mac, message.as_bytes());
-    let result = mac.finalize();
-    Ok(Value::string(hex::encode(result.into_bytes())))
+    let input =
+        File::open(&input_path).map_err(|e| format!("failed to open '{}': {}", input_path, e))?;
+    let output = File::create(&output_path)
+        .map_err(|e| format!("failed to create '{}': {}", output_path, e))?;
+    zstd::stream::copy_decode(input, output)
+        .map_err(|e| format!("zstd decompression failed: {}", e))?;
+
+    Ok(Value::Null)
 }
 
 // ============================================================================
-// Crypto Module (Advanced Cryptography)
+// Tar Module
 // ============================================================================
 
-/// Crypto module entry point
-pub fn crypto_method(method: &str, args: &[Value]) -> NativeResult {
+/// Tar module entry point - tar archives with optional gzip/zstd compression
+pub fn tar_method(method: &str, args: &[Value]) -> NativeResult {
     match method {
-        "random_bytes" => crypto_random_bytes(args),
-        "aes_encrypt" => crypto_aes_encrypt(args),
-        "aes_decrypt" => crypto_aes_decrypt(args),
-        "pbkdf2" => crypto_pbkdf2(args),
-        _ => Err(format!("Crypto has no method '{method}'")),
+        "create" => tar_create(args),
+        "extract" => tar_extract(args),
+        "list" => tar_list(args),
+        "read_text" => tar_read_entry(args, "read_text"),
+        "read_bytes" => tar_read_entry(args, "read_bytes"),
+        _ => Err(format!("Tar has no method '{method}'")),
+    }
+}
+
+/// Compression applied to the tar stream
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TarCompression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+/// Determine tar compression from an explicit `compression` option or the file extension
+fn get_tar_compression(path: &str, options: Option<&Value>) -> Result<TarCompression, String> {
+    if let Some(Value::Map(map)) = options {
+        if let Some(name) = get_map_string(&map.borrow(), "compression") {
+            return match name.as_str() {
+                "none" => Ok(TarCompression::None),
+                "gzip" | "gz" => Ok(TarCompression::Gzip),
+                "zstd" | "zst" => Ok(TarCompression::Zstd),
+                other => Err(format!(
+                    "unknown tar compression '{other}' (expected none, gzip, or zstd)"
+                )),
+            };
+        }
+    }
+    let lower = path.to_lowercase();
+    if lower.ends_with(".tar.gz") || lower.ends_with(".tgz") {
+        Ok(TarCompression::Gzip)
+    } else if lower.ends_with(".tar.zst") || lower.ends_with(".tzst") {
+        Ok(TarCompression::Zstd)
+    } else {
+        Ok(TarCompression::None)
+    }
+}
+
+/// Validate the optional options Map argument
+fn get_tar_options<'a>(
+    args: &'a [Value],
+    index: usize,
+    fn_name: &str,
+) -> Result<Option<&'a Value>, String> {
+    match args.get(index) {
+        None => Ok(None),
+        Some(v @ Value::Map(_)) => Ok(Some(v)),
+        Some(v) => Err(format!(
+            "Tar.{fn_name}() options must be Map, got {}",
+            v.type_name()
+        )),
     }
 }
 
-/// Crypto.random_bytes(n: Int) -> List<Int>
-/// Generates cryptographically secure random bytes using the OS random number generator.
-fn crypto_random_bytes(args: &[Value]) -> NativeResult {
-    if args.len() != 1 {
+/// Open a tar archive for streaming reads, wrapping it in the right decoder
+fn open_tar_archive(
+    path: &str,
+    compression: TarCompression,
+) -> Result<tar::Archive<Box<dyn Read>>, String> {
+    let file =
+        File::open(path).map_err(|e| format!("failed to open tar file '{}': {}", path, e))?;
+    let reader: Box<dyn Read> = match compression {
+        TarCompression::None => Box::new(file),
+        TarCompression::Gzip => Box::new(GzDecoder::new(file)),
+        TarCompression::Zstd => Box::new(
+            zstd::Decoder::new(file)
+                .map_err(|e| format!("failed to read zstd stream '{}': {}", path, e))?,
+        ),
+    };
+    Ok(tar::Archive::new(reader))
+}
+
+/// Reject archive paths that could escape the extraction directory
+/// (absolute paths, drive prefixes, or `..` components).
+fn check_archive_path(path: &Path) -> Result<(), String> {
+    use std::path::Component;
+    let escapes = path.components().any(|c| {
+        matches!(
+            c,
+            Component::ParentDir | Component::RootDir | Component::Prefix(_)
+        )
+    });
+    if escapes {
+        Err(format!(
+            "refusing to extract '{}': path escapes the output directory",
+            path.display()
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Append files and directories (recursively) to a tar builder.
+/// Entries are named relative to `base_dir` when given, otherwise by file name.
+fn append_tar_entries<W: Write>(
+    builder: &mut tar::Builder<W>,
+    files: &[Value],
+    base_dir: Option<&Path>,
+) -> Result<(), String> {
+    for file_val in files {
+        let file_path = get_string_arg(file_val, "file")?;
+        let path = Path::new(&file_path);
+
+        if !path.exists() {
+            return Err(format!("file not found: '{}'", file_path));
+        }
+
+        let entry_name = match base_dir {
+            Some(base) => path
+                .strip_prefix(base)
+                .map_err(|_| {
+                    format!(
+                        "'{}' is not inside base_dir '{}'",
+                        file_path,
+                        base.display()
+                    )
+                })?
+                .to_path_buf(),
+            None => path
+                .file_name()
+                .map(std::path::PathBuf::from)
+                .unwrap_or_else(|| path.to_path_buf()),
+        };
+
+        let result = if path.is_dir() {
+            builder.append_dir_all(&entry_name, path)
+        } else {
+            builder.append_path_with_name(path, &entry_name)
+        };
+        result.map_err(|e| format!("failed to add '{}' to archive: {}", file_path, e))?;
+    }
+    Ok(())
+}
+
+/// Tar.create(output_path: String, files: List<String>, options?: Map) -> nil
+/// Creates a tar archive, streaming file contents into it.
+/// Options: `compression` ("none", "gzip", "zstd"; inferred from the extension
+/// by default), `base_dir` (store entries relative to this directory).
+fn tar_create(args: &[Value]) -> NativeResult {
+    if args.len() != 2 && args.len() != 3 {
         return Err(format!(
-            "Crypto.random_bytes() expects 1 argument, got {}",
+            "Tar.create() expects 2-3 arguments, got {}",
             args.len()
         ));
     }
-    let n = get_int_arg(&args[0], "n")?;
-    if n < 0 {
+    let output_path = get_string_arg(&args[0], "output_path")?;
+    let files = match &args[1] {
+        Value::List(list) => list.borrow().clone(),
+        _ => return Err(format!("files must be List, got {}", args[1].type_name())),
+    };
+    let options = get_tar_options(args, 2, "create")?;
+    let compression = get_tar_compression(&output_path, options)?;
+    let base_dir = match options {
+        Some(Value::Map(map)) => get_map_string(&map.borrow(), "base_dir"),
+        _ => None,
+    };
+    let base_dir = base_dir.as_deref().map(Path::new);
+
+    let file = File::create(&output_path)
+        .map_err(|e| format!("failed to create tar file '{}': {}", output_path, e))?;
+    let finish_err = |e: std::io::Error| format!("failed to finalize tar archive: {}", e);
+
+    match compression {
+        TarCompression::None => {
+            let mut builder = tar::Builder::new(file);
+            append_tar_entries(&mut builder, &files, base_dir)?;
+            builder.into_inner().map_err(finish_err)?;
+        }
+        TarCompression::Gzip => {
+            let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+            append_tar_entries(&mut builder, &files, base_dir)?;
+            builder
+                .into_inner()
+                .and_then(GzEncoder::finish)
+                .map_err(finish_err)?;
+        }
+        TarCompression::Zstd => {
+            let encoder = zstd::Encoder::new(file, zstd::DEFAULT_COMPRESSION_LEVEL)
+                .map_err(|e| format!("failed to create zstd stream: {}", e))?;
+            let mut builder = tar::Builder::new(encoder);
+            append_tar_entries(&mut builder, &files, base_dir)?;
+            builder
+                .into_inner()
+                .and_then(zstd::Encoder::finish)
+                .map_err(finish_err)?;
+        }
+    }
+
+    Ok(Value::Null)
+}
+
+/// Tar.extract(path: String, output_dir: String, options?: Map) -> nil
+/// Extracts all entries, refusing any entry (or link target) that would
+/// escape `output_dir`.
+fn tar_extract(args: &[Value]) -> NativeResult {
+    if args.len() != 2 && args.len() != 3 {
         return Err(format!(
-            "Crypto.random_bytes(): n must be non-negative, got {n}"
+            "Tar.extract() expects 2-3 arguments, got {}",
+            args.len()
         ));
     }
-    if n > 1_000_000 {
-        return Err("Crypto.random_bytes(): n too large (max 1000000)".to_string());
-    }
+    let tar_path = get_string_arg(&args[0], "path")?;
+    let output_dir = get_string_arg(&args[1], "output_dir")?;
+    let options = get_tar_options(args, 2, "extract")?;
+    let compression = get_tar_compression(&tar_path, options)?;
+
+    let output_path = Path::new(&output_dir);
+    fs::create_dir_all(output_path)
+        .map_err(|e| format!("failed to create output directory '{}': {}", output_dir, e))?;
+
+    let mut archive = open_tar_archive(&tar_path, compression)?;
+    let entries = archive
+        .entries()
+        .map_err(|e| format!("failed to read tar archive '{}': {}", tar_path, e))?;
+
+    for entry in entries {
+        let mut entry = entry.map_err(|e| format!("failed to read tar entry: {}", e))?;
+        let entry_path = entry
+            .path()
+            .map_err(|e| format!("invalid tar entry path: {}", e))?
+            .into_owned();
+        check_archive_path(&entry_path)?;
+        if let Some(target) = entry
+            .link_name()
+            .map_err(|e| format!("invalid tar link target: {}", e))?
+        {
+            check_archive_path(&target)?;
+        }
+
+        let unpacked = entry
+            .unpack_in(output_path)
+            .map_err(|e| format!("failed to extract '{}': {}", entry_path.display(), e))?;
+        if !unpacked {
+            return Err(format!(
+                "refusing to extract '{}': path escapes the output directory",
+                entry_path.display()
+            ));
+        }
+    }
+
+    Ok(Value::Null)
+}
+
+/// Tar.list(path: String, options?: Map) -> List<Map>
+/// Lists entries with name, size, and is_dir
+fn tar_list(args: &[Value]) -> NativeResult {
+    if args.is_empty() || args.len() > 2 {
+        return Err(format!(
+            "Tar.list() expects 1-2 arguments, got {}",
+            args.len()
+        ));
+    }
+    let tar_path = get_string_arg(&args[0], "path")?;
+    let options = get_tar_options(args, 1, "list")?;
+    let compression = get_tar_compression(&tar_path, options)?;
+
+    let mut archive = open_tar_archive(&tar_path, compression)?;
+    let entries = archive
+        .entries()
+        .map_err(|e| format!("failed to read tar archive '{}': {}", tar_path, e))?;
+
+    let mut result = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("failed to read tar entry: {}", e))?;
+        let name = entry
+            .path()
+            .map_err(|e| format!("invalid tar entry path: {}", e))?
+            .to_string_lossy()
+            .to_string();
+
+        let mut map = ValueMap::new();
+        map.insert(
+            HashableValue::String(Rc::new("name".to_string())),
+            Value::string(name),
+        );
+        map.insert(
+            HashableValue::String(Rc::new("size".to_string())),
+            Value::Int(entry.size() as i64),
+        );
+        map.insert(
+            HashableValue::String(Rc::new("is_dir".to_string())),
+            Value::Bool(entry.header().entry_type().is_dir()),
+        );
+        result.push(Value::map(map));
+    }
+
+    Ok(Value::list(result))
+}
+
+/// Tar.read_text(path, entry_name, options?) -> String
+/// Tar.read_bytes(path, entry_name, options?) -> List<Int>
+/// Streams through the archive and reads a single entry
+fn tar_read_entry(args: &[Value], fn_name: &str) -> NativeResult {
+    if args.len() != 2 && args.len() != 3 {
+        return Err(format!(
+            "Tar.{fn_name}() expects 2-3 arguments, got {}",
+            args.len()
+        ));
+    }
+    let tar_path = get_string_arg(&args[0], "path")?;
+    let entry_name = get_string_arg(&args[1], "entry_name")?;
+    let options = get_tar_options(args, 2, fn_name)?;
+    let compression = get_tar_compression(&tar_path, options)?;
+
+    let mut archive = open_tar_archive(&tar_path, compression)?;
+    let entries = archive
+        .entries()
+        .map_err(|e| format!("failed to read tar archive '{}': {}", tar_path, e))?;
+
+    for entry in entries {
+        let mut entry = entry.map_err(|e| format!("failed to read tar entry: {}", e))?;
+        let matches = entry
+            .path()
+            .map(|p| p == Path::new(&entry_name))
+            .unwrap_or(false);
+        if !matches {
+            continue;
+        }
+        let mut content = Vec::new();
+        entry
+            .read_to_end(&mut content)
+            .map_err(|e| format!("failed to read entry '{}': {}", entry_name, e))?;
+        return if fn_name == "read_text" {
+            String::from_utf8(content)
+                .map(Value::string)
+                .map_err(|e| format!("entry '{}' is not valid UTF-8: {}", entry_name, e))
+        } else {
+            Ok(bytes_to_list(&content))
+        };
+    }
+
+    Err(format!("entry '{}' not found in archive", entry_name))
+}
+
+// ============================================================================
+// Hash Module
+// ============================================================================
+
+/// Hash module entry point
+pub fn hash_method(method: &str, args: &[Value]) -> NativeResult {
+    match method {
+        "sha256" => hash_sha256(args),
+        "sha256_bytes" => hash_sha256_bytes(args),
+        "sha512" => hash_sha512(args),
+        "sha512_bytes" => hash_sha512_bytes(args),
+        "md5" => hash_md5(args),
+        "md5_bytes" => hash_md5_bytes(args),
+        "hmac_sha256" => hash_hmac_sha256(args),
+        "hmac_sha512" => hash_hmac_sha512(args),
+        _ => Err(format!("Hash has no method '{method}'")),
+    }
+}
+
+/// Hash.sha256(data: String) -> String
+/// Returns hex-encoded SHA-256 hash
+fn hash_sha256(args: &[Value]) -> NativeResult {
+    if args.len() != 1 {
+        return Err(format!(
+            "Hash.sha256() expects 1 argument, got {}",
+            args.len()
+        ));
+    }
+    let data = get_string_arg(&args[0], "data")?;
+    let mut hasher = Sha256::new();
+    hasher.update(data.as_bytes());
+    let result = hasher.finalize();
+    Ok(Value::string(hex::encode(result)))
+}
+
+/// Hash.sha256_bytes(data: List<Int>) -> String
+/// Hash raw bytes and return hex-encoded result
+fn hash_sha256_bytes(args: &[Value]) -> NativeResult {
+    if args.len() != 1 {
+        return Err(format!(
+            "Hash.sha256_bytes() expects 1 argument, got {}",
+            args.len()
+        ));
+    }
+    let bytes = get_bytes_arg(&args[0])?;
+    let mut hasher = Sha256::new();
+    hasher.update(&bytes);
+    let result = hasher.finalize();
+    Ok(Value::string(hex::encode(result)))
+}
+
+/// Hash.sha512(data: String) -> String
+fn hash_sha512(args: &[Value]) -> NativeResult {
+    if args.len() != 1 {
+        return Err(format!(
+            "Hash.sha512() expects 1 argument, got {}",
+            args.len()
+        ));
+    }
+    let data = get_string_arg(&args[0], "data")?;
+    let mut hasher = Sha512::new();
+    hasher.update(data.as_bytes());
+    let result = hasher.finalize();
+    Ok(Value::string(hex::encode(result)))
+}
+
+/// Hash.sha512_bytes(data: List<Int>) -> String
+fn hash_sha512_bytes(args: &[Value]) -> NativeResult {
+    if args.len() != 1 {
+        return Err(format!(
+            "Hash.sha512_bytes() expects 1 argument, got {}",
+            args.len()
+        ));
+    }
+    let bytes = get_bytes_arg(&args[0])?;
+    let mut hasher = Sha512::new();
+    hasher.update(&bytes);
+    let result = hasher.finalize();
+    Ok(Value::string(hex::encode(result)))
+}
+
+/// Hash.md5(data: String) -> String
+/// Note: MD5 is for compatibility, not security
+fn hash_md5(args: &[Value]) -> NativeResult {
+    if args.len() != 1 {
+        return Err(format!("Hash.md5() expects 1 argument, got {}", args.len()));
+    }
+    let data = get_string_arg(&args[0], "data")?;
+    let mut hasher = Md5::new();
+    hasher.update(data.as_bytes());
+    let result = hasher.finalize();
+    Ok(Value::string(hex::encode(result)))
+}
+
+/// Hash.md5_bytes(data: List<Int>) -> String
+fn hash_md5_bytes(args: &[Value]) -> NativeResult {
+    if args.len() != 1 {
+        return Err(format!(
+            "Hash.md5_bytes() expects 1 argument, got {}",
+            args.len()
+        ));
+    }
+    let bytes = get_bytes_arg(&args[0])?;
+    let mut hasher = Md5::new();
+    hasher.update(&bytes);
+    let result = hasher.finalize();
+    Ok(Value::string(hex::encode(result)))
+}
+
+/// Hash.hmac_sha256(key: String, message: String) -> String
+fn hash_hmac_sha256(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err(format!(
+            "Hash.hmac_sha256() expects 2 arguments, got {}",
+            args.len()
+        ));
+    }
+    let key = get_string_arg(&args[0], "key")?;
+    let message = get_string_arg(&args[1], "message")?;
+
+    type HmacSha256 = Hmac<Sha256>;
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(key.as_bytes())
+        .map_err(|e| format!("HMAC key error: {e}"))?;
+    Mac::update(&mut mac, message.as_bytes());
+    let result = mac.finalize();
+    Ok(Value::string(hex::encode(result.into_bytes())))
+}
+
+/// Hash.hmac_sha512(key: String, message: String) -> String
+fn hash_hmac_sha512(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err(format!(
+            "Hash.hmac_sha512() expects 2 arguments, got {}",
+            args.len()
+        ));
+    }
+    let key = get_string_arg(&args[0], "key")?;
+    let message = get_string_arg(&args[1], "message")?;
+
+    type HmacSha512 = Hmac<Sha512>;
+    let mut mac = <HmacSha512 as Mac>::new_from_slice(key.as_bytes())
+        .map_err(|e| format!("HMAC key error: {e}"))?;
+    Mac::update(&mut mac, message.as_bytes());
+    let result = mac.finalize();
+    Ok(Value::string(hex::encode(result.into_bytes())))
+}
+
+// ============================================================================
+// Crypto Module (Advanced Cryptography)
+// ============================================================================
+
+/// Crypto module entry point
+pub fn crypto_method(method: &str, args: &[Value]) -> NativeResult {
+    match method {
+        "random_bytes" => crypto_random_bytes(args),
+        "aes_encrypt" => crypto_aes_encrypt(args),
+        "aes_decrypt" => crypto_aes_decrypt(args),
+        "pbkdf2" => crypto_pbkdf2(args),
+        "hmac_sha256" => crypto_hmac_sha256(args, false),
+        "hmac_sha256_bytes" => crypto_hmac_sha256(args, true),
+        "hmac_sha512" => crypto_hmac_sha512(args, false),
+        "hmac_sha512_bytes" => crypto_hmac_sha512(args, true),
+        "constant_time_eq" => crypto_constant_time_eq(args),
+        "ed25519_generate" => crypto_ed25519_generate(args),
+        "ed25519_sign" => crypto_ed25519_sign(args),
+        "ed25519_verify" => crypto_ed25519_verify(args),
+        "rsa_generate" => crypto_rsa_generate(args),
+        "rsa_sign" => crypto_rsa_sign(args),
+        "rsa_verify" => crypto_rsa_verify(args),
+        _ => Err(format!("Crypto has no method '{method}'")),
+    }
+}
+
+/// Crypto.random_bytes(n: Int) -> List<Int>
+/// Generates cryptographically secure random bytes using the OS random number generator.
+fn crypto_random_bytes(args: &[Value]) -> NativeResult {
+    if args.len() != 1 {
+        return Err(format!(
+            "Crypto.random_bytes() expects 1 argument, got {}",
+            args.len()
+        ));
+    }
+    let n = get_int_arg(&args[0], "n")?;
+    if n < 0 {
+        return Err(format!(
+            "Crypto.random_bytes(): n must be non-negative, got {n}"
+        ));
+    }
+    if n > 1_000_000 {
+        return Err("Crypto.random_bytes(): n too large (max 1000000)".to_string());
+    }
 
     use rand::RngCore;
     let mut bytes = vec![0u8; n as usize];
@@ -2995,10 +4956,11 @@ fn crypto_random_bytes(args: &[Value]) -> NativeResult {
 /// Encrypts data using AES-256-GCM authenticated encryption.
 /// Key must be 32 bytes (256 bits) - can be derived using Crypto.pbkdf2().
 /// Returns base64-encoded ciphertext (nonce + ciphertext + auth tag).
+/// An optional third argument supplies associated data that is authenticated but not encrypted.
 fn crypto_aes_encrypt(args: &[Value]) -> NativeResult {
-    if args.len() != 2 {
+    if args.len() != 2 && args.len() != 3 {
         return Err(format!(
-            "Crypto.aes_encrypt() expects 2 arguments (data, key), got {}",
+            "Crypto.aes_encrypt() expects 2-3 arguments (data, key, aad?), got {}",
             args.len()
         ));
     }
@@ -3028,8 +4990,19 @@ fn crypto_aes_encrypt(args: &[Value]) -> NativeResult {
     let cipher = Aes256Gcm::new(key);
     let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
 
+    let aad = match args.get(2) {
+        Some(v) => get_string_or_bytes_arg(v, "aad")?,
+        None => Vec::new(),
+    };
+
     let ciphertext = cipher
-        .encrypt(&nonce, data.as_bytes())
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: data.as_bytes(),
+                aad: &aad,
+            },
+        )
         .map_err(|e| format!("Encryption failed: {e}"))?;
 
     // Prepend nonce to ciphertext for storage (nonce is 12 bytes)
@@ -3045,10 +5018,11 @@ fn crypto_aes_encrypt(args: &[Value]) -> NativeResult {
 /// Crypto.aes_decrypt(encrypted: String, key: String) -> String
 /// Decrypts data encrypted with Crypto.aes_encrypt().
 /// Key must be the same 32-byte key used for encryption.
+/// If associated data was supplied on encryption, the same value must be passed here.
 fn crypto_aes_decrypt(args: &[Value]) -> NativeResult {
-    if args.len() != 2 {
+    if args.len() != 2 && args.len() != 3 {
         return Err(format!(
-            "Crypto.aes_decrypt() expects 2 arguments (encrypted, key), got {}",
+            "Crypto.aes_decrypt() expects 2-3 arguments (encrypted, key, aad?), got {}",
             args.len()
         ));
     }
@@ -3089,8 +5063,19 @@ fn crypto_aes_decrypt(args: &[Value]) -> NativeResult {
     let key = aes_gcm::Key::<Aes256Gcm>::from_slice(&key_bytes);
     let cipher = Aes256Gcm::new(key);
 
+    let aad = match args.get(2) {
+        Some(v) => get_string_or_bytes_arg(v, "aad")?,
+        None => Vec::new(),
+    };
+
     let plaintext = cipher
-        .decrypt(nonce, ciphertext)
+        .decrypt(
+            nonce,
+            Payload {
+                msg: ciphertext,
+                aad: &aad,
+            },
+        )
         .map_err(|_| "Decryption failed: invalid key or corrupted data".to_string())?;
 
     String::from_utf8(plaintext)
@@ -3126,6 +5111,261 @@ fn crypto_pbkdf2(args: &[Value]) -> NativeResult {
     Ok(Value::string(hex::encode(key)))
 }
 
+/// Crypto.hmac_sha256(key: String | List<Int>, message: String | List<Int>) -> String
+/// Computes HMAC-SHA256. Returns hex by default; the `_bytes` variant returns List<Int>
+/// so results can be chained as keys (e.g. AWS SigV4 signing key derivation).
+fn crypto_hmac_sha256(args: &[Value], as_bytes: bool) -> NativeResult {
+    let name = if as_bytes {
+        "hmac_sha256_bytes"
+    } else {
+        "hmac_sha256"
+    };
+    if args.len() != 2 {
+        return Err(format!(
+            "Crypto.{name}() expects 2 arguments (key, message), got {}",
+            args.len()
+        ));
+    }
+    let key = get_string_or_bytes_arg(&args[0], "key")?;
+    let message = get_string_or_bytes_arg(&args[1], "message")?;
+
+    type HmacSha256 = Hmac<Sha256>;
+    let mut mac =
+        <HmacSha256 as Mac>::new_from_slice(&key).map_err(|e| format!("HMAC key error: {e}"))?;
+    Mac::update(&mut mac, &message);
+    let result = mac.finalize().into_bytes();
+    if as_bytes {
+        Ok(bytes_to_list(&result))
+    } else {
+        Ok(Value::string(hex::encode(result)))
+    }
+}
+
+/// Crypto.hmac_sha512(key: String | List<Int>, message: String | List<Int>) -> String
+/// Computes HMAC-SHA512. The `_bytes` variant returns List<Int>.
+fn crypto_hmac_sha512(args: &[Value], as_bytes: bool) -> NativeResult {
+    let name = if as_bytes {
+        "hmac_sha512_bytes"
+    } else {
+        "hmac_sha512"
+    };
+    if args.len() != 2 {
+        return Err(format!(
+            "Crypto.{name}() expects 2 arguments (key, message), got {}",
+            args.len()
+        ));
+    }
+    let key = get_string_or_bytes_arg(&args[0], "key")?;
+    let message = get_string_or_bytes_arg(&args[1], "message")?;
+
+    type HmacSha512 = Hmac<Sha512>;
+    let mut mac =
+        <HmacSha512 as Mac>::new_from_slice(&key).map_err(|e| format!("HMAC key error: {e}"))?;
+    Mac::update(&mut mac, &message);
+    let result = mac.finalize().into_bytes();
+    if as_bytes {
+        Ok(bytes_to_list(&result))
+    } else {
+        Ok(Value::string(hex::encode(result)))
+    }
+}
+
+/// Crypto.constant_time_eq(a: String | List<Int>, b: String | List<Int>) -> Bool
+/// Compares two values in constant time (with respect to their contents), for checking
+/// signatures and MACs without leaking timing information.
+fn crypto_constant_time_eq(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err(format!(
+            "Crypto.constant_time_eq() expects 2 arguments, got {}",
+            args.len()
+        ));
+    }
+    let a = get_string_or_bytes_arg(&args[0], "a")?;
+    let b = get_string_or_bytes_arg(&args[1], "b")?;
+
+    use subtle::ConstantTimeEq;
+    Ok(Value::Bool(a.ct_eq(&b).into()))
+}
+
+/// Build a `{public_key, private_key}` map for key generation results
+fn key_pair_to_value(public_key: String, private_key: String) -> Value {
+    let mut result = ValueMap::new();
+    result.insert(
+        HashableValue::String(Rc::new("public_key".to_string())),
+        Value::string(public_key),
+    );
+    result.insert(
+        HashableValue::String(Rc::new("private_key".to_string())),
+        Value::string(private_key),
+    );
+    Value::map(result)
+}
+
+/// Decode a hex string into a fixed-size byte array
+fn decode_hex_array<const N: usize>(value: &str, what: &str) -> Result<[u8; N], String> {
+    let bytes = hex::decode(value).map_err(|e| format!("Invalid hex {what}: {e}"))?;
+    bytes
+        .try_into()
+        .map_err(|b: Vec<u8>| format!("{what} must be {N} bytes, got {}", b.len()))
+}
+
+/// Crypto.ed25519_generate() -> Map
+/// Generates an Ed25519 key pair. Returns `{public_key, private_key}` as hex strings
+/// (32 bytes each).
+fn crypto_ed25519_generate(args: &[Value]) -> NativeResult {
+    if !args.is_empty() {
+        return Err(format!(
+            "Crypto.ed25519_generate() expects 0 arguments, got {}",
+            args.len()
+        ));
+    }
+    let signing_key = ed25519_dalek::SigningKey::generate(&mut OsRng);
+    Ok(key_pair_to_value(
+        hex::encode(signing_key.verifying_key().to_bytes()),
+        hex::encode(signing_key.to_bytes()),
+    ))
+}
+
+/// Crypto.ed25519_sign(private_key: String, message: String | List<Int>) -> String
+/// Signs a message with a hex-encoded Ed25519 private key. Returns a hex-encoded
+/// 64-byte signature.
+fn crypto_ed25519_sign(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err(format!(
+            "Crypto.ed25519_sign() expects 2 arguments (private_key, message), got {}",
+            args.len()
+        ));
+    }
+    let key_hex = get_string_arg(&args[0], "private_key")?;
+    let message = get_string_or_bytes_arg(&args[1], "message")?;
+
+    let key_bytes = decode_hex_array::<32>(&key_hex, "Ed25519 private key")?;
+    let signing_key = ed25519_dalek::SigningKey::from_bytes(&key_bytes);
+
+    use ed25519_dalek::Signer;
+    let signature = signing_key.sign(&message);
+    Ok(Value::string(hex::encode(signature.to_bytes())))
+}
+
+/// Crypto.ed25519_verify(public_key: String, message: String | List<Int>, signature: String) -> Bool
+/// Verifies a hex-encoded Ed25519 signature. Returns false for invalid signatures;
+/// malformed keys or signatures are errors.
+fn crypto_ed25519_verify(args: &[Value]) -> NativeResult {
+    if args.len() != 3 {
+        return Err(format!(
+            "Crypto.ed25519_verify() expects 3 arguments (public_key, message, signature), got {}",
+            args.len()
+        ));
+    }
+    let key_hex = get_string_arg(&args[0], "public_key")?;
+    let message = get_string_or_bytes_arg(&args[1], "message")?;
+    let sig_hex = get_string_arg(&args[2], "signature")?;
+
+    let key_bytes = decode_hex_array::<32>(&key_hex, "Ed25519 public key")?;
+    let sig_bytes = decode_hex_array::<64>(&sig_hex, "Ed25519 signature")?;
+
+    let verifying_key = ed25519_dalek::VerifyingKey::from_bytes(&key_bytes)
+        .map_err(|e| format!("Invalid Ed25519 public key: {e}"))?;
+    let signature = ed25519_dalek::Signature::from_bytes(&sig_bytes);
+
+    use ed25519_dalek::Verifier;
+    Ok(Value::Bool(
+        verifying_key.verify(&message, &signature).is_ok(),
+    ))
+}
+
+/// Crypto.rsa_generate(bits?: Int) -> Map
+/// Generates an RSA key pair (default 2048 bits). Returns `{public_key, private_key}`
+/// as PEM strings (SPKI and PKCS#8 respectively).
+fn crypto_rsa_generate(args: &[Value]) -> NativeResult {
+    if args.len() > 1 {
+        return Err(format!(
+            "Crypto.rsa_generate() expects 0-1 arguments (bits?), got {}",
+            args.len()
+        ));
+    }
+    let bits = match args.first() {
+        Some(v) => get_int_arg(v, "bits")?,
+        None => 2048,
+    };
+    if !(1024..=8192).contains(&bits) || bits % 8 != 0 {
+        return Err(format!(
+            "Crypto.rsa_generate(): bits must be a multiple of 8 between 1024 and 8192, got {bits}"
+        ));
+    }
+
+    use rsa::pkcs8::{EncodePrivateKey, EncodePublicKey, LineEnding};
+    let private_key = rsa::RsaPrivateKey::new(&mut OsRng, bits as usize)
+        .map_err(|e| format!("RSA key generation failed: {e}"))?;
+    let public_pem = private_key
+        .to_public_key()
+        .to_public_key_pem(LineEnding::LF)
+        .map_err(|e| format!("Failed to encode RSA public key: {e}"))?;
+    let private_pem = private_key
+        .to_pkcs8_pem(LineEnding::LF)
+        .map_err(|e| format!("Failed to encode RSA private key: {e}"))?;
+
+    Ok(key_pair_to_value(public_pem, private_pem.to_string()))
+}
+
+/// Crypto.rsa_sign(private_key: String, message: String | List<Int>) -> String
+/// Signs a message with RSASSA-PKCS1-v1_5 over SHA-256 using a PKCS#8 PEM private key.
+/// Returns a base64-encoded signature.
+fn crypto_rsa_sign(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err(format!(
+            "Crypto.rsa_sign() expects 2 arguments (private_key, message), got {}",
+            args.len()
+        ));
+    }
+    let pem = get_string_arg(&args[0], "private_key")?;
+    let message = get_string_or_bytes_arg(&args[1], "message")?;
+
+    use rsa::pkcs8::DecodePrivateKey;
+    use rsa::signature::{SignatureEncoding, Signer};
+    let private_key = rsa::RsaPrivateKey::from_pkcs8_pem(&pem)
+        .map_err(|e| format!("Invalid RSA private key: {e}"))?;
+    let signing_key = rsa::pkcs1v15::SigningKey::<Sha256>::new(private_key);
+    let signature = signing_key
+        .try_sign(&message)
+        .map_err(|e| format!("RSA signing failed: {e}"))?;
+
+    Ok(Value::string(
+        base64::engine::general_purpose::STANDARD.encode(signature.to_bytes()),
+    ))
+}
+
+/// Crypto.rsa_verify(public_key: String, message: String | List<Int>, signature: String) -> Bool
+/// Verifies a base64-encoded RSASSA-PKCS1-v1_5 SHA-256 signature against an SPKI PEM public key.
+fn crypto_rsa_verify(args: &[Value]) -> NativeResult {
+    if args.len() != 3 {
+        return Err(format!(
+            "Crypto.rsa_verify() expects 3 arguments (public_key, message, signature), got {}",
+            args.len()
+        ));
+    }
+    let pem = get_string_arg(&args[0], "public_key")?;
+    let message = get_string_or_bytes_arg(&args[1], "message")?;
+    let sig_b64 = get_string_arg(&args[2], "signature")?;
+
+    use rsa::pkcs8::DecodePublicKey;
+    use rsa::signature::Verifier;
+    let public_key = rsa::RsaPublicKey::from_public_key_pem(&pem)
+        .map_err(|e| format!("Invalid RSA public key: {e}"))?;
+    let sig_bytes = base64::engine::general_purpose::STANDARD
+        .decode(&sig_b64)
+        .map_err(|e| format!("Invalid base64 signature: {e}"))?;
+    let signature = match rsa::pkcs1v15::Signature::try_from(sig_bytes.as_slice()) {
+        Ok(sig) => sig,
+        Err(_) => return Ok(Value::Bool(false)),
+    };
+    let verifying_key = rsa::pkcs1v15::VerifyingKey::<Sha256>::new(public_key);
+
+    Ok(Value::Bool(
+        verifying_key.verify(&message, &signature).is_ok(),
+    ))
+}
+
 // ============================================================================
 // Uuid Module
 // ============================================================================
@@ -3142,22 +5382,34 @@ pub fn uuid_method(method: &str, args: &[Value]) -> NativeResult {
 }
 
 /// Uuid.v4() -> String
-/// Generate a random UUID (version 4)
+/// Generate a random UUID (version 4), from the seeded generator in
+/// deterministic execution
 fn uuid_v4(args: &[Value]) -> NativeResult {
     if !args.is_empty() {
         return Err(format!("Uuid.v4() expects 0 arguments, got {}", args.len()));
     }
-    let id = Uuid::new_v4();
+    let mut bytes = [0u8; 16];
+    determinism::with_rng(|rng| rng.fill_bytes(&mut bytes));
+    let id = uuid::Builder::from_random_bytes(bytes).into_uuid();
     Ok(Value::string(id.to_string()))
 }
 
 /// Uuid.v7() -> String
-/// Generate a time-based sortable UUID (version 7)
+/// Generate a time-based sortable UUID (version 7), from the virtual clock
+/// and the seeded generator in deterministic execution
 fn uuid_v7(args: &[Value]) -> NativeResult {
     if !args.is_empty() {
         return Err(format!("Uuid.v7() expects 0 arguments, got {}", args.len()));
     }
-    let id = Uuid::now_v7();
+    let id = match determinism::now_millis() {
+        Some(millis) => {
+            let mut random = [0u8; 10];
+            determinism::with_rng(|rng| rng.fill_bytes(&mut random));
+            let millis = u64::try_from(millis).unwrap_or_default();
+            uuid::Builder::from_unix_timestamp_millis(millis, &random).into_uuid()
+        }
+        None => Uuid::now_v7(),
+    };
     Ok(Value::string(id.to_string()))
 }
 
@@ -3221,8 +5473,7 @@ fn random_int(args: &[Value]) -> NativeResult {
         return Err(format!("Random.int(): min ({min}) must be <= max ({max})"));
     }
 
-    let mut rng = rand::thread_rng();
-    let result = rng.gen_range(min..=max);
+    let result = determinism::with_rng(|rng| rng.gen_range(min..=max));
     Ok(Value::Int(result))
 }
 
@@ -3235,8 +5486,7 @@ fn random_float(args: &[Value]) -> NativeResult {
             args.len()
         ));
     }
-    let mut rng = rand::thread_rng();
-    Ok(Value::Float(rng.gen()))
+    Ok(Value::Float(determinism::with_rng(|rng| rng.gen())))
 }
 
 /// Random.bool() -> Bool
@@ -3248,8 +5498,7 @@ fn random_bool(args: &[Value]) -> NativeResult {
             args.len()
         ));
     }
-    let mut rng = rand::thread_rng();
-    Ok(Value::Bool(rng.gen()))
+    Ok(Value::Bool(determinism::with_rng(|rng| rng.gen())))
 }
 
 /// Random.choice(list: List<T>) -> T
@@ -3276,8 +5525,7 @@ fn random_choice(args: &[Value]) -> NativeResult {
         return Err("Random.choice(): cannot choose from empty list".to_string());
     }
 
-    let mut rng = rand::thread_rng();
-    let index = rng.gen_range(0..list.len());
+    let index = determinism::with_rng(|rng| rng.gen_range(0..list.len()));
     Ok(list[index].clone())
 }
 
@@ -3302,15 +5550,16 @@ fn random_shuffle(args: &[Value]) -> NativeResult {
     };
 
     let mut shuffled = list;
-    let mut rng = rand::thread_rng();
 
     // Fisher-Yates shuffle
-    for i in (1..shuffled.len()).rev() {
-        let j = rng.gen_range(0..=i);
-        shuffled.swap(i, j);
-    }
+    determinism::with_rng(|rng| {
+        for i in (1..shuffled.len()).rev() {
+            let j = rng.gen_range(0..=i);
+            shuffled.swap(i, j);
+        }
+    });
 
-    Ok(Value::List(Rc::new(RefCell::new(shuffled))))
+    Ok(Value::list(shuffled))
 }
 
 /// Random.bytes(n: Int) -> List<Int>
@@ -3330,12 +5579,13 @@ fn random_bytes(args: &[Value]) -> NativeResult {
         return Err("Random.bytes(): n too large (max 1000000)".to_string());
     }
 
-    let mut rng = rand::thread_rng();
-    let bytes: Vec<Value> = (0..n)
-        .map(|_| Value::Int(i64::from(rng.gen::<u8>())))
-        .collect();
+    let bytes: Vec<Value> = determinism::with_rng(|rng| {
+        (0..n)
+            .map(|_| Value::Int(i64::from(rng.gen::<u8>())))
+            .collect()
+    });
 
-    Ok(Value::List(Rc::new(RefCell::new(bytes))))
+    Ok(Value::list(bytes))
 }
 
 // ============================================================================
@@ -4311,24 +6561,39 @@ fn input_choose(args: &[Value]) -> NativeResult {
 
 use std::sync::RwLock;
 
+/// Environment variable read for the initial log filter, such as
+/// `warn,db=debug`
+const LOG_FILTER_ENV: &str = "STRATUM_LOG";
+
+/// Target of messages logged without a `target` field
+const DEFAULT_LOG_TARGET: &str = "app";
+
+/// Rotated files kept by `Log.to_rotating_file()` unless told otherwise
+const DEFAULT_LOG_FILES_KEPT: i64 = 5;
+
 /// Log level for filtering messages
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
 enum LogLevel {
-    Debug = 0,
-    Info = 1,
-    Warn = 2,
-    Error = 3,
+    Trace = 0,
+    Debug = 1,
+    Info = 2,
+    Warn = 3,
+    Error = 4,
+    /// Only used in filters, to log nothing
+    Off = 5,
 }
 
 impl LogLevel {
     fn from_str(s: &str) -> Result<Self, String> {
         match s.to_lowercase().as_str() {
+            "trace" => Ok(LogLevel::Trace),
             "debug" => Ok(LogLevel::Debug),
             "info" => Ok(LogLevel::Info),
             "warn" | "warning" => Ok(LogLevel::Warn),
             "error" => Ok(LogLevel::Error),
+            "off" => Ok(LogLevel::Off),
             _ => Err(format!(
-                "invalid log level '{}', expected: debug, info, warn, or error",
+                "invalid log level '{}', expected: trace, debug, info, warn, error, or off",
                 s
             )),
         }
@@ -4336,10 +6601,102 @@ impl LogLevel {
 
     fn as_str(&self) -> &'static str {
         match self {
+            LogLevel::Trace => "TRACE",
             LogLevel::Debug => "DEBUG",
             LogLevel::Info => "INFO",
             LogLevel::Warn => "WARN",
             LogLevel::Error => "ERROR",
+            LogLevel::Off => "OFF",
+        }
+    }
+}
+
+/// Which messages are logged: a default level, and levels for targets that
+/// override it. A target's level also covers its sub-targets, so `db`
+/// covers `db.pool`.
+#[derive(Clone, Debug, PartialEq)]
+struct LogFilter {
+    level: LogLevel,
+    targets: Vec<(String, LogLevel)>,
+}
+
+impl Default for LogFilter {
+    fn default() -> Self {
+        Self {
+            level: LogLevel::Info,
+            targets: Vec::new(),
+        }
+    }
+}
+
+impl LogFilter {
+    /// Parse a filter such as `warn,db=debug,http.client=off`
+    fn parse(spec: &str) -> Result<Self, String> {
+        let mut filter = LogFilter::default();
+        for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            match part.split_once('=') {
+                Some((target, level)) => {
+                    filter.set_target(target.trim(), LogLevel::from_str(level.trim())?);
+                }
+                None => filter.level = LogLevel::from_str(part)?,
+            }
+        }
+        Ok(filter)
+    }
+
+    fn set_target(&mut self, target: &str, level: LogLevel) {
+        match self.targets.iter_mut().find(|(t, _)| t == target) {
+            Some(entry) => entry.1 = level,
+            None => self.targets.push((target.to_string(), level)),
+        }
+    }
+
+    /// The level set for `target` by its most specific entry
+    fn level_for(&self, target: &str) -> LogLevel {
+        self.targets
+            .iter()
+            .filter(|(prefix, _)| {
+                target
+                    .strip_prefix(prefix.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+            })
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.level, |(_, level)| *level)
+    }
+
+    fn enabled(&self, level: LogLevel, target: &str) -> bool {
+        level != LogLevel::Off && level >= self.level_for(target)
+    }
+}
+
+impl std::fmt::Display for LogFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.level.as_str().to_lowercase())?;
+        for (target, level) in &self.targets {
+            write!(f, ",{}={}", target, level.as_str().to_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+/// How log records are written
+#[derive(Clone, Debug, PartialEq)]
+enum LogFormat {
+    /// A template with `{level}`, `{timestamp}`, `{target}`, `{message}`
+    /// and `{fields}` placeholders
+    Template(String),
+    /// Aligned, human-readable lines with `key=value` fields
+    Pretty,
+    /// One JSON object per line
+    Json,
+}
+
+impl LogFormat {
+    fn from_str(format: &str) -> Self {
+        match format {
+            "pretty" => LogFormat::Pretty,
+            "json" => LogFormat::Json,
+            template => LogFormat::Template(template.to_string()),
         }
     }
 }
@@ -4350,22 +6707,35 @@ enum LogOutput {
     Stdout,
     Stderr,
     File(String),
+    /// A file moved to `path.1` once it would grow past `max_bytes`, with
+    /// older files shifted up to `path.{keep}`
+    RotatingFile {
+        path: String,
+        max_bytes: u64,
+        keep: u32,
+    },
 }
 
 /// Configuration for the logging system
 #[derive(Clone)]
 struct LogConfig {
-    level: LogLevel,
+    filter: LogFilter,
     output: LogOutput,
-    format: String,
+    format: LogFormat,
 }
 
 impl Default for LogConfig {
     fn default() -> Self {
+        // An unreadable filter in the environment is ignored rather than
+        // failing every log call
+        let filter = env::var(LOG_FILTER_ENV)
+            .ok()
+            .and_then(|spec| LogFilter::parse(&spec).ok())
+            .unwrap_or_default();
         Self {
-            level: LogLevel::Info,
+            filter,
             output: LogOutput::Stdout,
-            format: "[{level}] {timestamp} - {message}".to_string(),
+            format: LogFormat::Template("[{level}] {timestamp} - {message}".to_string()),
         }
     }
 }
@@ -4375,7 +6745,12 @@ static LOG_CONFIG: RwLock<Option<LogConfig>> = RwLock::new(None);
 
 fn get_log_config() -> LogConfig {
     let guard = LOG_CONFIG.read().unwrap();
-    guard.clone().unwrap_or_default()
+    if let Some(config) = guard.as_ref() {
+        return config.clone();
+    }
+    drop(guard);
+    update_log_config(|_| {});
+    get_log_config()
 }
 
 fn update_log_config<F: FnOnce(&mut LogConfig)>(f: F) {
@@ -4385,14 +6760,27 @@ fn update_log_config<F: FnOnce(&mut LogConfig)>(f: F) {
     *guard = Some(config);
 }
 
+/// A message to log, with its structured fields in the order given
+struct LogRecord<'a> {
+    level: LogLevel,
+    target: &'a str,
+    message: &'a str,
+    fields: &'a [(String, Value)],
+}
+
 pub fn log_method(method: &str, args: &[Value]) -> NativeResult {
     match method {
+        "trace" => log_message(LogLevel::Trace, args),
         "debug" => log_message(LogLevel::Debug, args),
         "info" => log_message(LogLevel::Info, args),
         "warn" | "warning" => log_message(LogLevel::Warn, args),
         "error" => log_message(LogLevel::Error, args),
         "set_level" => log_set_level(args),
+        "set_filter" => log_set_filter(args),
+        "filter" => log_get_filter(args),
+        "enabled" => log_enabled(args),
         "to_file" => log_to_file(args),
+        "to_rotating_file" => log_to_rotating_file(args),
         "to_stderr" => log_to_stderr(args),
         "to_stdout" => log_to_stdout(args),
         "set_format" => log_set_format(args),
@@ -4402,57 +6790,24 @@ pub fn log_method(method: &str, args: &[Value]) -> NativeResult {
 }
 
 fn log_message(level: LogLevel, args: &[Value]) -> NativeResult {
-    if args.is_empty() || args.len() > 2 {
-        return Err(format!(
-            "Log.{}() expects 1-2 arguments, got {}",
-            level.as_str().to_lowercase(),
-            args.len()
-        ));
-    }
-
     // Validate arguments FIRST, before level filtering
     // This ensures users get immediate feedback on invalid args
-    let message = get_string_arg(&args[0], "message")?;
-
-    // Validate optional context map
-    let context: Option<HashMap<String, String>> = if args.len() == 2 {
-        match &args[1] {
-            Value::Map(map) => {
-                let map = map.borrow();
-                let mut ctx = HashMap::new();
-                for (k, v) in map.iter() {
-                    let key = match k {
-                        HashableValue::Null => "null".to_string(),
-                        HashableValue::String(s) => s.to_string(),
-                        HashableValue::Int(i) => i.to_string(),
-                        HashableValue::Bool(b) => b.to_string(),
-                    };
-                    let val = value_to_log_string(v);
-                    ctx.insert(key, val);
-                }
-                Some(ctx)
-            }
-            _ => {
-                return Err(format!(
-                    "Log.{}() context must be a Map, got {}",
-                    level.as_str().to_lowercase(),
-                    args[1].type_name()
-                ))
-            }
-        }
-    } else {
-        None
-    };
+    let (message, target, fields) = log_message_parts(level, args)?;
 
     let config = get_log_config();
 
     // Check if this level should be logged (after validation)
-    if level < config.level {
+    if !config.filter.enabled(level, &target) {
         return Ok(Value::Null);
     }
 
-    // Format the log message
-    let formatted = format_log_message(&config.format, level, &message, context.as_ref());
+    let record = LogRecord {
+        level,
+        target: &target,
+        message: &message,
+        fields: &fields,
+    };
+    let formatted = format_log_record(&config.format, &record, &log_timestamp());
 
     // Write to output
     write_log_output(&config.output, &formatted)?;
@@ -4460,6 +6815,51 @@ fn log_message(level: LogLevel, args: &[Value]) -> NativeResult {
     Ok(Value::Null)
 }
 
+/// Split a log call's arguments into its message, its target and its other
+/// fields. A String `target` field names the target.
+fn log_message_parts(
+    level: LogLevel,
+    args: &[Value],
+) -> Result<(String, String, Vec<(String, Value)>), String> {
+    let name = level.as_str().to_lowercase();
+    if args.is_empty() || args.len() > 2 {
+        return Err(format!(
+            "Log.{}() expects 1-2 arguments, got {}",
+            name,
+            args.len()
+        ));
+    }
+    let message = get_string_arg(&args[0], "message")?;
+
+    let mut target = DEFAULT_LOG_TARGET.to_string();
+    let mut fields = Vec::new();
+    match args.get(1) {
+        None => {}
+        Some(Value::Map(map)) => {
+            for (k, v) in map.borrow().iter() {
+                let key = match k {
+                    HashableValue::Null => "null".to_string(),
+                    HashableValue::String(s) => s.to_string(),
+                    HashableValue::Int(i) => i.to_string(),
+                    HashableValue::Bool(b) => b.to_string(),
+                };
+                match v {
+                    Value::String(s) if key == "target" => target = s.to_string(),
+                    _ => fields.push((key, v.clone())),
+                }
+            }
+        }
+        Some(other) => {
+            return Err(format!(
+                "Log.{}() context must be a Map, got {}",
+                name,
+                other.type_name()
+            ))
+        }
+    }
+    Ok((message, target, fields))
+}
+
 fn value_to_log_string(v: &Value) -> String {
     match v {
         Value::Null => "null".to_string(),
@@ -4491,28 +6891,81 @@ fn value_to_log_string(v: &Value) -> String {
     }
 }
 
-fn format_log_message(
-    format: &str,
-    level: LogLevel,
-    message: &str,
-    context: Option<&HashMap<String, String>>,
-) -> String {
-    let timestamp = Local::now().format("%Y-%m-%dT%H:%M:%S%.3f%:z").to_string();
-
-    let mut result = format.to_string();
-    result = result.replace("{level}", level.as_str());
-    result = result.replace("{timestamp}", &timestamp);
-    result = result.replace("{message}", message);
+/// The time to stamp a record with, from the virtual clock when execution
+/// is deterministic
+fn log_timestamp() -> String {
+    match determinism::now_millis() {
+        Some(millis) => Utc
+            .timestamp_millis_opt(millis)
+            .single()
+            .map(|now| now.format("%Y-%m-%dT%H:%M:%S%.3f%:z").to_string())
+            .unwrap_or_default(),
+        None => Local::now().format("%Y-%m-%dT%H:%M:%S%.3f%:z").to_string(),
+    }
+}
 
-    // Append context if present
-    if let Some(ctx) = context {
-        if !ctx.is_empty() {
-            let pairs: Vec<String> = ctx.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
-            result.push_str(&format!(" {{{}}}", pairs.join(", ")));
+fn format_log_record(format: &LogFormat, record: &LogRecord<'_>, timestamp: &str) -> String {
+    match format {
+        LogFormat::Template(template) => {
+            let pairs: Vec<String> = record
+                .fields
+                .iter()
+                .map(|(k, v)| format!("{}={}", k, value_to_log_string(v)))
+                .collect();
+            let mut result = template
+                .replace("{level}", record.level.as_str())
+                .replace("{timestamp}", timestamp)
+                .replace("{target}", record.target)
+                .replace("{fields}", &pairs.join(" "))
+                .replace("{message}", record.message);
+
+            // Append fields the template has no place for
+            if !template.contains("{fields}") && !pairs.is_empty() {
+                result.push_str(&format!(" {{{}}}", pairs.join(", ")));
+            }
+            result
+        }
+        LogFormat::Pretty => {
+            let mut result = format!(
+                "{} {:<5} {}: {}",
+                timestamp,
+                record.level.as_str(),
+                record.target,
+                record.message
+            );
+            for (key, value) in record.fields {
+                let text = value_to_log_string(value);
+                // Quote values that would otherwise run into the next field
+                if text.is_empty() || text.contains(char::is_whitespace) {
+                    result.push_str(&format!(" {key}={text:?}"));
+                } else {
+                    result.push_str(&format!(" {key}={text}"));
+                }
+            }
+            result
+        }
+        LogFormat::Json => {
+            // Built by hand to keep the keys in a fixed, readable order
+            let json = |value: serde_json::Value| value.to_string();
+            let fields: Vec<String> = record
+                .fields
+                .iter()
+                .map(|(key, value)| {
+                    let value = value_to_json(value)
+                        .unwrap_or_else(|_| serde_json::Value::from(value_to_log_string(value)));
+                    format!("{}:{}", json(key.as_str().into()), json(value))
+                })
+                .collect();
+            format!(
+                "{{\"timestamp\":{},\"level\":{},\"target\":{},\"message\":{},\"fields\":{{{}}}}}",
+                json(timestamp.into()),
+                json(record.level.as_str().to_lowercase().into()),
+                json(record.target.into()),
+                json(record.message.into()),
+                fields.join(",")
+            )
         }
     }
-
-    result
 }
 
 fn write_log_output(output: &LogOutput, message: &str) -> Result<(), String> {
@@ -4525,18 +6978,52 @@ fn write_log_output(output: &LogOutput, message: &str) -> Result<(), String> {
             eprintln!("{message}");
             Ok(())
         }
-        LogOutput::File(path) => {
-            use std::fs::OpenOptions;
-            let mut file = OpenOptions::new()
-                .create(true)
-                .append(true)
-                .open(path)
-                .map_err(|e| format!("failed to open log file '{}': {}", path, e))?;
-            writeln!(file, "{message}")
-                .map_err(|e| format!("failed to write to log file '{}': {}", path, e))?;
-            Ok(())
+        LogOutput::File(path) => append_log_line(path, message),
+        LogOutput::RotatingFile {
+            path,
+            max_bytes,
+            keep,
+        } => {
+            let incoming = u64::try_from(message.len() + 1).unwrap_or(u64::MAX);
+            rotate_log_file(path, *max_bytes, *keep, incoming)?;
+            append_log_line(path, message)
+        }
+    }
+}
+
+fn append_log_line(path: &str, message: &str) -> Result<(), String> {
+    use std::fs::OpenOptions;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("failed to open log file '{}': {}", path, e))?;
+    writeln!(file, "{message}")
+        .map_err(|e| format!("failed to write to log file '{}': {}", path, e))?;
+    Ok(())
+}
+
+/// Move `path` to `path.1`, `path.1` to `path.2` and so on, dropping the
+/// oldest, if `incoming` more bytes would take it past `max_bytes`. A file
+/// with nothing in it yet is never rotated, so an oversized line is still
+/// written.
+fn rotate_log_file(path: &str, max_bytes: u64, keep: u32, incoming: u64) -> Result<(), String> {
+    let size = fs::metadata(path).map_or(0, |meta| meta.len());
+    if size == 0 || size.saturating_add(incoming) <= max_bytes {
+        return Ok(());
+    }
+    let rotate_err = |e: std::io::Error| format!("failed to rotate log file '{}': {}", path, e);
+    if keep == 0 {
+        return fs::remove_file(path).map_err(rotate_err);
+    }
+    let _ = fs::remove_file(format!("{path}.{keep}"));
+    for n in (1..keep).rev() {
+        let older = format!("{path}.{n}");
+        if Path::new(&older).exists() {
+            fs::rename(&older, format!("{path}.{}", n + 1)).map_err(rotate_err)?;
         }
     }
+    fs::rename(path, format!("{path}.1")).map_err(rotate_err)
 }
 
 fn log_set_level(args: &[Value]) -> NativeResult {
@@ -4548,7 +7035,7 @@ fn log_set_level(args: &[Value]) -> NativeResult {
     }
     let level_str = get_string_arg(&args[0], "level")?;
     let level = LogLevel::from_str(&level_str)?;
-    update_log_config(|c| c.level = level);
+    update_log_config(|c| c.filter.level = level);
     Ok(Value::Null)
 }
 
@@ -4560,7 +7047,44 @@ fn log_get_level(args: &[Value]) -> NativeResult {
         ));
     }
     let config = get_log_config();
-    Ok(Value::string(config.level.as_str().to_lowercase()))
+    Ok(Value::string(config.filter.level.as_str().to_lowercase()))
+}
+
+fn log_set_filter(args: &[Value]) -> NativeResult {
+    if args.len() != 1 {
+        return Err(format!(
+            "Log.set_filter() expects 1 argument, got {}",
+            args.len()
+        ));
+    }
+    let filter = LogFilter::parse(&get_string_arg(&args[0], "filter")?)?;
+    update_log_config(|c| c.filter = filter);
+    Ok(Value::Null)
+}
+
+fn log_get_filter(args: &[Value]) -> NativeResult {
+    if !args.is_empty() {
+        return Err(format!(
+            "Log.filter() expects 0 arguments, got {}",
+            args.len()
+        ));
+    }
+    Ok(Value::string(get_log_config().filter.to_string()))
+}
+
+fn log_enabled(args: &[Value]) -> NativeResult {
+    if args.is_empty() || args.len() > 2 {
+        return Err(format!(
+            "Log.enabled() expects 1-2 arguments, got {}",
+            args.len()
+        ));
+    }
+    let level = LogLevel::from_str(&get_string_arg(&args[0], "level")?)?;
+    let target = match args.get(1) {
+        Some(target) => get_string_arg(target, "target")?,
+        None => DEFAULT_LOG_TARGET.to_string(),
+    };
+    Ok(Value::Bool(get_log_config().filter.enabled(level, &target)))
 }
 
 fn log_to_file(args: &[Value]) -> NativeResult {
@@ -4575,6 +7099,34 @@ fn log_to_file(args: &[Value]) -> NativeResult {
     Ok(Value::Null)
 }
 
+fn log_to_rotating_file(args: &[Value]) -> NativeResult {
+    if args.len() < 2 || args.len() > 3 {
+        return Err(format!(
+            "Log.to_rotating_file() expects 2-3 arguments, got {}",
+            args.len()
+        ));
+    }
+    let path = get_string_arg(&args[0], "path")?;
+    let max_bytes = u64::try_from(get_int_arg(&args[1], "max_bytes")?)
+        .ok()
+        .filter(|&max| max > 0)
+        .ok_or("Log.to_rotating_file() max_bytes must be positive")?;
+    let keep = match args.get(2) {
+        Some(keep) => get_int_arg(keep, "keep")?,
+        None => DEFAULT_LOG_FILES_KEPT,
+    };
+    let keep =
+        u32::try_from(keep).map_err(|_| "Log.to_rotating_file() keep must not be negative")?;
+    update_log_config(|c| {
+        c.output = LogOutput::RotatingFile {
+            path,
+            max_bytes,
+            keep,
+        }
+    });
+    Ok(Value::Null)
+}
+
 fn log_to_stderr(args: &[Value]) -> NativeResult {
     if !args.is_empty() {
         return Err(format!(
@@ -4605,10 +7157,240 @@ fn log_set_format(args: &[Value]) -> NativeResult {
         ));
     }
     let format = get_string_arg(&args[0], "format")?;
-    update_log_config(|c| c.format = format);
+    update_log_config(|c| c.format = LogFormat::from_str(&format));
+    Ok(Value::Null)
+}
+
+// ============================================================================
+// I18n Module
+// ============================================================================
+
+pub fn i18n_method(method: &str, args: &[Value]) -> NativeResult {
+    match method {
+        "load" => i18n_load(args),
+        "add" => i18n_add(args),
+        "t" => i18n_t(args),
+        "has" => i18n_has(args),
+        "locale" => i18n_locale(args),
+        "set_locale" => i18n_set_locale(args),
+        "set_fallback" => i18n_set_fallback(args),
+        "locales" => i18n_locales(args),
+        "system_locales" => i18n_system_locales(args),
+        "plural" => i18n_plural(args),
+        "number" => i18n_number(args),
+        "date" => i18n_date(args),
+        _ => Err(format!("I18n has no method '{method}'")),
+    }
+}
+
+fn i18n_load(args: &[Value]) -> NativeResult {
+    if args.len() != 1 {
+        return Err(format!(
+            "I18n.load() expects 1 argument, got {}",
+            args.len()
+        ));
+    }
+    let path = get_string_arg(&args[0], "path")?;
+    let locales = i18n::load(Path::new(&path))?;
+    Ok(Value::list(
+        locales.into_iter().map(Value::string).collect(),
+    ))
+}
+
+fn i18n_add(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err(format!(
+            "I18n.add() expects 2 arguments, got {}",
+            args.len()
+        ));
+    }
+    let locale = get_string_arg(&args[0], "locale")?;
+    let source = get_string_arg(&args[1], "source")?;
+    i18n::add_source(&locale, source)?;
+    Ok(Value::Null)
+}
+
+fn i18n_t(args: &[Value]) -> NativeResult {
+    if args.is_empty() || args.len() > 2 {
+        return Err(format!(
+            "I18n.t() expects 1-2 arguments, got {}",
+            args.len()
+        ));
+    }
+    let key = get_string_arg(&args[0], "key")?;
+    let message_args = match args.get(1) {
+        None => Vec::new(),
+        Some(Value::Map(map)) => map
+            .borrow()
+            .iter()
+            .map(|(k, v)| {
+                let name = match k {
+                    HashableValue::Null => "null".to_string(),
+                    HashableValue::String(s) => s.to_string(),
+                    HashableValue::Int(i) => i.to_string(),
+                    HashableValue::Bool(b) => b.to_string(),
+                };
+                (name, v.clone())
+            })
+            .collect(),
+        Some(other) => {
+            return Err(format!(
+                "I18n.t() args must be a Map, got {}",
+                other.type_name()
+            ))
+        }
+    };
+    // A missing message shows its key, so an untranslated screen still
+    // says something
+    let text = i18n::translate(&key, &message_args).unwrap_or(key);
+    Ok(Value::string(text))
+}
+
+fn i18n_has(args: &[Value]) -> NativeResult {
+    if args.len() != 1 {
+        return Err(format!("I18n.has() expects 1 argument, got {}", args.len()));
+    }
+    let key = get_string_arg(&args[0], "key")?;
+    Ok(Value::Bool(i18n::translate(&key, &[]).is_some()))
+}
+
+fn i18n_locale(args: &[Value]) -> NativeResult {
+    if !args.is_empty() {
+        return Err(format!(
+            "I18n.locale() expects 0 arguments, got {}",
+            args.len()
+        ));
+    }
+    Ok(Value::string(i18n::current_locale().to_string()))
+}
+
+fn i18n_set_locale(args: &[Value]) -> NativeResult {
+    if args.len() != 1 {
+        return Err(format!(
+            "I18n.set_locale() expects 1 argument, got {}",
+            args.len()
+        ));
+    }
+    let locales = match &args[0] {
+        Value::List(list) => list
+            .borrow()
+            .iter()
+            .map(|locale| get_string_arg(locale, "locale"))
+            .collect::<Result<Vec<_>, _>>()?,
+        other => vec![get_string_arg(other, "locale")?],
+    };
+    Ok(Value::string(i18n::set_locales(&locales)?))
+}
+
+fn i18n_set_fallback(args: &[Value]) -> NativeResult {
+    if args.len() != 1 {
+        return Err(format!(
+            "I18n.set_fallback() expects 1 argument, got {}",
+            args.len()
+        ));
+    }
+    i18n::set_fallback(&get_string_arg(&args[0], "locale")?)?;
     Ok(Value::Null)
 }
 
+fn i18n_locales(args: &[Value]) -> NativeResult {
+    if !args.is_empty() {
+        return Err(format!(
+            "I18n.locales() expects 0 arguments, got {}",
+            args.len()
+        ));
+    }
+    let locales = i18n::available_locales();
+    Ok(Value::list(
+        locales.into_iter().map(Value::string).collect(),
+    ))
+}
+
+fn i18n_system_locales(args: &[Value]) -> NativeResult {
+    if !args.is_empty() {
+        return Err(format!(
+            "I18n.system_locales() expects 0 arguments, got {}",
+            args.len()
+        ));
+    }
+    let locales = i18n::system_locales();
+    Ok(Value::list(
+        locales
+            .iter()
+            .map(|locale| Value::string(locale.to_string()))
+            .collect(),
+    ))
+}
+
+/// The digits of a number argument, written out the way Rust writes them
+fn i18n_number_text(value: &Value, name: &str, decimals: Option<usize>) -> Result<String, String> {
+    match (value, decimals) {
+        (Value::Int(i), None) => Ok(i.to_string()),
+        (Value::Int(i), Some(0)) => Ok(i.to_string()),
+        (Value::Int(i), Some(decimals)) => Ok(format!("{}.{}", i, "0".repeat(decimals))),
+        (Value::Float(f), None) => Ok(f.to_string()),
+        (Value::Float(f), Some(decimals)) => Ok(format!("{:.*}", decimals, f)),
+        _ => Err(format!(
+            "{} must be Int or Float, got {}",
+            name,
+            value.type_name()
+        )),
+    }
+}
+
+fn i18n_plural(args: &[Value]) -> NativeResult {
+    if args.len() != 1 {
+        return Err(format!(
+            "I18n.plural() expects 1 argument, got {}",
+            args.len()
+        ));
+    }
+    let number = i18n_number_text(&args[0], "count", None)?;
+    let category = i18n::plural_category(&number, &i18n::current_locale());
+    Ok(Value::string(category))
+}
+
+fn i18n_number(args: &[Value]) -> NativeResult {
+    if args.is_empty() || args.len() > 2 {
+        return Err(format!(
+            "I18n.number() expects 1-2 arguments, got {}",
+            args.len()
+        ));
+    }
+    let decimals = match args.get(1) {
+        Some(decimals) => Some(
+            usize::try_from(get_int_arg(decimals, "decimals")?)
+                .map_err(|_| "I18n.number() decimals must not be negative")?,
+        ),
+        None => None,
+    };
+    let number = i18n_number_text(&args[0], "number", decimals)?;
+    Ok(Value::string(i18n::format_number(
+        &number,
+        &i18n::current_locale(),
+        true,
+    )))
+}
+
+fn i18n_date(args: &[Value]) -> NativeResult {
+    if args.is_empty() || args.len() > 2 {
+        return Err(format!(
+            "I18n.date() expects 1-2 arguments, got {}",
+            args.len()
+        ));
+    }
+    let ts = get_datetime_timestamp(&args[0])?;
+    let format = match args.get(1) {
+        Some(format) => get_string_arg(format, "format")?,
+        None => "%x".to_string(),
+    };
+    Ok(Value::string(i18n::format_date(
+        ts,
+        &format,
+        &i18n::current_locale(),
+    )?))
+}
+
 // ============================================================================
 // System Module
 // ============================================================================
@@ -4778,6 +7560,7 @@ fn system_uptime(args: &[Value]) -> NativeResult {
 pub fn process_method(method: &str, args: &[Value]) -> NativeResult {
     match method {
         "spawn" => process_spawn(args),
+        "start" => process_start(args),
         "kill" => process_kill(args),
         _ => Err(format!("Process has no method '{method}'")),
     }
@@ -4792,54 +7575,115 @@ fn process_spawn(args: &[Value]) -> NativeResult {
     }
 
     let program = get_string_arg(&args[0], "program")?;
-    let cmd_args: Vec<String> = if args.len() == 2 {
-        match &args[1] {
-            Value::List(list) => list
-                .borrow()
-                .iter()
-                .map(|v| match v {
-                    Value::String(s) => Ok(s.to_string()),
-                    _ => Err(format!(
-                        "Process.spawn() argument must be string, got {}",
-                        v.type_name()
-                    )),
-                })
-                .collect::<Result<Vec<_>, _>>()?,
-            _ => {
-                return Err(format!(
-                    "Process.spawn() expects List as second argument, got {}",
-                    args[1].type_name()
-                ))
-            }
-        }
-    } else {
-        Vec::new()
+    let cmd_args = match args.get(1) {
+        Some(value) => get_process_args(value, "spawn")?,
+        None => Vec::new(),
+    };
+
+    let child = Command::new(&program)
+        .args(&cmd_args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to spawn '{}': {}", program, e))?;
+
+    let pid = child.id();
+
+    // Store the child process handle for later interaction
+    let mut result = ValueMap::new();
+    result.insert(
+        HashableValue::String(Rc::new("pid".to_string())),
+        Value::Int(pid as i64),
+    );
+    result.insert(
+        HashableValue::String(Rc::new("program".to_string())),
+        Value::string(&program),
+    );
+
+    // Store the child handle in a thread-safe wrapper for later use
+    // For now, we return basic info - the process runs in background
+    Ok(Value::map(result))
+}
+
+/// Parse the optional argument list passed to Process.spawn/Process.start
+fn get_process_args(value: &Value, fn_name: &str) -> Result<Vec<String>, String> {
+    match value {
+        Value::List(list) => list
+            .borrow()
+            .iter()
+            .map(|v| match v {
+                Value::String(s) => Ok(s.to_string()),
+                _ => Err(format!(
+                    "Process.{fn_name}() argument must be string, got {}",
+                    v.type_name()
+                )),
+            })
+            .collect(),
+        _ => Err(format!(
+            "Process.{fn_name}() expects List as second argument, got {}",
+            value.type_name()
+        )),
+    }
+}
+
+/// Process.start(program, args?, options?) -> ChildProcess
+/// Spawns a process with piped stdin/stdout/stderr and returns a handle for
+/// streaming I/O. Options: `cwd` (String), `env` (Map<String, String>).
+fn process_start(args: &[Value]) -> NativeResult {
+    if args.is_empty() || args.len() > 3 {
+        return Err(format!(
+            "Process.start() expects 1-3 arguments, got {}",
+            args.len()
+        ));
+    }
+
+    let program = get_string_arg(&args[0], "program")?;
+    let cmd_args = match args.get(1) {
+        Some(value) => get_process_args(value, "start")?,
+        None => Vec::new(),
     };
 
-    let child = Command::new(&program)
+    let mut command = Command::new(&program);
+    command
         .args(&cmd_args)
-        .stdin(Stdio::null())
+        .stdin(Stdio::piped())
         .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .map_err(|e| format!("failed to spawn '{}': {}", program, e))?;
+        .stderr(Stdio::piped());
 
-    let pid = child.id();
+    if let Some(options) = args.get(2) {
+        let Value::Map(options) = options else {
+            return Err(format!(
+                "Process.start() options must be Map, got {}",
+                options.type_name()
+            ));
+        };
+        let options = options.borrow();
+        if let Some(cwd) = get_map_string(&options, "cwd") {
+            command.current_dir(cwd);
+        }
+        if let Some(env_value) = options.get(&HashableValue::String(Rc::new("env".to_string()))) {
+            let Value::Map(env_map) = env_value else {
+                return Err(format!(
+                    "Process.start() env option must be Map, got {}",
+                    env_value.type_name()
+                ));
+            };
+            for (key, value) in env_map.borrow().iter() {
+                if let HashableValue::String(key) = key {
+                    command.env(key.as_str(), value.to_string());
+                }
+            }
+        }
+    }
 
-    // Store the child process handle for later interaction
-    let mut result = HashMap::new();
-    result.insert(
-        HashableValue::String(Rc::new("pid".to_string())),
-        Value::Int(pid as i64),
-    );
-    result.insert(
-        HashableValue::String(Rc::new("program".to_string())),
-        Value::string(&program),
-    );
+    let child = command
+        .spawn()
+        .map_err(|e| format!("failed to start '{}': {}", program, e))?;
 
-    // Store the child handle in a thread-safe wrapper for later use
-    // For now, we return basic info - the process runs in background
-    Ok(Value::Map(Rc::new(std::cell::RefCell::new(result))))
+    Ok(Value::ChildProcess(Arc::new(ChildProcessWrapper::new(
+        child, program,
+    ))))
 }
 
 fn process_kill(args: &[Value]) -> NativeResult {
@@ -4887,6 +7731,237 @@ fn process_kill(args: &[Value]) -> NativeResult {
     }
 }
 
+// ============================================================================
+// ChildProcess Methods
+// ============================================================================
+
+/// Methods on ChildProcess value type (returned by Process.start)
+pub fn child_process_method(
+    proc: &Arc<ChildProcessWrapper>,
+    method: &str,
+    args: &[Value],
+) -> NativeResult {
+    match method {
+        "pid" => Ok(Value::Int(i64::from(proc.pid))),
+        "program" => Ok(Value::string(&proc.program)),
+        "write" => child_process_write(proc, args, false),
+        "write_line" => child_process_write(proc, args, true),
+        "close_stdin" => child_process_close_stdin(proc, args),
+        "read_line" => child_process_read(proc, "read_line", "stdout", args),
+        "read" => child_process_read(proc, "read", "stdout", args),
+        "read_stderr_line" => child_process_read(proc, "read_stderr_line", "stderr", args),
+        "read_stderr" => child_process_read(proc, "read_stderr", "stderr", args),
+        "wait" => child_process_wait(proc, args),
+        "try_wait" => child_process_try_wait(proc, args),
+        "is_running" => {
+            child_process_try_wait(proc, args).map(|status| Value::Bool(status == Value::Null))
+        }
+        "kill" => child_process_kill(proc, args),
+        _ => Err(format!("ChildProcess has no method '{method}'")),
+    }
+}
+
+/// proc.write(data) / proc.write_line(text) - Write to the process's stdin
+/// data can be String or List of bytes. Returns the number of bytes written.
+fn child_process_write(
+    proc: &Arc<ChildProcessWrapper>,
+    args: &[Value],
+    newline: bool,
+) -> NativeResult {
+    let name = if newline { "write_line" } else { "write" };
+    if args.len() != 1 {
+        return Err(format!(
+            "ChildProcess.{name}() expects 1 argument, got {}",
+            args.len()
+        ));
+    }
+    let mut data = get_string_or_bytes_arg(&args[0], "data")?;
+    if newline {
+        data.push(b'\n');
+    }
+
+    let mut stdin = proc
+        .stdin
+        .lock()
+        .map_err(|_| "ChildProcess stdin lock poisoned".to_string())?;
+    let pipe = stdin
+        .as_mut()
+        .ok_or_else(|| format!("ChildProcess.{name}(): stdin is closed"))?;
+    pipe.write_all(&data)
+        .and_then(|()| pipe.flush())
+        .map_err(|e| format!("failed to write to process {}: {}", proc.pid, e))?;
+    Ok(Value::Int(data.len() as i64))
+}
+
+/// proc.close_stdin() - Close stdin so the process sees end-of-file
+fn child_process_close_stdin(proc: &Arc<ChildProcessWrapper>, args: &[Value]) -> NativeResult {
+    if !args.is_empty() {
+        return Err(format!(
+            "ChildProcess.close_stdin() expects 0 arguments, got {}",
+            args.len()
+        ));
+    }
+    let mut stdin = proc
+        .stdin
+        .lock()
+        .map_err(|_| "ChildProcess stdin lock poisoned".to_string())?;
+    stdin.take();
+    Ok(Value::Null)
+}
+
+/// proc.read_line() / proc.read(max_bytes?) and their stderr variants (async)
+/// Returns a Future resolving to the next line (without trailing newline) or chunk,
+/// or null once the stream reaches end-of-file.
+fn child_process_read(
+    proc: &Arc<ChildProcessWrapper>,
+    name: &str,
+    stream: &str,
+    args: &[Value],
+) -> NativeResult {
+    let line_mode = name.ends_with("line");
+    let max_bytes = if line_mode {
+        if !args.is_empty() {
+            return Err(format!(
+                "ChildProcess.{name}() expects 0 arguments, got {}",
+                args.len()
+            ));
+        }
+        0
+    } else {
+        match args {
+            [] => 8192,
+            [Value::Int(n)] if *n > 0 => *n,
+            [Value::Int(n)] => {
+                return Err(format!(
+                    "ChildProcess.{name}() max_bytes must be positive, got {n}"
+                ))
+            }
+            [other] => {
+                return Err(format!(
+                    "ChildProcess.{name}() max_bytes must be Int, got {}",
+                    other.type_name()
+                ))
+            }
+            _ => {
+                return Err(format!(
+                    "ChildProcess.{name}() expects 0-1 arguments, got {}",
+                    args.len()
+                ))
+            }
+        }
+    };
+
+    let mut m = ValueMap::new();
+    m.insert(
+        HashableValue::String(Rc::new("process".into())),
+        Value::ChildProcess(Arc::clone(proc)),
+    );
+    m.insert(
+        HashableValue::String(Rc::new("stream".into())),
+        Value::string(stream),
+    );
+    m.insert(
+        HashableValue::String(Rc::new("line".into())),
+        Value::Bool(line_mode),
+    );
+    m.insert(
+        HashableValue::String(Rc::new("max_bytes".into())),
+        Value::Int(max_bytes),
+    );
+    let metadata = Value::map(m);
+    let future = FutureState::pending_with_metadata(metadata, "process_read".to_string());
+    Ok(Value::Future(Rc::new(RefCell::new(future))))
+}
+
+/// proc.wait(timeout_ms?) - Wait for the process to exit (async)
+/// Resolves to the exit code. If the timeout elapses first, the process is
+/// killed and the future fails.
+fn child_process_wait(proc: &Arc<ChildProcessWrapper>, args: &[Value]) -> NativeResult {
+    let timeout_ms = match args {
+        [] => Value::Null,
+        [Value::Int(ms)] if *ms >= 0 => Value::Int(*ms),
+        [Value::Int(ms)] => {
+            return Err(format!(
+                "ChildProcess.wait() timeout must be non-negative, got {ms}"
+            ))
+        }
+        [other] => {
+            return Err(format!(
+                "ChildProcess.wait() timeout must be Int, got {}",
+                other.type_name()
+            ))
+        }
+        _ => {
+            return Err(format!(
+                "ChildProcess.wait() expects 0-1 arguments, got {}",
+                args.len()
+            ))
+        }
+    };
+
+    let mut m = ValueMap::new();
+    m.insert(
+        HashableValue::String(Rc::new("process".into())),
+        Value::ChildProcess(Arc::clone(proc)),
+    );
+    m.insert(
+        HashableValue::String(Rc::new("timeout_ms".into())),
+        timeout_ms,
+    );
+    let metadata = Value::map(m);
+    let future = FutureState::pending_with_metadata(metadata, "process_wait".to_string());
+    Ok(Value::Future(Rc::new(RefCell::new(future))))
+}
+
+/// Convert a process exit status to an exit code
+/// Processes terminated by a signal have no exit code and report -1.
+pub(crate) fn exit_status_code(status: std::process::ExitStatus) -> i64 {
+    status.code().map_or(-1, i64::from)
+}
+
+/// proc.try_wait() - Return the exit code if the process has exited, else null
+fn child_process_try_wait(proc: &Arc<ChildProcessWrapper>, args: &[Value]) -> NativeResult {
+    if !args.is_empty() {
+        return Err(format!(
+            "ChildProcess.try_wait() expects 0 arguments, got {}",
+            args.len()
+        ));
+    }
+    let mut child = proc
+        .child
+        .lock()
+        .map_err(|_| "ChildProcess lock poisoned".to_string())?;
+    match child.try_wait() {
+        Ok(Some(status)) => Ok(Value::Int(exit_status_code(status))),
+        Ok(None) => Ok(Value::Null),
+        Err(e) => Err(format!("failed to query process {}: {}", proc.pid, e)),
+    }
+}
+
+/// proc.kill() - Forcefully terminate the process
+/// Returns false if the process had already exited.
+fn child_process_kill(proc: &Arc<ChildProcessWrapper>, args: &[Value]) -> NativeResult {
+    if !args.is_empty() {
+        return Err(format!(
+            "ChildProcess.kill() expects 0 arguments, got {}",
+            args.len()
+        ));
+    }
+    let mut child = proc
+        .child
+        .lock()
+        .map_err(|_| "ChildProcess lock poisoned".to_string())?;
+    if let Ok(Some(_)) = child.try_wait() {
+        return Ok(Value::Bool(false));
+    }
+    child
+        .kill()
+        .map_err(|e| format!("failed to kill process {}: {}", proc.pid, e))?;
+    // Reap the process so it does not linger as a zombie
+    let _ = child.wait();
+    Ok(Value::Bool(true))
+}
+
 // ============================================================================
 // Signal Module
 // ============================================================================
@@ -4932,7 +8007,7 @@ fn signal_handle(args: &[Value]) -> NativeResult {
     // Note: Actual signal handling requires VM-level integration.
     // This registers the intent; the VM executor handles the actual signals.
     // For now, return the signal registration info.
-    let mut result = HashMap::new();
+    let mut result = ValueMap::new();
     result.insert(
         HashableValue::String(Rc::new("signal".to_string())),
         Value::string(&signal_name),
@@ -4942,7 +8017,7 @@ fn signal_handle(args: &[Value]) -> NativeResult {
         Value::Bool(true),
     );
 
-    Ok(Value::Map(Rc::new(std::cell::RefCell::new(result))))
+    Ok(Value::map(result))
 }
 
 // ============================================================================
@@ -5124,7 +8199,7 @@ fn db_duckdb(args: &[Value]) -> NativeResult {
 // Helper Functions for Map Access
 // -----------------------------------------------------------------------------
 
-fn get_map_string(map: &HashMap<HashableValue, Value>, key: &str) -> Option<String> {
+fn get_map_string(map: &ValueMap, key: &str) -> Option<String> {
     let key = HashableValue::String(Rc::new(key.to_string()));
     match map.get(&key) {
         Some(Value::String(s)) => Some(s.to_string()),
@@ -5132,7 +8207,7 @@ fn get_map_string(map: &HashMap<HashableValue, Value>, key: &str) -> Option<Stri
     }
 }
 
-fn get_map_int(map: &HashMap<HashableValue, Value>, key: &str) -> Option<i64> {
+fn get_map_int(map: &ValueMap, key: &str) -> Option<i64> {
     let key = HashableValue::String(Rc::new(key.to_string()));
     match map.get(&key) {
         Some(Value::Int(i)) => Some(*i),
@@ -5140,6 +8215,14 @@ fn get_map_int(map: &HashMap<HashableValue, Value>, key: &str) -> Option<i64> {
     }
 }
 
+fn get_map_bool(map: &ValueMap, key: &str) -> Option<bool> {
+    let key = HashableValue::String(Rc::new(key.to_string()));
+    match map.get(&key) {
+        Some(Value::Bool(b)) => Some(*b),
+        _ => None,
+    }
+}
+
 // -----------------------------------------------------------------------------
 // Connection Methods
 // -----------------------------------------------------------------------------
@@ -5435,7 +8518,7 @@ fn db_columns(conn: &Arc<DbConnection>, args: &[Value]) -> NativeResult {
 }
 
 fn column_to_map(name: String, type_: String, nullable: bool, primary_key: bool) -> Value {
-    let mut map = HashMap::new();
+    let mut map = ValueMap::new();
     map.insert(
         HashableValue::String(Rc::new("name".to_string())),
         Value::string(name),
@@ -5452,7 +8535,7 @@ fn column_to_map(name: String, type_: String, nullable: bool, primary_key: bool)
         HashableValue::String(Rc::new("primary_key".to_string())),
         Value::Bool(primary_key),
     );
-    Value::Map(Rc::new(RefCell::new(map)))
+    Value::map(map)
 }
 
 fn db_table_exists(conn: &Arc<DbConnection>, args: &[Value]) -> NativeResult {
@@ -5574,12 +8657,12 @@ fn sqlite_query(
 
     let rows = stmt
         .query_map(param_refs.as_slice(), |row| {
-            let mut map = HashMap::new();
+            let mut map = ValueMap::new();
             for (i, name) in column_names.iter().enumerate() {
                 let value = sqlite_value_to_stratum(row.get_ref(i)?);
                 map.insert(HashableValue::String(Rc::new(name.clone())), value);
             }
-            Ok(Value::Map(Rc::new(RefCell::new(map))))
+            Ok(Value::map(map))
         })
         .map_err(|e| format!("query error: {}", e))?;
 
@@ -5709,7 +8792,7 @@ fn postgres_execute(
 }
 
 fn postgres_row_to_stratum(row: &postgres::Row) -> Value {
-    let mut map = HashMap::new();
+    let mut map = ValueMap::new();
 
     for (i, column) in row.columns().iter().enumerate() {
         let name = column.name().to_string();
@@ -5717,7 +8800,7 @@ fn postgres_row_to_stratum(row: &postgres::Row) -> Value {
         map.insert(HashableValue::String(Rc::new(name)), value);
     }
 
-    Value::Map(Rc::new(RefCell::new(map)))
+    Value::map(map)
 }
 
 fn postgres_column_to_stratum(
@@ -5816,7 +8899,7 @@ fn mysql_execute(
 }
 
 fn mysql_row_to_stratum(row: &mysql::Row) -> Value {
-    let mut map = HashMap::new();
+    let mut map = ValueMap::new();
 
     for (i, column) in row.columns_ref().iter().enumerate() {
         let name = column.name_str().to_string();
@@ -5825,7 +8908,7 @@ fn mysql_row_to_stratum(row: &mysql::Row) -> Value {
         map.insert(HashableValue::String(Rc::new(name)), value);
     }
 
-    Value::Map(Rc::new(RefCell::new(map)))
+    Value::map(map)
 }
 
 fn mysql_value_to_stratum(value: mysql::Value) -> Value {
@@ -5890,7 +8973,7 @@ fn duckdb_query(
             // Get column count from statement reference in the rows
             let stmt_ref = row.as_ref();
             let col_count = stmt_ref.column_count();
-            let mut map: HashMap<HashableValue, Value> = HashMap::new();
+            let mut map: ValueMap = ValueMap::new();
             for i in 0..col_count {
                 let name = stmt_ref
                     .column_name(i)
@@ -5901,7 +8984,7 @@ fn duckdb_query(
                 );
                 map.insert(HashableValue::String(Rc::new(name)), value);
             }
-            Ok(Value::Map(Rc::new(RefCell::new(map))))
+            Ok(Value::map(map))
         })
         .map_err(|e| format!("query error: {}", e))?;
 
@@ -6048,7 +9131,7 @@ fn async_all(args: &[Value]) -> NativeResult {
                     ));
                 }
             }
-            Value::List(Rc::new(RefCell::new(items.clone())))
+            Value::list(items.clone())
         }
         _ => {
             return Err(format!(
@@ -6084,7 +9167,7 @@ fn async_race(args: &[Value]) -> NativeResult {
                     ));
                 }
             }
-            Value::List(Rc::new(RefCell::new(items.clone())))
+            Value::list(items.clone())
         }
         _ => {
             return Err(format!(
@@ -6127,7 +9210,7 @@ fn async_timeout(args: &[Value]) -> NativeResult {
     };
 
     // Store both the future and timeout in a map
-    let mut metadata_map = std::collections::HashMap::new();
+    let mut metadata_map = ValueMap::new();
     metadata_map.insert(
         HashableValue::String(Rc::new("future".to_string())),
         inner_future,
@@ -6136,7 +9219,7 @@ fn async_timeout(args: &[Value]) -> NativeResult {
         HashableValue::String(Rc::new("ms".to_string())),
         Value::Int(timeout_ms),
     );
-    let metadata = Value::Map(Rc::new(RefCell::new(metadata_map)));
+    let metadata = Value::map(metadata_map);
 
     let future = FutureState::pending_with_metadata(metadata, "timeout".to_string());
     Ok(Value::Future(Rc::new(RefCell::new(future))))
@@ -6284,8 +9367,8 @@ fn tcp_stream_read(stream: &Arc<TcpStreamWrapper>, args: &[Value]) -> NativeResu
     };
 
     // Create metadata with stream reference and buffer size
-    let metadata = Value::Map(Rc::new(RefCell::new({
-        let mut m = HashMap::new();
+    let metadata = Value::map({
+        let mut m = ValueMap::new();
         m.insert(
             HashableValue::String(Rc::new("stream_addr".into())),
             Value::string(&stream.local_addr),
@@ -6299,7 +9382,7 @@ fn tcp_stream_read(stream: &Arc<TcpStreamWrapper>, args: &[Value]) -> NativeResu
             Value::Int(max_bytes as i64),
         );
         m
-    })));
+    });
 
     // Store the actual stream Arc in a static map keyed by address for executor to retrieve
     // For now, we use a simpler approach: create a pending future with metadata
@@ -6336,14 +9419,14 @@ fn tcp_stream_read_exact(stream: &Arc<TcpStreamWrapper>, args: &[Value]) -> Nati
     };
 
     let future = FutureState::pending_with_metadata(
-        Value::Map(Rc::new(RefCell::new({
-            let mut m = HashMap::new();
+        Value::map({
+            let mut m = ValueMap::new();
             m.insert(
                 HashableValue::String(Rc::new("num_bytes".into())),
                 Value::Int(num_bytes as i64),
             );
             m
-        }))),
+        }),
         "tcp_read_exact".to_string(),
     );
     let future_ref = Rc::new(RefCell::new(future));
@@ -6512,15 +9595,15 @@ fn udp_socket_send_to(socket: &Arc<UdpSocketWrapper>, args: &[Value]) -> NativeR
     };
 
     // Store socket in metadata, data/addr map in result for the executor
-    let data_map = Value::Map(Rc::new(RefCell::new({
-        let mut m = HashMap::new();
+    let data_map = Value::map({
+        let mut m = ValueMap::new();
         m.insert(HashableValue::String(Rc::new("data".into())), data);
         m.insert(
             HashableValue::String(Rc::new("addr".into())),
             Value::string(format!("{host}:{port}")),
         );
         m
-    })));
+    });
 
     let mut future = FutureState::pending_with_metadata(
         Value::UdpSocket(Arc::clone(socket)),
@@ -6942,6 +10025,8 @@ pub fn data_method(method: &str, args: &[Value]) -> NativeResult {
         // Parallel configuration
         "set_parallel_threshold" => data_set_parallel_threshold(args),
         "parallel_threshold" => data_parallel_threshold(args),
+        "set_threads" => data_set_threads(args),
+        "threads" => data_threads(args),
         _ => Err(format!("Data has no method '{method}'")),
     }
 }
@@ -6972,6 +10057,35 @@ fn data_parallel_threshold(_args: &[Value]) -> NativeResult {
     Ok(Value::Int(parallel_threshold() as i64))
 }
 
+/// Set the number of threads used by DataFrame operations
+fn data_set_threads(args: &[Value]) -> NativeResult {
+    use crate::data::set_threads;
+
+    if args.len() != 1 {
+        return Err(format!(
+            "Data.set_threads expects 1 argument, got {}",
+            args.len()
+        ));
+    }
+    match &args[0] {
+        Value::Int(n) if *n < 0 => Err(format!(
+            "Data.set_threads expects a non-negative thread count, got {n}"
+        )),
+        Value::Int(n) => {
+            set_threads(*n as usize).map_err(|e| e.to_string())?;
+            Ok(Value::Null)
+        }
+        _ => Err("Data.set_threads expects an Int".to_string()),
+    }
+}
+
+/// Get the number of threads used by DataFrame operations
+fn data_threads(_args: &[Value]) -> NativeResult {
+    use crate::data::threads;
+
+    Ok(Value::Int(threads() as i64))
+}
+
 /// Create a DataFrame from a list of maps (each map is a row)
 fn data_frame(args: &[Value]) -> NativeResult {
     use std::sync::Arc;
@@ -7463,7 +10577,7 @@ fn sql_context_tables(ctx: &std::sync::Arc<std::sync::Mutex<SqlContext>>) -> Nat
         .into_iter()
         .map(|s| Value::String(std::rc::Rc::new(s)))
         .collect();
-    Ok(Value::List(std::rc::Rc::new(std::cell::RefCell::new(list))))
+    Ok(Value::list(list))
 }
 
 // ============================================================================
@@ -7776,6 +10890,7 @@ fn parse_join_cols_args(args: &[Value], method: &str) -> Result<(String, String)
 pub fn cube_method(method: &str, args: &[Value]) -> NativeResult {
     match method {
         "from" => cube_from(args),
+        "load" => cube_load(args),
         _ => Err(format!("Cube has no method '{method}'")),
     }
 }
@@ -7829,6 +10944,27 @@ fn cube_from(args: &[Value]) -> NativeResult {
     }
 }
 
+/// Cube.load(path) - Load a cube saved with cube.save(path)
+fn cube_load(args: &[Value]) -> NativeResult {
+    let path = match args {
+        [Value::String(s)] => s.as_str(),
+        [other] => {
+            return Err(format!(
+                "Cube.load expects a file path (String), got {}",
+                other.type_name()
+            ))
+        }
+        _ => {
+            return Err(format!(
+                "Cube.load expects 1 argument (path), got {}",
+                args.len()
+            ))
+        }
+    };
+    let cube = crate::data::Cube::load(path).map_err(|e| e.to_string())?;
+    Ok(Value::Cube(std::sync::Arc::new(cube)))
+}
+
 // ============================================================================
 // Set Module
 // ============================================================================
@@ -7966,7 +11102,7 @@ fn test_mock(args: &[Value]) -> NativeResult {
         args[0].clone()
     };
 
-    let mut mock = HashMap::new();
+    let mut mock = ValueMap::new();
     // Marker to identify this as a mock
     mock.insert(
         HashableValue::String(Rc::new("__is_mock".to_string())),
@@ -7988,7 +11124,7 @@ fn test_mock(args: &[Value]) -> NativeResult {
         Value::Int(0),
     );
 
-    Ok(Value::Map(Rc::new(RefCell::new(mock))))
+    Ok(Value::map(mock))
 }
 
 /// Test.spy(fn?) -> Spy
@@ -8002,7 +11138,7 @@ fn test_spy(args: &[Value]) -> NativeResult {
         args[0].clone()
     };
 
-    let mut spy = HashMap::new();
+    let mut spy = ValueMap::new();
     // Marker to identify this as a spy
     spy.insert(
         HashableValue::String(Rc::new("__is_spy".to_string())),
@@ -8024,7 +11160,7 @@ fn test_spy(args: &[Value]) -> NativeResult {
         Value::Int(0),
     );
 
-    Ok(Value::Map(Rc::new(RefCell::new(spy))))
+    Ok(Value::map(spy))
 }
 
 // ============================================================================
@@ -8696,14 +11832,18 @@ pub fn dispatch_namespace_method(namespace: &str, method: &str, args: &[Value])
         "Json" => json_method(method, args),
         "Toml" => toml_method(method, args),
         "Yaml" => yaml_method(method, args),
+        "Csv" => csv_method(method, args),
         "Base64" => base64_method(method, args),
         "Url" => url_method(method, args),
         "Gzip" => gzip_method(method, args),
         "Zip" => zip_method(method, args),
+        "Zstd" => zstd_method(method, args),
+        "Tar" => tar_method(method, args),
         "DateTime" => datetime_method(method, args),
         "Duration" => duration_method(method, args),
         "Time" => time_method(method, args),
         "Regex" => regex_method(method, args),
+        "Template" => template_method(method, args),
         "Hash" => hash_method(method, args),
         "Crypto" => crypto_method(method, args),
         "Uuid" => uuid_method(method, args),
@@ -8711,6 +11851,7 @@ pub fn dispatch_namespace_method(namespace: &str, method: &str, args: &[Value])
         "Math" => math_method(method, args),
         "Input" => input_method(method, args),
         "Log" => log_method(method, args),
+        "I18n" => i18n_method(method, args),
         "System" => system_method(method, args),
         "Process" => process_method(method, args),
         "Signal" => signal_method(method, args),
@@ -8734,6 +11875,7 @@ pub fn dispatch_namespace_method(namespace: &str, method: &str, args: &[Value])
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::bytecode::StructTypeInfo;
     use std::fs;
     use tempfile::tempdir;
 
@@ -9002,6 +12144,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_args_program_args() {
+        set_program_args(vec!["input.txt".to_string(), "--verbose".to_string()]);
+        assert!(matches!(args_method("count", &[]).unwrap(), Value::Int(2)));
+        let arg = args_method("get", &[Value::Int(1)]).unwrap();
+        assert_eq!(arg.to_string(), "--verbose");
+        assert!(matches!(
+            args_method("get", &[Value::Int(2)]).unwrap(),
+            Value::Null
+        ));
+    }
+
     // ============================================================================
     // Shell Module Tests
     // ============================================================================
@@ -9082,34 +12236,34 @@ mod tests {
         assert!(result.unwrap_err().contains("must be Map"));
 
         // Test with valid empty options
-        let empty_map = Value::Map(Rc::new(RefCell::new(HashMap::new())));
+        let empty_map = Value::empty_map();
         let (headers, timeout) = extract_http_options(&empty_map).unwrap();
         assert!(headers.is_empty());
         assert!(timeout.is_none());
 
         // Test with timeout option
-        let mut map = HashMap::new();
+        let mut map = ValueMap::new();
         map.insert(
             HashableValue::String(Rc::new("timeout".to_string())),
             Value::Int(5000),
         );
-        let options_map = Value::Map(Rc::new(RefCell::new(map)));
+        let options_map = Value::map(map);
         let (headers, timeout) = extract_http_options(&options_map).unwrap();
         assert!(headers.is_empty());
         assert_eq!(timeout, Some(5000));
 
         // Test with headers option
-        let mut headers_map = HashMap::new();
+        let mut headers_map = ValueMap::new();
         headers_map.insert(
             HashableValue::String(Rc::new("Content-Type".to_string())),
             Value::string("application/json"),
         );
-        let mut map = HashMap::new();
+        let mut map = ValueMap::new();
         map.insert(
             HashableValue::String(Rc::new("headers".to_string())),
-            Value::Map(Rc::new(RefCell::new(headers_map))),
+            Value::map(headers_map),
         );
-        let options_map = Value::Map(Rc::new(RefCell::new(map)));
+        let options_map = Value::map(map);
         let (headers, timeout) = extract_http_options(&options_map).unwrap();
         assert_eq!(
             headers.get("Content-Type"),
@@ -9253,7 +12407,7 @@ mod tests {
 
     #[test]
     fn test_json_encode_map() {
-        let mut map = HashMap::new();
+        let mut map = ValueMap::new();
         map.insert(
             HashableValue::String(Rc::new("name".to_string())),
             Value::string("test"),
@@ -9262,7 +12416,7 @@ mod tests {
             HashableValue::String(Rc::new("value".to_string())),
             Value::Int(42),
         );
-        let map_value = Value::Map(Rc::new(RefCell::new(map)));
+        let map_value = Value::map(map);
 
         let result = json_method("encode", &[map_value]).unwrap();
         if let Value::String(s) = result {
@@ -9298,66 +12452,318 @@ mod tests {
     }
 
     #[test]
-    fn test_json_decode_array() {
-        let result = json_method("decode", &[Value::string("[1, 2, 3]")]).unwrap();
-        if let Value::List(list) = result {
-            let list = list.borrow();
-            assert_eq!(list.len(), 3);
-            assert_eq!(list[0], Value::Int(1));
-            assert_eq!(list[1], Value::Int(2));
-            assert_eq!(list[2], Value::Int(3));
-        } else {
-            panic!("Expected List");
-        }
+    fn test_json_decode_array() {
+        let result = json_method("decode", &[Value::string("[1, 2, 3]")]).unwrap();
+        if let Value::List(list) = result {
+            let list = list.borrow();
+            assert_eq!(list.len(), 3);
+            assert_eq!(list[0], Value::Int(1));
+            assert_eq!(list[1], Value::Int(2));
+            assert_eq!(list[2], Value::Int(3));
+        } else {
+            panic!("Expected List");
+        }
+    }
+
+    #[test]
+    fn test_json_decode_object() {
+        let result = json_method(
+            "decode",
+            &[Value::string("{\"name\": \"test\", \"value\": 42}")],
+        )
+        .unwrap();
+        if let Value::Map(map) = result {
+            let map = map.borrow();
+            let name_key = HashableValue::String(Rc::new("name".to_string()));
+            let value_key = HashableValue::String(Rc::new("value".to_string()));
+            assert_eq!(map.get(&name_key), Some(&Value::string("test")));
+            assert_eq!(map.get(&value_key), Some(&Value::Int(42)));
+        } else {
+            panic!("Expected Map");
+        }
+    }
+
+    #[test]
+    fn test_json_roundtrip() {
+        let original = Value::list(vec![
+            Value::Int(1),
+            Value::string("hello"),
+            Value::Bool(true),
+            Value::Null,
+        ]);
+        let encoded = json_method("encode", &[original.clone()]).unwrap();
+        let decoded = json_method("decode", &[encoded]).unwrap();
+        assert_eq!(original, decoded);
+    }
+
+    #[test]
+    fn test_json_decode_invalid() {
+        let result = json_method("decode", &[Value::string("invalid json")]);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("failed to parse JSON"));
+    }
+
+    #[test]
+    fn test_json_wrong_args() {
+        // Missing args
+        let result = json_method("encode", &[]);
+        assert!(result.is_err());
+
+        // Too many args
+        let result = json_method("encode", &[Value::Int(1), Value::Int(2)]);
+        assert!(result.is_err());
+    }
+
+    fn json_error_paths(result: &Value) -> Vec<(String, String)> {
+        let Value::Map(map) = result else {
+            panic!("Expected Map");
+        };
+        let map = map.borrow();
+        let errors = map
+            .get(&HashableValue::String(Rc::new("errors".to_string())))
+            .unwrap();
+        let Value::List(errors) = errors else {
+            panic!("Expected List");
+        };
+        errors
+            .borrow()
+            .iter()
+            .map(|e| match e {
+                Value::Map(m) => (
+                    get_map_string(&m.borrow(), "path").unwrap(),
+                    get_map_string(&m.borrow(), "message").unwrap(),
+                ),
+                _ => panic!("Expected Map"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_json_validate_reports_paths() {
+        let schema = Value::string(
+            r##"{
+                "type": "object",
+                "required": ["id", "email"],
+                "additionalProperties": false,
+                "$defs": {"tag": {"type": "string", "minLength": 2}},
+                "properties": {
+                    "id": {"type": "integer", "minimum": 1},
+                    "email": {"type": "string", "format": "email"},
+                    "tags": {"type": "array", "items": {"$ref": "#/$defs/tag"}}
+                }
+            }"##,
+        );
+        let value = json_method(
+            "decode",
+            &[Value::string(
+                r#"{"id": 0, "tags": ["ok", "x", 3], "extra": 1}"#,
+            )],
+        )
+        .unwrap();
+
+        let result = json_method("validate", &[value, schema]).unwrap();
+        let errors = json_error_paths(&result);
+        assert!(errors.contains(&(
+            "$.email".to_string(),
+            "required property is missing".to_string()
+        )));
+        assert!(errors.contains(&(
+            "$.id".to_string(),
+            "value 0 is less than minimum 1".to_string()
+        )));
+        assert!(errors.contains(&(
+            "$.tags[1]".to_string(),
+            "string is shorter than 2 characters".to_string()
+        )));
+        assert!(errors.contains(&(
+            "$.tags[2]".to_string(),
+            "expected string, got integer".to_string()
+        )));
+        assert!(errors.contains(&(
+            "$.extra".to_string(),
+            "additional property is not allowed".to_string()
+        )));
+    }
+
+    #[test]
+    fn test_json_validate_valid_and_combinators() {
+        let schema = json_method(
+            "decode",
+            &[Value::string(
+                r#"{"anyOf": [{"type": "string"}, {"type": "array", "items": {"enum": [1, 2]}}]}"#,
+            )],
+        )
+        .unwrap();
+
+        let ok = json_method(
+            "validate",
+            &[
+                Value::list(vec![Value::Int(1), Value::Int(2)]),
+                schema.clone(),
+            ],
+        )
+        .unwrap();
+        assert!(json_error_paths(&ok).is_empty());
+        if let Value::Map(map) = &ok {
+            assert_eq!(get_map_bool(&map.borrow(), "valid"), Some(true));
+        }
+
+        let bad = json_method("validate", &[Value::Int(5), schema]).unwrap();
+        assert_eq!(
+            json_error_paths(&bad),
+            vec![(
+                "$".to_string(),
+                "value does not match any of the allowed schemas".to_string()
+            )]
+        );
+    }
+
+    fn user_struct_types() -> (Rc<StructTypeInfo>, Rc<StructTypeInfo>) {
+        let address = Rc::new(StructTypeInfo {
+            name: "Address".to_string(),
+            fields: vec![
+                ("city".to_string(), FieldType::String),
+                ("zip".to_string(), FieldType::Int),
+            ],
+            derives: Vec::new(),
+        });
+        let user = Rc::new(StructTypeInfo {
+            name: "User".to_string(),
+            fields: vec![
+                ("name".to_string(), FieldType::String),
+                ("score".to_string(), FieldType::Float),
+                (
+                    "tags".to_string(),
+                    FieldType::List(Box::new(FieldType::String)),
+                ),
+                (
+                    "address".to_string(),
+                    FieldType::Nullable(Box::new(FieldType::Named("Address".to_string()))),
+                ),
+            ],
+            derives: Vec::new(),
+        });
+        (user, address)
+    }
+
+    #[test]
+    fn test_json_decode_as_struct() {
+        let (user, address) = user_struct_types();
+        let resolve = |name: &str| (name == "Address").then(|| Value::StructType(address.clone()));
+        let json = r#"{"name": "Ada", "score": 9, "tags": ["a"], "address": {"city": "Oslo", "zip": 150}}"#;
+
+        let result =
+            json_decode_as(&[Value::string(json), Value::StructType(user)], &resolve).unwrap();
+        let Value::Struct(instance) = result else {
+            panic!("Expected Struct");
+        };
+        let instance = instance.borrow();
+        assert_eq!(instance.type_name, "User");
+        assert_eq!(instance.fields["score"], Value::Float(9.0));
+        let Value::Struct(addr) = &instance.fields["address"] else {
+            panic!("Expected nested Struct");
+        };
+        assert_eq!(addr.borrow().type_name, "Address");
+        assert_eq!(addr.borrow().fields["zip"], Value::Int(150));
     }
 
     #[test]
-    fn test_json_decode_object() {
-        let result = json_method(
-            "decode",
-            &[Value::string("{\"name\": \"test\", \"value\": 42}")],
+    fn test_json_decode_as_error_paths() {
+        let (user, address) = user_struct_types();
+        let resolve = |name: &str| (name == "Address").then(|| Value::StructType(address.clone()));
+
+        let wrong_type = r#"{"name": "Ada", "score": 1.5, "tags": ["a"], "address": {"city": "Oslo", "zip": "0150"}}"#;
+        let err = json_decode_as(
+            &[Value::string(wrong_type), Value::StructType(user.clone())],
+            &resolve,
         )
-        .unwrap();
-        if let Value::Map(map) = result {
-            let map = map.borrow();
-            let name_key = HashableValue::String(Rc::new("name".to_string()));
-            let value_key = HashableValue::String(Rc::new("value".to_string()));
-            assert_eq!(map.get(&name_key), Some(&Value::string("test")));
-            assert_eq!(map.get(&value_key), Some(&Value::Int(42)));
-        } else {
-            panic!("Expected Map");
-        }
+        .unwrap_err();
+        assert_eq!(err, "$.address.zip: expected Int, got string");
+
+        let bad_item = r#"{"name": "Ada", "score": 1.5, "tags": ["a", 2]}"#;
+        let err = json_decode_as(
+            &[Value::string(bad_item), Value::StructType(user.clone())],
+            &resolve,
+        )
+        .unwrap_err();
+        assert_eq!(err, "$.tags[1]: expected String, got integer");
+
+        let missing = r#"{"score": 1.5, "tags": []}"#;
+        let err = json_decode_as(&[Value::string(missing), Value::StructType(user)], &resolve)
+            .unwrap_err();
+        assert_eq!(err, "$.name: missing required field of type String");
+    }
+
+    fn shape_enum_type() -> Rc<EnumTypeInfo> {
+        Rc::new(EnumTypeInfo {
+            name: "Shape".to_string(),
+            variants: vec![
+                ("Dot".to_string(), VariantFields::Unit),
+                ("Circle".to_string(), VariantFields::Tuple(FieldType::Float)),
+                (
+                    "Rect".to_string(),
+                    VariantFields::Struct(vec![
+                        ("w".to_string(), FieldType::Int),
+                        ("h".to_string(), FieldType::Int),
+                    ]),
+                ),
+            ],
+            derives: Vec::new(),
+        })
     }
 
     #[test]
-    fn test_json_roundtrip() {
-        let original = Value::list(vec![
-            Value::Int(1),
-            Value::string("hello"),
-            Value::Bool(true),
-            Value::Null,
-        ]);
-        let encoded = json_method("encode", &[original.clone()]).unwrap();
-        let decoded = json_method("decode", &[encoded]).unwrap();
-        assert_eq!(original, decoded);
+    fn test_json_enum_variant_round_trip() {
+        let shape = Value::EnumType(shape_enum_type());
+        let circle = Value::EnumVariant(Rc::new(EnumVariantInstance::new(
+            "Shape".to_string(),
+            "Circle".to_string(),
+            Some(Value::Float(1.5)),
+        )));
+        let dot = Value::EnumVariant(Rc::new(EnumVariantInstance::new(
+            "Shape".to_string(),
+            "Dot".to_string(),
+            None,
+        )));
+
+        for (value, expected) in [(circle, r#"{"Circle":1.5}"#), (dot, r#""Dot""#)] {
+            let encoded = json_method("encode", &[value.clone()]).unwrap();
+            assert_eq!(encoded, Value::string(expected));
+            let decoded = json_decode_type(&shape, &[encoded], &|_| None).unwrap();
+            assert_eq!(decoded, value);
+        }
     }
 
     #[test]
-    fn test_json_decode_invalid() {
-        let result = json_method("decode", &[Value::string("invalid json")]);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().contains("failed to parse JSON"));
+    fn test_json_decode_enum_struct_variant_and_errors() {
+        let shape = Value::EnumType(shape_enum_type());
+
+        let rect = json_decode_type(
+            &shape,
+            &[Value::string(r#"{"Rect": {"w": 2, "h": 3}}"#)],
+            &|_| None,
+        )
+        .unwrap();
+        let Value::EnumVariant(rect) = rect else {
+            panic!("Expected EnumVariant");
+        };
+        assert_eq!(rect.variant_name, "Rect");
+        let Some(Value::Struct(fields)) = &rect.data else {
+            panic!("Expected struct payload");
+        };
+        assert_eq!(fields.borrow().fields["h"], Value::Int(3));
+
+        let err = json_decode_type(&shape, &[Value::string(r#""Square""#)], &|_| None).unwrap_err();
+        assert_eq!(err, "$: unknown Shape variant 'Square'");
+        let err = json_decode_type(&shape, &[Value::string(r#"{"Circle": "big"}"#)], &|_| None)
+            .unwrap_err();
+        assert_eq!(err, "$.Circle: expected Float, got string");
     }
 
     #[test]
-    fn test_json_wrong_args() {
-        // Missing args
-        let result = json_method("encode", &[]);
-        assert!(result.is_err());
-
-        // Too many args
-        let result = json_method("encode", &[Value::Int(1), Value::Int(2)]);
-        assert!(result.is_err());
+    fn test_json_decode_as_requires_struct_type() {
+        let err = json_method("decode_as", &[Value::string("{}"), Value::Int(1)]).unwrap_err();
+        assert!(err.contains("expects a struct type"));
     }
 
     // ============================================================================
@@ -9367,12 +12773,12 @@ mod tests {
     #[test]
     fn test_toml_encode_primitives() {
         // Note: TOML requires a table structure at the root for encoding
-        let mut map = HashMap::new();
+        let mut map = ValueMap::new();
         map.insert(
             HashableValue::String(Rc::new("value".to_string())),
             Value::Int(42),
         );
-        let map_value = Value::Map(Rc::new(RefCell::new(map)));
+        let map_value = Value::map(map);
 
         let result = toml_method("encode", &[map_value]).unwrap();
         if let Value::String(s) = result {
@@ -9507,6 +12913,227 @@ mod tests {
         assert!(result.is_err());
     }
 
+    // ============================================================================
+    // Csv Module Tests
+    // ============================================================================
+
+    fn csv_opts(pairs: &[(&str, Value)]) -> Value {
+        let mut map = ValueMap::new();
+        for (key, value) in pairs {
+            map.insert(
+                HashableValue::String(Rc::new((*key).to_string())),
+                value.clone(),
+            );
+        }
+        Value::map(map)
+    }
+
+    fn map_field(row: &Value, key: &str) -> Value {
+        let Value::Map(map) = row else {
+            panic!("Expected Map, got {row:?}");
+        };
+        let key = HashableValue::String(Rc::new(key.to_string()));
+        map.borrow().get(&key).cloned().unwrap_or(Value::Null)
+    }
+
+    #[test]
+    fn test_csv_parse_with_headers() {
+        let text = "name,age\nAlice,30\n\"Smith, Bob\",25\n";
+        let rows = csv_method("parse", &[Value::string(text)]).unwrap();
+        let Value::List(rows) = rows else {
+            panic!("Expected List");
+        };
+        let rows = rows.borrow();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(map_field(&rows[0], "name"), Value::string("Alice"));
+        assert_eq!(map_field(&rows[1], "name"), Value::string("Smith, Bob"));
+        assert_eq!(map_field(&rows[1], "age"), Value::string("25"));
+    }
+
+    #[test]
+    fn test_csv_parse_custom_delimiter_without_headers() {
+        let opts = csv_opts(&[
+            ("delimiter", Value::string("\t")),
+            ("headers", Value::Bool(false)),
+            ("trim", Value::Bool(true)),
+        ]);
+        let rows = csv_method("parse", &[Value::string("a\t b \n1\t2\n"), opts]).unwrap();
+        assert_eq!(
+            rows,
+            Value::list(vec![
+                Value::list(vec![Value::string("a"), Value::string("b")]),
+                Value::list(vec![Value::string("1"), Value::string("2")]),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_csv_parse_ragged_rows() {
+        let text = "a,b\n1,2,3\n";
+        let err = csv_method("parse", &[Value::string(text)]).unwrap_err();
+        assert!(err.contains("failed to parse CSV"));
+
+        let opts = csv_opts(&[("flexible", Value::Bool(true))]);
+        let rows = csv_method("parse", &[Value::string("a,b\n1\n"), opts]).unwrap();
+        let Value::List(rows) = rows else {
+            panic!("Expected List");
+        };
+        assert_eq!(map_field(&rows.borrow()[0], "b"), Value::Null);
+    }
+
+    #[test]
+    fn test_csv_parse_line() {
+        let row = csv_method(
+            "parse_line",
+            &[
+                Value::string("x;'a;b';z"),
+                csv_opts(&[
+                    ("delimiter", Value::string(";")),
+                    ("quote", Value::string("'")),
+                ]),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            row,
+            Value::list(vec![
+                Value::string("x"),
+                Value::string("a;b"),
+                Value::string("z")
+            ])
+        );
+
+        let err = csv_method(
+            "parse_line",
+            &[
+                Value::string("x"),
+                csv_opts(&[("delimiter", Value::string("::"))]),
+            ],
+        )
+        .unwrap_err();
+        assert!(err.contains("single ASCII character"));
+    }
+
+    #[test]
+    fn test_csv_stringify_lists_and_maps() {
+        let rows = Value::list(vec![
+            Value::list(vec![Value::string("a,b"), Value::Int(1), Value::Null]),
+            Value::list(vec![
+                Value::string("plain"),
+                Value::Float(2.5),
+                Value::Bool(true),
+            ]),
+        ]);
+        let text = csv_method("stringify", &[rows]).unwrap();
+        assert_eq!(text, Value::string("\"a,b\",1,\nplain,2.5,true\n"));
+
+        let rows = Value::list(vec![
+            csv_opts(&[("name", Value::string("Ann")), ("age", Value::Int(41))]),
+            csv_opts(&[("name", Value::string("Ben"))]),
+        ]);
+        let opts = csv_opts(&[(
+            "columns",
+            Value::list(vec![Value::string("name"), Value::string("age")]),
+        )]);
+        let text = csv_method("stringify", &[rows.clone(), opts]).unwrap();
+        assert_eq!(text, Value::string("name,age\nAnn,41\nBen,\n"));
+
+        // Without explicit columns, Map keys are sorted
+        let text = csv_method("stringify", &[rows]).unwrap();
+        assert_eq!(text, Value::string("age,name\n41,Ann\n,Ben\n"));
+    }
+
+    #[test]
+    fn test_csv_streaming_read_write() {
+        let dir = tempdir().unwrap();
+        let input = dir.path().join("in.csv");
+        let output = dir.path().join("out.csv");
+        fs::write(&input, "id,amount\n1,10\n2,20\n3,30\n").unwrap();
+
+        let reader = csv_method("open", &[Value::string(input.to_string_lossy())]).unwrap();
+        let Value::CsvReader(reader) = reader else {
+            panic!("Expected CsvReader");
+        };
+        let writer = csv_method(
+            "create",
+            &[
+                Value::string(output.to_string_lossy()),
+                csv_opts(&[(
+                    "columns",
+                    Value::list(vec![Value::string("id"), Value::string("doubled")]),
+                )]),
+            ],
+        )
+        .unwrap();
+        let Value::CsvWriter(writer) = writer else {
+            panic!("Expected CsvWriter");
+        };
+
+        assert_eq!(
+            csv_reader_method(&reader, "headers", &[]).unwrap(),
+            Value::list(vec![Value::string("id"), Value::string("amount")])
+        );
+
+        loop {
+            let row = csv_reader_method(&reader, "next", &[]).unwrap();
+            if row == Value::Null {
+                break;
+            }
+            let Value::String(amount) = map_field(&row, "amount") else {
+                panic!("Expected String");
+            };
+            let doubled = amount.parse::<i64>().unwrap() * 2;
+            let out = csv_opts(&[
+                ("id", map_field(&row, "id")),
+                ("doubled", Value::Int(doubled)),
+            ]);
+            csv_writer_method(&writer, "write", &[out]).unwrap();
+        }
+        csv_writer_method(&writer, "close", &[]).unwrap();
+        csv_reader_method(&reader, "close", &[]).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&output).unwrap(),
+            "id,doubled\n1,20\n2,40\n3,60\n"
+        );
+        assert!(csv_reader_method(&reader, "next", &[])
+            .unwrap_err()
+            .contains("closed"));
+        assert!(csv_writer_method(&writer, "write", &[Value::list(vec![])])
+            .unwrap_err()
+            .contains("closed"));
+    }
+
+    #[test]
+    fn test_csv_append_skips_header_for_existing_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("log.csv");
+        let path_str = path.to_string_lossy().to_string();
+        let opts = csv_opts(&[
+            ("append", Value::Bool(true)),
+            ("columns", Value::list(vec![Value::string("event")])),
+        ]);
+
+        for event in ["start", "stop"] {
+            let Value::CsvWriter(writer) =
+                csv_method("create", &[Value::string(&path_str), opts.clone()]).unwrap()
+            else {
+                panic!("Expected CsvWriter");
+            };
+            let row = csv_opts(&[("event", Value::string(event))]);
+            csv_writer_method(&writer, "write_all", &[Value::list(vec![row])]).unwrap();
+            csv_writer_method(&writer, "close", &[]).unwrap();
+        }
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "event\nstart\nstop\n");
+    }
+
+    #[test]
+    fn test_csv_unknown_method() {
+        let result = csv_method("nope", &[]);
+        assert!(result.unwrap_err().contains("Csv has no method 'nope'"));
+    }
+
     // ============================================================================
     // Base64 Module Tests
     // ============================================================================
@@ -9804,19 +13431,296 @@ mod tests {
         let file_path = dir.path().join("readme.txt");
         fs::write(&file_path, "Hello from zip!").unwrap();
 
-        let zip_path = dir.path().join("test.zip");
-        let zip_path_str = zip_path.to_string_lossy().to_string();
-        let files = Value::list(vec![Value::string(file_path.to_string_lossy())]);
+        let zip_path = dir.path().join("test.zip");
+        let zip_path_str = zip_path.to_string_lossy().to_string();
+        let files = Value::list(vec![Value::string(file_path.to_string_lossy())]);
+
+        zip_method("create", &[Value::string(&zip_path_str), files]).unwrap();
+
+        // Read file directly from zip
+        let content = zip_method(
+            "read_text",
+            &[Value::string(&zip_path_str), Value::string("readme.txt")],
+        )
+        .unwrap();
+        assert_eq!(content, Value::string("Hello from zip!"));
+    }
+
+    #[test]
+    fn test_zip_extract_rejects_path_traversal() {
+        let dir = tempdir().unwrap();
+        let zip_path = dir.path().join("evil.zip");
+        {
+            let file = File::create(&zip_path).unwrap();
+            let mut writer = zip::ZipWriter::new(file);
+            writer
+                .start_file("../evil.txt", zip::write::SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(b"pwned").unwrap();
+            writer.finish().unwrap();
+        }
+
+        let extract_dir = dir.path().join("out");
+        let err = zip_method(
+            "extract",
+            &[
+                Value::string(zip_path.to_string_lossy()),
+                Value::string(extract_dir.to_string_lossy()),
+            ],
+        )
+        .unwrap_err();
+        assert!(err.contains("escapes the output directory"));
+        assert!(!dir.path().join("evil.txt").exists());
+    }
+
+    #[test]
+    fn test_gzip_compress_file_roundtrip() {
+        let dir = tempdir().unwrap();
+        let input = dir.path().join("input.txt");
+        let compressed = dir.path().join("input.txt.gz");
+        let output = dir.path().join("output.txt");
+        fs::write(&input, "streamed ".repeat(1000)).unwrap();
+
+        gzip_method(
+            "compress_file",
+            &[
+                Value::string(input.to_string_lossy()),
+                Value::string(compressed.to_string_lossy()),
+            ],
+        )
+        .unwrap();
+        gzip_method(
+            "decompress_file",
+            &[
+                Value::string(compressed.to_string_lossy()),
+                Value::string(output.to_string_lossy()),
+            ],
+        )
+        .unwrap();
+
+        assert!(fs::metadata(&compressed).unwrap().len() < fs::metadata(&input).unwrap().len());
+        assert_eq!(
+            fs::read_to_string(output).unwrap(),
+            "streamed ".repeat(1000)
+        );
+    }
+
+    // ============================================================================
+    // Zstd Module Tests
+    // ============================================================================
+
+    #[test]
+    fn test_zstd_compress_decompress() {
+        let input = Value::list(vec![Value::Int(1), Value::Int(2), Value::Int(3)]);
+        let compressed = zstd_method("compress", &[input.clone()]).unwrap();
+        let decompressed = zstd_method("decompress", &[compressed]).unwrap();
+        assert_eq!(decompressed, input);
+    }
+
+    #[test]
+    fn test_zstd_compress_text_with_level() {
+        let compressed = zstd_method(
+            "compress_text",
+            &[Value::string("Hello, Stratum!"), Value::Int(19)],
+        )
+        .unwrap();
+        let decompressed = zstd_method("decompress_text", &[compressed]).unwrap();
+        assert_eq!(decompressed, Value::string("Hello, Stratum!"));
+
+        let err = zstd_method("compress_text", &[Value::string("x"), Value::Int(99)]).unwrap_err();
+        assert!(err.contains("level must be between 1 and 22"));
+    }
+
+    #[test]
+    fn test_zstd_file_roundtrip() {
+        let dir = tempdir().unwrap();
+        let input = dir.path().join("data.txt");
+        let compressed = dir.path().join("data.txt.zst");
+        let output = dir.path().join("data.out");
+        fs::write(&input, "zstd ".repeat(500)).unwrap();
+
+        zstd_method(
+            "compress_file",
+            &[
+                Value::string(input.to_string_lossy()),
+                Value::string(compressed.to_string_lossy()),
+            ],
+        )
+        .unwrap();
+        zstd_method(
+            "decompress_file",
+            &[
+                Value::string(compressed.to_string_lossy()),
+                Value::string(output.to_string_lossy()),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(fs::read_to_string(output).unwrap(), "zstd ".repeat(500));
+    }
+
+    // ============================================================================
+    // Tar Module Tests
+    // ============================================================================
+
+    fn tar_roundtrip(archive_name: &str) {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src");
+        fs::create_dir_all(src.join("nested")).unwrap();
+        fs::write(src.join("a.txt"), "alpha").unwrap();
+        fs::write(src.join("nested").join("b.txt"), "beta").unwrap();
+
+        let tar_path = dir.path().join(archive_name);
+        let tar_path_str = tar_path.to_string_lossy().to_string();
+        let files = Value::list(vec![Value::string(src.to_string_lossy())]);
+        tar_method("create", &[Value::string(&tar_path_str), files]).unwrap();
+        assert!(tar_path.exists());
+
+        let entries = tar_method("list", &[Value::string(&tar_path_str)]).unwrap();
+        let Value::List(list) = entries else {
+            panic!("Expected List");
+        };
+        let names: Vec<String> = list
+            .borrow()
+            .iter()
+            .filter_map(|v| match v {
+                Value::Map(map) => get_map_string(&map.borrow(), "name"),
+                _ => None,
+            })
+            .collect();
+        assert!(names.contains(&"src/a.txt".to_string()));
+        assert!(names.contains(&"src/nested/b.txt".to_string()));
+
+        let content = tar_method(
+            "read_text",
+            &[
+                Value::string(&tar_path_str),
+                Value::string("src/nested/b.txt"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(content, Value::string("beta"));
+
+        let out = dir.path().join("out");
+        tar_method(
+            "extract",
+            &[
+                Value::string(&tar_path_str),
+                Value::string(out.to_string_lossy()),
+            ],
+        )
+        .unwrap();
+        assert_eq!(fs::read_to_string(out.join("src/a.txt")).unwrap(), "alpha");
+        assert_eq!(
+            fs::read_to_string(out.join("src/nested/b.txt")).unwrap(),
+            "beta"
+        );
+    }
+
+    #[test]
+    fn test_tar_create_extract_plain() {
+        tar_roundtrip("archive.tar");
+    }
+
+    #[test]
+    fn test_tar_create_extract_gzip() {
+        tar_roundtrip("archive.tar.gz");
+    }
+
+    #[test]
+    fn test_tar_create_extract_zstd() {
+        tar_roundtrip("archive.tar.zst");
+    }
+
+    #[test]
+    fn test_tar_explicit_compression_and_base_dir() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("notes.txt");
+        fs::write(&file_path, "hello tar").unwrap();
+
+        // Non-standard extension, so compression must be given explicitly
+        let tar_path = dir.path().join("bundle.bin");
+        let tar_path_str = tar_path.to_string_lossy().to_string();
+        let mut opts = ValueMap::new();
+        opts.insert(
+            HashableValue::String(Rc::new("compression".to_string())),
+            Value::string("zstd"),
+        );
+        opts.insert(
+            HashableValue::String(Rc::new("base_dir".to_string())),
+            Value::string(dir.path().to_string_lossy()),
+        );
+        let opts = Value::map(opts);
+
+        tar_method(
+            "create",
+            &[
+                Value::string(&tar_path_str),
+                Value::list(vec![Value::string(file_path.to_string_lossy())]),
+                opts.clone(),
+            ],
+        )
+        .unwrap();
 
-        zip_method("create", &[Value::string(&zip_path_str), files]).unwrap();
+        // Reading without the option fails because the stream is zstd-compressed
+        assert!(tar_method(
+            "read_text",
+            &[Value::string(&tar_path_str), Value::string("notes.txt")]
+        )
+        .is_err());
 
-        // Read file directly from zip
-        let content = zip_method(
+        let content = tar_method(
             "read_text",
-            &[Value::string(&zip_path_str), Value::string("readme.txt")],
+            &[
+                Value::string(&tar_path_str),
+                Value::string("notes.txt"),
+                opts,
+            ],
         )
         .unwrap();
-        assert_eq!(content, Value::string("Hello from zip!"));
+        assert_eq!(content, Value::string("hello tar"));
+    }
+
+    #[test]
+    fn test_tar_extract_rejects_path_traversal() {
+        let dir = tempdir().unwrap();
+        let tar_path = dir.path().join("evil.tar");
+        {
+            // tar::Builder refuses ".." paths, so write the raw header name directly
+            let mut builder = tar::Builder::new(File::create(&tar_path).unwrap());
+            let data = b"pwned";
+            let mut header = tar::Header::new_old();
+            header.as_old_mut().name[..11].copy_from_slice(b"../evil.txt");
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append(&header, &data[..]).unwrap();
+            builder.finish().unwrap();
+        }
+
+        let out = dir.path().join("out");
+        let err = tar_method(
+            "extract",
+            &[
+                Value::string(tar_path.to_string_lossy()),
+                Value::string(out.to_string_lossy()),
+            ],
+        )
+        .unwrap_err();
+        assert!(err.contains("escapes the output directory"));
+        assert!(!dir.path().join("evil.txt").exists());
+    }
+
+    #[test]
+    fn test_tar_unknown_compression() {
+        let mut opts = ValueMap::new();
+        opts.insert(
+            HashableValue::String(Rc::new("compression".to_string())),
+            Value::string("lzma"),
+        );
+        let err =
+            tar_method("list", &[Value::string("archive.tar"), Value::map(opts)]).unwrap_err();
+        assert!(err.contains("unknown tar compression 'lzma'"));
     }
 
     // ============================================================================
@@ -10229,18 +14133,12 @@ mod tests {
     #[test]
     fn test_regex_new_with_options() {
         // Create a case-insensitive regex
-        let mut options = HashMap::new();
+        let mut options = ValueMap::new();
         options.insert(
             HashableValue::String(Rc::new("case_insensitive".to_string())),
             Value::Bool(true),
         );
-        let result = regex_method(
-            "new",
-            &[
-                Value::string("hello"),
-                Value::Map(Rc::new(RefCell::new(options))),
-            ],
-        );
+        let result = regex_method("new", &[Value::string("hello"), Value::map(options)]);
         assert!(result.is_ok());
     }
 
@@ -10420,12 +14318,12 @@ mod tests {
 
     #[test]
     fn test_regex_case_insensitive() {
-        let mut options = HashMap::new();
+        let mut options = ValueMap::new();
         options.insert(
             HashableValue::String(Rc::new("case_insensitive".to_string())),
             Value::Bool(true),
         );
-        let opts = Value::Map(Rc::new(RefCell::new(options)));
+        let opts = Value::map(options);
 
         let result = regex_method(
             "is_match",
@@ -10436,12 +14334,12 @@ mod tests {
 
     #[test]
     fn test_regex_multiline() {
-        let mut options = HashMap::new();
+        let mut options = ValueMap::new();
         options.insert(
             HashableValue::String(Rc::new("multiline".to_string())),
             Value::Bool(true),
         );
-        let opts = Value::Map(Rc::new(RefCell::new(options)));
+        let opts = Value::map(options);
 
         // ^ should match start of each line in multiline mode
         let result = regex_method(
@@ -10467,6 +14365,131 @@ mod tests {
         assert_eq!(result, Ok(Value::Bool(true)));
     }
 
+    // ============================================================================
+    // Template Module Tests
+    // ============================================================================
+
+    fn tpl_map(pairs: &[(&str, Value)]) -> Value {
+        let mut map = ValueMap::new();
+        for (key, value) in pairs {
+            map.insert(
+                HashableValue::String(Rc::new((*key).to_string())),
+                value.clone(),
+            );
+        }
+        Value::map(map)
+    }
+
+    #[test]
+    fn test_template_render_loops_and_conditionals() {
+        let data = tpl_map(&[
+            ("title", Value::string("Report")),
+            (
+                "items",
+                Value::list(vec![Value::Int(1), Value::Int(2), Value::Int(3)]),
+            ),
+        ]);
+        let source =
+            "{{ title }}:{% for x in items %} {{ x }}{% if loop.last %}.{% endif %}{% endfor %}";
+        let result = template_method("render", &[Value::string(source), data]).unwrap();
+        assert_eq!(result, Value::string("Report: 1 2 3."));
+    }
+
+    #[test]
+    fn test_template_render_struct_data() {
+        let mut instance = StructInstance::new("User".to_string());
+        instance
+            .fields
+            .insert("name".to_string(), Value::string("Ada"));
+        let data = Value::Struct(Rc::new(RefCell::new(instance)));
+        let result = template_method("render", &[Value::string("Hi {{ name }}"), data]).unwrap();
+        assert_eq!(result, Value::string("Hi Ada"));
+    }
+
+    #[test]
+    fn test_template_escape_modes() {
+        let data = tpl_map(&[("v", Value::string("<b>&</b>"))]);
+        let raw = template_method("render", &[Value::string("{{ v }}"), data.clone()]).unwrap();
+        assert_eq!(raw, Value::string("<b>&</b>"));
+
+        let opts = tpl_map(&[("escape", Value::string("html"))]);
+        let html =
+            template_method("render", &[Value::string("{{ v }}"), data.clone(), opts]).unwrap();
+        assert_eq!(html, Value::string("&lt;b&gt;&amp;&lt;&#x2f;b&gt;"));
+
+        let opts = tpl_map(&[("escape", Value::string("xml"))]);
+        let err = template_method("render", &[Value::string("{{ v }}"), data, opts]).unwrap_err();
+        assert!(err.contains("unknown escape mode 'xml'"));
+    }
+
+    #[test]
+    fn test_template_partials_and_strict() {
+        let opts = tpl_map(&[
+            (
+                "partials",
+                tpl_map(&[("header", Value::string("# {{ title }}\n"))]),
+            ),
+            ("strict", Value::Bool(true)),
+        ]);
+        let data = tpl_map(&[("title", Value::string("Summary"))]);
+        let result = template_method(
+            "render",
+            &[
+                Value::string("{% include \"header\" %}body"),
+                data.clone(),
+                opts.clone(),
+            ],
+        )
+        .unwrap();
+        assert_eq!(result, Value::string("# Summary\nbody"));
+
+        let err =
+            template_method("render", &[Value::string("{{ missing }}"), data, opts]).unwrap_err();
+        assert!(err.contains("undefined"));
+    }
+
+    #[test]
+    fn test_template_render_file_with_include() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("partials")).unwrap();
+        fs::write(
+            dir.path().join("page.html"),
+            "{% include \"partials/nav.html\" %}<p>{{ body }}</p>",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("partials/nav.html"),
+            "<nav>{{ body }}</nav>",
+        )
+        .unwrap();
+
+        let data = tpl_map(&[("body", Value::string("a < b"))]);
+        let path = dir.path().join("page.html");
+        let result = template_method(
+            "render_file",
+            &[Value::string(path.to_string_lossy()), data],
+        )
+        .unwrap();
+        // .html templates are HTML-escaped by default
+        assert_eq!(result, Value::string("<nav>a &lt; b</nav><p>a &lt; b</p>"));
+    }
+
+    #[test]
+    fn test_template_validate() {
+        assert_eq!(
+            template_method("validate", &[Value::string("{% if x %}ok{% endif %}")]),
+            Ok(Value::Null)
+        );
+        let err = template_method("validate", &[Value::string("{% for x %}")]).unwrap_err();
+        assert!(err.contains("syntax error"));
+    }
+
+    #[test]
+    fn test_template_rejects_non_map_data() {
+        let err = template_method("render", &[Value::string("x"), Value::Int(1)]).unwrap_err();
+        assert!(err.contains("data must be Map or Struct"));
+    }
+
     // ============================================================================
     // Hash Module Tests
     // ============================================================================
@@ -10638,11 +14661,7 @@ mod tests {
 
     #[test]
     fn test_random_choice() {
-        let list = Value::List(Rc::new(RefCell::new(vec![
-            Value::Int(1),
-            Value::Int(2),
-            Value::Int(3),
-        ])));
+        let list = Value::list(vec![Value::Int(1), Value::Int(2), Value::Int(3)]);
         let result = random_method("choice", &[list]).unwrap();
         if let Value::Int(n) = result {
             assert!(n >= 1 && n <= 3);
@@ -10653,18 +14672,14 @@ mod tests {
 
     #[test]
     fn test_random_choice_empty() {
-        let list = Value::List(Rc::new(RefCell::new(vec![])));
+        let list = Value::list(vec![]);
         let result = random_method("choice", &[list]);
         assert!(result.is_err());
     }
 
     #[test]
     fn test_random_shuffle() {
-        let list = Value::List(Rc::new(RefCell::new(vec![
-            Value::Int(1),
-            Value::Int(2),
-            Value::Int(3),
-        ])));
+        let list = Value::list(vec![Value::Int(1), Value::Int(2), Value::Int(3)]);
         let result = random_method("shuffle", &[list]).unwrap();
         if let Value::List(shuffled) = result {
             // Same length
@@ -11431,7 +15446,7 @@ mod tests {
         let _ = log_method("to_stdout", &[]);
 
         // Log with context map
-        let mut context = HashMap::new();
+        let mut context = ValueMap::new();
         context.insert(
             HashableValue::String(Rc::new("user_id".to_string())),
             Value::Int(123),
@@ -11443,10 +15458,7 @@ mod tests {
 
         let result = log_method(
             "info",
-            &[
-                Value::string("User logged in"),
-                Value::Map(Rc::new(RefCell::new(context))),
-            ],
+            &[Value::string("User logged in"), Value::map(context)],
         );
         assert!(result.is_ok());
     }
@@ -11470,6 +15482,118 @@ mod tests {
         assert!(content.contains("TEST: Direct log message"));
     }
 
+    #[test]
+    fn test_log_filter_targets() {
+        let filter = LogFilter::parse("warn, db=debug,db.pool=off").unwrap();
+        assert_eq!(filter.to_string(), "warn,db=debug,db.pool=off");
+        assert!(filter.enabled(LogLevel::Warn, "app"));
+        assert!(!filter.enabled(LogLevel::Info, "app"));
+        assert!(filter.enabled(LogLevel::Debug, "db"));
+        assert!(filter.enabled(LogLevel::Debug, "db.query"));
+        assert!(!filter.enabled(LogLevel::Error, "db.pool"));
+        // A target only covers names under it, not names it starts
+        assert!(!filter.enabled(LogLevel::Debug, "dbx"));
+
+        assert_eq!(LogFilter::parse("").unwrap(), LogFilter::default());
+        assert!(LogFilter::parse("db=loud")
+            .unwrap_err()
+            .contains("invalid log level"));
+    }
+
+    #[test]
+    fn test_log_message_parts() {
+        let mut fields = ValueMap::new();
+        fields.insert(
+            HashableValue::String(Rc::new("target".to_string())),
+            Value::string("db"),
+        );
+        fields.insert(
+            HashableValue::String(Rc::new("rows".to_string())),
+            Value::Int(3),
+        );
+        let (message, target, fields) =
+            log_message_parts(LogLevel::Info, &[Value::string("done"), Value::map(fields)])
+                .unwrap();
+        assert_eq!(message, "done");
+        assert_eq!(target, "db");
+        assert_eq!(fields, vec![("rows".to_string(), Value::Int(3))]);
+
+        let (_, target, fields) =
+            log_message_parts(LogLevel::Trace, &[Value::string("hi")]).unwrap();
+        assert_eq!(target, DEFAULT_LOG_TARGET);
+        assert!(fields.is_empty());
+    }
+
+    #[test]
+    fn test_log_formats() {
+        let fields = vec![
+            ("user".to_string(), Value::string("Ada Lovelace")),
+            ("id".to_string(), Value::Int(7)),
+        ];
+        let record = LogRecord {
+            level: LogLevel::Warn,
+            target: "auth",
+            message: "slow login",
+            fields: &fields,
+        };
+        let at = "2000-01-01T00:00:00.000+00:00";
+
+        let template = LogFormat::from_str("[{level}] {target} - {message}");
+        assert_eq!(
+            format_log_record(&template, &record, at),
+            "[WARN] auth - slow login {user=Ada Lovelace, id=7}"
+        );
+        let template = LogFormat::from_str("{message} | {fields}");
+        assert_eq!(
+            format_log_record(&template, &record, at),
+            "slow login | user=Ada Lovelace id=7"
+        );
+        assert_eq!(
+            format_log_record(&LogFormat::from_str("pretty"), &record, at),
+            "2000-01-01T00:00:00.000+00:00 WARN  auth: slow login user=\"Ada Lovelace\" id=7"
+        );
+        assert_eq!(
+            format_log_record(&LogFormat::from_str("json"), &record, at),
+            r#"{"timestamp":"2000-01-01T00:00:00.000+00:00","level":"warn","target":"auth","message":"slow login","fields":{"user":"Ada Lovelace","id":7}}"#
+        );
+    }
+
+    #[test]
+    fn test_log_rotating_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("app.log").to_string_lossy().to_string();
+        let output = LogOutput::RotatingFile {
+            path: path.clone(),
+            max_bytes: 12,
+            keep: 2,
+        };
+        for line in ["first", "second", "third", "fourth"] {
+            write_log_output(&output, line).unwrap();
+        }
+        // Each line pushes the file past 12 bytes, so each starts a new file
+        // and only the two newest rotated files are kept
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fourth\n");
+        assert_eq!(fs::read_to_string(format!("{path}.1")).unwrap(), "third\n");
+        assert_eq!(fs::read_to_string(format!("{path}.2")).unwrap(), "second\n");
+        assert!(!Path::new(&format!("{path}.3")).exists());
+    }
+
+    #[test]
+    fn test_log_to_rotating_file_arg_validation() {
+        let result = log_method("to_rotating_file", &[Value::string("app.log")]);
+        assert!(result.unwrap_err().contains("expects 2-3 arguments"));
+        let result = log_method(
+            "to_rotating_file",
+            &[Value::string("app.log"), Value::Int(0)],
+        );
+        assert!(result.unwrap_err().contains("max_bytes must be positive"));
+        let result = log_method(
+            "to_rotating_file",
+            &[Value::string("app.log"), Value::Int(1024), Value::Int(-1)],
+        );
+        assert!(result.unwrap_err().contains("keep must not be negative"));
+    }
+
     #[test]
     fn test_log_unknown_method() {
         let result = log_method("unknown", &[]);
@@ -11477,6 +15601,57 @@ mod tests {
         assert!(result.unwrap_err().contains("has no method 'unknown'"));
     }
 
+    #[test]
+    fn test_i18n_formatting() {
+        let mut source = ValueMap::new();
+        source.insert(
+            HashableValue::String(Rc::new("count".to_string())),
+            Value::Int(1234),
+        );
+        i18n_method(
+            "add",
+            &[
+                Value::string("de"),
+                Value::string("files = { $count } Dateien"),
+            ],
+        )
+        .unwrap();
+        let locale = i18n_method("set_locale", &[Value::list(vec![Value::string("de-AT")])]);
+        assert_eq!(locale.unwrap(), Value::string("de"));
+
+        let t = i18n_method("t", &[Value::string("files"), Value::map(source)]);
+        assert_eq!(t.unwrap(), Value::string("1.234 Dateien"));
+        // Missing messages show their key
+        let t = i18n_method("t", &[Value::string("nope")]);
+        assert_eq!(t.unwrap(), Value::string("nope"));
+        assert_eq!(
+            i18n_method("has", &[Value::string("files")]).unwrap(),
+            Value::Bool(true)
+        );
+
+        let number = i18n_method("number", &[Value::Float(9876.5), Value::Int(2)]);
+        assert_eq!(number.unwrap(), Value::string("9.876,50"));
+        let number = i18n_method("number", &[Value::Int(-1000), Value::Int(1)]);
+        assert_eq!(number.unwrap(), Value::string("-1.000,0"));
+        let plural = i18n_method("plural", &[Value::Int(1)]);
+        assert_eq!(plural.unwrap(), Value::string("one"));
+
+        let mut date = ValueMap::new();
+        date.insert(
+            HashableValue::String(Rc::new("timestamp".to_string())),
+            Value::Int(1_709_596_800_000),
+        );
+        let date = i18n_method("date", &[Value::map(date)]);
+        assert_eq!(date.unwrap(), Value::string("05.03.2024"));
+
+        let result = i18n_method("t", &[Value::string("files"), Value::Int(1)]);
+        assert!(result.unwrap_err().contains("args must be a Map"));
+        let result = i18n_method("number", &[Value::string("12")]);
+        assert!(result.unwrap_err().contains("must be Int or Float"));
+        let result = i18n_method("set_locale", &[Value::string("not a locale")]);
+        assert!(result.unwrap_err().contains("invalid locale"));
+    }
+
     #[test]
     fn test_dispatch_log_namespace() {
         // Verify Log is properly routed through dispatch
@@ -11774,6 +15949,68 @@ mod tests {
         assert!(result.unwrap_err().contains("must be non-negative"));
     }
 
+    #[test]
+    fn test_process_start_invalid_args() {
+        let result = process_method("start", &[]);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("expects 1-3 arguments"));
+
+        let result = process_method(
+            "start",
+            &[
+                Value::string("echo"),
+                Value::list(vec![]),
+                Value::string("not a map"),
+            ],
+        );
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("options must be Map"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_process_start_handle() {
+        let result = process_method("start", &[Value::string("cat")]).unwrap();
+        let Value::ChildProcess(proc) = result else {
+            panic!("Expected ChildProcess");
+        };
+        assert!(proc.pid > 0);
+        assert_eq!(
+            child_process_method(&proc, "is_running", &[]),
+            Ok(Value::Bool(true))
+        );
+
+        // Reads return futures for the executor
+        let future = child_process_method(&proc, "read_line", &[]).unwrap();
+        assert!(matches!(future, Value::Future(_)));
+
+        assert_eq!(
+            child_process_method(&proc, "write", &[Value::string("hi")]),
+            Ok(Value::Int(2))
+        );
+        assert_eq!(
+            child_process_method(&proc, "kill", &[]),
+            Ok(Value::Bool(true))
+        );
+        assert_ne!(
+            child_process_method(&proc, "try_wait", &[]).unwrap(),
+            Value::Null
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_process_write_after_close_stdin() {
+        let result = process_method("start", &[Value::string("cat")]).unwrap();
+        let Value::ChildProcess(proc) = result else {
+            panic!("Expected ChildProcess");
+        };
+        child_process_method(&proc, "close_stdin", &[]).unwrap();
+        let result = child_process_method(&proc, "write", &[Value::string("late")]);
+        assert!(result.unwrap_err().contains("stdin is closed"));
+        child_process_method(&proc, "kill", &[]).unwrap();
+    }
+
     #[test]
     fn test_process_unknown_method() {
         let result = process_method("unknown", &[]);
@@ -12845,6 +17082,176 @@ mod tests {
         assert!(result.unwrap_err().contains("32 bytes"));
     }
 
+    #[test]
+    fn test_crypto_hmac_sha256() {
+        // RFC 4231 test case 2
+        let result = crypto_method(
+            "hmac_sha256",
+            &[
+                Value::string("Jefe"),
+                Value::string("what do ya want for nothing?"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            Value::string("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843")
+        );
+    }
+
+    #[test]
+    fn test_crypto_hmac_sha256_bytes_chaining() {
+        // Byte output can be fed back in as a key
+        let first = crypto_method(
+            "hmac_sha256_bytes",
+            &[Value::string("AWS4secret"), Value::string("20240101")],
+        )
+        .unwrap();
+        if let Value::List(bytes) = &first {
+            assert_eq!(bytes.borrow().len(), 32);
+        } else {
+            panic!("Expected List from hmac_sha256_bytes");
+        }
+        let second = crypto_method("hmac_sha256", &[first, Value::string("us-east-1")]).unwrap();
+        assert!(matches!(second, Value::String(s) if s.len() == 64));
+    }
+
+    #[test]
+    fn test_crypto_hmac_sha512() {
+        let result = crypto_method(
+            "hmac_sha512",
+            &[Value::string("key"), Value::string("message")],
+        )
+        .unwrap();
+        assert!(matches!(result, Value::String(s) if s.len() == 128));
+    }
+
+    #[test]
+    fn test_crypto_constant_time_eq() {
+        let eq = crypto_method(
+            "constant_time_eq",
+            &[Value::string("abc123"), Value::string("abc123")],
+        );
+        assert_eq!(eq, Ok(Value::Bool(true)));
+        let ne = crypto_method(
+            "constant_time_eq",
+            &[Value::string("abc123"), Value::string("abc124")],
+        );
+        assert_eq!(ne, Ok(Value::Bool(false)));
+        let len_mismatch = crypto_method(
+            "constant_time_eq",
+            &[Value::string("abc"), Value::string("abcd")],
+        );
+        assert_eq!(len_mismatch, Ok(Value::Bool(false)));
+    }
+
+    #[test]
+    fn test_crypto_aes_with_associated_data() {
+        let key = Value::string("0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef");
+        let encrypted = crypto_method(
+            "aes_encrypt",
+            &[
+                Value::string("payload"),
+                key.clone(),
+                Value::string("header"),
+            ],
+        )
+        .unwrap();
+
+        let decrypted = crypto_method(
+            "aes_decrypt",
+            &[encrypted.clone(), key.clone(), Value::string("header")],
+        );
+        assert_eq!(decrypted, Ok(Value::string("payload")));
+
+        // Mismatched associated data must fail authentication
+        let result = crypto_method("aes_decrypt", &[encrypted, key, Value::string("other")]);
+        assert!(result.is_err());
+    }
+
+    fn key_pair_field(pair: &Value, field: &str) -> Value {
+        if let Value::Map(map) = pair {
+            map.borrow()
+                .get(&HashableValue::String(Rc::new(field.to_string())))
+                .cloned()
+                .unwrap()
+        } else {
+            panic!("Expected Map key pair");
+        }
+    }
+
+    #[test]
+    fn test_crypto_ed25519_sign_verify() {
+        let pair = crypto_method("ed25519_generate", &[]).unwrap();
+        let public_key = key_pair_field(&pair, "public_key");
+        let private_key = key_pair_field(&pair, "private_key");
+
+        let signature =
+            crypto_method("ed25519_sign", &[private_key, Value::string("hello")]).unwrap();
+        assert!(matches!(&signature, Value::String(s) if s.len() == 128));
+
+        let valid = crypto_method(
+            "ed25519_verify",
+            &[
+                public_key.clone(),
+                Value::string("hello"),
+                signature.clone(),
+            ],
+        );
+        assert_eq!(valid, Ok(Value::Bool(true)));
+
+        let tampered = crypto_method(
+            "ed25519_verify",
+            &[public_key, Value::string("hellO"), signature],
+        );
+        assert_eq!(tampered, Ok(Value::Bool(false)));
+    }
+
+    #[test]
+    fn test_crypto_ed25519_invalid_key() {
+        let result = crypto_method(
+            "ed25519_sign",
+            &[Value::string("abcd"), Value::string("hello")],
+        );
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("32 bytes"));
+    }
+
+    #[test]
+    fn test_crypto_rsa_sign_verify() {
+        let pair = crypto_method("rsa_generate", &[Value::Int(1024)]).unwrap();
+        let public_key = key_pair_field(&pair, "public_key");
+        let private_key = key_pair_field(&pair, "private_key");
+        assert!(
+            matches!(&public_key, Value::String(s) if s.starts_with("-----BEGIN PUBLIC KEY-----"))
+        );
+
+        let signature =
+            crypto_method("rsa_sign", &[private_key, Value::string("payload")]).unwrap();
+
+        let valid = crypto_method(
+            "rsa_verify",
+            &[
+                public_key.clone(),
+                Value::string("payload"),
+                signature.clone(),
+            ],
+        );
+        assert_eq!(valid, Ok(Value::Bool(true)));
+
+        let tampered = crypto_method(
+            "rsa_verify",
+            &[public_key, Value::string("payload!"), signature],
+        );
+        assert_eq!(tampered, Ok(Value::Bool(false)));
+    }
+
+    #[test]
+    fn test_crypto_rsa_generate_invalid_bits() {
+        let result = crypto_method("rsa_generate", &[Value::Int(512)]);
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_crypto_dispatch() {
         // Verify Crypto namespace is properly registered
@@ -12975,10 +17382,7 @@ mod tests {
     // ============================================================================
 
     fn make_number_list(nums: &[f64]) -> Value {
-        use std::cell::RefCell;
-        Value::List(Rc::new(RefCell::new(
-            nums.iter().map(|&n| Value::Float(n)).collect(),
-        )))
+        Value::list(nums.iter().map(|&n| Value::Float(n)).collect())
     }
 
     #[test]
@@ -0,0 +1,684 @@
+//! Capability permissions for natives
+//!
+//! Natives that touch the outside world ask for a capability before they
+//! run: reading or writing files, opening network connections, reading or
+//! changing environment variables, and starting processes. A VM made with
+//! [`VM::new`](super::VM::new) grants every capability, as embedders expect.
+//! `stratum run` denies them all unless granted with `--allow-read`,
+//! `--allow-write`, `--allow-net`, `--allow-env`, `--allow-run` or
+//! `--allow-all`, each optionally limited to a list of paths, hosts,
+//! variables or programs.
+//!
+//! Capabilities are checked where a call reaches a native, so values that
+//! are already open, such as a TCP stream or a database connection, carry on
+//! working: holding one is itself the capability.
+//!
+//! Every namespace declares what its methods need: the built-in ones here,
+//! and external ones, such as `Gui`, when they are registered with the VM. A
+//! method of a namespace that touches the outside world that doesn't declare
+//! its needs fails closed: it only runs when every capability is granted.
+
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+
+use crate::bytecode::{HashableValue, Value};
+
+/// Something a native needs permission to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    /// Read files and directories
+    Read,
+    /// Create, change and delete files and directories
+    Write,
+    /// Open network connections and listen for them
+    Net,
+    /// Read and change environment variables
+    Env,
+    /// Start and stop processes
+    Run,
+}
+
+impl Capability {
+    /// Every capability
+    pub const ALL: [Capability; 5] = [
+        Capability::Read,
+        Capability::Write,
+        Capability::Net,
+        Capability::Env,
+        Capability::Run,
+    ];
+
+    /// The name used in `--allow-<name>` flags
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Capability::Read => "read",
+            Capability::Write => "write",
+            Capability::Net => "net",
+            Capability::Env => "env",
+            Capability::Run => "run",
+        }
+    }
+
+    fn describe(self, target: Option<&str>) -> String {
+        match (self, target) {
+            (Capability::Read, Some(path)) => format!("read access to '{path}'"),
+            (Capability::Read, None) => "read access to any file".to_string(),
+            (Capability::Write, Some(path)) => format!("write access to '{path}'"),
+            (Capability::Write, None) => "write access to any file".to_string(),
+            (Capability::Net, Some(host)) => format!("network access to '{host}'"),
+            (Capability::Net, None) => "network access to any host".to_string(),
+            (Capability::Env, Some(name)) => format!("access to the environment variable '{name}'"),
+            (Capability::Env, None) => "access to every environment variable".to_string(),
+            (Capability::Run, Some(program)) => format!("permission to run '{program}'"),
+            (Capability::Run, None) => "permission to run any program".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// How much of a capability is granted
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Grant {
+    /// None of it
+    #[default]
+    Denied,
+    /// All of it
+    All,
+    /// Only these paths (and what is inside them), hosts, variables or
+    /// programs
+    Only(Vec<String>),
+}
+
+/// A native's use of a capability, and what it is used on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Access {
+    /// The capability needed
+    pub capability: Capability,
+    /// The path, host, variable or program it is needed for, or `None` when
+    /// the native could reach any of them
+    pub target: Option<String>,
+}
+
+impl Access {
+    /// Use of `capability` on one path, host, variable or program
+    #[must_use]
+    pub fn new(capability: Capability, target: impl Into<String>) -> Self {
+        Self {
+            capability,
+            target: Some(target.into()),
+        }
+    }
+
+    /// Use of `capability` on anything
+    #[must_use]
+    pub fn any(capability: Capability) -> Self {
+        Self {
+            capability,
+            target: None,
+        }
+    }
+
+    /// Network access to the host and port of a URL, or to any host if it
+    /// doesn't parse
+    #[must_use]
+    pub fn url(url: &str) -> Self {
+        url_host(url).map_or_else(
+            || Access::any(Capability::Net),
+            |host| Access::new(Capability::Net, host),
+        )
+    }
+}
+
+/// The capabilities granted to a VM
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Permissions {
+    read: Grant,
+    write: Grant,
+    net: Grant,
+    env: Grant,
+    run: Grant,
+}
+
+impl Default for Permissions {
+    fn default() -> Self {
+        Self::allow_all()
+    }
+}
+
+impl Permissions {
+    /// Grant every capability
+    #[must_use]
+    pub fn allow_all() -> Self {
+        Self {
+            read: Grant::All,
+            write: Grant::All,
+            net: Grant::All,
+            env: Grant::All,
+            run: Grant::All,
+        }
+    }
+
+    /// Grant no capabilities
+    #[must_use]
+    pub fn deny_all() -> Self {
+        Self {
+            read: Grant::Denied,
+            write: Grant::Denied,
+            net: Grant::Denied,
+            env: Grant::Denied,
+            run: Grant::Denied,
+        }
+    }
+
+    /// Set how much of `capability` is granted. Relative paths granted for
+    /// reading or writing are resolved against the current directory now.
+    #[must_use]
+    pub fn with(mut self, capability: Capability, grant: Grant) -> Self {
+        let grant = match (capability, grant) {
+            (Capability::Read | Capability::Write, Grant::Only(paths)) => Grant::Only(
+                paths
+                    .iter()
+                    .map(|path| absolute(Path::new(path)).display().to_string())
+                    .collect(),
+            ),
+            (_, grant) => grant,
+        };
+        *self.grant_mut(capability) = grant;
+        self
+    }
+
+    /// How much of `capability` is granted
+    #[must_use]
+    pub fn grant(&self, capability: Capability) -> &Grant {
+        match capability {
+            Capability::Read => &self.read,
+            Capability::Write => &self.write,
+            Capability::Net => &self.net,
+            Capability::Env => &self.env,
+            Capability::Run => &self.run,
+        }
+    }
+
+    fn grant_mut(&mut self, capability: Capability) -> &mut Grant {
+        match capability {
+            Capability::Read => &mut self.read,
+            Capability::Write => &mut self.write,
+            Capability::Net => &mut self.net,
+            Capability::Env => &mut self.env,
+            Capability::Run => &mut self.run,
+        }
+    }
+
+    /// Whether every capability is fully granted, so nothing needs checking
+    #[must_use]
+    pub fn allows_all(&self) -> bool {
+        Capability::ALL
+            .iter()
+            .all(|&capability| *self.grant(capability) == Grant::All)
+    }
+
+    /// Check that `access` is granted
+    ///
+    /// # Errors
+    /// Returns a message naming the operation, what it needed and the flag
+    /// that grants it
+    pub fn check(&self, operation: &str, access: &Access) -> Result<(), String> {
+        let granted = match (self.grant(access.capability), &access.target) {
+            (Grant::All, _) => true,
+            (Grant::Denied, _) | (Grant::Only(_), None) => false,
+            (Grant::Only(allowed), Some(target)) => allowed
+                .iter()
+                .any(|allowed| matches(access.capability, allowed, target)),
+        };
+        if granted {
+            return Ok(());
+        }
+
+        let needs = access.capability.describe(access.target.as_deref());
+        let flag = match &access.target {
+            Some(target) => format!("--allow-{}={target}", access.capability.name()),
+            None => format!("--allow-{}", access.capability.name()),
+        };
+        Err(format!(
+            "{operation} needs {needs}; run again with {flag} to grant it"
+        ))
+    }
+}
+
+/// The error for a call to a method that doesn't declare what it needs
+pub(crate) fn undeclared(operation: &str) -> String {
+    format!(
+        "{operation} doesn't declare the access it needs; \
+         run again with --allow-all to grant it"
+    )
+}
+
+/// Whether a granted path, host, variable or program covers `target`
+fn matches(capability: Capability, allowed: &str, target: &str) -> bool {
+    match capability {
+        Capability::Read | Capability::Write => {
+            absolute(Path::new(target)).starts_with(Path::new(allowed))
+        }
+        // A host grants every port on it, `host:port` only that port
+        Capability::Net => {
+            allowed == target
+                || target
+                    .rsplit_once(':')
+                    .is_some_and(|(host, _)| host.trim_matches(['[', ']']) == allowed)
+        }
+        Capability::Env => allowed == target,
+        // Programs match by name as well as by path
+        Capability::Run => {
+            allowed == target
+                || Path::new(target)
+                    .file_name()
+                    .is_some_and(|name| name == std::ffi::OsStr::new(allowed))
+        }
+    }
+}
+
+/// `path` made absolute against the current directory, with `.` and `..`
+/// resolved without touching the filesystem
+fn absolute(path: &Path) -> PathBuf {
+    let joined = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir().unwrap_or_default().join(path)
+    };
+    let mut resolved = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            other => resolved.push(other),
+        }
+    }
+    resolved
+}
+
+/// Built-in namespaces none of whose methods reach files, the network, the
+/// environment or other programs
+const PURE_NAMESPACES: [&str; 24] = [
+    "Set", "Args", "Json", "Toml", "Yaml", "Base64", "Url", "DateTime", "Duration", "Time",
+    "Regex", "Hash", "Crypto", "Uuid", "Random", "Math", "Input", "Signal", "Async", "Agg", "Join",
+    "Test", "Xml", "Ref",
+];
+
+/// What a call to a built-in namespace method needs, judged from its
+/// arguments, or `None` for a method that declares nothing
+pub(crate) fn namespace_access(
+    namespace: &str,
+    method: &str,
+    args: &[Value],
+) -> Option<Vec<Access>> {
+    use Capability::{Env, Net, Read, Run, Write};
+
+    let arg = |index: usize, capability| match string_arg(args, index) {
+        Some(target) => Access::new(capability, target),
+        None => Access::any(capability),
+    };
+
+    if PURE_NAMESPACES.contains(&namespace) {
+        return Some(Vec::new());
+    }
+    let accesses = match (namespace, method) {
+        (
+            "Path",
+            "join" | "extension" | "ext" | "filename" | "file_name" | "parent" | "stem"
+            | "file_stem" | "is_absolute" | "is_relative",
+        )
+        | ("Csv", "parse" | "parse_line" | "stringify")
+        | ("Gzip" | "Zstd", "compress" | "decompress" | "compress_text" | "decompress_text")
+        | ("Template", "render" | "validate")
+        | (
+            "Log",
+            "trace" | "debug" | "info" | "warn" | "warning" | "error" | "level" | "set_level"
+            | "set_filter" | "filter" | "enabled" | "set_format" | "to_stderr" | "to_stdout",
+        )
+        | (
+            "I18n",
+            "add" | "t" | "has" | "locale" | "set_locale" | "set_fallback" | "locales"
+            | "system_locales" | "plural" | "number" | "date",
+        )
+        | (
+            "System",
+            "os" | "arch" | "cwd" | "temp_dir" | "exit" | "on_exit" | "cpu_count" | "total_memory"
+            | "hostname" | "uptime",
+        )
+        | (
+            "Data",
+            "frame"
+            | "dataframe"
+            | "series"
+            | "from_columns"
+            | "concat"
+            | "sql"
+            | "sql_context"
+            | "from_query"
+            | "set_parallel_threshold"
+            | "parallel_threshold"
+            | "set_threads"
+            | "threads",
+        )
+        | ("Cube", "from")
+        | ("Image", "new" | "create") => Vec::new(),
+
+        ("File", "read_text" | "read_bytes" | "read_lines" | "exists" | "size")
+        | ("Dir", "list" | "exists")
+        | ("Path", "exists" | "is_file" | "is_dir" | "normalize" | "canonicalize")
+        | ("Csv", "open")
+        | ("Image", "open" | "load")
+        | ("Cube", "load")
+        | ("Template", "render_file")
+        | ("Data", "read_csv" | "read_json" | "read_parquet")
+        | ("Zip", "list" | "read_text" | "read_bytes")
+        | ("Tar", "list" | "read_text" | "read_bytes")
+        | ("I18n", "load")
+        | ("System", "set_cwd") => vec![arg(0, Read)],
+
+        ("File", "write_text" | "write_bytes" | "append" | "delete" | "remove")
+        | ("Dir", "create" | "create_all" | "remove" | "delete" | "remove_all" | "delete_all")
+        | ("Csv", "create")
+        | ("Log", "to_file" | "to_rotating_file") => vec![arg(0, Write)],
+
+        ("Data", "write_csv" | "write_json" | "write_parquet") => vec![arg(1, Write)],
+        ("File", "copy")
+        | ("Gzip" | "Zstd", "compress_file" | "decompress_file")
+        | ("Zip" | "Tar", "extract") => vec![arg(0, Read), arg(1, Write)],
+        ("Zip", "extract_file") => vec![arg(0, Read), arg(2, Write)],
+        ("File", "rename" | "move") => vec![arg(0, Write), arg(1, Write)],
+        ("Zip" | "Tar", "create") => {
+            let mut accesses = vec![arg(0, Write)];
+            match args.get(1) {
+                Some(Value::List(files)) => {
+                    accesses.extend(files.borrow().iter().filter_map(|file| match file {
+                        Value::String(file) => Some(Access::new(Read, file.as_str())),
+                        _ => None,
+                    }))
+                }
+                _ => accesses.push(Access::any(Read)),
+            }
+            accesses
+        }
+        ("System", "temp_file") => vec![Access::new(
+            Write,
+            std::env::temp_dir().display().to_string(),
+        )],
+
+        // Whatever the database, its SQL can read and write any file
+        // (`ATTACH`, `COPY ... TO`, `read_csv`), and DuckDB's can fetch URLs
+        ("Db", "sqlite") => vec![Access::any(Read), Access::any(Write)],
+        ("Db", "duckdb") => vec![Access::any(Read), Access::any(Write), Access::any(Net)],
+        ("Db", "postgres" | "mysql") => vec![db_host(args.first())],
+        ("Http", "get" | "post" | "put" | "patch" | "delete" | "head")
+        | ("WebSocket", "connect") => vec![match string_arg(args, 0) {
+            Some(url) => Access::url(&url),
+            None => Access::any(Net),
+        }],
+        ("Tcp", "connect" | "listen") | ("Udp", "bind") | ("WebSocket", "listen" | "server") => {
+            vec![match (string_arg(args, 0), args.get(1)) {
+                (Some(host), Some(Value::Int(port))) => Access::new(Net, format!("{host}:{port}")),
+                _ => Access::any(Net),
+            }]
+        }
+
+        ("Env", "get" | "set" | "remove" | "unset" | "has" | "contains") => vec![arg(0, Env)],
+        ("Env", "all" | "vars") => vec![Access::any(Env)],
+
+        ("Process", "spawn" | "start") => vec![arg(0, Run)],
+        ("Process", "kill") | ("Shell", "run" | "exec") => vec![Access::any(Run)],
+
+        _ => return None,
+    };
+    Some(accesses)
+}
+
+/// What a method on a value needs, for values whose methods write files
+pub(crate) fn value_method_access(receiver: &Value, method: &str, args: &[Value]) -> Vec<Access> {
+    match (receiver, method) {
+        (
+            Value::DataFrame(_),
+            "to_parquet" | "write_parquet" | "to_csv" | "write_csv" | "to_json" | "write_json",
+        )
+        | (Value::Cube(_) | Value::Image(_), "save") => match string_arg(args, 0) {
+            Some(path) => vec![Access::new(Capability::Write, path)],
+            None => Vec::new(),
+        },
+        _ => Vec::new(),
+    }
+}
+
+fn string_arg(args: &[Value], index: usize) -> Option<String> {
+    match args.get(index) {
+        Some(Value::String(s)) => Some(s.to_string()),
+        _ => None,
+    }
+}
+
+/// The `host:port` of a URL
+fn url_host(url: &str) -> Option<String> {
+    let url = reqwest::Url::parse(url).ok()?;
+    let host = url.host_str()?;
+    Some(match url.port_or_known_default() {
+        Some(port) => format!("{host}:{port}"),
+        None => host.to_string(),
+    })
+}
+
+/// The server a database connection string or config map points at
+fn db_host(config: Option<&Value>) -> Access {
+    let host = match config {
+        Some(Value::String(url)) => url_host(url),
+        Some(Value::Map(map)) => {
+            let map = map.borrow();
+            let field = |name: &str| {
+                map.get(&HashableValue::String(Rc::new(name.to_string())))
+                    .cloned()
+            };
+            match (field("host"), field("port")) {
+                (Some(Value::String(host)), Some(Value::Int(port))) => {
+                    Some(format!("{host}:{port}"))
+                }
+                (Some(Value::String(host)), _) => Some(host.to_string()),
+                (None, _) => Some("localhost".to_string()),
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+    host.map_or_else(
+        || Access::any(Capability::Net),
+        |host| Access::new(Capability::Net, host),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allow_all_by_default() {
+        let permissions = Permissions::default();
+        assert!(permissions.allows_all());
+        let access = Access::new(Capability::Read, "/etc/passwd");
+        assert!(permissions.check("File.read_text", &access).is_ok());
+        assert!(!Permissions::deny_all().allows_all());
+    }
+
+    #[test]
+    fn test_denied_message_names_the_flag() {
+        let permissions = Permissions::deny_all();
+        let err = permissions
+            .check(
+                "Http.get",
+                &Access::new(Capability::Net, "api.example.com:443"),
+            )
+            .unwrap_err();
+        assert_eq!(
+            err,
+            "Http.get needs network access to 'api.example.com:443'; \
+             run again with --allow-net=api.example.com:443 to grant it"
+        );
+
+        let err = permissions
+            .check("Shell.run", &Access::any(Capability::Run))
+            .unwrap_err();
+        assert!(err.contains("run again with --allow-run to grant it"));
+    }
+
+    #[test]
+    fn test_paths_granted_with_their_contents() {
+        let dir = std::env::temp_dir().join("stratum-permissions");
+        let permissions = Permissions::deny_all().with(
+            Capability::Read,
+            Grant::Only(vec![dir.display().to_string()]),
+        );
+        let inside = dir.join("data").join("sales.csv");
+        let read = |path: &Path| Access::new(Capability::Read, path.display().to_string());
+
+        assert!(permissions.check("File.read_text", &read(&inside)).is_ok());
+        assert!(permissions.check("File.read_text", &read(&dir)).is_ok());
+        // `..` can't climb out of a granted directory
+        let escape = dir.join("..").join("secret.txt");
+        assert!(permissions.check("File.read_text", &read(&escape)).is_err());
+        // Granting reads grants no writes
+        let write = Access::new(Capability::Write, inside.display().to_string());
+        assert!(permissions.check("File.write_text", &write).is_err());
+    }
+
+    #[test]
+    fn test_hosts_env_and_programs() {
+        let permissions = Permissions::deny_all()
+            .with(
+                Capability::Net,
+                Grant::Only(vec!["api.example.com".into(), "localhost:8080".into()]),
+            )
+            .with(Capability::Env, Grant::Only(vec!["HOME".into()]))
+            .with(Capability::Run, Grant::Only(vec!["git".into()]));
+        let net = |target: &str| Access::new(Capability::Net, target);
+
+        assert!(permissions
+            .check("Http.get", &net("api.example.com:443"))
+            .is_ok());
+        assert!(permissions
+            .check("Tcp.connect", &net("localhost:8080"))
+            .is_ok());
+        assert!(permissions
+            .check("Tcp.connect", &net("localhost:9090"))
+            .is_err());
+        assert!(permissions
+            .check("Http.get", &net("example.com:443"))
+            .is_err());
+
+        let env = |name: &str| Access::new(Capability::Env, name);
+        assert!(permissions.check("Env.get", &env("HOME")).is_ok());
+        assert!(permissions.check("Env.get", &env("TOKEN")).is_err());
+        assert!(permissions
+            .check("Env.all", &Access::any(Capability::Env))
+            .is_err());
+
+        let run = |program: &str| Access::new(Capability::Run, program);
+        assert!(permissions
+            .check("Process.spawn", &run("/usr/bin/git"))
+            .is_ok());
+        assert!(permissions.check("Process.spawn", &run("curl")).is_err());
+    }
+
+    #[test]
+    fn test_namespace_access() {
+        let url = Value::string("https://api.example.com/v1/users");
+        assert_eq!(
+            namespace_access("Http", "get", &[url]),
+            Some(vec![Access::new(Capability::Net, "api.example.com:443")])
+        );
+
+        let copy = namespace_access("File", "copy", &[Value::string("a"), Value::string("b")]);
+        assert_eq!(
+            copy,
+            Some(vec![
+                Access::new(Capability::Read, "a"),
+                Access::new(Capability::Write, "b")
+            ])
+        );
+
+        assert_eq!(
+            namespace_access("Db", "sqlite", &[Value::string(":memory:")]),
+            Some(vec![
+                Access::any(Capability::Read),
+                Access::any(Capability::Write)
+            ])
+        );
+        assert_eq!(
+            namespace_access("Env", "all", &[]),
+            Some(vec![Access::any(Capability::Env)])
+        );
+        assert_eq!(
+            namespace_access(
+                "Tcp",
+                "connect",
+                &[Value::string("localhost"), Value::Int(80)]
+            ),
+            Some(vec![Access::new(Capability::Net, "localhost:80")])
+        );
+        assert_eq!(
+            namespace_access("Json", "parse", &[Value::string("{}")]),
+            Some(Vec::new())
+        );
+        assert_eq!(
+            namespace_access("Path", "join", &[Value::string("a")]),
+            Some(Vec::new())
+        );
+        // Unknown methods of namespaces that do I/O fail closed
+        assert_eq!(namespace_access("File", "shred", &[]), None);
+        assert_eq!(namespace_access("Http", "options", &[]), None);
+    }
+
+    /// Every `Namespace.method` the built-in natives dispatch, read from the
+    /// method tables in natives.rs
+    fn builtin_methods() -> Vec<(String, String)> {
+        let source = include_str!("natives.rs");
+        let body = |start: &str| {
+            let from = source.find(start).unwrap_or_else(|| panic!("no {start}"));
+            let end = from + source[from..].find("\n}\n").unwrap();
+            &source[from..end]
+        };
+        let namespace = regex::Regex::new(r#""(\w+)" => (\w+)\(method, args\)"#).unwrap();
+        let arm = regex::Regex::new(r#"(?m)^        ("\w+"(?:\s*\|\s*"\w+")*)\s*=>"#).unwrap();
+        let name = regex::Regex::new(r#""(\w+)""#).unwrap();
+
+        let mut methods = Vec::new();
+        for table in namespace.captures_iter(body("pub fn dispatch_namespace_method(")) {
+            let function = body(&format!("fn {}(", &table[2]));
+            for names in arm.captures_iter(function) {
+                for method in name.captures_iter(&names[1]) {
+                    methods.push((table[1].to_string(), method[1].to_string()));
+                }
+            }
+        }
+        methods
+    }
+
+    #[test]
+    fn test_every_builtin_method_declares_its_access() {
+        let methods = builtin_methods();
+        assert!(methods.len() > 300);
+        let undeclared: Vec<String> = methods
+            .iter()
+            .filter(|(namespace, method)| namespace_access(namespace, method, &[]).is_none())
+            .map(|(namespace, method)| format!("{namespace}.{method}"))
+            .collect();
+        assert!(
+            undeclared.is_empty(),
+            "declare what these need in namespace_access: {undeclared:?}"
+        );
+    }
+}
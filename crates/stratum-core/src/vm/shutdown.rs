@@ -0,0 +1,92 @@
+//! Graceful shutdown on SIGINT and SIGTERM
+//!
+//! Once [`VM::handle_shutdown_signals`](super::VM::handle_shutdown_signals)
+//! is called, the first SIGINT or SIGTERM no longer kills the process.
+//! It is recorded here instead, and the running VM notices it between
+//! instructions. The VM runs the `finally` blocks of the try statements
+//! still running, then the callbacks registered with `System.on_exit`, and
+//! then stops with an interrupted error.
+//!
+//! A program blocked in a native call, such as waiting for a connection,
+//! notices the signal when the call returns. A second signal exits at once,
+//! without cleanup, so a stuck program can still be stopped.
+
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+
+use signal_hook::consts::{SIGINT, SIGTERM};
+use signal_hook::flag;
+
+/// Set by the first signal once the handlers are installed; a signal that
+/// finds it set exits at once
+static RECEIVED: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+
+/// The signal not yet handled by a VM, or 0
+fn pending() -> &'static Arc<AtomicUsize> {
+    static PENDING: OnceLock<Arc<AtomicUsize>> = OnceLock::new();
+    PENDING.get_or_init(Arc::default)
+}
+
+/// Record SIGINT and SIGTERM for the VM instead of exiting. Installing the
+/// handlers more than once has no further effect.
+pub(crate) fn install() -> io::Result<()> {
+    if RECEIVED.get().is_some() {
+        return Ok(());
+    }
+    let received = Arc::new(AtomicBool::new(false));
+    for signal in [SIGINT, SIGTERM] {
+        // Registered first, so it sees `received` as the previous signal left it
+        flag::register_conditional_shutdown(signal, exit_code(signal), Arc::clone(&received))?;
+        flag::register(signal, Arc::clone(&received))?;
+        let recorded = usize::try_from(signal).unwrap_or_default();
+        flag::register_usize(signal, Arc::clone(pending()), recorded)?;
+    }
+    let _ = RECEIVED.set(received);
+    Ok(())
+}
+
+/// Take the signal received since the last call, if any
+pub(crate) fn take_pending() -> Option<i32> {
+    let signal = pending().swap(0, Ordering::SeqCst);
+    i32::try_from(signal).ok().filter(|&signal| signal != 0)
+}
+
+/// Record `signal` as if it had been received, without installing the
+/// handlers, so tests don't take over Ctrl-C for the whole test binary.
+/// Pair it with a VM whose `watch_signals` is set directly.
+#[cfg(test)]
+pub(crate) fn simulate(signal: i32) {
+    pending().store(
+        usize::try_from(signal).unwrap_or_default(),
+        Ordering::SeqCst,
+    );
+}
+
+/// The exit code of a process stopped by `signal`, as shells report it
+#[must_use]
+pub(crate) fn exit_code(signal: i32) -> i32 {
+    128 + signal
+}
+
+/// The conventional name of a shutdown signal
+#[must_use]
+pub(crate) fn signal_name(signal: i32) -> String {
+    match signal {
+        SIGINT => "SIGINT".to_string(),
+        SIGTERM => "SIGTERM".to_string(),
+        other => format!("signal {other}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_and_exit_codes() {
+        assert_eq!(signal_name(SIGINT), "SIGINT");
+        assert_eq!(signal_name(SIGTERM), "SIGTERM");
+        assert_eq!(exit_code(SIGINT), 128 + SIGINT);
+    }
+}
@@ -0,0 +1,222 @@
+//! Execution trace for step-back debugging
+//!
+//! In debug mode the VM checkpoints its state each time a frame enters a
+//! line: the call frames, the value stack, the exception handlers and the
+//! open upvalues. Globals and closed upvalues are not copied; instead the
+//! value each write replaces is logged, and undone when stepping back.
+//!
+//! Lists, maps and struct instances are shared between checkpoints, so
+//! after stepping back they show their latest contents.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+
+use crate::bytecode::{Upvalue, Value};
+
+use super::debug::DebugVariable;
+use super::{CallFrame, ExceptionHandler};
+
+/// Number of line entries kept by default
+pub const DEFAULT_TRACE_CAPACITY: usize = 1000;
+
+/// One line entered by one frame, as shown on a debugger timeline
+#[derive(Debug, Clone)]
+pub struct TraceEntry {
+    /// Line entered
+    pub line: u32,
+    /// Function executing the line
+    pub function_name: String,
+    /// Call depth (1 = the script)
+    pub depth: usize,
+    /// Size of the value stack when the line was entered
+    pub stack_len: usize,
+    /// Instructions executed on the line before the next entry
+    pub op_count: usize,
+    /// Variables assigned on the line, with the values they were given
+    pub writes: Vec<DebugVariable>,
+}
+
+/// A value overwritten since a checkpoint
+pub(super) enum Undo {
+    /// A global and its previous value (`None` if it was undefined)
+    Global(String, Option<Value>),
+    /// A captured variable and its previous state
+    Upvalue(Rc<RefCell<Upvalue>>, Upvalue),
+}
+
+/// The VM state on entering a line
+pub(super) struct Checkpoint {
+    pub(super) entry: TraceEntry,
+    pub(super) frames: Vec<CallFrame>,
+    pub(super) stack: Vec<Value>,
+    pub(super) handlers: Vec<ExceptionHandler>,
+    /// Upvalues open at the checkpoint and the stack slots they point to
+    pub(super) open_upvalues: Vec<(Rc<RefCell<Upvalue>>, usize)>,
+    /// Lines recorded by the debug context, so a restored line is not
+    /// entered again
+    pub(super) frame_lines: Vec<u32>,
+    /// Writes made since the checkpoint, oldest first
+    pub(super) undo: Vec<Undo>,
+}
+
+impl Checkpoint {
+    /// Undo the writes logged since the checkpoint, newest first
+    pub(super) fn undo_writes(&mut self, globals: &mut HashMap<String, Value>) {
+        for undo in self.undo.drain(..).rev() {
+            match undo {
+                Undo::Global(name, Some(value)) => {
+                    globals.insert(name, value);
+                }
+                Undo::Global(name, None) => {
+                    globals.remove(&name);
+                }
+                Undo::Upvalue(upvalue, state) => *upvalue.borrow_mut() = state,
+            }
+        }
+    }
+}
+
+/// A bounded history of checkpoints, oldest first
+pub(super) struct ExecutionTrace {
+    checkpoints: VecDeque<Checkpoint>,
+    capacity: usize,
+}
+
+impl Default for ExecutionTrace {
+    fn default() -> Self {
+        Self {
+            checkpoints: VecDeque::new(),
+            capacity: DEFAULT_TRACE_CAPACITY,
+        }
+    }
+}
+
+impl ExecutionTrace {
+    /// Whether checkpoints are recorded at all
+    pub(super) fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Set the number of line entries kept, dropping the oldest ones that
+    /// no longer fit; 0 turns tracing off
+    pub(super) fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.checkpoints.len() > capacity {
+            self.checkpoints.pop_front();
+        }
+    }
+
+    /// Record a checkpoint, dropping the oldest one if the trace is full
+    pub(super) fn push(&mut self, checkpoint: Checkpoint) {
+        if !self.is_enabled() {
+            return;
+        }
+        if self.checkpoints.len() == self.capacity {
+            self.checkpoints.pop_front();
+        }
+        self.checkpoints.push_back(checkpoint);
+    }
+
+    /// The most recent checkpoint, which writes and instructions are
+    /// attributed to
+    pub(super) fn latest_mut(&mut self) -> Option<&mut Checkpoint> {
+        self.checkpoints.back_mut()
+    }
+
+    /// The most recent checkpoint
+    pub(super) fn latest(&self) -> Option<&Checkpoint> {
+        self.checkpoints.back()
+    }
+
+    /// The most recent checkpoint taken in a frame at `depth`
+    pub(super) fn latest_at_depth_mut(&mut self, depth: usize) -> Option<&mut Checkpoint> {
+        self.checkpoints
+            .iter_mut()
+            .rev()
+            .find(|checkpoint| checkpoint.entry.depth == depth)
+    }
+
+    /// Remove the most recent checkpoint
+    pub(super) fn pop(&mut self) -> Option<Checkpoint> {
+        self.checkpoints.pop_back()
+    }
+
+    /// Number of checkpoints kept
+    pub(super) fn len(&self) -> usize {
+        self.checkpoints.len()
+    }
+
+    /// Forget every checkpoint, for a new run
+    pub(super) fn clear(&mut self) {
+        self.checkpoints.clear();
+    }
+
+    /// The recorded line entries, oldest first
+    pub(super) fn entries(&self) -> Vec<TraceEntry> {
+        self.checkpoints.iter().map(|c| c.entry.clone()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkpoint(line: u32) -> Checkpoint {
+        Checkpoint {
+            entry: TraceEntry {
+                line,
+                function_name: "main".to_string(),
+                depth: 1,
+                stack_len: 0,
+                op_count: 0,
+                writes: Vec::new(),
+            },
+            frames: Vec::new(),
+            stack: Vec::new(),
+            handlers: Vec::new(),
+            open_upvalues: Vec::new(),
+            frame_lines: Vec::new(),
+            undo: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_trace_is_bounded() {
+        let mut trace = ExecutionTrace::default();
+        trace.set_capacity(3);
+        for line in 1..=5 {
+            trace.push(checkpoint(line));
+        }
+        let lines: Vec<u32> = trace.entries().iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![3, 4, 5]);
+
+        trace.set_capacity(0);
+        assert!(!trace.is_enabled());
+        trace.push(checkpoint(6));
+        assert_eq!(trace.len(), 0);
+    }
+
+    #[test]
+    fn test_undo_writes() {
+        let mut globals = HashMap::new();
+        globals.insert("x".to_string(), Value::Int(3));
+        globals.insert("y".to_string(), Value::Int(1));
+        let upvalue = Rc::new(RefCell::new(Upvalue::Closed(Value::Int(9))));
+
+        let mut checkpoint = checkpoint(1);
+        // x: 1 -> 2 -> 3, y defined, upvalue 5 -> 9
+        checkpoint.undo = vec![
+            Undo::Global("x".to_string(), Some(Value::Int(1))),
+            Undo::Global("x".to_string(), Some(Value::Int(2))),
+            Undo::Global("y".to_string(), None),
+            Undo::Upvalue(upvalue.clone(), Upvalue::Closed(Value::Int(5))),
+        ];
+        checkpoint.undo_writes(&mut globals);
+
+        assert_eq!(globals.get("x"), Some(&Value::Int(1)));
+        assert!(!globals.contains_key("y"));
+        assert!(matches!(&*upvalue.borrow(), Upvalue::Closed(Value::Int(5))));
+        assert!(checkpoint.undo.is_empty());
+    }
+}
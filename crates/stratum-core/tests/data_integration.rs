@@ -1028,6 +1028,43 @@ fn test_dataframe_to_cube_pipeline() {
     }
 }
 
+#[test]
+fn test_cube_save_load_and_refresh() {
+    let path = std::env::temp_dir().join("stratum-test-cube-save.parquet");
+    let source = format!(
+        r#"{{
+        let df = Data.frame([
+            {{"region": "North", "revenue": 100.0}},
+            {{"region": "South", "revenue": 200.0}}
+        ])
+        let cube = Cube.from("sales", df)
+            |> dimension("region")
+            |> measure("revenue", "sum")
+            |> build()
+        cube.save("{path}")
+        let loaded = Cube.load("{path}")
+        let more = Data.frame([{{"region": "North", "revenue": 50.0}}])
+        let refreshed = loaded.refresh(more)
+        [loaded.name(), loaded.row_count(), refreshed.row_count(), refreshed.aggregates().num_rows()]
+    }}"#,
+        path = path.display()
+    );
+    let result = eval_expr_dynamic(&source);
+    let _ = std::fs::remove_file(&path);
+
+    match result {
+        Ok(Value::List(list)) => {
+            let list = list.borrow();
+            assert_eq!(list[0], Value::string("sales"));
+            assert_eq!(list[1], Value::Int(2));
+            assert_eq!(list[2], Value::Int(3));
+            assert_eq!(list[3], Value::Int(2));
+        }
+        Ok(other) => panic!("Expected List, got {:?}", other),
+        Err(e) => panic!("Program failed: {}", e),
+    }
+}
+
 #[test]
 fn test_dataframe_to_cube_full_pipeline() {
     // Test complete mixed DataFrame -> Cube pipeline
@@ -2089,6 +2126,78 @@ fn test_filter_by_indices_uses_parallel() {
     set_parallel_threshold(original);
 }
 
+#[test]
+fn test_parallel_kernels_match_sequential_results() {
+    use std::collections::HashMap;
+    use stratum_core::data::{
+        parallel_threshold, set_parallel_threshold, set_threads, threads, JoinSpec,
+    };
+
+    let n = 1000;
+    let ids: Vec<i64> = (0..n).collect();
+    let keys: Vec<i64> = ids.iter().map(|i| i % 7).collect();
+    let df = DataFrame::from_series(vec![
+        Series::from_ints("id", ids.clone()),
+        Series::from_ints("key", keys),
+    ])
+    .unwrap();
+
+    let original = parallel_threshold();
+    set_parallel_threshold(10);
+    set_threads(4).unwrap();
+    assert_eq!(threads(), 4);
+
+    // Sort keeps every row and orders them across chunk boundaries
+    let sorted = df.sort_by(&[("id", false)]).unwrap();
+    let sorted_ids = sorted.column("id").unwrap().to_values().unwrap();
+    let expected: Vec<Value> = (0..n).rev().map(Value::Int).collect();
+    assert_eq!(sorted_ids, expected);
+
+    // Arithmetic and comparisons are evaluated chunk by chunk
+    let id = df.column("id").unwrap();
+    let doubled = id.add(&id).unwrap().to_values().unwrap();
+    assert_eq!(doubled[999], Value::Int(1998));
+    let big = id.gt_scalar(&Value::Int(499)).unwrap().to_values().unwrap();
+    let rows: Vec<usize> = (0..big.len())
+        .filter(|&i| big[i] == Value::Bool(true))
+        .collect();
+    assert_eq!(rows.first(), Some(&500));
+    let filtered = df.filter_by_indices(&rows).unwrap();
+    assert_eq!(
+        filtered.column("id").unwrap().get(0).unwrap(),
+        Value::Int(500)
+    );
+
+    // Group rows are collected in row order, whichever chunk they fall in
+    let grouped = GroupedDataFrame::new(Arc::new(df.clone()), vec!["key".to_string()]).unwrap();
+    let sums = grouped.sum("id", Some("total")).unwrap();
+    let mut totals = HashMap::new();
+    for row in 0..sums.num_rows() {
+        let key = sums.column("key").unwrap().get(row).unwrap();
+        let total = sums.column("total").unwrap().get(row).unwrap();
+        totals.insert(format!("{key}"), total);
+    }
+    for key in 0..7 {
+        let expected: i64 = (0..n).filter(|i| i % 7 == key).sum();
+        assert_eq!(totals[&key.to_string()], Value::Int(expected));
+    }
+
+    // Joined rows keep the order of the left frame
+    let evens = DataFrame::from_series(vec![
+        Series::from_ints("id", (0..n).step_by(2).collect()),
+        Series::from_ints("half", (0..n / 2).collect()),
+    ])
+    .unwrap();
+    let joined = df.join(&evens, &JoinSpec::inner("id")).unwrap();
+    assert_eq!(joined.num_rows(), 500);
+    let joined_ids = joined.column("id").unwrap().to_values().unwrap();
+    let expected: Vec<Value> = (0..n).step_by(2).map(Value::Int).collect();
+    assert_eq!(joined_ids, expected);
+
+    set_threads(0).unwrap();
+    set_parallel_threshold(original);
+}
+
 #[test]
 fn test_lazy_frame_basic() {
     use stratum_core::data::LazyFrame;
@@ -0,0 +1,80 @@
+//! Checks on the examples in the book under `docs/`
+
+use std::path::Path;
+
+use stratum_core::doc::Book;
+use stratum_core::lexer::TokenKind;
+use stratum_core::Lexer;
+
+/// An example: the chapter it is in, the line its code starts on, and the code
+struct Example {
+    path: String,
+    line: usize,
+    code: String,
+}
+
+/// The `stratum` code blocks of every chapter
+fn examples() -> Vec<Example> {
+    let docs = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../docs");
+    let book = Book::load(&docs, "Stratum").unwrap();
+
+    let mut examples = Vec::new();
+    for chapter in &book.chapters {
+        // The language and first line of the block we're in, if any
+        let mut block: Option<(bool, usize)> = None;
+        let mut code = String::new();
+        for (number, line) in chapter.content.lines().enumerate() {
+            let fence = line.trim_start().strip_prefix("```");
+            match (block, fence) {
+                (None, Some(info)) => {
+                    let language = info.split([',', ' ']).next().unwrap_or("");
+                    block = Some((language == "stratum", number + 2));
+                }
+                (Some((is_stratum, line)), Some(_)) => {
+                    if is_stratum {
+                        examples.push(Example {
+                            path: chapter.path.clone(),
+                            line,
+                            code: std::mem::take(&mut code),
+                        });
+                    }
+                    code.clear();
+                    block = None;
+                }
+                (Some(_), None) => {
+                    code.push_str(line);
+                    code.push('\n');
+                }
+                (None, None) => {}
+            }
+        }
+    }
+    examples
+}
+
+#[test]
+fn test_examples_declare_functions_with_fx() {
+    let examples = examples();
+    assert!(examples.len() > 100, "found {} examples", examples.len());
+
+    let mut wrong = Vec::new();
+    for example in &examples {
+        let (tokens, _) = Lexer::tokenize(&example.code);
+        for window in tokens.windows(3) {
+            if window[0].kind == TokenKind::Ident
+                && window[0].lexeme == "fn"
+                && window[1].kind == TokenKind::Ident
+                && window[2].kind == TokenKind::LParen
+            {
+                let before = &example.code[..window[0].span.start as usize];
+                let line = example.line + before.matches('\n').count();
+                wrong.push(format!("{}:{line}: fn {}", example.path, window[1].lexeme));
+            }
+        }
+    }
+    assert!(
+        wrong.is_empty(),
+        "functions declared with `fn` instead of `fx`:\n{}",
+        wrong.join("\n")
+    );
+}
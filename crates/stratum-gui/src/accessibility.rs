@@ -0,0 +1,565 @@
+//! Accessibility: labels, roles and keyboard focus
+//!
+//! Every element has a role, and a label where one can be worked out:
+//! buttons are labelled by their text, text fields by their placeholder,
+//! checkboxes and toggles by their label. `.access_label()`,
+//! `.access_role()` and `.access_description()` set them explicitly.
+//! [`access_tree`] collects them into the tree a screen reader reads, and
+//! with the `accesskit` feature [`tree_update`] converts that tree into the
+//! AccessKit update the app hands to [`ScreenReader`](crate::screen_reader).
+//!
+//! Tab and Shift+Tab move keyboard focus through the focusable elements
+//! shown: buttons, text fields, checkboxes, toggles, radio buttons,
+//! dropdowns and sliders, plus any element given a tab index. Elements with
+//! a positive tab index come first, lowest first, then the rest in the order
+//! they're shown; a negative tab index leaves an element out. The focused
+//! element is drawn with a focus ring, and Enter or Space activates it.
+//!
+//! Focusable elements are numbered as they're rendered, so the numbering
+//! follows the view and focus stays put while the view keeps its shape.
+
+use std::cell::{Cell, RefCell};
+
+use iced::widget::{container, operation, Id};
+use iced::{Border, Element, Task};
+use stratum_core::bytecode::Value;
+
+use crate::element::GuiElement;
+use crate::runtime::Message;
+
+/// Id of no widget, focused to take focus away from text fields
+const NO_WIDGET: &str = "stratum-focus-none";
+
+thread_local! {
+    static FOCUSABLE: RefCell<Vec<Focusable>> = const { RefCell::new(Vec::new()) };
+    static FOCUSED: Cell<Option<usize>> = const { Cell::new(None) };
+    static PENDING: RefCell<Vec<Task<Message>>> = const { RefCell::new(Vec::new()) };
+    static FOCUS_BINDING: RefCell<Option<FocusBinding>> = const { RefCell::new(None) };
+}
+
+/// What an element is, for assistive technology
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Button,
+    CheckBox,
+    Switch,
+    RadioButton,
+    ComboBox,
+    Slider,
+    TextInput,
+    ProgressIndicator,
+    Image,
+    Label,
+    Heading,
+    Link,
+    List,
+    Table,
+    Tree,
+    Group,
+    Chart,
+    Canvas,
+    MenuBar,
+    /// A layout with no meaning of its own; its children stand in its place
+    Generic,
+}
+
+impl Role {
+    /// Parse a role name as used by `.access_role()`
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "button" => Self::Button,
+            "checkbox" => Self::CheckBox,
+            "switch" => Self::Switch,
+            "radio" => Self::RadioButton,
+            "combobox" => Self::ComboBox,
+            "slider" => Self::Slider,
+            "textbox" => Self::TextInput,
+            "progressbar" => Self::ProgressIndicator,
+            "image" => Self::Image,
+            "label" => Self::Label,
+            "heading" => Self::Heading,
+            "link" => Self::Link,
+            "list" => Self::List,
+            "table" => Self::Table,
+            "tree" => Self::Tree,
+            "group" => Self::Group,
+            "chart" => Self::Chart,
+            "canvas" => Self::Canvas,
+            "menubar" => Self::MenuBar,
+            "none" => Self::Generic,
+            _ => return None,
+        })
+    }
+
+    /// The name `from_name` parses
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Button => "button",
+            Self::CheckBox => "checkbox",
+            Self::Switch => "switch",
+            Self::RadioButton => "radio",
+            Self::ComboBox => "combobox",
+            Self::Slider => "slider",
+            Self::TextInput => "textbox",
+            Self::ProgressIndicator => "progressbar",
+            Self::Image => "image",
+            Self::Label => "label",
+            Self::Heading => "heading",
+            Self::Link => "link",
+            Self::List => "list",
+            Self::Table => "table",
+            Self::Tree => "tree",
+            Self::Group => "group",
+            Self::Chart => "chart",
+            Self::Canvas => "canvas",
+            Self::MenuBar => "menubar",
+            Self::Generic => "none",
+        }
+    }
+}
+
+/// Accessibility settings of an element; unset fields fall back to what
+/// the element's kind implies
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Access {
+    pub label: Option<String>,
+    pub role: Option<Role>,
+    /// Longer description read after the label
+    pub description: Option<String>,
+    /// Position in the tab order; negative leaves the element out
+    pub tab_index: Option<i32>,
+}
+
+/// A node of the accessibility tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccessNode {
+    pub role: Role,
+    pub label: Option<String>,
+    pub description: Option<String>,
+    /// Whether keyboard focus can land on the node
+    pub focusable: bool,
+    pub children: Vec<AccessNode>,
+}
+
+/// The accessibility tree of a view. Hidden elements are left out, and the
+/// children of unlabelled layouts take their layout's place.
+#[must_use]
+pub fn access_tree(root: &GuiElement) -> AccessNode {
+    AccessNode {
+        role: Role::Group,
+        label: root.access_label(),
+        description: root.style.access.description.clone(),
+        focusable: false,
+        children: access_children(root),
+    }
+}
+
+fn access_children(element: &GuiElement) -> Vec<AccessNode> {
+    let mut nodes = Vec::new();
+    for child in &element.children {
+        if !child.style.visible {
+            continue;
+        }
+        let role = child.access_role();
+        let label = child.access_label();
+        if role == Role::Generic && label.is_none() {
+            nodes.extend(access_children(child));
+        } else {
+            nodes.push(AccessNode {
+                role,
+                label,
+                description: child.style.access.description.clone(),
+                focusable: child.focusable().is_some(),
+                children: access_children(child),
+            });
+        }
+    }
+    nodes
+}
+
+/// A focusable element of the view being rendered
+#[derive(Debug, Clone)]
+pub struct Focusable {
+    pub tab_index: i32,
+    pub label: Option<String>,
+    /// Message Enter or Space sends while the element is focused
+    pub activate: Option<Message>,
+    /// Whether the element is a text field, which iced focuses itself
+    pub text_input: bool,
+}
+
+/// Forget the focusable elements of the last render, before rendering anew
+pub fn begin_frame() {
+    FOCUSABLE.with(|f| f.borrow_mut().clear());
+}
+
+/// Note a focusable element as it's rendered, returning its number
+pub fn register(focusable: Focusable) -> usize {
+    FOCUSABLE.with(|f| {
+        let mut f = f.borrow_mut();
+        f.push(focusable);
+        f.len() - 1
+    })
+}
+
+/// Whether the element with this number has keyboard focus
+#[must_use]
+pub fn is_focused(number: usize) -> bool {
+    FOCUSED.with(Cell::get) == Some(number)
+}
+
+/// The number of the focused element
+#[must_use]
+pub fn focused() -> Option<usize> {
+    FOCUSED.with(Cell::get)
+}
+
+/// Widget id of the text field with this number
+#[must_use]
+pub fn input_id(number: usize) -> Id {
+    Id::from(format!("stratum-focus-{number}"))
+}
+
+/// Numbers of the focusable elements in tab order
+#[must_use]
+pub fn tab_order() -> Vec<usize> {
+    FOCUSABLE.with(|f| {
+        let f = f.borrow();
+        let mut order: Vec<usize> = (0..f.len()).filter(|&i| f[i].tab_index >= 0).collect();
+        // Positive indexes first, lowest first; the sort is stable, so ties
+        // and the zeros stay in render order
+        order.sort_by_key(|&i| match f[i].tab_index {
+            0 => i32::MAX,
+            index => index,
+        });
+        order
+    })
+}
+
+/// Move focus to the next element in tab order, or the previous one,
+/// wrapping around at either end
+pub fn move_focus(forward: bool) {
+    let order = tab_order();
+    if order.is_empty() {
+        return;
+    }
+    let position = focused().and_then(|number| order.iter().position(|&n| n == number));
+    let next = match (position, forward) {
+        (None, true) => 0,
+        (None, false) => order.len() - 1,
+        (Some(p), true) => (p + 1) % order.len(),
+        (Some(p), false) => (p + order.len() - 1) % order.len(),
+    };
+    focus(order[next]);
+}
+
+/// Give the element with this number keyboard focus
+pub fn focus(number: usize) {
+    FOCUSED.with(|f| f.set(Some(number)));
+    let text_input = FOCUSABLE.with(|f| f.borrow().get(number).is_some_and(|f| f.text_input));
+    // Text fields take typing only when iced focuses them; focusing an id
+    // no widget has takes focus away from them all
+    let id = if text_input {
+        input_id(number)
+    } else {
+        Id::new(NO_WIDGET)
+    };
+    PENDING.with(|p| p.borrow_mut().push(operation::focus(id)));
+}
+
+/// Take keyboard focus away from every element
+pub fn clear_focus() {
+    FOCUSED.with(|f| f.set(None));
+    PENDING.with(|p| p.borrow_mut().push(operation::focus(Id::new(NO_WIDGET))));
+}
+
+/// Activate the focused element, as Enter or Space does
+pub fn activate() {
+    let message = focused().and_then(|number| {
+        FOCUSABLE.with(|f| f.borrow().get(number).and_then(|f| f.activate.clone()))
+    });
+    if let Some(message) = message {
+        PENDING.with(|p| p.borrow_mut().push(Task::done(message)));
+    }
+}
+
+/// Take the focus changes and activations waiting to be carried out
+pub fn take_pending() -> Task<Message> {
+    Task::batch(PENDING.with(|p| std::mem::take(&mut *p.borrow_mut())))
+}
+
+/// Draw a focus ring around a focused element
+pub fn focus_ring<'a>(content: Element<'a, Message>) -> Element<'a, Message> {
+    container(content)
+        .style(|theme: &iced::Theme| container::Style {
+            border: Border {
+                color: theme.palette().primary,
+                width: 2.0,
+                radius: 4.0.into(),
+            },
+            ..Default::default()
+        })
+        .into()
+}
+
+/// A state field kept in step with keyboard focus
+#[derive(Debug, Clone)]
+struct FocusBinding {
+    field: String,
+    /// The value last written to the field, to tell script changes apart
+    synced: Value,
+}
+
+/// Keep a state field set to the label of the focused element, and move
+/// focus when a callback sets the field to another label
+pub fn bind_focus(field: impl Into<String>) {
+    FOCUS_BINDING.with(|b| {
+        *b.borrow_mut() = Some(FocusBinding {
+            field: field.into(),
+            synced: Value::Null,
+        });
+    });
+}
+
+/// The field bound to focus, if any
+#[must_use]
+pub fn focus_field() -> Option<String> {
+    FOCUS_BINDING.with(|b| b.borrow().as_ref().map(|b| b.field.clone()))
+}
+
+/// Sync the bound field with focus, given its current value. Returns the
+/// value to write to the field if it should change.
+pub fn sync_focus_binding(current: &Value) -> Option<Value> {
+    let synced = FOCUS_BINDING.with(|b| b.borrow().as_ref().map(|b| b.synced.clone()))?;
+
+    // A callback set the field: focus the element with that label
+    if *current != synced {
+        match current {
+            Value::String(label) => {
+                let number = FOCUSABLE.with(|f| {
+                    f.borrow()
+                        .iter()
+                        .position(|f| f.label.as_deref() == Some(label.as_str()))
+                });
+                if let Some(number) = number {
+                    focus(number);
+                }
+            }
+            Value::Null => clear_focus(),
+            _ => {}
+        }
+    }
+
+    let label = focused()
+        .and_then(|number| FOCUSABLE.with(|f| f.borrow().get(number).and_then(|f| f.label.clone())))
+        .map_or(Value::Null, Value::string);
+    FOCUS_BINDING.with(|b| {
+        if let Some(binding) = b.borrow_mut().as_mut() {
+            binding.synced = label.clone();
+        }
+    });
+    (label != *current).then_some(label)
+}
+
+/// Forget focus, the focusable elements and any focus binding
+pub fn reset() {
+    FOCUSABLE.with(|f| f.borrow_mut().clear());
+    FOCUSED.with(|f| f.set(None));
+    PENDING.with(|p| p.borrow_mut().clear());
+    FOCUS_BINDING.with(|b| *b.borrow_mut() = None);
+}
+
+/// Convert an accessibility tree into an AccessKit update, with nodes
+/// numbered depth-first from the root and focus on the `focused`-th
+/// focusable node
+#[cfg(feature = "accesskit")]
+#[must_use]
+pub fn tree_update(root: &AccessNode, focused: Option<usize>) -> accesskit::TreeUpdate {
+    fn add(
+        node: &AccessNode,
+        nodes: &mut Vec<(accesskit::NodeId, accesskit::Node)>,
+        focusable: &mut Vec<accesskit::NodeId>,
+    ) -> accesskit::NodeId {
+        let id = accesskit::NodeId(nodes.len() as u64);
+        nodes.push((id, accesskit::Node::new(role_to_accesskit(node.role))));
+        let index = nodes.len() - 1;
+        if node.focusable {
+            focusable.push(id);
+        }
+
+        let children: Vec<_> = node
+            .children
+            .iter()
+            .map(|child| add(child, nodes, focusable))
+            .collect();
+        let built = &mut nodes[index].1;
+        if let Some(ref label) = node.label {
+            built.set_label(label.as_str());
+        }
+        if let Some(ref description) = node.description {
+            built.set_description(description.as_str());
+        }
+        if node.focusable {
+            built.add_action(accesskit::Action::Focus);
+            built.add_action(accesskit::Action::Click);
+        }
+        built.set_children(children);
+        id
+    }
+
+    let mut nodes = Vec::new();
+    let mut focusable = Vec::new();
+    let root_id = add(root, &mut nodes, &mut focusable);
+    let focus = focused
+        .and_then(|n| focusable.get(n).copied())
+        .unwrap_or(root_id);
+    accesskit::TreeUpdate {
+        nodes,
+        tree: Some(accesskit::Tree::new(root_id)),
+        focus,
+    }
+}
+
+#[cfg(feature = "accesskit")]
+fn role_to_accesskit(role: Role) -> accesskit::Role {
+    use accesskit::Role as A;
+    match role {
+        Role::Button => A::Button,
+        Role::CheckBox => A::CheckBox,
+        Role::Switch => A::Switch,
+        Role::RadioButton => A::RadioButton,
+        Role::ComboBox => A::ComboBox,
+        Role::Slider => A::Slider,
+        Role::TextInput => A::TextInput,
+        Role::ProgressIndicator => A::ProgressIndicator,
+        Role::Image => A::Image,
+        Role::Label => A::Label,
+        Role::Heading => A::Heading,
+        Role::Link => A::Link,
+        Role::List => A::List,
+        Role::Table => A::Table,
+        Role::Tree => A::Tree,
+        Role::Group => A::Group,
+        Role::Chart => A::Figure,
+        Role::Canvas => A::Canvas,
+        Role::MenuBar => A::MenuBar,
+        Role::Generic => A::GenericContainer,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn focusable(tab_index: i32, label: &str) -> Focusable {
+        Focusable {
+            tab_index,
+            label: Some(label.to_string()),
+            activate: Some(Message::NoOp),
+            text_input: false,
+        }
+    }
+
+    #[test]
+    fn test_role_names() {
+        for role in [Role::Button, Role::TextInput, Role::Generic, Role::MenuBar] {
+            assert_eq!(Role::from_name(role.name()), Some(role));
+        }
+        assert_eq!(Role::from_name("widget"), None);
+    }
+
+    #[test]
+    fn test_tab_order() {
+        reset();
+        begin_frame();
+        register(focusable(0, "a"));
+        register(focusable(2, "b"));
+        register(focusable(-1, "skipped"));
+        register(focusable(1, "c"));
+        register(focusable(0, "d"));
+        assert_eq!(tab_order(), [3, 1, 0, 4]);
+
+        move_focus(true);
+        assert_eq!(focused(), Some(3));
+        move_focus(false);
+        assert_eq!(focused(), Some(4));
+        move_focus(true);
+        assert_eq!(focused(), Some(3));
+        assert!(is_focused(3));
+        reset();
+    }
+
+    #[test]
+    fn test_focus_binding() {
+        reset();
+        begin_frame();
+        register(focusable(0, "Save"));
+        register(focusable(0, "Cancel"));
+        bind_focus("focused");
+        assert_eq!(focus_field().as_deref(), Some("focused"));
+
+        // Nothing focused and the field is null: nothing to write
+        assert_eq!(sync_focus_binding(&Value::Null), None);
+
+        // Tabbing writes the label to the field
+        move_focus(true);
+        assert_eq!(
+            sync_focus_binding(&Value::Null),
+            Some(Value::string("Save"))
+        );
+
+        // Setting the field moves focus
+        assert_eq!(sync_focus_binding(&Value::string("Cancel")), None);
+        assert_eq!(focused(), Some(1));
+        reset();
+    }
+
+    #[cfg(feature = "accesskit")]
+    #[test]
+    fn test_tree_update() {
+        use crate::element::GuiElement;
+        use accesskit::{Action, NodeId};
+
+        let root = GuiElement::vstack()
+            .child(
+                GuiElement::text("Sign in")
+                    .access_role(Role::Heading)
+                    .build(),
+            )
+            .child(GuiElement::text_field().placeholder("Email").build())
+            .child(
+                GuiElement::hstack()
+                    .child(GuiElement::button("Cancel").build())
+                    .child(GuiElement::button("Continue").build())
+                    .build(),
+            )
+            .build();
+        let update = tree_update(&access_tree(&root), Some(2));
+
+        // Depth-first from the root; the unlabelled row gives way to its buttons
+        let nodes: Vec<_> = update
+            .nodes
+            .iter()
+            .map(|(id, node)| (id.0, node.role(), node.label()))
+            .collect();
+        assert_eq!(
+            nodes,
+            [
+                (0, accesskit::Role::Group, None),
+                (1, accesskit::Role::Heading, Some("Sign in")),
+                (2, accesskit::Role::TextInput, Some("Email")),
+                (3, accesskit::Role::Button, Some("Cancel")),
+                (4, accesskit::Role::Button, Some("Continue")),
+            ]
+        );
+        assert_eq!(update.nodes[0].1.children(), [1, 2, 3, 4].map(NodeId));
+        assert_eq!(update.tree.as_ref().map(|tree| tree.root), Some(NodeId(0)));
+        assert!(update.nodes[3].1.supports_action(Action::Click));
+        assert!(!update.nodes[1].1.supports_action(Action::Focus));
+        // The third focusable node: the email field is the first
+        assert_eq!(update.focus, NodeId(4));
+        assert_eq!(tree_update(&access_tree(&root), None).focus, NodeId(0));
+    }
+}
@@ -0,0 +1,508 @@
+//! Animations and transitions
+//!
+//! `Gui.animate(field, to, duration, easing?)` moves a numeric state field to
+//! a new value over time, re-running the view function each frame as it
+//! goes. Elements given a `.transition(duration, easing?)` animate on their
+//! own: when the view function produces different padding, fixed sizes,
+//! colors or borders for them, they ease from what is shown to the new
+//! values. Elements are told apart by their position in the tree, so a
+//! transition follows an element as long as the tree keeps its shape.
+//!
+//! Both are stepped by the app on a timer subscription that only runs while
+//! something is moving.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use stratum_core::bytecode::Value;
+
+use crate::element::GuiElement;
+use crate::layout::Size;
+use crate::theme::Color;
+
+/// How often animations are stepped
+pub const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+/// How an animation's progress is spread over its duration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Easing {
+    /// Constant speed
+    Linear,
+    /// Start slowly
+    EaseIn,
+    /// End slowly
+    EaseOut,
+    /// Start and end slowly
+    #[default]
+    EaseInOut,
+    /// Start slowly, more strongly than `EaseIn`
+    EaseInCubic,
+    /// End slowly, more strongly than `EaseOut`
+    EaseOutCubic,
+    /// Start and end slowly, more strongly than `EaseInOut`
+    EaseInOutCubic,
+    /// Overshoot the end and settle back
+    EaseOutBack,
+    /// Bounce against the end like a dropped ball
+    Bounce,
+}
+
+impl Easing {
+    /// All easings, in the order they are listed to users
+    pub const ALL: [Self; 9] = [
+        Self::Linear,
+        Self::EaseIn,
+        Self::EaseOut,
+        Self::EaseInOut,
+        Self::EaseInCubic,
+        Self::EaseOutCubic,
+        Self::EaseInOutCubic,
+        Self::EaseOutBack,
+        Self::Bounce,
+    ];
+
+    /// Parse an easing name such as "ease_in_out" or "ease-in-out"
+    #[must_use]
+    pub fn parse(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase().replace('-', "_");
+        Self::ALL.into_iter().find(|easing| easing.name() == name)
+    }
+
+    /// The easing's name
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Linear => "linear",
+            Self::EaseIn => "ease_in",
+            Self::EaseOut => "ease_out",
+            Self::EaseInOut => "ease_in_out",
+            Self::EaseInCubic => "ease_in_cubic",
+            Self::EaseOutCubic => "ease_out_cubic",
+            Self::EaseInOutCubic => "ease_in_out_cubic",
+            Self::EaseOutBack => "ease_out_back",
+            Self::Bounce => "bounce",
+        }
+    }
+
+    /// Eased progress for linear progress `t` in 0..=1
+    #[must_use]
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t,
+            Self::EaseOut => t * (2.0 - t),
+            Self::EaseInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Self::EaseInCubic => t.powi(3),
+            Self::EaseOutCubic => 1.0 - (1.0 - t).powi(3),
+            Self::EaseInOutCubic => {
+                if t < 0.5 {
+                    4.0 * t.powi(3)
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            Self::EaseOutBack => {
+                const C1: f32 = 1.701_58;
+                const C3: f32 = C1 + 1.0;
+                1.0 + C3 * (t - 1.0).powi(3) + C1 * (t - 1.0).powi(2)
+            }
+            Self::Bounce => {
+                const N1: f32 = 7.5625;
+                const D1: f32 = 2.75;
+                if t < 1.0 / D1 {
+                    N1 * t * t
+                } else if t < 2.0 / D1 {
+                    let t = t - 1.5 / D1;
+                    N1 * t * t + 0.75
+                } else if t < 2.5 / D1 {
+                    let t = t - 2.25 / D1;
+                    N1 * t * t + 0.9375
+                } else {
+                    let t = t - 2.625 / D1;
+                    N1 * t * t + 0.984_375
+                }
+            }
+        }
+    }
+}
+
+/// Progress of something started at `started` lasting `duration`, in 0..=1
+fn progress(started: Instant, duration: Duration, now: Instant) -> f32 {
+    if duration.is_zero() {
+        return 1.0;
+    }
+    let elapsed = now.saturating_duration_since(started);
+    (elapsed.as_secs_f32() / duration.as_secs_f32()).min(1.0)
+}
+
+/// A state field moving towards a value
+#[derive(Debug, Clone)]
+pub struct Animation {
+    /// Field path of the animated state field
+    pub field: String,
+    /// Value to end at
+    pub to: f64,
+    /// How long the animation takes
+    pub duration: Duration,
+    /// How progress is spread over the duration
+    pub easing: Easing,
+    /// Value started from, with whether it was an integer; set when the
+    /// animation starts
+    from: Option<(f64, bool)>,
+    started: Option<Instant>,
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+impl Animation {
+    /// An animation of a field to a value
+    #[must_use]
+    pub fn new(field: impl Into<String>, to: f64, duration: Duration, easing: Easing) -> Self {
+        Self {
+            field: field.into(),
+            to,
+            duration,
+            easing,
+            from: None,
+            started: None,
+        }
+    }
+
+    /// Start from the field's current value; integer fields stay integers
+    pub fn start(&mut self, current: &Value, now: Instant) {
+        self.from = Some(match current {
+            Value::Int(i) => (*i as f64, true),
+            Value::Float(f) => (*f, false),
+            // Nothing to animate from: jump straight to the end
+            _ => (self.to, false),
+        });
+        self.started = Some(now);
+    }
+
+    /// The field's value at `now`, and whether the animation is over
+    #[must_use]
+    pub fn value_at(&self, now: Instant) -> (Value, bool) {
+        let (from, integer) = self.from.unwrap_or((self.to, false));
+        let t = self
+            .started
+            .map_or(1.0, |started| progress(started, self.duration, now));
+        let value = from + (self.to - from) * f64::from(self.easing.apply(t));
+        let done = t >= 1.0;
+        let value = if done { self.to } else { value };
+        if integer {
+            (Value::Int(value.round() as i64), done)
+        } else {
+            (Value::Float(value), done)
+        }
+    }
+}
+
+/// How an element animates changes to its style
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transition {
+    /// How long each change takes
+    pub duration: Duration,
+    /// How progress is spread over the duration
+    pub easing: Easing,
+}
+
+impl Transition {
+    /// A transition of the given duration and easing
+    #[must_use]
+    pub const fn new(duration: Duration, easing: Easing) -> Self {
+        Self { duration, easing }
+    }
+}
+
+/// The style values of an element that transitions animate
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct AnimatedStyle {
+    pub padding: Option<f32>,
+    pub width: Option<f32>,
+    pub height: Option<f32>,
+    pub background: Option<Color>,
+    pub foreground: Option<Color>,
+    pub border_color: Option<Color>,
+    pub border_width: Option<f32>,
+    pub corner_radius: Option<f32>,
+}
+
+impl AnimatedStyle {
+    /// The animated values of an element
+    #[must_use]
+    pub fn of(element: &GuiElement) -> Self {
+        let fixed = |size: Option<Size>| match size {
+            Some(Size::Fixed(px)) => Some(px),
+            _ => None,
+        };
+        let style = &element.style;
+        Self {
+            padding: style.padding,
+            width: fixed(style.width),
+            height: fixed(style.height),
+            background: style.widget_style.background,
+            foreground: style.widget_style.foreground,
+            border_color: style.widget_style.border_color,
+            border_width: style.widget_style.border_width,
+            corner_radius: style.widget_style.corner_radius,
+        }
+    }
+
+    /// Values a fraction `t` of the way to `to`. A value set on only one
+    /// side has nothing to ease from and takes `to`'s.
+    #[must_use]
+    pub fn lerp(&self, to: &Self, t: f32) -> Self {
+        let float = |a: Option<f32>, b: Option<f32>| match (a, b) {
+            (Some(a), Some(b)) => Some(a + (b - a) * t),
+            _ => b,
+        };
+        let color = |a: Option<Color>, b: Option<Color>| match (a, b) {
+            (Some(a), Some(b)) => Some(lerp_color(a, b, t)),
+            _ => b,
+        };
+        Self {
+            padding: float(self.padding, to.padding),
+            width: float(self.width, to.width),
+            height: float(self.height, to.height),
+            background: color(self.background, to.background),
+            foreground: color(self.foreground, to.foreground),
+            border_color: color(self.border_color, to.border_color),
+            border_width: float(self.border_width, to.border_width),
+            corner_radius: float(self.corner_radius, to.corner_radius),
+        }
+    }
+
+    /// An element showing these values
+    #[must_use]
+    pub fn apply_to(&self, element: &GuiElement) -> GuiElement {
+        let mut element = element.clone();
+        let style = &mut element.style;
+        style.padding = self.padding;
+        if self.width.is_some() {
+            style.width = self.width.map(Size::Fixed);
+        }
+        if self.height.is_some() {
+            style.height = self.height.map(Size::Fixed);
+        }
+        style.widget_style.background = self.background;
+        style.widget_style.foreground = self.foreground;
+        style.widget_style.border_color = self.border_color;
+        style.widget_style.border_width = self.border_width;
+        style.widget_style.corner_radius = self.corner_radius;
+        element
+    }
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn lerp_color(from: Color, to: Color, t: f32) -> Color {
+    let channel = |a: u8, b: u8| {
+        (f32::from(a) + (f32::from(b) - f32::from(a)) * t)
+            .round()
+            .clamp(0.0, 255.0) as u8
+    };
+    Color {
+        r: channel(from.r, to.r),
+        g: channel(from.g, to.g),
+        b: channel(from.b, to.b),
+        a: channel(from.a, to.a),
+    }
+}
+
+/// A transition under way, or settled at `to`
+#[derive(Debug, Clone)]
+struct TransitionState {
+    from: AnimatedStyle,
+    to: AnimatedStyle,
+    started: Instant,
+    transition: Transition,
+}
+
+impl TransitionState {
+    fn current(&self, now: Instant) -> (AnimatedStyle, bool) {
+        let t = progress(self.started, self.transition.duration, now);
+        if t >= 1.0 {
+            (self.to, true)
+        } else {
+            (
+                self.from.lerp(&self.to, self.transition.easing.apply(t)),
+                false,
+            )
+        }
+    }
+}
+
+/// The transitions of an element tree, by each element's path from the root
+#[derive(Debug, Default)]
+pub struct Transitions {
+    states: HashMap<Vec<usize>, TransitionState>,
+    animating: bool,
+}
+
+impl Transitions {
+    /// No transitions yet
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether a transition was under way at the last `apply`
+    #[must_use]
+    pub fn is_animating(&self) -> bool {
+        self.animating
+    }
+
+    /// The tree as it should be shown at `now`: elements with a transition
+    /// show their eased values, starting a transition wherever the view
+    /// function changed them
+    pub fn apply(&mut self, root: &Arc<GuiElement>, now: Instant) -> Arc<GuiElement> {
+        let mut seen = Vec::new();
+        self.animating = false;
+        let root = self.apply_at(root, &mut Vec::new(), now, &mut seen);
+        // Elements that left the tree start afresh if they come back
+        self.states.retain(|path, _| seen.contains(path));
+        root
+    }
+
+    fn apply_at(
+        &mut self,
+        element: &Arc<GuiElement>,
+        path: &mut Vec<usize>,
+        now: Instant,
+        seen: &mut Vec<Vec<usize>>,
+    ) -> Arc<GuiElement> {
+        let mut children = Vec::with_capacity(element.children.len());
+        let mut changed = false;
+        for (index, child) in element.children.iter().enumerate() {
+            path.push(index);
+            let shown = self.apply_at(child, path, now, seen);
+            path.pop();
+            changed |= !Arc::ptr_eq(&shown, child);
+            children.push(shown);
+        }
+
+        let Some(transition) = element.style.transition else {
+            if !changed {
+                return Arc::clone(element);
+            }
+            let mut element = element.as_ref().clone();
+            element.children = children;
+            return Arc::new(element);
+        };
+
+        seen.push(path.clone());
+        let target = AnimatedStyle::of(element);
+        let state = self
+            .states
+            .entry(path.clone())
+            .or_insert_with(|| TransitionState {
+                from: target,
+                to: target,
+                started: now,
+                transition,
+            });
+        if state.to != target {
+            // Ease from wherever the last transition got to
+            let (shown, _) = state.current(now);
+            *state = TransitionState {
+                from: shown,
+                to: target,
+                started: now,
+                transition,
+            };
+        }
+        let (shown, done) = state.current(now);
+        self.animating |= !done;
+
+        let mut element = shown.apply_to(element);
+        element.children = children;
+        Arc::new(element)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_easing_parse() {
+        assert_eq!(Easing::parse("ease_in_out"), Some(Easing::EaseInOut));
+        assert_eq!(Easing::parse("Ease-Out-Cubic"), Some(Easing::EaseOutCubic));
+        assert_eq!(Easing::parse("wobble"), None);
+        for easing in Easing::ALL {
+            assert_eq!(Easing::parse(easing.name()), Some(easing));
+        }
+    }
+
+    #[test]
+    fn test_easing_endpoints() {
+        for easing in Easing::ALL {
+            assert!(easing.apply(0.0).abs() < 1e-4, "{easing:?} at 0");
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-4, "{easing:?} at 1");
+        }
+        assert!((Easing::Linear.apply(0.25) - 0.25).abs() < f32::EPSILON);
+        assert!(Easing::EaseIn.apply(0.25) < 0.25);
+        assert!(Easing::EaseOut.apply(0.25) > 0.25);
+        assert!(Easing::EaseOutBack.apply(0.8) > 1.0);
+    }
+
+    #[test]
+    fn test_animation_steps() {
+        let start = Instant::now();
+        let mut animation = Animation::new("x", 100.0, Duration::from_millis(100), Easing::Linear);
+        animation.start(&Value::Int(0), start);
+
+        let (value, done) = animation.value_at(start + Duration::from_millis(50));
+        assert_eq!(value, Value::Int(50));
+        assert!(!done);
+        let (value, done) = animation.value_at(start + Duration::from_millis(150));
+        assert_eq!(value, Value::Int(100));
+        assert!(done);
+
+        // Fields without a number jump to the end
+        animation.start(&Value::Null, start);
+        assert_eq!(animation.value_at(start).0, Value::Float(100.0));
+    }
+
+    #[test]
+    fn test_transition_eases_changes() {
+        let transition = Transition::new(Duration::from_millis(100), Easing::Linear);
+        let view = |padding: f32| {
+            let child = GuiElement::text("hi")
+                .padding(padding)
+                .transition(transition)
+                .build();
+            Arc::new(GuiElement::vstack().child(child).build())
+        };
+        let padding = |root: &Arc<GuiElement>| root.children[0].style.padding.unwrap();
+
+        let start = Instant::now();
+        let mut transitions = Transitions::new();
+        let shown = transitions.apply(&view(0.0), start);
+        assert!(padding(&shown).abs() < f32::EPSILON);
+        assert!(!transitions.is_animating());
+
+        // The view changes: ease towards the new padding
+        transitions.apply(&view(20.0), start);
+        assert!(transitions.is_animating());
+        let shown = transitions.apply(&view(20.0), start + Duration::from_millis(50));
+        assert!((padding(&shown) - 10.0).abs() < 1e-3);
+        let shown = transitions.apply(&view(20.0), start + Duration::from_millis(100));
+        assert!((padding(&shown) - 20.0).abs() < f32::EPSILON);
+        assert!(!transitions.is_animating());
+    }
+
+    #[test]
+    fn test_lerp_color() {
+        let black = Color::rgb(0, 0, 0);
+        let white = Color::rgb(255, 255, 255);
+        assert_eq!(lerp_color(black, white, 0.5), Color::rgb(128, 128, 128));
+        assert_eq!(lerp_color(black, white, 1.0), white);
+    }
+}
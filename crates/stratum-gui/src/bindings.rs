@@ -8,12 +8,18 @@ use std::cell::{Cell, RefCell};
 use std::sync::Arc;
 
 use stratum_core::bytecode::Value;
-use stratum_core::vm::{RuntimeResult, VM};
+use stratum_core::vm::{Access, Capability, RuntimeResult, VM};
 
+use crate::animation::Animation;
+use crate::callback::CallbackId;
+use crate::canvas::CanvasContext;
+use crate::dnd::FileDropEvent;
 use crate::element::GuiElement;
 use crate::natives::gui_native_functions;
 use crate::runtime::GuiRuntime;
-use crate::theme::{StratumPalette, ThemePreset};
+use crate::theme::{StratumPalette, ThemeDefinition, ThemePreset};
+use crate::timer::{BackgroundTask, Timer};
+use crate::tray::TrayConfig;
 
 /// Pending theme change request
 #[derive(Clone)]
@@ -25,6 +31,8 @@ pub enum PendingTheme {
         name: String,
         palette: StratumPalette,
     },
+    /// Set a theme defined with Gui.theme()
+    Defined(ThemeDefinition),
 }
 
 /// A pending field update request from a callback
@@ -46,6 +54,18 @@ thread_local! {
     /// Pending field updates from callbacks
     /// These are processed after callback execution completes
     static PENDING_FIELD_UPDATES: RefCell<Vec<PendingFieldUpdate>> = const { RefCell::new(Vec::new()) };
+    /// Tray icon requested by Gui.tray(), created by the running app
+    static PENDING_TRAY: RefCell<Option<TrayConfig>> = const { RefCell::new(None) };
+    /// Window file drop callbacks registered by Gui.on_file_drop() and friends
+    static PENDING_FILE_DROP: RefCell<Vec<(FileDropEvent, CallbackId)>> = const { RefCell::new(Vec::new()) };
+    /// Animations requested by Gui.animate(), started by the running app
+    static PENDING_ANIMATIONS: RefCell<Vec<Animation>> = const { RefCell::new(Vec::new()) };
+    /// Timers started by Gui.every() and Gui.after(), kept by the running app
+    static PENDING_TIMERS: RefCell<Vec<Timer>> = const { RefCell::new(Vec::new()) };
+    /// Ids of timers stopped by Gui.cancel_timer()
+    static CANCELLED_TIMERS: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+    /// Tasks spawned by Gui.spawn_task(), run by the app one message each
+    static PENDING_TASKS: RefCell<Vec<BackgroundTask>> = const { RefCell::new(Vec::new()) };
 }
 
 /// Request application quit (called from Gui.quit())
@@ -76,11 +96,82 @@ pub fn request_custom_theme(name: String, palette: StratumPalette) {
     });
 }
 
+/// Request a theme defined in Stratum (called from Gui.theme())
+pub fn request_theme_definition(definition: ThemeDefinition) {
+    PENDING_THEME.with(|theme| {
+        *theme.borrow_mut() = Some(PendingTheme::Defined(definition));
+    });
+}
+
 /// Take the pending theme request and clear it
 pub fn take_pending_theme() -> Option<PendingTheme> {
     PENDING_THEME.with(|theme| theme.borrow_mut().take())
 }
 
+/// Request a system tray icon (called from Gui.tray())
+pub fn request_tray(config: TrayConfig) {
+    PENDING_TRAY.with(|tray| {
+        *tray.borrow_mut() = Some(config);
+    });
+}
+
+/// Take the pending tray request and clear it
+pub fn take_pending_tray() -> Option<TrayConfig> {
+    PENDING_TRAY.with(|tray| tray.borrow_mut().take())
+}
+
+/// Request an animation of a state field (called from Gui.animate())
+pub fn request_animation(animation: Animation) {
+    PENDING_ANIMATIONS.with(|pending| pending.borrow_mut().push(animation));
+}
+
+/// Take the animations requested since the last call
+pub fn take_pending_animations() -> Vec<Animation> {
+    PENDING_ANIMATIONS.with(|pending| std::mem::take(&mut *pending.borrow_mut()))
+}
+
+/// Start a timer (called from Gui.every() and Gui.after())
+pub fn request_timer(timer: Timer) {
+    PENDING_TIMERS.with(|pending| pending.borrow_mut().push(timer));
+}
+
+/// Take the timers started since the last call
+pub fn take_pending_timers() -> Vec<Timer> {
+    PENDING_TIMERS.with(|pending| std::mem::take(&mut *pending.borrow_mut()))
+}
+
+/// Stop a timer (called from Gui.cancel_timer())
+pub fn request_timer_cancel(id: u64) {
+    PENDING_TIMERS.with(|pending| pending.borrow_mut().retain(|timer| timer.id != id));
+    CANCELLED_TIMERS.with(|cancelled| cancelled.borrow_mut().push(id));
+}
+
+/// Take the ids of timers stopped since the last call
+pub fn take_cancelled_timers() -> Vec<u64> {
+    CANCELLED_TIMERS.with(|cancelled| std::mem::take(&mut *cancelled.borrow_mut()))
+}
+
+/// Queue a background task (called from Gui.spawn_task())
+pub fn request_task(task: BackgroundTask) {
+    PENDING_TASKS.with(|pending| pending.borrow_mut().push(task));
+}
+
+/// Take the tasks spawned since the last call
+pub fn take_pending_tasks() -> Vec<BackgroundTask> {
+    PENDING_TASKS.with(|pending| std::mem::take(&mut *pending.borrow_mut()))
+}
+
+/// Register a callback for files dragged over or dropped on the window
+/// (called from Gui.on_file_drop() and friends)
+pub fn request_file_drop_callback(event: FileDropEvent, callback_id: CallbackId) {
+    PENDING_FILE_DROP.with(|pending| pending.borrow_mut().push((event, callback_id)));
+}
+
+/// Take the file drop callbacks registered since the last call
+pub fn take_pending_file_drop_callbacks() -> Vec<(FileDropEvent, CallbackId)> {
+    PENDING_FILE_DROP.with(|pending| std::mem::take(&mut *pending.borrow_mut()))
+}
+
 /// Register a callback closure and return its ID
 ///
 /// This is called from Gui.register_callback() in Stratum code.
@@ -146,7 +237,7 @@ pub fn take_pending_field_updates() -> Vec<PendingFieldUpdate> {
 /// ```
 pub fn register_gui(vm: &mut VM) {
     // Register the main Gui namespace handler
-    vm.register_namespace("Gui", gui_method);
+    vm.register_namespace("Gui", gui_method, gui_access);
 
     // Register special methods that need VM access
     vm.register_vm_method("Gui", "run", gui_run_method);
@@ -156,7 +247,54 @@ pub fn register_gui(vm: &mut VM) {
     vm.register_vm_method("Gui", "update_field", gui_update_field_method);
 
     // Register method handler for GuiElement values to enable method chaining
-    vm.register_value_method_handler("GuiElement", gui_element_method);
+    vm.register_value_method_handler("GuiElement", gui_element_method, gui_element_access);
+}
+
+/// What a `Gui` method needs from the VM's permissions
+///
+/// Images, the tray icon and images in markdown are read from files or
+/// downloaded; everything else stays inside the window.
+pub fn gui_access(method: &str, args: &[Value]) -> Option<Vec<Access>> {
+    let accesses = match method {
+        "image" => image_access(args.first()),
+        "set_image_path" => image_access(args.get(1)),
+        "tray" => match args.first() {
+            Some(Value::String(icon)) => vec![Access::new(Capability::Read, icon.as_str())],
+            _ => vec![Access::any(Capability::Read)],
+        },
+        "markdown" => match args.first() {
+            Some(Value::String(text)) if !text.contains("![") => Vec::new(),
+            _ => vec![Access::any(Capability::Read)],
+        },
+        "run" | "app" | "quit" | "register_callback" | "update_field" => Vec::new(),
+        _ => {
+            gui_native_name(method)?;
+            Vec::new()
+        }
+    };
+    Some(accesses)
+}
+
+/// What a fluent method on a `GuiElement` needs from the VM's permissions
+pub fn gui_element_access(receiver: &Value, method: &str, args: &[Value]) -> Option<Vec<Access>> {
+    if CanvasContext::from_value(receiver).is_some() {
+        return Some(Vec::new());
+    }
+    match element_native_name(method)? {
+        "gui_set_image_path" => Some(image_access(args.first())),
+        _ => Some(Vec::new()),
+    }
+}
+
+/// Reading an image file, or downloading an image URL; image values need
+/// neither
+fn image_access(source: Option<&Value>) -> Vec<Access> {
+    match source {
+        Some(Value::String(url)) if crate::image_loader::is_url(url) => vec![Access::url(url)],
+        Some(Value::String(path)) => vec![Access::new(Capability::Read, path.as_str())],
+        Some(Value::Image(_)) => Vec::new(),
+        _ => vec![Access::any(Capability::Read), Access::any(Capability::Net)],
+    }
 }
 
 /// Handle method calls on GuiElement values for fluent method chaining
@@ -166,9 +304,34 @@ pub fn register_gui(vm: &mut VM) {
 /// The receiver (GuiElement) is prepended to the args and passed to the
 /// appropriate native function.
 pub fn gui_element_method(receiver: &Value, method: &str, args: &[Value]) -> Result<Value, String> {
+    // A canvas draw callback's context records drawing calls instead
+    if let Some(ctx) = CanvasContext::from_value(receiver) {
+        return ctx.call(method, args);
+    }
+
     // Get the native function registry
     let natives = gui_native_functions();
+    let native_name = element_native_name(method)
+        .ok_or_else(|| format!("GuiElement has no method '{method}'"))?;
 
+    // Build args with receiver prepended
+    let mut full_args = Vec::with_capacity(args.len() + 1);
+    full_args.push(receiver.clone());
+    full_args.extend_from_slice(args);
+
+    // Find and call the native function
+    for (name, func) in &natives {
+        if *name == native_name {
+            return (func.function)(&full_args);
+        }
+    }
+
+    Err(format!("GUI native function '{}' not found", native_name))
+}
+
+/// The gui native a fluent `GuiElement` method calls with the element
+/// prepended to its arguments
+fn element_native_name(method: &str) -> Option<&'static str> {
     // Map fluent method names to native function names
     // The native functions expect the element as the first argument
     let native_name = match method {
@@ -203,6 +366,7 @@ pub fn gui_element_method(receiver: &Value, method: &str, args: &[Value]) -> Res
         // Image properties
         "content_fit" => "gui_set_content_fit",
         "image_path" => "gui_set_image_path",
+        "rotation" => "gui_set_rotation",
 
         // Checkbox/Toggle/Radio properties
         "checkbox_label" => "gui_set_checkbox_label",
@@ -234,6 +398,16 @@ pub fn gui_element_method(receiver: &Value, method: &str, args: &[Value]) -> Res
         "selectable" => "gui_set_selectable",
         "selected_rows" => "gui_set_selected_rows",
         "column_width" => "gui_set_column_width",
+        "cell_formatter" | "format_column" => "gui_set_cell_formatter",
+        "table_id" => "gui_set_table_id",
+        "row_height" => "gui_set_row_height",
+        "resizable" => "gui_set_resizable",
+
+        // Tree and section configuration
+        "key" => "gui_set_key",
+        "expanded" | "expanded_nodes" => "gui_set_expanded",
+        "selected_node" => "gui_set_selected_node",
+        "indent" => "gui_set_indent",
 
         // Chart configuration
         "chart_title" | "title" => "gui_set_chart_title",
@@ -246,6 +420,11 @@ pub fn gui_element_method(receiver: &Value, method: &str, args: &[Value]) -> Res
         "show_grid" | "grid" => "gui_set_show_grid",
         "bar_color" => "gui_set_bar_color",
         "inner_radius" => "gui_set_inner_radius",
+        "stacked" => "gui_set_stacked",
+        "bins" => "gui_set_bins",
+        "tooltips" => "gui_set_tooltips",
+        "zoomable" => "gui_set_zoomable",
+        "toggle_legend" => "gui_set_toggle_legend",
 
         // OLAP Cube widget configuration
         "cube" => "gui_set_cube",
@@ -283,6 +462,38 @@ pub fn gui_element_method(receiver: &Value, method: &str, args: &[Value]) -> Res
         "on_selection_change" => "gui_on_selection_change",
         "on_row_click" => "gui_on_row_click",
         "on_cell_click" => "gui_on_cell_click",
+        "on_point_click" => "gui_on_point_click",
+
+        // Tree events
+        "on_expand" => "gui_on_expand",
+        "on_context_menu" => "gui_on_context_menu",
+
+        // Canvas events
+        "on_draw" => "gui_on_draw",
+        "on_canvas_event" => "gui_on_canvas_event",
+
+        // Menus
+        "context_menu" => "gui_context_menu",
+
+        // Form validation
+        "validate" => "gui_validate",
+
+        // Animation
+        "transition" => "gui_set_transition",
+
+        // Accessibility
+        "access_label" => "gui_set_access_label",
+        "access_role" => "gui_set_access_role",
+        "access_description" => "gui_set_access_description",
+        "tab_index" => "gui_set_tab_index",
+
+        // Drag and drop
+        "draggable" => "gui_draggable",
+        "drop_target" => "gui_drop_target",
+        "on_drop" => "gui_on_drop",
+
+        // Markdown
+        "on_link" => "gui_on_link",
 
         // OLAP events
         "on_drill" => "gui_on_drill",
@@ -312,6 +523,7 @@ pub fn gui_element_method(receiver: &Value, method: &str, args: &[Value]) -> Res
         "set_corner_radius" => "gui_set_corner_radius",
         "set_content_fit" => "gui_set_content_fit",
         "set_image_path" => "gui_set_image_path",
+        "set_rotation" => "gui_set_rotation",
         "set_checkbox_label" => "gui_set_checkbox_label",
         "set_toggle_on" => "gui_set_toggle_on",
         "set_toggle_label" => "gui_set_toggle_label",
@@ -332,6 +544,14 @@ pub fn gui_element_method(receiver: &Value, method: &str, args: &[Value]) -> Res
         "set_selectable" => "gui_set_selectable",
         "set_selected_rows" => "gui_set_selected_rows",
         "set_column_width" => "gui_set_column_width",
+        "set_cell_formatter" => "gui_set_cell_formatter",
+        "set_table_id" => "gui_set_table_id",
+        "set_row_height" => "gui_set_row_height",
+        "set_resizable" => "gui_set_resizable",
+        "set_key" => "gui_set_key",
+        "set_expanded" => "gui_set_expanded",
+        "set_selected_node" => "gui_set_selected_node",
+        "set_indent" => "gui_set_indent",
         "set_chart_title" => "gui_set_chart_title",
         "set_chart_size" => "gui_set_chart_size",
         "set_chart_data" => "gui_set_chart_data",
@@ -341,6 +561,11 @@ pub fn gui_element_method(receiver: &Value, method: &str, args: &[Value]) -> Res
         "set_show_grid" => "gui_set_show_grid",
         "set_bar_color" => "gui_set_bar_color",
         "set_inner_radius" => "gui_set_inner_radius",
+        "set_stacked" => "gui_set_stacked",
+        "set_bins" => "gui_set_bins",
+        "set_tooltips" => "gui_set_tooltips",
+        "set_zoomable" => "gui_set_zoomable",
+        "set_toggle_legend" => "gui_set_toggle_legend",
         "set_cube" => "gui_set_cube",
         "set_row_dimensions" => "gui_set_row_dimensions",
         "set_measures" => "gui_set_measures",
@@ -355,31 +580,33 @@ pub fn gui_element_method(receiver: &Value, method: &str, args: &[Value]) -> Res
         "add_chart_series" => "gui_add_chart_series",
         "bind_field" => "gui_bind_field",
 
-        _ => return Err(format!("GuiElement has no method '{method}'")),
+        _ => return None,
     };
+    Some(native_name)
+}
 
-    // Build args with receiver prepended
-    let mut full_args = Vec::with_capacity(args.len() + 1);
-    full_args.push(receiver.clone());
-    full_args.extend_from_slice(args);
+/// Dispatch GUI namespace methods
+///
+/// Maps clean Stratum method names (e.g., "vstack") to gui natives (e.g., "gui_vstack")
+pub fn gui_method(method: &str, args: &[Value]) -> Result<Value, String> {
+    // Get the native function registry
+    let natives = gui_native_functions();
+
+    let native_name =
+        gui_native_name(method).ok_or_else(|| format!("Gui has no method '{method}'"))?;
 
     // Find and call the native function
     for (name, func) in &natives {
         if *name == native_name {
-            return (func.function)(&full_args);
+            return (func.function)(args);
         }
     }
 
     Err(format!("GUI native function '{}' not found", native_name))
 }
 
-/// Dispatch GUI namespace methods
-///
-/// Maps clean Stratum method names (e.g., "vstack") to gui natives (e.g., "gui_vstack")
-pub fn gui_method(method: &str, args: &[Value]) -> Result<Value, String> {
-    // Get the native function registry
-    let natives = gui_native_functions();
-
+/// The gui native a `Gui` method calls
+fn gui_native_name(method: &str) -> Option<&'static str> {
     // Map the clean method name to the gui_* prefixed name
     let native_name = match method {
         // Layout functions
@@ -405,11 +632,89 @@ pub fn gui_method(method: &str, args: &[Value]) -> Result<Value, String> {
 
         // DataTable functions
         "data_table" => "gui_data_table",
+        "table" => "gui_table",
+
+        // Tree and section functions
+        "tree" => "gui_tree",
+        "section" => "gui_section",
+
+        // Canvas functions
+        "canvas" => "gui_canvas",
+
+        // Menu functions
+        "menu_bar" => "gui_menu_bar",
+        "context_menu" => "gui_context_menu",
+        "tray" => "gui_tray",
+
+        // Form validation functions
+        "validate" => "gui_validate",
+        "field_error" => "gui_field_error",
+        "form_valid" => "gui_form_valid",
+        "form_errors" => "gui_form_errors",
+        "reset_validation" => "gui_reset_validation",
+
+        // Markdown functions
+        "markdown" => "gui_markdown",
+
+        // Timer and task functions
+        "every" => "gui_every",
+        "after" => "gui_after",
+        "cancel_timer" => "gui_cancel_timer",
+        "spawn_task" => "gui_spawn_task",
+
+        // Navigation functions
+        "navigator" => "gui_navigator",
+        "push_page" => "gui_push_page",
+        "replace_page" => "gui_replace_page",
+        "pop_page" => "gui_pop_page",
+        "pop_to_root" => "gui_pop_to_root",
+        "go_back" => "gui_go_back",
+        "current_page" => "gui_current_page",
+        "page_params" => "gui_page_params",
+        "can_go_back" => "gui_can_go_back",
+
+        // Window functions
+        "open_window" => "gui_open_window",
+        "close_window" => "gui_close_window",
+        "send_to_window" => "gui_send_to_window",
+        "on_window" => "gui_on_window",
+        "window_state" => "gui_window_state",
+        "update_window_field" => "gui_update_window_field",
+        "current_window" => "gui_current_window",
+        "windows" => "gui_windows",
+
+        // Accessibility functions
+        "bind_focus" => "gui_bind_focus",
+
+        // Animation functions
+        "animate" => "gui_animate",
+        "easings" => "gui_easings",
+
+        // Clipboard and drag and drop functions
+        "clipboard_read" => "gui_clipboard_read",
+        "clipboard_write" => "gui_clipboard_write",
+        "draggable" => "gui_draggable",
+        "drop_target" => "gui_drop_target",
+        "on_file_drop" => "gui_on_file_drop",
+        "on_file_hover" => "gui_on_file_hover",
+        "on_file_hover_left" => "gui_on_file_hover_left",
+
+        // Dialog functions
+        "open_file_dialog" => "gui_open_file_dialog",
+        "save_file_dialog" => "gui_save_file_dialog",
+        "pick_folder" => "gui_pick_folder",
+        "alert" => "gui_alert",
+        "confirm" => "gui_confirm",
 
         // Chart functions
         "bar_chart" => "gui_bar_chart",
         "line_chart" => "gui_line_chart",
         "pie_chart" => "gui_pie_chart",
+        "scatter_chart" => "gui_scatter_chart",
+        "area_chart" => "gui_area_chart",
+        "stacked_bar_chart" => "gui_stacked_bar_chart",
+        "histogram" => "gui_histogram",
+        "candlestick_chart" => "gui_candlestick_chart",
 
         // OLAP Cube widget functions
         "cube_table" => "gui_cube_table",
@@ -432,6 +737,7 @@ pub fn gui_method(method: &str, args: &[Value]) -> Result<Value, String> {
         "theme_presets" => "gui_theme_presets",
         "set_theme" => "gui_set_theme",
         "custom_theme" => "gui_custom_theme",
+        "theme" => "gui_theme",
 
         // Element modification functions
         "set_text_bold" => "gui_set_text_bold",
@@ -459,6 +765,7 @@ pub fn gui_method(method: &str, args: &[Value]) -> Result<Value, String> {
         "set_image_path" => "gui_set_image_path",
         "set_content_fit" => "gui_set_content_fit",
         "set_opacity" => "gui_set_opacity",
+        "set_rotation" => "gui_set_rotation",
         "add_child" => "gui_add_child",
         "set_spacing" => "gui_set_spacing",
         "set_padding" => "gui_set_padding",
@@ -480,11 +787,31 @@ pub fn gui_method(method: &str, args: &[Value]) -> Result<Value, String> {
         "set_selectable" => "gui_set_selectable",
         "set_selected_rows" => "gui_set_selected_rows",
         "set_column_width" => "gui_set_column_width",
+        "set_cell_formatter" => "gui_set_cell_formatter",
+        "set_table_id" => "gui_set_table_id",
+        "set_row_height" => "gui_set_row_height",
+        "set_resizable" => "gui_set_resizable",
+        "set_key" => "gui_set_key",
+        "set_expanded" => "gui_set_expanded",
+        "set_selected_node" => "gui_set_selected_node",
+        "set_indent" => "gui_set_indent",
         "on_sort" => "gui_on_sort",
         "on_page_change" => "gui_on_page_change",
         "on_selection_change" => "gui_on_selection_change",
         "on_row_click" => "gui_on_row_click",
         "on_cell_click" => "gui_on_cell_click",
+        "on_point_click" => "gui_on_point_click",
+        "on_expand" => "gui_on_expand",
+        "on_context_menu" => "gui_on_context_menu",
+        "on_draw" => "gui_on_draw",
+        "on_canvas_event" => "gui_on_canvas_event",
+        "on_drop" => "gui_on_drop",
+        "on_link" => "gui_on_link",
+        "set_transition" => "gui_set_transition",
+        "set_access_label" => "gui_set_access_label",
+        "set_access_role" => "gui_set_access_role",
+        "set_access_description" => "gui_set_access_description",
+        "set_tab_index" => "gui_set_tab_index",
 
         // Chart configuration
         "set_chart_title" => "gui_set_chart_title",
@@ -497,6 +824,11 @@ pub fn gui_method(method: &str, args: &[Value]) -> Result<Value, String> {
         "set_show_grid" => "gui_set_show_grid",
         "set_bar_color" => "gui_set_bar_color",
         "set_inner_radius" => "gui_set_inner_radius",
+        "set_stacked" => "gui_set_stacked",
+        "set_bins" => "gui_set_bins",
+        "set_tooltips" => "gui_set_tooltips",
+        "set_zoomable" => "gui_set_zoomable",
+        "set_toggle_legend" => "gui_set_toggle_legend",
 
         // OLAP Cube widget configuration
         "set_cube" => "gui_set_cube",
@@ -531,17 +863,9 @@ pub fn gui_method(method: &str, args: &[Value]) -> Result<Value, String> {
         "on_toggle" => "gui_on_toggle",
         "on_select" => "gui_on_select",
 
-        _ => return Err(format!("Gui has no method '{method}'")),
+        _ => return None,
     };
-
-    // Find and call the native function
-    for (name, func) in &natives {
-        if *name == native_name {
-            return (func.function)(args);
-        }
-    }
-
-    Err(format!("GUI native function '{}' not found", native_name))
+    Some(native_name)
 }
 
 /// Handle Gui.run() which runs a GUI element in a window
@@ -561,6 +885,11 @@ pub fn gui_run_method(vm: &mut VM, _method: &str, args: &[Value]) -> RuntimeResu
         }));
     }
 
+    // The window is already open when the source is run again for a reload
+    if crate::hot_reload::is_reloading() {
+        return Ok(Value::Null);
+    }
+
     // Extract the GuiElement from the first argument
     let element = match &args[0] {
         Value::GuiElement(e) => {
@@ -665,6 +994,13 @@ pub fn gui_app_method(vm: &mut VM, _method: &str, args: &[Value]) -> RuntimeResu
         }
     };
 
+    // When the source is run again for a reload, hand the new view to the
+    // running app rather than opening another window
+    if crate::hot_reload::is_reloading() {
+        crate::hot_reload::capture_view(view_fn);
+        return Ok(Value::Null);
+    }
+
     // Extract optional window size from args 3 and 4
     let width = if let Some(Value::Int(w)) = args.get(3) {
         *w as u32
@@ -716,11 +1052,14 @@ pub fn gui_app_method(vm: &mut VM, _method: &str, args: &[Value]) -> RuntimeResu
     register_gui(&mut callback_vm);
 
     // Create the GUI runtime with state, view function, and VM for callbacks
-    let runtime = GuiRuntime::new(initial_state)
+    let mut runtime = GuiRuntime::new(initial_state)
         .with_window(&title, (width, height))
         .with_root(element)
         .with_view_fn(Arc::new(view_fn))
         .with_vm(callback_vm);
+    if let Some(source) = crate::hot_reload::source() {
+        runtime = runtime.with_hot_reload(source);
+    }
 
     // Run the GUI - this blocks until the window is closed
     runtime
@@ -826,6 +1165,65 @@ mod tests {
     use super::*;
     use stratum_core::VM;
 
+    /// Methods with the natives they call, read from a method table above
+    fn method_table(table: &str) -> Vec<(String, String)> {
+        let source = include_str!("bindings.rs");
+        let start = source.find(table).unwrap();
+        let end = start + source[start..].find("\n}\n").unwrap();
+        source[start..end]
+            .lines()
+            .filter_map(|line| {
+                let (names, native) = line.trim().split_once(" => ")?;
+                let native = native.strip_suffix(',')?.trim_matches('"');
+                native.starts_with("gui_").then(|| {
+                    names
+                        .split(" | ")
+                        .map(move |name| (name.trim_matches('"').to_string(), native.to_string()))
+                })
+            })
+            .flatten()
+            .collect()
+    }
+
+    #[test]
+    fn test_gui_methods_declare_their_access() {
+        let methods = method_table("fn gui_native_name(");
+        let element_methods = method_table("fn element_native_name(");
+        assert!(methods.len() > 100 && element_methods.len() > 100);
+
+        // Only the natives that load an image file need anything
+        let path = Value::string("logo.png");
+        for (method, native) in &methods {
+            let accesses = gui_access(method, &[path.clone(), path.clone()])
+                .unwrap_or_else(|| panic!("Gui.{method} declares no access"));
+            let loads = matches!(
+                native.as_str(),
+                "gui_image" | "gui_set_image_path" | "gui_tray"
+            );
+            assert_eq!(!accesses.is_empty(), loads, "Gui.{method}");
+        }
+        let element = gui_method("image", &[path.clone()]).unwrap();
+        for (method, native) in &element_methods {
+            let accesses = gui_element_access(&element, method, &[path.clone()])
+                .unwrap_or_else(|| panic!("GuiElement.{method} declares no access"));
+            assert_eq!(
+                !accesses.is_empty(),
+                native == "gui_set_image_path",
+                "GuiElement.{method}"
+            );
+        }
+
+        assert_eq!(
+            gui_access("image", &[Value::string("https://example.com/logo.png")]),
+            Some(vec![Access::new(Capability::Net, "example.com:443")])
+        );
+        assert_eq!(
+            gui_access("markdown", &[Value::string("![logo](logo.png)")]),
+            Some(vec![Access::any(Capability::Read)])
+        );
+        assert_eq!(gui_access("unknown_method", &[]), None);
+    }
+
     #[test]
     fn test_gui_method_unknown() {
         let result = gui_method("unknown_method", &[]);
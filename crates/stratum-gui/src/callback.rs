@@ -255,7 +255,7 @@ mod tests {
     use stratum_core::bytecode::NativeFunction;
 
     fn make_native_callback(f: fn(&[Value]) -> Result<Value, String>) -> Value {
-        Value::NativeFunction(NativeFunction::new("test_callback", 0, f))
+        Value::native(NativeFunction::new("test_callback", 0, f))
     }
 
     #[test]
@@ -0,0 +1,750 @@
+//! Canvas widget with an immediate drawing API
+//!
+//! `Gui.canvas(width, height, draw)` calls `draw` with a drawing context
+//! each time the view is rebuilt. The context records shapes, paths, text
+//! and images as [`DrawCommand`]s; the canvas then replays the recorded
+//! commands whenever iced redraws it. Mouse and keyboard input over the
+//! canvas reach the program's `on_canvas_event` callback as [`CanvasEvent`]s.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+use iced::alignment::{Horizontal, Vertical};
+use iced::mouse;
+use iced::widget::canvas::{self, Frame, Path, Stroke, Text};
+use iced::{keyboard, Color, Point, Rectangle, Renderer, Size, Theme};
+use stratum_core::bytecode::{GuiValue, HashableValue, Value, ValueMap};
+
+use crate::callback::CallbackId;
+use crate::runtime::Message;
+
+thread_local! {
+    static FRAMES: RefCell<HashMap<CallbackId, Arc<Vec<DrawCommand>>>> =
+        RefCell::new(HashMap::new());
+}
+
+/// A segment of a path
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathSegment {
+    MoveTo(f32, f32),
+    LineTo(f32, f32),
+    QuadTo {
+        cx: f32,
+        cy: f32,
+        x: f32,
+        y: f32,
+    },
+    Arc {
+        x: f32,
+        y: f32,
+        radius: f32,
+        start: f32,
+        end: f32,
+    },
+    Close,
+}
+
+/// A shape to fill or stroke
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Rect {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+    },
+    Circle {
+        x: f32,
+        y: f32,
+        radius: f32,
+    },
+    Line {
+        from: (f32, f32),
+        to: (f32, f32),
+    },
+    Path(Vec<PathSegment>),
+}
+
+impl Shape {
+    fn to_path(&self) -> Path {
+        match self {
+            Self::Rect {
+                x,
+                y,
+                width,
+                height,
+            } => Path::rectangle(Point::new(*x, *y), Size::new(*width, *height)),
+            Self::Circle { x, y, radius } => Path::circle(Point::new(*x, *y), *radius),
+            Self::Line { from, to } => {
+                Path::line(Point::new(from.0, from.1), Point::new(to.0, to.1))
+            }
+            Self::Path(segments) => Path::new(|builder| {
+                for segment in segments {
+                    match *segment {
+                        PathSegment::MoveTo(x, y) => builder.move_to(Point::new(x, y)),
+                        PathSegment::LineTo(x, y) => builder.line_to(Point::new(x, y)),
+                        PathSegment::QuadTo { cx, cy, x, y } => {
+                            builder.quadratic_curve_to(Point::new(cx, cy), Point::new(x, y));
+                        }
+                        PathSegment::Arc {
+                            x,
+                            y,
+                            radius,
+                            start,
+                            end,
+                        } => builder.arc(canvas::path::Arc {
+                            center: Point::new(x, y),
+                            radius,
+                            start_angle: iced::Radians(start),
+                            end_angle: iced::Radians(end),
+                        }),
+                        PathSegment::Close => builder.close(),
+                    }
+                }
+            }),
+        }
+    }
+}
+
+/// A recorded drawing operation
+#[derive(Debug, Clone, PartialEq)]
+pub enum DrawCommand {
+    Fill {
+        shape: Shape,
+        color: Color,
+    },
+    Stroke {
+        shape: Shape,
+        color: Color,
+        width: f32,
+    },
+    Text {
+        content: String,
+        x: f32,
+        y: f32,
+        size: f32,
+        color: Color,
+    },
+    Image {
+        path: String,
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+    },
+}
+
+/// Commands recorded by a context, with the current drawing settings
+#[derive(Debug)]
+struct Drawing {
+    width: f32,
+    height: f32,
+    commands: Vec<DrawCommand>,
+    fill: Color,
+    stroke: Color,
+    line_width: f32,
+    font_size: f32,
+    path: Vec<PathSegment>,
+}
+
+/// Drawing context passed to a canvas's draw callback
+///
+/// Clones share the same recording, so commands issued through any of them
+/// end up on the canvas.
+#[derive(Debug, Clone)]
+pub struct CanvasContext {
+    drawing: Arc<Mutex<Drawing>>,
+}
+
+impl CanvasContext {
+    /// A context for a canvas of the given size, filling and stroking black
+    #[must_use]
+    pub fn new(width: f32, height: f32) -> Self {
+        Self {
+            drawing: Arc::new(Mutex::new(Drawing {
+                width,
+                height,
+                commands: Vec::new(),
+                fill: Color::BLACK,
+                stroke: Color::BLACK,
+                line_width: 1.0,
+                font_size: 14.0,
+                path: Vec::new(),
+            })),
+        }
+    }
+
+    /// Wrap the context as a Stratum value
+    #[must_use]
+    pub fn into_value(self) -> Value {
+        Value::gui(self)
+    }
+
+    /// The context behind a Stratum value, if it is one
+    #[must_use]
+    pub fn from_value(value: &Value) -> Option<&Self> {
+        match value {
+            Value::GuiElement(element) => element.as_any().downcast_ref::<Self>(),
+            _ => None,
+        }
+    }
+
+    /// Commands recorded so far
+    #[must_use]
+    pub fn commands(&self) -> Vec<DrawCommand> {
+        self.drawing().commands.clone()
+    }
+
+    fn drawing(&self) -> std::sync::MutexGuard<'_, Drawing> {
+        self.drawing
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// Call a drawing method from Stratum, e.g. `ctx.rect(0, 0, 10, 10)`
+    pub fn call(&self, method: &str, args: &[Value]) -> Result<Value, String> {
+        let mut drawing = self.drawing();
+        match method {
+            "width" => return Ok(Value::Float(f64::from(drawing.width))),
+            "height" => return Ok(Value::Float(f64::from(drawing.height))),
+            "fill_color" => drawing.fill = color(method, args)?,
+            "stroke_color" => drawing.stroke = color(method, args)?,
+            "line_width" => drawing.line_width = number(method, args, 0, "width")?,
+            "font_size" => drawing.font_size = number(method, args, 0, "size")?,
+            "clear" => {
+                let shape = Shape::Rect {
+                    x: 0.0,
+                    y: 0.0,
+                    width: drawing.width,
+                    height: drawing.height,
+                };
+                let color = if args.is_empty() {
+                    Color::WHITE
+                } else {
+                    color(method, args)?
+                };
+                drawing.commands.clear();
+                drawing.commands.push(DrawCommand::Fill { shape, color });
+            }
+            "rect" | "fill_rect" | "stroke_rect" => {
+                let shape = Shape::Rect {
+                    x: number(method, args, 0, "x")?,
+                    y: number(method, args, 1, "y")?,
+                    width: number(method, args, 2, "width")?,
+                    height: number(method, args, 3, "height")?,
+                };
+                drawing.paint(shape, method == "stroke_rect");
+            }
+            "circle" | "fill_circle" | "stroke_circle" => {
+                let shape = Shape::Circle {
+                    x: number(method, args, 0, "x")?,
+                    y: number(method, args, 1, "y")?,
+                    radius: number(method, args, 2, "radius")?,
+                };
+                drawing.paint(shape, method == "stroke_circle");
+            }
+            "line" => {
+                let shape = Shape::Line {
+                    from: (
+                        number(method, args, 0, "x1")?,
+                        number(method, args, 1, "y1")?,
+                    ),
+                    to: (
+                        number(method, args, 2, "x2")?,
+                        number(method, args, 3, "y2")?,
+                    ),
+                };
+                drawing.paint(shape, true);
+            }
+            "begin_path" => drawing.path.clear(),
+            "move_to" => {
+                let segment = PathSegment::MoveTo(
+                    number(method, args, 0, "x")?,
+                    number(method, args, 1, "y")?,
+                );
+                drawing.path.push(segment);
+            }
+            "line_to" => {
+                let segment = PathSegment::LineTo(
+                    number(method, args, 0, "x")?,
+                    number(method, args, 1, "y")?,
+                );
+                drawing.path.push(segment);
+            }
+            "quad_to" => {
+                let segment = PathSegment::QuadTo {
+                    cx: number(method, args, 0, "cx")?,
+                    cy: number(method, args, 1, "cy")?,
+                    x: number(method, args, 2, "x")?,
+                    y: number(method, args, 3, "y")?,
+                };
+                drawing.path.push(segment);
+            }
+            "arc" => {
+                let segment = PathSegment::Arc {
+                    x: number(method, args, 0, "x")?,
+                    y: number(method, args, 1, "y")?,
+                    radius: number(method, args, 2, "radius")?,
+                    start: number(method, args, 3, "start_angle")?,
+                    end: number(method, args, 4, "end_angle")?,
+                };
+                drawing.path.push(segment);
+            }
+            "close_path" => drawing.path.push(PathSegment::Close),
+            "fill" | "stroke" => {
+                let shape = Shape::Path(drawing.path.clone());
+                drawing.paint(shape, method == "stroke");
+            }
+            "text" => {
+                let content = match args.first() {
+                    Some(Value::String(s)) => s.to_string(),
+                    Some(other) => format!("{other}"),
+                    None => return Err("text requires 3 arguments (content, x, y)".to_string()),
+                };
+                let command = DrawCommand::Text {
+                    content,
+                    x: number(method, args, 1, "x")?,
+                    y: number(method, args, 2, "y")?,
+                    size: drawing.font_size,
+                    color: drawing.fill,
+                };
+                drawing.commands.push(command);
+            }
+            "image" => {
+                let Some(Value::String(path)) = args.first() else {
+                    return Err("image requires a path string as its first argument".to_string());
+                };
+                let x = number(method, args, 1, "x")?;
+                let y = number(method, args, 2, "y")?;
+                let command = DrawCommand::Image {
+                    path: path.to_string(),
+                    x,
+                    y,
+                    width: optional_number(args, 3).unwrap_or(drawing.width - x),
+                    height: optional_number(args, 4).unwrap_or(drawing.height - y),
+                };
+                drawing.commands.push(command);
+            }
+            _ => return Err(format!("Unknown canvas context method: {method}")),
+        }
+        drop(drawing);
+
+        // Return the context so calls can be chained
+        Ok(self.clone().into_value())
+    }
+}
+
+impl Drawing {
+    fn paint(&mut self, shape: Shape, stroke: bool) {
+        let command = if stroke {
+            DrawCommand::Stroke {
+                shape,
+                color: self.stroke,
+                width: self.line_width,
+            }
+        } else {
+            DrawCommand::Fill {
+                shape,
+                color: self.fill,
+            }
+        };
+        self.commands.push(command);
+    }
+}
+
+impl GuiValue for CanvasContext {
+    fn kind_name(&self) -> &'static str {
+        "CanvasContext"
+    }
+
+    fn clone_boxed(&self) -> Arc<dyn GuiValue> {
+        Arc::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn optional_number(args: &[Value], index: usize) -> Option<f32> {
+    match args.get(index) {
+        Some(Value::Float(f)) => Some(*f as f32),
+        Some(Value::Int(i)) => Some(*i as f32),
+        _ => None,
+    }
+}
+
+fn number(method: &str, args: &[Value], index: usize, name: &str) -> Result<f32, String> {
+    match args.get(index) {
+        Some(Value::Float(_) | Value::Int(_)) => Ok(optional_number(args, index).unwrap_or(0.0)),
+        Some(other) => Err(format!(
+            "{method}: {name} must be a number, got {}",
+            other.type_name()
+        )),
+        None => Err(format!("{method}: missing argument {name}")),
+    }
+}
+
+/// A color from `r, g, b` and optional `a` arguments, each 0-255
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn color(method: &str, args: &[Value]) -> Result<Color, String> {
+    let channel = |index: usize, name: &str| -> Result<u8, String> {
+        number(method, args, index, name).map(|n| n.clamp(0.0, 255.0) as u8)
+    };
+    let alpha = match args.get(3) {
+        Some(_) => f32::from(channel(3, "a")?) / 255.0,
+        None => 1.0,
+    };
+    Ok(Color::from_rgba8(
+        channel(0, "r")?,
+        channel(1, "g")?,
+        channel(2, "b")?,
+        alpha,
+    ))
+}
+
+/// Commands a canvas's draw callback recorded in the last view
+#[must_use]
+pub fn canvas_frame(draw: CallbackId) -> Arc<Vec<DrawCommand>> {
+    FRAMES.with(|frames| frames.borrow().get(&draw).cloned().unwrap_or_default())
+}
+
+/// Replace the recorded commands of every canvas, keyed by draw callback
+pub fn set_canvas_frames(frames: HashMap<CallbackId, Arc<Vec<DrawCommand>>>) {
+    FRAMES.with(|current| *current.borrow_mut() = frames);
+}
+
+/// Mouse or keyboard input over a canvas
+#[derive(Debug, Clone, PartialEq)]
+pub struct CanvasEvent {
+    /// "mouse_down", "mouse_up", "mouse_move", "scroll", "key_down" or "key_up"
+    pub kind: &'static str,
+    /// Cursor position relative to the canvas
+    pub x: f32,
+    pub y: f32,
+    /// Mouse button: "left", "right", "middle" or "other"
+    pub button: Option<&'static str>,
+    /// Key name, for keyboard events
+    pub key: Option<String>,
+    /// Scroll amount, for scroll events
+    pub delta: (f32, f32),
+}
+
+impl CanvasEvent {
+    fn at(kind: &'static str, position: Point) -> Self {
+        Self {
+            kind,
+            x: position.x,
+            y: position.y,
+            button: None,
+            key: None,
+            delta: (0.0, 0.0),
+        }
+    }
+
+    /// The event passed to Stratum: a map with `type`, `x`, `y`, and
+    /// `button`, `key` or `dx`/`dy` depending on the event
+    #[must_use]
+    pub fn to_value(&self) -> Value {
+        let mut map = ValueMap::new();
+        let mut insert = |key: &str, value: Value| {
+            map.insert(HashableValue::String(Rc::new(key.to_string())), value);
+        };
+        insert("type", Value::string(self.kind));
+        insert("x", Value::Float(f64::from(self.x)));
+        insert("y", Value::Float(f64::from(self.y)));
+        if let Some(button) = self.button {
+            insert("button", Value::string(button));
+        }
+        if let Some(ref key) = self.key {
+            insert("key", Value::string(key.as_str()));
+        }
+        if self.kind == "scroll" {
+            insert("dx", Value::Float(f64::from(self.delta.0)));
+            insert("dy", Value::Float(f64::from(self.delta.1)));
+        }
+        Value::map(map)
+    }
+}
+
+fn button_name(button: mouse::Button) -> &'static str {
+    match button {
+        mouse::Button::Left => "left",
+        mouse::Button::Right => "right",
+        mouse::Button::Middle => "middle",
+        _ => "other",
+    }
+}
+
+/// Canvas program replaying recorded commands
+#[derive(Debug)]
+pub struct CanvasProgram {
+    pub commands: Arc<Vec<DrawCommand>>,
+    /// Callback for input over the canvas (receives an event map)
+    pub on_event: Option<CallbackId>,
+}
+
+impl canvas::Program<Message> for CanvasProgram {
+    type State = ();
+
+    fn update(
+        &self,
+        _state: &mut Self::State,
+        event: &canvas::Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> Option<canvas::Action<Message>> {
+        let callback_id = self.on_event?;
+        // Only input over the canvas counts
+        let position = cursor.position_in(bounds)?;
+
+        let event = match event {
+            canvas::Event::Mouse(mouse::Event::ButtonPressed(button)) => CanvasEvent {
+                button: Some(button_name(*button)),
+                ..CanvasEvent::at("mouse_down", position)
+            },
+            canvas::Event::Mouse(mouse::Event::ButtonReleased(button)) => CanvasEvent {
+                button: Some(button_name(*button)),
+                ..CanvasEvent::at("mouse_up", position)
+            },
+            canvas::Event::Mouse(mouse::Event::CursorMoved { .. }) => {
+                CanvasEvent::at("mouse_move", position)
+            }
+            canvas::Event::Mouse(mouse::Event::WheelScrolled { delta }) => {
+                let (dx, dy) = match *delta {
+                    mouse::ScrollDelta::Lines { x, y } | mouse::ScrollDelta::Pixels { x, y } => {
+                        (x, y)
+                    }
+                };
+                CanvasEvent {
+                    delta: (dx, dy),
+                    ..CanvasEvent::at("scroll", position)
+                }
+            }
+            canvas::Event::Keyboard(keyboard::Event::KeyPressed { key, .. }) => CanvasEvent {
+                key: Some(format!("{key:?}")),
+                ..CanvasEvent::at("key_down", position)
+            },
+            canvas::Event::Keyboard(keyboard::Event::KeyReleased { key, .. }) => CanvasEvent {
+                key: Some(format!("{key:?}")),
+                ..CanvasEvent::at("key_up", position)
+            },
+            _ => return None,
+        };
+
+        Some(canvas::Action::publish(Message::CanvasEvent { callback_id, event }).and_capture())
+    }
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<canvas::Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+
+        for command in self.commands.iter() {
+            match command {
+                DrawCommand::Fill { shape, color } => frame.fill(&shape.to_path(), *color),
+                DrawCommand::Stroke {
+                    shape,
+                    color,
+                    width,
+                } => frame.stroke(
+                    &shape.to_path(),
+                    Stroke::default().with_color(*color).with_width(*width),
+                ),
+                DrawCommand::Text {
+                    content,
+                    x,
+                    y,
+                    size,
+                    color,
+                } => frame.fill_text(Text {
+                    content: content.clone(),
+                    position: Point::new(*x, *y),
+                    color: *color,
+                    size: (*size).into(),
+                    align_x: Horizontal::Left.into(),
+                    align_y: Vertical::Top.into(),
+                    ..Text::default()
+                }),
+                DrawCommand::Image {
+                    path,
+                    x,
+                    y,
+                    width,
+                    height,
+                } => frame.draw_image(
+                    Rectangle::new(Point::new(*x, *y), Size::new(*width, *height)),
+                    canvas::Image::new(iced::widget::image::Handle::from_path(path)),
+                ),
+            }
+        }
+
+        vec![frame.into_geometry()]
+    }
+
+    fn mouse_interaction(
+        &self,
+        _state: &Self::State,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> mouse::Interaction {
+        if self.on_event.is_some() && cursor.is_over(bounds) {
+            mouse::Interaction::Crosshair
+        } else {
+            mouse::Interaction::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_records_commands() {
+        let ctx = CanvasContext::new(200.0, 100.0);
+        ctx.call(
+            "fill_color",
+            &[Value::Int(255), Value::Int(0), Value::Int(0)],
+        )
+        .unwrap();
+        ctx.call(
+            "rect",
+            &[
+                Value::Int(10),
+                Value::Int(20),
+                Value::Float(30.5),
+                Value::Int(40),
+            ],
+        )
+        .unwrap();
+        ctx.call("line_width", &[Value::Int(3)]).unwrap();
+        ctx.call(
+            "stroke_circle",
+            &[Value::Int(50), Value::Int(50), Value::Int(5)],
+        )
+        .unwrap();
+        ctx.call("text", &[Value::string("Hi"), Value::Int(1), Value::Int(2)])
+            .unwrap();
+
+        let red = Color::from_rgb8(255, 0, 0);
+        assert_eq!(
+            ctx.commands(),
+            [
+                DrawCommand::Fill {
+                    shape: Shape::Rect {
+                        x: 10.0,
+                        y: 20.0,
+                        width: 30.5,
+                        height: 40.0
+                    },
+                    color: red,
+                },
+                DrawCommand::Stroke {
+                    shape: Shape::Circle {
+                        x: 50.0,
+                        y: 50.0,
+                        radius: 5.0
+                    },
+                    color: Color::BLACK,
+                    width: 3.0,
+                },
+                DrawCommand::Text {
+                    content: "Hi".to_string(),
+                    x: 1.0,
+                    y: 2.0,
+                    size: 14.0,
+                    color: red,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_context_paths_and_values() {
+        let ctx = CanvasContext::new(200.0, 100.0);
+        let value = ctx.clone().into_value();
+        let shared = CanvasContext::from_value(&value).unwrap();
+
+        // Calls through a clone record on the same canvas
+        shared.call("begin_path", &[]).unwrap();
+        shared
+            .call("move_to", &[Value::Int(0), Value::Int(0)])
+            .unwrap();
+        shared
+            .call("line_to", &[Value::Int(10), Value::Int(0)])
+            .unwrap();
+        shared.call("close_path", &[]).unwrap();
+        shared.call("fill", &[]).unwrap();
+
+        assert_eq!(
+            ctx.commands(),
+            [DrawCommand::Fill {
+                shape: Shape::Path(vec![
+                    PathSegment::MoveTo(0.0, 0.0),
+                    PathSegment::LineTo(10.0, 0.0),
+                    PathSegment::Close,
+                ]),
+                color: Color::BLACK,
+            }]
+        );
+        assert!(
+            matches!(ctx.call("width", &[]), Ok(Value::Float(w)) if (w - 200.0).abs() < f64::EPSILON)
+        );
+        assert!(ctx.call("rect", &[Value::Int(1)]).is_err());
+        assert!(ctx.call("fill_color", &[Value::string("red")]).is_err());
+        assert!(ctx.call("unknown", &[]).is_err());
+        assert!(CanvasContext::from_value(&Value::Int(1)).is_none());
+    }
+
+    #[test]
+    fn test_clear_replaces_commands() {
+        let ctx = CanvasContext::new(20.0, 10.0);
+        ctx.call("circle", &[Value::Int(1), Value::Int(1), Value::Int(1)])
+            .unwrap();
+        ctx.call("clear", &[Value::Int(0), Value::Int(0), Value::Int(0)])
+            .unwrap();
+        assert_eq!(
+            ctx.commands(),
+            [DrawCommand::Fill {
+                shape: Shape::Rect {
+                    x: 0.0,
+                    y: 0.0,
+                    width: 20.0,
+                    height: 10.0
+                },
+                color: Color::BLACK,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_event_to_value() {
+        let event = CanvasEvent {
+            button: Some("left"),
+            ..CanvasEvent::at("mouse_down", Point::new(3.0, 4.0))
+        };
+        let Value::Map(map) = event.to_value() else {
+            panic!("Expected a map");
+        };
+        let map = map.borrow();
+        let get = |key: &str| map.get(&HashableValue::String(Rc::new(key.to_string())));
+        assert!(matches!(get("type"), Some(Value::String(s)) if s.as_str() == "mouse_down"));
+        assert!(matches!(get("button"), Some(Value::String(s)) if s.as_str() == "left"));
+        assert!(matches!(get("x"), Some(Value::Float(x)) if (x - 3.0).abs() < f64::EPSILON));
+        assert!(get("key").is_none());
+        assert!(get("dx").is_none());
+    }
+}
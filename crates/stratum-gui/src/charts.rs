@@ -2,13 +2,25 @@
 //!
 //! This module provides chart components (BarChart, LineChart, PieChart) that
 //! use iced's native canvas widget for rendering.
+//!
+//! Scatter, area, stacked bar, histogram and candlestick charts are drawn by
+//! one interactive program, [`PlotProgram`]. Each works out a [`PlotLayout`]
+//! of bars, dots and candles from its data and the chart's [`ChartState`];
+//! the same layout is drawn and hit-tested, so tooltips, clicks and the
+//! legend line up with what's on screen. Hovering a point shows its values,
+//! the mouse wheel zooms the x axis and dragging pans it, clicking a series
+//! in the legend hides it, and clicking a point calls the chart's
+//! `on_point_click` callback with the point. Bar, line and pie charts call
+//! their click callbacks the same way.
 
 use std::f32::consts::PI;
+use std::rc::Rc;
 
 use iced::alignment::{Horizontal, Vertical};
 use iced::mouse;
 use iced::widget::canvas::{self, Frame, Path, Stroke, Text};
 use iced::{Color, Point, Rectangle, Renderer, Size, Theme};
+use stratum_core::bytecode::{HashableValue, Value, ValueMap};
 
 use crate::callback::CallbackId;
 
@@ -229,9 +241,58 @@ pub struct BarChartProgram {
     pub config: BarChartConfig,
 }
 
+impl BarChartProgram {
+    /// The bar whose column is under `position`, laid out as `draw` does
+    fn point_at(&self, size: Size, position: Point) -> Option<ChartPoint> {
+        let config = &self.config;
+        let margin_left = 60.0;
+        let margin_right = 20.0;
+        let margin_top = if config.title.is_some() { 40.0 } else { 20.0 };
+        let margin_bottom = 50.0;
+        let chart_width = size.width - margin_left - margin_right;
+
+        if config.data.is_empty()
+            || position.y < margin_top
+            || position.y > size.height - margin_bottom
+        {
+            return None;
+        }
+
+        let num_bars = config.data.len();
+        let bar_spacing = 10.0;
+        #[allow(clippy::cast_precision_loss)]
+        let bar_width = (chart_width - bar_spacing * (num_bars + 1) as f32) / num_bars as f32;
+        #[allow(clippy::cast_precision_loss)]
+        let index = config.data.iter().enumerate().position(|(i, _)| {
+            let x = margin_left + bar_spacing + (bar_width + bar_spacing) * i as f32;
+            position.x >= x && position.x <= x + bar_width
+        })?;
+
+        let point = &config.data[index];
+        #[allow(clippy::cast_precision_loss)]
+        Some(ChartPoint::new(index, index as f64, point.value).with_label(point.label.as_str()))
+    }
+}
+
 impl canvas::Program<crate::runtime::Message> for BarChartProgram {
     type State = ();
 
+    fn update(
+        &self,
+        _state: &mut Self::State,
+        event: &canvas::Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> Option<canvas::Action<crate::runtime::Message>> {
+        let callback_id = self.config.on_bar_click?;
+        let canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) = event else {
+            return None;
+        };
+        let point = self.point_at(bounds.size(), cursor.position_in(bounds)?)?;
+        let message = crate::runtime::Message::ChartClick { callback_id, point };
+        Some(canvas::Action::publish(message).and_capture())
+    }
+
     fn draw(
         &self,
         _state: &Self::State,
@@ -442,9 +503,74 @@ pub struct LineChartProgram {
     pub config: LineChartConfig,
 }
 
+impl LineChartProgram {
+    /// The data point drawn nearest `position`, if close enough to click,
+    /// laid out as `draw` does
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+    fn point_at(&self, size: Size, position: Point) -> Option<ChartPoint> {
+        let config = &self.config;
+        if config.series.is_empty() || config.labels.is_empty() {
+            return None;
+        }
+        let margin_left = 60.0;
+        let margin_right = if config.show_legend { 120.0 } else { 20.0 };
+        let margin_top = if config.title.is_some() { 40.0 } else { 20.0 };
+        let margin_bottom = 50.0;
+        let chart_width = size.width - margin_left - margin_right;
+        let chart_height = size.height - margin_top - margin_bottom;
+
+        let values = || config.series.iter().flat_map(|s| s.values.iter().copied());
+        let max_value = values().fold(0.0_f64, f64::max);
+        let max_value = if max_value <= 0.0 { 1.0 } else { max_value };
+        let min_value = values().fold(f64::MAX, f64::min).min(0.0);
+        let value_range = max_value - min_value;
+
+        let num_points = config.labels.len();
+        let mut nearest: Option<(f32, ChartPoint)> = None;
+        for series in &config.series {
+            for (i, &value) in series.values.iter().enumerate() {
+                let x = margin_left
+                    + if num_points > 1 {
+                        chart_width / (num_points - 1) as f32 * i as f32
+                    } else {
+                        chart_width / 2.0
+                    };
+                let normalized = (value - min_value) / value_range;
+                let y = margin_top + chart_height * (1.0 - normalized as f32);
+                let distance = Point::new(x, y).distance(position);
+                if distance <= 8.0 && nearest.as_ref().map_or(true, |(d, _)| distance < *d) {
+                    let mut point =
+                        ChartPoint::new(i, i as f64, value).with_series(series.name.as_str());
+                    if let Some(label) = config.labels.get(i) {
+                        point = point.with_label(label.as_str());
+                    }
+                    nearest = Some((distance, point));
+                }
+            }
+        }
+        nearest.map(|(_, point)| point)
+    }
+}
+
 impl canvas::Program<crate::runtime::Message> for LineChartProgram {
     type State = ();
 
+    fn update(
+        &self,
+        _state: &mut Self::State,
+        event: &canvas::Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> Option<canvas::Action<crate::runtime::Message>> {
+        let callback_id = self.config.on_point_click?;
+        let canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) = event else {
+            return None;
+        };
+        let point = self.point_at(bounds.size(), cursor.position_in(bounds)?)?;
+        let message = crate::runtime::Message::ChartClick { callback_id, point };
+        Some(canvas::Action::publish(message).and_capture())
+    }
+
     fn draw(
         &self,
         _state: &Self::State,
@@ -718,9 +844,72 @@ pub struct PieChartProgram {
     pub config: PieChartConfig,
 }
 
+impl PieChartProgram {
+    /// The slice under `position`, laid out as `draw` does
+    fn point_at(&self, size: Size, position: Point) -> Option<ChartPoint> {
+        let config = &self.config;
+        let title_height = if config.title.is_some() { 40.0 } else { 10.0 };
+        let legend_width = if config.show_legend { 150.0 } else { 0.0 };
+        let available_width = size.width - legend_width - 20.0;
+        let available_height = size.height - title_height - 20.0;
+        let radius = (available_width.min(available_height) / 2.0 - 20.0).max(50.0);
+        let center = Point::new(
+            20.0 + available_width / 2.0,
+            title_height + available_height / 2.0,
+        );
+
+        let distance = center.distance(position);
+        if distance > radius || distance < radius * config.inner_radius_ratio {
+            return None;
+        }
+        let total: f64 = config.data.iter().map(|d| d.value.max(0.0)).sum();
+        if total <= 0.0 {
+            return None;
+        }
+
+        // Slices run clockwise from the top, as drawn
+        let angle = f64::from(
+            ((position.y - center.y).atan2(position.x - center.x) + PI / 2.0).rem_euclid(2.0 * PI),
+        );
+        let mut start = 0.0;
+        for (i, point) in config.data.iter().enumerate() {
+            if point.value <= 0.0 {
+                continue;
+            }
+            let sweep = point.value / total * 2.0 * std::f64::consts::PI;
+            if angle < start + sweep {
+                #[allow(clippy::cast_precision_loss)]
+                return Some(
+                    ChartPoint::new(i, i as f64, point.value)
+                        .with_label(point.label.as_str())
+                        .with("percent", point.value / total * 100.0),
+                );
+            }
+            start += sweep;
+        }
+        None
+    }
+}
+
 impl canvas::Program<crate::runtime::Message> for PieChartProgram {
     type State = ();
 
+    fn update(
+        &self,
+        _state: &mut Self::State,
+        event: &canvas::Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> Option<canvas::Action<crate::runtime::Message>> {
+        let callback_id = self.config.on_slice_click?;
+        let canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) = event else {
+            return None;
+        };
+        let point = self.point_at(bounds.size(), cursor.position_in(bounds)?)?;
+        let message = crate::runtime::Message::ChartClick { callback_id, point };
+        Some(canvas::Action::publish(message).and_capture())
+    }
+
     fn draw(
         &self,
         _state: &Self::State,
@@ -955,102 +1144,1717 @@ impl canvas::Program<crate::runtime::Message> for PieChartProgram {
     }
 }
 
-/// Helper trait for arc drawing in Path builder
-trait PathBuilderExt {
-    fn draw_arc(&mut self, center: Point, radius: f32, start_angle: f32, sweep_angle: f32);
+// =============================================================================
+// Interactive charts: scatter, area, stacked bar, histogram, candlestick
+// =============================================================================
+
+/// Furthest the x axis of an interactive chart zooms in
+pub const MAX_ZOOM: f64 = 50.0;
+
+/// Width of the legend drawn to the right of a chart
+const LEGEND_WIDTH: f32 = 120.0;
+
+/// A data point handed to a chart's click callback
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartPoint {
+    /// Series the point belongs to, for charts with several
+    pub series: Option<String>,
+    /// Category label, bin range or candle label
+    pub label: Option<String>,
+    /// Position of the point in its series
+    pub index: usize,
+    pub x: f64,
+    pub y: f64,
+    /// Further values of the point: a candle's open, high, low and close,
+    /// or a bin's start and end
+    pub extra: Vec<(&'static str, f64)>,
 }
 
-impl PathBuilderExt for canvas::path::Builder {
-    fn draw_arc(&mut self, center: Point, radius: f32, start_angle: f32, sweep_angle: f32) {
-        // Approximate arc with bezier curves
-        // For small angles, use fewer segments
-        let num_segments = ((sweep_angle.abs() / (PI / 4.0)).ceil() as usize).max(1);
-        let segment_angle = sweep_angle / num_segments as f32;
+impl ChartPoint {
+    /// A point with no series, label or further values
+    #[must_use]
+    pub fn new(index: usize, x: f64, y: f64) -> Self {
+        Self {
+            series: None,
+            label: None,
+            index,
+            x,
+            y,
+            extra: Vec::new(),
+        }
+    }
 
-        for i in 0..num_segments {
-            let angle1 = start_angle + segment_angle * i as f32;
-            let angle2 = angle1 + segment_angle;
+    /// Set the series the point belongs to
+    #[must_use]
+    pub fn with_series(mut self, series: impl Into<String>) -> Self {
+        self.series = Some(series.into());
+        self
+    }
 
-            // Calculate control points for cubic bezier approximation of arc
-            let k = 4.0 / 3.0 * (segment_angle / 4.0).tan();
+    /// Set the point's label
+    #[must_use]
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
 
-            let p1 = Point::new(
-                center.x + radius * angle1.cos(),
-                center.y + radius * angle1.sin(),
-            );
-            let p2 = Point::new(
-                center.x + radius * angle2.cos(),
-                center.y + radius * angle2.sin(),
-            );
+    /// Add a further value
+    #[must_use]
+    pub fn with(mut self, name: &'static str, value: f64) -> Self {
+        self.extra.push((name, value));
+        self
+    }
 
-            let c1 = Point::new(
-                p1.x - k * radius * angle1.sin(),
-                p1.y + k * radius * angle1.cos(),
-            );
-            let c2 = Point::new(
-                p2.x + k * radius * angle2.sin(),
-                p2.y - k * radius * angle2.cos(),
-            );
+    /// Lines of the tooltip shown for the point
+    #[must_use]
+    pub fn tooltip(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        match (&self.series, &self.label) {
+            (Some(series), Some(label)) => lines.push(format!("{series}: {label}")),
+            (Some(name), None) | (None, Some(name)) => lines.push(name.clone()),
+            (None, None) => {}
+        }
+        if self.extra.is_empty() || self.label.is_none() {
+            if self.label.is_none() {
+                lines.push(format!("x: {}", format_number(self.x)));
+            }
+            lines.push(format!("y: {}", format_number(self.y)));
+        }
+        for (name, value) in &self.extra {
+            lines.push(format!("{name}: {}", format_number(*value)));
+        }
+        lines
+    }
 
-            self.bezier_curve_to(c1, c2, p2);
+    /// The point passed to Stratum: a map with `index`, `x`, `y`, `series`
+    /// and `label` where set, and any further values by name
+    #[must_use]
+    pub fn to_value(&self) -> Value {
+        let mut map = ValueMap::new();
+        let mut insert = |key: &str, value: Value| {
+            map.insert(HashableValue::String(Rc::new(key.to_string())), value);
+        };
+        insert(
+            "index",
+            Value::Int(i64::try_from(self.index).unwrap_or(i64::MAX)),
+        );
+        insert("x", Value::Float(self.x));
+        insert("y", Value::Float(self.y));
+        if let Some(ref series) = self.series {
+            insert("series", Value::string(series.as_str()));
+        }
+        if let Some(ref label) = self.label {
+            insert("label", Value::string(label.as_str()));
         }
+        for (name, value) in &self.extra {
+            insert(name, Value::Float(*value));
+        }
+        Value::map(map)
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Format a value for axis ticks and tooltips: thousands abbreviated to
+/// "k", whole numbers without decimals
+fn format_number(value: f64) -> String {
+    if value.abs() >= 1000.0 {
+        format!("{:.1}k", value / 1000.0)
+    } else if (value - value.round()).abs() < 1e-9 {
+        format!("{value:.0}")
+    } else {
+        format!("{value:.2}")
+    }
+}
 
-    #[test]
-    fn test_data_point_creation() {
-        let point = DataPoint::new("Sales", 100.0);
-        assert_eq!(point.label, "Sales");
-        assert!((point.value - 100.0).abs() < f64::EPSILON);
+/// Options shared by the interactive charts
+#[derive(Debug, Clone)]
+pub struct PlotOptions {
+    /// Chart title
+    pub title: Option<String>,
+    /// Chart width in pixels
+    pub width: f32,
+    /// Chart height in pixels
+    pub height: f32,
+    /// Whether to show the legend
+    pub show_legend: bool,
+    /// Whether to show grid lines
+    pub show_grid: bool,
+    /// Custom series colors
+    pub series_colors: Vec<(u8, u8, u8)>,
+    /// X-axis label
+    pub x_label: Option<String>,
+    /// Y-axis label
+    pub y_label: Option<String>,
+    /// Whether to show the values of the point under the cursor
+    pub tooltips: bool,
+    /// Whether the mouse wheel zooms the x axis and dragging pans it
+    pub zoom: bool,
+    /// Whether clicking a series in the legend hides or shows it
+    pub toggle_legend: bool,
+    /// Callback when a point is clicked, called with the point
+    pub on_point_click: Option<CallbackId>,
+}
+
+impl Default for PlotOptions {
+    fn default() -> Self {
+        Self {
+            title: None,
+            width: 400.0,
+            height: 300.0,
+            show_legend: true,
+            show_grid: true,
+            series_colors: Vec::new(),
+            x_label: None,
+            y_label: None,
+            tooltips: true,
+            zoom: false,
+            toggle_legend: true,
+            on_point_click: None,
+        }
     }
+}
 
-    #[test]
-    fn test_data_series_creation() {
-        let series = DataSeries::new("Revenue", vec![10.0, 20.0, 30.0]);
-        assert_eq!(series.name, "Revenue");
-        assert_eq!(series.values.len(), 3);
+impl PlotOptions {
+    /// Color of the series at `index`, by its name unless set explicitly
+    #[must_use]
+    pub fn series_color(&self, index: usize, name: &str) -> Color {
+        match self.series_colors.get(index) {
+            Some(&(r, g, b)) => Color::from_rgb8(r, g, b),
+            None => color_for_label(name),
+        }
     }
+}
 
-    #[test]
-    fn test_bar_chart_config_default() {
-        let config = BarChartConfig::default();
-        assert!(config.title.is_none());
-        assert!(config.data.is_empty());
-        assert!((config.width - 400.0).abs() < f32::EPSILON);
-        assert!((config.height - 300.0).abs() < f32::EPSILON);
-        assert!(config.show_grid);
-        assert!(config.show_values);
+/// A named series of (x, y) points for scatter charts
+#[derive(Debug, Clone)]
+pub struct ScatterSeries {
+    /// Name of this series
+    pub name: String,
+    /// Points in this series
+    pub points: Vec<(f64, f64)>,
+}
+
+impl ScatterSeries {
+    /// Create a new scatter series
+    #[must_use]
+    pub fn new(name: impl Into<String>, points: Vec<(f64, f64)>) -> Self {
+        Self {
+            name: name.into(),
+            points,
+        }
     }
+}
 
-    #[test]
-    fn test_line_chart_config_default() {
-        let config = LineChartConfig::default();
-        assert!(config.title.is_none());
-        assert!(config.series.is_empty());
-        assert!(config.labels.is_empty());
-        assert!(config.show_legend);
-        assert!(config.show_grid);
-        assert!(config.show_points);
-        assert!(!config.fill_area);
+/// Scatter chart configuration
+#[derive(Debug, Clone)]
+pub struct ScatterChartConfig {
+    /// Data series
+    pub series: Vec<ScatterSeries>,
+    /// Radius of each point in pixels
+    pub point_radius: f32,
+    pub options: PlotOptions,
+}
+
+impl Default for ScatterChartConfig {
+    fn default() -> Self {
+        Self {
+            series: Vec::new(),
+            point_radius: 4.0,
+            options: PlotOptions::default(),
+        }
     }
+}
 
-    #[test]
-    fn test_pie_chart_config_default() {
-        let config = PieChartConfig::default();
-        assert!(config.title.is_none());
-        assert!(config.data.is_empty());
-        assert!(config.show_legend);
-        assert!(config.show_percentages);
-        assert!(!config.show_values);
-        assert!((config.inner_radius_ratio - 0.0).abs() < f32::EPSILON);
+/// Area chart configuration
+#[derive(Debug, Clone, Default)]
+pub struct AreaChartConfig {
+    /// X-axis labels
+    pub labels: Vec<String>,
+    /// Data series
+    pub series: Vec<DataSeries>,
+    /// Whether series are stacked on one another rather than overlapping
+    pub stacked: bool,
+    pub options: PlotOptions,
+}
+
+/// Stacked bar chart configuration
+#[derive(Debug, Clone, Default)]
+pub struct StackedBarChartConfig {
+    /// X-axis labels, one per bar
+    pub labels: Vec<String>,
+    /// Data series, one per segment of each bar
+    pub series: Vec<DataSeries>,
+    pub options: PlotOptions,
+}
+
+/// Histogram configuration
+#[derive(Debug, Clone)]
+pub struct HistogramConfig {
+    /// Values to count into bins
+    pub values: Vec<f64>,
+    /// Number of bins
+    pub bins: usize,
+    /// Bar color (uses default if None)
+    pub bar_color: Option<(u8, u8, u8)>,
+    pub options: PlotOptions,
+}
+
+impl Default for HistogramConfig {
+    fn default() -> Self {
+        Self {
+            values: Vec::new(),
+            bins: 10,
+            bar_color: None,
+            options: PlotOptions {
+                show_legend: false,
+                ..PlotOptions::default()
+            },
+        }
     }
+}
 
-    #[test]
-    fn test_chart_colors_count() {
-        assert_eq!(CHART_COLORS.len(), 10);
+/// A bin of a histogram
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bin {
+    pub start: f64,
+    pub end: f64,
+    pub count: usize,
+}
+
+/// Count values into `bins` bins of equal width spanning them. The last
+/// bin includes its end, so the largest value is counted.
+#[must_use]
+pub fn histogram_bins(values: &[f64], bins: usize) -> Vec<Bin> {
+    let values: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    if values.is_empty() || bins == 0 {
+        return Vec::new();
+    }
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    // All values equal: one bin around them
+    let (min, max, bins) = if max > min {
+        (min, max, bins)
+    } else {
+        (min - 0.5, max + 0.5, 1)
+    };
+
+    #[allow(clippy::cast_precision_loss)]
+    let width = (max - min) / bins as f64;
+    #[allow(clippy::cast_precision_loss)]
+    let mut result: Vec<Bin> = (0..bins)
+        .map(|i| Bin {
+            start: min + width * i as f64,
+            end: min + width * (i + 1) as f64,
+            count: 0,
+        })
+        .collect();
+    for value in values {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let index = (((value - min) / width) as usize).min(bins - 1);
+        result[index].count += 1;
+    }
+    result
+}
+
+/// One period of a candlestick chart
+#[derive(Debug, Clone)]
+pub struct Candle {
+    /// Label for the period, e.g. a date
+    pub label: String,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+}
+
+impl Candle {
+    /// Create a new candle
+    #[must_use]
+    pub fn new(label: impl Into<String>, open: f64, high: f64, low: f64, close: f64) -> Self {
+        Self {
+            label: label.into(),
+            open,
+            high,
+            low,
+            close,
+        }
+    }
+}
+
+/// Candlestick chart configuration
+#[derive(Debug, Clone)]
+pub struct CandlestickChartConfig {
+    /// Candles, one per period
+    pub candles: Vec<Candle>,
+    /// Color of candles that closed at or above their open
+    pub up_color: (u8, u8, u8),
+    /// Color of candles that closed below their open
+    pub down_color: (u8, u8, u8),
+    pub options: PlotOptions,
+}
+
+impl Default for CandlestickChartConfig {
+    fn default() -> Self {
+        Self {
+            candles: Vec::new(),
+            up_color: CHART_COLORS[3],
+            down_color: CHART_COLORS[1],
+            options: PlotOptions {
+                show_legend: false,
+                ..PlotOptions::default()
+            },
+        }
+    }
+}
+
+/// Zoom, pan, hover and hidden series of an interactive chart, kept by
+/// iced between redraws
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartState {
+    /// How far the x axis is zoomed in; 1 shows all the data
+    pub zoom: f64,
+    /// Start of the part of the x axis in view, as a fraction of the whole
+    pub pan: f64,
+    /// Series hidden by clicking them in the legend
+    pub hidden: Vec<String>,
+    /// Cursor position over the chart
+    pub hover: Option<Point>,
+    /// Cursor x and pan when a drag started
+    drag: Option<(f32, f64)>,
+}
+
+impl Default for ChartState {
+    fn default() -> Self {
+        Self {
+            zoom: 1.0,
+            pan: 0.0,
+            hidden: Vec::new(),
+            hover: None,
+            drag: None,
+        }
+    }
+}
+
+impl ChartState {
+    /// The part of the range `low..high` in view
+    #[must_use]
+    pub fn window(&self, low: f64, high: f64) -> (f64, f64) {
+        let span = high - low;
+        (
+            low + span * self.pan,
+            low + span * (self.pan + 1.0 / self.zoom),
+        )
+    }
+
+    /// Zoom by `factor`, keeping the point `at` (a fraction of the way
+    /// across the view) where it is
+    pub fn zoom_at(&mut self, factor: f64, at: f64) {
+        let focus = self.pan + at / self.zoom;
+        self.zoom = (self.zoom * factor).clamp(1.0, MAX_ZOOM);
+        self.pan = focus - at / self.zoom;
+        self.clamp_pan();
+    }
+
+    /// Move the view along by `fraction` of its width
+    pub fn pan_by(&mut self, fraction: f64) {
+        self.pan += fraction / self.zoom;
+        self.clamp_pan();
+    }
+
+    /// Show all the data again
+    pub fn reset_zoom(&mut self) {
+        self.zoom = 1.0;
+        self.pan = 0.0;
+    }
+
+    fn clamp_pan(&mut self) {
+        self.pan = self.pan.clamp(0.0, 1.0 - 1.0 / self.zoom);
+    }
+
+    /// Hide a series, or show it again if hidden
+    pub fn toggle_series(&mut self, name: &str) {
+        if let Some(i) = self.hidden.iter().position(|h| h == name) {
+            self.hidden.remove(i);
+        } else {
+            self.hidden.push(name.to_string());
+        }
+    }
+
+    /// Whether a series is hidden
+    #[must_use]
+    pub fn is_hidden(&self, name: &str) -> bool {
+        self.hidden.iter().any(|h| h == name)
+    }
+}
+
+/// Shape of a mark
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MarkShape {
+    Rect(Rectangle),
+    Dot(Point, f32),
+}
+
+/// A bar, dot or candle standing for one data point
+#[derive(Debug, Clone)]
+pub struct Mark {
+    pub shape: MarkShape,
+    pub color: Color,
+    pub point: ChartPoint,
+}
+
+impl Mark {
+    /// Whether `position` is on the mark, with some slack around dots
+    #[must_use]
+    pub fn contains(&self, position: Point) -> bool {
+        match self.shape {
+            MarkShape::Rect(rect) => rect.contains(position),
+            MarkShape::Dot(center, radius) => center.distance(position) <= radius + 3.0,
+        }
+    }
+}
+
+/// Where an interactive chart draws its data, worked out from the plot
+/// area, the zoom and the hidden series
+#[derive(Debug, Clone, Default)]
+pub struct PlotLayout {
+    /// Low and high ends of the y axis
+    pub y_range: (f64, f64),
+    /// Labels along the x axis, at their x positions
+    pub x_ticks: Vec<(f32, String)>,
+    /// Shapes filled under the marks, such as the areas of an area chart
+    pub fills: Vec<(Vec<Point>, Color)>,
+    /// Lines drawn under the marks, with their widths
+    pub lines: Vec<(Vec<Point>, Color, f32)>,
+    /// The marks, in drawing order
+    pub marks: Vec<Mark>,
+}
+
+impl PlotLayout {
+    /// The topmost mark under `position`
+    #[must_use]
+    pub fn mark_at(&self, position: Point) -> Option<&Mark> {
+        self.marks.iter().rev().find(|mark| mark.contains(position))
+    }
+}
+
+/// Maps data to pixels inside the plot area
+struct Scale {
+    area: Rectangle,
+    x: (f64, f64),
+    y: (f64, f64),
+}
+
+impl Scale {
+    #[allow(clippy::cast_possible_truncation)]
+    fn x(&self, value: f64) -> f32 {
+        self.area.x + ((value - self.x.0) / (self.x.1 - self.x.0)) as f32 * self.area.width
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn y(&self, value: f64) -> f32 {
+        self.area.y + self.area.height
+            - ((value - self.y.0) / (self.y.1 - self.y.0)) as f32 * self.area.height
+    }
+
+    fn shows_x(&self, value: f64) -> bool {
+        value >= self.x.0 && value <= self.x.1
+    }
+
+    /// A rectangle between two x and two y values, cut to the plot area
+    fn rect(&self, x0: f64, x1: f64, y0: f64, y1: f64) -> Rectangle {
+        let left = self.x(x0).max(self.area.x);
+        let right = self.x(x1).min(self.area.x + self.area.width);
+        let top = self.y(y0).min(self.y(y1));
+        let bottom = self.y(y0).max(self.y(y1));
+        Rectangle::new(
+            Point::new(left, top),
+            Size::new((right - left).max(0.0), bottom - top),
+        )
+    }
+}
+
+/// Low and high ends of an axis showing `values`, from zero if
+/// `from_zero`, otherwise padded a little either side
+fn value_range(values: impl Iterator<Item = f64>, from_zero: bool) -> (f64, f64) {
+    let (mut low, mut high) = values
+        .filter(|v| v.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| {
+            (lo.min(v), hi.max(v))
+        });
+    if low > high {
+        return (0.0, 1.0);
+    }
+    if from_zero {
+        low = low.min(0.0);
+        high = high.max(0.0);
+    } else {
+        let pad = (high - low) * 0.05;
+        low -= pad;
+        high += pad;
+    }
+    if high - low < f64::EPSILON {
+        (low - 1.0, high + 1.0)
+    } else {
+        (low, high)
+    }
+}
+
+/// Labels of the categories in view, thinned out to about ten
+#[allow(clippy::cast_precision_loss)]
+fn category_ticks(scale: &Scale, labels: &[String]) -> Vec<(f32, String)> {
+    let visible: Vec<usize> = (0..labels.len())
+        .filter(|&i| scale.shows_x(i as f64 + 0.5))
+        .collect();
+    let step = visible.len().div_ceil(10).max(1);
+    visible
+        .into_iter()
+        .step_by(step)
+        .map(|i| {
+            let x = scale.x(i as f64 + 0.5);
+            let label = &labels[i];
+            let label = if label.chars().count() > 8 {
+                format!("{}...", label.chars().take(6).collect::<String>())
+            } else {
+                label.clone()
+            };
+            (x, label)
+        })
+        .collect()
+}
+
+/// Five evenly spaced labels along a numeric x axis
+fn numeric_ticks(scale: &Scale) -> Vec<(f32, String)> {
+    (0..=4)
+        .map(|i| {
+            let value = scale.x.0 + (scale.x.1 - scale.x.0) * f64::from(i) / 4.0;
+            (scale.x(value), format_number(value))
+        })
+        .collect()
+}
+
+/// The part of `0..count` categories in view
+#[allow(clippy::cast_precision_loss)]
+fn category_window(count: usize, state: &ChartState) -> (f64, f64) {
+    state.window(0.0, count as f64)
+}
+
+/// Whether the category at `index` is in the x window
+#[allow(clippy::cast_precision_loss)]
+fn category_shown(window: (f64, f64), index: usize) -> bool {
+    let center = index as f64 + 0.5;
+    center >= window.0 && center <= window.1
+}
+
+/// A chart drawn by [`PlotProgram`]
+pub trait PlotChart {
+    /// Options shared by the interactive charts
+    fn options(&self) -> &PlotOptions;
+
+    /// Whether there is nothing to draw
+    fn is_empty(&self) -> bool;
+
+    /// Series names and colors, in legend order; empty for charts with one
+    /// series
+    fn legend(&self) -> Vec<(String, Color)>;
+
+    /// Where to draw the data inside `area`
+    fn layout(&self, area: Rectangle, state: &ChartState) -> PlotLayout;
+}
+
+impl PlotChart for ScatterChartConfig {
+    fn options(&self) -> &PlotOptions {
+        &self.options
+    }
+
+    fn is_empty(&self) -> bool {
+        self.series.iter().all(|s| s.points.is_empty())
+    }
+
+    fn legend(&self) -> Vec<(String, Color)> {
+        self.series
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (s.name.clone(), self.options.series_color(i, &s.name)))
+            .collect()
+    }
+
+    fn layout(&self, area: Rectangle, state: &ChartState) -> PlotLayout {
+        let shown: Vec<(usize, &ScatterSeries)> = self
+            .series
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| !state.is_hidden(&s.name))
+            .collect();
+        let points = || shown.iter().flat_map(|(_, s)| s.points.iter());
+
+        let (x_low, x_high) = value_range(points().map(|p| p.0), false);
+        let x = state.window(x_low, x_high);
+        let y = value_range(
+            points().filter(|p| p.0 >= x.0 && p.0 <= x.1).map(|p| p.1),
+            false,
+        );
+        let scale = Scale { area, x, y };
+
+        let mut marks = Vec::new();
+        for (series_idx, series) in shown {
+            let color = self.options.series_color(series_idx, &series.name);
+            for (i, &(px, py)) in series.points.iter().enumerate() {
+                if !scale.shows_x(px) {
+                    continue;
+                }
+                marks.push(Mark {
+                    shape: MarkShape::Dot(Point::new(scale.x(px), scale.y(py)), self.point_radius),
+                    color,
+                    point: ChartPoint::new(i, px, py).with_series(series.name.as_str()),
+                });
+            }
+        }
+
+        PlotLayout {
+            y_range: y,
+            x_ticks: numeric_ticks(&scale),
+            marks,
+            ..PlotLayout::default()
+        }
+    }
+}
+
+/// Top of each series stacked on the ones before it, or each series'
+/// own values if not stacked; hidden series are left out
+fn stack<'a>(
+    series: &'a [DataSeries],
+    state: &ChartState,
+    stacked: bool,
+) -> Vec<(usize, &'a DataSeries, Vec<(f64, f64)>)> {
+    let mut totals: Vec<f64> = Vec::new();
+    series
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| !state.is_hidden(&s.name))
+        .map(|(i, s)| {
+            let spans = s
+                .values
+                .iter()
+                .enumerate()
+                .map(|(j, &value)| {
+                    if !stacked {
+                        return (0.0, value);
+                    }
+                    if totals.len() <= j {
+                        totals.resize(j + 1, 0.0);
+                    }
+                    let base = totals[j];
+                    totals[j] += value;
+                    (base, base + value)
+                })
+                .collect();
+            (i, s, spans)
+        })
+        .collect()
+}
+
+impl PlotChart for AreaChartConfig {
+    fn options(&self) -> &PlotOptions {
+        &self.options
+    }
+
+    fn is_empty(&self) -> bool {
+        self.labels.is_empty() || self.series.is_empty()
+    }
+
+    fn legend(&self) -> Vec<(String, Color)> {
+        self.series
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (s.name.clone(), self.options.series_color(i, &s.name)))
+            .collect()
+    }
+
+    fn layout(&self, area: Rectangle, state: &ChartState) -> PlotLayout {
+        let x = category_window(self.labels.len(), state);
+        let stacks = stack(&self.series, state, self.stacked);
+        let y = value_range(
+            stacks.iter().flat_map(|(_, _, spans)| {
+                spans
+                    .iter()
+                    .enumerate()
+                    .filter(|(j, _)| category_shown(x, *j))
+                    .flat_map(|(_, &(base, top))| [base, top])
+            }),
+            true,
+        );
+        let scale = Scale { area, x, y };
+
+        let mut layout = PlotLayout {
+            y_range: y,
+            x_ticks: category_ticks(&scale, &self.labels),
+            ..PlotLayout::default()
+        };
+        for (series_idx, series, spans) in stacks {
+            let color = self.options.series_color(series_idx, &series.name);
+            let shown: Vec<(usize, (f64, f64))> = spans
+                .into_iter()
+                .enumerate()
+                .filter(|&(j, _)| j < self.labels.len() && category_shown(x, j))
+                .collect();
+            #[allow(clippy::cast_precision_loss)]
+            let top: Vec<Point> = shown
+                .iter()
+                .map(|&(j, (_, top))| Point::new(scale.x(j as f64 + 0.5), scale.y(top)))
+                .collect();
+            #[allow(clippy::cast_precision_loss)]
+            let base = shown
+                .iter()
+                .rev()
+                .map(|&(j, (base, _))| Point::new(scale.x(j as f64 + 0.5), scale.y(base)));
+
+            let mut fill_color = color;
+            fill_color.a = 0.3;
+            layout
+                .fills
+                .push((top.iter().copied().chain(base).collect(), fill_color));
+            layout.lines.push((top.clone(), color, 2.0));
+
+            for (&(j, (_, stacked_top)), &position) in shown.iter().zip(&top) {
+                let value = series.values[j];
+                #[allow(clippy::cast_precision_loss)]
+                let mut point = ChartPoint::new(j, j as f64, value)
+                    .with_series(series.name.as_str())
+                    .with_label(self.labels[j].as_str())
+                    .with("value", value);
+                if self.stacked {
+                    point = point.with("total", stacked_top);
+                }
+                layout.marks.push(Mark {
+                    shape: MarkShape::Dot(position, 3.0),
+                    color,
+                    point,
+                });
+            }
+        }
+        layout
+    }
+}
+
+impl PlotChart for StackedBarChartConfig {
+    fn options(&self) -> &PlotOptions {
+        &self.options
+    }
+
+    fn is_empty(&self) -> bool {
+        self.labels.is_empty() || self.series.is_empty()
+    }
+
+    fn legend(&self) -> Vec<(String, Color)> {
+        self.series
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (s.name.clone(), self.options.series_color(i, &s.name)))
+            .collect()
+    }
+
+    fn layout(&self, area: Rectangle, state: &ChartState) -> PlotLayout {
+        let x = category_window(self.labels.len(), state);
+        let shown: Vec<(usize, &DataSeries)> = self
+            .series
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| !state.is_hidden(&s.name))
+            .collect();
+
+        // Positive values stack up from zero and negative ones down from it
+        let mut segments = Vec::new();
+        let mut low = 0.0_f64;
+        let mut high = 0.0_f64;
+        for j in 0..self.labels.len() {
+            if !category_shown(x, j) {
+                continue;
+            }
+            let (mut up, mut down) = (0.0, 0.0);
+            for &(series_idx, series) in &shown {
+                let Some(&value) = series.values.get(j) else {
+                    continue;
+                };
+                let total = if value >= 0.0 { &mut up } else { &mut down };
+                let base = *total;
+                *total += value;
+                segments.push((j, series_idx, series, value, base, *total));
+            }
+            low = low.min(down);
+            high = high.max(up);
+        }
+        let y = value_range([low, high].into_iter(), true);
+        let scale = Scale { area, x, y };
+
+        let marks = segments
+            .into_iter()
+            .map(|(j, series_idx, series, value, base, top)| {
+                #[allow(clippy::cast_precision_loss)]
+                let center = j as f64 + 0.5;
+                let rect = scale.rect(center - 0.35, center + 0.35, base, top);
+                #[allow(clippy::cast_precision_loss)]
+                let point = ChartPoint::new(j, j as f64, value)
+                    .with_series(series.name.as_str())
+                    .with_label(self.labels[j].as_str())
+                    .with("value", value);
+                Mark {
+                    shape: MarkShape::Rect(rect),
+                    color: self.options.series_color(series_idx, &series.name),
+                    point,
+                }
+            })
+            .collect();
+
+        PlotLayout {
+            y_range: y,
+            x_ticks: category_ticks(&scale, &self.labels),
+            marks,
+            ..PlotLayout::default()
+        }
+    }
+}
+
+impl PlotChart for HistogramConfig {
+    fn options(&self) -> &PlotOptions {
+        &self.options
+    }
+
+    fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    fn legend(&self) -> Vec<(String, Color)> {
+        Vec::new()
+    }
+
+    fn layout(&self, area: Rectangle, state: &ChartState) -> PlotLayout {
+        let bins = histogram_bins(&self.values, self.bins);
+        let (Some(first), Some(last)) = (bins.first(), bins.last()) else {
+            return PlotLayout::default();
+        };
+        let x = state.window(first.start, last.end);
+        #[allow(clippy::cast_precision_loss)]
+        let y = value_range(
+            bins.iter()
+                .filter(|b| b.end >= x.0 && b.start <= x.1)
+                .map(|b| b.count as f64),
+            true,
+        );
+        let scale = Scale { area, x, y };
+        let color = self.bar_color.map_or_else(
+            || Color::from_rgb8(CHART_COLORS[0].0, CHART_COLORS[0].1, CHART_COLORS[0].2),
+            |(r, g, b)| Color::from_rgb8(r, g, b),
+        );
+
+        let marks = bins
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.end > x.0 && b.start < x.1)
+            .map(|(i, b)| {
+                #[allow(clippy::cast_precision_loss)]
+                let count = b.count as f64;
+                let mut rect = scale.rect(b.start, b.end, 0.0, count);
+                // A pixel's gap between bars
+                rect.width = (rect.width - 1.0).max(0.0);
+                let label = format!("{} - {}", format_number(b.start), format_number(b.end));
+                let point = ChartPoint::new(i, (b.start + b.end) / 2.0, count)
+                    .with_label(label)
+                    .with("count", count)
+                    .with("start", b.start)
+                    .with("end", b.end);
+                Mark {
+                    shape: MarkShape::Rect(rect),
+                    color,
+                    point,
+                }
+            })
+            .collect();
+
+        PlotLayout {
+            y_range: y,
+            x_ticks: numeric_ticks(&scale),
+            marks,
+            ..PlotLayout::default()
+        }
+    }
+}
+
+impl PlotChart for CandlestickChartConfig {
+    fn options(&self) -> &PlotOptions {
+        &self.options
+    }
+
+    fn is_empty(&self) -> bool {
+        self.candles.is_empty()
+    }
+
+    fn legend(&self) -> Vec<(String, Color)> {
+        Vec::new()
+    }
+
+    fn layout(&self, area: Rectangle, state: &ChartState) -> PlotLayout {
+        let x = category_window(self.candles.len(), state);
+        let shown: Vec<(usize, &Candle)> = self
+            .candles
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| category_shown(x, i))
+            .collect();
+        let y = value_range(shown.iter().flat_map(|(_, c)| [c.low, c.high]), false);
+        let scale = Scale { area, x, y };
+        let labels: Vec<String> = self.candles.iter().map(|c| c.label.clone()).collect();
+
+        let mut layout = PlotLayout {
+            y_range: y,
+            x_ticks: category_ticks(&scale, &labels),
+            ..PlotLayout::default()
+        };
+        for (i, candle) in shown {
+            let (r, g, b) = if candle.close >= candle.open {
+                self.up_color
+            } else {
+                self.down_color
+            };
+            let color = Color::from_rgb8(r, g, b);
+            #[allow(clippy::cast_precision_loss)]
+            let center = i as f64 + 0.5;
+            let wick_x = scale.x(center);
+            layout.lines.push((
+                vec![
+                    Point::new(wick_x, scale.y(candle.high)),
+                    Point::new(wick_x, scale.y(candle.low)),
+                ],
+                color,
+                1.0,
+            ));
+
+            let mut body = scale.rect(center - 0.3, center + 0.3, candle.open, candle.close);
+            // A candle that closed where it opened still shows as a line
+            body.height = body.height.max(1.0);
+            #[allow(clippy::cast_precision_loss)]
+            let point = ChartPoint::new(i, i as f64, candle.close)
+                .with_label(candle.label.as_str())
+                .with("open", candle.open)
+                .with("high", candle.high)
+                .with("low", candle.low)
+                .with("close", candle.close);
+            layout.marks.push(Mark {
+                shape: MarkShape::Rect(body),
+                color,
+                point,
+            });
+        }
+        layout
+    }
+}
+
+/// Where a chart with these options draws its data within `size`
+fn plot_area(size: Size, options: &PlotOptions, legend: bool) -> Rectangle {
+    let margin_left = 60.0;
+    let margin_right = if legend { LEGEND_WIDTH } else { 20.0 };
+    let margin_top = if options.title.is_some() { 40.0 } else { 20.0 };
+    let margin_bottom = 50.0;
+    Rectangle::new(
+        Point::new(margin_left, margin_top),
+        Size::new(
+            (size.width - margin_left - margin_right).max(1.0),
+            (size.height - margin_top - margin_bottom).max(1.0),
+        ),
+    )
+}
+
+/// Boxes of the legend entries, each covering its swatch and name
+fn legend_boxes(size: Size, area: Rectangle, count: usize) -> Vec<Rectangle> {
+    let x = size.width - LEGEND_WIDTH + 10.0;
+    (0..count)
+        .map(|i| {
+            #[allow(clippy::cast_precision_loss)]
+            let y = area.y + 20.0 + i as f32 * 20.0;
+            Rectangle::new(Point::new(x, y - 8.0), Size::new(LEGEND_WIDTH - 20.0, 16.0))
+        })
+        .collect()
+}
+
+/// Canvas program for rendering the interactive charts
+#[derive(Debug)]
+pub struct PlotProgram<C> {
+    pub config: C,
+}
+
+impl<C: PlotChart> PlotProgram<C> {
+    /// The plot area and legend entries within `size`
+    fn frame(&self, size: Size) -> (Rectangle, Vec<(String, Color)>) {
+        let options = self.config.options();
+        let legend = if options.show_legend {
+            self.config.legend()
+        } else {
+            Vec::new()
+        };
+        (plot_area(size, options, !legend.is_empty()), legend)
+    }
+
+    /// The legend entry under `position`
+    fn legend_at(&self, size: Size, position: Point) -> Option<String> {
+        let (area, legend) = self.frame(size);
+        legend_boxes(size, area, legend.len())
+            .into_iter()
+            .zip(legend)
+            .find(|(rect, _)| rect.contains(position))
+            .map(|(_, (name, _))| name)
+    }
+}
+
+impl<C: PlotChart> canvas::Program<crate::runtime::Message> for PlotProgram<C> {
+    type State = ChartState;
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        event: &canvas::Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> Option<canvas::Action<crate::runtime::Message>> {
+        let options = self.config.options();
+        let size = bounds.size();
+        let position = cursor.position_in(bounds);
+
+        match event {
+            canvas::Event::Mouse(mouse::Event::CursorMoved { .. }) => {
+                if let (Some((start_x, start_pan)), Some(position)) = (state.drag, position) {
+                    let (area, _) = self.frame(size);
+                    state.pan = start_pan;
+                    state.pan_by(f64::from((start_x - position.x) / area.width));
+                }
+                if position == state.hover {
+                    return None;
+                }
+                state.hover = position;
+                (options.tooltips || state.drag.is_some()).then(canvas::Action::request_redraw)
+            }
+            canvas::Event::Mouse(mouse::Event::CursorLeft) => {
+                state.hover = None;
+                state.drag = None;
+                Some(canvas::Action::request_redraw())
+            }
+            canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                let position = position?;
+                if options.toggle_legend {
+                    if let Some(name) = self.legend_at(size, position) {
+                        state.toggle_series(&name);
+                        return Some(canvas::Action::request_redraw().and_capture());
+                    }
+                }
+                let (area, _) = self.frame(size);
+                if let Some(callback_id) = options.on_point_click {
+                    if let Some(mark) = self.config.layout(area, state).mark_at(position) {
+                        let message = crate::runtime::Message::ChartClick {
+                            callback_id,
+                            point: mark.point.clone(),
+                        };
+                        return Some(canvas::Action::publish(message).and_capture());
+                    }
+                }
+                if options.zoom && area.contains(position) {
+                    state.drag = Some((position.x, state.pan));
+                    return Some(canvas::Action::capture());
+                }
+                None
+            }
+            canvas::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                state.drag.take().map(|_| canvas::Action::request_redraw())
+            }
+            canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Right))
+                if options.zoom =>
+            {
+                position?;
+                state.reset_zoom();
+                Some(canvas::Action::request_redraw().and_capture())
+            }
+            canvas::Event::Mouse(mouse::Event::WheelScrolled { delta }) if options.zoom => {
+                let position = position?;
+                let (area, _) = self.frame(size);
+                if !area.contains(position) {
+                    return None;
+                }
+                let lines = match *delta {
+                    mouse::ScrollDelta::Lines { y, .. } => y,
+                    mouse::ScrollDelta::Pixels { y, .. } => y / 40.0,
+                };
+                let at = f64::from((position.x - area.x) / area.width);
+                state.zoom_at(1.2_f64.powf(f64::from(lines)), at);
+                Some(canvas::Action::request_redraw().and_capture())
+            }
+            _ => None,
+        }
+    }
+
+    fn draw(
+        &self,
+        state: &Self::State,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<canvas::Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        let options = self.config.options();
+
+        if self.config.is_empty() {
+            let text = Text {
+                content: "No data".to_string(),
+                position: Point::new(bounds.width / 2.0, bounds.height / 2.0),
+                color: Color::from_rgb(0.5, 0.5, 0.5),
+                size: 16.0.into(),
+                align_x: Horizontal::Center.into(),
+                align_y: Vertical::Center.into(),
+                ..Text::default()
+            };
+            frame.fill_text(text);
+            return vec![frame.into_geometry()];
+        }
+
+        let (area, legend) = self.frame(bounds.size());
+        let layout = self.config.layout(area, state);
+        let bottom = area.y + area.height;
+
+        // Draw title
+        if let Some(ref title) = options.title {
+            let text = Text {
+                content: title.clone(),
+                position: Point::new(bounds.width / 2.0, 20.0),
+                color: Color::BLACK,
+                size: 18.0.into(),
+                align_x: Horizontal::Center.into(),
+                align_y: Vertical::Center.into(),
+                ..Text::default()
+            };
+            frame.fill_text(text);
+        }
+
+        // Draw grid lines and y-axis labels
+        if options.show_grid {
+            let grid_color = Color::from_rgb(0.9, 0.9, 0.9);
+            let num_grid_lines = 5;
+            let (low, high) = layout.y_range;
+
+            for i in 0..=num_grid_lines {
+                #[allow(clippy::cast_precision_loss)]
+                let y = area.y + area.height * (1.0 - i as f32 / num_grid_lines as f32);
+                let line = Path::line(Point::new(area.x, y), Point::new(area.x + area.width, y));
+                frame.stroke(
+                    &line,
+                    Stroke::default().with_color(grid_color).with_width(1.0),
+                );
+
+                let value = low + (high - low) * f64::from(i) / f64::from(num_grid_lines);
+                let text = Text {
+                    content: format_number(value),
+                    position: Point::new(area.x - 10.0, y),
+                    color: Color::from_rgb(0.4, 0.4, 0.4),
+                    size: 12.0.into(),
+                    align_x: Horizontal::Right.into(),
+                    align_y: Vertical::Center.into(),
+                    ..Text::default()
+                };
+                frame.fill_text(text);
+            }
+        }
+
+        // Draw data
+        for (points, color) in &layout.fills {
+            if points.len() < 3 {
+                continue;
+            }
+            let path = Path::new(|builder| {
+                builder.move_to(points[0]);
+                for point in &points[1..] {
+                    builder.line_to(*point);
+                }
+                builder.close();
+            });
+            frame.fill(&path, *color);
+        }
+        for (points, color, width) in &layout.lines {
+            for pair in points.windows(2) {
+                let line = Path::line(pair[0], pair[1]);
+                frame.stroke(
+                    &line,
+                    Stroke::default().with_color(*color).with_width(*width),
+                );
+            }
+        }
+        let hovered = state.hover.and_then(|position| layout.mark_at(position));
+        for mark in &layout.marks {
+            let path = match mark.shape {
+                MarkShape::Rect(rect) => Path::rectangle(rect.position(), rect.size()),
+                MarkShape::Dot(center, radius) => Path::circle(center, radius),
+            };
+            frame.fill(&path, mark.color);
+            if hovered.is_some_and(|h| std::ptr::eq(h, mark)) {
+                frame.stroke(
+                    &path,
+                    Stroke::default()
+                        .with_color(Color::from_rgb(0.2, 0.2, 0.2))
+                        .with_width(2.0),
+                );
+            }
+        }
+
+        // Draw x-axis labels
+        for (x, label) in &layout.x_ticks {
+            let text = Text {
+                content: label.clone(),
+                position: Point::new(*x, bottom + 15.0),
+                color: Color::from_rgb(0.3, 0.3, 0.3),
+                size: 11.0.into(),
+                align_x: Horizontal::Center.into(),
+                align_y: Vertical::Top.into(),
+                ..Text::default()
+            };
+            frame.fill_text(text);
+        }
+
+        // Draw axes
+        let axis_color = Color::from_rgb(0.3, 0.3, 0.3);
+        let y_axis = Path::line(Point::new(area.x, area.y), Point::new(area.x, bottom));
+        frame.stroke(
+            &y_axis,
+            Stroke::default().with_color(axis_color).with_width(1.5),
+        );
+        let x_axis = Path::line(
+            Point::new(area.x, bottom),
+            Point::new(area.x + area.width, bottom),
+        );
+        frame.stroke(
+            &x_axis,
+            Stroke::default().with_color(axis_color).with_width(1.5),
+        );
+
+        // Draw axis labels
+        if let Some(ref x_label) = options.x_label {
+            let text = Text {
+                content: x_label.clone(),
+                position: Point::new(area.x + area.width / 2.0, bounds.height - 5.0),
+                color: Color::from_rgb(0.3, 0.3, 0.3),
+                size: 12.0.into(),
+                align_x: Horizontal::Center.into(),
+                align_y: Vertical::Bottom.into(),
+                ..Text::default()
+            };
+            frame.fill_text(text);
+        }
+        if let Some(ref y_label) = options.y_label {
+            let text = Text {
+                content: y_label.clone(),
+                position: Point::new(15.0, area.y + area.height / 2.0),
+                color: Color::from_rgb(0.3, 0.3, 0.3),
+                size: 12.0.into(),
+                align_x: Horizontal::Center.into(),
+                align_y: Vertical::Center.into(),
+                ..Text::default()
+            };
+            frame.fill_text(text);
+        }
+
+        // Draw legend, with hidden series greyed out
+        let boxes = legend_boxes(bounds.size(), area, legend.len());
+        for (rect, (name, color)) in boxes.into_iter().zip(&legend) {
+            let hidden = state.is_hidden(name);
+            let (swatch, text_color) = if hidden {
+                (
+                    Color::from_rgb(0.8, 0.8, 0.8),
+                    Color::from_rgb(0.7, 0.7, 0.7),
+                )
+            } else {
+                (*color, Color::from_rgb(0.3, 0.3, 0.3))
+            };
+            let y = rect.center_y();
+            let box_path = Path::rectangle(Point::new(rect.x, y - 6.0), Size::new(12.0, 12.0));
+            frame.fill(&box_path, swatch);
+
+            let name = if name.chars().count() > 12 {
+                format!("{}...", name.chars().take(10).collect::<String>())
+            } else {
+                name.clone()
+            };
+            let text = Text {
+                content: name,
+                position: Point::new(rect.x + 18.0, y),
+                color: text_color,
+                size: 11.0.into(),
+                align_x: Horizontal::Left.into(),
+                align_y: Vertical::Center.into(),
+                ..Text::default()
+            };
+            frame.fill_text(text);
+        }
+
+        // Draw the tooltip of the point under the cursor
+        if let (true, Some(mark), Some(cursor)) = (options.tooltips, hovered, state.hover) {
+            draw_tooltip(&mut frame, bounds.size(), cursor, &mark.point.tooltip());
+        }
+
+        vec![frame.into_geometry()]
+    }
+
+    fn mouse_interaction(
+        &self,
+        state: &Self::State,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> mouse::Interaction {
+        let options = self.config.options();
+        let Some(position) = cursor.position_in(bounds) else {
+            return mouse::Interaction::default();
+        };
+        if state.drag.is_some() {
+            return mouse::Interaction::Grabbing;
+        }
+        let on_legend = options.toggle_legend && self.legend_at(bounds.size(), position).is_some();
+        let on_point = options.on_point_click.is_some() && {
+            let (area, _) = self.frame(bounds.size());
+            self.config.layout(area, state).mark_at(position).is_some()
+        };
+        if on_legend || on_point {
+            mouse::Interaction::Pointer
+        } else {
+            mouse::Interaction::default()
+        }
+    }
+}
+
+/// Draw a box of text lines beside the cursor, kept inside the chart
+fn draw_tooltip(frame: &mut Frame, size: Size, cursor: Point, lines: &[String]) {
+    if lines.is_empty() {
+        return;
+    }
+    let line_height = 15.0;
+    let longest = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    #[allow(clippy::cast_precision_loss)]
+    let box_size = Size::new(
+        longest as f32 * 6.5 + 16.0,
+        lines.len() as f32 * line_height + 10.0,
+    );
+    let mut origin = Point::new(cursor.x + 12.0, cursor.y + 12.0);
+    if origin.x + box_size.width > size.width {
+        origin.x = (cursor.x - 12.0 - box_size.width).max(0.0);
+    }
+    if origin.y + box_size.height > size.height {
+        origin.y = (cursor.y - 12.0 - box_size.height).max(0.0);
+    }
+
+    let background = Path::rectangle(origin, box_size);
+    frame.fill(&background, Color::from_rgba(1.0, 1.0, 1.0, 0.95));
+    frame.stroke(
+        &background,
+        Stroke::default()
+            .with_color(Color::from_rgb(0.7, 0.7, 0.7))
+            .with_width(1.0),
+    );
+    for (i, line) in lines.iter().enumerate() {
+        #[allow(clippy::cast_precision_loss)]
+        let y = origin.y + 5.0 + i as f32 * line_height;
+        let text = Text {
+            content: line.clone(),
+            position: Point::new(origin.x + 8.0, y),
+            color: Color::from_rgb(0.2, 0.2, 0.2),
+            size: 12.0.into(),
+            align_x: Horizontal::Left.into(),
+            align_y: Vertical::Top.into(),
+            ..Text::default()
+        };
+        frame.fill_text(text);
+    }
+}
+
+/// Helper trait for arc drawing in Path builder
+trait PathBuilderExt {
+    fn draw_arc(&mut self, center: Point, radius: f32, start_angle: f32, sweep_angle: f32);
+}
+
+impl PathBuilderExt for canvas::path::Builder {
+    fn draw_arc(&mut self, center: Point, radius: f32, start_angle: f32, sweep_angle: f32) {
+        // Approximate arc with bezier curves
+        // For small angles, use fewer segments
+        let num_segments = ((sweep_angle.abs() / (PI / 4.0)).ceil() as usize).max(1);
+        let segment_angle = sweep_angle / num_segments as f32;
+
+        for i in 0..num_segments {
+            let angle1 = start_angle + segment_angle * i as f32;
+            let angle2 = angle1 + segment_angle;
+
+            // Calculate control points for cubic bezier approximation of arc
+            let k = 4.0 / 3.0 * (segment_angle / 4.0).tan();
+
+            let p1 = Point::new(
+                center.x + radius * angle1.cos(),
+                center.y + radius * angle1.sin(),
+            );
+            let p2 = Point::new(
+                center.x + radius * angle2.cos(),
+                center.y + radius * angle2.sin(),
+            );
+
+            let c1 = Point::new(
+                p1.x - k * radius * angle1.sin(),
+                p1.y + k * radius * angle1.cos(),
+            );
+            let c2 = Point::new(
+                p2.x + k * radius * angle2.sin(),
+                p2.y - k * radius * angle2.cos(),
+            );
+
+            self.bezier_curve_to(c1, c2, p2);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_point_creation() {
+        let point = DataPoint::new("Sales", 100.0);
+        assert_eq!(point.label, "Sales");
+        assert!((point.value - 100.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_data_series_creation() {
+        let series = DataSeries::new("Revenue", vec![10.0, 20.0, 30.0]);
+        assert_eq!(series.name, "Revenue");
+        assert_eq!(series.values.len(), 3);
+    }
+
+    #[test]
+    fn test_bar_chart_config_default() {
+        let config = BarChartConfig::default();
+        assert!(config.title.is_none());
+        assert!(config.data.is_empty());
+        assert!((config.width - 400.0).abs() < f32::EPSILON);
+        assert!((config.height - 300.0).abs() < f32::EPSILON);
+        assert!(config.show_grid);
+        assert!(config.show_values);
+    }
+
+    #[test]
+    fn test_line_chart_config_default() {
+        let config = LineChartConfig::default();
+        assert!(config.title.is_none());
+        assert!(config.series.is_empty());
+        assert!(config.labels.is_empty());
+        assert!(config.show_legend);
+        assert!(config.show_grid);
+        assert!(config.show_points);
+        assert!(!config.fill_area);
+    }
+
+    #[test]
+    fn test_pie_chart_config_default() {
+        let config = PieChartConfig::default();
+        assert!(config.title.is_none());
+        assert!(config.data.is_empty());
+        assert!(config.show_legend);
+        assert!(config.show_percentages);
+        assert!(!config.show_values);
+        assert!((config.inner_radius_ratio - 0.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_chart_colors_count() {
+        assert_eq!(CHART_COLORS.len(), 10);
+    }
+
+    fn area() -> Rectangle {
+        Rectangle::new(Point::new(60.0, 20.0), Size::new(400.0, 200.0))
+    }
+
+    #[test]
+    fn test_histogram_bins() {
+        let bins = histogram_bins(&[1.0, 2.0, 2.5, 4.0, 5.0], 4);
+        assert_eq!(bins.len(), 4);
+        assert!((bins[0].start - 1.0).abs() < f64::EPSILON);
+        assert!((bins[3].end - 5.0).abs() < f64::EPSILON);
+        let counts: Vec<usize> = bins.iter().map(|b| b.count).collect();
+        // The largest value lands in the last bin
+        assert_eq!(counts, [1, 2, 0, 2]);
+
+        let same = histogram_bins(&[3.0, 3.0, f64::NAN], 5);
+        assert_eq!(same.len(), 1);
+        assert_eq!(same[0].count, 2);
+        assert!(histogram_bins(&[], 5).is_empty());
+    }
+
+    #[test]
+    fn test_chart_state_zoom_and_pan() {
+        let mut state = ChartState::default();
+        assert_eq!(state.window(0.0, 10.0), (0.0, 10.0));
+
+        // Zooming in at the middle keeps the middle in place
+        state.zoom_at(2.0, 0.5);
+        let (low, high) = state.window(0.0, 10.0);
+        assert!((low - 2.5).abs() < 1e-9 && (high - 7.5).abs() < 1e-9);
+
+        // Panning stops at the ends of the data
+        state.pan_by(10.0);
+        let (low, high) = state.window(0.0, 10.0);
+        assert!((low - 5.0).abs() < 1e-9 && (high - 10.0).abs() < 1e-9);
+
+        state.zoom_at(0.1, 0.0);
+        assert!((state.zoom - 1.0).abs() < f64::EPSILON);
+        assert!(state.pan.abs() < f64::EPSILON);
+        state.zoom_at(1000.0, 0.0);
+        assert!((state.zoom - MAX_ZOOM).abs() < f64::EPSILON);
+        state.reset_zoom();
+        assert_eq!(state.window(0.0, 10.0), (0.0, 10.0));
+
+        state.toggle_series("North");
+        assert!(state.is_hidden("North"));
+        state.toggle_series("North");
+        assert!(!state.is_hidden("North"));
+    }
+
+    #[test]
+    fn test_scatter_layout_hides_series_and_zooms() {
+        let config = ScatterChartConfig {
+            series: vec![
+                ScatterSeries::new("A", vec![(0.0, 1.0), (10.0, 5.0)]),
+                ScatterSeries::new("B", vec![(5.0, 3.0)]),
+            ],
+            ..ScatterChartConfig::default()
+        };
+        let mut state = ChartState::default();
+        let layout = config.layout(area(), &state);
+        assert_eq!(layout.marks.len(), 3);
+        assert_eq!(config.legend().len(), 2);
+
+        // Clicking a dot finds its point
+        let MarkShape::Dot(center, _) = layout.marks[2].shape else {
+            panic!("expected a dot");
+        };
+        let mark = layout.mark_at(center).unwrap();
+        assert_eq!(mark.point.series.as_deref(), Some("B"));
+        assert!((mark.point.x - 5.0).abs() < f64::EPSILON);
+
+        state.toggle_series("B");
+        assert_eq!(config.layout(area(), &state).marks.len(), 2);
+
+        // Zoomed in on the left, the right-hand point is out of view
+        state.zoom_at(4.0, 0.0);
+        assert_eq!(config.layout(area(), &state).marks.len(), 1);
+    }
+
+    #[test]
+    fn test_stacked_layouts() {
+        let labels = vec!["Q1".to_string(), "Q2".to_string()];
+        let series = vec![
+            DataSeries::new("North", vec![1.0, 2.0]),
+            DataSeries::new("South", vec![3.0, 4.0]),
+        ];
+        let bars = StackedBarChartConfig {
+            labels: labels.clone(),
+            series: series.clone(),
+            ..StackedBarChartConfig::default()
+        };
+        let state = ChartState::default();
+        let layout = bars.layout(area(), &state);
+        assert_eq!(layout.marks.len(), 4);
+        assert_eq!(layout.y_range, (0.0, 6.0));
+        // South sits on top of North in each bar
+        let (MarkShape::Rect(north), MarkShape::Rect(south)) =
+            (layout.marks[0].shape, layout.marks[1].shape)
+        else {
+            panic!("expected bars");
+        };
+        assert!((south.y + south.height - north.y).abs() < 0.01);
+        assert!((layout.marks[1].point.y - 3.0).abs() < f64::EPSILON);
+
+        let areas = AreaChartConfig {
+            labels,
+            series,
+            stacked: true,
+            ..AreaChartConfig::default()
+        };
+        let layout = areas.layout(area(), &state);
+        assert_eq!(layout.fills.len(), 2);
+        assert_eq!(layout.y_range, (0.0, 6.0));
+        let total = layout.marks[3]
+            .point
+            .extra
+            .iter()
+            .find(|(k, _)| *k == "total");
+        assert_eq!(total, Some(&("total", 6.0)));
+    }
+
+    #[test]
+    fn test_candlestick_layout() {
+        let config = CandlestickChartConfig {
+            candles: vec![
+                Candle::new("Mon", 10.0, 12.0, 9.0, 11.0),
+                Candle::new("Tue", 11.0, 11.5, 8.0, 9.0),
+            ],
+            ..CandlestickChartConfig::default()
+        };
+        let layout = config.layout(area(), &ChartState::default());
+        assert_eq!(layout.marks.len(), 2);
+        assert_eq!(layout.lines.len(), 2);
+        let (r, g, b) = config.up_color;
+        assert_eq!(layout.marks[0].color, Color::from_rgb8(r, g, b));
+        let (r, g, b) = config.down_color;
+        assert_eq!(layout.marks[1].color, Color::from_rgb8(r, g, b));
+        assert!(config.legend().is_empty());
+        assert_eq!(layout.x_ticks.len(), 2);
+    }
+
+    #[test]
+    fn test_chart_point_value() {
+        let point = ChartPoint::new(2, 2.0, 9.0)
+            .with_label("Wed")
+            .with("open", 8.0)
+            .with("close", 9.0);
+        assert_eq!(point.tooltip(), ["Wed", "open: 8", "close: 9"]);
+
+        let Value::Map(map) = point.to_value() else {
+            panic!("expected a map");
+        };
+        let map = map.borrow();
+        let get = |key: &str| map.get(&HashableValue::String(Rc::new(key.to_string())));
+        assert_eq!(get("index"), Some(&Value::Int(2)));
+        assert_eq!(get("label"), Some(&Value::string("Wed")));
+        assert_eq!(get("open"), Some(&Value::Float(8.0)));
+        assert!(get("series").is_none());
+
+        let scatter = ChartPoint::new(0, 1.5, 1000.0).with_series("A");
+        assert_eq!(scatter.tooltip(), ["A", "x: 1.50", "y: 1.0k"]);
+    }
+
+    #[test]
+    fn test_bar_and_pie_hit_testing() {
+        let size = Size::new(400.0, 300.0);
+        let bars = BarChartProgram {
+            config: BarChartConfig {
+                data: vec![DataPoint::new("A", 1.0), DataPoint::new("B", 2.0)],
+                ..BarChartConfig::default()
+            },
+        };
+        // Bars are 145px wide from x = 70 and x = 225
+        let point = bars.point_at(size, Point::new(300.0, 150.0)).unwrap();
+        assert_eq!(point.label.as_deref(), Some("B"));
+        assert!(bars.point_at(size, Point::new(220.0, 150.0)).is_none());
+        assert!(bars.point_at(size, Point::new(100.0, 290.0)).is_none());
+
+        let pie = PieChartProgram {
+            config: PieChartConfig {
+                data: vec![DataPoint::new("A", 1.0), DataPoint::new("B", 3.0)],
+                show_legend: false,
+                ..PieChartConfig::default()
+            },
+        };
+        // Centered at (210, 145); A covers the quarter clockwise from the top
+        let a = pie.point_at(size, Point::new(240.0, 115.0)).unwrap();
+        assert_eq!(a.label.as_deref(), Some("A"));
+        let b = pie.point_at(size, Point::new(180.0, 115.0)).unwrap();
+        assert_eq!(b.label.as_deref(), Some("B"));
+        assert!(pie.point_at(size, Point::new(5.0, 5.0)).is_none());
     }
 }
@@ -0,0 +1,41 @@
+//! System clipboard
+//!
+//! Text reads and writes through arboard. The handle is kept for the life of
+//! the GUI thread: on X11 and Wayland the text we copy is only offered to
+//! other programs while it is open.
+
+use std::cell::RefCell;
+
+use arboard::Clipboard;
+
+thread_local! {
+    static CLIPBOARD: RefCell<Option<Clipboard>> = const { RefCell::new(None) };
+}
+
+fn with_clipboard<R>(f: impl FnOnce(&mut Clipboard) -> Result<R, String>) -> Result<R, String> {
+    CLIPBOARD.with(|clipboard| {
+        let mut clipboard = clipboard.borrow_mut();
+        if clipboard.is_none() {
+            *clipboard = Some(Clipboard::new().map_err(|e| format!("clipboard unavailable: {e}"))?);
+        }
+        f(clipboard.as_mut().expect("clipboard was just opened"))
+    })
+}
+
+/// Text on the clipboard, or None if it holds something else or nothing
+pub fn read_text() -> Result<Option<String>, String> {
+    with_clipboard(|clipboard| match clipboard.get_text() {
+        Ok(text) => Ok(Some(text)),
+        Err(arboard::Error::ContentNotAvailable) => Ok(None),
+        Err(e) => Err(format!("failed to read the clipboard: {e}")),
+    })
+}
+
+/// Put text on the clipboard
+pub fn write_text(text: &str) -> Result<(), String> {
+    with_clipboard(|clipboard| {
+        clipboard
+            .set_text(text)
+            .map_err(|e| format!("failed to write the clipboard: {e}"))
+    })
+}
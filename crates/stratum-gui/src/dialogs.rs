@@ -0,0 +1,179 @@
+//! Native file, folder and message dialogs
+//!
+//! Thin wrappers over rfd's blocking dialogs. They are called from Stratum
+//! callbacks, which run on the GUI thread, and return once the user closes
+//! the dialog.
+
+use std::path::PathBuf;
+
+use rfd::{FileDialog, MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
+use stratum_core::bytecode::{HashableValue, Value};
+
+/// A named set of file extensions offered by a file dialog
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileFilter {
+    pub name: String,
+    pub extensions: Vec<String>,
+}
+
+impl FileFilter {
+    /// Filters from a Stratum value: a map of names to extension lists, a
+    /// list of extensions (one filter named "Files"), or null for none.
+    /// Extensions may be given with or without the leading dot.
+    pub fn from_value(value: &Value) -> Result<Vec<Self>, String> {
+        match value {
+            Value::Null => Ok(Vec::new()),
+            Value::List(list) => Ok(vec![Self {
+                name: "Files".to_string(),
+                extensions: extensions(&list.borrow())?,
+            }]),
+            Value::Map(map) => {
+                let mut filters = Vec::new();
+                for (name, exts) in map.borrow().iter() {
+                    let HashableValue::String(name) = name else {
+                        return Err("filter names must be strings".to_string());
+                    };
+                    let Value::List(exts) = exts else {
+                        return Err(format!(
+                            "extensions of filter '{name}' must be a list, got {}",
+                            exts.type_name()
+                        ));
+                    };
+                    filters.push(Self {
+                        name: name.to_string(),
+                        extensions: extensions(&exts.borrow())?,
+                    });
+                }
+                // Maps don't keep insertion order; list filters by name
+                filters.sort_by(|a, b| a.name.cmp(&b.name));
+                Ok(filters)
+            }
+            other => Err(format!(
+                "filters must be a map or list, got {}",
+                other.type_name()
+            )),
+        }
+    }
+}
+
+fn extensions(values: &[Value]) -> Result<Vec<String>, String> {
+    values
+        .iter()
+        .map(|value| match value {
+            Value::String(ext) => Ok(ext.trim_start_matches('.').to_string()),
+            other => Err(format!(
+                "file extensions must be strings, got {}",
+                other.type_name()
+            )),
+        })
+        .collect()
+}
+
+fn file_dialog(filters: &[FileFilter]) -> FileDialog {
+    filters.iter().fold(FileDialog::new(), |dialog, filter| {
+        dialog.add_filter(&filter.name, &filter.extensions)
+    })
+}
+
+/// Ask for a file to open
+#[must_use]
+pub fn open_file(filters: &[FileFilter], title: Option<&str>) -> Option<PathBuf> {
+    let mut dialog = file_dialog(filters);
+    if let Some(title) = title {
+        dialog = dialog.set_title(title);
+    }
+    dialog.pick_file()
+}
+
+/// Ask for one or more files to open
+#[must_use]
+pub fn open_files(filters: &[FileFilter], title: Option<&str>) -> Option<Vec<PathBuf>> {
+    let mut dialog = file_dialog(filters);
+    if let Some(title) = title {
+        dialog = dialog.set_title(title);
+    }
+    dialog.pick_files()
+}
+
+/// Ask where to save a file, suggesting a file name
+#[must_use]
+pub fn save_file(filters: &[FileFilter], file_name: Option<&str>) -> Option<PathBuf> {
+    let mut dialog = file_dialog(filters);
+    if let Some(name) = file_name {
+        dialog = dialog.set_file_name(name);
+    }
+    dialog.save_file()
+}
+
+/// Ask for a folder
+#[must_use]
+pub fn pick_folder(title: Option<&str>) -> Option<PathBuf> {
+    let mut dialog = FileDialog::new();
+    if let Some(title) = title {
+        dialog = dialog.set_title(title);
+    }
+    dialog.pick_folder()
+}
+
+/// Show a message with an OK button
+pub fn alert(title: &str, message: &str) {
+    MessageDialog::new()
+        .set_level(MessageLevel::Info)
+        .set_title(title)
+        .set_description(message)
+        .set_buttons(MessageButtons::Ok)
+        .show();
+}
+
+/// Ask a question with OK and Cancel buttons; true if OK was chosen
+#[must_use]
+pub fn confirm(title: &str, message: &str) -> bool {
+    let result = MessageDialog::new()
+        .set_level(MessageLevel::Warning)
+        .set_title(title)
+        .set_description(message)
+        .set_buttons(MessageButtons::OkCancel)
+        .show();
+    matches!(result, MessageDialogResult::Ok | MessageDialogResult::Yes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_filters_from_list() {
+        let value = Value::list(vec![Value::string(".csv"), Value::string("tsv")]);
+        assert_eq!(
+            FileFilter::from_value(&value).unwrap(),
+            [FileFilter {
+                name: "Files".to_string(),
+                extensions: vec!["csv".to_string(), "tsv".to_string()],
+            }]
+        );
+        assert!(FileFilter::from_value(&Value::Null).unwrap().is_empty());
+        assert!(FileFilter::from_value(&Value::list(vec![Value::Int(1)])).is_err());
+        assert!(FileFilter::from_value(&Value::Int(1)).is_err());
+    }
+
+    #[test]
+    fn test_filters_from_map() {
+        let value = Value::empty_map();
+        if let Value::Map(ref map) = value {
+            let mut map = map.borrow_mut();
+            map.insert(
+                HashableValue::String(Rc::new("Images".to_string())),
+                Value::list(vec![Value::string("png"), Value::string("jpg")]),
+            );
+            map.insert(
+                HashableValue::String(Rc::new("Data".to_string())),
+                Value::list(vec![Value::string("csv")]),
+            );
+        }
+        let filters = FileFilter::from_value(&value).unwrap();
+        let names: Vec<&str> = filters.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["Data", "Images"]);
+        assert_eq!(filters[1].extensions, ["png", "jpg"]);
+    }
+}
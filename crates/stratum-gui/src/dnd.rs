@@ -0,0 +1,128 @@
+//! Drag and drop between widgets
+//!
+//! `Gui.draggable(element, payload, kind?)` lets an element be picked up with
+//! the mouse and `Gui.drop_target(element, on_drop, accepts?)` calls back with
+//! the payload when it is released over the target. Payloads are Stratum
+//! values, which can't live in the element tree, so they are kept here by id
+//! until the view function rebuilds the tree. The item being dragged is kept
+//! here too, so targets that accept it can show so while it is under way.
+
+use std::cell::RefCell;
+
+use stratum_core::bytecode::Value;
+
+thread_local! {
+    static PAYLOADS: RefCell<Vec<Value>> = const { RefCell::new(Vec::new()) };
+    static DRAGGING: RefCell<Option<DragItem>> = const { RefCell::new(None) };
+}
+
+/// A payload being dragged
+#[derive(Debug, Clone)]
+pub struct DragItem {
+    /// Kind of payload, matched against what drop targets accept
+    pub kind: String,
+    /// The dragged value
+    pub payload: Value,
+}
+
+/// Window file events a Stratum callback can be registered for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileDropEvent {
+    /// Files dragged over the window
+    Hover,
+    /// Files dropped on the window
+    Drop,
+    /// Dragged files left the window without being dropped
+    HoverLeft,
+}
+
+/// Keep a payload for a draggable element; returns its id
+pub fn register_payload(payload: Value) -> usize {
+    PAYLOADS.with(|payloads| {
+        let mut payloads = payloads.borrow_mut();
+        payloads.push(payload);
+        payloads.len() - 1
+    })
+}
+
+/// Forget the payloads of the previous element tree
+pub fn clear_payloads() {
+    PAYLOADS.with(|payloads| payloads.borrow_mut().clear());
+}
+
+/// Start dragging a registered payload; false if the id is stale
+pub fn start_drag(kind: &str, payload: usize) -> bool {
+    let Some(payload) = PAYLOADS.with(|payloads| payloads.borrow().get(payload).cloned()) else {
+        return false;
+    };
+    DRAGGING.with(|dragging| {
+        *dragging.borrow_mut() = Some(DragItem {
+            kind: kind.to_string(),
+            payload,
+        });
+    });
+    true
+}
+
+/// Kind of the payload being dragged, if any
+pub fn dragging_kind() -> Option<String> {
+    DRAGGING.with(|dragging| dragging.borrow().as_ref().map(|item| item.kind.clone()))
+}
+
+/// End the drag, returning what was dragged
+pub fn finish_drag() -> Option<DragItem> {
+    DRAGGING.with(|dragging| dragging.borrow_mut().take())
+}
+
+/// Kind of a payload dragged without one: a struct's type name, otherwise
+/// the value's type
+#[must_use]
+pub fn default_kind(payload: &Value) -> String {
+    match payload {
+        Value::Struct(instance) => instance.borrow().type_name.clone(),
+        other => other.type_name().to_string(),
+    }
+}
+
+/// Whether a target accepting `accepts` takes a payload of `kind`; an empty
+/// list accepts every kind
+#[must_use]
+pub fn accepts(accepts: &[String], kind: &str) -> bool {
+    accepts.is_empty() || accepts.iter().any(|k| k == kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accepts() {
+        assert!(accepts(&[], "row"));
+        assert!(accepts(&["row".to_string(), "file".to_string()], "file"));
+        assert!(!accepts(&["row".to_string()], "column"));
+    }
+
+    #[test]
+    fn test_default_kind() {
+        assert_eq!(default_kind(&Value::string("a")), "String");
+        assert_eq!(default_kind(&Value::Int(1)), "Int");
+    }
+
+    #[test]
+    fn test_drag_lifecycle() {
+        clear_payloads();
+        let id = register_payload(Value::string("task-1"));
+        assert!(start_drag("task", id));
+        assert_eq!(dragging_kind().as_deref(), Some("task"));
+
+        let item = finish_drag().expect("a drag is under way");
+        assert_eq!(item.kind, "task");
+        assert_eq!(item.payload, Value::string("task-1"));
+        assert!(dragging_kind().is_none());
+
+        // Payloads of an old element tree can't be dragged
+        clear_payloads();
+        assert!(!start_drag("task", id));
+        assert!(finish_drag().is_none());
+    }
+}
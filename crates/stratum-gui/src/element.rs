@@ -7,27 +7,39 @@ use std::fmt;
 use std::sync::{Arc, RwLock};
 
 use iced::widget::{
-    button, canvas, checkbox, column, container, mouse_area, pick_list, progress_bar, radio, row,
-    scrollable, slider, text, text_input, toggler, Image,
+    button, canvas, checkbox, column, container, image, mouse_area, pick_list, progress_bar, radio,
+    row, scrollable, slider, text, text_input, toggler, Image,
 };
-use iced::{font, Color, ContentFit, Element, Fill, Font, Length, Point};
+use iced::{font, Color, ContentFit, Degrees, Element, Fill, Font, Length, Point, Rotation};
 
 use crate::charts::{
-    BarChartConfig, BarChartProgram, DataPoint, DataSeries, LineChartConfig, LineChartProgram,
-    PieChartConfig, PieChartProgram,
+    AreaChartConfig, BarChartConfig, BarChartProgram, Candle, CandlestickChartConfig, DataPoint,
+    DataSeries, HistogramConfig, LineChartConfig, LineChartProgram, PieChartConfig,
+    PieChartProgram, PlotChart, PlotOptions, PlotProgram, ScatterChartConfig, ScatterSeries,
+    StackedBarChartConfig,
 };
 
 use stratum_core::bytecode::{GuiValue, Value};
 use stratum_core::data::{CubeQuery, DataFrame};
 
+use crate::accessibility::{self, Access, Focusable, Role};
+use crate::animation::Transition;
 use crate::callback::{CallbackExecutor, CallbackId};
+use crate::canvas::{canvas_frame, CanvasProgram};
+use crate::dnd::{accepts, dragging_kind};
+use crate::form::field_error;
+use crate::image_loader::{self, RemoteImage};
 use crate::layout::{
     Container, Grid, HAlign, HStack, ScrollDirection, ScrollView, Size, Spacer, VAlign, VStack,
     ZStack,
 };
+use crate::markdown::{self, Block};
+use crate::menu::{context_menu_items, menu_entries, MenuEntry, MenuItem};
 use crate::runtime::Message;
 use crate::state::ReactiveState;
-use crate::theme::{Color as StratumColor, WidgetStyle};
+use crate::table::{with_table_view, DEFAULT_COLUMN_WIDTH, DEFAULT_ROW_HEIGHT};
+use crate::theme::{active_typography, active_widget_style, Color as StratumColor, WidgetStyle};
+use crate::tree::{section_expanded, visible_nodes, with_tree_view, TreeNode};
 
 /// A GUI element that can be composed into a widget tree.
 ///
@@ -65,6 +77,10 @@ pub struct ElementStyle {
     pub visible: bool,
     /// Widget-specific styling (background, foreground, border, etc.)
     pub widget_style: WidgetStyle,
+    /// How changes to padding, fixed sizes, colors and borders animate
+    pub transition: Option<Transition>,
+    /// Accessible label, role and tab order
+    pub access: Access,
 }
 
 impl ElementStyle {
@@ -156,12 +172,42 @@ pub enum GuiElementKind {
     ForEach(ForEachConfig),
     /// Data table for displaying DataFrames
     DataTable(DataTableConfig),
+    /// Tree of expandable nodes
+    Tree(TreeConfig),
+    /// Titled section whose children collapse
+    Section(SectionConfig),
+    /// Canvas drawn by a Stratum callback
+    Canvas(CanvasConfig),
+    /// Row of dropdown menus
+    MenuBar(MenuBarConfig),
+    /// Menu shown when the child is right-clicked
+    ContextMenu(ContextMenuConfig),
+    /// Child that can be dragged onto drop targets
+    Draggable(DraggableConfig),
+    /// Child that takes dragged payloads
+    DropTarget(DropTargetConfig),
+    /// Validation error of a form field, shown once the field is touched
+    FieldError(FieldErrorConfig),
+    /// Where the page on top of the navigation stack is shown
+    Navigator(NavigatorConfig),
+    /// Formatted markdown text
+    Markdown(MarkdownConfig),
     /// Bar chart for categorical data visualization
     BarChart(BarChartConfig),
     /// Line chart for trend visualization
     LineChart(LineChartConfig),
     /// Pie chart for proportion visualization
     PieChart(PieChartConfig),
+    /// Scatter chart of (x, y) points
+    ScatterChart(ScatterChartConfig),
+    /// Area chart, optionally stacked
+    AreaChart(AreaChartConfig),
+    /// Bar chart with a segment per series in each bar
+    StackedBarChart(StackedBarChartConfig),
+    /// Histogram of values counted into bins
+    Histogram(HistogramConfig),
+    /// Candlestick chart of open, high, low and close prices
+    CandlestickChart(CandlestickChartConfig),
     /// OLAP Cube table with drill-down support
     CubeTable(CubeTableConfig),
     /// OLAP Cube chart with drill-down support
@@ -176,6 +222,20 @@ pub enum GuiElementKind {
     Interactive(InteractiveConfig),
 }
 
+impl GuiElementKind {
+    /// Options of the interactive charts, if this is one
+    pub fn plot_options_mut(&mut self) -> Option<&mut PlotOptions> {
+        match self {
+            Self::ScatterChart(c) => Some(&mut c.options),
+            Self::AreaChart(c) => Some(&mut c.options),
+            Self::StackedBarChart(c) => Some(&mut c.options),
+            Self::Histogram(c) => Some(&mut c.options),
+            Self::CandlestickChart(c) => Some(&mut c.options),
+            _ => None,
+        }
+    }
+}
+
 /// VStack configuration
 #[derive(Debug, Clone, Default)]
 pub struct VStackConfig {
@@ -509,11 +569,13 @@ impl Default for ProgressBarConfig {
 
 /// Image configuration
 ///
-/// Images display raster graphics from a file path or bytes.
+/// Images display raster graphics from a file path, a URL or pixels.
 #[derive(Debug, Clone)]
 pub struct ImageConfig {
-    /// Path to the image file
+    /// Path or URL of the image
     pub path: Option<String>,
+    /// Pixels of an image value, shown in place of the path
+    pub handle: Option<image::Handle>,
     /// Image content fit mode
     pub content_fit: ImageContentFit,
     /// Optional fixed width
@@ -522,16 +584,20 @@ pub struct ImageConfig {
     pub image_height: Option<f32>,
     /// Opacity (0.0 to 1.0)
     pub opacity: f32,
+    /// Clockwise rotation in degrees
+    pub rotation: f32,
 }
 
 impl Default for ImageConfig {
     fn default() -> Self {
         Self {
             path: None,
+            handle: None,
             content_fit: ImageContentFit::Contain,
             image_width: None,
             image_height: None,
             opacity: 1.0,
+            rotation: 0.0,
         }
     }
 }
@@ -553,6 +619,20 @@ pub enum ImageContentFit {
 }
 
 impl ImageContentFit {
+    /// Parse a fit name: "contain" (or "fit"), "cover", "fill", "none" or
+    /// "scale-down"
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "contain" | "fit" => Some(Self::Contain),
+            "cover" => Some(Self::Cover),
+            "fill" => Some(Self::Fill),
+            "none" => Some(Self::None),
+            "scale-down" => Some(Self::ScaleDown),
+            _ => None,
+        }
+    }
+
     /// Convert to iced's ContentFit
     #[must_use]
     pub fn to_iced(self) -> ContentFit {
@@ -608,9 +688,21 @@ pub struct ForEachConfig {
 /// Data table configuration
 ///
 /// Displays tabular data from a DataFrame with features like sorting,
-/// pagination, row selection, and customizable column widths.
+/// pagination, row selection, and customizable column widths. A virtualized
+/// table (`Gui.table`) scrolls through all rows instead of paging, drawing
+/// only those in view.
 #[derive(Clone)]
 pub struct DataTableConfig {
+    /// Key for the table's scroll, sort, width and selection state
+    /// (None = derived from the columns)
+    pub id: Option<String>,
+    /// Whether to render only the rows in view instead of pages
+    pub virtualized: bool,
+    /// Row height in pixels (virtualized tables)
+    pub row_height: f32,
+    /// Whether columns can be resized by dragging their header edge
+    /// (virtualized tables)
+    pub resizable: bool,
     /// The DataFrame to display
     pub dataframe: Option<Arc<DataFrame>>,
     /// Columns to display (None = all columns)
@@ -643,11 +735,33 @@ pub struct DataTableConfig {
     pub on_selection_change: Option<CallbackId>,
     /// Custom cell renderers (column name -> callback that takes cell value and returns element)
     pub cell_renderers: Vec<(String, CallbackId)>,
+    /// Cell formatters (column name -> callback that takes cell value and row, returns text)
+    pub cell_formatters: Vec<(String, CallbackId)>,
+}
+
+impl DataTableConfig {
+    /// Key of the table's view state in [`crate::table`]
+    #[must_use]
+    pub fn view_id(&self) -> String {
+        if let Some(ref id) = self.id {
+            return id.clone();
+        }
+        let columns = match (&self.columns, &self.dataframe) {
+            (Some(columns), _) => columns.clone(),
+            (None, Some(df)) => df.columns(),
+            (None, None) => Vec::new(),
+        };
+        format!("table:{}", columns.join(","))
+    }
 }
 
 impl Default for DataTableConfig {
     fn default() -> Self {
         Self {
+            id: None,
+            virtualized: false,
+            row_height: DEFAULT_ROW_HEIGHT,
+            resizable: true,
             dataframe: None,
             columns: None,
             page_size: Some(50),
@@ -664,6 +778,7 @@ impl Default for DataTableConfig {
             on_page_change: None,
             on_selection_change: None,
             cell_renderers: Vec::new(),
+            cell_formatters: Vec::new(),
         }
     }
 }
@@ -671,6 +786,8 @@ impl Default for DataTableConfig {
 impl fmt::Debug for DataTableConfig {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("DataTableConfig")
+            .field("id", &self.id)
+            .field("virtualized", &self.virtualized)
             .field("columns", &self.columns)
             .field("page_size", &self.page_size)
             .field("current_page", &self.current_page)
@@ -692,6 +809,169 @@ pub enum SortDirection {
     Descending,
 }
 
+/// Tree view configuration
+///
+/// Displays nested nodes that expand and collapse. Nodes marked
+/// `has_children` without children are loaded by the `on_expand` callback.
+#[derive(Debug, Clone)]
+pub struct TreeConfig {
+    /// Key for the tree's expanded and selected nodes
+    /// (None = derived from the root node ids)
+    pub id: Option<String>,
+    /// Root nodes
+    pub nodes: Vec<TreeNode>,
+    /// Node ids expanded when the tree is first shown
+    pub expanded: Vec<String>,
+    /// Node id selected when the tree is first shown
+    pub selected: Option<String>,
+    /// Indentation per level in pixels
+    pub indent: f32,
+    /// State field path kept up to date with the expanded node ids
+    pub field_path: Option<String>,
+    /// Callback when a node expands (receives node id)
+    pub on_expand: Option<CallbackId>,
+    /// Callback when a node is selected (receives node id)
+    pub on_select: Option<CallbackId>,
+    /// Callback when a node is right-clicked (receives node id)
+    pub on_context_menu: Option<CallbackId>,
+}
+
+impl Default for TreeConfig {
+    fn default() -> Self {
+        Self {
+            id: None,
+            nodes: Vec::new(),
+            expanded: Vec::new(),
+            selected: None,
+            indent: 16.0,
+            field_path: None,
+            on_expand: None,
+            on_select: None,
+            on_context_menu: None,
+        }
+    }
+}
+
+impl TreeConfig {
+    /// Key of the tree's view state in [`crate::tree`]
+    #[must_use]
+    pub fn view_id(&self) -> String {
+        self.id.clone().unwrap_or_else(|| {
+            let roots: Vec<&str> = self.nodes.iter().map(|node| node.id.as_str()).collect();
+            format!("tree:{}", roots.join(","))
+        })
+    }
+}
+
+/// Collapsible section configuration
+///
+/// A header that expands and collapses the section's children. Stacked
+/// sections make an accordion.
+#[derive(Debug, Clone, Default)]
+pub struct SectionConfig {
+    /// Header text
+    pub title: String,
+    /// Key for the section's expanded state (None = derived from the title)
+    pub id: Option<String>,
+    /// Whether the section is expanded when first shown
+    pub expanded: bool,
+    /// State field path kept up to date with whether the section is expanded
+    pub field_path: Option<String>,
+    /// Callback when the section expands or collapses (receives expanded bool)
+    pub on_toggle: Option<CallbackId>,
+}
+
+impl SectionConfig {
+    /// Key of the section's expanded state in [`crate::tree`]
+    #[must_use]
+    pub fn view_id(&self) -> String {
+        self.id
+            .clone()
+            .unwrap_or_else(|| format!("section:{}", self.title))
+    }
+}
+
+/// Canvas configuration
+///
+/// The draw callback receives a [`crate::canvas::CanvasContext`] and records what to draw
+/// each time the view is rebuilt.
+#[derive(Debug, Clone, Default)]
+pub struct CanvasConfig {
+    /// Width in pixels, as reported by the context
+    pub width: f32,
+    /// Height in pixels, as reported by the context
+    pub height: f32,
+    /// Callback drawing the canvas (receives the drawing context)
+    pub draw: Option<CallbackId>,
+    /// Callback for mouse and keyboard input over the canvas (receives an event map)
+    pub on_event: Option<CallbackId>,
+}
+
+/// Menu bar configuration
+#[derive(Debug, Clone, Default)]
+pub struct MenuBarConfig {
+    /// Top-level menus; one without items acts as a button
+    pub menus: Vec<MenuItem>,
+}
+
+/// Context menu configuration
+///
+/// Wraps a single child, showing the menu where it is right-clicked.
+#[derive(Debug, Clone, Default)]
+pub struct ContextMenuConfig {
+    /// Items of the menu
+    pub items: Vec<MenuItem>,
+}
+
+/// Draggable configuration
+///
+/// Wraps a single child that starts a drag when pressed.
+#[derive(Debug, Clone, Default)]
+pub struct DraggableConfig {
+    /// Kind of payload, matched against what drop targets accept
+    pub kind: String,
+    /// Id of the payload registered with `crate::dnd`
+    pub payload: usize,
+}
+
+/// Drop target configuration
+///
+/// Wraps a single child that takes payloads released over it.
+#[derive(Debug, Clone, Default)]
+pub struct DropTargetConfig {
+    /// Payload kinds taken; empty takes any
+    pub accepts: Vec<String>,
+    /// Callback for a drop (receives the payload and its kind)
+    pub on_drop: Option<CallbackId>,
+}
+
+/// Field error configuration
+#[derive(Debug, Clone, Default)]
+pub struct FieldErrorConfig {
+    /// Path of the validated state field
+    pub field: String,
+}
+
+/// Navigator configuration
+///
+/// The app fills in the page shown as the single child.
+#[derive(Debug, Clone, Default)]
+pub struct NavigatorConfig {
+    /// Name of the page shown, once filled in
+    pub page: Option<String>,
+}
+
+/// Markdown configuration
+#[derive(Debug, Clone, Default)]
+pub struct MarkdownConfig {
+    /// The markdown text
+    pub source: String,
+    /// The text parsed into blocks
+    pub blocks: Vec<Block>,
+    /// Callback for a clicked link (receives the URL)
+    pub on_link: Option<CallbackId>,
+}
+
 // =============================================================================
 // OLAP Cube Widget Configurations
 // =============================================================================
@@ -1494,6 +1774,109 @@ impl GuiElement {
         GuiElementBuilder::new(GuiElementKind::DataTable(DataTableConfig::default()))
     }
 
+    /// Create a virtualized table element
+    ///
+    /// Scrolls through all rows of its DataFrame, rendering only those in
+    /// view, with sortable and resizable columns.
+    #[must_use]
+    pub fn table() -> GuiElementBuilder {
+        GuiElementBuilder::new(GuiElementKind::DataTable(DataTableConfig {
+            virtualized: true,
+            page_size: None,
+            ..Default::default()
+        }))
+    }
+
+    /// Create a tree view element
+    #[must_use]
+    pub fn tree(nodes: Vec<TreeNode>) -> GuiElementBuilder {
+        GuiElementBuilder::new(GuiElementKind::Tree(TreeConfig {
+            nodes,
+            ..Default::default()
+        }))
+    }
+
+    /// Create a collapsible section element
+    #[must_use]
+    pub fn section(title: impl Into<String>) -> GuiElementBuilder {
+        GuiElementBuilder::new(GuiElementKind::Section(SectionConfig {
+            title: title.into(),
+            ..Default::default()
+        }))
+    }
+
+    /// Create a canvas element of the given size
+    #[must_use]
+    pub fn canvas(width: f32, height: f32) -> GuiElementBuilder {
+        GuiElementBuilder::new(GuiElementKind::Canvas(CanvasConfig {
+            width,
+            height,
+            ..Default::default()
+        }))
+    }
+
+    /// Create a menu bar element
+    #[must_use]
+    pub fn menu_bar(menus: Vec<MenuItem>) -> GuiElementBuilder {
+        GuiElementBuilder::new(GuiElementKind::MenuBar(MenuBarConfig { menus }))
+    }
+
+    /// Wrap an element so right-clicking it shows a menu
+    #[must_use]
+    pub fn context_menu(child: GuiElement, items: Vec<MenuItem>) -> GuiElementBuilder {
+        GuiElementBuilder::new(GuiElementKind::ContextMenu(ContextMenuConfig { items }))
+            .child(child)
+    }
+
+    /// Wrap an element so it can be dragged, carrying a registered payload
+    #[must_use]
+    pub fn draggable(
+        child: GuiElement,
+        kind: impl Into<String>,
+        payload: usize,
+    ) -> GuiElementBuilder {
+        GuiElementBuilder::new(GuiElementKind::Draggable(DraggableConfig {
+            kind: kind.into(),
+            payload,
+        }))
+        .child(child)
+    }
+
+    /// Wrap an element so payloads of the given kinds can be dropped on it
+    #[must_use]
+    pub fn drop_target(child: GuiElement, accepts: Vec<String>) -> GuiElementBuilder {
+        GuiElementBuilder::new(GuiElementKind::DropTarget(DropTargetConfig {
+            accepts,
+            on_drop: None,
+        }))
+        .child(child)
+    }
+
+    /// Create an element showing a form field's validation error
+    #[must_use]
+    pub fn field_error(field: impl Into<String>) -> GuiElementBuilder {
+        GuiElementBuilder::new(GuiElementKind::FieldError(FieldErrorConfig {
+            field: field.into(),
+        }))
+    }
+
+    /// Create the place where the current page of the navigator is shown
+    #[must_use]
+    pub fn navigator() -> GuiElementBuilder {
+        GuiElementBuilder::new(GuiElementKind::Navigator(NavigatorConfig::default()))
+    }
+
+    /// Create an element showing formatted markdown text
+    #[must_use]
+    pub fn markdown(source: impl Into<String>) -> GuiElementBuilder {
+        let source = source.into();
+        GuiElementBuilder::new(GuiElementKind::Markdown(MarkdownConfig {
+            blocks: markdown::parse(&source),
+            source,
+            on_link: None,
+        }))
+    }
+
     /// Create a data table with a DataFrame
     #[must_use]
     pub fn data_table_with_data(dataframe: Arc<DataFrame>) -> GuiElementBuilder {
@@ -1551,6 +1934,57 @@ impl GuiElement {
         }))
     }
 
+    /// Create a scatter chart with data
+    #[must_use]
+    pub fn scatter_chart_with_data(series: Vec<ScatterSeries>) -> GuiElementBuilder {
+        GuiElementBuilder::new(GuiElementKind::ScatterChart(ScatterChartConfig {
+            series,
+            ..Default::default()
+        }))
+    }
+
+    /// Create an area chart with data
+    #[must_use]
+    pub fn area_chart_with_data(labels: Vec<String>, series: Vec<DataSeries>) -> GuiElementBuilder {
+        GuiElementBuilder::new(GuiElementKind::AreaChart(AreaChartConfig {
+            labels,
+            series,
+            ..Default::default()
+        }))
+    }
+
+    /// Create a stacked bar chart with data
+    #[must_use]
+    pub fn stacked_bar_chart_with_data(
+        labels: Vec<String>,
+        series: Vec<DataSeries>,
+    ) -> GuiElementBuilder {
+        GuiElementBuilder::new(GuiElementKind::StackedBarChart(StackedBarChartConfig {
+            labels,
+            series,
+            ..Default::default()
+        }))
+    }
+
+    /// Create a histogram of values counted into `bins` bins
+    #[must_use]
+    pub fn histogram_with_data(values: Vec<f64>, bins: usize) -> GuiElementBuilder {
+        GuiElementBuilder::new(GuiElementKind::Histogram(HistogramConfig {
+            values,
+            bins: bins.max(1),
+            ..Default::default()
+        }))
+    }
+
+    /// Create a candlestick chart with data
+    #[must_use]
+    pub fn candlestick_chart_with_data(candles: Vec<Candle>) -> GuiElementBuilder {
+        GuiElementBuilder::new(GuiElementKind::CandlestickChart(CandlestickChartConfig {
+            candles,
+            ..Default::default()
+        }))
+    }
+
     // =========================================================================
     // OLAP Cube Widget Builders
     // =========================================================================
@@ -1676,6 +2110,21 @@ impl GuiElement {
             return iced::widget::Space::new().into();
         }
 
+        // Number focusable elements as they're rendered, ringing the focused one
+        let Some(focusable) = self.focusable() else {
+            return self.render_kind(None);
+        };
+        let number = accessibility::register(focusable);
+        let content = self.render_kind(Some(number));
+        if accessibility::is_focused(number) {
+            accessibility::focus_ring(content)
+        } else {
+            content
+        }
+    }
+
+    /// Render the element's kind; `focus` is its number if it's focusable
+    fn render_kind(&self, focus: Option<usize>) -> Element<'_, Message> {
         match &self.kind {
             GuiElementKind::VStack(config) => {
                 let children: Vec<Element<'_, Message>> =
@@ -1823,28 +2272,39 @@ impl GuiElement {
                     c = c.height(height);
                 }
 
-                c.render(content)
+                // Apply the theme's container style around the laid out content
+                match active_widget_style("container") {
+                    Some(style) => container(c.render(content))
+                        .style(move |_theme| style.apply_container(container::Style::default()))
+                        .into(),
+                    None => c.render(content),
+                }
             }
 
             GuiElementKind::Text(config) => {
                 let mut t = text(&config.content);
+                let typography = active_typography();
 
-                if let Some(size) = config.size {
+                if let Some(size) = config.size.or(typography.size) {
                     t = t.size(size);
                 }
 
-                // Apply bold font weight
+                // Apply bold font weight, in the theme's font if it has one
                 if config.bold {
                     t = t.font(Font {
                         weight: font::Weight::Bold,
-                        ..Font::default()
+                        ..typography.font.unwrap_or_default()
                     });
+                } else if let Some(font) = typography.font {
+                    t = t.font(font);
                 }
 
-                // Apply color
+                // Apply color, or the theme's text color
                 if let Some((r, g, b, a)) = config.color {
                     let color = Color::from_rgba8(r, g, b, f32::from(a) / 255.0);
                     t = t.color(color);
+                } else if let Some(color) = active_widget_style("text").and_then(|s| s.foreground) {
+                    t = t.color(color.to_iced());
                 }
 
                 // Wrap in container if padding is needed
@@ -1856,12 +2316,24 @@ impl GuiElement {
             }
 
             GuiElementKind::Button(config) => {
-                let label = text(&config.label);
+                let typography = active_typography();
+                let mut label = text(&config.label);
+                if let Some(font) = typography.font {
+                    label = label.font(font);
+                }
+                if let Some(size) = typography.size {
+                    label = label.size(size);
+                }
                 let mut b = button(label);
 
                 if let Some(padding) = self.style.padding {
                     b = b.padding(padding);
                 }
+                if let Some(style) = active_widget_style("button") {
+                    b = b.style(move |theme, status| {
+                        style.apply_button(button::primary(theme, status))
+                    });
+                }
 
                 if !config.disabled {
                     if let Some(callback_id) = config.on_click {
@@ -1874,12 +2346,29 @@ impl GuiElement {
 
             GuiElementKind::TextField(config) => {
                 let mut input = text_input(&config.placeholder, &config.value);
+                if let Some(number) = focus {
+                    input = input.id(accessibility::input_id(number));
+                }
 
                 // Apply secure mode for password fields
                 if config.secure {
                     input = input.secure(true);
                 }
 
+                // Apply the theme's font and text input style
+                let typography = active_typography();
+                if let Some(font) = typography.font {
+                    input = input.font(font);
+                }
+                if let Some(size) = typography.size {
+                    input = input.size(size);
+                }
+                if let Some(style) = active_widget_style("text_input") {
+                    input = input.style(move |theme, status| {
+                        style.apply_text_input(text_input::default(theme, status))
+                    });
+                }
+
                 // Handle text input changes
                 // Priority: field_path binding > on_change callback
                 if let Some(ref field) = config.field_path {
@@ -2137,42 +2626,61 @@ impl GuiElement {
             }
 
             GuiElementKind::Image(config) => {
-                if let Some(ref path) = config.path {
-                    let mut img = Image::new(path.as_str());
+                let handle = if let Some(ref handle) = config.handle {
+                    handle.clone()
+                } else if let Some(ref path) = config.path {
+                    if !image_loader::is_url(path) {
+                        image::Handle::from_path(path)
+                    } else {
+                        match image_loader::remote(path) {
+                            Some(RemoteImage::Ready(handle)) => handle,
+                            Some(RemoteImage::Failed(e)) => return text(e).size(12).into(),
+                            Some(RemoteImage::Loading) | None => {
+                                return self.image_placeholder(config);
+                            }
+                        }
+                    }
+                } else {
+                    // No source - render empty space
+                    return iced::widget::Space::new().into();
+                };
+                let mut img = Image::new(handle);
 
-                    // Apply content fit
-                    img = img.content_fit(config.content_fit.to_iced());
+                // Apply content fit
+                img = img.content_fit(config.content_fit.to_iced());
 
-                    // Apply opacity if not fully opaque
-                    if config.opacity < 1.0 {
-                        img = img.opacity(config.opacity);
-                    }
+                // Apply opacity if not fully opaque
+                if config.opacity < 1.0 {
+                    img = img.opacity(config.opacity);
+                }
+                if config.rotation != 0.0 {
+                    img = img.rotation(Rotation::Floating(Degrees(config.rotation).into()));
+                }
+                if let Some(radius) = self.style.widget_style.corner_radius {
+                    img = img.border_radius(radius);
+                }
 
-                    // Apply dimensions from config
-                    if let Some(w) = config.image_width {
-                        img = img.width(w);
-                    }
-                    if let Some(h) = config.image_height {
-                        img = img.height(h);
-                    }
+                // Apply dimensions from config
+                if let Some(w) = config.image_width {
+                    img = img.width(w);
+                }
+                if let Some(h) = config.image_height {
+                    img = img.height(h);
+                }
 
-                    // Apply dimensions from style (overrides config)
-                    if let Some(width) = self.style.width {
-                        img = img.width(width.to_iced());
-                    }
-                    if let Some(height) = self.style.height {
-                        img = img.height(height.to_iced());
-                    }
+                // Apply dimensions from style (overrides config)
+                if let Some(width) = self.style.width {
+                    img = img.width(width.to_iced());
+                }
+                if let Some(height) = self.style.height {
+                    img = img.height(height.to_iced());
+                }
 
-                    // Wrap in container if padding is needed
-                    if let Some(padding) = self.style.padding {
-                        container(img).padding(padding).into()
-                    } else {
-                        img.into()
-                    }
+                // Wrap in container if padding is needed
+                if let Some(padding) = self.style.padding {
+                    container(img).padding(padding).into()
                 } else {
-                    // No path - render empty space
-                    iced::widget::Space::new().into()
+                    img.into()
                 }
             }
 
@@ -2185,12 +2693,88 @@ impl GuiElement {
             // DataTable renders a table from DataFrame data
             GuiElementKind::DataTable(config) => self.render_data_table(config),
 
+            GuiElementKind::Tree(config) => self.render_tree(config),
+
+            GuiElementKind::Section(config) => self.render_section(config),
+
+            GuiElementKind::Canvas(config) => self.render_canvas(config),
+
+            GuiElementKind::MenuBar(config) => self.render_menu_bar(config),
+
+            GuiElementKind::ContextMenu(config) => {
+                let content: Element<'_, Message> = match self.children.first() {
+                    Some(child) => child.render(),
+                    None => iced::widget::Space::new().into(),
+                };
+                mouse_area(content)
+                    .on_right_press(Message::OpenContextMenu {
+                        items: context_menu_items(&config.items),
+                    })
+                    .into()
+            }
+
+            GuiElementKind::Draggable(config) => {
+                let content: Element<'_, Message> = match self.children.first() {
+                    Some(child) => child.render(),
+                    None => iced::widget::Space::new().into(),
+                };
+                mouse_area(content)
+                    .on_press(Message::DragStart {
+                        kind: config.kind.clone(),
+                        payload: config.payload,
+                    })
+                    .interaction(iced::mouse::Interaction::Grab)
+                    .into()
+            }
+
+            GuiElementKind::DropTarget(config) => {
+                let content: Element<'_, Message> = match self.children.first() {
+                    Some(child) => child.render(),
+                    None => iced::widget::Space::new().into(),
+                };
+                // Outline the target while something it takes is dragged
+                let content = if dragging_kind().is_some_and(|kind| accepts(&config.accepts, &kind))
+                {
+                    container(content).style(container::bordered_box).into()
+                } else {
+                    content
+                };
+                mouse_area(content)
+                    .on_release(Message::Drop {
+                        accepts: config.accepts.clone(),
+                        on_drop: config.on_drop,
+                    })
+                    .into()
+            }
+
+            GuiElementKind::FieldError(config) => match field_error(&config.field) {
+                Some(error) => text(error).size(13).style(text::danger).into(),
+                None => iced::widget::Space::new().into(),
+            },
+
+            GuiElementKind::Navigator(_) => match self.children.first() {
+                Some(page) => page.render(),
+                None => iced::widget::Space::new().into(),
+            },
+
+            GuiElementKind::Markdown(config) => markdown::view(&config.blocks, config.on_link),
+
             GuiElementKind::BarChart(config) => self.render_bar_chart(config),
 
             GuiElementKind::LineChart(config) => self.render_line_chart(config),
 
             GuiElementKind::PieChart(config) => self.render_pie_chart(config),
 
+            GuiElementKind::ScatterChart(config) => self.render_plot_chart(config),
+
+            GuiElementKind::AreaChart(config) => self.render_plot_chart(config),
+
+            GuiElementKind::StackedBarChart(config) => self.render_plot_chart(config),
+
+            GuiElementKind::Histogram(config) => self.render_plot_chart(config),
+
+            GuiElementKind::CandlestickChart(config) => self.render_plot_chart(config),
+
             // OLAP Cube widgets
             GuiElementKind::CubeTable(config) => self.render_cube_table(config),
 
@@ -2312,6 +2896,10 @@ impl GuiElement {
             return container(text("No data")).padding(20).into();
         };
 
+        if config.virtualized {
+            return self.render_virtual_table(config, df);
+        }
+
         // Determine which columns to display
         let columns_to_show: Vec<String> = if let Some(ref cols) = config.columns {
             cols.clone()
@@ -2593,6 +3181,438 @@ impl GuiElement {
         }
     }
 
+    /// Render a virtualized table: a fixed header over a scrollable body
+    /// holding only the rows in view, padded above and below to the height
+    /// of the rows out of view
+    #[allow(clippy::cast_precision_loss)]
+    fn render_virtual_table(
+        &self,
+        config: &DataTableConfig,
+        df: &Arc<DataFrame>,
+    ) -> Element<'_, Message> {
+        use iced::widget::{Column, Row, Space};
+
+        let id = config.view_id();
+        let columns = config.columns.clone().unwrap_or_else(|| df.columns());
+        if columns.is_empty() {
+            return container(text("No columns")).padding(20).into();
+        }
+        let total = df.num_rows();
+        let row_height = config.row_height;
+        let series: Vec<_> = columns.iter().map(|c| df.column(c).ok()).collect();
+
+        // Read the view state once: the rows in view, their text, and what
+        // the user sorted, selected and resized
+        let (visible, rows, selected, sort, dragged) = with_table_view(&id, |view| {
+            let selected = view
+                .selected
+                .get_or_insert_with(|| config.selected_rows.clone())
+                .clone();
+            let order = view.row_order(df);
+            let visible = view.visible(total, row_height);
+            let rows: Vec<(usize, Vec<String>)> = visible
+                .clone()
+                .map(|position| {
+                    let row = order.as_ref().map_or(position, |order| order[position]);
+                    let cells = columns
+                        .iter()
+                        .zip(&series)
+                        .map(|(column, series)| match view.formatted(row, column) {
+                            Some(formatted) => formatted.to_string(),
+                            None => series
+                                .as_ref()
+                                .and_then(|series| series.get(row).ok())
+                                .map(|value| format!("{value}"))
+                                .unwrap_or_default(),
+                        })
+                        .collect();
+                    (row, cells)
+                })
+                .collect();
+            (
+                visible,
+                rows,
+                selected,
+                view.sort.clone(),
+                view.widths.clone(),
+            )
+        });
+
+        // A sort callback means the program sorts the DataFrame itself and
+        // reports the sorted column back through `sort_column`
+        let sorted = if config.on_sort.is_some() {
+            config
+                .sort_column
+                .clone()
+                .map(|column| (column, config.sort_ascending))
+        } else {
+            sort
+        };
+
+        let widths: Vec<f32> = columns
+            .iter()
+            .map(|column| {
+                dragged
+                    .get(column)
+                    .copied()
+                    .or_else(|| {
+                        config
+                            .column_widths
+                            .iter()
+                            .find(|(c, _)| c == column)
+                            .map(|(_, w)| *w)
+                    })
+                    .unwrap_or(DEFAULT_COLUMN_WIDTH)
+            })
+            .collect();
+
+        // Header: sort buttons with a drag handle on their right edge
+        let header = Row::with_children(columns.iter().zip(&widths).map(|(column, &width)| {
+            let indicator = match &sorted {
+                Some((c, true)) if c == column => " ▲",
+                Some((c, false)) if c == column => " ▼",
+                _ => "",
+            };
+            let label = text(format!("{column}{indicator}")).font(Font {
+                weight: font::Weight::Bold,
+                ..Font::default()
+            });
+            let sort_message = match config.on_sort {
+                Some(callback_id) => Message::DataTableSort {
+                    callback_id,
+                    column: column.clone(),
+                },
+                None => Message::DataTableToggleSort {
+                    table: id.clone(),
+                    column: column.clone(),
+                },
+            };
+            let title: Element<'_, Message> = if config.sortable {
+                button(label)
+                    .on_press(sort_message)
+                    .style(button::text)
+                    .padding([4, 8])
+                    .width(Fill)
+                    .into()
+            } else {
+                container(label).padding([4, 8]).width(Fill).into()
+            };
+            let mut cell = Row::new().push(title);
+            if config.resizable {
+                cell = cell.push(
+                    mouse_area(container(Space::new()).width(6).height(Fill))
+                        .on_press(Message::DataTableResizeStart {
+                            table: id.clone(),
+                            column: column.clone(),
+                            width,
+                        })
+                        .interaction(iced::mouse::Interaction::ResizingHorizontally),
+                );
+            }
+            cell.width(width).height(row_height).into()
+        }));
+
+        // Body: the rows in view between spacers for those out of view
+        let mut body: Vec<Element<'_, Message>> = Vec::with_capacity(rows.len() + 2);
+        body.push(
+            Space::new()
+                .height(visible.start as f32 * row_height)
+                .into(),
+        );
+        for (row, cells) in rows {
+            let cells = Row::with_children(columns.iter().zip(&widths).zip(cells).map(
+                |((column, &width), value)| {
+                    let cell = container(text(value).wrapping(text::Wrapping::None))
+                        .padding([4, 8])
+                        .width(width)
+                        .height(row_height)
+                        .clip(true);
+                    match config.on_cell_click {
+                        Some(callback_id) => mouse_area(cell)
+                            .on_press(Message::DataTableCellClick {
+                                callback_id,
+                                row,
+                                column: column.clone(),
+                            })
+                            .into(),
+                        None => cell.into(),
+                    }
+                },
+            ));
+            let message = if config.selectable {
+                Some(Message::DataTableSelect {
+                    table: id.clone(),
+                    row,
+                    on_selection_change: config.on_selection_change,
+                    on_row_click: config.on_row_click,
+                })
+            } else {
+                config
+                    .on_row_click
+                    .map(|callback_id| Message::DataTableRowClick { callback_id, row })
+            };
+            let style: fn(&iced::Theme, button::Status) -> button::Style =
+                if selected.contains(&row) {
+                    button::primary
+                } else {
+                    button::text
+                };
+            body.push(
+                button(cells)
+                    .on_press_maybe(message)
+                    .style(style)
+                    .padding(0)
+                    .width(Fill)
+                    .into(),
+            );
+        }
+        body.push(
+            Space::new()
+                .height((total - visible.end) as f32 * row_height)
+                .into(),
+        );
+
+        let table_id = id.clone();
+        let body = scrollable(Column::with_children(body).width(Fill))
+            .on_scroll(move |viewport| Message::DataTableScroll {
+                table: table_id.clone(),
+                offset: viewport.absolute_offset().y,
+                height: viewport.bounds().height,
+            })
+            .height(Fill);
+
+        let status = if config.selectable {
+            format!("{total} rows, {} selected", selected.len())
+        } else {
+            format!("{total} rows")
+        };
+
+        let mut table = column![header, body, text(status).size(12)]
+            .spacing(2)
+            .height(
+                self.style
+                    .height
+                    .map_or(Length::Fixed(400.0), Size::to_iced),
+            );
+        if let Some(width) = self.style.width {
+            table = table.width(width.to_iced());
+        }
+        if let Some(padding) = self.style.padding {
+            table = table.padding(padding);
+        }
+        table.into()
+    }
+
+    /// Render a tree: a row per node in view, indented by depth, with an
+    /// arrow to expand branches
+    #[allow(clippy::cast_precision_loss)]
+    fn render_tree(&self, config: &TreeConfig) -> Element<'_, Message> {
+        use iced::widget::{Column, Space};
+
+        let id = config.view_id();
+        let (expanded, selected) = with_tree_view(&id, |view| {
+            view.seed(&config.expanded, config.selected.as_deref());
+            (
+                view.expanded.clone().unwrap_or_default(),
+                view.selected.clone(),
+            )
+        });
+
+        let rows = visible_nodes(&config.nodes, &expanded)
+            .into_iter()
+            .map(|(depth, node)| {
+                let arrow: Element<'_, Message> = if node.is_branch() {
+                    let is_expanded = expanded.contains(&node.id);
+                    button(text(if is_expanded { "▾" } else { "▸" }))
+                        .on_press(Message::TreeToggle {
+                            tree: id.clone(),
+                            node: node.id.clone(),
+                            field_path: config.field_path.clone(),
+                            on_expand: config.on_expand,
+                        })
+                        .style(button::text)
+                        .padding([0, 4])
+                        .width(20)
+                        .into()
+                } else {
+                    Space::new().width(20).into()
+                };
+
+                let label = match &node.icon {
+                    Some(icon) => format!("{icon} {}", node.label),
+                    None => node.label.clone(),
+                };
+                let style: fn(&iced::Theme, button::Status) -> button::Style =
+                    if selected.as_deref() == Some(node.id.as_str()) {
+                        button::primary
+                    } else {
+                        button::text
+                    };
+                let label = button(text(label))
+                    .on_press(Message::TreeSelect {
+                        tree: id.clone(),
+                        node: node.id.clone(),
+                        on_select: config.on_select,
+                    })
+                    .style(style)
+                    .padding([2, 6]);
+                let label: Element<'_, Message> = match config.on_context_menu {
+                    Some(callback_id) => mouse_area(label)
+                        .on_right_press(Message::TreeContextMenu {
+                            callback_id,
+                            node: node.id.clone(),
+                        })
+                        .into(),
+                    None => label.into(),
+                };
+
+                row![
+                    Space::new().width(depth as f32 * config.indent),
+                    arrow,
+                    label
+                ]
+                .align_y(iced::Alignment::Center)
+                .into()
+            });
+
+        let mut tree = Column::with_children(rows).spacing(1);
+        if let Some(padding) = self.style.padding {
+            tree = tree.padding(padding);
+        }
+        if let Some(width) = self.style.width {
+            tree = tree.width(width.to_iced());
+        }
+        if let Some(height) = self.style.height {
+            tree = tree.height(height.to_iced());
+        }
+        tree.into()
+    }
+
+    /// Render a canvas from the commands its draw callback last recorded
+    fn render_canvas(&self, config: &CanvasConfig) -> Element<'_, Message> {
+        let program = CanvasProgram {
+            commands: config.draw.map(canvas_frame).unwrap_or_default(),
+            on_event: config.on_event,
+        };
+
+        let width = self
+            .style
+            .width
+            .map(|s| s.to_iced())
+            .unwrap_or(Length::Fixed(config.width));
+        let height = self
+            .style
+            .height
+            .map(|s| s.to_iced())
+            .unwrap_or(Length::Fixed(config.height));
+
+        let drawing = canvas(program).width(width).height(height);
+
+        if let Some(padding) = self.style.padding {
+            container(drawing).padding(padding).into()
+        } else {
+            drawing.into()
+        }
+    }
+
+    /// Render the space an image takes while it downloads
+    fn image_placeholder(&self, config: &ImageConfig) -> Element<'_, Message> {
+        let width = self
+            .style
+            .width
+            .map(|s| s.to_iced())
+            .or(config.image_width.map(Length::Fixed))
+            .unwrap_or(Length::Shrink);
+        let height = self
+            .style
+            .height
+            .map(|s| s.to_iced())
+            .or(config.image_height.map(Length::Fixed))
+            .unwrap_or(Length::Shrink);
+        iced::widget::Space::new()
+            .width(width)
+            .height(height)
+            .into()
+    }
+
+    /// Render a menu bar: a dropdown per menu, listing submenu items
+    /// indented under their title
+    fn render_menu_bar(&self, config: &MenuBarConfig) -> Element<'_, Message> {
+        use iced::widget::Row;
+
+        let menus = config.menus.iter().map(|menu| -> Element<'_, Message> {
+            if menu.items.is_empty() {
+                let mut title = button(text(menu.label.clone()))
+                    .style(button::text)
+                    .padding([4, 10]);
+                if !menu.disabled {
+                    title = title.on_press(Message::MenuSelect {
+                        callback_id: menu.on_select,
+                    });
+                }
+                title.into()
+            } else {
+                pick_list(menu_entries(&menu.items), None::<MenuEntry>, |entry| {
+                    Message::MenuSelect {
+                        callback_id: entry.callback,
+                    }
+                })
+                .placeholder(menu.label.clone())
+                .handle(pick_list::Handle::None)
+                .padding([4, 10])
+                .into()
+            }
+        });
+
+        let mut bar = Row::with_children(menus)
+            .spacing(2)
+            .align_y(iced::Alignment::Center);
+        if let Some(padding) = self.style.padding {
+            bar = bar.padding(padding);
+        }
+        container(bar)
+            .width(self.style.width.map_or(Fill, |w| w.to_iced()))
+            .into()
+    }
+
+    /// Render a collapsible section: a header that toggles the children
+    fn render_section(&self, config: &SectionConfig) -> Element<'_, Message> {
+        use iced::widget::Column;
+
+        let id = config.view_id();
+        let expanded = section_expanded(&id, config.expanded);
+
+        let title = text(config.title.clone()).font(Font {
+            weight: font::Weight::Bold,
+            ..Font::default()
+        });
+        let header = button(row![text(if expanded { "▾" } else { "▸" }), title].spacing(6))
+            .on_press(Message::SectionToggle {
+                section: id,
+                expanded: !expanded,
+                field_path: config.field_path.clone(),
+                on_toggle: config.on_toggle,
+            })
+            .style(button::text)
+            .padding([6, 8])
+            .width(Fill);
+
+        let mut section = column![header].spacing(4);
+        if expanded {
+            let children = Column::with_children(self.children.iter().map(|c| c.render()))
+                .spacing(4)
+                .padding([4, 12]);
+            section = section.push(children);
+        }
+        if let Some(padding) = self.style.padding {
+            section = section.padding(padding);
+        }
+        if let Some(width) = self.style.width {
+            section = section.width(width.to_iced());
+        }
+        section.into()
+    }
+
     /// Render this element to an iced Element with state access
     ///
     /// This method is required for conditional and list rendering, which need
@@ -2891,6 +3911,35 @@ impl GuiElement {
         }
     }
 
+    /// Render one of the interactive charts using iced's canvas widget
+    fn render_plot_chart<C>(&self, config: &C) -> Element<'_, Message>
+    where
+        C: PlotChart + Clone + 'static,
+    {
+        let options = config.options();
+        let width = self
+            .style
+            .width
+            .map(|s| s.to_iced())
+            .unwrap_or(Length::Fixed(options.width));
+        let height = self
+            .style
+            .height
+            .map(|s| s.to_iced())
+            .unwrap_or(Length::Fixed(options.height));
+
+        let program = PlotProgram {
+            config: config.clone(),
+        };
+        let chart = canvas(program).width(width).height(height);
+
+        if let Some(padding) = self.style.padding {
+            container(chart).padding(padding).into()
+        } else {
+            chart.into()
+        }
+    }
+
     // =========================================================================
     // OLAP Cube Widget Rendering
     // =========================================================================
@@ -4060,7 +5109,145 @@ impl GuiElement {
     /// Convert this GuiElement into a Value for use in Stratum code
     #[must_use]
     pub fn into_value(self) -> Value {
-        Value::GuiElement(Arc::new(self))
+        Value::gui(self)
+    }
+
+    /// The element's accessible role: the one set, or the one its kind implies
+    #[must_use]
+    pub fn access_role(&self) -> Role {
+        if let Some(role) = self.style.access.role {
+            return role;
+        }
+        match &self.kind {
+            GuiElementKind::Text(_) | GuiElementKind::FieldError(_) => Role::Label,
+            GuiElementKind::Button(_) => Role::Button,
+            GuiElementKind::TextField(_) => Role::TextInput,
+            GuiElementKind::Checkbox(_) => Role::CheckBox,
+            GuiElementKind::RadioButton(_) => Role::RadioButton,
+            GuiElementKind::Dropdown(_) => Role::ComboBox,
+            GuiElementKind::Slider(_) => Role::Slider,
+            GuiElementKind::Toggle(_) => Role::Switch,
+            GuiElementKind::ProgressBar(_) => Role::ProgressIndicator,
+            GuiElementKind::Image(_) => Role::Image,
+            GuiElementKind::DataTable(_) | GuiElementKind::CubeTable(_) => Role::Table,
+            GuiElementKind::Tree(_) | GuiElementKind::HierarchyNavigator(_) => Role::Tree,
+            GuiElementKind::Section(_)
+            | GuiElementKind::Markdown(_)
+            | GuiElementKind::DimensionFilter(_)
+            | GuiElementKind::MeasureSelector(_) => Role::Group,
+            GuiElementKind::Canvas(_) => Role::Canvas,
+            GuiElementKind::MenuBar(_) => Role::MenuBar,
+            GuiElementKind::BarChart(_)
+            | GuiElementKind::LineChart(_)
+            | GuiElementKind::PieChart(_)
+            | GuiElementKind::ScatterChart(_)
+            | GuiElementKind::AreaChart(_)
+            | GuiElementKind::StackedBarChart(_)
+            | GuiElementKind::Histogram(_)
+            | GuiElementKind::CandlestickChart(_)
+            | GuiElementKind::CubeChart(_) => Role::Chart,
+            GuiElementKind::VStack(_)
+            | GuiElementKind::HStack(_)
+            | GuiElementKind::ZStack(_)
+            | GuiElementKind::Grid(_)
+            | GuiElementKind::ScrollView(_)
+            | GuiElementKind::Spacer(_)
+            | GuiElementKind::Container(_)
+            | GuiElementKind::Conditional(_)
+            | GuiElementKind::ForEach(_)
+            | GuiElementKind::ContextMenu(_)
+            | GuiElementKind::Draggable(_)
+            | GuiElementKind::DropTarget(_)
+            | GuiElementKind::Navigator(_)
+            | GuiElementKind::Interactive(_) => Role::Generic,
+        }
+    }
+
+    /// The element's accessible label: the one set, or its visible text
+    #[must_use]
+    pub fn access_label(&self) -> Option<String> {
+        if let Some(ref label) = self.style.access.label {
+            return Some(label.clone());
+        }
+        let text = match &self.kind {
+            GuiElementKind::Text(c) => &c.content,
+            GuiElementKind::Button(c) => &c.label,
+            GuiElementKind::TextField(c) => &c.placeholder,
+            GuiElementKind::Checkbox(c) => &c.label,
+            GuiElementKind::RadioButton(c) => &c.label,
+            GuiElementKind::Toggle(c) => &c.label,
+            GuiElementKind::Dropdown(c) => return c.placeholder.clone(),
+            GuiElementKind::Section(c) => &c.title,
+            _ => return None,
+        };
+        (!text.is_empty()).then(|| text.clone())
+    }
+
+    /// How the element takes keyboard focus, if it can
+    #[must_use]
+    pub fn focusable(&self) -> Option<Focusable> {
+        let focusable_kind = matches!(
+            self.kind,
+            GuiElementKind::Button(ButtonConfig {
+                disabled: false,
+                ..
+            }) | GuiElementKind::TextField(_)
+                | GuiElementKind::Checkbox(_)
+                | GuiElementKind::RadioButton(_)
+                | GuiElementKind::Dropdown(_)
+                | GuiElementKind::Slider(_)
+                | GuiElementKind::Toggle(_)
+        );
+        let tab_index = match self.style.access.tab_index {
+            Some(index) if index >= 0 => index,
+            None if focusable_kind => 0,
+            _ => return None,
+        };
+
+        let activate = match &self.kind {
+            GuiElementKind::Button(c) if !c.disabled => c.on_click.map(Message::InvokeCallback),
+            GuiElementKind::Checkbox(c) => match (&c.field_path, c.on_toggle) {
+                (Some(field), _) => Some(Message::SetBoolField {
+                    field: field.clone(),
+                    value: !c.checked,
+                }),
+                (None, Some(callback_id)) => Some(Message::CheckboxToggled {
+                    callback_id,
+                    checked: !c.checked,
+                }),
+                (None, None) => None,
+            },
+            GuiElementKind::Toggle(c) => match (&c.field_path, c.on_toggle) {
+                (Some(field), _) => Some(Message::SetBoolField {
+                    field: field.clone(),
+                    value: !c.is_on,
+                }),
+                (None, Some(callback_id)) => Some(Message::ToggleSwitched {
+                    callback_id,
+                    is_on: !c.is_on,
+                }),
+                (None, None) => None,
+            },
+            GuiElementKind::RadioButton(c) => match (&c.field_path, c.on_select) {
+                (Some(field), _) => Some(Message::SetStringField {
+                    field: field.clone(),
+                    value: c.value.clone(),
+                }),
+                (None, Some(callback_id)) => Some(Message::RadioButtonSelected {
+                    callback_id,
+                    value: c.value.clone(),
+                }),
+                (None, None) => None,
+            },
+            _ => None,
+        };
+
+        Some(Focusable {
+            tab_index,
+            label: self.access_label(),
+            activate,
+            text_input: matches!(self.kind, GuiElementKind::TextField(_)),
+        })
     }
 }
 
@@ -4087,9 +5274,24 @@ impl GuiValue for GuiElement {
             GuiElementKind::Conditional(_) => "Conditional",
             GuiElementKind::ForEach(_) => "ForEach",
             GuiElementKind::DataTable(_) => "DataTable",
+            GuiElementKind::Tree(_) => "Tree",
+            GuiElementKind::Section(_) => "Section",
+            GuiElementKind::Canvas(_) => "Canvas",
+            GuiElementKind::MenuBar(_) => "MenuBar",
+            GuiElementKind::ContextMenu(_) => "ContextMenu",
+            GuiElementKind::Draggable(_) => "Draggable",
+            GuiElementKind::DropTarget(_) => "DropTarget",
+            GuiElementKind::FieldError(_) => "FieldError",
+            GuiElementKind::Navigator(_) => "Navigator",
+            GuiElementKind::Markdown(_) => "Markdown",
             GuiElementKind::BarChart(_) => "BarChart",
             GuiElementKind::LineChart(_) => "LineChart",
             GuiElementKind::PieChart(_) => "PieChart",
+            GuiElementKind::ScatterChart(_) => "ScatterChart",
+            GuiElementKind::AreaChart(_) => "AreaChart",
+            GuiElementKind::StackedBarChart(_) => "StackedBarChart",
+            GuiElementKind::Histogram(_) => "Histogram",
+            GuiElementKind::CandlestickChart(_) => "CandlestickChart",
             GuiElementKind::CubeTable(_) => "CubeTable",
             GuiElementKind::CubeChart(_) => "CubeChart",
             GuiElementKind::DimensionFilter(_) => "DimensionFilter",
@@ -4174,6 +5376,41 @@ impl GuiElementBuilder {
         self
     }
 
+    /// Animate changes to the element's style
+    #[must_use]
+    pub fn transition(mut self, transition: Transition) -> Self {
+        self.style.transition = Some(transition);
+        self
+    }
+
+    /// Set the label screen readers announce
+    #[must_use]
+    pub fn access_label(mut self, label: impl Into<String>) -> Self {
+        self.style.access.label = Some(label.into());
+        self
+    }
+
+    /// Set the role screen readers announce
+    #[must_use]
+    pub fn access_role(mut self, role: Role) -> Self {
+        self.style.access.role = Some(role);
+        self
+    }
+
+    /// Set a description screen readers read after the label
+    #[must_use]
+    pub fn access_description(mut self, description: impl Into<String>) -> Self {
+        self.style.access.description = Some(description.into());
+        self
+    }
+
+    /// Set the element's place in the tab order; negative leaves it out
+    #[must_use]
+    pub fn tab_index(mut self, index: i32) -> Self {
+        self.style.access.tab_index = Some(index);
+        self
+    }
+
     /// Set spacing (for VStack, HStack, Grid)
     #[must_use]
     pub fn spacing(mut self, spacing: f32) -> Self {
@@ -4303,6 +5540,8 @@ impl GuiElementBuilder {
             GuiElementKind::Dropdown(c) => c.field_path = Some(path),
             GuiElementKind::Slider(c) => c.field_path = Some(path),
             GuiElementKind::Toggle(c) => c.field_path = Some(path),
+            GuiElementKind::Tree(c) => c.field_path = Some(path),
+            GuiElementKind::Section(c) => c.field_path = Some(path),
             _ => {}
         }
         self
@@ -4343,6 +5582,7 @@ impl GuiElementBuilder {
         match &mut self.kind {
             GuiElementKind::Checkbox(c) => c.on_toggle = Some(callback_id),
             GuiElementKind::Toggle(c) => c.on_toggle = Some(callback_id),
+            GuiElementKind::Section(c) => c.on_toggle = Some(callback_id),
             _ => {}
         }
         self
@@ -4373,6 +5613,7 @@ impl GuiElementBuilder {
             GuiElementKind::RadioButton(c) => c.on_select = Some(callback_id),
             GuiElementKind::Dropdown(c) => c.on_select = Some(callback_id),
             GuiElementKind::DimensionFilter(c) => c.on_select = Some(callback_id),
+            GuiElementKind::Tree(c) => c.on_select = Some(callback_id),
             _ => {}
         }
         self
@@ -4543,6 +5784,24 @@ impl GuiElementBuilder {
         self
     }
 
+    /// Show the pixels of an image value (for Image elements)
+    #[must_use]
+    pub fn image_handle(mut self, handle: image::Handle) -> Self {
+        if let GuiElementKind::Image(c) = &mut self.kind {
+            c.handle = Some(handle);
+        }
+        self
+    }
+
+    /// Set image rotation in degrees, clockwise (for Image elements)
+    #[must_use]
+    pub fn rotation(mut self, degrees: f32) -> Self {
+        if let GuiElementKind::Image(c) = &mut self.kind {
+            c.rotation = degrees;
+        }
+        self
+    }
+
     /// Set image dimensions (for Image elements)
     #[must_use]
     pub fn image_dimensions(mut self, width: f32, height: f32) -> Self {
@@ -4735,16 +5994,165 @@ impl GuiElementBuilder {
         self
     }
 
+    /// Set a formatter for a column's cells (for virtualized DataTable elements)
+    #[must_use]
+    pub fn cell_formatter(mut self, column: impl Into<String>, callback_id: CallbackId) -> Self {
+        if let GuiElementKind::DataTable(c) = &mut self.kind {
+            let column = column.into();
+            c.cell_formatters.retain(|(col, _)| *col != column);
+            c.cell_formatters.push((column, callback_id));
+        }
+        self
+    }
+
+    /// Set the key of the table's view state (for DataTable elements)
+    #[must_use]
+    pub fn table_id(mut self, id: impl Into<String>) -> Self {
+        if let GuiElementKind::DataTable(c) = &mut self.kind {
+            c.id = Some(id.into());
+        }
+        self
+    }
+
+    /// Set the row height in pixels (for virtualized DataTable elements)
+    #[must_use]
+    pub fn row_height(mut self, height: f32) -> Self {
+        if let GuiElementKind::DataTable(c) = &mut self.kind {
+            c.row_height = height;
+        }
+        self
+    }
+
+    /// Enable or disable resizing columns by dragging (for virtualized DataTable elements)
+    #[must_use]
+    pub fn resizable(mut self, resizable: bool) -> Self {
+        if let GuiElementKind::DataTable(c) = &mut self.kind {
+            c.resizable = resizable;
+        }
+        self
+    }
+
+    // ========== Tree and Section Builder Methods ==========
+
+    /// Set the key the expanded state is kept under (for Tree and Section elements)
+    #[must_use]
+    pub fn key(mut self, id: impl Into<String>) -> Self {
+        match &mut self.kind {
+            GuiElementKind::Tree(c) => c.id = Some(id.into()),
+            GuiElementKind::Section(c) => c.id = Some(id.into()),
+            _ => {}
+        }
+        self
+    }
+
+    /// Set the node ids expanded when first shown (for Tree elements)
+    #[must_use]
+    pub fn expanded_nodes(mut self, ids: Vec<String>) -> Self {
+        if let GuiElementKind::Tree(c) = &mut self.kind {
+            c.expanded = ids;
+        }
+        self
+    }
+
+    /// Set the node id selected when first shown (for Tree elements)
+    #[must_use]
+    pub fn selected_node(mut self, id: impl Into<String>) -> Self {
+        if let GuiElementKind::Tree(c) = &mut self.kind {
+            c.selected = Some(id.into());
+        }
+        self
+    }
+
+    /// Set the indentation per level in pixels (for Tree elements)
+    #[must_use]
+    pub fn indent(mut self, indent: f32) -> Self {
+        if let GuiElementKind::Tree(c) = &mut self.kind {
+            c.indent = indent;
+        }
+        self
+    }
+
+    /// Set callback for a node expanding, to load its children (for Tree elements)
+    #[must_use]
+    pub fn on_expand(mut self, callback_id: CallbackId) -> Self {
+        if let GuiElementKind::Tree(c) = &mut self.kind {
+            c.on_expand = Some(callback_id);
+        }
+        self
+    }
+
+    /// Set callback for a node being right-clicked (for Tree elements)
+    #[must_use]
+    pub fn on_context_menu(mut self, callback_id: CallbackId) -> Self {
+        if let GuiElementKind::Tree(c) = &mut self.kind {
+            c.on_context_menu = Some(callback_id);
+        }
+        self
+    }
+
+    /// Set whether the section is expanded when first shown (for Section elements)
+    #[must_use]
+    pub fn expanded(mut self, expanded: bool) -> Self {
+        if let GuiElementKind::Section(c) = &mut self.kind {
+            c.expanded = expanded;
+        }
+        self
+    }
+
+    // ========== Canvas Builder Methods ==========
+
+    /// Set the callback that draws the canvas (for Canvas elements)
+    #[must_use]
+    pub fn on_draw(mut self, callback_id: CallbackId) -> Self {
+        if let GuiElementKind::Canvas(c) = &mut self.kind {
+            c.draw = Some(callback_id);
+        }
+        self
+    }
+
+    /// Set callback for mouse and keyboard input (for Canvas elements)
+    #[must_use]
+    pub fn on_canvas_event(mut self, callback_id: CallbackId) -> Self {
+        if let GuiElementKind::Canvas(c) = &mut self.kind {
+            c.on_event = Some(callback_id);
+        }
+        self
+    }
+
+    // ========== Drag and Drop Builder Methods ==========
+
+    /// Set callback for a payload dropped on the element (for DropTarget elements)
+    #[must_use]
+    pub fn on_drop(mut self, callback_id: CallbackId) -> Self {
+        if let GuiElementKind::DropTarget(c) = &mut self.kind {
+            c.on_drop = Some(callback_id);
+        }
+        self
+    }
+
+    /// Set callback for a clicked link (for Markdown elements)
+    #[must_use]
+    pub fn on_link(mut self, callback_id: CallbackId) -> Self {
+        if let GuiElementKind::Markdown(c) = &mut self.kind {
+            c.on_link = Some(callback_id);
+        }
+        self
+    }
+
     // ========== Chart Builder Methods ==========
 
-    /// Set the chart title (for BarChart, LineChart, PieChart)
+    /// Set the chart title (for BarChart, LineChart, PieChart and the interactive charts)
     #[must_use]
     pub fn chart_title(mut self, title: impl Into<String>) -> Self {
         match &mut self.kind {
             GuiElementKind::BarChart(c) => c.title = Some(title.into()),
             GuiElementKind::LineChart(c) => c.title = Some(title.into()),
             GuiElementKind::PieChart(c) => c.title = Some(title.into()),
-            _ => {}
+            kind => {
+                if let Some(options) = kind.plot_options_mut() {
+                    options.title = Some(title.into());
+                }
+            }
         }
         self
     }
@@ -4760,7 +6168,7 @@ impl GuiElementBuilder {
         self
     }
 
-    /// Set chart size (for BarChart, LineChart, PieChart)
+    /// Set chart size (for BarChart, LineChart, PieChart and the interactive charts)
     #[must_use]
     pub fn chart_size(mut self, width: f32, height: f32) -> Self {
         match &mut self.kind {
@@ -4776,30 +6184,43 @@ impl GuiElementBuilder {
                 c.width = width;
                 c.height = height;
             }
-            _ => {}
+            kind => {
+                if let Some(options) = kind.plot_options_mut() {
+                    options.width = width;
+                    options.height = height;
+                }
+            }
         }
         self
     }
 
-    /// Show or hide legend (for BarChart, LineChart, PieChart)
+    /// Show or hide legend (for BarChart, LineChart, PieChart and the interactive charts)
     #[must_use]
     pub fn show_legend(mut self, show: bool) -> Self {
         match &mut self.kind {
             GuiElementKind::BarChart(c) => c.show_legend = show,
             GuiElementKind::LineChart(c) => c.show_legend = show,
             GuiElementKind::PieChart(c) => c.show_legend = show,
-            _ => {}
+            kind => {
+                if let Some(options) = kind.plot_options_mut() {
+                    options.show_legend = show;
+                }
+            }
         }
         self
     }
 
-    /// Show or hide grid lines (for BarChart, LineChart)
+    /// Show or hide grid lines (for BarChart, LineChart and the interactive charts)
     #[must_use]
     pub fn show_grid(mut self, show: bool) -> Self {
         match &mut self.kind {
             GuiElementKind::BarChart(c) => c.show_grid = show,
             GuiElementKind::LineChart(c) => c.show_grid = show,
-            _ => {}
+            kind => {
+                if let Some(options) = kind.plot_options_mut() {
+                    options.show_grid = show;
+                }
+            }
         }
         self
     }
@@ -4813,60 +6234,158 @@ impl GuiElementBuilder {
         self
     }
 
-    /// Set bar color (for BarChart)
+    /// Set bar color (for BarChart, Histogram)
     #[must_use]
     pub fn bar_color(mut self, r: u8, g: u8, b: u8) -> Self {
-        if let GuiElementKind::BarChart(c) = &mut self.kind {
-            c.bar_color = Some((r, g, b));
+        match &mut self.kind {
+            GuiElementKind::BarChart(c) => c.bar_color = Some((r, g, b)),
+            GuiElementKind::Histogram(c) => c.bar_color = Some((r, g, b)),
+            _ => {}
         }
         self
     }
 
-    /// Set x-axis label (for BarChart, LineChart)
+    /// Set x-axis label (for BarChart, LineChart and the interactive charts)
     #[must_use]
     pub fn x_label(mut self, label: impl Into<String>) -> Self {
         match &mut self.kind {
             GuiElementKind::BarChart(c) => c.x_label = Some(label.into()),
             GuiElementKind::LineChart(c) => c.x_label = Some(label.into()),
-            _ => {}
+            kind => {
+                if let Some(options) = kind.plot_options_mut() {
+                    options.x_label = Some(label.into());
+                }
+            }
         }
         self
     }
 
-    /// Set y-axis label (for BarChart, LineChart)
+    /// Set y-axis label (for BarChart, LineChart and the interactive charts)
     #[must_use]
     pub fn y_label(mut self, label: impl Into<String>) -> Self {
         match &mut self.kind {
             GuiElementKind::BarChart(c) => c.y_label = Some(label.into()),
             GuiElementKind::LineChart(c) => c.y_label = Some(label.into()),
-            _ => {}
+            kind => {
+                if let Some(options) = kind.plot_options_mut() {
+                    options.y_label = Some(label.into());
+                }
+            }
         }
         self
     }
 
-    /// Set x-axis labels (for LineChart)
+    /// Set x-axis labels (for LineChart, AreaChart, StackedBarChart)
     #[must_use]
     pub fn line_labels(mut self, labels: Vec<String>) -> Self {
-        if let GuiElementKind::LineChart(c) = &mut self.kind {
-            c.labels = labels;
+        match &mut self.kind {
+            GuiElementKind::LineChart(c) => c.labels = labels,
+            GuiElementKind::AreaChart(c) => c.labels = labels,
+            GuiElementKind::StackedBarChart(c) => c.labels = labels,
+            _ => {}
         }
         self
     }
 
-    /// Add a data series (for LineChart)
+    /// Add a data series (for LineChart, AreaChart, StackedBarChart)
     #[must_use]
     pub fn add_series(mut self, series: DataSeries) -> Self {
-        if let GuiElementKind::LineChart(c) = &mut self.kind {
-            c.series.push(series);
+        match &mut self.kind {
+            GuiElementKind::LineChart(c) => c.series.push(series),
+            GuiElementKind::AreaChart(c) => c.series.push(series),
+            GuiElementKind::StackedBarChart(c) => c.series.push(series),
+            _ => {}
         }
         self
     }
 
-    /// Set all data series (for LineChart)
+    /// Set all data series (for LineChart, AreaChart, StackedBarChart)
     #[must_use]
     pub fn line_series(mut self, series: Vec<DataSeries>) -> Self {
-        if let GuiElementKind::LineChart(c) = &mut self.kind {
-            c.series = series;
+        match &mut self.kind {
+            GuiElementKind::LineChart(c) => c.series = series,
+            GuiElementKind::AreaChart(c) => c.series = series,
+            GuiElementKind::StackedBarChart(c) => c.series = series,
+            _ => {}
+        }
+        self
+    }
+
+    /// Add a series of (x, y) points (for ScatterChart)
+    #[must_use]
+    pub fn add_scatter_series(mut self, series: ScatterSeries) -> Self {
+        if let GuiElementKind::ScatterChart(c) = &mut self.kind {
+            c.series.push(series);
+        }
+        self
+    }
+
+    /// Stack series on one another (for AreaChart)
+    #[must_use]
+    pub fn stacked(mut self, stacked: bool) -> Self {
+        if let GuiElementKind::AreaChart(c) = &mut self.kind {
+            c.stacked = stacked;
+        }
+        self
+    }
+
+    /// Set the number of bins (for Histogram)
+    #[must_use]
+    pub fn bins(mut self, bins: usize) -> Self {
+        if let GuiElementKind::Histogram(c) = &mut self.kind {
+            c.bins = bins.max(1);
+        }
+        self
+    }
+
+    /// Set the candles (for CandlestickChart)
+    #[must_use]
+    pub fn candles(mut self, candles: Vec<Candle>) -> Self {
+        if let GuiElementKind::CandlestickChart(c) = &mut self.kind {
+            c.candles = candles;
+        }
+        self
+    }
+
+    /// Show the values of the point under the cursor (for the interactive charts)
+    #[must_use]
+    pub fn tooltips(mut self, show: bool) -> Self {
+        if let Some(options) = self.kind.plot_options_mut() {
+            options.tooltips = show;
+        }
+        self
+    }
+
+    /// Zoom with the mouse wheel and pan by dragging (for the interactive charts)
+    #[must_use]
+    pub fn zoomable(mut self, zoom: bool) -> Self {
+        if let Some(options) = self.kind.plot_options_mut() {
+            options.zoom = zoom;
+        }
+        self
+    }
+
+    /// Hide and show series by clicking the legend (for the interactive charts)
+    #[must_use]
+    pub fn toggle_legend(mut self, toggle: bool) -> Self {
+        if let Some(options) = self.kind.plot_options_mut() {
+            options.toggle_legend = toggle;
+        }
+        self
+    }
+
+    /// Set the callback called with a chart's clicked point
+    #[must_use]
+    pub fn on_point_click(mut self, callback_id: CallbackId) -> Self {
+        match &mut self.kind {
+            GuiElementKind::BarChart(c) => c.on_bar_click = Some(callback_id),
+            GuiElementKind::LineChart(c) => c.on_point_click = Some(callback_id),
+            GuiElementKind::PieChart(c) => c.on_slice_click = Some(callback_id),
+            kind => {
+                if let Some(options) = kind.plot_options_mut() {
+                    options.on_point_click = Some(callback_id);
+                }
+            }
         }
         self
     }
@@ -4889,11 +6408,16 @@ impl GuiElementBuilder {
         self
     }
 
-    /// Set series colors (for LineChart)
+    /// Set series colors (for LineChart and the interactive charts)
     #[must_use]
     pub fn series_colors(mut self, colors: Vec<(u8, u8, u8)>) -> Self {
-        if let GuiElementKind::LineChart(c) = &mut self.kind {
-            c.series_colors = colors;
+        match &mut self.kind {
+            GuiElementKind::LineChart(c) => c.series_colors = colors,
+            kind => {
+                if let Some(options) = kind.plot_options_mut() {
+                    options.series_colors = colors;
+                }
+            }
         }
         self
     }
@@ -6074,6 +7598,54 @@ mod tests {
         assert_eq!(element.style.height, Some(Size::Fixed(400.0)));
     }
 
+    #[test]
+    fn test_interactive_chart_builders() {
+        let element = GuiElement::area_chart_with_data(
+            vec!["Q1".to_string(), "Q2".to_string()],
+            vec![DataSeries::new("North", vec![1.0, 2.0])],
+        )
+        .stacked(true)
+        .chart_title("Sales")
+        .chart_size(500.0, 250.0)
+        .zoomable(true)
+        .tooltips(false)
+        .on_point_click(CallbackId::new(7))
+        .build();
+
+        if let GuiElementKind::AreaChart(config) = &element.kind {
+            assert!(config.stacked);
+            assert_eq!(config.options.title.as_deref(), Some("Sales"));
+            assert!((config.options.width - 500.0).abs() < f32::EPSILON);
+            assert!(config.options.zoom);
+            assert!(!config.options.tooltips);
+            assert_eq!(config.options.on_point_click, Some(CallbackId::new(7)));
+        } else {
+            panic!("Expected AreaChart");
+        }
+        assert_eq!(element.access_role(), Role::Chart);
+
+        let element = GuiElement::histogram_with_data(vec![1.0, 2.0, 3.0], 0)
+            .bins(4)
+            .bar_color(10, 20, 30)
+            .build();
+        if let GuiElementKind::Histogram(config) = &element.kind {
+            assert_eq!(config.bins, 4);
+            assert_eq!(config.bar_color, Some((10, 20, 30)));
+        } else {
+            panic!("Expected Histogram");
+        }
+
+        // Bar, line and pie charts take click callbacks too
+        let element = GuiElement::pie_chart()
+            .on_point_click(CallbackId::new(3))
+            .build();
+        if let GuiElementKind::PieChart(config) = &element.kind {
+            assert_eq!(config.on_slice_click, Some(CallbackId::new(3)));
+        } else {
+            panic!("Expected PieChart");
+        }
+    }
+
     // OLAP Cube Widget Tests (5.7.7-5.7.11)
 
     #[test]
@@ -6429,4 +8001,99 @@ mod tests {
         assert_eq!(element.style.width, Some(Size::Fixed(200.0)));
         assert_eq!(element.style.height, Some(Size::Fixed(100.0)));
     }
+
+    #[test]
+    fn test_drag_and_drop_elements() {
+        let card = GuiElement::text("Card").build();
+        let draggable = GuiElement::draggable(card.clone(), "task", 3).build();
+        if let GuiElementKind::Draggable(config) = &draggable.kind {
+            assert_eq!(config.kind, "task");
+            assert_eq!(config.payload, 3);
+        } else {
+            panic!("Expected Draggable element");
+        }
+        assert_eq!(draggable.children.len(), 1);
+
+        let target = GuiElement::drop_target(card, vec!["task".to_string()])
+            .on_drop(CallbackId::new(7))
+            .build();
+        if let GuiElementKind::DropTarget(config) = &target.kind {
+            assert_eq!(config.accepts, ["task"]);
+            assert_eq!(config.on_drop, Some(CallbackId::new(7)));
+        } else {
+            panic!("Expected DropTarget element");
+        }
+    }
+
+    #[test]
+    fn test_markdown_element() {
+        let element = GuiElement::markdown("# Help\n\nSee [docs](docs.md).")
+            .on_link(CallbackId::new(4))
+            .build();
+        if let GuiElementKind::Markdown(config) = &element.kind {
+            assert_eq!(config.blocks.len(), 2);
+            assert_eq!(config.on_link, Some(CallbackId::new(4)));
+        } else {
+            panic!("Expected Markdown element");
+        }
+    }
+
+    #[test]
+    fn test_image_element() {
+        assert_eq!(
+            ImageContentFit::from_name("fit"),
+            Some(ImageContentFit::Contain)
+        );
+        assert_eq!(
+            ImageContentFit::from_name("fill"),
+            Some(ImageContentFit::Fill)
+        );
+        assert_eq!(ImageContentFit::from_name("stretch"), None);
+
+        let pixels = image::Handle::from_rgba(1, 1, vec![255, 0, 0, 255]);
+        let element = GuiElement::image("logo.png")
+            .image_handle(pixels)
+            .rotation(45.0)
+            .build();
+        if let GuiElementKind::Image(config) = &element.kind {
+            assert!(config.handle.is_some());
+            assert!((config.rotation - 45.0).abs() < f32::EPSILON);
+        } else {
+            panic!("Expected Image element");
+        }
+    }
+
+    #[test]
+    fn test_access_roles_and_labels() {
+        let button = GuiElement::button("Save").build();
+        assert_eq!(button.access_role(), Role::Button);
+        assert_eq!(button.access_label().as_deref(), Some("Save"));
+        assert!(button.focusable().is_some());
+
+        let icon = GuiElement::image("save.png")
+            .access_label("Save icon")
+            .access_role(Role::Button)
+            .tab_index(2)
+            .build();
+        assert_eq!(icon.access_role(), Role::Button);
+        assert_eq!(icon.access_label().as_deref(), Some("Save icon"));
+        assert_eq!(icon.focusable().map(|f| f.tab_index), Some(2));
+
+        let text = GuiElement::text("Hello").build();
+        assert_eq!(text.access_role(), Role::Label);
+        assert!(text.focusable().is_none());
+
+        let skipped = GuiElement::button("Skip").tab_index(-1).build();
+        assert!(skipped.focusable().is_none());
+
+        // Unlabelled layouts give way to their children
+        let root = GuiElement::vstack()
+            .child(GuiElement::hstack().child(button).child(text).build())
+            .child(GuiElement::text("Hidden").visible(false).build())
+            .build();
+        let tree = accessibility::access_tree(&root);
+        let roles: Vec<Role> = tree.children.iter().map(|n| n.role).collect();
+        assert_eq!(roles, [Role::Button, Role::Label]);
+        assert!(tree.children[0].focusable);
+    }
 }
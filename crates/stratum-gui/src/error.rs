@@ -28,6 +28,14 @@ pub enum GuiError {
     /// Iced backend error
     #[error("Iced error: {0}")]
     Iced(String),
+
+    /// System tray error
+    #[error("System tray error: {0}")]
+    Tray(String),
+
+    /// Reloading changed source failed
+    #[error("Hot reload failed: {0}")]
+    HotReload(String),
 }
 
 /// Result type alias for GUI operations
@@ -0,0 +1,484 @@
+//! Form validation and two-way bindings
+//!
+//! Inputs bound to a state field with `&state.field` write what the user
+//! enters back to the state, and show the field's current value each time
+//! the view function rebuilds the tree (see [`bind_values`]).
+//!
+//! `.validate(rules)` attaches rules to the field an input is bound to:
+//! `required`, a regex `pattern`, a numeric `min`/`max` range and
+//! `min_length`/`max_length`. The app re-checks every field after each
+//! change. A field's error is shown by `Gui.field_error(field)` once the user
+//! has changed that field, or once `Gui.form_valid()` has checked the whole
+//! form. Rules, errors and which fields were touched are kept here by field
+//! path, outliving the element tree like other view state.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+
+use regex::Regex;
+use stratum_core::bytecode::{HashableValue, Value};
+
+use crate::element::{GuiElement, GuiElementKind};
+use crate::state::ReactiveState;
+
+thread_local! {
+    static RULES: RefCell<HashMap<String, FieldRules>> = RefCell::new(HashMap::new());
+    static ERRORS: RefCell<BTreeMap<String, String>> = const { RefCell::new(BTreeMap::new()) };
+    static TOUCHED: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
+}
+
+/// A check a field's value must pass
+#[derive(Debug, Clone)]
+pub enum Rule {
+    /// Not null, empty text or an empty list
+    Required,
+    /// Text matching a regex
+    Pattern(Regex),
+    /// A number no less than this
+    Min(f64),
+    /// A number no greater than this
+    Max(f64),
+    /// Text of at least this many characters
+    MinLength(usize),
+    /// Text of at most this many characters
+    MaxLength(usize),
+}
+
+impl PartialEq for Rule {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Required, Self::Required) => true,
+            (Self::Pattern(a), Self::Pattern(b)) => a.as_str() == b.as_str(),
+            (Self::Min(a), Self::Min(b)) | (Self::Max(a), Self::Max(b)) => {
+                a.to_bits() == b.to_bits()
+            }
+            (Self::MinLength(a), Self::MinLength(b)) | (Self::MaxLength(a), Self::MaxLength(b)) => {
+                a == b
+            }
+            _ => false,
+        }
+    }
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::String(s) => s.trim().is_empty(),
+        Value::List(list) => list.borrow().is_empty(),
+        _ => false,
+    }
+}
+
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        #[allow(clippy::cast_precision_loss)]
+        Value::Int(i) => Some(*i as f64),
+        Value::Float(f) => Some(*f),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+impl Rule {
+    /// Why `value` fails the rule, or None if it passes. Rules other than
+    /// `Required` pass empty values, so optional fields can have them.
+    #[must_use]
+    pub fn check(&self, value: &Value) -> Option<String> {
+        if is_empty(value) {
+            return matches!(self, Self::Required).then(|| "This field is required".to_string());
+        }
+        let text = match value {
+            Value::String(s) => s.to_string(),
+            other => format!("{other}"),
+        };
+        match self {
+            Self::Required => None,
+            Self::Pattern(regex) => (!regex.is_match(&text))
+                .then(|| "This value is not in the expected format".to_string()),
+            Self::Min(min) => match as_number(value) {
+                Some(n) if n >= *min => None,
+                Some(_) => Some(format!("Must be at least {min}")),
+                None => Some("Must be a number".to_string()),
+            },
+            Self::Max(max) => match as_number(value) {
+                Some(n) if n <= *max => None,
+                Some(_) => Some(format!("Must be at most {max}")),
+                None => Some("Must be a number".to_string()),
+            },
+            Self::MinLength(len) => {
+                (text.chars().count() < *len).then(|| format!("Must be at least {len} characters"))
+            }
+            Self::MaxLength(len) => {
+                (text.chars().count() > *len).then(|| format!("Must be at most {len} characters"))
+            }
+        }
+    }
+}
+
+/// The rules of a field, with a message shown instead of theirs
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FieldRules {
+    pub rules: Vec<Rule>,
+    pub message: Option<String>,
+}
+
+impl FieldRules {
+    /// Rules from a Stratum map such as `{"required": true, "max": 120}`
+    /// with an optional `message`
+    pub fn from_value(value: &Value) -> Result<Self, String> {
+        let Value::Map(map) = value else {
+            return Err(format!(
+                "validation rules must be a map, got {}",
+                value.type_name()
+            ));
+        };
+        let map = map.borrow();
+        let get = |key: &str| map.get(&HashableValue::String(key.to_string().into()));
+        let number = |key: &str| -> Result<Option<f64>, String> {
+            match get(key) {
+                None | Some(Value::Null) => Ok(None),
+                Some(v) => as_number(v)
+                    .filter(|_| !matches!(v, Value::String(_)))
+                    .map(Some)
+                    .ok_or_else(|| format!("'{key}' must be a number, got {}", v.type_name())),
+            }
+        };
+        let length = |key: &str| -> Result<Option<usize>, String> {
+            match get(key) {
+                None | Some(Value::Null) => Ok(None),
+                Some(Value::Int(n)) if *n >= 0 => Ok(usize::try_from(*n).ok()),
+                Some(v) => Err(format!("'{key}' must be a non-negative integer, got {v}")),
+            }
+        };
+
+        let mut rules = Vec::new();
+        if matches!(get("required"), Some(Value::Bool(true))) {
+            rules.push(Rule::Required);
+        }
+        match get("pattern") {
+            None | Some(Value::Null) => {}
+            Some(Value::String(pattern)) => rules.push(Rule::Pattern(
+                Regex::new(pattern).map_err(|e| format!("invalid pattern: {e}"))?,
+            )),
+            Some(Value::Regex(regex)) => rules.push(Rule::Pattern(regex.as_ref().clone())),
+            Some(v) => return Err(format!("'pattern' must be a string, got {}", v.type_name())),
+        }
+        if let Some(min) = number("min")? {
+            rules.push(Rule::Min(min));
+        }
+        if let Some(max) = number("max")? {
+            rules.push(Rule::Max(max));
+        }
+        if let Some(len) = length("min_length")? {
+            rules.push(Rule::MinLength(len));
+        }
+        if let Some(len) = length("max_length")? {
+            rules.push(Rule::MaxLength(len));
+        }
+        let message = match get("message") {
+            Some(Value::String(s)) => Some(s.to_string()),
+            _ => None,
+        };
+
+        Ok(Self { rules, message })
+    }
+
+    /// The first error of `value`, if any
+    #[must_use]
+    pub fn check(&self, value: &Value) -> Option<String> {
+        let error = self.rules.iter().find_map(|rule| rule.check(value))?;
+        Some(self.message.clone().unwrap_or(error))
+    }
+}
+
+/// Attach rules to a field, replacing its earlier ones
+pub fn set_rules(field: &str, rules: FieldRules) {
+    RULES.with(|all| {
+        all.borrow_mut().insert(field.to_string(), rules);
+    });
+}
+
+/// Check every field with rules against the state
+pub fn revalidate(state: &ReactiveState) {
+    let errors: BTreeMap<String, String> = RULES.with(|all| {
+        all.borrow()
+            .iter()
+            .filter_map(|(field, rules)| {
+                let value = state.get_path(field).unwrap_or(Value::Null);
+                rules.check(&value).map(|error| (field.clone(), error))
+            })
+            .collect()
+    });
+    ERRORS.with(|all| *all.borrow_mut() = errors);
+}
+
+/// Note that the user changed a field, so its error can show
+pub fn touch(field: &str) {
+    TOUCHED.with(|touched| {
+        touched.borrow_mut().insert(field.to_string());
+    });
+}
+
+/// The error to show for a field: only once it was touched
+#[must_use]
+pub fn field_error(field: &str) -> Option<String> {
+    if !TOUCHED.with(|touched| touched.borrow().contains(field)) {
+        return None;
+    }
+    ERRORS.with(|all| all.borrow().get(field).cloned())
+}
+
+/// Whether every field passes its rules; shows all errors from then on
+#[must_use]
+pub fn form_valid() -> bool {
+    RULES.with(|all| {
+        TOUCHED.with(|touched| touched.borrow_mut().extend(all.borrow().keys().cloned()));
+    });
+    ERRORS.with(|all| all.borrow().is_empty())
+}
+
+/// Errors of all fields, shown or not
+#[must_use]
+pub fn form_errors() -> BTreeMap<String, String> {
+    ERRORS.with(|all| all.borrow().clone())
+}
+
+/// Hide errors again until fields are changed, e.g. after a form is reset
+pub fn reset_validation() {
+    TOUCHED.with(|touched| touched.borrow_mut().clear());
+}
+
+/// The tree with each bound input showing its field's value
+pub fn bind_values(element: &Arc<GuiElement>, state: &ReactiveState) -> Arc<GuiElement> {
+    let children: Vec<Arc<GuiElement>> = element
+        .children
+        .iter()
+        .map(|child| bind_values(child, state))
+        .collect();
+    let children_changed = children
+        .iter()
+        .zip(&element.children)
+        .any(|(a, b)| !Arc::ptr_eq(a, b));
+
+    if bound_field(&element.kind).is_none() && !children_changed {
+        return Arc::clone(element);
+    }
+    let mut bound = element.as_ref().clone();
+    bind_value(&mut bound.kind, state);
+    bound.children = children;
+    Arc::new(bound)
+}
+
+// Show the bound field's value in an input, if the field has a fitting value
+fn bind_value(kind: &mut GuiElementKind, state: &ReactiveState) {
+    let text = |value: Value| match value {
+        Value::String(s) => s.to_string(),
+        Value::Null => String::new(),
+        other => format!("{other}"),
+    };
+    match kind {
+        GuiElementKind::TextField(c) => {
+            let Some(value) = c.field_path.as_deref().and_then(|f| state.get_path(f)) else {
+                return;
+            };
+            c.value = text(value);
+        }
+        GuiElementKind::Checkbox(c) => {
+            let Some(Value::Bool(b)) = c.field_path.as_deref().and_then(|f| state.get_path(f))
+            else {
+                return;
+            };
+            c.checked = b;
+        }
+        GuiElementKind::Toggle(c) => {
+            let Some(Value::Bool(b)) = c.field_path.as_deref().and_then(|f| state.get_path(f))
+            else {
+                return;
+            };
+            c.is_on = b;
+        }
+        GuiElementKind::Slider(c) => {
+            let Some(n) = c
+                .field_path
+                .as_deref()
+                .and_then(|f| state.get_path(f))
+                .and_then(|v| as_number(&v))
+            else {
+                return;
+            };
+            c.value = n.clamp(c.min, c.max);
+        }
+        GuiElementKind::Dropdown(c) => {
+            let Some(value) = c.field_path.as_deref().and_then(|f| state.get_path(f)) else {
+                return;
+            };
+            c.selected = (!matches!(value, Value::Null)).then(|| text(value));
+        }
+        GuiElementKind::RadioButton(c) => {
+            let Some(value) = c.field_path.as_deref().and_then(|f| state.get_path(f)) else {
+                return;
+            };
+            c.selected_value = (!matches!(value, Value::Null)).then(|| text(value));
+        }
+        _ => {}
+    }
+}
+
+/// The field path an input is bound to
+#[must_use]
+pub fn bound_field(kind: &GuiElementKind) -> Option<&str> {
+    match kind {
+        GuiElementKind::TextField(c) => c.field_path.as_deref(),
+        GuiElementKind::Checkbox(c) => c.field_path.as_deref(),
+        GuiElementKind::RadioButton(c) => c.field_path.as_deref(),
+        GuiElementKind::Dropdown(c) => c.field_path.as_deref(),
+        GuiElementKind::Slider(c) => c.field_path.as_deref(),
+        GuiElementKind::Toggle(c) => c.field_path.as_deref(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use stratum_core::bytecode::StructInstance;
+
+    fn map(entries: Vec<(&str, Value)>) -> Value {
+        let map = Value::empty_map();
+        if let Value::Map(ref inner) = map {
+            for (key, value) in entries {
+                inner
+                    .borrow_mut()
+                    .insert(HashableValue::String(Rc::new(key.to_string())), value);
+            }
+        }
+        map
+    }
+
+    fn state(fields: Vec<(&str, Value)>) -> ReactiveState {
+        let mut instance = StructInstance::new("Form".to_string());
+        for (name, value) in fields {
+            instance.fields.insert(name.to_string(), value);
+        }
+        ReactiveState::new(Value::Struct(Rc::new(RefCell::new(instance))))
+    }
+
+    #[test]
+    fn test_rules_from_value() {
+        let rules = FieldRules::from_value(&map(vec![
+            ("required", Value::Bool(true)),
+            ("min", Value::Int(18)),
+            ("max_length", Value::Int(3)),
+        ]))
+        .unwrap();
+        assert_eq!(
+            rules.rules,
+            [Rule::Required, Rule::Min(18.0), Rule::MaxLength(3)]
+        );
+        assert!(FieldRules::from_value(&map(vec![("pattern", Value::string("("))])).is_err());
+        assert!(FieldRules::from_value(&map(vec![("min", Value::string("1"))])).is_err());
+        assert!(FieldRules::from_value(&Value::Int(1)).is_err());
+    }
+
+    #[test]
+    fn test_rule_checks() {
+        assert!(Rule::Required.check(&Value::string("  ")).is_some());
+        assert!(Rule::Required.check(&Value::Int(0)).is_none());
+        // Optional fields pass other rules while empty
+        assert!(Rule::Min(1.0).check(&Value::Null).is_none());
+
+        let email = Rule::Pattern(Regex::new(r"^[^@\s]+@[^@\s]+$").unwrap());
+        assert!(email.check(&Value::string("ada@example.com")).is_none());
+        assert!(email.check(&Value::string("ada")).is_some());
+
+        assert!(Rule::Min(18.0).check(&Value::string("21")).is_none());
+        assert_eq!(
+            Rule::Max(10.0).check(&Value::Int(11)).as_deref(),
+            Some("Must be at most 10")
+        );
+        assert_eq!(
+            Rule::Min(0.0).check(&Value::string("abc")).as_deref(),
+            Some("Must be a number")
+        );
+        assert!(Rule::MinLength(3).check(&Value::string("ab")).is_some());
+        assert!(Rule::MaxLength(3).check(&Value::string("abc")).is_none());
+    }
+
+    #[test]
+    fn test_errors_show_once_touched() {
+        let state = state(vec![("name", Value::string("")), ("age", Value::Int(12))]);
+        set_rules(
+            "name",
+            FieldRules {
+                rules: vec![Rule::Required],
+                message: Some("Enter your name".to_string()),
+            },
+        );
+        set_rules(
+            "age",
+            FieldRules {
+                rules: vec![Rule::Min(18.0)],
+                message: None,
+            },
+        );
+        revalidate(&state);
+        assert!(field_error("name").is_none());
+        assert_eq!(form_errors().len(), 2);
+
+        touch("age");
+        assert_eq!(field_error("age").as_deref(), Some("Must be at least 18"));
+        assert!(field_error("name").is_none());
+
+        assert!(!form_valid());
+        assert_eq!(field_error("name").as_deref(), Some("Enter your name"));
+
+        state.update_field("name", Value::string("Ada"));
+        state.update_field("age", Value::Int(30));
+        revalidate(&state);
+        assert!(form_valid());
+
+        reset_validation();
+        state.update_field("age", Value::Int(3));
+        revalidate(&state);
+        assert!(field_error("age").is_none());
+    }
+
+    #[test]
+    fn test_bind_values() {
+        let state = state(vec![
+            ("name", Value::string("Ada")),
+            ("subscribed", Value::Bool(true)),
+            ("volume", Value::Int(7)),
+        ]);
+        let root = Arc::new(
+            GuiElement::vstack()
+                .child(GuiElement::text_field().bind_field("name").build())
+                .child(
+                    GuiElement::checkbox("Subscribe")
+                        .bind_field("subscribed")
+                        .build(),
+                )
+                .child(GuiElement::slider(0.0, 10.0).bind_field("volume").build())
+                .child(GuiElement::text("static").build())
+                .build(),
+        );
+        let bound = bind_values(&root, &state);
+
+        let GuiElementKind::TextField(ref field) = bound.children[0].kind else {
+            panic!("Expected TextField element");
+        };
+        assert_eq!(field.value, "Ada");
+        let GuiElementKind::Checkbox(ref checkbox) = bound.children[1].kind else {
+            panic!("Expected Checkbox element");
+        };
+        assert!(checkbox.checked);
+        let GuiElementKind::Slider(ref slider) = bound.children[2].kind else {
+            panic!("Expected Slider element");
+        };
+        assert!((slider.value - 7.0).abs() < f64::EPSILON);
+        // Elements without bindings are shared, not copied
+        assert!(Arc::ptr_eq(&bound.children[3], &root.children[3]));
+    }
+}
@@ -0,0 +1,227 @@
+//! Hot reload of view code
+//!
+//! With `stratum run --hot-reload app.strat`, the app watches its source
+//! file. When the file changes, the runtime compiles it again and runs it
+//! in the callback VM, so functions are defined anew. Running it reaches
+//! `Gui.app()` again, which during a reload hands its view function back
+//! here instead of opening another window; the runtime swaps that view in
+//! and rebuilds the window from the state it already has. Callbacks are
+//! closures made by the view, so they come from the new code too.
+//!
+//! Top-level code other than `Gui.app()` runs again on each reload. Timers,
+//! animations and tasks started before a reload keep the closures they were
+//! given.
+
+use std::cell::{Cell, RefCell};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use stratum_core::bytecode::Value;
+use stratum_core::{Compiler, Parser, TypeChecker, VM};
+
+use crate::error::{GuiError, GuiResult};
+
+/// How often the source file is checked for changes
+pub const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+thread_local! {
+    static SOURCE: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+    static RELOADING: Cell<bool> = const { Cell::new(false) };
+    static RELOADED_VIEW: RefCell<Option<Value>> = const { RefCell::new(None) };
+}
+
+/// Watch `path` and reload it into the next app started with `Gui.app()`
+pub fn enable(path: impl Into<PathBuf>) {
+    SOURCE.with(|source| *source.borrow_mut() = Some(path.into()));
+}
+
+/// The source file to watch, if hot reload is on
+#[must_use]
+pub fn source() -> Option<PathBuf> {
+    SOURCE.with(|source| source.borrow().clone())
+}
+
+/// Whether the source file is being run again for a reload
+#[must_use]
+pub fn is_reloading() -> bool {
+    RELOADING.with(Cell::get)
+}
+
+/// Keep the view function `Gui.app()` was called with during a reload
+pub fn capture_view(view: Value) {
+    RELOADED_VIEW.with(|reloaded| *reloaded.borrow_mut() = Some(view));
+}
+
+/// Notices when a file changes, by its modification time
+#[derive(Debug, Clone)]
+pub struct Watcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl Watcher {
+    /// Watch a file, taking its current contents as seen
+    #[must_use]
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let modified = modified(&path);
+        Self { path, modified }
+    }
+
+    /// The file watched
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the file changed since the last call. A file that can't be
+    /// read, as while an editor replaces it, hasn't changed yet.
+    pub fn poll(&mut self) -> bool {
+        match modified(&self.path) {
+            Some(time) if Some(time) != self.modified => {
+                self.modified = Some(time);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Compile the source file again and run it in `vm`, returning the view
+/// function it passes to `Gui.app()`. The app keeps its old view if this
+/// fails.
+pub fn reload(vm: &mut VM, path: &Path) -> GuiResult<Value> {
+    let fail = |what: &str, errors: Vec<String>| {
+        GuiError::HotReload(format!(
+            "{what} in {}:\n  {}",
+            path.display(),
+            errors.join("\n  ")
+        ))
+    };
+
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| GuiError::HotReload(format!("failed to read {}: {e}", path.display())))?;
+    let module = Parser::parse_module(&source).map_err(|errors| {
+        fail(
+            "parse errors",
+            errors.iter().map(ToString::to_string).collect(),
+        )
+    })?;
+    let checked = TypeChecker::new().check_module(&module);
+    if !checked.errors.is_empty() {
+        let errors = checked.errors.iter().map(ToString::to_string).collect();
+        return Err(fail("type errors", errors));
+    }
+    let function = Compiler::with_source(path.display().to_string())
+        .compile_module(&module)
+        .map_err(|errors| {
+            fail(
+                "compile errors",
+                errors.iter().map(ToString::to_string).collect(),
+            )
+        })?;
+
+    RELOADED_VIEW.with(|reloaded| reloaded.borrow_mut().take());
+    RELOADING.with(|r| r.set(true));
+    let result = run_module(vm, function);
+    RELOADING.with(|r| r.set(false));
+    result?;
+
+    RELOADED_VIEW
+        .with(|reloaded| reloaded.borrow_mut().take())
+        .ok_or_else(|| GuiError::HotReload(format!("{} no longer calls Gui.app()", path.display())))
+}
+
+/// Run the module, then its `main()` if it has one, as `stratum run` does
+fn run_module(
+    vm: &mut VM,
+    function: std::rc::Rc<stratum_core::bytecode::Function>,
+) -> GuiResult<()> {
+    let runtime_error = |e: stratum_core::vm::RuntimeError| GuiError::HotReload(e.to_string());
+
+    vm.run(function).map_err(runtime_error)?;
+    if vm.globals().contains_key("main") {
+        let call = Parser::parse_expression("main()")
+            .ok()
+            .and_then(|call| Compiler::new().compile_expression(&call).ok())
+            .ok_or_else(|| GuiError::HotReload("failed to compile the call to main()".into()))?;
+        vm.run(call).map_err(runtime_error)?;
+    }
+    Ok(())
+}
+
+/// Stop watching and forget any reload in progress
+pub fn reset() {
+    SOURCE.with(|source| source.borrow_mut().take());
+    RELOADING.with(|r| r.set(false));
+    RELOADED_VIEW.with(|reloaded| reloaded.borrow_mut().take());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_file(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("stratum-hot-reload-{name}.strat"));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_watcher_notices_changes() {
+        let path = temp_file("watch", "let a = 1");
+        let mut watcher = Watcher::new(&path);
+        assert!(!watcher.poll());
+
+        // Make sure the new modification time differs on coarse filesystems
+        let later = SystemTime::now() + Duration::from_secs(5);
+        std::fs::write(&path, "let a = 2").unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        assert!(watcher.poll());
+        assert!(!watcher.poll());
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(!watcher.poll());
+    }
+
+    #[test]
+    fn test_reload_errors_keep_old_view() {
+        let mut vm = VM::new();
+        let path = temp_file("broken", "fn view(state) {");
+        let err = reload(&mut vm, &path).unwrap_err();
+        assert!(err.to_string().contains("parse errors"));
+        assert!(!is_reloading());
+
+        // Valid code that never calls Gui.app() has no view to swap in
+        std::fs::write(&path, "fn view(state) { state }").unwrap();
+        let err = reload(&mut vm, &path).unwrap_err();
+        assert!(err.to_string().contains("no longer calls Gui.app()"));
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(reload(&mut vm, Path::new("/nonexistent/app.strat")).is_err());
+    }
+
+    #[test]
+    fn test_enable_and_capture() {
+        reset();
+        assert!(source().is_none());
+        enable("app.strat");
+        assert_eq!(source(), Some(PathBuf::from("app.strat")));
+        capture_view(Value::Int(1));
+        assert_eq!(
+            RELOADED_VIEW.with(|reloaded| reloaded.borrow_mut().take()),
+            Some(Value::Int(1))
+        );
+        reset();
+        assert!(source().is_none());
+    }
+}
@@ -0,0 +1,126 @@
+//! Image sources for `Gui.image`
+//!
+//! An image element shows a local file, a URL or an image value made with
+//! the `Image` namespace. Local files are handed to iced as paths. URLs are
+//! downloaded on a worker thread the first time an element shows them; the
+//! element shows a placeholder until the download finishes, and the bytes are
+//! kept by URL for the rest of the run. Image values are converted to RGBA
+//! pixels when the element is built, so processed images show as they are
+//! without being saved first.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use iced::futures::channel::oneshot;
+use iced::widget::image::Handle;
+use stratum_core::bytecode::ImageWrapper;
+
+thread_local! {
+    static REMOTE: RefCell<HashMap<String, RemoteImage>> = RefCell::new(HashMap::new());
+}
+
+/// Download state of an image URL
+#[derive(Debug, Clone)]
+pub enum RemoteImage {
+    /// Being downloaded
+    Loading,
+    /// Downloaded and ready to show
+    Ready(Handle),
+    /// The download failed, with the reason
+    Failed(String),
+}
+
+/// Whether an image source is a URL rather than a local path
+#[must_use]
+pub fn is_url(source: &str) -> bool {
+    source.starts_with("http://") || source.starts_with("https://")
+}
+
+/// The download state of a URL, if it has been asked for
+#[must_use]
+pub fn remote(url: &str) -> Option<RemoteImage> {
+    REMOTE.with(|remote| remote.borrow().get(url).cloned())
+}
+
+/// Mark a URL as loading; false if it is already loading or loaded
+pub fn start_loading(url: &str) -> bool {
+    REMOTE.with(|remote| {
+        let mut remote = remote.borrow_mut();
+        if remote.contains_key(url) {
+            return false;
+        }
+        remote.insert(url.to_string(), RemoteImage::Loading);
+        true
+    })
+}
+
+/// Record the result of downloading a URL
+pub fn finish_loading(url: &str, result: Result<Handle, String>) {
+    let state = match result {
+        Ok(handle) => RemoteImage::Ready(handle),
+        Err(e) => RemoteImage::Failed(e),
+    };
+    REMOTE.with(|remote| remote.borrow_mut().insert(url.to_string(), state));
+}
+
+/// Forget all downloaded images
+pub fn reset() {
+    REMOTE.with(|remote| remote.borrow_mut().clear());
+}
+
+/// Download an image on a worker thread
+pub async fn fetch(url: String) -> Result<Handle, String> {
+    let (sender, receiver) = oneshot::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(download(&url));
+    });
+    receiver
+        .await
+        .unwrap_or_else(|_| Err("image download stopped".to_string()))
+}
+
+fn download(url: &str) -> Result<Handle, String> {
+    let response = reqwest::blocking::get(url)
+        .and_then(reqwest::blocking::Response::error_for_status)
+        .map_err(|e| format!("failed to load image {url}: {e}"))?;
+    let bytes = response
+        .bytes()
+        .map_err(|e| format!("failed to load image {url}: {e}"))?;
+    Ok(Handle::from_bytes(bytes.to_vec()))
+}
+
+/// Pixels of an image value, ready to show
+#[must_use]
+pub fn handle_from_image(image: &ImageWrapper) -> Handle {
+    let rgba = image.image.to_rgba8();
+    let (width, height) = rgba.dimensions();
+    Handle::from_rgba(width, height, rgba.into_raw())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_url() {
+        assert!(is_url("https://example.com/logo.png"));
+        assert!(is_url("http://localhost:8080/a.jpg"));
+        assert!(!is_url("assets/logo.png"));
+        assert!(!is_url("/tmp/https.png"));
+    }
+
+    #[test]
+    fn test_loading_states() {
+        reset();
+        let url = "https://example.com/a.png";
+        assert!(remote(url).is_none());
+        assert!(start_loading(url));
+        assert!(!start_loading(url));
+        assert!(matches!(remote(url), Some(RemoteImage::Loading)));
+
+        finish_loading(url, Err("404".to_string()));
+        assert!(matches!(remote(url), Some(RemoteImage::Failed(e)) if e == "404"));
+        assert!(!start_loading(url));
+        reset();
+    }
+}
@@ -66,9 +66,60 @@ pub mod element;
 /// Native functions for GUI element creation
 pub mod natives;
 
-/// Chart widgets (BarChart, LineChart, PieChart)
+/// Chart widgets: bar, line, pie, scatter, area, stacked bar, histogram and candlestick
 pub mod charts;
 
+/// Canvas widget drawn from Stratum callbacks
+pub mod canvas;
+
+/// Native file, folder and message dialogs
+pub mod dialogs;
+
+/// Menu bars, context menus and keyboard shortcuts
+pub mod menu;
+
+/// System tray icon
+pub mod tray;
+
+/// System clipboard access
+pub mod clipboard;
+
+/// Animated state fields and element transitions
+pub mod animation;
+
+/// Form validation and two-way input bindings
+pub mod form;
+
+/// Drag and drop between widgets and file drops on the window
+pub mod dnd;
+
+/// Page navigation for multi-page apps
+pub mod navigation;
+
+/// Markdown display
+pub mod markdown;
+
+/// Timers and background tasks
+pub mod timer;
+
+/// Image sources: paths, URLs and image values
+pub mod image_loader;
+
+/// Accessible labels and roles, and keyboard focus
+pub mod accessibility;
+
+/// Handing the accessibility tree to screen readers
+pub mod screen_reader;
+
+/// Reloading view code when the source file changes
+pub mod hot_reload;
+
+/// View state of virtualized tables
+pub mod table;
+
+/// Tree views and collapsible sections
+pub mod tree;
+
 /// Theming and styling system
 pub mod theme;
 
@@ -76,40 +127,66 @@ pub mod theme;
 pub mod bindings;
 
 // Re-exports for convenience
+pub use accessibility::{Access, AccessNode, Role};
+pub use animation::{Animation, Easing, Transition};
 pub use bindings::register_gui;
 pub use callback::{Callback, CallbackExecutor, CallbackId, CallbackRegistry};
+pub use canvas::{CanvasContext, CanvasEvent, DrawCommand};
 pub use charts::{
-    BarChartConfig, DataPoint, DataSeries, LineChartConfig, PieChartConfig, CHART_COLORS,
+    AreaChartConfig, BarChartConfig, Candle, CandlestickChartConfig, ChartPoint, DataPoint,
+    DataSeries, HistogramConfig, LineChartConfig, PieChartConfig, PlotOptions, ScatterChartConfig,
+    ScatterSeries, StackedBarChartConfig, CHART_COLORS,
 };
+pub use dialogs::FileFilter;
+pub use dnd::{DragItem, FileDropEvent};
 pub use element::{
+    CanvasConfig,
     ConditionalConfig,
+    ContextMenuConfig,
     CubeChartConfig,
     CubeChartType,
     // OLAP Cube widget configs
     CubeTableConfig,
     DimensionFilterConfig,
+    DraggableConfig,
+    DropTargetConfig,
+    FieldErrorConfig,
     ForEachConfig,
     GuiElement,
     GuiElementKind,
     HierarchyNavigatorConfig,
+    MarkdownConfig,
     MeasureSelectorConfig,
+    MenuBarConfig,
+    NavigatorConfig,
+    SectionConfig,
+    TreeConfig,
 };
 pub use error::{GuiError, GuiResult};
+pub use form::{FieldRules, Rule};
+pub use hot_reload::enable as enable_hot_reload;
 pub use layout::{
     Container, Grid, HAlign, HStack, LayoutProps, ScrollDirection, ScrollView, Size, Spacer,
     VAlign, VStack, ZStack,
 };
 pub use lifecycle::{LifecycleBuilder, LifecycleHooks, LifecycleManager, LifecyclePhase};
+pub use menu::{Accelerator, MenuItem};
 pub use modal::{Modal, ModalConfig, ModalManager, ModalMessage, ModalResult};
 pub use natives::gui_native_functions;
+pub use navigation::{Page, Route};
 pub use runtime::{AppConfig, AppTheme, Backend, GuiRuntime, Message};
 pub use state::{
     ComputedProperty, ComputedPropertyAccess, FieldBinding, ReactiveState, StateSubscription,
 };
+pub use table::{rows_to_dataframe, TableView};
 pub use theme::{Color, Shadow, StratumPalette, StratumTheme, ThemePreset, WidgetStyle};
+pub use timer::{BackgroundTask, Timer};
+pub use tray::{Tray, TrayConfig};
+pub use tree::{TreeNode, TreeView};
 pub use widgets::{
     get_binding_path, is_state_binding, resolve_binding, LayoutConfig, ResolvedBinding, TextStyle,
 };
 pub use window::{
-    Position, WindowEvent, WindowId, WindowLevel, WindowManager, WindowSettings, WindowState,
+    Position, WindowEvent, WindowHandler, WindowId, WindowLevel, WindowManager, WindowSettings,
+    WindowState,
 };
@@ -0,0 +1,685 @@
+//! Markdown display
+//!
+//! `Gui.markdown(text)` shows headings, paragraphs with bold, italic, code
+//! and links, bulleted and numbered lists, block quotes, rules, images and
+//! fenced code blocks. Code blocks in Stratum, or with no language given,
+//! are highlighted with the Stratum lexer. The text is parsed once, when
+//! the element is built; links call the element's `on_link` callback with
+//! their URL.
+//!
+//! The parser covers the common subset of CommonMark used for help text,
+//! not every corner of the spec: nested block quotes, tables and HTML are
+//! shown as plain text.
+
+use iced::widget::{column, container, rich_text, row, span, text, Image, Space};
+use iced::{font, Color, Element, Fill, Font, Theme};
+use stratum_core::lexer::{Lexer, TokenKind};
+
+use crate::callback::CallbackId;
+use crate::runtime::Message;
+
+/// Spaces of indentation per list level
+const LIST_INDENT: usize = 2;
+
+/// A run of text with one style
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Run {
+    pub text: String,
+    pub strong: bool,
+    pub emphasis: bool,
+    pub code: bool,
+    /// URL the run links to
+    pub link: Option<String>,
+}
+
+/// The marker of a list item
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListMarker {
+    Bullet,
+    Number(u64),
+}
+
+/// A block of a markdown document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block {
+    Heading {
+        /// 1 to 6
+        level: u8,
+        runs: Vec<Run>,
+    },
+    Paragraph(Vec<Run>),
+    ListItem {
+        /// Nesting depth, 0 for a top-level item
+        level: usize,
+        marker: ListMarker,
+        runs: Vec<Run>,
+    },
+    Quote(Vec<Run>),
+    CodeBlock {
+        language: Option<String>,
+        code: String,
+    },
+    Image {
+        alt: String,
+        url: String,
+    },
+    Rule,
+}
+
+/// Parse a markdown document into blocks
+#[must_use]
+pub fn parse(source: &str) -> Vec<Block> {
+    let mut parser = Parser::default();
+    let mut lines = source.lines();
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim_start();
+        let indent = indent_of(line);
+
+        if let Some(fence) = fence_of(trimmed) {
+            parser.flush();
+            let language = trimmed[fence.len()..].trim();
+            let mut code = Vec::new();
+            for line in lines.by_ref() {
+                if line.trim_start().starts_with(fence) {
+                    break;
+                }
+                code.push(line);
+            }
+            parser.blocks.push(Block::CodeBlock {
+                language: (!language.is_empty()).then(|| language.to_string()),
+                code: code.join("\n"),
+            });
+            continue;
+        }
+
+        if trimmed.is_empty() {
+            parser.flush();
+            continue;
+        }
+        if let Some((level, heading)) = heading_of(trimmed) {
+            parser.flush();
+            parser.blocks.push(Block::Heading {
+                level,
+                runs: parse_inline(heading),
+            });
+            continue;
+        }
+        if is_rule(trimmed) {
+            parser.flush();
+            parser.blocks.push(Block::Rule);
+            continue;
+        }
+        if let Some(quoted) = trimmed.strip_prefix('>') {
+            if parser.quote.is_empty() {
+                parser.flush();
+            }
+            parser.quote.push(quoted.trim_start().to_string());
+            continue;
+        }
+        if let Some((marker, item)) = list_item_of(trimmed) {
+            parser.flush();
+            parser.item = Some((indent / LIST_INDENT, marker, item.to_string()));
+            continue;
+        }
+
+        // Lazy continuation of the open quote, list item or paragraph
+        if let Some(quoted) = parser.quote.last_mut() {
+            quoted.push(' ');
+            quoted.push_str(trimmed);
+        } else if let Some((_, _, item)) = parser.item.as_mut() {
+            item.push(' ');
+            item.push_str(trimmed);
+        } else {
+            parser.paragraph.push(trimmed.to_string());
+        }
+    }
+
+    parser.flush();
+    parser.blocks
+}
+
+#[derive(Default)]
+struct Parser {
+    blocks: Vec<Block>,
+    paragraph: Vec<String>,
+    quote: Vec<String>,
+    item: Option<(usize, ListMarker, String)>,
+}
+
+impl Parser {
+    /// End the open paragraph, quote or list item
+    fn flush(&mut self) {
+        if !self.paragraph.is_empty() {
+            let paragraph = std::mem::take(&mut self.paragraph).join(" ");
+            let block = match image_of(&paragraph) {
+                Some((alt, url)) => Block::Image {
+                    alt: alt.to_string(),
+                    url: url.to_string(),
+                },
+                None => Block::Paragraph(parse_inline(&paragraph)),
+            };
+            self.blocks.push(block);
+        }
+        if !self.quote.is_empty() {
+            let quote = std::mem::take(&mut self.quote).join(" ");
+            self.blocks.push(Block::Quote(parse_inline(&quote)));
+        }
+        if let Some((level, marker, item)) = self.item.take() {
+            self.blocks.push(Block::ListItem {
+                level,
+                marker,
+                runs: parse_inline(&item),
+            });
+        }
+    }
+}
+
+/// Columns of indentation, counting a tab as four
+fn indent_of(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum()
+}
+
+fn fence_of(line: &str) -> Option<&'static str> {
+    if line.starts_with("```") {
+        Some("```")
+    } else if line.starts_with("~~~") {
+        Some("~~~")
+    } else {
+        None
+    }
+}
+
+fn heading_of(line: &str) -> Option<(u8, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let rest = &line[level..];
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with(' ')) {
+        return None;
+    }
+    // Closing hashes are decoration
+    let heading = rest.trim().trim_end_matches('#').trim_end();
+    Some((u8::try_from(level).unwrap_or(6), heading))
+}
+
+fn is_rule(line: &str) -> bool {
+    let marks: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+    marks.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|&m| marks.iter().all(|&c| c == m))
+}
+
+fn list_item_of(line: &str) -> Option<(ListMarker, &str)> {
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(item) = line.strip_prefix(bullet) {
+            return Some((ListMarker::Bullet, item.trim_start()));
+        }
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    let rest = &line[digits..];
+    if digits == 0 || digits > 9 {
+        return None;
+    }
+    let item = rest
+        .strip_prefix(". ")
+        .or_else(|| rest.strip_prefix(") "))?;
+    let number = line[..digits].parse().ok()?;
+    Some((ListMarker::Number(number), item.trim_start()))
+}
+
+/// `![alt](url)` filling the whole text
+fn image_of(text: &str) -> Option<(&str, &str)> {
+    let rest = text.trim().strip_prefix("![")?;
+    let (alt, rest) = rest.split_once("](")?;
+    let url = rest.strip_suffix(')')?;
+    (!url.contains(')')).then_some((alt, url))
+}
+
+/// Parse inline markup: `**bold**`, `*italic*`, `` `code` ``,
+/// `[text](url)` links and backslash escapes
+#[must_use]
+pub fn parse_inline(source: &str) -> Vec<Run> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut runs = Vec::new();
+    let mut style = Run::default();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            '\\' if next.is_some_and(|n| n.is_ascii_punctuation()) => {
+                style.text.push(chars[i + 1]);
+                i += 2;
+            }
+            '`' => match chars[i + 1..].iter().position(|&c| c == '`') {
+                Some(len) => {
+                    flush_run(&mut runs, &mut style);
+                    runs.push(Run {
+                        text: chars[i + 1..i + 1 + len].iter().collect(),
+                        code: true,
+                        ..Run::default()
+                    });
+                    i += len + 2;
+                }
+                None => {
+                    style.text.push(c);
+                    i += 1;
+                }
+            },
+            '*' | '_' if next == Some(c) => {
+                flush_run(&mut runs, &mut style);
+                style.strong = !style.strong;
+                i += 2;
+            }
+            '*' | '_' if c == '*' || at_word_edge(&chars, i, style.emphasis) => {
+                flush_run(&mut runs, &mut style);
+                style.emphasis = !style.emphasis;
+                i += 1;
+            }
+            '[' | '!' => match link_at(&chars, i) {
+                Some((label, url, end)) => {
+                    flush_run(&mut runs, &mut style);
+                    runs.push(Run {
+                        text: label,
+                        link: Some(url),
+                        ..style.clone()
+                    });
+                    i = end;
+                }
+                None => {
+                    style.text.push(c);
+                    i += 1;
+                }
+            },
+            _ => {
+                style.text.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    flush_run(&mut runs, &mut style);
+    runs
+}
+
+/// End the current run, keeping its style for the next
+fn flush_run(runs: &mut Vec<Run>, style: &mut Run) {
+    if !style.text.is_empty() {
+        runs.push(style.clone());
+        style.text.clear();
+    }
+}
+
+/// Whether an underscore opens or closes emphasis, rather than sitting
+/// inside a word like `snake_case`
+fn at_word_edge(chars: &[char], i: usize, closing: bool) -> bool {
+    let word = |c: Option<&char>| c.is_some_and(|c| c.is_alphanumeric());
+    if closing {
+        !word(chars.get(i + 1))
+    } else {
+        i == 0 || !word(chars.get(i - 1))
+    }
+}
+
+/// A `[label](url)` link, or `![alt](url)` image shown as a link, at `i`:
+/// the label, the URL and the index after it
+fn link_at(chars: &[char], i: usize) -> Option<(String, String, usize)> {
+    let open = if chars[i] == '!' { i + 1 } else { i };
+    if chars.get(open) != Some(&'[') {
+        return None;
+    }
+    let close = open + chars[open..].iter().position(|&c| c == ']')?;
+    if chars.get(close + 1) != Some(&'(') {
+        return None;
+    }
+    let end = close + 1 + chars[close + 1..].iter().position(|&c| c == ')')?;
+    let label: String = chars[open + 1..close].iter().collect();
+    let url: String = chars[close + 2..end].iter().collect();
+    Some((label, url.trim().to_string(), end + 1))
+}
+
+/// What a piece of highlighted code is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeClass {
+    Plain,
+    Keyword,
+    Number,
+    String,
+    Constant,
+    Comment,
+}
+
+impl CodeClass {
+    fn of(kind: &TokenKind) -> Self {
+        match kind {
+            TokenKind::Fx
+            | TokenKind::Let
+            | TokenKind::Const
+            | TokenKind::If
+            | TokenKind::Else
+            | TokenKind::For
+            | TokenKind::While
+            | TokenKind::Match
+            | TokenKind::Import
+            | TokenKind::Struct
+            | TokenKind::Enum
+            | TokenKind::Interface
+            | TokenKind::Impl
+            | TokenKind::Async
+            | TokenKind::Await
+            | TokenKind::Try
+            | TokenKind::Catch
+            | TokenKind::In
+            | TokenKind::Return
+            | TokenKind::Break
+            | TokenKind::Continue
+            | TokenKind::Throw => Self::Keyword,
+            TokenKind::Int
+            | TokenKind::HexInt
+            | TokenKind::BinaryInt
+            | TokenKind::OctalInt
+            | TokenKind::Float => Self::Number,
+            TokenKind::StringStart
+            | TokenKind::StringPart
+            | TokenKind::StringEnd
+            | TokenKind::MultiLineStringStart
+            | TokenKind::MultiLineStringEnd
+            | TokenKind::RawString => Self::String,
+            TokenKind::True | TokenKind::False | TokenKind::Null => Self::Constant,
+            TokenKind::LineComment | TokenKind::BlockComment => Self::Comment,
+            _ => Self::Plain,
+        }
+    }
+
+    fn color(self) -> Option<Color> {
+        match self {
+            Self::Plain => None,
+            Self::Keyword => Some(Color::from_rgb8(0xA6, 0x4C, 0xC9)),
+            Self::Number => Some(Color::from_rgb8(0xC9, 0x7A, 0x2C)),
+            Self::String => Some(Color::from_rgb8(0x4C, 0x9A, 0x4F)),
+            Self::Constant => Some(Color::from_rgb8(0x33, 0x80, 0xC8)),
+            Self::Comment => Some(Color::from_rgb8(0x80, 0x80, 0x80)),
+        }
+    }
+}
+
+/// Split Stratum source into highlighted pieces
+#[must_use]
+pub fn highlight(code: &str) -> Vec<(String, CodeClass)> {
+    let (mut tokens, _errors) = Lexer::tokenize(code);
+    tokens.sort_by_key(|token| token.span.start);
+
+    let mut pieces = Vec::new();
+    let mut at = 0;
+    for token in tokens {
+        let start = (token.span.start as usize).clamp(at, code.len());
+        let end = (token.span.end as usize).clamp(start, code.len());
+        if start > at {
+            pieces.push((code[at..start].to_string(), CodeClass::Plain));
+        }
+        if end > start {
+            pieces.push((code[start..end].to_string(), CodeClass::of(&token.kind)));
+        }
+        at = end;
+    }
+    if at < code.len() {
+        pieces.push((code[at..].to_string(), CodeClass::Plain));
+    }
+    pieces
+}
+
+fn is_stratum(language: Option<&str>) -> bool {
+    language.map_or(true, |language| {
+        matches!(language.to_ascii_lowercase().as_str(), "stratum" | "strat")
+    })
+}
+
+/// Render parsed blocks; links send their URL to `on_link`
+pub fn view(blocks: &[Block], on_link: Option<CallbackId>) -> Element<'_, Message> {
+    column(blocks.iter().map(|block| view_block(block, on_link)))
+        .spacing(10)
+        .width(Fill)
+        .into()
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn view_block(block: &Block, on_link: Option<CallbackId>) -> Element<'_, Message> {
+    match block {
+        Block::Heading { level, runs } => {
+            let size = match level {
+                1 => 28.0,
+                2 => 24.0,
+                3 => 20.0,
+                4 => 18.0,
+                5 => 16.0,
+                _ => 14.0,
+            };
+            view_runs(runs, on_link, size, true)
+        }
+        Block::Paragraph(runs) => view_runs(runs, on_link, 15.0, false),
+        Block::ListItem {
+            level,
+            marker,
+            runs,
+        } => {
+            let marker = match marker {
+                ListMarker::Bullet => "•".to_string(),
+                ListMarker::Number(n) => format!("{n}."),
+            };
+            row![
+                text(marker).size(15).width(24),
+                view_runs(runs, on_link, 15.0, false)
+            ]
+            .padding(iced::Padding::ZERO.left(20.0 * *level as f32))
+            .into()
+        }
+        Block::Quote(runs) => row![
+            container(Space::new())
+                .width(3)
+                .height(Fill)
+                .style(|theme: &Theme| {
+                    container::Style::default()
+                        .background(theme.extended_palette().background.strong.color)
+                }),
+            view_runs(runs, on_link, 15.0, false)
+        ]
+        .spacing(10)
+        .height(iced::Length::Shrink)
+        .into(),
+        Block::CodeBlock { language, code } => {
+            let pieces = if is_stratum(language.as_deref()) {
+                highlight(code)
+            } else {
+                vec![(code.clone(), CodeClass::Plain)]
+            };
+            let spans: Vec<_> = pieces
+                .into_iter()
+                .map(|(piece, class)| {
+                    let piece = span(piece).font(Font::MONOSPACE).size(14);
+                    match class.color() {
+                        Some(color) => piece.color(color),
+                        None => piece,
+                    }
+                })
+                .collect();
+            container(rich_text::<String, _, _, _>(spans))
+                .padding(10)
+                .width(Fill)
+                .style(container::rounded_box)
+                .into()
+        }
+        Block::Image { alt, url } => {
+            if url.is_empty() {
+                text(alt.as_str()).into()
+            } else {
+                Image::new(url.as_str()).into()
+            }
+        }
+        Block::Rule => container(Space::new())
+            .width(Fill)
+            .height(1)
+            .style(|theme: &Theme| {
+                container::Style::default()
+                    .background(theme.extended_palette().background.strong.color)
+            })
+            .into(),
+    }
+}
+
+fn view_runs(
+    runs: &[Run],
+    on_link: Option<CallbackId>,
+    size: f32,
+    strong: bool,
+) -> Element<'_, Message> {
+    let spans: Vec<_> = runs
+        .iter()
+        .map(|run| {
+            let mut font = if run.code {
+                Font::MONOSPACE
+            } else {
+                Font::default()
+            };
+            if strong || run.strong {
+                font.weight = font::Weight::Bold;
+            }
+            if run.emphasis {
+                font.style = font::Style::Italic;
+            }
+            let mut piece = span(run.text.as_str()).font(font).size(size);
+            if let Some(ref url) = run.link {
+                piece = piece
+                    .link(url.clone())
+                    .color(Color::from_rgb8(0x33, 0x80, 0xC8))
+                    .underline(true);
+            }
+            piece
+        })
+        .collect();
+
+    let rich = rich_text(spans).width(Fill);
+    match on_link {
+        Some(callback_id) => rich
+            .on_link_click(move |url| Message::LinkClicked { callback_id, url })
+            .into(),
+        None => rich.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(text: &str) -> Run {
+        Run {
+            text: text.to_string(),
+            ..Run::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_blocks() {
+        let blocks = parse(
+            "# Title #\n\nSome *text*\ncontinued.\n\n- one\n  - two\n1. first\n> quoted\n> more\n\n---\n![logo](logo.png)",
+        );
+        assert_eq!(
+            blocks[0],
+            Block::Heading {
+                level: 1,
+                runs: vec![plain("Title")]
+            }
+        );
+        let Block::Paragraph(runs) = &blocks[1] else {
+            panic!("Expected a paragraph, got {:?}", blocks[1]);
+        };
+        assert_eq!(runs.len(), 3);
+        assert!(runs[1].emphasis);
+        assert_eq!(runs[2].text, " continued.");
+
+        assert!(matches!(
+            blocks[2],
+            Block::ListItem {
+                level: 0,
+                marker: ListMarker::Bullet,
+                ..
+            }
+        ));
+        assert!(matches!(blocks[3], Block::ListItem { level: 1, .. }));
+        assert!(matches!(
+            blocks[4],
+            Block::ListItem {
+                marker: ListMarker::Number(1),
+                ..
+            }
+        ));
+        assert_eq!(blocks[5], Block::Quote(vec![plain("quoted more")]));
+        assert_eq!(blocks[6], Block::Rule);
+        assert_eq!(
+            blocks[7],
+            Block::Image {
+                alt: "logo".to_string(),
+                url: "logo.png".to_string()
+            }
+        );
+        assert_eq!(blocks.len(), 8);
+    }
+
+    #[test]
+    fn test_parse_code_blocks() {
+        let blocks = parse("```stratum\nlet x = 1\n\nprint(x)\n```\n~~~\nplain\n~~~");
+        assert_eq!(
+            blocks,
+            [
+                Block::CodeBlock {
+                    language: Some("stratum".to_string()),
+                    code: "let x = 1\n\nprint(x)".to_string()
+                },
+                Block::CodeBlock {
+                    language: None,
+                    code: "plain".to_string()
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_inline() {
+        let runs =
+            parse_inline("**bold _and italic_** `code` [docs](https://x.dev) snake_case \\*");
+        assert_eq!(runs[0].text, "bold ");
+        assert!(runs[0].strong && !runs[0].emphasis);
+        assert!(runs[1].strong && runs[1].emphasis);
+        assert!(runs[3].code);
+        assert_eq!(runs[3].text, "code");
+        assert_eq!(runs[5].link.as_deref(), Some("https://x.dev"));
+        assert_eq!(runs[5].text, "docs");
+        assert_eq!(runs[6].text, " snake_case *");
+
+        // Unclosed markup is text
+        assert_eq!(parse_inline("a ` b [c]"), [plain("a ` b [c]")]);
+    }
+
+    #[test]
+    fn test_highlight() {
+        let pieces = highlight("let x = 42 // answer");
+        let class_of = |text: &str| {
+            pieces
+                .iter()
+                .find(|(piece, _)| piece.trim() == text)
+                .map(|(_, class)| *class)
+        };
+        assert_eq!(class_of("let"), Some(CodeClass::Keyword));
+        assert_eq!(class_of("42"), Some(CodeClass::Number));
+        assert_eq!(class_of("// answer"), Some(CodeClass::Comment));
+        let joined: String = pieces.iter().map(|(piece, _)| piece.as_str()).collect();
+        assert_eq!(joined, "let x = 42 // answer");
+
+        assert!(is_stratum(None));
+        assert!(is_stratum(Some("Stratum")));
+        assert!(!is_stratum(Some("python")));
+    }
+}
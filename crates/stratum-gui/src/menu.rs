@@ -0,0 +1,502 @@
+//! Menus: menu bars, context menus and the system tray
+//!
+//! Menus are declared in Stratum as lists of items, each a map with a
+//! `label` and optional `on_select` callback, `shortcut` accelerator,
+//! `disabled` flag and nested `items` for a submenu; `"-"` is a separator.
+//! The same [`MenuItem`] tree backs `Gui.menu_bar`, `Gui.context_menu` and
+//! `Gui.tray`.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use iced::Point;
+use stratum_core::bytecode::{HashableValue, Value};
+
+use crate::callback::CallbackId;
+use crate::runtime::{ContextMenuItem, KeyModifiers};
+
+/// Last cursor position in the window, for placing context menus
+static CURSOR_X: AtomicU32 = AtomicU32::new(0);
+static CURSOR_Y: AtomicU32 = AtomicU32::new(0);
+
+/// Indentation of submenu items when a menu is shown as a flat list
+const SUBMENU_INDENT: &str = "    ";
+
+/// A keyboard shortcut such as `Ctrl+Shift+S`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Accelerator {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    /// Command on macOS, the Windows/Super key elsewhere
+    pub logo: bool,
+    /// Key name: a lowercase character, or a named key like `F5` or `Enter`
+    pub key: String,
+}
+
+impl Accelerator {
+    /// Parse a shortcut like `Ctrl+S`, `Shift+F5` or `CmdOrCtrl+Q`.
+    /// `CmdOrCtrl` is Command on macOS and Control elsewhere.
+    pub fn parse(shortcut: &str) -> Result<Self, String> {
+        let mut accelerator = Self::default();
+        let mut parts: Vec<&str> = shortcut.split('+').map(str::trim).collect();
+        let key = parts.pop().filter(|key| !key.is_empty());
+
+        for part in parts {
+            match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => accelerator.ctrl = true,
+                "shift" => accelerator.shift = true,
+                "alt" | "option" => accelerator.alt = true,
+                "cmd" | "command" | "super" | "meta" | "logo" => accelerator.logo = true,
+                "cmdorctrl" | "commandorcontrol" | "mod" => {
+                    if cfg!(target_os = "macos") {
+                        accelerator.logo = true;
+                    } else {
+                        accelerator.ctrl = true;
+                    }
+                }
+                _ => {
+                    return Err(format!(
+                        "unknown modifier '{part}' in shortcut '{shortcut}'"
+                    ))
+                }
+            }
+        }
+
+        let Some(key) = key else {
+            return Err(format!("shortcut '{shortcut}' has no key"));
+        };
+        accelerator.key = match key.to_ascii_lowercase().as_str() {
+            "esc" => "Escape".to_string(),
+            "del" => "Delete".to_string(),
+            "return" => "Enter".to_string(),
+            "up" | "down" | "left" | "right" => {
+                let mut name = "Arrow".to_string();
+                name.push_str(&key[..1].to_ascii_uppercase());
+                name.push_str(&key[1..].to_ascii_lowercase());
+                name
+            }
+            _ if key.chars().count() == 1 => key.to_lowercase(),
+            _ => key.to_string(),
+        };
+        Ok(accelerator)
+    }
+
+    /// Whether a key press matches, given the key as iced formats it
+    /// (`Character("s")`, `Named(Enter)`) and the modifiers held
+    #[must_use]
+    pub fn matches(&self, key: &str, modifiers: KeyModifiers) -> bool {
+        self.ctrl == modifiers.ctrl
+            && self.shift == modifiers.shift
+            && self.alt == modifiers.alt
+            && self.logo == modifiers.logo
+            && key_name(key).eq_ignore_ascii_case(&self.key)
+    }
+}
+
+impl fmt::Display for Accelerator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.alt {
+            write!(f, "Alt+")?;
+        }
+        if self.shift {
+            write!(f, "Shift+")?;
+        }
+        if self.logo {
+            write!(
+                f,
+                "{}+",
+                if cfg!(target_os = "macos") {
+                    "Cmd"
+                } else {
+                    "Super"
+                }
+            )?;
+        }
+        if self.key.chars().count() == 1 {
+            write!(f, "{}", self.key.to_uppercase())
+        } else {
+            write!(f, "{}", self.key)
+        }
+    }
+}
+
+/// The key name inside iced's debug form of a key
+fn key_name(key: &str) -> &str {
+    key.strip_prefix("Character(\"")
+        .and_then(|rest| rest.strip_suffix("\")"))
+        .or_else(|| {
+            key.strip_prefix("Named(")
+                .and_then(|rest| rest.strip_suffix(')'))
+        })
+        .unwrap_or(key)
+}
+
+/// An item of a menu, or a submenu when it has items of its own
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MenuItem {
+    pub label: String,
+    /// Keyboard shortcut, shown next to the label and handled by menu bars
+    pub shortcut: Option<Accelerator>,
+    /// Callback when the item is chosen (receives no arguments)
+    pub on_select: Option<CallbackId>,
+    /// Items of the submenu
+    pub items: Vec<MenuItem>,
+    pub disabled: bool,
+    pub separator: bool,
+}
+
+impl MenuItem {
+    /// A menu item with a label
+    #[must_use]
+    pub fn new(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            ..Self::default()
+        }
+    }
+
+    /// A separator line between items
+    #[must_use]
+    pub fn separator() -> Self {
+        Self {
+            separator: true,
+            ..Self::default()
+        }
+    }
+
+    /// An item from a Stratum value: `"-"` for a separator, a string for a
+    /// label alone, or a map or struct with `label` and optional
+    /// `on_select` (a callback id or closure), `shortcut`, `disabled`,
+    /// `separator` and `items`
+    pub fn from_value(value: &Value) -> Result<Self, String> {
+        let field = |name: &str| -> Option<Value> {
+            match value {
+                Value::Map(map) => map
+                    .borrow()
+                    .get(&HashableValue::String(name.to_string().into()))
+                    .cloned(),
+                Value::Struct(instance) => instance.borrow().fields.get(name).cloned(),
+                _ => None,
+            }
+        };
+
+        match value {
+            Value::String(s) if !s.is_empty() && s.chars().all(|c| c == '-') => {
+                return Ok(Self::separator())
+            }
+            Value::String(label) => return Ok(Self::new(label.as_str())),
+            Value::Map(_) | Value::Struct(_) => {}
+            other => {
+                return Err(format!(
+                    "menu item must be a string, map or struct, got {}",
+                    other.type_name()
+                ))
+            }
+        }
+
+        if matches!(field("separator"), Some(Value::Bool(true))) {
+            return Ok(Self::separator());
+        }
+
+        let label = match field("label") {
+            Some(Value::String(s)) => s.to_string(),
+            Some(other) => format!("{other}"),
+            None => return Err("menu item needs a label".to_string()),
+        };
+        let shortcut = match field("shortcut") {
+            Some(Value::String(s)) => Some(Accelerator::parse(&s)?),
+            Some(Value::Null) | None => None,
+            Some(other) => {
+                return Err(format!(
+                    "menu item shortcut must be a string, got {}",
+                    other.type_name()
+                ))
+            }
+        };
+        let on_select = match field("on_select") {
+            Some(Value::Int(id)) if id >= 0 => Some(CallbackId::new(id.unsigned_abs())),
+            Some(callback @ Value::Closure(_)) => {
+                let id = crate::bindings::register_pending_callback(callback);
+                Some(CallbackId::new(id.unsigned_abs()))
+            }
+            Some(Value::Null) | None => None,
+            Some(other) => {
+                return Err(format!(
+                    "menu item on_select must be a callback, got {}",
+                    other.type_name()
+                ))
+            }
+        };
+        let items = match field("items") {
+            Some(Value::List(list)) => menu_items_from_list(&list.borrow())?,
+            Some(Value::Null) | None => Vec::new(),
+            Some(other) => {
+                return Err(format!(
+                    "menu item items must be a list, got {}",
+                    other.type_name()
+                ))
+            }
+        };
+
+        Ok(Self {
+            label,
+            shortcut,
+            on_select,
+            items,
+            disabled: matches!(field("disabled"), Some(Value::Bool(true))),
+            separator: false,
+        })
+    }
+
+    /// Label with the shortcut after it, as shown in flat menus
+    fn display_label(&self) -> String {
+        match &self.shortcut {
+            Some(shortcut) => format!("{}    {shortcut}", self.label),
+            None => self.label.clone(),
+        }
+    }
+}
+
+/// Menu items from a list of Stratum values
+pub fn menu_items_from_list(values: &[Value]) -> Result<Vec<MenuItem>, String> {
+    values.iter().map(MenuItem::from_value).collect()
+}
+
+/// The callback of the enabled item whose shortcut a key press matches,
+/// searching submenus too
+#[must_use]
+pub fn find_shortcut(items: &[MenuItem], key: &str, modifiers: KeyModifiers) -> Option<CallbackId> {
+    items.iter().filter(|item| !item.disabled).find_map(|item| {
+        match (&item.shortcut, item.on_select) {
+            (Some(shortcut), Some(callback_id)) if shortcut.matches(key, modifiers) => {
+                Some(callback_id)
+            }
+            _ => find_shortcut(&item.items, key, modifiers),
+        }
+    })
+}
+
+/// An entry of a menu shown as a dropdown list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MenuEntry {
+    pub label: String,
+    /// Callback when chosen; None for separators, submenu titles and
+    /// disabled items
+    pub callback: Option<CallbackId>,
+}
+
+impl fmt::Display for MenuEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.label)
+    }
+}
+
+/// A menu's items as one list, with submenu items indented under their title
+#[must_use]
+pub fn menu_entries(items: &[MenuItem]) -> Vec<MenuEntry> {
+    fn walk(items: &[MenuItem], indent: &str, out: &mut Vec<MenuEntry>) {
+        for item in items {
+            if item.separator {
+                out.push(MenuEntry {
+                    label: format!("{indent}────────"),
+                    callback: None,
+                });
+                continue;
+            }
+            let callback = if item.disabled { None } else { item.on_select };
+            out.push(MenuEntry {
+                label: format!("{indent}{}", item.display_label()),
+                callback,
+            });
+            if !item.disabled {
+                walk(&item.items, &format!("{indent}{SUBMENU_INDENT}"), out);
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    walk(items, "", &mut out);
+    out
+}
+
+/// A menu's items as context menu items, with submenu items indented
+#[must_use]
+pub fn context_menu_items(items: &[MenuItem]) -> Vec<ContextMenuItem> {
+    fn walk(items: &[MenuItem], indent: &str, out: &mut Vec<ContextMenuItem>) {
+        for item in items {
+            if item.separator {
+                out.push(ContextMenuItem::separator());
+                continue;
+            }
+            let mut entry = ContextMenuItem::new(format!("{indent}{}", item.display_label()))
+                .disabled(item.disabled);
+            if let Some(callback_id) = item.on_select {
+                entry = entry.on_select(callback_id);
+            }
+            out.push(entry);
+            if !item.disabled {
+                walk(&item.items, &format!("{indent}{SUBMENU_INDENT}"), out);
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    walk(items, "", &mut out);
+    out
+}
+
+/// Remember where the cursor is; called for every cursor move
+pub fn record_cursor(position: Point) {
+    CURSOR_X.store(position.x.to_bits(), Ordering::Relaxed);
+    CURSOR_Y.store(position.y.to_bits(), Ordering::Relaxed);
+}
+
+/// Where the cursor last was
+#[must_use]
+pub fn last_cursor() -> Point {
+    Point::new(
+        f32::from_bits(CURSOR_X.load(Ordering::Relaxed)),
+        f32::from_bits(CURSOR_Y.load(Ordering::Relaxed)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    fn map(entries: Vec<(&str, Value)>) -> Value {
+        let map = Value::empty_map();
+        if let Value::Map(ref inner) = map {
+            for (key, value) in entries {
+                inner
+                    .borrow_mut()
+                    .insert(HashableValue::String(Rc::new(key.to_string())), value);
+            }
+        }
+        map
+    }
+
+    fn ctrl() -> KeyModifiers {
+        KeyModifiers {
+            ctrl: true,
+            ..KeyModifiers::none()
+        }
+    }
+
+    #[test]
+    fn test_accelerator_parse() {
+        let accelerator = Accelerator::parse("Ctrl+Shift+S").unwrap();
+        assert!(accelerator.ctrl && accelerator.shift && !accelerator.alt);
+        assert_eq!(accelerator.key, "s");
+        assert_eq!(accelerator.to_string(), "Ctrl+Shift+S");
+
+        assert_eq!(Accelerator::parse("F5").unwrap().key, "F5");
+        assert_eq!(Accelerator::parse("Alt+Up").unwrap().key, "ArrowUp");
+        assert_eq!(Accelerator::parse("Esc").unwrap().key, "Escape");
+        assert!(Accelerator::parse("Hyper+S").is_err());
+        assert!(Accelerator::parse("Ctrl+").is_err());
+    }
+
+    #[test]
+    fn test_accelerator_matches() {
+        let save = Accelerator::parse("Ctrl+S").unwrap();
+        assert!(save.matches("Character(\"s\")", ctrl()));
+        assert!(!save.matches("Character(\"s\")", KeyModifiers::none()));
+        assert!(!save.matches("Character(\"a\")", ctrl()));
+
+        let refresh = Accelerator::parse("F5").unwrap();
+        assert!(refresh.matches("Named(F5)", KeyModifiers::none()));
+    }
+
+    #[test]
+    fn test_menu_item_from_value() {
+        let value = map(vec![
+            ("label", Value::string("File")),
+            (
+                "items",
+                Value::list(vec![
+                    map(vec![
+                        ("label", Value::string("Save")),
+                        ("shortcut", Value::string("Ctrl+S")),
+                        ("on_select", Value::Int(3)),
+                    ]),
+                    Value::string("-"),
+                    map(vec![
+                        ("label", Value::string("Quit")),
+                        ("on_select", Value::Int(4)),
+                        ("disabled", Value::Bool(true)),
+                    ]),
+                ]),
+            ),
+        ]);
+        let file = MenuItem::from_value(&value).unwrap();
+        assert_eq!(file.label, "File");
+        assert_eq!(file.items.len(), 3);
+        assert!(file.items[1].separator);
+        assert_eq!(file.items[0].on_select, Some(CallbackId::new(3)));
+
+        let menus = [file];
+        assert_eq!(
+            find_shortcut(&menus, "Character(\"s\")", ctrl()),
+            Some(CallbackId::new(3))
+        );
+        assert_eq!(find_shortcut(&menus, "Character(\"q\")", ctrl()), None);
+
+        assert!(MenuItem::from_value(&Value::Int(1)).is_err());
+        assert!(MenuItem::from_value(&map(vec![("on_select", Value::Int(1))])).is_err());
+        assert!(MenuItem::from_value(&map(vec![
+            ("label", Value::string("Bad")),
+            ("shortcut", Value::string("Hyper+X")),
+        ]))
+        .is_err());
+    }
+
+    #[test]
+    fn test_flattened_menus() {
+        let mut recent = MenuItem::new("Recent");
+        recent.items = vec![MenuItem {
+            on_select: Some(CallbackId::new(2)),
+            ..MenuItem::new("a.csv")
+        }];
+        let items = vec![
+            MenuItem {
+                on_select: Some(CallbackId::new(1)),
+                shortcut: Accelerator::parse("Ctrl+O").ok(),
+                ..MenuItem::new("Open")
+            },
+            recent,
+            MenuItem::separator(),
+            MenuItem {
+                on_select: Some(CallbackId::new(3)),
+                disabled: true,
+                ..MenuItem::new("Close")
+            },
+        ];
+
+        let entries = menu_entries(&items);
+        let labels: Vec<&str> = entries.iter().map(|e| e.label.as_str()).collect();
+        assert_eq!(
+            labels,
+            ["Open    Ctrl+O", "Recent", "    a.csv", "────────", "Close"]
+        );
+        assert_eq!(entries[2].callback, Some(CallbackId::new(2)));
+        // Disabled items can't be chosen
+        assert_eq!(entries[4].callback, None);
+
+        let context = context_menu_items(&items);
+        assert_eq!(context.len(), 5);
+        assert!(context[3].separator);
+        assert!(context[4].disabled);
+    }
+
+    #[test]
+    fn test_cursor_position() {
+        record_cursor(Point::new(12.5, 40.0));
+        assert_eq!(last_cursor(), Point::new(12.5, 40.0));
+    }
+}
@@ -5,12 +5,27 @@
 
 use std::sync::Arc;
 
-use stratum_core::bytecode::{NativeFunction, Value};
+use stratum_core::bytecode::{HashableValue, NativeFunction, Value};
 
+use crate::accessibility::{self, Role};
+use crate::animation::{Animation, Easing, Transition};
 use crate::callback::CallbackId;
-use crate::charts::{BarChartConfig, DataPoint, DataSeries, LineChartConfig, PieChartConfig};
-use crate::element::{GuiElement, GuiElementKind, ImageContentFit};
+use crate::charts::{
+    BarChartConfig, Candle, DataPoint, DataSeries, LineChartConfig, PieChartConfig, ScatterSeries,
+};
+use crate::dialogs::{self, FileFilter};
+use crate::dnd::{default_kind, register_payload, FileDropEvent};
+use crate::element::{GuiElement, GuiElementKind, ImageConfig, ImageContentFit};
+use crate::form::{self, FieldRules};
+use crate::image_loader;
 use crate::layout::{HAlign, ScrollDirection, Size, VAlign};
+use crate::menu::{menu_items_from_list, MenuItem};
+use crate::navigation::{self, Page};
+use crate::table::rows_to_dataframe;
+use crate::timer::{BackgroundTask, Timer};
+use crate::tray::TrayConfig;
+use crate::tree::nodes_from_list;
+use crate::window::{self as windows, Position, WindowHandler, WindowSettings};
 
 /// Result type for native GUI functions
 pub type NativeResult = Result<Value, String>;
@@ -194,6 +209,10 @@ pub fn gui_native_functions() -> Vec<(&'static str, NativeFunction)> {
             "gui_set_opacity",
             NativeFunction::new("gui_set_opacity", 2, gui_set_opacity),
         ),
+        (
+            "gui_set_rotation",
+            NativeFunction::new("gui_set_rotation", 2, gui_set_rotation),
+        ),
         // Utility functions
         (
             "gui_add_child",
@@ -287,6 +306,273 @@ pub fn gui_native_functions() -> Vec<(&'static str, NativeFunction)> {
             "gui_on_cell_click",
             NativeFunction::new("gui_on_cell_click", 2, gui_on_cell_click),
         ),
+        // Virtualized table functions
+        ("gui_table", NativeFunction::new("gui_table", -1, gui_table)),
+        (
+            "gui_set_cell_formatter",
+            NativeFunction::new("gui_set_cell_formatter", 3, gui_set_cell_formatter),
+        ),
+        (
+            "gui_set_table_id",
+            NativeFunction::new("gui_set_table_id", 2, gui_set_table_id),
+        ),
+        (
+            "gui_set_row_height",
+            NativeFunction::new("gui_set_row_height", 2, gui_set_row_height),
+        ),
+        (
+            "gui_set_resizable",
+            NativeFunction::new("gui_set_resizable", 2, gui_set_resizable),
+        ),
+        // Tree and section functions
+        ("gui_tree", NativeFunction::new("gui_tree", 1, gui_tree)),
+        (
+            "gui_section",
+            NativeFunction::new("gui_section", -1, gui_section),
+        ),
+        (
+            "gui_set_key",
+            NativeFunction::new("gui_set_key", 2, gui_set_key),
+        ),
+        (
+            "gui_set_expanded",
+            NativeFunction::new("gui_set_expanded", 2, gui_set_expanded),
+        ),
+        (
+            "gui_set_selected_node",
+            NativeFunction::new("gui_set_selected_node", 2, gui_set_selected_node),
+        ),
+        (
+            "gui_set_indent",
+            NativeFunction::new("gui_set_indent", 2, gui_set_indent),
+        ),
+        (
+            "gui_on_expand",
+            NativeFunction::new("gui_on_expand", 2, gui_on_expand),
+        ),
+        (
+            "gui_on_context_menu",
+            NativeFunction::new("gui_on_context_menu", 2, gui_on_context_menu),
+        ),
+        // Canvas functions
+        (
+            "gui_canvas",
+            NativeFunction::new("gui_canvas", -1, gui_canvas),
+        ),
+        (
+            "gui_on_draw",
+            NativeFunction::new("gui_on_draw", 2, gui_on_draw),
+        ),
+        (
+            "gui_on_canvas_event",
+            NativeFunction::new("gui_on_canvas_event", 2, gui_on_canvas_event),
+        ),
+        // Menu functions
+        (
+            "gui_menu_bar",
+            NativeFunction::new("gui_menu_bar", 1, gui_menu_bar),
+        ),
+        (
+            "gui_context_menu",
+            NativeFunction::new("gui_context_menu", 2, gui_context_menu),
+        ),
+        ("gui_tray", NativeFunction::new("gui_tray", -1, gui_tray)),
+        // Form validation functions
+        (
+            "gui_validate",
+            NativeFunction::new("gui_validate", 2, gui_validate),
+        ),
+        (
+            "gui_field_error",
+            NativeFunction::new("gui_field_error", 1, gui_field_error),
+        ),
+        (
+            "gui_form_valid",
+            NativeFunction::new("gui_form_valid", 0, gui_form_valid),
+        ),
+        (
+            "gui_form_errors",
+            NativeFunction::new("gui_form_errors", 0, gui_form_errors),
+        ),
+        (
+            "gui_reset_validation",
+            NativeFunction::new("gui_reset_validation", 0, gui_reset_validation),
+        ),
+        // Markdown functions
+        (
+            "gui_markdown",
+            NativeFunction::new("gui_markdown", 1, gui_markdown),
+        ),
+        (
+            "gui_on_link",
+            NativeFunction::new("gui_on_link", 2, gui_on_link),
+        ),
+        // Timer and task functions
+        ("gui_every", NativeFunction::new("gui_every", 2, gui_every)),
+        ("gui_after", NativeFunction::new("gui_after", 2, gui_after)),
+        (
+            "gui_cancel_timer",
+            NativeFunction::new("gui_cancel_timer", 1, gui_cancel_timer),
+        ),
+        (
+            "gui_spawn_task",
+            NativeFunction::new("gui_spawn_task", -1, gui_spawn_task),
+        ),
+        // Navigation functions
+        (
+            "gui_navigator",
+            NativeFunction::new("gui_navigator", -1, gui_navigator),
+        ),
+        (
+            "gui_push_page",
+            NativeFunction::new("gui_push_page", -1, gui_push_page),
+        ),
+        (
+            "gui_replace_page",
+            NativeFunction::new("gui_replace_page", -1, gui_replace_page),
+        ),
+        (
+            "gui_pop_page",
+            NativeFunction::new("gui_pop_page", 0, gui_pop_page),
+        ),
+        (
+            "gui_pop_to_root",
+            NativeFunction::new("gui_pop_to_root", 0, gui_pop_to_root),
+        ),
+        (
+            "gui_go_back",
+            NativeFunction::new("gui_go_back", 0, gui_go_back),
+        ),
+        (
+            "gui_current_page",
+            NativeFunction::new("gui_current_page", 0, gui_current_page),
+        ),
+        (
+            "gui_page_params",
+            NativeFunction::new("gui_page_params", 0, gui_page_params),
+        ),
+        (
+            "gui_can_go_back",
+            NativeFunction::new("gui_can_go_back", 0, gui_can_go_back),
+        ),
+        // Window functions
+        (
+            "gui_open_window",
+            NativeFunction::new("gui_open_window", -1, gui_open_window),
+        ),
+        (
+            "gui_close_window",
+            NativeFunction::new("gui_close_window", 1, gui_close_window),
+        ),
+        (
+            "gui_send_to_window",
+            NativeFunction::new("gui_send_to_window", 2, gui_send_to_window),
+        ),
+        (
+            "gui_on_window",
+            NativeFunction::new("gui_on_window", 3, gui_on_window),
+        ),
+        (
+            "gui_window_state",
+            NativeFunction::new("gui_window_state", 1, gui_window_state),
+        ),
+        (
+            "gui_update_window_field",
+            NativeFunction::new("gui_update_window_field", 3, gui_update_window_field),
+        ),
+        (
+            "gui_current_window",
+            NativeFunction::new("gui_current_window", 0, gui_current_window),
+        ),
+        (
+            "gui_windows",
+            NativeFunction::new("gui_windows", 0, gui_windows),
+        ),
+        // Animation functions
+        (
+            "gui_animate",
+            NativeFunction::new("gui_animate", -1, gui_animate),
+        ),
+        (
+            "gui_set_transition",
+            NativeFunction::new("gui_set_transition", -1, gui_set_transition),
+        ),
+        // Accessibility
+        (
+            "gui_set_access_label",
+            NativeFunction::new("gui_set_access_label", 2, gui_set_access_label),
+        ),
+        (
+            "gui_set_access_role",
+            NativeFunction::new("gui_set_access_role", 2, gui_set_access_role),
+        ),
+        (
+            "gui_set_access_description",
+            NativeFunction::new("gui_set_access_description", 2, gui_set_access_description),
+        ),
+        (
+            "gui_set_tab_index",
+            NativeFunction::new("gui_set_tab_index", 2, gui_set_tab_index),
+        ),
+        (
+            "gui_bind_focus",
+            NativeFunction::new("gui_bind_focus", 1, gui_bind_focus),
+        ),
+        (
+            "gui_easings",
+            NativeFunction::new("gui_easings", 0, gui_easings),
+        ),
+        // Clipboard and drag and drop functions
+        (
+            "gui_clipboard_read",
+            NativeFunction::new("gui_clipboard_read", 0, gui_clipboard_read),
+        ),
+        (
+            "gui_clipboard_write",
+            NativeFunction::new("gui_clipboard_write", 1, gui_clipboard_write),
+        ),
+        (
+            "gui_draggable",
+            NativeFunction::new("gui_draggable", -1, gui_draggable),
+        ),
+        (
+            "gui_drop_target",
+            NativeFunction::new("gui_drop_target", -1, gui_drop_target),
+        ),
+        (
+            "gui_on_drop",
+            NativeFunction::new("gui_on_drop", 2, gui_on_drop),
+        ),
+        (
+            "gui_on_file_drop",
+            NativeFunction::new("gui_on_file_drop", 1, gui_on_file_drop),
+        ),
+        (
+            "gui_on_file_hover",
+            NativeFunction::new("gui_on_file_hover", 1, gui_on_file_hover),
+        ),
+        (
+            "gui_on_file_hover_left",
+            NativeFunction::new("gui_on_file_hover_left", 1, gui_on_file_hover_left),
+        ),
+        // Dialog functions
+        (
+            "gui_open_file_dialog",
+            NativeFunction::new("gui_open_file_dialog", -1, gui_open_file_dialog),
+        ),
+        (
+            "gui_save_file_dialog",
+            NativeFunction::new("gui_save_file_dialog", -1, gui_save_file_dialog),
+        ),
+        (
+            "gui_pick_folder",
+            NativeFunction::new("gui_pick_folder", -1, gui_pick_folder),
+        ),
+        ("gui_alert", NativeFunction::new("gui_alert", -1, gui_alert)),
+        (
+            "gui_confirm",
+            NativeFunction::new("gui_confirm", -1, gui_confirm),
+        ),
         // Chart functions
         (
             "gui_bar_chart",
@@ -340,6 +626,50 @@ pub fn gui_native_functions() -> Vec<(&'static str, NativeFunction)> {
             "gui_set_inner_radius",
             NativeFunction::new("gui_set_inner_radius", 2, gui_set_inner_radius),
         ),
+        (
+            "gui_scatter_chart",
+            NativeFunction::new("gui_scatter_chart", -1, gui_scatter_chart),
+        ),
+        (
+            "gui_area_chart",
+            NativeFunction::new("gui_area_chart", -1, gui_area_chart),
+        ),
+        (
+            "gui_stacked_bar_chart",
+            NativeFunction::new("gui_stacked_bar_chart", -1, gui_stacked_bar_chart),
+        ),
+        (
+            "gui_histogram",
+            NativeFunction::new("gui_histogram", -1, gui_histogram),
+        ),
+        (
+            "gui_candlestick_chart",
+            NativeFunction::new("gui_candlestick_chart", -1, gui_candlestick_chart),
+        ),
+        (
+            "gui_set_stacked",
+            NativeFunction::new("gui_set_stacked", 2, gui_set_stacked),
+        ),
+        (
+            "gui_set_bins",
+            NativeFunction::new("gui_set_bins", 2, gui_set_bins),
+        ),
+        (
+            "gui_set_tooltips",
+            NativeFunction::new("gui_set_tooltips", 2, gui_set_tooltips),
+        ),
+        (
+            "gui_set_zoomable",
+            NativeFunction::new("gui_set_zoomable", 2, gui_set_zoomable),
+        ),
+        (
+            "gui_set_toggle_legend",
+            NativeFunction::new("gui_set_toggle_legend", 2, gui_set_toggle_legend),
+        ),
+        (
+            "gui_on_point_click",
+            NativeFunction::new("gui_on_point_click", 2, gui_on_point_click),
+        ),
         // OLAP Cube widget functions
         (
             "gui_cube_table",
@@ -448,6 +778,7 @@ pub fn gui_native_functions() -> Vec<(&'static str, NativeFunction)> {
             "gui_custom_theme",
             NativeFunction::new("gui_custom_theme", 2, gui_custom_theme),
         ),
+        ("gui_theme", NativeFunction::new("gui_theme", 1, gui_theme)),
         // Interactive element functions
         (
             "gui_interactive",
@@ -577,6 +908,18 @@ fn get_callback_id(value: &Value) -> Result<CallbackId, String> {
     }
 }
 
+// Helper to extract a callback given as an id or a closure, registering
+// the closure
+fn get_callback(value: &Value) -> Result<CallbackId, String> {
+    match value {
+        Value::Closure(_) => {
+            let id = crate::bindings::register_pending_callback(value.clone());
+            Ok(CallbackId::new(id as u64))
+        }
+        _ => get_callback_id(value),
+    }
+}
+
 // Helper to extract required string argument
 fn get_string(args: &[Value], index: usize, name: &str) -> Result<String, String> {
     match args.get(index) {
@@ -589,7 +932,7 @@ fn get_string(args: &[Value], index: usize, name: &str) -> Result<String, String
 // Helper to extract StateBinding path if present
 fn get_state_binding_path(value: &Value) -> Option<String> {
     match value {
-        Value::StateBinding(path) => Some(path.clone()),
+        Value::StateBinding(path) => Some(path.to_string()),
         _ => None,
     }
 }
@@ -1217,9 +1560,15 @@ fn gui_bind_field(args: &[Value]) -> NativeResult {
         GuiElementKind::Toggle(config) => {
             config.field_path = Some(field_path);
         }
+        GuiElementKind::Tree(config) => {
+            config.field_path = Some(field_path);
+        }
+        GuiElementKind::Section(config) => {
+            config.field_path = Some(field_path);
+        }
         _ => {
             return Err(
-                "gui_bind_field can only be applied to TextField, Checkbox, RadioButton, Dropdown, Slider, or Toggle elements".to_string(),
+                "gui_bind_field can only be applied to TextField, Checkbox, RadioButton, Dropdown, Slider, Toggle, Tree, or Section elements".to_string(),
             );
         }
     }
@@ -1695,41 +2044,59 @@ fn gui_set_progress(args: &[Value]) -> NativeResult {
 // ==================== Image Native Functions ====================
 
 /// Create an Image element
-/// gui_image(path) or gui_image(path, content_fit)
-/// content_fit can be: "contain", "cover", "fill", "none", "scale-down"
+/// gui_image(source) or gui_image(source, content_fit)
+/// source can be a file path, an http(s) URL or an Image value
+/// content_fit can be: "contain" (or "fit"), "cover", "fill", "none", "scale-down"
 fn gui_image(args: &[Value]) -> NativeResult {
-    let path = get_string(args, 0, "path")?;
+    let source = args.first().ok_or("missing required argument: source")?;
 
-    let mut builder = GuiElement::image(&path);
+    let mut element = GuiElement::image(String::new()).build();
+    if let GuiElementKind::Image(ref mut config) = element.kind {
+        set_image_source(config, source)?;
 
-    // Second arg is content fit
-    if let Some(Value::String(s)) = args.get(1) {
-        let fit = match s.as_str() {
-            "contain" => ImageContentFit::Contain,
-            "cover" => ImageContentFit::Cover,
-            "fill" => ImageContentFit::Fill,
-            "none" => ImageContentFit::None,
-            "scale-down" => ImageContentFit::ScaleDown,
-            _ => return Err(format!("invalid content_fit: {}", s)),
-        };
-        builder = builder.content_fit(fit);
+        // Second arg is content fit
+        if let Some(Value::String(s)) = args.get(1) {
+            config.content_fit = ImageContentFit::from_name(s)
+                .ok_or_else(|| format!("invalid content_fit: {}", s))?;
+        }
     }
 
-    Ok(builder.build().into_value())
+    Ok(element.into_value())
+}
+
+/// Point an image at a path, a URL or the pixels of an Image value
+fn set_image_source(config: &mut ImageConfig, source: &Value) -> Result<(), String> {
+    match source {
+        Value::String(path) => {
+            config.path = Some(path.to_string());
+            config.handle = None;
+        }
+        Value::Image(image) => {
+            config.path = image.source_path.clone();
+            config.handle = Some(image_loader::handle_from_image(image));
+        }
+        other => {
+            return Err(format!(
+                "image source must be a path, URL or Image, got {}",
+                other.type_name()
+            ))
+        }
+    }
+    Ok(())
 }
 
 /// Set image path
-/// gui_set_image_path(element, path) -> new_element
+/// gui_set_image_path(element, source) -> new_element
+/// source can be a file path, an http(s) URL or an Image value
 fn gui_set_image_path(args: &[Value]) -> NativeResult {
     if args.len() != 2 {
         return Err("gui_set_image_path requires 2 arguments".to_string());
     }
 
     let mut element = clone_gui_element(&args[0])?;
-    let path = get_string(args, 1, "path")?;
 
     if let GuiElementKind::Image(ref mut config) = element.kind {
-        config.path = Some(path);
+        set_image_source(config, &args[1])?;
     } else {
         return Err("gui_set_image_path can only be applied to Image elements".to_string());
     }
@@ -1748,14 +2115,8 @@ fn gui_set_content_fit(args: &[Value]) -> NativeResult {
     let mut element = clone_gui_element(&args[0])?;
     let fit_str = get_string(args, 1, "fit")?;
 
-    let fit = match fit_str.as_str() {
-        "contain" => ImageContentFit::Contain,
-        "cover" => ImageContentFit::Cover,
-        "fill" => ImageContentFit::Fill,
-        "none" => ImageContentFit::None,
-        "scale-down" => ImageContentFit::ScaleDown,
-        _ => return Err(format!("invalid content_fit: {}", fit_str)),
-    };
+    let fit = ImageContentFit::from_name(&fit_str)
+        .ok_or_else(|| format!("invalid content_fit: {}", fit_str))?;
 
     if let GuiElementKind::Image(ref mut config) = element.kind {
         config.content_fit = fit;
@@ -1785,6 +2146,25 @@ fn gui_set_opacity(args: &[Value]) -> NativeResult {
     Ok(element.into_value())
 }
 
+/// Set image rotation
+/// gui_set_rotation(element, degrees) -> new_element
+fn gui_set_rotation(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err("gui_set_rotation requires 2 arguments".to_string());
+    }
+
+    let mut element = clone_gui_element(&args[0])?;
+    let degrees = get_float(args, 1, "degrees")? as f32;
+
+    if let GuiElementKind::Image(ref mut config) = element.kind {
+        config.rotation = degrees;
+    } else {
+        return Err("gui_set_rotation can only be applied to Image elements".to_string());
+    }
+
+    Ok(element.into_value())
+}
+
 // ==================== Conditional Rendering ====================
 
 /// Create a conditional element
@@ -1936,82 +2316,1296 @@ fn gui_data_table(args: &[Value]) -> NativeResult {
     Ok(builder.build().into_value())
 }
 
-/// Set the columns to display in a data table
-/// gui_set_table_columns(element, columns) -> new_element
-fn gui_set_table_columns(args: &[Value]) -> NativeResult {
-    if args.len() != 2 {
-        return Err("gui_set_table_columns requires 2 arguments (element, columns)".to_string());
-    }
-
-    let mut element = clone_gui_element(&args[0])?;
-
-    // Extract column names from list
-    let columns = match &args[1] {
-        Value::List(list) => {
-            let list = list.borrow();
-            let mut cols = Vec::new();
-            for item in list.iter() {
-                if let Value::String(s) = item {
-                    cols.push(s.to_string());
-                } else {
-                    return Err(format!(
-                        "column name must be a string, got {}",
-                        item.type_name()
-                    ));
-                }
-            }
-            cols
+/// Create a virtualized table that scrolls through all rows, drawing only
+/// those in view
+/// gui_table(dataframe) -> table element bound to the DataFrame
+/// gui_table(columns, rows) -> table element over a list of rows
+fn gui_table(args: &[Value]) -> NativeResult {
+    let df = match args {
+        [Value::DataFrame(df)] => Arc::clone(df),
+        [columns, Value::List(rows)] => {
+            let columns = string_list(columns, "columns")?;
+            let rows = rows
+                .borrow()
+                .iter()
+                .map(|row| match row {
+                    Value::List(values) => Ok(values.borrow().clone()),
+                    other => Err(format!(
+                        "each row must be a list, got {}",
+                        other.type_name()
+                    )),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Arc::new(rows_to_dataframe(&columns, &rows)?)
         }
-        _ => {
+        [_, rows] => {
+            return Err(format!("rows must be a list, got {}", rows.type_name()));
+        }
+        [other] => {
             return Err(format!(
-                "columns must be a list, got {}",
-                args[1].type_name()
-            ))
+                "gui_table argument must be a DataFrame, got {}",
+                other.type_name()
+            ));
+        }
+        _ => {
+            return Err(
+                "gui_table requires a DataFrame or 2 arguments (columns, rows)".to_string(),
+            );
         }
     };
 
+    Ok(GuiElement::table().dataframe(df).build().into_value())
+}
+
+/// Set a formatter callback for a table column, called with the cell value
+/// and row index and returning the text to show
+/// gui_set_cell_formatter(element, column, callback_id) -> new_element
+fn gui_set_cell_formatter(args: &[Value]) -> NativeResult {
+    if args.len() != 3 {
+        return Err(
+            "gui_set_cell_formatter requires 3 arguments (element, column, callback_id)"
+                .to_string(),
+        );
+    }
+
+    let mut element = clone_gui_element(&args[0])?;
+    let column = get_string(args, 1, "column")?;
+    let callback_id = get_callback_id(&args[2])?;
+
     if let GuiElementKind::DataTable(ref mut config) = element.kind {
-        config.columns = Some(columns);
+        config.cell_formatters.retain(|(c, _)| c != &column);
+        config.cell_formatters.push((column, callback_id));
     } else {
-        return Err("gui_set_table_columns can only be applied to DataTable elements".to_string());
+        return Err("gui_set_cell_formatter can only be applied to DataTable elements".to_string());
     }
 
     Ok(element.into_value())
 }
 
-/// Set the page size for pagination
-/// gui_set_page_size(element, size) -> new_element
-fn gui_set_page_size(args: &[Value]) -> NativeResult {
+/// Set the key a table keeps its scroll position, sort, column widths and
+/// selection under, for tables showing the same columns
+/// gui_set_table_id(element, id) -> new_element
+fn gui_set_table_id(args: &[Value]) -> NativeResult {
     if args.len() != 2 {
-        return Err("gui_set_page_size requires 2 arguments (element, size)".to_string());
+        return Err("gui_set_table_id requires 2 arguments (element, id)".to_string());
     }
 
     let mut element = clone_gui_element(&args[0])?;
-    let size = get_int(args, 1, "size")?;
+    let id = get_string(args, 1, "id")?;
 
-    match &mut element.kind {
-        GuiElementKind::DataTable(config) => {
-            config.page_size = if size > 0 { Some(size as usize) } else { None };
-        }
-        GuiElementKind::CubeTable(config) => {
-            config.page_size = if size > 0 { Some(size as usize) } else { None };
-        }
-        _ => {
-            return Err(
-                "gui_set_page_size can only be applied to DataTable or CubeTable elements"
-                    .to_string(),
-            )
-        }
+    if let GuiElementKind::DataTable(ref mut config) = element.kind {
+        config.id = Some(id);
+    } else {
+        return Err("gui_set_table_id can only be applied to DataTable elements".to_string());
     }
 
     Ok(element.into_value())
 }
 
-/// Set the current page (0-indexed)
-/// gui_set_current_page(element, page) -> new_element
-fn gui_set_current_page(args: &[Value]) -> NativeResult {
+/// Set the row height of a virtualized table in pixels
+/// gui_set_row_height(element, height) -> new_element
+fn gui_set_row_height(args: &[Value]) -> NativeResult {
     if args.len() != 2 {
-        return Err("gui_set_current_page requires 2 arguments (element, page)".to_string());
+        return Err("gui_set_row_height requires 2 arguments (element, height)".to_string());
+    }
+
+    let mut element = clone_gui_element(&args[0])?;
+    let height = get_float(args, 1, "height")? as f32;
+    if height <= 0.0 {
+        return Err("row height must be positive".to_string());
+    }
+
+    if let GuiElementKind::DataTable(ref mut config) = element.kind {
+        config.row_height = height;
+    } else {
+        return Err("gui_set_row_height can only be applied to DataTable elements".to_string());
+    }
+
+    Ok(element.into_value())
+}
+
+/// Enable or disable resizing table columns by dragging their header edge
+/// gui_set_resizable(element, resizable) -> new_element
+fn gui_set_resizable(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err("gui_set_resizable requires 2 arguments (element, resizable)".to_string());
+    }
+
+    let mut element = clone_gui_element(&args[0])?;
+    let resizable = match &args[1] {
+        Value::Bool(b) => *b,
+        _ => {
+            return Err(format!(
+                "resizable must be a boolean, got {}",
+                args[1].type_name()
+            ))
+        }
+    };
+
+    if let GuiElementKind::DataTable(ref mut config) = element.kind {
+        config.resizable = resizable;
+    } else {
+        return Err("gui_set_resizable can only be applied to DataTable elements".to_string());
+    }
+
+    Ok(element.into_value())
+}
+
+// ==================== Tree and Section Functions ====================
+
+/// Create a tree view from a list of nodes. A node is a string (a leaf) or
+/// a map with `label` and optional `id`, `icon`, `children` and
+/// `has_children` (children loaded by the on_expand callback).
+/// gui_tree(nodes) -> tree element
+fn gui_tree(args: &[Value]) -> NativeResult {
+    let nodes = match args.first() {
+        Some(Value::List(list)) => nodes_from_list(&list.borrow())?,
+        Some(other) => return Err(format!("nodes must be a list, got {}", other.type_name())),
+        None => return Err("gui_tree requires 1 argument (nodes)".to_string()),
+    };
+
+    Ok(GuiElement::tree(nodes).build().into_value())
+}
+
+/// Create a collapsible section with a title and children
+/// gui_section(title) or gui_section(title, children) or gui_section(title, children, expanded)
+fn gui_section(args: &[Value]) -> NativeResult {
+    let title = get_string(args, 0, "title")?;
+    let mut builder = GuiElement::section(title);
+
+    if let Some(Value::Bool(expanded)) = args.get(2) {
+        builder = builder.expanded(*expanded);
+    }
+
+    let mut element = builder.build();
+    match args.get(1) {
+        Some(child @ Value::GuiElement(_)) => {
+            element.children = vec![Arc::new(clone_gui_element(child)?)];
+        }
+        Some(Value::Null) | None => {}
+        Some(children) => {
+            let children = collect_children(children)?;
+            element.children = children.into_iter().map(Arc::new).collect();
+        }
+    }
+
+    Ok(element.into_value())
+}
+
+/// Set the key a tree or section keeps its expanded state under
+/// gui_set_key(element, key) -> new_element
+fn gui_set_key(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err("gui_set_key requires 2 arguments (element, key)".to_string());
+    }
+
+    let mut element = clone_gui_element(&args[0])?;
+    let key = get_string(args, 1, "key")?;
+
+    match &mut element.kind {
+        GuiElementKind::Tree(config) => config.id = Some(key),
+        GuiElementKind::Section(config) => config.id = Some(key),
+        GuiElementKind::DataTable(config) => config.id = Some(key),
+        _ => {
+            return Err(
+                "gui_set_key can only be applied to Tree, Section, or DataTable elements"
+                    .to_string(),
+            )
+        }
+    }
+
+    Ok(element.into_value())
+}
+
+/// Set what is expanded when first shown: node ids for a tree, or a
+/// boolean for a section
+/// gui_set_expanded(element, expanded) -> new_element
+fn gui_set_expanded(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err("gui_set_expanded requires 2 arguments (element, expanded)".to_string());
+    }
+
+    let mut element = clone_gui_element(&args[0])?;
+
+    match (&mut element.kind, &args[1]) {
+        (GuiElementKind::Tree(config), ids) => config.expanded = string_list(ids, "expanded")?,
+        (GuiElementKind::Section(config), Value::Bool(expanded)) => config.expanded = *expanded,
+        (GuiElementKind::Section(_), other) => {
+            return Err(format!(
+                "expanded must be a boolean, got {}",
+                other.type_name()
+            ))
+        }
+        _ => {
+            return Err(
+                "gui_set_expanded can only be applied to Tree or Section elements".to_string(),
+            )
+        }
+    }
+
+    Ok(element.into_value())
+}
+
+/// Set the node selected when a tree is first shown
+/// gui_set_selected_node(element, node_id) -> new_element
+fn gui_set_selected_node(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err("gui_set_selected_node requires 2 arguments (element, node_id)".to_string());
+    }
+
+    let mut element = clone_gui_element(&args[0])?;
+    let node = get_string(args, 1, "node_id")?;
+
+    if let GuiElementKind::Tree(ref mut config) = element.kind {
+        config.selected = Some(node);
+    } else {
+        return Err("gui_set_selected_node can only be applied to Tree elements".to_string());
+    }
+
+    Ok(element.into_value())
+}
+
+/// Set the indentation per tree level in pixels
+/// gui_set_indent(element, indent) -> new_element
+fn gui_set_indent(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err("gui_set_indent requires 2 arguments (element, indent)".to_string());
+    }
+
+    let mut element = clone_gui_element(&args[0])?;
+    let indent = get_float(args, 1, "indent")? as f32;
+
+    if let GuiElementKind::Tree(ref mut config) = element.kind {
+        config.indent = indent.max(0.0);
+    } else {
+        return Err("gui_set_indent can only be applied to Tree elements".to_string());
+    }
+
+    Ok(element.into_value())
+}
+
+/// Set the callback for a tree node expanding, called with the node id.
+/// Load the node's children here.
+/// gui_on_expand(element, callback_id) -> new_element
+fn gui_on_expand(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err("gui_on_expand requires 2 arguments (element, callback_id)".to_string());
+    }
+
+    let mut element = clone_gui_element(&args[0])?;
+    let callback_id = get_callback_id(&args[1])?;
+
+    if let GuiElementKind::Tree(ref mut config) = element.kind {
+        config.on_expand = Some(callback_id);
+    } else {
+        return Err("gui_on_expand can only be applied to Tree elements".to_string());
+    }
+
+    Ok(element.into_value())
+}
+
+/// Set the callback for a tree node being right-clicked, called with the node id
+/// gui_on_context_menu(element, callback_id) -> new_element
+fn gui_on_context_menu(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err("gui_on_context_menu requires 2 arguments (element, callback_id)".to_string());
+    }
+
+    let mut element = clone_gui_element(&args[0])?;
+    let callback_id = get_callback_id(&args[1])?;
+
+    if let GuiElementKind::Tree(ref mut config) = element.kind {
+        config.on_context_menu = Some(callback_id);
+    } else {
+        return Err("gui_on_context_menu can only be applied to Tree elements".to_string());
+    }
+
+    Ok(element.into_value())
+}
+
+// ==================== Canvas Functions ====================
+
+/// Create a canvas drawn by a callback, which receives a drawing context
+/// gui_canvas(width, height) or gui_canvas(width, height, draw)
+fn gui_canvas(args: &[Value]) -> NativeResult {
+    if args.len() < 2 {
+        return Err("gui_canvas requires at least 2 arguments (width, height)".to_string());
+    }
+
+    let width = get_float(args, 0, "width")? as f32;
+    let height = get_float(args, 1, "height")? as f32;
+    let mut builder = GuiElement::canvas(width, height);
+
+    if let Some(draw) = args.get(2) {
+        builder = builder.on_draw(get_callback(draw)?);
+    }
+
+    Ok(builder.build().into_value())
+}
+
+/// Set the callback that draws a canvas
+/// gui_on_draw(element, callback) -> new_element
+fn gui_on_draw(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err("gui_on_draw requires 2 arguments (element, callback)".to_string());
+    }
+
+    let mut element = clone_gui_element(&args[0])?;
+    let callback_id = get_callback(&args[1])?;
+
+    if let GuiElementKind::Canvas(ref mut config) = element.kind {
+        config.draw = Some(callback_id);
+    } else {
+        return Err("gui_on_draw can only be applied to Canvas elements".to_string());
+    }
+
+    Ok(element.into_value())
+}
+
+/// Set the callback for mouse and keyboard input over a canvas, called with
+/// an event map
+/// gui_on_canvas_event(element, callback) -> new_element
+fn gui_on_canvas_event(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err("gui_on_canvas_event requires 2 arguments (element, callback)".to_string());
+    }
+
+    let mut element = clone_gui_element(&args[0])?;
+    let callback_id = get_callback(&args[1])?;
+
+    if let GuiElementKind::Canvas(ref mut config) = element.kind {
+        config.on_event = Some(callback_id);
+    } else {
+        return Err("gui_on_canvas_event can only be applied to Canvas elements".to_string());
+    }
+
+    Ok(element.into_value())
+}
+
+// ==================== Menu Functions ====================
+
+// Helper to extract a list of menu items
+fn get_menu_items(value: &Value, name: &str) -> Result<Vec<MenuItem>, String> {
+    match value {
+        Value::List(list) => menu_items_from_list(&list.borrow()),
+        other => Err(format!("{name} must be a list, got {}", other.type_name())),
+    }
+}
+
+/// Create a menu bar from a list of menus. Each menu is a map with a
+/// `label` and `items`; each item has a `label` and optional `on_select`,
+/// `shortcut`, `disabled` and `items` for a submenu. `"-"` is a separator.
+/// gui_menu_bar(menus) -> menu bar element
+fn gui_menu_bar(args: &[Value]) -> NativeResult {
+    let menus = match args.first() {
+        Some(value) => get_menu_items(value, "menus")?,
+        None => return Err("gui_menu_bar requires 1 argument (menus)".to_string()),
+    };
+
+    Ok(GuiElement::menu_bar(menus).build().into_value())
+}
+
+/// Show a menu when an element is right-clicked
+/// gui_context_menu(element, items) -> new_element
+fn gui_context_menu(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err("gui_context_menu requires 2 arguments (element, items)".to_string());
+    }
+
+    let element = clone_gui_element(&args[0])?;
+    let items = get_menu_items(&args[1], "items")?;
+
+    Ok(GuiElement::context_menu(element, items)
+        .build()
+        .into_value())
+}
+
+/// Show an icon in the system tray with a menu. The icon appears once the
+/// app is running.
+/// gui_tray(icon_path, items) or gui_tray(icon_path, items, tooltip)
+fn gui_tray(args: &[Value]) -> NativeResult {
+    if args.len() < 2 {
+        return Err("gui_tray requires at least 2 arguments (icon_path, items)".to_string());
+    }
+
+    let icon = get_string(args, 0, "icon_path")?;
+    let items = get_menu_items(&args[1], "items")?;
+    let tooltip = get_opt_string(args, 2, "tooltip")?;
+
+    crate::bindings::request_tray(TrayConfig {
+        icon,
+        tooltip,
+        items,
+    });
+    Ok(Value::Null)
+}
+
+// ==================== Dialog Functions ====================
+
+fn path_value(path: &std::path::Path) -> Value {
+    Value::string(path.to_string_lossy().into_owned())
+}
+
+// Helper to extract an optional string argument, treating null as absent
+fn get_opt_string(args: &[Value], index: usize, name: &str) -> Result<Option<String>, String> {
+    match args.get(index) {
+        Some(Value::Null) | None => Ok(None),
+        Some(_) => get_string(args, index, name).map(Some),
+    }
+}
+
+/// Ask the user for a file to open; returns its path, or null if cancelled.
+/// With `multiple`, returns a list of paths.
+/// gui_open_file_dialog() or gui_open_file_dialog(filters) or
+/// gui_open_file_dialog(filters, multiple)
+fn gui_open_file_dialog(args: &[Value]) -> NativeResult {
+    let filters = FileFilter::from_value(args.first().unwrap_or(&Value::Null))?;
+    let multiple = matches!(args.get(1), Some(Value::Bool(true)));
+
+    if multiple {
+        Ok(match dialogs::open_files(&filters, None) {
+            Some(paths) => Value::list(paths.iter().map(|p| path_value(p)).collect()),
+            None => Value::Null,
+        })
+    } else {
+        Ok(dialogs::open_file(&filters, None).map_or(Value::Null, |p| path_value(&p)))
+    }
+}
+
+/// Ask the user where to save a file; returns the path, or null if cancelled
+/// gui_save_file_dialog() or gui_save_file_dialog(file_name) or
+/// gui_save_file_dialog(file_name, filters)
+fn gui_save_file_dialog(args: &[Value]) -> NativeResult {
+    let file_name = get_opt_string(args, 0, "file_name")?;
+    let filters = FileFilter::from_value(args.get(1).unwrap_or(&Value::Null))?;
+
+    Ok(dialogs::save_file(&filters, file_name.as_deref()).map_or(Value::Null, |p| path_value(&p)))
+}
+
+/// Ask the user for a folder; returns its path, or null if cancelled
+/// gui_pick_folder() or gui_pick_folder(title)
+fn gui_pick_folder(args: &[Value]) -> NativeResult {
+    let title = get_opt_string(args, 0, "title")?;
+
+    Ok(dialogs::pick_folder(title.as_deref()).map_or(Value::Null, |p| path_value(&p)))
+}
+
+// Helper to extract a dialog's title and message: (message) or (title, message)
+fn dialog_text(args: &[Value], function: &str) -> Result<(String, String), String> {
+    match args.len() {
+        1 => Ok((String::new(), get_string(args, 0, "message")?)),
+        2 => Ok((
+            get_string(args, 0, "title")?,
+            get_string(args, 1, "message")?,
+        )),
+        _ => Err(format!(
+            "{function} requires 1 or 2 arguments (title?, message)"
+        )),
+    }
+}
+
+/// Show a message and wait for the user to dismiss it
+/// gui_alert(message) or gui_alert(title, message)
+fn gui_alert(args: &[Value]) -> NativeResult {
+    let (title, message) = dialog_text(args, "gui_alert")?;
+    dialogs::alert(&title, &message);
+    Ok(Value::Null)
+}
+
+/// Ask the user to confirm; returns true for OK and false for Cancel
+/// gui_confirm(message) or gui_confirm(title, message)
+fn gui_confirm(args: &[Value]) -> NativeResult {
+    let (title, message) = dialog_text(args, "gui_confirm")?;
+    Ok(Value::Bool(dialogs::confirm(&title, &message)))
+}
+
+// Helper to extract a field path given as a string or state binding
+fn get_field_path(value: &Value) -> Result<String, String> {
+    match value {
+        Value::String(s) => Ok(s.to_string()),
+        other => get_state_binding_path(other).ok_or_else(|| {
+            format!(
+                "field must be a field path or state binding, got {}",
+                other.type_name()
+            )
+        }),
+    }
+}
+
+/// Attach validation rules to a field, given directly or as the input bound
+/// to it. Returns its first argument.
+/// gui_validate(element_or_field, rules)
+fn gui_validate(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err("gui_validate requires 2 arguments (element, rules)".to_string());
+    }
+
+    let field = if let Value::GuiElement(_) = &args[0] {
+        let element = clone_gui_element(&args[0])?;
+        form::bound_field(&element.kind)
+            .map(str::to_string)
+            .ok_or("gui_validate needs an input bound to a state field")?
+    } else {
+        get_field_path(&args[0])?
+    };
+    form::set_rules(&field, FieldRules::from_value(&args[1])?);
+
+    Ok(args[0].clone())
+}
+
+/// Create an element showing a field's validation error once the user has
+/// changed the field, or after gui_form_valid()
+/// gui_field_error(field) -> element
+fn gui_field_error(args: &[Value]) -> NativeResult {
+    let field = get_field_path(args.first().unwrap_or(&Value::Null))?;
+    Ok(GuiElement::field_error(field).build().into_value())
+}
+
+/// Whether every validated field passes its rules; shows all errors
+/// gui_form_valid() -> bool
+fn gui_form_valid(_args: &[Value]) -> NativeResult {
+    Ok(Value::Bool(form::form_valid()))
+}
+
+/// Errors of all validated fields, by field path
+/// gui_form_errors() -> map
+fn gui_form_errors(_args: &[Value]) -> NativeResult {
+    let errors = Value::empty_map();
+    if let Value::Map(ref map) = errors {
+        let mut map = map.borrow_mut();
+        for (field, error) in form::form_errors() {
+            map.insert(
+                stratum_core::bytecode::HashableValue::String(field.into()),
+                Value::string(error),
+            );
+        }
+    }
+    Ok(errors)
+}
+
+/// Hide validation errors until fields are changed again
+/// gui_reset_validation()
+fn gui_reset_validation(_args: &[Value]) -> NativeResult {
+    form::reset_validation();
+    Ok(Value::Null)
+}
+
+/// Create an element showing formatted markdown text
+/// gui_markdown(text) -> element
+fn gui_markdown(args: &[Value]) -> NativeResult {
+    let source = get_string(args, 0, "text")?;
+    Ok(GuiElement::markdown(source).build().into_value())
+}
+
+/// Set the callback for a clicked link in markdown, called with the URL
+/// gui_on_link(element, callback) -> new_element
+fn gui_on_link(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err("gui_on_link requires 2 arguments (element, callback)".to_string());
+    }
+
+    let mut element = clone_gui_element(&args[0])?;
+    let callback_id = get_callback(&args[1])?;
+
+    if let GuiElementKind::Markdown(ref mut config) = element.kind {
+        config.on_link = Some(callback_id);
+    } else {
+        return Err("gui_on_link can only be applied to Markdown elements".to_string());
+    }
+
+    Ok(element.into_value())
+}
+
+// Helper to extract a callback kept as a value: a callback id or a function
+fn get_handler(value: &Value, name: &str) -> Result<Value, String> {
+    match value {
+        Value::Int(id) if *id >= 0 => Ok(value.clone()),
+        Value::Closure(_) | Value::NativeFunction(_) => Ok(value.clone()),
+        other => Err(format!(
+            "{name} must be a function or callback id, got {}",
+            other.type_name()
+        )),
+    }
+}
+
+#[allow(clippy::cast_possible_wrap)]
+fn start_timer(args: &[Value], repeat: bool) -> NativeResult {
+    let interval = get_duration(args, 0)?;
+    let callback = get_handler(args.get(1).unwrap_or(&Value::Null), "callback")?;
+    let timer = Timer::new(interval, repeat, callback, std::time::Instant::now());
+    let id = timer.id;
+    crate::bindings::request_timer(timer);
+    Ok(Value::Int(id as i64))
+}
+
+/// Call back every `ms` milliseconds until cancelled; returns the timer id
+/// gui_every(ms, callback) -> int
+fn gui_every(args: &[Value]) -> NativeResult {
+    if get_duration(args, 0)?.is_zero() {
+        return Err("gui_every needs an interval above 0 ms".to_string());
+    }
+    start_timer(args, true)
+}
+
+/// Call back once after `ms` milliseconds; returns the timer id
+/// gui_after(ms, callback) -> int
+fn gui_after(args: &[Value]) -> NativeResult {
+    start_timer(args, false)
+}
+
+/// Stop a timer started by gui_every() or gui_after()
+/// gui_cancel_timer(id)
+fn gui_cancel_timer(args: &[Value]) -> NativeResult {
+    match args.first() {
+        Some(Value::Int(id)) if *id > 0 => {
+            crate::bindings::request_timer_cancel(id.unsigned_abs());
+            Ok(Value::Null)
+        }
+        Some(other) => Err(format!(
+            "timer id must be a positive integer, got {}",
+            other.type_name()
+        )),
+        None => Err("missing required argument: id".to_string()),
+    }
+}
+
+/// Run `work` on a worker thread, passing its result to `on_complete` or its
+/// error message to `on_error`; returns the task id
+/// gui_spawn_task(work, on_complete?, on_error?) -> int
+#[allow(clippy::cast_possible_wrap)]
+fn gui_spawn_task(args: &[Value]) -> NativeResult {
+    if args.is_empty() || args.len() > 3 {
+        return Err(
+            "gui_spawn_task requires 1 to 3 arguments (work, on_complete?, on_error?)".to_string(),
+        );
+    }
+
+    // The work is copied to a worker thread, so it can't be a callback id
+    let work = match &args[0] {
+        Value::Closure(_) | Value::NativeFunction(_) => args[0].clone(),
+        other => {
+            return Err(format!(
+                "work must be a function, got {}",
+                other.type_name()
+            ))
+        }
+    };
+    let optional = |index: usize, name: &str| match args.get(index) {
+        Some(Value::Null) | None => Ok(None),
+        Some(value) => get_handler(value, name).map(Some),
+    };
+    let task = BackgroundTask::new(work, optional(1, "on_complete")?, optional(2, "on_error")?);
+    let id = task.id;
+    crate::bindings::request_task(task);
+    Ok(Value::Int(id as i64))
+}
+
+/// Declare the pages of the app and create the place the current page is
+/// shown. `pages` maps page names to view functions, called with the state
+/// and the page's parameters, or to maps with `view` and optional
+/// `on_enter`, `on_leave` and `on_back` hooks. `initial` is opened with
+/// `params` when no page is open yet.
+/// gui_navigator(pages, initial, params?) -> element
+fn gui_navigator(args: &[Value]) -> NativeResult {
+    use std::collections::HashMap;
+
+    if args.len() < 2 || args.len() > 3 {
+        return Err(
+            "gui_navigator requires 2 or 3 arguments (pages, initial, params?)".to_string(),
+        );
+    }
+
+    let Value::Map(ref map) = args[0] else {
+        return Err(format!(
+            "pages must be a map of page names to views, got {}",
+            args[0].type_name()
+        ));
+    };
+    let mut pages = HashMap::new();
+    for (name, page) in map.borrow().iter() {
+        let HashableValue::String(name) = name else {
+            return Err("page names must be strings".to_string());
+        };
+        let page = Page::from_value(page).map_err(|e| format!("page '{name}': {e}"))?;
+        pages.insert(name.to_string(), page);
+    }
+    let initial = get_string(args, 1, "initial")?;
+    navigation::set_pages(pages, &initial, args.get(2).cloned().unwrap_or(Value::Null))?;
+
+    Ok(GuiElement::navigator().build().into_value())
+}
+
+/// Open a page on top of the current one
+/// gui_push_page(page, params?)
+fn gui_push_page(args: &[Value]) -> NativeResult {
+    let page = get_string(args, 0, "page")?;
+    navigation::push(&page, args.get(1).cloned().unwrap_or(Value::Null))?;
+    Ok(Value::Null)
+}
+
+/// Show a page in place of the current one
+/// gui_replace_page(page, params?)
+fn gui_replace_page(args: &[Value]) -> NativeResult {
+    let page = get_string(args, 0, "page")?;
+    navigation::replace(&page, args.get(1).cloned().unwrap_or(Value::Null))?;
+    Ok(Value::Null)
+}
+
+/// Close the current page; false if it is the only page open
+/// gui_pop_page() -> bool
+fn gui_pop_page(_args: &[Value]) -> NativeResult {
+    Ok(Value::Bool(navigation::pop()))
+}
+
+/// Close pages down to the first one; false if it is already shown
+/// gui_pop_to_root() -> bool
+fn gui_pop_to_root(_args: &[Value]) -> NativeResult {
+    Ok(Value::Bool(navigation::pop_to_root()))
+}
+
+/// Go back as the back button does, letting the page's on_back hook keep
+/// it open
+/// gui_go_back()
+fn gui_go_back(_args: &[Value]) -> NativeResult {
+    navigation::request_back();
+    Ok(Value::Null)
+}
+
+/// Name of the page shown, or null before a navigator is created
+/// gui_current_page() -> string
+fn gui_current_page(_args: &[Value]) -> NativeResult {
+    Ok(navigation::current().map_or(Value::Null, |route| Value::string(route.page)))
+}
+
+/// Parameters the page shown was opened with
+/// gui_page_params() -> value
+fn gui_page_params(_args: &[Value]) -> NativeResult {
+    Ok(navigation::current().map_or(Value::Null, |route| route.params))
+}
+
+/// Whether there is a page to go back to
+/// gui_can_go_back() -> bool
+fn gui_can_go_back(_args: &[Value]) -> NativeResult {
+    Ok(Value::Bool(navigation::depth() > 1))
+}
+
+// Helper to extract a window number
+fn get_window(args: &[Value], index: usize) -> Result<u64, String> {
+    let number = get_int(args, index, "window")?;
+    u64::try_from(number)
+        .ok()
+        .filter(|number| windows::script_window_exists(*number))
+        .ok_or_else(|| format!("no window {number}"))
+}
+
+// Helper to read window settings from the options of gui_open_window()
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn window_settings(options: &Value) -> Result<WindowSettings, String> {
+    let size = |name: &str| -> Result<Option<u32>, String> {
+        Ok(optional_f32(options, name, "window")?.map(|n| n.max(1.0) as u32))
+    };
+    let flag = |name: &str| match record_field(options, name) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Bool(b)) => Ok(Some(b)),
+        Some(other) => Err(format!(
+            "window.{name} must be a bool, got {}",
+            other.type_name()
+        )),
+    };
+
+    let title = match record_field(options, "title") {
+        None | Some(Value::Null) => "Stratum App".to_string(),
+        Some(Value::String(title)) => title.to_string(),
+        Some(other) => {
+            return Err(format!(
+                "window.title must be a string, got {}",
+                other.type_name()
+            ))
+        }
+    };
+    let mut settings = WindowSettings::new(title)
+        .with_size(400, 300)
+        .with_exit_on_close(false);
+    let (width, height) = settings.size;
+    settings = settings.with_size(
+        size("width")?.unwrap_or(width),
+        size("height")?.unwrap_or(height),
+    );
+    if let (Some(width), Some(height)) = (size("min_width")?, size("min_height")?) {
+        settings = settings.with_min_size(width, height);
+    }
+    if let (Some(x), Some(y)) = (
+        optional_f32(options, "x", "window")?,
+        optional_f32(options, "y", "window")?,
+    ) {
+        settings = settings.with_position(Position::Specific(x, y));
+    }
+    if let Some(resizable) = flag("resizable")? {
+        settings = settings.with_resizable(resizable);
+    }
+    if let Some(exit) = flag("exit_on_close")? {
+        settings = settings.with_exit_on_close(exit);
+    }
+    Ok(settings)
+}
+
+/// Open a window showing `view`, called with the app state and the
+/// window's own state. Options may give the `title`, `width`, `height`,
+/// `min_width`, `min_height`, `x`, `y`, `resizable`, `exit_on_close`, the
+/// window's initial `state` and `on_message`, `on_moved`, `on_resized`,
+/// `on_close_requested` and `on_closed` handlers. Returns the window's
+/// number.
+/// gui_open_window(view, options?) -> int
+#[allow(clippy::cast_possible_wrap)]
+fn gui_open_window(args: &[Value]) -> NativeResult {
+    use std::collections::HashMap;
+
+    if args.is_empty() || args.len() > 2 {
+        return Err("gui_open_window requires 1 or 2 arguments (view, options?)".to_string());
+    }
+    let view = get_handler(&args[0], "view")?;
+    let options = args.get(1).cloned().unwrap_or(Value::Null);
+    match options {
+        Value::Null | Value::Map(_) | Value::Struct(_) => {}
+        ref other => {
+            return Err(format!(
+                "window options must be a map or struct, got {}",
+                other.type_name()
+            ))
+        }
+    }
+
+    let settings = window_settings(&options)?;
+    let state = record_field(&options, "state").unwrap_or(Value::Null);
+    let mut handlers = HashMap::new();
+    for handler in WindowHandler::ALL {
+        let name = format!("on_{}", handler.name());
+        match record_field(&options, &name) {
+            None | Some(Value::Null) => {}
+            Some(callback) => {
+                handlers.insert(handler, get_handler(&callback, &name)?);
+            }
+        }
+    }
+
+    let number = windows::open_script_window(view, settings, state, handlers);
+    Ok(Value::Int(number as i64))
+}
+
+/// Close a window, as if its close button was pressed but without asking
+/// its `on_close_requested` handler
+/// gui_close_window(window) -> bool
+fn gui_close_window(args: &[Value]) -> NativeResult {
+    let number = get_window(args, 0)?;
+    Ok(Value::Bool(windows::close_script_window(number)))
+}
+
+/// Send a message to a window's `on_message` handler, called after the
+/// current event is handled
+/// gui_send_to_window(window, message) -> null
+fn gui_send_to_window(args: &[Value]) -> NativeResult {
+    let number = get_window(args, 0)?;
+    let message = args.get(1).cloned().unwrap_or(Value::Null);
+    windows::send_to_window(number, message);
+    Ok(Value::Null)
+}
+
+/// Handle an event of a window: "message", "moved", "resized",
+/// "close_requested" or "closed". The main window is window 0.
+/// gui_on_window(window, event, handler) -> null
+fn gui_on_window(args: &[Value]) -> NativeResult {
+    let number = get_window(args, 0)?;
+    let event = get_string(args, 1, "event")?;
+    let handler = WindowHandler::from_name(&event).ok_or_else(|| {
+        let names: Vec<&str> = WindowHandler::ALL.iter().map(|h| h.name()).collect();
+        format!(
+            "unknown window event '{event}', expected one of: {}",
+            names.join(", ")
+        )
+    })?;
+    let callback = get_handler(args.get(2).unwrap_or(&Value::Null), "handler")?;
+    windows::set_window_handler(number, handler, callback);
+    Ok(Value::Null)
+}
+
+/// The state of a window opened by gui_open_window(); null for the main
+/// window, whose state is the app's
+/// gui_window_state(window) -> value
+fn gui_window_state(args: &[Value]) -> NativeResult {
+    let number = get_window(args, 0)?;
+    Ok(windows::window_state(number)
+        .map(|state| state.get().clone())
+        .unwrap_or(Value::Null))
+}
+
+/// Set a field of a window's state, by name or dotted path
+/// gui_update_window_field(window, field, value) -> bool
+fn gui_update_window_field(args: &[Value]) -> NativeResult {
+    let number = get_window(args, 0)?;
+    if number == windows::MAIN_WINDOW {
+        return Err(
+            "the main window uses the app state; use gui_update_field() instead".to_string(),
+        );
+    }
+    let field = get_field_path(args.get(1).ok_or("missing required argument: field")?)?;
+    let value = args.get(2).cloned().unwrap_or(Value::Null);
+    let updated =
+        windows::window_state(number).is_some_and(|state| state.update_path(&field, value));
+    Ok(Value::Bool(updated))
+}
+
+/// The number of the window whose view or handler is running
+/// gui_current_window() -> int
+#[allow(clippy::cast_possible_wrap)]
+fn gui_current_window(_args: &[Value]) -> NativeResult {
+    Ok(Value::Int(windows::current_window() as i64))
+}
+
+/// Numbers of the open windows, the main window first
+/// gui_windows() -> list
+#[allow(clippy::cast_possible_wrap)]
+fn gui_windows(_args: &[Value]) -> NativeResult {
+    Ok(Value::list(
+        windows::script_window_numbers()
+            .into_iter()
+            .map(|number| Value::Int(number as i64))
+            .collect(),
+    ))
+}
+
+// Helper to extract a duration in milliseconds
+fn get_duration(args: &[Value], index: usize) -> Result<std::time::Duration, String> {
+    let ms = get_float(args, index, "duration")?;
+    if ms < 0.0 || !ms.is_finite() {
+        return Err(format!(
+            "duration must be a non-negative number of milliseconds, got {ms}"
+        ));
+    }
+    Ok(std::time::Duration::from_secs_f64(ms / 1000.0))
+}
+
+// Helper to extract an optional easing name, ease_in_out by default
+fn get_easing(args: &[Value], index: usize) -> Result<Easing, String> {
+    match get_opt_string(args, index, "easing")? {
+        None => Ok(Easing::default()),
+        Some(name) => Easing::parse(&name).ok_or_else(|| {
+            let names: Vec<&str> = Easing::ALL.iter().map(|e| e.name()).collect();
+            format!(
+                "unknown easing '{name}'; expected one of {}",
+                names.join(", ")
+            )
+        }),
+    }
+}
+
+/// Animate a numeric state field to a value over a duration in milliseconds
+/// gui_animate(field, to, duration) or gui_animate(field, to, duration, easing)
+fn gui_animate(args: &[Value]) -> NativeResult {
+    if args.len() < 3 || args.len() > 4 {
+        return Err(
+            "gui_animate requires 3 or 4 arguments (field, to, duration, easing?)".to_string(),
+        );
+    }
+
+    let field = get_field_path(&args[0])?;
+    let to = get_float(args, 1, "to")?;
+    let duration = get_duration(args, 2)?;
+    let easing = get_easing(args, 3)?;
+
+    crate::bindings::request_animation(Animation::new(field, to, duration, easing));
+    Ok(Value::Null)
+}
+
+/// Animate changes to an element's padding, fixed sizes, colors and borders
+/// gui_set_transition(element, duration) or gui_set_transition(element, duration, easing)
+fn gui_set_transition(args: &[Value]) -> NativeResult {
+    if args.len() < 2 || args.len() > 3 {
+        return Err(
+            "gui_set_transition requires 2 or 3 arguments (element, duration, easing?)".to_string(),
+        );
+    }
+
+    let mut element = clone_gui_element(&args[0])?;
+    let duration = get_duration(args, 1)?;
+    let easing = get_easing(args, 2)?;
+    element.style.transition = Some(Transition::new(duration, easing));
+
+    Ok(element.into_value())
+}
+
+// ==================== Accessibility ====================
+
+/// Set the label screen readers announce for an element
+/// gui_set_access_label(element, label) -> new_element
+fn gui_set_access_label(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err("gui_set_access_label requires 2 arguments (element, label)".to_string());
+    }
+
+    let mut element = clone_gui_element(&args[0])?;
+    element.style.access.label = Some(get_string(args, 1, "label")?);
+    Ok(element.into_value())
+}
+
+/// Set the role screen readers announce for an element
+/// gui_set_access_role(element, role) -> new_element
+fn gui_set_access_role(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err("gui_set_access_role requires 2 arguments (element, role)".to_string());
+    }
+
+    let mut element = clone_gui_element(&args[0])?;
+    let name = get_string(args, 1, "role")?;
+    let role = Role::from_name(&name).ok_or_else(|| format!("unknown role: {name}"))?;
+    element.style.access.role = Some(role);
+    Ok(element.into_value())
+}
+
+/// Set the description screen readers read after an element's label
+/// gui_set_access_description(element, description) -> new_element
+fn gui_set_access_description(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err(
+            "gui_set_access_description requires 2 arguments (element, description)".to_string(),
+        );
+    }
+
+    let mut element = clone_gui_element(&args[0])?;
+    element.style.access.description = Some(get_string(args, 1, "description")?);
+    Ok(element.into_value())
+}
+
+/// Set an element's place in the tab order; negative leaves it out
+/// gui_set_tab_index(element, index) -> new_element
+fn gui_set_tab_index(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err("gui_set_tab_index requires 2 arguments (element, index)".to_string());
+    }
+
+    let mut element = clone_gui_element(&args[0])?;
+    let index = get_int(args, 1, "index")?;
+    let index = i32::try_from(index).map_err(|_| format!("tab index out of range: {index}"))?;
+    element.style.access.tab_index = Some(index);
+    Ok(element.into_value())
+}
+
+/// Keep a state field set to the label of the focused element; setting the
+/// field to another label moves focus there
+/// gui_bind_focus(field) -> null
+fn gui_bind_focus(args: &[Value]) -> NativeResult {
+    let field = get_field_path(args.first().ok_or("missing required argument: field")?)?;
+    accessibility::bind_focus(field);
+    Ok(Value::Null)
+}
+
+/// Names of the easings animations and transitions accept
+/// gui_easings() -> list
+fn gui_easings(_args: &[Value]) -> NativeResult {
+    Ok(Value::list(
+        Easing::ALL
+            .iter()
+            .map(|e| Value::string(e.name()))
+            .collect(),
+    ))
+}
+
+/// Read text from the system clipboard; null if it holds no text
+/// gui_clipboard_read() -> string
+fn gui_clipboard_read(_args: &[Value]) -> NativeResult {
+    Ok(crate::clipboard::read_text()?.map_or(Value::Null, Value::string))
+}
+
+/// Put text on the system clipboard
+/// gui_clipboard_write(text)
+fn gui_clipboard_write(args: &[Value]) -> NativeResult {
+    let text = get_string(args, 0, "text")?;
+    crate::clipboard::write_text(&text)?;
+    Ok(Value::Null)
+}
+
+/// Let an element be dragged onto drop targets, carrying a payload. The kind
+/// defaults to the payload's struct name or type.
+/// gui_draggable(element, payload) or gui_draggable(element, payload, kind)
+fn gui_draggable(args: &[Value]) -> NativeResult {
+    if args.len() < 2 || args.len() > 3 {
+        return Err(
+            "gui_draggable requires 2 or 3 arguments (element, payload, kind?)".to_string(),
+        );
+    }
+
+    let element = clone_gui_element(&args[0])?;
+    let kind = match get_opt_string(args, 2, "kind")? {
+        Some(kind) => kind,
+        None => default_kind(&args[1]),
+    };
+    let payload = register_payload(args[1].clone());
+
+    Ok(GuiElement::draggable(element, kind, payload)
+        .build()
+        .into_value())
+}
+
+/// Let payloads be dropped on an element. The callback receives the payload
+/// and its kind; `accepts` limits the kinds taken.
+/// gui_drop_target(element, on_drop) or gui_drop_target(element, on_drop, accepts)
+fn gui_drop_target(args: &[Value]) -> NativeResult {
+    if args.len() < 2 || args.len() > 3 {
+        return Err(
+            "gui_drop_target requires 2 or 3 arguments (element, on_drop, accepts?)".to_string(),
+        );
+    }
+
+    let element = clone_gui_element(&args[0])?;
+    let callback_id = get_callback(&args[1])?;
+    let accepts = match args.get(2) {
+        Some(Value::String(kind)) => vec![kind.to_string()],
+        Some(Value::Null) | None => Vec::new(),
+        Some(value) => string_list(value, "accepts")?,
+    };
+
+    Ok(GuiElement::drop_target(element, accepts)
+        .on_drop(callback_id)
+        .build()
+        .into_value())
+}
+
+/// Set the drop callback of a drop target
+/// gui_on_drop(element, callback) -> new_element
+fn gui_on_drop(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err("gui_on_drop requires 2 arguments (element, callback)".to_string());
+    }
+
+    let mut element = clone_gui_element(&args[0])?;
+    let callback_id = get_callback(&args[1])?;
+
+    if let GuiElementKind::DropTarget(ref mut config) = element.kind {
+        config.on_drop = Some(callback_id);
+    } else {
+        return Err("gui_on_drop can only be applied to DropTarget elements".to_string());
+    }
+
+    Ok(element.into_value())
+}
+
+// Helper to register a window file drop callback
+fn file_drop_callback(args: &[Value], event: FileDropEvent) -> NativeResult {
+    let callback = args.first().ok_or("missing required argument: callback")?;
+    crate::bindings::request_file_drop_callback(event, get_callback(callback)?);
+    Ok(Value::Null)
+}
+
+/// Call back with the paths of files dropped on the window
+/// gui_on_file_drop(callback)
+fn gui_on_file_drop(args: &[Value]) -> NativeResult {
+    file_drop_callback(args, FileDropEvent::Drop)
+}
+
+/// Call back with the paths of files dragged over the window
+/// gui_on_file_hover(callback)
+fn gui_on_file_hover(args: &[Value]) -> NativeResult {
+    file_drop_callback(args, FileDropEvent::Hover)
+}
+
+/// Call back when dragged files leave the window without being dropped
+/// gui_on_file_hover_left(callback)
+fn gui_on_file_hover_left(args: &[Value]) -> NativeResult {
+    file_drop_callback(args, FileDropEvent::HoverLeft)
+}
+
+// Helper to extract a list of strings
+fn string_list(value: &Value, name: &str) -> Result<Vec<String>, String> {
+    let Value::List(list) = value else {
+        return Err(format!("{name} must be a list, got {}", value.type_name()));
+    };
+    list.borrow()
+        .iter()
+        .map(|item| match item {
+            Value::String(s) => Ok(s.to_string()),
+            _ => Err(format!(
+                "{name} must contain strings, got {}",
+                item.type_name()
+            )),
+        })
+        .collect()
+}
+
+/// Set the columns to display in a data table
+/// gui_set_table_columns(element, columns) -> new_element
+fn gui_set_table_columns(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err("gui_set_table_columns requires 2 arguments (element, columns)".to_string());
+    }
+
+    let mut element = clone_gui_element(&args[0])?;
+
+    // Extract column names from list
+    let columns = match &args[1] {
+        Value::List(list) => {
+            let list = list.borrow();
+            let mut cols = Vec::new();
+            for item in list.iter() {
+                if let Value::String(s) = item {
+                    cols.push(s.to_string());
+                } else {
+                    return Err(format!(
+                        "column name must be a string, got {}",
+                        item.type_name()
+                    ));
+                }
+            }
+            cols
+        }
+        _ => {
+            return Err(format!(
+                "columns must be a list, got {}",
+                args[1].type_name()
+            ))
+        }
+    };
+
+    if let GuiElementKind::DataTable(ref mut config) = element.kind {
+        config.columns = Some(columns);
+    } else {
+        return Err("gui_set_table_columns can only be applied to DataTable elements".to_string());
+    }
+
+    Ok(element.into_value())
+}
+
+/// Set the page size for pagination
+/// gui_set_page_size(element, size) -> new_element
+fn gui_set_page_size(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err("gui_set_page_size requires 2 arguments (element, size)".to_string());
+    }
+
+    let mut element = clone_gui_element(&args[0])?;
+    let size = get_int(args, 1, "size")?;
+
+    match &mut element.kind {
+        GuiElementKind::DataTable(config) => {
+            config.page_size = if size > 0 { Some(size as usize) } else { None };
+        }
+        GuiElementKind::CubeTable(config) => {
+            config.page_size = if size > 0 { Some(size as usize) } else { None };
+        }
+        _ => {
+            return Err(
+                "gui_set_page_size can only be applied to DataTable or CubeTable elements"
+                    .to_string(),
+            )
+        }
+    }
+
+    Ok(element.into_value())
+}
+
+/// Set the current page (0-indexed)
+/// gui_set_current_page(element, page) -> new_element
+fn gui_set_current_page(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err("gui_set_current_page requires 2 arguments (element, page)".to_string());
     }
 
     let mut element = clone_gui_element(&args[0])?;
@@ -2372,7 +3966,12 @@ fn gui_set_chart_title(args: &[Value]) -> NativeResult {
         GuiElementKind::LineChart(c) => c.title = Some(title),
         GuiElementKind::PieChart(c) => c.title = Some(title),
         GuiElementKind::CubeChart(c) => c.title = Some(title),
-        _ => return Err("gui_set_chart_title can only be applied to chart elements".to_string()),
+        kind => match kind.plot_options_mut() {
+            Some(options) => options.title = Some(title),
+            None => {
+                return Err("gui_set_chart_title can only be applied to chart elements".to_string())
+            }
+        },
     }
 
     Ok(element.into_value())
@@ -2406,7 +4005,15 @@ fn gui_set_chart_size(args: &[Value]) -> NativeResult {
             c.width = width;
             c.height = height;
         }
-        _ => return Err("gui_set_chart_size can only be applied to chart elements".to_string()),
+        kind => match kind.plot_options_mut() {
+            Some(options) => {
+                options.width = width;
+                options.height = height;
+            }
+            None => {
+                return Err("gui_set_chart_size can only be applied to chart elements".to_string())
+            }
+        },
     }
 
     Ok(element.into_value())
@@ -2501,7 +4108,8 @@ fn gui_set_chart_data_arrays(args: &[Value]) -> NativeResult {
     Ok(element.into_value())
 }
 
-/// Add a data series to a LineChart
+/// Add a data series to a LineChart, AreaChart, StackedBarChart, or to a
+/// ScatterChart as a list of [x, y] points
 /// gui_add_chart_series(element, name, values) -> new_element
 fn gui_add_chart_series(args: &[Value]) -> NativeResult {
     if args.len() != 3 {
@@ -2513,6 +4121,12 @@ fn gui_add_chart_series(args: &[Value]) -> NativeResult {
     let mut element = clone_gui_element(&args[0])?;
     let name = get_string(args, 1, "name")?;
 
+    if let GuiElementKind::ScatterChart(c) = &mut element.kind {
+        c.series
+            .push(ScatterSeries::new(name, parse_scatter_points(&args[2])?));
+        return Ok(element.into_value());
+    }
+
     let values = match &args[2] {
         Value::List(list) => {
             let list = list.borrow();
@@ -2527,16 +4141,24 @@ fn gui_add_chart_series(args: &[Value]) -> NativeResult {
         _ => return Err("values must be a list of numbers".to_string()),
     };
 
-    if let GuiElementKind::LineChart(c) = &mut element.kind {
-        c.series.push(DataSeries::new(name, values));
-    } else {
-        return Err("gui_add_chart_series can only be applied to LineChart".to_string());
+    let series = DataSeries::new(name, values);
+    match &mut element.kind {
+        GuiElementKind::LineChart(c) => c.series.push(series),
+        GuiElementKind::AreaChart(c) => c.series.push(series),
+        GuiElementKind::StackedBarChart(c) => c.series.push(series),
+        _ => {
+            return Err(
+                "gui_add_chart_series can only be applied to LineChart, AreaChart, \
+                 StackedBarChart or ScatterChart"
+                    .to_string(),
+            )
+        }
     }
 
     Ok(element.into_value())
 }
 
-/// Set x-axis labels for a LineChart
+/// Set x-axis labels for a LineChart, AreaChart or StackedBarChart
 /// gui_set_chart_labels(element, labels) -> new_element
 fn gui_set_chart_labels(args: &[Value]) -> NativeResult {
     if args.len() != 2 {
@@ -2558,10 +4180,14 @@ fn gui_set_chart_labels(args: &[Value]) -> NativeResult {
         _ => return Err("labels must be a list of strings".to_string()),
     };
 
-    if let GuiElementKind::LineChart(c) = &mut element.kind {
-        c.labels = labels;
-    } else {
-        return Err("gui_set_chart_labels can only be applied to LineChart".to_string());
+    match &mut element.kind {
+        GuiElementKind::LineChart(c) => c.labels = labels,
+        GuiElementKind::AreaChart(c) => c.labels = labels,
+        GuiElementKind::StackedBarChart(c) => c.labels = labels,
+        _ => return Err(
+            "gui_set_chart_labels can only be applied to LineChart, AreaChart or StackedBarChart"
+                .to_string(),
+        ),
     }
 
     Ok(element.into_value())
@@ -2585,7 +4211,12 @@ fn gui_set_show_legend(args: &[Value]) -> NativeResult {
         GuiElementKind::LineChart(c) => c.show_legend = show,
         GuiElementKind::PieChart(c) => c.show_legend = show,
         GuiElementKind::CubeChart(c) => c.show_legend = show,
-        _ => return Err("gui_set_show_legend can only be applied to chart elements".to_string()),
+        kind => match kind.plot_options_mut() {
+            Some(options) => options.show_legend = show,
+            None => {
+                return Err("gui_set_show_legend can only be applied to chart elements".to_string())
+            }
+        },
     }
 
     Ok(element.into_value())
@@ -2608,18 +4239,21 @@ fn gui_set_show_grid(args: &[Value]) -> NativeResult {
         GuiElementKind::BarChart(c) => c.show_grid = show,
         GuiElementKind::LineChart(c) => c.show_grid = show,
         GuiElementKind::CubeChart(c) => c.show_grid = show,
-        _ => {
-            return Err(
-                "gui_set_show_grid can only be applied to BarChart, LineChart, or CubeChart"
-                    .to_string(),
-            )
-        }
+        kind => match kind.plot_options_mut() {
+            Some(options) => options.show_grid = show,
+            None => {
+                return Err(
+                    "gui_set_show_grid can't be applied to PieChart or non-chart elements"
+                        .to_string(),
+                )
+            }
+        },
     }
 
     Ok(element.into_value())
 }
 
-/// Set bar color for BarChart
+/// Set bar color for BarChart or Histogram
 /// gui_set_bar_color(element, r, g, b) -> new_element
 fn gui_set_bar_color(args: &[Value]) -> NativeResult {
     if args.len() != 4 {
@@ -2631,29 +4265,324 @@ fn gui_set_bar_color(args: &[Value]) -> NativeResult {
     let g = get_int(args, 2, "g")? as u8;
     let b = get_int(args, 3, "b")? as u8;
 
-    if let GuiElementKind::BarChart(c) = &mut element.kind {
-        c.bar_color = Some((r, g, b));
-    } else {
-        return Err("gui_set_bar_color can only be applied to BarChart".to_string());
+    match &mut element.kind {
+        GuiElementKind::BarChart(c) => c.bar_color = Some((r, g, b)),
+        GuiElementKind::Histogram(c) => c.bar_color = Some((r, g, b)),
+        _ => {
+            return Err(
+                "gui_set_bar_color can only be applied to BarChart or Histogram".to_string(),
+            )
+        }
+    }
+
+    Ok(element.into_value())
+}
+
+/// Set inner radius ratio for PieChart (donut chart)
+/// gui_set_inner_radius(element, ratio) -> new_element
+fn gui_set_inner_radius(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err("gui_set_inner_radius requires 2 arguments (element, ratio)".to_string());
+    }
+
+    let mut element = clone_gui_element(&args[0])?;
+    let ratio = get_float(args, 1, "ratio")? as f32;
+
+    if let GuiElementKind::PieChart(c) = &mut element.kind {
+        c.inner_radius_ratio = ratio.clamp(0.0, 0.9);
+    } else {
+        return Err("gui_set_inner_radius can only be applied to PieChart".to_string());
+    }
+
+    Ok(element.into_value())
+}
+
+/// Create a ScatterChart element
+/// gui_scatter_chart() or gui_scatter_chart(points) where points is a list of [x, y] pairs
+fn gui_scatter_chart(args: &[Value]) -> NativeResult {
+    let mut series = Vec::new();
+    if let Some(points) = args.first() {
+        series.push(ScatterSeries::new(
+            "Series 1",
+            parse_scatter_points(points)?,
+        ));
+    }
+
+    let element = GuiElement::scatter_chart_with_data(series).build();
+    Ok(element.into_value())
+}
+
+/// Create an AreaChart element
+/// gui_area_chart() or gui_area_chart(labels)
+fn gui_area_chart(args: &[Value]) -> NativeResult {
+    let labels = args.first().map(parse_chart_labels).transpose()?;
+    let element = GuiElement::area_chart_with_data(labels.unwrap_or_default(), Vec::new()).build();
+    Ok(element.into_value())
+}
+
+/// Create a StackedBarChart element
+/// gui_stacked_bar_chart() or gui_stacked_bar_chart(labels)
+fn gui_stacked_bar_chart(args: &[Value]) -> NativeResult {
+    let labels = args.first().map(parse_chart_labels).transpose()?;
+    let element =
+        GuiElement::stacked_bar_chart_with_data(labels.unwrap_or_default(), Vec::new()).build();
+    Ok(element.into_value())
+}
+
+/// Create a Histogram element
+/// gui_histogram() or gui_histogram(values, bins?)
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn gui_histogram(args: &[Value]) -> NativeResult {
+    let values = match args.first() {
+        None => Vec::new(),
+        Some(Value::List(list)) => list
+            .borrow()
+            .iter()
+            .map(|v| match v {
+                Value::Float(f) => Ok(*f),
+                Value::Int(i) => Ok(*i as f64),
+                _ => Err(format!("values must be numeric, got {}", v.type_name())),
+            })
+            .collect::<Result<Vec<f64>, String>>()?,
+        Some(_) => return Err("values must be a list of numbers".to_string()),
+    };
+    let bins = if args.len() > 1 {
+        get_int(args, 1, "bins")?
+    } else {
+        10
+    };
+    if bins < 1 {
+        return Err("bins must be at least 1".to_string());
+    }
+
+    let element = GuiElement::histogram_with_data(values, bins as usize).build();
+    Ok(element.into_value())
+}
+
+/// Create a CandlestickChart element
+/// gui_candlestick_chart() or gui_candlestick_chart(candles) where each
+/// candle is a [label, open, high, low, close] list or a map with those keys
+fn gui_candlestick_chart(args: &[Value]) -> NativeResult {
+    let candles = match args.first() {
+        None => Vec::new(),
+        Some(Value::List(list)) => list
+            .borrow()
+            .iter()
+            .map(parse_candle)
+            .collect::<Result<Vec<Candle>, String>>()?,
+        Some(_) => return Err("candles must be a list".to_string()),
+    };
+
+    let element = GuiElement::candlestick_chart_with_data(candles).build();
+    Ok(element.into_value())
+}
+
+/// Helper to parse one candle of a candlestick chart
+fn parse_candle(value: &Value) -> Result<Candle, String> {
+    let number = |v: Option<Value>, name: &str| match v {
+        Some(Value::Float(f)) => Ok(f),
+        Some(Value::Int(i)) => Ok(i as f64),
+        Some(v) => Err(format!(
+            "candle {name} must be a number, got {}",
+            v.type_name()
+        )),
+        None => Err(format!("candle is missing {name}")),
+    };
+    let label = |v: Option<Value>| match v {
+        Some(Value::String(s)) => s.to_string(),
+        Some(v) => v.to_string(),
+        None => String::new(),
+    };
+
+    match value {
+        Value::List(fields) => {
+            let fields = fields.borrow();
+            if fields.len() != 5 {
+                return Err("candles must be [label, open, high, low, close] lists".to_string());
+            }
+            Ok(Candle::new(
+                label(Some(fields[0].clone())),
+                number(Some(fields[1].clone()), "open")?,
+                number(Some(fields[2].clone()), "high")?,
+                number(Some(fields[3].clone()), "low")?,
+                number(Some(fields[4].clone()), "close")?,
+            ))
+        }
+        Value::Map(_) | Value::Struct(_) => Ok(Candle::new(
+            label(record_field(value, "label")),
+            number(record_field(value, "open"), "open")?,
+            number(record_field(value, "high"), "high")?,
+            number(record_field(value, "low"), "low")?,
+            number(record_field(value, "close"), "close")?,
+        )),
+        v => Err(format!(
+            "candles must be lists or maps, got {}",
+            v.type_name()
+        )),
+    }
+}
+
+/// Helper to parse the [x, y] points of a scatter chart series
+fn parse_scatter_points(value: &Value) -> Result<Vec<(f64, f64)>, String> {
+    let Value::List(list) = value else {
+        return Err("points must be a list of [x, y] pairs".to_string());
+    };
+    list.borrow()
+        .iter()
+        .map(|item| {
+            let Value::List(pair) = item else {
+                return Err("points must be a list of [x, y] pairs".to_string());
+            };
+            let pair = pair.borrow();
+            match (pair.first(), pair.get(1)) {
+                (Some(x), Some(y)) => {
+                    let number = |v: &Value| match v {
+                        Value::Float(f) => Ok(*f),
+                        Value::Int(i) => Ok(*i as f64),
+                        v => Err(format!(
+                            "point coordinates must be numbers, got {}",
+                            v.type_name()
+                        )),
+                    };
+                    Ok((number(x)?, number(y)?))
+                }
+                _ => Err("points must be a list of [x, y] pairs".to_string()),
+            }
+        })
+        .collect()
+}
+
+/// Helper to parse x-axis labels
+fn parse_chart_labels(value: &Value) -> Result<Vec<String>, String> {
+    match value {
+        Value::List(list) => Ok(list
+            .borrow()
+            .iter()
+            .map(|v| match v {
+                Value::String(s) => s.to_string(),
+                v => v.to_string(),
+            })
+            .collect()),
+        _ => Err("labels must be a list of strings".to_string()),
+    }
+}
+
+/// Stack the series of an AreaChart on one another
+/// gui_set_stacked(element, stacked) -> new_element
+fn gui_set_stacked(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err("gui_set_stacked requires 2 arguments (element, stacked)".to_string());
+    }
+
+    let mut element = clone_gui_element(&args[0])?;
+    let stacked = match &args[1] {
+        Value::Bool(b) => *b,
+        _ => return Err("stacked must be a boolean".to_string()),
+    };
+
+    if let GuiElementKind::AreaChart(c) = &mut element.kind {
+        c.stacked = stacked;
+    } else {
+        return Err("gui_set_stacked can only be applied to AreaChart".to_string());
+    }
+
+    Ok(element.into_value())
+}
+
+/// Set the number of bins of a Histogram
+/// gui_set_bins(element, bins) -> new_element
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn gui_set_bins(args: &[Value]) -> NativeResult {
+    if args.len() != 2 {
+        return Err("gui_set_bins requires 2 arguments (element, bins)".to_string());
+    }
+
+    let mut element = clone_gui_element(&args[0])?;
+    let bins = get_int(args, 1, "bins")?;
+    if bins < 1 {
+        return Err("bins must be at least 1".to_string());
+    }
+
+    if let GuiElementKind::Histogram(c) = &mut element.kind {
+        c.bins = bins as usize;
+    } else {
+        return Err("gui_set_bins can only be applied to Histogram".to_string());
+    }
+
+    Ok(element.into_value())
+}
+
+// Helper to set a boolean option of the interactive charts
+fn set_plot_flag(
+    args: &[Value],
+    native: &str,
+    set: fn(&mut crate::charts::PlotOptions, bool),
+) -> NativeResult {
+    if args.len() != 2 {
+        return Err(format!("{native} requires 2 arguments (element, enabled)"));
+    }
+
+    let mut element = clone_gui_element(&args[0])?;
+    let enabled = match &args[1] {
+        Value::Bool(b) => *b,
+        _ => return Err("enabled must be a boolean".to_string()),
+    };
+
+    match element.kind.plot_options_mut() {
+        Some(options) => set(options, enabled),
+        None => {
+            return Err(format!(
+                "{native} can only be applied to scatter, area, stacked bar, histogram or \
+                 candlestick charts"
+            ))
+        }
     }
 
     Ok(element.into_value())
 }
 
-/// Set inner radius ratio for PieChart (donut chart)
-/// gui_set_inner_radius(element, ratio) -> new_element
-fn gui_set_inner_radius(args: &[Value]) -> NativeResult {
+/// Show the values of the point under the cursor
+/// gui_set_tooltips(element, enabled) -> new_element
+fn gui_set_tooltips(args: &[Value]) -> NativeResult {
+    set_plot_flag(args, "gui_set_tooltips", |options, on| {
+        options.tooltips = on;
+    })
+}
+
+/// Zoom the x axis with the mouse wheel and pan it by dragging
+/// gui_set_zoomable(element, enabled) -> new_element
+fn gui_set_zoomable(args: &[Value]) -> NativeResult {
+    set_plot_flag(args, "gui_set_zoomable", |options, on| options.zoom = on)
+}
+
+/// Hide and show series by clicking them in the legend
+/// gui_set_toggle_legend(element, enabled) -> new_element
+fn gui_set_toggle_legend(args: &[Value]) -> NativeResult {
+    set_plot_flag(args, "gui_set_toggle_legend", |options, on| {
+        options.toggle_legend = on;
+    })
+}
+
+/// Set the callback called with the point of a chart that was clicked
+/// gui_on_point_click(element, callback) -> new_element
+fn gui_on_point_click(args: &[Value]) -> NativeResult {
     if args.len() != 2 {
-        return Err("gui_set_inner_radius requires 2 arguments (element, ratio)".to_string());
+        return Err("gui_on_point_click requires 2 arguments (element, callback)".to_string());
     }
 
     let mut element = clone_gui_element(&args[0])?;
-    let ratio = get_float(args, 1, "ratio")? as f32;
+    let callback_id = get_callback(&args[1])?;
 
-    if let GuiElementKind::PieChart(c) = &mut element.kind {
-        c.inner_radius_ratio = ratio.clamp(0.0, 0.9);
-    } else {
-        return Err("gui_set_inner_radius can only be applied to PieChart".to_string());
+    match &mut element.kind {
+        GuiElementKind::BarChart(c) => c.on_bar_click = Some(callback_id),
+        GuiElementKind::LineChart(c) => c.on_point_click = Some(callback_id),
+        GuiElementKind::PieChart(c) => c.on_slice_click = Some(callback_id),
+        kind => match kind.plot_options_mut() {
+            Some(options) => options.on_point_click = Some(callback_id),
+            None => {
+                return Err("gui_on_point_click can only be applied to chart elements".to_string())
+            }
+        },
     }
 
     Ok(element.into_value())
@@ -3241,7 +5170,6 @@ fn gui_set_corner_radius(args: &[Value]) -> NativeResult {
 /// gui_theme_presets() -> [string]
 fn gui_theme_presets(_args: &[Value]) -> NativeResult {
     use crate::theme::ThemePreset;
-    use std::cell::RefCell;
     use std::rc::Rc;
 
     let names: Vec<Value> = ThemePreset::all_names()
@@ -3249,7 +5177,7 @@ fn gui_theme_presets(_args: &[Value]) -> NativeResult {
         .map(|s| Value::String(Rc::new((*s).to_string())))
         .collect();
 
-    Ok(Value::List(Rc::new(RefCell::new(names))))
+    Ok(Value::list(names))
 }
 
 /// Set the application theme by preset name
@@ -3313,6 +5241,153 @@ fn gui_custom_theme(args: &[Value]) -> NativeResult {
     Ok(Value::Null)
 }
 
+/// Define and set a theme from Stratum
+/// gui_theme(definition) -> null
+/// definition is a map or struct with optional fields:
+///   - name: theme name (default "custom")
+///   - base: preset to start from (default "system", following the OS)
+///   - palette, dark_palette: colors replacing the base's, by palette field
+///   - typography: font (family name) and size
+///   - widgets: styles for "button", "text_input", "container" and "text",
+///     each with background, foreground, border_color, border_width and
+///     corner_radius
+fn gui_theme(args: &[Value]) -> NativeResult {
+    use crate::bindings::request_theme_definition;
+    use crate::theme::{ThemeDefinition, ThemePreset, Typography};
+
+    let definition = args
+        .first()
+        .ok_or("missing required argument: definition")?;
+    if !matches!(definition, Value::Map(_) | Value::Struct(_)) {
+        return Err(format!(
+            "theme must be a map or struct, got {}",
+            definition.type_name()
+        ));
+    }
+
+    let name = match record_field(definition, "name") {
+        Some(Value::String(name)) => name.to_string(),
+        None | Some(Value::Null) => "custom".to_string(),
+        Some(other) => {
+            return Err(format!(
+                "theme name must be a string, got {}",
+                other.type_name()
+            ))
+        }
+    };
+    let base = match record_field(definition, "base") {
+        Some(Value::String(base)) => ThemePreset::from_name(&base)
+            .ok_or_else(|| format!("Unknown theme preset: '{}'", base))?,
+        None | Some(Value::Null) => ThemePreset::System,
+        Some(other) => {
+            return Err(format!(
+                "theme base must be a string, got {}",
+                other.type_name()
+            ))
+        }
+    };
+
+    let mut theme = ThemeDefinition::new(name, base);
+    if let Some(palette) = record_field(definition, "palette") {
+        theme.palette = palette_overrides(&palette, "palette")?;
+    }
+    if let Some(palette) = record_field(definition, "dark_palette") {
+        theme.dark_palette = palette_overrides(&palette, "dark_palette")?;
+    }
+
+    if let Some(typography) = record_field(definition, "typography") {
+        theme.typography = match record_field(&typography, "font") {
+            Some(Value::String(family)) => Typography::default().with_family(&family),
+            None | Some(Value::Null) => Typography::default(),
+            Some(other) => {
+                return Err(format!(
+                    "typography.font must be a string, got {}",
+                    other.type_name()
+                ))
+            }
+        };
+        theme.typography.size = optional_f32(&typography, "size", "typography")?;
+    }
+
+    if let Some(widgets) = record_field(definition, "widgets") {
+        for widget in ["button", "text_input", "container", "text"] {
+            if let Some(style) = record_field(&widgets, widget) {
+                theme
+                    .widgets
+                    .insert(widget.to_string(), widget_style(&style, widget)?);
+            }
+        }
+    }
+
+    request_theme_definition(theme);
+    Ok(Value::Null)
+}
+
+/// A field of a map or struct
+fn record_field(value: &Value, name: &str) -> Option<Value> {
+    match value {
+        Value::Map(map) => map
+            .borrow()
+            .get(&HashableValue::String(name.to_string().into()))
+            .cloned(),
+        Value::Struct(instance) => instance.borrow().fields.get(name).cloned(),
+        _ => None,
+    }
+}
+
+/// An optional color field of a map or struct
+fn optional_color(
+    value: &Value,
+    name: &str,
+    context: &str,
+) -> Result<Option<crate::theme::Color>, String> {
+    match record_field(value, name) {
+        None | Some(Value::Null) => Ok(None),
+        Some(color) => extract_color_value(&color, &format!("{context}.{name}")).map(Some),
+    }
+}
+
+/// An optional number field of a map or struct
+#[allow(clippy::cast_possible_truncation)]
+fn optional_f32(value: &Value, name: &str, context: &str) -> Result<Option<f32>, String> {
+    match record_field(value, name) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Int(i)) => Ok(Some(i as f32)),
+        Some(Value::Float(f)) => Ok(Some(f as f32)),
+        Some(other) => Err(format!(
+            "{context}.{name} must be a number, got {}",
+            other.type_name()
+        )),
+    }
+}
+
+/// Palette colors to replace, from a map or struct of palette fields
+fn palette_overrides(
+    value: &Value,
+    context: &str,
+) -> Result<crate::theme::PaletteOverrides, String> {
+    Ok(crate::theme::PaletteOverrides {
+        background: optional_color(value, "background", context)?,
+        text: optional_color(value, "text", context)?,
+        primary: optional_color(value, "primary", context)?,
+        success: optional_color(value, "success", context)?,
+        warning: optional_color(value, "warning", context)?,
+        danger: optional_color(value, "danger", context)?,
+    })
+}
+
+/// A widget style from a map or struct of style fields
+fn widget_style(value: &Value, context: &str) -> Result<crate::theme::WidgetStyle, String> {
+    Ok(crate::theme::WidgetStyle {
+        background: optional_color(value, "background", context)?,
+        foreground: optional_color(value, "foreground", context)?,
+        border_color: optional_color(value, "border_color", context)?,
+        border_width: optional_f32(value, "border_width", context)?,
+        corner_radius: optional_f32(value, "corner_radius", context)?,
+        shadow: None,
+    })
+}
+
 /// Helper to extract a Color from a struct field
 fn extract_color_from_field(
     fields: &std::collections::HashMap<String, Value>,
@@ -3699,9 +5774,11 @@ fn gui_on_toggle(args: &[Value]) -> NativeResult {
     match &mut element.kind {
         GuiElementKind::Checkbox(config) => config.on_toggle = Some(callback_id),
         GuiElementKind::Toggle(config) => config.on_toggle = Some(callback_id),
+        GuiElementKind::Section(config) => config.on_toggle = Some(callback_id),
         _ => {
             return Err(
-                "gui_on_toggle can only be applied to Checkbox or Toggle elements".to_string(),
+                "gui_on_toggle can only be applied to Checkbox, Toggle, or Section elements"
+                    .to_string(),
             )
         }
     }
@@ -3723,7 +5800,8 @@ fn gui_on_select(args: &[Value]) -> NativeResult {
         GuiElementKind::RadioButton(config) => config.on_select = Some(callback_id),
         GuiElementKind::Dropdown(config) => config.on_select = Some(callback_id),
         GuiElementKind::DimensionFilter(config) => config.on_select = Some(callback_id),
-        _ => return Err("gui_on_select can only be applied to RadioButton, Dropdown, or DimensionFilter elements".to_string()),
+        GuiElementKind::Tree(config) => config.on_select = Some(callback_id),
+        _ => return Err("gui_on_select can only be applied to RadioButton, Dropdown, DimensionFilter, or Tree elements".to_string()),
     }
 
     Ok(element.into_value())
@@ -3946,6 +6024,60 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_gui_accessibility() {
+        let elem = gui_button(&[Value::string("Go")]).unwrap();
+        let elem = gui_set_access_label(&[elem, Value::string("Start the import")]).unwrap();
+        let elem = gui_set_access_role(&[elem, Value::string("link")]).unwrap();
+        let elem = gui_set_access_description(&[elem, Value::string("Opens a file")]).unwrap();
+        let elem = gui_set_tab_index(&[elem, Value::Int(1)]).unwrap();
+        let access = clone_gui_element(&elem).unwrap().style.access;
+        assert_eq!(access.label.as_deref(), Some("Start the import"));
+        assert_eq!(access.role, Some(Role::Link));
+        assert_eq!(access.description.as_deref(), Some("Opens a file"));
+        assert_eq!(access.tab_index, Some(1));
+
+        assert!(gui_set_access_role(&[elem.clone(), Value::string("widget")]).is_err());
+        assert!(gui_set_tab_index(&[elem, Value::Int(i64::MAX)]).is_err());
+
+        gui_bind_focus(&[Value::string("focused")]).unwrap();
+        assert_eq!(accessibility::focus_field().as_deref(), Some("focused"));
+        assert!(gui_bind_focus(&[Value::Int(1)]).is_err());
+        accessibility::reset();
+    }
+
+    #[test]
+    fn test_gui_image_sources() {
+        use stratum_core::bytecode::ImageWrapper;
+
+        let image = ImageWrapper::new(image::DynamicImage::new_rgba8(4, 2), None, None);
+        let elem = gui_image(&[Value::Image(Arc::new(image)), Value::string("fit")]).unwrap();
+        let config = match clone_gui_element(&elem).unwrap().kind {
+            GuiElementKind::Image(config) => config,
+            _ => panic!("Expected Image element"),
+        };
+        assert!(config.handle.is_some());
+        assert_eq!(config.content_fit, ImageContentFit::Contain);
+
+        // A URL replaces the pixels
+        let elem = gui_set_image_path(&[elem, Value::string("https://example.com/a.png")]).unwrap();
+        if let GuiElementKind::Image(config) = &clone_gui_element(&elem).unwrap().kind {
+            assert!(config.handle.is_none());
+            assert_eq!(config.path.as_deref(), Some("https://example.com/a.png"));
+        }
+
+        assert!(gui_image(&[Value::Int(1)]).is_err());
+        assert!(gui_image(&[Value::string("a.png"), Value::string("stretch")]).is_err());
+
+        let elem = gui_set_rotation(&[elem, Value::Int(90)]).unwrap();
+        if let GuiElementKind::Image(config) = &clone_gui_element(&elem).unwrap().kind {
+            assert!((config.rotation - 90.0).abs() < f32::EPSILON);
+        }
+        assert!(
+            gui_set_rotation(&[gui_text(&[Value::string("x")]).unwrap(), Value::Int(1)]).is_err()
+        );
+    }
+
     // ==================== Existing Tests ====================
 
     #[test]
@@ -4486,11 +6618,11 @@ mod tests {
 
     #[test]
     fn test_gui_dropdown() {
-        let options = Value::List(std::rc::Rc::new(std::cell::RefCell::new(vec![
+        let options = Value::list(vec![
             Value::string("Red"),
             Value::string("Green"),
             Value::string("Blue"),
-        ])));
+        ]);
         let result = gui_dropdown(&[options]);
         assert!(result.is_ok());
         let value = result.unwrap();
@@ -4499,10 +6631,7 @@ mod tests {
 
     #[test]
     fn test_gui_dropdown_with_selected() {
-        let options = Value::List(std::rc::Rc::new(std::cell::RefCell::new(vec![
-            Value::string("A"),
-            Value::string("B"),
-        ])));
+        let options = Value::list(vec![Value::string("A"), Value::string("B")]);
         let result = gui_dropdown(&[options, Value::string("B")]);
         assert!(result.is_ok());
 
@@ -4520,9 +6649,7 @@ mod tests {
 
     #[test]
     fn test_gui_dropdown_with_placeholder() {
-        let options = Value::List(std::rc::Rc::new(std::cell::RefCell::new(vec![
-            Value::string("X"),
-        ])));
+        let options = Value::list(vec![Value::string("X")]);
         let result = gui_dropdown(&[options, Value::Null, Value::string("Choose...")]);
         assert!(result.is_ok());
 
@@ -4540,9 +6667,7 @@ mod tests {
 
     #[test]
     fn test_gui_dropdown_with_callback() {
-        let options = Value::List(std::rc::Rc::new(std::cell::RefCell::new(vec![
-            Value::string("A"),
-        ])));
+        let options = Value::list(vec![Value::string("A")]);
         let result = gui_dropdown(&[options, Value::Null, Value::Null, Value::Int(99)]);
         assert!(result.is_ok());
 
@@ -4560,15 +6685,10 @@ mod tests {
 
     #[test]
     fn test_gui_set_dropdown_options() {
-        let initial_options = Value::List(std::rc::Rc::new(std::cell::RefCell::new(vec![
-            Value::string("Old"),
-        ])));
+        let initial_options = Value::list(vec![Value::string("Old")]);
         let elem = gui_dropdown(&[initial_options]).unwrap();
 
-        let new_options = Value::List(std::rc::Rc::new(std::cell::RefCell::new(vec![
-            Value::string("New1"),
-            Value::string("New2"),
-        ])));
+        let new_options = Value::list(vec![Value::string("New1"), Value::string("New2")]);
         let result = gui_set_dropdown_options(&[elem, new_options]);
         assert!(result.is_ok());
 
@@ -4587,9 +6707,7 @@ mod tests {
     #[test]
     fn test_gui_set_dropdown_options_wrong_type() {
         let elem = gui_button(&[Value::string("Button")]).unwrap();
-        let options = Value::List(std::rc::Rc::new(std::cell::RefCell::new(vec![
-            Value::string("A"),
-        ])));
+        let options = Value::list(vec![Value::string("A")]);
         let result = gui_set_dropdown_options(&[elem, options]);
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("Dropdown elements"));
@@ -4597,10 +6715,7 @@ mod tests {
 
     #[test]
     fn test_gui_set_dropdown_selected() {
-        let options = Value::List(std::rc::Rc::new(std::cell::RefCell::new(vec![
-            Value::string("A"),
-            Value::string("B"),
-        ])));
+        let options = Value::list(vec![Value::string("A"), Value::string("B")]);
         let elem = gui_dropdown(&[options]).unwrap();
         let result = gui_set_dropdown_selected(&[elem, Value::string("B")]);
         assert!(result.is_ok());
@@ -4619,9 +6734,7 @@ mod tests {
 
     #[test]
     fn test_gui_set_dropdown_selected_null() {
-        let options = Value::List(std::rc::Rc::new(std::cell::RefCell::new(vec![
-            Value::string("A"),
-        ])));
+        let options = Value::list(vec![Value::string("A")]);
         let elem = gui_dropdown(&[options, Value::string("A")]).unwrap();
         let result = gui_set_dropdown_selected(&[elem, Value::Null]);
         assert!(result.is_ok());
@@ -4648,9 +6761,7 @@ mod tests {
 
     #[test]
     fn test_gui_set_dropdown_placeholder() {
-        let options = Value::List(std::rc::Rc::new(std::cell::RefCell::new(vec![
-            Value::string("A"),
-        ])));
+        let options = Value::list(vec![Value::string("A")]);
         let elem = gui_dropdown(&[options]).unwrap();
         let result = gui_set_dropdown_placeholder(&[elem, Value::string("Select one...")]);
         assert!(result.is_ok());
@@ -4677,9 +6788,7 @@ mod tests {
 
     #[test]
     fn test_gui_bind_field_dropdown() {
-        let options = Value::List(std::rc::Rc::new(std::cell::RefCell::new(vec![
-            Value::string("A"),
-        ])));
+        let options = Value::list(vec![Value::string("A")]);
         let elem = gui_dropdown(&[options]).unwrap();
         let result = gui_bind_field(&[elem, Value::string("state.color")]);
         assert!(result.is_ok());
@@ -4912,6 +7021,538 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_gui_table_from_rows() {
+        let columns = Value::list(vec![Value::string("name"), Value::string("age")]);
+        let rows = Value::list(vec![
+            Value::list(vec![Value::string("Ada"), Value::Int(36)]),
+            Value::list(vec![Value::string("Alan"), Value::Int(41)]),
+        ]);
+        let elem = gui_table(&[columns, rows]).unwrap();
+        let elem = gui_set_cell_formatter(&[elem, Value::string("age"), Value::Int(1)]).unwrap();
+        let elem = gui_set_row_height(&[elem, Value::Float(32.0)]).unwrap();
+
+        let table = clone_gui_element(&elem).unwrap();
+        let GuiElementKind::DataTable(config) = table.kind else {
+            panic!("Expected DataTable element");
+        };
+        assert!(config.virtualized);
+        assert_eq!(config.page_size, None);
+        assert_eq!(config.dataframe.unwrap().num_rows(), 2);
+        assert_eq!(
+            config.cell_formatters,
+            [("age".to_string(), CallbackId::new(1))]
+        );
+        assert!((config.row_height - 32.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_gui_table_from_dataframe() {
+        let elem = gui_table(&[create_test_dataframe()]).unwrap();
+        let elem = gui_set_table_id(&[elem, Value::string("people")]).unwrap();
+        let table = clone_gui_element(&elem).unwrap();
+        let GuiElementKind::DataTable(config) = table.kind else {
+            panic!("Expected DataTable element");
+        };
+        assert_eq!(config.view_id(), "people");
+    }
+
+    #[test]
+    fn test_gui_table_errors() {
+        assert!(gui_table(&[]).is_err());
+        assert!(gui_table(&[Value::Int(1)]).is_err());
+        let columns = Value::list(vec![Value::string("a")]);
+        assert!(gui_table(&[columns.clone(), Value::Int(1)]).is_err());
+        // A row with the wrong number of values
+        let rows = Value::list(vec![Value::list(vec![Value::Int(1), Value::Int(2)])]);
+        assert!(gui_table(&[columns, rows]).is_err());
+        let table = gui_table(&[create_test_dataframe()]).unwrap();
+        assert!(gui_set_row_height(&[table, Value::Float(0.0)]).is_err());
+    }
+
+    // ==================== Tree and Section Tests ====================
+
+    #[test]
+    fn test_gui_tree() {
+        let nodes = Value::list(vec![Value::string("README.md"), Value::string("src")]);
+        let elem = gui_tree(&[nodes]).unwrap();
+        let elem = gui_set_expanded(&[elem, Value::list(vec![Value::string("src")])]).unwrap();
+        let elem = gui_set_selected_node(&[elem, Value::string("README.md")]).unwrap();
+        let elem = gui_on_expand(&[elem, Value::Int(1)]).unwrap();
+        let elem = gui_on_context_menu(&[elem, Value::Int(2)]).unwrap();
+        let elem = gui_on_select(&[elem, Value::Int(3)]).unwrap();
+        let elem = gui_bind_field(&[elem, Value::string("expanded")]).unwrap();
+
+        let tree = clone_gui_element(&elem).unwrap();
+        let GuiElementKind::Tree(config) = tree.kind else {
+            panic!("Expected Tree element");
+        };
+        assert_eq!(config.nodes.len(), 2);
+        assert_eq!(config.expanded, ["src"]);
+        assert_eq!(config.selected.as_deref(), Some("README.md"));
+        assert_eq!(config.on_expand, Some(CallbackId::new(1)));
+        assert_eq!(config.on_context_menu, Some(CallbackId::new(2)));
+        assert_eq!(config.on_select, Some(CallbackId::new(3)));
+        assert_eq!(config.field_path.as_deref(), Some("expanded"));
+        assert_eq!(config.view_id(), "tree:README.md,src");
+
+        assert!(gui_tree(&[Value::Int(1)]).is_err());
+        assert!(gui_tree(&[Value::list(vec![Value::Int(1)])]).is_err());
+    }
+
+    #[test]
+    fn test_gui_section() {
+        let child = gui_text(&[Value::string("Body")]).unwrap();
+        let elem = gui_section(&[
+            Value::string("Advanced"),
+            Value::list(vec![child]),
+            Value::Bool(true),
+        ])
+        .unwrap();
+        let elem = gui_set_key(&[elem, Value::string("advanced")]).unwrap();
+        let elem = gui_on_toggle(&[elem, Value::Int(4)]).unwrap();
+
+        let section = clone_gui_element(&elem).unwrap();
+        assert_eq!(section.children.len(), 1);
+        let GuiElementKind::Section(config) = section.kind else {
+            panic!("Expected Section element");
+        };
+        assert_eq!(config.title, "Advanced");
+        assert!(config.expanded);
+        assert_eq!(config.view_id(), "advanced");
+        assert_eq!(config.on_toggle, Some(CallbackId::new(4)));
+
+        let elem = gui_section(&[Value::string("Empty")]).unwrap();
+        assert!(gui_set_expanded(&[elem, Value::Int(1)]).is_err());
+    }
+
+    // ==================== Canvas Tests ====================
+
+    #[test]
+    fn test_gui_canvas() {
+        let elem = gui_canvas(&[Value::Int(320), Value::Float(240.0), Value::Int(5)]).unwrap();
+        let elem = gui_on_canvas_event(&[elem, Value::Int(6)]).unwrap();
+
+        let canvas = clone_gui_element(&elem).unwrap();
+        let GuiElementKind::Canvas(config) = canvas.kind else {
+            panic!("Expected Canvas element");
+        };
+        assert!((config.width - 320.0).abs() < f32::EPSILON);
+        assert!((config.height - 240.0).abs() < f32::EPSILON);
+        assert_eq!(config.draw, Some(CallbackId::new(5)));
+        assert_eq!(config.on_event, Some(CallbackId::new(6)));
+
+        assert!(gui_canvas(&[Value::Int(1)]).is_err());
+        assert!(gui_canvas(&[Value::Int(1), Value::Int(1), Value::string("draw")]).is_err());
+        let text = gui_text(&[Value::string("x")]).unwrap();
+        assert!(gui_on_draw(&[text, Value::Int(1)]).is_err());
+    }
+
+    // ==================== Menu Tests ====================
+
+    #[test]
+    fn test_gui_menu_bar() {
+        use std::rc::Rc;
+        use stratum_core::bytecode::HashableValue;
+
+        let file = Value::empty_map();
+        if let Value::Map(ref map) = file {
+            let mut map = map.borrow_mut();
+            map.insert(
+                HashableValue::String(Rc::new("label".to_string())),
+                Value::string("File"),
+            );
+            map.insert(
+                HashableValue::String(Rc::new("items".to_string())),
+                Value::list(vec![Value::string("Open"), Value::string("-")]),
+            );
+        }
+        let elem = gui_menu_bar(&[Value::list(vec![file, Value::string("Help")])]).unwrap();
+
+        let bar = clone_gui_element(&elem).unwrap();
+        let GuiElementKind::MenuBar(config) = bar.kind else {
+            panic!("Expected MenuBar element");
+        };
+        assert_eq!(config.menus.len(), 2);
+        assert_eq!(config.menus[0].items.len(), 2);
+        assert!(config.menus[0].items[1].separator);
+
+        assert!(gui_menu_bar(&[Value::Int(1)]).is_err());
+        assert!(gui_menu_bar(&[Value::list(vec![Value::Int(1)])]).is_err());
+    }
+
+    #[test]
+    fn test_gui_context_menu() {
+        let label = gui_text(&[Value::string("Right-click me")]).unwrap();
+        let elem = gui_context_menu(&[label, Value::list(vec![Value::string("Copy")])]).unwrap();
+
+        let wrapper = clone_gui_element(&elem).unwrap();
+        assert_eq!(wrapper.children.len(), 1);
+        let GuiElementKind::ContextMenu(config) = wrapper.kind else {
+            panic!("Expected ContextMenu element");
+        };
+        assert_eq!(config.items[0].label, "Copy");
+
+        assert!(gui_context_menu(&[Value::Int(1), Value::list(vec![])]).is_err());
+    }
+
+    #[test]
+    fn test_gui_tray_request() {
+        let result = gui_tray(&[
+            Value::string("icon.png"),
+            Value::list(vec![Value::string("Quit")]),
+            Value::string("My App"),
+        ])
+        .unwrap();
+        assert!(matches!(result, Value::Null));
+
+        let tray = crate::bindings::take_pending_tray().unwrap();
+        assert_eq!(tray.icon, "icon.png");
+        assert_eq!(tray.tooltip.as_deref(), Some("My App"));
+        assert_eq!(tray.items.len(), 1);
+        assert!(gui_tray(&[Value::string("icon.png")]).is_err());
+    }
+
+    // ==================== Form Validation Tests ====================
+
+    #[test]
+    fn test_gui_validate() {
+        use std::rc::Rc;
+        use stratum_core::bytecode::HashableValue;
+
+        let rules = Value::empty_map();
+        if let Value::Map(ref map) = rules {
+            map.borrow_mut().insert(
+                HashableValue::String(Rc::new("required".to_string())),
+                Value::Bool(true),
+            );
+        }
+
+        let input = gui_text_field(&[Value::state_binding("email")]).unwrap();
+        let result = gui_validate(&[input, rules.clone()]).unwrap();
+        assert!(matches!(result, Value::GuiElement(_)));
+        assert_eq!(form::form_errors().len(), 0);
+
+        // Unbound inputs have no field to validate
+        let unbound = gui_text_field(&[Value::string("x")]).unwrap();
+        assert!(gui_validate(&[unbound, rules.clone()]).is_err());
+        assert!(gui_validate(&[Value::string("name"), Value::Int(1)]).is_err());
+        assert!(gui_validate(&[Value::string("name"), rules]).is_ok());
+
+        let elem = gui_field_error(&[Value::state_binding("email")]).unwrap();
+        let GuiElementKind::FieldError(config) = clone_gui_element(&elem).unwrap().kind else {
+            panic!("Expected FieldError element");
+        };
+        assert_eq!(config.field, "email");
+        assert!(gui_field_error(&[Value::Int(1)]).is_err());
+        assert!(matches!(gui_form_errors(&[]).unwrap(), Value::Map(_)));
+    }
+
+    // ==================== Markdown Tests ====================
+
+    #[test]
+    fn test_gui_markdown() {
+        let elem = gui_markdown(&[Value::string("# Title\n\n- [a](a.md)")]).unwrap();
+        let elem = gui_on_link(&[elem, Value::Int(5)]).unwrap();
+        let GuiElementKind::Markdown(config) = clone_gui_element(&elem).unwrap().kind else {
+            panic!("Expected Markdown element");
+        };
+        assert_eq!(config.source, "# Title\n\n- [a](a.md)");
+        assert_eq!(config.blocks.len(), 2);
+        assert_eq!(config.on_link, Some(CallbackId::new(5)));
+
+        assert!(gui_markdown(&[Value::Int(1)]).is_err());
+        let text = gui_text(&[Value::string("x")]).unwrap();
+        assert!(gui_on_link(&[text, Value::Int(5)]).is_err());
+    }
+
+    // ==================== Timer and Task Tests ====================
+
+    #[test]
+    fn test_gui_timers() {
+        crate::bindings::take_pending_timers();
+        let callback = Value::native(NativeFunction::new("tick", 0, gui_pop_page));
+        let every = gui_every(&[Value::Int(250), callback.clone()]).unwrap();
+        gui_after(&[Value::Int(1000), Value::Int(3)]).unwrap();
+
+        let timers = crate::bindings::take_pending_timers();
+        assert_eq!(timers.len(), 2);
+        assert!(timers[0].repeat && !timers[1].repeat);
+        assert_eq!(Value::Int(i64::try_from(timers[0].id).unwrap()), every);
+        assert_eq!(timers[1].interval, std::time::Duration::from_secs(1));
+
+        assert!(gui_every(&[Value::Int(0), callback.clone()]).is_err());
+        assert!(gui_after(&[Value::Int(10), Value::string("tick")]).is_err());
+
+        // Cancelling a timer that hasn't started drops it
+        let id = gui_after(&[Value::Int(10), callback]).unwrap();
+        gui_cancel_timer(&[id]).unwrap();
+        assert!(crate::bindings::take_pending_timers().is_empty());
+        assert_eq!(crate::bindings::take_cancelled_timers().len(), 1);
+        assert!(gui_cancel_timer(&[Value::string("x")]).is_err());
+    }
+
+    #[test]
+    fn test_gui_spawn_task() {
+        crate::bindings::take_pending_tasks();
+        let work = Value::native(NativeFunction::new("work", 0, gui_pop_page));
+        gui_spawn_task(&[work.clone(), Value::Int(2)]).unwrap();
+        gui_spawn_task(&[work.clone(), Value::Null, work.clone()]).unwrap();
+
+        let tasks = crate::bindings::take_pending_tasks();
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].on_complete, Some(Value::Int(2)));
+        assert!(tasks[0].on_error.is_none());
+        assert!(tasks[1].on_complete.is_none() && tasks[1].on_error.is_some());
+
+        assert!(gui_spawn_task(&[]).is_err());
+        assert!(gui_spawn_task(&[Value::Int(-1)]).is_err());
+        assert!(gui_spawn_task(&[Value::Int(3)]).is_err());
+        assert!(gui_spawn_task(&[work, Value::Bool(true)]).is_err());
+    }
+
+    // ==================== Navigation Tests ====================
+
+    #[test]
+    fn test_gui_navigation() {
+        navigation::reset();
+        let view = Value::native(NativeFunction::new("view", 2, gui_pop_page));
+        let pages = Value::empty_map();
+        if let Value::Map(ref map) = pages {
+            for name in ["home", "detail"] {
+                map.borrow_mut()
+                    .insert(HashableValue::String(name.to_string().into()), view.clone());
+            }
+        }
+
+        let elem = gui_navigator(&[pages.clone(), Value::string("home")]).unwrap();
+        assert!(matches!(
+            clone_gui_element(&elem).unwrap().kind,
+            GuiElementKind::Navigator(_)
+        ));
+        assert_eq!(gui_current_page(&[]).unwrap(), Value::string("home"));
+        assert_eq!(gui_can_go_back(&[]).unwrap(), Value::Bool(false));
+
+        gui_push_page(&[Value::string("detail"), Value::Int(42)]).unwrap();
+        assert_eq!(gui_current_page(&[]).unwrap(), Value::string("detail"));
+        assert_eq!(gui_page_params(&[]).unwrap(), Value::Int(42));
+        assert_eq!(gui_can_go_back(&[]).unwrap(), Value::Bool(true));
+        assert!(gui_push_page(&[Value::string("missing")]).is_err());
+
+        gui_replace_page(&[Value::string("home")]).unwrap();
+        assert_eq!(gui_pop_page(&[]).unwrap(), Value::Bool(true));
+        assert_eq!(gui_pop_page(&[]).unwrap(), Value::Bool(false));
+
+        assert!(gui_navigator(&[pages, Value::string("missing")]).is_err());
+        assert!(gui_navigator(&[Value::Int(1), Value::string("home")]).is_err());
+        navigation::reset();
+    }
+
+    // ==================== Window Tests ====================
+
+    #[test]
+    fn test_gui_windows() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+        use stratum_core::bytecode::StructInstance;
+
+        windows::reset_script_windows();
+        let view = Value::native(NativeFunction::new("view", 2, gui_pop_page));
+        let mut instance = StructInstance::new("Inspector".to_string());
+        instance.fields.insert("count".to_string(), Value::Int(0));
+        let options = Value::empty_map();
+        if let Value::Map(ref map) = options {
+            let mut map = map.borrow_mut();
+            let mut set = |name: &str, value: Value| {
+                map.insert(HashableValue::String(name.to_string().into()), value);
+            };
+            set("title", Value::string("Inspector"));
+            set("width", Value::Int(320));
+            set("state", Value::Struct(Rc::new(RefCell::new(instance))));
+            set("on_close_requested", view.clone());
+        }
+
+        let window = gui_open_window(&[view.clone(), options]).unwrap();
+        let Value::Int(number) = window else {
+            panic!("expected a window number, got {window:?}");
+        };
+        let number = number.unsigned_abs();
+        assert_eq!(
+            gui_windows(&[]).unwrap(),
+            Value::list(vec![Value::Int(0), window.clone()])
+        );
+        let requests = windows::take_window_requests();
+        let [windows::WindowRequest::Open(_, settings)] = &requests[..] else {
+            panic!("expected one window to open");
+        };
+        assert_eq!(settings.title, "Inspector");
+        assert_eq!(settings.size, (320, 300));
+        assert!(!settings.exit_on_close);
+        assert!(windows::window_handler(number, WindowHandler::CloseRequested).is_some());
+
+        // Each window has its own state
+        assert_eq!(
+            gui_update_window_field(&[window.clone(), Value::string("count"), Value::Int(2)])
+                .unwrap(),
+            Value::Bool(true)
+        );
+        let Value::Struct(state) = gui_window_state(&[window.clone()]).unwrap() else {
+            panic!("expected the window's state");
+        };
+        assert_eq!(state.borrow().fields.get("count"), Some(&Value::Int(2)));
+        assert_eq!(gui_window_state(&[Value::Int(0)]).unwrap(), Value::Null);
+        assert!(
+            gui_update_window_field(&[Value::Int(0), Value::string("count"), Value::Int(1)])
+                .is_err()
+        );
+
+        gui_send_to_window(&[window.clone(), Value::string("refresh")]).unwrap();
+        assert_eq!(
+            windows::take_window_messages(),
+            [(number, Value::string("refresh"))]
+        );
+        gui_on_window(&[Value::Int(0), Value::string("moved"), view.clone()]).unwrap();
+        assert!(gui_on_window(&[Value::Int(0), Value::string("clicked"), view.clone()]).is_err());
+        assert_eq!(gui_current_window(&[]).unwrap(), Value::Int(0));
+
+        assert_eq!(gui_close_window(&[window]).unwrap(), Value::Bool(true));
+        assert!(gui_send_to_window(&[Value::Int(99), Value::Null]).is_err());
+        assert!(gui_open_window(&[view.clone(), Value::Int(1)]).is_err());
+        assert!(gui_open_window(&[Value::string("view")]).is_err());
+        windows::reset_script_windows();
+    }
+
+    // ==================== Animation Tests ====================
+
+    #[test]
+    fn test_gui_animate_request() {
+        crate::bindings::take_pending_animations();
+        gui_animate(&[
+            Value::state_binding("panel.width"),
+            Value::Int(300),
+            Value::Int(250),
+            Value::string("ease-out"),
+        ])
+        .unwrap();
+        gui_animate(&[Value::string("opacity"), Value::Float(0.5), Value::Int(100)]).unwrap();
+
+        let animations = crate::bindings::take_pending_animations();
+        assert_eq!(animations.len(), 2);
+        assert_eq!(animations[0].field, "panel.width");
+        assert!((animations[0].to - 300.0).abs() < f64::EPSILON);
+        assert_eq!(
+            animations[0].duration,
+            std::time::Duration::from_millis(250)
+        );
+        assert_eq!(animations[0].easing, Easing::EaseOut);
+        assert_eq!(animations[1].easing, Easing::EaseInOut);
+
+        assert!(gui_animate(&[Value::string("x"), Value::Int(1), Value::Int(-5)]).is_err());
+        assert!(gui_animate(&[
+            Value::string("x"),
+            Value::Int(1),
+            Value::Int(5),
+            Value::string("wobble")
+        ])
+        .is_err());
+        assert!(gui_animate(&[Value::Int(1), Value::Int(1), Value::Int(5)]).is_err());
+    }
+
+    #[test]
+    fn test_gui_set_transition() {
+        let text = gui_text(&[Value::string("x")]).unwrap();
+        let elem = gui_set_transition(&[text, Value::Int(200), Value::string("linear")]).unwrap();
+        let transition = clone_gui_element(&elem).unwrap().style.transition.unwrap();
+        assert_eq!(transition.duration, std::time::Duration::from_millis(200));
+        assert_eq!(transition.easing, Easing::Linear);
+
+        let Value::List(easings) = gui_easings(&[]).unwrap() else {
+            panic!("Expected a list of easings");
+        };
+        assert!(easings.borrow().contains(&Value::string("bounce")));
+    }
+
+    // ==================== Drag and Drop Tests ====================
+
+    #[test]
+    fn test_gui_draggable() {
+        let text = gui_text(&[Value::string("Card")]).unwrap();
+        let elem =
+            gui_draggable(&[text.clone(), Value::string("task-1"), Value::string("task")]).unwrap();
+        let GuiElementKind::Draggable(config) = clone_gui_element(&elem).unwrap().kind else {
+            panic!("Expected Draggable element");
+        };
+        assert_eq!(config.kind, "task");
+        assert!(crate::dnd::start_drag(&config.kind, config.payload));
+        let item = crate::dnd::finish_drag().unwrap();
+        assert_eq!(item.payload, Value::string("task-1"));
+
+        // Without a kind, the payload's type names it
+        let elem = gui_draggable(&[text.clone(), Value::Int(4)]).unwrap();
+        let GuiElementKind::Draggable(config) = clone_gui_element(&elem).unwrap().kind else {
+            panic!("Expected Draggable element");
+        };
+        assert_eq!(config.kind, "Int");
+        assert!(gui_draggable(&[text]).is_err());
+    }
+
+    #[test]
+    fn test_gui_drop_target() {
+        let text = gui_text(&[Value::string("Done")]).unwrap();
+        let elem = gui_drop_target(&[
+            text.clone(),
+            Value::Int(3),
+            Value::list(vec![Value::string("task")]),
+        ])
+        .unwrap();
+        let elem = gui_on_drop(&[elem, Value::Int(4)]).unwrap();
+        let GuiElementKind::DropTarget(config) = clone_gui_element(&elem).unwrap().kind else {
+            panic!("Expected DropTarget element");
+        };
+        assert_eq!(config.accepts, ["task"]);
+        assert_eq!(config.on_drop, Some(CallbackId::new(4)));
+
+        let elem = gui_drop_target(&[text.clone(), Value::Int(3), Value::string("file")]).unwrap();
+        let GuiElementKind::DropTarget(config) = clone_gui_element(&elem).unwrap().kind else {
+            panic!("Expected DropTarget element");
+        };
+        assert_eq!(config.accepts, ["file"]);
+
+        assert!(gui_drop_target(&[text.clone(), Value::Int(3), Value::Int(1)]).is_err());
+        assert!(gui_on_drop(&[text, Value::Int(1)]).is_err());
+    }
+
+    #[test]
+    fn test_gui_on_file_drop() {
+        crate::bindings::take_pending_file_drop_callbacks();
+        gui_on_file_drop(&[Value::Int(2)]).unwrap();
+        gui_on_file_hover_left(&[Value::Int(3)]).unwrap();
+        assert_eq!(
+            crate::bindings::take_pending_file_drop_callbacks(),
+            [
+                (FileDropEvent::Drop, CallbackId::new(2)),
+                (FileDropEvent::HoverLeft, CallbackId::new(3))
+            ]
+        );
+        assert!(gui_on_file_hover(&[Value::string("x")]).is_err());
+        assert!(gui_clipboard_write(&[Value::Int(1)]).is_err());
+    }
+
+    // ==================== Dialog Tests ====================
+
+    #[test]
+    fn test_dialog_arguments() {
+        // Argument errors are reported before any dialog opens
+        assert!(gui_open_file_dialog(&[Value::Int(1)]).is_err());
+        assert!(gui_save_file_dialog(&[Value::Int(1)]).is_err());
+        assert!(gui_save_file_dialog(&[Value::Null, Value::string("csv")]).is_err());
+        assert!(gui_pick_folder(&[Value::Int(1)]).is_err());
+        assert!(gui_alert(&[]).is_err());
+        assert!(gui_confirm(&[Value::Int(1)]).is_err());
+        assert!(dialog_text(&[Value::string("Quit?")], "gui_confirm")
+            .is_ok_and(|(title, message)| title.is_empty() && message == "Quit?"));
+    }
+
     #[test]
     fn test_data_table_wrong_element() {
         let elem = gui_text(&[Value::string("Not a table")]).unwrap();
@@ -5026,6 +7667,75 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_gui_theme_definition() {
+        use stratum_core::bytecode::HashableValue;
+
+        fn map(entries: Vec<(&str, Value)>) -> Value {
+            let map = Value::empty_map();
+            if let Value::Map(ref m) = map {
+                for (key, value) in entries {
+                    m.borrow_mut()
+                        .insert(HashableValue::String(key.to_string().into()), value);
+                }
+            }
+            map
+        }
+
+        let _ = crate::bindings::take_pending_theme();
+        let definition = map(vec![
+            ("name", Value::string("brand")),
+            ("base", Value::string("light")),
+            ("palette", map(vec![("primary", Value::string("#FF0066"))])),
+            (
+                "typography",
+                map(vec![
+                    ("font", Value::string("Inter")),
+                    ("size", Value::Int(15)),
+                ]),
+            ),
+            (
+                "widgets",
+                map(vec![(
+                    "button",
+                    map(vec![("corner_radius", Value::Float(8.0))]),
+                )]),
+            ),
+        ]);
+        assert_eq!(gui_theme(&[definition]).unwrap(), Value::Null);
+
+        let Some(crate::bindings::PendingTheme::Defined(theme)) =
+            crate::bindings::take_pending_theme()
+        else {
+            panic!("Expected a defined theme");
+        };
+        assert_eq!(theme.name, "brand");
+        assert_eq!(theme.base, crate::theme::ThemePreset::Light);
+        assert_eq!(
+            theme.palette().primary,
+            crate::theme::Color::rgb(255, 0, 102)
+        );
+        assert_eq!(theme.typography.size, Some(15.0));
+        assert!(theme.typography.font.is_some());
+        assert_eq!(theme.widgets["button"].corner_radius, Some(8.0));
+
+        // The base defaults to following the OS
+        gui_theme(&[Value::empty_map()]).unwrap();
+        if let Some(crate::bindings::PendingTheme::Defined(theme)) =
+            crate::bindings::take_pending_theme()
+        {
+            assert_eq!(theme.base, crate::theme::ThemePreset::System);
+        }
+
+        assert!(gui_theme(&[Value::Int(1)]).is_err());
+        assert!(gui_theme(&[map(vec![("base", Value::string("neon"))])]).is_err());
+        assert!(gui_theme(&[map(vec![(
+            "palette",
+            map(vec![("text", Value::string("#zz"))])
+        )])])
+        .is_err());
+    }
+
     #[test]
     fn test_gui_custom_theme_with_struct() {
         use std::cell::RefCell;
@@ -5040,19 +7750,11 @@ mod tests {
         let mut palette_fields = HashMap::new();
         palette_fields.insert(
             "background".to_string(),
-            Value::List(Rc::new(RefCell::new(vec![
-                Value::Int(40),
-                Value::Int(44),
-                Value::Int(52),
-            ]))),
+            Value::list(vec![Value::Int(40), Value::Int(44), Value::Int(52)]),
         );
         palette_fields.insert(
             "text".to_string(),
-            Value::List(Rc::new(RefCell::new(vec![
-                Value::Int(255),
-                Value::Int(255),
-                Value::Int(255),
-            ]))),
+            Value::list(vec![Value::Int(255), Value::Int(255), Value::Int(255)]),
         );
         palette_fields.insert(
             "primary".to_string(),
@@ -5060,27 +7762,15 @@ mod tests {
         );
         palette_fields.insert(
             "success".to_string(),
-            Value::List(Rc::new(RefCell::new(vec![
-                Value::Int(152),
-                Value::Int(195),
-                Value::Int(121),
-            ]))),
+            Value::list(vec![Value::Int(152), Value::Int(195), Value::Int(121)]),
         );
         palette_fields.insert(
             "warning".to_string(),
-            Value::List(Rc::new(RefCell::new(vec![
-                Value::Int(229),
-                Value::Int(192),
-                Value::Int(123),
-            ]))),
+            Value::list(vec![Value::Int(229), Value::Int(192), Value::Int(123)]),
         );
         palette_fields.insert(
             "danger".to_string(),
-            Value::List(Rc::new(RefCell::new(vec![
-                Value::Int(224),
-                Value::Int(108),
-                Value::Int(117),
-            ]))),
+            Value::list(vec![Value::Int(224), Value::Int(108), Value::Int(117)]),
         );
 
         let palette_instance = StructInstance {
@@ -5115,43 +7805,23 @@ mod tests {
         let mut palette_fields = HashMap::new();
         palette_fields.insert(
             "background".to_string(),
-            Value::List(Rc::new(RefCell::new(vec![
-                Value::Int(40),
-                Value::Int(44),
-                Value::Int(52),
-            ]))),
+            Value::list(vec![Value::Int(40), Value::Int(44), Value::Int(52)]),
         );
         palette_fields.insert(
             "text".to_string(),
-            Value::List(Rc::new(RefCell::new(vec![
-                Value::Int(255),
-                Value::Int(255),
-                Value::Int(255),
-            ]))),
+            Value::list(vec![Value::Int(255), Value::Int(255), Value::Int(255)]),
         );
         palette_fields.insert(
             "primary".to_string(),
-            Value::List(Rc::new(RefCell::new(vec![
-                Value::Int(97),
-                Value::Int(175),
-                Value::Int(239),
-            ]))),
+            Value::list(vec![Value::Int(97), Value::Int(175), Value::Int(239)]),
         );
         palette_fields.insert(
             "success".to_string(),
-            Value::List(Rc::new(RefCell::new(vec![
-                Value::Int(152),
-                Value::Int(195),
-                Value::Int(121),
-            ]))),
+            Value::list(vec![Value::Int(152), Value::Int(195), Value::Int(121)]),
         );
         palette_fields.insert(
             "warning".to_string(),
-            Value::List(Rc::new(RefCell::new(vec![
-                Value::Int(229),
-                Value::Int(192),
-                Value::Int(123),
-            ]))),
+            Value::list(vec![Value::Int(229), Value::Int(192), Value::Int(123)]),
         );
         // 'danger' field intentionally missing
 
@@ -5253,6 +7923,98 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_gui_interactive_charts() {
+        let pair = |x: i64, y: f64| Value::list(vec![Value::Int(x), Value::Float(y)]);
+        let scatter = gui_scatter_chart(&[Value::list(vec![pair(1, 2.0), pair(3, 4.5)])]).unwrap();
+        let scatter =
+            gui_add_chart_series(&[scatter, Value::string("B"), Value::list(vec![pair(0, 1.0)])])
+                .unwrap();
+        let scatter = gui_set_zoomable(&[scatter, Value::Bool(true)]).unwrap();
+        let scatter = gui_on_point_click(&[scatter, Value::Int(5)]).unwrap();
+        let GuiElementKind::ScatterChart(config) = clone_gui_element(&scatter).unwrap().kind else {
+            panic!("expected a scatter chart");
+        };
+        assert_eq!(config.series.len(), 2);
+        assert_eq!(config.series[0].points, [(1.0, 2.0), (3.0, 4.5)]);
+        assert!(config.options.zoom);
+        assert_eq!(config.options.on_point_click, Some(CallbackId::new(5)));
+        let bad_points = Value::list(vec![Value::Int(1)]);
+        assert!(gui_add_chart_series(&[scatter, Value::string("C"), bad_points]).is_err());
+
+        let labels = Value::list(vec![Value::string("Q1"), Value::string("Q2")]);
+        let area = gui_area_chart(&[labels.clone()]).unwrap();
+        let values = Value::list(vec![Value::Int(1), Value::Int(2)]);
+        let area = gui_add_chart_series(&[area, Value::string("North"), values]).unwrap();
+        let area = gui_set_stacked(&[area, Value::Bool(true)]).unwrap();
+        let GuiElementKind::AreaChart(config) = clone_gui_element(&area).unwrap().kind else {
+            panic!("expected an area chart");
+        };
+        assert!(config.stacked);
+        assert_eq!(config.labels, ["Q1", "Q2"]);
+        assert_eq!(config.series.len(), 1);
+
+        let bars = gui_stacked_bar_chart(&[labels]).unwrap();
+        let bars = gui_set_chart_title(&[bars, Value::string("Mix")]).unwrap();
+        assert!(gui_set_stacked(&[bars.clone(), Value::Bool(true)]).is_err());
+        let bars = gui_set_tooltips(&[bars, Value::Bool(false)]).unwrap();
+        let GuiElementKind::StackedBarChart(config) = clone_gui_element(&bars).unwrap().kind else {
+            panic!("expected a stacked bar chart");
+        };
+        assert_eq!(config.options.title.as_deref(), Some("Mix"));
+        assert!(!config.options.tooltips);
+
+        let values = Value::list(vec![Value::Int(1), Value::Float(2.5)]);
+        let histogram = gui_histogram(&[values, Value::Int(4)]).unwrap();
+        let histogram = gui_set_bins(&[histogram, Value::Int(6)]).unwrap();
+        let GuiElementKind::Histogram(config) = clone_gui_element(&histogram).unwrap().kind else {
+            panic!("expected a histogram");
+        };
+        assert_eq!(config.bins, 6);
+        assert_eq!(config.values, [1.0, 2.5]);
+        assert!(gui_histogram(&[Value::list(vec![]), Value::Int(0)]).is_err());
+
+        let tuesday = Value::empty_map();
+        if let Value::Map(ref map) = tuesday {
+            let mut map = map.borrow_mut();
+            let mut set = |name: &str, value: Value| {
+                map.insert(HashableValue::String(name.to_string().into()), value);
+            };
+            set("label", Value::string("Tue"));
+            set("open", Value::Int(11));
+            set("high", Value::Float(13.5));
+            set("low", Value::Int(10));
+            set("close", Value::Int(10));
+        }
+        let monday = Value::list(vec![
+            Value::string("Mon"),
+            Value::Int(10),
+            Value::Int(12),
+            Value::Int(9),
+            Value::Int(11),
+        ]);
+        let candles = gui_candlestick_chart(&[Value::list(vec![monday, tuesday])]).unwrap();
+        let GuiElementKind::CandlestickChart(config) = clone_gui_element(&candles).unwrap().kind
+        else {
+            panic!("expected a candlestick chart");
+        };
+        assert_eq!(config.candles.len(), 2);
+        assert_eq!(config.candles[1].label, "Tue");
+        assert!((config.candles[1].high - 13.5).abs() < f64::EPSILON);
+        let short = Value::list(vec![Value::list(vec![Value::Int(1)])]);
+        assert!(gui_candlestick_chart(&[short]).is_err());
+
+        // Bar, line and pie charts take point callbacks too; other elements don't
+        let pie = gui_on_point_click(&[gui_pie_chart(&[]).unwrap(), Value::Int(2)]).unwrap();
+        let GuiElementKind::PieChart(config) = clone_gui_element(&pie).unwrap().kind else {
+            panic!("expected a pie chart");
+        };
+        assert_eq!(config.on_slice_click, Some(CallbackId::new(2)));
+        let text = gui_text(&[Value::string("x")]).unwrap();
+        assert!(gui_on_point_click(&[text.clone(), Value::Int(2)]).is_err());
+        assert!(gui_set_zoomable(&[text, Value::Bool(true)]).is_err());
+    }
+
     #[test]
     fn test_gui_cube_chart_basic() {
         let result = gui_cube_chart(&[]);
@@ -5730,7 +8492,7 @@ mod tests {
     #[test]
     fn test_gui_text_field_with_state_binding() {
         // Test that TextField accepts a StateBinding and sets field_path
-        let binding = Value::StateBinding("state.name".to_string());
+        let binding = Value::state_binding("state.name");
         let result = gui_text_field(&[binding]);
         assert!(result.is_ok());
 
@@ -5768,7 +8530,7 @@ mod tests {
     #[test]
     fn test_gui_checkbox_with_state_binding() {
         // Test that Checkbox accepts a StateBinding and sets field_path
-        let binding = Value::StateBinding("state.agreed".to_string());
+        let binding = Value::state_binding("state.agreed");
         let result = gui_checkbox(&[Value::string("I agree"), binding]);
         assert!(result.is_ok());
 
@@ -5787,7 +8549,7 @@ mod tests {
     #[test]
     fn test_gui_slider_with_state_binding() {
         // Test that Slider accepts a StateBinding and sets field_path
-        let binding = Value::StateBinding("state.volume".to_string());
+        let binding = Value::state_binding("state.volume");
         let result = gui_slider(&[Value::Float(0.0), Value::Float(100.0), binding]);
         assert!(result.is_ok());
 
@@ -5811,7 +8573,7 @@ mod tests {
             Value::string("Green"),
             Value::string("Blue"),
         ]);
-        let binding = Value::StateBinding("state.color".to_string());
+        let binding = Value::state_binding("state.color");
         let result = gui_dropdown(&[options, binding]);
         assert!(result.is_ok());
 
@@ -5830,7 +8592,7 @@ mod tests {
     #[test]
     fn test_gui_radio_button_with_state_binding() {
         // Test that RadioButton accepts a StateBinding and sets field_path
-        let binding = Value::StateBinding("state.size".to_string());
+        let binding = Value::state_binding("state.size");
         let result = gui_radio_button(&[Value::string("Small"), Value::string("small"), binding]);
         assert!(result.is_ok());
 
@@ -5849,7 +8611,7 @@ mod tests {
     #[test]
     fn test_gui_toggle_with_state_binding() {
         // Test that Toggle accepts a StateBinding and sets field_path
-        let binding = Value::StateBinding("state.enabled".to_string());
+        let binding = Value::state_binding("state.enabled");
         let result = gui_toggle(&[Value::string("Enable"), binding]);
         assert!(result.is_ok());
 
@@ -5868,7 +8630,7 @@ mod tests {
     #[test]
     fn test_nested_state_binding_path() {
         // Test that nested paths like "state.user.profile.name" are preserved
-        let binding = Value::StateBinding("state.user.profile.name".to_string());
+        let binding = Value::state_binding("state.user.profile.name");
         let result = gui_text_field(&[binding]);
         assert!(result.is_ok());
 
@@ -0,0 +1,329 @@
+//! Page navigation for multi-page apps
+//!
+//! `Gui.navigator(pages, initial)` declares the pages of an app, each a view
+//! function called with the state and the page's parameters, and shows the
+//! page on top of a navigation stack. `Gui.push`, `Gui.pop` and
+//! `Gui.replace` change the stack from callbacks. Pages may have `on_enter`
+//! and `on_leave` hooks, called with the page's parameters when it becomes
+//! or stops being the page shown, and an `on_back` hook that can keep the
+//! page open by returning false.
+//!
+//! View functions and parameters are Stratum values, which can't live in
+//! the element tree, so the pages and the stack are kept here. Hooks are
+//! queued as the stack changes and run by the app after the callback that
+//! navigated.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+use stratum_core::bytecode::{HashableValue, Value};
+
+thread_local! {
+    static PAGES: RefCell<HashMap<String, Page>> = RefCell::new(HashMap::new());
+    static STACK: RefCell<Vec<Route>> = const { RefCell::new(Vec::new()) };
+    static HOOK_CALLS: RefCell<Vec<(Value, Value)>> = const { RefCell::new(Vec::new()) };
+    static BACK_REQUESTED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// A page of a navigator
+#[derive(Debug, Clone)]
+pub struct Page {
+    /// View function, called with the state and the page's parameters
+    pub view: Value,
+    /// Called with the parameters when the page becomes the page shown
+    pub on_enter: Option<Value>,
+    /// Called with the parameters when the page stops being shown
+    pub on_leave: Option<Value>,
+    /// Called with the parameters on a back request; returning false keeps
+    /// the page open
+    pub on_back: Option<Value>,
+}
+
+impl Page {
+    /// A page from a Stratum value: a view function alone, or a map or
+    /// struct with `view` and optional `on_enter`, `on_leave` and `on_back`
+    pub fn from_value(value: &Value) -> Result<Self, String> {
+        let field = |name: &str| -> Option<Value> {
+            match value {
+                Value::Map(map) => map
+                    .borrow()
+                    .get(&HashableValue::String(name.to_string().into()))
+                    .cloned(),
+                Value::Struct(instance) => instance.borrow().fields.get(name).cloned(),
+                _ => None,
+            }
+        };
+        let hook = |name: &str| -> Result<Option<Value>, String> {
+            match field(name) {
+                Some(hook @ (Value::Closure(_) | Value::NativeFunction(_))) => Ok(Some(hook)),
+                Some(Value::Null) | None => Ok(None),
+                Some(other) => Err(format!(
+                    "page {name} must be a function, got {}",
+                    other.type_name()
+                )),
+            }
+        };
+
+        match value {
+            Value::Closure(_) | Value::NativeFunction(_) => Ok(Self {
+                view: value.clone(),
+                on_enter: None,
+                on_leave: None,
+                on_back: None,
+            }),
+            Value::Map(_) | Value::Struct(_) => Ok(Self {
+                view: hook("view")?.ok_or("page needs a view function")?,
+                on_enter: hook("on_enter")?,
+                on_leave: hook("on_leave")?,
+                on_back: hook("on_back")?,
+            }),
+            other => Err(format!(
+                "page must be a view function, map or struct, got {}",
+                other.type_name()
+            )),
+        }
+    }
+}
+
+/// An entry of the navigation stack
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    /// Name of the page
+    pub page: String,
+    /// Parameters the page was opened with
+    pub params: Value,
+}
+
+/// Declare the pages, opening `initial` if nothing is open yet. Pages are
+/// declared again each time the view is built; the stack is kept.
+pub fn set_pages(pages: HashMap<String, Page>, initial: &str, params: Value) -> Result<(), String> {
+    if !pages.contains_key(initial) {
+        return Err(format!("navigator has no page '{initial}'"));
+    }
+    PAGES.with(|p| *p.borrow_mut() = pages);
+    if depth() == 0 {
+        enter(Route {
+            page: initial.to_string(),
+            params,
+        });
+    }
+    Ok(())
+}
+
+/// Open a page on top of the current one
+pub fn push(page: &str, params: Value) -> Result<(), String> {
+    check_page(page)?;
+    leave_current();
+    enter(Route {
+        page: page.to_string(),
+        params,
+    });
+    Ok(())
+}
+
+/// Close the current page, going back to the one below; false if it is the
+/// only page open
+pub fn pop() -> bool {
+    if depth() < 2 {
+        return false;
+    }
+    leave_current();
+    STACK.with(|stack| stack.borrow_mut().pop());
+    if let Some(route) = current() {
+        queue_hook(&route, |page| page.on_enter.clone());
+    }
+    true
+}
+
+/// Close pages down to the first one opened; false if it is already shown
+pub fn pop_to_root() -> bool {
+    if depth() < 2 {
+        return false;
+    }
+    leave_current();
+    STACK.with(|stack| stack.borrow_mut().truncate(1));
+    if let Some(route) = current() {
+        queue_hook(&route, |page| page.on_enter.clone());
+    }
+    true
+}
+
+/// Show a page in place of the current one
+pub fn replace(page: &str, params: Value) -> Result<(), String> {
+    check_page(page)?;
+    leave_current();
+    STACK.with(|stack| stack.borrow_mut().pop());
+    enter(Route {
+        page: page.to_string(),
+        params,
+    });
+    Ok(())
+}
+
+/// The page shown
+#[must_use]
+pub fn current() -> Option<Route> {
+    STACK.with(|stack| stack.borrow().last().cloned())
+}
+
+/// Number of pages open
+#[must_use]
+pub fn depth() -> usize {
+    STACK.with(|stack| stack.borrow().len())
+}
+
+/// A declared page
+#[must_use]
+pub fn page(name: &str) -> Option<Page> {
+    PAGES.with(|pages| pages.borrow().get(name).cloned())
+}
+
+/// Ask to go back, as the back button does; the app asks the page's
+/// `on_back` hook before popping
+pub fn request_back() {
+    BACK_REQUESTED.with(|b| b.set(true));
+}
+
+/// Take a back request, clearing it
+pub fn take_back_request() -> bool {
+    BACK_REQUESTED.with(|b| b.replace(false))
+}
+
+/// Take the hooks queued by navigation, each with the parameters to call it
+/// with
+pub fn take_hook_calls() -> Vec<(Value, Value)> {
+    HOOK_CALLS.with(|calls| std::mem::take(&mut *calls.borrow_mut()))
+}
+
+/// Forget all pages and the stack
+pub fn reset() {
+    PAGES.with(|pages| pages.borrow_mut().clear());
+    STACK.with(|stack| stack.borrow_mut().clear());
+    HOOK_CALLS.with(|calls| calls.borrow_mut().clear());
+    BACK_REQUESTED.with(|b| b.set(false));
+}
+
+fn check_page(name: &str) -> Result<(), String> {
+    if PAGES.with(|pages| pages.borrow().contains_key(name)) {
+        Ok(())
+    } else {
+        Err(format!("navigator has no page '{name}'"))
+    }
+}
+
+fn enter(route: Route) {
+    queue_hook(&route, |page| page.on_enter.clone());
+    STACK.with(|stack| stack.borrow_mut().push(route));
+}
+
+fn leave_current() {
+    if let Some(route) = current() {
+        queue_hook(&route, |page| page.on_leave.clone());
+    }
+}
+
+fn queue_hook(route: &Route, hook: impl Fn(&Page) -> Option<Value>) {
+    if let Some(hook) = page(&route.page).as_ref().and_then(hook) {
+        HOOK_CALLS.with(|calls| calls.borrow_mut().push((hook, route.params.clone())));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stratum_core::bytecode::NativeFunction;
+
+    fn native(_args: &[Value]) -> Result<Value, String> {
+        Ok(Value::Null)
+    }
+
+    fn function(name: &'static str) -> Value {
+        Value::native(NativeFunction::new(name, -1, native))
+    }
+
+    fn view() -> Value {
+        function("view")
+    }
+
+    fn pages(names: &[&str]) -> HashMap<String, Page> {
+        names
+            .iter()
+            .map(|name| (name.to_string(), Page::from_value(&view()).unwrap()))
+            .collect()
+    }
+
+    fn stack() -> Vec<String> {
+        STACK.with(|stack| stack.borrow().iter().map(|r| r.page.clone()).collect())
+    }
+
+    #[test]
+    fn test_push_pop_replace() {
+        reset();
+        set_pages(pages(&["home", "detail", "settings"]), "home", Value::Null).unwrap();
+        assert_eq!(stack(), ["home"]);
+        assert!(!pop());
+
+        push("detail", Value::Int(3)).unwrap();
+        assert_eq!(current().unwrap().params, Value::Int(3));
+        replace("settings", Value::Null).unwrap();
+        assert_eq!(stack(), ["home", "settings"]);
+        push("detail", Value::Int(4)).unwrap();
+        assert_eq!(depth(), 3);
+
+        assert!(pop());
+        assert_eq!(current().unwrap().page, "settings");
+        push("detail", Value::Int(5)).unwrap();
+        assert!(pop_to_root());
+        assert_eq!(stack(), ["home"]);
+
+        assert!(push("missing", Value::Null).is_err());
+        assert!(set_pages(pages(&["home"]), "missing", Value::Null).is_err());
+
+        // Declaring the pages again keeps the stack
+        push("detail", Value::Int(6)).unwrap();
+        set_pages(pages(&["home", "detail"]), "home", Value::Null).unwrap();
+        assert_eq!(stack(), ["home", "detail"]);
+        reset();
+    }
+
+    #[test]
+    fn test_hooks_queued() {
+        reset();
+        let hooked = Page {
+            view: view(),
+            on_enter: Some(function("enter")),
+            on_leave: Some(function("leave")),
+            on_back: None,
+        };
+        let mut declared = pages(&["home"]);
+        declared.insert("detail".to_string(), hooked);
+        set_pages(declared, "home", Value::Null).unwrap();
+        assert!(take_hook_calls().is_empty());
+
+        push("detail", Value::Int(1)).unwrap();
+        let calls = take_hook_calls();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].1, Value::Int(1));
+
+        // Leaving detail calls on_leave; home has no hooks
+        pop();
+        assert_eq!(take_hook_calls().len(), 1);
+        reset();
+    }
+
+    #[test]
+    fn test_page_from_value() {
+        assert!(Page::from_value(&view()).is_ok());
+        assert!(Page::from_value(&Value::Int(1)).is_err());
+        assert!(Page::from_value(&Value::empty_map()).is_err());
+    }
+
+    #[test]
+    fn test_back_request() {
+        reset();
+        assert!(!take_back_request());
+        request_back();
+        assert!(take_back_request());
+        assert!(!take_back_request());
+    }
+}
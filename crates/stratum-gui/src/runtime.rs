@@ -5,24 +5,47 @@
 //! and multi-window applications.
 
 use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::Arc;
+use std::time::Instant;
 
 use iced::widget::{button, column, container, row, scrollable, text};
 use iced::{window, Center, Color, Element, Fill, Subscription, Task, Theme};
 
-use stratum_core::bytecode::Value;
+use stratum_core::bytecode::{Portable, Value};
 use stratum_core::VM;
 
+use crate::accessibility;
+use crate::animation::{Animation, Transitions, FRAME_INTERVAL};
 use crate::callback::{Callback, CallbackExecutor, CallbackId, CallbackRegistry};
+use crate::canvas::{set_canvas_frames, CanvasContext, CanvasEvent};
+use crate::charts::ChartPoint;
+use crate::dnd::{clear_payloads, dragging_kind, finish_drag, start_drag, FileDropEvent};
 use crate::element::GuiElement;
 use crate::error::{GuiError, GuiResult};
+use crate::form;
+use crate::hot_reload::{self, Watcher};
+use crate::image_loader;
 use crate::lifecycle::{LifecycleHooks, LifecycleManager};
+use crate::menu::{find_shortcut, last_cursor, record_cursor};
 use crate::modal::{ModalConfig, ModalManager, ModalResult};
+use crate::navigation;
+use crate::screen_reader::ScreenReader;
 use crate::state::ReactiveState;
-use crate::theme::{StratumPalette, StratumTheme, ThemePreset};
+use crate::table::{with_table_view, ColumnResize};
+use crate::theme::{self, StratumPalette, StratumTheme, ThemePreset};
+use crate::timer::{self, tick_interval, BackgroundTask, Timer};
+use crate::tray::Tray;
+use crate::tree::{set_section_expanded, with_tree_view};
 use crate::widgets::LayoutConfig;
-use crate::window::{WindowId, WindowManager, WindowSettings};
+use crate::window::{
+    as_window, attach_window, iced_window, remove_script_window, script_window_numbers,
+    take_window_messages, take_window_requests, window_handler, window_number, window_state,
+    window_view, WindowHandler, WindowId, WindowManager, WindowRequest, WindowSettings,
+    MAIN_WINDOW,
+};
 
 /// Supported GUI backends
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -101,6 +124,114 @@ pub enum Message {
         row: usize,
         column: String,
     },
+    /// Virtualized table scrolled - records the rows in view
+    DataTableScroll {
+        table: String,
+        offset: f32,
+        height: f32,
+    },
+    /// Virtualized table header clicked without an on_sort callback - sorts the table itself
+    DataTableToggleSort { table: String, column: String },
+    /// Virtualized table row clicked - toggles its selection and invokes the callbacks
+    DataTableSelect {
+        table: String,
+        row: usize,
+        on_selection_change: Option<CallbackId>,
+        on_row_click: Option<CallbackId>,
+    },
+    /// Edge of a table column header pressed - starts resizing the column
+    DataTableResizeStart {
+        table: String,
+        column: String,
+        width: f32,
+    },
+    /// Cursor moved to an x position while resizing a table column
+    DataTableResizeMove(f32),
+    /// Mouse released while resizing a table column
+    DataTableResizeEnd,
+    /// Tree node arrow clicked - expands or collapses the node
+    TreeToggle {
+        tree: String,
+        node: String,
+        field_path: Option<String>,
+        on_expand: Option<CallbackId>,
+    },
+    /// Tree node clicked - selects it and invokes callback with its id
+    TreeSelect {
+        tree: String,
+        node: String,
+        on_select: Option<CallbackId>,
+    },
+    /// Tree node right-clicked - invokes callback with its id
+    TreeContextMenu {
+        callback_id: CallbackId,
+        node: String,
+    },
+    /// Section header clicked - expands or collapses the section
+    SectionToggle {
+        section: String,
+        expanded: bool,
+        field_path: Option<String>,
+        on_toggle: Option<CallbackId>,
+    },
+    /// A menu bar item was chosen (callback is None for items without one)
+    MenuSelect { callback_id: Option<CallbackId> },
+    /// An element with a context menu was right-clicked
+    OpenContextMenu { items: Vec<ContextMenuItem> },
+    /// Check the tray icon's menu for chosen items
+    TrayPoll,
+    /// Carry out what a screen reader asked for
+    ScreenReaderPoll,
+    /// Check whether the OS switched between light and dark mode
+    SystemThemePoll,
+    /// Check whether the app's source file changed, reloading its view
+    HotReloadPoll,
+    /// A draggable element was pressed
+    DragStart { kind: String, payload: usize },
+    /// The mouse was released over a drop target
+    Drop {
+        accepts: Vec<String>,
+        on_drop: Option<CallbackId>,
+    },
+    /// The mouse was released, ending any drag
+    DragEnd,
+    /// Step animations and transitions
+    AnimationTick(Instant),
+    /// Fire the timers that are due
+    TimerTick(Instant),
+    /// Start a task spawned by Gui.spawn_task() on a worker thread
+    RunTask(u64),
+    /// A task's work finished, with its result or error message
+    TaskFinished {
+        id: u64,
+        result: Result<Portable, String>,
+    },
+    /// An image URL finished downloading
+    ImageLoaded {
+        url: String,
+        result: Result<iced::widget::image::Handle, String>,
+    },
+    /// A markdown link was clicked
+    LinkClicked {
+        callback_id: CallbackId,
+        url: String,
+    },
+    /// Back button: ask the page shown whether to go back, then pop it
+    NavigateBack,
+    /// Tab or Shift+Tab: move keyboard focus forward or back
+    MoveFocus { forward: bool },
+    /// Enter or Space: activate the focused element
+    ActivateFocus,
+    /// Mouse or keyboard input over a canvas
+    CanvasEvent {
+        callback_id: CallbackId,
+        event: CanvasEvent,
+    },
+    /// A point of a chart was clicked - invokes callback with the point
+    ChartClick {
+        callback_id: CallbackId,
+        point: ChartPoint,
+    },
     /// Invoke a registered callback by ID (callback accesses state directly)
     InvokeCallback(CallbackId),
     /// Request application shutdown
@@ -123,6 +254,10 @@ pub enum Message {
         width: u32,
         height: u32,
     },
+    /// Window moved
+    WindowMoved { id: window::Id, x: i32, y: i32 },
+    /// The user asked to close a window; its handler may keep it open
+    WindowCloseRequested(window::Id),
     /// Request to open a new window
     OpenWindow(WindowSettings),
     /// Request to close a specific window
@@ -487,6 +622,8 @@ pub struct GuiRuntime {
     root_element: Option<Arc<GuiElement>>,
     /// View function for reactive rendering (Stratum closure that takes state, returns GuiElement)
     view_fn: Option<Arc<Value>>,
+    /// Source file to reload the view function from when it changes
+    hot_reload: Option<PathBuf>,
 }
 
 impl GuiRuntime {
@@ -501,6 +638,7 @@ impl GuiRuntime {
             lifecycle_hooks: LifecycleHooks::default(),
             root_element: None,
             view_fn: None,
+            hot_reload: None,
         }
     }
 
@@ -515,6 +653,17 @@ impl GuiRuntime {
         self
     }
 
+    /// Watch the app's source file and swap in its new view function when
+    /// it changes, keeping the current state
+    ///
+    /// Needs a VM set with [`Self::with_vm`], which the source is run in
+    /// again on each change.
+    #[must_use]
+    pub fn with_hot_reload(mut self, source: impl Into<PathBuf>) -> Self {
+        self.hot_reload = Some(source.into());
+        self
+    }
+
     /// Set the root GUI element tree to render
     ///
     /// When a root element is provided, the runtime will render it instead
@@ -682,7 +831,6 @@ impl GuiRuntime {
             }
         }
 
-        let title = self.config.window.title.clone();
         let theme = self.config.theme;
         let main_window_settings = self.config.window.clone();
 
@@ -707,12 +855,6 @@ impl GuiRuntime {
         // Create initial window manager
         let window_manager = WindowManager::new();
 
-        // Build iced settings from WindowSettings
-        let window_size = main_window_settings.size;
-        let resizable = main_window_settings.resizable;
-        let min_size = main_window_settings.min_size;
-        let _max_size = main_window_settings.max_size;
-
         // Capture values for the boot closure
         // Wrap non-Clone types in Rc<RefCell<Option<T>>> so the closure can implement Fn
         let state = self.state.clone();
@@ -721,17 +863,21 @@ impl GuiRuntime {
         let registry = self.registry.clone();
         let main_window_settings_clone = main_window_settings.clone();
         let initial_theme = theme.clone();
+        theme::refresh_system_mode();
+        theme::set_active(&initial_theme);
         let root_element = self.root_element.clone();
         let view_fn = self.view_fn.clone();
+        let hot_reload = self.hot_reload.clone();
 
         // Wrap types that need to be moved out of the closure
         let executor_cell = Rc::new(RefCell::new(Some(executor)));
         let lifecycle_cell = Rc::new(RefCell::new(Some(lifecycle)));
         let window_manager_cell = Rc::new(RefCell::new(Some(window_manager)));
 
-        // In iced 0.14, application() takes (boot, update, view) where boot returns (State, Task)
+        // daemon() takes (boot, update, view) where boot returns (State, Task); unlike
+        // application() it draws any number of windows, each opened by a task.
         // The boot function must implement Fn (not just FnOnce), so we use Option::take()
-        let mut app_builder = iced::application(
+        iced::daemon(
             move || {
                 // Take values out of cells (this works because boot is only called once)
                 // executor is Option<Option<CallbackExecutor>>, flatten it
@@ -745,10 +891,15 @@ impl GuiRuntime {
                     .take()
                     .expect("boot should only be called once");
 
-                // Register main window (we'll get the actual ID from WindowOpened event)
-                window_manager.register_main(main_window_settings_clone.clone());
+                // Open the main window, which Stratum code knows as window 0
+                let (main_id, open_main) = open_window(&main_window_settings_clone);
+                window_manager.register(
+                    WindowId::from_iced(main_id),
+                    main_window_settings_clone.clone(),
+                );
+                attach_window(MAIN_WINDOW, main_id);
 
-                let app = App {
+                let mut app = App {
                     state: state.clone(),
                     spacing,
                     padding,
@@ -766,29 +917,61 @@ impl GuiRuntime {
                     context_menu: None,
                     root_element: root_element.clone(),
                     view_fn: view_fn.clone(),
+                    hot_reload: hot_reload.clone().map(Watcher::new),
+                    window_roots: HashMap::new(),
                     selected_measures: Vec::new(),
+                    column_resize: None,
+                    tray: None,
+                    screen_reader: ScreenReader::connect(),
+                    animations: Vec::new(),
+                    transitions: Transitions::new(),
+                    timers: Vec::new(),
+                    tasks: Vec::new(),
                 };
-
-                (app, Task::none())
+                app.check_pending_navigation();
+                if let Some(root) = app.root_element.clone() {
+                    app.root_element = Some(app.show_pages(&root));
+                }
+                app.refresh_windows();
+                form::revalidate(&app.state);
+                app.show_bound_values();
+                app.format_table_cells();
+                app.draw_canvases();
+                app.update_screen_reader();
+                app.check_pending_tray();
+                app.check_pending_file_drop();
+                app.check_pending_animations();
+                app.check_pending_timers();
+                let tasks = Task::batch([
+                    open_main.map(Message::WindowOpened),
+                    app.check_pending_windows(),
+                    app.check_pending_tasks(),
+                    app.load_images(),
+                ]);
+
+                (app, tasks)
             },
             App::update,
             App::view,
         )
-        .title(move |_: &App| title.clone())
-        .theme(|app: &App| app.theme.to_iced_theme())
+        .title(|app: &App, id| app.window_manager.title(WindowId::from_iced(id)))
+        .theme(|app: &App, _id| app.theme.to_iced_theme())
         .subscription(App::subscription)
-        .window_size((window_size.0 as f32, window_size.1 as f32))
-        .resizable(resizable);
+        .run()
+        .map_err(|e| GuiError::Iced(e.to_string()))
+    }
+}
 
-        if let Some((w, h)) = min_size {
-            app_builder = app_builder.window(iced::window::Settings {
-                min_size: Some(iced::Size::new(w as f32, h as f32)),
-                ..Default::default()
-            });
-        }
+/// Rounds of messages between windows delivered per update
+const MAX_MESSAGE_ROUNDS: usize = 8;
 
-        app_builder.run().map_err(|e| GuiError::Iced(e.to_string()))
-    }
+/// Open a window whose close requests come to the app as messages, so
+/// Stratum handlers can keep it open
+fn open_window(settings: &WindowSettings) -> (window::Id, Task<window::Id>) {
+    window::open(iced::window::Settings {
+        exit_on_close_request: false,
+        ..settings.to_iced()
+    })
 }
 
 /// The iced application state
@@ -821,8 +1004,26 @@ struct App {
     root_element: Option<Arc<GuiElement>>,
     /// View function for reactive rendering (Stratum closure)
     view_fn: Option<Arc<Value>>,
+    /// Watcher of the source file the view function is reloaded from
+    hot_reload: Option<Watcher>,
+    /// Element trees of the windows opened by Gui.open_window(), by number
+    window_roots: HashMap<u64, Arc<GuiElement>>,
     /// Internal state for selected measures (when no callback registered)
     selected_measures: Vec<String>,
+    /// Table column being resized by dragging
+    column_resize: Option<ColumnResize>,
+    /// System tray icon, once Gui.tray() asked for one
+    tray: Option<Tray>,
+    /// Connection to screen readers, where there are any to connect to
+    screen_reader: Option<ScreenReader>,
+    /// State fields being animated by Gui.animate()
+    animations: Vec<Animation>,
+    /// Transitions of elements in the view
+    transitions: Transitions,
+    /// Timers started by Gui.every() and Gui.after()
+    timers: Vec<Timer>,
+    /// Spawned tasks waiting for their message
+    tasks: Vec<BackgroundTask>,
 }
 
 /// State for an active context menu
@@ -840,8 +1041,6 @@ impl App {
     /// Re-invoke the view function and update root_element
     /// Called after callbacks execute to reflect state changes in the UI
     fn refresh_view(&mut self) {
-        use crate::bindings::take_pending_callbacks;
-        use crate::callback::Callback;
         use crate::element::GuiElement;
 
         // Only refresh if we have both a view_fn and an executor
@@ -849,21 +1048,21 @@ impl App {
             // Get current state value
             let state_value = self.state.get().clone();
 
+            // Payloads of the old tree go with it; an ongoing drag keeps its own
+            clear_payloads();
+
             // Invoke view_fn with current state
             match executor.execute_closure(view_fn.as_ref(), vec![state_value]) {
                 Ok(result) => {
                     // Register any new callbacks that were created during view function execution
-                    let pending_callbacks = take_pending_callbacks();
-                    for callback_value in pending_callbacks {
-                        if let Ok(callback) = Callback::new(callback_value) {
-                            self.registry.borrow_mut().register(callback);
-                        }
-                    }
+                    self.register_pending_callbacks();
 
-                    // Extract GuiElement from result
+                    // Extract GuiElement from result, showing the current page
+                    // and easing elements with transitions
                     if let Value::GuiElement(elem) = result {
                         if let Some(gui_elem) = elem.as_any().downcast_ref::<GuiElement>() {
-                            self.root_element = Some(Arc::new(gui_elem.clone()));
+                            let root = self.show_pages(&Arc::new(gui_elem.clone()));
+                            self.root_element = Some(self.transitions.apply(&root, Instant::now()));
                         }
                     }
                 }
@@ -874,6 +1073,161 @@ impl App {
         }
     }
 
+    /// Register the callbacks created by Stratum code since the last call
+    fn register_pending_callbacks(&self) {
+        use crate::bindings::take_pending_callbacks;
+
+        for callback_value in take_pending_callbacks() {
+            if let Ok(callback) = Callback::new(callback_value) {
+                self.registry.borrow_mut().register(callback);
+            }
+        }
+    }
+
+    /// Fill each navigator in the tree with the page on top of the
+    /// navigation stack, built by the page's view function
+    fn show_pages(&self, element: &Arc<GuiElement>) -> Arc<GuiElement> {
+        use crate::element::{GuiElementKind, NavigatorConfig};
+
+        if let GuiElementKind::Navigator(_) = element.kind {
+            let mut navigator = element.as_ref().clone();
+            navigator.children.clear();
+            let route = navigation::current();
+            let page = route
+                .as_ref()
+                .and_then(|route| navigation::page(&route.page));
+            if let (Some(route), Some(page), Some(executor)) = (route, page, &self.executor) {
+                let state_value = self.state.get().clone();
+                match executor.execute_closure(&page.view, vec![state_value, route.params]) {
+                    Ok(Value::GuiElement(elem)) => {
+                        self.register_pending_callbacks();
+                        if let Some(page) = elem.as_any().downcast_ref::<GuiElement>() {
+                            navigator.children.push(Arc::new(page.clone()));
+                        }
+                    }
+                    Ok(other) => eprintln!(
+                        "Page '{}' view must return a GuiElement, got {}",
+                        route.page,
+                        other.type_name()
+                    ),
+                    Err(e) => eprintln!("Page '{}' view error: {e}", route.page),
+                }
+                navigator.kind = GuiElementKind::Navigator(NavigatorConfig {
+                    page: Some(route.page),
+                });
+            }
+            return Arc::new(navigator);
+        }
+
+        let children: Vec<Arc<GuiElement>> = element
+            .children
+            .iter()
+            .map(|child| self.show_pages(child))
+            .collect();
+        if children
+            .iter()
+            .zip(&element.children)
+            .all(|(a, b)| Arc::ptr_eq(a, b))
+        {
+            return Arc::clone(element);
+        }
+        let mut shown = element.as_ref().clone();
+        shown.children = children;
+        Arc::new(shown)
+    }
+
+    /// Show the current value of each bound field in its input
+    fn show_bound_values(&mut self) {
+        if let Some(ref root) = self.root_element {
+            self.root_element = Some(form::bind_values(root, &self.state));
+        }
+    }
+
+    /// Run the cell formatters of virtualized tables over the rows in view.
+    /// Each cell is formatted once, so scrolling back costs nothing.
+    fn format_table_cells(&self) {
+        use crate::element::GuiElementKind;
+
+        let (Some(root), Some(executor)) = (&self.root_element, &self.executor) else {
+            return;
+        };
+        let mut pending = vec![root.as_ref()];
+        while let Some(element) = pending.pop() {
+            pending.extend(element.children.iter().map(AsRef::as_ref));
+            let GuiElementKind::DataTable(config) = &element.kind else {
+                continue;
+            };
+            let Some(ref df) = config.dataframe else {
+                continue;
+            };
+            if !config.virtualized || config.cell_formatters.is_empty() {
+                continue;
+            }
+
+            let id = config.view_id();
+            let rows: Vec<usize> = with_table_view(&id, |view| {
+                let order = view.row_order(df);
+                view.visible(df.num_rows(), config.row_height)
+                    .map(|position| order.as_ref().map_or(position, |order| order[position]))
+                    .collect()
+            });
+            for (column, callback_id) in &config.cell_formatters {
+                let Ok(series) = df.column(column) else {
+                    continue;
+                };
+                for &row in &rows {
+                    if !with_table_view(&id, |view| view.needs_format(df, row, column)) {
+                        continue;
+                    }
+                    let value = series.get(row).unwrap_or(Value::Null);
+                    let text = match executor
+                        .execute(*callback_id, vec![value.clone(), Value::Int(row as i64)])
+                    {
+                        Ok(Value::String(s)) => s.to_string(),
+                        Ok(other) => format!("{other}"),
+                        Err(e) => {
+                            // Show the raw value rather than retrying every update
+                            eprintln!("DataTable cell formatter error: {e}");
+                            format!("{value}")
+                        }
+                    };
+                    with_table_view(&id, |view| view.set_formatted(row, column, text));
+                }
+            }
+        }
+    }
+
+    /// Run the draw callback of every canvas in the view, recording what
+    /// each draws for rendering
+    fn draw_canvases(&self) {
+        use crate::element::GuiElementKind;
+
+        let (Some(root), Some(executor)) = (&self.root_element, &self.executor) else {
+            return;
+        };
+        let mut frames = std::collections::HashMap::new();
+        let mut pending = vec![root.as_ref()];
+        while let Some(element) = pending.pop() {
+            pending.extend(element.children.iter().map(AsRef::as_ref));
+            let GuiElementKind::Canvas(config) = &element.kind else {
+                continue;
+            };
+            let Some(draw) = config.draw else {
+                continue;
+            };
+            if frames.contains_key(&draw) {
+                continue;
+            }
+
+            let ctx = CanvasContext::new(config.width, config.height);
+            if let Err(e) = executor.execute(draw, vec![ctx.clone().into_value()]) {
+                eprintln!("Canvas draw callback error: {e}");
+            }
+            frames.insert(draw, Arc::new(ctx.commands()));
+        }
+        set_canvas_frames(frames);
+    }
+
     /// Check if quit was requested and return appropriate task
     fn check_quit_requested(&mut self) -> Option<Task<Message>> {
         use crate::bindings::take_quit_request;
@@ -892,16 +1246,349 @@ impl App {
 
         if let Some(pending) = take_pending_theme() {
             match pending {
-                PendingTheme::Preset(preset) => {
-                    self.theme = StratumTheme::preset(preset);
-                }
+                PendingTheme::Preset(preset) => self.set_theme(StratumTheme::preset(preset)),
                 PendingTheme::Custom { name, palette } => {
-                    self.theme = StratumTheme::custom(name, palette);
+                    self.set_theme(StratumTheme::custom(name, palette));
                 }
+                PendingTheme::Defined(definition) => {
+                    self.set_theme(StratumTheme::Defined(Arc::new(definition)));
+                }
+            }
+        }
+    }
+
+    /// Switch themes, restyling widgets the theme styles
+    fn set_theme(&mut self, new_theme: StratumTheme) {
+        theme::set_active(&new_theme);
+        self.theme = new_theme;
+    }
+
+    /// Create the tray icon if Gui.tray() asked for one
+    fn check_pending_tray(&mut self) {
+        use crate::bindings::take_pending_tray;
+
+        if let Some(config) = take_pending_tray() {
+            match Tray::new(&config) {
+                Ok(tray) => self.tray = Some(tray),
+                Err(e) => eprintln!("{e}"),
             }
         }
     }
 
+    /// Start the animations requested since the last check, each from its
+    /// field's current value. A new animation of a field replaces the old.
+    fn check_pending_animations(&mut self) {
+        use crate::bindings::take_pending_animations;
+
+        let now = Instant::now();
+        for mut animation in take_pending_animations() {
+            let current = self
+                .state
+                .get_field(&animation.field)
+                .unwrap_or(Value::Null);
+            animation.start(&current, now);
+            self.animations.retain(|a| a.field != animation.field);
+            self.animations.push(animation);
+        }
+    }
+
+    /// Go back if asked to and the page shown lets it, then run the page
+    /// hooks queued by navigation, applying the field updates they make
+    fn check_pending_navigation(&mut self) {
+        let Some(ref executor) = self.executor else {
+            return;
+        };
+        if navigation::take_back_request() {
+            let route = navigation::current();
+            let on_back = route
+                .as_ref()
+                .and_then(|route| navigation::page(&route.page))
+                .and_then(|page| page.on_back);
+            let go_back = match (route, on_back) {
+                (Some(route), Some(on_back)) => {
+                    match executor.execute_closure(&on_back, vec![route.params]) {
+                        Ok(Value::Bool(false)) => false,
+                        Ok(_) => true,
+                        Err(e) => {
+                            eprintln!("Page on_back hook error: {e}");
+                            true
+                        }
+                    }
+                }
+                _ => true,
+            };
+            if go_back {
+                navigation::pop();
+            }
+        }
+
+        for (hook, params) in navigation::take_hook_calls() {
+            if let Err(e) = executor.execute_closure(&hook, vec![params]) {
+                eprintln!("Page hook error: {e}");
+            }
+        }
+        self.apply_field_updates();
+    }
+
+    /// Apply the field updates queued by callbacks via Gui.update_field()
+    fn apply_field_updates(&mut self) {
+        use crate::bindings::take_pending_field_updates;
+
+        for update in take_pending_field_updates() {
+            self.state.update_field(&update.field, update.value);
+        }
+    }
+
+    /// Call a handler kept as a value: a callback id or a function
+    fn call_handler(&self, handler: &Value, args: Vec<Value>) -> GuiResult<Value> {
+        let Some(ref executor) = self.executor else {
+            return Err(GuiError::EventHandling(
+                "No executor for callbacks".to_string(),
+            ));
+        };
+        match handler {
+            Value::Int(id) => executor.execute(CallbackId::new(id.unsigned_abs()), args),
+            other => executor.execute_closure(other, args),
+        }
+    }
+
+    /// Deliver the messages sent between windows, then open and close the
+    /// windows Stratum code asked for
+    fn check_pending_windows(&mut self) -> Task<Message> {
+        // Handlers may send messages of their own; deliver those as well,
+        // but don't let two windows echo each other forever
+        for _ in 0..MAX_MESSAGE_ROUNDS {
+            let messages = take_window_messages();
+            if messages.is_empty() {
+                break;
+            }
+            for (number, message) in messages {
+                self.call_window_handler(number, WindowHandler::Message, vec![message]);
+            }
+        }
+        self.apply_field_updates();
+
+        let mut tasks = Vec::new();
+        for request in take_window_requests() {
+            match request {
+                WindowRequest::Open(number, settings) => {
+                    let (id, open_task) = open_window(&settings);
+                    attach_window(number, id);
+                    self.window_manager
+                        .register(WindowId::from_iced(id), settings);
+                    tasks.push(open_task.map(Message::WindowOpened));
+                }
+                WindowRequest::Close(number) => {
+                    if let Some(id) = iced_window(number) {
+                        tasks.push(window::close(id));
+                    }
+                }
+            }
+        }
+        Task::batch(tasks)
+    }
+
+    /// Call a window's handler for an event, if it has one, as that window
+    fn call_window_handler(
+        &self,
+        number: u64,
+        handler: WindowHandler,
+        args: Vec<Value>,
+    ) -> Option<Value> {
+        let callback = window_handler(number, handler)?;
+        match as_window(number, || self.call_handler(&callback, args)) {
+            Ok(result) => Some(result),
+            Err(e) => {
+                eprintln!("Window {number} {} handler error: {e}", handler.name());
+                None
+            }
+        }
+    }
+
+    /// Build the tree of each window opened by Gui.open_window(), calling
+    /// its view with the app state and the window's own state
+    fn refresh_windows(&mut self) {
+        let Some(ref executor) = self.executor else {
+            return;
+        };
+        let mut roots = HashMap::new();
+        for number in script_window_numbers() {
+            let (Some(view), Some(state)) = (window_view(number), window_state(number)) else {
+                continue;
+            };
+            let args = vec![self.state.get().clone(), state.get().clone()];
+            match as_window(number, || executor.execute_closure(&view, args)) {
+                Ok(Value::GuiElement(elem)) => {
+                    self.register_pending_callbacks();
+                    if let Some(root) = elem.as_any().downcast_ref::<GuiElement>() {
+                        let root = form::bind_values(&Arc::new(root.clone()), &self.state);
+                        roots.insert(number, root);
+                    }
+                }
+                Ok(other) => eprintln!(
+                    "Window {number} view must return a GuiElement, got {}",
+                    other.type_name()
+                ),
+                Err(e) => eprintln!("Window {number} view error: {e}"),
+            }
+        }
+        self.window_roots = roots;
+    }
+
+    /// If the source file changed, run it again and swap in the view
+    /// function it defines; true if the view was replaced. The state is
+    /// kept, and the old view stays if the new code doesn't compile or run.
+    fn reload_view(&mut self) -> bool {
+        let (Some(watcher), Some(executor)) = (&mut self.hot_reload, &self.executor) else {
+            return false;
+        };
+        if !watcher.poll() {
+            return false;
+        }
+        let reloaded = hot_reload::reload(&mut executor.vm().borrow_mut(), watcher.path());
+        match reloaded {
+            Ok(view_fn) => {
+                eprintln!("Reloaded {}", watcher.path().display());
+                self.view_fn = Some(Arc::new(view_fn));
+                self.register_pending_callbacks();
+                true
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                false
+            }
+        }
+    }
+
+    /// Keep the timers started since the last check and drop cancelled ones
+    fn check_pending_timers(&mut self) {
+        use crate::bindings::{take_cancelled_timers, take_pending_timers};
+
+        self.timers.extend(take_pending_timers());
+        let cancelled = take_cancelled_timers();
+        self.timers.retain(|timer| !cancelled.contains(&timer.id));
+    }
+
+    /// Take the tasks spawned since the last check, sending a message to
+    /// run each so the window redraws first
+    fn check_pending_tasks(&mut self) -> Task<Message> {
+        use crate::bindings::take_pending_tasks;
+
+        let tasks = take_pending_tasks();
+        let messages: Vec<Task<Message>> = tasks
+            .iter()
+            .map(|task| Task::done(Message::RunTask(task.id)))
+            .collect();
+        self.tasks.extend(tasks);
+        Task::batch(messages)
+    }
+
+    /// Hand a task's result to its on_complete callback, or its error to
+    /// on_error
+    fn finish_task(&mut self, id: u64, result: Result<Portable, String>) {
+        let Some(index) = self.tasks.iter().position(|task| task.id == id) else {
+            return;
+        };
+        let task = self.tasks.remove(index);
+        match result {
+            Ok(result) => {
+                if let Some(ref on_complete) = task.on_complete {
+                    if let Err(e) = self.call_handler(on_complete, vec![result.into_value()]) {
+                        eprintln!("Task on_complete callback error: {e}");
+                    }
+                }
+            }
+            Err(e) => match task.on_error {
+                Some(ref on_error) => {
+                    if let Err(e) = self.call_handler(on_error, vec![Value::string(e)]) {
+                        eprintln!("Task on_error callback error: {e}");
+                    }
+                }
+                None => eprintln!("Task error: {e}"),
+            },
+        }
+        self.apply_field_updates();
+    }
+
+    /// Keep the field bound by Gui.bind_focus() in step with keyboard focus
+    fn sync_focus_binding(&self) {
+        let Some(field) = accessibility::focus_field() else {
+            return;
+        };
+        let current = self.state.get_field(&field).unwrap_or(Value::Null);
+        if let Some(label) = accessibility::sync_focus_binding(&current) {
+            self.state.update_field(&field, label);
+        }
+    }
+
+    /// Hand the main window's accessibility tree and focus to screen readers
+    fn update_screen_reader(&mut self) {
+        let (Some(reader), Some(root)) = (&mut self.screen_reader, &self.root_element) else {
+            return;
+        };
+        reader.update(&accessibility::access_tree(root), accessibility::focused());
+    }
+
+    /// Start downloading the image URLs shown that aren't loaded yet
+    fn load_images(&self) -> Task<Message> {
+        use crate::element::GuiElementKind;
+
+        let Some(root) = &self.root_element else {
+            return Task::none();
+        };
+        let mut downloads = Vec::new();
+        let mut pending = vec![root.as_ref()];
+        while let Some(element) = pending.pop() {
+            pending.extend(element.children.iter().map(AsRef::as_ref));
+            let GuiElementKind::Image(config) = &element.kind else {
+                continue;
+            };
+            let Some(url) = config.path.clone() else {
+                continue;
+            };
+            if config.handle.is_none()
+                && image_loader::is_url(&url)
+                && image_loader::start_loading(&url)
+            {
+                downloads.push(Task::perform(
+                    image_loader::fetch(url.clone()),
+                    move |result| Message::ImageLoaded { url, result },
+                ));
+            }
+        }
+        Task::batch(downloads)
+    }
+
+    /// Take the window file drop callbacks registered since the last check
+    fn check_pending_file_drop(&mut self) {
+        use crate::bindings::take_pending_file_drop_callbacks;
+
+        for (event, callback_id) in take_pending_file_drop_callbacks() {
+            let slot = match event {
+                FileDropEvent::Hover => &mut self.file_hover_callback,
+                FileDropEvent::Drop => &mut self.file_drop_callback,
+                FileDropEvent::HoverLeft => &mut self.file_hover_left_callback,
+            };
+            *slot = Some(callback_id);
+        }
+    }
+
+    /// The callback of the menu bar item a key press is a shortcut for
+    fn menu_shortcut(&self, key: &str, modifiers: KeyModifiers) -> Option<CallbackId> {
+        use crate::element::GuiElementKind;
+
+        let mut pending = vec![self.root_element.as_deref()?];
+        while let Some(element) = pending.pop() {
+            pending.extend(element.children.iter().map(AsRef::as_ref));
+            if let GuiElementKind::MenuBar(config) = &element.kind {
+                if let Some(callback_id) = find_shortcut(&config.menus, key, modifiers) {
+                    return Some(callback_id);
+                }
+            }
+        }
+        None
+    }
+
     /// Update the application state based on a message
     fn update(&mut self, message: Message) -> Task<Message> {
         match message {
@@ -914,17 +1601,20 @@ impl App {
                 self.state.update_field("count", Value::Int(current - 1));
             }
             Message::SetIntField { field, value } => {
-                self.state.update_field(&field, Value::Int(value));
+                self.state.set_bound(&field, Value::Int(value));
+                form::touch(&field);
             }
             Message::SetStringField { field, value } => {
-                self.state
-                    .update_field(&field, Value::String(Rc::new(value)));
+                self.state.set_bound(&field, Value::String(Rc::new(value)));
+                form::touch(&field);
             }
             Message::SetBoolField { field, value } => {
-                self.state.update_field(&field, Value::Bool(value));
+                self.state.set_bound(&field, Value::Bool(value));
+                form::touch(&field);
             }
             Message::SetFloatField { field, value } => {
-                self.state.update_field(&field, Value::Float(value));
+                self.state.set_bound(&field, Value::Float(value));
+                form::touch(&field);
             }
             Message::TextFieldChanged { callback_id, value } => {
                 if let Some(ref executor) = self.executor {
@@ -1000,8 +1690,7 @@ impl App {
                 if let Some(ref executor) = self.executor {
                     let row_values: Vec<Value> =
                         rows.into_iter().map(|r| Value::Int(r as i64)).collect();
-                    let rows_arg =
-                        Value::List(std::rc::Rc::new(std::cell::RefCell::new(row_values)));
+                    let rows_arg = Value::list(row_values);
                     if let Err(e) = executor.execute(callback_id, vec![rows_arg]) {
                         eprintln!("DataTable on_selection_change callback error: {e}");
                     }
@@ -1028,6 +1717,264 @@ impl App {
                     }
                 }
             }
+            Message::DataTableScroll {
+                table,
+                offset,
+                height,
+            } => {
+                with_table_view(&table, |view| {
+                    view.offset = offset;
+                    view.viewport_height = Some(height);
+                });
+            }
+            Message::DataTableToggleSort { table, column } => {
+                with_table_view(&table, |view| view.toggle_sort(column));
+            }
+            Message::DataTableSelect {
+                table,
+                row,
+                on_selection_change,
+                on_row_click,
+            } => {
+                let rows = with_table_view(&table, |view| view.toggle_row(row));
+                if let Some(ref executor) = self.executor {
+                    if let Some(callback_id) = on_selection_change {
+                        let row_values: Vec<Value> =
+                            rows.into_iter().map(|r| Value::Int(r as i64)).collect();
+                        let rows_arg = Value::list(row_values);
+                        if let Err(e) = executor.execute(callback_id, vec![rows_arg]) {
+                            eprintln!("DataTable on_selection_change callback error: {e}");
+                        }
+                    }
+                    if let Some(callback_id) = on_row_click {
+                        if let Err(e) = executor.execute(callback_id, vec![Value::Int(row as i64)])
+                        {
+                            eprintln!("DataTable on_row_click callback error: {e}");
+                        }
+                    }
+                }
+            }
+            Message::DataTableResizeStart {
+                table,
+                column,
+                width,
+            } => {
+                self.column_resize = Some(ColumnResize {
+                    table,
+                    column,
+                    width,
+                    start_x: None,
+                });
+            }
+            Message::DataTableResizeMove(x) => {
+                if let Some(ref mut resize) = self.column_resize {
+                    let width = resize.drag(x);
+                    with_table_view(&resize.table, |view| {
+                        view.widths.insert(resize.column.clone(), width);
+                    });
+                }
+            }
+            Message::DataTableResizeEnd => {
+                self.column_resize = None;
+            }
+            Message::TreeToggle {
+                tree,
+                node,
+                field_path,
+                on_expand,
+            } => {
+                let (expanded, ids) = with_tree_view(&tree, |view| view.toggle(&node));
+                if let Some(field) = field_path {
+                    let ids = ids
+                        .into_iter()
+                        .map(|id| Value::String(Rc::new(id)))
+                        .collect();
+                    self.state.update_field(&field, Value::list(ids));
+                }
+                if let (true, Some(callback_id), Some(executor)) =
+                    (expanded, on_expand, &self.executor)
+                {
+                    let node_arg = Value::String(Rc::new(node));
+                    if let Err(e) = executor.execute(callback_id, vec![node_arg]) {
+                        eprintln!("Tree on_expand callback error: {e}");
+                    }
+                }
+            }
+            Message::TreeSelect {
+                tree,
+                node,
+                on_select,
+            } => {
+                with_tree_view(&tree, |view| view.selected = Some(node.clone()));
+                if let (Some(callback_id), Some(executor)) = (on_select, &self.executor) {
+                    let node_arg = Value::String(Rc::new(node));
+                    if let Err(e) = executor.execute(callback_id, vec![node_arg]) {
+                        eprintln!("Tree on_select callback error: {e}");
+                    }
+                }
+            }
+            Message::TreeContextMenu { callback_id, node } => {
+                if let Some(ref executor) = self.executor {
+                    let node_arg = Value::String(Rc::new(node));
+                    if let Err(e) = executor.execute(callback_id, vec![node_arg]) {
+                        eprintln!("Tree on_context_menu callback error: {e}");
+                    }
+                }
+            }
+            Message::SectionToggle {
+                section,
+                expanded,
+                field_path,
+                on_toggle,
+            } => {
+                set_section_expanded(&section, expanded);
+                if let Some(field) = field_path {
+                    self.state.update_field(&field, Value::Bool(expanded));
+                }
+                if let (Some(callback_id), Some(executor)) = (on_toggle, &self.executor) {
+                    if let Err(e) = executor.execute(callback_id, vec![Value::Bool(expanded)]) {
+                        eprintln!("Section on_toggle callback error: {e}");
+                    }
+                }
+            }
+            Message::MenuSelect { callback_id } => {
+                if let (Some(callback_id), Some(executor)) = (callback_id, &self.executor) {
+                    if let Err(e) = executor.execute(callback_id, vec![]) {
+                        eprintln!("Menu item callback error: {e}");
+                    }
+                }
+            }
+            Message::OpenContextMenu { items } => {
+                let position = last_cursor();
+                self.context_menu = Some(ContextMenuState {
+                    x: position.x,
+                    y: position.y,
+                    items,
+                });
+            }
+            Message::SystemThemePoll => {
+                if !theme::refresh_system_mode() {
+                    return Task::none();
+                }
+            }
+            Message::HotReloadPoll => {
+                if !self.reload_view() {
+                    return Task::none();
+                }
+            }
+            Message::TrayPoll => {
+                let chosen = self.tray.as_ref().map(Tray::poll).unwrap_or_default();
+                if chosen.is_empty() {
+                    return Task::none();
+                }
+                if let Some(ref executor) = self.executor {
+                    for callback_id in chosen {
+                        if let Err(e) = executor.execute(callback_id, vec![]) {
+                            eprintln!("Tray menu callback error: {e}");
+                        }
+                    }
+                }
+            }
+            Message::ScreenReaderPoll => {
+                let asked = self.screen_reader.as_ref().is_some_and(ScreenReader::poll);
+                if !asked {
+                    return Task::none();
+                }
+            }
+            Message::DragStart { kind, payload } => {
+                start_drag(&kind, payload);
+            }
+            Message::Drop { accepts, on_drop } => {
+                let Some(item) = finish_drag() else {
+                    return Task::none();
+                };
+                if crate::dnd::accepts(&accepts, &item.kind) {
+                    if let (Some(callback_id), Some(executor)) = (on_drop, &self.executor) {
+                        let args = vec![item.payload, Value::string(item.kind)];
+                        if let Err(e) = executor.execute(callback_id, args) {
+                            eprintln!("Drop callback error: {e}");
+                        }
+                    }
+                }
+            }
+            Message::AnimationTick(now) => {
+                self.animations.retain(|animation| {
+                    let (value, done) = animation.value_at(now);
+                    self.state.update_field(&animation.field, value);
+                    !done
+                });
+            }
+            Message::TimerTick(now) => {
+                let mut due = Vec::new();
+                self.timers.retain_mut(|timer| {
+                    if !timer.fire(now) {
+                        return true;
+                    }
+                    due.push(timer.callback.clone());
+                    timer.repeat
+                });
+                for callback in due {
+                    if let Err(e) = self.call_handler(&callback, vec![]) {
+                        eprintln!("Timer callback error: {e}");
+                    }
+                }
+                self.apply_field_updates();
+            }
+            Message::RunTask(id) => {
+                let Some(task) = self.tasks.iter().find(|task| task.id == id) else {
+                    return Task::none();
+                };
+                // The work is copied into a VM of its own on a worker thread,
+                // with the permissions of the app's VM
+                match Portable::from_value(&task.work) {
+                    Ok(work) => {
+                        let permissions = self
+                            .executor
+                            .as_ref()
+                            .map(|executor| executor.vm().borrow().permissions().clone())
+                            .unwrap_or_default();
+                        return Task::perform(timer::run(work, permissions), move |result| {
+                            Message::TaskFinished { id, result }
+                        });
+                    }
+                    Err(e) => self.finish_task(id, Err(e)),
+                }
+            }
+            Message::TaskFinished { id, result } => self.finish_task(id, result),
+            Message::ImageLoaded { url, result } => {
+                if let Err(ref e) = result {
+                    eprintln!("{e}");
+                }
+                image_loader::finish_loading(&url, result);
+            }
+            Message::LinkClicked { callback_id, url } => {
+                if let Some(ref executor) = self.executor {
+                    if let Err(e) = executor.execute(callback_id, vec![Value::string(url)]) {
+                        eprintln!("Markdown on_link callback error: {e}");
+                    }
+                }
+            }
+            Message::NavigateBack => navigation::request_back(),
+            Message::MoveFocus { forward } => accessibility::move_focus(forward),
+            Message::ActivateFocus => accessibility::activate(),
+            Message::DragEnd => {
+                // Released away from any target: nothing to do but redraw
+                finish_drag();
+            }
+            Message::CanvasEvent { callback_id, event } => {
+                if let Some(ref executor) = self.executor {
+                    if let Err(e) = executor.execute(callback_id, vec![event.to_value()]) {
+                        eprintln!("Canvas event callback error: {e}");
+                    }
+                }
+            }
+            Message::ChartClick { callback_id, point } => {
+                if let Some(ref executor) = self.executor {
+                    if let Err(e) = executor.execute(callback_id, vec![point.to_value()]) {
+                        eprintln!("Chart on_point_click callback error: {e}");
+                    }
+                }
+            }
             Message::InvokeCallback(id) => {
                 if let Some(ref executor) = self.executor {
                     if let Err(e) = executor.execute_with_state(id, &self.state) {
@@ -1059,12 +2006,34 @@ impl App {
                     .set_focused(WindowId::from_iced(id), true);
             }
             Message::WindowClosed(id) => {
-                self.window_manager.unregister(WindowId::from_iced(id));
-                if self.window_manager.is_empty() {
+                let closed = self.window_manager.unregister(WindowId::from_iced(id));
+                if let Some(number) = window_number(id) {
+                    self.call_window_handler(number, WindowHandler::Closed, Vec::new());
+                    if number != MAIN_WINDOW {
+                        remove_script_window(number);
+                        self.window_roots.remove(&number);
+                    }
+                }
+                let exits = closed.is_some_and(|window| window.settings.exit_on_close);
+                if exits || self.window_manager.is_empty() {
                     let _ = self.lifecycle.shutdown();
                     return iced::exit();
                 }
             }
+            Message::WindowCloseRequested(id) => {
+                let keep_open = window_number(id).and_then(|number| {
+                    self.call_window_handler(number, WindowHandler::CloseRequested, Vec::new())
+                }) == Some(Value::Bool(false));
+                if !keep_open {
+                    return window::close(id);
+                }
+            }
+            Message::WindowMoved { id, x, y } => {
+                if let Some(number) = window_number(id) {
+                    let args = vec![Value::Int(i64::from(x)), Value::Int(i64::from(y))];
+                    self.call_window_handler(number, WindowHandler::Moved, args);
+                }
+            }
             Message::WindowFocused(id) => {
                 // Unfocus all, then focus the active one
                 for wid in self.window_manager.ids() {
@@ -1083,9 +2052,13 @@ impl App {
                 self.window_manager
                     .set_size(WindowId::from_iced(id), width, height);
                 let _ = self.lifecycle.on_resize(width, height);
+                if let Some(number) = window_number(id) {
+                    let args = vec![Value::Int(i64::from(width)), Value::Int(i64::from(height))];
+                    self.call_window_handler(number, WindowHandler::Resized, args);
+                }
             }
             Message::OpenWindow(settings) => {
-                let (id, open_task) = window::open(settings.to_iced());
+                let (id, open_task) = open_window(&settings);
                 // Pre-register with a temporary title, will be updated on open
                 let window_id = WindowId::from_iced(id);
                 self.window_manager.register(window_id, settings);
@@ -1188,8 +2161,7 @@ impl App {
                         .into_iter()
                         .map(|m| Value::String(Rc::new(m)))
                         .collect();
-                    let measures_arg =
-                        Value::List(Rc::new(std::cell::RefCell::new(measure_values)));
+                    let measures_arg = Value::list(measure_values);
                     if let Err(e) = executor.execute(callback_id, vec![measures_arg]) {
                         eprintln!("Cube measure select callback error: {e}");
                     }
@@ -1211,10 +2183,10 @@ impl App {
 
             // Theme switching
             Message::SetThemePreset(preset) => {
-                self.theme = StratumTheme::preset(preset);
+                self.set_theme(StratumTheme::preset(preset));
             }
             Message::SetCustomTheme { name, palette } => {
-                self.theme = StratumTheme::custom(name, palette);
+                self.set_theme(StratumTheme::custom(name, palette));
             }
 
             // Interactive/Mouse events
@@ -1320,13 +2292,30 @@ impl App {
                 key,
                 modifiers,
             } => {
+                // Alt+Left goes back, like the mouse's back button
+                if key == "Named(ArrowLeft)"
+                    && modifiers.alt
+                    && !modifiers.ctrl
+                    && !modifiers.shift
+                    && !modifiers.logo
+                {
+                    navigation::request_back();
+                }
+                // Menu bar shortcuts run their item's callback
+                if let Some(callback_id) = self.menu_shortcut(&key, modifiers) {
+                    if let Some(ref executor) = self.executor {
+                        if let Err(e) = executor.execute(callback_id, vec![]) {
+                            eprintln!("Menu shortcut callback error: {e}");
+                        }
+                    }
+                }
                 // Use the registered global key press callback instead of the placeholder in the message
                 if let Some(callback_id) = self.key_press_callback {
                     if let Some(ref executor) = self.executor {
-                        use stratum_core::bytecode::HashableValue;
+                        use stratum_core::bytecode::{HashableValue, ValueMap};
                         let key_arg = Value::String(Rc::new(key));
                         // Pack modifiers as a struct-like map
-                        let mut mods_map = std::collections::HashMap::new();
+                        let mut mods_map = ValueMap::new();
                         mods_map.insert(
                             HashableValue::String(Rc::new("shift".to_string())),
                             Value::Bool(modifiers.shift),
@@ -1343,7 +2332,7 @@ impl App {
                             HashableValue::String(Rc::new("logo".to_string())),
                             Value::Bool(modifiers.logo),
                         );
-                        let mods_arg = Value::Map(Rc::new(std::cell::RefCell::new(mods_map)));
+                        let mods_arg = Value::map(mods_map);
                         if let Err(e) = executor.execute(callback_id, vec![key_arg, mods_arg]) {
                             eprintln!("Key pressed callback error: {e}");
                         }
@@ -1358,9 +2347,9 @@ impl App {
                 // Use the registered global key release callback instead of the placeholder in the message
                 if let Some(callback_id) = self.key_release_callback {
                     if let Some(ref executor) = self.executor {
-                        use stratum_core::bytecode::HashableValue;
+                        use stratum_core::bytecode::{HashableValue, ValueMap};
                         let key_arg = Value::String(Rc::new(key));
-                        let mut mods_map = std::collections::HashMap::new();
+                        let mut mods_map = ValueMap::new();
                         mods_map.insert(
                             HashableValue::String(Rc::new("shift".to_string())),
                             Value::Bool(modifiers.shift),
@@ -1377,7 +2366,7 @@ impl App {
                             HashableValue::String(Rc::new("logo".to_string())),
                             Value::Bool(modifiers.logo),
                         );
-                        let mods_arg = Value::Map(Rc::new(std::cell::RefCell::new(mods_map)));
+                        let mods_arg = Value::map(mods_map);
                         if let Err(e) = executor.execute(callback_id, vec![key_arg, mods_arg]) {
                             eprintln!("Key released callback error: {e}");
                         }
@@ -1415,7 +2404,7 @@ impl App {
                             .iter()
                             .map(|p| Value::String(Rc::new(p.to_string_lossy().to_string())))
                             .collect();
-                        let paths_arg = Value::List(Rc::new(std::cell::RefCell::new(paths_values)));
+                        let paths_arg = Value::list(paths_values);
                         let _ = executor.execute(file_hover_cb, vec![paths_arg]);
                     }
                 }
@@ -1427,7 +2416,7 @@ impl App {
                             .iter()
                             .map(|p| Value::String(Rc::new(p.to_string_lossy().to_string())))
                             .collect();
-                        let paths_arg = Value::List(Rc::new(std::cell::RefCell::new(paths_values)));
+                        let paths_arg = Value::list(paths_values);
                         let _ = executor.execute(file_drop_cb, vec![paths_arg]);
                     }
                 }
@@ -1483,8 +2472,7 @@ impl App {
                         .iter()
                         .map(|m| Value::String(Rc::new(m.clone())))
                         .collect();
-                    self.state
-                        .update_field(&field, Value::List(Rc::new(RefCell::new(measure_values))));
+                    self.state.update_field(&field, Value::list(measure_values));
                 }
             }
 
@@ -1507,28 +2495,65 @@ impl App {
             }
         }
 
+        // Go back and run page hooks before the view shows the new page
+        self.check_pending_navigation();
+        let window_tasks = self.check_pending_windows();
+
+        // Check form fields against their rules before the view shows errors
+        form::revalidate(&self.state);
+        self.sync_focus_binding();
+
         // After any message processing, refresh the view if we have a view_fn
         // This ensures the UI reflects any state changes from callbacks
         self.refresh_view();
+        self.refresh_windows();
+        self.show_bound_values();
+        self.format_table_cells();
+        self.draw_canvases();
+        self.update_screen_reader();
 
         // Check if a theme change was requested by a callback (via Gui.set_theme())
         self.check_pending_theme();
+        self.check_pending_tray();
+        self.check_pending_file_drop();
+        self.check_pending_animations();
+        self.check_pending_timers();
+        let tasks = Task::batch([
+            window_tasks,
+            self.check_pending_tasks(),
+            self.load_images(),
+            accessibility::take_pending(),
+        ]);
 
         // Check if quit was requested by a callback (via Gui.quit())
         if let Some(quit_task) = self.check_quit_requested() {
             return quit_task;
         }
 
-        Task::none()
+        tasks
     }
 
-    /// Build the view from the current state
-    fn view(&self) -> Element<'_, Message> {
+    /// Build the view of a window from the current state
+    fn view(&self, id: window::Id) -> Element<'_, Message> {
         use crate::modal::modal_overlay;
 
+        // Windows opened by Gui.open_window() show their own tree
+        if let Some(number) = window_number(id).filter(|number| *number != MAIN_WINDOW) {
+            let content: Element<'_, Message> = match self.window_roots.get(&number) {
+                Some(root) => root.render(),
+                None => text("").into(),
+            };
+            return container(scrollable(content).width(Fill).height(Fill))
+                .padding(self.padding)
+                .center_x(Fill)
+                .center_y(Fill)
+                .into();
+        }
+
         // If a root element is provided, render it; otherwise show the demo
         let content: Element<'_, Message> = if let Some(ref root) = self.root_element {
             // Render the user-provided GUI element tree
+            accessibility::begin_frame();
             root.render()
         } else {
             // Fall back to the counter demo
@@ -1587,6 +2612,7 @@ impl App {
                             self.window_manager.next_window_number()
                         ))
                         .with_size(400, 300)
+                        .with_exit_on_close(false)
                     )),
                 button(text("Show Modal"))
                     .padding(8)
@@ -1696,6 +2722,7 @@ impl App {
     fn subscription(&self) -> Subscription<Message> {
         let mut subscriptions = vec![
             window::close_events().map(Message::WindowClosed),
+            window::close_requests().map(Message::WindowCloseRequested),
             window::resize_events().map(|(id, size)| Message::WindowResized {
                 id,
                 width: size.width as u32,
@@ -1705,8 +2732,15 @@ impl App {
 
         // Add keyboard and file drop event subscriptions
         // Note: We use iced::event::listen_with with a pure function to avoid closure capture issues
-        subscriptions.push(iced::event::listen_with(|event, _status, _id| {
+        subscriptions.push(iced::event::listen_with(|event, _status, id| {
             match event {
+                iced::Event::Window(iced::window::Event::Moved(position)) => {
+                    Some(Message::WindowMoved {
+                        id,
+                        x: position.x as i32,
+                        y: position.y as i32,
+                    })
+                }
                 // Keyboard events
                 iced::Event::Keyboard(iced::keyboard::Event::KeyPressed {
                     key, modifiers, ..
@@ -1740,10 +2774,109 @@ impl App {
                 iced::Event::Window(iced::window::Event::FilesHoveredLeft) => {
                     Some(Message::FileHoverLeft)
                 }
+                // The mouse's back button goes back a page
+                iced::Event::Mouse(iced::mouse::Event::ButtonPressed(
+                    iced::mouse::Button::Back,
+                )) => Some(Message::NavigateBack),
+                // Remember the cursor for placing context menus, without a message
+                iced::Event::Mouse(iced::mouse::Event::CursorMoved { position }) => {
+                    record_cursor(position);
+                    None
+                }
                 _ => None,
             }
         }));
 
+        // Tab, Enter and Space move and use keyboard focus, unless a widget
+        // such as a text field used the key itself
+        subscriptions.push(iced::event::listen_with(|event, status, _id| {
+            use iced::keyboard::{key::Named, Event, Key};
+
+            let iced::Event::Keyboard(Event::KeyPressed { key, modifiers, .. }) = event else {
+                return None;
+            };
+            if status == iced::event::Status::Captured
+                || modifiers.control()
+                || modifiers.alt()
+                || modifiers.logo()
+            {
+                return None;
+            }
+            match key {
+                Key::Named(Named::Tab) => Some(Message::MoveFocus {
+                    forward: !modifiers.shift(),
+                }),
+                Key::Named(Named::Enter | Named::Space) => Some(Message::ActivateFocus),
+                _ => None,
+            }
+        }));
+
+        // Track the cursor while a table column is dragged wider or narrower
+        if self.column_resize.is_some() {
+            subscriptions.push(iced::event::listen_with(
+                |event, _status, _id| match event {
+                    iced::Event::Mouse(iced::mouse::Event::CursorMoved { position }) => {
+                        Some(Message::DataTableResizeMove(position.x))
+                    }
+                    iced::Event::Mouse(iced::mouse::Event::ButtonReleased(
+                        iced::mouse::Button::Left,
+                    )) => Some(Message::DataTableResizeEnd),
+                    _ => None,
+                },
+            ));
+        }
+
+        // End a drag when the mouse is released, wherever that is
+        if dragging_kind().is_some() {
+            subscriptions.push(iced::event::listen_with(
+                |event, _status, _id| match event {
+                    iced::Event::Mouse(iced::mouse::Event::ButtonReleased(
+                        iced::mouse::Button::Left,
+                    )) => Some(Message::DragEnd),
+                    _ => None,
+                },
+            ));
+        }
+
+        // Step animations and transitions while anything is moving
+        if !self.animations.is_empty() || self.transitions.is_animating() {
+            subscriptions.push(iced::time::every(FRAME_INTERVAL).map(Message::AnimationTick));
+        }
+
+        // Check timers as often as the shortest of them needs
+        if let Some(tick) = tick_interval(&self.timers) {
+            subscriptions.push(iced::time::every(tick).map(Message::TimerTick));
+        }
+
+        // Follow the OS between light and dark mode while the theme does
+        if self.theme.follows_system() {
+            subscriptions.push(
+                iced::time::every(std::time::Duration::from_secs(2))
+                    .map(|_| Message::SystemThemePoll),
+            );
+        }
+
+        // Watch the source file for changes to the view code
+        if self.hot_reload.is_some() {
+            subscriptions
+                .push(iced::time::every(hot_reload::POLL_INTERVAL).map(|_| Message::HotReloadPoll));
+        }
+
+        // Poll the tray icon's menu while there is one
+        if self.tray.is_some() {
+            subscriptions.push(
+                iced::time::every(std::time::Duration::from_millis(200)).map(|_| Message::TrayPoll),
+            );
+        }
+
+        // Poll for screen reader requests while connected to any
+        if self.screen_reader.is_some() {
+            subscriptions.push(
+                iced::time::every(std::time::Duration::from_millis(200))
+                    .map(|_| Message::ScreenReaderPoll),
+            );
+        }
+
         Subscription::batch(subscriptions)
     }
 
@@ -1789,7 +2922,16 @@ mod tests {
             context_menu: None,
             root_element: None,
             view_fn: None,
+            hot_reload: None,
+            window_roots: HashMap::new(),
             selected_measures: Vec::new(),
+            column_resize: None,
+            tray: None,
+            screen_reader: None,
+            animations: Vec::new(),
+            transitions: Transitions::new(),
+            timers: Vec::new(),
+            tasks: Vec::new(),
         }
     }
 
@@ -1803,6 +2945,23 @@ mod tests {
         assert_eq!(runtime.get_count(), 0);
     }
 
+    #[test]
+    fn test_runtime_with_hot_reload() {
+        let runtime = GuiRuntime::new(create_counter_state(0));
+        assert!(runtime.hot_reload.is_none());
+        let runtime = runtime.with_hot_reload("app.strat");
+        assert_eq!(runtime.hot_reload, Some(PathBuf::from("app.strat")));
+    }
+
+    #[test]
+    fn test_reload_view_without_changes() {
+        let mut app = create_test_app(0);
+        assert!(!app.reload_view());
+        app.hot_reload = Some(Watcher::new("/nonexistent/app.strat"));
+        assert!(!app.reload_view());
+        assert!(app.view_fn.is_none());
+    }
+
     #[test]
     fn test_runtime_with_theme() {
         let state = create_counter_state(0);
@@ -1934,7 +3093,7 @@ mod tests {
         let state = create_counter_state(0);
         let runtime = GuiRuntime::new(state);
 
-        let handler = Value::NativeFunction(NativeFunction::new("test", 0, |_| Ok(Value::Null)));
+        let handler = Value::native(NativeFunction::new("test", 0, |_| Ok(Value::Null)));
         let id = runtime.register_callback(handler).unwrap();
 
         assert!(runtime.registry.borrow().contains(id));
@@ -2184,11 +3343,11 @@ mod tests {
         fields.insert("show_details".to_string(), Value::Bool(false));
         fields.insert(
             "items".to_string(),
-            Value::List(Rc::new(RefCell::new(vec![
+            Value::list(vec![
                 Value::String(Rc::new("Item 1".to_string())),
                 Value::String(Rc::new("Item 2".to_string())),
                 Value::String(Rc::new("Item 3".to_string())),
-            ]))),
+            ]),
         );
         let mut instance = StructInstance::new("BindingTestState".to_string());
         instance.fields = fields;
@@ -2214,7 +3373,16 @@ mod tests {
             context_menu: None,
             root_element: None,
             view_fn: None,
+            hot_reload: None,
+            window_roots: HashMap::new(),
             selected_measures: Vec::new(),
+            column_resize: None,
+            tray: None,
+            screen_reader: None,
+            animations: Vec::new(),
+            transitions: Transitions::new(),
+            timers: Vec::new(),
+            tasks: Vec::new(),
         }
     }
 
@@ -2714,6 +3882,365 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_e2e_virtual_table_interaction() {
+        // Test: scrolling, sorting, selecting and resizing a virtualized table
+        use crate::table::with_table_view;
+
+        let mut app = create_test_app(0);
+        let table = "test_e2e_virtual_table".to_string();
+
+        let _ = app.update(Message::DataTableScroll {
+            table: table.clone(),
+            offset: 2800.0,
+            height: 280.0,
+        });
+        let visible = with_table_view(&table, |view| view.visible(1_000_000, 28.0));
+        assert_eq!(visible, 96..115);
+
+        let _ = app.update(Message::DataTableToggleSort {
+            table: table.clone(),
+            column: "age".to_string(),
+        });
+        let _ = app.update(Message::DataTableSelect {
+            table: table.clone(),
+            row: 7,
+            on_selection_change: None,
+            on_row_click: None,
+        });
+        with_table_view(&table, |view| {
+            assert_eq!(view.sort, Some(("age".to_string(), true)));
+            assert_eq!(view.selected, Some(vec![7]));
+        });
+
+        let _ = app.update(Message::DataTableResizeStart {
+            table: table.clone(),
+            column: "age".to_string(),
+            width: 100.0,
+        });
+        let _ = app.update(Message::DataTableResizeMove(500.0));
+        let _ = app.update(Message::DataTableResizeMove(530.0));
+        let _ = app.update(Message::DataTableResizeEnd);
+        assert!(app.column_resize.is_none());
+        let width = with_table_view(&table, |view| view.widths.get("age").copied());
+        assert!(width.is_some_and(|w| (w - 130.0).abs() < f32::EPSILON));
+    }
+
+    #[test]
+    fn test_e2e_tree_and_section_binding() {
+        // Test: expanding tree nodes and sections updates their bound fields
+        use crate::tree::{section_expanded, with_tree_view};
+
+        let mut app = create_binding_test_app();
+        let tree = "test_e2e_tree".to_string();
+
+        let _ = app.update(Message::TreeToggle {
+            tree: tree.clone(),
+            node: "src".to_string(),
+            field_path: Some("items".to_string()),
+            on_expand: None,
+        });
+        let _ = app.update(Message::TreeSelect {
+            tree: tree.clone(),
+            node: "src/main.strat".to_string(),
+            on_select: None,
+        });
+        let Some(Value::List(expanded)) = app.state.get_field("items") else {
+            panic!("Expected expanded node list");
+        };
+        assert_eq!(
+            *expanded.borrow(),
+            [Value::String(Rc::new("src".to_string()))]
+        );
+        with_tree_view(&tree, |view| {
+            assert_eq!(view.selected.as_deref(), Some("src/main.strat"));
+        });
+
+        let _ = app.update(Message::SectionToggle {
+            section: "test_e2e_section".to_string(),
+            expanded: true,
+            field_path: Some("show_details".to_string()),
+            on_toggle: None,
+        });
+        assert!(section_expanded("test_e2e_section", false));
+        assert_eq!(app.state.get_field("show_details"), Some(Value::Bool(true)));
+    }
+
+    #[test]
+    fn test_e2e_context_menu_opens_at_cursor() {
+        // Test: right-clicking an element with a context menu shows it where the cursor is
+        use crate::menu::record_cursor;
+
+        let mut app = create_binding_test_app();
+        record_cursor(iced::Point::new(30.0, 45.0));
+        let _ = app.update(Message::OpenContextMenu {
+            items: vec![ContextMenuItem::new("Copy").on_select(CallbackId::new(1))],
+        });
+
+        let menu = app
+            .context_menu
+            .as_ref()
+            .expect("context menu should be open");
+        assert!((menu.x - 30.0).abs() < f32::EPSILON);
+        assert!((menu.y - 45.0).abs() < f32::EPSILON);
+        assert_eq!(menu.items[0].label, "Copy");
+
+        // Without a tray or screen reader, polling does nothing
+        let _ = app.update(Message::TrayPoll);
+        let _ = app.update(Message::ScreenReaderPoll);
+        let _ = app.update(Message::HideContextMenu);
+        assert!(app.context_menu.is_none());
+    }
+
+    #[test]
+    fn test_e2e_bound_input_validation() {
+        // Test: a bound text field shows its field and its error once edited
+        use crate::element::{GuiElement, GuiElementKind};
+        use crate::form::{field_error, set_rules, FieldRules, Rule};
+
+        let mut app = create_binding_test_app();
+        app.root_element = Some(Arc::new(
+            GuiElement::text_field().bind_field("text_value").build(),
+        ));
+        set_rules(
+            "text_value",
+            FieldRules {
+                rules: vec![Rule::MinLength(3)],
+                message: None,
+            },
+        );
+
+        let _ = app.update(Message::SetStringField {
+            field: "text_value".to_string(),
+            value: "ab".to_string(),
+        });
+        let Some(GuiElementKind::TextField(config)) =
+            app.root_element.as_ref().map(|root| &root.kind)
+        else {
+            panic!("Expected TextField element");
+        };
+        assert_eq!(config.value, "ab");
+        assert_eq!(
+            field_error("text_value").as_deref(),
+            Some("Must be at least 3 characters")
+        );
+
+        let _ = app.update(Message::SetStringField {
+            field: "text_value".to_string(),
+            value: "abc".to_string(),
+        });
+        assert!(field_error("text_value").is_none());
+    }
+
+    #[test]
+    fn test_e2e_animation_steps_field() {
+        // Test: Gui.animate() moves a field over time, then stops ticking
+        use crate::animation::{Animation, Easing};
+        use crate::bindings::request_animation;
+        use std::time::Duration;
+
+        let mut app = create_binding_test_app();
+        request_animation(Animation::new(
+            "count",
+            10.0,
+            Duration::from_millis(100),
+            Easing::Linear,
+        ));
+        app.check_pending_animations();
+        assert_eq!(app.animations.len(), 1);
+
+        let _ = app.update(Message::AnimationTick(
+            Instant::now() + Duration::from_millis(200),
+        ));
+        assert_eq!(app.state.get_field("count"), Some(Value::Int(10)));
+        assert!(app.animations.is_empty());
+    }
+
+    #[test]
+    fn test_e2e_navigator_shows_current_page() {
+        // Test: the navigator shows the page on top of the stack, and the back
+        // button pops it unless its on_back hook says no
+        use crate::navigation::{self, Page};
+        use std::collections::HashMap;
+        use stratum_core::bytecode::NativeFunction;
+
+        fn page_view(args: &[Value]) -> Result<Value, String> {
+            Ok(GuiElement::text(format!("{}", args[1]))
+                .build()
+                .into_value())
+        }
+        fn keep_open(_args: &[Value]) -> Result<Value, String> {
+            Ok(Value::Bool(false))
+        }
+
+        navigation::reset();
+        let view = Value::native(NativeFunction::new("page_view", 2, page_view));
+        let mut pages = HashMap::new();
+        pages.insert("home".to_string(), Page::from_value(&view).unwrap());
+        pages.insert(
+            "editor".to_string(),
+            Page {
+                on_back: Some(Value::native(NativeFunction::new(
+                    "keep_open",
+                    1,
+                    keep_open,
+                ))),
+                ..Page::from_value(&view).unwrap()
+            },
+        );
+        pages.insert("detail".to_string(), Page::from_value(&view).unwrap());
+        navigation::set_pages(pages, "home", Value::Null).unwrap();
+        navigation::push("detail", Value::Int(7)).unwrap();
+
+        let mut app = create_binding_test_app();
+        let vm = Rc::new(RefCell::new(VM::new()));
+        app.executor = Some(CallbackExecutor::new(vm, app.registry.clone()));
+
+        let root = Arc::new(
+            GuiElement::vstack()
+                .child(GuiElement::navigator().build())
+                .build(),
+        );
+        let shown = app.show_pages(&root);
+        let navigator = &shown.children[0];
+        match &navigator.kind {
+            crate::element::GuiElementKind::Navigator(config) => {
+                assert_eq!(config.page.as_deref(), Some("detail"));
+            }
+            other => panic!("Expected Navigator, got {other:?}"),
+        }
+        assert_eq!(navigator.children.len(), 1);
+
+        let _ = app.update(Message::NavigateBack);
+        assert_eq!(navigation::current().unwrap().page, "home");
+
+        // The editor keeps itself open
+        navigation::push("editor", Value::Null).unwrap();
+        let _ = app.update(Message::NavigateBack);
+        assert_eq!(navigation::current().unwrap().page, "editor");
+        navigation::reset();
+    }
+
+    #[test]
+    fn test_e2e_timers_and_tasks() {
+        // Test: due timers fire through the message loop, one-shot timers stop,
+        // and a task's result or error reaches its callback
+        use crate::bindings::{request_field_update, request_task, request_timer};
+        use crate::timer::{BackgroundTask, Timer};
+        use std::time::Duration;
+        use stratum_core::bytecode::NativeFunction;
+
+        fn bump(_args: &[Value]) -> Result<Value, String> {
+            request_field_update("count".to_string(), Value::Int(7));
+            Ok(Value::Null)
+        }
+        fn work(_args: &[Value]) -> Result<Value, String> {
+            Ok(Value::string("done"))
+        }
+        fn store(args: &[Value]) -> Result<Value, String> {
+            request_field_update("text_value".to_string(), args[0].clone());
+            Ok(Value::Null)
+        }
+        let native = |name: &'static str, f: fn(&[Value]) -> Result<Value, String>| {
+            Value::native(NativeFunction::new(name, -1, f))
+        };
+
+        let mut app = create_binding_test_app();
+        let vm = Rc::new(RefCell::new(VM::new()));
+        app.executor = Some(CallbackExecutor::new(vm, app.registry.clone()));
+
+        let start = Instant::now();
+        request_timer(Timer::new(
+            Duration::from_millis(100),
+            true,
+            native("bump", bump),
+            start,
+        ));
+        request_timer(Timer::new(
+            Duration::from_millis(50),
+            false,
+            native("bump", bump),
+            start,
+        ));
+        app.check_pending_timers();
+        assert_eq!(app.timers.len(), 2);
+
+        let _ = app.update(Message::TimerTick(start + Duration::from_millis(60)));
+        assert_eq!(app.state.get_field("count"), Some(Value::Int(7)));
+        assert_eq!(app.timers.len(), 1);
+
+        // The work runs on a worker thread; its result comes back as a message
+        let task = BackgroundTask::new(native("work", work), Some(native("store", store)), None);
+        let id = task.id;
+        request_task(task);
+        let _ = app.check_pending_tasks();
+        let _ = app.update(Message::RunTask(id));
+        assert_eq!(app.tasks.len(), 1);
+        let _ = app.update(Message::TaskFinished {
+            id,
+            result: Ok(Portable::String("done".to_string())),
+        });
+        assert_eq!(
+            app.state.get_field("text_value"),
+            Some(Value::string("done"))
+        );
+        assert!(app.tasks.is_empty());
+
+        // Work that can't move to another thread fails at once
+        let binding = Value::StateBinding(Rc::new("count".to_string()));
+        let task = BackgroundTask::new(binding, None, Some(native("store", store)));
+        let id = task.id;
+        request_task(task);
+        let _ = app.check_pending_tasks();
+        let _ = app.update(Message::RunTask(id));
+        assert!(app.tasks.is_empty());
+        assert!(matches!(
+            app.state.get_field("text_value"),
+            Some(Value::String(error)) if error.contains("another thread")
+        ));
+    }
+
+    #[test]
+    fn test_e2e_drag_and_drop() {
+        // Test: a drag ends at a drop target or when the mouse is released elsewhere
+        use crate::dnd::register_payload;
+
+        let mut app = create_binding_test_app();
+        let payload = register_payload(Value::string("task-1"));
+        let _ = app.update(Message::DragStart {
+            kind: "task".to_string(),
+            payload,
+        });
+        assert_eq!(dragging_kind().as_deref(), Some("task"));
+
+        let _ = app.update(Message::Drop {
+            accepts: vec!["task".to_string()],
+            on_drop: Some(CallbackId::new(1)),
+        });
+        assert!(dragging_kind().is_none());
+
+        let _ = app.update(Message::DragStart {
+            kind: "task".to_string(),
+            payload,
+        });
+        let _ = app.update(Message::DragEnd);
+        assert!(dragging_kind().is_none());
+    }
+
+    #[test]
+    fn test_e2e_file_drop_callbacks_registered() {
+        // Test: Gui.on_file_drop() and friends reach the running app
+        use crate::bindings::request_file_drop_callback;
+
+        let mut app = create_binding_test_app();
+        request_file_drop_callback(FileDropEvent::Drop, CallbackId::new(4));
+        request_file_drop_callback(FileDropEvent::Hover, CallbackId::new(5));
+        app.check_pending_file_drop();
+        assert_eq!(app.file_drop_callback, Some(CallbackId::new(4)));
+        assert_eq!(app.file_hover_callback, Some(CallbackId::new(5)));
+        assert!(app.file_hover_left_callback.is_none());
+    }
+
     // -------------------------------------------------------------------------
     // Chart Rendering End-to-End Tests
     // -------------------------------------------------------------------------
@@ -2965,7 +4492,16 @@ mod tests {
             context_menu: None,
             root_element: None,
             view_fn: None,
+            hot_reload: None,
+            window_roots: HashMap::new(),
             selected_measures: Vec::new(),
+            column_resize: None,
+            tray: None,
+            screen_reader: None,
+            animations: Vec::new(),
+            transitions: Transitions::new(),
+            timers: Vec::new(),
+            tasks: Vec::new(),
         };
 
         // Initially no todos are completed
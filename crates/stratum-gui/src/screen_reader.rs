@@ -0,0 +1,230 @@
+//! Screen reader connection
+//!
+//! With the `accesskit` feature on Linux and the BSDs, the app connects to
+//! AT-SPI through AccessKit once its window is up, and hands over the
+//! accessibility tree of the main window after every view rebuild and focus
+//! change, so screen readers such as Orca can read it. Focus and click
+//! requests from the screen reader are polled like the tray menu and carried
+//! out as keyboard focus moves and activations.
+//!
+//! AccessKit's Windows and macOS adapters have to subclass the native window
+//! before it's first shown, which iced doesn't allow, so elsewhere, and
+//! without the feature, there's no screen reader to connect to.
+
+use crate::accessibility::AccessNode;
+
+#[cfg(all(
+    feature = "accesskit",
+    any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )
+))]
+pub use platform::ScreenReader;
+
+#[cfg(all(
+    feature = "accesskit",
+    any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )
+))]
+mod platform {
+    use std::sync::mpsc::{self, Receiver, Sender};
+    use std::sync::{Arc, Mutex};
+
+    use accesskit::{
+        ActionHandler, ActionRequest, ActivationHandler, DeactivationHandler, TreeUpdate,
+    };
+    use accesskit_unix::Adapter;
+
+    use super::AccessNode;
+    use crate::accessibility::{self, tree_update};
+
+    /// The latest tree, for a screen reader that starts after the app
+    type Latest = Arc<Mutex<Option<TreeUpdate>>>;
+
+    /// A connection to the platform's screen readers, kept while it lives
+    pub struct ScreenReader {
+        adapter: Adapter,
+        latest: Latest,
+        actions: Receiver<ActionRequest>,
+    }
+
+    impl ScreenReader {
+        /// Connect to the screen readers of the desktop
+        #[must_use]
+        pub fn connect() -> Option<Self> {
+            let latest = Latest::default();
+            let (sender, actions) = mpsc::channel();
+            let adapter = Adapter::new(
+                Activation(Arc::clone(&latest)),
+                Actions(Mutex::new(sender)),
+                Deactivation,
+            );
+            Some(Self {
+                adapter,
+                latest,
+                actions,
+            })
+        }
+
+        /// Hand a view's tree to the screen reader, unless it hasn't changed
+        pub fn update(&mut self, root: &AccessNode, focused: Option<usize>) {
+            let update = tree_update(root, focused);
+            {
+                let mut latest = self.latest.lock().unwrap_or_else(|e| e.into_inner());
+                if latest.as_ref() == Some(&update) {
+                    return;
+                }
+                *latest = Some(update.clone());
+            }
+            self.adapter.update_if_active(|| update);
+        }
+
+        /// Carry out the actions asked for since the last poll. Returns
+        /// whether there were any.
+        pub fn poll(&self) -> bool {
+            let latest = self.latest.lock().unwrap_or_else(|e| e.into_inner());
+            let Some(update) = latest.as_ref() else {
+                return false;
+            };
+            let mut any = false;
+            for request in self.actions.try_iter() {
+                carry_out(update, &request);
+                any = true;
+            }
+            any
+        }
+    }
+
+    struct Activation(Latest);
+
+    impl ActivationHandler for Activation {
+        fn request_initial_tree(&mut self) -> Option<TreeUpdate> {
+            self.0.lock().unwrap_or_else(|e| e.into_inner()).clone()
+        }
+    }
+
+    struct Actions(Mutex<Sender<ActionRequest>>);
+
+    impl ActionHandler for Actions {
+        fn do_action(&mut self, request: ActionRequest) {
+            // The app has quit if nothing receives
+            let _ = self
+                .0
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .send(request);
+        }
+    }
+
+    struct Deactivation;
+
+    impl DeactivationHandler for Deactivation {
+        fn deactivate_accessibility(&mut self) {}
+    }
+
+    /// Carry out a screen reader's request on the tree it was made on: focus a
+    /// focusable node, or focus and activate it for a click
+    fn carry_out(update: &TreeUpdate, request: &ActionRequest) {
+        use accesskit::Action;
+
+        // Focusable nodes are numbered as the focusable elements are rendered
+        let number = update
+            .nodes
+            .iter()
+            .filter(|(_, node)| node.supports_action(Action::Focus))
+            .position(|(id, _)| *id == request.target);
+        let Some(number) = number else {
+            return;
+        };
+        match request.action {
+            Action::Focus => accessibility::focus(number),
+            Action::Click => {
+                accessibility::focus(number);
+                accessibility::activate();
+            }
+            _ => {}
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::accessibility::access_tree;
+        use crate::element::GuiElement;
+        use accesskit::{Action, NodeId};
+
+        #[test]
+        fn test_actions_move_focus() {
+            accessibility::reset();
+            let root = GuiElement::vstack()
+                .child(GuiElement::text("Sign in").build())
+                .child(GuiElement::button("Cancel").build())
+                .child(GuiElement::button("Continue").build())
+                .build();
+            let update = tree_update(&access_tree(&root), None);
+
+            // Nodes are the root, the text and the two buttons
+            let request = |action, target| ActionRequest {
+                action,
+                target: NodeId(target),
+                data: None,
+            };
+            carry_out(&update, &request(Action::Focus, 3));
+            assert_eq!(accessibility::focused(), Some(1));
+            // The text can't take focus
+            carry_out(&update, &request(Action::Focus, 1));
+            assert_eq!(accessibility::focused(), Some(1));
+            carry_out(&update, &request(Action::Click, 2));
+            assert_eq!(accessibility::focused(), Some(0));
+            accessibility::reset();
+        }
+    }
+}
+
+/// Stand-in where there's no screen reader to connect to
+#[cfg(not(all(
+    feature = "accesskit",
+    any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )
+)))]
+pub struct ScreenReader;
+
+#[cfg(not(all(
+    feature = "accesskit",
+    any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )
+)))]
+impl ScreenReader {
+    /// Never connects
+    #[must_use]
+    pub fn connect() -> Option<Self> {
+        None
+    }
+
+    /// Does nothing
+    pub fn update(&mut self, _root: &AccessNode, _focused: Option<usize>) {}
+
+    /// Never any actions
+    pub fn poll(&self) -> bool {
+        false
+    }
+}
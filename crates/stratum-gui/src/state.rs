@@ -275,7 +275,7 @@ impl ReactiveState {
     /// # Example
     /// ```ignore
     /// // Given state with field "count" = 42
-    /// let binding = Value::StateBinding("count".to_string());
+    /// let binding = Value::state_binding("count");
     /// let resolved = state.resolve_binding(&binding);
     /// assert_eq!(resolved, Value::Int(42));
     /// ```
@@ -296,6 +296,27 @@ impl ReactiveState {
         }
     }
 
+    /// Write a value from a bound input widget, keeping the field's type
+    /// where the input allows: text typed into a number field is stored as
+    /// a number once it parses, and a slider over an integer field stores
+    /// integers. Anything else is stored as given.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn set_bound(&self, path: &str, value: Value) -> bool {
+        let value = match (self.get_path(path), value) {
+            (Some(Value::Int(_)), Value::String(text)) => match text.trim().parse::<i64>() {
+                Ok(i) => Value::Int(i),
+                Err(_) => Value::String(text),
+            },
+            (Some(Value::Float(_)), Value::String(text)) => match text.trim().parse::<f64>() {
+                Ok(f) => Value::Float(f),
+                Err(_) => Value::String(text),
+            },
+            (Some(Value::Int(_)), Value::Float(f)) => Value::Int(f.round() as i64),
+            (_, value) => value,
+        };
+        self.update_path(path, value)
+    }
+
     /// Create a FieldBinding for the given path
     #[must_use]
     pub fn bind(&self, path: &str) -> FieldBinding {
@@ -632,11 +653,7 @@ mod tests {
 
     #[test]
     fn test_get_list() {
-        let list = Value::List(Rc::new(RefCell::new(vec![
-            Value::Int(1),
-            Value::Int(2),
-            Value::Int(3),
-        ])));
+        let list = Value::list(vec![Value::Int(1), Value::Int(2), Value::Int(3)]);
 
         let mut fields = HashMap::new();
         fields.insert("items".to_string(), list);
@@ -657,7 +674,7 @@ mod tests {
         let state = ReactiveState::new(create_struct("State", fields));
 
         // Resolving a StateBinding returns the value at that path
-        let binding = Value::StateBinding("count".to_string());
+        let binding = Value::state_binding("count");
         let resolved = state.resolve_binding(&binding);
         assert_eq!(resolved, Value::Int(42));
 
@@ -667,14 +684,14 @@ mod tests {
         assert_eq!(resolved, Value::Int(100));
 
         // Resolving a non-existent path returns Null
-        let bad_binding = Value::StateBinding("nonexistent".to_string());
+        let bad_binding = Value::state_binding("nonexistent");
         let resolved = state.resolve_binding(&bad_binding);
         assert_eq!(resolved, Value::Null);
     }
 
     #[test]
     fn test_binding_path() {
-        let binding = Value::StateBinding("state.count".to_string());
+        let binding = Value::state_binding("state.count");
         assert_eq!(ReactiveState::binding_path(&binding), Some("state.count"));
 
         let non_binding = Value::Int(42);
@@ -747,4 +764,30 @@ mod tests {
             panic!("Expected NeedsCompute after dependency changed");
         }
     }
+
+    #[test]
+    fn test_set_bound_keeps_field_types() {
+        let mut fields = HashMap::new();
+        fields.insert("age".to_string(), Value::Int(30));
+        fields.insert("ratio".to_string(), Value::Float(0.5));
+        fields.insert("name".to_string(), Value::string("Ada"));
+        let state = ReactiveState::new(create_struct("Form", fields));
+
+        assert!(state.set_bound("age", Value::string(" 42 ")));
+        assert_eq!(state.get_field("age"), Some(Value::Int(42)));
+        assert!(state.set_bound("ratio", Value::string("0.25")));
+        assert_eq!(state.get_field("ratio"), Some(Value::Float(0.25)));
+        assert!(state.set_bound("name", Value::string("Grace")));
+        assert_eq!(state.get_field("name"), Some(Value::string("Grace")));
+
+        // Sliders over integer fields store integers
+        state.set_bound("age", Value::Float(12.6));
+        assert_eq!(state.get_field("age"), Some(Value::Int(13)));
+
+        // Text that isn't a number yet is kept so it can be shown and validated
+        state.set_bound("age", Value::string("4x"));
+        assert_eq!(state.get_field("age"), Some(Value::string("4x")));
+
+        assert!(!state.set_bound("missing", Value::Int(1)));
+    }
 }
@@ -0,0 +1,351 @@
+//! Virtualized tables
+//!
+//! `Gui.table` draws only the rows scrolled into view, so a table over a
+//! million-row DataFrame costs no more to render than one over fifty. The
+//! view function rebuilds the element tree on every refresh, so what the
+//! user does to a table in between (scrolling, resizing a column, sorting,
+//! selecting rows) is kept here, keyed by the table's id.
+
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
+
+use stratum_core::bytecode::Value;
+use stratum_core::data::{DataFrame, Series};
+
+/// Height of a table row in pixels
+pub const DEFAULT_ROW_HEIGHT: f32 = 28.0;
+
+/// Width of a column without a set width
+pub const DEFAULT_COLUMN_WIDTH: f32 = 140.0;
+
+/// Narrowest a column can be dragged to
+pub const MIN_COLUMN_WIDTH: f32 = 40.0;
+
+/// Rows rendered past each edge of the viewport, so fast scrolling
+/// doesn't show empty space before the next frame
+const OVERSCAN: usize = 4;
+
+/// Viewport height assumed before the table reports its own
+const DEFAULT_VIEWPORT_HEIGHT: f32 = 600.0;
+
+thread_local! {
+    static TABLE_VIEWS: RefCell<HashMap<String, TableView>> = RefCell::new(HashMap::new());
+}
+
+/// What the user did to a table between view refreshes
+#[derive(Debug, Clone, Default)]
+pub struct TableView {
+    /// Scroll offset in pixels
+    pub offset: f32,
+    /// Height of the visible area, `None` until the table is scrolled
+    pub viewport_height: Option<f32>,
+    /// Column widths set by dragging
+    pub widths: HashMap<String, f32>,
+    /// Column sorted by clicking its header, and whether ascending
+    pub sort: Option<(String, bool)>,
+    /// Selected rows (indices into the DataFrame), seeded from the table's
+    /// `selected_rows` when first drawn
+    pub selected: Option<Vec<usize>>,
+    /// Display order of the rows for `sort`
+    order: Option<SortOrder>,
+    /// Text of formatted cells by row and column
+    formatted: HashMap<(usize, String), String>,
+    /// DataFrame the formatted cells were computed for
+    formatted_for: usize,
+}
+
+#[derive(Debug, Clone)]
+struct SortOrder {
+    /// DataFrame the order was computed for
+    dataframe: usize,
+    column: String,
+    ascending: bool,
+    rows: Arc<Vec<usize>>,
+}
+
+impl TableView {
+    /// Rows in display order, sorted by the clicked column if any.
+    /// The order is computed once per DataFrame and column.
+    pub fn row_order(&mut self, df: &Arc<DataFrame>) -> Option<Arc<Vec<usize>>> {
+        let (column, ascending) = self.sort.clone()?;
+        let key = dataframe_key(df);
+        let stale = match &self.order {
+            Some(order) => {
+                order.dataframe != key || order.column != column || order.ascending != ascending
+            }
+            None => true,
+        };
+        if stale {
+            self.order = Some(SortOrder {
+                dataframe: key,
+                rows: Arc::new(sort_order(df, &column, ascending)),
+                column,
+                ascending,
+            });
+        }
+        self.order.as_ref().map(|order| Arc::clone(&order.rows))
+    }
+
+    /// Toggle sorting by a column: ascending first, then descending
+    pub fn toggle_sort(&mut self, column: String) {
+        let ascending = match &self.sort {
+            Some((sorted, ascending)) if *sorted == column => !ascending,
+            _ => true,
+        };
+        self.sort = Some((column, ascending));
+    }
+
+    /// Select or deselect a row and return the selection
+    pub fn toggle_row(&mut self, row: usize) -> Vec<usize> {
+        let selected = self.selected.get_or_insert_with(Vec::new);
+        if let Some(index) = selected.iter().position(|&r| r == row) {
+            selected.remove(index);
+        } else {
+            selected.push(row);
+        }
+        selected.clone()
+    }
+
+    /// Formatted text of a cell, if a formatter has run for it
+    pub fn formatted(&self, row: usize, column: &str) -> Option<&str> {
+        self.formatted
+            .get(&(row, column.to_string()))
+            .map(String::as_str)
+    }
+
+    /// Whether a cell still needs formatting for a DataFrame. A new
+    /// DataFrame drops the text formatted for the old one.
+    pub fn needs_format(&mut self, df: &Arc<DataFrame>, row: usize, column: &str) -> bool {
+        let key = dataframe_key(df);
+        if self.formatted_for != key {
+            self.formatted.clear();
+            self.formatted_for = key;
+        }
+        !self.formatted.contains_key(&(row, column.to_string()))
+    }
+
+    /// Store the formatted text of a cell
+    pub fn set_formatted(&mut self, row: usize, column: &str, text: String) {
+        self.formatted.insert((row, column.to_string()), text);
+    }
+
+    /// Positions (not DataFrame rows) in view, with overscan
+    pub fn visible(&self, total: usize, row_height: f32) -> Range<usize> {
+        visible_rows(
+            total,
+            row_height,
+            self.offset,
+            self.viewport_height.unwrap_or(DEFAULT_VIEWPORT_HEIGHT),
+        )
+    }
+}
+
+/// Run a function on a table's view state, creating it if needed
+pub fn with_table_view<R>(id: &str, f: impl FnOnce(&mut TableView) -> R) -> R {
+    TABLE_VIEWS.with(|views| {
+        let mut views = views.borrow_mut();
+        f(views.entry(id.to_string()).or_default())
+    })
+}
+
+/// Forget the view state of all tables
+pub fn clear_table_views() {
+    TABLE_VIEWS.with(|views| views.borrow_mut().clear());
+}
+
+/// Identity of a DataFrame, to tell when a table is given new data
+fn dataframe_key(df: &Arc<DataFrame>) -> usize {
+    Arc::as_ptr(df) as usize
+}
+
+/// Positions of the rows in a viewport, with a few rows of overscan
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss
+)]
+pub fn visible_rows(
+    total: usize,
+    row_height: f32,
+    offset: f32,
+    viewport_height: f32,
+) -> Range<usize> {
+    if total == 0 || row_height <= 0.0 {
+        return 0..0;
+    }
+    let first = (offset.max(0.0) / row_height).floor() as usize;
+    let count = (viewport_height.max(0.0) / row_height).ceil() as usize + 1;
+    let start = first.saturating_sub(OVERSCAN).min(total);
+    let end = (first + count + OVERSCAN).min(total);
+    start..end
+}
+
+/// A column being resized by dragging the edge of its header
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnResize {
+    pub table: String,
+    pub column: String,
+    /// Width when the drag started
+    pub width: f32,
+    /// Cursor x when the drag started, set by the first move
+    pub start_x: Option<f32>,
+}
+
+impl ColumnResize {
+    /// Width of the column with the cursor at `x`
+    pub fn drag(&mut self, x: f32) -> f32 {
+        let start = *self.start_x.get_or_insert(x);
+        (self.width + x - start).max(MIN_COLUMN_WIDTH)
+    }
+}
+
+/// Row indices of a DataFrame sorted by a column. Nulls sort last in
+/// either direction and equal values keep their order.
+pub fn sort_order(df: &DataFrame, column: &str, ascending: bool) -> Vec<usize> {
+    let mut rows: Vec<usize> = (0..df.num_rows()).collect();
+    let Ok(series) = df.column(column) else {
+        return rows;
+    };
+    let values: Vec<Value> = rows
+        .iter()
+        .map(|&row| series.get(row).unwrap_or(Value::Null))
+        .collect();
+    rows.sort_by(|&a, &b| match (&values[a], &values[b]) {
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => Ordering::Greater,
+        (_, Value::Null) => Ordering::Less,
+        (x, y) if ascending => compare_values(x, y),
+        (x, y) => compare_values(y, x),
+    });
+    rows
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn compare_values(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Int(x), Value::Int(y)) => x.cmp(y),
+        (Value::Float(x), Value::Float(y)) => x.total_cmp(y),
+        (Value::Int(x), Value::Float(y)) => (*x as f64).total_cmp(y),
+        (Value::Float(x), Value::Int(y)) => x.total_cmp(&(*y as f64)),
+        (Value::Bool(x), Value::Bool(y)) => x.cmp(y),
+        (Value::String(x), Value::String(y)) => x.cmp(y),
+        _ => format!("{a}").cmp(&format!("{b}")),
+    }
+}
+
+/// Build a DataFrame from column names and rows of values
+pub fn rows_to_dataframe(columns: &[String], rows: &[Vec<Value>]) -> Result<DataFrame, String> {
+    if let Some((index, row)) = rows
+        .iter()
+        .enumerate()
+        .find(|(_, row)| row.len() != columns.len())
+    {
+        return Err(format!(
+            "row {index} has {} values, expected {} (one per column)",
+            row.len(),
+            columns.len()
+        ));
+    }
+    let series = columns
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let values: Vec<Value> = rows.iter().map(|row| row[i].clone()).collect();
+            Series::from_values(name.as_str(), &values).map_err(|e| format!("column '{name}': {e}"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    DataFrame::from_series(series).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn people() -> Arc<DataFrame> {
+        let columns = vec!["name".to_string(), "age".to_string()];
+        let rows = vec![
+            vec![Value::string("Cleo"), Value::Int(41)],
+            vec![Value::string("Ari"), Value::Null],
+            vec![Value::string("Bea"), Value::Int(29)],
+        ];
+        Arc::new(rows_to_dataframe(&columns, &rows).unwrap())
+    }
+
+    #[test]
+    fn test_visible_rows() {
+        assert_eq!(visible_rows(0, 28.0, 0.0, 600.0), 0..0);
+        assert_eq!(visible_rows(10, 28.0, 0.0, 600.0), 0..10);
+        // 100 px rows, 300 px viewport scrolled to row 50
+        assert_eq!(visible_rows(1_000_000, 100.0, 5000.0, 300.0), 46..58);
+        assert_eq!(visible_rows(52, 100.0, 5000.0, 300.0), 46..52);
+    }
+
+    #[test]
+    fn test_rows_to_dataframe() {
+        let df = people();
+        assert_eq!(df.num_rows(), 3);
+        assert_eq!(df.columns(), ["name", "age"]);
+
+        let error = rows_to_dataframe(&["a".to_string()], &[vec![Value::Int(1), Value::Int(2)]])
+            .unwrap_err();
+        assert!(error.contains("row 0 has 2 values"));
+    }
+
+    #[test]
+    fn test_sort_order() {
+        let df = people();
+        assert_eq!(sort_order(&df, "name", true), [1, 2, 0]);
+        assert_eq!(sort_order(&df, "name", false), [0, 2, 1]);
+        // Nulls last either way
+        assert_eq!(sort_order(&df, "age", true), [2, 0, 1]);
+        assert_eq!(sort_order(&df, "age", false), [0, 2, 1]);
+        assert_eq!(sort_order(&df, "missing", true), [0, 1, 2]);
+    }
+
+    #[test]
+    fn test_table_view_sort_and_select() {
+        let df = people();
+        let mut view = TableView::default();
+        assert_eq!(view.row_order(&df), None);
+
+        view.toggle_sort("name".to_string());
+        assert_eq!(view.row_order(&df).unwrap().as_slice(), [1, 2, 0]);
+        view.toggle_sort("name".to_string());
+        assert_eq!(view.row_order(&df).unwrap().as_slice(), [0, 2, 1]);
+
+        view.selected = Some(vec![0]);
+        assert_eq!(view.toggle_row(1), [0, 1]);
+        assert_eq!(view.toggle_row(0), [1]);
+    }
+
+    #[test]
+    fn test_formatted_cells_follow_dataframe() {
+        let df = people();
+        let mut view = TableView::default();
+        assert!(view.needs_format(&df, 0, "age"));
+        view.set_formatted(0, "age", "41 years".to_string());
+        assert!(!view.needs_format(&df, 0, "age"));
+        assert_eq!(view.formatted(0, "age"), Some("41 years"));
+
+        // New data drops the old text
+        let other = people();
+        assert!(view.needs_format(&other, 0, "age"));
+        assert_eq!(view.formatted(0, "age"), None);
+    }
+
+    #[test]
+    fn test_column_resize() {
+        let mut resize = ColumnResize {
+            table: "t".to_string(),
+            column: "name".to_string(),
+            width: 100.0,
+            start_x: None,
+        };
+        assert!((resize.drag(300.0) - 100.0).abs() < f32::EPSILON);
+        assert!((resize.drag(350.0) - 150.0).abs() < f32::EPSILON);
+        assert!((resize.drag(0.0) - MIN_COLUMN_WIDTH).abs() < f32::EPSILON);
+    }
+}
@@ -6,9 +6,71 @@
 //! - Custom theme creation from Stratum code
 //! - Runtime theme switching
 //! - Widget-level styling
+//! - Themes defined in Stratum code, with fonts and per-widget styles
+//! - Following the OS's light or dark mode
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 use iced::theme::Palette;
-use iced::Theme;
+use iced::widget::{button, container, text_input};
+use iced::{Background, Border, Font, Theme};
+
+/// Whether the OS was last seen in light mode
+static SYSTEM_LIGHT: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// The theme the app shows, for widgets styled from it as they render
+    static ACTIVE: RefCell<Option<Arc<ThemeDefinition>>> = const { RefCell::new(None) };
+}
+
+/// Ask the OS whether it is in light or dark mode, returning true if the
+/// answer changed since last asked. An OS that doesn't say counts as dark.
+pub fn refresh_system_mode() -> bool {
+    let light = matches!(dark_light::detect(), Ok(dark_light::Mode::Light));
+    SYSTEM_LIGHT.swap(light, Ordering::Relaxed) != light
+}
+
+/// Whether the OS is in light mode, as of the last [`refresh_system_mode`]
+#[must_use]
+pub fn system_is_light() -> bool {
+    SYSTEM_LIGHT.load(Ordering::Relaxed)
+}
+
+/// Make a theme the one widgets are styled from as they render
+pub fn set_active(theme: &StratumTheme) {
+    let definition = match theme {
+        StratumTheme::Defined(definition) => Some(Arc::clone(definition)),
+        _ => None,
+    };
+    ACTIVE.with(|active| *active.borrow_mut() = definition);
+}
+
+/// Fonts of the theme shown
+#[must_use]
+pub fn active_typography() -> Typography {
+    ACTIVE.with(|active| {
+        active
+            .borrow()
+            .as_ref()
+            .map(|d| d.typography.clone())
+            .unwrap_or_default()
+    })
+}
+
+/// Style the theme shown gives a kind of widget: "button", "text_input",
+/// "container" or "text"
+#[must_use]
+pub fn active_widget_style(widget: &str) -> Option<WidgetStyle> {
+    ACTIVE.with(|active| {
+        active
+            .borrow()
+            .as_ref()
+            .and_then(|d| d.widgets.get(widget).cloned())
+    })
+}
 
 /// A color in RGBA format
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -179,6 +241,14 @@ impl StratumPalette {
         }
     }
 
+    /// Whether the palette is light, judged by its background's luminance
+    #[must_use]
+    pub fn is_light(&self) -> bool {
+        let bg = &self.background;
+        let luminance = 0.299 * f32::from(bg.r) + 0.587 * f32::from(bg.g) + 0.114 * f32::from(bg.b);
+        luminance > 127.5
+    }
+
     /// Light theme palette
     pub const LIGHT: Self = Self {
         background: Color::rgb(255, 255, 255),
@@ -376,15 +446,22 @@ impl ThemePreset {
             Self::Nightfly => Theme::Nightfly,
             Self::Oxocarbon => Theme::Oxocarbon,
             Self::Ferra => Theme::Ferra,
-            // System defaults to Dark for now
-            // TODO: Detect system preference
-            Self::System => Theme::Dark,
+            Self::System => {
+                if system_is_light() {
+                    Theme::Light
+                } else {
+                    Theme::Dark
+                }
+            }
         }
     }
 
     /// Check if this is a light theme
     #[must_use]
-    pub const fn is_light(&self) -> bool {
+    pub fn is_light(&self) -> bool {
+        if *self == Self::System {
+            return system_is_light();
+        }
         matches!(
             self,
             Self::Light
@@ -409,6 +486,8 @@ pub enum StratumTheme {
         /// The color palette
         palette: StratumPalette,
     },
+    /// A theme defined from Stratum with `Gui.theme()`
+    Defined(Arc<ThemeDefinition>),
 }
 
 impl StratumTheme {
@@ -433,6 +512,9 @@ impl StratumTheme {
         match self {
             Self::Preset(preset) => preset.to_iced_theme(),
             Self::Custom { name, palette } => Theme::custom(name.clone(), palette.to_iced()),
+            Self::Defined(definition) => {
+                Theme::custom(definition.name.clone(), definition.palette().to_iced())
+            }
         }
     }
 
@@ -442,6 +524,7 @@ impl StratumTheme {
         match self {
             Self::Preset(preset) => StratumPalette::from_iced(&preset.to_iced_theme().palette()),
             Self::Custom { palette, .. } => palette.clone(),
+            Self::Defined(definition) => definition.palette(),
         }
     }
 
@@ -451,6 +534,7 @@ impl StratumTheme {
         match self {
             Self::Preset(preset) => preset.name(),
             Self::Custom { name, .. } => name,
+            Self::Defined(definition) => &definition.name,
         }
     }
 
@@ -459,13 +543,109 @@ impl StratumTheme {
     pub fn is_light(&self) -> bool {
         match self {
             Self::Preset(preset) => preset.is_light(),
-            Self::Custom { palette, .. } => {
-                // Determine if light based on background luminance
-                let bg = &palette.background;
-                let luminance =
-                    0.299 * f32::from(bg.r) + 0.587 * f32::from(bg.g) + 0.114 * f32::from(bg.b);
-                luminance > 127.5
-            }
+            Self::Custom { palette, .. } => palette.is_light(),
+            Self::Defined(definition) => definition.palette().is_light(),
+        }
+    }
+
+    /// Whether the theme changes with the OS's light or dark mode
+    #[must_use]
+    pub fn follows_system(&self) -> bool {
+        match self {
+            Self::Preset(preset) => *preset == ThemePreset::System,
+            Self::Custom { .. } => false,
+            Self::Defined(definition) => definition.base == ThemePreset::System,
+        }
+    }
+}
+
+/// Fonts of a theme
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Typography {
+    /// Font for text, or the default font
+    pub font: Option<Font>,
+    /// Text size, or the default size
+    pub size: Option<f32>,
+}
+
+impl Typography {
+    /// Typography with a font family by name, as installed on the system
+    #[must_use]
+    pub fn with_family(mut self, family: &str) -> Self {
+        // iced keeps font names for the life of the app; themes are defined
+        // rarely enough for leaking the name to be fine
+        self.font = Some(Font::with_name(Box::leak(
+            family.to_string().into_boxed_str(),
+        )));
+        self
+    }
+}
+
+/// Colors a theme changes from its base palette
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PaletteOverrides {
+    pub background: Option<Color>,
+    pub text: Option<Color>,
+    pub primary: Option<Color>,
+    pub success: Option<Color>,
+    pub warning: Option<Color>,
+    pub danger: Option<Color>,
+}
+
+impl PaletteOverrides {
+    /// The palette with these colors in place of its own
+    #[must_use]
+    pub fn apply(&self, palette: StratumPalette) -> StratumPalette {
+        StratumPalette {
+            background: self.background.unwrap_or(palette.background),
+            text: self.text.unwrap_or(palette.text),
+            primary: self.primary.unwrap_or(palette.primary),
+            success: self.success.unwrap_or(palette.success),
+            warning: self.warning.unwrap_or(palette.warning),
+            danger: self.danger.unwrap_or(palette.danger),
+        }
+    }
+}
+
+/// A theme defined from Stratum: a base preset with colors, fonts and
+/// widget styles of its own
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThemeDefinition {
+    pub name: String,
+    /// Preset the theme starts from; `System` follows the OS's mode
+    pub base: ThemePreset,
+    /// Colors used whatever the mode
+    pub palette: PaletteOverrides,
+    /// Colors used on top of `palette` when the theme is dark
+    pub dark_palette: PaletteOverrides,
+    pub typography: Typography,
+    /// Styles by widget kind: "button", "text_input", "container" or "text"
+    pub widgets: HashMap<String, WidgetStyle>,
+}
+
+impl ThemeDefinition {
+    /// A theme with nothing changed from its base
+    #[must_use]
+    pub fn new(name: impl Into<String>, base: ThemePreset) -> Self {
+        Self {
+            name: name.into(),
+            base,
+            palette: PaletteOverrides::default(),
+            dark_palette: PaletteOverrides::default(),
+            typography: Typography::default(),
+            widgets: HashMap::new(),
+        }
+    }
+
+    /// The palette shown, for the OS's current mode if the base follows it
+    #[must_use]
+    pub fn palette(&self) -> StratumPalette {
+        let base = StratumPalette::from_iced(&self.base.to_iced_theme().palette());
+        let palette = self.palette.apply(base);
+        if palette.is_light() {
+            palette
+        } else {
+            self.dark_palette.apply(palette)
         }
     }
 }
@@ -477,7 +657,7 @@ impl Default for StratumTheme {
 }
 
 /// Widget-level styling that can be applied to individual elements
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct WidgetStyle {
     /// Background color
     pub background: Option<Color>,
@@ -549,6 +729,64 @@ impl WidgetStyle {
         self
     }
 
+    /// A button style with this style's colors, border and shadow
+    #[must_use]
+    pub fn apply_button(&self, mut style: button::Style) -> button::Style {
+        if let Some(background) = self.background {
+            style.background = Some(Background::Color(background.to_iced()));
+        }
+        if let Some(foreground) = self.foreground {
+            style.text_color = foreground.to_iced();
+        }
+        style.border = self.apply_border(style.border);
+        if let Some(shadow) = self.shadow {
+            style.shadow = shadow.to_iced();
+        }
+        style
+    }
+
+    /// A text input style with this style's colors and border
+    #[must_use]
+    pub fn apply_text_input(&self, mut style: text_input::Style) -> text_input::Style {
+        if let Some(background) = self.background {
+            style.background = Background::Color(background.to_iced());
+        }
+        if let Some(foreground) = self.foreground {
+            style.value = foreground.to_iced();
+        }
+        style.border = self.apply_border(style.border);
+        style
+    }
+
+    /// A container style with this style's colors, border and shadow
+    #[must_use]
+    pub fn apply_container(&self, mut style: container::Style) -> container::Style {
+        if let Some(background) = self.background {
+            style.background = Some(Background::Color(background.to_iced()));
+        }
+        if let Some(foreground) = self.foreground {
+            style.text_color = Some(foreground.to_iced());
+        }
+        style.border = self.apply_border(style.border);
+        if let Some(shadow) = self.shadow {
+            style.shadow = shadow.to_iced();
+        }
+        style
+    }
+
+    fn apply_border(&self, mut border: Border) -> Border {
+        if let Some(color) = self.border_color {
+            border.color = color.to_iced();
+        }
+        if let Some(width) = self.border_width {
+            border.width = width;
+        }
+        if let Some(radius) = self.corner_radius {
+            border.radius = radius.into();
+        }
+        border
+    }
+
     /// Check if any styling is set
     #[must_use]
     pub const fn is_empty(&self) -> bool {
@@ -586,6 +824,16 @@ impl Shadow {
         }
     }
 
+    /// Convert to iced's Shadow
+    #[must_use]
+    pub fn to_iced(self) -> iced::Shadow {
+        iced::Shadow {
+            color: self.color.to_iced(),
+            offset: iced::Vector::new(self.offset_x, self.offset_y),
+            blur_radius: self.blur_radius,
+        }
+    }
+
     /// Create a subtle shadow
     #[must_use]
     pub const fn subtle() -> Self {
@@ -729,6 +977,53 @@ mod tests {
         assert!(!ThemePreset::Dracula.is_light());
     }
 
+    #[test]
+    fn test_theme_definition() {
+        let mut definition = ThemeDefinition::new("brand", ThemePreset::Light);
+        definition.palette.primary = Some(Color::rgb(200, 0, 100));
+        definition.dark_palette.background = Some(Color::rgb(10, 10, 10));
+        let palette = definition.palette();
+        assert_eq!(palette.primary, Color::rgb(200, 0, 100));
+        // Light base: the dark colors don't apply
+        assert_eq!(
+            palette.background,
+            StratumPalette::from_iced(&Theme::Light.palette()).background
+        );
+
+        definition.base = ThemePreset::Dark;
+        assert_eq!(definition.palette().background, Color::rgb(10, 10, 10));
+
+        let theme = StratumTheme::Defined(Arc::new(definition));
+        assert_eq!(theme.name(), "brand");
+        assert!(!theme.is_light());
+        assert!(!theme.follows_system());
+        assert!(StratumTheme::preset(ThemePreset::System).follows_system());
+    }
+
+    #[test]
+    fn test_active_widget_styles() {
+        let mut definition = ThemeDefinition::new("rounded", ThemePreset::Dark);
+        definition.widgets.insert(
+            "button".to_string(),
+            WidgetStyle::new().with_corner_radius(12.0),
+        );
+        definition.typography = Typography::default().with_family("Inter");
+        definition.typography.size = Some(15.0);
+
+        set_active(&StratumTheme::Defined(Arc::new(definition)));
+        assert!(active_widget_style("button").is_some());
+        assert!(active_widget_style("text").is_none());
+        assert_eq!(active_typography().size, Some(15.0));
+
+        let style = active_widget_style("button")
+            .unwrap()
+            .apply_button(button::Style::default());
+        assert_eq!(style.border.radius, 12.0.into());
+
+        set_active(&StratumTheme::default());
+        assert!(active_widget_style("button").is_none());
+    }
+
     #[test]
     fn test_shadow_presets() {
         let subtle = Shadow::subtle();
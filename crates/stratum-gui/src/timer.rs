@@ -0,0 +1,208 @@
+//! Timers and background tasks
+//!
+//! `Gui.every(ms, callback)` and `Gui.after(ms, callback)` call back on an
+//! interval or once after a delay, and `Gui.spawn_task(work, on_complete)`
+//! runs work off the UI thread, delivering its result to `on_complete`.
+//! Callbacks go through the app's message loop, so they run between events
+//! like any other handler and may update state freely.
+//!
+//! Stratum values can't be shared between threads, so a task's work is
+//! copied, with the variables it captured, into a VM of its own on a worker
+//! thread, and its result is copied back (see [`Portable`]). The worker VM
+//! has the app's permissions and the built-in namespaces, but not the app's
+//! globals or `Gui`.
+
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+use iced::futures::channel::oneshot;
+use stratum_core::bytecode::{Portable, Value};
+use stratum_core::{Permissions, VM};
+
+/// Shortest interval timers are checked at
+pub const MIN_TICK: Duration = Duration::from_millis(10);
+
+thread_local! {
+    static NEXT_ID: Cell<u64> = const { Cell::new(1) };
+}
+
+/// Id for a new timer or task, unique for the thread
+pub fn next_id() -> u64 {
+    NEXT_ID.with(|id| {
+        let next = id.get();
+        id.set(next + 1);
+        next
+    })
+}
+
+/// A callback due once or on an interval
+#[derive(Debug, Clone)]
+pub struct Timer {
+    /// Id returned to Stratum for cancelling the timer
+    pub id: u64,
+    pub interval: Duration,
+    /// Whether the timer keeps firing after the first time
+    pub repeat: bool,
+    /// Callback id or closure, called with no arguments
+    pub callback: Value,
+    /// When the timer next fires
+    pub due: Instant,
+}
+
+impl Timer {
+    /// A timer first due one interval from `now`
+    #[must_use]
+    pub fn new(interval: Duration, repeat: bool, callback: Value, now: Instant) -> Self {
+        Self {
+            id: next_id(),
+            interval,
+            repeat,
+            callback,
+            due: now + interval,
+        }
+    }
+
+    /// If the timer is due, move it to its next time and return true. A
+    /// repeating timer that fell behind skips the times it missed rather
+    /// than firing for each.
+    pub fn fire(&mut self, now: Instant) -> bool {
+        if now < self.due {
+            return false;
+        }
+        self.due += self.interval;
+        if self.due <= now {
+            self.due = now + self.interval;
+        }
+        true
+    }
+}
+
+/// How often to check timers: the largest tick that lands on every
+/// timer's interval, but no shorter than [`MIN_TICK`]
+#[must_use]
+pub fn tick_interval(timers: &[Timer]) -> Option<Duration> {
+    fn gcd(a: u128, b: u128) -> u128 {
+        if b == 0 {
+            a
+        } else {
+            gcd(b, a % b)
+        }
+    }
+
+    let ms = timers
+        .iter()
+        .map(|timer| timer.interval.as_millis().max(1))
+        .reduce(gcd)?;
+    Some(Duration::from_millis(u64::try_from(ms).unwrap_or(u64::MAX)).max(MIN_TICK))
+}
+
+/// Work to run on a worker thread, with callbacks for its result
+#[derive(Debug, Clone)]
+pub struct BackgroundTask {
+    /// Id returned to Stratum
+    pub id: u64,
+    /// Function doing the work, called with no arguments
+    pub work: Value,
+    /// Called with the work's result
+    pub on_complete: Option<Value>,
+    /// Called with the error message if the work fails
+    pub on_error: Option<Value>,
+}
+
+impl BackgroundTask {
+    /// A task with a new id
+    #[must_use]
+    pub fn new(work: Value, on_complete: Option<Value>, on_error: Option<Value>) -> Self {
+        Self {
+            id: next_id(),
+            work,
+            on_complete,
+            on_error,
+        }
+    }
+}
+
+/// Run a task's work on a worker thread, returning its result or error
+/// message
+pub async fn run(work: Portable, permissions: Permissions) -> Result<Portable, String> {
+    let (sender, receiver) = oneshot::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(execute(work, permissions));
+    });
+    receiver
+        .await
+        .unwrap_or_else(|_| Err("task stopped without a result".to_string()))
+}
+
+/// Call a task's work in a new VM with the permissions given
+fn execute(work: Portable, permissions: Permissions) -> Result<Portable, String> {
+    let mut vm = VM::new();
+    vm.set_permissions(permissions);
+    let result = vm
+        .invoke_callback(&work.into_value(), vec![])
+        .map_err(|e| e.to_string())?;
+    Portable::from_value(&result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timer_fires_when_due() {
+        let start = Instant::now();
+        let mut timer = Timer::new(Duration::from_millis(100), true, Value::Null, start);
+        assert!(!timer.fire(start + Duration::from_millis(50)));
+        assert!(timer.fire(start + Duration::from_millis(100)));
+        assert_eq!(timer.due, start + Duration::from_millis(200));
+
+        // Far behind: fire once and start over from now
+        let late = start + Duration::from_millis(1000);
+        assert!(timer.fire(late));
+        assert_eq!(timer.due, late + Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_tick_interval() {
+        let now = Instant::now();
+        let timer = |ms| Timer::new(Duration::from_millis(ms), true, Value::Null, now);
+        assert_eq!(tick_interval(&[]), None);
+        assert_eq!(
+            tick_interval(&[timer(300), timer(1000)]),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(
+            tick_interval(&[timer(500)]),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(tick_interval(&[timer(3), timer(4)]), Some(MIN_TICK));
+    }
+
+    #[test]
+    fn test_work_runs_in_its_own_vm() {
+        use stratum_core::{Compiler, Parser};
+
+        let source = "fx answer(n) {\n    || n * 6\n}\nlet work = answer(7)\n\
+            let read = || File.read_text(\"secret.txt\")";
+        let module = Parser::parse_module(source).unwrap();
+        let mut vm = VM::new();
+        vm.run(Compiler::new().compile_module(&module).unwrap())
+            .unwrap();
+        let work = |name: &str| Portable::from_value(&vm.globals()[name]).unwrap();
+
+        let answer = work("work");
+        let worker = std::thread::spawn(move || execute(answer, Permissions::default()));
+        assert_eq!(worker.join().unwrap().unwrap().into_value(), Value::Int(42));
+
+        // The worker has the app's permissions
+        let denied = execute(work("read"), Permissions::deny_all()).unwrap_err();
+        assert!(denied.contains("--allow-read"), "{denied}");
+    }
+
+    #[test]
+    fn test_ids_are_unique() {
+        let task = BackgroundTask::new(Value::Null, None, None);
+        let timer = Timer::new(Duration::from_secs(1), false, Value::Null, Instant::now());
+        assert_ne!(task.id, timer.id);
+    }
+}
@@ -0,0 +1,125 @@
+//! System tray icon
+//!
+//! `Gui.tray(icon, items)` asks for an icon in the system tray with its own
+//! menu. The app creates it once the window is up and polls the menu for
+//! chosen items. Tray support needs the `tray` feature; without it creating
+//! a tray fails with an error.
+
+use crate::callback::CallbackId;
+use crate::error::GuiResult;
+use crate::menu::MenuItem;
+
+/// A requested tray icon
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrayConfig {
+    /// Path of the icon image
+    pub icon: String,
+    /// Text shown when hovering the icon
+    pub tooltip: Option<String>,
+    /// Items of the icon's menu
+    pub items: Vec<MenuItem>,
+}
+
+#[cfg(feature = "tray")]
+pub use platform::Tray;
+
+#[cfg(feature = "tray")]
+mod platform {
+    use std::collections::HashMap;
+
+    use tray_icon::menu::{IsMenuItem, Menu, MenuEvent, MenuId, PredefinedMenuItem, Submenu};
+    use tray_icon::{Icon, TrayIcon, TrayIconBuilder};
+
+    use super::{CallbackId, GuiResult, MenuItem, TrayConfig};
+    use crate::error::GuiError;
+
+    /// A tray icon shown while the value lives
+    pub struct Tray {
+        _icon: TrayIcon,
+        callbacks: HashMap<MenuId, CallbackId>,
+    }
+
+    impl Tray {
+        /// Show a tray icon
+        pub fn new(config: &TrayConfig) -> GuiResult<Self> {
+            let image = image::open(&config.icon)
+                .map_err(|e| GuiError::Tray(format!("Failed to load {}: {e}", config.icon)))?
+                .into_rgba8();
+            let (width, height) = image.dimensions();
+            let icon = Icon::from_rgba(image.into_raw(), width, height)
+                .map_err(|e| GuiError::Tray(e.to_string()))?;
+
+            let mut callbacks = HashMap::new();
+            let menu = Menu::new();
+            for item in &config.items {
+                let entry = menu_entry(item, &mut callbacks);
+                menu.append(entry.as_ref())
+                    .map_err(|e| GuiError::Tray(e.to_string()))?;
+            }
+
+            let mut builder = TrayIconBuilder::new()
+                .with_icon(icon)
+                .with_menu(Box::new(menu));
+            if let Some(ref tooltip) = config.tooltip {
+                builder = builder.with_tooltip(tooltip);
+            }
+            let icon = builder.build().map_err(|e| GuiError::Tray(e.to_string()))?;
+
+            Ok(Self {
+                _icon: icon,
+                callbacks,
+            })
+        }
+
+        /// Callbacks of the menu items chosen since the last poll
+        pub fn poll(&self) -> Vec<CallbackId> {
+            MenuEvent::receiver()
+                .try_iter()
+                .filter_map(|event| self.callbacks.get(&event.id).copied())
+                .collect()
+        }
+    }
+
+    fn menu_entry(
+        item: &MenuItem,
+        callbacks: &mut HashMap<MenuId, CallbackId>,
+    ) -> Box<dyn IsMenuItem> {
+        if item.separator {
+            return Box::new(PredefinedMenuItem::separator());
+        }
+        if !item.items.is_empty() {
+            let submenu = Submenu::new(&item.label, !item.disabled);
+            for child in &item.items {
+                let entry = menu_entry(child, callbacks);
+                // Appending only fails for an item already in the submenu
+                let _ = submenu.append(entry.as_ref());
+            }
+            return Box::new(submenu);
+        }
+
+        let entry = tray_icon::menu::MenuItem::new(&item.label, !item.disabled, None);
+        if let Some(callback_id) = item.on_select {
+            callbacks.insert(entry.id().clone(), callback_id);
+        }
+        Box::new(entry)
+    }
+}
+
+/// Stand-in when built without the `tray` feature
+#[cfg(not(feature = "tray"))]
+pub struct Tray;
+
+#[cfg(not(feature = "tray"))]
+impl Tray {
+    /// Fails: tray icons need the `tray` feature
+    pub fn new(_config: &TrayConfig) -> GuiResult<Self> {
+        Err(crate::error::GuiError::Tray(
+            "stratum-gui was built without the 'tray' feature".to_string(),
+        ))
+    }
+
+    /// Never any chosen items
+    pub fn poll(&self) -> Vec<CallbackId> {
+        Vec::new()
+    }
+}
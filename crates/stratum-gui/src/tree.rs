@@ -0,0 +1,297 @@
+//! Tree views and collapsible sections
+//!
+//! `Gui.tree` shows nested nodes that expand and collapse, asking a callback
+//! for a node's children the first time it opens. `Gui.section` is a titled
+//! block whose body folds away; a stack of sections makes an accordion. As
+//! with tables, which nodes are expanded and selected outlives the view
+//! function rebuilding the element tree, kept here by tree or section id.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use stratum_core::bytecode::{HashableValue, Value};
+
+thread_local! {
+    static TREE_VIEWS: RefCell<HashMap<String, TreeView>> = RefCell::new(HashMap::new());
+    static SECTIONS: RefCell<HashMap<String, bool>> = RefCell::new(HashMap::new());
+}
+
+/// A node of a tree view
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TreeNode {
+    /// Identifier passed to callbacks, unique within the tree
+    pub id: String,
+    /// Text shown for the node
+    pub label: String,
+    /// Optional icon text shown before the label
+    pub icon: Option<String>,
+    /// Loaded children
+    pub children: Vec<TreeNode>,
+    /// Whether the node has children not loaded yet
+    pub has_children: bool,
+}
+
+impl TreeNode {
+    /// A leaf node
+    #[must_use]
+    pub fn new(id: impl Into<String>, label: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            label: label.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Whether the node can expand
+    #[must_use]
+    pub fn is_branch(&self) -> bool {
+        self.has_children || !self.children.is_empty()
+    }
+
+    /// Whether the node can expand but its children aren't loaded
+    #[must_use]
+    pub fn needs_children(&self) -> bool {
+        self.has_children && self.children.is_empty()
+    }
+
+    /// A node from a Stratum value: a string for a leaf, or a map or struct
+    /// with `label` and optional `id` (defaults to the label), `icon`,
+    /// `children` and `has_children`
+    pub fn from_value(value: &Value) -> Result<Self, String> {
+        let field = |name: &str| -> Option<Value> {
+            match value {
+                Value::Map(map) => map
+                    .borrow()
+                    .get(&HashableValue::String(name.to_string().into()))
+                    .cloned(),
+                Value::Struct(instance) => instance.borrow().fields.get(name).cloned(),
+                _ => None,
+            }
+        };
+
+        match value {
+            Value::String(label) => return Ok(Self::new(label.as_str(), label.as_str())),
+            Value::Map(_) | Value::Struct(_) => {}
+            other => {
+                return Err(format!(
+                    "tree node must be a string, map or struct, got {}",
+                    other.type_name()
+                ))
+            }
+        }
+
+        let label = match field("label") {
+            Some(Value::String(s)) => s.to_string(),
+            Some(other) => format!("{other}"),
+            None => return Err("tree node needs a label".to_string()),
+        };
+        let id = match field("id") {
+            Some(Value::String(s)) => s.to_string(),
+            Some(Value::Null) | None => label.clone(),
+            Some(other) => format!("{other}"),
+        };
+        let icon = match field("icon") {
+            Some(Value::String(s)) => Some(s.to_string()),
+            _ => None,
+        };
+        let children = match field("children") {
+            Some(Value::List(list)) => nodes_from_list(&list.borrow())?,
+            Some(Value::Null) | None => Vec::new(),
+            Some(other) => {
+                return Err(format!(
+                    "tree node children must be a list, got {}",
+                    other.type_name()
+                ))
+            }
+        };
+        let has_children = matches!(field("has_children"), Some(Value::Bool(true)));
+
+        Ok(Self {
+            id,
+            label,
+            icon,
+            children,
+            has_children,
+        })
+    }
+}
+
+/// Nodes from a list of Stratum values
+pub fn nodes_from_list(values: &[Value]) -> Result<Vec<TreeNode>, String> {
+    values.iter().map(TreeNode::from_value).collect()
+}
+
+/// Nodes shown with some expanded, in order, with their depth
+pub fn visible_nodes<'a>(nodes: &'a [TreeNode], expanded: &[String]) -> Vec<(usize, &'a TreeNode)> {
+    fn walk<'a>(
+        nodes: &'a [TreeNode],
+        depth: usize,
+        expanded: &[String],
+        out: &mut Vec<(usize, &'a TreeNode)>,
+    ) {
+        for node in nodes {
+            out.push((depth, node));
+            if expanded.contains(&node.id) {
+                walk(&node.children, depth + 1, expanded, out);
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    walk(nodes, 0, expanded, &mut out);
+    out
+}
+
+/// Expanded and selected nodes of a tree
+#[derive(Debug, Clone, Default)]
+pub struct TreeView {
+    /// Expanded node ids, seeded from the tree's own when first drawn
+    pub expanded: Option<Vec<String>>,
+    /// Selected node id, seeded like `expanded`
+    pub selected: Option<String>,
+    seeded: bool,
+}
+
+impl TreeView {
+    /// Take the tree's initial expanded and selected nodes, the first time
+    pub fn seed(&mut self, expanded: &[String], selected: Option<&str>) {
+        if !self.seeded {
+            self.expanded = Some(expanded.to_vec());
+            self.selected = selected.map(str::to_string);
+            self.seeded = true;
+        }
+    }
+
+    /// Expand or collapse a node; returns whether it is now expanded and
+    /// the expanded nodes
+    pub fn toggle(&mut self, node: &str) -> (bool, Vec<String>) {
+        let expanded = self.expanded.get_or_insert_with(Vec::new);
+        let now_expanded = match expanded.iter().position(|id| id == node) {
+            Some(index) => {
+                expanded.remove(index);
+                false
+            }
+            None => {
+                expanded.push(node.to_string());
+                true
+            }
+        };
+        (now_expanded, expanded.clone())
+    }
+}
+
+/// Run a function on a tree's view state, creating it if needed
+pub fn with_tree_view<R>(id: &str, f: impl FnOnce(&mut TreeView) -> R) -> R {
+    TREE_VIEWS.with(|views| {
+        let mut views = views.borrow_mut();
+        f(views.entry(id.to_string()).or_default())
+    })
+}
+
+/// Whether a section is expanded, `initial` until it is toggled
+pub fn section_expanded(id: &str, initial: bool) -> bool {
+    SECTIONS.with(|sections| {
+        *sections
+            .borrow_mut()
+            .entry(id.to_string())
+            .or_insert(initial)
+    })
+}
+
+/// Record a section being expanded or collapsed
+pub fn set_section_expanded(id: &str, expanded: bool) {
+    SECTIONS.with(|sections| {
+        sections.borrow_mut().insert(id.to_string(), expanded);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    fn map(entries: Vec<(&str, Value)>) -> Value {
+        let map = Value::empty_map();
+        if let Value::Map(ref inner) = map {
+            for (key, value) in entries {
+                inner
+                    .borrow_mut()
+                    .insert(HashableValue::String(Rc::new(key.to_string())), value);
+            }
+        }
+        map
+    }
+
+    #[test]
+    fn test_node_from_value() {
+        let value = map(vec![
+            ("label", Value::string("src")),
+            (
+                "children",
+                Value::list(vec![
+                    Value::string("main.strat"),
+                    map(vec![
+                        ("id", Value::string("src/util")),
+                        ("label", Value::string("util")),
+                        ("has_children", Value::Bool(true)),
+                    ]),
+                ]),
+            ),
+        ]);
+        let node = TreeNode::from_value(&value).unwrap();
+        assert_eq!(node.id, "src");
+        assert_eq!(node.children[0], TreeNode::new("main.strat", "main.strat"));
+        assert!(node.children[1].needs_children());
+        assert!(node.is_branch());
+        assert!(!node.needs_children());
+
+        assert!(TreeNode::from_value(&Value::Int(1)).is_err());
+        assert!(TreeNode::from_value(&map(vec![("id", Value::string("x"))])).is_err());
+    }
+
+    #[test]
+    fn test_visible_nodes() {
+        let mut src = TreeNode::new("src", "src");
+        src.children = vec![TreeNode::new("a", "a"), TreeNode::new("b", "b")];
+        let nodes = vec![src, TreeNode::new("README", "README")];
+
+        let ids = |expanded: &[String]| -> Vec<(usize, String)> {
+            visible_nodes(&nodes, expanded)
+                .into_iter()
+                .map(|(depth, node)| (depth, node.id.clone()))
+                .collect()
+        };
+        assert_eq!(ids(&[]).len(), 2);
+        assert_eq!(
+            ids(&["src".to_string()]),
+            [
+                (0, "src".to_string()),
+                (1, "a".to_string()),
+                (1, "b".to_string()),
+                (0, "README".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tree_view_toggle() {
+        let mut view = TreeView::default();
+        view.seed(&["src".to_string()], Some("a"));
+        // Later seeds don't override what the user did
+        view.seed(&[], None);
+        assert_eq!(view.selected.as_deref(), Some("a"));
+
+        assert_eq!(
+            view.toggle("docs"),
+            (true, vec!["src".to_string(), "docs".to_string()])
+        );
+        assert_eq!(view.toggle("src"), (false, vec!["docs".to_string()]));
+    }
+
+    #[test]
+    fn test_section_expanded() {
+        assert!(section_expanded("test_section_expanded", true));
+        set_section_expanded("test_section_expanded", false);
+        assert!(!section_expanded("test_section_expanded", true));
+    }
+}
@@ -115,7 +115,7 @@ pub fn get_binding_path(value: &Value) -> Option<&str> {
 #[must_use]
 pub fn resolve_binding(value: &Value) -> Option<String> {
     match value {
-        Value::StateBinding(path) => Some(path.clone()),
+        Value::StateBinding(path) => Some(path.to_string()),
         _ => None,
     }
 }
@@ -2,8 +2,21 @@
 //!
 //! This module provides types and functionality for managing windows,
 //! including multi-window support and window configuration.
+//!
+//! Stratum code opens windows with `Gui.open_window(view, options)`, each
+//! with its own view function and its own state, and talks between them
+//! with `Gui.send_to_window(id, message)`. Views, state and handlers are
+//! Stratum values, which can't live in the element tree or cross threads,
+//! so they are kept here by window number; the running app opens and closes
+//! the windows asked for and calls the handlers as window events arrive.
+//! The main window is number [`MAIN_WINDOW`].
+
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap};
+
+use stratum_core::bytecode::Value;
 
-use std::collections::BTreeMap;
+use crate::state::ReactiveState;
 
 pub use iced::window::Id as IcedWindowId;
 
@@ -416,6 +429,253 @@ pub enum WindowEvent {
     Moved { id: WindowId, x: i32, y: i32 },
 }
 
+/// Number of the main window
+pub const MAIN_WINDOW: u64 = 0;
+
+thread_local! {
+    static SCRIPT_WINDOWS: RefCell<BTreeMap<u64, ScriptWindow>> = const { RefCell::new(BTreeMap::new()) };
+    static REQUESTS: RefCell<Vec<WindowRequest>> = const { RefCell::new(Vec::new()) };
+    static INBOX: RefCell<Vec<(u64, Value)>> = const { RefCell::new(Vec::new()) };
+    static NEXT_NUMBER: Cell<u64> = const { Cell::new(MAIN_WINDOW + 1) };
+    static CURRENT: Cell<u64> = const { Cell::new(MAIN_WINDOW) };
+}
+
+/// Window events Stratum code can handle with `Gui.on_window()`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WindowHandler {
+    /// A message from `Gui.send_to_window()`, called with the message
+    Message,
+    /// The window moved, called with its x and y
+    Moved,
+    /// The window was resized, called with its width and height
+    Resized,
+    /// The user asked to close the window; returning false keeps it open
+    CloseRequested,
+    /// The window closed
+    Closed,
+}
+
+impl WindowHandler {
+    /// Every handler, in the order of their names
+    pub const ALL: [Self; 5] = [
+        Self::Message,
+        Self::Moved,
+        Self::Resized,
+        Self::CloseRequested,
+        Self::Closed,
+    ];
+
+    /// The handler named as in `Gui.on_window()` and the options of
+    /// `Gui.open_window()`, without the `on_` prefix
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.strip_prefix("on_").unwrap_or(name);
+        Self::ALL.into_iter().find(|handler| handler.name() == name)
+    }
+
+    /// Name of the handler
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Message => "message",
+            Self::Moved => "moved",
+            Self::Resized => "resized",
+            Self::CloseRequested => "close_requested",
+            Self::Closed => "closed",
+        }
+    }
+}
+
+/// A window as Stratum code sees it
+#[derive(Debug, Clone)]
+pub struct ScriptWindow {
+    /// View function, called with the app state and the window's state;
+    /// `None` for the main window, which uses the app's view
+    pub view: Option<Value>,
+    /// The window's own state
+    pub state: ReactiveState,
+    /// Event handlers by event
+    pub handlers: HashMap<WindowHandler, Value>,
+    /// The iced window, once the app has opened it
+    pub iced_id: Option<IcedWindowId>,
+}
+
+impl ScriptWindow {
+    fn new(view: Option<Value>, state: Value) -> Self {
+        Self {
+            view,
+            state: ReactiveState::new(state),
+            handlers: HashMap::new(),
+            iced_id: None,
+        }
+    }
+}
+
+/// A change to the open windows asked for by Stratum code
+#[derive(Debug, Clone)]
+pub enum WindowRequest {
+    /// Open the window with this number
+    Open(u64, WindowSettings),
+    /// Close the window with this number
+    Close(u64),
+}
+
+/// Keep a window opened by Stratum code and ask the app to open it,
+/// returning its number
+pub fn open_script_window(
+    view: Value,
+    settings: WindowSettings,
+    state: Value,
+    handlers: HashMap<WindowHandler, Value>,
+) -> u64 {
+    let number = NEXT_NUMBER.with(|next| next.replace(next.get() + 1));
+    let mut window = ScriptWindow::new(Some(view), state);
+    window.handlers = handlers;
+    SCRIPT_WINDOWS.with(|windows| windows.borrow_mut().insert(number, window));
+    REQUESTS.with(|requests| {
+        requests
+            .borrow_mut()
+            .push(WindowRequest::Open(number, settings));
+    });
+    number
+}
+
+/// Ask the app to close a window; false if there is no such window
+pub fn close_script_window(number: u64) -> bool {
+    if !script_window_exists(number) {
+        return false;
+    }
+    REQUESTS.with(|requests| requests.borrow_mut().push(WindowRequest::Close(number)));
+    true
+}
+
+/// Whether a window with this number is open or opening
+#[must_use]
+pub fn script_window_exists(number: u64) -> bool {
+    number == MAIN_WINDOW || SCRIPT_WINDOWS.with(|windows| windows.borrow().contains_key(&number))
+}
+
+/// Numbers of the windows open or opening, the main window first
+#[must_use]
+pub fn script_window_numbers() -> Vec<u64> {
+    let mut numbers = vec![MAIN_WINDOW];
+    SCRIPT_WINDOWS.with(|windows| {
+        numbers.extend(windows.borrow().keys().filter(|n| **n != MAIN_WINDOW));
+    });
+    numbers
+}
+
+/// Set the handler for an event of a window
+pub fn set_window_handler(number: u64, handler: WindowHandler, callback: Value) -> bool {
+    with_window(number, |window| {
+        window.handlers.insert(handler, callback);
+    })
+    .is_some()
+}
+
+/// The handler for an event of a window
+#[must_use]
+pub fn window_handler(number: u64, handler: WindowHandler) -> Option<Value> {
+    with_window(number, |window| window.handlers.get(&handler).cloned()).flatten()
+}
+
+/// The view function of a window opened by Stratum code
+#[must_use]
+pub fn window_view(number: u64) -> Option<Value> {
+    with_window(number, |window| window.view.clone()).flatten()
+}
+
+/// The state of a window
+#[must_use]
+pub fn window_state(number: u64) -> Option<ReactiveState> {
+    with_window(number, |window| window.state.clone())
+}
+
+/// Queue a message for a window's message handler; false if there is no
+/// such window
+pub fn send_to_window(number: u64, message: Value) -> bool {
+    if !script_window_exists(number) {
+        return false;
+    }
+    INBOX.with(|inbox| inbox.borrow_mut().push((number, message)));
+    true
+}
+
+/// Take the messages sent since the last call, each with its window
+pub fn take_window_messages() -> Vec<(u64, Value)> {
+    INBOX.with(|inbox| std::mem::take(&mut *inbox.borrow_mut()))
+}
+
+/// Take the windows asked to open or close since the last call
+pub fn take_window_requests() -> Vec<WindowRequest> {
+    REQUESTS.with(|requests| std::mem::take(&mut *requests.borrow_mut()))
+}
+
+/// Note the iced window a numbered window was opened as
+pub fn attach_window(number: u64, id: IcedWindowId) {
+    with_window(number, |window| window.iced_id = Some(id));
+}
+
+/// The number of the window opened as this iced window
+#[must_use]
+pub fn window_number(id: IcedWindowId) -> Option<u64> {
+    SCRIPT_WINDOWS.with(|windows| {
+        windows
+            .borrow()
+            .iter()
+            .find(|(_, window)| window.iced_id == Some(id))
+            .map(|(number, _)| *number)
+    })
+}
+
+/// The iced window a numbered window was opened as
+#[must_use]
+pub fn iced_window(number: u64) -> Option<IcedWindowId> {
+    with_window(number, |window| window.iced_id).flatten()
+}
+
+/// Forget a window once it has closed
+pub fn remove_script_window(number: u64) -> Option<ScriptWindow> {
+    SCRIPT_WINDOWS.with(|windows| windows.borrow_mut().remove(&number))
+}
+
+/// Run `f` as window `number`, so `Gui.current_window()` returns it
+pub fn as_window<T>(number: u64, f: impl FnOnce() -> T) -> T {
+    let previous = CURRENT.with(|current| current.replace(number));
+    let result = f();
+    CURRENT.with(|current| current.set(previous));
+    result
+}
+
+/// The window whose view or handler is running
+#[must_use]
+pub fn current_window() -> u64 {
+    CURRENT.with(Cell::get)
+}
+
+/// Forget all windows opened by Stratum code and the main window's handlers
+pub fn reset_script_windows() {
+    SCRIPT_WINDOWS.with(|windows| windows.borrow_mut().clear());
+    REQUESTS.with(|requests| requests.borrow_mut().clear());
+    INBOX.with(|inbox| inbox.borrow_mut().clear());
+    NEXT_NUMBER.with(|next| next.set(MAIN_WINDOW + 1));
+    CURRENT.with(|current| current.set(MAIN_WINDOW));
+}
+
+/// Run `f` on a window, creating the main window's entry the first time
+fn with_window<T>(number: u64, f: impl FnOnce(&mut ScriptWindow) -> T) -> Option<T> {
+    SCRIPT_WINDOWS.with(|windows| {
+        let mut windows = windows.borrow_mut();
+        if number == MAIN_WINDOW {
+            let main = windows
+                .entry(MAIN_WINDOW)
+                .or_insert_with(|| ScriptWindow::new(None, Value::Null));
+            return Some(f(main));
+        }
+        windows.get_mut(&number).map(f)
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -512,4 +772,94 @@ mod tests {
         manager.set_size(main_id, 1024, 768);
         assert_eq!(manager.get(main_id).unwrap().current_size, (1024, 768));
     }
+
+    fn view() -> Value {
+        fn native(_args: &[Value]) -> Result<Value, String> {
+            Ok(Value::Null)
+        }
+        Value::native(stratum_core::bytecode::NativeFunction::new(
+            "view", 2, native,
+        ))
+    }
+
+    #[test]
+    fn test_script_windows() {
+        reset_script_windows();
+        let mut handlers = HashMap::new();
+        handlers.insert(WindowHandler::Closed, view());
+        let number = open_script_window(
+            view(),
+            WindowSettings::new("Inspector"),
+            Value::Null,
+            handlers,
+        );
+        assert_ne!(number, MAIN_WINDOW);
+        assert_eq!(script_window_numbers(), [MAIN_WINDOW, number]);
+        assert!(window_view(number).is_some());
+        assert!(window_handler(number, WindowHandler::Closed).is_some());
+        assert!(window_handler(number, WindowHandler::Moved).is_none());
+
+        let requests = take_window_requests();
+        assert!(
+            matches!(&requests[..], [WindowRequest::Open(n, s)] if *n == number && s.title == "Inspector")
+        );
+
+        // The app attaches the iced window once opened
+        let id = IcedWindowId::unique();
+        attach_window(number, id);
+        assert_eq!(window_number(id), Some(number));
+        assert_eq!(iced_window(number), Some(id));
+
+        assert!(close_script_window(number));
+        assert!(matches!(&take_window_requests()[..], [WindowRequest::Close(n)] if *n == number));
+        assert!(remove_script_window(number).is_some());
+        assert!(!script_window_exists(number));
+        assert!(!close_script_window(number));
+        reset_script_windows();
+    }
+
+    #[test]
+    fn test_window_messages_and_handlers() {
+        reset_script_windows();
+        assert!(send_to_window(MAIN_WINDOW, Value::Int(1)));
+        assert!(!send_to_window(42, Value::Int(2)));
+        assert_eq!(take_window_messages(), [(MAIN_WINDOW, Value::Int(1))]);
+        assert!(take_window_messages().is_empty());
+
+        // The main window takes handlers without being opened by Stratum code
+        assert!(set_window_handler(
+            MAIN_WINDOW,
+            WindowHandler::CloseRequested,
+            view()
+        ));
+        assert!(window_handler(MAIN_WINDOW, WindowHandler::CloseRequested).is_some());
+        assert!(window_view(MAIN_WINDOW).is_none());
+        assert!(!set_window_handler(42, WindowHandler::Moved, view()));
+        reset_script_windows();
+    }
+
+    #[test]
+    fn test_window_handler_names() {
+        assert_eq!(
+            WindowHandler::from_name("moved"),
+            Some(WindowHandler::Moved)
+        );
+        assert_eq!(
+            WindowHandler::from_name("on_close_requested"),
+            Some(WindowHandler::CloseRequested)
+        );
+        assert_eq!(WindowHandler::from_name("clicked"), None);
+        for handler in WindowHandler::ALL {
+            assert_eq!(WindowHandler::from_name(handler.name()), Some(handler));
+        }
+    }
+
+    #[test]
+    fn test_current_window() {
+        assert_eq!(current_window(), MAIN_WINDOW);
+        let inner = as_window(3, || as_window(5, current_window));
+        assert_eq!(inner, 5);
+        assert_eq!(as_window(3, current_window), 3);
+        assert_eq!(current_window(), MAIN_WINDOW);
+    }
 }
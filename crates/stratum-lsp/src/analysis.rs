@@ -0,0 +1,123 @@
+//! In-process language features
+//!
+//! Editors that embed Stratum, like the Workshop, use [`DocumentAnalysis`] to
+//! get the diagnostics, completions, hover and go-to-definition that LSP
+//! clients get, without running the server.
+
+use tower_lsp::lsp_types::{CompletionItem, Diagnostic, Location, Position, Url};
+
+use crate::cache::DocumentCache;
+use crate::diagnostics::DiagnosticsConfig;
+use crate::hover::HoverInfo;
+use crate::{completions, definition, diagnostics, hover};
+
+/// Language features for one open document
+///
+/// Analysis results are cached until the content changes, so asking for
+/// several features of the same content parses and type checks it once.
+#[derive(Debug, Clone)]
+pub struct DocumentAnalysis {
+    uri: Url,
+    cache: DocumentCache,
+    config: DiagnosticsConfig,
+}
+
+impl DocumentAnalysis {
+    /// Analyze a document's content
+    pub fn new(uri: Url, content: impl Into<String>) -> Self {
+        Self {
+            uri,
+            cache: DocumentCache::new(content.into(), 0),
+            config: DiagnosticsConfig::default(),
+        }
+    }
+
+    /// Choose which optional diagnostics to report
+    #[must_use]
+    pub fn with_diagnostics_config(mut self, config: DiagnosticsConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// The document's URI
+    pub fn uri(&self) -> &Url {
+        &self.uri
+    }
+
+    /// The document's content
+    pub fn content(&self) -> &str {
+        self.cache.content()
+    }
+
+    /// Replace the document's content
+    pub fn set_content(&mut self, content: impl Into<String>) {
+        let content = content.into();
+        if content != self.cache.content() {
+            let version = self.cache.version() + 1;
+            self.cache.apply_change(None, content, version);
+        }
+    }
+
+    /// Parse errors, type errors and lints of the document
+    pub fn diagnostics(&mut self) -> Vec<Diagnostic> {
+        let data = self.cache.get_all_cached();
+        diagnostics::compute_diagnostics_cached(&data, self.config)
+    }
+
+    /// Completions at a position
+    pub fn completions(&mut self, position: Position) -> Vec<CompletionItem> {
+        let data = self.cache.get_all_cached();
+        completions::compute_completions_cached(&data, position)
+    }
+
+    /// Hover information at a position, as Markdown
+    pub fn hover(&mut self, position: Position) -> Option<HoverInfo> {
+        let data = self.cache.get_all_cached();
+        hover::compute_hover_cached(&self.uri, &data, position)
+    }
+
+    /// Where the symbol at a position is defined
+    pub fn definition(&mut self, position: Position) -> Option<Location> {
+        let data = self.cache.get_all_cached();
+        definition::compute_definition_cached(&self.uri, &data, position)
+            .map(|result| result.location)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "fx add(a: Int, b: Int) -> Int { a + b }\nlet total = add(1, 2)\n";
+
+    fn analysis(source: &str) -> DocumentAnalysis {
+        DocumentAnalysis::new(Url::parse("file:///test.strat").unwrap(), source)
+    }
+
+    #[test]
+    fn test_features() {
+        let mut analysis = analysis(SOURCE);
+        assert!(analysis.diagnostics().is_empty());
+
+        let hover = analysis.hover(Position::new(0, 4)).unwrap();
+        assert!(hover.contents.contains("fx add"));
+
+        let location = analysis.definition(Position::new(1, 13)).unwrap();
+        assert_eq!(location.uri, *analysis.uri());
+        assert_eq!(location.range.start, Position::new(0, 3));
+
+        let items = analysis.completions(Position::new(1, 12));
+        assert!(items.iter().any(|item| item.label == "add"));
+    }
+
+    #[test]
+    fn test_set_content() {
+        let mut analysis = analysis(SOURCE);
+        analysis.set_content("fx broken( {");
+        assert_eq!(analysis.content(), "fx broken( {");
+        assert!(!analysis.diagnostics().is_empty());
+
+        analysis.set_content(SOURCE);
+        assert!(analysis.diagnostics().is_empty());
+    }
+}
@@ -3,6 +3,7 @@
 //! This module contains the main `LanguageServer` trait implementation.
 
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tower_lsp::jsonrpc::Result;
@@ -11,14 +12,20 @@ use tower_lsp::{Client, LanguageServer};
 
 use crate::cache::DocumentCache;
 use crate::code_actions;
+use crate::code_lens::{self, ParsedDocument};
 use crate::completions;
 use crate::definition;
-use crate::diagnostics;
+use crate::diagnostics::{self, DiagnosticsConfig};
 use crate::document_symbols;
 use crate::formatting;
+use crate::hierarchy::ProjectIndex;
 use crate::hover;
+use crate::inlay_hints::{self, InlayHintConfig};
+use crate::project::Project;
+use crate::ranges;
 use crate::references;
 use crate::rename;
+use crate::semantic_tokens;
 use crate::signature_help;
 use crate::workspace_symbols;
 
@@ -28,6 +35,16 @@ pub struct StratumLanguageServer {
     client: Client,
     /// Open documents indexed by URI with cached analysis data
     documents: Arc<RwLock<HashMap<Url, DocumentCache>>>,
+    /// Which inlay hints the client wants
+    inlay_hint_config: Arc<RwLock<InlayHintConfig>>,
+    /// Which optional diagnostics the client wants
+    diagnostics_config: Arc<RwLock<DiagnosticsConfig>>,
+    /// The semantic tokens last sent for each document, for delta requests
+    semantic_tokens: Arc<RwLock<HashMap<Url, SemanticTokens>>>,
+    /// Source of semantic token result ids
+    next_result_id: AtomicU64,
+    /// Packages containing open documents, checked as a whole
+    projects: Arc<RwLock<Vec<Project>>>,
 }
 
 impl StratumLanguageServer {
@@ -37,28 +54,111 @@ impl StratumLanguageServer {
         Self {
             client,
             documents: Arc::new(RwLock::new(HashMap::new())),
+            inlay_hint_config: Arc::new(RwLock::new(InlayHintConfig::default())),
+            diagnostics_config: Arc::new(RwLock::new(DiagnosticsConfig::default())),
+            semantic_tokens: Arc::new(RwLock::new(HashMap::new())),
+            next_result_id: AtomicU64::new(1),
+            projects: Arc::new(RwLock::new(Vec::new())),
         }
     }
 
-    /// Publish diagnostics for a document using cached data
+    /// The content of every open document
+    async fn open_documents(&self) -> Vec<(Url, String)> {
+        let docs = self.documents.read().await;
+        docs.iter()
+            .map(|(uri, cache)| (uri.clone(), cache.content().to_string()))
+            .collect()
+    }
+
+    /// Compute the semantic tokens of a document and remember them under a
+    /// fresh result id, so the next request can be answered with a delta
+    async fn semantic_tokens_for(&self, uri: &Url) -> Option<SemanticTokens> {
+        let data = {
+            let mut docs = self.documents.write().await;
+            let cache = docs.get_mut(uri)?;
+            let data = cache.get_all_cached();
+            semantic_tokens::compute_semantic_tokens_cached(&data)?
+        };
+
+        let result_id = self.next_result_id.fetch_add(1, Ordering::Relaxed);
+        let tokens = SemanticTokens {
+            result_id: Some(result_id.to_string()),
+            data,
+        };
+        self.semantic_tokens
+            .write()
+            .await
+            .insert(uri.clone(), tokens.clone());
+        Some(tokens)
+    }
+
+    /// Load the package containing a newly opened document, unless it is
+    /// already known
+    async fn load_project(&self, uri: &Url) {
+        let mut projects = self.projects.write().await;
+        if projects.iter().any(|project| project.contains(uri)) {
+            return;
+        }
+        if let Some(project) = Project::load(uri) {
+            projects.push(project);
+        }
+    }
+
+    /// Publish diagnostics for a document. Inside a package, this also
+    /// republishes every other file of the package whose diagnostics changed.
     async fn publish_diagnostics_cached(&self, uri: Url, version: Option<i32>) {
-        let diags = {
+        let config = *self.diagnostics_config.read().await;
+        let results = {
+            let mut projects = self.projects.write().await;
             let mut docs = self.documents.write().await;
-            if let Some(cache) = docs.get_mut(&uri) {
+            if let Some(project) = projects.iter_mut().find(|project| project.contains(&uri)) {
+                project.check(&mut docs, &uri, config)
+            } else if let Some(cache) = docs.get_mut(&uri) {
                 let data = cache.get_all_cached();
-                diagnostics::compute_diagnostics_cached(&data)
+                vec![(
+                    uri.clone(),
+                    diagnostics::compute_diagnostics_cached(&data, config),
+                )]
             } else {
-                vec![]
+                vec![(uri.clone(), vec![])]
             }
         };
 
-        self.client.publish_diagnostics(uri, diags, version).await;
+        self.publish_results(results, &uri, version).await;
+    }
+
+    /// Publish the diagnostics of several files; only `uri` gets `version`
+    async fn publish_results(
+        &self,
+        results: Vec<(Url, Vec<Diagnostic>)>,
+        uri: &Url,
+        version: Option<i32>,
+    ) {
+        for (file, diags) in results {
+            let version = if file == *uri { version } else { None };
+            self.client.publish_diagnostics(file, diags, version).await;
+        }
     }
 }
 
 #[tower_lsp::async_trait]
 impl LanguageServer for StratumLanguageServer {
-    async fn initialize(&self, _params: InitializeParams) -> Result<InitializeResult> {
+    async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
+        if let Some(config) = params
+            .initialization_options
+            .as_ref()
+            .and_then(InlayHintConfig::from_settings)
+        {
+            *self.inlay_hint_config.write().await = config;
+        }
+        if let Some(config) = params
+            .initialization_options
+            .as_ref()
+            .and_then(DiagnosticsConfig::from_settings)
+        {
+            *self.diagnostics_config.write().await = config;
+        }
+
         Ok(InitializeResult {
             capabilities: ServerCapabilities {
                 text_document_sync: Some(TextDocumentSyncCapability::Kind(
@@ -85,16 +185,41 @@ impl LanguageServer for StratumLanguageServer {
                 })),
                 document_formatting_provider: Some(OneOf::Left(true)),
                 document_range_formatting_provider: Some(OneOf::Left(true)),
+                inlay_hint_provider: Some(OneOf::Left(true)),
+                folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+                selection_range_provider: Some(SelectionRangeProviderCapability::Simple(true)),
+                call_hierarchy_provider: Some(CallHierarchyServerCapability::Simple(true)),
+                semantic_tokens_provider: Some(
+                    SemanticTokensServerCapabilities::SemanticTokensOptions(
+                        SemanticTokensOptions {
+                            legend: semantic_tokens::legend(),
+                            full: Some(SemanticTokensFullOptions::Delta { delta: Some(true) }),
+                            range: Some(false),
+                            work_done_progress_options: Default::default(),
+                        },
+                    ),
+                ),
                 code_action_provider: Some(CodeActionProviderCapability::Options(
                     CodeActionOptions {
                         code_action_kinds: Some(vec![
                             CodeActionKind::QUICKFIX,
                             CodeActionKind::REFACTOR_EXTRACT,
+                            CodeActionKind::SOURCE_ORGANIZE_IMPORTS,
                         ]),
                         work_done_progress_options: Default::default(),
                         resolve_provider: Some(false),
                     },
                 )),
+                code_lens_provider: Some(CodeLensOptions {
+                    resolve_provider: Some(false),
+                }),
+                execute_command_provider: Some(ExecuteCommandOptions {
+                    commands: code_lens::SERVER_COMMANDS
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect(),
+                    work_done_progress_options: Default::default(),
+                }),
                 ..Default::default()
             },
             server_info: Some(ServerInfo {
@@ -105,6 +230,24 @@ impl LanguageServer for StratumLanguageServer {
     }
 
     async fn initialized(&self, _params: InitializedParams) {
+        // Type hierarchy has no static server capability in the protocol
+        // types we build against, so it is registered dynamically
+        let registration = Registration {
+            id: "stratum-type-hierarchy".to_string(),
+            method: "textDocument/prepareTypeHierarchy".to_string(),
+            register_options: Some(serde_json::json!({
+                "documentSelector": [{ "language": "stratum" }]
+            })),
+        };
+        if let Err(err) = self.client.register_capability(vec![registration]).await {
+            self.client
+                .log_message(
+                    MessageType::WARNING,
+                    format!("Type hierarchy is unavailable: {err}"),
+                )
+                .await;
+        }
+
         self.client
             .log_message(MessageType::INFO, "Stratum language server initialized")
             .await;
@@ -124,6 +267,7 @@ impl LanguageServer for StratumLanguageServer {
             let mut docs = self.documents.write().await;
             docs.insert(uri.clone(), DocumentCache::new(content, version));
         }
+        self.load_project(&uri).await;
 
         // Publish diagnostics using cached data
         self.publish_diagnostics_cached(uri, Some(version)).await;
@@ -150,22 +294,62 @@ impl LanguageServer for StratumLanguageServer {
     async fn did_close(&self, params: DidCloseTextDocumentParams) {
         let uri = params.text_document.uri;
 
-        // Remove document from tracking
-        {
+        // Remove document from tracking. A package source file stays part of
+        // the package, checked from disk from now on.
+        let config = *self.diagnostics_config.read().await;
+        let results = {
+            let mut projects = self.projects.write().await;
             let mut docs = self.documents.write().await;
             docs.remove(&uri);
+            match projects.iter_mut().find(|project| project.contains(&uri)) {
+                Some(project) => {
+                    project.reload(&uri);
+                    if project.is_source(&uri) {
+                        Some(project.check(&mut docs, &uri, config))
+                    } else {
+                        project.forget(&uri);
+                        None
+                    }
+                }
+                None => None,
+            }
+        };
+        self.semantic_tokens.write().await.remove(&uri);
+
+        match results {
+            Some(results) => self.publish_results(results, &uri, None).await,
+            // Clear diagnostics for closed document
+            None => self.client.publish_diagnostics(uri, vec![], None).await,
         }
+    }
 
-        // Clear diagnostics for closed document
-        self.client.publish_diagnostics(uri, vec![], None).await;
+    async fn did_change_configuration(&self, params: DidChangeConfigurationParams) {
+        if let Some(config) = InlayHintConfig::from_settings(&params.settings) {
+            *self.inlay_hint_config.write().await = config;
+        }
+        if let Some(config) = DiagnosticsConfig::from_settings(&params.settings) {
+            let changed = *self.diagnostics_config.read().await != config;
+            *self.diagnostics_config.write().await = config;
+            if changed {
+                for (uri, _) in self.open_documents().await {
+                    self.publish_diagnostics_cached(uri, None).await;
+                }
+            }
+        }
     }
 
     async fn did_save(&self, params: DidSaveTextDocumentParams) {
         // Re-check on save to pick up any external changes
         // If content is provided, we could update the cache, but typically
         // the editor has already sent didChange events
-        self.publish_diagnostics_cached(params.text_document.uri, None)
-            .await;
+        let uri = params.text_document.uri;
+        {
+            let mut projects = self.projects.write().await;
+            if let Some(project) = projects.iter_mut().find(|project| project.contains(&uri)) {
+                project.reload(&uri);
+            }
+        }
+        self.publish_diagnostics_cached(uri, None).await;
     }
 
     async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
@@ -176,7 +360,7 @@ impl LanguageServer for StratumLanguageServer {
         let mut docs = self.documents.write().await;
         if let Some(cache) = docs.get_mut(&uri) {
             let data = cache.get_all_cached();
-            if let Some(info) = hover::compute_hover_cached(&data, position) {
+            if let Some(info) = hover::compute_hover_cached(&uri, &data, position) {
                 return Ok(Some(hover::hover_info_to_lsp(info)));
             }
         }
@@ -226,11 +410,25 @@ impl LanguageServer for StratumLanguageServer {
         let uri = params.text_document_position.text_document.uri;
         let position = params.text_document_position.position;
 
-        // Get the document and use cached data
+        // Inside a package, names from its other files can be imported
+        let projects = self.projects.read().await;
+        let project = projects.iter().find(|project| project.contains(&uri));
         let mut docs = self.documents.write().await;
+        let index = project.map(|project| project.symbol_index(&mut docs));
+        let own_module = project.and_then(|project| project.module_path(&uri));
+
+        // Get the document and use cached data
         if let Some(cache) = docs.get_mut(&uri) {
             let data = cache.get_all_cached();
-            let items = completions::compute_completions_cached(&data, position);
+            let mut items = completions::compute_completions_cached(&data, position);
+            if let Some(index) = &index {
+                items.extend(completions::auto_import_completions(
+                    &data,
+                    position,
+                    index,
+                    own_module.as_deref(),
+                ));
+            }
             if !items.is_empty() {
                 return Ok(Some(CompletionResponse::Array(items)));
             }
@@ -280,13 +478,7 @@ impl LanguageServer for StratumLanguageServer {
     ) -> Result<Option<Vec<SymbolInformation>>> {
         let query = &params.query;
 
-        // Collect all open documents with their content
-        let docs = self.documents.read().await;
-        let documents: Vec<(Url, String)> = docs
-            .iter()
-            .map(|(uri, cache)| (uri.clone(), cache.content().to_string()))
-            .collect();
-
+        let documents = self.open_documents().await;
         let symbols = workspace_symbols::compute_workspace_symbols(query, &documents);
         if symbols.is_empty() {
             Ok(None)
@@ -381,4 +573,214 @@ impl LanguageServer for StratumLanguageServer {
 
         Ok(None)
     }
+
+    async fn code_lens(&self, params: CodeLensParams) -> Result<Option<Vec<CodeLens>>> {
+        let uri = params.text_document.uri;
+
+        // Reference counts look at every open document
+        let mut docs = self.documents.write().await;
+        for cache in docs.values_mut() {
+            cache.get_or_parse();
+        }
+        let documents: Vec<ParsedDocument<'_>> = docs
+            .iter()
+            .filter_map(|(uri, cache)| {
+                Some(ParsedDocument {
+                    uri,
+                    module: cache.parsed_ast()?,
+                    line_index: cache.line_index(),
+                })
+            })
+            .collect();
+
+        let lenses = code_lens::compute_code_lenses(&uri, &documents);
+        if lenses.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(lenses))
+    }
+
+    async fn execute_command(
+        &self,
+        params: ExecuteCommandParams,
+    ) -> Result<Option<serde_json::Value>> {
+        let Some(args) = code_lens::command_line(&params.command, &params.arguments) else {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(format!(
+                "unknown command or arguments: {}",
+                params.command
+            )));
+        };
+
+        // The server runs as `stratum lsp`, so the CLI is this executable
+        let program = std::env::current_exe().unwrap_or_else(|_| "stratum".into());
+        let mut command = tokio::process::Command::new(program);
+        command.args(&args);
+        if let Some(dir) = std::path::Path::new(&args[1]).parent() {
+            command.current_dir(dir);
+        }
+
+        let description = format!("stratum {}", args.join(" "));
+        match command.output().await {
+            Ok(output) => {
+                let mut log = String::from_utf8_lossy(&output.stdout).into_owned();
+                log.push_str(&String::from_utf8_lossy(&output.stderr));
+                self.client
+                    .log_message(MessageType::INFO, format!("$ {description}\n{log}"))
+                    .await;
+                if output.status.success() {
+                    self.client
+                        .show_message(MessageType::INFO, format!("`{description}` succeeded"))
+                        .await;
+                } else {
+                    self.client
+                        .show_message(
+                            MessageType::ERROR,
+                            format!("`{description}` failed; see the output for details"),
+                        )
+                        .await;
+                }
+            }
+            Err(err) => {
+                self.client
+                    .show_message(
+                        MessageType::ERROR,
+                        format!("Failed to start `{description}`: {err}"),
+                    )
+                    .await;
+            }
+        }
+
+        Ok(None)
+    }
+
+    async fn inlay_hint(&self, params: InlayHintParams) -> Result<Option<Vec<InlayHint>>> {
+        let uri = params.text_document.uri;
+        let config = *self.inlay_hint_config.read().await;
+
+        // Get the document and use cached data
+        let mut docs = self.documents.write().await;
+        if let Some(cache) = docs.get_mut(&uri) {
+            let data = cache.get_all_cached();
+            let hints = inlay_hints::compute_inlay_hints_cached(&data, params.range, config);
+            return Ok(Some(hints));
+        }
+
+        Ok(None)
+    }
+
+    async fn folding_range(&self, params: FoldingRangeParams) -> Result<Option<Vec<FoldingRange>>> {
+        let uri = params.text_document.uri;
+
+        let mut docs = self.documents.write().await;
+        if let Some(cache) = docs.get_mut(&uri) {
+            let data = cache.get_all_cached();
+            return Ok(Some(ranges::compute_folding_ranges_cached(&data)));
+        }
+
+        Ok(None)
+    }
+
+    async fn selection_range(
+        &self,
+        params: SelectionRangeParams,
+    ) -> Result<Option<Vec<SelectionRange>>> {
+        let uri = params.text_document.uri;
+
+        let mut docs = self.documents.write().await;
+        if let Some(cache) = docs.get_mut(&uri) {
+            let data = cache.get_all_cached();
+            return Ok(Some(ranges::compute_selection_ranges_cached(
+                &data,
+                &params.positions,
+            )));
+        }
+
+        Ok(None)
+    }
+
+    async fn semantic_tokens_full(
+        &self,
+        params: SemanticTokensParams,
+    ) -> Result<Option<SemanticTokensResult>> {
+        let tokens = self.semantic_tokens_for(&params.text_document.uri).await;
+        Ok(tokens.map(SemanticTokensResult::Tokens))
+    }
+
+    async fn semantic_tokens_full_delta(
+        &self,
+        params: SemanticTokensDeltaParams,
+    ) -> Result<Option<SemanticTokensFullDeltaResult>> {
+        let uri = params.text_document.uri;
+
+        // A delta is only possible against the tokens the client still holds
+        let previous = self
+            .semantic_tokens
+            .read()
+            .await
+            .get(&uri)
+            .filter(|tokens| tokens.result_id.as_ref() == Some(&params.previous_result_id))
+            .map(|tokens| tokens.data.clone());
+
+        let Some(tokens) = self.semantic_tokens_for(&uri).await else {
+            return Ok(None);
+        };
+        let result = match previous {
+            Some(previous) => SemanticTokensFullDeltaResult::TokensDelta(SemanticTokensDelta {
+                edits: semantic_tokens::compute_delta(&previous, &tokens.data),
+                result_id: tokens.result_id,
+            }),
+            None => SemanticTokensFullDeltaResult::Tokens(tokens),
+        };
+        Ok(Some(result))
+    }
+
+    async fn prepare_call_hierarchy(
+        &self,
+        params: CallHierarchyPrepareParams,
+    ) -> Result<Option<Vec<CallHierarchyItem>>> {
+        let position = params.text_document_position_params;
+        let index = ProjectIndex::build(&self.open_documents().await);
+        Ok(index.prepare_call_hierarchy(&position.text_document.uri, position.position))
+    }
+
+    async fn incoming_calls(
+        &self,
+        params: CallHierarchyIncomingCallsParams,
+    ) -> Result<Option<Vec<CallHierarchyIncomingCall>>> {
+        let index = ProjectIndex::build(&self.open_documents().await);
+        Ok(Some(index.incoming_calls(&params.item)))
+    }
+
+    async fn outgoing_calls(
+        &self,
+        params: CallHierarchyOutgoingCallsParams,
+    ) -> Result<Option<Vec<CallHierarchyOutgoingCall>>> {
+        let index = ProjectIndex::build(&self.open_documents().await);
+        Ok(Some(index.outgoing_calls(&params.item)))
+    }
+
+    async fn prepare_type_hierarchy(
+        &self,
+        params: TypeHierarchyPrepareParams,
+    ) -> Result<Option<Vec<TypeHierarchyItem>>> {
+        let position = params.text_document_position_params;
+        let index = ProjectIndex::build(&self.open_documents().await);
+        Ok(index.prepare_type_hierarchy(&position.text_document.uri, position.position))
+    }
+
+    async fn supertypes(
+        &self,
+        params: TypeHierarchySupertypesParams,
+    ) -> Result<Option<Vec<TypeHierarchyItem>>> {
+        let index = ProjectIndex::build(&self.open_documents().await);
+        Ok(Some(index.supertypes(&params.item)))
+    }
+
+    async fn subtypes(
+        &self,
+        params: TypeHierarchySubtypesParams,
+    ) -> Result<Option<Vec<TypeHierarchyItem>>> {
+        let index = ProjectIndex::build(&self.open_documents().await);
+        Ok(Some(index.subtypes(&params.item)))
+    }
 }
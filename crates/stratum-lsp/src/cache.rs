@@ -2,12 +2,16 @@
 //!
 //! This module provides caching of parsed ASTs, type check results,
 //! symbol indices, and line indices to avoid redundant computation.
+//!
+//! Edits are parsed incrementally: top-level items outside the edited region
+//! are reused from the previous parse, and edits that only touch whitespace or
+//! comments reuse the previous type check result as well.
 
 use std::sync::Arc;
 
 use stratum_core::ast::Module;
 use stratum_core::lexer::LineIndex;
-use stratum_core::parser::{ParseError, Parser};
+use stratum_core::parser::{IncrementalParser, ParseError, TextEdit};
 use stratum_core::types::{TypeCheckResult, TypeChecker};
 
 use crate::definition::SymbolIndex;
@@ -21,12 +25,20 @@ pub struct DocumentCache {
     version: i32,
     /// Cached line index
     line_index: Arc<LineIndex>,
+    /// Incremental parser holding the last parsed version of the document
+    parser: Option<IncrementalParser>,
     /// Cached parse result (None if not yet parsed)
     parse_result: Option<ParseResult>,
     /// Cached type check result (None if not yet type-checked)
     type_result: Option<Arc<TypeCheckResult>>,
+    /// Type check result of the last parsed version, kept across edits so it
+    /// can be reused when the edit turns out not to change any tokens
+    stale_type_result: Option<Arc<TypeCheckResult>>,
     /// Cached symbol index (None if not yet built)
     symbol_index: Option<Arc<SymbolIndex>>,
+    /// The other modules of the document's package, whose declarations are
+    /// visible to it when type checking
+    siblings: Vec<Arc<Module>>,
 }
 
 /// Result of parsing - either success with AST or failure with errors
@@ -46,9 +58,12 @@ impl DocumentCache {
             content,
             version,
             line_index,
+            parser: None,
             parse_result: None,
             type_result: None,
+            stale_type_result: None,
             symbol_index: None,
+            siblings: Vec::new(),
         }
     }
 
@@ -58,7 +73,6 @@ impl DocumentCache {
     }
 
     /// Get the document version
-    #[allow(dead_code)] // Public API for external consumers
     pub fn version(&self) -> i32 {
         self.version
     }
@@ -70,7 +84,8 @@ impl DocumentCache {
     }
 
     /// Apply an incremental text change to the document
-    /// This invalidates all cached analysis results
+    /// This invalidates all cached analysis results; the next parse only
+    /// reparses the items affected by the change
     pub fn apply_change(
         &mut self,
         range: Option<tower_lsp::lsp_types::Range>,
@@ -106,12 +121,32 @@ impl DocumentCache {
         self.version = version;
         self.line_index = Arc::new(LineIndex::new(&self.content));
 
-        // Invalidate cached analysis
-        self.parse_result = None;
-        self.type_result = None;
+        // Invalidate cached analysis. The type check result belongs to the
+        // version the incremental parser last saw, so it's only kept if that
+        // version was parsed since the previous change.
+        if self.parse_result.take().is_some() {
+            self.stale_type_result = self.type_result.take();
+        }
         self.symbol_index = None;
     }
 
+    /// Set the other modules of the document's package
+    ///
+    /// The type check result is only invalidated when the modules actually
+    /// changed, so unrelated edits elsewhere keep it.
+    pub fn set_siblings(&mut self, siblings: Vec<Arc<Module>>) {
+        let unchanged = siblings.len() == self.siblings.len()
+            && siblings
+                .iter()
+                .zip(&self.siblings)
+                .all(|(new, old)| Arc::ptr_eq(new, old));
+        if !unchanged {
+            self.siblings = siblings;
+            self.type_result = None;
+            self.stale_type_result = None;
+        }
+    }
+
     /// Convert an LSP position to a byte offset
     fn position_to_offset(&self, position: tower_lsp::lsp_types::Position) -> Option<u32> {
         let line_start = self.line_index.line_start(position.line as usize)?;
@@ -122,9 +157,30 @@ impl DocumentCache {
     /// Returns Ok with the AST if parsing succeeded, or Err with parse errors
     pub fn get_or_parse(&mut self) -> &ParseResult {
         if self.parse_result.is_none() {
-            let result = match Parser::parse_module(&self.content) {
-                Ok(module) => ParseResult::Ok(Arc::new(module)),
-                Err(errors) => ParseResult::Err(errors),
+            let stale_type_result = self.stale_type_result.take();
+            let parser = match &mut self.parser {
+                Some(parser) => {
+                    let reparse = parser.update(&self.content);
+                    // Whitespace and comment edits leave the AST unchanged apart
+                    // from positions, so the old type errors only need moving
+                    if !reparse.tokens_changed {
+                        self.type_result = match (stale_type_result, reparse.edit) {
+                            (Some(result), Some(edit)) => {
+                                Some(Arc::new(remap_type_result(&result, edit)))
+                            }
+                            (result, _) => result,
+                        };
+                    }
+                    parser
+                }
+                None => self.parser.insert(IncrementalParser::new(&self.content)),
+            };
+
+            let errors = parser.errors();
+            let result = if errors.is_empty() {
+                ParseResult::Ok(Arc::clone(parser.module()))
+            } else {
+                ParseResult::Err(errors)
             };
             self.parse_result = Some(result);
         }
@@ -132,7 +188,6 @@ impl DocumentCache {
     }
 
     /// Get the parsed AST if available (without parsing)
-    #[allow(dead_code)] // Public API for external consumers
     pub fn parsed_ast(&self) -> Option<&Arc<Module>> {
         match &self.parse_result {
             Some(ParseResult::Ok(module)) => Some(module),
@@ -152,8 +207,9 @@ impl DocumentCache {
 
         if self.type_result.is_none() {
             if let Some(ParseResult::Ok(module)) = &self.parse_result {
+                let siblings: Vec<&Module> = self.siblings.iter().map(AsRef::as_ref).collect();
                 let mut checker = TypeChecker::new();
-                let result = checker.check_module(module);
+                let result = checker.check_module_in_package(module, &siblings);
                 self.type_result = Some(Arc::new(result));
             }
         }
@@ -201,6 +257,24 @@ impl DocumentCache {
     }
 }
 
+/// Move the locations in a type check result through a text edit
+fn remap_type_result(result: &TypeCheckResult, edit: TextEdit) -> TypeCheckResult {
+    let mut result = result.clone();
+    for error in &mut result.errors {
+        error.span = edit.map_span(error.span);
+        for (span, _) in &mut error.related {
+            *span = edit.map_span(*span);
+        }
+    }
+    for (span, _) in &mut result.binding_types {
+        *span = edit.map_span(*span);
+    }
+    for name in &mut result.resolved_names {
+        name.span = edit.map_span(name.span);
+    }
+    result
+}
+
 /// Borrowed references to all cached data
 #[derive(Debug)]
 pub struct CachedData<'a> {
@@ -265,6 +339,7 @@ mod tests {
         // Apply a change - should invalidate cache
         cache.apply_change(None, "let y = 100".to_string(), 2);
         assert!(cache.parse_result.is_none());
+        assert!(cache.type_result.is_none());
         assert_eq!(cache.content(), "let y = 100");
         assert_eq!(cache.version(), 2);
     }
@@ -302,6 +377,63 @@ mod tests {
         assert!(cache.symbol_index.is_some());
     }
 
+    #[test]
+    fn test_incremental_reparse_matches_full_parse() {
+        let source = "fx add(a: Int, b: Int) -> Int { a + b }\n\nfx main() {\n    add(1, 2)\n}\n";
+        let mut cache = DocumentCache::new(source.to_string(), 1);
+        let _ = cache.get_or_parse();
+
+        // Change "2" to "20" inside main
+        let range = Range {
+            start: Position {
+                line: 3,
+                character: 11,
+            },
+            end: Position {
+                line: 3,
+                character: 12,
+            },
+        };
+        cache.apply_change(Some(range), "20".to_string(), 2);
+
+        let expected = stratum_core::Parser::parse_module(cache.content()).unwrap();
+        match cache.get_or_parse() {
+            ParseResult::Ok(module) => assert_eq!(**module, expected),
+            ParseResult::Err(errors) => panic!("unexpected parse errors: {errors:?}"),
+        }
+    }
+
+    #[test]
+    fn test_whitespace_edit_reuses_type_errors() {
+        let mut cache = DocumentCache::new("fx f() -> Int { \"s\" }".to_string(), 1);
+        let errors = cache.get_or_type_check().unwrap().errors.clone();
+        assert!(!errors.is_empty());
+
+        // Insert a comment line at the top; the error moves down with the code
+        let range = Range {
+            start: Position {
+                line: 0,
+                character: 0,
+            },
+            end: Position {
+                line: 0,
+                character: 0,
+            },
+        };
+        cache.apply_change(Some(range), "// note\n".to_string(), 2);
+        let _ = cache.get_or_parse();
+        assert!(cache.type_result.is_some(), "type result should be reused");
+
+        let remapped = cache.get_or_type_check().unwrap().errors.clone();
+        let module = stratum_core::Parser::parse_module(cache.content()).unwrap();
+        let fresh = TypeChecker::new().check_module(&module).errors;
+        let spans = |errors: &[stratum_core::types::TypeError]| {
+            errors.iter().map(|e| e.span).collect::<Vec<_>>()
+        };
+        assert_eq!(spans(&remapped), spans(&fresh));
+        assert_eq!(remapped[0].span.start, errors[0].span.start + 8);
+    }
+
     #[test]
     fn test_parse_error_caching() {
         let mut cache = DocumentCache::new("fx broken(".to_string(), 1);
@@ -3,7 +3,9 @@
 //! This module provides quick fixes and refactorings:
 //! - Quick fixes for diagnostics (did-you-mean, missing fields, extra fields)
 //! - Refactorings (extract variable)
+//! - Source actions (organize imports)
 
+use stratum_core::formatter::Formatter;
 use stratum_core::lexer::LineIndex;
 use stratum_core::parser::Parser;
 use tower_lsp::lsp_types::{
@@ -37,6 +39,11 @@ pub fn compute_code_actions_cached(
         actions.append(&mut refactors);
     }
 
+    // Source actions for the whole file
+    if let Some(action) = compute_organize_imports(uri, data.content) {
+        actions.push(CodeActionOrCommand::CodeAction(action));
+    }
+
     actions
 }
 
@@ -150,6 +157,11 @@ pub fn compute_code_actions(
         actions.append(&mut refactors);
     }
 
+    // Source actions for the whole file
+    if let Some(action) = compute_organize_imports(uri, source) {
+        actions.push(CodeActionOrCommand::CodeAction(action));
+    }
+
     actions
 }
 
@@ -633,6 +645,49 @@ fn compute_extract_variable(uri: &Url, source: &str, range: Range) -> Option<Cod
     })
 }
 
+/// Compute the organize imports source action
+///
+/// Sorts and groups the imports and removes unused ones. Not offered when
+/// the imports are already organized.
+fn compute_organize_imports(uri: &Url, source: &str) -> Option<CodeAction> {
+    let edit = Formatter::organize_imports(source)?;
+    let line_index = LineIndex::new(source);
+    let position = |offset| {
+        let location = line_index.location(offset);
+        Position {
+            line: location.line.saturating_sub(1),
+            character: location.column.saturating_sub(1),
+        }
+    };
+
+    let mut changes = HashMap::new();
+    changes.insert(
+        uri.clone(),
+        vec![TextEdit {
+            range: Range {
+                start: position(edit.span.start),
+                end: position(edit.span.end),
+            },
+            new_text: edit.text,
+        }],
+    );
+
+    Some(CodeAction {
+        title: "Organize imports".to_string(),
+        kind: Some(CodeActionKind::SOURCE_ORGANIZE_IMPORTS),
+        diagnostics: None,
+        is_preferred: None,
+        disabled: None,
+        edit: Some(WorkspaceEdit {
+            changes: Some(changes),
+            document_changes: None,
+            change_annotations: None,
+        }),
+        command: None,
+        data: None,
+    })
+}
+
 /// Convert an LSP Position to a byte offset
 fn position_to_offset(line_index: &LineIndex, position: Position, source: &str) -> Option<u32> {
     let line = position.line as usize;
@@ -778,4 +833,35 @@ fx main() {
 
         assert!(has_extract);
     }
+
+    #[test]
+    fn test_organize_imports_action() {
+        let source = "import json\nimport std.io\nimport std.math\n\nio.println(json.encode(1))\n";
+        let uri = Url::parse("file:///test.strat").unwrap();
+
+        let action = compute_organize_imports(&uri, source).expect("expected an action");
+        assert_eq!(action.kind, Some(CodeActionKind::SOURCE_ORGANIZE_IMPORTS));
+
+        let changes = action.edit.unwrap().changes.unwrap();
+        let edits = &changes[&uri];
+        assert_eq!(edits.len(), 1);
+        assert_eq!(
+            edits[0].range.start,
+            Position {
+                line: 0,
+                character: 0
+            }
+        );
+        assert_eq!(
+            edits[0].range.end,
+            Position {
+                line: 2,
+                character: 15
+            }
+        );
+        assert_eq!(edits[0].new_text, "import std.io\n\nimport json");
+
+        let organized = "import std.io\n\nimport json\n\nio.println(json.encode(1))\n";
+        assert!(compute_organize_imports(&uri, organized).is_none());
+    }
 }
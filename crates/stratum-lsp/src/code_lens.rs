@@ -0,0 +1,225 @@
+//! Code lens implementation for Stratum LSP
+//!
+//! Puts a "Run test" lens above every `#[test]` function and a "Run" lens
+//! above `main`; both run the `stratum` CLI through `workspace/executeCommand`.
+//! Every other top-level function gets a lens with the number of references
+//! to it across the open documents.
+
+use serde_json::{json, Value};
+use stratum_core::ast::{ItemKind, Module};
+use stratum_core::lexer::{LineIndex, Span};
+use tower_lsp::lsp_types::{CodeLens, Command, Location, Position, Range, Url};
+
+use crate::references;
+
+/// Runs a single test: `stratum test <file> --filter <name>`
+pub const RUN_TEST_COMMAND: &str = "stratum.runTest";
+
+/// Runs a file's `main` function: `stratum run <file>`
+pub const RUN_MAIN_COMMAND: &str = "stratum.runMain";
+
+/// Shows a list of locations; implemented by the editor extension, since
+/// the server cannot open a references view itself
+pub const SHOW_REFERENCES_COMMAND: &str = "stratum.showReferences";
+
+/// The commands the server executes itself
+pub const SERVER_COMMANDS: &[&str] = &[RUN_TEST_COMMAND, RUN_MAIN_COMMAND];
+
+/// An open document that parsed successfully
+pub struct ParsedDocument<'a> {
+    pub uri: &'a Url,
+    pub module: &'a Module,
+    pub line_index: &'a LineIndex,
+}
+
+/// Compute the code lenses of the document `uri`, which must be one of
+/// `documents`
+pub fn compute_code_lenses(uri: &Url, documents: &[ParsedDocument<'_>]) -> Vec<CodeLens> {
+    let Some(current) = documents.iter().find(|doc| doc.uri == uri) else {
+        return vec![];
+    };
+
+    let mut lenses = Vec::new();
+    for item in current.module.items() {
+        let ItemKind::Function(func) = &item.kind else {
+            continue;
+        };
+
+        // Start the lens at the first attribute, so it sits above `#[test]`
+        let start = func
+            .attributes
+            .iter()
+            .map(|attr| attr.span.start)
+            .chain(std::iter::once(func.span.start))
+            .min()
+            .unwrap_or(func.span.start);
+        let range = span_to_range(Span::new(start, func.span.end), current.line_index);
+        let name = &func.name.name;
+
+        if func.is_test() {
+            lenses.push(command_lens(
+                range,
+                "▶ Run test",
+                RUN_TEST_COMMAND,
+                vec![json!(uri), json!(name)],
+            ));
+        } else if name == "main" {
+            lenses.push(command_lens(
+                range,
+                "▶ Run",
+                RUN_MAIN_COMMAND,
+                vec![json!(uri)],
+            ));
+        } else {
+            let name_range = span_to_range(func.name.span, current.line_index);
+            let locations: Vec<Location> = documents
+                .iter()
+                .flat_map(|doc| {
+                    references::find_name_references(doc.module, name, doc.uri, doc.line_index)
+                })
+                .filter(|loc| !(loc.uri == *uri && loc.range == name_range))
+                .collect();
+            let title = match locations.len() {
+                1 => "1 reference".to_string(),
+                count => format!("{count} references"),
+            };
+            lenses.push(command_lens(
+                range,
+                &title,
+                SHOW_REFERENCES_COMMAND,
+                vec![json!(uri), json!(name_range.start), json!(locations)],
+            ));
+        }
+    }
+
+    lenses
+}
+
+/// The `stratum` arguments for one of [`SERVER_COMMANDS`], or `None` if
+/// the command is unknown or its arguments are malformed
+pub fn command_line(command: &str, arguments: &[Value]) -> Option<Vec<String>> {
+    let uri: Url = serde_json::from_value(arguments.first()?.clone()).ok()?;
+    let file = uri.to_file_path().ok()?.to_string_lossy().into_owned();
+
+    match command {
+        RUN_TEST_COMMAND => {
+            let name = arguments.get(1)?.as_str()?;
+            Some(vec![
+                "test".to_string(),
+                file,
+                "--filter".to_string(),
+                name.to_string(),
+            ])
+        }
+        RUN_MAIN_COMMAND => Some(vec!["run".to_string(), file]),
+        _ => None,
+    }
+}
+
+fn command_lens(range: Range, title: &str, command: &str, arguments: Vec<Value>) -> CodeLens {
+    CodeLens {
+        range,
+        command: Some(Command {
+            title: title.to_string(),
+            command: command.to_string(),
+            arguments: Some(arguments),
+        }),
+        data: None,
+    }
+}
+
+/// Convert a span to an LSP range
+fn span_to_range(span: Span, line_index: &LineIndex) -> Range {
+    let start = line_index.location(span.start);
+    let end = line_index.location(span.end);
+    Range {
+        start: Position {
+            line: start.line.saturating_sub(1),
+            character: start.column.saturating_sub(1),
+        },
+        end: Position {
+            line: end.line.saturating_sub(1),
+            character: end.column.saturating_sub(1),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stratum_core::parser::Parser;
+
+    fn lenses(sources: &[(&str, &str)]) -> Vec<CodeLens> {
+        let parsed: Vec<(Url, Module, LineIndex)> = sources
+            .iter()
+            .map(|(path, source)| {
+                (
+                    Url::parse(&format!("file:///project/{path}")).unwrap(),
+                    Parser::parse_module(source).unwrap(),
+                    LineIndex::new(source),
+                )
+            })
+            .collect();
+        let documents: Vec<ParsedDocument<'_>> = parsed
+            .iter()
+            .map(|(uri, module, line_index)| ParsedDocument {
+                uri,
+                module,
+                line_index,
+            })
+            .collect();
+        compute_code_lenses(&parsed[0].0, &documents)
+    }
+
+    fn command(lens: &CodeLens) -> &Command {
+        lens.command.as_ref().unwrap()
+    }
+
+    #[test]
+    fn test_run_lenses() {
+        let source =
+            "fx main() {\n    println(1);\n}\n\n#[test]\nfx test_add() {\n    assert(true);\n}\n";
+        let lenses = lenses(&[("main.strat", source)]);
+        assert_eq!(lenses.len(), 2);
+
+        assert_eq!(command(&lenses[0]).command, RUN_MAIN_COMMAND);
+        assert_eq!(lenses[0].range.start.line, 0);
+
+        let run_test = command(&lenses[1]);
+        assert_eq!(run_test.command, RUN_TEST_COMMAND);
+        assert_eq!(run_test.arguments.as_ref().unwrap()[1], json!("test_add"));
+        // The lens sits on the attribute line
+        assert_eq!(lenses[1].range.start.line, 4);
+    }
+
+    #[test]
+    fn test_reference_counts_across_documents() {
+        let lib = "fx double(x: Int) -> Int {\n    x * 2\n}\n\nfx unused() {}\n";
+        let main = "fx main() {\n    println(double(double(1)));\n}\n";
+        let lenses = lenses(&[("lib.strat", lib), ("main.strat", main)]);
+        assert_eq!(lenses.len(), 2);
+
+        let double = command(&lenses[0]);
+        assert_eq!(double.title, "2 references");
+        assert_eq!(double.command, SHOW_REFERENCES_COMMAND);
+        let locations = &double.arguments.as_ref().unwrap()[2];
+        assert_eq!(locations.as_array().unwrap().len(), 2);
+
+        assert_eq!(command(&lenses[1]).title, "0 references");
+    }
+
+    #[test]
+    fn test_command_line() {
+        let uri = json!("file:///project/tests.strat");
+        assert_eq!(
+            command_line(RUN_TEST_COMMAND, &[uri.clone(), json!("test_add")]).unwrap(),
+            ["test", "/project/tests.strat", "--filter", "test_add"]
+        );
+        assert_eq!(
+            command_line(RUN_MAIN_COMMAND, &[uri.clone()]).unwrap(),
+            ["run", "/project/tests.strat"]
+        );
+        assert!(command_line(RUN_TEST_COMMAND, &[uri.clone()]).is_none());
+        assert!(command_line("stratum.unknown", &[uri]).is_none());
+    }
+}
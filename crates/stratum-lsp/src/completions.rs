@@ -4,14 +4,22 @@
 //! - Keyword completions with snippets
 //! - Symbol completions (functions, variables, structs, enums)
 //! - Struct field completions after `.`
+//! - Struct literal and `match` arm snippets (see [`crate::snippets`])
+//! - Names declared in other files of the package, adding their import
 
-use stratum_core::ast::{Expr, ExprKind, ItemKind, Module, StructDef, TopLevelItem};
+use std::collections::HashSet;
+
+use stratum_core::ast::{Expr, ExprKind, ImportKind, ItemKind, Module, StructDef, TopLevelItem};
 use stratum_core::lexer::{LineIndex, Span};
 use stratum_core::parser::Parser;
-use tower_lsp::lsp_types::{CompletionItem, CompletionItemKind, InsertTextFormat, Position};
+use tower_lsp::lsp_types::{
+    self, CompletionItem, CompletionItemKind, InsertTextFormat, Position, Range, TextEdit,
+};
 
 use crate::cache::CachedData;
 use crate::definition::{SymbolIndex, SymbolKind};
+use crate::snippets;
+use crate::workspace_symbols::WorkspaceSymbolIndex;
 
 /// Completion context - what kind of completion is being requested
 #[derive(Debug, Clone, PartialEq)]
@@ -49,6 +57,7 @@ pub fn compute_completions_cached(
         CompletionContext::General { prefix, offset } => {
             let mut items = keyword_completions(&prefix);
             items.extend(symbol_completions(module, &prefix, offset));
+            items.extend(snippets::snippet_completions(data, offset, &prefix));
             items
         }
         CompletionContext::FieldAccess {
@@ -94,6 +103,137 @@ pub fn compute_completions(source: &str, position: Position) -> Vec<CompletionIt
     }
 }
 
+/// Complete names declared in other files of the package
+///
+/// Each item carries an additional edit that imports the name at the top of
+/// the file. Names this file declares or already imports are left to the
+/// regular symbol completions, as are those of `own_module`, the module path
+/// of this file.
+pub fn auto_import_completions(
+    data: &CachedData<'_>,
+    position: Position,
+    index: &WorkspaceSymbolIndex,
+    own_module: Option<&str>,
+) -> Vec<CompletionItem> {
+    let Some(offset) = position_to_offset(data.line_index, position) else {
+        return vec![];
+    };
+    let CompletionContext::General { prefix, .. } = determine_context(data.content, offset) else {
+        return vec![];
+    };
+    let Some(module) = data.ast() else {
+        return vec![];
+    };
+    if prefix.is_empty() {
+        return vec![];
+    }
+
+    let visible = visible_names(module);
+    let insert_at = import_insertion_point(module, data.content, data.line_index);
+
+    index
+        .matching(&prefix)
+        .filter(|symbol| Some(symbol.module_path.as_str()) != own_module)
+        .filter(|symbol| !visible.contains(symbol.name.as_str()))
+        .map(|symbol| {
+            let import = format!("import {}.{}", symbol.module_path, symbol.name);
+            let new_text = match insert_at {
+                Some(_) => format!("{import}\n"),
+                None => format!("{import}\n\n"),
+            };
+            let position = insert_at.unwrap_or_default();
+            CompletionItem {
+                label: symbol.name.clone(),
+                kind: Some(completion_kind(symbol.kind)),
+                detail: Some(import),
+                // After the names already in scope
+                sort_text: Some(format!("1_{}", symbol.name)),
+                additional_text_edits: Some(vec![TextEdit {
+                    range: Range {
+                        start: position,
+                        end: position,
+                    },
+                    new_text,
+                }]),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+/// The names a module declares at the top level or imports
+fn visible_names(module: &Module) -> HashSet<&str> {
+    let mut names = HashSet::new();
+    for item in module.items() {
+        match &item.kind {
+            ItemKind::Function(func) => {
+                names.insert(func.name.name.as_str());
+            }
+            ItemKind::Struct(struct_def) => {
+                names.insert(struct_def.name.name.as_str());
+            }
+            ItemKind::Enum(enum_def) => {
+                names.insert(enum_def.name.name.as_str());
+            }
+            ItemKind::Interface(interface_def) => {
+                names.insert(interface_def.name.name.as_str());
+            }
+            ItemKind::Import(import) => match &import.kind {
+                ImportKind::Item => {
+                    if let Some(last) = import.path.last() {
+                        names.insert(last.name.as_str());
+                    }
+                }
+                ImportKind::Alias(alias) => {
+                    names.insert(alias.name.as_str());
+                }
+                ImportKind::List(items) => {
+                    for item in items {
+                        names.insert(item.alias.as_ref().unwrap_or(&item.name).name.as_str());
+                    }
+                }
+                ImportKind::Glob => {}
+            },
+            ItemKind::Impl(_) => {}
+        }
+    }
+    names
+}
+
+/// The start of the line after the last import, or `None` if the module
+/// has no imports and a new one goes at the top of the file
+fn import_insertion_point(
+    module: &Module,
+    source: &str,
+    line_index: &LineIndex,
+) -> Option<Position> {
+    let last = module
+        .items()
+        .into_iter()
+        .filter_map(|item| match &item.kind {
+            ItemKind::Import(import) => Some(import.span),
+            _ => None,
+        })
+        .max_by_key(|span| span.end)?;
+    // Import spans run on to the next token; stop at the import's own text
+    let text = source.get(last.start as usize..last.end as usize)?;
+    let end = last.start + u32::try_from(text.trim_end().len()).ok()?;
+    // Line numbers are 1-based, so this is the next line
+    let line = line_index.location(end).line;
+    Some(Position { line, character: 0 })
+}
+
+/// The completion kind for a workspace symbol
+fn completion_kind(kind: lsp_types::SymbolKind) -> CompletionItemKind {
+    match kind {
+        lsp_types::SymbolKind::FUNCTION => CompletionItemKind::FUNCTION,
+        lsp_types::SymbolKind::STRUCT => CompletionItemKind::STRUCT,
+        lsp_types::SymbolKind::ENUM => CompletionItemKind::ENUM,
+        lsp_types::SymbolKind::INTERFACE => CompletionItemKind::INTERFACE,
+        _ => CompletionItemKind::VARIABLE,
+    }
+}
+
 /// Determine the completion context from source and cursor position
 fn determine_context(source: &str, offset: u32) -> CompletionContext {
     let offset = (offset as usize).min(source.len());
@@ -200,6 +340,12 @@ fn keyword_completions(prefix: &str) -> Vec<CompletionItem> {
             true,
         ),
         ("let", "let ${1:name} = ${0}", "Variable binding", true),
+        (
+            "const",
+            "const ${1:NAME} = ${0}",
+            "Compile-time constant",
+            true,
+        ),
         (
             "struct",
             "struct ${1:Name} {\n\t${0}\n}",
@@ -573,4 +719,36 @@ fx main() {
         let items = compute_completions(source, position);
         assert!(items.iter().any(|i| i.label == "helper"));
     }
+
+    #[test]
+    fn test_auto_import_completion() {
+        let text =
+            Parser::parse_module("fx trim(s: String) -> String { s }\nfx shout() {}").unwrap();
+        let index = WorkspaceSymbolIndex::build([("util.text".to_string(), &text)]);
+        let complete = |source: &str, position: Position| {
+            let mut cache = crate::cache::DocumentCache::new(source.to_string(), 1);
+            let data = cache.get_all_cached();
+            auto_import_completions(&data, position, &index, Some("main"))
+        };
+
+        // Without imports, the import goes at the top of the file
+        let items = complete("fx main() {\n    tr\n}\n", Position::new(1, 6));
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].label, "trim");
+        let edit = &items[0].additional_text_edits.as_ref().unwrap()[0];
+        assert_eq!(edit.range.start, Position::new(0, 0));
+        assert_eq!(edit.new_text, "import util.text.trim\n\n");
+
+        // Otherwise below the last import
+        let source = "import io\nimport util.text.shout\n\nfx main() {\n    shout();\n    t\n}\n";
+        let items = complete(source, Position::new(5, 5));
+        assert_eq!(items.len(), 1);
+        let edit = &items[0].additional_text_edits.as_ref().unwrap()[0];
+        assert_eq!(edit.range.start, Position::new(2, 0));
+        assert_eq!(edit.new_text, "import util.text.trim\n");
+
+        // Names already imported are not offered again
+        let items = complete(source, Position::new(4, 6));
+        assert!(items.is_empty());
+    }
 }
@@ -1,19 +1,54 @@
 //! Diagnostics computation for Stratum source files
 //!
 //! This module handles parsing and type-checking source code,
-//! then converts errors to LSP diagnostics format.
+//! then converts errors to LSP diagnostics format. Each diagnostic carries
+//! its error code, linked to the code's explanation. When enabled, public
+//! functions, structs and enums without doc comments are reported too.
 
+use serde::Deserialize;
+use stratum_core::ast::Module;
+use stratum_core::doc::{missing_docs, MissingDoc};
+use stratum_core::error_codes;
 use stratum_core::lexer::{LineIndex, Span};
 use stratum_core::parser::{ParseError, Parser};
 use stratum_core::types::{TypeChecker, TypeError};
-use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, Position, Range};
+use tower_lsp::lsp_types::{
+    CodeDescription, Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range, Url,
+};
 
 use crate::cache::CachedData;
 
+/// Which optional diagnostics to report
+///
+/// Read from the `diagnostics` object of the initialization options or the
+/// `stratum.diagnostics` workspace settings, with camelCase keys.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DiagnosticsConfig {
+    /// Warn about public functions, structs and enums without doc comments
+    pub missing_docs: bool,
+}
+
+impl DiagnosticsConfig {
+    /// Read the configuration from a `diagnostics` settings object
+    ///
+    /// Missing keys keep their defaults; a malformed object yields `None`.
+    pub fn from_settings(settings: &serde_json::Value) -> Option<Self> {
+        let value = settings
+            .get("stratum")
+            .unwrap_or(settings)
+            .get("diagnostics")?;
+        serde_json::from_value(value.clone()).ok()
+    }
+}
+
 /// Compute diagnostics using cached data
 ///
 /// This uses the pre-parsed AST and type check results from the cache.
-pub fn compute_diagnostics_cached(data: &CachedData<'_>) -> Vec<Diagnostic> {
+pub fn compute_diagnostics_cached(
+    data: &CachedData<'_>,
+    config: DiagnosticsConfig,
+) -> Vec<Diagnostic> {
     let mut diagnostics = Vec::new();
 
     // Check for parse errors
@@ -31,6 +66,10 @@ pub fn compute_diagnostics_cached(data: &CachedData<'_>) -> Vec<Diagnostic> {
         }
     }
 
+    if let (true, Some(module)) = (config.missing_docs, data.ast()) {
+        diagnostics.extend(missing_doc_diagnostics(module, data.line_index));
+    }
+
     diagnostics
 }
 
@@ -39,6 +78,16 @@ pub fn compute_diagnostics_cached(data: &CachedData<'_>) -> Vec<Diagnostic> {
 /// This runs the parser and type checker, collecting all errors.
 #[allow(dead_code)] // Standalone API used by tests
 pub fn compute_diagnostics(source: &str) -> Vec<Diagnostic> {
+    compute_package_diagnostics(source, &[], DiagnosticsConfig::default())
+}
+
+/// Compute diagnostics for a file checked together with the other modules
+/// of its package (see [`TypeChecker::check_module_in_package`])
+pub fn compute_package_diagnostics(
+    source: &str,
+    siblings: &[&Module],
+    config: DiagnosticsConfig,
+) -> Vec<Diagnostic> {
     let mut diagnostics = Vec::new();
     let line_index = LineIndex::new(source);
 
@@ -47,11 +96,14 @@ pub fn compute_diagnostics(source: &str) -> Vec<Diagnostic> {
         Ok(module) => {
             // Parsing succeeded, now type check
             let mut type_checker = TypeChecker::new();
-            let result = type_checker.check_module(&module);
+            let result = type_checker.check_module_in_package(&module, siblings);
 
             for error in result.errors {
                 diagnostics.push(type_error_to_diagnostic(&error, &line_index));
             }
+            if config.missing_docs {
+                diagnostics.extend(missing_doc_diagnostics(&module, &line_index));
+            }
         }
         Err(parse_errors) => {
             // Add all parse errors
@@ -82,6 +134,13 @@ fn span_to_range(span: Span, line_index: &LineIndex) -> Range {
     }
 }
 
+/// Link to the explanation of an error code
+fn code_description(code: &str) -> Option<CodeDescription> {
+    let entry = error_codes::lookup(code)?;
+    let href = Url::parse(&entry.url()).ok()?;
+    Some(CodeDescription { href })
+}
+
 /// Convert a parse error to an LSP diagnostic
 fn parse_error_to_diagnostic(error: &ParseError, line_index: &LineIndex) -> Diagnostic {
     let range = span_to_range(error.span, line_index);
@@ -94,8 +153,8 @@ fn parse_error_to_diagnostic(error: &ParseError, line_index: &LineIndex) -> Diag
     Diagnostic {
         range,
         severity: Some(DiagnosticSeverity::ERROR),
-        code: None,
-        code_description: None,
+        code: Some(NumberOrString::String(error.kind.code().to_string())),
+        code_description: code_description(error.kind.code()),
         source: Some("stratum".to_string()),
         message,
         related_information: None,
@@ -129,8 +188,8 @@ fn type_error_to_diagnostic(error: &TypeError, line_index: &LineIndex) -> Diagno
     Diagnostic {
         range,
         severity: Some(DiagnosticSeverity::ERROR),
-        code: None,
-        code_description: None,
+        code: Some(NumberOrString::String(error.kind.code().to_string())),
+        code_description: code_description(error.kind.code()),
         source: Some("stratum".to_string()),
         message,
         related_information,
@@ -139,6 +198,33 @@ fn type_error_to_diagnostic(error: &TypeError, line_index: &LineIndex) -> Diagno
     }
 }
 
+/// Warnings for the public items of a module without doc comments
+fn missing_doc_diagnostics(module: &Module, line_index: &LineIndex) -> Vec<Diagnostic> {
+    missing_docs(module)
+        .into_iter()
+        .map(|item| missing_doc_to_diagnostic(&item, line_index))
+        .collect()
+}
+
+/// Convert a public item without a doc comment to an LSP diagnostic
+fn missing_doc_to_diagnostic(item: &MissingDoc, line_index: &LineIndex) -> Diagnostic {
+    Diagnostic {
+        range: span_to_range(item.span, line_index),
+        severity: Some(DiagnosticSeverity::WARNING),
+        code: Some(NumberOrString::String("missing_docs".to_string())),
+        code_description: None,
+        source: Some("stratum".to_string()),
+        message: format!(
+            "missing documentation for public {} `{}`",
+            item.kind.display_name().to_lowercase(),
+            item.name
+        ),
+        related_information: None,
+        tags: None,
+        data: None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -178,6 +264,18 @@ mod tests {
         assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::ERROR));
     }
 
+    #[test]
+    fn test_diagnostic_error_code() {
+        let source = "fx main() {\n    let x: Int = \"hello\"\n}";
+        let diagnostics = compute_diagnostics(source);
+        assert_eq!(
+            diagnostics[0].code,
+            Some(NumberOrString::String("E0100".to_string()))
+        );
+        let href = &diagnostics[0].code_description.as_ref().unwrap().href;
+        assert!(href.as_str().ends_with("errors.md#e0100"));
+    }
+
     #[test]
     fn test_undefined_variable_error() {
         let source = r#"
@@ -194,6 +292,57 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_package_diagnostics_see_sibling_declarations() {
+        let lib = Parser::parse_module("fx greet(name: String) -> String { name }").unwrap();
+
+        let source = "fx main() {\n    greet(\"a\");\n}";
+        assert!(!compute_diagnostics(source).is_empty());
+        let config = DiagnosticsConfig::default();
+        assert!(compute_package_diagnostics(source, &[&lib], config).is_empty());
+
+        let source = "fx main() {\n    greet(1);\n}";
+        let diagnostics = compute_package_diagnostics(source, &[&lib], config);
+        assert!(!diagnostics.is_empty());
+        assert!(diagnostics.iter().all(|d| d.range.start.line == 1));
+    }
+
+    #[test]
+    fn test_missing_docs_diagnostics() {
+        let source = "/// Add two numbers.\nfx add(a: Int, b: Int) -> Int { a + b }\n\nfx sub(a: Int, b: Int) -> Int { a - b }\n\nfx _helper() {}\n\nstruct Point { x: Int }";
+        assert!(compute_diagnostics(source).is_empty());
+
+        let config = DiagnosticsConfig { missing_docs: true };
+        let diagnostics = compute_package_diagnostics(source, &[], config);
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "missing documentation for public function `sub`",
+                "missing documentation for public struct `Point`",
+            ]
+        );
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(diagnostics[0].range.start, Position::new(3, 3));
+    }
+
+    #[test]
+    fn test_diagnostics_config_from_settings() {
+        let settings = serde_json::json!({ "stratum": { "diagnostics": { "missingDocs": true } } });
+        assert_eq!(
+            DiagnosticsConfig::from_settings(&settings),
+            Some(DiagnosticsConfig { missing_docs: true })
+        );
+        assert_eq!(
+            DiagnosticsConfig::from_settings(&serde_json::json!({ "diagnostics": {} })),
+            Some(DiagnosticsConfig::default())
+        );
+        assert_eq!(
+            DiagnosticsConfig::from_settings(&serde_json::json!({})),
+            None
+        );
+    }
+
     #[test]
     fn test_span_to_range_single_line() {
         let source = "let x = 42";
@@ -3,7 +3,7 @@
 //! This module provides code formatting using the stratum-core formatter.
 
 use stratum_core::formatter::Formatter;
-use stratum_core::lexer::LineIndex;
+use stratum_core::lexer::{LineIndex, Span};
 use stratum_core::parser::Parser;
 use tower_lsp::lsp_types::{Position, Range, TextEdit};
 
@@ -16,7 +16,7 @@ pub fn compute_formatting(source: &str) -> Option<Vec<TextEdit>> {
     let module = Parser::parse_module(source).ok()?;
 
     // Format the module
-    let formatted = Formatter::format_module(&module);
+    let formatted = Formatter::format_source(source, &module);
 
     // If the source is already formatted, return empty edits
     if source == formatted {
@@ -51,15 +51,52 @@ pub fn compute_formatting(source: &str) -> Option<Vec<TextEdit>> {
 
 /// Compute formatting edits for a range within a document
 ///
-/// Note: Currently formats the entire document as range formatting
-/// requires more sophisticated diffing. Returns None if source cannot be parsed.
-pub fn compute_range_formatting(source: &str, _range: Range) -> Option<Vec<TextEdit>> {
-    // For now, we format the entire document
-    // True range formatting would require:
-    // 1. Identifying which AST nodes fall within the range
-    // 2. Formatting only those nodes
-    // 3. Computing diffs
-    compute_formatting(source)
+/// Only the statements touched by `range` are reformatted, so the rest of
+/// the document is left as written. Returns None if the source cannot be
+/// parsed.
+pub fn compute_range_formatting(source: &str, range: Range) -> Option<Vec<TextEdit>> {
+    let line_index = LineIndex::new(source);
+    let start = position_to_offset(&line_index, range.start)?;
+    let end = position_to_offset(&line_index, range.end)?.max(start);
+
+    let Some(edit) = Formatter::format_range(source, Span::new(start, end)) else {
+        // Unparseable source formats nothing; a selection over blank lines
+        // or between statements has nothing to format either
+        Parser::parse_module(source).ok()?;
+        return Some(vec![]);
+    };
+
+    if source.get(edit.span.start as usize..edit.span.end as usize) == Some(edit.text.as_str()) {
+        return Some(vec![]);
+    }
+
+    Some(vec![TextEdit {
+        range: span_to_range(edit.span, &line_index),
+        new_text: edit.text,
+    }])
+}
+
+/// Convert an LSP Position to a byte offset
+fn position_to_offset(line_index: &LineIndex, position: Position) -> Option<u32> {
+    let line_start = line_index.line_start(position.line as usize)?;
+    Some(line_start + position.character)
+}
+
+/// Convert a Span to an LSP Range
+fn span_to_range(span: Span, line_index: &LineIndex) -> Range {
+    let start = line_index.location(span.start);
+    let end = line_index.location(span.end);
+
+    Range {
+        start: Position {
+            line: start.line.saturating_sub(1),
+            character: start.column.saturating_sub(1),
+        },
+        end: Position {
+            line: end.line.saturating_sub(1),
+            character: end.column.saturating_sub(1),
+        },
+    }
 }
 
 #[cfg(test)]
@@ -126,4 +163,50 @@ mod tests {
         let formatted = &edits[0].new_text;
         assert!(formatted.contains("// Comment"));
     }
+
+    fn range(start: (u32, u32), end: (u32, u32)) -> Range {
+        Range {
+            start: Position {
+                line: start.0,
+                character: start.1,
+            },
+            end: Position {
+                line: end.0,
+                character: end.1,
+            },
+        }
+    }
+
+    #[test]
+    fn test_range_formatting_touches_only_selection() {
+        let source = "fx one()->Int{1+1}\n\nfx two()  ->  Int {\n    let x=2\n    x\n}\n";
+        let edits = compute_range_formatting(source, range((3, 4), (3, 11))).unwrap();
+
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].range, range((3, 0), (3, 11)));
+        assert_eq!(edits[0].new_text, "    let x = 2");
+    }
+
+    #[test]
+    fn test_range_formatting_whole_function() {
+        let source = "fx one()->Int{1+1}\n\nfx two() -> Int {\n    2\n}\n";
+        let edits = compute_range_formatting(source, range((0, 0), (0, 5))).unwrap();
+
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].range, range((0, 0), (0, 18)));
+        assert_eq!(edits[0].new_text, "fx one() -> Int {\n    1 + 1\n}");
+    }
+
+    #[test]
+    fn test_range_formatting_already_formatted() {
+        let source = "fx main() {\n    let x = 2\n    x\n}\n";
+        let edits = compute_range_formatting(source, range((1, 0), (2, 0))).unwrap();
+        assert!(edits.is_empty());
+    }
+
+    #[test]
+    fn test_range_formatting_invalid_source() {
+        let source = "fx incomplete(";
+        assert!(compute_range_formatting(source, range((0, 0), (0, 3))).is_none());
+    }
 }
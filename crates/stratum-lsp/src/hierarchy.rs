@@ -0,0 +1,752 @@
+//! Call and type hierarchy for Stratum LSP
+//!
+//! Both hierarchies are answered from a [`ProjectIndex`] built over every
+//! document in the workspace, so callers in other files and implementations
+//! of an interface elsewhere in the project are found too.
+//!
+//! - Call hierarchy: functions and impl methods, with the calls each makes
+//! - Type hierarchy: structs, enums and interfaces, where a type's
+//!   supertypes are the interfaces it implements
+
+use std::collections::HashMap;
+
+use stratum_core::ast::{
+    Block, CallArg, ElseBranch, Expr, ExprKind, Function, Ident, ItemKind, Stmt, StmtKind,
+    StringPart, TypeAnnotation, TypeKind,
+};
+use stratum_core::lexer::{LineIndex, Span};
+use stratum_core::parser::Parser;
+use tower_lsp::lsp_types::{
+    CallHierarchyIncomingCall, CallHierarchyItem, CallHierarchyOutgoingCall, Position, Range,
+    SymbolKind, TypeHierarchyItem, Url,
+};
+
+/// Where an indexed declaration lives
+#[derive(Debug, Clone)]
+struct EntryLocation {
+    uri: Url,
+    /// The whole declaration
+    range: Range,
+    /// Just the declared name
+    selection_range: Range,
+}
+
+impl EntryLocation {
+    fn new(uri: &Url, span: Span, name: &Ident, line_index: &LineIndex) -> Self {
+        Self {
+            uri: uri.clone(),
+            range: span_to_range(span, line_index),
+            selection_range: span_to_range(name.span, line_index),
+        }
+    }
+
+    fn matches(&self, item: &CallHierarchyItem) -> bool {
+        self.uri == item.uri && self.selection_range == item.selection_range
+    }
+}
+
+/// A function or impl method
+#[derive(Debug)]
+struct FunctionEntry {
+    name: String,
+    /// The type an impl method belongs to
+    owner: Option<String>,
+    location: EntryLocation,
+    calls: Vec<CallSite>,
+}
+
+/// A call made from inside a function
+#[derive(Debug)]
+struct CallSite {
+    target: CallTarget,
+    /// The callee's name at the call site
+    range: Range,
+}
+
+#[derive(Debug)]
+enum CallTarget {
+    /// `name(...)`
+    Function(String),
+    /// `receiver.name(...)`, with the receiver's name when it is a plain
+    /// identifier (a variable, or a type for static methods)
+    Method {
+        name: String,
+        receiver: Option<String>,
+    },
+}
+
+/// A struct, enum or interface
+#[derive(Debug)]
+struct TypeEntry {
+    name: String,
+    kind: SymbolKind,
+    location: EntryLocation,
+    /// Interfaces implemented through `impl Interface for Type`
+    interfaces: Vec<String>,
+}
+
+/// Functions, calls and types of every document in the workspace
+#[derive(Debug, Default)]
+pub struct ProjectIndex {
+    functions: Vec<FunctionEntry>,
+    types: Vec<TypeEntry>,
+    /// Mentions of type names in impl headers, which also start a type
+    /// hierarchy
+    type_mentions: Vec<(Url, Range, String)>,
+}
+
+impl ProjectIndex {
+    /// Index the given documents; documents that fail to parse are skipped
+    pub fn build(documents: &[(Url, String)]) -> Self {
+        let mut index = Self::default();
+        let mut impls: Vec<(String, String)> = Vec::new();
+
+        for (uri, content) in documents {
+            let Ok(module) = Parser::parse_module(content) else {
+                continue;
+            };
+            let line_index = LineIndex::new(content);
+
+            for item in module.items() {
+                match &item.kind {
+                    ItemKind::Function(func) => {
+                        index.add_function(uri, func, None, &line_index);
+                    }
+                    ItemKind::Impl(imp) => {
+                        let owner = type_name(&imp.target);
+                        for method in &imp.methods {
+                            index.add_function(uri, method, owner.clone(), &line_index);
+                        }
+                        for ty in std::iter::once(&imp.target).chain(&imp.interface) {
+                            if let TypeKind::Named { name, .. } = &ty.kind {
+                                let range = span_to_range(name.span, &line_index);
+                                index
+                                    .type_mentions
+                                    .push((uri.clone(), range, name.name.clone()));
+                            }
+                        }
+                        if let (Some(interface), Some(owner)) =
+                            (imp.interface.as_ref().and_then(type_name), owner)
+                        {
+                            impls.push((owner, interface));
+                        }
+                    }
+                    ItemKind::Struct(def) => index.types.push(TypeEntry {
+                        name: def.name.name.clone(),
+                        kind: SymbolKind::STRUCT,
+                        location: EntryLocation::new(uri, def.span, &def.name, &line_index),
+                        interfaces: Vec::new(),
+                    }),
+                    ItemKind::Enum(def) => index.types.push(TypeEntry {
+                        name: def.name.name.clone(),
+                        kind: SymbolKind::ENUM,
+                        location: EntryLocation::new(uri, def.span, &def.name, &line_index),
+                        interfaces: Vec::new(),
+                    }),
+                    ItemKind::Interface(def) => index.types.push(TypeEntry {
+                        name: def.name.name.clone(),
+                        kind: SymbolKind::INTERFACE,
+                        location: EntryLocation::new(uri, def.span, &def.name, &line_index),
+                        interfaces: Vec::new(),
+                    }),
+                    ItemKind::Import(_) => {}
+                }
+            }
+        }
+
+        // Impl blocks may live in a different file than the type itself
+        for (owner, interface) in impls {
+            for entry in index.types.iter_mut().filter(|entry| entry.name == owner) {
+                if !entry.interfaces.contains(&interface) {
+                    entry.interfaces.push(interface.clone());
+                }
+            }
+        }
+
+        index
+    }
+
+    fn add_function(
+        &mut self,
+        uri: &Url,
+        func: &Function,
+        owner: Option<String>,
+        line_index: &LineIndex,
+    ) {
+        let mut collector = CallCollector {
+            line_index,
+            calls: Vec::new(),
+        };
+        collector.block(&func.body);
+        for default in func
+            .params
+            .iter()
+            .filter_map(|param| param.default.as_ref())
+        {
+            collector.expr(default);
+        }
+
+        self.functions.push(FunctionEntry {
+            name: func.name.name.clone(),
+            owner,
+            location: EntryLocation::new(uri, func.span, &func.name, line_index),
+            calls: collector.calls,
+        });
+    }
+
+    /// The functions at `position`: the declaration under the cursor, or
+    /// the functions a call under the cursor may reach
+    pub fn prepare_call_hierarchy(
+        &self,
+        uri: &Url,
+        position: Position,
+    ) -> Option<Vec<CallHierarchyItem>> {
+        if let Some(entry) = self.functions.iter().find(|entry| {
+            entry.location.uri == *uri && contains(entry.location.selection_range, position)
+        }) {
+            return Some(vec![function_item(entry)]);
+        }
+
+        let call = self
+            .functions
+            .iter()
+            .filter(|entry| entry.location.uri == *uri)
+            .flat_map(|entry| &entry.calls)
+            .find(|call| contains(call.range, position))?;
+        let items: Vec<CallHierarchyItem> = self
+            .resolve(&call.target)
+            .into_iter()
+            .map(function_item)
+            .collect();
+        (!items.is_empty()).then_some(items)
+    }
+
+    /// The functions that call `item`, with the ranges of their calls
+    pub fn incoming_calls(&self, item: &CallHierarchyItem) -> Vec<CallHierarchyIncomingCall> {
+        let Some(target) = self.find_function(item) else {
+            return vec![];
+        };
+
+        self.functions
+            .iter()
+            .filter_map(|caller| {
+                let from_ranges: Vec<Range> = caller
+                    .calls
+                    .iter()
+                    .filter(|call| {
+                        self.resolve(&call.target)
+                            .iter()
+                            .any(|callee| std::ptr::eq(*callee, target))
+                    })
+                    .map(|call| call.range)
+                    .collect();
+                (!from_ranges.is_empty()).then(|| CallHierarchyIncomingCall {
+                    from: function_item(caller),
+                    from_ranges,
+                })
+            })
+            .collect()
+    }
+
+    /// The functions `item` calls, with the ranges of the calls, in the
+    /// order they are first called
+    pub fn outgoing_calls(&self, item: &CallHierarchyItem) -> Vec<CallHierarchyOutgoingCall> {
+        let Some(caller) = self.find_function(item) else {
+            return vec![];
+        };
+
+        let mut order: Vec<usize> = Vec::new();
+        let mut ranges: HashMap<usize, Vec<Range>> = HashMap::new();
+        for call in &caller.calls {
+            for callee in self.resolve(&call.target) {
+                let Some(position) = self
+                    .functions
+                    .iter()
+                    .position(|entry| std::ptr::eq(entry, callee))
+                else {
+                    continue;
+                };
+                if !ranges.contains_key(&position) {
+                    order.push(position);
+                }
+                ranges.entry(position).or_default().push(call.range);
+            }
+        }
+
+        order
+            .into_iter()
+            .map(|position| CallHierarchyOutgoingCall {
+                to: function_item(&self.functions[position]),
+                from_ranges: ranges.remove(&position).unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    /// The type declared or named in an impl header at `position`
+    pub fn prepare_type_hierarchy(
+        &self,
+        uri: &Url,
+        position: Position,
+    ) -> Option<Vec<TypeHierarchyItem>> {
+        if let Some(entry) = self.types.iter().find(|entry| {
+            entry.location.uri == *uri && contains(entry.location.selection_range, position)
+        }) {
+            return Some(vec![type_item(entry)]);
+        }
+
+        let (_, _, name) = self
+            .type_mentions
+            .iter()
+            .find(|(mention_uri, range, _)| mention_uri == uri && contains(*range, position))?;
+        let items: Vec<TypeHierarchyItem> = self
+            .types
+            .iter()
+            .filter(|entry| entry.name == *name)
+            .map(type_item)
+            .collect();
+        (!items.is_empty()).then_some(items)
+    }
+
+    /// The interfaces `item` implements
+    pub fn supertypes(&self, item: &TypeHierarchyItem) -> Vec<TypeHierarchyItem> {
+        let Some(entry) = self.find_type(item) else {
+            return vec![];
+        };
+        self.types
+            .iter()
+            .filter(|ty| entry.interfaces.contains(&ty.name))
+            .map(type_item)
+            .collect()
+    }
+
+    /// The types implementing `item`, when it is an interface
+    pub fn subtypes(&self, item: &TypeHierarchyItem) -> Vec<TypeHierarchyItem> {
+        let Some(entry) = self.find_type(item) else {
+            return vec![];
+        };
+        self.types
+            .iter()
+            .filter(|ty| ty.interfaces.contains(&entry.name))
+            .map(type_item)
+            .collect()
+    }
+
+    fn find_function(&self, item: &CallHierarchyItem) -> Option<&FunctionEntry> {
+        self.functions
+            .iter()
+            .find(|entry| entry.location.matches(item))
+    }
+
+    fn find_type(&self, item: &TypeHierarchyItem) -> Option<&TypeEntry> {
+        self.types.iter().find(|entry| entry.location.matches(item))
+    }
+
+    /// The functions a call may reach
+    ///
+    /// A method call on a type name (`Point.new()`) only reaches that type's
+    /// method; on anything else it may reach any method of that name.
+    fn resolve(&self, target: &CallTarget) -> Vec<&FunctionEntry> {
+        match target {
+            CallTarget::Function(name) => self
+                .functions
+                .iter()
+                .filter(|entry| entry.owner.is_none() && entry.name == *name)
+                .collect(),
+            CallTarget::Method { name, receiver } => {
+                let methods = self
+                    .functions
+                    .iter()
+                    .filter(|entry| entry.owner.is_some() && entry.name == *name);
+                let on_type: Vec<&FunctionEntry> = methods
+                    .clone()
+                    .filter(|entry| entry.owner.is_some() && entry.owner == *receiver)
+                    .collect();
+                if on_type.is_empty() {
+                    methods.collect()
+                } else {
+                    on_type
+                }
+            }
+        }
+    }
+}
+
+fn function_item(entry: &FunctionEntry) -> CallHierarchyItem {
+    CallHierarchyItem {
+        name: entry.name.clone(),
+        kind: if entry.owner.is_some() {
+            SymbolKind::METHOD
+        } else {
+            SymbolKind::FUNCTION
+        },
+        tags: None,
+        detail: entry.owner.clone(),
+        uri: entry.location.uri.clone(),
+        range: entry.location.range,
+        selection_range: entry.location.selection_range,
+        data: None,
+    }
+}
+
+fn type_item(entry: &TypeEntry) -> TypeHierarchyItem {
+    TypeHierarchyItem {
+        name: entry.name.clone(),
+        kind: entry.kind,
+        tags: None,
+        detail: None,
+        uri: entry.location.uri.clone(),
+        range: entry.location.range,
+        selection_range: entry.location.selection_range,
+        data: None,
+    }
+}
+
+fn type_name(ty: &TypeAnnotation) -> Option<String> {
+    match &ty.kind {
+        TypeKind::Named { name, .. } => Some(name.name.clone()),
+        _ => None,
+    }
+}
+
+/// Collects the calls made inside a function body
+struct CallCollector<'a> {
+    line_index: &'a LineIndex,
+    calls: Vec<CallSite>,
+}
+
+impl CallCollector<'_> {
+    fn block(&mut self, block: &Block) {
+        for stmt in &block.stmts {
+            self.stmt(stmt);
+        }
+        if let Some(expr) = &block.expr {
+            self.expr(expr);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::Let { value: expr, .. }
+            | StmtKind::Expr(expr)
+            | StmtKind::Throw(expr)
+            | StmtKind::Return(Some(expr)) => self.expr(expr),
+            StmtKind::Assign { target, value } | StmtKind::CompoundAssign { target, value, .. } => {
+                self.expr(target);
+                self.expr(value);
+            }
+            StmtKind::For { iter, body, .. } => {
+                self.expr(iter);
+                self.block(body);
+            }
+            StmtKind::While { cond, body } => {
+                self.expr(cond);
+                self.block(body);
+            }
+            StmtKind::Loop { body } => self.block(body),
+            StmtKind::TryCatch {
+                try_block,
+                catches,
+                finally,
+            } => {
+                self.block(try_block);
+                for catch in catches {
+                    self.block(&catch.body);
+                }
+                if let Some(finally) = finally {
+                    self.block(finally);
+                }
+            }
+            StmtKind::Return(None) | StmtKind::Break | StmtKind::Continue => {}
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Literal(_)
+            | ExprKind::Ident(_)
+            | ExprKind::Placeholder
+            | ExprKind::ColumnShorthand(_) => {}
+            ExprKind::Binary { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            ExprKind::Unary { expr, .. }
+            | ExprKind::Paren(expr)
+            | ExprKind::Field { expr, .. }
+            | ExprKind::NullSafeField { expr, .. }
+            | ExprKind::Await(expr)
+            | ExprKind::Try(expr)
+            | ExprKind::StateBinding(expr) => self.expr(expr),
+            ExprKind::Call {
+                callee,
+                args,
+                trailing_closure,
+            } => {
+                self.call_site(callee);
+                self.expr(callee);
+                for arg in args {
+                    match arg {
+                        CallArg::Positional(value) | CallArg::Named { value, .. } => {
+                            self.expr(value);
+                        }
+                    }
+                }
+                if let Some(closure) = trailing_closure {
+                    self.expr(closure);
+                }
+            }
+            ExprKind::Index { expr, index } | ExprKind::NullSafeIndex { expr, index } => {
+                self.expr(expr);
+                self.expr(index);
+            }
+            ExprKind::If {
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                self.expr(cond);
+                self.block(then_branch);
+                match else_branch {
+                    Some(ElseBranch::Block(block)) => self.block(block),
+                    Some(ElseBranch::ElseIf(expr)) => self.expr(expr),
+                    None => {}
+                }
+            }
+            ExprKind::Match { expr, arms } => {
+                self.expr(expr);
+                for arm in arms {
+                    if let Some(guard) = &arm.guard {
+                        self.expr(guard);
+                    }
+                    self.expr(&arm.body);
+                }
+            }
+            ExprKind::Lambda { body, .. } => self.expr(body),
+            ExprKind::Block(block) => self.block(block),
+            ExprKind::List(items) => {
+                for item in items {
+                    self.expr(item);
+                }
+            }
+            ExprKind::Map(entries) => {
+                for (key, value) in entries {
+                    self.expr(key);
+                    self.expr(value);
+                }
+            }
+            ExprKind::StringInterp { parts } => {
+                for part in parts {
+                    if let StringPart::Expr(expr) = part {
+                        self.expr(expr);
+                    }
+                }
+            }
+            ExprKind::StructInit { fields, .. } => {
+                for value in fields.iter().filter_map(|field| field.value.as_ref()) {
+                    self.expr(value);
+                }
+            }
+            ExprKind::EnumVariant { data, .. } => {
+                if let Some(data) = data {
+                    self.expr(data);
+                }
+            }
+        }
+    }
+
+    fn call_site(&mut self, callee: &Expr) {
+        let (target, name) = match &callee.kind {
+            ExprKind::Ident(name) => (CallTarget::Function(name.name.clone()), name),
+            ExprKind::Field { expr, field } | ExprKind::NullSafeField { expr, field } => {
+                let receiver = match &expr.kind {
+                    ExprKind::Ident(receiver) => Some(receiver.name.clone()),
+                    _ => None,
+                };
+                let target = CallTarget::Method {
+                    name: field.name.clone(),
+                    receiver,
+                };
+                (target, field)
+            }
+            _ => return,
+        };
+        self.calls.push(CallSite {
+            target,
+            range: span_to_range(name.span, self.line_index),
+        });
+    }
+}
+
+fn contains(range: Range, position: Position) -> bool {
+    range.start <= position && position <= range.end
+}
+
+/// Convert a Span to an LSP Range
+fn span_to_range(span: Span, line_index: &LineIndex) -> Range {
+    let start = line_index.location(span.start);
+    let end = line_index.location(span.end);
+
+    Range {
+        start: Position {
+            line: start.line.saturating_sub(1),
+            character: start.column.saturating_sub(1),
+        },
+        end: Position {
+            line: end.line.saturating_sub(1),
+            character: end.column.saturating_sub(1),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uri(name: &str) -> Url {
+        Url::parse(&format!("file:///project/{name}")).unwrap()
+    }
+
+    fn position(line: u32, character: u32) -> Position {
+        Position { line, character }
+    }
+
+    const SHAPES: &str = "interface Shape {
+    fx area(self) -> Float
+}
+
+struct Square {
+    side: Float
+}
+
+impl Shape for Square {
+    fx area(self) -> Float {
+        self.side * self.side
+    }
+}
+
+fx total(shapes: List<Square>) -> Float {
+    let sum = 0.0
+    for s in shapes {
+        sum = sum + s.area()
+    }
+    sum
+}
+";
+
+    const MAIN: &str = "fx main() {
+    let squares = [Square { side: 2.0 }]
+    println(total(squares));
+    println(total([]));
+}
+";
+
+    fn index() -> ProjectIndex {
+        ProjectIndex::build(&[
+            (uri("shapes.strat"), SHAPES.to_string()),
+            (uri("main.strat"), MAIN.to_string()),
+        ])
+    }
+
+    fn names(items: &[CallHierarchyItem]) -> Vec<&str> {
+        items.iter().map(|item| item.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_prepare_on_declaration_and_call() {
+        let index = index();
+
+        let items = index
+            .prepare_call_hierarchy(&uri("shapes.strat"), position(14, 4))
+            .unwrap();
+        assert_eq!(names(&items), vec!["total"]);
+        assert_eq!(items[0].kind, SymbolKind::FUNCTION);
+
+        // From a call site in another file
+        let items = index
+            .prepare_call_hierarchy(&uri("main.strat"), position(2, 13))
+            .unwrap();
+        assert_eq!(names(&items), vec!["total"]);
+        assert_eq!(items[0].uri, uri("shapes.strat"));
+
+        assert!(index
+            .prepare_call_hierarchy(&uri("main.strat"), position(1, 0))
+            .is_none());
+    }
+
+    #[test]
+    fn test_incoming_calls_across_files() {
+        let index = index();
+        let total = index
+            .prepare_call_hierarchy(&uri("shapes.strat"), position(14, 4))
+            .unwrap()
+            .remove(0);
+
+        let incoming = index.incoming_calls(&total);
+        assert_eq!(incoming.len(), 1);
+        assert_eq!(incoming[0].from.name, "main");
+        assert_eq!(incoming[0].from.uri, uri("main.strat"));
+        assert_eq!(
+            incoming[0].from_ranges,
+            vec![
+                Range {
+                    start: position(2, 12),
+                    end: position(2, 17)
+                },
+                Range {
+                    start: position(3, 12),
+                    end: position(3, 17)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_outgoing_calls() {
+        let index = index();
+        let main = index
+            .prepare_call_hierarchy(&uri("main.strat"), position(0, 4))
+            .unwrap()
+            .remove(0);
+
+        let outgoing = index.outgoing_calls(&main);
+        // `println` is a builtin and has no declaration to navigate to
+        assert_eq!(outgoing.len(), 1);
+        assert_eq!(outgoing[0].to.name, "total");
+        assert_eq!(outgoing[0].from_ranges.len(), 2);
+
+        let total = outgoing[0].to.clone();
+        let outgoing = index.outgoing_calls(&total);
+        assert_eq!(outgoing.len(), 1);
+        assert_eq!(outgoing[0].to.name, "area");
+        assert_eq!(outgoing[0].to.kind, SymbolKind::METHOD);
+        assert_eq!(outgoing[0].to.detail.as_deref(), Some("Square"));
+    }
+
+    #[test]
+    fn test_type_hierarchy() {
+        let index = index();
+        let shapes = uri("shapes.strat");
+
+        let square = index
+            .prepare_type_hierarchy(&shapes, position(4, 8))
+            .unwrap()
+            .remove(0);
+        assert_eq!(square.name, "Square");
+        assert_eq!(square.kind, SymbolKind::STRUCT);
+
+        let supertypes = index.supertypes(&square);
+        assert_eq!(supertypes.len(), 1);
+        assert_eq!(supertypes[0].name, "Shape");
+        assert_eq!(supertypes[0].kind, SymbolKind::INTERFACE);
+
+        let subtypes = index.subtypes(&supertypes[0]);
+        assert_eq!(subtypes.len(), 1);
+        assert_eq!(subtypes[0].name, "Square");
+
+        // The interface named in an impl header
+        let items = index
+            .prepare_type_hierarchy(&shapes, position(8, 6))
+            .unwrap();
+        assert_eq!(items[0].name, "Shape");
+    }
+}
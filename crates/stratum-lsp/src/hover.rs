@@ -5,12 +5,13 @@
 
 use stratum_core::ast::{
     Block, CallArg, Expr, ExprKind, Function, Item, ItemKind, Literal, Module, Param, Pattern,
-    PatternKind, Stmt, StmtKind, StructDef, TopLevelItem, TopLevelLet,
+    PatternKind, Stmt, StmtKind, StructDef, TopLevelItem, TopLevelLet, Trivia,
 };
+use stratum_core::doc::{DocExtractor, ProjectDoc};
 use stratum_core::lexer::{LineIndex, Span};
 use stratum_core::parser::Parser;
 use stratum_core::types::TypeChecker;
-use tower_lsp::lsp_types::{Hover, HoverContents, MarkupContent, MarkupKind, Position, Range};
+use tower_lsp::lsp_types::{Hover, HoverContents, MarkupContent, MarkupKind, Position, Range, Url};
 
 use crate::cache::CachedData;
 
@@ -24,7 +25,11 @@ pub struct HoverInfo {
 }
 
 /// Compute hover information using cached data
-pub fn compute_hover_cached(data: &CachedData<'_>, position: Position) -> Option<HoverInfo> {
+pub fn compute_hover_cached(
+    uri: &Url,
+    data: &CachedData<'_>,
+    position: Position,
+) -> Option<HoverInfo> {
     // Convert LSP position to byte offset
     let offset = position_to_offset(data.line_index, position)?;
 
@@ -36,7 +41,14 @@ pub fn compute_hover_cached(data: &CachedData<'_>, position: Position) -> Option
     let _ = type_checker.check_module(module);
 
     // Find the node at the position
-    let node_info = find_node_at_position(module, offset, &type_checker)?;
+    let mut node_info = find_node_at_position(module, offset, &type_checker)?;
+    add_item_docs(
+        &mut node_info,
+        module,
+        data.content,
+        data.line_index,
+        Some(uri),
+    );
 
     // Convert span to range
     let range = span_to_range(node_info.span, data.line_index);
@@ -63,7 +75,8 @@ pub fn compute_hover(source: &str, position: Position) -> Option<HoverInfo> {
     let _ = type_checker.check_module(&module);
 
     // Find the node at the position
-    let node_info = find_node_at_position(&module, offset, &type_checker)?;
+    let mut node_info = find_node_at_position(&module, offset, &type_checker)?;
+    add_item_docs(&mut node_info, &module, source, &line_index, None);
 
     // Convert span to range
     let range = span_to_range(node_info.span, &line_index);
@@ -183,32 +196,8 @@ fn find_in_function(func: &Function, offset: u32, checker: &TypeChecker) -> Opti
 
     // Check if hovering over function name
     if span_contains(func.name.span, offset) {
-        let params_str = func
-            .params
-            .iter()
-            .map(|p| {
-                if let Some(ty) = &p.ty {
-                    format!("{}: {}", p.name.name, type_annotation_to_string(ty))
-                } else {
-                    p.name.name.clone()
-                }
-            })
-            .collect::<Vec<_>>()
-            .join(", ");
-
-        let return_str = func
-            .return_type
-            .as_ref()
-            .map(|ty| format!(" -> {}", type_annotation_to_string(ty)))
-            .unwrap_or_default();
-
-        let async_str = if func.is_async { "async " } else { "" };
-
         return Some(NodeInfo {
-            hover_text: format!(
-                "```stratum\n{}fx {}({}){}",
-                async_str, func.name.name, params_str, return_str
-            ) + "\n```",
+            hover_text: function_hover(func),
             span: func.name.span,
         });
     }
@@ -224,6 +213,35 @@ fn find_in_function(func: &Function, offset: u32, checker: &TypeChecker) -> Opti
     find_in_block(&func.body, offset, checker)
 }
 
+/// The declaration of a function, as shown when hovering over its name
+fn function_hover(func: &Function) -> String {
+    let params_str = func
+        .params
+        .iter()
+        .map(|p| {
+            if let Some(ty) = &p.ty {
+                format!("{}: {}", p.name.name, type_annotation_to_string(ty))
+            } else {
+                p.name.name.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let return_str = func
+        .return_type
+        .as_ref()
+        .map(|ty| format!(" -> {}", type_annotation_to_string(ty)))
+        .unwrap_or_default();
+
+    let async_str = if func.is_async { "async " } else { "" };
+
+    format!(
+        "```stratum\n{}fx {}({}){}",
+        async_str, func.name.name, params_str, return_str
+    ) + "\n```"
+}
+
 fn find_in_param(param: &Param, offset: u32) -> Option<NodeInfo> {
     if span_contains(param.name.span, offset) {
         let ty_str = param
@@ -245,25 +263,8 @@ fn find_in_param(param: &Param, offset: u32) -> Option<NodeInfo> {
 
 fn find_in_struct(struct_def: &StructDef, offset: u32) -> Option<NodeInfo> {
     if span_contains(struct_def.name.span, offset) {
-        let type_params = if struct_def.type_params.is_empty() {
-            String::new()
-        } else {
-            format!(
-                "<{}>",
-                struct_def
-                    .type_params
-                    .iter()
-                    .map(|p| p.name.name.clone())
-                    .collect::<Vec<_>>()
-                    .join(", ")
-            )
-        };
-
         return Some(NodeInfo {
-            hover_text: format!(
-                "```stratum\nstruct {}{}\n```",
-                struct_def.name.name, type_params
-            ),
+            hover_text: struct_hover(struct_def),
             span: struct_def.name.span,
         });
     }
@@ -286,6 +287,129 @@ fn find_in_struct(struct_def: &StructDef, offset: u32) -> Option<NodeInfo> {
     None
 }
 
+/// The declaration of a struct, as shown when hovering over its name
+fn struct_hover(struct_def: &StructDef) -> String {
+    let type_params = if struct_def.type_params.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "<{}>",
+            struct_def
+                .type_params
+                .iter()
+                .map(|p| p.name.name.clone())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    };
+    format!(
+        "```stratum\nstruct {}{}\n```",
+        struct_def.name.name, type_params
+    )
+}
+
+/// Show the declaration and doc comment of the top-level item the hovered
+/// identifier names, both where the item is declared and where it is used.
+///
+/// Intra-doc links resolve as in generated documentation, and link to the
+/// line of their target in `uri` when it is given.
+fn add_item_docs(
+    info: &mut NodeInfo,
+    module: &Module,
+    source: &str,
+    line_index: &LineIndex,
+    uri: Option<&Url>,
+) {
+    let Some(name) = source.get(info.span.start as usize..info.span.end as usize) else {
+        return;
+    };
+    let Some((span, declaration, trivia)) = top_level_item(module, name) else {
+        return;
+    };
+    // Identifiers referring to the item rather than parameters, fields or
+    // locals of the same name
+    let is_reference = info
+        .hover_text
+        .starts_with(&format!("```stratum\n{name}: "))
+        && !info.hover_text.contains("\n\n(");
+    if span != info.span && !is_reference {
+        return;
+    }
+
+    info.hover_text = declaration;
+    let Some(doc) = trivia.doc.as_ref().filter(|doc| !doc.is_empty()) else {
+        return;
+    };
+    let mut project = ProjectDoc::new("");
+    project.add_module(DocExtractor::extract(module, ""));
+    let docs = doc.to_markdown(|target| {
+        let symbol = project.resolve_link(target, "")?;
+        let span = definition_span(module, &symbol.name)?;
+        let line = line_index.location(span.start).line;
+        Some(format!("{}#L{line}", uri?))
+    });
+    info.hover_text.push_str("\n\n---\n\n");
+    info.hover_text.push_str(&docs);
+}
+
+/// The name span, declaration and trivia of the top-level item `name`
+fn top_level_item<'a>(module: &'a Module, name: &str) -> Option<(Span, String, &'a Trivia)> {
+    module.top_level.iter().find_map(|top_level| {
+        let TopLevelItem::Item(item) = top_level else {
+            return None;
+        };
+        match &item.kind {
+            ItemKind::Function(func) if func.name.name == name => {
+                Some((func.name.span, function_hover(func), &func.trivia))
+            }
+            ItemKind::Struct(def) if def.name.name == name => {
+                Some((def.name.span, struct_hover(def), &def.trivia))
+            }
+            ItemKind::Enum(def) if def.name.name == name => Some((
+                def.name.span,
+                format!("```stratum\nenum {name}\n```"),
+                &def.trivia,
+            )),
+            ItemKind::Interface(def) if def.name.name == name => Some((
+                def.name.span,
+                format!("```stratum\ninterface {name}\n```"),
+                &def.trivia,
+            )),
+            _ => None,
+        }
+    })
+}
+
+/// Where the item or member `name` is declared in `module`
+fn definition_span(module: &Module, name: &str) -> Option<Span> {
+    if let Some((span, _, _)) = top_level_item(module, name) {
+        return Some(span);
+    }
+    module.top_level.iter().find_map(|top_level| {
+        let TopLevelItem::Item(item) = top_level else {
+            return None;
+        };
+        match &item.kind {
+            ItemKind::Struct(def) => def
+                .fields
+                .iter()
+                .find(|field| field.name.name == name)
+                .map(|field| field.name.span),
+            ItemKind::Enum(def) => def
+                .variants
+                .iter()
+                .find(|variant| variant.name.name == name)
+                .map(|variant| variant.name.span),
+            ItemKind::Impl(def) => def
+                .methods
+                .iter()
+                .find(|method| method.name.name == name)
+                .map(|method| method.name.span),
+            _ => None,
+        }
+    })
+}
+
 fn find_in_top_level_let(
     let_decl: &TopLevelLet,
     offset: u32,
@@ -837,4 +961,71 @@ mod tests {
         assert!(info.contents.contains("x"));
         assert!(info.contents.contains("Int"));
     }
+
+    #[test]
+    fn test_hover_shows_doc_comment() {
+        let source = "/// Add two numbers.\n///\n/// The inverse of [sub].\nfx add(a: Int, b: Int) -> Int { a + b }\n\nfx sub(a: Int, b: Int) -> Int { a - b }\n\nfx main() { add(1, 2) }";
+
+        // On the declaration
+        let info = compute_hover(
+            source,
+            Position {
+                line: 3,
+                character: 4,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            info.contents,
+            "```stratum\nfx add(a: Int, b: Int) -> Int\n```\n\n---\n\nAdd two numbers.\n\nThe inverse of `sub`."
+        );
+
+        // At a call, with the link pointing at the definition
+        let module = Parser::parse_module(source).unwrap();
+        let line_index = LineIndex::new(source);
+        let offset = position_to_offset(
+            &line_index,
+            Position {
+                line: 7,
+                character: 13,
+            },
+        )
+        .unwrap();
+        let mut info = find_node_at_position(&module, offset, &TypeChecker::new()).unwrap();
+        let uri = Url::parse("file:///project/src/main.strat").unwrap();
+        add_item_docs(&mut info, &module, source, &line_index, Some(&uri));
+        assert!(info
+            .hover_text
+            .starts_with("```stratum\nfx add(a: Int, b: Int) -> Int\n```"));
+        assert!(
+            info.hover_text
+                .ends_with("The inverse of [`sub`](file:///project/src/main.strat#L6)."),
+            "{}",
+            info.hover_text
+        );
+    }
+
+    #[test]
+    fn test_hover_without_doc_comment() {
+        let source = "fx add(a: Int) -> Int { a }\nfx main() { let add_one = 1\nadd_one }";
+        let info = compute_hover(
+            source,
+            Position {
+                line: 0,
+                character: 4,
+            },
+        )
+        .unwrap();
+        assert!(!info.contents.contains("---"));
+        // Locals are not mistaken for items
+        let info = compute_hover(
+            source,
+            Position {
+                line: 2,
+                character: 2,
+            },
+        )
+        .unwrap();
+        assert!(!info.contents.contains("fx add"));
+    }
 }
@@ -0,0 +1,520 @@
+//! Inlay hints for the Stratum LSP
+//!
+//! This module provides three kinds of hints, each of which can be turned off
+//! through the `inlayHints` settings:
+//! - Inferred types of `let` and `for` bindings without an annotation
+//! - Inferred types of closure parameters without an annotation
+//! - Parameter names in front of positional arguments at call sites
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use stratum_core::ast::{
+    Block, CallArg, ElseBranch, Expr, ExprKind, Function, ItemKind, Module, Pattern, PatternKind,
+    Stmt, StmtKind, StringPart, TopLevelItem,
+};
+use stratum_core::lexer::{LineIndex, Span};
+use stratum_core::parser::Parser;
+use stratum_core::types::{Type, TypeCheckResult, TypeChecker};
+use tower_lsp::lsp_types::{InlayHint, InlayHintKind, InlayHintLabel, Position, Range};
+
+use crate::cache::CachedData;
+
+/// Which categories of inlay hints to show
+///
+/// Read from the `inlayHints` object of the initialization options or the
+/// `stratum.inlayHints` workspace settings, with camelCase keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct InlayHintConfig {
+    /// Show inferred types of `let` and `for` bindings
+    pub variable_types: bool,
+    /// Show inferred types of closure parameters
+    pub closure_parameter_types: bool,
+    /// Show parameter names at call sites
+    pub parameter_names: bool,
+}
+
+impl Default for InlayHintConfig {
+    fn default() -> Self {
+        Self {
+            variable_types: true,
+            closure_parameter_types: true,
+            parameter_names: true,
+        }
+    }
+}
+
+impl InlayHintConfig {
+    /// Read the configuration from an `inlayHints` settings object
+    ///
+    /// Missing keys keep their defaults; a malformed object yields `None`.
+    pub fn from_settings(settings: &serde_json::Value) -> Option<Self> {
+        let value = settings
+            .get("stratum")
+            .unwrap_or(settings)
+            .get("inlayHints")?;
+        serde_json::from_value(value.clone()).ok()
+    }
+}
+
+/// Compute inlay hints using cached data
+pub fn compute_inlay_hints_cached(
+    data: &CachedData<'_>,
+    range: Range,
+    config: InlayHintConfig,
+) -> Vec<InlayHint> {
+    let (Some(module), Some(types)) = (data.ast(), data.type_result) else {
+        return vec![];
+    };
+    collect_hints(module, types, data.line_index, range, config)
+}
+
+/// Compute inlay hints for a range of the source (non-cached version)
+#[allow(dead_code)] // Standalone API used by tests
+pub fn compute_inlay_hints(source: &str, range: Range, config: InlayHintConfig) -> Vec<InlayHint> {
+    let Ok(module) = Parser::parse_module(source) else {
+        return vec![];
+    };
+    let types = TypeChecker::new().check_module(&module);
+    let line_index = LineIndex::new(source);
+    collect_hints(&module, &types, &line_index, range, config)
+}
+
+fn collect_hints(
+    module: &Module,
+    types: &TypeCheckResult,
+    line_index: &LineIndex,
+    range: Range,
+    config: InlayHintConfig,
+) -> Vec<InlayHint> {
+    let functions = module
+        .items()
+        .into_iter()
+        .filter_map(|item| match &item.kind {
+            ItemKind::Function(func) => Some((func.name.name.as_str(), func)),
+            _ => None,
+        })
+        .collect();
+
+    let mut collector = HintCollector {
+        config,
+        line_index,
+        range,
+        types: types.binding_types.iter().map(|(s, t)| (*s, t)).collect(),
+        functions,
+        hints: Vec::new(),
+    };
+    collector.module(module);
+    collector.hints
+}
+
+/// Walks a module and collects the hints inside the requested range
+struct HintCollector<'a> {
+    config: InlayHintConfig,
+    line_index: &'a LineIndex,
+    range: Range,
+    /// Inferred binding types, keyed by the span of the bound name
+    types: HashMap<Span, &'a Type>,
+    /// Top-level functions, whose parameter names are shown at call sites
+    functions: HashMap<&'a str, &'a Function>,
+    hints: Vec<InlayHint>,
+}
+
+impl HintCollector<'_> {
+    fn module(&mut self, module: &Module) {
+        for top in &module.top_level {
+            match top {
+                TopLevelItem::Item(item) => match &item.kind {
+                    ItemKind::Function(func) => self.block(&func.body),
+                    ItemKind::Impl(imp) => {
+                        for method in &imp.methods {
+                            self.block(&method.body);
+                        }
+                    }
+                    _ => {}
+                },
+                TopLevelItem::Let(decl) => {
+                    if decl.ty.is_none() {
+                        self.binding_hint(&decl.pattern);
+                    }
+                    self.expr(&decl.value);
+                }
+                TopLevelItem::Statement(stmt) => self.stmt(stmt),
+            }
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        for stmt in &block.stmts {
+            self.stmt(stmt);
+        }
+        if let Some(expr) = &block.expr {
+            self.expr(expr);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::Let { pattern, ty, value } => {
+                if ty.is_none() {
+                    self.binding_hint(pattern);
+                }
+                self.expr(value);
+            }
+            StmtKind::Expr(expr) | StmtKind::Throw(expr) | StmtKind::Return(Some(expr)) => {
+                self.expr(expr);
+            }
+            StmtKind::Assign { target, value } | StmtKind::CompoundAssign { target, value, .. } => {
+                self.expr(target);
+                self.expr(value);
+            }
+            StmtKind::For {
+                pattern,
+                iter,
+                body,
+            } => {
+                self.binding_hint(pattern);
+                self.expr(iter);
+                self.block(body);
+            }
+            StmtKind::While { cond, body } => {
+                self.expr(cond);
+                self.block(body);
+            }
+            StmtKind::Loop { body } => self.block(body),
+            StmtKind::TryCatch {
+                try_block,
+                catches,
+                finally,
+            } => {
+                self.block(try_block);
+                for catch in catches {
+                    self.block(&catch.body);
+                }
+                if let Some(finally) = finally {
+                    self.block(finally);
+                }
+            }
+            StmtKind::Return(None) | StmtKind::Break | StmtKind::Continue => {}
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Literal(_)
+            | ExprKind::Ident(_)
+            | ExprKind::Placeholder
+            | ExprKind::ColumnShorthand(_) => {}
+            ExprKind::Binary { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            ExprKind::Unary { expr, .. }
+            | ExprKind::Paren(expr)
+            | ExprKind::Field { expr, .. }
+            | ExprKind::NullSafeField { expr, .. }
+            | ExprKind::Await(expr)
+            | ExprKind::Try(expr)
+            | ExprKind::StateBinding(expr) => self.expr(expr),
+            ExprKind::Call {
+                callee,
+                args,
+                trailing_closure,
+            } => {
+                self.expr(callee);
+                self.parameter_name_hints(callee, args);
+                for arg in args {
+                    match arg {
+                        CallArg::Positional(value) | CallArg::Named { value, .. } => {
+                            self.expr(value);
+                        }
+                    }
+                }
+                if let Some(closure) = trailing_closure {
+                    self.expr(closure);
+                }
+            }
+            ExprKind::Index { expr, index } | ExprKind::NullSafeIndex { expr, index } => {
+                self.expr(expr);
+                self.expr(index);
+            }
+            ExprKind::If {
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                self.expr(cond);
+                self.block(then_branch);
+                match else_branch {
+                    Some(ElseBranch::Block(block)) => self.block(block),
+                    Some(ElseBranch::ElseIf(expr)) => self.expr(expr),
+                    None => {}
+                }
+            }
+            ExprKind::Match { expr, arms } => {
+                self.expr(expr);
+                for arm in arms {
+                    if let Some(guard) = &arm.guard {
+                        self.expr(guard);
+                    }
+                    self.expr(&arm.body);
+                }
+            }
+            ExprKind::Lambda { params, body, .. } => {
+                if self.config.closure_parameter_types {
+                    for param in params.iter().filter(|param| param.ty.is_none()) {
+                        self.type_hint(param.name.span);
+                    }
+                }
+                self.expr(body);
+            }
+            ExprKind::Block(block) => self.block(block),
+            ExprKind::List(items) => {
+                for item in items {
+                    self.expr(item);
+                }
+            }
+            ExprKind::Map(entries) => {
+                for (key, value) in entries {
+                    self.expr(key);
+                    self.expr(value);
+                }
+            }
+            ExprKind::StringInterp { parts } => {
+                for part in parts {
+                    if let StringPart::Expr(expr) = part {
+                        self.expr(expr);
+                    }
+                }
+            }
+            ExprKind::StructInit { fields, .. } => {
+                for value in fields.iter().filter_map(|field| field.value.as_ref()) {
+                    self.expr(value);
+                }
+            }
+            ExprKind::EnumVariant { data, .. } => {
+                if let Some(data) = data {
+                    self.expr(data);
+                }
+            }
+        }
+    }
+
+    /// Type hint for a `let` or `for` binding of a single name
+    fn binding_hint(&mut self, pattern: &Pattern) {
+        if !self.config.variable_types {
+            return;
+        }
+        if let PatternKind::Ident(ident) = &pattern.kind {
+            if ident.name != "_" {
+                self.type_hint(ident.span);
+            }
+        }
+    }
+
+    /// `: Type` after the name at `span`, when its type is fully known
+    fn type_hint(&mut self, span: Span) {
+        let Some(ty) = self.types.get(&span) else {
+            return;
+        };
+        if ty.has_type_vars() || matches!(ty, Type::Any | Type::Error | Type::Namespace(_)) {
+            return;
+        }
+        let label = format!(": {ty}");
+        self.push(span.end, label, InlayHintKind::TYPE);
+    }
+
+    /// `name:` in front of each positional argument of a call to a known function
+    fn parameter_name_hints(&mut self, callee: &Expr, args: &[CallArg]) {
+        if !self.config.parameter_names {
+            return;
+        }
+        let ExprKind::Ident(name) = &callee.kind else {
+            return;
+        };
+        let Some(func) = self.functions.get(name.name.as_str()).copied() else {
+            return;
+        };
+
+        for (param, arg) in func.params.iter().zip(args) {
+            let CallArg::Positional(value) = arg else {
+                // Named arguments already spell out the parameter
+                break;
+            };
+            let param_name = &param.name.name;
+            let redundant = param_name.starts_with('_')
+                || matches!(&value.kind, ExprKind::Ident(ident) if &ident.name == param_name);
+            if !redundant {
+                self.push(
+                    value.span.start,
+                    format!("{param_name}:"),
+                    InlayHintKind::PARAMETER,
+                );
+            }
+        }
+    }
+
+    fn push(&mut self, offset: u32, label: String, kind: InlayHintKind) {
+        let position = offset_to_position(self.line_index, offset);
+        if position < self.range.start || position > self.range.end {
+            return;
+        }
+        let is_parameter = kind == InlayHintKind::PARAMETER;
+        self.hints.push(InlayHint {
+            position,
+            label: InlayHintLabel::String(label),
+            kind: Some(kind),
+            text_edits: None,
+            tooltip: None,
+            padding_left: None,
+            padding_right: is_parameter.then_some(true),
+            data: None,
+        });
+    }
+}
+
+/// Convert a byte offset to an LSP Position
+fn offset_to_position(line_index: &LineIndex, offset: u32) -> Position {
+    let location = line_index.location(offset);
+    Position {
+        line: location.line.saturating_sub(1),
+        character: location.column.saturating_sub(1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn whole_file() -> Range {
+        Range {
+            start: Position {
+                line: 0,
+                character: 0,
+            },
+            end: Position {
+                line: u32::MAX,
+                character: 0,
+            },
+        }
+    }
+
+    fn labels(source: &str, config: InlayHintConfig) -> Vec<(u32, u32, String)> {
+        compute_inlay_hints(source, whole_file(), config)
+            .into_iter()
+            .map(|hint| {
+                let InlayHintLabel::String(label) = hint.label else {
+                    panic!("expected a string label");
+                };
+                (hint.position.line, hint.position.character, label)
+            })
+            .collect()
+    }
+
+    const SOURCE: &str = "fx add(a: Int, b: Int) -> Int {
+    a + b
+}
+
+fx main() {
+    let total = add(1, 2)
+    let b = 3
+    let doubled = [total].map(|n| n * 2)
+    let typed: Int = add(total, b)
+    for item in doubled {
+        println(item);
+    }
+}
+";
+
+    #[test]
+    fn test_variable_type_hints() {
+        let config = InlayHintConfig {
+            closure_parameter_types: false,
+            parameter_names: false,
+            ..InlayHintConfig::default()
+        };
+        assert_eq!(
+            labels(SOURCE, config),
+            vec![
+                (5, 13, ": Int".to_string()),
+                (6, 9, ": Int".to_string()),
+                (7, 15, ": List<Int>".to_string()),
+                (9, 12, ": Int".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_closure_parameter_type_hints() {
+        let config = InlayHintConfig {
+            variable_types: false,
+            parameter_names: false,
+            ..InlayHintConfig::default()
+        };
+        assert_eq!(labels(SOURCE, config), vec![(7, 32, ": Int".to_string())]);
+    }
+
+    #[test]
+    fn test_parameter_name_hints() {
+        let config = InlayHintConfig {
+            variable_types: false,
+            closure_parameter_types: false,
+            ..InlayHintConfig::default()
+        };
+        // `add(total, b)` already names the second parameter
+        assert_eq!(
+            labels(SOURCE, config),
+            vec![
+                (5, 20, "a:".to_string()),
+                (5, 23, "b:".to_string()),
+                (8, 25, "a:".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hints_limited_to_range() {
+        let range = Range {
+            start: Position {
+                line: 6,
+                character: 0,
+            },
+            end: Position {
+                line: 6,
+                character: 20,
+            },
+        };
+        let hints = compute_inlay_hints(SOURCE, range, InlayHintConfig::default());
+        assert_eq!(hints.len(), 1);
+        assert_eq!(
+            hints[0].position,
+            Position {
+                line: 6,
+                character: 9
+            }
+        );
+    }
+
+    #[test]
+    fn test_config_from_settings() {
+        let settings = serde_json::json!({
+            "stratum": { "inlayHints": { "parameterNames": false } }
+        });
+        assert_eq!(
+            InlayHintConfig::from_settings(&settings),
+            Some(InlayHintConfig {
+                parameter_names: false,
+                ..InlayHintConfig::default()
+            })
+        );
+
+        let options = serde_json::json!({ "inlayHints": { "variableTypes": false } });
+        assert!(
+            !InlayHintConfig::from_settings(&options)
+                .unwrap()
+                .variable_types
+        );
+        assert_eq!(InlayHintConfig::from_settings(&serde_json::json!({})), None);
+    }
+}
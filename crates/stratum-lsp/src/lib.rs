@@ -2,22 +2,35 @@
 //!
 //! This crate provides an LSP server for the Stratum programming language,
 //! offering real-time diagnostics, hover information, and other IDE features.
+//! Editors embedding Stratum can also use [`DocumentAnalysis`] in process.
 
+mod analysis;
 mod backend;
 mod cache;
 mod code_actions;
+mod code_lens;
 mod completions;
 mod definition;
 mod diagnostics;
 mod document_symbols;
 mod formatting;
+mod hierarchy;
 mod hover;
+mod inlay_hints;
+mod project;
+mod ranges;
 mod references;
 mod rename;
+mod semantic_tokens;
 mod signature_help;
+mod snippets;
 mod workspace_symbols;
 
+pub use analysis::DocumentAnalysis;
 pub use backend::StratumLanguageServer;
+pub use diagnostics::DiagnosticsConfig;
+pub use hover::HoverInfo;
+pub use tower_lsp::lsp_types;
 
 use tower_lsp::{LspService, Server};
 
@@ -0,0 +1,308 @@
+//! Package-wide analysis for Stratum LSP
+//!
+//! A document inside a Stratum package (a directory with a `stratum.toml`)
+//! is type checked together with every source file of the package, so it
+//! can use the functions and types declared in the others. When one file
+//! changes, the rest of the package is checked again and diagnostics are
+//! published for every file whose diagnostics changed, open or not; this
+//! is how changing a function signature surfaces at its callers.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::Arc;
+
+use stratum_core::ast::Module;
+use stratum_core::parser::Parser;
+use stratum_pkg::PackageLayout;
+use tower_lsp::lsp_types::{Diagnostic, Url};
+
+use crate::cache::{DocumentCache, ParseResult};
+use crate::diagnostics::{self, DiagnosticsConfig};
+use crate::workspace_symbols::WorkspaceSymbolIndex;
+
+/// A source file of the package as it is on disk
+#[derive(Debug)]
+struct SourceFile {
+    content: String,
+    /// `None` if the file does not parse
+    module: Option<Arc<Module>>,
+}
+
+impl SourceFile {
+    fn read(path: &Path) -> Option<Self> {
+        let content = std::fs::read_to_string(path).ok()?;
+        let module = Parser::parse_module(&content).ok().map(Arc::new);
+        Some(Self { content, module })
+    }
+}
+
+/// A Stratum package with at least one open document
+#[derive(Debug)]
+pub struct Project {
+    /// Where the package and its sources are
+    layout: PackageLayout,
+    /// Every source file under `src/`, as last read from disk
+    sources: BTreeMap<Url, SourceFile>,
+    /// The diagnostics last published for each file
+    published: HashMap<Url, Vec<Diagnostic>>,
+}
+
+impl Project {
+    /// Load the package containing the file at `uri`, if there is one
+    pub fn load(uri: &Url) -> Option<Self> {
+        let path = uri.to_file_path().ok()?;
+        let layout = PackageLayout::find_root(path.parent()?).ok()?;
+
+        let mut sources = BTreeMap::new();
+        for file in layout.source_files().ok()? {
+            if let (Ok(uri), Some(source)) = (Url::from_file_path(&file), SourceFile::read(&file)) {
+                sources.insert(uri, source);
+            }
+        }
+
+        Some(Self {
+            layout,
+            sources,
+            published: HashMap::new(),
+        })
+    }
+
+    /// Whether the file at `uri` lies inside the package
+    pub fn contains(&self, uri: &Url) -> bool {
+        uri.to_file_path()
+            .is_ok_and(|path| path.starts_with(&self.layout.root))
+    }
+
+    /// Read a source file again after it was saved or closed, picking up
+    /// files created since the package was loaded
+    pub fn reload(&mut self, uri: &Url) {
+        let Ok(path) = uri.to_file_path() else {
+            return;
+        };
+        if self.layout.module_path(&path).is_none() {
+            return;
+        }
+
+        match SourceFile::read(&path) {
+            Some(source) => {
+                self.sources.insert(uri.clone(), source);
+            }
+            None => {
+                self.sources.remove(uri);
+            }
+        }
+    }
+
+    /// Check every source file and open document of the package
+    ///
+    /// Open documents are checked from their editor contents, which also
+    /// stand in for their file on disk when checking the others. Returns
+    /// the diagnostics that differ from the ones last published, plus
+    /// those of `always`.
+    pub fn check(
+        &mut self,
+        documents: &mut HashMap<Url, DocumentCache>,
+        always: &Url,
+        config: DiagnosticsConfig,
+    ) -> Vec<(Url, Vec<Diagnostic>)> {
+        let modules = self.modules(documents);
+        let siblings_of = |uri: &Url| -> Vec<Arc<Module>> {
+            modules
+                .iter()
+                .filter(|(other, _)| *other != uri)
+                .map(|(_, module)| Arc::clone(module))
+                .collect()
+        };
+
+        let mut results = Vec::new();
+        for (uri, cache) in documents.iter_mut() {
+            if self.contains(uri) {
+                cache.set_siblings(siblings_of(uri));
+                let data = cache.get_all_cached();
+                results.push((
+                    uri.clone(),
+                    diagnostics::compute_diagnostics_cached(&data, config),
+                ));
+            }
+        }
+        for (uri, source) in &self.sources {
+            if !documents.contains_key(uri) {
+                let siblings = siblings_of(uri);
+                let siblings: Vec<&Module> = siblings.iter().map(AsRef::as_ref).collect();
+                let diagnostics =
+                    diagnostics::compute_package_diagnostics(&source.content, &siblings, config);
+                results.push((uri.clone(), diagnostics));
+            }
+        }
+
+        results
+            .into_iter()
+            .filter(|(uri, diagnostics)| {
+                let changed = self.published.get(uri) != Some(diagnostics);
+                if changed {
+                    self.published.insert(uri.clone(), diagnostics.clone());
+                }
+                changed || uri == always
+            })
+            .collect()
+    }
+
+    /// Index the top-level declarations of every source file by module
+    /// path, for completions that add the import they need
+    pub fn symbol_index(
+        &self,
+        documents: &mut HashMap<Url, DocumentCache>,
+    ) -> WorkspaceSymbolIndex {
+        let modules = self.modules(documents);
+        let modules = modules.iter().filter_map(|(uri, module)| {
+            let path = uri.to_file_path().ok()?;
+            Some((self.layout.module_path(&path)?, module.as_ref()))
+        });
+        WorkspaceSymbolIndex::build(modules)
+    }
+
+    /// The module path the file at `uri` is imported by, if it is a source
+    /// file of the package
+    pub fn module_path(&self, uri: &Url) -> Option<String> {
+        self.layout.module_path(&uri.to_file_path().ok()?)
+    }
+
+    /// The parsed module of every source file, taken from the editor for
+    /// open documents. Files that do not parse are left out.
+    fn modules(&self, documents: &mut HashMap<Url, DocumentCache>) -> BTreeMap<Url, Arc<Module>> {
+        let mut modules: BTreeMap<Url, Arc<Module>> = self
+            .sources
+            .iter()
+            .filter_map(|(uri, source)| Some((uri.clone(), Arc::clone(source.module.as_ref()?))))
+            .collect();
+        for (uri, cache) in documents.iter_mut() {
+            if self.sources.contains_key(uri) {
+                match cache.get_or_parse() {
+                    ParseResult::Ok(module) => {
+                        modules.insert(uri.clone(), Arc::clone(module));
+                    }
+                    ParseResult::Err(_) => {
+                        modules.remove(uri);
+                    }
+                }
+            }
+        }
+        modules
+    }
+
+    /// Forget what was published for a file that is no longer tracked
+    pub fn forget(&mut self, uri: &Url) {
+        self.published.remove(uri);
+    }
+
+    /// Whether the file at `uri` is one of the package's source files
+    pub fn is_source(&self, uri: &Url) -> bool {
+        self.sources.contains_key(uri)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn package(files: &[(&str, &str)]) -> TempDir {
+        let tmp = TempDir::new().unwrap();
+        fs::write(
+            tmp.path().join("stratum.toml"),
+            "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        for (path, content) in files {
+            let path = tmp.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        tmp
+    }
+
+    fn uri(tmp: &TempDir, path: &str) -> Url {
+        Url::from_file_path(tmp.path().join(path)).unwrap()
+    }
+
+    fn open(documents: &mut HashMap<Url, DocumentCache>, uri: &Url, content: &str) {
+        documents.insert(uri.clone(), DocumentCache::new(content.to_string(), 1));
+    }
+
+    const LIB: &str = "fx greet(name: String) -> String {\n    name\n}\n";
+    const MAIN: &str = "fx main() {\n    println(greet(\"world\"));\n}\n";
+
+    #[test]
+    fn test_checks_files_against_the_package() {
+        let tmp = package(&[("src/lib.strat", LIB), ("src/main.strat", MAIN)]);
+        let main = uri(&tmp, "src/main.strat");
+        let mut project = Project::load(&main).unwrap();
+        assert!(project.is_source(&uri(&tmp, "src/lib.strat")));
+
+        let mut documents = HashMap::new();
+        open(&mut documents, &main, MAIN);
+        let results = project.check(&mut documents, &main, DiagnosticsConfig::default());
+
+        // `greet` comes from lib.strat, so neither file has errors
+        assert_eq!(results.len(), 2);
+        assert!(results
+            .iter()
+            .all(|(_, diagnostics)| diagnostics.is_empty()));
+    }
+
+    #[test]
+    fn test_edit_reports_errors_in_other_files() {
+        let tmp = package(&[("src/lib.strat", LIB), ("src/main.strat", MAIN)]);
+        let lib = uri(&tmp, "src/lib.strat");
+        let main = uri(&tmp, "src/main.strat");
+        let mut project = Project::load(&lib).unwrap();
+
+        let mut documents = HashMap::new();
+        open(&mut documents, &lib, LIB);
+        project.check(&mut documents, &lib, DiagnosticsConfig::default());
+
+        // Changing the signature breaks the call in the closed main.strat
+        let changed = "fx greet(name: String, times: Int) -> String {\n    name\n}\n";
+        open(&mut documents, &lib, changed);
+        let results = project.check(&mut documents, &lib, DiagnosticsConfig::default());
+        let (_, main_diagnostics) = results
+            .iter()
+            .find(|(uri, _)| *uri == main)
+            .expect("main.strat should be republished");
+        assert_eq!(main_diagnostics.len(), 1);
+        assert_eq!(main_diagnostics[0].range.start.line, 1);
+
+        // Nothing changed since, so only the edited file is republished
+        let results = project.check(&mut documents, &lib, DiagnosticsConfig::default());
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, lib);
+    }
+
+    #[test]
+    fn test_reload_picks_up_new_files() {
+        let tmp = package(&[("src/main.strat", MAIN)]);
+        let main = uri(&tmp, "src/main.strat");
+        let mut project = Project::load(&main).unwrap();
+
+        fs::create_dir_all(tmp.path().join("src/util")).unwrap();
+        fs::write(tmp.path().join("src/util/greet.strat"), LIB).unwrap();
+        let greet = uri(&tmp, "src/util/greet.strat");
+        project.reload(&greet);
+        assert!(project.is_source(&greet));
+
+        let mut documents = HashMap::new();
+        open(&mut documents, &main, MAIN);
+        let results = project.check(&mut documents, &main, DiagnosticsConfig::default());
+        assert!(results
+            .iter()
+            .all(|(_, diagnostics)| diagnostics.is_empty()));
+    }
+
+    #[test]
+    fn test_files_outside_a_package() {
+        let tmp = TempDir::new().unwrap();
+        fs::write(tmp.path().join("script.strat"), MAIN).unwrap();
+        assert!(Project::load(&uri(&tmp, "script.strat")).is_none());
+    }
+}
@@ -0,0 +1,642 @@
+//! Folding ranges and selection ranges for Stratum LSP
+//!
+//! Both come from the spans of the syntax tree. Folding ranges cover
+//! declarations, blocks, `match` expressions, multi-line literals, runs of
+//! imports and comment blocks. Selection ranges expand the selection from
+//! the innermost node at the cursor outwards, one enclosing node at a time.
+
+use stratum_core::ast::{
+    Block, CallArg, ElseBranch, EnumDef, Expr, ExprKind, Function, ImplDef, InterfaceDef, Item,
+    ItemKind, Literal, Module, Param, Pattern, PatternKind, Stmt, StmtKind, StringPart, StructDef,
+    TopLevelItem,
+};
+use stratum_core::lexer::{Lexer, LineIndex, Span, TokenKind};
+use stratum_core::parser::Parser;
+use tower_lsp::lsp_types::{FoldingRange, FoldingRangeKind, Position, Range, SelectionRange};
+
+use crate::cache::CachedData;
+
+/// Compute folding ranges using cached data
+pub fn compute_folding_ranges_cached(data: &CachedData<'_>) -> Vec<FoldingRange> {
+    folding_ranges(data.content, data.ast().map(AsRef::as_ref), data.line_index)
+}
+
+/// Compute folding ranges for a document (non-cached)
+#[allow(dead_code)] // Standalone API used by tests
+pub fn compute_folding_ranges(source: &str) -> Vec<FoldingRange> {
+    let module = Parser::parse_module(source).ok();
+    folding_ranges(source, module.as_ref(), &LineIndex::new(source))
+}
+
+/// Compute selection ranges using cached data
+pub fn compute_selection_ranges_cached(
+    data: &CachedData<'_>,
+    positions: &[Position],
+) -> Vec<SelectionRange> {
+    let nodes = data
+        .ast()
+        .map(|module| NodeCollector::collect(module))
+        .unwrap_or_default();
+    positions
+        .iter()
+        .map(|&position| selection_range(data.content, &nodes, data.line_index, position))
+        .collect()
+}
+
+/// Compute selection ranges for a document (non-cached)
+#[allow(dead_code)] // Standalone API used by tests
+pub fn compute_selection_ranges(source: &str, positions: &[Position]) -> Vec<SelectionRange> {
+    let line_index = LineIndex::new(source);
+    let nodes = Parser::parse_module(source)
+        .map(|module| NodeCollector::collect(&module))
+        .unwrap_or_default();
+    positions
+        .iter()
+        .map(|&position| selection_range(source, &nodes, &line_index, position))
+        .collect()
+}
+
+fn folding_ranges(
+    source: &str,
+    module: Option<&Module>,
+    line_index: &LineIndex,
+) -> Vec<FoldingRange> {
+    let lines = |span: Span| {
+        let span = trim_span(source, span);
+        (
+            line_index.location(span.start).line.saturating_sub(1),
+            line_index.location(span.end).line.saturating_sub(1),
+        )
+    };
+    let (tokens, _) = Lexer::tokenize(source);
+    let mut ranges = Vec::new();
+
+    // Syntax nodes fold up to the line holding their closing delimiter
+    if let Some(module) = module {
+        for node in NodeCollector::collect(module) {
+            if node.folds {
+                let (start, end) = lines(node.span);
+                if end > start + 1 {
+                    ranges.push(folding_range(start, end - 1, None));
+                }
+            }
+        }
+
+        // Consecutive imports fold together
+        let mut imports: Option<(u32, u32)> = None;
+        for item in &module.top_level {
+            let import = match item {
+                TopLevelItem::Item(Item {
+                    kind: ItemKind::Import(import),
+                    ..
+                }) => {
+                    // The span runs up to the next token, which may be a
+                    // comment on a later line
+                    let end = tokens
+                        .iter()
+                        .filter(|token| !token.kind.is_trivia())
+                        .map(|token| token.span.end)
+                        .take_while(|&end| end <= import.span.end)
+                        .last()
+                        .unwrap_or(import.span.end);
+                    Some(lines(Span::new(import.span.start, end)))
+                }
+                _ => None,
+            };
+            imports = match (imports, import) {
+                (Some((start, _)), Some((_, end))) => Some((start, end)),
+                (None, Some(run)) => Some(run),
+                (Some((start, end)), None) => {
+                    ranges.push(folding_range(start, end, Some(FoldingRangeKind::Imports)));
+                    None
+                }
+                (None, None) => None,
+            };
+        }
+        if let Some((start, end)) = imports {
+            ranges.push(folding_range(start, end, Some(FoldingRangeKind::Imports)));
+        }
+    }
+
+    // Block comments and runs of line comments on consecutive lines
+    let mut comments: Option<(u32, u32)> = None;
+    for token in &tokens {
+        match token.kind {
+            TokenKind::LineComment => {
+                let (line, _) = lines(token.span);
+                comments = match comments {
+                    Some((start, end)) if line == end + 1 => Some((start, line)),
+                    previous => {
+                        if let Some((start, end)) = previous {
+                            ranges.push(folding_range(start, end, Some(FoldingRangeKind::Comment)));
+                        }
+                        Some((line, line))
+                    }
+                };
+            }
+            TokenKind::BlockComment => {
+                let (start, end) = lines(token.span);
+                ranges.push(folding_range(start, end, Some(FoldingRangeKind::Comment)));
+            }
+            TokenKind::Newline => {}
+            _ => {
+                // Code between comments ends the run
+                if let Some((start, end)) = comments.take() {
+                    ranges.push(folding_range(start, end, Some(FoldingRangeKind::Comment)));
+                }
+            }
+        }
+    }
+    if let Some((start, end)) = comments {
+        ranges.push(folding_range(start, end, Some(FoldingRangeKind::Comment)));
+    }
+
+    // A single line has nothing to fold; of ranges starting on the same
+    // line, the outermost wins
+    ranges.retain(|range| range.end_line > range.start_line);
+    ranges.sort_by(|a, b| {
+        a.start_line
+            .cmp(&b.start_line)
+            .then(b.end_line.cmp(&a.end_line))
+    });
+    ranges.dedup_by_key(|range| range.start_line);
+    ranges
+}
+
+fn folding_range(start_line: u32, end_line: u32, kind: Option<FoldingRangeKind>) -> FoldingRange {
+    FoldingRange {
+        start_line,
+        end_line,
+        kind,
+        ..Default::default()
+    }
+}
+
+/// The chain of nodes containing `position`, innermost first
+fn selection_range(
+    source: &str,
+    nodes: &[Node],
+    line_index: &LineIndex,
+    position: Position,
+) -> SelectionRange {
+    let empty = SelectionRange {
+        range: Range {
+            start: position,
+            end: position,
+        },
+        parent: None,
+    };
+    let Some(offset) = line_index
+        .line_start(position.line as usize)
+        .map(|line_start| line_start + position.character)
+    else {
+        return empty;
+    };
+
+    let mut spans: Vec<Span> = nodes
+        .iter()
+        .map(|node| trim_span(source, node.span))
+        .filter(|span| span.start <= offset && offset <= span.end)
+        .collect();
+    // Outermost first, so each range can take the previous one as parent
+    spans.sort_by(|a, b| a.start.cmp(&b.start).then(b.end.cmp(&a.end)));
+    spans.dedup();
+
+    spans
+        .into_iter()
+        .fold(None, |parent, span| {
+            Some(SelectionRange {
+                range: span_to_range(span, line_index),
+                parent: parent.map(Box::new),
+            })
+        })
+        .unwrap_or(empty)
+}
+
+/// A span without the whitespace some nodes carry at their end
+fn trim_span(source: &str, span: Span) -> Span {
+    let Some(text) = source.get(span.start as usize..span.end as usize) else {
+        return span;
+    };
+    let trimmed = u32::try_from(text.trim_end().len()).unwrap_or(span.end - span.start);
+    Span::new(span.start, span.start + trimmed)
+}
+
+/// Convert a span to an LSP range
+fn span_to_range(span: Span, line_index: &LineIndex) -> Range {
+    let start = line_index.location(span.start);
+    let end = line_index.location(span.end);
+    Range {
+        start: Position {
+            line: start.line.saturating_sub(1),
+            character: start.column.saturating_sub(1),
+        },
+        end: Position {
+            line: end.line.saturating_sub(1),
+            character: end.column.saturating_sub(1),
+        },
+    }
+}
+
+/// A syntax node
+struct Node {
+    span: Span,
+    /// Whether the node gets a folding range when it spans several lines
+    folds: bool,
+}
+
+/// Collects the span of every node in a module
+#[derive(Default)]
+struct NodeCollector {
+    nodes: Vec<Node>,
+}
+
+impl NodeCollector {
+    fn collect(module: &Module) -> Vec<Node> {
+        let mut collector = Self::default();
+        for item in &module.top_level {
+            match item {
+                TopLevelItem::Item(item) => collector.item(item),
+                TopLevelItem::Let(let_decl) => {
+                    collector.leaf(let_decl.span);
+                    collector.pattern(&let_decl.pattern);
+                    if let Some(ty) = &let_decl.ty {
+                        collector.leaf(ty.span);
+                    }
+                    collector.expr(&let_decl.value);
+                }
+                TopLevelItem::Statement(stmt) => collector.stmt(stmt),
+            }
+        }
+        collector.nodes
+    }
+
+    fn leaf(&mut self, span: Span) {
+        self.nodes.push(Node { span, folds: false });
+    }
+
+    fn fold(&mut self, span: Span) {
+        self.nodes.push(Node { span, folds: true });
+    }
+
+    fn item(&mut self, item: &Item) {
+        match &item.kind {
+            ItemKind::Function(func) => self.function(func),
+            ItemKind::Struct(struct_def) => self.struct_def(struct_def),
+            ItemKind::Enum(enum_def) => self.enum_def(enum_def),
+            ItemKind::Interface(interface_def) => self.interface_def(interface_def),
+            ItemKind::Impl(impl_def) => self.impl_def(impl_def),
+            ItemKind::Import(import) => self.leaf(import.span),
+        }
+    }
+
+    fn function(&mut self, func: &Function) {
+        self.fold(func.span);
+        self.leaf(func.name.span);
+        self.params(&func.params);
+        if let Some(ty) = &func.return_type {
+            self.leaf(ty.span);
+        }
+        self.block(&func.body);
+    }
+
+    fn params(&mut self, params: &[Param]) {
+        for param in params {
+            self.leaf(param.span);
+            self.leaf(param.name.span);
+            if let Some(ty) = &param.ty {
+                self.leaf(ty.span);
+            }
+            if let Some(default) = &param.default {
+                self.expr(default);
+            }
+        }
+    }
+
+    fn struct_def(&mut self, struct_def: &StructDef) {
+        self.fold(struct_def.span);
+        self.leaf(struct_def.name.span);
+        for field in &struct_def.fields {
+            self.leaf(field.span);
+            self.leaf(field.name.span);
+            self.leaf(field.ty.span);
+        }
+    }
+
+    fn enum_def(&mut self, enum_def: &EnumDef) {
+        self.fold(enum_def.span);
+        self.leaf(enum_def.name.span);
+        for variant in &enum_def.variants {
+            self.leaf(variant.span);
+            self.leaf(variant.name.span);
+        }
+    }
+
+    fn interface_def(&mut self, interface_def: &InterfaceDef) {
+        self.fold(interface_def.span);
+        self.leaf(interface_def.name.span);
+        for method in &interface_def.methods {
+            self.fold(method.span);
+            self.leaf(method.name.span);
+            self.params(&method.params);
+            if let Some(body) = &method.default_body {
+                self.block(body);
+            }
+        }
+    }
+
+    fn impl_def(&mut self, impl_def: &ImplDef) {
+        self.fold(impl_def.span);
+        self.leaf(impl_def.target.span);
+        if let Some(interface) = &impl_def.interface {
+            self.leaf(interface.span);
+        }
+        for method in &impl_def.methods {
+            self.function(method);
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        self.fold(block.span);
+        for stmt in &block.stmts {
+            self.stmt(stmt);
+        }
+        if let Some(expr) = &block.expr {
+            self.expr(expr);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        self.leaf(stmt.span);
+        match &stmt.kind {
+            StmtKind::Let { pattern, ty, value } => {
+                self.pattern(pattern);
+                if let Some(ty) = ty {
+                    self.leaf(ty.span);
+                }
+                self.expr(value);
+            }
+            StmtKind::Expr(expr) | StmtKind::Throw(expr) | StmtKind::Return(Some(expr)) => {
+                self.expr(expr);
+            }
+            StmtKind::Assign { target, value } | StmtKind::CompoundAssign { target, value, .. } => {
+                self.expr(target);
+                self.expr(value);
+            }
+            StmtKind::For {
+                pattern,
+                iter,
+                body,
+            } => {
+                self.pattern(pattern);
+                self.expr(iter);
+                self.block(body);
+            }
+            StmtKind::While { cond, body } => {
+                self.expr(cond);
+                self.block(body);
+            }
+            StmtKind::Loop { body } => self.block(body),
+            StmtKind::TryCatch {
+                try_block,
+                catches,
+                finally,
+            } => {
+                self.block(try_block);
+                for catch in catches {
+                    self.leaf(catch.span);
+                    self.block(&catch.body);
+                }
+                if let Some(finally) = finally {
+                    self.block(finally);
+                }
+            }
+            StmtKind::Return(None) | StmtKind::Break | StmtKind::Continue => {}
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Literal(Literal::String(_))
+            | ExprKind::List(_)
+            | ExprKind::Map(_)
+            | ExprKind::StructInit { .. }
+            | ExprKind::StringInterp { .. }
+            | ExprKind::Match { .. }
+            | ExprKind::Block(_) => self.fold(expr.span),
+            _ => self.leaf(expr.span),
+        }
+
+        match &expr.kind {
+            ExprKind::Literal(_)
+            | ExprKind::Ident(_)
+            | ExprKind::Placeholder
+            | ExprKind::ColumnShorthand(_) => {}
+            ExprKind::Binary { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            ExprKind::Field { expr, field } | ExprKind::NullSafeField { expr, field } => {
+                self.expr(expr);
+                self.leaf(field.span);
+            }
+            ExprKind::Unary { expr, .. }
+            | ExprKind::Paren(expr)
+            | ExprKind::Await(expr)
+            | ExprKind::Try(expr)
+            | ExprKind::StateBinding(expr) => self.expr(expr),
+            ExprKind::Call {
+                callee,
+                args,
+                trailing_closure,
+            } => {
+                self.expr(callee);
+                for arg in args {
+                    match arg {
+                        CallArg::Positional(value) => self.expr(value),
+                        CallArg::Named { name, value, .. } => {
+                            self.leaf(name.span);
+                            self.expr(value);
+                        }
+                    }
+                }
+                if let Some(closure) = trailing_closure {
+                    self.expr(closure);
+                }
+            }
+            ExprKind::Index { expr, index } | ExprKind::NullSafeIndex { expr, index } => {
+                self.expr(expr);
+                self.expr(index);
+            }
+            ExprKind::If {
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                self.expr(cond);
+                self.block(then_branch);
+                match else_branch {
+                    Some(ElseBranch::Block(block)) => self.block(block),
+                    Some(ElseBranch::ElseIf(expr)) => self.expr(expr),
+                    None => {}
+                }
+            }
+            ExprKind::Match { expr, arms } => {
+                self.expr(expr);
+                for arm in arms {
+                    self.leaf(arm.span);
+                    self.pattern(&arm.pattern);
+                    if let Some(guard) = &arm.guard {
+                        self.expr(guard);
+                    }
+                    self.expr(&arm.body);
+                }
+            }
+            ExprKind::Lambda { params, body, .. } => {
+                self.params(params);
+                self.expr(body);
+            }
+            ExprKind::Block(block) => self.block(block),
+            ExprKind::List(items) => {
+                for item in items {
+                    self.expr(item);
+                }
+            }
+            ExprKind::Map(entries) => {
+                for (key, value) in entries {
+                    self.expr(key);
+                    self.expr(value);
+                }
+            }
+            ExprKind::StringInterp { parts } => {
+                for part in parts {
+                    if let StringPart::Expr(expr) = part {
+                        self.expr(expr);
+                    }
+                }
+            }
+            ExprKind::StructInit { name, fields } => {
+                self.leaf(name.span);
+                for field in fields {
+                    self.leaf(field.span);
+                    self.leaf(field.name.span);
+                    if let Some(value) = &field.value {
+                        self.expr(value);
+                    }
+                }
+            }
+            ExprKind::EnumVariant { data, .. } => {
+                if let Some(data) = data {
+                    self.expr(data);
+                }
+            }
+        }
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) {
+        self.leaf(pattern.span);
+        match &pattern.kind {
+            PatternKind::Wildcard | PatternKind::Ident(_) | PatternKind::Literal(_) => {}
+            PatternKind::Variant { data, .. } => {
+                if let Some(data) = data {
+                    self.pattern(data);
+                }
+            }
+            PatternKind::Struct { fields, .. } => {
+                for field in fields {
+                    self.leaf(field.span);
+                    if let Some(pattern) = &field.pattern {
+                        self.pattern(pattern);
+                    }
+                }
+            }
+            PatternKind::List { elements, rest } => {
+                for element in elements {
+                    self.pattern(element);
+                }
+                if let Some(rest) = rest {
+                    self.pattern(rest);
+                }
+            }
+            PatternKind::Or(patterns) => {
+                for pattern in patterns {
+                    self.pattern(pattern);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn folds(source: &str) -> Vec<(u32, u32, Option<FoldingRangeKind>)> {
+        compute_folding_ranges(source)
+            .into_iter()
+            .map(|range| (range.start_line, range.end_line, range.kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_folds_functions_and_blocks() {
+        let source = "fx main() {\n    let x = 1;\n    if x > 0 {\n        println(x);\n        println(x);\n    }\n}\n";
+        assert_eq!(folds(source), [(0, 5, None), (2, 4, None)]);
+    }
+
+    #[test]
+    fn test_folds_multi_line_literals() {
+        let source = "fx main() {\n    let xs = [\n        1,\n        2\n    ];\n    let p = Point {\n        x: 1,\n        y: 2\n    };\n}\n";
+        assert_eq!(folds(source), [(0, 8, None), (1, 3, None), (5, 7, None)]);
+    }
+
+    #[test]
+    fn test_folds_imports_and_comments() {
+        let source = "import io\nimport json\n\n// First line\n// second line\nfx main() {\n    /* a\n       b */\n    println(1);\n}\n";
+        assert_eq!(
+            folds(source),
+            [
+                (0, 1, Some(FoldingRangeKind::Imports)),
+                (3, 4, Some(FoldingRangeKind::Comment)),
+                (5, 8, None),
+                (6, 7, Some(FoldingRangeKind::Comment)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_selection_expands_outwards() {
+        let source = "fx main() {\n    println(add(1, 2));\n}\n";
+        // On the `1`
+        let selection = &compute_selection_ranges(source, &[Position::new(1, 16)])[0];
+
+        let mut ranges = Vec::new();
+        let mut current = Some(selection);
+        while let Some(range) = current {
+            let start = range.range.start;
+            let end = range.range.end;
+            ranges.push(((start.line, start.character), (end.line, end.character)));
+            current = range.parent.as_deref();
+        }
+        assert_eq!(
+            ranges,
+            [
+                ((1, 16), (1, 17)), // 1
+                ((1, 12), (1, 21)), // add(1, 2)
+                ((1, 4), (1, 22)),  // println(add(1, 2))
+                ((1, 4), (1, 23)),  // the statement
+                ((0, 10), (2, 1)),  // the body
+                ((0, 0), (2, 1)),   // the function
+            ]
+        );
+    }
+
+    #[test]
+    fn test_selection_outside_any_node() {
+        let source = "\n\nfx main() {}\n";
+        let selection = &compute_selection_ranges(source, &[Position::new(0, 0)])[0];
+        assert_eq!(selection.range.start, Position::new(0, 0));
+        assert_eq!(selection.range.end, Position::new(0, 0));
+        assert!(selection.parent.is_none());
+    }
+}
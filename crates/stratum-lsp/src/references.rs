@@ -115,6 +115,19 @@ pub fn compute_references(
     )
 }
 
+/// Find every use of `name` in a module, ignoring scopes
+///
+/// Used for names declared in another document, where no definition is
+/// available to scope the search.
+pub fn find_name_references(
+    module: &Module,
+    name: &str,
+    uri: &Url,
+    line_index: &LineIndex,
+) -> Vec<Location> {
+    collect_all_references(module, name, None, uri, line_index, false)
+}
+
 /// Collect all references to a symbol in the module
 fn collect_all_references(
     module: &Module,
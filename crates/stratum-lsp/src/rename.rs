@@ -199,6 +199,7 @@ fn is_keyword(name: &str) -> bool {
     matches!(
         name,
         "fx" | "let"
+            | "const"
             | "if"
             | "else"
             | "for"
@@ -0,0 +1,876 @@
+//! Semantic tokens for the Stratum LSP
+//!
+//! Classifies identifiers by what they refer to, so editors can highlight
+//! functions, types, enum variants, parameters, constants and namespaces
+//! beyond what the TextMate grammar can tell from syntax alone. Names
+//! defined in the document are classified through the symbol index; names
+//! the type checker resolved to a builtin are marked as library names.
+
+use std::collections::{HashMap, HashSet};
+
+use stratum_core::ast::{
+    Block, CallArg, ElseBranch, EnumVariantData, Expr, ExprKind, Function, Ident, ImportKind,
+    ItemKind, Module, Param, Pattern, PatternKind, Stmt, StmtKind, StringPart, TopLevelItem,
+    TypeAnnotation, TypeKind, TypeParam,
+};
+use stratum_core::lexer::{LineIndex, Span};
+use stratum_core::parser::Parser;
+use stratum_core::types::{ResolvedName, Type, TypeChecker};
+use tower_lsp::lsp_types::{
+    SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokensEdit,
+    SemanticTokensLegend,
+};
+
+use crate::cache::CachedData;
+use crate::definition::{SymbolIndex, SymbolKind};
+
+/// Token types, in legend order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TokenType {
+    Namespace,
+    Type,
+    Struct,
+    Enum,
+    Interface,
+    EnumMember,
+    TypeParameter,
+    Function,
+    Method,
+    Parameter,
+    Variable,
+    Property,
+}
+
+const TOKEN_TYPES: [SemanticTokenType; 12] = [
+    SemanticTokenType::NAMESPACE,
+    SemanticTokenType::TYPE,
+    SemanticTokenType::STRUCT,
+    SemanticTokenType::ENUM,
+    SemanticTokenType::INTERFACE,
+    SemanticTokenType::ENUM_MEMBER,
+    SemanticTokenType::TYPE_PARAMETER,
+    SemanticTokenType::FUNCTION,
+    SemanticTokenType::METHOD,
+    SemanticTokenType::PARAMETER,
+    SemanticTokenType::VARIABLE,
+    SemanticTokenType::PROPERTY,
+];
+
+const TOKEN_MODIFIERS: [SemanticTokenModifier; 3] = [
+    SemanticTokenModifier::DECLARATION,
+    SemanticTokenModifier::READONLY,
+    SemanticTokenModifier::DEFAULT_LIBRARY,
+];
+
+/// Modifier bits, matching the order of `TOKEN_MODIFIERS`
+const DECLARATION: u32 = 1;
+const READONLY: u32 = 1 << 1;
+const DEFAULT_LIBRARY: u32 = 1 << 2;
+
+/// The legend advertised in the server capabilities
+pub fn legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: TOKEN_TYPES.to_vec(),
+        token_modifiers: TOKEN_MODIFIERS.to_vec(),
+    }
+}
+
+/// Compute semantic tokens using cached data
+pub fn compute_semantic_tokens_cached(data: &CachedData<'_>) -> Option<Vec<SemanticToken>> {
+    let module = data.ast()?;
+    let symbols = data.symbol_index?;
+    let resolved = data
+        .type_result
+        .map_or(&[][..], |result| result.resolved_names.as_slice());
+    Some(collect_tokens(module, symbols, resolved, data.line_index))
+}
+
+/// Compute semantic tokens for a document (non-cached version)
+#[allow(dead_code)] // Standalone API used by tests
+pub fn compute_semantic_tokens(source: &str) -> Option<Vec<SemanticToken>> {
+    let module = Parser::parse_module(source).ok()?;
+    let symbols = SymbolIndex::from_module(&module);
+    let types = TypeChecker::new().check_module(&module);
+    let line_index = LineIndex::new(source);
+    Some(collect_tokens(
+        &module,
+        &symbols,
+        &types.resolved_names,
+        &line_index,
+    ))
+}
+
+/// The edits that turn `previous` into `current`
+///
+/// Only the differing middle is replaced, which keeps the response small
+/// for the common case of an edit touching a single line.
+pub fn compute_delta(
+    previous: &[SemanticToken],
+    current: &[SemanticToken],
+) -> Vec<SemanticTokensEdit> {
+    let prefix = previous
+        .iter()
+        .zip(current)
+        .take_while(|(old, new)| old == new)
+        .count();
+    let suffix = previous[prefix..]
+        .iter()
+        .rev()
+        .zip(current[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+
+    let deleted = previous.len() - prefix - suffix;
+    let inserted = &current[prefix..current.len() - suffix];
+    if deleted == 0 && inserted.is_empty() {
+        return vec![];
+    }
+
+    // Edit offsets count integers, and each token is five of them
+    vec![SemanticTokensEdit {
+        start: to_u32(prefix * 5),
+        delete_count: to_u32(deleted * 5),
+        data: Some(inserted.to_vec()),
+    }]
+}
+
+fn to_u32(value: usize) -> u32 {
+    u32::try_from(value).unwrap_or(u32::MAX)
+}
+
+fn collect_tokens(
+    module: &Module,
+    symbols: &SymbolIndex,
+    resolved: &[ResolvedName],
+    line_index: &LineIndex,
+) -> Vec<SemanticToken> {
+    let mut collector = TokenCollector {
+        symbols,
+        resolved: resolved.iter().map(|name| (name.span, name)).collect(),
+        namespaces: HashSet::new(),
+        variants: HashSet::new(),
+        type_params: Vec::new(),
+        declared: HashSet::new(),
+        tokens: Vec::new(),
+    };
+    collector.module(module);
+    encode(collector.tokens, line_index)
+}
+
+/// Walks a module and classifies every identifier it can
+struct TokenCollector<'a> {
+    symbols: &'a SymbolIndex,
+    /// Type checker resolutions, keyed by the span of the name
+    resolved: HashMap<Span, &'a ResolvedName>,
+    /// Names bound by `import a.b` and `import a as b`
+    namespaces: HashSet<&'a str>,
+    /// Enum variants declared in the module, for bare variant patterns
+    variants: HashSet<&'a str>,
+    /// Type parameters of the item being walked
+    type_params: Vec<&'a str>,
+    /// Every name declared so far, so locals are never taken for builtins
+    declared: HashSet<String>,
+    tokens: Vec<(Span, TokenType, u32)>,
+}
+
+impl<'a> TokenCollector<'a> {
+    fn module(&mut self, module: &'a Module) {
+        for item in module.items() {
+            match &item.kind {
+                ItemKind::Import(import) => match &import.kind {
+                    ImportKind::Item => {
+                        if let Some(last) = import.path.last() {
+                            self.namespaces.insert(&last.name);
+                        }
+                    }
+                    ImportKind::Alias(alias) => {
+                        self.namespaces.insert(&alias.name);
+                    }
+                    ImportKind::List(_) | ImportKind::Glob => {}
+                },
+                ItemKind::Enum(def) => {
+                    self.variants.extend(
+                        def.variants
+                            .iter()
+                            .map(|variant| variant.name.name.as_str()),
+                    );
+                }
+                _ => {}
+            }
+        }
+
+        for top in &module.top_level {
+            match top {
+                TopLevelItem::Item(item) => self.item(&item.kind),
+                TopLevelItem::Let(decl) => {
+                    let modifiers = if decl.is_const { READONLY } else { 0 };
+                    self.binding(&decl.pattern, modifiers);
+                    if let Some(ty) = &decl.ty {
+                        self.type_annotation(ty);
+                    }
+                    self.expr(&decl.value);
+                }
+                TopLevelItem::Statement(stmt) => self.stmt(stmt),
+            }
+        }
+    }
+
+    fn item(&mut self, kind: &'a ItemKind) {
+        match kind {
+            ItemKind::Function(func) => self.function(func, TokenType::Function),
+            ItemKind::Struct(def) => {
+                self.declare(&def.name, TokenType::Struct);
+                self.enter_type_params(&def.type_params);
+                for field in &def.fields {
+                    self.declare(&field.name, TokenType::Property);
+                    self.type_annotation(&field.ty);
+                }
+                self.type_params.clear();
+            }
+            ItemKind::Enum(def) => {
+                self.declare(&def.name, TokenType::Enum);
+                self.enter_type_params(&def.type_params);
+                for variant in &def.variants {
+                    self.declare(&variant.name, TokenType::EnumMember);
+                    match &variant.data {
+                        Some(EnumVariantData::Tuple(types)) => {
+                            for ty in types {
+                                self.type_annotation(ty);
+                            }
+                        }
+                        Some(EnumVariantData::Struct(fields)) => {
+                            for field in fields {
+                                self.declare(&field.name, TokenType::Property);
+                                self.type_annotation(&field.ty);
+                            }
+                        }
+                        None => {}
+                    }
+                }
+                self.type_params.clear();
+            }
+            ItemKind::Interface(def) => {
+                self.declare(&def.name, TokenType::Interface);
+                self.enter_type_params(&def.type_params);
+                for method in &def.methods {
+                    self.declare(&method.name, TokenType::Method);
+                    self.params(&method.params);
+                    if let Some(ty) = &method.return_type {
+                        self.type_annotation(ty);
+                    }
+                    if let Some(body) = &method.default_body {
+                        self.block(body);
+                    }
+                }
+                self.type_params.clear();
+            }
+            ItemKind::Impl(imp) => {
+                self.enter_type_params(&imp.type_params);
+                if let Some(interface) = &imp.interface {
+                    self.type_annotation(interface);
+                }
+                self.type_annotation(&imp.target);
+                for method in &imp.methods {
+                    self.function(method, TokenType::Method);
+                }
+                self.type_params.clear();
+            }
+            ItemKind::Import(import) => {
+                for segment in &import.path {
+                    self.push(segment.span, TokenType::Namespace, 0);
+                }
+                if let ImportKind::Alias(alias) = &import.kind {
+                    self.declare(alias, TokenType::Namespace);
+                }
+            }
+        }
+    }
+
+    fn function(&mut self, func: &'a Function, kind: TokenType) {
+        let outer = self.type_params.len();
+        self.declare(&func.name, kind);
+        self.enter_type_params(&func.type_params);
+        self.params(&func.params);
+        if let Some(ty) = &func.return_type {
+            self.type_annotation(ty);
+        }
+        self.block(&func.body);
+        self.type_params.truncate(outer);
+    }
+
+    fn enter_type_params(&mut self, params: &'a [TypeParam]) {
+        for param in params {
+            self.declare(&param.name, TokenType::TypeParameter);
+            for bound in &param.bounds {
+                self.push(bound.span, TokenType::Interface, 0);
+            }
+            self.type_params.push(&param.name.name);
+        }
+    }
+
+    fn params(&mut self, params: &'a [Param]) {
+        for param in params {
+            self.declare(&param.name, TokenType::Parameter);
+            if let Some(ty) = &param.ty {
+                self.type_annotation(ty);
+            }
+            if let Some(default) = &param.default {
+                self.expr(default);
+            }
+        }
+    }
+
+    fn block(&mut self, block: &'a Block) {
+        for stmt in &block.stmts {
+            self.stmt(stmt);
+        }
+        if let Some(expr) = &block.expr {
+            self.expr(expr);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &'a Stmt) {
+        match &stmt.kind {
+            StmtKind::Let { pattern, ty, value } => {
+                self.binding(pattern, 0);
+                if let Some(ty) = ty {
+                    self.type_annotation(ty);
+                }
+                self.expr(value);
+            }
+            StmtKind::Expr(expr) | StmtKind::Throw(expr) | StmtKind::Return(Some(expr)) => {
+                self.expr(expr);
+            }
+            StmtKind::Assign { target, value } | StmtKind::CompoundAssign { target, value, .. } => {
+                self.expr(target);
+                self.expr(value);
+            }
+            StmtKind::For {
+                pattern,
+                iter,
+                body,
+            } => {
+                self.binding(pattern, 0);
+                self.expr(iter);
+                self.block(body);
+            }
+            StmtKind::While { cond, body } => {
+                self.expr(cond);
+                self.block(body);
+            }
+            StmtKind::Loop { body } => self.block(body),
+            StmtKind::TryCatch {
+                try_block,
+                catches,
+                finally,
+            } => {
+                self.block(try_block);
+                for catch in catches {
+                    if let Some(ty) = &catch.exception_type {
+                        self.type_annotation(ty);
+                    }
+                    if let Some(binding) = &catch.binding {
+                        self.declare(binding, TokenType::Variable);
+                    }
+                    self.block(&catch.body);
+                }
+                if let Some(finally) = finally {
+                    self.block(finally);
+                }
+            }
+            StmtKind::Return(None) | StmtKind::Break | StmtKind::Continue => {}
+        }
+    }
+
+    fn expr(&mut self, expr: &'a Expr) {
+        match &expr.kind {
+            ExprKind::Literal(_) | ExprKind::Placeholder => {}
+            ExprKind::Ident(ident) => self.reference(ident),
+            ExprKind::ColumnShorthand(column) => self.push(column.span, TokenType::Property, 0),
+            ExprKind::Binary { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            ExprKind::Unary { expr, .. }
+            | ExprKind::Paren(expr)
+            | ExprKind::Await(expr)
+            | ExprKind::Try(expr)
+            | ExprKind::StateBinding(expr) => self.expr(expr),
+            ExprKind::Field { expr, field } | ExprKind::NullSafeField { expr, field } => {
+                self.expr(expr);
+                let kind = self.member_kind(expr, TokenType::Property);
+                self.push(field.span, kind, 0);
+            }
+            ExprKind::Call {
+                callee,
+                args,
+                trailing_closure,
+            } => {
+                match &callee.kind {
+                    // `value.name(...)` calls a method rather than reading a field
+                    ExprKind::Field { expr, field } | ExprKind::NullSafeField { expr, field } => {
+                        self.expr(expr);
+                        let kind = self.member_kind(expr, TokenType::Method);
+                        self.push(field.span, kind, 0);
+                    }
+                    _ => self.expr(callee),
+                }
+                for arg in args {
+                    match arg {
+                        CallArg::Positional(value) => self.expr(value),
+                        CallArg::Named { name, value, .. } => {
+                            self.push(name.span, TokenType::Parameter, 0);
+                            self.expr(value);
+                        }
+                    }
+                }
+                if let Some(closure) = trailing_closure {
+                    self.expr(closure);
+                }
+            }
+            ExprKind::Index { expr, index } | ExprKind::NullSafeIndex { expr, index } => {
+                self.expr(expr);
+                self.expr(index);
+            }
+            ExprKind::If {
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                self.expr(cond);
+                self.block(then_branch);
+                match else_branch {
+                    Some(ElseBranch::Block(block)) => self.block(block),
+                    Some(ElseBranch::ElseIf(expr)) => self.expr(expr),
+                    None => {}
+                }
+            }
+            ExprKind::Match { expr, arms } => {
+                self.expr(expr);
+                for arm in arms {
+                    self.binding(&arm.pattern, 0);
+                    if let Some(guard) = &arm.guard {
+                        self.expr(guard);
+                    }
+                    self.expr(&arm.body);
+                }
+            }
+            ExprKind::Lambda {
+                params,
+                return_type,
+                body,
+            } => {
+                self.params(params);
+                if let Some(ty) = return_type {
+                    self.type_annotation(ty);
+                }
+                self.expr(body);
+            }
+            ExprKind::Block(block) => self.block(block),
+            ExprKind::List(items) => {
+                for item in items {
+                    self.expr(item);
+                }
+            }
+            ExprKind::Map(entries) => {
+                for (key, value) in entries {
+                    self.expr(key);
+                    self.expr(value);
+                }
+            }
+            ExprKind::StringInterp { parts } => {
+                for part in parts {
+                    if let StringPart::Expr(expr) = part {
+                        self.expr(expr);
+                    }
+                }
+            }
+            ExprKind::StructInit { name, fields } => {
+                self.type_name(name);
+                for field in fields {
+                    self.push(field.name.span, TokenType::Property, 0);
+                    if let Some(value) = &field.value {
+                        self.expr(value);
+                    }
+                }
+            }
+            ExprKind::EnumVariant {
+                enum_name,
+                variant,
+                data,
+            } => {
+                if let Some(enum_name) = enum_name {
+                    self.type_name(enum_name);
+                }
+                self.push(variant.span, TokenType::EnumMember, 0);
+                if let Some(data) = data {
+                    self.expr(data);
+                }
+            }
+        }
+    }
+
+    /// Names introduced by a `let`, `for` or match arm pattern
+    fn binding(&mut self, pattern: &'a Pattern, modifiers: u32) {
+        match &pattern.kind {
+            PatternKind::Wildcard | PatternKind::Literal(_) => {}
+            PatternKind::Ident(ident) => {
+                // A bare `None` in a match arm names a variant, not a binding
+                if self.variants.contains(ident.name.as_str()) {
+                    self.push(ident.span, TokenType::EnumMember, 0);
+                } else {
+                    self.declared.insert(ident.name.clone());
+                    self.push(ident.span, TokenType::Variable, DECLARATION | modifiers);
+                }
+            }
+            PatternKind::Variant {
+                enum_name,
+                variant,
+                data,
+            } => {
+                if let Some(enum_name) = enum_name {
+                    self.type_name(enum_name);
+                }
+                self.push(variant.span, TokenType::EnumMember, 0);
+                if let Some(data) = data {
+                    self.binding(data, modifiers);
+                }
+            }
+            PatternKind::Struct { name, fields } => {
+                self.type_name(name);
+                for field in fields {
+                    match &field.pattern {
+                        Some(pattern) => {
+                            self.push(field.name.span, TokenType::Property, 0);
+                            self.binding(pattern, modifiers);
+                        }
+                        // `Point { x }` binds the field to a variable of the same name
+                        None => self.declare(&field.name, TokenType::Variable),
+                    }
+                }
+            }
+            PatternKind::List { elements, rest } => {
+                for element in elements {
+                    self.binding(element, modifiers);
+                }
+                if let Some(rest) = rest {
+                    self.binding(rest, modifiers);
+                }
+            }
+            PatternKind::Or(alternatives) => {
+                for alternative in alternatives {
+                    self.binding(alternative, modifiers);
+                }
+            }
+        }
+    }
+
+    fn type_annotation(&mut self, ty: &'a TypeAnnotation) {
+        match &ty.kind {
+            TypeKind::Named { name, args } => {
+                self.type_name(name);
+                for arg in args {
+                    self.type_annotation(arg);
+                }
+            }
+            TypeKind::Nullable(inner) | TypeKind::List(inner) => self.type_annotation(inner),
+            TypeKind::Function { params, ret } => {
+                for param in params {
+                    self.type_annotation(param);
+                }
+                self.type_annotation(ret);
+            }
+            TypeKind::Tuple(items) => {
+                for item in items {
+                    self.type_annotation(item);
+                }
+            }
+            TypeKind::Unit | TypeKind::Never | TypeKind::Inferred => {}
+        }
+    }
+
+    /// A name in type position: a declared type, a type parameter, or a
+    /// builtin such as `Int`
+    fn type_name(&mut self, name: &Ident) {
+        let kind = match self.symbols.lookup(&name.name, name.span.start) {
+            Some(info) if info.kind == SymbolKind::Struct => TokenType::Struct,
+            Some(info) if info.kind == SymbolKind::Enum => TokenType::Enum,
+            Some(info) if info.kind == SymbolKind::Interface => TokenType::Interface,
+            _ if self.type_params.contains(&name.name.as_str()) => TokenType::TypeParameter,
+            _ => {
+                self.push(name.span, TokenType::Type, DEFAULT_LIBRARY);
+                return;
+            }
+        };
+        self.push(name.span, kind, 0);
+    }
+
+    /// The kind of `name` in `expr.name`: a variant when `expr` names an
+    /// enum, otherwise `default`
+    fn member_kind(&self, expr: &Expr, default: TokenType) -> TokenType {
+        let ExprKind::Ident(ident) = &expr.kind else {
+            return default;
+        };
+        match self.symbols.lookup(&ident.name, ident.span.start) {
+            Some(info) if info.kind == SymbolKind::Enum => TokenType::EnumMember,
+            _ => default,
+        }
+    }
+
+    /// A name read in an expression
+    fn reference(&mut self, ident: &Ident) {
+        let resolved = self.resolved.get(&ident.span).copied();
+        let readonly = if resolved.is_some_and(|name| name.is_const) {
+            READONLY
+        } else {
+            0
+        };
+
+        if let Some(info) = self.symbols.lookup(&ident.name, ident.span.start) {
+            let kind = match info.kind {
+                SymbolKind::Function => TokenType::Function,
+                SymbolKind::Struct => TokenType::Struct,
+                SymbolKind::Enum => TokenType::Enum,
+                SymbolKind::Interface => TokenType::Interface,
+                SymbolKind::Parameter => TokenType::Parameter,
+                SymbolKind::Field => TokenType::Property,
+                SymbolKind::EnumVariant => TokenType::EnumMember,
+                SymbolKind::Variable => TokenType::Variable,
+            };
+            self.push(ident.span, kind, readonly);
+        } else if let Some(name) = resolved {
+            let kind = match name.ty {
+                Type::Namespace(_) => TokenType::Namespace,
+                Type::Function { .. } => TokenType::Function,
+                _ => TokenType::Variable,
+            };
+            let library = if self.declared.contains(&ident.name) {
+                0
+            } else {
+                DEFAULT_LIBRARY
+            };
+            self.push(ident.span, kind, library | readonly);
+        } else if self.namespaces.contains(ident.name.as_str()) {
+            self.push(ident.span, TokenType::Namespace, 0);
+        }
+    }
+
+    fn declare(&mut self, ident: &Ident, kind: TokenType) {
+        self.declared.insert(ident.name.clone());
+        self.push(ident.span, kind, DECLARATION);
+    }
+
+    fn push(&mut self, span: Span, kind: TokenType, modifiers: u32) {
+        if span.start < span.end {
+            self.tokens.push((span, kind, modifiers));
+        }
+    }
+}
+
+/// Encode tokens relative to the previous one, as the protocol requires
+fn encode(mut tokens: Vec<(Span, TokenType, u32)>, line_index: &LineIndex) -> Vec<SemanticToken> {
+    tokens.sort_by_key(|(span, _, _)| span.start);
+    tokens.dedup_by_key(|(span, _, _)| span.start);
+
+    let mut encoded = Vec::with_capacity(tokens.len());
+    let (mut prev_line, mut prev_start) = (0, 0);
+    for (span, kind, modifiers) in tokens {
+        let location = line_index.location(span.start);
+        let line = location.line.saturating_sub(1);
+        let start = location.column.saturating_sub(1);
+        encoded.push(SemanticToken {
+            delta_line: line - prev_line,
+            delta_start: if line == prev_line {
+                start - prev_start
+            } else {
+                start
+            },
+            length: span.end - span.start,
+            token_type: kind as u32,
+            token_modifiers_bitset: modifiers,
+        });
+        prev_line = line;
+        prev_start = start;
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Decode tokens back to `(line, column, text, type, modifiers)`
+    fn decode(source: &str) -> Vec<(u32, u32, String, SemanticTokenType, u32)> {
+        let lines: Vec<&str> = source.lines().collect();
+        let (mut line, mut start) = (0, 0);
+        compute_semantic_tokens(source)
+            .unwrap()
+            .into_iter()
+            .map(|token| {
+                if token.delta_line > 0 {
+                    start = 0;
+                }
+                line += token.delta_line;
+                start += token.delta_start;
+                let from = start as usize;
+                let text = lines[line as usize][from..from + token.length as usize].to_string();
+                let kind = TOKEN_TYPES[token.token_type as usize].clone();
+                (line, start, text, kind, token.token_modifiers_bitset)
+            })
+            .collect()
+    }
+
+    fn kind_of(source: &str, text: &str) -> Vec<(SemanticTokenType, u32)> {
+        decode(source)
+            .into_iter()
+            .filter(|(_, _, token, _, _)| token == text)
+            .map(|(_, _, _, kind, modifiers)| (kind, modifiers))
+            .collect()
+    }
+
+    const SOURCE: &str = "const LIMIT = 10
+
+struct Point {
+    x: Int,
+    y: Int
+}
+
+enum Shape {
+    Circle(Float),
+    Empty
+}
+
+fx area(shape: Shape) -> Float {
+    match shape {
+        Shape::Circle(r) => r * r,
+        Empty => 0.0
+    }
+}
+
+fx main() {
+    let p = Point { x: LIMIT, y: 2 }
+    println(area(Shape::Circle(1.0)) + File.size(\"a\"));
+    p.x
+}
+";
+
+    #[test]
+    fn test_declarations() {
+        assert_eq!(
+            kind_of(SOURCE, "Point")[0],
+            (SemanticTokenType::STRUCT, DECLARATION)
+        );
+        assert_eq!(
+            kind_of(SOURCE, "Shape")[0],
+            (SemanticTokenType::ENUM, DECLARATION)
+        );
+        assert_eq!(
+            kind_of(SOURCE, "area")[0],
+            (SemanticTokenType::FUNCTION, DECLARATION)
+        );
+        assert_eq!(
+            kind_of(SOURCE, "shape")[0],
+            (SemanticTokenType::PARAMETER, DECLARATION)
+        );
+        assert_eq!(
+            kind_of(SOURCE, "LIMIT")[0],
+            (SemanticTokenType::VARIABLE, DECLARATION | READONLY)
+        );
+        assert_eq!(
+            kind_of(SOURCE, "Empty")[0],
+            (SemanticTokenType::ENUM_MEMBER, DECLARATION)
+        );
+    }
+
+    #[test]
+    fn test_references() {
+        assert_eq!(kind_of(SOURCE, "area")[1], (SemanticTokenType::FUNCTION, 0));
+        assert_eq!(
+            kind_of(SOURCE, "shape")[1],
+            (SemanticTokenType::PARAMETER, 0)
+        );
+        assert_eq!(
+            kind_of(SOURCE, "LIMIT")[1],
+            (SemanticTokenType::VARIABLE, READONLY)
+        );
+        assert_eq!(
+            kind_of(SOURCE, "Circle")[1..],
+            [
+                (SemanticTokenType::ENUM_MEMBER, 0),
+                (SemanticTokenType::ENUM_MEMBER, 0)
+            ]
+        );
+        // A bare variant in a match arm is not a binding
+        assert_eq!(
+            kind_of(SOURCE, "Empty")[1],
+            (SemanticTokenType::ENUM_MEMBER, 0)
+        );
+        assert_eq!(kind_of(SOURCE, "Point")[1], (SemanticTokenType::STRUCT, 0));
+        assert_eq!(kind_of(SOURCE, "x")[1], (SemanticTokenType::PROPERTY, 0));
+    }
+
+    #[test]
+    fn test_builtins_and_types() {
+        assert_eq!(
+            kind_of(SOURCE, "println"),
+            [(SemanticTokenType::FUNCTION, DEFAULT_LIBRARY)]
+        );
+        assert_eq!(
+            kind_of(SOURCE, "File"),
+            [(SemanticTokenType::NAMESPACE, DEFAULT_LIBRARY)]
+        );
+        assert_eq!(kind_of(SOURCE, "size"), [(SemanticTokenType::METHOD, 0)]);
+        assert_eq!(
+            kind_of(SOURCE, "Float")[0],
+            (SemanticTokenType::TYPE, DEFAULT_LIBRARY)
+        );
+    }
+
+    #[test]
+    fn test_imports_and_type_parameters() {
+        let source = "import std.math\n\nfx first<T>(items: List<T>) -> T {\n    items[0]\n}\n\nmath.sqrt(first([4.0]))\n";
+        assert_eq!(
+            kind_of(source, "math"),
+            [
+                (SemanticTokenType::NAMESPACE, 0),
+                (SemanticTokenType::NAMESPACE, 0)
+            ]
+        );
+        assert_eq!(
+            kind_of(source, "T"),
+            [
+                (SemanticTokenType::TYPE_PARAMETER, DECLARATION),
+                (SemanticTokenType::TYPE_PARAMETER, 0),
+                (SemanticTokenType::TYPE_PARAMETER, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_relative_encoding() {
+        let tokens = compute_semantic_tokens("fx f(a: Int) {\n    a\n}\n").unwrap();
+        let encoded: Vec<(u32, u32, u32)> = tokens
+            .iter()
+            .map(|token| (token.delta_line, token.delta_start, token.length))
+            .collect();
+        // `f`, `a`, `Int` on the first line, then `a` on the second
+        assert_eq!(encoded, vec![(0, 3, 1), (0, 2, 1), (0, 3, 3), (1, 4, 1)]);
+    }
+
+    #[test]
+    fn test_delta() {
+        let previous = compute_semantic_tokens("fx f(a: Int) {\n    a\n}\n").unwrap();
+        let current = compute_semantic_tokens("fx f(a: Int) {\n    let b = a\n    b\n}\n").unwrap();
+        let edits = compute_delta(&previous, &current);
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].start, 15);
+
+        let mut applied: Vec<SemanticToken> = previous.clone();
+        let start = edits[0].start as usize / 5;
+        let end = start + edits[0].delete_count as usize / 5;
+        applied.splice(start..end, edits[0].data.clone().unwrap());
+        assert_eq!(applied, current);
+
+        assert!(compute_delta(&current, &current).is_empty());
+    }
+}
@@ -0,0 +1,575 @@
+//! Snippet completions for Stratum LSP
+//!
+//! Offers struct literals with every field as a tabstop, and the missing
+//! arms of a `match` on an enum. The type checker tells which struct the
+//! cursor expects (a call argument, for instance) and which enum is being
+//! matched on; those snippets are preselected.
+
+use stratum_core::ast::{
+    Block, CallArg, ElseBranch, EnumDef, EnumVariant, Expr, ExprKind, Function, ItemKind, MatchArm,
+    Module, PatternKind, Stmt, StmtKind, StringPart, StructDef, TopLevelItem, TypeAnnotation,
+    TypeKind,
+};
+use stratum_core::lexer::Span;
+use stratum_core::types::{Type, TypeCheckResult};
+use tower_lsp::lsp_types::{CompletionItem, CompletionItemKind, InsertTextFormat};
+
+use crate::cache::CachedData;
+
+/// Compute snippet completions for the identifier `prefix` ending at `offset`
+pub fn snippet_completions(
+    data: &CachedData<'_>,
+    offset: u32,
+    prefix: &str,
+) -> Vec<CompletionItem> {
+    let Some(module) = data.ast() else {
+        return vec![];
+    };
+    let type_result = data.type_result.map(AsRef::as_ref);
+    let site = CursorSite::find(module, offset);
+
+    let expected = site
+        .expected
+        .and_then(|expected| expected.type_name(module, type_result));
+    let mut items = struct_literal_completions(module, prefix, expected.as_deref());
+
+    if let (Some((scrutinee, arms)), Some(type_result)) = (site.match_site, type_result) {
+        if let Some(item) = match_arms_completion(module, type_result, scrutinee, arms) {
+            items.push(item);
+        }
+    }
+
+    items
+}
+
+/// Struct literal snippets for the structs whose name starts with `prefix`
+///
+/// The struct the cursor expects is offered even without a prefix.
+fn struct_literal_completions(
+    module: &Module,
+    prefix: &str,
+    expected: Option<&str>,
+) -> Vec<CompletionItem> {
+    let prefix_lower = prefix.to_lowercase();
+    module
+        .items()
+        .into_iter()
+        .filter_map(|item| match &item.kind {
+            ItemKind::Struct(struct_def) => Some(struct_def),
+            _ => None,
+        })
+        .filter(|struct_def| {
+            let name = &struct_def.name.name;
+            let matches = !prefix.is_empty() && name.to_lowercase().starts_with(&prefix_lower);
+            matches || expected == Some(name.as_str())
+        })
+        .map(|struct_def| {
+            let is_expected = expected == Some(struct_def.name.name.as_str());
+            struct_literal(struct_def, is_expected)
+        })
+        .collect()
+}
+
+fn struct_literal(struct_def: &StructDef, is_expected: bool) -> CompletionItem {
+    let name = &struct_def.name.name;
+    let fields: Vec<String> = struct_def
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| format!("{}: ${}", field.name.name, i + 1))
+        .collect();
+    let snippet = if fields.is_empty() {
+        format!("{name} {{}}$0")
+    } else {
+        format!("{name} {{ {} }}$0", fields.join(", "))
+    };
+
+    CompletionItem {
+        label: format!("{name} {{ … }}"),
+        kind: Some(CompletionItemKind::SNIPPET),
+        detail: Some("struct literal".to_string()),
+        filter_text: Some(name.clone()),
+        insert_text: Some(snippet),
+        insert_text_format: Some(InsertTextFormat::SNIPPET),
+        // The expected struct goes first, the others right after their name
+        sort_text: Some(if is_expected {
+            format!("0_0_{name}")
+        } else {
+            format!("0_{name}_")
+        }),
+        preselect: is_expected.then_some(true),
+        ..Default::default()
+    }
+}
+
+/// One snippet with an arm for every variant the match does not cover yet
+fn match_arms_completion(
+    module: &Module,
+    type_result: &TypeCheckResult,
+    scrutinee: &Expr,
+    arms: &[MatchArm],
+) -> Option<CompletionItem> {
+    let ExprKind::Ident(_) = &scrutinee.kind else {
+        return None;
+    };
+    let resolved = type_result
+        .resolved_names
+        .iter()
+        .find(|name| name.span == scrutinee.span)?;
+    let Type::Enum { name, .. } = &resolved.ty else {
+        return None;
+    };
+    let enum_def = find_enum(module, name)?;
+
+    // A catch-all arm covers everything
+    let is_catch_all = |arm: &MatchArm| {
+        arm.guard.is_none()
+            && matches!(
+                arm.pattern.kind,
+                PatternKind::Wildcard | PatternKind::Ident(_)
+            )
+    };
+    if arms.iter().any(is_catch_all) {
+        return None;
+    }
+    let covered: Vec<&str> = arms
+        .iter()
+        .filter(|arm| arm.guard.is_none())
+        .filter_map(|arm| match &arm.pattern.kind {
+            PatternKind::Variant { variant, .. } => Some(variant.name.as_str()),
+            _ => None,
+        })
+        .collect();
+    let missing: Vec<&EnumVariant> = enum_def
+        .variants
+        .iter()
+        .filter(|variant| !covered.contains(&variant.name.name.as_str()))
+        .collect();
+    if missing.is_empty() {
+        return None;
+    }
+
+    let mut tabstop = 0;
+    let mut next_tabstop = || {
+        tabstop += 1;
+        tabstop
+    };
+    let snippet: Vec<String> = missing
+        .iter()
+        .map(|variant| {
+            let pattern = if variant.data.is_some() {
+                format!("{name}::{}(${{{}:_}})", variant.name.name, next_tabstop())
+            } else {
+                format!("{name}::{}", variant.name.name)
+            };
+            format!("{pattern} => {{${}}}", next_tabstop())
+        })
+        .collect();
+    let variants: Vec<String> = missing
+        .iter()
+        .map(|variant| format!("{name}::{}", variant.name.name))
+        .collect();
+
+    Some(CompletionItem {
+        label: format!("match arms for {name}"),
+        kind: Some(CompletionItemKind::SNIPPET),
+        detail: Some(variants.join(", ")),
+        filter_text: Some(name.clone()),
+        insert_text: Some(snippet.join("\n")),
+        insert_text_format: Some(InsertTextFormat::SNIPPET),
+        sort_text: Some(format!("0_0_{name}")),
+        preselect: Some(true),
+        ..Default::default()
+    })
+}
+
+fn find_struct<'a>(module: &'a Module, name: &str) -> Option<&'a StructDef> {
+    module
+        .items()
+        .into_iter()
+        .find_map(|item| match &item.kind {
+            ItemKind::Struct(struct_def) if struct_def.name.name == name => Some(struct_def),
+            _ => None,
+        })
+}
+
+fn find_enum<'a>(module: &'a Module, name: &str) -> Option<&'a EnumDef> {
+    module
+        .items()
+        .into_iter()
+        .find_map(|item| match &item.kind {
+            ItemKind::Enum(enum_def) if enum_def.name.name == name => Some(enum_def),
+            _ => None,
+        })
+}
+
+/// Where the value being typed at the cursor goes
+enum Expected<'a> {
+    /// A `let` or `return` with this type annotation
+    Annotation(&'a TypeAnnotation),
+    /// The positional argument `index` of a call
+    Argument { callee: &'a Expr, index: usize },
+    /// A field of a struct literal
+    Field {
+        struct_name: &'a str,
+        field: &'a str,
+    },
+}
+
+impl Expected<'_> {
+    /// The name of the expected struct or enum, if known
+    fn type_name(&self, module: &Module, type_result: Option<&TypeCheckResult>) -> Option<String> {
+        match self {
+            Expected::Annotation(ty) => annotation_name(ty),
+            Expected::Argument { callee, index } => {
+                let resolved = type_result?
+                    .resolved_names
+                    .iter()
+                    .find(|name| name.span == callee.span)?;
+                let Type::Function { params, .. } = &resolved.ty else {
+                    return None;
+                };
+                match params.get(*index)? {
+                    Type::Struct { name, .. } | Type::Enum { name, .. } => Some(name.clone()),
+                    _ => None,
+                }
+            }
+            Expected::Field { struct_name, field } => {
+                let struct_def = find_struct(module, struct_name)?;
+                let field = struct_def.fields.iter().find(|f| f.name.name == *field)?;
+                annotation_name(&field.ty)
+            }
+        }
+    }
+}
+
+fn annotation_name(ty: &TypeAnnotation) -> Option<String> {
+    match &ty.kind {
+        TypeKind::Named { name, .. } => Some(name.name.clone()),
+        _ => None,
+    }
+}
+
+/// What surrounds the cursor, found by descending through the nodes that
+/// contain it
+#[derive(Default)]
+struct CursorSite<'a> {
+    offset: u32,
+    /// Where the innermost expression at the cursor goes, if it is directly
+    /// a call argument, `let` value, returned value or field value
+    expected: Option<Expected<'a>>,
+    /// The scrutinee and arms of a `match` whose braces hold the cursor
+    /// outside of any arm
+    match_site: Option<(&'a Expr, &'a [MatchArm])>,
+    /// The return type of the enclosing function
+    return_type: Option<&'a TypeAnnotation>,
+}
+
+impl<'a> CursorSite<'a> {
+    fn find(module: &'a Module, offset: u32) -> Self {
+        let mut site = Self {
+            offset,
+            ..Self::default()
+        };
+        for item in &module.top_level {
+            match item {
+                TopLevelItem::Item(item) => match &item.kind {
+                    ItemKind::Function(func) => site.function(func),
+                    ItemKind::Impl(impl_def) => {
+                        for method in &impl_def.methods {
+                            site.function(method);
+                        }
+                    }
+                    _ => {}
+                },
+                TopLevelItem::Let(let_decl) => {
+                    if site.contains(let_decl.value.span) {
+                        site.expected = let_decl.ty.as_ref().map(Expected::Annotation);
+                        site.expr(&let_decl.value);
+                    }
+                }
+                TopLevelItem::Statement(stmt) => site.stmt(stmt),
+            }
+        }
+        site
+    }
+
+    fn contains(&self, span: Span) -> bool {
+        span.start <= self.offset && self.offset <= span.end
+    }
+
+    fn function(&mut self, func: &'a Function) {
+        if self.contains(func.body.span) {
+            self.return_type = func.return_type.as_ref();
+            self.block(&func.body);
+        }
+    }
+
+    fn block(&mut self, block: &'a Block) {
+        for stmt in &block.stmts {
+            self.stmt(stmt);
+        }
+        if let Some(expr) = &block.expr {
+            self.expr(expr);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &'a Stmt) {
+        if !self.contains(stmt.span) {
+            return;
+        }
+        match &stmt.kind {
+            StmtKind::Let { ty, value, .. } => {
+                if self.contains(value.span) {
+                    self.expected = ty.as_ref().map(Expected::Annotation);
+                    self.expr(value);
+                }
+            }
+            StmtKind::Return(Some(expr)) => {
+                if self.contains(expr.span) {
+                    self.expected = self.return_type.map(Expected::Annotation);
+                    self.expr(expr);
+                }
+            }
+            StmtKind::Expr(expr) | StmtKind::Throw(expr) => self.expr(expr),
+            StmtKind::Assign { target, value } | StmtKind::CompoundAssign { target, value, .. } => {
+                self.expr(target);
+                self.expr(value);
+            }
+            StmtKind::For { iter, body, .. } => {
+                self.expr(iter);
+                self.block(body);
+            }
+            StmtKind::While { cond, body } => {
+                self.expr(cond);
+                self.block(body);
+            }
+            StmtKind::Loop { body } => self.block(body),
+            StmtKind::TryCatch {
+                try_block,
+                catches,
+                finally,
+            } => {
+                self.block(try_block);
+                for catch in catches {
+                    self.block(&catch.body);
+                }
+                if let Some(finally) = finally {
+                    self.block(finally);
+                }
+            }
+            StmtKind::Return(None) | StmtKind::Break | StmtKind::Continue => {}
+        }
+    }
+
+    fn expr(&mut self, expr: &'a Expr) {
+        if !self.contains(expr.span) {
+            return;
+        }
+        // The cursor is inside this expression, so anything found further
+        // out no longer applies, unless the expression is the value itself
+        self.match_site = None;
+        if !matches!(expr.kind, ExprKind::Ident(_)) {
+            self.expected = None;
+        }
+
+        match &expr.kind {
+            ExprKind::Literal(_)
+            | ExprKind::Ident(_)
+            | ExprKind::Placeholder
+            | ExprKind::ColumnShorthand(_) => {}
+            ExprKind::Binary { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            ExprKind::Unary { expr, .. }
+            | ExprKind::Paren(expr)
+            | ExprKind::Field { expr, .. }
+            | ExprKind::NullSafeField { expr, .. }
+            | ExprKind::Await(expr)
+            | ExprKind::Try(expr)
+            | ExprKind::StateBinding(expr) => self.expr(expr),
+            ExprKind::Call {
+                callee,
+                args,
+                trailing_closure,
+            } => {
+                self.expr(callee);
+                if self.offset > callee.span.end {
+                    // Arguments before the cursor decide which one it is in
+                    let index = args
+                        .iter()
+                        .filter(|arg| call_arg_value(arg).span.end < self.offset)
+                        .count();
+                    self.expected = match args.get(index) {
+                        Some(CallArg::Named { .. }) => None,
+                        _ => Some(Expected::Argument { callee, index }),
+                    };
+                }
+                for arg in args {
+                    self.expr(call_arg_value(arg));
+                }
+                if let Some(closure) = trailing_closure {
+                    self.expr(closure);
+                }
+            }
+            ExprKind::Index { expr, index } | ExprKind::NullSafeIndex { expr, index } => {
+                self.expr(expr);
+                self.expr(index);
+            }
+            ExprKind::If {
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                self.expr(cond);
+                self.block(then_branch);
+                match else_branch {
+                    Some(ElseBranch::Block(block)) => self.block(block),
+                    Some(ElseBranch::ElseIf(expr)) => self.expr(expr),
+                    None => {}
+                }
+            }
+            ExprKind::Match {
+                expr: scrutinee,
+                arms,
+            } => {
+                if self.offset > scrutinee.span.end
+                    && !arms.iter().any(|arm| self.contains(arm.span))
+                {
+                    self.match_site = Some((scrutinee, arms));
+                }
+                self.expr(scrutinee);
+                for arm in arms {
+                    if let Some(guard) = &arm.guard {
+                        self.expr(guard);
+                    }
+                    self.expr(&arm.body);
+                }
+            }
+            ExprKind::Lambda { body, .. } => self.expr(body),
+            ExprKind::Block(block) => self.block(block),
+            ExprKind::List(items) => {
+                for item in items {
+                    self.expr(item);
+                }
+            }
+            ExprKind::Map(entries) => {
+                for (key, value) in entries {
+                    self.expr(key);
+                    self.expr(value);
+                }
+            }
+            ExprKind::StringInterp { parts } => {
+                for part in parts {
+                    if let StringPart::Expr(expr) = part {
+                        self.expr(expr);
+                    }
+                }
+            }
+            ExprKind::StructInit { name, fields } => {
+                for field in fields {
+                    if let Some(value) = &field.value {
+                        if self.contains(value.span) {
+                            self.expected = Some(Expected::Field {
+                                struct_name: &name.name,
+                                field: &field.name.name,
+                            });
+                            self.expr(value);
+                        }
+                    }
+                }
+            }
+            ExprKind::EnumVariant { data, .. } => {
+                if let Some(data) = data {
+                    self.expr(data);
+                }
+            }
+        }
+    }
+}
+
+fn call_arg_value(arg: &CallArg) -> &Expr {
+    match arg {
+        CallArg::Positional(value) | CallArg::Named { value, .. } => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::DocumentCache;
+
+    /// Complete at the `|` in `source`
+    fn complete(source: &str) -> Vec<CompletionItem> {
+        let offset = source.find('|').unwrap();
+        let source = source.replacen('|', "", 1);
+        let prefix_start = source[..offset]
+            .rfind(|c: char| !c.is_alphanumeric() && c != '_')
+            .map_or(0, |i| i + 1);
+        let prefix = source[prefix_start..offset].to_string();
+
+        let mut cache = DocumentCache::new(source, 1);
+        let data = cache.get_all_cached();
+        snippet_completions(&data, u32::try_from(offset).unwrap(), &prefix)
+    }
+
+    const SHAPES: &str = "struct Point {\n    x: Int,\n    y: Int\n}\n\nenum Shape {\n    Circle(Float),\n    Square(Float),\n    Empty\n}\n\nfx draw(at: Point) {}\n";
+
+    #[test]
+    fn test_struct_literal_snippet() {
+        let items = complete(&format!("{SHAPES}fx main() {{\n    let p = Po|;\n}}\n"));
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].label, "Point { … }");
+        assert_eq!(
+            items[0].insert_text.as_deref(),
+            Some("Point { x: $1, y: $2 }$0")
+        );
+        assert_eq!(items[0].preselect, None);
+    }
+
+    #[test]
+    fn test_expected_struct_from_call_argument() {
+        let items = complete(&format!("{SHAPES}fx main() {{\n    draw(|);\n}}\n"));
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].filter_text.as_deref(), Some("Point"));
+        assert_eq!(items[0].preselect, Some(true));
+
+        // Nested inside another expression, the argument type does not apply
+        let items = complete(&format!("{SHAPES}fx main() {{\n    draw([|]);\n}}\n"));
+        assert!(items.is_empty());
+    }
+
+    #[test]
+    fn test_expected_struct_from_annotation() {
+        let items = complete(&format!(
+            "{SHAPES}struct Line {{\n    from: Point,\n    to: Point\n}}\n\nfx main() {{\n    let a: Point = P|;\n}}\n"
+        ));
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].preselect, Some(true));
+    }
+
+    #[test]
+    fn test_match_arms() {
+        let source = format!(
+            "{SHAPES}fx area(shape: Shape) -> Float {{\n    match shape {{\n        Shape::Empty => 0.0,\n        |\n    }}\n}}\n"
+        );
+        let items = complete(&source);
+        let arms = items
+            .iter()
+            .find(|item| item.label == "match arms for Shape")
+            .expect("match arms");
+        assert_eq!(
+            arms.insert_text.as_deref(),
+            Some("Shape::Circle(${1:_}) => {$2}\nShape::Square(${3:_}) => {$4}")
+        );
+    }
+
+    #[test]
+    fn test_no_match_arms_when_exhaustive() {
+        let source = format!(
+            "{SHAPES}fx area(shape: Shape) -> Float {{\n    match shape {{\n        Shape::Circle(r) => r,\n        _ => 0.0\n        |\n    }}\n}}\n"
+        );
+        assert!(complete(&source).is_empty());
+    }
+}
@@ -73,6 +73,49 @@ pub fn compute_workspace_symbols(
     results
 }
 
+/// A top-level declaration that other files can import
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportedSymbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// The module path of the declaring file, such as `util.text`
+    pub module_path: String,
+}
+
+/// The top-level declarations of a set of modules
+#[derive(Debug, Default)]
+pub struct WorkspaceSymbolIndex {
+    symbols: Vec<ExportedSymbol>,
+}
+
+impl WorkspaceSymbolIndex {
+    /// Index the given modules, each with its module path
+    pub fn build<'a>(modules: impl IntoIterator<Item = (String, &'a Module)>) -> Self {
+        let mut symbols = Vec::new();
+        for (module_path, module) in modules {
+            for symbol in collect_all_symbols(module) {
+                if symbol.container_name.is_none() {
+                    symbols.push(ExportedSymbol {
+                        name: symbol.name,
+                        kind: symbol.kind,
+                        module_path: module_path.clone(),
+                    });
+                }
+            }
+        }
+        symbols.sort_by(|a, b| (&a.name, &a.module_path).cmp(&(&b.name, &b.module_path)));
+        Self { symbols }
+    }
+
+    /// The symbols whose name starts with `prefix`, ignoring case
+    pub fn matching<'a>(&'a self, prefix: &str) -> impl Iterator<Item = &'a ExportedSymbol> {
+        let prefix = prefix.to_lowercase();
+        self.symbols
+            .iter()
+            .filter(move |symbol| symbol.name.to_lowercase().starts_with(&prefix))
+    }
+}
+
 /// Collect all symbols from a module
 fn collect_all_symbols(module: &Module) -> Vec<SymbolInfo> {
     let mut symbols = Vec::new();
@@ -380,4 +423,28 @@ fx MyFunction() {}
         let results = compute_workspace_symbols("foo", &documents);
         assert_eq!(results.len(), 2);
     }
+
+    #[test]
+    fn test_index_exports_top_level_symbols() {
+        let text = Parser::parse_module(
+            "struct Text {\n    value: String\n}\n\nimpl Text {\n    fx trim(self) -> Text { self }\n}\n\nfx trim_all() {}\n",
+        )
+        .unwrap();
+        let math = Parser::parse_module("fx triple(x: Int) -> Int { x * 3 }").unwrap();
+        let index = WorkspaceSymbolIndex::build([
+            ("util.text".to_string(), &text),
+            ("math".to_string(), &math),
+        ]);
+
+        let names: Vec<(&str, &str)> = index
+            .matching("tr")
+            .map(|symbol| (symbol.name.as_str(), symbol.module_path.as_str()))
+            .collect();
+        // Methods and fields are not importable on their own
+        assert_eq!(names, [("trim_all", "util.text"), ("triple", "math")]);
+        assert_eq!(
+            index.matching("te").next().unwrap().kind,
+            SymbolKind::STRUCT
+        );
+    }
 }
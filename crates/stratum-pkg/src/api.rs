@@ -0,0 +1,556 @@
+//! Client for the Stratum package registry HTTP API.
+//!
+//! Packages published to a registry are addressed by name, unlike the
+//! GitHub-release packages in [`crate::registry`]. The API is:
+//!
+//! - `GET  /api/v1/packages?q=QUERY&per_page=N` - search the index
+//! - `GET  /api/v1/packages/NAME` - package metadata and versions
+//! - `GET  /api/v1/packages/NAME/owners` - users allowed to publish
+//! - `GET  /api/v1/me` - the user a token belongs to
+//! - `PUT  /api/v1/packages/new` - publish a version
+//! - `DELETE /api/v1/packages/NAME/VERSION/yank` - yank a version
+//! - `PUT  /api/v1/packages/NAME/VERSION/unyank` - restore a yanked version
+//!
+//! Authenticated requests send the token in the `Authorization` header.
+//! Failed requests answer with `{"errors": [{"detail": "..."}]}`.
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Errors returned by the registry API client.
+#[derive(Error, Debug)]
+pub enum ApiError {
+    /// The request could not be sent or the response read.
+    #[error("network error: {0}")]
+    Network(String),
+
+    /// The registry rejected the token, or none was given.
+    #[error("not authorized: {0}")]
+    Unauthorized(String),
+
+    /// The package or version does not exist.
+    #[error("not found: {0}")]
+    NotFound(String),
+
+    /// The registry answered with an error.
+    #[error("registry error ({status}): {detail}")]
+    Registry { status: u16, detail: String },
+
+    /// The response was not the JSON expected.
+    #[error("invalid registry response: {0}")]
+    InvalidResponse(String),
+
+    /// The operation needs a token but the client has none.
+    #[error("no registry token; run `stratum login` first")]
+    MissingToken,
+}
+
+/// Metadata uploaded with a new package version.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PublishMetadata {
+    /// Package name.
+    pub name: String,
+    /// Version being published.
+    pub vers: String,
+    /// Short description.
+    pub description: Option<String>,
+    /// License identifier.
+    pub license: Option<String>,
+    /// Source repository URL.
+    pub repository: Option<String>,
+    /// Documentation URL.
+    pub documentation: Option<String>,
+    /// Homepage URL.
+    pub homepage: Option<String>,
+    /// Search keywords.
+    pub keywords: Vec<String>,
+    /// Package authors.
+    pub authors: Vec<String>,
+    /// Dependencies on other registry packages.
+    pub deps: Vec<PublishDependency>,
+    /// SHA256 of the tarball, hex encoded.
+    pub cksum: String,
+}
+
+/// A dependency of a published version.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PublishDependency {
+    /// Dependency name.
+    pub name: String,
+    /// Version requirement.
+    pub version_req: String,
+    /// Whether only tests and examples use it.
+    pub dev: bool,
+}
+
+/// A package in search results.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SearchEntry {
+    /// Package name.
+    pub name: String,
+    /// Newest version that is not yanked.
+    pub max_version: String,
+    /// Short description.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Total downloads.
+    #[serde(default)]
+    pub downloads: u64,
+}
+
+/// Results of [`RegistryApi::search`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SearchResults {
+    /// Matching packages, best first.
+    pub packages: Vec<SearchEntry>,
+    /// Number of matches, including those past the page.
+    #[serde(default)]
+    pub total: u64,
+}
+
+/// A package and its versions, as returned by [`RegistryApi::info`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PackageInfo {
+    /// Package name.
+    pub name: String,
+    /// Short description.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// License identifier.
+    #[serde(default)]
+    pub license: Option<String>,
+    /// Source repository URL.
+    #[serde(default)]
+    pub repository: Option<String>,
+    /// Homepage URL.
+    #[serde(default)]
+    pub homepage: Option<String>,
+    /// Search keywords.
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    /// Total downloads.
+    #[serde(default)]
+    pub downloads: u64,
+    /// Published versions, newest first.
+    #[serde(default)]
+    pub versions: Vec<VersionInfo>,
+}
+
+impl PackageInfo {
+    /// The newest version that is not yanked.
+    #[must_use]
+    pub fn latest(&self) -> Option<&VersionInfo> {
+        self.versions.iter().find(|v| !v.yanked)
+    }
+}
+
+/// A published version of a package.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct VersionInfo {
+    /// Version number.
+    pub num: String,
+    /// Whether the version is yanked.
+    #[serde(default)]
+    pub yanked: bool,
+    /// Publication time, RFC 3339.
+    #[serde(default)]
+    pub created_at: Option<String>,
+    /// SHA256 of the tarball, hex encoded.
+    #[serde(default)]
+    pub checksum: Option<String>,
+}
+
+/// A registry user.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct User {
+    /// Login name.
+    pub login: String,
+    /// Display name.
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// Response to [`RegistryApi::publish`].
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct PublishWarnings {
+    /// Non-fatal problems the registry noticed, such as unknown keywords.
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct InfoResponse {
+    package: PackageInfo,
+}
+
+#[derive(Deserialize)]
+struct OwnersResponse {
+    users: Vec<User>,
+}
+
+#[derive(Deserialize)]
+struct MeResponse {
+    user: User,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    errors: Vec<ErrorDetail>,
+}
+
+#[derive(Deserialize)]
+struct ErrorDetail {
+    detail: String,
+}
+
+/// Client for a package registry.
+pub struct RegistryApi {
+    base_url: String,
+    token: Option<String>,
+    http_client: reqwest::blocking::Client,
+}
+
+impl RegistryApi {
+    /// Create a client for the registry at `base_url`.
+    #[must_use]
+    pub fn new(base_url: &str, http_client: reqwest::blocking::Client) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: None,
+            http_client,
+        }
+    }
+
+    /// Authenticate requests with `token`.
+    #[must_use]
+    pub fn with_token(mut self, token: Option<String>) -> Self {
+        self.token = token;
+        self
+    }
+
+    /// The registry base URL, without a trailing slash.
+    #[must_use]
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Search the registry index.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails.
+    pub fn search(&self, query: &str, limit: usize) -> Result<SearchResults, ApiError> {
+        let request = self
+            .http_client
+            .get(self.url("packages"))
+            .query(&[("q", query), ("per_page", &limit.to_string())]);
+        self.send(request)
+    }
+
+    /// Look up a package and its versions.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ApiError::NotFound`] if there is no such package.
+    pub fn info(&self, name: &str) -> Result<PackageInfo, ApiError> {
+        let request = self.http_client.get(self.url(&format!("packages/{name}")));
+        self.send::<InfoResponse>(request).map(|r| r.package)
+    }
+
+    /// The users allowed to publish `name`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ApiError::NotFound`] if there is no such package.
+    pub fn owners(&self, name: &str) -> Result<Vec<User>, ApiError> {
+        let request = self
+            .http_client
+            .get(self.url(&format!("packages/{name}/owners")));
+        self.send::<OwnersResponse>(request).map(|r| r.users)
+    }
+
+    /// The user the token belongs to.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no token or the registry rejects it.
+    pub fn me(&self) -> Result<User, ApiError> {
+        let request = self.authorized(self.http_client.get(self.url("me")))?;
+        self.send::<MeResponse>(request).map(|r| r.user)
+    }
+
+    /// Check that the token's user may publish `name`.
+    ///
+    /// A package that does not exist yet may be published by anyone; the
+    /// publisher becomes its first owner.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ApiError::Unauthorized`] if the package belongs to others.
+    pub fn check_ownership(&self, name: &str) -> Result<(), ApiError> {
+        let user = self.me()?;
+        let owners = match self.owners(name) {
+            Ok(owners) => owners,
+            Err(ApiError::NotFound(_)) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        if owners.iter().any(|owner| owner.login == user.login) {
+            Ok(())
+        } else {
+            let names: Vec<_> = owners.iter().map(|o| o.login.as_str()).collect();
+            Err(ApiError::Unauthorized(format!(
+                "'{}' is not an owner of '{name}' (owners: {})",
+                user.login,
+                names.join(", ")
+            )))
+        }
+    }
+
+    /// Upload a new version.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no token or the registry rejects the
+    /// upload, for example because the version already exists.
+    pub fn publish(
+        &self,
+        metadata: &PublishMetadata,
+        tarball: &[u8],
+    ) -> Result<PublishWarnings, ApiError> {
+        let body = encode_publish_body(metadata, tarball)?;
+        let request = self.authorized(
+            self.http_client
+                .put(self.url("packages/new"))
+                .header("Content-Type", "application/octet-stream")
+                .body(body),
+        )?;
+        self.send(request)
+    }
+
+    /// Yank a version so that new lockfiles no longer select it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no token or the registry rejects it.
+    pub fn yank(&self, name: &str, version: &str) -> Result<(), ApiError> {
+        let request = self.authorized(
+            self.http_client
+                .delete(self.url(&format!("packages/{name}/{version}/yank"))),
+        )?;
+        self.send::<serde_json::Value>(request).map(|_| ())
+    }
+
+    /// Undo [`RegistryApi::yank`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no token or the registry rejects it.
+    pub fn unyank(&self, name: &str, version: &str) -> Result<(), ApiError> {
+        let request = self.authorized(
+            self.http_client
+                .put(self.url(&format!("packages/{name}/{version}/unyank"))),
+        )?;
+        self.send::<serde_json::Value>(request).map(|_| ())
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/api/v1/{path}", self.base_url)
+    }
+
+    fn authorized(
+        &self,
+        request: reqwest::blocking::RequestBuilder,
+    ) -> Result<reqwest::blocking::RequestBuilder, ApiError> {
+        let token = self.token.as_ref().ok_or(ApiError::MissingToken)?;
+        Ok(request.header("Authorization", token))
+    }
+
+    fn send<T: serde::de::DeserializeOwned>(
+        &self,
+        request: reqwest::blocking::RequestBuilder,
+    ) -> Result<T, ApiError> {
+        let response = request
+            .header("Accept", "application/json")
+            .send()
+            .map_err(|e| ApiError::Network(e.to_string()))?;
+        let status = response.status();
+        let body = response
+            .text()
+            .map_err(|e| ApiError::Network(e.to_string()))?;
+        if !status.is_success() {
+            return Err(error_from_response(status.as_u16(), &body));
+        }
+        serde_json::from_str(&body).map_err(|e| ApiError::InvalidResponse(e.to_string()))
+    }
+}
+
+/// Frame a publish request: the metadata JSON and the tarball, each preceded
+/// by its length as a little-endian `u32`.
+fn encode_publish_body(metadata: &PublishMetadata, tarball: &[u8]) -> Result<Vec<u8>, ApiError> {
+    let json =
+        serde_json::to_vec(metadata).map_err(|e| ApiError::InvalidResponse(e.to_string()))?;
+    let mut body = Vec::with_capacity(json.len() + tarball.len() + 8);
+    for part in [json.as_slice(), tarball] {
+        let len = u32::try_from(part.len()).map_err(|_| {
+            ApiError::Network(format!("upload part of {} bytes is too large", part.len()))
+        })?;
+        body.extend_from_slice(&len.to_le_bytes());
+        body.extend_from_slice(part);
+    }
+    Ok(body)
+}
+
+/// Map an error status and body to an [`ApiError`].
+fn error_from_response(status: u16, body: &str) -> ApiError {
+    let detail = serde_json::from_str::<ErrorResponse>(body)
+        .ok()
+        .map(|r| {
+            r.errors
+                .into_iter()
+                .map(|e| e.detail)
+                .collect::<Vec<_>>()
+                .join("; ")
+        })
+        .filter(|detail| !detail.is_empty())
+        .unwrap_or_else(|| format!("status {status}"));
+    match status {
+        401 | 403 => ApiError::Unauthorized(detail),
+        404 => ApiError::NotFound(detail),
+        _ => ApiError::Registry { status, detail },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    /// Answer one request with `status` and `body`. Returns the server URL
+    /// and a handle yielding the request line and headers.
+    fn serve_once(
+        status: &'static str,
+        body: &'static str,
+    ) -> (String, std::thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut head = String::new();
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if let Some(len) = line.to_lowercase().strip_prefix("content-length:") {
+                    content_length = len.trim().parse().unwrap();
+                }
+                if line == "\r\n" {
+                    break;
+                }
+                head.push_str(&line);
+            }
+            let mut body_in = vec![0; content_length];
+            reader.read_exact(&mut body_in).unwrap();
+            let mut stream = stream;
+            write!(
+                stream,
+                "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .unwrap();
+            head
+        });
+        (url, handle)
+    }
+
+    fn client() -> reqwest::blocking::Client {
+        reqwest::blocking::Client::new()
+    }
+
+    #[test]
+    fn test_encode_publish_body() {
+        let metadata = PublishMetadata {
+            name: "http".to_string(),
+            vers: "1.0.0".to_string(),
+            ..PublishMetadata::default()
+        };
+        let body = encode_publish_body(&metadata, b"tarball").unwrap();
+
+        let json_len = u32::from_le_bytes(body[..4].try_into().unwrap()) as usize;
+        let json: PublishMetadata = serde_json::from_slice(&body[4..4 + json_len]).unwrap();
+        assert_eq!(json, metadata);
+        let rest = &body[4 + json_len..];
+        assert_eq!(u32::from_le_bytes(rest[..4].try_into().unwrap()), 7);
+        assert_eq!(&rest[4..], b"tarball");
+    }
+
+    #[test]
+    fn test_error_from_response() {
+        let body = r#"{"errors":[{"detail":"version 1.0.0 already exists"}]}"#;
+        match error_from_response(400, body) {
+            ApiError::Registry { status, detail } => {
+                assert_eq!(status, 400);
+                assert_eq!(detail, "version 1.0.0 already exists");
+            }
+            e => panic!("unexpected error {e:?}"),
+        }
+        assert!(matches!(
+            error_from_response(403, "forbidden"),
+            ApiError::Unauthorized(detail) if detail == "status 403"
+        ));
+        assert!(matches!(
+            error_from_response(404, "{}"),
+            ApiError::NotFound(_)
+        ));
+    }
+
+    #[test]
+    fn test_package_info_latest_skips_yanked() {
+        let info: InfoResponse = serde_json::from_str(
+            r#"{"package": {"name": "http", "versions": [
+                {"num": "1.1.0", "yanked": true},
+                {"num": "1.0.0"}
+            ]}}"#,
+        )
+        .unwrap();
+        assert_eq!(info.package.latest().unwrap().num, "1.0.0");
+        assert_eq!(info.package.description, None);
+    }
+
+    #[test]
+    fn test_search_request() {
+        let (url, server) = serve_once(
+            "200 OK",
+            r#"{"packages": [{"name": "http", "max_version": "1.2.0", "description": "HTTP client"}], "total": 1}"#,
+        );
+        let api = RegistryApi::new(&format!("{url}/"), client());
+        let results = api.search("http client", 5).unwrap();
+        assert_eq!(results.total, 1);
+        assert_eq!(results.packages[0].name, "http");
+        assert_eq!(results.packages[0].max_version, "1.2.0");
+
+        let head = server.join().unwrap();
+        assert!(
+            head.starts_with("GET /api/v1/packages?q=http+client&per_page=5 "),
+            "{head}"
+        );
+    }
+
+    #[test]
+    fn test_yank_sends_token() {
+        let (url, server) = serve_once("200 OK", r#"{"ok": true}"#);
+        let api = RegistryApi::new(&url, client()).with_token(Some("secret".to_string()));
+        api.yank("http", "1.0.0").unwrap();
+
+        let head = server.join().unwrap();
+        assert!(head.starts_with("DELETE /api/v1/packages/http/1.0.0/yank "));
+        assert!(head.to_lowercase().contains("authorization: secret"));
+
+        let anonymous = RegistryApi::new(&url, client());
+        assert!(matches!(
+            anonymous.unyank("http", "1.0.0"),
+            Err(ApiError::MissingToken)
+        ));
+    }
+}
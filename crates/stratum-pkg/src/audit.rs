@@ -0,0 +1,372 @@
+//! Checking locked dependencies against an advisory database.
+//!
+//! The database is a JSON document listing advisories:
+//!
+//! ```json
+//! {
+//!   "advisories": [
+//!     {
+//!       "id": "STRAT-2025-0001",
+//!       "package": "http",
+//!       "kind": "vulnerability",
+//!       "severity": "high",
+//!       "title": "Header injection through unvalidated names",
+//!       "url": "https://example.com/advisories/STRAT-2025-0001",
+//!       "affected": "<1.2.3",
+//!       "patched": [">=1.2.3"]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! `affected` and each `patched` entry are version requirements; an advisory
+//! without `affected` applies to every version not matched by `patched`.
+
+use crate::Lockfile;
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Errors that can occur when auditing.
+#[derive(Error, Debug)]
+pub enum AuditError {
+    /// Failed to read the database.
+    #[error("failed to read advisory database: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// The database is not valid JSON of the expected shape.
+    #[error("invalid advisory database: {0}")]
+    Parse(String),
+
+    /// An advisory has a version requirement that does not parse.
+    #[error("advisory {id} has invalid version requirement '{requirement}'")]
+    InvalidRequirement { id: String, requirement: String },
+}
+
+/// What an advisory reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AdvisoryKind {
+    /// A security vulnerability.
+    Vulnerability,
+    /// The package is no longer maintained.
+    Unmaintained,
+    /// The version was withdrawn by its authors.
+    Yanked,
+}
+
+impl AdvisoryKind {
+    /// The name used in the database and in reports.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Vulnerability => "vulnerability",
+            Self::Unmaintained => "unmaintained",
+            Self::Yanked => "yanked",
+        }
+    }
+}
+
+/// How severe a vulnerability is, ordered from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Hard to exploit or of little impact.
+    Low,
+    /// Exploitable under some conditions.
+    Medium,
+    /// Exploitable with significant impact.
+    High,
+    /// Remotely exploitable with severe impact.
+    Critical,
+}
+
+impl Severity {
+    /// The name used in the database and in reports.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+            Self::Critical => "critical",
+        }
+    }
+}
+
+/// One entry of the advisory database.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Advisory {
+    /// Identifier, such as `STRAT-2025-0001`.
+    pub id: String,
+    /// Affected package.
+    pub package: String,
+    /// What the advisory reports.
+    pub kind: AdvisoryKind,
+    /// Severity, for vulnerabilities.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub severity: Option<Severity>,
+    /// One-line summary.
+    pub title: String,
+    /// Link to the full advisory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Versions affected; all versions if absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub affected: Option<String>,
+    /// Versions with the problem fixed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub patched: Vec<String>,
+}
+
+impl Advisory {
+    /// Whether `version` has the problem.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a version requirement does not parse.
+    pub fn affects(&self, version: &Version) -> Result<bool, AuditError> {
+        let parse = |requirement: &str| {
+            VersionReq::parse(requirement).map_err(|_| AuditError::InvalidRequirement {
+                id: self.id.clone(),
+                requirement: requirement.to_string(),
+            })
+        };
+        for patched in &self.patched {
+            if parse(patched)?.matches(version) {
+                return Ok(false);
+            }
+        }
+        match &self.affected {
+            Some(affected) => Ok(parse(affected)?.matches(version)),
+            None => Ok(true),
+        }
+    }
+}
+
+/// A set of advisories.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AdvisoryDatabase {
+    /// All advisories.
+    #[serde(default)]
+    pub advisories: Vec<Advisory>,
+}
+
+impl AdvisoryDatabase {
+    /// Parse a database from JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JSON is invalid.
+    pub fn parse(content: &str) -> Result<Self, AuditError> {
+        serde_json::from_str(content).map_err(|e| AuditError::Parse(e.to_string()))
+    }
+
+    /// The default file a downloaded database is cached in.
+    #[must_use]
+    pub fn default_cache_path() -> PathBuf {
+        crate::registry::dirs_cache_dir()
+            .join("stratum")
+            .join("advisory-db.json")
+    }
+
+    /// Load a database from a file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, AuditError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+}
+
+/// A locked package an advisory applies to.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Finding {
+    /// Package name.
+    pub package: String,
+    /// Locked version.
+    pub version: String,
+    /// The advisory that applies.
+    pub advisory: Advisory,
+}
+
+impl Finding {
+    /// Whether the finding should fail an audit: vulnerabilities do,
+    /// unmaintained and yanked packages are warnings.
+    #[must_use]
+    pub fn is_error(&self) -> bool {
+        self.advisory.kind == AdvisoryKind::Vulnerability
+    }
+}
+
+/// The version of a locked package to audit.
+///
+/// Registry packages without a resolved version record the requirement
+/// instead; the lowest version it accepts is audited, so `"1.2"` and `"^1.2"`
+/// are checked as `1.2.0`. GitHub tags may carry a `v` prefix. Path packages
+/// have no version and are skipped.
+#[must_use]
+pub fn locked_version(version: &str) -> Option<Version> {
+    let version = version
+        .trim()
+        .trim_start_matches(['^', '~', '=', 'v'])
+        .trim();
+    if let Ok(version) = Version::parse(version) {
+        return Some(version);
+    }
+    let mut parts: Vec<&str> = version.split('.').collect();
+    if parts.is_empty() || parts.len() > 3 || parts.iter().any(|p| p.parse::<u64>().is_err()) {
+        return None;
+    }
+    parts.resize(3, "0");
+    Version::parse(&parts.join(".")).ok()
+}
+
+/// Find the advisories that apply to the packages in `lockfile`.
+///
+/// Findings are sorted with errors first, then by severity, package and
+/// advisory id.
+///
+/// # Errors
+///
+/// Returns an error if an applicable advisory has an invalid requirement.
+pub fn audit(lockfile: &Lockfile, database: &AdvisoryDatabase) -> Result<Vec<Finding>, AuditError> {
+    let mut findings = Vec::new();
+    for package in &lockfile.packages {
+        if package.source == "path" {
+            continue;
+        }
+        let Some(locked) = package.version.as_deref() else {
+            continue;
+        };
+        let Some(version) = locked_version(locked) else {
+            tracing::debug!(package = %package.name, version = locked, "unparseable version");
+            continue;
+        };
+        for advisory in database
+            .advisories
+            .iter()
+            .filter(|a| a.package == package.name)
+        {
+            if advisory.affects(&version)? {
+                findings.push(Finding {
+                    package: package.name.clone(),
+                    version: locked.to_string(),
+                    advisory: advisory.clone(),
+                });
+            }
+        }
+    }
+    findings.sort_by(|a, b| {
+        b.is_error()
+            .cmp(&a.is_error())
+            .then(b.advisory.severity.cmp(&a.advisory.severity))
+            .then(a.package.cmp(&b.package))
+            .then(a.advisory.id.cmp(&b.advisory.id))
+    });
+    Ok(findings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATABASE: &str = r#"{
+        "advisories": [
+            {
+                "id": "STRAT-2025-0001",
+                "package": "http",
+                "kind": "vulnerability",
+                "severity": "high",
+                "title": "Header injection",
+                "affected": "<1.2.3",
+                "patched": [">=1.2.3"]
+            },
+            {
+                "id": "STRAT-2025-0002",
+                "package": "http",
+                "kind": "vulnerability",
+                "severity": "critical",
+                "title": "Request smuggling",
+                "affected": ">=1.0.0, <1.1.0"
+            },
+            {
+                "id": "STRAT-2025-0003",
+                "package": "yaml",
+                "kind": "unmaintained",
+                "title": "No longer maintained; use toml"
+            }
+        ]
+    }"#;
+
+    fn lockfile(packages: &[(&str, &str, &str)]) -> Lockfile {
+        let mut content = String::from("version = 1\n");
+        for (name, version, source) in packages {
+            content.push_str(&format!(
+                "\n[[package]]\nname = \"{name}\"\nversion = \"{version}\"\nsource = \"{source}\"\n"
+            ));
+        }
+        Lockfile::parse(&content).unwrap()
+    }
+
+    #[test]
+    fn test_advisory_affects() {
+        let db = AdvisoryDatabase::parse(DATABASE).unwrap();
+        let injection = &db.advisories[0];
+        assert!(injection.affects(&Version::new(1, 2, 2)).unwrap());
+        assert!(!injection.affects(&Version::new(1, 2, 3)).unwrap());
+        assert!(!injection.affects(&Version::new(2, 0, 0)).unwrap());
+        assert!(db.advisories[2].affects(&Version::new(0, 9, 0)).unwrap());
+
+        let mut invalid = injection.clone();
+        invalid.affected = Some("soon".to_string());
+        assert!(matches!(
+            invalid.affects(&Version::new(1, 0, 0)),
+            Err(AuditError::InvalidRequirement { .. })
+        ));
+    }
+
+    #[test]
+    fn test_locked_version() {
+        assert_eq!(locked_version("1.2.3"), Some(Version::new(1, 2, 3)));
+        assert_eq!(locked_version("v1.2.3"), Some(Version::new(1, 2, 3)));
+        assert_eq!(locked_version("^1.2"), Some(Version::new(1, 2, 0)));
+        assert_eq!(locked_version("1"), Some(Version::new(1, 0, 0)));
+        assert_eq!(locked_version("main"), None);
+        assert_eq!(locked_version(">=1, <2"), None);
+    }
+
+    #[test]
+    fn test_audit_lockfile() {
+        let db = AdvisoryDatabase::parse(DATABASE).unwrap();
+        let lock = lockfile(&[
+            ("http", "1.0.5", "registry"),
+            ("yaml", "0.4", "registry"),
+            ("json", "2.0.0", "registry"),
+            ("util", "0.1.0", "path"),
+        ]);
+        let findings = audit(&lock, &db).unwrap();
+        let ids: Vec<_> = findings.iter().map(|f| f.advisory.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            ["STRAT-2025-0002", "STRAT-2025-0001", "STRAT-2025-0003"]
+        );
+        assert!(findings[0].is_error());
+        assert!(!findings[2].is_error());
+        assert_eq!(findings[2].version, "0.4");
+
+        let patched = lockfile(&[("http", "1.2.3", "registry")]);
+        assert!(audit(&patched, &db).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_database() {
+        assert!(matches!(
+            AdvisoryDatabase::parse("{\"advisories\": [{\"id\": 1}]}"),
+            Err(AuditError::Parse(_))
+        ));
+        assert!(AdvisoryDatabase::parse("{}").unwrap().advisories.is_empty());
+    }
+}
@@ -0,0 +1,394 @@
+//! Build scripts.
+//!
+//! A package may have a build script, `build.strat` at its root or the file
+//! named by `build` in `[package]`, which runs before the package is
+//! compiled. It runs with the package root as its working directory and
+//! these variables set:
+//!
+//! - `OUT_DIR`: a directory for generated files, kept between builds
+//! - `STRATUM_MANIFEST_DIR`: the package root
+//! - `STRATUM_PKG_NAME` and `STRATUM_PKG_VERSION`
+//! - `STRATUM_PROFILE`: `debug` or `release`
+//!
+//! The script talks back by printing directives to stdout:
+//!
+//! ```text
+//! stratum:env=API_URL=https://example.com
+//! stratum:rerun-if-changed=schema/api.json
+//! stratum:rerun-if-env-changed=API_TOKEN
+//! stratum:warning=schema is out of date
+//! ```
+//!
+//! `env` sets a variable in the environment of the compiled program, where
+//! `Env.get` reads it. A script that declares no `rerun-if-changed` paths
+//! runs on every build; otherwise its output is reused until the script,
+//! the manifest, a declared path or a declared variable changes.
+
+use crate::{Manifest, PackageLayout, TargetDir};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// The default build script file name.
+pub const BUILD_SCRIPT: &str = "build.strat";
+
+/// The prefix of build script directives.
+const DIRECTIVE_PREFIX: &str = "stratum:";
+
+/// File in the build directory holding the last script output.
+const OUTPUT_FILE: &str = "output";
+
+/// File in the build directory holding the fingerprint of the last run.
+const FINGERPRINT_FILE: &str = "fingerprint";
+
+/// Errors that can occur with build scripts.
+#[derive(Error, Debug)]
+pub enum BuildScriptError {
+    /// The script named in the manifest does not exist.
+    #[error("build script '{0}' not found")]
+    NotFound(PathBuf),
+
+    /// A `stratum:` line is not a known directive.
+    #[error("invalid build script directive on line {line}: {message}")]
+    InvalidDirective { line: usize, message: String },
+
+    /// Failed to read or write the build directory.
+    #[error("build script I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// What a build script asked for, parsed from its stdout.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BuildOutput {
+    /// Variables to set in the compiled program's environment.
+    pub env: BTreeMap<String, String>,
+    /// Paths, relative to the package root, that make the script rerun.
+    pub rerun_if_changed: Vec<PathBuf>,
+    /// Variables whose value makes the script rerun.
+    pub rerun_if_env_changed: Vec<String>,
+    /// Warnings to show the user.
+    pub warnings: Vec<String>,
+}
+
+impl BuildOutput {
+    /// Parse the directives in a script's stdout; other lines are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for an unknown directive or a malformed `env`.
+    pub fn parse(stdout: &str) -> Result<Self, BuildScriptError> {
+        let mut output = Self::default();
+        for (index, line) in stdout.lines().enumerate() {
+            let Some(directive) = line.trim_end().strip_prefix(DIRECTIVE_PREFIX) else {
+                continue;
+            };
+            let invalid = |message: String| BuildScriptError::InvalidDirective {
+                line: index + 1,
+                message,
+            };
+            let (name, value) = directive
+                .split_once('=')
+                .ok_or_else(|| invalid(format!("expected `stratum:NAME=VALUE`, got `{line}`")))?;
+            match name {
+                "env" => {
+                    let (key, value) = value
+                        .split_once('=')
+                        .filter(|(key, _)| !key.is_empty())
+                        .ok_or_else(|| {
+                            invalid(format!("expected `env=KEY=VALUE`, got `{value}`"))
+                        })?;
+                    output.env.insert(key.to_string(), value.to_string());
+                }
+                "rerun-if-changed" => output.rerun_if_changed.push(PathBuf::from(value)),
+                "rerun-if-env-changed" => output.rerun_if_env_changed.push(value.to_string()),
+                "warning" => output.warnings.push(value.to_string()),
+                _ => return Err(invalid(format!("unknown directive `{name}`"))),
+            }
+        }
+        Ok(output)
+    }
+
+    /// The environment as pairs, for passing on to a linker or process.
+    #[must_use]
+    pub fn env_pairs(&self) -> Vec<(String, String)> {
+        self.env
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect()
+    }
+}
+
+/// A package's build script and the directories it runs with.
+#[derive(Debug, Clone)]
+pub struct BuildScript {
+    /// The script file.
+    pub script: PathBuf,
+    /// The package root.
+    pub root: PathBuf,
+    /// `target/build/<package>`, holding the out dir and cached output.
+    pub build_dir: PathBuf,
+    /// Package name.
+    pub name: String,
+    /// Package version.
+    pub version: String,
+}
+
+impl BuildScript {
+    /// The build script of the package at `layout`, if it has one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the manifest names a script that does not exist.
+    pub fn find(
+        layout: &PackageLayout,
+        manifest: &Manifest,
+    ) -> Result<Option<Self>, BuildScriptError> {
+        let script = match &manifest.package.build {
+            Some(path) => {
+                let script = layout.root.join(path);
+                if !script.is_file() {
+                    return Err(BuildScriptError::NotFound(script));
+                }
+                script
+            }
+            None => {
+                let script = layout.root.join(BUILD_SCRIPT);
+                if !script.is_file() {
+                    return Ok(None);
+                }
+                script
+            }
+        };
+        Ok(Some(Self {
+            script,
+            root: layout.root.clone(),
+            build_dir: TargetDir::for_package(&layout.root)
+                .build()
+                .join(&manifest.package.name),
+            name: manifest.package.name.clone(),
+            version: manifest.package.version.clone(),
+        }))
+    }
+
+    /// The directory the script writes generated files to.
+    #[must_use]
+    pub fn out_dir(&self) -> PathBuf {
+        self.build_dir.join("out")
+    }
+
+    /// The variables the script runs with.
+    #[must_use]
+    pub fn env(&self, release: bool) -> Vec<(&'static str, String)> {
+        vec![
+            ("OUT_DIR", self.out_dir().display().to_string()),
+            ("STRATUM_MANIFEST_DIR", self.root.display().to_string()),
+            ("STRATUM_PKG_NAME", self.name.clone()),
+            ("STRATUM_PKG_VERSION", self.version.clone()),
+            ("STRATUM_PROFILE", profile(release).to_string()),
+        ]
+    }
+
+    /// The output of the last run, if nothing it depends on has changed.
+    ///
+    /// Output without `rerun-if-changed` paths is never reused.
+    #[must_use]
+    pub fn cached(&self, release: bool) -> Option<BuildOutput> {
+        let stdout = std::fs::read_to_string(self.build_dir.join(OUTPUT_FILE)).ok()?;
+        let saved = std::fs::read_to_string(self.build_dir.join(FINGERPRINT_FILE)).ok()?;
+        let output = BuildOutput::parse(&stdout).ok()?;
+        if output.rerun_if_changed.is_empty() || saved != self.fingerprint(&output, release) {
+            return None;
+        }
+        Some(output)
+    }
+
+    /// Record a run's stdout, and its parsed `output`, for [`Self::cached`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the build directory cannot be written.
+    pub fn save(
+        &self,
+        stdout: &str,
+        output: &BuildOutput,
+        release: bool,
+    ) -> Result<(), BuildScriptError> {
+        std::fs::create_dir_all(&self.build_dir)?;
+        std::fs::write(self.build_dir.join(OUTPUT_FILE), stdout)?;
+        std::fs::write(
+            self.build_dir.join(FINGERPRINT_FILE),
+            self.fingerprint(output, release),
+        )?;
+        Ok(())
+    }
+
+    /// A hash of everything `output` says the script depends on.
+    fn fingerprint(&self, output: &BuildOutput, release: bool) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(profile(release));
+        for file in [self.script.clone(), self.root.join(crate::MANIFEST_FILE)] {
+            hasher.update(std::fs::read(file).unwrap_or_default());
+        }
+        for path in &output.rerun_if_changed {
+            hasher.update(path.to_string_lossy().as_bytes());
+            hash_path(&mut hasher, &self.root.join(path));
+        }
+        for name in &output.rerun_if_env_changed {
+            hasher.update(name);
+            hasher.update(std::env::var(name).unwrap_or_default());
+        }
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+/// The profile name for a build.
+fn profile(release: bool) -> &'static str {
+    if release {
+        "release"
+    } else {
+        "debug"
+    }
+}
+
+/// Hash a file's modification time and size, or a directory's entries.
+fn hash_path(hasher: &mut Sha256, path: &Path) {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        hasher.update("missing");
+        return;
+    };
+    if metadata.is_dir() {
+        let mut entries: Vec<PathBuf> = std::fs::read_dir(path)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .collect();
+        entries.sort();
+        for entry in entries {
+            hasher.update(entry.to_string_lossy().as_bytes());
+            hash_path(hasher, &entry);
+        }
+    } else {
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        hasher.update(modified.to_le_bytes());
+        hasher.update(metadata.len().to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(dir: &Path, build: Option<&str>) -> (PackageLayout, Manifest) {
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(dir.join("src").join("main.strat"), "fx main() {}").unwrap();
+        let build = build
+            .map(|path| format!("build = \"{path}\"\n"))
+            .unwrap_or_default();
+        std::fs::write(
+            dir.join("stratum.toml"),
+            format!("[package]\nname = \"app\"\nversion = \"0.2.0\"\nedition = \"2025\"\n{build}"),
+        )
+        .unwrap();
+        let layout = PackageLayout::discover(dir).unwrap();
+        let manifest = Manifest::from_path(&layout.manifest_path).unwrap();
+        (layout, manifest)
+    }
+
+    #[test]
+    fn test_parse_directives() {
+        let output = BuildOutput::parse(
+            "generating bindings\n\
+             stratum:env=API_URL=https://example.com/?a=b\n\
+             stratum:rerun-if-changed=schema.json\n\
+             stratum:rerun-if-env-changed=API_TOKEN\n\
+             stratum:warning=schema is old\n",
+        )
+        .unwrap();
+        assert_eq!(output.env["API_URL"], "https://example.com/?a=b");
+        assert_eq!(output.rerun_if_changed, [PathBuf::from("schema.json")]);
+        assert_eq!(output.rerun_if_env_changed, ["API_TOKEN"]);
+        assert_eq!(output.warnings, ["schema is old"]);
+        assert_eq!(
+            output.env_pairs(),
+            [(
+                "API_URL".to_string(),
+                "https://example.com/?a=b".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_directives() {
+        for stdout in [
+            "ok\nstratum:link=foo",
+            "stratum:env=NOVALUE",
+            "stratum:env==x",
+            "stratum:warning",
+        ] {
+            assert!(
+                matches!(
+                    BuildOutput::parse(stdout),
+                    Err(BuildScriptError::InvalidDirective { .. })
+                ),
+                "{stdout}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_find_build_script() {
+        let dir = tempfile::tempdir().unwrap();
+        let (layout, manifest) = package(dir.path(), None);
+        assert!(BuildScript::find(&layout, &manifest).unwrap().is_none());
+
+        std::fs::write(dir.path().join(BUILD_SCRIPT), "").unwrap();
+        let script = BuildScript::find(&layout, &manifest).unwrap().unwrap();
+        assert_eq!(script.script, dir.path().join(BUILD_SCRIPT));
+        assert_eq!(script.out_dir(), dir.path().join("target/build/app/out"));
+        let env = script.env(true);
+        assert!(env.contains(&("STRATUM_PKG_VERSION", "0.2.0".to_string())));
+        assert!(env.contains(&("STRATUM_PROFILE", "release".to_string())));
+
+        let (layout, manifest) = package(dir.path(), Some("tools/gen.strat"));
+        assert!(matches!(
+            BuildScript::find(&layout, &manifest),
+            Err(BuildScriptError::NotFound(_))
+        ));
+        std::fs::create_dir_all(dir.path().join("tools")).unwrap();
+        std::fs::write(dir.path().join("tools/gen.strat"), "").unwrap();
+        let script = BuildScript::find(&layout, &manifest).unwrap().unwrap();
+        assert!(script.script.ends_with("tools/gen.strat"));
+    }
+
+    #[test]
+    fn test_cached_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let (layout, manifest) = package(dir.path(), None);
+        std::fs::write(dir.path().join(BUILD_SCRIPT), "").unwrap();
+        std::fs::write(dir.path().join("schema.json"), "{}").unwrap();
+        let script = BuildScript::find(&layout, &manifest).unwrap().unwrap();
+        assert!(script.cached(false).is_none());
+
+        // Without rerun-if-changed the script always reruns
+        let stdout = "stratum:env=A=1\n";
+        script
+            .save(stdout, &BuildOutput::parse(stdout).unwrap(), false)
+            .unwrap();
+        assert!(script.cached(false).is_none());
+
+        let stdout = "stratum:env=A=1\nstratum:rerun-if-changed=schema.json\n";
+        let output = BuildOutput::parse(stdout).unwrap();
+        script.save(stdout, &output, false).unwrap();
+        assert_eq!(script.cached(false), Some(output));
+        assert!(script.cached(true).is_none());
+
+        std::fs::write(dir.path().join("schema.json"), "{\"changed\": true}").unwrap();
+        assert!(script.cached(false).is_none());
+    }
+}
@@ -6,13 +6,25 @@
 //! - Workspace support for multi-package projects
 //! - Dependency resolution and conflict detection
 //! - Lock file support for reproducible builds
+//! - Auditing locked dependencies against an advisory database
+//! - License policies and SPDX reports
+//! - Inline dependencies for single-file scripts
+//! - Build scripts that run before compilation
+//! - The `target` directory of build artifacts
 //! - GitHub-based package registry support
+//! - A client for the package registry HTTP API
 
+pub mod api;
+pub mod audit;
+pub mod build_script;
+pub mod license;
 mod lockfile;
 mod manifest;
 mod package;
 pub mod registry;
 mod resolve;
+mod script;
+mod target;
 mod workspace;
 
 pub use lockfile::{LockError, LockedPackage, Lockfile, LOCK_FILE};
@@ -24,7 +36,10 @@ pub use package::{
     SOURCE_DIR, SOURCE_EXT, TESTS_DIR,
 };
 pub use resolve::{
-    matches_version, DependencySection, DependencySource, GitReference, ResolveError,
-    ResolvedDependencies, ResolvedDependency, Resolver, VersionRequirement,
+    find_duplicates, matches_version, minimal_version, DependencySection, DependencySource,
+    DuplicateDependency, GitReference, ResolutionStrategy, ResolveError, ResolvedDependencies,
+    ResolvedDependency, Resolver, VersionRequirement,
 };
+pub use script::{ScriptDependencies, ScriptEnvironment, ScriptError};
+pub use target::{Artifacts, CleanSummary, TargetDir, TARGET_DIR_ENV};
 pub use workspace::{Workspace, WorkspaceManifest, WorkspaceMember};
@@ -0,0 +1,495 @@
+//! License policies and SPDX reports.
+//!
+//! A package lists the licenses it accepts from its dependencies in a
+//! `[licenses]` table, or a workspace for all its members in
+//! `[workspace.licenses]`:
+//!
+//! ```toml
+//! [licenses]
+//! allow = ["MIT", "Apache-2.0", "BSD-3-Clause"]
+//! deny = ["GPL-3.0-only"]
+//! unknown = "deny"
+//! ```
+//!
+//! Dependency licenses are SPDX expressions such as `MIT OR Apache-2.0`.
+//! An `OR` expression passes if any side does and an `AND` expression if
+//! both do.
+
+use crate::resolve::{DependencySource, ResolvedDependencies};
+use crate::Manifest;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use thiserror::Error;
+
+/// Errors that can occur when parsing license expressions.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum LicenseError {
+    /// The expression is not valid SPDX.
+    #[error("invalid license expression '{expression}': {reason}")]
+    InvalidExpression { expression: String, reason: String },
+}
+
+/// How to treat dependencies whose license is not known.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnknownLicense {
+    /// Accept them silently.
+    Allow,
+    /// Accept them with a warning.
+    #[default]
+    Warn,
+    /// Reject them.
+    Deny,
+}
+
+/// The licenses a package accepts from its dependencies.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LicensePolicy {
+    /// Licenses that are accepted; any license not denied if empty.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<String>,
+    /// Licenses that are rejected.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub deny: Vec<String>,
+    /// How to treat dependencies without a known license.
+    pub unknown: UnknownLicense,
+}
+
+/// A parsed SPDX license expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LicenseExpr {
+    /// A license identifier, such as `MIT`.
+    License(String),
+    /// A license with an exception: `GPL-2.0-only WITH Classpath-exception-2.0`.
+    With(String, String),
+    /// Both licenses apply.
+    And(Box<LicenseExpr>, Box<LicenseExpr>),
+    /// Either license may be chosen.
+    Or(Box<LicenseExpr>, Box<LicenseExpr>),
+}
+
+/// The outcome of checking one dependency against a policy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LicenseVerdict {
+    /// The license is accepted.
+    Allowed,
+    /// The license is rejected.
+    Denied(String),
+    /// The dependency has no known license.
+    Unknown,
+}
+
+/// A dependency and the verdict on its license.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LicenseCheck {
+    /// Dependency name.
+    pub name: String,
+    /// Its license expression, if known.
+    pub license: Option<String>,
+    /// The verdict.
+    pub verdict: LicenseVerdict,
+}
+
+impl LicenseExpr {
+    /// Parse an SPDX expression.
+    ///
+    /// `WITH` binds tighter than `AND`, which binds tighter than `OR`.
+    /// Operators may be lowercase, and the legacy `MIT/Apache-2.0` form is
+    /// read as `MIT OR Apache-2.0`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the expression is empty or malformed.
+    pub fn parse(expression: &str) -> Result<Self, LicenseError> {
+        let error = |reason: &str| LicenseError::InvalidExpression {
+            expression: expression.to_string(),
+            reason: reason.to_string(),
+        };
+        let spaced = expression
+            .replace('(', " ( ")
+            .replace(')', " ) ")
+            .replace('/', " OR ");
+        let tokens: Vec<&str> = spaced.split_whitespace().collect();
+        if tokens.is_empty() {
+            return Err(error("empty expression"));
+        }
+        let mut parser = ExprParser { tokens, pos: 0 };
+        let expr = parser.or().map_err(|reason| error(&reason))?;
+        if let Some(token) = parser.peek() {
+            return Err(error(&format!("unexpected '{token}'")));
+        }
+        Ok(expr)
+    }
+
+    /// Every license identifier in the expression.
+    #[must_use]
+    pub fn licenses(&self) -> Vec<&str> {
+        match self {
+            Self::License(id) | Self::With(id, _) => vec![id.as_str()],
+            Self::And(a, b) | Self::Or(a, b) => {
+                let mut ids = a.licenses();
+                ids.extend(b.licenses());
+                ids
+            }
+        }
+    }
+}
+
+impl fmt::Display for LicenseExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let group = |f: &mut fmt::Formatter<'_>, expr: &Self, parent_is_and: bool| {
+            if parent_is_and && matches!(expr, Self::Or(..)) {
+                write!(f, "({expr})")
+            } else {
+                write!(f, "{expr}")
+            }
+        };
+        match self {
+            Self::License(id) => write!(f, "{id}"),
+            Self::With(id, exception) => write!(f, "{id} WITH {exception}"),
+            Self::And(a, b) => {
+                group(f, a, true)?;
+                write!(f, " AND ")?;
+                group(f, b, true)
+            }
+            Self::Or(a, b) => write!(f, "{a} OR {b}"),
+        }
+    }
+}
+
+/// Recursive descent over expression tokens.
+struct ExprParser<'a> {
+    tokens: Vec<&'a str>,
+    pos: usize,
+}
+
+impl<'a> ExprParser<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, operator: &str) -> bool {
+        if self
+            .peek()
+            .is_some_and(|t| t.eq_ignore_ascii_case(operator))
+        {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn or(&mut self) -> Result<LicenseExpr, String> {
+        let mut expr = self.and()?;
+        while self.eat("OR") {
+            expr = LicenseExpr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<LicenseExpr, String> {
+        let mut expr = self.with()?;
+        while self.eat("AND") {
+            expr = LicenseExpr::And(Box::new(expr), Box::new(self.with()?));
+        }
+        Ok(expr)
+    }
+
+    fn with(&mut self) -> Result<LicenseExpr, String> {
+        if self.eat("(") {
+            let expr = self.or()?;
+            if !self.eat(")") {
+                return Err("missing ')'".to_string());
+            }
+            return Ok(expr);
+        }
+        let id = self.identifier()?;
+        if self.eat("WITH") {
+            let exception = self.identifier()?;
+            return Ok(LicenseExpr::With(id, exception));
+        }
+        Ok(LicenseExpr::License(id))
+    }
+
+    fn identifier(&mut self) -> Result<String, String> {
+        let Some(token) = self.peek() else {
+            return Err("expected a license".to_string());
+        };
+        let is_operator = ["AND", "OR", "WITH", "(", ")"]
+            .iter()
+            .any(|op| token.eq_ignore_ascii_case(op));
+        let is_valid = token
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '+' | ':'));
+        if is_operator || !is_valid {
+            return Err(format!("expected a license, found '{token}'"));
+        }
+        self.pos += 1;
+        Ok(token.to_string())
+    }
+}
+
+impl LicensePolicy {
+    /// Whether no policy is configured.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Check a license expression against the policy.
+    #[must_use]
+    pub fn evaluate(&self, license: Option<&str>) -> LicenseVerdict {
+        let Some(license) = license else {
+            return LicenseVerdict::Unknown;
+        };
+        let expr = match LicenseExpr::parse(license) {
+            Ok(expr) => expr,
+            Err(e) => return LicenseVerdict::Denied(e.to_string()),
+        };
+        match self.accepts(&expr) {
+            Ok(()) => LicenseVerdict::Allowed,
+            Err(reason) => LicenseVerdict::Denied(reason),
+        }
+    }
+
+    fn accepts(&self, expr: &LicenseExpr) -> Result<(), String> {
+        match expr {
+            LicenseExpr::License(id) => self.accepts_license(id, id),
+            LicenseExpr::With(id, exception) => {
+                self.accepts_license(id, &format!("{id} WITH {exception}"))
+            }
+            LicenseExpr::And(a, b) => self.accepts(a).and_then(|()| self.accepts(b)),
+            LicenseExpr::Or(a, b) => self.accepts(a).or_else(|_| self.accepts(b)),
+        }
+    }
+
+    /// Check a license by its identifier or, with an exception, its full
+    /// `id WITH exception` form.
+    fn accepts_license(&self, id: &str, full: &str) -> Result<(), String> {
+        let listed = |list: &[String]| {
+            list.iter()
+                .any(|l| l.eq_ignore_ascii_case(id) || l.eq_ignore_ascii_case(full))
+        };
+        if listed(&self.deny) {
+            return Err(format!("{full} is denied"));
+        }
+        if !self.allow.is_empty() && !listed(&self.allow) {
+            return Err(format!("{full} is not in the allow list"));
+        }
+        Ok(())
+    }
+
+    /// Check the license of every resolved dependency.
+    #[must_use]
+    pub fn check(&self, resolved: &ResolvedDependencies) -> Vec<LicenseCheck> {
+        resolved
+            .iter()
+            .map(|(name, dep)| LicenseCheck {
+                name: name.clone(),
+                license: dep.license.clone(),
+                verdict: self.evaluate(dep.license.as_deref()),
+            })
+            .collect()
+    }
+}
+
+/// Render an SPDX 2.3 tag-value document describing `manifest` and its
+/// resolved dependencies.
+///
+/// `created` is the creation time in RFC 3339 form, such as
+/// `2025-01-01T00:00:00Z`.
+#[must_use]
+pub fn spdx_report(manifest: &Manifest, resolved: &ResolvedDependencies, created: &str) -> String {
+    use std::fmt::Write;
+
+    let package = &manifest.package;
+    let root_id = spdx_id(&package.name);
+    let document = format!("{}-{}", package.name, package.version);
+
+    let mut out = String::new();
+    let _ = writeln!(out, "SPDXVersion: SPDX-2.3");
+    let _ = writeln!(out, "DataLicense: CC0-1.0");
+    let _ = writeln!(out, "SPDXID: SPDXRef-DOCUMENT");
+    let _ = writeln!(out, "DocumentName: {document}");
+    let _ = writeln!(
+        out,
+        "DocumentNamespace: https://spdx.org/spdxdocs/{document}"
+    );
+    let _ = writeln!(out, "Creator: Tool: stratum-{}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(out, "Created: {created}");
+
+    let mut packages = vec![(
+        package.name.as_str(),
+        Some(package.version.clone()),
+        package.repository.clone(),
+        package.license.clone(),
+    )];
+    for (name, dep) in resolved.iter() {
+        let (version, location) = match &dep.source {
+            DependencySource::Registry { version_req } => (Some(version_req.to_string()), None),
+            DependencySource::Path { .. } => (None, None),
+            DependencySource::Git { url, .. } => (None, Some(url.clone())),
+        };
+        packages.push((name.as_str(), version, location, dep.license.clone()));
+    }
+
+    for (name, version, location, license) in &packages {
+        let license = license.as_deref().unwrap_or("NOASSERTION");
+        let _ = writeln!(out, "\nPackageName: {name}");
+        let _ = writeln!(out, "SPDXID: {}", spdx_id(name));
+        if let Some(version) = version {
+            let _ = writeln!(out, "PackageVersion: {version}");
+        }
+        let _ = writeln!(
+            out,
+            "PackageDownloadLocation: {}",
+            location.as_deref().unwrap_or("NOASSERTION")
+        );
+        let _ = writeln!(out, "FilesAnalyzed: false");
+        let _ = writeln!(out, "PackageLicenseConcluded: {license}");
+        let _ = writeln!(out, "PackageLicenseDeclared: {license}");
+        let _ = writeln!(out, "PackageCopyrightText: NOASSERTION");
+    }
+
+    let _ = writeln!(out, "\nRelationship: SPDXRef-DOCUMENT DESCRIBES {root_id}");
+    for (name, _) in resolved.iter() {
+        let _ = writeln!(out, "Relationship: {root_id} DEPENDS_ON {}", spdx_id(name));
+    }
+    out
+}
+
+/// An SPDX element id for a package; ids allow letters, digits, `.` and `-`.
+fn spdx_id(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!("SPDXRef-Package-{name}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Resolver;
+
+    fn policy(toml: &str) -> LicensePolicy {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn test_parse_expression() {
+        assert_eq!(
+            LicenseExpr::parse("MIT").unwrap(),
+            LicenseExpr::License("MIT".to_string())
+        );
+        let expr = LicenseExpr::parse("MIT OR Apache-2.0 AND BSD-3-Clause").unwrap();
+        assert!(matches!(expr, LicenseExpr::Or(..)));
+        assert_eq!(expr.licenses(), ["MIT", "Apache-2.0", "BSD-3-Clause"]);
+
+        let expr =
+            LicenseExpr::parse("(MIT or Apache-2.0) AND GPL-2.0-only WITH Classpath-exception-2.0")
+                .unwrap();
+        assert_eq!(
+            expr.to_string(),
+            "(MIT OR Apache-2.0) AND GPL-2.0-only WITH Classpath-exception-2.0"
+        );
+        assert_eq!(
+            LicenseExpr::parse("MIT/Apache-2.0").unwrap().to_string(),
+            "MIT OR Apache-2.0"
+        );
+
+        assert!(LicenseExpr::parse("").is_err());
+        assert!(LicenseExpr::parse("MIT OR").is_err());
+        assert!(LicenseExpr::parse("(MIT").is_err());
+        assert!(LicenseExpr::parse("MIT Apache-2.0").is_err());
+        assert!(LicenseExpr::parse("MIT, Apache-2.0").is_err());
+    }
+
+    #[test]
+    fn test_policy_evaluate() {
+        let p = policy("allow = [\"MIT\", \"Apache-2.0\"]\ndeny = [\"GPL-3.0-only\"]\n");
+        assert_eq!(p.evaluate(Some("MIT")), LicenseVerdict::Allowed);
+        assert_eq!(p.evaluate(Some("mit")), LicenseVerdict::Allowed);
+        assert_eq!(
+            p.evaluate(Some("GPL-3.0-only OR Apache-2.0")),
+            LicenseVerdict::Allowed
+        );
+        assert_eq!(
+            p.evaluate(Some("MIT AND GPL-3.0-only")),
+            LicenseVerdict::Denied("GPL-3.0-only is denied".to_string())
+        );
+        assert_eq!(
+            p.evaluate(Some("BSD-3-Clause")),
+            LicenseVerdict::Denied("BSD-3-Clause is not in the allow list".to_string())
+        );
+        assert_eq!(p.evaluate(None), LicenseVerdict::Unknown);
+        assert!(matches!(
+            p.evaluate(Some("MIT OR")),
+            LicenseVerdict::Denied(_)
+        ));
+
+        let deny_only = policy("deny = [\"AGPL-3.0-only\"]\nunknown = \"deny\"\n");
+        assert_eq!(deny_only.unknown, UnknownLicense::Deny);
+        assert_eq!(
+            deny_only.evaluate(Some("BSD-3-Clause")),
+            LicenseVerdict::Allowed
+        );
+        assert!(LicensePolicy::default().is_empty());
+        assert!(!deny_only.is_empty());
+    }
+
+    #[test]
+    fn test_policy_with_exception() {
+        let p = policy("allow = [\"GPL-2.0-only WITH Classpath-exception-2.0\"]\n");
+        assert_eq!(
+            p.evaluate(Some("GPL-2.0-only WITH Classpath-exception-2.0")),
+            LicenseVerdict::Allowed
+        );
+        assert!(matches!(
+            p.evaluate(Some("GPL-2.0-only")),
+            LicenseVerdict::Denied(_)
+        ));
+    }
+
+    #[test]
+    fn test_spdx_report() {
+        let manifest = Manifest::parse(
+            r#"
+[package]
+name = "my_app"
+version = "1.0.0"
+edition = "2025"
+license = "MIT"
+
+[dependencies]
+http = "1.2"
+"#,
+        )
+        .unwrap();
+        let mut resolved = Resolver::new().resolve(&manifest).unwrap();
+        resolved.dependencies.get_mut("http").unwrap().license =
+            Some("MIT OR Apache-2.0".to_string());
+
+        let report = spdx_report(&manifest, &resolved, "2025-01-01T00:00:00Z");
+        assert!(report.starts_with("SPDXVersion: SPDX-2.3\n"));
+        assert!(report.contains("DocumentName: my_app-1.0.0\n"));
+        assert!(report.contains("SPDXID: SPDXRef-Package-my-app\n"));
+        assert!(report.contains("PackageName: http\n"));
+        assert!(report.contains("PackageVersion: ^1.2\n"));
+        assert!(report.contains("PackageLicenseDeclared: MIT OR Apache-2.0\n"));
+        assert!(report
+            .contains("Relationship: SPDXRef-Package-my-app DEPENDS_ON SPDXRef-Package-http\n"));
+
+        let checks = policy("allow = [\"Apache-2.0\"]\n").check(&resolved);
+        assert_eq!(checks.len(), 1);
+        assert_eq!(checks[0].verdict, LicenseVerdict::Allowed);
+    }
+}
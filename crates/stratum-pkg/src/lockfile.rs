@@ -8,6 +8,7 @@ use crate::resolve::{
     Resolver,
 };
 use crate::Manifest;
+use semver::{Version, VersionReq};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 use thiserror::Error;
@@ -94,6 +95,11 @@ pub struct LockedPackage {
     /// Which section this dependency came from.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub section: Option<String>,
+
+    /// The source a `[patch]` or `[replace]` entry swapped out, such as
+    /// `registry (^1.0)`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub patched: Option<String>,
 }
 
 impl Lockfile {
@@ -179,6 +185,15 @@ impl Lockfile {
     /// Returns an error if dependency resolution fails.
     pub fn generate(manifest: &Manifest, include_dev: bool) -> Result<Self, LockError> {
         let resolver = Resolver::new().with_dev(include_dev).with_build(true);
+        Self::generate_with(manifest, &resolver)
+    }
+
+    /// Generate a lock file from a manifest with a configured resolver.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if dependency resolution fails.
+    pub fn generate_with(manifest: &Manifest, resolver: &Resolver) -> Result<Self, LockError> {
         let resolved = resolver.resolve(manifest)?;
         Ok(Self::from_resolved(&resolved))
     }
@@ -273,7 +288,11 @@ impl LockedPackage {
         let (source, version, path, git, branch, tag, rev) = match &dep.source {
             DependencySource::Registry { version_req } => (
                 "registry".to_string(),
-                Some(version_req.to_string()),
+                Some(
+                    dep.version
+                        .as_ref()
+                        .map_or_else(|| version_req.to_string(), ToString::to_string),
+                ),
                 None,
                 None,
                 None,
@@ -324,6 +343,7 @@ impl LockedPackage {
                 DependencySection::Dev => "dev-dependencies".to_string(),
                 DependencySection::Build => "build-dependencies".to_string(),
             }),
+            patched: dep.patched.as_ref().map(ToString::to_string),
         }
     }
 }
@@ -332,12 +352,13 @@ impl LockedPackage {
 fn packages_match(a: &LockedPackage, b: &LockedPackage) -> bool {
     a.name == b.name
         && a.source == b.source
-        && a.version == b.version
+        && versions_match(a.version.as_deref(), b.version.as_deref())
         && a.path == b.path
         && a.git == b.git
         && a.branch == b.branch
         && a.tag == b.tag
         && a.rev == b.rev
+        && a.patched == b.patched
         // Features order might differ, so compare as sets
         && {
             let a_features: std::collections::BTreeSet<_> = a.features.iter().collect();
@@ -346,6 +367,25 @@ fn packages_match(a: &LockedPackage, b: &LockedPackage) -> bool {
         }
 }
 
+/// Whether two locked versions agree, counting an exact version as agreeing
+/// with a requirement it satisfies.
+fn versions_match(a: Option<&str>, b: Option<&str>) -> bool {
+    if a == b {
+        return true;
+    }
+    let (Some(a), Some(b)) = (a, b) else {
+        return false;
+    };
+    let satisfies = |version: &str, requirement: &str| match (
+        Version::parse(version),
+        VersionReq::parse(requirement),
+    ) {
+        (Ok(version), Ok(requirement)) => requirement.matches(&version),
+        _ => false,
+    };
+    satisfies(a, b) || satisfies(b, a)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -505,6 +545,65 @@ mod tests {
         assert!(err.to_string().contains("has changed"));
     }
 
+    #[test]
+    fn test_lockfile_patched_dependency() {
+        let mut manifest = make_manifest(vec![("http", DependencySpec::Simple("1.0".to_string()))]);
+        manifest.patch.insert(
+            "http".to_string(),
+            DependencySpec::Detailed(Dependency {
+                path: Some("../http".to_string()),
+                ..Default::default()
+            }),
+        );
+
+        let lockfile = Lockfile::generate(&manifest, false).unwrap();
+        let pkg = lockfile.get("http").unwrap();
+        assert_eq!(pkg.source, "path");
+        assert_eq!(pkg.path.as_deref(), Some("../http"));
+        assert_eq!(pkg.patched.as_deref(), Some("registry (^1.0)"));
+
+        let parsed = Lockfile::parse(&lockfile.to_toml_string().unwrap()).unwrap();
+        assert_eq!(parsed.get("http"), Some(pkg));
+        assert!(lockfile.check_sync(&manifest).is_ok());
+
+        // Dropping the patch puts the lock file out of sync
+        manifest.patch.clear();
+        assert!(matches!(
+            lockfile.check_sync(&manifest),
+            Err(LockError::OutOfSync { .. })
+        ));
+    }
+
+    #[test]
+    fn test_lockfile_selected_versions() {
+        let manifest = make_manifest(vec![
+            ("http", DependencySpec::Simple("^1.2".to_string())),
+            ("json", DependencySpec::Simple("2.0".to_string())),
+        ]);
+        let versions = |list: &[&str]| list.iter().map(|v| Version::parse(v).unwrap()).collect();
+        let resolver = Resolver::new()
+            .with_available_versions("http", versions(&["1.1.0", "1.2.0", "1.4.2", "2.0.0"]))
+            .with_precise("json", Version::new(2, 3, 1));
+
+        let lockfile = Lockfile::generate_with(&manifest, &resolver).unwrap();
+        assert_eq!(
+            lockfile.get("http").unwrap().version.as_deref(),
+            Some("1.4.2")
+        );
+        assert_eq!(
+            lockfile.get("json").unwrap().version.as_deref(),
+            Some("2.3.1")
+        );
+        // Locked versions that satisfy the manifest keep the lock file in sync
+        assert!(lockfile.check_sync(&manifest).is_ok());
+
+        let bumped = make_manifest(vec![
+            ("http", DependencySpec::Simple("^1.5".to_string())),
+            ("json", DependencySpec::Simple("2.0".to_string())),
+        ]);
+        assert!(lockfile.check_sync(&bumped).is_err());
+    }
+
     #[test]
     fn test_lockfile_toml_format() {
         let manifest = make_manifest(vec![("http", DependencySpec::Simple("^1.0".to_string()))]);
@@ -535,6 +634,7 @@ mod tests {
             features: vec!["a".to_string(), "b".to_string()],
             checksum: None,
             section: Some("dependencies".to_string()),
+            patched: None,
         };
 
         let pkg2 = LockedPackage {
@@ -1,5 +1,6 @@
 //! Stratum package manifest (`stratum.toml`) parsing and validation.
 
+use crate::license::LicensePolicy;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::path::Path;
@@ -25,6 +26,9 @@ pub enum ManifestError {
 
     #[error("unknown edition '{0}', expected one of: 2025")]
     UnknownEdition(String),
+
+    #[error("invalid patch for '{0}': {1}")]
+    InvalidPatch(String, &'static str),
 }
 
 /// The complete stratum.toml manifest.
@@ -65,6 +69,22 @@ pub struct Manifest {
     /// Benchmark targets.
     #[serde(default, rename = "bench")]
     pub benches: Vec<Target>,
+
+    /// Lint rule levels for `stratum lint` (rule name to "allow", "warn", or "deny").
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub lints: BTreeMap<String, String>,
+    /// Licenses accepted from dependencies, checked by `stratum license check`.
+    #[serde(default, skip_serializing_if = "LicensePolicy::is_empty")]
+    pub licenses: LicensePolicy,
+
+    /// Path or git sources that replace every dependency with the given name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub patch: BTreeMap<String, DependencySpec>,
+
+    /// Path or git sources that replace one version of a dependency, keyed
+    /// by `name@version`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub replace: BTreeMap<String, DependencySpec>,
 }
 
 /// Package metadata section.
@@ -131,6 +151,11 @@ pub struct Package {
     /// Default execution mode for the package.
     #[serde(default, rename = "default-run")]
     pub default_run: Option<String>,
+
+    /// Build script, relative to the package root. Defaults to
+    /// `build.strat` when that file exists.
+    #[serde(default)]
+    pub build: Option<String>,
 }
 
 /// Stratum language edition.
@@ -254,6 +279,15 @@ fn default_true() -> bool {
     true
 }
 
+/// Split a `[replace]` key into its package name and exact version.
+pub(crate) fn split_replace_key(key: &str) -> Option<(&str, semver::Version)> {
+    let (name, version) = key.split_once('@')?;
+    if name.is_empty() {
+        return None;
+    }
+    Some((name, semver::Version::parse(version).ok()?))
+}
+
 /// A build target (binary, library, test, example, benchmark).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
@@ -319,6 +353,7 @@ impl Manifest {
     fn validate(&self) -> Result<(), ManifestError> {
         self.validate_name()?;
         self.validate_version()?;
+        self.validate_patches()?;
         Ok(())
     }
 
@@ -371,6 +406,62 @@ impl Manifest {
         Ok(())
     }
 
+    /// Validate the `[patch]` and `[replace]` sections.
+    fn validate_patches(&self) -> Result<(), ManifestError> {
+        for (key, spec) in self.patch.iter().chain(&self.replace) {
+            if !spec.is_path() && !spec.is_git() {
+                return Err(ManifestError::InvalidPatch(
+                    key.clone(),
+                    "a patch must give a `path` or `git` source",
+                ));
+            }
+        }
+        for key in self.replace.keys() {
+            if split_replace_key(key).is_none() {
+                return Err(ManifestError::InvalidPatch(
+                    key.clone(),
+                    "`[replace]` keys must be `name@version`, such as `http@1.2.0`",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Add `[patch]` and `[replace]` entries from a workspace root, keeping
+    /// the package's own entries for the same keys.
+    ///
+    /// `prefix` is the path from this package to the workspace root, which
+    /// is prepended to relative patch paths.
+    pub fn inherit_patches(
+        &mut self,
+        patch: &BTreeMap<String, DependencySpec>,
+        replace: &BTreeMap<String, DependencySpec>,
+        prefix: &Path,
+    ) {
+        let rebase = |spec: &DependencySpec| match spec {
+            DependencySpec::Detailed(dep) => {
+                let mut dep = dep.clone();
+                if let Some(path) = &dep.path {
+                    if Path::new(path).is_relative() {
+                        dep.path = Some(prefix.join(path).to_string_lossy().replace('\\', "/"));
+                    }
+                }
+                DependencySpec::Detailed(dep)
+            }
+            DependencySpec::Simple(_) => spec.clone(),
+        };
+        for (key, spec) in patch {
+            self.patch
+                .entry(key.clone())
+                .or_insert_with(|| rebase(spec));
+        }
+        for (key, spec) in replace {
+            self.replace
+                .entry(key.clone())
+                .or_insert_with(|| rebase(spec));
+        }
+    }
+
     /// Serialize the manifest to a TOML string.
     ///
     /// # Errors
@@ -409,6 +500,7 @@ impl Default for Manifest {
                 exclude: Vec::new(),
                 include: Vec::new(),
                 default_run: None,
+                build: None,
             },
             dependencies: BTreeMap::new(),
             dev_dependencies: BTreeMap::new(),
@@ -418,6 +510,10 @@ impl Default for Manifest {
             tests: Vec::new(),
             examples: Vec::new(),
             benches: Vec::new(),
+            lints: BTreeMap::new(),
+            licenses: LicensePolicy::default(),
+            patch: BTreeMap::new(),
+            replace: BTreeMap::new(),
         }
     }
 }
@@ -499,6 +595,110 @@ remote-lib = { git = "https://github.com/example/lib", branch = "main" }
         assert!(dep.is_git());
     }
 
+    #[test]
+    fn parse_lints_table() {
+        let toml = r#"
+[package]
+name = "test"
+version = "0.1.0"
+edition = "2025"
+
+[lints]
+shadowing = "warn"
+unused_variables = "deny"
+"#;
+        let manifest = Manifest::parse(toml).unwrap();
+        assert_eq!(manifest.lints.len(), 2);
+        assert_eq!(manifest.lints["unused_variables"], "deny");
+
+        let serialized = Manifest::default().to_toml_string().unwrap();
+        assert!(!serialized.contains("[lints]"));
+    }
+
+    #[test]
+    fn parse_licenses_table() {
+        let toml = r#"
+[package]
+name = "test"
+version = "0.1.0"
+edition = "2025"
+
+[licenses]
+allow = ["MIT", "Apache-2.0"]
+unknown = "deny"
+"#;
+        let manifest = Manifest::parse(toml).unwrap();
+        assert_eq!(manifest.licenses.allow, ["MIT", "Apache-2.0"]);
+        assert_eq!(
+            manifest.licenses.unknown,
+            crate::license::UnknownLicense::Deny
+        );
+
+        let serialized = Manifest::default().to_toml_string().unwrap();
+        assert!(!serialized.contains("[licenses]"));
+    }
+
+    #[test]
+    fn parse_patch_and_replace() {
+        let toml = r#"
+[package]
+name = "app"
+version = "0.1.0"
+edition = "2025"
+
+[dependencies]
+http = "1.0"
+
+[patch]
+http = { path = "../http" }
+
+[replace]
+"json@2.1.0" = { git = "https://github.com/fork/json", branch = "fix" }
+"#;
+        let manifest = Manifest::parse(toml).unwrap();
+        assert!(manifest.patch["http"].is_path());
+        assert!(manifest.replace["json@2.1.0"].is_git());
+
+        let mut inherited = manifest.clone();
+        let mut workspace_patch = BTreeMap::new();
+        workspace_patch.insert(
+            "http".to_string(),
+            DependencySpec::Detailed(Dependency {
+                git: Some("https://github.com/fork/http".to_string()),
+                ..Default::default()
+            }),
+        );
+        workspace_patch.insert(
+            "yaml".to_string(),
+            DependencySpec::Detailed(Dependency {
+                path: Some("vendor/yaml".to_string()),
+                ..Default::default()
+            }),
+        );
+        inherited.inherit_patches(&workspace_patch, &BTreeMap::new(), Path::new("../.."));
+        assert!(inherited.patch["http"].is_path());
+        match &inherited.patch["yaml"] {
+            DependencySpec::Detailed(dep) => {
+                assert_eq!(dep.path.as_deref(), Some("../../vendor/yaml"));
+            }
+            DependencySpec::Simple(_) => panic!("expected a path patch"),
+        }
+    }
+
+    #[test]
+    fn invalid_patches() {
+        let base = "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2025\"\n";
+        for section in [
+            "[patch]\nhttp = \"1.0\"\n",
+            "[patch]\nhttp = { version = \"1.0\" }\n",
+            "[replace]\nhttp = { path = \"../http\" }\n",
+            "[replace]\n\"http@1\" = { path = \"../http\" }\n",
+        ] {
+            let err = Manifest::parse(&format!("{base}{section}")).unwrap_err();
+            assert!(matches!(err, ManifestError::InvalidPatch(..)), "{section}");
+        }
+    }
+
     #[test]
     fn invalid_name_empty() {
         let toml = r#"
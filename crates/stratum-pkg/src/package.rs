@@ -168,6 +168,53 @@ impl PackageLayout {
             path.exists().then_some(path)
         })
     }
+
+    /// Get every source file under the source directory, including
+    /// subdirectories, sorted by path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the source directory cannot be read.
+    pub fn source_files(&self) -> Result<Vec<PathBuf>, PackageError> {
+        let mut files = Vec::new();
+        if let Some(dir) = &self.src_dir {
+            collect_source_files(dir, &mut files)?;
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// Get the module path other files import a source file by, such as
+    /// `util.text` for `src/util/text.strat`.
+    ///
+    /// Returns `None` for files outside the source directory and files
+    /// without the source extension.
+    #[must_use]
+    pub fn module_path(&self, file: &Path) -> Option<String> {
+        let relative = file.strip_prefix(self.src_dir.as_ref()?).ok()?;
+        if relative.extension()? != SOURCE_EXT {
+            return None;
+        }
+        let stem = relative.with_extension("");
+        let segments: Option<Vec<&str>> = stem
+            .components()
+            .map(|component| component.as_os_str().to_str())
+            .collect();
+        Some(segments?.join("."))
+    }
+}
+
+/// Recursively collect the source files in a directory.
+fn collect_source_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), PackageError> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_source_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == SOURCE_EXT) {
+            files.push(path);
+        }
+    }
+    Ok(())
 }
 
 /// Complete package structure with manifest and layout.
@@ -525,6 +572,53 @@ edition = "2025"
         assert!(layout.tests_dir.is_some());
     }
 
+    #[test]
+    fn source_files_include_subdirectories() {
+        let tmp = TempDir::new().unwrap();
+        let manifest = r#"
+[package]
+name = "test-pkg"
+version = "0.1.0"
+edition = "2025"
+"#;
+        create_test_package(tmp.path(), manifest);
+        fs::write(tmp.path().join("src/main.strat"), "fx main() {}").unwrap();
+        fs::create_dir(tmp.path().join("src/util")).unwrap();
+        fs::write(tmp.path().join("src/util/text.strat"), "fx trim() {}").unwrap();
+        fs::write(tmp.path().join("src/notes.md"), "# notes").unwrap();
+
+        let layout = PackageLayout::discover(tmp.path()).unwrap();
+        let files = layout.source_files().unwrap();
+        assert_eq!(
+            files,
+            vec![
+                tmp.path().join("src/main.strat"),
+                tmp.path().join("src/util/text.strat"),
+            ]
+        );
+    }
+
+    #[test]
+    fn module_paths_follow_source_directories() {
+        let tmp = TempDir::new().unwrap();
+        let manifest = r#"
+[package]
+name = "test-pkg"
+version = "0.1.0"
+"#;
+        create_test_package(tmp.path(), manifest);
+
+        let layout = PackageLayout::discover(tmp.path()).unwrap();
+        let module_path = |path: &str| layout.module_path(&tmp.path().join(path));
+        assert_eq!(module_path("src/main.strat").as_deref(), Some("main"));
+        assert_eq!(
+            module_path("src/util/text.strat").as_deref(),
+            Some("util.text")
+        );
+        assert_eq!(module_path("src/notes.md"), None);
+        assert_eq!(module_path("tests/basic.strat"), None);
+    }
+
     #[test]
     fn find_root_from_subdirectory() {
         let tmp = TempDir::new().unwrap();
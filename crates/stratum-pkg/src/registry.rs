@@ -357,7 +357,7 @@ impl Default for RegistryConfig {
 }
 
 /// Get the platform-appropriate cache directory.
-fn dirs_cache_dir() -> PathBuf {
+pub(crate) fn dirs_cache_dir() -> PathBuf {
     // Try XDG_CACHE_HOME first, then fall back to ~/.cache or platform default
     if let Ok(cache) = std::env::var("XDG_CACHE_HOME") {
         return PathBuf::from(cache);
@@ -465,6 +465,7 @@ impl RegistryClient {
     ///
     /// Returns an error if the release cannot be fetched.
     pub fn fetch_release(&self, pkg: &GitHubPackage) -> Result<GitHubRelease, RegistryError> {
+        let _span = tracing::info_span!("fetch_release", package = %pkg).entered();
         let url = if let Some(ref version) = pkg.version {
             // Try with and without 'v' prefix
             pkg.release_by_tag_url(version)
@@ -575,6 +576,8 @@ impl RegistryClient {
     ///
     /// Returns an error if the download fails.
     fn download(&self, url: &str) -> Result<Vec<u8>, RegistryError> {
+        let span = tracing::info_span!("download", url, bytes = tracing::field::Empty);
+        let _entered = span.enter();
         let mut req = self.http_client.get(url);
         if let Some(ref token) = self.config.github_token {
             req = req.header("Authorization", format!("Bearer {token}"));
@@ -592,10 +595,12 @@ impl RegistryClient {
             )));
         }
 
-        response
+        let bytes = response
             .bytes()
             .map(|b| b.to_vec())
-            .map_err(|e| RegistryError::Network(e.to_string()))
+            .map_err(|e| RegistryError::Network(e.to_string()))?;
+        span.record("bytes", bytes.len());
+        Ok(bytes)
     }
 
     /// Calculate SHA256 checksum of data.
@@ -612,6 +617,8 @@ impl RegistryClient {
     ///
     /// Returns an error if the package cannot be fetched or cached.
     pub fn fetch_package(&self, pkg: &GitHubPackage) -> Result<FetchedPackage, RegistryError> {
+        let _span = tracing::info_span!("fetch_package", package = %pkg).entered();
+
         // Fetch release information
         let release = self.fetch_release(pkg)?;
 
@@ -773,7 +780,7 @@ fn extract_tarball(
 }
 
 /// Find the manifest file in an extracted package directory.
-fn find_manifest_in_extracted(dir: &Path) -> Result<PathBuf, RegistryError> {
+pub(crate) fn find_manifest_in_extracted(dir: &Path) -> Result<PathBuf, RegistryError> {
     // Check direct path first
     let direct = dir.join(MANIFEST_FILE);
     if direct.exists() {
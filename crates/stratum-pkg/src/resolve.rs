@@ -6,9 +6,11 @@
 //! - Conflict detection for incompatible version requirements
 //! - Resolution of dependencies from a manifest
 
-use crate::{Dependency, DependencySpec, Manifest};
+use crate::registry::PackageIndex;
+use crate::{Dependency, DependencySpec, Manifest, MANIFEST_FILE};
 use semver::{Version, VersionReq};
 use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 /// Errors that can occur during dependency resolution.
@@ -36,6 +38,21 @@ pub enum ResolveError {
     /// Missing dependency (path or git not found).
     #[error("dependency '{package}' not found: {reason}")]
     MissingDependency { package: String, reason: String },
+
+    /// A version pinned with `--precise` does not satisfy the requirement.
+    #[error("cannot use {package} {version}: it does not satisfy the requirement '{requirement}'")]
+    PreciseMismatch {
+        package: String,
+        version: Version,
+        requirement: VersionReq,
+    },
+
+    /// No known version satisfies the requirement.
+    #[error("no version of '{package}' matches '{requirement}'")]
+    NoMatchingVersion {
+        package: String,
+        requirement: VersionReq,
+    },
 }
 
 fn format_requirements(reqs: &[VersionRequirement]) -> String {
@@ -102,6 +119,17 @@ pub struct VersionRequirement {
     pub source: String,
 }
 
+/// How registry dependencies choose a version.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResolutionStrategy {
+    /// The newest version each requirement accepts.
+    #[default]
+    Latest,
+    /// The oldest version each requirement accepts, to check that a
+    /// package's lower bounds are honest.
+    Minimal,
+}
+
 /// A resolved dependency with all information needed to fetch it.
 #[derive(Debug, Clone)]
 pub struct ResolvedDependency {
@@ -117,6 +145,14 @@ pub struct ResolvedDependency {
     pub optional: bool,
     /// Which section this came from.
     pub section: DependencySection,
+    /// SPDX license expression from the dependency's manifest, once
+    /// collected with [`Resolver::collect_licenses`].
+    pub license: Option<String>,
+    /// The source the manifest asked for, when `[patch]` or `[replace]`
+    /// swapped it for `source`.
+    pub patched: Option<DependencySource>,
+    /// The version selected for a registry dependency, when one is known.
+    pub version: Option<Version>,
 }
 
 /// Which section a dependency came from.
@@ -203,6 +239,12 @@ pub struct Resolver {
     include_dev: bool,
     /// Whether to include build dependencies in resolution.
     include_build: bool,
+    /// How registry dependencies choose a version.
+    strategy: ResolutionStrategy,
+    /// Published versions of registry packages, by name.
+    available: HashMap<String, Vec<Version>>,
+    /// Versions pinned by name, such as with `stratum update --precise`.
+    precise: HashMap<String, Version>,
 }
 
 impl Resolver {
@@ -226,6 +268,31 @@ impl Resolver {
         self
     }
 
+    /// Choose versions with `strategy`.
+    #[must_use]
+    pub fn with_strategy(mut self, strategy: ResolutionStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Choose among `versions` for the registry package `name`.
+    ///
+    /// Without published versions, [`ResolutionStrategy::Minimal`] uses the
+    /// lowest version a requirement accepts and
+    /// [`ResolutionStrategy::Latest`] leaves the requirement unresolved.
+    #[must_use]
+    pub fn with_available_versions(mut self, name: &str, versions: Vec<Version>) -> Self {
+        self.available.insert(name.to_string(), versions);
+        self
+    }
+
+    /// Use exactly `version` for the registry package `name`.
+    #[must_use]
+    pub fn with_precise(mut self, name: &str, version: Version) -> Self {
+        self.precise.insert(name.to_string(), version);
+        self
+    }
+
     /// Resolve dependencies from a manifest.
     ///
     /// # Errors
@@ -233,13 +300,16 @@ impl Resolver {
     /// Returns an error if:
     /// - A version requirement is invalid
     /// - Conflicting requirements exist for the same package
+    /// - A pinned version, or every available version, fails a requirement
     pub fn resolve(&self, manifest: &Manifest) -> Result<ResolvedDependencies, ResolveError> {
+        let _span = tracing::info_span!("resolve", package = %manifest.package.name).entered();
         let mut dependencies = BTreeMap::new();
         let mut version_requirements: HashMap<String, Vec<VersionRequirement>> = HashMap::new();
 
         // Process regular dependencies
         for (name, spec) in &manifest.dependencies {
             let resolved = self.resolve_dependency(name, spec, DependencySection::Dependencies)?;
+            let resolved = self.apply_patches(manifest, resolved)?;
             if let DependencySource::Registry { ref version_req } = resolved.source {
                 version_requirements
                     .entry(name.clone())
@@ -256,6 +326,7 @@ impl Resolver {
         if self.include_dev {
             for (name, spec) in &manifest.dev_dependencies {
                 let resolved = self.resolve_dependency(name, spec, DependencySection::Dev)?;
+                let resolved = self.apply_patches(manifest, resolved)?;
 
                 // Check for conflicts with existing dependencies
                 if let Some(existing) = dependencies.get(name) {
@@ -278,6 +349,7 @@ impl Resolver {
         if self.include_build {
             for (name, spec) in &manifest.build_dependencies {
                 let resolved = self.resolve_dependency(name, spec, DependencySection::Build)?;
+                let resolved = self.apply_patches(manifest, resolved)?;
 
                 // Check for conflicts with existing dependencies
                 if let Some(existing) = dependencies.get(name) {
@@ -299,12 +371,76 @@ impl Resolver {
         // Check for version conflicts across all registry dependencies
         self.check_version_conflicts(&version_requirements)?;
 
+        for dep in dependencies.values_mut() {
+            if let DependencySource::Registry { version_req } = &dep.source {
+                dep.version = self.select_version(&dep.name, version_req)?;
+            }
+        }
+
         Ok(ResolvedDependencies {
             dependencies,
             version_requirements,
         })
     }
 
+    /// Find the manifest of each dependency that is available locally.
+    ///
+    /// Path dependencies are read relative to `root`; other dependencies are
+    /// looked up among the packages the registry client has cached in
+    /// `cache_dir`. Dependencies whose manifest is not available are left out.
+    #[must_use]
+    pub fn locate_manifests(
+        &self,
+        resolved: &ResolvedDependencies,
+        root: &Path,
+        cache_dir: &Path,
+    ) -> BTreeMap<String, PathBuf> {
+        let index = PackageIndex::load(&cache_dir.join("index.toml")).unwrap_or_default();
+        resolved
+            .dependencies
+            .iter()
+            .filter_map(|(name, dep)| {
+                let manifest_path = match &dep.source {
+                    DependencySource::Path { path } => {
+                        Some(root.join(path).join(MANIFEST_FILE)).filter(|path| path.is_file())
+                    }
+                    _ => index.get(name).and_then(|entry| {
+                        let dir = cache_dir
+                            .join(&entry.owner)
+                            .join(&entry.repo)
+                            .join(&entry.version)
+                            .join("src");
+                        crate::registry::find_manifest_in_extracted(&dir).ok()
+                    }),
+                };
+                Some((name.clone(), manifest_path?))
+            })
+            .collect()
+    }
+
+    /// Fill in the license of each dependency from its manifest.
+    ///
+    /// Manifests are found as by [`Resolver::locate_manifests`].
+    /// Dependencies whose manifest is not available, or that declare no
+    /// `license`, are left without one.
+    pub fn collect_licenses(
+        &self,
+        resolved: &mut ResolvedDependencies,
+        root: &Path,
+        cache_dir: &Path,
+    ) {
+        let manifests = self.locate_manifests(resolved, root, cache_dir);
+        for (name, dep) in &mut resolved.dependencies {
+            dep.license = manifests
+                .get(name)
+                .and_then(|path| Manifest::from_path(path).ok())
+                .and_then(|manifest| manifest.package.license);
+            if dep.license.is_none() {
+                tracing::debug!(dependency = %name, "no license found");
+            }
+        }
+    }
+
     /// Resolve a single dependency specification.
     fn resolve_dependency(
         &self,
@@ -322,6 +458,9 @@ impl Resolver {
                     default_features: true,
                     optional: false,
                     section,
+                    license: None,
+                    patched: None,
+                    version: None,
                 })
             }
             DependencySpec::Detailed(dep) => {
@@ -358,11 +497,82 @@ impl Resolver {
                     default_features: dep.default_features,
                     optional: dep.optional,
                     section,
+                    license: None,
+                    patched: None,
+                    version: None,
                 })
             }
         }
     }
 
+    /// The version of `name` to use for `requirement`, if one is known.
+    fn select_version(
+        &self,
+        name: &str,
+        requirement: &VersionReq,
+    ) -> Result<Option<Version>, ResolveError> {
+        if let Some(version) = self.precise.get(name) {
+            if !requirement.matches(version) {
+                return Err(ResolveError::PreciseMismatch {
+                    package: name.to_string(),
+                    version: version.clone(),
+                    requirement: requirement.clone(),
+                });
+            }
+            return Ok(Some(version.clone()));
+        }
+
+        let Some(available) = self.available.get(name) else {
+            return Ok(match self.strategy {
+                ResolutionStrategy::Latest => None,
+                ResolutionStrategy::Minimal => minimal_version(requirement),
+            });
+        };
+        let matching = available.iter().filter(|v| requirement.matches(v));
+        let selected = match self.strategy {
+            ResolutionStrategy::Latest => matching.max(),
+            ResolutionStrategy::Minimal => matching.min(),
+        };
+        selected
+            .cloned()
+            .map(Some)
+            .ok_or_else(|| ResolveError::NoMatchingVersion {
+                package: name.to_string(),
+                requirement: requirement.clone(),
+            })
+    }
+
+    /// Swap the source of `resolved` for its `[replace]` or `[patch]` entry.
+    ///
+    /// A `[replace]` entry applies when the dependency is a registry
+    /// dependency whose requirement accepts the entry's version, and takes
+    /// precedence over a `[patch]` entry for the same package.
+    fn apply_patches(
+        &self,
+        manifest: &Manifest,
+        mut resolved: ResolvedDependency,
+    ) -> Result<ResolvedDependency, ResolveError> {
+        let replacement = manifest.replace.iter().find_map(|(key, spec)| {
+            let (name, version) = crate::manifest::split_replace_key(key)?;
+            let DependencySource::Registry { version_req } = &resolved.source else {
+                return None;
+            };
+            (name == resolved.name && version_req.matches(&version)).then_some(spec)
+        });
+        let Some(spec) = replacement.or_else(|| manifest.patch.get(&resolved.name)) else {
+            return Ok(resolved);
+        };
+        let patch = self.resolve_dependency(&resolved.name, spec, resolved.section)?;
+        tracing::debug!(
+            dependency = %resolved.name,
+            from = %resolved.source,
+            to = %patch.source,
+            "patched"
+        );
+        resolved.patched = Some(std::mem::replace(&mut resolved.source, patch.source));
+        Ok(resolved)
+    }
+
     /// Check for conflicts between an existing and new dependency.
     fn check_conflict(
         &self,
@@ -514,6 +724,86 @@ pub fn matches_version(version: &Version, requirement: &VersionReq) -> bool {
     requirement.matches(version)
 }
 
+/// The lowest version `requirement` accepts, such as `1.2.0` for `^1.2`.
+#[must_use]
+pub fn minimal_version(requirement: &VersionReq) -> Option<Version> {
+    let mut lowest = Version::new(0, 0, 0);
+    for comparator in &requirement.comparators {
+        let (major, minor, patch) = (
+            comparator.major,
+            comparator.minor.unwrap_or(0),
+            comparator.patch.unwrap_or(0),
+        );
+        let bound = match comparator.op {
+            semver::Op::Less | semver::Op::LessEq => continue,
+            semver::Op::Greater => match (comparator.minor, comparator.patch) {
+                (None, _) => Version::new(major + 1, 0, 0),
+                (Some(_), None) => Version::new(major, minor + 1, 0),
+                (Some(_), Some(_)) => Version::new(major, minor, patch + 1),
+            },
+            _ => Version {
+                pre: comparator.pre.clone(),
+                ..Version::new(major, minor, patch)
+            },
+        };
+        lowest = lowest.max(bound);
+    }
+    requirement.matches(&lowest).then_some(lowest)
+}
+
+/// A package required at versions that are not semver compatible.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateDependency {
+    /// The package name.
+    pub name: String,
+    /// Each compatible range, such as `1.x` or `0.3.x`, with the packages
+    /// that require it.
+    pub versions: BTreeMap<String, Vec<String>>,
+}
+
+/// Find registry packages that the given packages require at more than one
+/// incompatible version, such as both `1.x` and `2.x`.
+///
+/// Each item is a requiring package's name and its resolved dependencies.
+/// A requirement counts at its selected version, or else the lowest version
+/// it accepts.
+pub fn find_duplicates<'a>(
+    packages: impl IntoIterator<Item = (&'a str, &'a ResolvedDependencies)>,
+) -> Vec<DuplicateDependency> {
+    let mut seen: BTreeMap<&str, BTreeMap<String, Vec<String>>> = BTreeMap::new();
+    for (requirer, resolved) in packages {
+        for dep in resolved.registry_deps() {
+            let DependencySource::Registry { version_req } = &dep.source else {
+                continue;
+            };
+            let Some(version) = dep.version.clone().or_else(|| minimal_version(version_req)) else {
+                continue;
+            };
+            let range = if version.major > 0 {
+                format!("{}.x", version.major)
+            } else {
+                format!("0.{}.x", version.minor)
+            };
+            let requirer = match dep.section {
+                DependencySection::Dependencies => requirer.to_string(),
+                section => format!("{requirer} ({section})"),
+            };
+            seen.entry(dep.name.as_str())
+                .or_default()
+                .entry(range)
+                .or_default()
+                .push(requirer);
+        }
+    }
+    seen.into_iter()
+        .filter(|(_, versions)| versions.len() > 1)
+        .map(|(name, versions)| DuplicateDependency {
+            name: name.to_string(),
+            versions,
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -615,6 +905,155 @@ mod tests {
         assert!(matches!(dep.source, DependencySource::Path { .. }));
     }
 
+    #[test]
+    fn test_resolve_patched_deps() {
+        let mut manifest = make_manifest(vec![
+            ("http", DependencySpec::Simple("^1.0".to_string())),
+            ("json", DependencySpec::Simple("2.0".to_string())),
+            ("yaml", DependencySpec::Simple("0.3".to_string())),
+        ]);
+        manifest.patch.insert(
+            "http".to_string(),
+            DependencySpec::Detailed(Dependency {
+                path: Some("../http".to_string()),
+                ..Default::default()
+            }),
+        );
+        let fork = DependencySpec::Detailed(Dependency {
+            git: Some("https://github.com/fork/json".to_string()),
+            tag: Some("v2.1.0-fix".to_string()),
+            ..Default::default()
+        });
+        manifest
+            .replace
+            .insert("json@2.1.0".to_string(), fork.clone());
+        // Does not match the `0.3` requirement
+        manifest.replace.insert("yaml@1.0.0".to_string(), fork);
+
+        let resolved = Resolver::new().resolve(&manifest).unwrap();
+        let http = resolved.get("http").unwrap();
+        assert_eq!(
+            http.source,
+            DependencySource::Path {
+                path: "../http".to_string()
+            }
+        );
+        assert!(matches!(
+            http.patched,
+            Some(DependencySource::Registry { .. })
+        ));
+        let json = resolved.get("json").unwrap();
+        assert!(matches!(json.source, DependencySource::Git { .. }));
+        let yaml = resolved.get("yaml").unwrap();
+        assert!(matches!(yaml.source, DependencySource::Registry { .. }));
+        assert!(yaml.patched.is_none());
+        assert!(!resolved.version_requirements.contains_key("http"));
+    }
+
+    #[test]
+    fn test_minimal_version() {
+        let minimal = |req: &str| minimal_version(&VersionReq::parse(req).unwrap());
+        assert_eq!(minimal("^1.2"), Some(Version::new(1, 2, 0)));
+        assert_eq!(minimal("~0.3.1"), Some(Version::new(0, 3, 1)));
+        assert_eq!(minimal("*"), Some(Version::new(0, 0, 0)));
+        assert_eq!(minimal(">=1.0, <2.0"), Some(Version::new(1, 0, 0)));
+        assert_eq!(minimal(">1.4"), Some(Version::new(1, 5, 0)));
+        assert_eq!(minimal(">1.4.2"), Some(Version::new(1, 4, 3)));
+        assert_eq!(
+            minimal("=2.0.0-beta.1"),
+            Version::parse("2.0.0-beta.1").ok()
+        );
+        assert_eq!(minimal("<1.0"), Some(Version::new(0, 0, 0)));
+    }
+
+    #[test]
+    fn test_resolution_strategies() {
+        let manifest = make_manifest(vec![
+            ("http", DependencySpec::Simple("^1.2".to_string())),
+            ("json", DependencySpec::Simple("~2.1".to_string())),
+        ]);
+        let versions: Vec<Version> = ["1.1.0", "1.2.0", "1.2.5", "1.9.0", "2.0.0"]
+            .iter()
+            .map(|v| Version::parse(v).unwrap())
+            .collect();
+
+        let latest = Resolver::new()
+            .with_available_versions("http", versions.clone())
+            .resolve(&manifest)
+            .unwrap();
+        assert_eq!(
+            latest.get("http").unwrap().version,
+            Some(Version::new(1, 9, 0))
+        );
+        assert_eq!(latest.get("json").unwrap().version, None);
+
+        let minimal = Resolver::new()
+            .with_strategy(ResolutionStrategy::Minimal)
+            .with_available_versions("http", versions.clone())
+            .resolve(&manifest)
+            .unwrap();
+        assert_eq!(
+            minimal.get("http").unwrap().version,
+            Some(Version::new(1, 2, 0))
+        );
+        assert_eq!(
+            minimal.get("json").unwrap().version,
+            Some(Version::new(2, 1, 0))
+        );
+
+        let precise = Resolver::new()
+            .with_available_versions("http", versions.clone())
+            .with_precise("http", Version::new(1, 2, 5))
+            .resolve(&manifest)
+            .unwrap();
+        assert_eq!(
+            precise.get("http").unwrap().version,
+            Some(Version::new(1, 2, 5))
+        );
+
+        let err = Resolver::new()
+            .with_precise("http", Version::new(2, 0, 0))
+            .resolve(&manifest)
+            .unwrap_err();
+        assert!(matches!(err, ResolveError::PreciseMismatch { .. }));
+
+        let err = Resolver::new()
+            .with_available_versions("http", vec![Version::new(1, 0, 0)])
+            .resolve(&manifest)
+            .unwrap_err();
+        assert!(matches!(err, ResolveError::NoMatchingVersion { .. }));
+    }
+
+    #[test]
+    fn test_find_duplicates() {
+        let app = make_manifest(vec![
+            ("http", DependencySpec::Simple("^1.2".to_string())),
+            ("json", DependencySpec::Simple("0.3".to_string())),
+        ]);
+        let mut cli = make_manifest(vec![("json", DependencySpec::Simple("0.4".to_string()))]);
+        cli.dev_dependencies.insert(
+            "http".to_string(),
+            DependencySpec::Simple("2.0".to_string()),
+        );
+        let server = make_manifest(vec![("http", DependencySpec::Simple("1.5".to_string()))]);
+
+        let resolver = Resolver::new().with_dev(true);
+        let app = resolver.resolve(&app).unwrap();
+        let cli = resolver.resolve(&cli).unwrap();
+        let server = resolver.resolve(&server).unwrap();
+        let duplicates = find_duplicates([("app", &app), ("cli", &cli), ("server", &server)]);
+
+        assert_eq!(duplicates.len(), 2);
+        assert_eq!(duplicates[0].name, "http");
+        assert_eq!(duplicates[0].versions["1.x"], ["app", "server"]);
+        assert_eq!(duplicates[0].versions["2.x"], ["cli (dev-dependencies)"]);
+        assert_eq!(duplicates[1].name, "json");
+        assert_eq!(duplicates[1].versions["0.3.x"], ["app"]);
+        assert_eq!(duplicates[1].versions["0.4.x"], ["cli"]);
+
+        assert!(find_duplicates([("app", &app), ("server", &server)]).is_empty());
+    }
+
     #[test]
     fn test_resolve_git_dep() {
         let manifest = make_manifest(vec![(
@@ -746,6 +1185,44 @@ mod tests {
         assert_eq!(resolved.path_deps().count(), 1);
         assert_eq!(resolved.git_deps().count(), 1);
     }
+
+    #[test]
+    fn test_collect_licenses() {
+        let root = tempfile::tempdir().unwrap();
+        let local = root.path().join("local");
+        std::fs::create_dir_all(&local).unwrap();
+        std::fs::write(
+            local.join(MANIFEST_FILE),
+            "[package]\nname = \"local\"\nversion = \"0.1.0\"\nedition = \"2025\"\nlicense = \"MIT OR Apache-2.0\"\n",
+        )
+        .unwrap();
+
+        let manifest = make_manifest(vec![
+            ("http", DependencySpec::Simple("^1.0".to_string())),
+            (
+                "local",
+                DependencySpec::Detailed(Dependency {
+                    path: Some("local".to_string()),
+                    ..Default::default()
+                }),
+            ),
+        ]);
+        let resolver = Resolver::new();
+        let mut resolved = resolver.resolve(&manifest).unwrap();
+        assert_eq!(resolved.get("local").unwrap().license, None);
+
+        let cache = tempfile::tempdir().unwrap();
+        resolver.collect_licenses(&mut resolved, root.path(), cache.path());
+        assert_eq!(
+            resolved.get("local").unwrap().license.as_deref(),
+            Some("MIT OR Apache-2.0")
+        );
+        assert_eq!(resolved.get("http").unwrap().license, None);
+
+        let manifests = resolver.locate_manifests(&resolved, root.path(), cache.path());
+        assert_eq!(manifests.len(), 1);
+        assert_eq!(manifests["local"], local.join(MANIFEST_FILE));
+    }
 }
 
 impl Default for Dependency {
@@ -0,0 +1,265 @@
+//! Inline dependencies for single-file scripts.
+//!
+//! A script run directly (`#!/usr/bin/env stratum`) has no `stratum.toml`.
+//! Instead it declares its dependencies in the comment block at the top of
+//! the file, one TOML table body per `//! deps:` line:
+//!
+//! ```text
+//! #!/usr/bin/env stratum
+//! //! deps: http = "1.0"
+//! //! deps: json = { version = "2.0", features = ["pretty"] }
+//! ```
+//!
+//! The declared dependencies are resolved into an environment in the cache
+//! directory, keyed by their contents, so scripts with the same
+//! dependencies share one resolution.
+
+use crate::{DependencySpec, LockError, Lockfile, Manifest, LOCK_FILE, MANIFEST_FILE};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// The comment prefix that declares script dependencies.
+const DEPS_PREFIX: &str = "//! deps:";
+
+/// Package name given to script environments.
+const SCRIPT_PACKAGE: &str = "script";
+
+/// Errors that can occur when preparing a script environment.
+#[derive(Error, Debug)]
+pub enum ScriptError {
+    /// A `//! deps:` line is not valid TOML.
+    #[error("invalid dependency on line {line}: {source}")]
+    InvalidDependency {
+        line: usize,
+        #[source]
+        source: toml::de::Error,
+    },
+
+    /// Failed to resolve or write the lock file.
+    #[error(transparent)]
+    Lock(#[from] LockError),
+
+    /// Failed to create the environment directory.
+    #[error("failed to create script environment: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Dependencies declared in a script's leading comment block.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptDependencies {
+    /// Dependencies by name.
+    pub dependencies: BTreeMap<String, DependencySpec>,
+}
+
+impl ScriptDependencies {
+    /// Parse the `//! deps:` lines from the top of a script.
+    ///
+    /// Only the leading block of comments and blank lines (after an optional
+    /// shebang) is read; declarations after the first line of code are
+    /// ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a declaration is not valid TOML.
+    pub fn parse(source: &str) -> Result<Self, ScriptError> {
+        let mut dependencies = BTreeMap::new();
+        for (index, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if index == 0 && line.starts_with("#!") && !line.starts_with("#![") {
+                continue;
+            }
+            if let Some(decl) = line.strip_prefix(DEPS_PREFIX) {
+                let parsed: BTreeMap<String, DependencySpec> = toml::from_str(decl.trim())
+                    .map_err(|source| ScriptError::InvalidDependency {
+                        line: index + 1,
+                        source,
+                    })?;
+                dependencies.extend(parsed);
+            } else if !line.is_empty() && !line.starts_with("//") {
+                break;
+            }
+        }
+        Ok(Self { dependencies })
+    }
+
+    /// Returns true if the script declares no dependencies.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.dependencies.is_empty()
+    }
+
+    /// Build the manifest for a script environment.
+    ///
+    /// Relative path dependencies are resolved against `script_dir`, since
+    /// the environment lives elsewhere.
+    #[must_use]
+    pub fn manifest(&self, script_dir: &Path) -> Manifest {
+        let mut manifest = Manifest::default();
+        manifest.package.name = SCRIPT_PACKAGE.to_string();
+        manifest.package.version = "0.0.0".to_string();
+        manifest.dependencies = self
+            .dependencies
+            .iter()
+            .map(|(name, spec)| (name.clone(), absolute_path_dep(spec, script_dir)))
+            .collect();
+        manifest
+    }
+}
+
+/// Make a path dependency's path absolute.
+fn absolute_path_dep(spec: &DependencySpec, script_dir: &Path) -> DependencySpec {
+    let DependencySpec::Detailed(dep) = spec else {
+        return spec.clone();
+    };
+    let mut dep = dep.clone();
+    if let Some(path) = dep.path.as_deref().filter(|p| Path::new(p).is_relative()) {
+        dep.path = Some(script_dir.join(path).to_string_lossy().into_owned());
+    }
+    DependencySpec::Detailed(dep)
+}
+
+/// A resolved environment for a script's dependencies.
+#[derive(Debug, Clone)]
+pub struct ScriptEnvironment {
+    /// Directory holding the environment's manifest and lock file.
+    pub root: PathBuf,
+
+    /// The generated manifest.
+    pub manifest: Manifest,
+
+    /// The resolved dependencies.
+    pub lockfile: Lockfile,
+
+    /// Whether the environment was resolved now rather than reused.
+    pub created: bool,
+}
+
+impl ScriptEnvironment {
+    /// The default directory script environments are cached in.
+    #[must_use]
+    pub fn default_cache_dir() -> PathBuf {
+        crate::registry::dirs_cache_dir()
+            .join("stratum")
+            .join("scripts")
+    }
+
+    /// Resolve a script's dependencies into an environment under
+    /// `cache_dir`, reusing a previous resolution when it is still in sync.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if resolution fails or the environment cannot be
+    /// written.
+    pub fn prepare(
+        deps: &ScriptDependencies,
+        script_dir: &Path,
+        cache_dir: &Path,
+    ) -> Result<Self, ScriptError> {
+        let manifest = deps.manifest(script_dir);
+        let contents = manifest.to_toml_string().map_err(LockError::Serialize)?;
+        let root = cache_dir.join(fingerprint(&contents));
+        let lock_path = root.join(LOCK_FILE);
+
+        if let Ok(lockfile) = Lockfile::from_path(&lock_path) {
+            if lockfile.check_sync(&manifest).is_ok() {
+                return Ok(Self {
+                    root,
+                    manifest,
+                    lockfile,
+                    created: false,
+                });
+            }
+        }
+
+        let lockfile = Lockfile::generate(&manifest, false)?;
+        std::fs::create_dir_all(&root)?;
+        std::fs::write(root.join(MANIFEST_FILE), contents)?;
+        lockfile.write(&lock_path)?;
+        Ok(Self {
+            root,
+            manifest,
+            lockfile,
+            created: true,
+        })
+    }
+}
+
+/// A short content hash naming an environment directory.
+fn fingerprint(contents: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(contents.as_bytes());
+    hex::encode(&hasher.finalize()[..8])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_script_deps() {
+        let source = r#"#!/usr/bin/env stratum
+//! deps: http = "1.0"
+//! deps: json = { version = "2.0", features = ["pretty"] }
+// a plain comment
+
+fx main() {}
+//! deps: ignored = "1.0"
+"#;
+        let deps = ScriptDependencies::parse(source).unwrap();
+        assert_eq!(deps.dependencies.len(), 2);
+        assert_eq!(deps.dependencies["http"].version(), Some("1.0"));
+        assert_eq!(deps.dependencies["json"].version(), Some("2.0"));
+        assert!(!deps.dependencies.contains_key("ignored"));
+    }
+
+    #[test]
+    fn test_parse_script_without_deps() {
+        let deps = ScriptDependencies::parse("println(1)\n").unwrap();
+        assert!(deps.is_empty());
+    }
+
+    #[test]
+    fn test_parse_invalid_dep() {
+        let err = ScriptDependencies::parse("//! deps: http = \n").unwrap_err();
+        assert!(matches!(
+            err,
+            ScriptError::InvalidDependency { line: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn test_manifest_absolute_path_deps() {
+        let deps = ScriptDependencies::parse("//! deps: util = { path = \"lib/util\" }\n").unwrap();
+        let manifest = deps.manifest(Path::new("/scripts"));
+        let DependencySpec::Detailed(dep) = &manifest.dependencies["util"] else {
+            panic!("expected detailed dependency");
+        };
+        assert_eq!(
+            dep.path.as_deref().map(Path::new),
+            Some(Path::new("/scripts/lib/util"))
+        );
+    }
+
+    #[test]
+    fn test_prepare_reuses_environment() {
+        let cache = tempfile::tempdir().unwrap();
+        let deps = ScriptDependencies::parse("//! deps: http = \"1.0\"\n").unwrap();
+
+        let env = ScriptEnvironment::prepare(&deps, Path::new("."), cache.path()).unwrap();
+        assert!(env.created);
+        assert_eq!(env.lockfile.len(), 1);
+        assert!(env.root.join(LOCK_FILE).exists());
+        assert!(env.root.join(MANIFEST_FILE).exists());
+
+        let again = ScriptEnvironment::prepare(&deps, Path::new("."), cache.path()).unwrap();
+        assert!(!again.created);
+        assert_eq!(again.root, env.root);
+
+        let other = ScriptDependencies::parse("//! deps: http = \"2.0\"\n").unwrap();
+        let changed = ScriptEnvironment::prepare(&other, Path::new("."), cache.path()).unwrap();
+        assert!(changed.created);
+        assert_ne!(changed.root, env.root);
+    }
+}
@@ -0,0 +1,265 @@
+//! The `target` directory holding a package's build artifacts.
+//!
+//! ```text
+//! target/
+//! ├── debug/            # Executables built without --release
+//! │   └── obj/          # AOT object files and linker wrappers
+//! ├── release/          # Executables built with --release
+//! │   └── obj/
+//! ├── build/<package>/  # Build script output directories and cached results
+//! ├── coverage/         # HTML and Cobertura coverage reports
+//! └── doc/              # Generated documentation
+//! ```
+//!
+//! Members of a workspace share the workspace root's directory, and
+//! `STRATUM_TARGET_DIR` moves it anywhere else.
+
+use std::ffi::OsString;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::package::PackageLayout;
+use crate::workspace::{self, Workspace};
+
+/// Environment variable overriding the location of the target directory.
+pub const TARGET_DIR_ENV: &str = "STRATUM_TARGET_DIR";
+
+/// A target directory and the layout of the artifacts within it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetDir {
+    /// The target directory itself.
+    pub root: PathBuf,
+}
+
+/// Artifacts that `stratum clean` can remove on their own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Artifacts {
+    /// Generated documentation.
+    Doc,
+    /// Cached build script results, so build scripts run again.
+    Cache,
+    /// Everything built with `--release`.
+    Release,
+}
+
+/// What [`TargetDir::clean`] removed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CleanSummary {
+    /// Number of files removed.
+    pub files: u64,
+    /// Total size of the removed files, in bytes.
+    pub bytes: u64,
+}
+
+impl TargetDir {
+    /// A target directory at `root`.
+    #[must_use]
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// The target directory of the package at `package_root`.
+    #[must_use]
+    pub fn for_package(package_root: &Path) -> Self {
+        Self::resolve(package_root, std::env::var_os(TARGET_DIR_ENV))
+    }
+
+    /// The target directory of the package or workspace containing `dir`,
+    /// or `None` if `dir` is in neither and `STRATUM_TARGET_DIR` is not set.
+    #[must_use]
+    pub fn find(dir: &Path) -> Option<Self> {
+        let env = std::env::var_os(TARGET_DIR_ENV).filter(|value| !value.is_empty());
+        if env.is_some() {
+            return Some(Self::resolve(dir, env));
+        }
+        let root = PackageLayout::find_root(dir)
+            .map(|layout| layout.root)
+            .or_else(|_| Workspace::find(dir).map(|workspace| workspace.root))
+            .ok()?;
+        Some(Self::resolve(&root, None))
+    }
+
+    /// The target directory of the package at `package_root`, given the
+    /// value of `STRATUM_TARGET_DIR`.
+    fn resolve(package_root: &Path, env: Option<OsString>) -> Self {
+        match env.filter(|value| !value.is_empty()) {
+            // A relative override is relative to where the command was run
+            Some(value) => Self::new(
+                std::env::current_dir()
+                    .unwrap_or_default()
+                    .join(PathBuf::from(value)),
+            ),
+            None => Self::new(workspace::target_dir(package_root)),
+        }
+    }
+
+    /// The directory executables of a profile are built into.
+    #[must_use]
+    pub fn profile(&self, release: bool) -> PathBuf {
+        self.root.join(if release { "release" } else { "debug" })
+    }
+
+    /// The directory AOT object files of a profile are kept in.
+    #[must_use]
+    pub fn objects(&self, release: bool) -> PathBuf {
+        self.profile(release).join("obj")
+    }
+
+    /// The directory build scripts run in, one subdirectory per package.
+    #[must_use]
+    pub fn build(&self) -> PathBuf {
+        self.root.join("build")
+    }
+
+    /// The directory coverage reports are written to.
+    #[must_use]
+    pub fn coverage(&self) -> PathBuf {
+        self.root.join("coverage")
+    }
+
+    /// The directory documentation is generated into.
+    #[must_use]
+    pub fn doc(&self) -> PathBuf {
+        self.root.join("doc")
+    }
+
+    /// Remove `only` the given artifacts, or the whole directory if `only`
+    /// is empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file cannot be removed.
+    pub fn clean(&self, only: &[Artifacts]) -> io::Result<CleanSummary> {
+        let dirs = if only.is_empty() {
+            vec![self.root.clone()]
+        } else {
+            only.iter()
+                .map(|artifacts| match artifacts {
+                    Artifacts::Doc => self.doc(),
+                    Artifacts::Cache => self.build(),
+                    Artifacts::Release => self.profile(true),
+                })
+                .collect()
+        };
+
+        let mut summary = CleanSummary::default();
+        for dir in dirs {
+            if !dir.exists() {
+                continue;
+            }
+            measure(&dir, &mut summary)?;
+            std::fs::remove_dir_all(&dir)?;
+        }
+        Ok(summary)
+    }
+}
+
+/// Add the files under `dir` to `summary`.
+fn measure(dir: &Path, summary: &mut CleanSummary) -> io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            measure(&entry.path(), summary)?;
+        } else {
+            summary.files += 1;
+            summary.bytes += entry.metadata()?.len();
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn write_package(dir: &Path) {
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("src/main.strat"), "").unwrap();
+        fs::write(
+            dir.join("stratum.toml"),
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2025\"\n",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn layout() {
+        let target = TargetDir::new("/work/target");
+        assert_eq!(target.profile(false), Path::new("/work/target/debug"));
+        assert_eq!(target.objects(true), Path::new("/work/target/release/obj"));
+        assert_eq!(target.build(), Path::new("/work/target/build"));
+        assert_eq!(target.coverage(), Path::new("/work/target/coverage"));
+        assert_eq!(target.doc(), Path::new("/work/target/doc"));
+    }
+
+    #[test]
+    fn resolve_with_override() {
+        let tmp = TempDir::new().unwrap();
+        write_package(tmp.path());
+
+        assert_eq!(
+            TargetDir::resolve(tmp.path(), None).root,
+            tmp.path().join("target")
+        );
+        assert_eq!(
+            TargetDir::resolve(tmp.path(), Some(OsString::new())).root,
+            tmp.path().join("target")
+        );
+        let elsewhere = tmp.path().join("elsewhere");
+        assert_eq!(
+            TargetDir::resolve(tmp.path(), Some(elsewhere.clone().into_os_string())).root,
+            elsewhere
+        );
+        assert_eq!(
+            TargetDir::resolve(tmp.path(), Some("out".into())).root,
+            std::env::current_dir().unwrap().join("out")
+        );
+    }
+
+    #[test]
+    fn find_outside_package() {
+        let tmp = TempDir::new().unwrap();
+        write_package(&tmp.path().join("app"));
+        fs::create_dir_all(tmp.path().join("app/src/nested")).unwrap();
+        fs::create_dir_all(tmp.path().join("loose")).unwrap();
+
+        if std::env::var_os(TARGET_DIR_ENV).is_none() {
+            assert_eq!(
+                TargetDir::find(&tmp.path().join("app/src/nested")),
+                Some(TargetDir::new(tmp.path().join("app/target")))
+            );
+            assert_eq!(TargetDir::find(&tmp.path().join("loose")), None);
+        }
+    }
+
+    #[test]
+    fn clean_artifacts() {
+        let tmp = TempDir::new().unwrap();
+        let target = TargetDir::new(tmp.path().join("target"));
+        // Nothing to clean before anything is built
+        assert_eq!(target.clean(&[]).unwrap(), CleanSummary::default());
+
+        fs::create_dir_all(target.objects(true)).unwrap();
+        fs::create_dir_all(target.profile(false)).unwrap();
+        fs::create_dir_all(target.doc()).unwrap();
+        fs::write(target.objects(true).join("app.o"), "object").unwrap();
+        fs::write(target.profile(true).join("app"), "exe").unwrap();
+        fs::write(target.profile(false).join("app"), "exe").unwrap();
+        fs::write(target.doc().join("index.html"), "<html>").unwrap();
+
+        let summary = target.clean(&[Artifacts::Release]).unwrap();
+        assert_eq!(summary, CleanSummary { files: 2, bytes: 9 });
+        assert!(!target.profile(true).exists());
+        assert!(target.profile(false).exists());
+
+        let summary = target.clean(&[Artifacts::Doc, Artifacts::Cache]).unwrap();
+        assert_eq!(summary.files, 1);
+        assert!(!target.doc().exists());
+
+        assert_eq!(target.clean(&[]).unwrap().files, 1);
+        assert!(!target.root.exists());
+    }
+}
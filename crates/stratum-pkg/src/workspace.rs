@@ -18,7 +18,8 @@
 //! json = "2.1"
 //! ```
 
-use crate::manifest::{DependencySpec, Edition, ManifestError};
+use crate::license::LicensePolicy;
+use crate::manifest::{DependencySpec, Edition, Manifest, ManifestError};
 use crate::package::{PackageError, PackageStructure, MANIFEST_FILE};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
@@ -60,6 +61,14 @@ pub struct WorkspaceManifest {
     /// Package configuration (if this is also a package).
     #[serde(default)]
     pub package: Option<PackageInWorkspace>,
+
+    /// Patches applied to every member's dependencies.
+    #[serde(default)]
+    pub patch: BTreeMap<String, DependencySpec>,
+
+    /// Version replacements applied to every member's dependencies.
+    #[serde(default)]
+    pub replace: BTreeMap<String, DependencySpec>,
 }
 
 /// Workspace configuration section.
@@ -81,6 +90,10 @@ pub struct WorkspaceConfig {
     /// Shared dependencies that members can inherit.
     #[serde(default)]
     pub dependencies: BTreeMap<String, DependencySpec>,
+    /// Licenses members accept from their dependencies, unless a member
+    /// sets its own `[licenses]`.
+    #[serde(default, skip_serializing_if = "LicensePolicy::is_empty")]
+    pub licenses: LicensePolicy,
 }
 
 /// Shared package configuration for workspace members.
@@ -267,6 +280,12 @@ pub struct Workspace {
 
     /// Discovered member packages.
     pub members: Vec<WorkspaceMember>,
+
+    /// `[patch]` entries of the workspace root, relative to the root.
+    pub patch: BTreeMap<String, DependencySpec>,
+
+    /// `[replace]` entries of the workspace root, relative to the root.
+    pub replace: BTreeMap<String, DependencySpec>,
 }
 
 impl Workspace {
@@ -295,6 +314,8 @@ impl Workspace {
             manifest_path,
             config,
             members,
+            patch: workspace_manifest.patch,
+            replace: workspace_manifest.replace,
         })
     }
 
@@ -406,12 +427,46 @@ impl Workspace {
         self.members.iter().map(|m| m.name.as_str()).collect()
     }
 
+    /// Get the members named in `names`, in workspace order, or every member
+    /// if `names` is empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a name is not a member of the workspace.
+    pub fn select(&self, names: &[String]) -> Result<Vec<&WorkspaceMember>, WorkspaceError> {
+        if let Some(missing) = names.iter().find(|name| self.member(name).is_none()) {
+            return Err(WorkspaceError::MemberNotFound(missing.clone()));
+        }
+        Ok(self
+            .members
+            .iter()
+            .filter(|m| names.is_empty() || names.contains(&m.name))
+            .collect())
+    }
+
+    /// The `target` directory shared by all members.
+    #[must_use]
+    pub fn target_dir(&self) -> PathBuf {
+        self.root.join("target")
+    }
+
     /// Check if a path is within this workspace.
     #[must_use]
     pub fn contains(&self, path: &Path) -> bool {
         path.starts_with(&self.root)
     }
 
+    /// Apply the workspace's `[patch]` and `[replace]` entries to the
+    /// manifest of the package at `package_root`, keeping the package's own
+    /// entries for the same keys.
+    pub fn apply_patches(&self, package_root: &Path, manifest: &mut Manifest) {
+        let prefix = match package_root.strip_prefix(&self.root) {
+            Ok(relative) => relative.components().map(|_| Path::new("..")).collect(),
+            Err(_) => self.root.clone(),
+        };
+        manifest.inherit_patches(&self.patch, &self.replace, &prefix);
+    }
+
     /// Resolve a workspace dependency to a concrete version.
     #[must_use]
     pub fn resolve_dependency(&self, name: &str) -> Option<&DependencySpec> {
@@ -419,6 +474,16 @@ impl Workspace {
     }
 }
 
+/// The `target` directory of the package at `package_root`: the shared one
+/// of its workspace, if it is in one, or else its own.
+#[must_use]
+pub fn target_dir(package_root: &Path) -> PathBuf {
+    Workspace::find(package_root).map_or_else(
+        |_| package_root.join("target"),
+        |workspace| workspace.target_dir(),
+    )
+}
+
 impl WorkspaceManifest {
     /// Load a workspace manifest from a file.
     ///
@@ -544,4 +609,94 @@ edition.workspace = true
         assert!(matches!(pkg.version, VersionOrWorkspace::Workspace(_)));
         assert!(matches!(pkg.edition, EditionOrWorkspace::Workspace(_)));
     }
+
+    #[test]
+    fn workspace_patches_apply_to_members() {
+        let tmp = TempDir::new().unwrap();
+        let root_manifest = r#"
+[workspace]
+members = ["crates/*"]
+
+[patch]
+http = { path = "vendor/http" }
+json = { git = "https://github.com/fork/json", branch = "fix" }
+"#;
+        fs::write(tmp.path().join(MANIFEST_FILE), root_manifest).unwrap();
+        let member_dir = tmp.path().join("crates/app");
+        fs::create_dir_all(member_dir.join("src")).unwrap();
+        let member_manifest = r#"
+[package]
+name = "app"
+version = "0.1.0"
+edition = "2025"
+
+[dependencies]
+http = "1.0"
+json = "2.0"
+
+[patch]
+json = { path = "../../forks/json" }
+"#;
+        fs::write(member_dir.join(MANIFEST_FILE), member_manifest).unwrap();
+        fs::write(member_dir.join("src/main.strat"), "").unwrap();
+
+        let workspace = Workspace::find(&member_dir).unwrap();
+        let mut manifest = workspace.members[0].package.manifest.clone();
+        workspace.apply_patches(&member_dir, &mut manifest);
+
+        match &manifest.patch["http"] {
+            DependencySpec::Detailed(dep) => {
+                assert_eq!(dep.path.as_deref(), Some("../../vendor/http"));
+            }
+            DependencySpec::Simple(_) => panic!("expected a path patch"),
+        }
+        // The member's own patch wins over the workspace's
+        assert!(manifest.patch["json"].is_path());
+    }
+
+    #[test]
+    fn select_members() {
+        let tmp = TempDir::new().unwrap();
+        fs::write(
+            tmp.path().join(MANIFEST_FILE),
+            "[workspace]\nmembers = [\"crates/*\"]\n",
+        )
+        .unwrap();
+        for name in ["a", "b", "c"] {
+            let member_dir = tmp.path().join("crates").join(name);
+            fs::create_dir_all(member_dir.join("src")).unwrap();
+            fs::write(
+                member_dir.join(MANIFEST_FILE),
+                format!("[package]\nname = \"{name}\"\nversion = \"0.1.0\"\nedition = \"2025\"\n"),
+            )
+            .unwrap();
+            fs::write(member_dir.join("src/lib.strat"), "").unwrap();
+        }
+
+        let workspace = Workspace::load(tmp.path()).unwrap();
+        let names = |members: Vec<&WorkspaceMember>| {
+            let mut names: Vec<String> = members.iter().map(|m| m.name.clone()).collect();
+            names.sort();
+            names
+        };
+        assert_eq!(names(workspace.select(&[]).unwrap()), ["a", "b", "c"]);
+        assert_eq!(
+            names(
+                workspace
+                    .select(&["c".to_string(), "a".to_string()])
+                    .unwrap()
+            ),
+            ["a", "c"]
+        );
+        assert!(matches!(
+            workspace.select(&["d".to_string()]),
+            Err(WorkspaceError::MemberNotFound(name)) if name == "d"
+        ));
+
+        // Members share the workspace's target directory
+        let member = tmp.path().join("crates/b");
+        assert_eq!(target_dir(&member), tmp.path().join("target"));
+        let lone = TempDir::new().unwrap();
+        assert_eq!(target_dir(lone.path()), lone.path().join("target"));
+    }
 }
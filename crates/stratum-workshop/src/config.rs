@@ -2,6 +2,8 @@
 //!
 //! Stores user preferences including panel visibility and layout ratios.
 
+use crate::keymap::Keymap;
+use crate::tabs::SplitLayout;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -49,6 +51,9 @@ pub struct LayoutConfig {
     pub data_explorer_ratio: f32,
     /// Panel visibility settings
     pub visibility: PanelVisibility,
+    /// Editor groups and how they are arranged
+    #[serde(default)]
+    pub editor_split: SplitLayout,
 }
 
 fn default_data_explorer_ratio() -> f32 {
@@ -62,6 +67,7 @@ impl Default for LayoutConfig {
             output_ratio: DEFAULT_OUTPUT_RATIO,
             data_explorer_ratio: DEFAULT_DATA_EXPLORER_RATIO,
             visibility: PanelVisibility::default(),
+            editor_split: SplitLayout::default(),
         }
     }
 }
@@ -90,6 +96,9 @@ pub struct WorkshopConfig {
     pub window_size: (u32, u32),
     /// Window position (x, y) - None means centered
     pub window_position: Option<(i32, i32)>,
+    /// Editor keymap
+    #[serde(default)]
+    pub keymap: Keymap,
 }
 
 impl Default for WorkshopConfig {
@@ -101,6 +110,7 @@ impl Default for WorkshopConfig {
             last_folder: None,
             window_size: (1200, 800),
             window_position: None,
+            keymap: Keymap::default(),
         }
     }
 }
@@ -117,7 +127,7 @@ impl WorkshopConfig {
     /// Load configuration from disk
     pub fn load() -> Self {
         Self::config_path()
-            .and_then(|path| std::fs::read_to_string(&path).ok())
+            .and_then(|path| std::fs::read_to_string(path).ok())
             .and_then(|content| toml::from_str(&content).ok())
             .unwrap_or_default()
     }
@@ -132,8 +142,8 @@ impl WorkshopConfig {
             std::fs::create_dir_all(parent)?;
         }
 
-        let content =
-            toml::to_string_pretty(self).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        let content = toml::to_string_pretty(self)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
 
         std::fs::write(path, content)
     }
@@ -170,12 +180,14 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::float_cmp)]
     fn test_clamp_ratios() {
         let mut layout = LayoutConfig {
             file_browser_ratio: 0.0,
             output_ratio: 1.0,
             data_explorer_ratio: 0.5,
             visibility: PanelVisibility::default(),
+            editor_split: SplitLayout::default(),
         };
         layout.clamp_ratios();
         assert_eq!(layout.file_browser_ratio, 0.1);
@@ -204,9 +216,23 @@ mod tests {
 
     #[test]
     fn test_config_serialization() {
-        let config = WorkshopConfig::default();
+        let mut config = WorkshopConfig {
+            keymap: Keymap::Vim,
+            ..WorkshopConfig::default()
+        };
+        config.layout.editor_split = SplitLayout {
+            axis: crate::tabs::SplitAxis::Rows,
+            groups: 2,
+        };
         let toml_str = toml::to_string_pretty(&config).expect("Failed to serialize");
         let parsed: WorkshopConfig = toml::from_str(&toml_str).expect("Failed to deserialize");
         assert_eq!(config.window_size, parsed.window_size);
+        assert_eq!(parsed.keymap, Keymap::Vim);
+        assert_eq!(parsed.layout.editor_split, config.layout.editor_split);
+
+        // Configs saved before keymaps existed use the standard one
+        let old = toml_str.replace("keymap = \"vim\"", "");
+        let parsed: WorkshopConfig = toml::from_str(&old).expect("Failed to deserialize");
+        assert_eq!(parsed.keymap, Keymap::Standard);
     }
 }
@@ -8,7 +8,8 @@ use std::rc::Rc;
 
 use stratum_core::bytecode::{Function, Value};
 use stratum_core::{
-    Compiler, DebugStackFrame, DebugState, DebugStepResult, DebugVariable, Parser, PauseReason, VM,
+    Compiler, DebugStackFrame, DebugState, DebugStepResult, DebugVariable, HotReload, Parser,
+    PauseReason, TraceEntry, VM,
 };
 
 /// State of the debug session
@@ -97,7 +98,11 @@ impl DebugSession {
             .and_then(|n| n.to_str())
             .unwrap_or("<untitled>");
 
-        let function = match Compiler::with_source(file_name.to_string()).compile_module(&module) {
+        // Unoptimized, so stepping follows the source line by line
+        let function = match Compiler::with_source(file_name.to_string())
+            .with_optimization(false)
+            .compile_module(&module)
+        {
             Ok(f) => f,  // compile_module already returns Rc<Function>
             Err(errors) => {
                 let error_msg = errors.iter().map(|e| format!("{}", e)).collect::<Vec<_>>().join("\n");
@@ -171,6 +176,75 @@ impl DebugSession {
         self.handle_debug_result(result)
     }
 
+    /// Swap the functions of the edited source into the paused program
+    ///
+    /// Later calls run the new code; restart a frame with
+    /// [`Self::restart_frame`] to rerun the function it is in.
+    pub fn hot_reload(&mut self, source: &str) -> Result<HotReload, String> {
+        if self.state != DebugSessionState::Paused {
+            return Err("Cannot reload: not paused".to_string());
+        }
+
+        let module = Parser::parse_module(source).map_err(|errors| {
+            errors.iter().map(|e| format!("{}", e)).collect::<Vec<_>>().join("\n")
+        })?;
+        let file_name = self
+            .source_file
+            .as_ref()
+            .and_then(|p| p.file_name())
+            .and_then(|n| n.to_str())
+            .unwrap_or("<untitled>");
+        let definitions = Compiler::with_source(file_name.to_string())
+            .with_optimization(false)
+            .compile_definitions(&module)
+            .map_err(|errors| {
+                errors.iter().map(|e| format!("{}", e)).collect::<Vec<_>>().join("\n")
+            })?;
+
+        Ok(self.vm.hot_reload(definitions))
+    }
+
+    /// Restart a frame of the call stack (0 = innermost) from its first line
+    pub fn restart_frame(&mut self, frame_index: usize) -> DebugResult {
+        if self.state != DebugSessionState::Paused {
+            return DebugResult::Error("Cannot restart: not paused".to_string());
+        }
+
+        match self.vm.restart_frame(frame_index) {
+            Ok(state) => self.handle_debug_result(DebugStepResult::Paused(state)),
+            Err(e) => DebugResult::Error(e),
+        }
+    }
+
+    /// Step back to the previous line recorded in the execution trace
+    pub fn step_back(&mut self) -> DebugResult {
+        if self.state != DebugSessionState::Paused {
+            return DebugResult::Error("Cannot step back: not paused".to_string());
+        }
+
+        match self.vm.step_back() {
+            Some(state) => self.handle_debug_result(DebugStepResult::Paused(state)),
+            None => DebugResult::Error("Already at the start of the recorded trace".to_string()),
+        }
+    }
+
+    /// Run backwards through the execution trace to the previous breakpoint
+    pub fn reverse_continue(&mut self) -> DebugResult {
+        if self.state != DebugSessionState::Paused {
+            return DebugResult::Error("Cannot reverse: not paused".to_string());
+        }
+
+        match self.vm.reverse_continue() {
+            Some(state) => self.handle_debug_result(DebugStepResult::Paused(state)),
+            None => DebugResult::Error("Already at the start of the recorded trace".to_string()),
+        }
+    }
+
+    /// Get the recorded lines, oldest first, for the timeline
+    pub fn execution_trace(&self) -> Vec<TraceEntry> {
+        self.vm.execution_trace()
+    }
+
     /// Stop the debug session
     pub fn stop(&mut self) {
         self.state = DebugSessionState::Idle;
@@ -293,6 +367,42 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_debug_session_hot_reload() {
+        let mut session = DebugSession::new();
+        let source = "fx double(x) {\nx * 2\n}\nfx main() {\ndouble(5)\n}\nmain()";
+        assert!(matches!(
+            session.start(source, None, &[(2, None)]),
+            DebugResult::Paused { line: 2, .. }
+        ));
+
+        let report = session.hot_reload(&source.replace("x * 2", "x * 3")).unwrap();
+        assert_eq!(report.reloaded, vec!["double".to_string()]);
+
+        match session.restart_frame(0) {
+            DebugResult::Paused { line, .. } => assert_eq!(line, 2),
+            _ => panic!("Expected a pause after restarting"),
+        }
+        assert!(matches!(session.continue_execution(), DebugResult::Completed(_)));
+    }
+
+    #[test]
+    fn test_debug_session_step_back() {
+        let mut session = DebugSession::new();
+        let source = "let x = 1\nx = x + 1\nx = x + 1\nx";
+        assert!(matches!(
+            session.start(source, None, &[(3, None)]),
+            DebugResult::Paused { line: 3, .. }
+        ));
+        assert!(!session.execution_trace().is_empty());
+
+        match session.step_back() {
+            DebugResult::Paused { line, .. } => assert_eq!(line, 2),
+            _ => panic!("Expected a pause after stepping back"),
+        }
+        assert!(matches!(session.continue_execution(), DebugResult::Completed(_)));
+    }
+
     #[test]
     fn test_debug_session_with_breakpoint() {
         let mut session = DebugSession::new();
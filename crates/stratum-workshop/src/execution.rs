@@ -430,6 +430,8 @@ pub fn build_source(source: &str, file_path: &Path, release: bool) -> BuildResul
         output: output_path.clone(),
         optimize: release,
         extra_flags: Vec::new(),
+        env: Vec::new(),
+        object_dir: None,
     });
 
     if let Err(e) = linker.link(product) {
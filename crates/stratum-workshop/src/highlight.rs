@@ -67,6 +67,7 @@ impl HighlightKind {
             // Keywords
             TokenKind::Fx
             | TokenKind::Let
+            | TokenKind::Const
             | TokenKind::If
             | TokenKind::Else
             | TokenKind::For
@@ -106,6 +107,9 @@ impl HighlightKind {
             TokenKind::StringStart
             | TokenKind::StringPart
             | TokenKind::StringEnd
+            | TokenKind::MultiLineStringStart
+            | TokenKind::MultiLineStringEnd
+            | TokenKind::RawString
             | TokenKind::InterpolationStart
             | TokenKind::InterpolationEnd => Self::String,
 
@@ -0,0 +1,1293 @@
+//! Keymaps for the editor
+//!
+//! The standard keymap leaves keys to the editor widget. The Vim keymap is
+//! modal: Normal mode moves and edits with counts, operators and registers,
+//! Insert mode types, and Visual mode selects characters or lines. The Emacs
+//! keymap adds the usual Ctrl and Alt keys for moving, killing and yanking.
+//!
+//! Keymaps work on the editor's text and cursor and hand back the new text,
+//! cursor and selection, so they don't depend on the editor widget.
+
+use crate::language::move_cursor;
+use iced::keyboard::{key, Key};
+use iced::widget::text_editor;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+
+/// A line and column, 0-based
+pub type Position = (usize, usize);
+
+/// How editor keys behave
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Keymap {
+    #[default]
+    Standard,
+    Vim,
+    Emacs,
+}
+
+impl Keymap {
+    /// The keymap after this one, for cycling through them
+    #[must_use]
+    pub fn next(self) -> Self {
+        match self {
+            Self::Standard => Self::Vim,
+            Self::Vim => Self::Emacs,
+            Self::Emacs => Self::Standard,
+        }
+    }
+}
+
+impl fmt::Display for Keymap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Standard => "Standard",
+            Self::Vim => "Vim",
+            Self::Emacs => "Emacs",
+        })
+    }
+}
+
+/// Vim mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Normal,
+    Insert,
+    Visual,
+    VisualLine,
+}
+
+/// A key the keymap handles
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Input {
+    Char(char),
+    Escape,
+    Enter,
+    Backspace,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+}
+
+/// A key press with the modifiers keymaps care about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EditorKey {
+    pub input: Input,
+    pub ctrl: bool,
+    pub alt: bool,
+}
+
+impl EditorKey {
+    fn plain(input: Input) -> Self {
+        Self {
+            input,
+            ctrl: false,
+            alt: false,
+        }
+    }
+}
+
+/// Who handles a key press in the editor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Handling {
+    /// The editor widget, as usual
+    Editor,
+    /// The keymap
+    Keymap(EditorKey),
+    /// Nobody, like typing in Vim's Normal mode
+    Ignore,
+}
+
+/// Who handles a key press, given the keymap and Vim mode
+pub fn handling(keymap: Keymap, mode: Mode, key_press: &text_editor::KeyPress) -> Handling {
+    let ctrl = key_press.modifiers.command();
+    let alt = key_press.modifiers.alt();
+    let input = match key_press.key.as_ref() {
+        // Typed text has shift applied, so `$` rather than `4`
+        Key::Character(c) if !ctrl && !alt => key_press
+            .text
+            .as_deref()
+            .and_then(|text| text.chars().next())
+            .or_else(|| c.chars().next())
+            .map(Input::Char),
+        Key::Character(c) => c.chars().next().map(Input::Char),
+        Key::Named(key::Named::Space) => Some(Input::Char(' ')),
+        Key::Named(key::Named::Escape) => Some(Input::Escape),
+        Key::Named(key::Named::Enter) => Some(Input::Enter),
+        Key::Named(key::Named::Backspace) => Some(Input::Backspace),
+        Key::Named(key::Named::ArrowLeft) => Some(Input::Left),
+        Key::Named(key::Named::ArrowRight) => Some(Input::Right),
+        Key::Named(key::Named::ArrowUp) => Some(Input::Up),
+        Key::Named(key::Named::ArrowDown) => Some(Input::Down),
+        Key::Named(key::Named::Home) => Some(Input::Home),
+        Key::Named(key::Named::End) => Some(Input::End),
+        _ => None,
+    };
+    let key = input.map(|input| EditorKey { input, ctrl, alt });
+
+    match (keymap, mode, key) {
+        (Keymap::Standard, _, _) => Handling::Editor,
+        (Keymap::Vim, Mode::Insert, Some(key))
+            if key.input == Input::Escape || (key.ctrl && key.input == Input::Char('[')) =>
+        {
+            Handling::Keymap(key)
+        }
+        (Keymap::Vim, Mode::Insert, _) => Handling::Editor,
+        // Shortcuts and function keys keep working outside Insert mode
+        (Keymap::Vim, _, _) if ctrl || alt => Handling::Editor,
+        (Keymap::Vim, _, Some(key)) => Handling::Keymap(key),
+        (Keymap::Vim, _, None) => match key_press.key.as_ref() {
+            Key::Named(named) if is_function_key(named) => Handling::Editor,
+            _ => Handling::Ignore,
+        },
+        (Keymap::Emacs, _, Some(key)) if is_emacs_key(key) => Handling::Keymap(key),
+        (Keymap::Emacs, _, _) => Handling::Editor,
+    }
+}
+
+fn is_function_key(named: key::Named) -> bool {
+    use key::Named::{F1, F10, F11, F12, F2, F3, F4, F5, F6, F7, F8, F9};
+    matches!(
+        named,
+        F1 | F2 | F3 | F4 | F5 | F6 | F7 | F8 | F9 | F10 | F11 | F12
+    )
+}
+
+fn is_emacs_key(key: EditorKey) -> bool {
+    let Input::Char(c) = key.input else {
+        return false;
+    };
+    match (key.ctrl, key.alt) {
+        (true, false) => "fbnpaedkywg ".contains(c),
+        (false, true) => "fbdw<>".contains(c),
+        _ => false,
+    }
+}
+
+/// What a key did to the editor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    /// The new text, if the key edited it
+    pub text: Option<String>,
+    pub cursor: Position,
+    /// Start of the selection to show, which ends at the cursor
+    pub anchor: Option<Position>,
+}
+
+impl Outcome {
+    /// Show the outcome in the editor
+    pub fn apply(&self, content: &mut text_editor::Content) {
+        use text_editor::{Action, Motion};
+
+        if let Some(text) = &self.text {
+            *content = text_editor::Content::with_text(text);
+        }
+        let Some(anchor) = self.anchor else {
+            move_cursor(content, self.cursor.0, self.cursor.1);
+            return;
+        };
+        move_cursor(content, anchor.0, anchor.1);
+        let (line, column) = self.cursor;
+        if line == anchor.0 {
+            let motion = if column > anchor.1 {
+                Motion::Right
+            } else {
+                Motion::Left
+            };
+            for _ in 0..column.abs_diff(anchor.1) {
+                content.perform(Action::Select(motion));
+            }
+            return;
+        }
+        let motion = if line > anchor.0 {
+            Motion::Down
+        } else {
+            Motion::Up
+        };
+        for _ in 0..line.abs_diff(anchor.0) {
+            content.perform(Action::Select(motion));
+        }
+        content.perform(Action::Select(Motion::Home));
+        for _ in 0..column {
+            content.perform(Action::Select(Motion::Right));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Delete,
+    Change,
+    Yank,
+}
+
+impl Operator {
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            'd' => Some(Self::Delete),
+            'c' => Some(Self::Change),
+            'y' => Some(Self::Yank),
+            _ => None,
+        }
+    }
+
+    fn key(self) -> char {
+        match self {
+            Self::Delete => 'd',
+            Self::Change => 'c',
+            Self::Yank => 'y',
+        }
+    }
+}
+
+/// How an operator treats the text a motion moves over
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    /// Up to the target, not including it
+    Exclusive,
+    /// Up to and including the target
+    Inclusive,
+    /// Whole lines
+    Linewise,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Register {
+    text: String,
+    linewise: bool,
+}
+
+/// The keymap in use and its state: Vim's mode, pending keys and registers,
+/// or Emacs's mark
+#[derive(Debug)]
+pub struct KeymapState {
+    keymap: Keymap,
+    mode: Mode,
+    count: Option<usize>,
+    /// Operator waiting for a motion, with the count typed before it
+    operator: Option<(Operator, Option<usize>)>,
+    /// `g` typed, waiting for the second `g`
+    g_prefix: bool,
+    /// `"` typed, waiting for a register name
+    register_prefix: bool,
+    /// Register for the next yank, delete or put
+    register: Option<char>,
+    registers: HashMap<char, Register>,
+    /// Where Visual mode started, or Emacs's mark
+    anchor: Option<Position>,
+    /// The Visual mode cursor, which the selection shown ends past
+    visual_cursor: Position,
+    /// Whether the last Emacs key killed text, so the next kill appends
+    last_kill: bool,
+}
+
+impl KeymapState {
+    pub fn new(keymap: Keymap) -> Self {
+        Self {
+            keymap,
+            mode: if keymap == Keymap::Vim {
+                Mode::Normal
+            } else {
+                Mode::Insert
+            },
+            count: None,
+            operator: None,
+            g_prefix: false,
+            register_prefix: false,
+            register: None,
+            registers: HashMap::new(),
+            anchor: None,
+            visual_cursor: (0, 0),
+            last_kill: false,
+        }
+    }
+
+    pub fn keymap(&self) -> Keymap {
+        self.keymap
+    }
+
+    /// Switch keymaps, keeping registers
+    pub fn set_keymap(&mut self, keymap: Keymap) {
+        let registers = std::mem::take(&mut self.registers);
+        *self = Self::new(keymap);
+        self.registers = registers;
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Start over in a newly opened file: Normal mode in Vim, no pending
+    /// keys, no mark
+    pub fn reset(&mut self) {
+        self.set_keymap(self.keymap);
+    }
+
+    /// Text in a register
+    pub fn register(&self, name: char) -> Option<&str> {
+        self.registers.get(&name).map(|r| r.text.as_str())
+    }
+
+    /// The mode and pending keys, for the status bar
+    pub fn status(&self) -> Option<String> {
+        match self.keymap {
+            Keymap::Standard => None,
+            Keymap::Emacs => Some(
+                if self.anchor.is_some() {
+                    "Emacs (mark set)"
+                } else {
+                    "Emacs"
+                }
+                .to_string(),
+            ),
+            Keymap::Vim => {
+                let mode = match self.mode {
+                    Mode::Normal => "NORMAL",
+                    Mode::Insert => "INSERT",
+                    Mode::Visual => "VISUAL",
+                    Mode::VisualLine => "VISUAL LINE",
+                };
+                let mut pending = String::new();
+                if let Some(register) = self.register {
+                    pending.push('"');
+                    pending.push(register);
+                }
+                if let Some((operator, count)) = self.operator {
+                    pending.extend(count.map(|n| n.to_string()));
+                    pending.push(operator.key());
+                }
+                pending.extend(self.count.map(|n| n.to_string()));
+                if self.register_prefix {
+                    pending.push('"');
+                }
+                if self.g_prefix {
+                    pending.push('g');
+                }
+                Some(format!("-- {mode} -- {pending}").trim_end().to_string())
+            }
+        }
+    }
+
+    /// Handle a key on the editor's text with the cursor at a position
+    pub fn handle(&mut self, key: EditorKey, text: &str, cursor: Position) -> Outcome {
+        let mut buffer = Buffer::new(text);
+        let cursor = if matches!(self.mode, Mode::Visual | Mode::VisualLine) {
+            buffer.clamp(self.visual_cursor, false)
+        } else {
+            buffer.clamp(cursor, true)
+        };
+        let cursor = match self.keymap {
+            Keymap::Standard => cursor,
+            Keymap::Vim => self.vim(key, &mut buffer, cursor),
+            Keymap::Emacs => self.emacs(key, &mut buffer, cursor),
+        };
+        let new_text = buffer.text();
+        let edited = new_text != text;
+        if edited && self.keymap == Keymap::Emacs {
+            self.anchor = None;
+        }
+
+        let (anchor, cursor) = match (self.mode, self.anchor) {
+            (Mode::Visual, Some(anchor)) => {
+                self.visual_cursor = cursor;
+                let (start, end) = (buffer.offset(anchor), buffer.offset(cursor));
+                if end >= start {
+                    (Some(anchor), buffer.position(end + 1))
+                } else {
+                    (Some(buffer.position(start + 1)), cursor)
+                }
+            }
+            (Mode::VisualLine, Some(anchor)) => {
+                self.visual_cursor = cursor;
+                let (first, last) = (anchor.0, cursor.0);
+                if last >= first {
+                    (Some((first, 0)), (last, buffer.line_len(last)))
+                } else {
+                    (Some((first, buffer.line_len(first))), (last, 0))
+                }
+            }
+            (_, anchor) if self.keymap == Keymap::Emacs => (anchor, cursor),
+            _ => (None, cursor),
+        };
+        Outcome {
+            text: edited.then_some(new_text),
+            cursor,
+            anchor,
+        }
+    }
+
+    fn vim(&mut self, key: EditorKey, buffer: &mut Buffer, cursor: Position) -> Position {
+        let c = match key.input {
+            Input::Escape => {
+                return self.escape(buffer, cursor);
+            }
+            Input::Char('[') if key.ctrl => return self.escape(buffer, cursor),
+            Input::Char(c) => c,
+            Input::Left | Input::Backspace => 'h',
+            Input::Right => 'l',
+            Input::Up => 'k',
+            Input::Down | Input::Enter => 'j',
+            Input::Home => '0',
+            Input::End => '$',
+        };
+        if self.mode == Mode::Insert {
+            return cursor;
+        }
+
+        if self.register_prefix {
+            self.register_prefix = false;
+            if c.is_ascii_alphanumeric() || c == '"' {
+                self.register = Some(c);
+            }
+            return cursor;
+        }
+        if c == '"' {
+            self.register_prefix = true;
+            return cursor;
+        }
+        if let Some(digit) = c.to_digit(10) {
+            if digit > 0 || self.count.is_some() {
+                let count = self.count.unwrap_or(0).saturating_mul(10);
+                self.count = Some(count.saturating_add(digit as usize));
+                return cursor;
+            }
+        }
+        if c == 'g' && !self.g_prefix {
+            self.g_prefix = true;
+            return cursor;
+        }
+        let gg = std::mem::take(&mut self.g_prefix);
+        if gg && c != 'g' {
+            self.clear_pending();
+            return cursor;
+        }
+        let count = self.count.take();
+        let n = count.unwrap_or(1);
+
+        if matches!(self.mode, Mode::Visual | Mode::VisualLine) {
+            return self.visual(c, gg, count, buffer, cursor);
+        }
+
+        if let Some((operator, operator_count)) = self.operator.take() {
+            // Counts before and after the operator multiply
+            let count = match (operator_count, count) {
+                (None, None) => None,
+                (a, b) => Some(a.unwrap_or(1) * b.unwrap_or(1)),
+            };
+            let n = count.unwrap_or(1);
+            if c == operator.key() {
+                let last = (cursor.0 + n - 1).min(buffer.last_line());
+                return self.operate(operator, buffer, cursor, (last, 0), Kind::Linewise);
+            }
+            // `cw` changes to the end of the word, like `ce`
+            let c = if operator == Operator::Change && c == 'w' && !buffer.at_blank(cursor) {
+                'e'
+            } else {
+                c
+            };
+            if let Some((target, kind)) = motion(buffer, cursor, c, gg, count, true) {
+                return self.operate(operator, buffer, cursor, target, kind);
+            }
+            self.register = None;
+            return cursor;
+        }
+
+        if let Some((target, _)) = motion(buffer, cursor, c, gg, count, false) {
+            return buffer.clamp(target, false);
+        }
+        if let Some(operator) = Operator::from_char(c) {
+            self.operator = Some((operator, count));
+            return cursor;
+        }
+
+        let (line, column) = cursor;
+        let len = buffer.line_len(line);
+        match c {
+            'i' => self.mode = Mode::Insert,
+            'a' => {
+                self.mode = Mode::Insert;
+                return (line, (column + 1).min(len));
+            }
+            'I' => {
+                self.mode = Mode::Insert;
+                return buffer.first_non_blank(line);
+            }
+            'A' => {
+                self.mode = Mode::Insert;
+                return (line, len);
+            }
+            'o' => {
+                let at = buffer.line_end(line);
+                buffer.replace(at..at, "\n");
+                self.mode = Mode::Insert;
+                return (line + 1, 0);
+            }
+            'O' => {
+                let at = buffer.starts[line];
+                buffer.replace(at..at, "\n");
+                self.mode = Mode::Insert;
+                return (line, 0);
+            }
+            'v' | 'V' => {
+                self.mode = if c == 'v' {
+                    Mode::Visual
+                } else {
+                    Mode::VisualLine
+                };
+                self.anchor = Some(cursor);
+            }
+            'x' if len > 0 => {
+                let target = (line, (column + n).min(len));
+                return self.operate(Operator::Delete, buffer, cursor, target, Kind::Exclusive);
+            }
+            'X' if column > 0 => {
+                let target = (line, column.saturating_sub(n));
+                return self.operate(Operator::Delete, buffer, cursor, target, Kind::Exclusive);
+            }
+            'D' | 'C' => {
+                let operator = if c == 'D' {
+                    Operator::Delete
+                } else {
+                    Operator::Change
+                };
+                let (target, kind) = motion(buffer, cursor, '$', false, count, true)
+                    .unwrap_or((cursor, Kind::Exclusive));
+                return self.operate(operator, buffer, cursor, target, kind);
+            }
+            's' => {
+                let target = (line, (column + n).min(len));
+                return self.operate(Operator::Change, buffer, cursor, target, Kind::Exclusive);
+            }
+            'S' | 'Y' => {
+                let operator = if c == 'S' {
+                    Operator::Change
+                } else {
+                    Operator::Yank
+                };
+                let last = (line + n - 1).min(buffer.last_line());
+                return self.operate(operator, buffer, cursor, (last, 0), Kind::Linewise);
+            }
+            'p' | 'P' => return self.put(buffer, cursor, c == 'p', n),
+            'J' => {
+                for _ in 0..n.saturating_sub(1).max(1) {
+                    if line >= buffer.last_line() {
+                        break;
+                    }
+                    let end = buffer.line_end(line);
+                    let next = buffer.starts[line + 1];
+                    let blanks = buffer.chars[next..buffer.line_end(line + 1)]
+                        .iter()
+                        .take_while(|c| c.is_whitespace())
+                        .count();
+                    let joined =
+                        next + blanks == buffer.line_end(line + 1) || end == buffer.starts[line];
+                    buffer.replace(end..next + blanks, if joined { "" } else { " " });
+                }
+                return buffer.clamp((line, len), false);
+            }
+            _ => self.register = None,
+        }
+        cursor
+    }
+
+    /// Leave Insert or Visual mode, or drop pending keys
+    fn escape(&mut self, buffer: &Buffer, cursor: Position) -> Position {
+        self.clear_pending();
+        let mode = std::mem::replace(&mut self.mode, Mode::Normal);
+        self.anchor = None;
+        if mode == Mode::Insert {
+            // Vim leaves the cursor on the last character typed
+            buffer.clamp((cursor.0, cursor.1.saturating_sub(1)), false)
+        } else {
+            cursor
+        }
+    }
+
+    fn clear_pending(&mut self) {
+        self.count = None;
+        self.operator = None;
+        self.g_prefix = false;
+        self.register_prefix = false;
+        self.register = None;
+    }
+
+    /// A key in Visual mode: motions extend the selection, operators act
+    /// on it
+    fn visual(
+        &mut self,
+        c: char,
+        gg: bool,
+        count: Option<usize>,
+        buffer: &mut Buffer,
+        cursor: Position,
+    ) -> Position {
+        if let Some((target, _)) = motion(buffer, cursor, c, gg, count, false) {
+            return buffer.clamp(target, false);
+        }
+        let anchor = self.anchor.unwrap_or(cursor);
+        let operator = match c {
+            'd' | 'x' => Operator::Delete,
+            'c' | 's' => Operator::Change,
+            'y' => Operator::Yank,
+            'o' => {
+                self.anchor = Some(cursor);
+                return anchor;
+            }
+            'v' | 'V' => {
+                let mode = if c == 'v' {
+                    Mode::Visual
+                } else {
+                    Mode::VisualLine
+                };
+                if self.mode == mode {
+                    self.mode = Mode::Normal;
+                    self.anchor = None;
+                } else {
+                    self.mode = mode;
+                }
+                return cursor;
+            }
+            _ => return cursor,
+        };
+        let kind = if self.mode == Mode::VisualLine {
+            Kind::Linewise
+        } else {
+            Kind::Inclusive
+        };
+        self.mode = Mode::Normal;
+        self.anchor = None;
+        self.operate(operator, buffer, anchor, cursor, kind)
+    }
+
+    /// Delete, change or yank from `from` to `to`
+    fn operate(
+        &mut self,
+        operator: Operator,
+        buffer: &mut Buffer,
+        from: Position,
+        to: Position,
+        kind: Kind,
+    ) -> Position {
+        if kind == Kind::Linewise {
+            let (first, last) = (from.0.min(to.0), from.0.max(to.0));
+            let range = buffer.starts[first]..buffer.line_end(last);
+            let mut text = buffer.slice(range.clone());
+            text.push('\n');
+            self.store(text, true, operator == Operator::Yank);
+            return match operator {
+                Operator::Yank => buffer.clamp((first, from.1), false),
+                Operator::Delete => {
+                    buffer.replace(buffer.lines_range(first, last), "");
+                    buffer.first_non_blank(first.min(buffer.last_line()))
+                }
+                Operator::Change => {
+                    buffer.replace(range, "");
+                    self.mode = Mode::Insert;
+                    (first, 0)
+                }
+            };
+        }
+
+        let (from, to) = (buffer.offset(from), buffer.offset(to));
+        let (start, mut end) = (from.min(to), from.max(to));
+        if kind == Kind::Inclusive {
+            let line = buffer.position(end).0;
+            end = (end + 1).min(buffer.line_end(line)).max(end);
+        }
+        self.store(buffer.slice(start..end), false, operator == Operator::Yank);
+        match operator {
+            Operator::Yank => {}
+            Operator::Delete => buffer.replace(start..end, ""),
+            Operator::Change => {
+                buffer.replace(start..end, "");
+                self.mode = Mode::Insert;
+                return buffer.position(start);
+            }
+        }
+        buffer.clamp(buffer.position(start), false)
+    }
+
+    /// Save yanked or deleted text in the selected register, and the
+    /// unnamed one. Uppercase register names append.
+    fn store(&mut self, text: String, linewise: bool, yank: bool) {
+        let name = self.register.take().unwrap_or('"');
+        let register = if name.is_ascii_uppercase() {
+            let lower = name.to_ascii_lowercase();
+            let register = self.registers.entry(lower).or_insert(Register {
+                text: String::new(),
+                linewise,
+            });
+            register.text.push_str(&text);
+            register.linewise |= linewise;
+            register.clone()
+        } else {
+            let register = Register { text, linewise };
+            self.registers.insert(name, register.clone());
+            register
+        };
+        // Register 0 holds the last yank that didn't name a register
+        if yank && name == '"' {
+            self.registers.insert('0', register.clone());
+        }
+        self.registers.insert('"', register);
+    }
+
+    /// Put a register's text after or before the cursor, `n` times
+    fn put(&mut self, buffer: &mut Buffer, cursor: Position, after: bool, n: usize) -> Position {
+        let name = self.register.take().unwrap_or('"');
+        let Some(register) = self.registers.get(&name.to_ascii_lowercase()).cloned() else {
+            return cursor;
+        };
+        if register.text.is_empty() {
+            return cursor;
+        }
+        let (line, _) = cursor;
+        if register.linewise {
+            let mut text = register.text;
+            if !text.ends_with('\n') {
+                text.push('\n');
+            }
+            let text = text.repeat(n);
+            if after {
+                let at = buffer.line_end(line);
+                buffer.replace(at..at, &format!("\n{}", &text[..text.len() - 1]));
+                buffer.first_non_blank(line + 1)
+            } else {
+                let at = buffer.starts[line];
+                buffer.replace(at..at, &text);
+                buffer.first_non_blank(line)
+            }
+        } else {
+            let text = register.text.repeat(n);
+            let mut at = buffer.offset(cursor);
+            if after && buffer.line_len(line) > 0 {
+                at += 1;
+            }
+            buffer.replace(at..at, &text);
+            buffer.clamp(buffer.position(at + text.chars().count() - 1), false)
+        }
+    }
+
+    fn emacs(&mut self, key: EditorKey, buffer: &mut Buffer, cursor: Position) -> Position {
+        let Input::Char(c) = key.input else {
+            return cursor;
+        };
+        let killing = std::mem::take(&mut self.last_kill);
+        let offset = buffer.offset(cursor);
+        let (line, column) = cursor;
+        match (key.ctrl, key.alt, c) {
+            (true, false, 'f') => buffer.position((offset + 1).min(buffer.chars.len())),
+            (true, false, 'b') => buffer.position(offset.saturating_sub(1)),
+            (true, false, 'n') => buffer.clamp((line + 1, column), true),
+            (true, false, 'p') => buffer.clamp((line.saturating_sub(1), column), true),
+            (true, false, 'a') => (line, 0),
+            (true, false, 'e') => (line, buffer.line_len(line)),
+            (false, true, 'f') => buffer.position(buffer.forward_word(offset)),
+            (false, true, 'b') => buffer.position(buffer.backward_word(offset)),
+            (false, true, '<') => (0, 0),
+            (false, true, '>') => buffer.position(buffer.chars.len()),
+            (true, false, 'd') => {
+                buffer.replace(offset..(offset + 1).min(buffer.chars.len()), "");
+                cursor
+            }
+            (false, true, 'd') => self.kill(buffer, offset..buffer.forward_word(offset), killing),
+            (true, false, 'k') => {
+                let end = buffer.line_end(line);
+                // At the end of a line, kill the newline
+                let end = if offset == end {
+                    (end + 1).min(buffer.chars.len())
+                } else {
+                    end
+                };
+                self.kill(buffer, offset..end, killing)
+            }
+            (true, false, 'w') => match self.anchor.take() {
+                Some(mark) => {
+                    let mark = buffer.offset(mark);
+                    self.kill(buffer, mark.min(offset)..mark.max(offset), killing)
+                }
+                None => cursor,
+            },
+            (false, true, 'w') => {
+                if let Some(mark) = self.anchor.take() {
+                    let mark = buffer.offset(mark);
+                    let text = buffer.slice(mark.min(offset)..mark.max(offset));
+                    self.store(text, false, true);
+                }
+                cursor
+            }
+            (true, false, 'y') => {
+                let text = self.register('"').unwrap_or_default().to_string();
+                buffer.replace(offset..offset, &text);
+                buffer.position(offset + text.chars().count())
+            }
+            (true, false, ' ') => {
+                self.anchor = Some(cursor);
+                cursor
+            }
+            (true, false, 'g') => {
+                self.anchor = None;
+                cursor
+            }
+            _ => cursor,
+        }
+    }
+
+    /// Kill text into the unnamed register, appending to the last kill if
+    /// the previous key killed too
+    fn kill(&mut self, buffer: &mut Buffer, range: Range<usize>, append: bool) -> Position {
+        let mut text = buffer.slice(range.clone());
+        if append {
+            text.insert_str(0, self.register('"').unwrap_or_default());
+        }
+        self.store(text, false, false);
+        self.last_kill = true;
+        buffer.replace(range.clone(), "");
+        buffer.position(range.start)
+    }
+}
+
+/// Where a Vim motion key moves the cursor, and how an operator treats the
+/// text it moves over
+fn motion(
+    buffer: &Buffer,
+    cursor: Position,
+    c: char,
+    gg: bool,
+    count: Option<usize>,
+    operator: bool,
+) -> Option<(Position, Kind)> {
+    let n = count.unwrap_or(1);
+    let (line, column) = cursor;
+    let last = buffer.last_line();
+    let len = buffer.line_len(line);
+    let target = match c {
+        'g' if gg => {
+            let line = count.map_or(0, |n| n - 1).min(last);
+            (buffer.first_non_blank(line), Kind::Linewise)
+        }
+        'G' => {
+            let line = count.map_or(last, |n| n.saturating_sub(1)).min(last);
+            (buffer.first_non_blank(line), Kind::Linewise)
+        }
+        'h' => ((line, column.saturating_sub(n)), Kind::Exclusive),
+        'l' | ' ' => {
+            let end = if operator { len } else { len.saturating_sub(1) };
+            ((line, (column + n).min(end)), Kind::Exclusive)
+        }
+        'j' => (((line + n).min(last), column), Kind::Linewise),
+        'k' => ((line.saturating_sub(n), column), Kind::Linewise),
+        'w' => {
+            let offset = (0..n).fold(buffer.offset(cursor), |o, _| buffer.next_word(o));
+            let mut target = buffer.position(offset);
+            // An operator stops at the end of the line
+            if operator && target.0 > line {
+                target = (line, len);
+            }
+            (target, Kind::Exclusive)
+        }
+        'b' => {
+            let offset = (0..n).fold(buffer.offset(cursor), |o, _| buffer.previous_word(o));
+            (buffer.position(offset), Kind::Exclusive)
+        }
+        'e' => {
+            let offset = (0..n).fold(buffer.offset(cursor), |o, _| buffer.word_end(o));
+            (buffer.position(offset), Kind::Inclusive)
+        }
+        '0' => ((line, 0), Kind::Exclusive),
+        '^' => (buffer.first_non_blank(line), Kind::Exclusive),
+        '$' => {
+            let line = (line + n - 1).min(last);
+            (
+                (line, buffer.line_len(line).saturating_sub(1)),
+                Kind::Inclusive,
+            )
+        }
+        _ => return None,
+    };
+    Some(target)
+}
+
+/// Text as characters, with the offset each line starts at
+struct Buffer {
+    chars: Vec<char>,
+    starts: Vec<usize>,
+}
+
+impl Buffer {
+    fn new(text: &str) -> Self {
+        let mut buffer = Self {
+            chars: text.chars().collect(),
+            starts: Vec::new(),
+        };
+        buffer.index_lines();
+        buffer
+    }
+
+    fn index_lines(&mut self) {
+        self.starts = std::iter::once(0)
+            .chain(
+                self.chars
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, c)| **c == '\n')
+                    .map(|(i, _)| i + 1),
+            )
+            .collect();
+    }
+
+    fn text(&self) -> String {
+        self.chars.iter().collect()
+    }
+
+    fn last_line(&self) -> usize {
+        self.starts.len() - 1
+    }
+
+    /// Offset of the end of a line, before its newline
+    fn line_end(&self, line: usize) -> usize {
+        self.starts
+            .get(line + 1)
+            .map_or(self.chars.len(), |next| next - 1)
+    }
+
+    fn line_len(&self, line: usize) -> usize {
+        self.line_end(line) - self.starts[line]
+    }
+
+    fn offset(&self, (line, column): Position) -> usize {
+        let line = line.min(self.last_line());
+        self.starts[line] + column.min(self.line_len(line))
+    }
+
+    fn position(&self, offset: usize) -> Position {
+        let offset = offset.min(self.chars.len());
+        let line = self.starts.partition_point(|start| *start <= offset) - 1;
+        (line, offset - self.starts[line])
+    }
+
+    /// A position on the text: past a line's last character only when
+    /// `insert`, as in Vim's Insert mode
+    fn clamp(&self, (line, column): Position, insert: bool) -> Position {
+        let line = line.min(self.last_line());
+        let len = self.line_len(line);
+        let max = if insert { len } else { len.saturating_sub(1) };
+        (line, column.min(max))
+    }
+
+    fn first_non_blank(&self, line: usize) -> Position {
+        let start = self.starts[line];
+        let blanks = self.chars[start..self.line_end(line)]
+            .iter()
+            .take_while(|c| c.is_whitespace())
+            .count();
+        self.clamp((line, blanks), false)
+    }
+
+    fn at_blank(&self, position: Position) -> bool {
+        self.chars
+            .get(self.offset(position))
+            .filter(|c| !c.is_whitespace())
+            .is_none()
+    }
+
+    fn slice(&self, range: Range<usize>) -> String {
+        self.chars[range].iter().collect()
+    }
+
+    fn replace(&mut self, range: Range<usize>, text: &str) {
+        self.chars.splice(range, text.chars());
+        self.index_lines();
+    }
+
+    /// Whole lines with one newline: the one after them, or before them for
+    /// the last line
+    fn lines_range(&self, first: usize, last: usize) -> Range<usize> {
+        match self.starts.get(last + 1) {
+            Some(next) => self.starts[first]..*next,
+            None => self.starts[first].saturating_sub(1)..self.chars.len(),
+        }
+    }
+
+    fn class(&self, offset: usize) -> CharClass {
+        CharClass::of(self.chars[offset])
+    }
+
+    /// Start of the next word, like Vim's `w`
+    fn next_word(&self, offset: usize) -> usize {
+        let len = self.chars.len();
+        let mut offset = offset;
+        if offset < len {
+            let class = self.class(offset);
+            if class != CharClass::Blank {
+                while offset < len && self.class(offset) == class {
+                    offset += 1;
+                }
+            }
+        }
+        while offset < len && self.class(offset) == CharClass::Blank {
+            offset += 1;
+        }
+        offset
+    }
+
+    /// Start of this or the previous word, like Vim's `b`
+    fn previous_word(&self, offset: usize) -> usize {
+        let mut offset = offset.min(self.chars.len());
+        while offset > 0 && self.class(offset - 1) == CharClass::Blank {
+            offset -= 1;
+        }
+        if offset == 0 {
+            return 0;
+        }
+        let class = self.class(offset - 1);
+        while offset > 0 && self.class(offset - 1) == class {
+            offset -= 1;
+        }
+        offset
+    }
+
+    /// Last character of this or the next word, like Vim's `e`
+    fn word_end(&self, offset: usize) -> usize {
+        let len = self.chars.len();
+        let mut offset = offset + 1;
+        while offset < len && self.class(offset) == CharClass::Blank {
+            offset += 1;
+        }
+        if offset >= len {
+            return len.saturating_sub(1);
+        }
+        let class = self.class(offset);
+        while offset + 1 < len && self.class(offset + 1) == class {
+            offset += 1;
+        }
+        offset
+    }
+
+    /// Past the end of the next word, like Emacs's `M-f`
+    fn forward_word(&self, offset: usize) -> usize {
+        let len = self.chars.len();
+        let mut offset = offset;
+        while offset < len && self.class(offset) != CharClass::Word {
+            offset += 1;
+        }
+        while offset < len && self.class(offset) == CharClass::Word {
+            offset += 1;
+        }
+        offset
+    }
+
+    /// Start of this or the previous word, like Emacs's `M-b`
+    fn backward_word(&self, offset: usize) -> usize {
+        let mut offset = offset.min(self.chars.len());
+        while offset > 0 && self.class(offset - 1) != CharClass::Word {
+            offset -= 1;
+        }
+        while offset > 0 && self.class(offset - 1) == CharClass::Word {
+            offset -= 1;
+        }
+        offset
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    Blank,
+    Word,
+    Punctuation,
+}
+
+impl CharClass {
+    fn of(c: char) -> Self {
+        if c.is_whitespace() {
+            Self::Blank
+        } else if c.is_alphanumeric() || c == '_' {
+            Self::Word
+        } else {
+            Self::Punctuation
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Type keys in Vim and return the text and cursor
+    fn vim(state: &mut KeymapState, text: &str, keys: &str) -> (String, Position) {
+        let mut text = text.to_string();
+        let mut cursor = (0, 0);
+        for c in keys.chars() {
+            let input = if c == '\u{1b}' {
+                Input::Escape
+            } else {
+                Input::Char(c)
+            };
+            if state.mode() == Mode::Insert && input != Input::Escape {
+                // The editor widget types in Insert mode
+                let mut buffer = Buffer::new(&text);
+                let offset = buffer.offset(cursor);
+                buffer.replace(offset..offset, &c.to_string());
+                text = buffer.text();
+                cursor = buffer.position(offset + 1);
+                continue;
+            }
+            let outcome = state.handle(EditorKey::plain(input), &text, cursor);
+            text = outcome.text.unwrap_or(text);
+            cursor = outcome.cursor;
+        }
+        (text, cursor)
+    }
+
+    #[test]
+    fn test_motions_and_counts() {
+        let mut state = KeymapState::new(Keymap::Vim);
+        let text = "let total = add(1, 2)\nfoo bar\n  baz";
+        assert_eq!(vim(&mut state, text, "w").1, (0, 4));
+        assert_eq!(vim(&mut state, text, "3w").1, (0, 12));
+        assert_eq!(vim(&mut state, text, "e").1, (0, 2));
+        assert_eq!(vim(&mut state, text, "$").1, (0, 20));
+        assert_eq!(vim(&mut state, text, "G").1, (2, 2));
+        assert_eq!(vim(&mut state, text, "2gg").1, (1, 0));
+        assert_eq!(vim(&mut state, text, "jll").1, (1, 2));
+        assert_eq!(vim(&mut state, text, "10l").1, (0, 10));
+    }
+
+    #[test]
+    fn test_operators() {
+        let mut state = KeymapState::new(Keymap::Vim);
+        let text = "one two three\nfour\nfive";
+        assert_eq!(vim(&mut state, text, "dw").0, "two three\nfour\nfive");
+        assert_eq!(vim(&mut state, text, "d2w").0, "three\nfour\nfive");
+        assert_eq!(vim(&mut state, text, "2dd").0, "five");
+        assert_eq!(vim(&mut state, text, "Gdd").0, "one two three\nfour");
+        assert_eq!(vim(&mut state, text, "wD").0, "one \nfour\nfive");
+        assert_eq!(vim(&mut state, text, "x2x").0, " two three\nfour\nfive");
+        assert_eq!(
+            vim(&mut state, text, "cwsix\u{1b}").0,
+            "six two three\nfour\nfive"
+        );
+        assert_eq!(
+            vim(&mut state, text, "jccnine\u{1b}").0,
+            "one two three\nnine\nfive"
+        );
+        assert_eq!(vim(&mut state, text, "J").0, "one two three four\nfive");
+    }
+
+    #[test]
+    fn test_insert_mode() {
+        let mut state = KeymapState::new(Keymap::Vim);
+        let (text, cursor) = vim(&mut state, "ab", "Ac\u{1b}");
+        assert_eq!((text.as_str(), cursor), ("abc", (0, 2)));
+        assert_eq!(state.mode(), Mode::Normal);
+        assert_eq!(vim(&mut state, "a\nb", "ox\u{1b}").0, "a\nx\nb");
+        assert_eq!(vim(&mut state, "a\nb", "jOx\u{1b}").0, "a\nx\nb");
+        assert_eq!(vim(&mut state, "  a", "I-\u{1b}").0, "  -a");
+    }
+
+    #[test]
+    fn test_registers() {
+        let mut state = KeymapState::new(Keymap::Vim);
+        let text = "one\ntwo";
+        assert_eq!(vim(&mut state, text, "yyjp").0, "one\ntwo\none");
+        assert_eq!(vim(&mut state, text, "yyP").0, "one\none\ntwo");
+        assert_eq!(vim(&mut state, text, "\"ayyj\"byy\"ap").0, "one\ntwo\none");
+        assert_eq!(state.register('b'), Some("two\n"));
+        assert_eq!(state.register('0'), Some("one\n"));
+
+        // Uppercase names append, and deletes don't change register 0
+        vim(&mut state, text, "\"Ayw");
+        assert_eq!(state.register('a'), Some("one\none"));
+        vim(&mut state, text, "jdd");
+        assert_eq!(state.register('0'), Some("one\n"));
+        assert_eq!(state.register('"'), Some("two\n"));
+
+        assert_eq!(vim(&mut state, "abc", "xp").0, "bac");
+        assert_eq!(vim(&mut state, "abc", "yl3p").0, "aaaabc");
+    }
+
+    #[test]
+    fn test_visual_mode() {
+        let mut state = KeymapState::new(Keymap::Vim);
+        assert_eq!(vim(&mut state, "one two", "vlld").0, " two");
+        assert_eq!(vim(&mut state, "one two", "wvhhhd").0, "owo");
+        assert_eq!(vim(&mut state, "a\nb\nc", "Vjd").0, "c");
+        assert_eq!(vim(&mut state, "a\nb\nc", "jVyP").0, "a\nb\nb\nc");
+        assert_eq!(vim(&mut state, "abc", "lvcX\u{1b}").0, "aXc");
+
+        // The selection shown covers the character under the cursor
+        let mut state = KeymapState::new(Keymap::Vim);
+        vim(&mut state, "abc", "v");
+        let outcome = state.handle(EditorKey::plain(Input::Char('l')), "abc", (0, 0));
+        assert_eq!((outcome.anchor, outcome.cursor), (Some((0, 0)), (0, 2)));
+        assert_eq!(state.status().as_deref(), Some("-- VISUAL --"));
+    }
+
+    #[test]
+    fn test_status() {
+        let mut state = KeymapState::new(Keymap::Vim);
+        vim(&mut state, "a", "\"a2d");
+        assert_eq!(state.status().as_deref(), Some("-- NORMAL -- \"a2d"));
+        vim(&mut state, "a", "\u{1b}");
+        assert_eq!(state.status().as_deref(), Some("-- NORMAL --"));
+        assert_eq!(KeymapState::new(Keymap::Standard).status(), None);
+    }
+
+    #[test]
+    fn test_emacs() {
+        let ctrl = |c| EditorKey {
+            input: Input::Char(c),
+            ctrl: true,
+            alt: false,
+        };
+        let alt = |c| EditorKey {
+            input: Input::Char(c),
+            ctrl: false,
+            alt: true,
+        };
+        let mut state = KeymapState::new(Keymap::Emacs);
+        let text = "one two\nthree";
+
+        assert_eq!(state.handle(ctrl('e'), text, (0, 0)).cursor, (0, 7));
+        assert_eq!(state.handle(ctrl('n'), text, (0, 6)).cursor, (1, 5));
+        assert_eq!(state.handle(alt('f'), text, (0, 0)).cursor, (0, 3));
+        assert_eq!(state.handle(alt('b'), text, (0, 6)).cursor, (0, 4));
+        assert_eq!(state.handle(alt('>'), text, (0, 0)).cursor, (1, 5));
+
+        // Consecutive kills append, and yanking puts them back
+        let outcome = state.handle(ctrl('k'), text, (0, 3));
+        let text = outcome.text.unwrap();
+        assert_eq!(text, "one\nthree");
+        let text = state.handle(ctrl('k'), &text, (0, 3)).text.unwrap();
+        assert_eq!(text, "onethree");
+        assert_eq!(state.register('"'), Some(" two\n"));
+        let outcome = state.handle(ctrl('y'), &text, (0, 8));
+        assert_eq!(outcome.text.as_deref(), Some("onethree two\n"));
+
+        // The region between the mark and the cursor
+        let text = "one two";
+        state.handle(ctrl(' '), text, (0, 4));
+        let outcome = state.handle(ctrl('e'), text, (0, 4));
+        assert_eq!((outcome.anchor, outcome.cursor), (Some((0, 4)), (0, 7)));
+        let outcome = state.handle(ctrl('w'), text, (0, 7));
+        assert_eq!(outcome.text.as_deref(), Some("one "));
+        assert_eq!(state.status().as_deref(), Some("Emacs"));
+    }
+
+    #[test]
+    fn test_keymap_serialization() {
+        assert_eq!(serde_json::to_string(&Keymap::Vim).unwrap(), "\"vim\"");
+        assert_eq!(Keymap::Emacs.next(), Keymap::Standard);
+        let mut state = KeymapState::new(Keymap::Vim);
+        vim(&mut state, "a", "yy");
+        state.set_keymap(Keymap::Emacs);
+        assert_eq!(state.mode(), Mode::Insert);
+        assert_eq!(state.register('"'), Some("a\n"));
+    }
+}
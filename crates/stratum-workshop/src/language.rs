@@ -0,0 +1,313 @@
+//! Language features for the editor
+//!
+//! The editor gets diagnostics, completions, hover information and
+//! go-to-definition from stratum-lsp running in process, so they match what
+//! VS Code shows.
+
+use iced::widget::text_editor;
+use std::iter::Peekable;
+use std::path::Path;
+use std::str::Chars;
+use stratum_lsp::lsp_types::{
+    CompletionItem, Diagnostic, DiagnosticSeverity, InsertTextFormat, Position, Url,
+};
+use stratum_lsp::DocumentAnalysis;
+
+/// Completions listed at once
+const MAX_COMPLETIONS: usize = 8;
+
+/// Language features of a file open in the editor
+#[derive(Debug)]
+pub struct Language {
+    analysis: DocumentAnalysis,
+    /// Diagnostics of the current content, in order
+    pub diagnostics: Vec<Diagnostic>,
+    /// Hover information for the symbol at the cursor
+    pub hover: Option<String>,
+    /// Open completion list
+    pub completion: Option<Completion>,
+}
+
+/// A list of completions at the cursor
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    pub items: Vec<CompletionItem>,
+    pub selected: usize,
+}
+
+impl Language {
+    /// Language features for a file, if it is Stratum source. Untitled files
+    /// are assumed to be.
+    pub fn for_file(path: Option<&Path>, content: &str) -> Option<Self> {
+        let uri = match path {
+            Some(path) => {
+                let is_stratum = path.extension().and_then(|e| e.to_str()).is_some_and(|e| {
+                    e.eq_ignore_ascii_case("strat") || e.eq_ignore_ascii_case("st")
+                });
+                if !is_stratum {
+                    return None;
+                }
+                Url::from_file_path(path).ok()?
+            }
+            None => Url::parse("untitled:Untitled.strat").ok()?,
+        };
+        let mut language = Self {
+            analysis: DocumentAnalysis::new(uri, content),
+            diagnostics: Vec::new(),
+            hover: None,
+            completion: None,
+        };
+        language.diagnostics = language.analysis.diagnostics();
+        Some(language)
+    }
+
+    /// Analyze new content of the file
+    pub fn set_content(&mut self, content: String) {
+        self.analysis.set_content(content);
+        self.diagnostics = self.analysis.diagnostics();
+    }
+
+    /// Show the hover information for a cursor position
+    pub fn update_hover(&mut self, cursor: Position) {
+        self.hover = self
+            .analysis
+            .hover(cursor)
+            .map(|info| hover_text(&info.contents))
+            .filter(|text| !text.is_empty());
+    }
+
+    /// Open the completion list for a cursor position; closes it when there
+    /// are no completions
+    pub fn complete(&mut self, cursor: Position) {
+        let mut items = self.analysis.completions(cursor);
+        items.sort_by_cached_key(|item| {
+            item.sort_text.clone().unwrap_or_else(|| item.label.clone())
+        });
+        items.truncate(MAX_COMPLETIONS);
+        self.completion = (!items.is_empty()).then_some(Completion { items, selected: 0 });
+    }
+
+    /// Where the symbol at a cursor position is defined in this file
+    pub fn definition(&mut self, cursor: Position) -> Option<Position> {
+        let location = self.analysis.definition(cursor)?;
+        (location.uri == *self.analysis.uri()).then_some(location.range.start)
+    }
+
+    /// Update after an editor action: reanalyze edits, keep the completion
+    /// list open while an identifier is typed, and show the hover
+    /// information at the new cursor position
+    pub fn after_action(
+        &mut self,
+        content: &text_editor::Content,
+        edited: bool,
+        typed: Option<char>,
+    ) {
+        if edited {
+            self.set_content(content.text());
+        }
+        let cursor = cursor_position(content);
+        let completing = typed.is_some_and(|c| {
+            c == '.' || (self.completion.is_some() && (c.is_alphanumeric() || c == '_'))
+        });
+        if completing {
+            self.complete(cursor);
+        } else {
+            self.completion = None;
+        }
+        self.update_hover(cursor);
+    }
+
+    /// Move the completion selection to the next or previous item, wrapping
+    /// around
+    pub fn select_completion(&mut self, next: bool) {
+        if let Some(completion) = &mut self.completion {
+            let len = completion.items.len();
+            completion.selected = if next {
+                (completion.selected + 1) % len
+            } else {
+                (completion.selected + len - 1) % len
+            };
+        }
+    }
+}
+
+/// The LSP position of the editor's cursor
+pub fn cursor_position(content: &text_editor::Content) -> Position {
+    let position = content.cursor().position;
+    Position::new(
+        u32::try_from(position.line).unwrap_or(u32::MAX),
+        u32::try_from(position.column).unwrap_or(u32::MAX),
+    )
+}
+
+/// Move the editor's cursor to a line and column (0-based)
+pub fn move_cursor(content: &mut text_editor::Content, line: usize, column: usize) {
+    use text_editor::{Action, Motion};
+    content.perform(Action::Move(Motion::DocumentStart));
+    for _ in 0..line {
+        content.perform(Action::Move(Motion::Down));
+    }
+    for _ in 0..column {
+        content.perform(Action::Move(Motion::Right));
+    }
+}
+
+/// Replace the identifier before the cursor with a completion
+pub fn apply_completion(content: &mut text_editor::Content, item: &CompletionItem) {
+    use text_editor::{Action, Edit};
+    let position = content.cursor().position;
+    let text = content.text();
+    let line = text.lines().nth(position.line).unwrap_or_default();
+    for _ in 0..identifier_prefix_len(line, position.column) {
+        content.perform(Action::Edit(Edit::Backspace));
+    }
+    let text = insert_text(item);
+    content.perform(Action::Edit(Edit::Paste(std::sync::Arc::new(text))));
+}
+
+/// Number of identifier characters right before a column of a line
+fn identifier_prefix_len(line: &str, column: usize) -> usize {
+    line.chars()
+        .take(column)
+        .collect::<Vec<_>>()
+        .iter()
+        .rev()
+        .take_while(|c| c.is_alphanumeric() || **c == '_')
+        .count()
+}
+
+/// The text a completion inserts, with snippet placeholders replaced by
+/// their defaults
+fn insert_text(item: &CompletionItem) -> String {
+    let text = item.insert_text.as_deref().unwrap_or(&item.label);
+    if item.insert_text_format != Some(InsertTextFormat::SNIPPET) {
+        return text.to_string();
+    }
+    let mut plain = String::with_capacity(text.len());
+    expand_snippet(&mut text.chars().peekable(), &mut plain, false);
+    plain
+}
+
+/// Expand snippet syntax up to the end of the text, or to the `}` closing a
+/// placeholder when `nested`
+fn expand_snippet(chars: &mut Peekable<Chars>, plain: &mut String, nested: bool) {
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some(_)) => plain.extend(chars.next()),
+            ('}', _) if nested => return,
+            // `${1:default}` keeps the default, `${1}` is dropped
+            ('$', Some('{')) => {
+                chars.next();
+                while chars.next_if(char::is_ascii_digit).is_some() {}
+                if chars.next_if_eq(&':').is_some() {
+                    expand_snippet(chars, plain, true);
+                } else {
+                    expand_snippet(chars, &mut String::new(), true);
+                }
+            }
+            // `$1` is dropped
+            ('$', Some(d)) if d.is_ascii_digit() => {
+                while chars.next_if(char::is_ascii_digit).is_some() {}
+            }
+            (c, _) => plain.push(c),
+        }
+    }
+}
+
+/// Hover Markdown as one line of plain text: code fences dropped, lines
+/// joined
+fn hover_text(markdown: &str) -> String {
+    markdown
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("```") && *line != "---")
+        .collect::<Vec<_>>()
+        .join("  ")
+}
+
+/// Whether a diagnostic is an error rather than a warning or hint
+pub fn is_error(diagnostic: &Diagnostic) -> bool {
+    diagnostic.severity.unwrap_or(DiagnosticSeverity::ERROR) == DiagnosticSeverity::ERROR
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(label: &str, insert: Option<&str>, snippet: bool) -> CompletionItem {
+        CompletionItem {
+            label: label.to_string(),
+            insert_text: insert.map(str::to_string),
+            insert_text_format: snippet.then_some(InsertTextFormat::SNIPPET),
+            ..CompletionItem::default()
+        }
+    }
+
+    #[test]
+    fn test_insert_text() {
+        assert_eq!(insert_text(&item("len", None, false)), "len");
+        assert_eq!(
+            insert_text(&item("add", Some("add(${1:a}, ${2:b})$0"), true)),
+            "add(a, b)"
+        );
+        assert_eq!(
+            insert_text(&item(
+                "for",
+                Some("for ${1:x} in ${2:items} {\n\t$0\n}"),
+                true
+            )),
+            "for x in items {\n\t\n}"
+        );
+        assert_eq!(
+            insert_text(&item("s", Some("\\$1 ${1:{a\\}} ${2}."), true)),
+            "$1 {a} ."
+        );
+    }
+
+    #[test]
+    fn test_identifier_prefix_len() {
+        assert_eq!(identifier_prefix_len("let x = tot", 11), 3);
+        assert_eq!(identifier_prefix_len("list.le", 7), 2);
+        assert_eq!(identifier_prefix_len("list.", 5), 0);
+        assert_eq!(identifier_prefix_len("añb", 3), 3);
+    }
+
+    #[test]
+    fn test_hover_text() {
+        assert_eq!(
+            hover_text("```stratum\nfx add(a: Int) -> Int\n```\n---\nAdds numbers."),
+            "fx add(a: Int) -> Int  Adds numbers."
+        );
+    }
+
+    #[test]
+    fn test_language() {
+        assert!(Language::for_file(Some(Path::new("/tmp/notes.txt")), "").is_none());
+
+        let source = "fx add(a: Int, b: Int) -> Int { a + b }\nlet total = add(1, 2)\n";
+        let mut language = Language::for_file(Some(Path::new("/tmp/a.strat")), source).unwrap();
+        assert!(language.diagnostics.is_empty());
+        assert_eq!(
+            language.definition(Position::new(1, 13)),
+            Some(Position::new(0, 3))
+        );
+
+        language.update_hover(Position::new(0, 4));
+        assert!(language
+            .hover
+            .as_deref()
+            .is_some_and(|h| h.contains("fx add")));
+
+        language.complete(Position::new(1, 12));
+        let completion = language.completion.clone().unwrap();
+        assert!(completion.items.len() <= MAX_COMPLETIONS);
+        language.select_completion(false);
+        assert_eq!(
+            language.completion.as_ref().unwrap().selected,
+            completion.items.len() - 1
+        );
+
+        language.set_content("fx broken( {".to_string());
+        assert!(language.diagnostics.iter().any(is_error));
+    }
+}
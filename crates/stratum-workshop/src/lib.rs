@@ -7,16 +7,20 @@
 //!
 //! ```text
 //! ┌─────────────────────────────────────────────────┐
-//! │  New  Open  Save  Close  |  Run  |  About      │
-//! ├─────────────────────────────────────────────────┤
-//! │  [Optional: Editor pane when file is open]     │
-//! ├─────────────────────────────────────────────────┤
-//! │                                                 │
-//! │  >>> REPL                                       │
-//! │  >>> _                                          │
-//! │                                                 │
-//! ├─────────────────────────────────────────────────┤
-//! │  Ready                                          │
+//! │  New  Open  Folder  Save  Close | Run | Search  │
+//! ├──────────┬──────────────────────────────────────┤
+//! │ [Search, │ [Optional: Editor tab groups]        │
+//! │ Git,     ├──────────────────────────────────────┤
+//! │ Plugins, │ [Optional: Notebook]                 │
+//! │ Run      ├──────────────────────────────────────┤
+//! │ panels,  │ [Optional: Table viewer, Profiler]   │
+//! │ if open] ├──────────────────────────────────────┤
+//! │          │ >>> REPL                             │
+//! │          │ >>> _                                │
+//! │          ├──────────────────────────────────────┤
+//! │          │ [Optional: Terminal]                 │
+//! ├──────────┴──────────────────────────────────────┤
+//! │  Ready                                    main  │
 //! └─────────────────────────────────────────────────┘
 //! ```
 //!
@@ -33,24 +37,38 @@
 //! launch(Some(PathBuf::from("/path/to/file.strat"))).unwrap();
 //! ```
 
+pub mod config;
+mod keymap;
+mod language;
 pub mod panels;
+mod plugins;
+mod session;
+mod tabs;
 pub mod workshop;
 
-pub use panels::{ReplMessage, ReplPanel};
+pub use panels::{
+    NotebookMessage, NotebookPanel, ReplMessage, ReplPanel, SearchMessage, SearchPanel,
+    TableMessage, TableViewer,
+};
 pub use workshop::{Workshop, WorkshopMessage, WorkshopState};
 
+use config::WorkshopConfig;
 use iced::{Size, Subscription, Task};
-use std::path::PathBuf;
+use session::Session;
+use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 
 /// Global storage for initial path to pass to boot function
 static INITIAL_PATH: OnceLock<Option<PathBuf>> = OnceLock::new();
 
+/// Session restored on startup, loaded before the window opens for its size
+static INITIAL_SESSION: OnceLock<Option<Session>> = OnceLock::new();
+
 /// Launch Stratum Shell
 ///
 /// # Arguments
 ///
-/// * `initial_path` - Optional path to open on startup (file only)
+/// * `initial_path` - Optional file or folder to open on startup
 ///
 /// # Returns
 ///
@@ -69,23 +87,50 @@ static INITIAL_PATH: OnceLock<Option<PathBuf>> = OnceLock::new();
 /// launch(Some(PathBuf::from("/my/script.strat"))).unwrap();
 /// ```
 pub fn launch(initial_path: Option<PathBuf>) -> iced::Result {
-    // Store initial path for boot function to access
+    let session = initial_session(initial_path.as_deref());
+    let window_size = session
+        .as_ref()
+        .and_then(|session| session.window_size)
+        .map_or(Size::new(700.0, 500.0), |(width, height)| {
+            Size::new(width, height)
+        });
+    // Store initial path and session for boot function to access
     let _ = INITIAL_PATH.set(initial_path);
+    let _ = INITIAL_SESSION.set(session);
 
     iced::application(boot, update, view)
         .title("Stratum Shell")
-        .window_size(Size::new(700.0, 500.0))
+        .window_size(window_size)
         .subscription(subscription)
+        // Closing the window saves the session first
+        .exit_on_close_request(false)
         .run()
 }
 
+/// Session to restore: the opened folder's, or with no path the last
+/// folder's. Opening a single file starts without one.
+fn initial_session(path: Option<&Path>) -> Option<Session> {
+    match path {
+        Some(path) if path.is_dir() => Session::load(Some(path)),
+        Some(_) => None,
+        None => Session::load(WorkshopConfig::load().last_folder.as_deref()),
+    }
+}
+
 /// Boot function - initializes application state
 fn boot() -> (Workshop, Task<WorkshopMessage>) {
     let mut workshop = Workshop::new();
 
+    if let Some(Some(session)) = INITIAL_SESSION.get() {
+        let task = workshop.restore_session(session.clone());
+        return (workshop, task);
+    }
+
     // Handle initial path argument
     if let Some(Some(path)) = INITIAL_PATH.get() {
-        if path.is_file() {
+        if path.is_dir() {
+            let _ = workshop.update(WorkshopMessage::FolderOpened(Some(path.clone())));
+        } else if path.is_file() {
             if let Ok(content) = std::fs::read_to_string(path) {
                 // Directly open the file
                 let _ = workshop.update(WorkshopMessage::FileDialogOpened(Some((
@@ -1,11 +1,11 @@
 //! Debug panel for displaying call stack and variables
 //!
-//! This panel shows the current call stack and local variables
-//! when the debugger is paused.
+//! This panel shows the current call stack, local variables and the
+//! timeline of recently executed lines when the debugger is paused.
 
 use iced::widget::{column, container, scrollable, text, Column, Row};
 use iced::{Element, Font, Length};
-use stratum_core::{DebugStackFrame, DebugVariable};
+use stratum_core::{DebugStackFrame, DebugVariable, TraceEntry};
 
 /// Message type for debug panel actions
 #[derive(Debug, Clone)]
@@ -46,6 +46,7 @@ impl DebugPanel {
         &'a self,
         call_stack: &'a [DebugStackFrame],
         locals: &'a [DebugVariable],
+        trace: &'a [TraceEntry],
         is_debugging: bool,
     ) -> Element<'a, DebugPanelMessage> {
         if !is_debugging || call_stack.is_empty() {
@@ -54,11 +55,13 @@ impl DebugPanel {
 
         let call_stack_section = self.call_stack_view(call_stack);
         let variables_section = self.variables_view(locals);
+        let timeline_section = self.timeline_view(trace);
 
         container(
             column![
                 call_stack_section,
                 variables_section,
+                timeline_section,
             ]
             .spacing(10)
             .padding(10),
@@ -183,4 +186,62 @@ impl DebugPanel {
         .spacing(4)
         .into()
     }
+
+    /// Render the timeline of recorded lines, newest first
+    fn timeline_view<'a>(&self, trace: &'a [TraceEntry]) -> Element<'a, DebugPanelMessage> {
+        let header = text("Timeline")
+            .size(14)
+            .font(Font::DEFAULT)
+            .color(iced::Color::from_rgb(0.8, 0.8, 0.8));
+
+        if trace.is_empty() {
+            return column![
+                header,
+                text("(no recorded lines)")
+                    .size(11)
+                    .color(iced::Color::from_rgb(0.5, 0.5, 0.5)),
+            ]
+            .spacing(4)
+            .into();
+        }
+
+        let entries: Vec<Element<'a, DebugPanelMessage>> = trace
+            .iter()
+            .rev()
+            .map(|entry| {
+                let writes = entry
+                    .writes
+                    .iter()
+                    .map(|var| format!("{} = {}", var.name, var.value))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let indent = "  ".repeat(entry.depth.saturating_sub(1));
+
+                Row::new()
+                    .push(
+                        text(format!("{}{} - line {}", indent, entry.function_name, entry.line))
+                            .size(12)
+                            .font(Font::MONOSPACE)
+                            .color(iced::Color::from_rgb(0.7, 0.7, 0.7)),
+                    )
+                    .push(
+                        text(if writes.is_empty() { String::new() } else { format!("  {}", writes) })
+                            .size(12)
+                            .font(Font::MONOSPACE)
+                            .color(iced::Color::from_rgb(0.9, 0.7, 0.5)),
+                    )
+                    .spacing(0)
+                    .into()
+            })
+            .collect();
+
+        let content = Column::with_children(entries).spacing(2);
+
+        column![
+            header,
+            scrollable(content).height(Length::Fixed(160.0)),
+        ]
+        .spacing(4)
+        .into()
+    }
 }
@@ -1,7 +1,40 @@
 //! Panel implementations for Stratum Shell
 //!
-//! For the simplified IDLE-style interface, we only need the REPL panel.
+//! For the simplified IDLE-style interface, we only need the REPL panel, the
+//! table viewer for DataFrame and Cube results, the search panel, the
+//! terminal, the source control panel, the notebook, run configurations, and
+//! the profiler.
 
+mod notebook;
+mod profiler;
 mod repl;
+mod run_configs;
+mod search;
+mod source_control;
+mod table_viewer;
+mod terminal;
 
+pub use notebook::{
+    CellKind, CellOutput, Chart, NotebookAction, NotebookCell, NotebookMessage, NotebookPanel,
+};
+pub use profiler::{
+    profile_source, AllocationRow, AllocationSort, FlameFrame, FlameNode, Flamegraph, Profile,
+    ProfilerAction, ProfilerMessage, ProfilerPanel, ProfilerView,
+};
 pub use repl::{ReplMessage, ReplPanel};
+pub use run_configs::{
+    load_run_configs, save_run_configs, split_args, RunConfig, RunConfigAction, RunConfigMessage,
+    RunConfigPanel, RunMode, RunTarget, RUN_CONFIG_FILE,
+};
+pub use search::{
+    plan_replace, search_folder, search_text, FileEdit, FileMatches, LineMatch, SearchAction,
+    SearchMessage, SearchPanel, SearchQuery, SearchResults,
+};
+pub use source_control::{
+    ChangedFile, FileStatus, LineChange, RepoStatus, SourceControlAction, SourceControlMessage,
+    SourceControlPanel,
+};
+pub use table_viewer::{
+    Cell, SortOrder, TableAction, TableColumn, TableData, TableMessage, TableViewer,
+};
+pub use terminal::{Program, TerminalAction, TerminalMessage, TerminalPanel};
@@ -0,0 +1,1200 @@
+//! Notebook panel
+//!
+//! A notebook (`.stratnb`) is a list of code and Markdown cells. Code cells
+//! run one at a time against a VM whose globals persist between runs, like
+//! the REPL's, and show their printed output and result under the cell:
+//! DataFrames and Cubes as tables, `Gui` charts as charts. Notebooks save
+//! as JSON holding the cells' sources, and export to a `.strat` script or a
+//! standalone HTML page with the outputs.
+
+use super::repl::{eval, pretty_print};
+use super::table_viewer::TableData;
+use iced::keyboard::{self, key};
+use iced::widget::{
+    button, canvas, column, container, row, rule, scrollable, text, text_editor, Column, Space,
+};
+use iced::{Color, Element, Font, Length, Theme};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use stratum_core::bytecode::Value;
+use stratum_core::VM;
+use stratum_gui::charts::{
+    color_index_for_label, BarChartProgram, LineChartProgram, PieChartProgram, CHART_COLORS,
+};
+use stratum_gui::{BarChartConfig, GuiElement, GuiElementKind, LineChartConfig, PieChartConfig};
+
+/// Version of the `.stratnb` format this panel writes
+const FORMAT_VERSION: u32 = 1;
+/// Table rows shown under a cell; the table viewer shows them all
+const PREVIEW_ROWS: usize = 10;
+/// Table rows written to an HTML export
+const HTML_ROWS: usize = 500;
+
+/// Messages for the notebook panel
+#[derive(Debug, Clone)]
+pub enum NotebookMessage {
+    /// Edit a cell's source
+    Edit(usize, text_editor::Action),
+    /// Select a cell
+    Select(usize),
+    /// Run a cell and select the next one
+    Run(usize),
+    /// Run every code cell in order
+    RunAll,
+    /// Start a new VM and clear every output
+    Restart,
+    /// Add a cell after the selected one
+    AddCell(CellKind),
+    /// Delete a cell
+    Delete(usize),
+    /// Move a cell up
+    MoveUp(usize),
+    /// Move a cell down
+    MoveDown(usize),
+    /// Switch a Markdown cell between its source and its preview
+    ToggleMarkdown(usize),
+    /// Open a table output in the table viewer
+    OpenTable { cell: usize, output: usize },
+    /// Save the notebook
+    Save,
+    /// Export the code cells as a `.strat` script
+    ExportScript,
+    /// Export the notebook and its outputs as HTML
+    ExportHtml,
+    /// Close the notebook; asks first when there are unsaved changes
+    Close,
+}
+
+/// Actions returned from update that Workshop needs to handle
+#[derive(Debug, Clone)]
+pub enum NotebookAction {
+    /// Write the notebook to its file, or to one the user picks
+    Save { path: Option<PathBuf>, json: String },
+    /// Save this script to a file the user picks
+    ExportScript(String),
+    /// Save this HTML to a file the user picks
+    ExportHtml(String),
+    /// Show a table in the table viewer
+    OpenTable(TableData),
+    /// Close the notebook
+    Close,
+}
+
+/// What a cell holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CellKind {
+    Code,
+    Markdown,
+}
+
+/// An output of a code cell
+#[derive(Debug, Clone)]
+pub enum CellOutput {
+    /// Printed output, or a result shown as text
+    Text(String),
+    Error(String),
+    Table(TableData),
+    Chart(Chart),
+}
+
+/// A chart built with `Gui.bar_chart`, `Gui.line_chart` or `Gui.pie_chart`
+#[derive(Debug, Clone)]
+pub enum Chart {
+    Bar(BarChartConfig),
+    Line(LineChartConfig),
+    Pie(PieChartConfig),
+}
+
+/// A cell of a notebook
+pub struct NotebookCell {
+    pub kind: CellKind,
+    pub content: text_editor::Content,
+    pub outputs: Vec<CellOutput>,
+    /// When the cell last ran, counting runs since the VM started
+    pub execution: Option<usize>,
+    /// Whether a Markdown cell shows its source rather than its preview
+    editing: bool,
+}
+
+/// The `.stratnb` file format
+#[derive(Debug, Serialize, Deserialize)]
+struct NotebookFile {
+    version: u32,
+    cells: Vec<CellFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CellFile {
+    kind: CellKind,
+    source: String,
+}
+
+/// Notebook panel with its own VM session
+pub struct NotebookPanel {
+    path: Option<PathBuf>,
+    cells: Vec<NotebookCell>,
+    selected: usize,
+    modified: bool,
+    /// Whether Close was pressed with unsaved changes
+    confirm_close: bool,
+    vm: VM,
+    /// Cells run since the VM started
+    executions: usize,
+}
+
+impl std::fmt::Debug for NotebookPanel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NotebookPanel")
+            .field("path", &self.path)
+            .field("cells", &self.cells.len())
+            .field("selected", &self.selected)
+            .field("modified", &self.modified)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for NotebookPanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NotebookCell {
+    fn new(kind: CellKind, source: &str) -> Self {
+        Self {
+            kind,
+            content: text_editor::Content::with_text(source),
+            outputs: Vec::new(),
+            execution: None,
+            editing: source.is_empty(),
+        }
+    }
+
+    /// The cell's source
+    pub fn source(&self) -> String {
+        self.content.text()
+    }
+}
+
+impl NotebookPanel {
+    /// An untitled notebook with one empty code cell
+    pub fn new() -> Self {
+        Self::with_cells(None, vec![NotebookCell::new(CellKind::Code, "")])
+    }
+
+    /// Read a notebook from the contents of its file
+    pub fn open(path: PathBuf, json: &str) -> Result<Self, String> {
+        let file: NotebookFile =
+            serde_json::from_str(json).map_err(|e| format!("Invalid notebook: {e}"))?;
+        if file.version > FORMAT_VERSION {
+            return Err(format!(
+                "Notebook format version {} is newer than this Workshop supports",
+                file.version
+            ));
+        }
+        let cells = file
+            .cells
+            .iter()
+            .map(|cell| NotebookCell::new(cell.kind, &cell.source))
+            .collect();
+        Ok(Self::with_cells(Some(path), cells))
+    }
+
+    fn with_cells(path: Option<PathBuf>, cells: Vec<NotebookCell>) -> Self {
+        Self {
+            path,
+            cells,
+            selected: 0,
+            modified: false,
+            confirm_close: false,
+            vm: new_vm(),
+            executions: 0,
+        }
+    }
+
+    /// The notebook's file, if saved
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn cells(&self) -> &[NotebookCell] {
+        &self.cells
+    }
+
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    /// Record that the notebook was saved to a file
+    pub fn set_saved(&mut self, path: PathBuf) {
+        self.path = Some(path);
+        self.modified = false;
+        self.confirm_close = false;
+    }
+
+    /// The notebook in the `.stratnb` format
+    pub fn to_json(&self) -> String {
+        let file = NotebookFile {
+            version: FORMAT_VERSION,
+            cells: self
+                .cells
+                .iter()
+                .map(|cell| CellFile {
+                    kind: cell.kind,
+                    source: cell.source(),
+                })
+                .collect(),
+        };
+        serde_json::to_string_pretty(&file).unwrap_or_default()
+    }
+
+    /// The code cells as a script, with Markdown cells as comments
+    pub fn to_script(&self) -> String {
+        let blocks: Vec<String> = self
+            .cells
+            .iter()
+            .map(|cell| {
+                let source = cell.source();
+                match cell.kind {
+                    CellKind::Code => source.trim_end().to_string(),
+                    CellKind::Markdown => source
+                        .trim_end()
+                        .lines()
+                        .map(|line| format!("// {line}").trim_end().to_string())
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                }
+            })
+            .filter(|block| !block.is_empty())
+            .collect();
+        let mut script = blocks.join("\n\n");
+        script.push('\n');
+        script
+    }
+
+    /// The notebook and its outputs as a standalone HTML page
+    pub fn to_html(&self) -> String {
+        let title = self.path.as_ref().and_then(|p| p.file_stem()).map_or_else(
+            || "Untitled".to_string(),
+            |n| n.to_string_lossy().to_string(),
+        );
+        let mut html = String::new();
+        let _ = write!(
+            html,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+             <style>{HTML_STYLE}</style>\n</head>\n<body>\n",
+            escape_html(&title)
+        );
+        for cell in &self.cells {
+            match cell.kind {
+                CellKind::Markdown => {
+                    let _ = writeln!(
+                        html,
+                        "<div class=\"markdown\">\n{}</div>",
+                        markdown_to_html(&cell.source())
+                    );
+                }
+                CellKind::Code => {
+                    let _ = writeln!(
+                        html,
+                        "<div class=\"cell\">\n<pre class=\"source\"><code>{}</code></pre>",
+                        escape_html(cell.source().trim_end())
+                    );
+                    for output in &cell.outputs {
+                        html.push_str(&output_to_html(output));
+                    }
+                    html.push_str("</div>\n");
+                }
+            }
+        }
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+
+    /// Handle a notebook message
+    pub fn update(&mut self, message: NotebookMessage) -> Option<NotebookAction> {
+        if !matches!(message, NotebookMessage::Close) {
+            self.confirm_close = false;
+        }
+        match message {
+            NotebookMessage::Edit(index, action) => {
+                let cell = self.cells.get_mut(index)?;
+                self.selected = index;
+                let is_edit = action.is_edit();
+                cell.content.perform(action);
+                self.modified |= is_edit;
+            }
+            NotebookMessage::Select(index) => {
+                if index < self.cells.len() {
+                    self.selected = index;
+                }
+            }
+            NotebookMessage::Run(index) => {
+                self.run(index);
+                if index + 1 < self.cells.len() {
+                    self.selected = index + 1;
+                }
+            }
+            NotebookMessage::RunAll => {
+                for index in 0..self.cells.len() {
+                    self.run(index);
+                }
+            }
+            NotebookMessage::Restart => {
+                self.vm = new_vm();
+                self.executions = 0;
+                for cell in &mut self.cells {
+                    cell.outputs.clear();
+                    cell.execution = None;
+                }
+            }
+            NotebookMessage::AddCell(kind) => {
+                let index = (self.selected + 1).min(self.cells.len());
+                self.cells.insert(index, NotebookCell::new(kind, ""));
+                self.selected = index;
+                self.modified = true;
+            }
+            NotebookMessage::Delete(index) => {
+                if index < self.cells.len() {
+                    self.cells.remove(index);
+                    if self.cells.is_empty() {
+                        self.cells.push(NotebookCell::new(CellKind::Code, ""));
+                    }
+                    self.selected = self.selected.min(self.cells.len() - 1);
+                    self.modified = true;
+                }
+            }
+            NotebookMessage::MoveUp(index) => {
+                if index > 0 && index < self.cells.len() {
+                    self.cells.swap(index - 1, index);
+                    self.selected = index - 1;
+                    self.modified = true;
+                }
+            }
+            NotebookMessage::MoveDown(index) => {
+                if index + 1 < self.cells.len() {
+                    self.cells.swap(index, index + 1);
+                    self.selected = index + 1;
+                    self.modified = true;
+                }
+            }
+            NotebookMessage::ToggleMarkdown(index) => {
+                let cell = self.cells.get_mut(index)?;
+                cell.editing = !cell.editing;
+                self.selected = index;
+            }
+            NotebookMessage::OpenTable { cell, output } => {
+                if let Some(CellOutput::Table(table)) =
+                    self.cells.get(cell).and_then(|c| c.outputs.get(output))
+                {
+                    return Some(NotebookAction::OpenTable(table.clone()));
+                }
+            }
+            NotebookMessage::Save => {
+                return Some(NotebookAction::Save {
+                    path: self.path.clone(),
+                    json: self.to_json(),
+                });
+            }
+            NotebookMessage::ExportScript => {
+                return Some(NotebookAction::ExportScript(self.to_script()));
+            }
+            NotebookMessage::ExportHtml => {
+                return Some(NotebookAction::ExportHtml(self.to_html()));
+            }
+            NotebookMessage::Close => {
+                if self.modified && !self.confirm_close {
+                    self.confirm_close = true;
+                } else {
+                    return Some(NotebookAction::Close);
+                }
+            }
+        }
+        None
+    }
+
+    /// Run a code cell, or show a Markdown cell's preview
+    fn run(&mut self, index: usize) {
+        let Some(cell) = self.cells.get_mut(index) else {
+            return;
+        };
+        if cell.kind == CellKind::Markdown {
+            cell.editing = false;
+            return;
+        }
+        let source = cell.source();
+        if source.trim().is_empty() {
+            cell.outputs.clear();
+            cell.execution = None;
+            return;
+        }
+
+        self.executions += 1;
+        cell.execution = Some(self.executions);
+        cell.outputs = match eval(&mut self.vm, &source) {
+            Ok((stdout, value)) => {
+                let mut outputs = Vec::new();
+                if !stdout.is_empty() {
+                    outputs.push(CellOutput::Text(stdout.join("\n")));
+                }
+                outputs.extend(value_output(&format!("Out[{}]", self.executions), &value));
+                outputs
+            }
+            Err(err) => vec![CellOutput::Error(err)],
+        };
+    }
+
+    /// Render the notebook panel
+    pub fn view(&self) -> Element<'_, NotebookMessage> {
+        let title = self.path.as_ref().and_then(|p| p.file_name()).map_or_else(
+            || "Untitled".to_string(),
+            |n| n.to_string_lossy().to_string(),
+        );
+        let modified = if self.modified { " *" } else { "" };
+        let close_label = if self.confirm_close {
+            "Discard changes"
+        } else {
+            "x"
+        };
+
+        let toolbar = container(
+            row![
+                text(format!("{title}{modified}")).size(12),
+                Space::new().width(Length::Fixed(12.0)),
+                tool_button("+ Code", NotebookMessage::AddCell(CellKind::Code)),
+                tool_button("+ Markdown", NotebookMessage::AddCell(CellKind::Markdown)),
+                tool_button("Run all", NotebookMessage::RunAll),
+                tool_button("Restart", NotebookMessage::Restart),
+                text("|").size(12),
+                tool_button("Save", NotebookMessage::Save),
+                tool_button("Export .strat", NotebookMessage::ExportScript),
+                tool_button("Export HTML", NotebookMessage::ExportHtml),
+                Space::new().width(Length::Fill),
+                tool_button(close_label, NotebookMessage::Close),
+            ]
+            .spacing(4)
+            .align_y(iced::Alignment::Center),
+        )
+        .padding([4, 8])
+        .width(Length::Fill)
+        .style(|theme: &Theme| {
+            let palette = theme.extended_palette();
+            container::Style {
+                background: Some(palette.background.weak.color.into()),
+                ..Default::default()
+            }
+        });
+
+        let cells = self
+            .cells
+            .iter()
+            .enumerate()
+            .map(|(index, cell)| self.cell_view(index, cell));
+
+        container(column![
+            toolbar,
+            rule::horizontal(1),
+            scrollable(Column::with_children(cells).spacing(8).padding(8)).height(Length::Fill),
+        ])
+        .width(Length::Fill)
+        .height(Length::FillPortion(1))
+        .into()
+    }
+
+    fn cell_view<'a>(
+        &'a self,
+        index: usize,
+        cell: &'a NotebookCell,
+    ) -> Element<'a, NotebookMessage> {
+        let selected = index == self.selected;
+        let prompt = match (cell.kind, cell.execution) {
+            (CellKind::Markdown, _) => String::new(),
+            (CellKind::Code, Some(n)) => format!("[{n}]"),
+            (CellKind::Code, None) => "[ ]".to_string(),
+        };
+
+        let body: Element<'a, NotebookMessage> = if cell.kind == CellKind::Markdown && !cell.editing
+        {
+            button(markdown_view(&cell.source()))
+                .on_press(NotebookMessage::Select(index))
+                .padding(4)
+                .width(Length::Fill)
+                .style(button::text)
+                .into()
+        } else {
+            text_editor(&cell.content)
+                .on_action(move |action| NotebookMessage::Edit(index, action))
+                .key_binding(move |key_press| {
+                    // Shift+Enter runs the cell
+                    let run = matches!(key_press.key, keyboard::Key::Named(key::Named::Enter))
+                        && key_press.modifiers.shift();
+                    if run {
+                        Some(text_editor::Binding::Custom(NotebookMessage::Run(index)))
+                    } else {
+                        text_editor::Binding::from_key_press(key_press)
+                    }
+                })
+                .font(Font::MONOSPACE)
+                .size(13)
+                .into()
+        };
+
+        let mut content = column![body].spacing(4);
+        if selected {
+            let mut actions = row![tool_button("Run", NotebookMessage::Run(index))].spacing(4);
+            if cell.kind == CellKind::Markdown {
+                let label = if cell.editing { "Preview" } else { "Edit" };
+                actions = actions.push(tool_button(label, NotebookMessage::ToggleMarkdown(index)));
+            }
+            actions = actions
+                .push(tool_button("Up", NotebookMessage::MoveUp(index)))
+                .push(tool_button("Down", NotebookMessage::MoveDown(index)))
+                .push(tool_button("Delete", NotebookMessage::Delete(index)));
+            content = content.push(actions);
+        }
+        for (position, output) in cell.outputs.iter().enumerate() {
+            content = content.push(output_view(index, position, output));
+        }
+
+        container(
+            row![
+                text(prompt)
+                    .size(12)
+                    .font(Font::MONOSPACE)
+                    .width(Length::Fixed(40.0)),
+                content.width(Length::Fill),
+            ]
+            .spacing(4),
+        )
+        .padding(4)
+        .width(Length::Fill)
+        .style(move |theme: &Theme| {
+            let palette = theme.extended_palette();
+            container::Style {
+                border: iced::Border {
+                    color: if selected {
+                        palette.primary.base.color
+                    } else {
+                        Color::TRANSPARENT
+                    },
+                    width: 1.0,
+                    radius: 4.0.into(),
+                },
+                ..Default::default()
+            }
+        })
+        .into()
+    }
+}
+
+/// A VM with the `Gui` namespace, so cells can build charts
+fn new_vm() -> VM {
+    let mut vm = VM::new();
+    stratum_gui::register_gui(&mut vm);
+    vm
+}
+
+/// The output showing a cell's result, if it isn't null
+fn value_output(title: &str, value: &Value) -> Option<CellOutput> {
+    if let Some(chart) = Chart::from_value(value) {
+        return Some(CellOutput::Chart(chart));
+    }
+    match TableData::from_value(title, value) {
+        Some(Ok(table)) => Some(CellOutput::Table(table)),
+        Some(Err(err)) => Some(CellOutput::Error(err)),
+        None if matches!(value, Value::Null) => None,
+        None => Some(CellOutput::Text(pretty_print(value))),
+    }
+}
+
+impl Chart {
+    /// The chart a value holds, if it is a chart element
+    fn from_value(value: &Value) -> Option<Self> {
+        let Value::GuiElement(element) = value else {
+            return None;
+        };
+        match &element.as_any().downcast_ref::<GuiElement>()?.kind {
+            GuiElementKind::BarChart(config) => Some(Chart::Bar(config.clone())),
+            GuiElementKind::LineChart(config) => Some(Chart::Line(config.clone())),
+            GuiElementKind::PieChart(config) => Some(Chart::Pie(config.clone())),
+            _ => None,
+        }
+    }
+
+    fn size(&self) -> (f32, f32) {
+        match self {
+            Chart::Bar(config) => (config.width, config.height),
+            Chart::Line(config) => (config.width, config.height),
+            Chart::Pie(config) => (config.width, config.height),
+        }
+    }
+
+    fn title(&self) -> Option<&str> {
+        match self {
+            Chart::Bar(config) => config.title.as_deref(),
+            Chart::Line(config) => config.title.as_deref(),
+            Chart::Pie(config) => config.title.as_deref(),
+        }
+    }
+
+    /// Render the chart with stratum-gui's chart programs
+    fn view(&self) -> Element<'_, stratum_gui::runtime::Message> {
+        let (width, height) = self.size();
+        match self {
+            Chart::Bar(config) => canvas(BarChartProgram {
+                config: config.clone(),
+            })
+            .width(width)
+            .height(height)
+            .into(),
+            Chart::Line(config) => canvas(LineChartProgram {
+                config: config.clone(),
+            })
+            .width(width)
+            .height(height)
+            .into(),
+            Chart::Pie(config) => canvas(PieChartProgram {
+                config: config.clone(),
+            })
+            .width(width)
+            .height(height)
+            .into(),
+        }
+    }
+
+    /// The chart as an SVG image
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+    fn to_svg(&self) -> String {
+        const MARGIN: f32 = 30.0;
+        let (width, height) = self.size();
+        let top = if self.title().is_some() { 40.0 } else { 20.0 };
+        let plot_width = (width - 2.0 * MARGIN).max(1.0);
+        let plot_height = (height - top - MARGIN).max(1.0);
+        let bottom = top + plot_height;
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+             font-family=\"sans-serif\" font-size=\"11\">"
+        );
+        if let Some(title) = self.title() {
+            let _ = write!(
+                svg,
+                "<text x=\"{}\" y=\"20\" text-anchor=\"middle\" font-size=\"14\">{}</text>",
+                width / 2.0,
+                escape_html(title)
+            );
+        }
+
+        match self {
+            Chart::Bar(config) => {
+                let max = config.data.iter().map(|d| d.value).fold(0.0, f64::max);
+                let slot = plot_width / config.data.len().max(1) as f32;
+                let color = config.bar_color.unwrap_or(CHART_COLORS[0]);
+                for (i, point) in config.data.iter().enumerate() {
+                    let bar_height = if max > 0.0 {
+                        (point.value.max(0.0) / max) as f32 * plot_height
+                    } else {
+                        0.0
+                    };
+                    let x = MARGIN + slot * i as f32;
+                    let _ = write!(
+                        svg,
+                        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{bar_height}\" fill=\"{}\"/>\
+                         <text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>",
+                        x + slot * 0.1,
+                        bottom - bar_height,
+                        slot * 0.8,
+                        rgb(color),
+                        x + slot / 2.0,
+                        bottom + 14.0,
+                        escape_html(&point.label)
+                    );
+                }
+            }
+            Chart::Line(config) => {
+                let values = config.series.iter().flat_map(|s| s.values.iter().copied());
+                let (min, max) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| {
+                    (lo.min(v), hi.max(v))
+                });
+                let span = if max > min { max - min } else { 1.0 };
+                for (i, series) in config.series.iter().enumerate() {
+                    let step = plot_width / series.values.len().saturating_sub(1).max(1) as f32;
+                    let points: Vec<String> = series
+                        .values
+                        .iter()
+                        .enumerate()
+                        .map(|(j, v)| {
+                            let y = bottom - ((v - min) / span) as f32 * plot_height;
+                            format!("{},{y}", MARGIN + step * j as f32)
+                        })
+                        .collect();
+                    let color = config
+                        .series_colors
+                        .get(i)
+                        .copied()
+                        .unwrap_or(CHART_COLORS[i % CHART_COLORS.len()]);
+                    let _ = write!(
+                        svg,
+                        "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"2\"/>",
+                        points.join(" "),
+                        rgb(color)
+                    );
+                }
+                let step = plot_width / config.labels.len().saturating_sub(1).max(1) as f32;
+                for (j, label) in config.labels.iter().enumerate() {
+                    let _ = write!(
+                        svg,
+                        "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>",
+                        MARGIN + step * j as f32,
+                        bottom + 14.0,
+                        escape_html(label)
+                    );
+                }
+            }
+            Chart::Pie(config) => {
+                let total: f64 = config.data.iter().map(|d| d.value.max(0.0)).sum();
+                let radius = plot_width.min(plot_height) / 2.0;
+                let (cx, cy) = (width / 2.0, top + plot_height / 2.0);
+                let mut angle = -std::f32::consts::FRAC_PI_2;
+                for (i, point) in config.data.iter().enumerate() {
+                    if total <= 0.0 || point.value <= 0.0 {
+                        continue;
+                    }
+                    let share = (point.value / total) as f32;
+                    let color = config
+                        .slice_colors
+                        .get(i)
+                        .copied()
+                        .unwrap_or_else(|| CHART_COLORS[color_index_for_label(&point.label)]);
+                    if share >= 1.0 {
+                        let _ = write!(
+                            svg,
+                            "<circle cx=\"{cx}\" cy=\"{cy}\" r=\"{radius}\" fill=\"{}\"/>",
+                            rgb(color)
+                        );
+                        continue;
+                    }
+                    let end = angle + share * std::f32::consts::TAU;
+                    let _ = write!(
+                        svg,
+                        "<path d=\"M{cx},{cy} L{},{} A{radius},{radius} 0 {} 1 {},{} Z\" fill=\"{}\">\
+                         <title>{}</title></path>",
+                        cx + radius * angle.cos(),
+                        cy + radius * angle.sin(),
+                        u8::from(share > 0.5),
+                        cx + radius * end.cos(),
+                        cy + radius * end.sin(),
+                        rgb(color),
+                        escape_html(&point.label)
+                    );
+                    angle = end;
+                }
+            }
+        }
+        svg.push_str("</svg>");
+        svg
+    }
+}
+
+fn rgb((r, g, b): (u8, u8, u8)) -> String {
+    format!("rgb({r},{g},{b})")
+}
+
+fn tool_button(label: &str, message: NotebookMessage) -> Element<'_, NotebookMessage> {
+    button(text(label).size(11))
+        .on_press(message)
+        .padding([2, 8])
+        .style(button::text)
+        .into()
+}
+
+/// Render a cell output
+fn output_view(cell: usize, position: usize, output: &CellOutput) -> Element<'_, NotebookMessage> {
+    match output {
+        CellOutput::Text(output) => text(output)
+            .size(12)
+            .font(Font::MONOSPACE)
+            .color(Color::from_rgb(0.6, 0.8, 0.6))
+            .into(),
+        CellOutput::Error(error) => text(error)
+            .size(12)
+            .font(Font::MONOSPACE)
+            .color(Color::from_rgb(1.0, 0.4, 0.4))
+            .into(),
+        CellOutput::Table(table) => {
+            let header = row(table.columns.iter().map(|column| {
+                text(&column.name)
+                    .size(12)
+                    .font(Font::MONOSPACE)
+                    .width(Length::Fixed(120.0))
+                    .into()
+            }));
+            let rows = table.rows.iter().take(PREVIEW_ROWS).map(|cells| {
+                row(cells.iter().map(|cell| {
+                    text(cell.to_string())
+                        .size(12)
+                        .font(Font::MONOSPACE)
+                        .width(Length::Fixed(120.0))
+                        .into()
+                }))
+                .into()
+            });
+            let footer = row![
+                text(format!(
+                    "{} rows x {} columns",
+                    table.rows.len(),
+                    table.columns.len()
+                ))
+                .size(11),
+                tool_button(
+                    "Open in table viewer",
+                    NotebookMessage::OpenTable {
+                        cell,
+                        output: position
+                    }
+                ),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center);
+            column![
+                header,
+                rule::horizontal(1),
+                Column::with_children(rows),
+                footer
+            ]
+            .spacing(2)
+            .into()
+        }
+        // Clicking a chart selects its cell
+        CellOutput::Chart(chart) => chart.view().map(move |_| NotebookMessage::Select(cell)),
+    }
+}
+
+/// Render Markdown as text: headings larger, list items with bullets, and
+/// fenced code in monospace
+fn markdown_view<'a>(source: &str) -> Element<'a, NotebookMessage> {
+    let mut in_code = false;
+    let lines = source.lines().filter_map(|line| {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+            return None;
+        }
+        let element = if in_code {
+            text(line.to_string()).size(12).font(Font::MONOSPACE)
+        } else if let Some((level, heading)) = heading(line) {
+            text(heading.to_string()).size(match level {
+                1 => 22,
+                2 => 18,
+                _ => 15,
+            })
+        } else if let Some(item) = list_item(line) {
+            text(format!("  •  {item}")).size(13)
+        } else {
+            text(line.to_string()).size(13)
+        };
+        Some(element.into())
+    });
+    Column::with_children(lines).spacing(2).into()
+}
+
+/// A heading's level and text
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let rest = line[level..].strip_prefix(' ')?;
+    (1..=6).contains(&level).then_some((level, rest.trim()))
+}
+
+/// A list item's text
+fn list_item(line: &str) -> Option<&str> {
+    let line = line.trim_start();
+    line.strip_prefix("- ").or_else(|| line.strip_prefix("* "))
+}
+
+/// Convert the Markdown of a cell to HTML: headings, lists, fenced code,
+/// paragraphs, and inline code
+fn markdown_to_html(source: &str) -> String {
+    let mut html = String::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut in_list = false;
+    let mut in_code = false;
+
+    let flush = |html: &mut String, paragraph: &mut Vec<&str>, in_list: &mut bool| {
+        if !paragraph.is_empty() {
+            let _ = writeln!(html, "<p>{}</p>", inline_html(&paragraph.join(" ")));
+            paragraph.clear();
+        }
+        if *in_list {
+            html.push_str("</ul>\n");
+            *in_list = false;
+        }
+    };
+
+    for line in source.lines() {
+        if line.trim_start().starts_with("```") {
+            flush(&mut html, &mut paragraph, &mut in_list);
+            html.push_str(if in_code {
+                "</code></pre>\n"
+            } else {
+                "<pre><code>"
+            });
+            in_code = !in_code;
+        } else if in_code {
+            html.push_str(&escape_html(line));
+            html.push('\n');
+        } else if let Some((level, heading)) = heading(line) {
+            flush(&mut html, &mut paragraph, &mut in_list);
+            let _ = writeln!(html, "<h{level}>{}</h{level}>", inline_html(heading));
+        } else if let Some(item) = list_item(line) {
+            if !in_list {
+                flush(&mut html, &mut paragraph, &mut in_list);
+                html.push_str("<ul>\n");
+                in_list = true;
+            }
+            let _ = writeln!(html, "<li>{}</li>", inline_html(item));
+        } else if line.trim().is_empty() {
+            flush(&mut html, &mut paragraph, &mut in_list);
+        } else {
+            if in_list {
+                flush(&mut html, &mut paragraph, &mut in_list);
+            }
+            paragraph.push(line.trim());
+        }
+    }
+    if in_code {
+        html.push_str("</code></pre>\n");
+    }
+    flush(&mut html, &mut paragraph, &mut in_list);
+    html
+}
+
+/// Escape text for HTML, with `code` spans in `<code>`
+fn inline_html(text: &str) -> String {
+    text.split('`')
+        .enumerate()
+        .map(|(i, part)| {
+            if i % 2 == 1 {
+                format!("<code>{}</code>", escape_html(part))
+            } else {
+                escape_html(part)
+            }
+        })
+        .collect()
+}
+
+/// Render a cell output as HTML
+fn output_to_html(output: &CellOutput) -> String {
+    match output {
+        CellOutput::Text(output) => {
+            format!("<pre class=\"output\">{}</pre>\n", escape_html(output))
+        }
+        CellOutput::Error(error) => format!("<pre class=\"error\">{}</pre>\n", escape_html(error)),
+        CellOutput::Table(table) => {
+            let mut html = String::from("<table>\n<tr>");
+            for column in &table.columns {
+                let _ = write!(html, "<th>{}</th>", escape_html(&column.name));
+            }
+            html.push_str("</tr>\n");
+            for cells in table.rows.iter().take(HTML_ROWS) {
+                html.push_str("<tr>");
+                for cell in cells {
+                    let _ = write!(html, "<td>{}</td>", escape_html(&cell.to_string()));
+                }
+                html.push_str("</tr>\n");
+            }
+            html.push_str("</table>\n");
+            if table.rows.len() > HTML_ROWS {
+                let _ = writeln!(
+                    html,
+                    "<p class=\"note\">{} of {} rows</p>",
+                    HTML_ROWS,
+                    table.rows.len()
+                );
+            }
+            html
+        }
+        CellOutput::Chart(chart) => format!("<div class=\"chart\">{}</div>\n", chart.to_svg()),
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+const HTML_STYLE: &str = "body{font-family:sans-serif;max-width:960px;margin:2em auto;\
+color:#222}.cell{margin:1em 0}pre{margin:0;padding:.5em;overflow-x:auto}\
+.source{background:#f4f4f4;border-radius:4px}.output{color:#333}.error{color:#b00}\
+table{border-collapse:collapse;margin:.5em 0}th,td{border:1px solid #ddd;\
+padding:2px 8px;text-align:left}.note{color:#777;font-size:small}";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notebook(cells: &[(CellKind, &str)]) -> NotebookPanel {
+        let cells = cells
+            .iter()
+            .map(|(kind, source)| NotebookCell::new(*kind, source))
+            .collect();
+        NotebookPanel::with_cells(None, cells)
+    }
+
+    fn text_outputs(cell: &NotebookCell) -> Vec<&str> {
+        cell.outputs
+            .iter()
+            .filter_map(|output| match output {
+                CellOutput::Text(text) | CellOutput::Error(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let notebook = notebook(&[
+            (CellKind::Markdown, "# Sales"),
+            (CellKind::Code, "let x = 1\nx + 1"),
+        ]);
+        let json = notebook.to_json();
+        let opened = NotebookPanel::open(PathBuf::from("a.stratnb"), &json).unwrap();
+        assert_eq!(opened.cells().len(), 2);
+        assert_eq!(opened.cells()[0].kind, CellKind::Markdown);
+        assert_eq!(opened.cells()[1].source(), "let x = 1\nx + 1");
+        assert_eq!(opened.path(), Some(Path::new("a.stratnb")));
+
+        assert!(NotebookPanel::open(PathBuf::from("a.stratnb"), "{").is_err());
+        let newer = r#"{"version": 99, "cells": []}"#;
+        assert!(NotebookPanel::open(PathBuf::from("a.stratnb"), newer)
+            .unwrap_err()
+            .contains("newer"));
+    }
+
+    #[test]
+    fn test_cells_share_a_session() {
+        let mut notebook = notebook(&[
+            (CellKind::Code, "let x = 2\nprintln(\"set\")"),
+            (CellKind::Markdown, "Then use it"),
+            (CellKind::Code, "x * 3"),
+        ]);
+        let _ = notebook.update(NotebookMessage::RunAll);
+        assert_eq!(text_outputs(&notebook.cells()[0]), ["set"]);
+        assert_eq!(text_outputs(&notebook.cells()[2]), ["6"]);
+        assert_eq!(notebook.cells()[2].execution, Some(2));
+        assert!(notebook.cells()[1].execution.is_none());
+
+        let _ = notebook.update(NotebookMessage::Restart);
+        assert!(notebook.cells()[2].outputs.is_empty());
+        let _ = notebook.update(NotebookMessage::Run(2));
+        assert!(matches!(
+            notebook.cells()[2].outputs[..],
+            [CellOutput::Error(_)]
+        ));
+        assert_eq!(notebook.cells()[2].execution, Some(1));
+    }
+
+    #[test]
+    fn test_rich_outputs() {
+        let mut notebook = notebook(&[
+            (CellKind::Code, r#"Data.frame([{"a": 1}, {"a": 2}])"#),
+            (CellKind::Code, r#"Gui.bar_chart([["A", 10], ["B", 20]])"#),
+        ]);
+        let _ = notebook.update(NotebookMessage::RunAll);
+        let [CellOutput::Table(table)] = &notebook.cells()[0].outputs[..] else {
+            panic!("expected a table");
+        };
+        assert_eq!(table.rows.len(), 2);
+        assert!(matches!(
+            notebook.cells()[1].outputs[..],
+            [CellOutput::Chart(Chart::Bar(_))]
+        ));
+
+        let action = notebook.update(NotebookMessage::OpenTable { cell: 0, output: 0 });
+        assert!(matches!(action, Some(NotebookAction::OpenTable(t)) if t.rows.len() == 2));
+
+        let html = notebook.to_html();
+        assert!(html.contains("<th>a</th>"));
+        assert!(html.contains("<svg"));
+        assert_eq!(html.matches("<rect").count(), 2);
+    }
+
+    #[test]
+    fn test_export_script() {
+        let notebook = notebook(&[
+            (CellKind::Markdown, "# Title\n\nNotes"),
+            (CellKind::Code, "let x = 1\n"),
+            (CellKind::Code, ""),
+            (CellKind::Code, "x"),
+        ]);
+        assert_eq!(
+            notebook.to_script(),
+            "// # Title\n//\n// Notes\n\nlet x = 1\n\nx\n"
+        );
+    }
+
+    #[test]
+    fn test_markdown_to_html() {
+        assert_eq!(
+            markdown_to_html("# A <b>\nsome `x < 1`\ntext\n- one\n- two\n```\nlet a = 1\n```"),
+            "<h1>A &lt;b&gt;</h1>\n<p>some <code>x &lt; 1</code> text</p>\n\
+             <ul>\n<li>one</li>\n<li>two</li>\n</ul>\n<pre><code>let a = 1\n</code></pre>\n"
+        );
+        assert_eq!(heading("#not a heading"), None);
+        assert_eq!(heading("### Three"), Some((3, "Three")));
+    }
+
+    #[test]
+    fn test_editing_cells() {
+        let mut notebook = notebook(&[(CellKind::Code, "1"), (CellKind::Code, "2")]);
+        let _ = notebook.update(NotebookMessage::Select(0));
+        let _ = notebook.update(NotebookMessage::AddCell(CellKind::Markdown));
+        assert_eq!(notebook.cells()[1].kind, CellKind::Markdown);
+        assert!(notebook.is_modified());
+
+        let _ = notebook.update(NotebookMessage::MoveDown(1));
+        assert_eq!(notebook.cells()[1].source(), "2");
+        let _ = notebook.update(NotebookMessage::MoveUp(0));
+        assert_eq!(notebook.cells()[0].source(), "1");
+        let _ = notebook.update(NotebookMessage::Delete(2));
+        assert_eq!(notebook.cells().len(), 2);
+
+        for _ in 0..2 {
+            let _ = notebook.update(NotebookMessage::Delete(0));
+        }
+        assert_eq!(notebook.cells().len(), 1);
+        assert_eq!(notebook.cells()[0].kind, CellKind::Code);
+    }
+
+    #[test]
+    fn test_close_asks_before_discarding() {
+        let mut notebook = NotebookPanel::new();
+        assert!(matches!(
+            notebook.update(NotebookMessage::Close),
+            Some(NotebookAction::Close)
+        ));
+
+        let _ = notebook.update(NotebookMessage::AddCell(CellKind::Code));
+        assert!(notebook.update(NotebookMessage::Close).is_none());
+        assert!(matches!(
+            notebook.update(NotebookMessage::Close),
+            Some(NotebookAction::Close)
+        ));
+
+        notebook.set_saved(PathBuf::from("a.stratnb"));
+        assert!(!notebook.is_modified());
+        assert!(matches!(
+            notebook.update(NotebookMessage::Close),
+            Some(NotebookAction::Close)
+        ));
+    }
+}
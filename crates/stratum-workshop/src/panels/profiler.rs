@@ -0,0 +1,670 @@
+//! Profiler panel
+//!
+//! Shows a profiled run as a flamegraph and a table of allocations. The
+//! flamegraph is read from the CPU profiler's speedscope JSON, either
+//! produced by profiling the editor's file in the Workshop or written by
+//! `stratum profile --format speedscope`; clicking a frame opens its source
+//! line. Allocations come from the memory profiler, grouped by call site.
+
+use iced::widget::{button, column, container, row, scrollable, text, Column, Row, Space};
+use iced::{Color, Element, Font, Length, Theme};
+use serde::Deserialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use stratum_core::bytecode::Function;
+use stratum_core::data::global_profiler;
+use stratum_core::{Compiler, MemoryStats, Parser, VM};
+
+/// Deepest flamegraph level drawn
+const MAX_DEPTH: usize = 32;
+/// Frames with under 1/`MIN_SHARE` of the samples are left out of the
+/// flamegraph
+const MIN_SHARE: u64 = 200;
+/// Most allocation sites listed
+const MAX_ALLOCATIONS: usize = 200;
+
+/// Messages for the profiler panel
+#[derive(Debug, Clone)]
+pub enum ProfilerMessage {
+    /// Show the flamegraph or the allocations
+    SetView(ProfilerView),
+    /// Open the source line of a flamegraph frame
+    OpenFrame(usize),
+    /// Open the source line of an allocation site
+    OpenAllocation(usize),
+    /// Sort allocations by a column
+    SortAllocations(AllocationSort),
+    /// Load a speedscope profile from a file
+    OpenFile,
+    /// Close the panel
+    Close,
+}
+
+/// Actions returned from update that Workshop needs to handle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProfilerAction {
+    /// Open a file at a line (1-based)
+    Open { path: PathBuf, line: usize },
+    /// Pick a speedscope file to load
+    OpenFile,
+    /// Close the panel
+    Close,
+}
+
+/// What the panel shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProfilerView {
+    #[default]
+    Flamegraph,
+    Allocations,
+}
+
+/// Column the allocations are sorted by, largest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AllocationSort {
+    #[default]
+    Bytes,
+    Count,
+}
+
+/// A function and line in a profile
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlameFrame {
+    pub name: String,
+    pub file: Option<PathBuf>,
+    /// Line (1-based), if known
+    pub line: Option<u32>,
+}
+
+/// A frame on the sampled stacks, with the frames it called
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlameNode {
+    /// Index into [`Flamegraph::frames`]
+    pub frame: usize,
+    /// Samples with this frame on the stack, weighted
+    pub weight: u64,
+    /// Callees, heaviest first
+    pub children: Vec<FlameNode>,
+}
+
+/// Sampled call stacks merged into a tree
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Flamegraph {
+    pub frames: Vec<FlameFrame>,
+    /// Outermost frames, heaviest first
+    pub roots: Vec<FlameNode>,
+    /// Weight of all samples
+    pub total: u64,
+}
+
+/// The parts of a speedscope file the flamegraph needs
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Speedscope {
+    shared: SpeedscopeShared,
+    profiles: Vec<SpeedscopeProfile>,
+    #[serde(default)]
+    active_profile_index: usize,
+}
+
+#[derive(Deserialize)]
+struct SpeedscopeShared {
+    frames: Vec<SpeedscopeFrame>,
+}
+
+#[derive(Deserialize)]
+struct SpeedscopeFrame {
+    name: String,
+    file: Option<String>,
+    line: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct SpeedscopeProfile {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    samples: Vec<Vec<usize>>,
+    #[serde(default)]
+    weights: Vec<u64>,
+}
+
+impl Flamegraph {
+    /// Read a sampled speedscope profile, like the CPU profiler writes
+    pub fn from_speedscope(json: &str) -> Result<Self, String> {
+        let file: Speedscope =
+            serde_json::from_str(json).map_err(|e| format!("Not a speedscope profile: {e}"))?;
+        let profile = file
+            .profiles
+            .get(file.active_profile_index)
+            .or(file.profiles.first())
+            .ok_or("The profile has no samples")?;
+        if profile.kind != "sampled" {
+            return Err(format!(
+                "Only sampled profiles can be shown, not {}",
+                profile.kind
+            ));
+        }
+
+        let frames: Vec<FlameFrame> = file
+            .shared
+            .frames
+            .into_iter()
+            .map(|frame| FlameFrame {
+                name: frame.name,
+                file: frame.file.map(PathBuf::from),
+                line: frame.line,
+            })
+            .collect();
+        let mut flamegraph = Self {
+            frames,
+            ..Self::default()
+        };
+        for (i, stack) in profile.samples.iter().enumerate() {
+            if let Some(&frame) = stack.iter().find(|&&f| f >= flamegraph.frames.len()) {
+                return Err(format!("Sample {i} refers to missing frame {frame}"));
+            }
+            let weight = profile.weights.get(i).copied().unwrap_or(1);
+            flamegraph.add(stack, weight);
+        }
+        sort_nodes(&mut flamegraph.roots);
+        Ok(flamegraph)
+    }
+
+    /// Add a stack's weight along its path, outermost frame first
+    fn add(&mut self, stack: &[usize], weight: u64) {
+        self.total += weight;
+        let mut nodes = &mut self.roots;
+        for &frame in stack {
+            let index = match nodes.iter().position(|node| node.frame == frame) {
+                Some(index) => index,
+                None => {
+                    nodes.push(FlameNode {
+                        frame,
+                        weight: 0,
+                        children: Vec::new(),
+                    });
+                    nodes.len() - 1
+                }
+            };
+            let node = &mut nodes[index];
+            node.weight += weight;
+            nodes = &mut node.children;
+        }
+    }
+}
+
+fn sort_nodes(nodes: &mut [FlameNode]) {
+    nodes.sort_by(|a, b| b.weight.cmp(&a.weight).then(a.frame.cmp(&b.frame)));
+    for node in nodes {
+        sort_nodes(&mut node.children);
+    }
+}
+
+/// Values of one category allocated at one call site
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AllocationRow {
+    pub function: String,
+    pub file: Option<PathBuf>,
+    pub line: u32,
+    pub category: String,
+    pub count: usize,
+    pub bytes: usize,
+}
+
+/// A profiled run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Profile {
+    /// What was profiled, for the header
+    pub name: String,
+    pub flamegraph: Flamegraph,
+    /// Allocations by call site, empty when the profile was loaded from a
+    /// file
+    pub allocations: Vec<AllocationRow>,
+}
+
+/// Run Stratum source like `stratum profile`, sampling the call stack
+/// every `interval` instructions and tracking allocations. Calls `main()`
+/// when the source defines it.
+///
+/// The memory profiler is shared by the process, so only one profile may
+/// run at a time.
+pub fn profile_source(name: &str, source: &str, interval: u64) -> Result<Profile, String> {
+    let module = Parser::parse_module(source).map_err(|errors| join_errors(&errors))?;
+    let function = Compiler::with_source(name)
+        .compile_module(&module)
+        .map_err(|errors| join_errors(&errors))?;
+
+    // JIT-compiled code runs outside the interpreter and cannot be sampled
+    let mut vm = VM::new_without_jit();
+    vm.enable_cpu_profiling(interval);
+    vm.set_allocation_profiling(true);
+    stratum_core::reset_profiler();
+    stratum_core::enable_profiling();
+
+    let result = run_with_main(&mut vm, function);
+    vm.sweep_allocations();
+    stratum_core::disable_profiling();
+    result?;
+
+    let cpu = vm
+        .take_cpu_profile()
+        .ok_or("The profiler was not enabled")?;
+    let flamegraph = Flamegraph::from_speedscope(&cpu.to_speedscope(name))?;
+    let allocations = global_profiler()
+        .call_sites()
+        .into_iter()
+        .take(MAX_ALLOCATIONS)
+        .map(|site| AllocationRow {
+            function: site.site.function,
+            file: site.site.file.map(PathBuf::from),
+            line: site.site.line,
+            category: site.category,
+            count: site.allocation_count,
+            bytes: site.total_bytes,
+        })
+        .collect();
+    Ok(Profile {
+        name: name.to_string(),
+        flamegraph,
+        allocations,
+    })
+}
+
+/// Run a module, then its `main()` if it defines one
+fn run_with_main(vm: &mut VM, function: Rc<Function>) -> Result<(), String> {
+    vm.run(function).map_err(|e| e.to_string())?;
+    if vm.globals().contains_key("main") {
+        let call = Parser::parse_expression("main()").map_err(|_| "Cannot call main()")?;
+        let main = Compiler::new()
+            .compile_expression(&call)
+            .map_err(|_| "Cannot call main()")?;
+        vm.run(main).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Profiler panel
+#[derive(Debug)]
+pub struct ProfilerPanel {
+    profile: Profile,
+    view: ProfilerView,
+    sort: AllocationSort,
+}
+
+impl ProfilerPanel {
+    pub fn new(profile: Profile) -> Self {
+        let view = if profile.flamegraph.total == 0 && !profile.allocations.is_empty() {
+            ProfilerView::Allocations
+        } else {
+            ProfilerView::Flamegraph
+        };
+        Self {
+            profile,
+            view,
+            sort: AllocationSort::Bytes,
+        }
+    }
+
+    pub fn profile(&self) -> &Profile {
+        &self.profile
+    }
+
+    /// Allocations in the order shown
+    fn sorted_allocations(&self) -> Vec<(usize, &AllocationRow)> {
+        let mut allocations: Vec<_> = self.profile.allocations.iter().enumerate().collect();
+        match self.sort {
+            AllocationSort::Bytes => allocations.sort_by(|a, b| b.1.bytes.cmp(&a.1.bytes)),
+            AllocationSort::Count => allocations.sort_by(|a, b| b.1.count.cmp(&a.1.count)),
+        }
+        allocations
+    }
+
+    /// Handle a message
+    pub fn update(&mut self, message: ProfilerMessage) -> Option<ProfilerAction> {
+        match message {
+            ProfilerMessage::SetView(view) => self.view = view,
+            ProfilerMessage::OpenFrame(index) => {
+                let frame = self.profile.flamegraph.frames.get(index)?;
+                return Some(ProfilerAction::Open {
+                    path: frame.file.clone()?,
+                    line: frame.line.map_or(1, |line| line as usize),
+                });
+            }
+            ProfilerMessage::OpenAllocation(index) => {
+                let allocation = self.profile.allocations.get(index)?;
+                return Some(ProfilerAction::Open {
+                    path: allocation.file.clone()?,
+                    line: allocation.line as usize,
+                });
+            }
+            ProfilerMessage::SortAllocations(sort) => self.sort = sort,
+            ProfilerMessage::OpenFile => return Some(ProfilerAction::OpenFile),
+            ProfilerMessage::Close => return Some(ProfilerAction::Close),
+        }
+        None
+    }
+
+    /// Render the panel
+    pub fn view(&self) -> Element<'_, ProfilerMessage> {
+        let tab = |label: &'static str, view: ProfilerView| {
+            button(text(label).size(11))
+                .on_press(ProfilerMessage::SetView(view))
+                .padding([2, 8])
+                .style(if self.view == view {
+                    button::primary
+                } else {
+                    button::text
+                })
+        };
+        let header = row![
+            text(format!("Profile - {}", self.profile.name)).size(12),
+            tab("Flamegraph", ProfilerView::Flamegraph),
+            tab("Allocations", ProfilerView::Allocations),
+            Space::new().width(Length::Fill),
+            button(text("Open").size(10))
+                .on_press(ProfilerMessage::OpenFile)
+                .padding([2, 6])
+                .style(button::text),
+            button(text("x").size(10))
+                .on_press(ProfilerMessage::Close)
+                .padding([2, 6])
+                .style(button::text),
+        ]
+        .spacing(4)
+        .padding([4, 8])
+        .align_y(iced::Alignment::Center);
+
+        let body = match self.view {
+            ProfilerView::Flamegraph => self.flamegraph_view(),
+            ProfilerView::Allocations => self.allocations_view(),
+        };
+
+        container(column![header, scrollable(body).height(Length::Fill)])
+            .width(Length::Fill)
+            .height(Length::FillPortion(1))
+            .style(|theme: &Theme| {
+                let palette = theme.extended_palette();
+                container::Style {
+                    background: Some(palette.background.weak.color.into()),
+                    ..Default::default()
+                }
+            })
+            .into()
+    }
+
+    /// The flamegraph, callers above their callees, widths in proportion to
+    /// samples
+    fn flamegraph_view(&self) -> Element<'_, ProfilerMessage> {
+        let flamegraph = &self.profile.flamegraph;
+        if flamegraph.total == 0 {
+            return text("No samples recorded; profile a longer run.")
+                .size(11)
+                .into();
+        }
+        let min_weight = flamegraph.total / MIN_SHARE;
+        container(self.nodes_view(&flamegraph.roots, flamegraph.total, min_weight, 0))
+            .padding(8)
+            .width(Length::Fill)
+            .into()
+    }
+
+    /// Frames side by side within a parent of `parent_weight`, the rest of
+    /// the width left empty for the parent's own samples
+    fn nodes_view<'a>(
+        &'a self,
+        nodes: &'a [FlameNode],
+        parent_weight: u64,
+        min_weight: u64,
+        depth: usize,
+    ) -> Element<'a, ProfilerMessage> {
+        let shown: Vec<&FlameNode> = nodes
+            .iter()
+            .filter(|node| node.weight >= min_weight.max(1))
+            .collect();
+        if shown.is_empty() || depth >= MAX_DEPTH {
+            return Space::new().into();
+        }
+        let portion = |weight: u64| {
+            u16::try_from(weight * 1000 / parent_weight.max(1))
+                .unwrap_or(1000)
+                .max(1)
+        };
+        let rest = parent_weight - shown.iter().map(|node| node.weight).sum::<u64>();
+        let mut row = Row::with_children(shown.into_iter().map(|node| {
+            column![
+                self.frame_view(node, depth),
+                self.nodes_view(&node.children, node.weight, min_weight, depth + 1),
+            ]
+            .width(Length::FillPortion(portion(node.weight)))
+            .into()
+        }));
+        if rest > 0 {
+            row = row.push(Space::new().width(Length::FillPortion(portion(rest))));
+        }
+        row.into()
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn frame_view(&self, node: &FlameNode, depth: usize) -> Element<'_, ProfilerMessage> {
+        let flamegraph = &self.profile.flamegraph;
+        let frame = &flamegraph.frames[node.frame];
+        let share = node.weight as f64 * 100.0 / flamegraph.total as f64;
+        let label = match frame.line {
+            Some(line) => format!("{}:{line} ({share:.1}%)", frame.name),
+            None => format!("{} ({share:.1}%)", frame.name),
+        };
+        // Warmer colors for deeper frames, like a classic flamegraph
+        let hue = (depth % 6) as f32 / 6.0;
+        let color = Color::from_rgb(0.85, 0.35 + 0.4 * hue, 0.2);
+        button(text(label).size(10).font(Font::MONOSPACE))
+            .on_press_maybe(
+                frame
+                    .file
+                    .is_some()
+                    .then_some(ProfilerMessage::OpenFrame(node.frame)),
+            )
+            .width(Length::Fill)
+            .padding([1, 3])
+            .clip(true)
+            .style(move |_theme: &Theme, status| button::Style {
+                background: Some(
+                    match status {
+                        button::Status::Hovered => Color { a: 0.8, ..color },
+                        _ => color,
+                    }
+                    .into(),
+                ),
+                text_color: Color::BLACK,
+                border: iced::Border {
+                    color: Color::WHITE,
+                    width: 0.5,
+                    radius: 0.0.into(),
+                },
+                ..button::Style::default()
+            })
+            .into()
+    }
+
+    /// Allocation sites with their counts and bytes
+    fn allocations_view(&self) -> Element<'_, ProfilerMessage> {
+        if self.profile.allocations.is_empty() {
+            return container(text("No allocations recorded").size(11))
+                .padding(8)
+                .into();
+        }
+        let heading = |label: &'static str, sort: Option<AllocationSort>| {
+            let label = text(label).size(11).font(Font::MONOSPACE);
+            match sort {
+                Some(sort) => button(label)
+                    .on_press(ProfilerMessage::SortAllocations(sort))
+                    .padding(0)
+                    .style(if self.sort == sort {
+                        button::primary
+                    } else {
+                        button::text
+                    })
+                    .into(),
+                None => Element::from(label),
+            }
+        };
+        let header = row![
+            container(heading("Function", None)).width(Length::FillPortion(3)),
+            container(heading("Location", None)).width(Length::FillPortion(3)),
+            container(heading("Category", None)).width(Length::FillPortion(2)),
+            container(heading("Count", Some(AllocationSort::Count))).width(Length::FillPortion(1)),
+            container(heading("Bytes", Some(AllocationSort::Bytes))).width(Length::FillPortion(2)),
+        ]
+        .spacing(8);
+
+        let rows = self
+            .sorted_allocations()
+            .into_iter()
+            .map(|(i, allocation)| {
+                let location = match &allocation.file {
+                    Some(file) => format!("{}:{}", file_name(file), allocation.line),
+                    None => format!("line {}", allocation.line),
+                };
+                let cell = |value: String, portion: u16| {
+                    container(text(value).size(11).font(Font::MONOSPACE))
+                        .width(Length::FillPortion(portion))
+                };
+                button(
+                    row![
+                        cell(allocation.function.clone(), 3),
+                        cell(location, 3),
+                        cell(allocation.category.clone(), 2),
+                        cell(allocation.count.to_string(), 1),
+                        cell(MemoryStats::format_bytes(allocation.bytes), 2),
+                    ]
+                    .spacing(8),
+                )
+                .on_press_maybe(
+                    allocation
+                        .file
+                        .is_some()
+                        .then_some(ProfilerMessage::OpenAllocation(i)),
+                )
+                .padding([1, 0])
+                .width(Length::Fill)
+                .style(button::text)
+                .into()
+            });
+
+        column![header, Column::with_children(rows).spacing(1)]
+            .spacing(4)
+            .padding(8)
+            .into()
+    }
+}
+
+fn join_errors(errors: &[impl fmt::Display]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A path's file name, or the whole path when it has none
+fn file_name(path: &Path) -> String {
+    path.file_name().map_or_else(
+        || path.display().to_string(),
+        |n| n.to_string_lossy().to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPEEDSCOPE: &str = r#"{
+        "shared": {"frames": [
+            {"name": "<script>", "line": 1, "file": "app.strat"},
+            {"name": "work", "line": 4, "file": "app.strat"},
+            {"name": "helper", "line": 9}
+        ]},
+        "profiles": [{
+            "type": "sampled",
+            "samples": [[0, 1], [0, 1, 2], [0, 1], [0]],
+            "weights": [10, 30, 10, 5]
+        }]
+    }"#;
+
+    #[test]
+    fn test_flamegraph_from_speedscope() {
+        let flamegraph = Flamegraph::from_speedscope(SPEEDSCOPE).unwrap();
+        assert_eq!(flamegraph.total, 55);
+        assert_eq!(flamegraph.frames[2].file, None);
+        let root = &flamegraph.roots[0];
+        assert_eq!((root.frame, root.weight), (0, 55));
+        let work = &root.children[0];
+        assert_eq!((work.frame, work.weight), (1, 50));
+        assert_eq!(work.children[0].weight, 30);
+
+        assert!(Flamegraph::from_speedscope("{}").is_err());
+        assert!(Flamegraph::from_speedscope(
+            r#"{"shared": {"frames": []}, "profiles": [{"type": "sampled", "samples": [[3]]}]}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_open_frames_and_allocations() {
+        let mut panel = ProfilerPanel::new(Profile {
+            name: "app.strat".to_string(),
+            flamegraph: Flamegraph::from_speedscope(SPEEDSCOPE).unwrap(),
+            allocations: vec![
+                AllocationRow {
+                    function: "work".to_string(),
+                    file: Some(PathBuf::from("app.strat")),
+                    line: 5,
+                    category: "List".to_string(),
+                    count: 3,
+                    bytes: 120,
+                },
+                AllocationRow {
+                    function: "helper".to_string(),
+                    file: None,
+                    line: 10,
+                    category: "String".to_string(),
+                    count: 8,
+                    bytes: 64,
+                },
+            ],
+        });
+        assert_eq!(
+            panel.update(ProfilerMessage::OpenFrame(1)),
+            Some(ProfilerAction::Open {
+                path: PathBuf::from("app.strat"),
+                line: 4
+            })
+        );
+        // Frames and sites without a file have nowhere to go
+        assert_eq!(panel.update(ProfilerMessage::OpenFrame(2)), None);
+        assert_eq!(panel.update(ProfilerMessage::OpenAllocation(1)), None);
+
+        assert_eq!(panel.sorted_allocations()[0].1.function, "work");
+        panel.update(ProfilerMessage::SortAllocations(AllocationSort::Count));
+        assert_eq!(panel.sorted_allocations()[0].1.function, "helper");
+    }
+
+    #[test]
+    fn test_profile_source() {
+        let source = "fx build(n) {\n    [n, n + 1]\n}\nfx main() {\n    for i in 0..200 {\n        build(i)\n    }\n}\n";
+        let profile = profile_source("app.strat", source, 10).unwrap();
+        assert!(profile.flamegraph.total > 0);
+        assert!(profile
+            .flamegraph
+            .frames
+            .iter()
+            .any(|frame| frame.name == "build"));
+        assert!(profile
+            .allocations
+            .iter()
+            .any(|allocation| allocation.function == "build" && allocation.category == "List"));
+
+        assert!(profile_source("bad.strat", "fn (", 10).is_err());
+    }
+}
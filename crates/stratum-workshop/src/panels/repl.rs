@@ -3,6 +3,7 @@
 //! Provides an interactive REPL at the bottom of the window.
 //! Implements Phase 6.5 of the Workshop IDE.
 
+use super::table_viewer::TableData;
 use iced::widget::{column, container, row, scrollable, text, text_input, Column};
 use iced::{Element, Length};
 use std::cell::RefCell;
@@ -51,6 +52,8 @@ pub struct ReplPanel {
     multi_line_mode: bool,
     /// Accumulated input for multi-line mode
     accumulated_input: String,
+    /// Table of the last DataFrame or Cube result, until the Workshop opens it
+    pending_table: Option<TableData>,
 }
 
 impl std::fmt::Debug for ReplPanel {
@@ -80,6 +83,7 @@ impl ReplPanel {
             vm: RefCell::new(VM::new()),
             multi_line_mode: false,
             accumulated_input: String::new(),
+            pending_table: None,
         }
     }
 
@@ -166,8 +170,17 @@ impl ReplPanel {
             Ok((stdout, value)) => {
                 // Combine captured stdout with the result value
                 let mut output_parts = stdout;
-                if !matches!(value, Value::Null) {
-                    output_parts.push(pretty_print(&value));
+                match TableData::from_value(&input_for_history, &value) {
+                    Some(Ok(table)) => {
+                        output_parts
+                            .push(format!("{} (opened in table viewer)", pretty_print(&value)));
+                        self.pending_table = Some(table);
+                    }
+                    Some(Err(err)) => output_parts.push(err),
+                    None if !matches!(value, Value::Null) => {
+                        output_parts.push(pretty_print(&value));
+                    }
+                    None => {}
                 }
                 (output_parts.join("\n"), false)
             }
@@ -185,35 +198,15 @@ impl ReplPanel {
         self.history_index = None;
     }
 
+    /// Take the table of the last DataFrame or Cube result, if not taken yet
+    pub fn take_table(&mut self) -> Option<TableData> {
+        self.pending_table.take()
+    }
+
     /// Evaluate a string of Stratum code
     /// Returns (captured_stdout, result_value) or error
     fn eval(&self, input: &str) -> Result<(Vec<String>, Value), String> {
-        // Parse the input - supports expressions, statements, and function definitions
-        let repl_input = Parser::parse_repl_input(input).map_err(|errors| {
-            errors
-                .iter()
-                .map(|e| format!("Parse error: {e}"))
-                .collect::<Vec<_>>()
-                .join("\n")
-        })?;
-
-        // Compile based on input type
-        let function = Compiler::new()
-            .compile_repl_input(&repl_input)
-            .map_err(|errors| {
-                errors
-                    .iter()
-                    .map(|e| format!("Compile error: {e}"))
-                    .collect::<Vec<_>>()
-                    .join("\n")
-            })?;
-
-        // Run in the VM with output capture (globals are preserved between runs)
-        let (result, captured) = with_output_capture(|| self.vm.borrow_mut().run(function));
-
-        result
-            .map(|value| (captured.stdout, value))
-            .map_err(|e| format!("Runtime error: {e}"))
+        eval(&mut self.vm.borrow_mut(), input)
     }
 
     /// Handle REPL commands (starting with :)
@@ -462,6 +455,37 @@ enum CommandResult {
     // Some(Handled(...)) for commands, and None for non-commands
 }
 
+/// Evaluate a string of Stratum code in a VM, whose globals persist between
+/// evaluations. Returns the captured stdout and the result value, or an error.
+pub(super) fn eval(vm: &mut VM, input: &str) -> Result<(Vec<String>, Value), String> {
+    // Parse the input - supports expressions, statements, and function definitions
+    let repl_input = Parser::parse_repl_input(input).map_err(|errors| {
+        errors
+            .iter()
+            .map(|e| format!("Parse error: {e}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    })?;
+
+    // Compile based on input type
+    let function = Compiler::new()
+        .compile_repl_input(&repl_input)
+        .map_err(|errors| {
+            errors
+                .iter()
+                .map(|e| format!("Compile error: {e}"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })?;
+
+    // Run in the VM with output capture (globals are preserved between runs)
+    let (result, captured) = with_output_capture(|| vm.run(function));
+
+    result
+        .map(|value| (captured.stdout, value))
+        .map_err(|e| format!("Runtime error: {e}"))
+}
+
 /// Check if the input is complete (balanced brackets/braces/parens)
 fn is_complete(input: &str) -> bool {
     let mut paren_depth = 0i32;
@@ -562,7 +586,7 @@ fn is_complete(input: &str) -> bool {
 }
 
 /// Pretty-print a value for REPL output
-fn pretty_print(value: &Value) -> String {
+pub(super) fn pretty_print(value: &Value) -> String {
     match value {
         Value::String(s) => format!("\"{s}\""),
         Value::List(list) => {
@@ -794,6 +818,25 @@ mod tests {
         assert_eq!(stdout, vec!["hello"]);
     }
 
+    #[test]
+    fn test_dataframe_result_opens_table() {
+        let mut repl = ReplPanel::new();
+        repl.update(ReplMessage::InputChanged(
+            r#"Data.frame([{"name": "Alice", "age": 30}])"#.to_string(),
+        ));
+        repl.update(ReplMessage::Submit);
+        assert!(repl.history[0].output.ends_with("(opened in table viewer)"));
+
+        let table = repl.take_table().unwrap();
+        assert_eq!(table.rows.len(), 1);
+        assert_eq!(table.columns.len(), 2);
+        assert!(repl.take_table().is_none());
+
+        repl.update(ReplMessage::InputChanged("1 + 2".to_string()));
+        repl.update(ReplMessage::Submit);
+        assert!(repl.take_table().is_none());
+    }
+
     #[test]
     fn test_pretty_print() {
         assert_eq!(pretty_print(&Value::Int(42)), "42");
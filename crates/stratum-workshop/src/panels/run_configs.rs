@@ -0,0 +1,756 @@
+//! Run configurations panel
+//!
+//! A run configuration names an entry file with its arguments, environment,
+//! working directory and execution mode, and runs it with `stratum run` in
+//! the terminal. Configurations are stored with the project in
+//! `.workshop/run.toml` so they can be shared, and each may bind a key.
+
+use super::terminal::Program;
+use crate::plugins::KeyChord;
+use iced::widget::{button, column, container, row, scrollable, text, text_input, Column, Row};
+use iced::{Color, Element, Length, Theme};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// File in the project the configurations are stored in
+pub const RUN_CONFIG_FILE: &str = ".workshop/run.toml";
+
+/// How functions are executed, overriding `#[compile]` and `#[interpret]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RunMode {
+    /// Follow the directives in the source
+    #[default]
+    Directives,
+    /// Interpret every function
+    InterpretAll,
+    /// Compile every function with the JIT
+    CompileAll,
+}
+
+impl RunMode {
+    const ALL: [Self; 3] = [Self::Directives, Self::InterpretAll, Self::CompileAll];
+
+    /// The `stratum run` flag for the mode
+    fn flag(self) -> Option<&'static str> {
+        match self {
+            Self::Directives => None,
+            Self::InterpretAll => Some("--interpret-all"),
+            Self::CompileAll => Some("--compile-all"),
+        }
+    }
+}
+
+impl fmt::Display for RunMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Directives => "Directives",
+            Self::InterpretAll => "Interpret all",
+            Self::CompileAll => "Compile all",
+        })
+    }
+}
+
+/// A way to run the project
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunConfig {
+    pub name: String,
+    /// Entry file, relative to the project
+    pub entry: PathBuf,
+    /// Arguments passed to the program
+    pub args: Vec<String>,
+    pub env: BTreeMap<String, String>,
+    /// Working directory, relative to the project; the project when unset
+    pub cwd: Option<PathBuf>,
+    pub mode: RunMode,
+    /// Whether hot functions are compiled with the JIT
+    pub jit: bool,
+    /// Key that runs the configuration, like `ctrl+shift+1`
+    pub shortcut: Option<String>,
+}
+
+impl Default for RunConfig {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            entry: PathBuf::from("src/main.strat"),
+            args: Vec::new(),
+            env: BTreeMap::new(),
+            cwd: None,
+            mode: RunMode::Directives,
+            jit: true,
+            shortcut: None,
+        }
+    }
+}
+
+impl RunConfig {
+    /// The `stratum run` command for a project
+    pub fn program(&self, root: &Path) -> Program {
+        let mut args = vec![
+            "run".to_string(),
+            root.join(&self.entry).display().to_string(),
+        ];
+        args.extend(self.mode.flag().map(str::to_string));
+        if !self.jit {
+            args.push("--no-jit".to_string());
+        }
+        if !self.args.is_empty() {
+            args.push("--".to_string());
+            args.extend(self.args.iter().cloned());
+        }
+        Program {
+            program: "stratum".to_string(),
+            args,
+            env: self
+                .env
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+            cwd: self
+                .cwd
+                .as_ref()
+                .map_or_else(|| root.to_path_buf(), |cwd| root.join(cwd)),
+        }
+    }
+
+    /// The bound key, if it parses
+    pub fn chord(&self) -> Option<KeyChord> {
+        KeyChord::parse(self.shortcut.as_deref()?).ok()
+    }
+}
+
+/// The configurations file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct RunConfigFile {
+    #[serde(default, rename = "config")]
+    configs: Vec<RunConfig>,
+}
+
+/// Load a project's configurations; a project without the file has none
+pub fn load_run_configs(root: &Path) -> Result<Vec<RunConfig>, String> {
+    let path = root.join(RUN_CONFIG_FILE);
+    match std::fs::read_to_string(&path) {
+        Ok(content) => toml::from_str::<RunConfigFile>(&content)
+            .map(|file| file.configs)
+            .map_err(|e| format!("{}: {e}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("{}: {e}", path.display())),
+    }
+}
+
+/// Store a project's configurations
+pub fn save_run_configs(root: &Path, configs: &[RunConfig]) -> Result<(), String> {
+    let path = root.join(RUN_CONFIG_FILE);
+    let file = RunConfigFile {
+        configs: configs.to_vec(),
+    };
+    let content = toml::to_string_pretty(&file).map_err(|e| e.to_string())?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(&path, content).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// Split a line into words at whitespace, keeping double-quoted text
+/// together
+pub fn split_args(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quoted = false;
+    let mut in_word = false;
+    for c in line.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_word = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            c => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+/// Join words into a line [`split_args`] splits back
+fn join_args(words: &[impl AsRef<str>]) -> String {
+    words
+        .iter()
+        .map(|word| {
+            let word = word.as_ref();
+            if word.is_empty() || word.contains(char::is_whitespace) {
+                format!("\"{word}\"")
+            } else {
+                word.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Messages for the run configurations panel
+#[derive(Debug, Clone)]
+pub enum RunConfigMessage {
+    /// Edit a configuration
+    Edit(usize),
+    /// Add a configuration and edit it
+    Add,
+    /// Remove the edited configuration
+    Remove,
+    NameChanged(String),
+    EntryChanged(String),
+    ArgsChanged(String),
+    /// `KEY=VALUE` pairs changed
+    EnvChanged(String),
+    CwdChanged(String),
+    ShortcutChanged(String),
+    SetMode(RunMode),
+    ToggleJit,
+    /// Store the configurations in the project
+    Save,
+    /// Run the edited configuration
+    Run,
+    /// Close the panel
+    Close,
+}
+
+/// Actions returned from update that Workshop needs to handle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RunConfigAction {
+    /// Run a configuration
+    Run(usize),
+    /// The configurations were stored in this file
+    Saved(PathBuf),
+    /// Close the panel
+    Close,
+}
+
+/// The edited configuration's fields as typed
+#[derive(Debug, Clone, Default)]
+struct Draft {
+    name: String,
+    entry: String,
+    args: String,
+    env: String,
+    cwd: String,
+    shortcut: String,
+}
+
+impl Draft {
+    fn new(config: &RunConfig) -> Self {
+        Self {
+            name: config.name.clone(),
+            entry: config.entry.display().to_string(),
+            args: join_args(&config.args),
+            env: join_args(
+                &config
+                    .env
+                    .iter()
+                    .map(|(key, value)| format!("{key}={value}"))
+                    .collect::<Vec<_>>(),
+            ),
+            cwd: config
+                .cwd
+                .as_ref()
+                .map(|cwd| cwd.display().to_string())
+                .unwrap_or_default(),
+            shortcut: config.shortcut.clone().unwrap_or_default(),
+        }
+    }
+}
+
+/// Run configurations panel
+#[derive(Debug, Default)]
+pub struct RunConfigPanel {
+    /// The project, once a folder is opened
+    root: Option<PathBuf>,
+    configs: Vec<RunConfig>,
+    /// Configuration the Run button runs; the editor's file in the REPL
+    /// when `None`
+    selected: Option<usize>,
+    /// Configuration being edited, and its fields as typed
+    editing: Option<usize>,
+    draft: Draft,
+    /// Whether there are changes not stored in the project
+    modified: bool,
+    error: Option<String>,
+}
+
+impl RunConfigPanel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the configurations of a project
+    pub fn open(&mut self, root: PathBuf) {
+        *self = Self::new();
+        match load_run_configs(&root) {
+            Ok(configs) => self.configs = configs,
+            Err(e) => self.error = Some(e),
+        }
+        self.root = Some(root);
+        if !self.configs.is_empty() {
+            self.edit(0);
+        }
+    }
+
+    pub fn root(&self) -> Option<&Path> {
+        self.root.as_deref()
+    }
+
+    pub fn configs(&self) -> &[RunConfig] {
+        &self.configs
+    }
+
+    /// Configuration the Run button runs, if any
+    pub fn selected(&self) -> Option<usize> {
+        self.selected
+    }
+
+    /// Pick what the Run button runs
+    pub fn select(&mut self, index: Option<usize>) {
+        self.selected = index.filter(|&i| i < self.configs.len());
+    }
+
+    /// Whether there are changes not stored in the project
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    /// Configuration bound to a key
+    pub fn for_chord(&self, chord: &KeyChord) -> Option<usize> {
+        self.configs
+            .iter()
+            .position(|config| config.chord().as_ref() == Some(chord))
+    }
+
+    fn edit(&mut self, index: usize) {
+        if let Some(config) = self.configs.get(index) {
+            self.editing = Some(index);
+            self.draft = Draft::new(config);
+        }
+    }
+
+    /// Apply a change to the edited configuration
+    fn change(&mut self, apply: impl FnOnce(&mut RunConfig, &mut Draft)) {
+        if let Some(config) = self.editing.and_then(|i| self.configs.get_mut(i)) {
+            apply(config, &mut self.draft);
+            self.modified = true;
+        }
+    }
+
+    /// Check the configurations before they are stored
+    fn validate(&self) -> Result<(), String> {
+        for (i, config) in self.configs.iter().enumerate() {
+            if config.name.trim().is_empty() {
+                return Err(format!("Configuration {} needs a name", i + 1));
+            }
+            if self.configs[..i].iter().any(|c| c.name == config.name) {
+                return Err(format!("Two configurations are named '{}'", config.name));
+            }
+            if let Some(shortcut) = &config.shortcut {
+                KeyChord::parse(shortcut).map_err(|e| format!("{}: {e}", config.name))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle a message
+    pub fn update(&mut self, message: RunConfigMessage) -> Option<RunConfigAction> {
+        match message {
+            RunConfigMessage::Edit(index) => self.edit(index),
+            RunConfigMessage::Add => {
+                self.configs.push(RunConfig {
+                    name: format!("Config {}", self.configs.len() + 1),
+                    ..RunConfig::default()
+                });
+                self.edit(self.configs.len() - 1);
+                self.modified = true;
+            }
+            RunConfigMessage::Remove => {
+                if let Some(index) = self.editing.take() {
+                    self.configs.remove(index);
+                    self.selected = match self.selected {
+                        Some(i) if i == index => None,
+                        Some(i) if i > index => Some(i - 1),
+                        selected => selected,
+                    };
+                    self.edit(index.min(self.configs.len().saturating_sub(1)));
+                    self.modified = true;
+                }
+            }
+            RunConfigMessage::NameChanged(name) => self.change(|config, draft| {
+                config.name.clone_from(&name);
+                draft.name = name;
+            }),
+            RunConfigMessage::EntryChanged(entry) => self.change(|config, draft| {
+                config.entry = PathBuf::from(&entry);
+                draft.entry = entry;
+            }),
+            RunConfigMessage::ArgsChanged(args) => self.change(|config, draft| {
+                config.args = split_args(&args);
+                draft.args = args;
+            }),
+            RunConfigMessage::EnvChanged(env) => self.change(|config, draft| {
+                config.env = split_args(&env)
+                    .into_iter()
+                    .filter_map(|pair| {
+                        let (key, value) = pair.split_once('=')?;
+                        Some((key.to_string(), value.to_string()))
+                    })
+                    .collect();
+                draft.env = env;
+            }),
+            RunConfigMessage::CwdChanged(cwd) => self.change(|config, draft| {
+                config.cwd = (!cwd.trim().is_empty()).then(|| PathBuf::from(&cwd));
+                draft.cwd = cwd;
+            }),
+            RunConfigMessage::ShortcutChanged(shortcut) => self.change(|config, draft| {
+                config.shortcut = (!shortcut.trim().is_empty()).then(|| shortcut.clone());
+                draft.shortcut = shortcut;
+            }),
+            RunConfigMessage::SetMode(mode) => self.change(|config, _| config.mode = mode),
+            RunConfigMessage::ToggleJit => self.change(|config, _| config.jit = !config.jit),
+            RunConfigMessage::Save => {
+                let root = self.root.clone()?;
+                match self
+                    .validate()
+                    .and_then(|()| save_run_configs(&root, &self.configs))
+                {
+                    Ok(()) => {
+                        self.modified = false;
+                        self.error = None;
+                        return Some(RunConfigAction::Saved(root.join(RUN_CONFIG_FILE)));
+                    }
+                    Err(e) => self.error = Some(e),
+                }
+            }
+            RunConfigMessage::Run => return self.editing.map(RunConfigAction::Run),
+            RunConfigMessage::Close => return Some(RunConfigAction::Close),
+        }
+        None
+    }
+
+    /// Render the panel
+    pub fn view(&self) -> Element<'_, RunConfigMessage> {
+        let small_button = |label: &'static str, message: Option<RunConfigMessage>| {
+            button(text(label).size(10))
+                .on_press_maybe(message)
+                .padding([2, 6])
+                .style(button::text)
+        };
+
+        let header = row![
+            text("Run Configurations").size(12),
+            iced::widget::Space::new().width(Length::Fill),
+            small_button("+", self.root.is_some().then_some(RunConfigMessage::Add)),
+            small_button("x", Some(RunConfigMessage::Close)),
+        ]
+        .align_y(iced::Alignment::Center);
+
+        let mut view = column![header].spacing(6).padding(8);
+        if self.root.is_none() {
+            view = view.push(text("Open a folder to add run configurations").size(11));
+        }
+        if let Some(error) = &self.error {
+            view = view.push(text(error).size(11).color(Color::from_rgb(0.9, 0.4, 0.4)));
+        }
+
+        let list = Column::with_children(self.configs.iter().enumerate().map(|(i, config)| {
+            let shortcut = config
+                .chord()
+                .map(|chord| format!("  {chord}"))
+                .unwrap_or_default();
+            button(text(format!("{}{shortcut}", config.name)).size(11))
+                .on_press(RunConfigMessage::Edit(i))
+                .padding([2, 6])
+                .width(Length::Fill)
+                .style(if self.editing == Some(i) {
+                    button::primary
+                } else {
+                    button::text
+                })
+                .into()
+        }))
+        .spacing(2);
+        view = view.push(list);
+
+        if let Some(config) = self.editing.and_then(|i| self.configs.get(i)) {
+            view = view.push(self.form(config));
+        }
+
+        container(scrollable(view))
+            .width(Length::Fixed(300.0))
+            .height(Length::Fill)
+            .style(|theme: &Theme| {
+                let palette = theme.extended_palette();
+                container::Style {
+                    background: Some(palette.background.weak.color.into()),
+                    ..Default::default()
+                }
+            })
+            .into()
+    }
+
+    /// The edited configuration's fields
+    fn form<'a>(&'a self, config: &'a RunConfig) -> Element<'a, RunConfigMessage> {
+        let field = |label: &'static str,
+                     placeholder: &'static str,
+                     value: &'a str,
+                     on_input: fn(String) -> RunConfigMessage| {
+            column![
+                text(label).size(10),
+                text_input(placeholder, value)
+                    .on_input(on_input)
+                    .size(12)
+                    .padding(4),
+            ]
+            .spacing(2)
+        };
+        let toggle = |label: String, on: bool, message: RunConfigMessage| {
+            button(text(label).size(10))
+                .on_press(message)
+                .padding([3, 6])
+                .style(if on {
+                    button::primary
+                } else {
+                    button::secondary
+                })
+        };
+
+        let modes = Row::with_children(RunMode::ALL.into_iter().map(|mode| {
+            toggle(
+                mode.to_string(),
+                config.mode == mode,
+                RunConfigMessage::SetMode(mode),
+            )
+            .into()
+        }))
+        .spacing(4);
+
+        column![
+            field(
+                "Name",
+                "Name",
+                &self.draft.name,
+                RunConfigMessage::NameChanged
+            ),
+            field(
+                "Entry file",
+                "src/main.strat",
+                &self.draft.entry,
+                RunConfigMessage::EntryChanged
+            ),
+            field(
+                "Arguments",
+                "--verbose \"two words\"",
+                &self.draft.args,
+                RunConfigMessage::ArgsChanged
+            ),
+            field(
+                "Environment",
+                "KEY=value",
+                &self.draft.env,
+                RunConfigMessage::EnvChanged
+            ),
+            field(
+                "Working directory",
+                "Project folder",
+                &self.draft.cwd,
+                RunConfigMessage::CwdChanged
+            ),
+            field(
+                "Shortcut",
+                "ctrl+shift+1",
+                &self.draft.shortcut,
+                RunConfigMessage::ShortcutChanged
+            ),
+            modes,
+            toggle("JIT".to_string(), config.jit, RunConfigMessage::ToggleJit),
+            row![
+                button(text("Run").size(11))
+                    .on_press(RunConfigMessage::Run)
+                    .padding([3, 8])
+                    .style(button::primary),
+                button(text(if self.modified { "Save*" } else { "Save" }).size(11))
+                    .on_press(RunConfigMessage::Save)
+                    .padding([3, 8])
+                    .style(button::secondary),
+                button(text("Remove").size(11))
+                    .on_press(RunConfigMessage::Remove)
+                    .padding([3, 8])
+                    .style(button::secondary),
+            ]
+            .spacing(4),
+        ]
+        .spacing(6)
+        .into()
+    }
+}
+
+/// An entry of the Run toolbar's dropdown
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunTarget {
+    /// Configuration, or `None` for the editor's file in the REPL
+    pub index: Option<usize>,
+    name: String,
+}
+
+impl RunTarget {
+    /// The dropdown's entries: the editor's file, then each configuration
+    pub fn all(configs: &[RunConfig]) -> Vec<Self> {
+        std::iter::once(Self {
+            index: None,
+            name: "Current file".to_string(),
+        })
+        .chain(configs.iter().enumerate().map(|(i, config)| Self {
+            index: Some(i),
+            name: config.name.clone(),
+        }))
+        .collect()
+    }
+}
+
+impl fmt::Display for RunTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_args() {
+        assert_eq!(
+            split_args(r#" --name "Ada Lovelace"  -v "" x"#),
+            ["--name", "Ada Lovelace", "-v", "", "x"]
+        );
+        assert!(split_args("   ").is_empty());
+        let words = ["a b", "", "c"];
+        assert_eq!(split_args(&join_args(&words)), words);
+    }
+
+    #[test]
+    fn test_program() {
+        let config = RunConfig {
+            name: "Dev".to_string(),
+            entry: PathBuf::from("src/app.strat"),
+            args: vec!["--port".to_string(), "8080".to_string()],
+            env: BTreeMap::from([("LOG".to_string(), "debug".to_string())]),
+            cwd: Some(PathBuf::from("data")),
+            mode: RunMode::InterpretAll,
+            jit: false,
+            shortcut: None,
+        };
+        let root = Path::new("/project");
+        let program = config.program(root);
+        assert_eq!(program.program, "stratum");
+        assert_eq!(
+            program.args,
+            [
+                "run",
+                &root.join("src/app.strat").display().to_string(),
+                "--interpret-all",
+                "--no-jit",
+                "--",
+                "--port",
+                "8080"
+            ]
+        );
+        assert_eq!(program.env, [("LOG".to_string(), "debug".to_string())]);
+        assert_eq!(program.cwd, root.join("data"));
+
+        let program = RunConfig::default().program(root);
+        assert_eq!(program.args.len(), 2);
+        assert_eq!(program.cwd, root);
+    }
+
+    #[test]
+    fn test_edit_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut panel = RunConfigPanel::new();
+        panel.open(dir.path().to_path_buf());
+        assert!(panel.configs().is_empty());
+
+        panel.update(RunConfigMessage::Add);
+        panel.update(RunConfigMessage::NameChanged("Test".to_string()));
+        panel.update(RunConfigMessage::ArgsChanged("a \"b c\"".to_string()));
+        panel.update(RunConfigMessage::EnvChanged("X=1 broken Y=a=b".to_string()));
+        panel.update(RunConfigMessage::ShortcutChanged(
+            "ctrl+shift+1".to_string(),
+        ));
+        panel.update(RunConfigMessage::SetMode(RunMode::CompileAll));
+        assert!(panel.is_modified());
+        assert_eq!(
+            panel.update(RunConfigMessage::Save),
+            Some(RunConfigAction::Saved(dir.path().join(RUN_CONFIG_FILE)))
+        );
+        assert!(!panel.is_modified());
+
+        let configs = load_run_configs(dir.path()).unwrap();
+        assert_eq!(configs, panel.configs());
+        assert_eq!(configs[0].args, ["a", "b c"]);
+        assert_eq!(
+            configs[0].env,
+            BTreeMap::from([
+                ("X".to_string(), "1".to_string()),
+                ("Y".to_string(), "a=b".to_string())
+            ])
+        );
+
+        let chord = KeyChord::parse("Ctrl+Shift+1").unwrap();
+        assert_eq!(panel.for_chord(&chord), Some(0));
+        assert_eq!(
+            panel.update(RunConfigMessage::Run),
+            Some(RunConfigAction::Run(0))
+        );
+    }
+
+    #[test]
+    fn test_invalid_configs_not_saved() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut panel = RunConfigPanel::new();
+        panel.open(dir.path().to_path_buf());
+        panel.update(RunConfigMessage::Add);
+        panel.update(RunConfigMessage::ShortcutChanged("shift+r".to_string()));
+        assert_eq!(panel.update(RunConfigMessage::Save), None);
+        assert!(panel.error.is_some());
+        assert!(!dir.path().join(RUN_CONFIG_FILE).exists());
+
+        // Removing the selected configuration goes back to the current file
+        panel.select(Some(0));
+        panel.update(RunConfigMessage::Remove);
+        assert_eq!(panel.selected(), None);
+        assert!(panel.configs().is_empty());
+    }
+
+    #[test]
+    fn test_missing_fields_default() {
+        let file: RunConfigFile = toml::from_str("[[config]]\nname = \"Main\"\n").unwrap();
+        assert_eq!(file.configs[0].entry, Path::new("src/main.strat"));
+        assert!(file.configs[0].jit);
+        assert_eq!(
+            RunTarget::all(&file.configs)
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            ["Current file", "Main"]
+        );
+    }
+}
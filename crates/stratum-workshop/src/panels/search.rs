@@ -0,0 +1,688 @@
+//! Search panel
+//!
+//! Searches every file under the opened folder for text or a regular
+//! expression, lists the matches grouped by file, and replaces them across
+//! files. Files open in the editor are searched and edited through their
+//! buffer, so unsaved changes are neither missed nor overwritten.
+
+use iced::widget::{button, column, container, row, rule, scrollable, text, text_input, Column};
+use iced::{Color, Element, Font, Length, Theme};
+use regex::{NoExpand, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Most matches listed; replacing still covers every match
+const MAX_MATCHES: usize = 2000;
+/// Directories never searched, besides hidden ones
+const SKIPPED_DIRS: &[&str] = &["target", "node_modules"];
+
+/// Messages for the search panel
+#[derive(Debug, Clone)]
+pub enum SearchMessage {
+    /// Search text changed
+    QueryChanged(String),
+    /// Replacement text changed
+    ReplacementChanged(String),
+    /// Toggle regular expression search
+    ToggleRegex,
+    /// Toggle case-sensitive search
+    ToggleCase,
+    /// Run the search
+    Search,
+    /// Replace every match in every file
+    ReplaceAll,
+    /// Undo the last replace
+    Undo,
+    /// Show or hide the matches of a file
+    ToggleFile(PathBuf),
+    /// Open a file at a line (1-based)
+    OpenMatch { path: PathBuf, line: usize },
+    /// Close the panel
+    Close,
+}
+
+/// Actions returned from update that Workshop needs to handle
+#[derive(Debug, Clone, PartialEq)]
+pub enum SearchAction {
+    /// Search the folder
+    Search(SearchQuery),
+    /// Replace every match of the query in the folder
+    Replace {
+        query: SearchQuery,
+        replacement: String,
+    },
+    /// Revert these edits
+    Undo(Vec<FileEdit>),
+    /// Open a file at a line (1-based)
+    Open { path: PathBuf, line: usize },
+    /// Close the panel
+    Close,
+}
+
+/// What to search for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchQuery {
+    pub pattern: String,
+    /// Whether the pattern is a regular expression rather than plain text
+    pub regex: bool,
+    pub case_sensitive: bool,
+}
+
+impl SearchQuery {
+    /// Compile the query into a regular expression
+    pub fn compile(&self) -> Result<Regex, regex::Error> {
+        let pattern = if self.regex {
+            self.pattern.clone()
+        } else {
+            regex::escape(&self.pattern)
+        };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(!self.case_sensitive)
+            .multi_line(true)
+            .build()
+    }
+}
+
+/// A line containing a match
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineMatch {
+    /// Line number (1-based)
+    pub line: usize,
+    /// The line, without leading whitespace
+    pub preview: String,
+    /// Byte range of the first match on the line within the preview
+    pub start: usize,
+    pub end: usize,
+}
+
+/// The matches in one file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileMatches {
+    pub path: PathBuf,
+    pub matches: Vec<LineMatch>,
+}
+
+/// The result of a search
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchResults {
+    pub files: Vec<FileMatches>,
+    /// Whether matches past [`MAX_MATCHES`] were left out
+    pub truncated: bool,
+}
+
+impl SearchResults {
+    /// Number of matching lines listed
+    pub fn match_count(&self) -> usize {
+        self.files.iter().map(|f| f.matches.len()).sum()
+    }
+}
+
+/// A change to the contents of one file, kept so it can be undone
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileEdit {
+    pub path: PathBuf,
+    pub before: String,
+    pub after: String,
+}
+
+/// Search every file under a folder. `buffers` holds the contents of files
+/// open in the editor, which are searched instead of the files on disk.
+pub fn search_folder(root: &Path, regex: &Regex, buffers: &[(PathBuf, String)]) -> SearchResults {
+    let mut results = SearchResults::default();
+    let mut count = 0;
+    for (path, content) in folder_contents(root, buffers) {
+        let mut matches = search_text(&content, regex);
+        if matches.is_empty() {
+            continue;
+        }
+        if count + matches.len() > MAX_MATCHES {
+            matches.truncate(MAX_MATCHES - count);
+            results.truncated = true;
+        }
+        count += matches.len();
+        if !matches.is_empty() {
+            results.files.push(FileMatches { path, matches });
+        }
+        if results.truncated {
+            break;
+        }
+    }
+    results
+}
+
+/// Find the lines of a text that match
+pub fn search_text(content: &str, regex: &Regex) -> Vec<LineMatch> {
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let found = regex.find(line)?;
+            let preview = line.trim_start();
+            let indent = line.len() - preview.len();
+            // A match in the leading whitespace starts the preview
+            let start = found.start().saturating_sub(indent);
+            let end = found.end().saturating_sub(indent).max(start);
+            Some(LineMatch {
+                line: index + 1,
+                preview: preview.to_string(),
+                start,
+                end,
+            })
+        })
+        .collect()
+}
+
+/// Compute the edits that replace every match under a folder. With a regular
+/// expression the replacement may refer to groups as `$1` or `$name`.
+pub fn plan_replace(
+    root: &Path,
+    query: &SearchQuery,
+    replacement: &str,
+    buffers: &[(PathBuf, String)],
+) -> Result<Vec<FileEdit>, regex::Error> {
+    let regex = query.compile()?;
+    Ok(folder_contents(root, buffers)
+        .filter_map(|(path, before)| {
+            let after = if query.regex {
+                regex.replace_all(&before, replacement)
+            } else {
+                regex.replace_all(&before, NoExpand(replacement))
+            };
+            if after == before {
+                return None;
+            }
+            let after = after.into_owned();
+            Some(FileEdit {
+                path,
+                before,
+                after,
+            })
+        })
+        .collect())
+}
+
+/// The contents of every text file under a folder, in path order, taking
+/// open files from their buffers
+fn folder_contents<'a>(
+    root: &Path,
+    buffers: &'a [(PathBuf, String)],
+) -> impl Iterator<Item = (PathBuf, String)> + 'a {
+    let mut files = Vec::new();
+    collect_files(root, &mut files);
+    files.sort();
+    files.into_iter().filter_map(move |path| {
+        let buffer = buffers.iter().find(|(open, _)| *open == path);
+        let content = match buffer {
+            Some((_, buffer)) => buffer.clone(),
+            // Files that are not UTF-8 text are skipped
+            None => std::fs::read_to_string(&path).ok()?,
+        };
+        Some((path, content))
+    })
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            if !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_ref()) {
+                collect_files(&path, files);
+            }
+        } else if file_type.is_file() {
+            files.push(path);
+        }
+    }
+}
+
+/// Search panel
+#[derive(Debug, Default)]
+pub struct SearchPanel {
+    query: String,
+    replacement: String,
+    regex: bool,
+    case_sensitive: bool,
+    /// Folder of the last search, for showing relative paths
+    root: Option<PathBuf>,
+    results: SearchResults,
+    error: Option<String>,
+    /// Files whose matches are hidden
+    collapsed: HashSet<PathBuf>,
+    /// Edits of each replace, most recent last
+    undo_stack: Vec<Vec<FileEdit>>,
+}
+
+impl SearchPanel {
+    /// Create an empty search panel
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The query as currently entered
+    pub fn query(&self) -> SearchQuery {
+        SearchQuery {
+            pattern: self.query.clone(),
+            regex: self.regex,
+            case_sensitive: self.case_sensitive,
+        }
+    }
+
+    /// The result of the last search
+    pub fn results(&self) -> &SearchResults {
+        &self.results
+    }
+
+    /// The error of the last search or replace, if any
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Whether there is a replace to undo
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    /// Files whose matches are hidden, in order
+    pub fn collapsed_files(&self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = self.collapsed.iter().cloned().collect();
+        files.sort();
+        files
+    }
+
+    /// Enter a query, as restored from a session, and search with it
+    pub fn set_query(&mut self, query: SearchQuery) -> Option<SearchAction> {
+        self.query = query.pattern;
+        self.regex = query.regex;
+        self.case_sensitive = query.case_sensitive;
+        self.search()
+    }
+
+    /// Hide the matches of files, as restored from a session
+    pub fn collapse(&mut self, files: impl IntoIterator<Item = PathBuf>) {
+        self.collapsed.extend(files);
+    }
+
+    /// Show the result of a search of a folder
+    pub fn set_results(&mut self, root: PathBuf, results: SearchResults) {
+        self.root = Some(root);
+        self.results = results;
+        self.error = None;
+        self.collapsed.clear();
+    }
+
+    /// Show an error in place of results
+    pub fn set_error(&mut self, error: impl Into<String>) {
+        self.results = SearchResults::default();
+        self.error = Some(error.into());
+    }
+
+    /// Remember the edits of a replace so it can be undone
+    pub fn push_undo(&mut self, edits: Vec<FileEdit>) {
+        if !edits.is_empty() {
+            self.undo_stack.push(edits);
+        }
+    }
+
+    /// Handle a message
+    pub fn update(&mut self, message: SearchMessage) -> Option<SearchAction> {
+        match message {
+            SearchMessage::QueryChanged(query) => {
+                self.query = query;
+                None
+            }
+            SearchMessage::ReplacementChanged(replacement) => {
+                self.replacement = replacement;
+                None
+            }
+            SearchMessage::ToggleRegex => {
+                self.regex = !self.regex;
+                self.search()
+            }
+            SearchMessage::ToggleCase => {
+                self.case_sensitive = !self.case_sensitive;
+                self.search()
+            }
+            SearchMessage::Search => self.search(),
+            SearchMessage::ReplaceAll => {
+                let query = self.query();
+                if query.pattern.is_empty() {
+                    return None;
+                }
+                Some(SearchAction::Replace {
+                    query,
+                    replacement: self.replacement.clone(),
+                })
+            }
+            SearchMessage::Undo => self.undo_stack.pop().map(SearchAction::Undo),
+            SearchMessage::ToggleFile(path) => {
+                if !self.collapsed.remove(&path) {
+                    self.collapsed.insert(path);
+                }
+                None
+            }
+            SearchMessage::OpenMatch { path, line } => Some(SearchAction::Open { path, line }),
+            SearchMessage::Close => Some(SearchAction::Close),
+        }
+    }
+
+    fn search(&mut self) -> Option<SearchAction> {
+        if self.query.is_empty() {
+            self.results = SearchResults::default();
+            self.error = None;
+            return None;
+        }
+        Some(SearchAction::Search(self.query()))
+    }
+
+    /// Render the panel
+    pub fn view(&self) -> Element<'_, SearchMessage> {
+        let toggle = |label: &'static str, on: bool, message: SearchMessage| {
+            button(text(label).size(11).font(Font::MONOSPACE))
+                .on_press(message)
+                .padding([3, 6])
+                .style(if on { button::primary } else { button::text })
+        };
+
+        let header = row![
+            text("Search").size(12),
+            iced::widget::Space::new().width(Length::Fill),
+            button(text("x").size(10))
+                .on_press(SearchMessage::Close)
+                .padding([2, 6])
+                .style(button::text),
+        ]
+        .align_y(iced::Alignment::Center);
+
+        let search_row = row![
+            text_input("Search", &self.query)
+                .on_input(SearchMessage::QueryChanged)
+                .on_submit(SearchMessage::Search)
+                .size(12)
+                .padding(4),
+            toggle("Aa", self.case_sensitive, SearchMessage::ToggleCase),
+            toggle(".*", self.regex, SearchMessage::ToggleRegex),
+        ]
+        .spacing(4)
+        .align_y(iced::Alignment::Center);
+
+        let replace_row = row![
+            text_input("Replace", &self.replacement)
+                .on_input(SearchMessage::ReplacementChanged)
+                .size(12)
+                .padding(4),
+            button(text("All").size(11))
+                .on_press_maybe((!self.query.is_empty()).then_some(SearchMessage::ReplaceAll))
+                .padding([3, 6])
+                .style(button::secondary),
+            button(text("Undo").size(11))
+                .on_press_maybe(self.can_undo().then_some(SearchMessage::Undo))
+                .padding([3, 6])
+                .style(button::secondary),
+        ]
+        .spacing(4)
+        .align_y(iced::Alignment::Center);
+
+        let summary = if let Some(error) = &self.error {
+            text(error).size(11).color(Color::from_rgb(0.9, 0.4, 0.4))
+        } else if self.root.is_some() {
+            let more = if self.results.truncated {
+                " (showing the first)"
+            } else {
+                ""
+            };
+            text(format!(
+                "{} matches in {} files{more}",
+                self.results.match_count(),
+                self.results.files.len()
+            ))
+            .size(11)
+            .color(Color::from_rgb(0.6, 0.6, 0.6))
+        } else {
+            text("")
+        };
+
+        container(column![
+            column![header, search_row, replace_row, summary]
+                .spacing(6)
+                .padding(8),
+            rule::horizontal(1),
+            scrollable(self.results_view()).height(Length::Fill),
+        ])
+        .width(Length::Fixed(300.0))
+        .height(Length::Fill)
+        .style(|theme: &Theme| {
+            let palette = theme.extended_palette();
+            container::Style {
+                background: Some(palette.background.weak.color.into()),
+                ..Default::default()
+            }
+        })
+        .into()
+    }
+
+    /// Matches grouped by file, each opening its line when clicked
+    fn results_view(&self) -> Element<'_, SearchMessage> {
+        let mut list = Column::new().spacing(1).padding(4);
+        for file in &self.results.files {
+            let collapsed = self.collapsed.contains(&file.path);
+            let name = self
+                .root
+                .as_deref()
+                .and_then(|root| file.path.strip_prefix(root).ok())
+                .unwrap_or(&file.path)
+                .display()
+                .to_string();
+            let marker = if collapsed { ">" } else { "v" };
+            list = list.push(
+                button(
+                    text(format!("{marker} {name} ({})", file.matches.len()))
+                        .size(11)
+                        .font(Font::MONOSPACE),
+                )
+                .on_press(SearchMessage::ToggleFile(file.path.clone()))
+                .padding([2, 4])
+                .width(Length::Fill)
+                .style(button::text),
+            );
+            if collapsed {
+                continue;
+            }
+            for m in &file.matches {
+                let preview = row![
+                    text(format!("{:>4}  ", m.line))
+                        .size(11)
+                        .font(Font::MONOSPACE)
+                        .color(Color::from_rgb(0.5, 0.5, 0.5)),
+                    text(&m.preview[..m.start]).size(11).font(Font::MONOSPACE),
+                    text(&m.preview[m.start..m.end])
+                        .size(11)
+                        .font(Font::MONOSPACE)
+                        .color(Color::from_rgb(1.0, 0.8, 0.3)),
+                    text(&m.preview[m.end..]).size(11).font(Font::MONOSPACE),
+                ];
+                list = list.push(
+                    button(preview)
+                        .on_press(SearchMessage::OpenMatch {
+                            path: file.path.clone(),
+                            line: m.line,
+                        })
+                        .padding([1, 12])
+                        .width(Length::Fill)
+                        .style(button::text),
+                );
+            }
+        }
+        list.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(pattern: &str, regex: bool, case_sensitive: bool) -> SearchQuery {
+        SearchQuery {
+            pattern: pattern.to_string(),
+            regex,
+            case_sensitive,
+        }
+    }
+
+    fn folder() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::write(root.join("main.strat"), "fx main() {\n    total(1)\n}\n").unwrap();
+        std::fs::write(
+            root.join("src/util.strat"),
+            "fx total(n) {\n    n + Total\n}\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("target/out.strat"), "total").unwrap();
+        std::fs::write(root.join(".git/HEAD"), "total").unwrap();
+        std::fs::write(root.join("image.bin"), [0xff, 0xfe, b't']).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_search_text() {
+        let regex = query("total", false, true).compile().unwrap();
+        let matches = search_text("fx main() {\n    total(1) + total(2)\n}", &regex);
+        assert_eq!(
+            matches,
+            [LineMatch {
+                line: 2,
+                preview: "total(1) + total(2)".to_string(),
+                start: 0,
+                end: 5,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_query_compile() {
+        let plain = query("a.b", false, true).compile().unwrap();
+        assert!(plain.is_match("a.b") && !plain.is_match("axb"));
+        let regex = query("a.b", true, true).compile().unwrap();
+        assert!(regex.is_match("axb"));
+        let insensitive = query("ABC", false, false).compile().unwrap();
+        assert!(insensitive.is_match("abc"));
+        assert!(query("(", true, true).compile().is_err());
+    }
+
+    #[test]
+    fn test_search_folder() {
+        let dir = folder();
+        let root = dir.path();
+        let regex = query("total", false, false).compile().unwrap();
+        let results = search_folder(root, &regex, &[]);
+        let found: Vec<_> = results
+            .files
+            .iter()
+            .map(|f| (f.path.strip_prefix(root).unwrap(), f.matches.len()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (Path::new("main.strat"), 1),
+                (Path::new("src/util.strat"), 2)
+            ]
+        );
+        assert!(!results.truncated);
+
+        // An open buffer is searched instead of the file on disk
+        let buffers = [(root.join("main.strat"), "fx main() {}".to_string())];
+        let results = search_folder(root, &regex, &buffers);
+        assert_eq!(results.files.len(), 1);
+        assert_eq!(results.match_count(), 2);
+    }
+
+    #[test]
+    fn test_search_folder_truncates() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("many.txt"), "x\n".repeat(MAX_MATCHES + 10)).unwrap();
+        let regex = query("x", false, true).compile().unwrap();
+        let results = search_folder(dir.path(), &regex, &[]);
+        assert_eq!(results.match_count(), MAX_MATCHES);
+        assert!(results.truncated);
+    }
+
+    #[test]
+    fn test_plan_replace() {
+        let dir = folder();
+        let root = dir.path();
+        let edits = plan_replace(root, &query("total", false, true), "sum$1", &[]).unwrap();
+        assert_eq!(edits.len(), 2);
+        assert_eq!(edits[0].after, "fx main() {\n    sum$1(1)\n}\n");
+        assert_eq!(edits[1].after, "fx sum$1(n) {\n    n + Total\n}\n");
+
+        let buffers = [(root.join("main.strat"), "let total = 1".to_string())];
+        let edits =
+            plan_replace(root, &query(r"(t)otal", true, false), "${1}ally", &buffers).unwrap();
+        assert_eq!(edits[0].before, "let total = 1");
+        assert_eq!(edits[0].after, "let tally = 1");
+        assert_eq!(edits[1].after, "fx tally(n) {\n    n + Tally\n}\n");
+
+        assert!(plan_replace(root, &query("(", true, true), "", &[]).is_err());
+    }
+
+    #[test]
+    fn test_panel_actions() {
+        let mut panel = SearchPanel::new();
+        assert_eq!(panel.update(SearchMessage::Search), None);
+        assert_eq!(panel.update(SearchMessage::ReplaceAll), None);
+
+        panel.update(SearchMessage::QueryChanged("total".to_string()));
+        panel.update(SearchMessage::ReplacementChanged("sum".to_string()));
+        assert_eq!(
+            panel.update(SearchMessage::ToggleRegex),
+            Some(SearchAction::Search(query("total", true, false)))
+        );
+        assert_eq!(
+            panel.update(SearchMessage::ReplaceAll),
+            Some(SearchAction::Replace {
+                query: query("total", true, false),
+                replacement: "sum".to_string(),
+            })
+        );
+
+        assert_eq!(panel.update(SearchMessage::Undo), None);
+        let edit = FileEdit {
+            path: PathBuf::from("a.strat"),
+            before: "total".to_string(),
+            after: "sum".to_string(),
+        };
+        panel.push_undo(Vec::new());
+        panel.push_undo(vec![edit.clone()]);
+        assert!(panel.can_undo());
+        assert_eq!(
+            panel.update(SearchMessage::Undo),
+            Some(SearchAction::Undo(vec![edit]))
+        );
+        assert!(!panel.can_undo());
+    }
+
+    #[test]
+    fn test_restore_query() {
+        let mut panel = SearchPanel::new();
+        assert_eq!(
+            panel.set_query(query("total", false, true)),
+            Some(SearchAction::Search(query("total", false, true)))
+        );
+        assert_eq!(panel.query(), query("total", false, true));
+
+        panel.collapse([PathBuf::from("b.strat"), PathBuf::from("a.strat")]);
+        panel.update(SearchMessage::ToggleFile(PathBuf::from("b.strat")));
+        assert_eq!(panel.collapsed_files(), [PathBuf::from("a.strat")]);
+    }
+}
@@ -0,0 +1,680 @@
+//! Source control panel
+//!
+//! Shows the branch and the changed files of the git repository containing
+//! the opened folder, and stages, unstages and commits them. It also diffs
+//! editor buffers against `HEAD` for the change markers in the editor gutter.
+
+use git2::{ErrorCode, IndexAddOption, Repository, Status, StatusOptions};
+use iced::widget::{
+    button, column, container, row, rule, scrollable, text, text_input, Column, Space,
+};
+use iced::{Color, Element, Font, Length, Theme};
+use std::path::{Path, PathBuf};
+
+/// Messages for the source control panel
+#[derive(Debug, Clone)]
+pub enum SourceControlMessage {
+    /// Read the repository's status again
+    Refresh,
+    /// Stage a file (relative to the repository root)
+    Stage(PathBuf),
+    /// Unstage a file (relative to the repository root)
+    Unstage(PathBuf),
+    /// Stage every changed file
+    StageAll,
+    /// Commit message changed
+    MessageChanged(String),
+    /// Commit the staged files
+    Commit,
+    /// Open a file (relative to the repository root) in the editor
+    Open(PathBuf),
+    /// Close the panel
+    Close,
+}
+
+/// Actions returned from update that Workshop needs to handle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceControlAction {
+    /// Open a file in the editor
+    Open(PathBuf),
+    /// The repository changed, so change markers need updating
+    Changed(String),
+    /// Close the panel
+    Close,
+}
+
+/// How a file changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileStatus {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+    Untracked,
+    Conflicted,
+}
+
+impl FileStatus {
+    /// One-letter code, as `git status --short` shows
+    pub fn code(self) -> char {
+        match self {
+            FileStatus::Added => 'A',
+            FileStatus::Modified => 'M',
+            FileStatus::Deleted => 'D',
+            FileStatus::Renamed => 'R',
+            FileStatus::Untracked => 'U',
+            FileStatus::Conflicted => '!',
+        }
+    }
+}
+
+/// A changed file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangedFile {
+    /// Path relative to the repository root
+    pub path: PathBuf,
+    pub status: FileStatus,
+    /// Whether the change is staged rather than only in the working tree
+    pub staged: bool,
+}
+
+/// The state of a repository
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepoStatus {
+    /// Current branch, or `None` when `HEAD` is detached
+    pub branch: Option<String>,
+    /// Changed files, staged changes first. A file with staged and unstaged
+    /// changes appears twice.
+    pub files: Vec<ChangedFile>,
+}
+
+/// How a line of a buffer differs from `HEAD`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineChange {
+    Added,
+    Modified,
+    /// Lines were removed after this one
+    Removed,
+}
+
+/// Read the branch and changed files of a repository
+pub fn repo_status(repo: &Repository) -> Result<RepoStatus, git2::Error> {
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .renames_head_to_index(true);
+    let mut staged = Vec::new();
+    let mut unstaged = Vec::new();
+    for entry in repo.statuses(Some(&mut options))?.iter() {
+        let Some(path) = entry.path() else {
+            continue;
+        };
+        let path = PathBuf::from(path);
+        let status = entry.status();
+        if status.is_conflicted() {
+            unstaged.push(ChangedFile {
+                path,
+                status: FileStatus::Conflicted,
+                staged: false,
+            });
+            continue;
+        }
+        if let Some(kind) = index_status(status) {
+            staged.push(ChangedFile {
+                path: path.clone(),
+                status: kind,
+                staged: true,
+            });
+        }
+        if let Some(kind) = worktree_status(status) {
+            unstaged.push(ChangedFile {
+                path,
+                status: kind,
+                staged: false,
+            });
+        }
+    }
+    staged.extend(unstaged);
+    Ok(RepoStatus {
+        branch: branch(repo),
+        files: staged,
+    })
+}
+
+fn index_status(status: Status) -> Option<FileStatus> {
+    if status.is_index_new() {
+        Some(FileStatus::Added)
+    } else if status.is_index_modified() || status.is_index_typechange() {
+        Some(FileStatus::Modified)
+    } else if status.is_index_deleted() {
+        Some(FileStatus::Deleted)
+    } else if status.is_index_renamed() {
+        Some(FileStatus::Renamed)
+    } else {
+        None
+    }
+}
+
+fn worktree_status(status: Status) -> Option<FileStatus> {
+    if status.is_wt_new() {
+        Some(FileStatus::Untracked)
+    } else if status.is_wt_modified() || status.is_wt_typechange() {
+        Some(FileStatus::Modified)
+    } else if status.is_wt_deleted() {
+        Some(FileStatus::Deleted)
+    } else if status.is_wt_renamed() {
+        Some(FileStatus::Renamed)
+    } else {
+        None
+    }
+}
+
+/// The current branch, including one without commits yet
+fn branch(repo: &Repository) -> Option<String> {
+    let head = repo.find_reference("HEAD").ok()?;
+    let target = head.symbolic_target()?;
+    target.strip_prefix("refs/heads/").map(str::to_string)
+}
+
+/// Stage a file, or its removal if it was deleted
+pub fn stage(repo: &Repository, path: &Path) -> Result<(), git2::Error> {
+    let mut index = repo.index()?;
+    let exists = repo
+        .workdir()
+        .is_some_and(|workdir| workdir.join(path).exists());
+    if exists {
+        index.add_path(path)?;
+    } else {
+        index.remove_path(path)?;
+    }
+    index.write()
+}
+
+/// Stage every changed file, including new and deleted ones
+pub fn stage_all(repo: &Repository) -> Result<(), git2::Error> {
+    let mut index = repo.index()?;
+    index.add_all(["*"], IndexAddOption::DEFAULT, None)?;
+    index.update_all(["*"], None)?;
+    index.write()
+}
+
+/// Unstage a file, leaving the working tree as it is
+pub fn unstage(repo: &Repository, path: &Path) -> Result<(), git2::Error> {
+    match repo.head() {
+        Ok(head) => {
+            let commit = head.peel_to_commit()?;
+            repo.reset_default(Some(commit.as_object()), [path])
+        }
+        // Before the first commit, unstaging removes the file from the index
+        Err(e) if is_unborn(&e) => {
+            let mut index = repo.index()?;
+            index.remove_path(path)?;
+            index.write()
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Commit the staged files with the user's configured signature. Returns
+/// the short id of the new commit.
+pub fn commit(repo: &Repository, message: &str) -> Result<String, git2::Error> {
+    let mut index = repo.index()?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let parent = match repo.head() {
+        Ok(head) => Some(head.peel_to_commit()?),
+        Err(e) if is_unborn(&e) => None,
+        Err(e) => return Err(e),
+    };
+    if let Some(parent) = &parent {
+        if parent.tree_id() == tree.id() {
+            return Err(git2::Error::from_str("nothing staged to commit"));
+        }
+    }
+    let signature = repo.signature()?;
+    let parents: Vec<_> = parent.iter().collect();
+    let id = repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &parents,
+    )?;
+    let short = repo.find_object(id, None)?.short_id()?;
+    Ok(short.as_str().unwrap_or_default().to_string())
+}
+
+fn is_unborn(error: &git2::Error) -> bool {
+    matches!(error.code(), ErrorCode::UnbornBranch | ErrorCode::NotFound)
+}
+
+/// Compare a buffer with its file's contents at `HEAD`. `path` is relative
+/// to the repository root. Every line of a file not in `HEAD` is added.
+/// Returns the changed lines (1-based), in order.
+pub fn line_changes(
+    repo: &Repository,
+    path: &Path,
+    content: &str,
+) -> Result<Vec<(usize, LineChange)>, git2::Error> {
+    let head_blob = match repo.head() {
+        Ok(head) => match head.peel_to_tree()?.get_path(path) {
+            Ok(entry) => Some(entry.to_object(repo)?.peel_to_blob()?),
+            Err(e) if e.code() == ErrorCode::NotFound => None,
+            Err(e) => return Err(e),
+        },
+        Err(e) if is_unborn(&e) => None,
+        Err(e) => return Err(e),
+    };
+    let Some(blob) = head_blob else {
+        return Ok((1..=content.lines().count())
+            .map(|line| (line, LineChange::Added))
+            .collect());
+    };
+
+    let patch = git2::Patch::from_buffers(
+        blob.content(),
+        Some(path),
+        content.as_bytes(),
+        Some(path),
+        None,
+    )?;
+    let mut changes = Vec::new();
+    for hunk in 0..patch.num_hunks() {
+        // Removed lines not yet paired with added ones
+        let mut removed = 0;
+        let mut last_line = 0;
+        for index in 0..patch.num_lines_in_hunk(hunk)? {
+            let line = patch.line_in_hunk(hunk, index)?;
+            match line.origin() {
+                '-' => removed += 1,
+                '+' => {
+                    let new_line = line.new_lineno().unwrap_or_default() as usize;
+                    let change = if removed > 0 {
+                        removed -= 1;
+                        LineChange::Modified
+                    } else {
+                        LineChange::Added
+                    };
+                    changes.push((new_line, change));
+                    last_line = new_line;
+                }
+                _ => {
+                    let new_line = line.new_lineno().unwrap_or_default() as usize;
+                    if removed > 0 {
+                        changes.push((new_line.saturating_sub(1).max(1), LineChange::Removed));
+                        removed = 0;
+                    }
+                    last_line = new_line;
+                }
+            }
+        }
+        if removed > 0 {
+            changes.push((last_line.max(1), LineChange::Removed));
+        }
+    }
+    changes.sort_by_key(|(line, _)| *line);
+    changes.dedup_by_key(|(line, _)| *line);
+    Ok(changes)
+}
+
+/// Source control panel
+pub struct SourceControlPanel {
+    repo: Repository,
+    status: RepoStatus,
+    message: String,
+    error: Option<String>,
+}
+
+impl std::fmt::Debug for SourceControlPanel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SourceControlPanel")
+            .field("root", &self.root())
+            .field("status", &self.status)
+            .field("message", &self.message)
+            .field("error", &self.error)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SourceControlPanel {
+    /// Open the repository containing a folder, if any
+    pub fn open(folder: &Path) -> Option<Self> {
+        let repo = Repository::discover(folder).ok()?;
+        // Bare repositories have no files to show
+        repo.workdir()?;
+        let mut panel = Self {
+            repo,
+            status: RepoStatus::default(),
+            message: String::new(),
+            error: None,
+        };
+        panel.refresh();
+        Some(panel)
+    }
+
+    /// The repository's root folder
+    pub fn root(&self) -> &Path {
+        self.repo.workdir().unwrap_or_else(|| self.repo.path())
+    }
+
+    /// The current branch, or `None` when `HEAD` is detached
+    pub fn branch(&self) -> Option<&str> {
+        self.status.branch.as_deref()
+    }
+
+    /// The repository's state as last read
+    pub fn status(&self) -> &RepoStatus {
+        &self.status
+    }
+
+    /// The error of the last operation, if any
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Read the repository's status again
+    pub fn refresh(&mut self) {
+        match repo_status(&self.repo) {
+            Ok(status) => {
+                self.status = status;
+                self.error = None;
+            }
+            Err(e) => self.error = Some(e.message().to_string()),
+        }
+    }
+
+    /// Change markers of a buffer of a file in the repository; none for
+    /// files outside it
+    pub fn line_changes(&self, path: &Path, content: &str) -> Vec<(usize, LineChange)> {
+        path.strip_prefix(self.root())
+            .ok()
+            .and_then(|relative| line_changes(&self.repo, relative, content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Handle a message
+    pub fn update(&mut self, message: SourceControlMessage) -> Option<SourceControlAction> {
+        let result = match message {
+            SourceControlMessage::Refresh => Ok(()),
+            SourceControlMessage::Stage(path) => stage(&self.repo, &path),
+            SourceControlMessage::Unstage(path) => unstage(&self.repo, &path),
+            SourceControlMessage::StageAll => stage_all(&self.repo),
+            SourceControlMessage::MessageChanged(message) => {
+                self.message = message;
+                return None;
+            }
+            SourceControlMessage::Commit => {
+                let message = self.message.trim();
+                if message.is_empty() {
+                    self.error = Some("Enter a commit message".to_string());
+                    return None;
+                }
+                match commit(&self.repo, message) {
+                    Ok(id) => {
+                        self.message.clear();
+                        self.refresh();
+                        return Some(SourceControlAction::Changed(format!("Committed {id}")));
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            SourceControlMessage::Open(path) => {
+                return Some(SourceControlAction::Open(self.root().join(path)));
+            }
+            SourceControlMessage::Close => return Some(SourceControlAction::Close),
+        };
+        match result {
+            Ok(()) => {
+                self.refresh();
+                None
+            }
+            Err(e) => {
+                self.error = Some(e.message().to_string());
+                None
+            }
+        }
+    }
+
+    /// Render the panel
+    pub fn view(&self) -> Element<'_, SourceControlMessage> {
+        let branch = self.branch().unwrap_or("(detached)");
+        let header = row![
+            text(format!("Source Control - {branch}")).size(12),
+            Space::new().width(Length::Fill),
+            button(text("Refresh").size(10))
+                .on_press(SourceControlMessage::Refresh)
+                .padding([2, 6])
+                .style(button::text),
+            button(text("x").size(10))
+                .on_press(SourceControlMessage::Close)
+                .padding([2, 6])
+                .style(button::text),
+        ]
+        .align_y(iced::Alignment::Center);
+
+        let has_staged = self.status.files.iter().any(|f| f.staged);
+        let commit_row = row![
+            text_input("Commit message", &self.message)
+                .on_input(SourceControlMessage::MessageChanged)
+                .on_submit(SourceControlMessage::Commit)
+                .size(12)
+                .padding(4),
+            button(text("Commit").size(11))
+                .on_press_maybe(has_staged.then_some(SourceControlMessage::Commit))
+                .padding([3, 6])
+                .style(button::primary),
+        ]
+        .spacing(4)
+        .align_y(iced::Alignment::Center);
+
+        let mut top = column![header, commit_row].spacing(6).padding(8);
+        if let Some(error) = &self.error {
+            top = top.push(text(error).size(11).color(Color::from_rgb(0.9, 0.4, 0.4)));
+        }
+
+        let mut files = Column::new().spacing(1).padding(4);
+        for (title, staged) in [("Staged", true), ("Changes", false)] {
+            let group: Vec<_> = self
+                .status
+                .files
+                .iter()
+                .filter(|f| f.staged == staged)
+                .collect();
+            let mut heading = row![text(format!("{title} ({})", group.len())).size(11)]
+                .align_y(iced::Alignment::Center);
+            if !staged && !group.is_empty() {
+                heading = heading.push(Space::new().width(Length::Fill)).push(
+                    button(text("Stage all").size(10))
+                        .on_press(SourceControlMessage::StageAll)
+                        .padding([1, 6])
+                        .style(button::text),
+                );
+            }
+            files = files.push(heading);
+            for file in group {
+                files = files.push(self.file_row(file));
+            }
+        }
+
+        container(column![
+            top,
+            rule::horizontal(1),
+            scrollable(files).height(Length::Fill),
+        ])
+        .width(Length::Fixed(280.0))
+        .height(Length::Fill)
+        .style(|theme: &Theme| {
+            let palette = theme.extended_palette();
+            container::Style {
+                background: Some(palette.background.weak.color.into()),
+                ..Default::default()
+            }
+        })
+        .into()
+    }
+
+    fn file_row<'a>(&self, file: &'a ChangedFile) -> Element<'a, SourceControlMessage> {
+        let color = match file.status {
+            FileStatus::Added | FileStatus::Untracked => Color::from_rgb(0.4, 0.8, 0.4),
+            FileStatus::Deleted | FileStatus::Conflicted => Color::from_rgb(0.9, 0.4, 0.4),
+            FileStatus::Modified | FileStatus::Renamed => Color::from_rgb(0.5, 0.7, 1.0),
+        };
+        let (label, message) = if file.staged {
+            ("-", SourceControlMessage::Unstage(file.path.clone()))
+        } else {
+            ("+", SourceControlMessage::Stage(file.path.clone()))
+        };
+        row![
+            button(
+                text(file.path.display().to_string())
+                    .size(11)
+                    .font(Font::MONOSPACE)
+            )
+            .on_press(SourceControlMessage::Open(file.path.clone()))
+            .padding([1, 8])
+            .width(Length::Fill)
+            .style(button::text),
+            text(file.status.code().to_string())
+                .size(11)
+                .font(Font::MONOSPACE)
+                .color(color),
+            button(text(label).size(11).font(Font::MONOSPACE))
+                .on_press(message)
+                .padding([1, 6])
+                .style(button::text),
+        ]
+        .align_y(iced::Alignment::Center)
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A repository with `a.strat` committed, and a configured signature
+    fn repository() -> (tempfile::TempDir, Repository) {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        std::fs::write(dir.path().join("a.strat"), "a\nb\nc\n").unwrap();
+        stage(&repo, Path::new("a.strat")).unwrap();
+        commit(&repo, "Initial commit").unwrap();
+        (dir, repo)
+    }
+
+    fn changed(status: &RepoStatus) -> Vec<(&str, FileStatus, bool)> {
+        status
+            .files
+            .iter()
+            .map(|f| (f.path.to_str().unwrap(), f.status, f.staged))
+            .collect()
+    }
+
+    #[test]
+    fn test_status() {
+        let (dir, repo) = repository();
+        let status = repo_status(&repo).unwrap();
+        assert!(status.files.is_empty());
+        assert!(status.branch.is_some());
+
+        std::fs::write(dir.path().join("a.strat"), "a\n").unwrap();
+        std::fs::write(dir.path().join("b.strat"), "b\n").unwrap();
+        let status = repo_status(&repo).unwrap();
+        assert_eq!(
+            changed(&status),
+            [
+                ("a.strat", FileStatus::Modified, false),
+                ("b.strat", FileStatus::Untracked, false)
+            ]
+        );
+
+        stage(&repo, Path::new("b.strat")).unwrap();
+        let status = repo_status(&repo).unwrap();
+        assert_eq!(
+            changed(&status),
+            [
+                ("b.strat", FileStatus::Added, true),
+                ("a.strat", FileStatus::Modified, false)
+            ]
+        );
+
+        unstage(&repo, Path::new("b.strat")).unwrap();
+        let status = repo_status(&repo).unwrap();
+        assert_eq!(status.files[1].status, FileStatus::Untracked);
+    }
+
+    #[test]
+    fn test_commit() {
+        let (dir, repo) = repository();
+        assert!(commit(&repo, "Nothing").is_err());
+
+        std::fs::remove_file(dir.path().join("a.strat")).unwrap();
+        std::fs::write(dir.path().join("b.strat"), "b\n").unwrap();
+        stage_all(&repo).unwrap();
+        let status = repo_status(&repo).unwrap();
+        assert_eq!(
+            changed(&status),
+            [
+                ("a.strat", FileStatus::Deleted, true),
+                ("b.strat", FileStatus::Added, true)
+            ]
+        );
+
+        commit(&repo, "Replace a with b").unwrap();
+        assert!(repo_status(&repo).unwrap().files.is_empty());
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.message(), Some("Replace a with b"));
+        assert_eq!(head.parent_count(), 1);
+    }
+
+    #[test]
+    fn test_line_changes() {
+        let (_dir, repo) = repository();
+        let path = Path::new("a.strat");
+        assert!(line_changes(&repo, path, "a\nb\nc\n").unwrap().is_empty());
+        assert_eq!(
+            line_changes(&repo, path, "a\nB\nc\nd\n").unwrap(),
+            [(2, LineChange::Modified), (4, LineChange::Added)]
+        );
+        assert_eq!(
+            line_changes(&repo, path, "a\nc\n").unwrap(),
+            [(1, LineChange::Removed)]
+        );
+        assert_eq!(
+            line_changes(&repo, Path::new("new.strat"), "x\ny").unwrap(),
+            [(1, LineChange::Added), (2, LineChange::Added)]
+        );
+    }
+
+    #[test]
+    fn test_panel() {
+        let (dir, _repo) = repository();
+        let mut panel = SourceControlPanel::open(&dir.path().join(".")).unwrap();
+        assert!(panel.branch().is_some());
+
+        std::fs::write(dir.path().join("a.strat"), "a\nb\n").unwrap();
+        panel.update(SourceControlMessage::Refresh);
+        assert_eq!(panel.status().files.len(), 1);
+
+        panel.update(SourceControlMessage::Commit);
+        assert_eq!(panel.error(), Some("Enter a commit message"));
+
+        panel.update(SourceControlMessage::Stage(PathBuf::from("a.strat")));
+        panel.update(SourceControlMessage::MessageChanged("Drop c".to_string()));
+        let action = panel.update(SourceControlMessage::Commit);
+        assert!(
+            matches!(action, Some(SourceControlAction::Changed(m)) if m.starts_with("Committed"))
+        );
+        assert!(panel.status().files.is_empty());
+
+        assert_eq!(
+            panel.update(SourceControlMessage::Open(PathBuf::from("a.strat"))),
+            Some(SourceControlAction::Open(panel.root().join("a.strat")))
+        );
+        assert!(SourceControlPanel::open(tempfile::tempdir().unwrap().path()).is_none());
+    }
+}
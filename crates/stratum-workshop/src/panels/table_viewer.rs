@@ -0,0 +1,615 @@
+//! Table viewer panel
+//!
+//! Shows a DataFrame or Cube produced by the REPL or a run as a grid instead
+//! of as text. Only the rows in view are rendered, so large frames scroll
+//! smoothly. Columns sort when their header is clicked, the filter narrows
+//! the rows, and the rows shown can be exported as CSV.
+
+use iced::widget::{
+    button, column, container, row, rule, scrollable, text, text_input, Column, Space,
+};
+use iced::{Color, Element, Font, Length, Theme};
+use std::cmp::Ordering;
+use std::ops::Range;
+use stratum_core::bytecode::Value;
+use stratum_core::data::DataFrame;
+
+/// Width of every column, in pixels
+const COLUMN_WIDTH: f32 = 120.0;
+/// Height of every row, in pixels
+const ROW_HEIGHT: f32 = 20.0;
+/// Rows rendered before the viewport size is known
+const DEFAULT_VISIBLE_ROWS: usize = 50;
+/// Extra rows rendered past each edge of the viewport
+const OVERSCAN_ROWS: usize = 5;
+
+/// Messages for the table viewer
+#[derive(Debug, Clone)]
+pub enum TableMessage {
+    /// Sort by a column, or change the direction of the current sort
+    SortBy(usize),
+    /// Filter text changed
+    FilterChanged(String),
+    /// The rows were scrolled or resized
+    Scrolled { offset: f32, height: f32 },
+    /// Export the rows shown as CSV
+    ExportCsv,
+    /// Close the viewer
+    Close,
+}
+
+/// Actions returned from update that Workshop needs to handle
+#[derive(Debug, Clone, PartialEq)]
+pub enum TableAction {
+    /// Save this CSV to a file the user picks
+    ExportCsv(String),
+    /// Close the viewer
+    Close,
+}
+
+/// A cell of a table
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl Cell {
+    fn from_value(value: &Value) -> Self {
+        match value {
+            Value::Null => Cell::Null,
+            Value::Bool(b) => Cell::Bool(*b),
+            Value::Int(i) => Cell::Int(*i),
+            Value::Float(f) => Cell::Float(*f),
+            Value::String(s) => Cell::Text(s.to_string()),
+            other => Cell::Text(other.to_string()),
+        }
+    }
+
+    /// Order cells for sorting: numbers by value, then booleans, then text,
+    /// with nulls last
+    fn compare(&self, other: &Cell) -> Ordering {
+        match (self, other) {
+            (Cell::Null, Cell::Null) => Ordering::Equal,
+            (Cell::Null, _) => Ordering::Greater,
+            (_, Cell::Null) => Ordering::Less,
+            (Cell::Int(a), Cell::Int(b)) => a.cmp(b),
+            (Cell::Bool(a), Cell::Bool(b)) => a.cmp(b),
+            (Cell::Text(a), Cell::Text(b)) => a.cmp(b),
+            (a, b) => match (a.as_f64(), b.as_f64()) {
+                (Some(x), Some(y)) => x.total_cmp(&y),
+                _ => a.rank().cmp(&b.rank()),
+            },
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Cell::Int(i) => Some(*i as f64),
+            Cell::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            Cell::Int(_) | Cell::Float(_) => 0,
+            Cell::Bool(_) => 1,
+            Cell::Text(_) => 2,
+            Cell::Null => 3,
+        }
+    }
+}
+
+impl std::fmt::Display for Cell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Cell::Null => write!(f, "null"),
+            Cell::Bool(b) => write!(f, "{b}"),
+            Cell::Int(i) => write!(f, "{i}"),
+            Cell::Float(x) => write!(f, "{x}"),
+            Cell::Text(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+/// A column of a table
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableColumn {
+    /// Column name
+    pub name: String,
+    /// Type of the column's values
+    pub dtype: String,
+}
+
+/// The contents of a table: its columns and rows
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableData {
+    /// What the table shows, such as the expression that produced it
+    pub title: String,
+    pub columns: Vec<TableColumn>,
+    pub rows: Vec<Vec<Cell>>,
+}
+
+impl TableData {
+    /// Read every row of a DataFrame
+    pub fn from_dataframe(title: impl Into<String>, df: &DataFrame) -> Self {
+        let mut columns = Vec::new();
+        let mut rows = vec![Vec::with_capacity(df.num_columns()); df.num_rows()];
+        for index in 0..df.num_columns() {
+            let Ok(series) = df.column_by_index(index) else {
+                continue;
+            };
+            columns.push(TableColumn {
+                name: series.name().to_string(),
+                dtype: series.stratum_type().to_string(),
+            });
+            for (row_index, row) in rows.iter_mut().enumerate() {
+                let cell = series
+                    .get(row_index)
+                    .map_or(Cell::Null, |value| Cell::from_value(&value));
+                row.push(cell);
+            }
+        }
+        Self {
+            title: title.into(),
+            columns,
+            rows,
+        }
+    }
+
+    /// The table of a DataFrame or Cube value; `None` for other values
+    pub fn from_value(title: impl Into<String>, value: &Value) -> Option<Result<Self, String>> {
+        match value {
+            Value::DataFrame(df) => Some(Ok(Self::from_dataframe(title, df))),
+            Value::Cube(cube) => Some(
+                cube.to_dataframe()
+                    .map(|df| Self::from_dataframe(title, &df))
+                    .map_err(|e| e.to_string()),
+            ),
+            _ => None,
+        }
+    }
+}
+
+/// Sort direction of a column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+/// Table viewer panel
+#[derive(Debug)]
+pub struct TableViewer {
+    data: TableData,
+    filter: String,
+    /// The column sorted by, if any
+    sort: Option<(usize, SortOrder)>,
+    /// Indices of the rows shown, filtered and sorted
+    shown: Vec<usize>,
+    /// Scroll position of the rows, in pixels
+    scroll_offset: f32,
+    /// Height of the visible rows area, in pixels, once known
+    viewport_height: f32,
+}
+
+impl TableViewer {
+    /// Create a viewer showing every row of a table
+    pub fn new(data: TableData) -> Self {
+        let shown = (0..data.rows.len()).collect();
+        Self {
+            data,
+            filter: String::new(),
+            sort: None,
+            shown,
+            scroll_offset: 0.0,
+            viewport_height: 0.0,
+        }
+    }
+
+    /// The table shown
+    pub fn data(&self) -> &TableData {
+        &self.data
+    }
+
+    /// Handle a message
+    pub fn update(&mut self, message: TableMessage) -> Option<TableAction> {
+        match message {
+            TableMessage::SortBy(column) => {
+                self.sort = match self.sort {
+                    Some((current, SortOrder::Ascending)) if current == column => {
+                        Some((column, SortOrder::Descending))
+                    }
+                    Some((current, SortOrder::Descending)) if current == column => None,
+                    _ => Some((column, SortOrder::Ascending)),
+                };
+                self.refresh();
+                None
+            }
+            TableMessage::FilterChanged(filter) => {
+                self.filter = filter;
+                self.refresh();
+                None
+            }
+            TableMessage::Scrolled { offset, height } => {
+                self.scroll_offset = offset;
+                self.viewport_height = height;
+                None
+            }
+            TableMessage::ExportCsv => Some(TableAction::ExportCsv(self.to_csv())),
+            TableMessage::Close => Some(TableAction::Close),
+        }
+    }
+
+    /// Recompute the rows shown after the filter or sort changed
+    fn refresh(&mut self) {
+        let filter = Filter::parse(&self.filter, &self.data.columns);
+        let rows = &self.data.rows;
+        self.shown = (0..rows.len())
+            .filter(|&i| filter.matches(&rows[i]))
+            .collect();
+        if let Some((column, order)) = self.sort {
+            // A stable sort keeps the original order of equal cells
+            self.shown.sort_by(|&a, &b| {
+                let ordering = rows[a][column].compare(&rows[b][column]);
+                match order {
+                    SortOrder::Ascending => ordering,
+                    SortOrder::Descending => ordering.reverse(),
+                }
+            });
+        }
+    }
+
+    /// The rows shown, as CSV with a header line
+    pub fn to_csv(&self) -> String {
+        let mut csv = csv_line(self.data.columns.iter().map(|c| c.name.clone()));
+        for &i in &self.shown {
+            csv.push_str(&csv_line(self.data.rows[i].iter().map(|cell| match cell {
+                Cell::Null => String::new(),
+                cell => cell.to_string(),
+            })));
+        }
+        csv
+    }
+
+    /// Positions in `shown` of the rows to render for the current scroll
+    /// position
+    fn visible_range(&self) -> Range<usize> {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let (first, count) = if self.viewport_height > 0.0 {
+            (
+                (self.scroll_offset / ROW_HEIGHT) as usize,
+                (self.viewport_height / ROW_HEIGHT).ceil() as usize,
+            )
+        } else {
+            (0, DEFAULT_VISIBLE_ROWS)
+        };
+        let start = first.saturating_sub(OVERSCAN_ROWS).min(self.shown.len());
+        let end = (first + count + OVERSCAN_ROWS).min(self.shown.len());
+        start..end
+    }
+
+    /// Render the panel
+    pub fn view(&self) -> Element<'_, TableMessage> {
+        let toolbar = row![
+            text(&self.data.title).size(12).font(Font::MONOSPACE),
+            text(format!(
+                "{} of {} rows",
+                self.shown.len(),
+                self.data.rows.len()
+            ))
+            .size(11)
+            .color(Color::from_rgb(0.6, 0.6, 0.6)),
+            Space::new().width(Length::Fill),
+            text_input("Filter (text or column:text)...", &self.filter)
+                .on_input(TableMessage::FilterChanged)
+                .size(11)
+                .padding(3)
+                .width(Length::Fixed(220.0)),
+            button(text("Export CSV").size(11))
+                .on_press(TableMessage::ExportCsv)
+                .padding([3, 8])
+                .style(button::secondary),
+            button(text("x").size(10))
+                .on_press(TableMessage::Close)
+                .padding([2, 6])
+                .style(button::text),
+        ]
+        .spacing(8)
+        .padding([4, 8])
+        .align_y(iced::Alignment::Center);
+
+        #[allow(clippy::cast_precision_loss)]
+        let grid_width = self.data.columns.len() as f32 * COLUMN_WIDTH;
+        let grid = column![self.header(), rule::horizontal(1), self.body()].width(grid_width);
+
+        container(column![
+            toolbar,
+            rule::horizontal(1),
+            scrollable(grid)
+                .direction(scrollable::Direction::Horizontal(
+                    scrollable::Scrollbar::default()
+                ))
+                .width(Length::Fill)
+                .height(Length::Fill),
+        ])
+        .width(Length::Fill)
+        .height(Length::FillPortion(1))
+        .style(|theme: &Theme| {
+            let palette = theme.extended_palette();
+            container::Style {
+                background: Some(palette.background.base.color.into()),
+                ..Default::default()
+            }
+        })
+        .into()
+    }
+
+    /// Column headers: name, sort direction and type
+    fn header(&self) -> Element<'_, TableMessage> {
+        let headers = self.data.columns.iter().enumerate().map(|(i, column)| {
+            let arrow = match self.sort {
+                Some((sorted, SortOrder::Ascending)) if sorted == i => " ^",
+                Some((sorted, SortOrder::Descending)) if sorted == i => " v",
+                _ => "",
+            };
+            button(
+                column![
+                    text(format!("{}{arrow}", column.name))
+                        .size(11)
+                        .font(Font::MONOSPACE),
+                    text(&column.dtype)
+                        .size(9)
+                        .color(Color::from_rgb(0.6, 0.7, 0.6)),
+                ]
+                .spacing(1),
+            )
+            .on_press(TableMessage::SortBy(i))
+            .padding([2, 4])
+            .width(COLUMN_WIDTH)
+            .style(button::text)
+            .into()
+        });
+        row(headers).into()
+    }
+
+    /// The rows in view, between spacers standing in for the rest
+    fn body(&self) -> Element<'_, TableMessage> {
+        let range = self.visible_range();
+        #[allow(clippy::cast_precision_loss)]
+        let spacer = |rows: usize| Space::new().height(rows as f32 * ROW_HEIGHT);
+
+        let mut rows = Column::new().push(spacer(range.start));
+        for &i in &self.shown[range.clone()] {
+            let cells = self.data.rows[i].iter().map(|cell| {
+                let color = match cell {
+                    Cell::Null => Color::from_rgb(0.5, 0.5, 0.5),
+                    Cell::Int(_) | Cell::Float(_) => Color::from_rgb(0.7, 0.85, 1.0),
+                    _ => Color::from_rgb(0.9, 0.9, 0.9),
+                };
+                container(
+                    text(cell.to_string())
+                        .size(11)
+                        .font(Font::MONOSPACE)
+                        .color(color),
+                )
+                .padding([2, 4])
+                .width(COLUMN_WIDTH)
+                .height(ROW_HEIGHT)
+                .clip(true)
+                .into()
+            });
+            rows = rows.push(row(cells));
+        }
+        rows = rows.push(spacer(self.shown.len() - range.end));
+
+        scrollable(rows)
+            .on_scroll(|viewport| TableMessage::Scrolled {
+                offset: viewport.absolute_offset().y,
+                height: viewport.bounds().height,
+            })
+            .height(Length::Fill)
+            .into()
+    }
+}
+
+/// A row filter: text to find in any cell, or with `column:text` in one
+/// column. Matching ignores case.
+struct Filter {
+    column: Option<usize>,
+    needle: String,
+}
+
+impl Filter {
+    fn parse(filter: &str, columns: &[TableColumn]) -> Self {
+        let filter = filter.trim();
+        if let Some((name, needle)) = filter.split_once(':') {
+            let name = name.trim();
+            if let Some(column) = columns
+                .iter()
+                .position(|c| c.name.eq_ignore_ascii_case(name))
+            {
+                return Self {
+                    column: Some(column),
+                    needle: needle.trim().to_lowercase(),
+                };
+            }
+        }
+        Self {
+            column: None,
+            needle: filter.to_lowercase(),
+        }
+    }
+
+    fn matches(&self, row: &[Cell]) -> bool {
+        if self.needle.is_empty() {
+            return true;
+        }
+        let contains = |cell: &Cell| cell.to_string().to_lowercase().contains(&self.needle);
+        match self.column {
+            Some(column) => row.get(column).is_some_and(contains),
+            None => row.iter().any(contains),
+        }
+    }
+}
+
+/// A CSV line, quoting fields that need it
+fn csv_line(fields: impl Iterator<Item = String>) -> String {
+    let fields: Vec<String> = fields
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field
+            }
+        })
+        .collect();
+    format!("{}\n", fields.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stratum_core::data::Series;
+
+    fn sample() -> TableData {
+        let column = |name: &str, dtype: &str| TableColumn {
+            name: name.to_string(),
+            dtype: dtype.to_string(),
+        };
+        let row = |city: &str, sales: Option<i64>, share: f64| {
+            vec![
+                Cell::Text(city.to_string()),
+                sales.map_or(Cell::Null, Cell::Int),
+                Cell::Float(share),
+            ]
+        };
+        TableData {
+            title: "sales".to_string(),
+            columns: vec![
+                column("city", "String"),
+                column("sales", "Int"),
+                column("share", "Float"),
+            ],
+            rows: vec![
+                row("Oslo", Some(30), 0.25),
+                row("Lima", None, 0.5),
+                row("Pune, IN", Some(120), 0.125),
+                row("Lyon", Some(30), 0.125),
+            ],
+        }
+    }
+
+    fn cities(viewer: &TableViewer) -> Vec<String> {
+        viewer
+            .shown
+            .iter()
+            .map(|&i| viewer.data.rows[i][0].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_sort_cycles_through_directions() {
+        let mut viewer = TableViewer::new(sample());
+        viewer.update(TableMessage::SortBy(1));
+        assert_eq!(cities(&viewer), ["Oslo", "Lyon", "Pune, IN", "Lima"]);
+        viewer.update(TableMessage::SortBy(1));
+        assert_eq!(cities(&viewer), ["Lima", "Pune, IN", "Oslo", "Lyon"]);
+        viewer.update(TableMessage::SortBy(1));
+        assert_eq!(cities(&viewer), ["Oslo", "Lima", "Pune, IN", "Lyon"]);
+
+        viewer.update(TableMessage::SortBy(0));
+        assert_eq!(cities(&viewer), ["Lima", "Lyon", "Oslo", "Pune, IN"]);
+    }
+
+    #[test]
+    fn test_filter() {
+        let mut viewer = TableViewer::new(sample());
+        viewer.update(TableMessage::FilterChanged("ly".to_string()));
+        assert_eq!(cities(&viewer), ["Lyon"]);
+
+        viewer.update(TableMessage::FilterChanged("SALES: 30".to_string()));
+        assert_eq!(cities(&viewer), ["Oslo", "Lyon"]);
+
+        // An unknown column name is searched for as text
+        viewer.update(TableMessage::FilterChanged("region:x".to_string()));
+        assert!(cities(&viewer).is_empty());
+
+        viewer.update(TableMessage::SortBy(2));
+        viewer.update(TableMessage::FilterChanged("l".to_string()));
+        assert_eq!(cities(&viewer), ["Lyon", "Oslo", "Lima"]);
+    }
+
+    #[test]
+    fn test_export_csv() {
+        let mut viewer = TableViewer::new(sample());
+        viewer.update(TableMessage::FilterChanged("p".to_string()));
+        assert_eq!(
+            viewer.update(TableMessage::ExportCsv),
+            Some(TableAction::ExportCsv(
+                "city,sales,share\n\"Pune, IN\",120,0.125\n".to_string()
+            ))
+        );
+        assert_eq!(
+            csv_line(["a\"b".to_string(), String::new()].into_iter()),
+            "\"a\"\"b\",\n"
+        );
+    }
+
+    #[test]
+    fn test_visible_range() {
+        let mut data = sample();
+        data.rows = (0..1000)
+            .map(|i| vec![Cell::Int(i), Cell::Null, Cell::Null])
+            .collect();
+        let mut viewer = TableViewer::new(data);
+        assert_eq!(
+            viewer.visible_range(),
+            0..DEFAULT_VISIBLE_ROWS + OVERSCAN_ROWS
+        );
+
+        viewer.update(TableMessage::Scrolled {
+            offset: 100.0 * ROW_HEIGHT,
+            height: 10.0 * ROW_HEIGHT,
+        });
+        assert_eq!(viewer.visible_range(), 95..115);
+
+        viewer.update(TableMessage::Scrolled {
+            offset: 995.0 * ROW_HEIGHT,
+            height: 10.0 * ROW_HEIGHT,
+        });
+        assert_eq!(viewer.visible_range(), 990..1000);
+    }
+
+    #[test]
+    fn test_from_dataframe() {
+        let df = DataFrame::from_series(vec![
+            Series::from_strings("name", vec!["a", "b"]),
+            Series::from_optional_ints("count", vec![Some(1), None]),
+        ])
+        .unwrap();
+        let value = Value::DataFrame(std::sync::Arc::new(df));
+        let table = TableData::from_value("df", &value).unwrap().unwrap();
+        assert_eq!(table.columns[0].name, "name");
+        assert_eq!(table.columns[1].name, "count");
+        assert_eq!(
+            table.rows,
+            [
+                vec![Cell::Text("a".to_string()), Cell::Int(1)],
+                vec![Cell::Text("b".to_string()), Cell::Null],
+            ]
+        );
+        assert!(TableData::from_value("n", &Value::Int(1)).is_none());
+    }
+
+    #[test]
+    fn test_close() {
+        let mut viewer = TableViewer::new(sample());
+        assert_eq!(viewer.update(TableMessage::Close), Some(TableAction::Close));
+    }
+}
@@ -0,0 +1,558 @@
+//! Terminal panel
+//!
+//! Runs the user's shell in a pseudo-terminal inside the Workshop, starting
+//! in the opened project and following it when another folder is opened.
+//! Output is shown as plain text: escape sequences are dropped and carriage
+//! returns and backspaces are applied to the current line.
+
+use iced::futures::channel::mpsc;
+use iced::widget::{button, column, container, row, scrollable, text, text_input, Column, Space};
+use iced::{Element, Font, Length, Theme};
+use portable_pty::{native_pty_system, Child, CommandBuilder, MasterPty, PtySize};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Lines kept in the scrollback
+const MAX_SCROLLBACK: usize = 5000;
+
+/// Messages for the terminal panel
+#[derive(Debug, Clone)]
+pub enum TerminalMessage {
+    /// Input text changed
+    InputChanged(String),
+    /// Send the input line to the shell
+    Submit,
+    /// Output read from a shell session
+    Output { session: u64, text: String },
+    /// A shell session's output ended
+    Exited { session: u64 },
+    /// Send Ctrl+C to the shell
+    Interrupt,
+    /// Start a new shell
+    Restart,
+    /// Clear the scrollback
+    Clear,
+    /// Close the panel
+    Close,
+}
+
+/// Actions returned from update that Workshop needs to handle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerminalAction {
+    /// Start a new shell and stream its output
+    Start,
+    /// Close the panel
+    Close,
+}
+
+/// A program run in the terminal in place of the shell
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    pub program: String,
+    pub args: Vec<String>,
+    /// Variables set on top of the Workshop's environment
+    pub env: Vec<(String, String)>,
+    pub cwd: PathBuf,
+}
+
+impl std::fmt::Display for Program {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.program)?;
+        for arg in &self.args {
+            if arg.is_empty() || arg.contains(char::is_whitespace) {
+                write!(f, " \"{arg}\"")?;
+            } else {
+                write!(f, " {arg}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A running shell
+struct Session {
+    id: u64,
+    writer: Box<dyn Write + Send>,
+    child: Box<dyn Child + Send + Sync>,
+    // Kept open for the life of the shell
+    _master: Box<dyn MasterPty + Send>,
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+    }
+}
+
+/// Terminal panel
+pub struct TerminalPanel {
+    /// Directory shells start in, and that the shell is moved to when the
+    /// opened folder changes
+    cwd: PathBuf,
+    scrollback: Scrollback,
+    input: String,
+    session: Option<Session>,
+    next_session: u64,
+}
+
+impl std::fmt::Debug for TerminalPanel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TerminalPanel")
+            .field("cwd", &self.cwd)
+            .field("input", &self.input)
+            .field("running", &self.is_running())
+            .finish_non_exhaustive()
+    }
+}
+
+impl TerminalPanel {
+    /// Create a terminal panel whose shells start in a directory
+    pub fn new(cwd: PathBuf) -> Self {
+        Self {
+            cwd,
+            scrollback: Scrollback::default(),
+            input: String::new(),
+            session: None,
+            next_session: 0,
+        }
+    }
+
+    /// The directory the shell was started in or last moved to
+    pub fn cwd(&self) -> &Path {
+        &self.cwd
+    }
+
+    /// Whether a shell is running
+    pub fn is_running(&self) -> bool {
+        self.session.is_some()
+    }
+
+    /// The lines of output, including the line being written
+    pub fn lines(&self) -> impl Iterator<Item = &str> {
+        self.scrollback.lines()
+    }
+
+    /// Start the user's shell, replacing any running one. Returns the stream
+    /// of the new shell's output.
+    pub fn start(&mut self) -> Result<mpsc::UnboundedReceiver<TerminalMessage>, String> {
+        let mut cmd = CommandBuilder::new_default_prog();
+        cmd.cwd(&self.cwd);
+        self.spawn(cmd, &[])
+    }
+
+    /// Run a program in place of the shell, replacing any running one.
+    /// Returns the stream of its output; Restart goes back to the shell.
+    pub fn run(
+        &mut self,
+        program: &Program,
+    ) -> Result<mpsc::UnboundedReceiver<TerminalMessage>, String> {
+        let mut cmd = CommandBuilder::new(&program.program);
+        cmd.args(&program.args);
+        cmd.cwd(&program.cwd);
+        self.scrollback.push(&format!("\n> {program}\n"));
+        self.spawn(cmd, &program.env)
+    }
+
+    /// Spawn a command in a new pseudo-terminal, with variables set last,
+    /// and stream its output
+    fn spawn(
+        &mut self,
+        mut cmd: CommandBuilder,
+        env: &[(String, String)],
+    ) -> Result<mpsc::UnboundedReceiver<TerminalMessage>, String> {
+        self.session = None;
+        let pair = native_pty_system()
+            .openpty(PtySize {
+                rows: 24,
+                cols: 120,
+                pixel_width: 0,
+                pixel_height: 0,
+            })
+            .map_err(|e| e.to_string())?;
+
+        // Output is shown without escape sequences, so ask programs for none
+        cmd.env("TERM", "dumb");
+        if let Some(path) = search_path_with_current_exe() {
+            cmd.env("PATH", path);
+        }
+        for (key, value) in env {
+            cmd.env(key, value);
+        }
+        let child = pair.slave.spawn_command(cmd).map_err(|e| e.to_string())?;
+        drop(pair.slave);
+
+        let mut reader = pair.master.try_clone_reader().map_err(|e| e.to_string())?;
+        let writer = pair.master.take_writer().map_err(|e| e.to_string())?;
+
+        let id = self.next_session;
+        self.next_session += 1;
+        let (sender, receiver) = mpsc::unbounded();
+        std::thread::spawn(move || {
+            let mut buffer = [0u8; 4096];
+            let mut pending = Vec::new();
+            while let Ok(n) = reader.read(&mut buffer) {
+                if n == 0 {
+                    break;
+                }
+                pending.extend_from_slice(&buffer[..n]);
+                let text = decode_utf8(&mut pending);
+                if !text.is_empty()
+                    && sender
+                        .unbounded_send(TerminalMessage::Output { session: id, text })
+                        .is_err()
+                {
+                    return;
+                }
+            }
+            let _ = sender.unbounded_send(TerminalMessage::Exited { session: id });
+        });
+
+        self.session = Some(Session {
+            id,
+            writer,
+            child,
+            _master: pair.master,
+        });
+        Ok(receiver)
+    }
+
+    /// Follow the opened project to another directory
+    pub fn set_cwd(&mut self, cwd: PathBuf) {
+        if cwd == self.cwd {
+            return;
+        }
+        let command = cd_command(&cwd);
+        self.cwd = cwd;
+        self.write(&command);
+    }
+
+    /// Write to the shell's input, if a shell is running
+    fn write(&mut self, input: &str) {
+        if let Some(session) = &mut self.session {
+            let _ = session
+                .writer
+                .write_all(input.as_bytes())
+                .and_then(|()| session.writer.flush());
+        }
+    }
+
+    /// Handle a message
+    pub fn update(&mut self, message: TerminalMessage) -> Option<TerminalAction> {
+        match message {
+            TerminalMessage::InputChanged(input) => {
+                self.input = input;
+            }
+            TerminalMessage::Submit => {
+                let mut line = std::mem::take(&mut self.input);
+                line.push('\r');
+                self.write(&line);
+            }
+            TerminalMessage::Output { session, text } => {
+                if self.is_session(session) {
+                    self.scrollback.push(&text);
+                }
+            }
+            TerminalMessage::Exited { session } => {
+                if self.is_session(session) {
+                    let status = self
+                        .session
+                        .take()
+                        .and_then(|mut s| s.child.try_wait().ok().flatten());
+                    let message = match status {
+                        Some(status) => {
+                            format!("[process exited with code {}]", status.exit_code())
+                        }
+                        None => "[process exited]".to_string(),
+                    };
+                    self.scrollback.push(&format!("\n{message}\n"));
+                }
+            }
+            TerminalMessage::Interrupt => self.write("\x03"),
+            TerminalMessage::Restart => return Some(TerminalAction::Start),
+            TerminalMessage::Clear => self.scrollback = Scrollback::default(),
+            TerminalMessage::Close => return Some(TerminalAction::Close),
+        }
+        None
+    }
+
+    fn is_session(&self, id: u64) -> bool {
+        self.session.as_ref().is_some_and(|s| s.id == id)
+    }
+
+    /// Render the panel
+    pub fn view(&self) -> Element<'_, TerminalMessage> {
+        let header = row![
+            text(format!("Terminal - {}", self.cwd.display())).size(12),
+            Space::new().width(Length::Fill),
+            button(text("Ctrl+C").size(10))
+                .on_press_maybe(self.is_running().then_some(TerminalMessage::Interrupt))
+                .padding([2, 6])
+                .style(button::text),
+            button(text("Clear").size(10))
+                .on_press(TerminalMessage::Clear)
+                .padding([2, 6])
+                .style(button::text),
+            button(text("Restart").size(10))
+                .on_press(TerminalMessage::Restart)
+                .padding([2, 6])
+                .style(button::text),
+            button(text("x").size(10))
+                .on_press(TerminalMessage::Close)
+                .padding([2, 6])
+                .style(button::text),
+        ]
+        .spacing(4)
+        .padding([4, 8])
+        .align_y(iced::Alignment::Center);
+
+        let output = Column::with_children(
+            self.lines()
+                .map(|line| text(line).size(12).font(Font::MONOSPACE).into()),
+        )
+        .padding(4)
+        .width(Length::Fill);
+
+        let input = text_input(
+            if self.is_running() {
+                "Command"
+            } else {
+                "Shell exited - press Restart"
+            },
+            &self.input,
+        )
+        .on_input(TerminalMessage::InputChanged)
+        .on_submit(TerminalMessage::Submit)
+        .font(Font::MONOSPACE)
+        .size(12)
+        .padding(4);
+
+        container(column![
+            header,
+            scrollable(output)
+                .anchor_bottom()
+                .height(Length::Fill)
+                .width(Length::Fill),
+            input,
+        ])
+        .width(Length::Fill)
+        .height(Length::FillPortion(1))
+        .style(|theme: &Theme| {
+            let palette = theme.extended_palette();
+            container::Style {
+                background: Some(palette.background.strong.color.into()),
+                ..Default::default()
+            }
+        })
+        .into()
+    }
+}
+
+/// Terminal output as lines of plain text
+#[derive(Debug, Default)]
+struct Scrollback {
+    lines: Vec<String>,
+    /// The line being written
+    current: String,
+    /// A carriage return was read; the next character starts the line over
+    /// unless it is a newline
+    carriage_return: bool,
+    escape: Escape,
+}
+
+/// Where the scrollback is within an escape sequence
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Escape {
+    #[default]
+    None,
+    /// After ESC
+    Start,
+    /// After `ESC (` or `ESC )`, before the character set
+    Charset,
+    /// In a control sequence (`ESC [`), until its final byte
+    Csi,
+    /// In an operating system command (`ESC ]`), until BEL or `ESC \`
+    Osc,
+    /// After ESC inside an operating system command
+    OscTerminator,
+}
+
+impl Scrollback {
+    fn push(&mut self, text: &str) {
+        for c in text.chars() {
+            self.escape = match (self.escape, c) {
+                (Escape::None, '\x1b') => Escape::Start,
+                (Escape::None, c) => {
+                    self.push_char(c);
+                    Escape::None
+                }
+                (Escape::Start, '[') => Escape::Csi,
+                (Escape::Start, ']') => Escape::Osc,
+                (Escape::Start, '(' | ')') => Escape::Charset,
+                (Escape::Start | Escape::Charset, _) => Escape::None,
+                (Escape::Csi, '\x40'..='\x7e') => Escape::None,
+                (Escape::Csi, _) => Escape::Csi,
+                (Escape::Osc, '\x07') => Escape::None,
+                (Escape::Osc | Escape::OscTerminator, '\x1b') => Escape::OscTerminator,
+                (Escape::OscTerminator, '\\') => Escape::None,
+                (Escape::Osc | Escape::OscTerminator, _) => Escape::Osc,
+            };
+        }
+    }
+
+    fn push_char(&mut self, c: char) {
+        if std::mem::take(&mut self.carriage_return) && c != '\n' {
+            self.current.clear();
+        }
+        match c {
+            '\n' => {
+                self.lines.push(std::mem::take(&mut self.current));
+                if self.lines.len() > MAX_SCROLLBACK {
+                    let excess = self.lines.len() - MAX_SCROLLBACK;
+                    self.lines.drain(..excess);
+                }
+            }
+            '\r' => self.carriage_return = true,
+            '\x08' => {
+                self.current.pop();
+            }
+            '\t' => self.current.push_str("    "),
+            c if c.is_control() => {}
+            c => self.current.push(c),
+        }
+    }
+
+    fn lines(&self) -> impl Iterator<Item = &str> {
+        self.lines
+            .iter()
+            .map(String::as_str)
+            .chain(Some(self.current.as_str()).filter(|line| !line.is_empty()))
+    }
+}
+
+/// Decode the complete UTF-8 text at the start of `pending`, leaving an
+/// incomplete character at the end for the next read. Invalid bytes become
+/// U+FFFD.
+fn decode_utf8(pending: &mut Vec<u8>) -> String {
+    let mut text = String::new();
+    loop {
+        match std::str::from_utf8(pending) {
+            Ok(valid) => {
+                text.push_str(valid);
+                pending.clear();
+                return text;
+            }
+            Err(e) => {
+                let valid_up_to = e.valid_up_to();
+                text.push_str(&String::from_utf8_lossy(&pending[..valid_up_to]));
+                let Some(len) = e.error_len() else {
+                    pending.drain(..valid_up_to);
+                    return text;
+                };
+                text.push(char::REPLACEMENT_CHARACTER);
+                pending.drain(..valid_up_to + len);
+            }
+        }
+    }
+}
+
+/// The shell command that changes to a directory
+fn cd_command(dir: &Path) -> String {
+    let dir = dir.display().to_string();
+    if cfg!(windows) {
+        format!("cd /d \"{dir}\"\r")
+    } else {
+        format!("cd '{}'\r", dir.replace('\'', r"'\''"))
+    }
+}
+
+/// PATH with the directory of the running executable first, so the
+/// `stratum` that launched the Workshop is the one the shell finds
+fn search_path_with_current_exe() -> Option<std::ffi::OsString> {
+    let exe_dir = std::env::current_exe().ok()?.parent()?.to_path_buf();
+    let paths = std::env::var_os("PATH").unwrap_or_default();
+    std::env::join_paths(std::iter::once(exe_dir).chain(std::env::split_paths(&paths))).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(scrollback: &Scrollback) -> Vec<&str> {
+        scrollback.lines().collect()
+    }
+
+    #[test]
+    fn test_program_display() {
+        let program = Program {
+            program: "stratum".to_string(),
+            args: vec!["run".to_string(), "my app.strat".to_string(), String::new()],
+            env: Vec::new(),
+            cwd: PathBuf::from("/project"),
+        };
+        assert_eq!(program.to_string(), "stratum run \"my app.strat\" \"\"");
+    }
+
+    #[test]
+    fn test_scrollback_strips_escapes() {
+        let mut scrollback = Scrollback::default();
+        scrollback.push("\x1b[1;32mok\x1b[0m done\n\x1b]0;title\x07$ ");
+        assert_eq!(lines(&scrollback), ["ok done", "$ "]);
+
+        // Sequences split across reads are still dropped
+        scrollback.push("\x1b(B\x1b[3");
+        scrollback.push("1mred\x1b]2;t\x1b\\!\n");
+        assert_eq!(lines(&scrollback), ["ok done", "$ red!"]);
+    }
+
+    #[test]
+    fn test_scrollback_line_editing() {
+        let mut scrollback = Scrollback::default();
+        scrollback.push("10%\r50%\r100%\r\nabc\x08\x08d\ta\x07\n");
+        assert_eq!(lines(&scrollback), ["100%", "ad    a"]);
+    }
+
+    #[test]
+    fn test_scrollback_limit() {
+        let mut scrollback = Scrollback::default();
+        scrollback.push(&"line\n".repeat(MAX_SCROLLBACK + 3));
+        scrollback.push("last");
+        assert_eq!(scrollback.lines().count(), MAX_SCROLLBACK + 1);
+        assert_eq!(scrollback.lines().last(), Some("last"));
+    }
+
+    #[test]
+    fn test_decode_utf8() {
+        let mut pending = "añb".as_bytes()[..2].to_vec();
+        assert_eq!(decode_utf8(&mut pending), "a");
+        assert_eq!(pending, [0xc3]);
+        pending.extend_from_slice(&"añb".as_bytes()[2..]);
+        assert_eq!(decode_utf8(&mut pending), "ñb");
+        assert!(pending.is_empty());
+
+        let mut pending = vec![b'x', 0xff, b'y'];
+        assert_eq!(decode_utf8(&mut pending), "x\u{fffd}y");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_cd_command() {
+        assert_eq!(
+            cd_command(Path::new("/home/me/it's here")),
+            "cd '/home/me/it'\\''s here'\r"
+        );
+    }
+
+    #[test]
+    fn test_stale_session_output_is_ignored() {
+        let mut terminal = TerminalPanel::new(PathBuf::from("."));
+        terminal.update(TerminalMessage::Output {
+            session: 0,
+            text: "old\n".to_string(),
+        });
+        assert_eq!(terminal.lines().count(), 0);
+        assert_eq!(
+            terminal.update(TerminalMessage::Restart),
+            Some(TerminalAction::Start)
+        );
+    }
+}
@@ -0,0 +1,626 @@
+//! Workshop plugins scripted in Stratum
+//!
+//! A plugin is a Stratum package in the Workshop's plugin folder. Each one is
+//! loaded at startup into a VM of its own, and its `activate()` function
+//! registers commands, panels, editor decorations and key bindings through
+//! the `Workshop` namespace:
+//!
+//! ```stratum
+//! fx activate() {
+//!     Workshop.command("Uppercase", |ctx| ctx["text"].to_upper())
+//!     Workshop.keybinding("ctrl+shift+u", "Uppercase")
+//!     Workshop.panel("Size", |ctx| "{ctx["text"].len()} characters")
+//!     Workshop.decorations(|ctx| [[1, "first line"]])
+//! }
+//! ```
+//!
+//! Registered closures run through the plugin's callback registry. They get
+//! a context map with the open file's `path`, `text` and cursor `line`
+//! (1-based). A command returning a string replaces the editor's text, a
+//! panel returns its contents, and a decorations function returns
+//! `[line, text]` pairs. `Workshop.status(message)` shows a message in the
+//! status bar.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use iced::keyboard::{self, Modifiers};
+use stratum_core::bytecode::{HashableValue, Value, ValueMap};
+use stratum_core::{Access, Compiler, Parser, VM};
+use stratum_gui::{CallbackExecutor, CallbackId, CallbackRegistry};
+use stratum_pkg::{Manifest, PackageLayout};
+
+thread_local! {
+    /// Registrations made through the `Workshop` namespace while a plugin
+    /// runs, applied to the host once it returns
+    static PENDING_REGISTRATIONS: RefCell<Vec<Registration>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Something a plugin asked the Workshop for
+enum Registration {
+    Command { title: String, callback: Value },
+    Keybinding { chord: KeyChord, command: String },
+    Panel { title: String, callback: Value },
+    Decorations(Value),
+    Status(String),
+}
+
+/// A key with modifiers, like `Ctrl+Shift+U`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyChord {
+    ctrl: bool,
+    alt: bool,
+    shift: bool,
+    /// The key's character, lowercase
+    key: String,
+}
+
+impl KeyChord {
+    /// Parse a chord like `ctrl+shift+u`. Bindings need Ctrl or Alt so they
+    /// don't take over typing.
+    pub fn parse(chord: &str) -> Result<Self, String> {
+        let mut parts: Vec<String> = chord.split('+').map(|p| p.trim().to_lowercase()).collect();
+        let key = parts.pop().unwrap_or_default();
+        let mut parsed = Self {
+            ctrl: false,
+            alt: false,
+            shift: false,
+            key,
+        };
+        for part in &parts {
+            match part.as_str() {
+                "ctrl" | "cmd" => parsed.ctrl = true,
+                "alt" => parsed.alt = true,
+                "shift" => parsed.shift = true,
+                _ => return Err(format!("unknown modifier '{part}' in '{chord}'")),
+            }
+        }
+        if parsed.key.chars().count() != 1 {
+            return Err(format!("'{chord}' must end with a single key"));
+        }
+        if !parsed.ctrl && !parsed.alt {
+            return Err(format!("'{chord}' needs Ctrl or Alt"));
+        }
+        Ok(parsed)
+    }
+
+    /// The chord of a key press, if it could be bound
+    pub fn from_key(key: &keyboard::Key, modifiers: Modifiers) -> Option<Self> {
+        let keyboard::Key::Character(c) = key else {
+            return None;
+        };
+        (modifiers.command() || modifiers.alt()).then(|| Self {
+            ctrl: modifiers.command(),
+            alt: modifiers.alt(),
+            shift: modifiers.shift(),
+            key: c.to_lowercase(),
+        })
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (held, name) in [
+            (self.ctrl, "Ctrl+"),
+            (self.alt, "Alt+"),
+            (self.shift, "Shift+"),
+        ] {
+            if held {
+                f.write_str(name)?;
+            }
+        }
+        f.write_str(&self.key.to_uppercase())
+    }
+}
+
+/// What plugin callbacks know about the editor
+#[derive(Debug, Clone, Default)]
+pub struct Context {
+    pub path: Option<PathBuf>,
+    pub text: String,
+    /// Cursor line, 1-based
+    pub line: usize,
+}
+
+/// A command registered by a plugin
+pub struct Command {
+    plugin: usize,
+    pub title: String,
+    callback: CallbackId,
+    /// Key binding, if the plugin gave it one
+    pub chord: Option<KeyChord>,
+}
+
+/// A panel registered by a plugin
+pub struct Panel {
+    plugin: usize,
+    pub title: String,
+    callback: CallbackId,
+    /// Contents from the last refresh
+    pub content: String,
+}
+
+/// A note a plugin attached to an editor line
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decoration {
+    /// 1-based
+    pub line: usize,
+    pub text: String,
+}
+
+/// What running a command did
+#[derive(Debug, Default)]
+pub struct CommandOutput {
+    /// New text for the editor
+    pub text: Option<String>,
+    /// Messages for the status bar
+    pub status: Vec<String>,
+}
+
+struct Plugin {
+    name: String,
+    executor: CallbackExecutor,
+}
+
+/// The loaded plugins and everything they registered
+#[derive(Default)]
+pub struct PluginHost {
+    plugins: Vec<Plugin>,
+    commands: Vec<Command>,
+    panels: Vec<Panel>,
+    decorators: Vec<(usize, CallbackId)>,
+    /// Plugins that failed to load, with why
+    errors: Vec<String>,
+}
+
+impl PluginHost {
+    /// Load the plugins in the user's plugin folder
+    pub fn load() -> Self {
+        plugin_dir().map_or_else(Self::default, |dir| Self::load_dir(&dir))
+    }
+
+    /// Load every package in a folder as a plugin, in name order. A plugin
+    /// that fails to load is skipped and its error kept.
+    pub fn load_dir(dir: &Path) -> Self {
+        let mut host = Self::default();
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return host;
+        };
+        let mut roots: Vec<PathBuf> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .collect();
+        roots.sort();
+        for root in roots {
+            if let Err(e) = host.load_plugin(&root) {
+                let name = root.file_name().unwrap_or_default().to_string_lossy();
+                host.errors.push(format!("{name}: {e}"));
+            }
+        }
+        host
+    }
+
+    /// Run a plugin package's entry point and `activate()`, then apply what
+    /// it registered
+    fn load_plugin(&mut self, root: &Path) -> Result<(), String> {
+        let layout = PackageLayout::discover(root).map_err(|e| e.to_string())?;
+        let name = Manifest::from_path(&layout.manifest_path)
+            .map_err(|e| e.to_string())?
+            .package
+            .name;
+        let entry = layout
+            .lib_path()
+            .or_else(|| layout.main_path())
+            .ok_or("no src/lib.strat or src/main.strat")?;
+        let source = std::fs::read_to_string(&entry).map_err(|e| e.to_string())?;
+
+        let mut vm = VM::new();
+        vm.register_namespace("Workshop", workshop_method, workshop_access);
+        take_registrations();
+        let result = run_source(&mut vm, &entry.display().to_string(), &source).and_then(|()| {
+            if vm.globals().contains_key("activate") {
+                activate(&mut vm)
+            } else {
+                Ok(())
+            }
+        });
+        if let Err(e) = result {
+            take_registrations();
+            return Err(e);
+        }
+
+        self.plugins.push(Plugin {
+            name,
+            executor: CallbackExecutor::new(
+                Rc::new(RefCell::new(vm)),
+                Rc::new(RefCell::new(CallbackRegistry::new())),
+            ),
+        });
+        let index = self.plugins.len() - 1;
+        if let Err(e) = self.apply_registrations(index) {
+            self.commands.retain(|command| command.plugin != index);
+            self.panels.retain(|panel| panel.plugin != index);
+            self.decorators.retain(|(plugin, _)| *plugin != index);
+            self.plugins.pop();
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Register what a plugin asked for since the last call, returning its
+    /// status messages
+    fn apply_registrations(&mut self, plugin: usize) -> Result<Vec<String>, String> {
+        let mut status = Vec::new();
+        for registration in take_registrations() {
+            let registry = self.plugins[plugin].executor.registry();
+            let register = |callback: Value| {
+                registry
+                    .borrow_mut()
+                    .register_value(callback)
+                    .map_err(|e| e.to_string())
+            };
+            match registration {
+                Registration::Command { title, callback } => {
+                    let callback = register(callback)?;
+                    self.commands.push(Command {
+                        plugin,
+                        title,
+                        callback,
+                        chord: None,
+                    });
+                }
+                Registration::Keybinding { chord, command } => {
+                    let command = self
+                        .commands
+                        .iter_mut()
+                        .find(|c| c.plugin == plugin && c.title == command)
+                        .ok_or_else(|| format!("no command '{command}' to bind {chord} to"))?;
+                    command.chord = Some(chord);
+                }
+                Registration::Panel { title, callback } => {
+                    let callback = register(callback)?;
+                    self.panels.push(Panel {
+                        plugin,
+                        title,
+                        callback,
+                        content: String::new(),
+                    });
+                }
+                Registration::Decorations(callback) => {
+                    let callback = register(callback)?;
+                    self.decorators.push((plugin, callback));
+                }
+                Registration::Status(message) => status.push(message),
+            }
+        }
+        Ok(status)
+    }
+
+    /// Names of the loaded plugins
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.plugins.iter().map(|plugin| plugin.name.as_str())
+    }
+
+    pub fn commands(&self) -> &[Command] {
+        &self.commands
+    }
+
+    pub fn panels(&self) -> &[Panel] {
+        &self.panels
+    }
+
+    /// Why plugins failed to load
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+
+    /// The command bound to a key chord
+    pub fn command_for(&self, chord: &KeyChord) -> Option<usize> {
+        self.commands
+            .iter()
+            .position(|command| command.chord.as_ref() == Some(chord))
+    }
+
+    /// Run a command
+    pub fn run_command(
+        &mut self,
+        index: usize,
+        context: &Context,
+    ) -> Result<CommandOutput, String> {
+        let command = self.commands.get(index).ok_or("no such command")?;
+        let (plugin, callback) = (command.plugin, command.callback);
+        let value = self.call(plugin, callback, context)?;
+        Ok(CommandOutput {
+            text: match value {
+                Value::String(text) => Some(text.to_string()),
+                _ => None,
+            },
+            status: self.apply_registrations(plugin)?,
+        })
+    }
+
+    /// Ask every panel for its contents. A panel that fails shows its error.
+    pub fn refresh_panels(&mut self, context: &Context) {
+        for index in 0..self.panels.len() {
+            let Panel {
+                plugin, callback, ..
+            } = self.panels[index];
+            let content = match self.call(plugin, callback, context) {
+                Ok(Value::String(text)) => text.to_string(),
+                Ok(Value::Null) => String::new(),
+                Ok(value) => value.to_string(),
+                Err(e) => format!("Error: {e}"),
+            };
+            take_registrations();
+            self.panels[index].content = content;
+        }
+    }
+
+    /// Decorations of every plugin for the editor, by line
+    pub fn decorations(&self, context: &Context) -> Result<Vec<Decoration>, String> {
+        let mut decorations = Vec::new();
+        for index in 0..self.decorators.len() {
+            let (plugin, callback) = self.decorators[index];
+            let value = self.call(plugin, callback, context);
+            take_registrations();
+            decorations.extend(parse_decorations(&value?)?);
+        }
+        decorations.sort_by_key(|decoration| decoration.line);
+        Ok(decorations)
+    }
+
+    fn call(
+        &self,
+        plugin: usize,
+        callback: CallbackId,
+        context: &Context,
+    ) -> Result<Value, String> {
+        let plugin = &self.plugins[plugin];
+        plugin
+            .executor
+            .execute(callback, vec![context_value(context)])
+            .map_err(|e| format!("{}: {e}", plugin.name))
+    }
+}
+
+/// Folder the Workshop loads plugins from
+pub fn plugin_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("stratum").join("workshop").join("plugins"))
+}
+
+/// Parse, compile and run Stratum source in a VM
+fn run_source(vm: &mut VM, name: &str, source: &str) -> Result<(), String> {
+    let module = Parser::parse_module(source).map_err(|errors| join_errors(&errors))?;
+    let function = Compiler::with_source(name)
+        .compile_module(&module)
+        .map_err(|errors| join_errors(&errors))?;
+    vm.run(function).map(|_| ()).map_err(|e| e.to_string())
+}
+
+/// Call a plugin's `activate()`
+fn activate(vm: &mut VM) -> Result<(), String> {
+    let call = Parser::parse_expression("activate()").map_err(|errors| join_errors(&errors))?;
+    let function = Compiler::new()
+        .compile_expression(&call)
+        .map_err(|errors| join_errors(&errors))?;
+    vm.run(function).map(|_| ()).map_err(|e| e.to_string())
+}
+
+fn join_errors(errors: &[impl fmt::Display]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn take_registrations() -> Vec<Registration> {
+    PENDING_REGISTRATIONS.with(|pending| std::mem::take(&mut *pending.borrow_mut()))
+}
+
+/// Handler of the `Workshop` namespace
+fn workshop_method(method: &str, args: &[Value]) -> Result<Value, String> {
+    let string = |index: usize| match args.get(index) {
+        Some(Value::String(s)) => Ok(s.to_string()),
+        _ => Err(format!(
+            "Workshop.{method} expects a string argument {}",
+            index + 1
+        )),
+    };
+    let callback = |index: usize| match args.get(index) {
+        Some(value @ (Value::Closure(_) | Value::NativeFunction(_))) => Ok(value.clone()),
+        _ => Err(format!(
+            "Workshop.{method} expects a function argument {}",
+            index + 1
+        )),
+    };
+    let registration = match method {
+        "command" => Registration::Command {
+            title: string(0)?,
+            callback: callback(1)?,
+        },
+        "keybinding" => Registration::Keybinding {
+            chord: KeyChord::parse(&string(0)?)?,
+            command: string(1)?,
+        },
+        "panel" => Registration::Panel {
+            title: string(0)?,
+            callback: callback(1)?,
+        },
+        "decorations" => Registration::Decorations(callback(0)?),
+        "status" => Registration::Status(string(0)?),
+        _ => return Err(format!("Workshop has no method '{method}'")),
+    };
+    PENDING_REGISTRATIONS.with(|pending| pending.borrow_mut().push(registration));
+    Ok(Value::Null)
+}
+
+/// What `Workshop` methods need from the VM's permissions: nothing, as they
+/// only record what the plugin registers
+fn workshop_access(method: &str, _args: &[Value]) -> Option<Vec<Access>> {
+    match method {
+        "command" | "keybinding" | "panel" | "decorations" | "status" => Some(Vec::new()),
+        _ => None,
+    }
+}
+
+/// The context map callbacks are called with
+fn context_value(context: &Context) -> Value {
+    let path = context
+        .path
+        .as_ref()
+        .map_or(Value::Null, |p| Value::string(p.display().to_string()));
+    let line = i64::try_from(context.line).unwrap_or(i64::MAX);
+    let map: ValueMap = [
+        ("path", path),
+        ("text", Value::string(context.text.clone())),
+        ("line", Value::Int(line)),
+    ]
+    .into_iter()
+    .map(|(key, value)| (HashableValue::String(Rc::new(key.to_string())), value))
+    .collect();
+    Value::map(map)
+}
+
+/// Decorations from a list of `[line, text]` pairs
+fn parse_decorations(value: &Value) -> Result<Vec<Decoration>, String> {
+    let invalid = || "decorations must be a list of [line, text] pairs".to_string();
+    let Value::List(items) = value else {
+        return Err(invalid());
+    };
+    let items = items.borrow();
+    items
+        .iter()
+        .map(|item| {
+            let Value::List(pair) = item else {
+                return Err(invalid());
+            };
+            match pair.borrow().as_slice() {
+                [Value::Int(line), text] if *line > 0 => Ok(Decoration {
+                    line: usize::try_from(*line).map_err(|_| invalid())?,
+                    text: match text {
+                        Value::String(s) => s.to_string(),
+                        other => other.to_string(),
+                    },
+                }),
+                _ => Err(invalid()),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_plugin(dir: &Path, name: &str, source: &str) {
+        let root = dir.join(name);
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("stratum.toml"),
+            format!("[package]\nname = \"{name}\"\nversion = \"0.1.0\"\nedition = \"2025\"\n"),
+        )
+        .unwrap();
+        std::fs::write(root.join("src").join("lib.strat"), source).unwrap();
+    }
+
+    #[test]
+    fn test_key_chord() {
+        let chord = KeyChord::parse("Ctrl+Shift+U").unwrap();
+        assert_eq!(chord.to_string(), "Ctrl+Shift+U");
+        let pressed = KeyChord::from_key(
+            &keyboard::Key::Character("U".into()),
+            Modifiers::COMMAND | Modifiers::SHIFT,
+        );
+        assert_eq!(pressed, Some(chord));
+
+        assert!(
+            KeyChord::from_key(&keyboard::Key::Character("u".into()), Modifiers::SHIFT).is_none()
+        );
+        assert!(KeyChord::parse("shift+u").is_err());
+        assert!(KeyChord::parse("ctrl+").is_err());
+        assert!(KeyChord::parse("hyper+u").is_err());
+    }
+
+    #[test]
+    fn test_load_and_run_plugins() {
+        let dir = tempfile::tempdir().unwrap();
+        write_plugin(
+            dir.path(),
+            "upper",
+            r#"
+fx activate() {
+    Workshop.command("Uppercase", |ctx| {
+        Workshop.status("Uppercased")
+        ctx["text"].to_upper()
+    })
+    Workshop.keybinding("ctrl+shift+u", "Uppercase")
+    Workshop.panel("Line", |ctx| "line {ctx["line"]}")
+    Workshop.decorations(|ctx| [[2, "second"], [1, "first"]])
+}
+"#,
+        );
+        write_plugin(dir.path(), "broken", "fx activate( {");
+
+        let mut host = PluginHost::load_dir(dir.path());
+        assert_eq!(host.names().collect::<Vec<_>>(), ["upper"]);
+        assert_eq!(host.errors().len(), 1);
+        assert!(host.errors()[0].starts_with("broken: "));
+
+        let context = Context {
+            path: None,
+            text: "hello".to_string(),
+            line: 3,
+        };
+        let chord = KeyChord::parse("ctrl+shift+u").unwrap();
+        let index = host.command_for(&chord).unwrap();
+        assert_eq!(host.commands()[index].title, "Uppercase");
+        let output = host.run_command(index, &context).unwrap();
+        assert_eq!(output.text.as_deref(), Some("HELLO"));
+        assert_eq!(output.status, ["Uppercased"]);
+
+        host.refresh_panels(&context);
+        assert_eq!(host.panels()[0].content, "line 3");
+
+        let decorations = host.decorations(&context).unwrap();
+        assert_eq!(decorations.len(), 2);
+        assert_eq!(decorations[0].text, "first");
+    }
+
+    #[test]
+    fn test_invalid_registrations() {
+        let dir = tempfile::tempdir().unwrap();
+        write_plugin(
+            dir.path(),
+            "unbound",
+            "fx activate() {\n    Workshop.keybinding(\"ctrl+k\", \"Missing\")\n}\n",
+        );
+        write_plugin(
+            dir.path(),
+            "unknown",
+            "fx activate() {\n    Workshop.menu(\"File\")\n}\n",
+        );
+        let host = PluginHost::load_dir(dir.path());
+        assert_eq!(host.errors().len(), 2);
+        assert!(host.errors()[0].contains("no command 'Missing'"));
+        assert!(host.errors()[1].contains("Workshop has no method 'menu'"));
+    }
+
+    #[test]
+    fn test_parse_decorations() {
+        let pair = |line, text: &str| Value::list(vec![Value::Int(line), Value::string(text)]);
+        let value = Value::list(vec![pair(4, "TODO")]);
+        assert_eq!(
+            parse_decorations(&value).unwrap(),
+            [Decoration {
+                line: 4,
+                text: "TODO".to_string()
+            }]
+        );
+        assert!(parse_decorations(&Value::list(vec![pair(0, "x")])).is_err());
+        assert!(parse_decorations(&Value::string("x")).is_err());
+    }
+}
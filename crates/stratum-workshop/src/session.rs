@@ -0,0 +1,181 @@
+//! Session restore
+//!
+//! When the Workshop exits it saves a session for the opened folder: the
+//! editor tabs with their cursors and unsaved text, the panels shown, the
+//! search, the run configuration picked, and the window size. Opening the folder again, or launching
+//! without a path after working in it, restores the session, so unsaved
+//! changes survive exiting without a prompt.
+
+use crate::panels::SearchQuery;
+use crate::tabs::SplitAxis;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Everything restored for a folder, or for no folder
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    pub folder: Option<PathBuf>,
+    pub axis: SplitAxis,
+    pub groups: Vec<GroupSession>,
+    pub focused_group: usize,
+    pub panels: PanelSession,
+    /// Search query, run again on restore
+    pub search: Option<SearchQuery>,
+    /// Files whose search matches are hidden
+    pub collapsed_files: Vec<PathBuf>,
+    /// Open notebook file
+    pub notebook: Option<PathBuf>,
+    /// Window width and height
+    pub window_size: Option<(f32, f32)>,
+    /// Name of the run configuration the Run button runs
+    pub run_target: Option<String>,
+}
+
+/// Tabs of an editor group
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GroupSession {
+    pub tabs: Vec<TabSession>,
+    pub active: usize,
+}
+
+/// An editor tab
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TabSession {
+    /// File, or `None` for an untitled tab
+    pub path: Option<PathBuf>,
+    /// Cursor line and column (0-based)
+    pub line: usize,
+    pub column: usize,
+    /// Text not saved to the file, restored in place of the file
+    pub unsaved: Option<String>,
+}
+
+/// Which panels are shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PanelSession {
+    pub editor: bool,
+    pub search: bool,
+    pub terminal: bool,
+    pub source_control: bool,
+    pub plugins: bool,
+    pub run_configs: bool,
+}
+
+impl Default for PanelSession {
+    fn default() -> Self {
+        Self {
+            editor: true,
+            search: false,
+            terminal: false,
+            source_control: false,
+            plugins: false,
+            run_configs: false,
+        }
+    }
+}
+
+impl Session {
+    /// The saved session of a folder, or of no folder
+    pub fn load(folder: Option<&Path>) -> Option<Self> {
+        Self::load_from(&session_dir()?, folder)
+    }
+
+    /// The session of a folder saved in a sessions folder. A session saved
+    /// for another folder under the same file name doesn't count.
+    pub fn load_from(dir: &Path, folder: Option<&Path>) -> Option<Self> {
+        let content = std::fs::read_to_string(dir.join(file_name(folder))).ok()?;
+        let session: Self = serde_json::from_str(&content).ok()?;
+        (session.folder.as_deref() == folder).then_some(session)
+    }
+
+    /// Save the session in the user's sessions folder
+    pub fn save(&self) -> std::io::Result<()> {
+        let dir = session_dir().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, "Config directory not found")
+        })?;
+        self.save_to(&dir)
+    }
+
+    /// Save the session in a sessions folder
+    pub fn save_to(&self, dir: &Path) -> std::io::Result<()> {
+        std::fs::create_dir_all(dir)?;
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        std::fs::write(dir.join(file_name(self.folder.as_deref())), content)
+    }
+}
+
+/// Folder the Workshop saves sessions in
+pub fn session_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("stratum").join("workshop").join("sessions"))
+}
+
+/// File a folder's session is saved in, named after the folder's path
+fn file_name(folder: Option<&Path>) -> String {
+    match folder {
+        Some(folder) => {
+            let name: String = folder
+                .to_string_lossy()
+                .chars()
+                .map(|c| if c.is_alphanumeric() { c } else { '_' })
+                .collect();
+            format!("{}.json", name.trim_matches('_'))
+        }
+        None => "default.json".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_name() {
+        assert_eq!(file_name(None), "default.json");
+        assert_eq!(
+            file_name(Some(Path::new("/home/ada/my-project"))),
+            "home_ada_my_project.json"
+        );
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let folder = PathBuf::from("/home/ada/project");
+        let session = Session {
+            folder: Some(folder.clone()),
+            axis: SplitAxis::Rows,
+            groups: vec![GroupSession {
+                tabs: vec![TabSession {
+                    path: None,
+                    line: 1,
+                    column: 2,
+                    unsaved: Some("let x = 1".to_string()),
+                }],
+                active: 0,
+            }],
+            window_size: Some((800.0, 600.0)),
+            ..Session::default()
+        };
+        session.save_to(dir.path()).unwrap();
+
+        assert_eq!(Session::load_from(dir.path(), Some(&folder)), Some(session));
+        assert_eq!(Session::load_from(dir.path(), None), None);
+
+        // A folder whose name maps to the same file gets no session
+        assert_eq!(
+            Session::load_from(dir.path(), Some(Path::new("/home/ada_project"))),
+            None
+        );
+    }
+
+    #[test]
+    fn test_missing_fields_default() {
+        let session: Session = serde_json::from_str(r#"{"panels": {"search": true}}"#).unwrap();
+        assert!(session.panels.editor);
+        assert!(session.panels.search);
+        assert!(session.groups.is_empty());
+    }
+}
@@ -0,0 +1,381 @@
+//! Editor tabs in split groups
+//!
+//! The editor area splits into groups, side by side or stacked, each with
+//! its own tabs. The workshop keeps the focused group's active tab itself,
+//! so its slot here is empty: every change puts that tab back in its slot,
+//! rearranges the tabs, and hands out the focused tab again.
+
+use serde::{Deserialize, Serialize};
+
+/// How editor groups are arranged
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SplitAxis {
+    /// Side by side
+    #[default]
+    Columns,
+    /// Stacked
+    Rows,
+}
+
+/// The editor split, saved in the layout config
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SplitLayout {
+    pub axis: SplitAxis,
+    /// Number of groups
+    pub groups: usize,
+}
+
+impl Default for SplitLayout {
+    fn default() -> Self {
+        Self {
+            axis: SplitAxis::Columns,
+            groups: 1,
+        }
+    }
+}
+
+/// Tabs in split groups, except the focused tab
+#[derive(Debug)]
+pub struct TabGroups<T> {
+    groups: Vec<Group<T>>,
+    focused: usize,
+    axis: SplitAxis,
+}
+
+#[derive(Debug)]
+struct Group<T> {
+    /// Tabs in order; `None` is the focused tab, held by the caller
+    tabs: Vec<Option<T>>,
+    active: usize,
+}
+
+impl<T> Group<T> {
+    fn new() -> Self {
+        Self {
+            tabs: Vec::new(),
+            active: 0,
+        }
+    }
+}
+
+impl<T> TabGroups<T> {
+    /// Empty groups in a saved layout
+    pub fn new(layout: SplitLayout) -> Self {
+        Self {
+            groups: (0..layout.groups.max(1)).map(|_| Group::new()).collect(),
+            focused: 0,
+            axis: layout.axis,
+        }
+    }
+
+    pub fn layout(&self) -> SplitLayout {
+        SplitLayout {
+            axis: self.axis,
+            groups: self.groups.len(),
+        }
+    }
+
+    pub fn axis(&self) -> SplitAxis {
+        self.axis
+    }
+
+    pub fn group_count(&self) -> usize {
+        self.groups.len()
+    }
+
+    pub fn focused(&self) -> usize {
+        self.focused
+    }
+
+    /// Whether no group has tabs
+    pub fn is_empty(&self) -> bool {
+        self.groups.iter().all(|group| group.tabs.is_empty())
+    }
+
+    /// Tabs of a group, with the focused tab filled in, and the index of its
+    /// active tab
+    pub fn group<'a>(&'a self, index: usize, current: Option<&'a T>) -> (Vec<&'a T>, usize) {
+        let Some(group) = self.groups.get(index) else {
+            return (Vec::new(), 0);
+        };
+        let tabs = group
+            .tabs
+            .iter()
+            .filter_map(|tab| tab.as_ref().or(current))
+            .collect();
+        (tabs, group.active)
+    }
+
+    /// All tabs, the focused one first
+    pub fn iter<'a>(&'a self, current: Option<&'a T>) -> impl Iterator<Item = &'a T> {
+        current.into_iter().chain(
+            self.groups
+                .iter()
+                .flat_map(|group| group.tabs.iter().flatten()),
+        )
+    }
+
+    /// All tabs mutably, the focused one first
+    pub fn iter_mut<'a>(
+        &'a mut self,
+        current: &'a mut Option<T>,
+    ) -> impl Iterator<Item = &'a mut T> {
+        current.iter_mut().chain(
+            self.groups
+                .iter_mut()
+                .flat_map(|group| group.tabs.iter_mut().flatten()),
+        )
+    }
+
+    /// Group and index of the first tab matching a predicate
+    pub fn position(
+        &self,
+        current: Option<&T>,
+        predicate: impl Fn(&T) -> bool,
+    ) -> Option<(usize, usize)> {
+        self.groups.iter().enumerate().find_map(|(g, group)| {
+            let tab = group
+                .tabs
+                .iter()
+                .position(|tab| tab.as_ref().or(current).is_some_and(&predicate))?;
+            Some((g, tab))
+        })
+    }
+
+    /// Open a tab in the focused group and focus it
+    pub fn open(&mut self, current: &mut Option<T>, tab: T) {
+        self.put_back(current);
+        let group = &mut self.groups[self.focused];
+        group.tabs.push(Some(tab));
+        group.active = group.tabs.len() - 1;
+        self.take_focused(current);
+    }
+
+    /// Focus a tab, or a group when it has no tab at that index
+    pub fn focus(&mut self, current: &mut Option<T>, group: usize, tab: usize) {
+        if group >= self.groups.len() {
+            return;
+        }
+        self.put_back(current);
+        self.focused = group;
+        let group = &mut self.groups[group];
+        if tab < group.tabs.len() {
+            group.active = tab;
+        }
+        self.take_focused(current);
+    }
+
+    /// Close the focused tab and return it. A group left without tabs is
+    /// removed, unless it is the only one; closing in an empty group removes
+    /// the group.
+    pub fn close(&mut self, current: &mut Option<T>) -> Option<T> {
+        let closed = current.take();
+        let group = &mut self.groups[self.focused];
+        if !group.tabs.is_empty() {
+            group.tabs.remove(group.active);
+            group.active = group.active.min(group.tabs.len().saturating_sub(1));
+        }
+        if closed.is_none() || group.tabs.is_empty() {
+            self.remove_group_if_empty(self.focused);
+        }
+        self.take_focused(current);
+        closed
+    }
+
+    /// Add a group after the focused one, arranging all groups along an
+    /// axis, and focus it. The active tab moves to the new group when it
+    /// leaves others behind.
+    pub fn split(&mut self, current: &mut Option<T>, axis: SplitAxis) {
+        self.put_back(current);
+        self.axis = axis;
+        let mut group = Group::new();
+        let source = &mut self.groups[self.focused];
+        if source.tabs.len() > 1 {
+            group.tabs.push(source.tabs.remove(source.active));
+            source.active = source.active.min(source.tabs.len() - 1);
+        }
+        self.focused += 1;
+        self.groups.insert(self.focused, group);
+        self.take_focused(current);
+    }
+
+    /// Move a tab to the end of another group and focus it there
+    pub fn move_tab(&mut self, current: &mut Option<T>, from: (usize, usize), to: usize) {
+        let (group, tab) = from;
+        if group == to
+            || to >= self.groups.len()
+            || self.groups.get(group).map_or(0, |g| g.tabs.len()) <= tab
+        {
+            return;
+        }
+        self.put_back(current);
+        let source = &mut self.groups[group];
+        let moved = source.tabs.remove(tab);
+        if source.active > tab || source.active == source.tabs.len() {
+            source.active = source.active.saturating_sub(1);
+        }
+        let target = &mut self.groups[to];
+        target.tabs.push(moved);
+        target.active = target.tabs.len() - 1;
+        self.focused = to;
+        self.remove_group_if_empty(group);
+        self.take_focused(current);
+    }
+
+    /// Put the focused tab back in its slot
+    fn put_back(&mut self, current: &mut Option<T>) {
+        let Some(tab) = current.take() else {
+            return;
+        };
+        let group = &mut self.groups[self.focused];
+        if let Some(slot @ None) = group.tabs.get_mut(group.active) {
+            *slot = Some(tab);
+        } else {
+            group.tabs.push(Some(tab));
+            group.active = group.tabs.len() - 1;
+        }
+    }
+
+    /// Hand out the focused tab, leaving its slot empty
+    fn take_focused(&mut self, current: &mut Option<T>) {
+        let group = &mut self.groups[self.focused];
+        *current = group.tabs.get_mut(group.active).and_then(Option::take);
+    }
+
+    /// Remove a group without tabs, keeping at least one group
+    fn remove_group_if_empty(&mut self, index: usize) {
+        if self.groups.len() > 1 && self.groups[index].tabs.is_empty() {
+            self.groups.remove(index);
+            if self.focused > index || self.focused == self.groups.len() {
+                self.focused -= 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tab names of each group, the active one marked with `*`
+    fn names(tabs: &TabGroups<&'static str>, current: Option<&&'static str>) -> Vec<String> {
+        (0..tabs.group_count())
+            .map(|g| {
+                let (group, active) = tabs.group(g, current);
+                group
+                    .iter()
+                    .enumerate()
+                    .map(|(i, name)| {
+                        if i == active {
+                            format!("*{name}")
+                        } else {
+                            (**name).to_string()
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_open_focus_and_close() {
+        let mut tabs = TabGroups::new(SplitLayout::default());
+        let mut current = None;
+        tabs.open(&mut current, "a");
+        tabs.open(&mut current, "b");
+        assert_eq!(current, Some("b"));
+        assert_eq!(names(&tabs, current.as_ref()), ["a *b"]);
+
+        tabs.focus(&mut current, 0, 0);
+        assert_eq!(current, Some("a"));
+        assert_eq!(tabs.iter(current.as_ref()).count(), 2);
+        assert_eq!(tabs.position(current.as_ref(), |t| *t == "b"), Some((0, 1)));
+
+        assert_eq!(tabs.close(&mut current), Some("a"));
+        assert_eq!(current, Some("b"));
+        assert_eq!(tabs.close(&mut current), Some("b"));
+        assert_eq!(current, None);
+        assert!(tabs.is_empty());
+        assert_eq!(tabs.group_count(), 1);
+    }
+
+    #[test]
+    fn test_split_and_move_tabs() {
+        let mut tabs = TabGroups::new(SplitLayout::default());
+        let mut current = None;
+        for name in ["a", "b", "c"] {
+            tabs.open(&mut current, name);
+        }
+
+        // The active tab moves to the new group
+        tabs.split(&mut current, SplitAxis::Rows);
+        assert_eq!(names(&tabs, current.as_ref()), ["a *b", "*c"]);
+        assert_eq!(tabs.focused(), 1);
+        assert_eq!(
+            tabs.layout(),
+            SplitLayout {
+                axis: SplitAxis::Rows,
+                groups: 2
+            }
+        );
+
+        tabs.move_tab(&mut current, (0, 0), 1);
+        assert_eq!(names(&tabs, current.as_ref()), ["*b", "c *a"]);
+        assert_eq!(current, Some("a"));
+
+        for tab in tabs.iter_mut(&mut current) {
+            *tab = if *tab == "b" { "B" } else { tab };
+        }
+
+        // Moving the last tab out of a group removes it
+        tabs.move_tab(&mut current, (0, 0), 1);
+        assert_eq!(names(&tabs, current.as_ref()), ["c a *B"]);
+        assert_eq!(tabs.focused(), 0);
+    }
+
+    #[test]
+    fn test_empty_groups() {
+        let mut tabs = TabGroups::new(SplitLayout {
+            axis: SplitAxis::Columns,
+            groups: 3,
+        });
+        let mut current = None;
+        tabs.focus(&mut current, 2, 0);
+        tabs.open(&mut current, "a");
+        assert_eq!(names(&tabs, current.as_ref()), ["", "", "*a"]);
+
+        // Closing in an empty group removes the group
+        tabs.focus(&mut current, 1, 0);
+        assert_eq!(current, None);
+        tabs.close(&mut current);
+        assert_eq!(names(&tabs, current.as_ref()), ["", "*a"]);
+        assert_eq!(tabs.focused(), 1);
+        assert_eq!(current, Some("a"));
+
+        // A single tab splits into an empty group
+        tabs.split(&mut current, SplitAxis::Columns);
+        assert_eq!(names(&tabs, current.as_ref()), ["", "*a", ""]);
+        assert_eq!(current, None);
+    }
+
+    #[test]
+    fn test_layout_serialization() {
+        let layout = SplitLayout {
+            axis: SplitAxis::Rows,
+            groups: 2,
+        };
+        let json = serde_json::to_string(&layout).unwrap();
+        assert_eq!(json, r#"{"axis":"rows","groups":2}"#);
+        assert_eq!(TabGroups::<()>::new(layout).layout(), layout);
+        assert_eq!(
+            TabGroups::<()>::new(SplitLayout {
+                groups: 0,
+                ..layout
+            })
+            .group_count(),
+            1
+        );
+    }
+}
@@ -3,22 +3,79 @@
 //! A clean, minimal IDE focused on the REPL with optional file editing.
 //! Inspired by Python's IDLE - simple, approachable, effective.
 
-use crate::panels::{ReplMessage, ReplPanel};
+use crate::config::WorkshopConfig;
+use crate::keymap::{handling, EditorKey, Handling, Keymap, KeymapState, Mode};
+use crate::language::{apply_completion, cursor_position, is_error, move_cursor, Language};
+use crate::panels::{
+    plan_replace, profile_source, search_folder, CellKind, FileEdit, Flamegraph, LineChange,
+    NotebookAction, NotebookMessage, NotebookPanel, Profile, ProfilerAction, ProfilerMessage,
+    ProfilerPanel, ReplMessage, ReplPanel, RunConfigAction, RunConfigMessage, RunConfigPanel,
+    RunTarget, SearchAction, SearchMessage, SearchPanel, SearchQuery, SourceControlAction,
+    SourceControlMessage, SourceControlPanel, TableAction, TableMessage, TableViewer,
+    TerminalAction, TerminalMessage, TerminalPanel,
+};
+use crate::plugins::{plugin_dir, Context, Decoration, KeyChord, PluginHost};
+use crate::session::{GroupSession, PanelSession, Session, TabSession};
+use crate::tabs::{SplitAxis, SplitLayout, TabGroups};
+use iced::futures::channel::oneshot;
 use iced::keyboard;
 use iced::keyboard::key;
-use iced::widget::{button, column, container, row, rule, scrollable, text, text_editor, Space};
-use iced::{Color, Element, Length, Subscription, Task, Theme};
+use iced::widget::{
+    button, column, container, mouse_area, pick_list, row, rule, scrollable, text, text_editor,
+    Column, Row, Space,
+};
+use iced::{Color, Element, Length, Size, Subscription, Task, Theme};
 use rfd::AsyncFileDialog;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+/// Problems listed under the editor
+const MAX_PROBLEMS: usize = 5;
+/// Instructions between samples when profiling the editor's file, finer
+/// than `stratum profile` since scripts run here are often short
+const PROFILE_INTERVAL: u64 = 100;
 
 /// Main application state
 pub struct Workshop {
     /// The REPL panel (main focus)
     pub repl: ReplPanel,
-    /// Optional editor state (when a file is open)
+    /// Focused editor tab, if any
     editor: Option<EditorState>,
+    /// Editor tabs in split groups, except the focused one
+    tabs: TabGroups<EditorState>,
+    /// Tab being dragged to another group, by group and index
+    dragging: Option<(usize, usize)>,
     /// Whether to show the editor pane
     show_editor: bool,
+    /// Open notebook, if any
+    notebook: Option<NotebookPanel>,
+    /// Table viewer for the last DataFrame or Cube result
+    table: Option<TableViewer>,
+    /// Flamegraph and allocations of the last profiled run
+    profiler: Option<ProfilerPanel>,
+    /// Whether a profiled run is in progress
+    profiling: bool,
+    /// Opened folder, searched by the search panel
+    folder: Option<PathBuf>,
+    /// Search panel, when shown
+    search: Option<SearchPanel>,
+    /// Terminal panel, when shown
+    terminal: Option<TerminalPanel>,
+    /// Git repository of the opened folder, if it is in one
+    git: Option<SourceControlPanel>,
+    /// Whether to show the source control panel
+    show_source_control: bool,
+    /// Plugins loaded at startup
+    plugins: PluginHost,
+    /// Whether to show the plugins panel
+    show_plugins: bool,
+    /// Run configurations of the opened folder
+    run_configs: RunConfigPanel,
+    /// Whether to show the run configurations panel
+    show_run_configs: bool,
+    /// Editor keymap, with Vim's mode and registers
+    keymap: KeymapState,
+    /// Window size, saved in the session
+    window_size: Option<Size>,
     /// Modal dialog state
     modal: Option<ModalState>,
     /// Status message
@@ -33,6 +90,56 @@ struct EditorState {
     content: text_editor::Content,
     /// Whether the file has been modified
     modified: bool,
+    /// Lines that differ from the file at `HEAD`, for the gutter
+    line_changes: Vec<(usize, LineChange)>,
+    /// Diagnostics, completions and hover information, for Stratum files
+    language: Option<Language>,
+    /// Notes plugins attached to lines, for the gutter
+    decorations: Vec<Decoration>,
+}
+
+impl EditorState {
+    /// File name shown in the tab and window title
+    fn name(&self) -> String {
+        self.path
+            .as_ref()
+            .and_then(|p| p.file_name())
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "Untitled".to_string())
+    }
+
+    /// The tab as saved in a session, with its text when unsaved
+    fn tab_session(&self) -> TabSession {
+        let cursor = self.content.cursor().position;
+        TabSession {
+            path: self.path.clone(),
+            line: cursor.line,
+            column: cursor.column,
+            unsaved: self.modified.then(|| self.content.text()),
+        }
+    }
+
+    /// A tab restored from a session, or `None` when its file is gone
+    fn restore(tab: &TabSession) -> Option<Self> {
+        let (text, modified) = match (&tab.unsaved, &tab.path) {
+            (Some(text), _) => (text.clone(), true),
+            (None, Some(path)) => (std::fs::read_to_string(path).ok()?, false),
+            (None, None) => (String::new(), false),
+        };
+        let mut content = text_editor::Content::with_text(&text);
+        let lines: Vec<&str> = text.split('\n').collect();
+        let line = tab.line.min(lines.len() - 1);
+        let column = tab.column.min(lines[line].chars().count());
+        move_cursor(&mut content, line, column);
+        Some(Self {
+            language: Language::for_file(tab.path.as_deref(), &text),
+            path: tab.path.clone(),
+            content,
+            modified,
+            line_changes: Vec::new(),
+            decorations: Vec::new(),
+        })
+    }
 }
 
 /// Modal dialog types
@@ -58,18 +165,97 @@ pub enum WorkshopMessage {
     // File operations
     NewFile,
     OpenFile,
+    OpenFolder,
     SaveFile,
     CloseFile,
 
     // Editor
     EditorAction(text_editor::Action),
     ToggleEditor,
+    TriggerCompletion,
+    NextCompletion,
+    PreviousCompletion,
+    /// Insert a completion, or the selected one
+    AcceptCompletion(Option<usize>),
+    CloseCompletion,
+    GotoDefinition,
+    /// A key handled by the Vim or Emacs keymap
+    EditorKey(EditorKey),
+    /// Switch keymaps and remember the choice
+    SetKeymap(Keymap),
+    /// Move the cursor to a line and column (0-based)
+    GotoLine {
+        line: usize,
+        column: usize,
+    },
+
+    // Editor tabs
+    FocusTab {
+        group: usize,
+        tab: usize,
+    },
+    /// Focus a tab and start dragging it
+    DragTab {
+        group: usize,
+        tab: usize,
+    },
+    /// Drop the dragged tab on a group
+    DropTab(usize),
+    EndTabDrag,
+    CloseTab {
+        group: usize,
+        tab: usize,
+    },
+    SplitEditor(SplitAxis),
 
     // Run
+    /// Run the selected configuration, or the editor's file in the REPL
     RunFile,
+    /// Pick what the Run button runs
+    SelectRunTarget(RunTarget),
+    RunConfig(usize),
+    ToggleRunConfigs,
+    RunConfigs(RunConfigMessage),
+    /// Run the editor's file under the profiler
+    ProfileFile,
+    ProfileFinished(Result<Profile, String>),
+    /// A speedscope file was picked
+    ProfileOpened(Option<(PathBuf, String)>),
+    Profiler(ProfilerMessage),
+
+    // Notebook
+    NewNotebook,
+    Notebook(NotebookMessage),
+    NotebookSaved(PathBuf),
+
+    // Table viewer
+    Table(TableMessage),
 
     // Dialogs
+    Exported(PathBuf),
+
+    // Search
+    ToggleSearch,
+    Search(SearchMessage),
+
+    // Terminal
+    ToggleTerminal,
+    Terminal(TerminalMessage),
+
+    // Source control
+    ToggleSourceControl,
+    SourceControl(SourceControlMessage),
+
+    // Plugins
+    TogglePlugins,
+    RunPluginCommand(usize),
+    /// A key press that may be bound to a run configuration or a plugin
+    /// command
+    BoundKey(KeyChord),
+    RefreshPluginPanels,
+
     FileDialogOpened(Option<(PathBuf, String)>),
+    FolderOpened(Option<PathBuf>),
     FileSaved(PathBuf),
     FileSaveError(String),
 
@@ -79,6 +265,8 @@ pub enum WorkshopMessage {
     ModalDiscard,
 
     // App
+    WindowResized(Size),
+    /// Save the session and exit
     Exit,
 }
 
@@ -91,10 +279,28 @@ impl Default for Workshop {
 impl Workshop {
     /// Create a new Workshop instance
     pub fn new() -> Self {
+        let config = WorkshopConfig::load();
         Self {
             repl: ReplPanel::new(),
             editor: None,
+            tabs: TabGroups::new(config.layout.editor_split),
+            dragging: None,
             show_editor: false,
+            notebook: None,
+            table: None,
+            profiler: None,
+            profiling: false,
+            folder: None,
+            search: None,
+            terminal: None,
+            git: None,
+            show_source_control: false,
+            plugins: PluginHost::load(),
+            show_plugins: false,
+            run_configs: RunConfigPanel::new(),
+            show_run_configs: false,
+            keymap: KeymapState::new(config.keymap),
+            window_size: None,
             modal: None,
             status: "Ready".to_string(),
         }
@@ -103,12 +309,7 @@ impl Workshop {
     /// Get the window title
     pub fn title(&self) -> String {
         if let Some(editor) = &self.editor {
-            let name = editor
-                .path
-                .as_ref()
-                .and_then(|p| p.file_name())
-                .map(|n| n.to_string_lossy().to_string())
-                .unwrap_or_else(|| "Untitled".to_string());
+            let name = editor.name();
             let modified = if editor.modified { " *" } else { "" };
             format!("{}{} - Stratum Shell", name, modified)
         } else {
@@ -121,20 +322,19 @@ impl Workshop {
         match message {
             WorkshopMessage::Repl(msg) => {
                 self.repl.update(msg);
+                self.open_pending_table();
             }
 
             WorkshopMessage::NewFile => {
-                if self.editor.as_ref().is_some_and(|e| e.modified) {
-                    self.modal = Some(ModalState::UnsavedChanges);
-                } else {
-                    self.editor = Some(EditorState {
-                        path: None,
-                        content: text_editor::Content::new(),
-                        modified: false,
-                    });
-                    self.show_editor = true;
-                    self.status = "New file".to_string();
-                }
+                self.open_editor(EditorState {
+                    path: None,
+                    content: text_editor::Content::new(),
+                    modified: false,
+                    line_changes: Vec::new(),
+                    language: Language::for_file(None, ""),
+                    decorations: Vec::new(),
+                });
+                self.status = "New file".to_string();
             }
 
             WorkshopMessage::OpenFile => {
@@ -142,6 +342,7 @@ impl Workshop {
                     async {
                         let file = AsyncFileDialog::new()
                             .add_filter("Stratum", &["strat", "st"])
+                            .add_filter("Notebook", &["stratnb"])
                             .add_filter("All files", &["*"])
                             .set_title("Open File")
                             .pick_file()
@@ -162,19 +363,188 @@ impl Workshop {
                 );
             }
 
+            WorkshopMessage::OpenFolder => {
+                return Task::perform(
+                    async {
+                        AsyncFileDialog::new()
+                            .set_title("Open Folder")
+                            .pick_folder()
+                            .await
+                            .map(|handle| handle.path().to_path_buf())
+                    },
+                    WorkshopMessage::FolderOpened,
+                );
+            }
+
+            WorkshopMessage::FolderOpened(result) => {
+                if let Some(path) = result {
+                    self.status = format!("Opened folder {}", path.display());
+                    if let Some(terminal) = &mut self.terminal {
+                        terminal.set_cwd(path.clone());
+                    }
+                    self.run_configs.open(path.clone());
+                    self.folder = Some(path);
+                    self.search.get_or_insert_with(SearchPanel::new);
+                    self.open_repository();
+                    self.refresh_gutter();
+                }
+            }
+
+            WorkshopMessage::ToggleSearch => {
+                self.search = match self.search.take() {
+                    Some(_) => None,
+                    None => Some(SearchPanel::new()),
+                };
+            }
+
+            WorkshopMessage::Search(msg) => {
+                let action = self.search.as_mut().and_then(|search| search.update(msg));
+                if let Some(action) = action {
+                    self.handle_search_action(action);
+                }
+            }
+
+            WorkshopMessage::ToggleTerminal => {
+                if self.terminal.take().is_none() {
+                    let cwd = self
+                        .search_root()
+                        .or_else(|| std::env::current_dir().ok())
+                        .unwrap_or_default();
+                    self.terminal = Some(TerminalPanel::new(cwd));
+                    return self.start_terminal();
+                }
+            }
+
+            WorkshopMessage::Terminal(msg) => {
+                let action = self.terminal.as_mut().and_then(|t| t.update(msg));
+                match action {
+                    Some(TerminalAction::Start) => return self.start_terminal(),
+                    Some(TerminalAction::Close) => self.terminal = None,
+                    None => {}
+                }
+            }
+
+            WorkshopMessage::ToggleSourceControl => {
+                if self.git.is_some() {
+                    self.show_source_control = !self.show_source_control;
+                } else {
+                    self.status = "Not in a git repository".to_string();
+                }
+            }
+
+            WorkshopMessage::SourceControl(msg) => {
+                let action = self.git.as_mut().and_then(|git| git.update(msg));
+                match action {
+                    Some(SourceControlAction::Open(path)) => self.open_at_line(path, 1),
+                    Some(SourceControlAction::Changed(status)) => {
+                        self.status = status;
+                        self.refresh_gutter();
+                    }
+                    Some(SourceControlAction::Close) => self.show_source_control = false,
+                    None => {}
+                }
+            }
+
+            WorkshopMessage::TogglePlugins => {
+                self.show_plugins = !self.show_plugins;
+                if self.show_plugins {
+                    self.plugins.refresh_panels(&self.plugin_context());
+                }
+            }
+
+            WorkshopMessage::RunPluginCommand(index) => {
+                let title = self
+                    .plugins
+                    .commands()
+                    .get(index)
+                    .map(|command| command.title.clone())
+                    .unwrap_or_default();
+                match self.plugins.run_command(index, &self.plugin_context()) {
+                    Ok(output) => {
+                        if let (Some(text), Some(editor)) = (output.text, &mut self.editor) {
+                            if text != editor.content.text() {
+                                let line = editor.content.cursor().position.line;
+                                editor.content = text_editor::Content::with_text(&text);
+                                move_cursor(&mut editor.content, line, 0);
+                                editor.modified = true;
+                                if let Some(language) = &mut editor.language {
+                                    language.set_content(text);
+                                }
+                            }
+                        }
+                        self.status = output
+                            .status
+                            .last()
+                            .cloned()
+                            .unwrap_or_else(|| format!("Ran {title}"));
+                        self.refresh_gutter();
+                        if self.show_plugins {
+                            self.plugins.refresh_panels(&self.plugin_context());
+                        }
+                    }
+                    Err(e) => self.status = format!("Plugin error: {e}"),
+                }
+            }
+
+            WorkshopMessage::BoundKey(chord) => {
+                if let Some(index) = self.run_configs.for_chord(&chord) {
+                    return self.run_config(index);
+                }
+                if let Some(index) = self.plugins.command_for(&chord) {
+                    return self.update(WorkshopMessage::RunPluginCommand(index));
+                }
+            }
+
+            WorkshopMessage::RefreshPluginPanels => {
+                self.plugins.refresh_panels(&self.plugin_context());
+            }
+
+            WorkshopMessage::FileDialogOpened(Some((path, content)))
+                if path.extension().is_some_and(|e| e == "stratnb") =>
+            {
+                if self
+                    .notebook
+                    .as_ref()
+                    .is_some_and(NotebookPanel::is_modified)
+                {
+                    self.status = "Save or close the open notebook first".to_string();
+                    return Task::none();
+                }
+                match NotebookPanel::open(path.clone(), &content) {
+                    Ok(notebook) => {
+                        self.notebook = Some(notebook);
+                        self.status = format!("Opened {}", path.display());
+                    }
+                    Err(e) => self.status = e,
+                }
+            }
+
             WorkshopMessage::FileDialogOpened(result) => {
                 if let Some((path, content)) = result {
                     let name = path
                         .file_name()
                         .map(|n| n.to_string_lossy().to_string())
                         .unwrap_or_default();
-                    self.editor = Some(EditorState {
+                    // A file already open in a tab is focused instead
+                    let open = self.tabs.position(self.editor.as_ref(), |editor| {
+                        editor.path.as_ref() == Some(&path)
+                    });
+                    if let Some((group, tab)) = open {
+                        return self.update(WorkshopMessage::FocusTab { group, tab });
+                    }
+                    self.open_editor(EditorState {
+                        language: Language::for_file(Some(&path), &content),
                         path: Some(path),
                         content: text_editor::Content::with_text(&content),
                         modified: false,
+                        line_changes: Vec::new(),
+                        decorations: Vec::new(),
                     });
-                    self.show_editor = true;
                     self.status = format!("Opened {}", name);
+                    if self.folder.is_none() {
+                        self.open_repository();
+                    }
+                    self.refresh_gutter();
                 }
             }
 
@@ -222,6 +592,11 @@ impl Workshop {
 
             WorkshopMessage::FileSaved(path) => {
                 if let Some(editor) = &mut self.editor {
+                    // Saving an untitled file gives it a path, and maybe an
+                    // extension that isn't Stratum's
+                    if editor.path.as_ref() != Some(&path) {
+                        editor.language = Language::for_file(Some(&path), &editor.content.text());
+                    }
                     editor.path = Some(path.clone());
                     editor.modified = false;
                 }
@@ -230,6 +605,9 @@ impl Workshop {
                     .map(|n| n.to_string_lossy().to_string())
                     .unwrap_or_default();
                 self.status = format!("Saved {}", name);
+                if let Some(git) = &mut self.git {
+                    git.refresh();
+                }
             }
 
             WorkshopMessage::FileSaveError(err) => {
@@ -242,29 +620,173 @@ impl Workshop {
                 if self.editor.as_ref().is_some_and(|e| e.modified) {
                     self.modal = Some(ModalState::UnsavedChanges);
                 } else {
-                    self.editor = None;
-                    self.show_editor = false;
+                    self.change_tabs(|tabs, editor| {
+                        tabs.close(editor);
+                    });
                     self.status = "Ready".to_string();
                 }
             }
 
+            WorkshopMessage::FocusTab { group, tab } => {
+                self.change_tabs(|tabs, editor| tabs.focus(editor, group, tab));
+            }
+
+            WorkshopMessage::DragTab { group, tab } => {
+                self.change_tabs(|tabs, editor| tabs.focus(editor, group, tab));
+                self.dragging = Some((group, tab));
+            }
+
+            WorkshopMessage::DropTab(group) => {
+                if let Some(from) = self.dragging.take() {
+                    self.change_tabs(|tabs, editor| tabs.move_tab(editor, from, group));
+                }
+            }
+
+            WorkshopMessage::EndTabDrag => self.dragging = None,
+
+            WorkshopMessage::CloseTab { group, tab } => {
+                let _ = self.update(WorkshopMessage::FocusTab { group, tab });
+                return self.update(WorkshopMessage::CloseFile);
+            }
+
+            WorkshopMessage::SplitEditor(axis) => {
+                self.change_tabs(|tabs, editor| tabs.split(editor, axis));
+            }
+
             WorkshopMessage::EditorAction(action) => {
                 if let Some(editor) = &mut self.editor {
                     let is_edit = action.is_edit();
+                    let typed = match &action {
+                        text_editor::Action::Edit(text_editor::Edit::Insert(c)) => Some(*c),
+                        _ => None,
+                    };
                     editor.content.perform(action);
+                    if let Some(language) = &mut editor.language {
+                        language.after_action(&editor.content, is_edit, typed);
+                    }
                     if is_edit {
                         editor.modified = true;
+                    } else {
+                        return Task::none();
+                    }
+                }
+                self.refresh_gutter();
+            }
+
+            WorkshopMessage::EditorKey(key) => {
+                let Some(editor) = &mut self.editor else {
+                    return Task::none();
+                };
+                let cursor = editor.content.cursor().position;
+                let outcome =
+                    self.keymap
+                        .handle(key, &editor.content.text(), (cursor.line, cursor.column));
+                outcome.apply(&mut editor.content);
+                let edited = outcome.text.is_some();
+                if let Some(language) = &mut editor.language {
+                    language.after_action(&editor.content, edited, None);
+                }
+                if !edited {
+                    return Task::none();
+                }
+                editor.modified = true;
+                self.refresh_gutter();
+            }
+
+            WorkshopMessage::SetKeymap(keymap) => {
+                self.keymap.set_keymap(keymap);
+                let mut config = WorkshopConfig::load();
+                config.keymap = keymap;
+                self.status = match config.save() {
+                    Ok(()) => format!("{keymap} keymap"),
+                    Err(e) => format!("{keymap} keymap (not saved: {e})"),
+                };
+            }
+
+            WorkshopMessage::TriggerCompletion => {
+                if let Some(editor) = &mut self.editor {
+                    if let Some(language) = &mut editor.language {
+                        language.complete(cursor_position(&editor.content));
+                    }
+                }
+            }
+
+            WorkshopMessage::NextCompletion => {
+                if let Some(language) = self.editor.as_mut().and_then(|e| e.language.as_mut()) {
+                    language.select_completion(true);
+                }
+            }
+
+            WorkshopMessage::PreviousCompletion => {
+                if let Some(language) = self.editor.as_mut().and_then(|e| e.language.as_mut()) {
+                    language.select_completion(false);
+                }
+            }
+
+            WorkshopMessage::AcceptCompletion(index) => {
+                let Some(editor) = &mut self.editor else {
+                    return Task::none();
+                };
+                let Some(language) = &mut editor.language else {
+                    return Task::none();
+                };
+                let Some(completion) = language.completion.take() else {
+                    return Task::none();
+                };
+                let index = index.unwrap_or(completion.selected);
+                if let Some(item) = completion.items.get(index) {
+                    apply_completion(&mut editor.content, item);
+                    language.after_action(&editor.content, true, None);
+                    editor.modified = true;
+                    self.refresh_gutter();
+                }
+            }
+
+            WorkshopMessage::CloseCompletion => {
+                if let Some(language) = self.editor.as_mut().and_then(|e| e.language.as_mut()) {
+                    language.completion = None;
+                }
+            }
+
+            WorkshopMessage::GotoDefinition => {
+                let Some(editor) = &mut self.editor else {
+                    return Task::none();
+                };
+                let Some(language) = &mut editor.language else {
+                    return Task::none();
+                };
+                match language.definition(cursor_position(&editor.content)) {
+                    Some(position) => {
+                        move_cursor(
+                            &mut editor.content,
+                            position.line as usize,
+                            position.character as usize,
+                        );
+                        language.after_action(&editor.content, false, None);
+                    }
+                    None => self.status = "No definition found".to_string(),
+                }
+            }
+
+            WorkshopMessage::GotoLine { line, column } => {
+                if let Some(editor) = &mut self.editor {
+                    move_cursor(&mut editor.content, line, column);
+                    if let Some(language) = &mut editor.language {
+                        language.after_action(&editor.content, false, None);
                     }
                 }
             }
 
             WorkshopMessage::ToggleEditor => {
-                if self.editor.is_some() {
+                if !self.tabs.is_empty() {
                     self.show_editor = !self.show_editor;
                 }
             }
 
             WorkshopMessage::RunFile => {
+                if let Some(index) = self.run_configs.selected() {
+                    return self.run_config(index);
+                }
                 if let Some(editor) = &self.editor {
                     let source = editor.content.text();
                     // Execute the file content in the REPL
@@ -278,9 +800,204 @@ impl Workshop {
                         }
                     }
                     self.status = "Executed file".to_string();
+                    self.open_pending_table();
+                }
+            }
+
+            WorkshopMessage::SelectRunTarget(target) => self.run_configs.select(target.index),
+
+            WorkshopMessage::RunConfig(index) => return self.run_config(index),
+
+            WorkshopMessage::ToggleRunConfigs => self.show_run_configs = !self.show_run_configs,
+
+            WorkshopMessage::RunConfigs(msg) => match self.run_configs.update(msg) {
+                Some(RunConfigAction::Run(index)) => return self.run_config(index),
+                Some(RunConfigAction::Saved(path)) => {
+                    self.status = format!("Saved {}", path.display());
+                }
+                Some(RunConfigAction::Close) => self.show_run_configs = false,
+                None => {}
+            },
+
+            WorkshopMessage::ProfileFile => {
+                let Some(editor) = self.editor.as_ref().filter(|_| !self.profiling) else {
+                    return Task::none();
+                };
+                let name = editor.path.as_ref().map_or_else(
+                    || "untitled.strat".to_string(),
+                    |path| path.display().to_string(),
+                );
+                let source = editor.content.text();
+                self.status = format!("Profiling {}...", editor.name());
+                self.profiling = true;
+                // Runs on its own thread so the window stays responsive
+                let (sender, receiver) = oneshot::channel();
+                std::thread::spawn(move || {
+                    let _ = sender.send(profile_source(&name, &source, PROFILE_INTERVAL));
+                });
+                return Task::perform(
+                    async move {
+                        receiver
+                            .await
+                            .unwrap_or_else(|_| Err("The profiled run stopped".to_string()))
+                    },
+                    WorkshopMessage::ProfileFinished,
+                );
+            }
+
+            WorkshopMessage::ProfileFinished(result) => {
+                self.profiling = false;
+                match result {
+                    Ok(profile) => {
+                        self.status = format!("Profiled {}", profile.name);
+                        self.profiler = Some(ProfilerPanel::new(profile));
+                    }
+                    Err(e) => self.status = format!("Profile failed: {e}"),
+                }
+            }
+
+            WorkshopMessage::ProfileOpened(Some((path, content))) => {
+                match Flamegraph::from_speedscope(&content) {
+                    Ok(flamegraph) => {
+                        self.status = format!("Opened {}", path.display());
+                        self.profiler = Some(ProfilerPanel::new(Profile {
+                            name: path
+                                .file_name()
+                                .map(|n| n.to_string_lossy().to_string())
+                                .unwrap_or_default(),
+                            flamegraph,
+                            allocations: Vec::new(),
+                        }));
+                    }
+                    Err(e) => self.status = e,
+                }
+            }
+
+            WorkshopMessage::ProfileOpened(None) => {}
+
+            WorkshopMessage::Profiler(msg) => {
+                let action = self.profiler.as_mut().and_then(|p| p.update(msg));
+                match action {
+                    Some(ProfilerAction::Open { path, line }) => {
+                        // Profiles name files as they were given to the run
+                        let path = match self.search_root() {
+                            Some(root) if path.is_relative() => root.join(path),
+                            _ => path,
+                        };
+                        self.open_at_line(path, line);
+                    }
+                    Some(ProfilerAction::OpenFile) => {
+                        return Task::perform(
+                            async {
+                                let handle = AsyncFileDialog::new()
+                                    .add_filter("Speedscope profile", &["json"])
+                                    .set_title("Open Profile")
+                                    .pick_file()
+                                    .await?;
+                                let path = handle.path().to_path_buf();
+                                let content = tokio::fs::read_to_string(&path).await.ok()?;
+                                Some((path, content))
+                            },
+                            WorkshopMessage::ProfileOpened,
+                        );
+                    }
+                    Some(ProfilerAction::Close) => self.profiler = None,
+                    None => {}
                 }
             }
 
+            WorkshopMessage::Table(msg) => {
+                let action = self.table.as_mut().and_then(|table| table.update(msg));
+                match action {
+                    Some(TableAction::ExportCsv(csv)) => {
+                        return Self::export(csv, "CSV", "table.csv".to_string(), "Export CSV");
+                    }
+                    Some(TableAction::Close) => {
+                        self.table = None;
+                    }
+                    None => {}
+                }
+            }
+
+            WorkshopMessage::NewNotebook => {
+                if self
+                    .notebook
+                    .as_ref()
+                    .is_some_and(NotebookPanel::is_modified)
+                {
+                    self.status = "Save or close the open notebook first".to_string();
+                } else {
+                    self.notebook = Some(NotebookPanel::new());
+                    self.status = "New notebook".to_string();
+                }
+            }
+
+            WorkshopMessage::Notebook(msg) => {
+                let action = self.notebook.as_mut().and_then(|n| n.update(msg));
+                let stem = self
+                    .notebook
+                    .as_ref()
+                    .and_then(|n| n.path())
+                    .and_then(|p| p.file_stem())
+                    .map_or_else(
+                        || "notebook".to_string(),
+                        |s| s.to_string_lossy().to_string(),
+                    );
+                match action {
+                    Some(NotebookAction::Save { path, json }) => {
+                        return Task::perform(
+                            async move {
+                                let path = match path {
+                                    Some(path) => Some(path),
+                                    None => AsyncFileDialog::new()
+                                        .add_filter("Notebook", &["stratnb"])
+                                        .set_title("Save Notebook")
+                                        .set_file_name("untitled.stratnb")
+                                        .save_file()
+                                        .await
+                                        .map(|handle| handle.path().to_path_buf()),
+                                };
+                                let Some(path) = path else {
+                                    return WorkshopMessage::FileSaveError("Cancelled".to_string());
+                                };
+                                match tokio::fs::write(&path, json).await {
+                                    Ok(()) => WorkshopMessage::NotebookSaved(path),
+                                    Err(e) => WorkshopMessage::FileSaveError(e.to_string()),
+                                }
+                            },
+                            |msg| msg,
+                        );
+                    }
+                    Some(NotebookAction::ExportScript(script)) => {
+                        return Self::export(
+                            script,
+                            "Stratum",
+                            format!("{stem}.strat"),
+                            "Export Script",
+                        );
+                    }
+                    Some(NotebookAction::ExportHtml(html)) => {
+                        return Self::export(html, "HTML", format!("{stem}.html"), "Export HTML");
+                    }
+                    Some(NotebookAction::OpenTable(data)) => {
+                        self.table = Some(TableViewer::new(data));
+                    }
+                    Some(NotebookAction::Close) => self.notebook = None,
+                    None => {}
+                }
+            }
+
+            WorkshopMessage::NotebookSaved(path) => {
+                self.status = format!("Saved {}", path.display());
+                if let Some(notebook) = &mut self.notebook {
+                    notebook.set_saved(path);
+                }
+            }
+
+            WorkshopMessage::Exported(path) => {
+                self.status = format!("Exported {}", path.display());
+            }
+
             WorkshopMessage::ShowAbout => {
                 self.modal = Some(ModalState::About);
             }
@@ -292,73 +1009,578 @@ impl Workshop {
             WorkshopMessage::ModalDiscard => {
                 // Discard changes and close
                 self.modal = None;
-                self.editor = None;
-                self.show_editor = false;
+                self.change_tabs(|tabs, editor| {
+                    tabs.close(editor);
+                });
                 self.status = "Ready".to_string();
             }
 
+            WorkshopMessage::WindowResized(size) => self.window_size = Some(size),
+
             WorkshopMessage::Exit => {
-                if self.editor.as_ref().is_some_and(|e| e.modified) {
-                    self.modal = Some(ModalState::UnsavedChanges);
-                } else {
-                    std::process::exit(0);
+                if self
+                    .notebook
+                    .as_ref()
+                    .is_some_and(NotebookPanel::is_modified)
+                {
+                    self.status = "Save or close the open notebook first".to_string();
+                    return Task::none();
+                }
+                if self.run_configs.is_modified() {
+                    self.show_run_configs = true;
+                    self.status = "Save the run configurations first".to_string();
+                    return Task::none();
+                }
+                // Unsaved files are kept in the session, so there is only
+                // something to ask about when it can't be saved
+                if let Err(e) = self.save_session() {
+                    let modified = self
+                        .tabs
+                        .position(self.editor.as_ref(), |editor| editor.modified);
+                    if let Some((group, tab)) = modified {
+                        // Show the unsaved file the dialog is about
+                        let _ = self.update(WorkshopMessage::FocusTab { group, tab });
+                        self.modal = Some(ModalState::UnsavedChanges);
+                        self.status = format!("Session not saved: {e}");
+                        return Task::none();
+                    }
                 }
+                std::process::exit(0);
             }
         }
 
         Task::none()
     }
 
-    /// Render the application
-    pub fn view(&self) -> Element<'_, WorkshopMessage> {
-        let menu_bar = self.menu_bar();
+    /// Save exported contents to a file the user picks, suggesting
+    /// `file_name`
+    fn export(
+        contents: String,
+        filter: &'static str,
+        file_name: String,
+        title: &'static str,
+    ) -> Task<WorkshopMessage> {
+        Task::perform(
+            async move {
+                let extension = file_name.rsplit('.').next().unwrap_or_default().to_string();
+                let file = AsyncFileDialog::new()
+                    .add_filter(filter, &[extension])
+                    .set_title(title)
+                    .set_file_name(file_name)
+                    .save_file()
+                    .await;
+
+                if let Some(handle) = file {
+                    let path = handle.path().to_path_buf();
+                    match tokio::fs::write(&path, &contents).await {
+                        Ok(()) => WorkshopMessage::Exported(path),
+                        Err(e) => WorkshopMessage::FileSaveError(e.to_string()),
+                    }
+                } else {
+                    WorkshopMessage::FileSaveError("Cancelled".to_string())
+                }
+            },
+            |msg| msg,
+        )
+    }
 
-        // Main content: optional editor + REPL
-        let main_content = if self.show_editor && self.editor.is_some() {
-            let editor = self.editor.as_ref().unwrap();
-            let editor_view = self.editor_view(editor);
+    /// Folder searched by the search panel: the opened folder, or else the
+    /// folder of the open file
+    fn search_root(&self) -> Option<PathBuf> {
+        self.folder.clone().or_else(|| {
+            let path = self.editor.as_ref()?.path.as_ref()?;
+            path.parent().map(Path::to_path_buf)
+        })
+    }
 
-            column![
-                editor_view,
-                rule::horizontal(1),
-                self.repl.view().map(WorkshopMessage::Repl),
-            ]
-            .spacing(0)
-        } else {
-            column![self.repl.view().map(WorkshopMessage::Repl),]
+    /// Contents of the files open in editor tabs, which search and replace
+    /// use in place of the files on disk
+    fn buffers(&self) -> Vec<(PathBuf, String)> {
+        self.tabs
+            .iter(self.editor.as_ref())
+            .filter_map(|editor| Some((editor.path.clone()?, editor.content.text())))
+            .collect()
+    }
+
+    /// Carry out an action of the search panel
+    fn handle_search_action(&mut self, action: SearchAction) {
+        match action {
+            SearchAction::Search(query) => self.run_search(&query),
+            SearchAction::Replace { query, replacement } => {
+                let Some(root) = self.search_root() else {
+                    self.set_search_error("Open a folder to replace in");
+                    return;
+                };
+                let edits = match plan_replace(&root, &query, &replacement, &self.buffers()) {
+                    Ok(edits) => edits,
+                    Err(e) => {
+                        self.set_search_error(e.to_string());
+                        return;
+                    }
+                };
+                match self.apply_edits(&edits, false) {
+                    Ok(()) => {
+                        self.status = format!("Replaced in {} files", edits.len());
+                        if let Some(search) = &mut self.search {
+                            search.push_undo(edits);
+                        }
+                    }
+                    Err(e) => self.status = format!("Replace failed: {}", e),
+                }
+                self.run_search(&query);
+            }
+            SearchAction::Undo(edits) => {
+                match self.apply_edits(&edits, true) {
+                    Ok(()) => self.status = format!("Undid replace in {} files", edits.len()),
+                    Err(e) => self.status = format!("Undo failed: {}", e),
+                }
+                if let Some(search) = &self.search {
+                    let query = search.query();
+                    if !query.pattern.is_empty() {
+                        self.run_search(&query);
+                    }
+                }
+            }
+            SearchAction::Open { path, line } => self.open_at_line(path, line),
+            SearchAction::Close => self.search = None,
+        }
+    }
+
+    /// Search the folder and show the results in the search panel
+    fn run_search(&mut self, query: &SearchQuery) {
+        let Some(root) = self.search_root() else {
+            self.set_search_error("Open a folder to search");
+            return;
+        };
+        let regex = match query.compile() {
+            Ok(regex) => regex,
+            Err(e) => {
+                self.set_search_error(e.to_string());
+                return;
+            }
         };
+        let results = search_folder(&root, &regex, &self.buffers());
+        if let Some(search) = &mut self.search {
+            search.set_results(root, results);
+        }
+    }
 
-        let status_bar = self.status_bar();
+    fn set_search_error(&mut self, error: impl Into<String>) {
+        if let Some(search) = &mut self.search {
+            search.set_error(error);
+        }
+    }
 
-        let base_content: Element<WorkshopMessage> = container(
-            column![menu_bar, rule::horizontal(1), main_content, status_bar]
-                .spacing(0)
-                .width(Length::Fill)
-                .height(Length::Fill),
-        )
-        .width(Length::Fill)
-        .height(Length::Fill)
-        .into();
+    /// Apply the edits of a replace, or revert them. Files open in editor
+    /// tabs are edited in their buffers; other files are written to disk
+    /// unless they changed since the edit was planned.
+    fn apply_edits(&mut self, edits: &[FileEdit], revert: bool) -> Result<(), String> {
+        let changed: Vec<String> = edits
+            .iter()
+            .filter(|edit| !self.is_open(&edit.path))
+            .filter(|edit| {
+                let expected = if revert { &edit.after } else { &edit.before };
+                std::fs::read_to_string(&edit.path).ok().as_ref() != Some(expected)
+            })
+            .map(|edit| edit.path.display().to_string())
+            .collect();
+        if !changed.is_empty() {
+            return Err(format!("changed since the search: {}", changed.join(", ")));
+        }
 
-        // Render modal if present
-        if let Some(modal_state) = &self.modal {
-            self.modal_overlay(base_content, modal_state)
-        } else {
-            base_content
+        for edit in edits {
+            let content = if revert { &edit.before } else { &edit.after };
+            let open = self
+                .tabs
+                .iter_mut(&mut self.editor)
+                .find(|editor| editor.path.as_ref() == Some(&edit.path));
+            if let Some(editor) = open {
+                editor.content = text_editor::Content::with_text(content);
+                editor.modified = true;
+                if let Some(language) = &mut editor.language {
+                    language.set_content(content.clone());
+                }
+            } else {
+                std::fs::write(&edit.path, content).map_err(|e| e.to_string())?;
+            }
         }
+        Ok(())
     }
 
-    /// Render the menu bar
-    fn menu_bar(&self) -> Element<'_, WorkshopMessage> {
-        container(
-            row![
-                Self::menu_button("New", WorkshopMessage::NewFile),
-                Self::menu_button("Open", WorkshopMessage::OpenFile),
-                Self::menu_button("Save", WorkshopMessage::SaveFile),
-                Self::menu_button("Close", WorkshopMessage::CloseFile),
-                text("|").size(12),
+    /// Whether a file is open in an editor tab
+    fn is_open(&self, path: &Path) -> bool {
+        self.tabs
+            .iter(self.editor.as_ref())
+            .any(|editor| editor.path.as_deref() == Some(path))
+    }
+
+    /// The session to save: the folder, editor tabs, panels, search and
+    /// window size
+    pub(crate) fn session(&self) -> Session {
+        let groups = (0..self.tabs.group_count())
+            .map(|group| {
+                let (tabs, active) = self.tabs.group(group, self.editor.as_ref());
+                GroupSession {
+                    tabs: tabs.into_iter().map(EditorState::tab_session).collect(),
+                    active,
+                }
+            })
+            .collect();
+        let search = self.search.as_ref();
+        Session {
+            folder: self.folder.clone(),
+            axis: self.tabs.axis(),
+            groups,
+            focused_group: self.tabs.focused(),
+            panels: PanelSession {
+                editor: self.show_editor,
+                search: search.is_some(),
+                terminal: self.terminal.is_some(),
+                source_control: self.show_source_control,
+                plugins: self.show_plugins,
+                run_configs: self.show_run_configs,
+            },
+            search: search
+                .map(SearchPanel::query)
+                .filter(|query| !query.pattern.is_empty()),
+            collapsed_files: search.map(SearchPanel::collapsed_files).unwrap_or_default(),
+            notebook: self
+                .notebook
+                .as_ref()
+                .and_then(NotebookPanel::path)
+                .map(Path::to_path_buf),
+            window_size: self.window_size.map(|size| (size.width, size.height)),
+            run_target: self
+                .run_configs
+                .selected()
+                .map(|index| self.run_configs.configs()[index].name.clone()),
+        }
+    }
+
+    /// Restore a saved session. Tabs whose files are gone are skipped.
+    pub(crate) fn restore_session(&mut self, session: Session) -> Task<WorkshopMessage> {
+        if let Some(folder) = session.folder {
+            let _ = self.update(WorkshopMessage::FolderOpened(Some(folder)));
+        }
+
+        self.editor = None;
+        self.tabs = TabGroups::new(SplitLayout {
+            axis: session.axis,
+            groups: session.groups.len(),
+        });
+        for (index, group) in session.groups.iter().enumerate() {
+            self.tabs.focus(&mut self.editor, index, 0);
+            for tab in &group.tabs {
+                if let Some(editor) = EditorState::restore(tab) {
+                    self.tabs.open(&mut self.editor, editor);
+                }
+            }
+            self.tabs.focus(&mut self.editor, index, group.active);
+        }
+        self.tabs
+            .focus(&mut self.editor, session.focused_group, usize::MAX);
+        self.keymap.reset();
+        self.show_editor = session.panels.editor && !self.tabs.is_empty();
+        self.window_size = session
+            .window_size
+            .map(|(width, height)| Size::new(width, height));
+
+        self.search = session.panels.search.then(SearchPanel::new);
+        let action = self
+            .search
+            .as_mut()
+            .zip(session.search)
+            .and_then(|(search, query)| search.set_query(query));
+        if let Some(action) = action {
+            self.handle_search_action(action);
+        }
+        if let Some(search) = &mut self.search {
+            search.collapse(session.collapsed_files);
+        }
+        self.show_source_control = session.panels.source_control && self.git.is_some();
+        self.show_plugins = session.panels.plugins;
+        if self.show_plugins {
+            self.plugins.refresh_panels(&self.plugin_context());
+        }
+        self.show_run_configs = session.panels.run_configs;
+        let target = session.run_target.and_then(|name| {
+            self.run_configs
+                .configs()
+                .iter()
+                .position(|config| config.name == name)
+        });
+        self.run_configs.select(target);
+        if let Some(path) = session.notebook {
+            if let Ok(content) = std::fs::read_to_string(&path) {
+                let _ = self.update(WorkshopMessage::FileDialogOpened(Some((path, content))));
+            }
+        }
+        self.refresh_gutter();
+
+        if session.panels.terminal && self.terminal.is_none() {
+            return self.update(WorkshopMessage::ToggleTerminal);
+        }
+        Task::none()
+    }
+
+    /// Save the session, and remember its folder for the next launch
+    fn save_session(&self) -> std::io::Result<()> {
+        self.session().save()?;
+        let mut config = WorkshopConfig::load();
+        match &self.folder {
+            Some(folder) => config.add_recent_folder(folder.clone()),
+            None => config.last_folder = None,
+        }
+        config.save()
+    }
+
+    /// Open an editor in a new tab of the focused group
+    fn open_editor(&mut self, editor: EditorState) {
+        self.change_tabs(|tabs, focused| tabs.open(focused, editor));
+    }
+
+    /// Rearrange the editor tabs, and save the split layout when it changes
+    fn change_tabs(
+        &mut self,
+        change: impl FnOnce(&mut TabGroups<EditorState>, &mut Option<EditorState>),
+    ) {
+        let layout = self.tabs.layout();
+        change(&mut self.tabs, &mut self.editor);
+        self.keymap.reset();
+        self.show_editor = !self.tabs.is_empty();
+        if self.tabs.layout() != layout {
+            let mut config = WorkshopConfig::load();
+            config.layout.editor_split = self.tabs.layout();
+            if let Err(e) = config.save() {
+                self.status = format!("Layout not saved: {e}");
+            }
+        }
+    }
+
+    /// Open a file in the editor with the cursor on a line (1-based)
+    fn open_at_line(&mut self, path: PathBuf, line: usize) {
+        if self.is_open(&path) {
+            let open = self.tabs.position(self.editor.as_ref(), |editor| {
+                editor.path.as_ref() == Some(&path)
+            });
+            if let Some((group, tab)) = open {
+                let _ = self.update(WorkshopMessage::FocusTab { group, tab });
+            }
+        } else {
+            match std::fs::read_to_string(&path) {
+                Ok(content) => {
+                    let _ = self.update(WorkshopMessage::FileDialogOpened(Some((path, content))));
+                }
+                Err(e) => {
+                    self.status = format!("Open failed: {}", e);
+                    return;
+                }
+            }
+        }
+        if let Some(editor) = &mut self.editor {
+            move_cursor(&mut editor.content, line.saturating_sub(1), 0);
+            self.show_editor = true;
+        }
+    }
+
+    /// Open the git repository containing the searched folder, if any
+    fn open_repository(&mut self) {
+        self.git = self
+            .search_root()
+            .and_then(|root| SourceControlPanel::open(&root));
+        if self.git.is_none() {
+            self.show_source_control = false;
+        }
+    }
+
+    /// Diff the editor's buffer against `HEAD` and ask plugins for
+    /// decorations, for the gutter
+    fn refresh_gutter(&mut self) {
+        let context = self.plugin_context();
+        let Some(editor) = &mut self.editor else {
+            return;
+        };
+        editor.line_changes = match (&self.git, &editor.path) {
+            (Some(git), Some(path)) => git.line_changes(path, &context.text),
+            _ => Vec::new(),
+        };
+        editor.decorations = match self.plugins.decorations(&context) {
+            Ok(decorations) => decorations,
+            Err(e) => {
+                self.status = format!("Plugin error: {e}");
+                Vec::new()
+            }
+        };
+    }
+
+    /// What plugin callbacks are told about the editor
+    fn plugin_context(&self) -> Context {
+        self.editor
+            .as_ref()
+            .map(|editor| Context {
+                path: editor.path.clone(),
+                text: editor.content.text(),
+                line: editor.content.cursor().position.line + 1,
+            })
+            .unwrap_or_default()
+    }
+
+    /// Start a shell in the terminal panel and stream its output
+    fn start_terminal(&mut self) -> Task<WorkshopMessage> {
+        let Some(terminal) = &mut self.terminal else {
+            return Task::none();
+        };
+        match terminal.start() {
+            Ok(output) => Task::run(output, WorkshopMessage::Terminal),
+            Err(e) => {
+                self.status = format!("Terminal failed: {}", e);
+                Task::none()
+            }
+        }
+    }
+
+    /// Run a configuration with `stratum run` in the terminal
+    fn run_config(&mut self, index: usize) -> Task<WorkshopMessage> {
+        let Some(root) = self.run_configs.root() else {
+            return Task::none();
+        };
+        let Some(config) = self.run_configs.configs().get(index) else {
+            return Task::none();
+        };
+        let program = config.program(root);
+        let name = config.name.clone();
+        let terminal = self
+            .terminal
+            .get_or_insert_with(|| TerminalPanel::new(program.cwd.clone()));
+        match terminal.run(&program) {
+            Ok(output) => {
+                let unsaved = self
+                    .tabs
+                    .position(self.editor.as_ref(), |editor| editor.modified)
+                    .is_some();
+                self.status = if unsaved {
+                    format!("Running {name} (unsaved changes are not included)")
+                } else {
+                    format!("Running {name}")
+                };
+                Task::run(output, WorkshopMessage::Terminal)
+            }
+            Err(e) => {
+                self.status = format!("Could not run {name}: {e}");
+                Task::none()
+            }
+        }
+    }
+
+    /// Show the table of the REPL's last DataFrame or Cube result, if any
+    fn open_pending_table(&mut self) {
+        if let Some(data) = self.repl.take_table() {
+            self.status = format!("{} rows x {} columns", data.rows.len(), data.columns.len());
+            self.table = Some(TableViewer::new(data));
+        }
+    }
+
+    /// Render the application
+    pub fn view(&self) -> Element<'_, WorkshopMessage> {
+        let menu_bar = self.menu_bar();
+
+        // Main content: optional editor + optional table viewer + REPL
+        let mut main_content = column![].spacing(0);
+        if self.show_editor && !self.tabs.is_empty() {
+            main_content = main_content
+                .push(self.editor_area())
+                .push(rule::horizontal(1));
+        }
+        if let Some(notebook) = &self.notebook {
+            main_content = main_content
+                .push(notebook.view().map(WorkshopMessage::Notebook))
+                .push(rule::horizontal(1));
+        }
+        if let Some(table) = &self.table {
+            main_content = main_content
+                .push(table.view().map(WorkshopMessage::Table))
+                .push(rule::horizontal(1));
+        }
+        if let Some(profiler) = &self.profiler {
+            main_content = main_content
+                .push(profiler.view().map(WorkshopMessage::Profiler))
+                .push(rule::horizontal(1));
+        }
+        let mut main_content = main_content.push(self.repl.view().map(WorkshopMessage::Repl));
+        if let Some(terminal) = &self.terminal {
+            main_content = main_content
+                .push(rule::horizontal(1))
+                .push(terminal.view().map(WorkshopMessage::Terminal));
+        }
+
+        // Search and source control panels to the left of the main content,
+        // when shown
+        let mut panels = row![].height(Length::Fill);
+        if let Some(search) = &self.search {
+            panels = panels
+                .push(search.view().map(WorkshopMessage::Search))
+                .push(rule::vertical(1));
+        }
+        if let Some(git) = self.git.as_ref().filter(|_| self.show_source_control) {
+            panels = panels
+                .push(git.view().map(WorkshopMessage::SourceControl))
+                .push(rule::vertical(1));
+        }
+        if self.show_plugins {
+            panels = panels.push(self.plugins_view()).push(rule::vertical(1));
+        }
+        if self.show_run_configs {
+            panels = panels
+                .push(self.run_configs.view().map(WorkshopMessage::RunConfigs))
+                .push(rule::vertical(1));
+        }
+        let main_content = panels.push(main_content);
+
+        let status_bar = self.status_bar();
+
+        let base_content: Element<WorkshopMessage> = container(
+            column![menu_bar, rule::horizontal(1), main_content, status_bar]
+                .spacing(0)
+                .width(Length::Fill)
+                .height(Length::Fill),
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into();
+
+        // Render modal if present
+        if let Some(modal_state) = &self.modal {
+            self.modal_overlay(base_content, modal_state)
+        } else {
+            base_content
+        }
+    }
+
+    /// Render the menu bar
+    fn menu_bar(&self) -> Element<'_, WorkshopMessage> {
+        container(
+            row![
+                Self::menu_button("New", WorkshopMessage::NewFile),
+                Self::menu_button("Open", WorkshopMessage::OpenFile),
+                Self::menu_button("Folder", WorkshopMessage::OpenFolder),
+                Self::menu_button("Save", WorkshopMessage::SaveFile),
+                Self::menu_button("Close", WorkshopMessage::CloseFile),
+                text("|").size(12),
                 Self::menu_button("Run", WorkshopMessage::RunFile),
+                self.run_target_list(),
+                Self::menu_button("Configs", WorkshopMessage::ToggleRunConfigs),
+                Self::menu_button("Profile", WorkshopMessage::ProfileFile),
+                Self::menu_button("Notebook", WorkshopMessage::NewNotebook),
+                text("|").size(12),
+                Self::menu_button("Search", WorkshopMessage::ToggleSearch),
+                Self::menu_button("Terminal", WorkshopMessage::ToggleTerminal),
+                Self::menu_button("Git", WorkshopMessage::ToggleSourceControl),
+                Self::menu_button("Plugins", WorkshopMessage::TogglePlugins),
                 text("|").size(12),
+                button(text(format!("Keys: {}", self.keymap.keymap())).size(12))
+                    .on_press(WorkshopMessage::SetKeymap(self.keymap.keymap().next()))
+                    .padding([4, 10])
+                    .style(button::text),
                 Self::menu_button("About", WorkshopMessage::ShowAbout),
                 Space::new().width(Length::Fill),
             ]
@@ -377,6 +1599,19 @@ impl Workshop {
         .into()
     }
 
+    /// Dropdown of what the Run button runs
+    fn run_target_list(&self) -> Element<'_, WorkshopMessage> {
+        let targets = RunTarget::all(self.run_configs.configs());
+        let selected = targets
+            .iter()
+            .find(|target| target.index == self.run_configs.selected())
+            .cloned();
+        pick_list(targets, selected, WorkshopMessage::SelectRunTarget)
+            .text_size(12)
+            .padding([2, 6])
+            .into()
+    }
+
     /// Create a menu button
     fn menu_button(
         label: &'static str,
@@ -389,58 +1624,411 @@ impl Workshop {
             .into()
     }
 
-    /// Render the editor view
-    fn editor_view<'a>(&self, editor: &'a EditorState) -> Element<'a, WorkshopMessage> {
-        let title = editor
-            .path
-            .as_ref()
-            .and_then(|p| p.file_name())
-            .map(|n| n.to_string_lossy().to_string())
-            .unwrap_or_else(|| "Untitled".to_string());
+    /// Render the editor groups, side by side or stacked
+    fn editor_area(&self) -> Element<'_, WorkshopMessage> {
+        let mut groups: Vec<Element<'_, WorkshopMessage>> = Vec::new();
+        for group in 0..self.tabs.group_count() {
+            if group > 0 {
+                groups.push(match self.tabs.axis() {
+                    SplitAxis::Columns => rule::vertical(1).into(),
+                    SplitAxis::Rows => rule::horizontal(1).into(),
+                });
+            }
+            groups.push(self.group_view(group));
+        }
+        let area: Element<'_, WorkshopMessage> = match self.tabs.axis() {
+            SplitAxis::Columns => Row::with_children(groups).into(),
+            SplitAxis::Rows => Column::with_children(groups).into(),
+        };
+        container(area)
+            .width(Length::Fill)
+            .height(Length::FillPortion(1))
+            .into()
+    }
 
-        let modified = if editor.modified { " *" } else { "" };
+    /// Render an editor group: its tab bar and active tab. Tabs dragged from
+    /// other groups drop anywhere on it.
+    fn group_view(&self, group: usize) -> Element<'_, WorkshopMessage> {
+        let (tabs, active) = self.tabs.group(group, self.editor.as_ref());
+        let focused = group == self.tabs.focused();
+        let body = match tabs.get(active).copied() {
+            Some(editor) => self.editor_view(editor, (!focused).then_some((group, active))),
+            None => container(text("Drag a tab here").size(12))
+                .center(Length::Fill)
+                .into(),
+        };
+        mouse_area(
+            column![self.tab_bar(group, &tabs, active), body]
+                .width(Length::Fill)
+                .height(Length::Fill),
+        )
+        .on_release(WorkshopMessage::DropTab(group))
+        .into()
+    }
 
-        let header = container(
-            row![
-                text(format!("{}{}", title, modified)).size(12),
-                Space::new().width(Length::Fill),
+    /// Render a group's tabs, which are dragged by pressing them, and the
+    /// split buttons
+    fn tab_bar<'a>(
+        &self,
+        group: usize,
+        tabs: &[&'a EditorState],
+        active: usize,
+    ) -> Element<'a, WorkshopMessage> {
+        let focused = group == self.tabs.focused();
+        let mut bar = row![].spacing(2).align_y(iced::Alignment::Center);
+        for (tab, editor) in tabs.iter().enumerate() {
+            let modified = if editor.modified { " *" } else { "" };
+            let label = row![
+                text(format!("{}{}", editor.name(), modified)).size(12),
                 button(text("x").size(10))
-                    .on_press(WorkshopMessage::CloseFile)
-                    .padding([2, 6])
+                    .on_press(WorkshopMessage::CloseTab { group, tab })
+                    .padding([0, 4])
                     .style(button::text),
             ]
-            .align_y(iced::Alignment::Center),
-        )
-        .padding([4, 8])
-        .width(Length::Fill)
-        .style(|theme: &Theme| {
-            let palette = theme.extended_palette();
-            container::Style {
-                background: Some(palette.background.weak.color.into()),
-                ..Default::default()
+            .spacing(4)
+            .align_y(iced::Alignment::Center);
+            let selected = tab == active;
+            let label = container(label)
+                .padding([2, 8])
+                .style(move |theme: &Theme| {
+                    let palette = theme.extended_palette();
+                    let background = match (selected, focused) {
+                        (true, true) => Some(palette.primary.weak.color.into()),
+                        (true, false) => Some(palette.background.strong.color.into()),
+                        _ => None,
+                    };
+                    container::Style {
+                        background,
+                        ..Default::default()
+                    }
+                });
+            bar = bar.push(mouse_area(label).on_press(WorkshopMessage::DragTab { group, tab }));
+        }
+        bar = bar.push(Space::new().width(Length::Fill));
+        if tabs.is_empty() {
+            bar = bar.push(
+                button(text("Close group").size(10))
+                    .on_press(WorkshopMessage::CloseTab { group, tab: 0 })
+                    .padding([2, 6])
+                    .style(button::text),
+            );
+        } else if focused {
+            for (label, axis) in [
+                ("Split →", SplitAxis::Columns),
+                ("Split ↓", SplitAxis::Rows),
+            ] {
+                bar = bar.push(
+                    button(text(label).size(10))
+                        .on_press(WorkshopMessage::SplitEditor(axis))
+                        .padding([2, 6])
+                        .style(button::text),
+                );
             }
-        });
+        }
 
+        container(bar)
+            .padding([2, 4])
+            .width(Length::Fill)
+            .style(|theme: &Theme| {
+                let palette = theme.extended_palette();
+                container::Style {
+                    background: Some(palette.background.weak.color.into()),
+                    ..Default::default()
+                }
+            })
+            .into()
+    }
+
+    /// Render an editor tab. A tab of an unfocused group only focuses its
+    /// group and tab when clicked.
+    fn editor_view<'a>(
+        &self,
+        editor: &'a EditorState,
+        unfocused: Option<(usize, usize)>,
+    ) -> Element<'a, WorkshopMessage> {
+        let completing = editor
+            .language
+            .as_ref()
+            .is_some_and(|language| language.completion.is_some());
+        let (keymap, mode) = (self.keymap.keymap(), self.keymap.mode());
         let editor_widget = text_editor(&editor.content)
-            .on_action(WorkshopMessage::EditorAction)
             .font(iced::Font::MONOSPACE)
             .size(13)
             .height(Length::FillPortion(1));
+        let editor_widget = match unfocused {
+            Some((group, tab)) => {
+                editor_widget.on_action(move |_| WorkshopMessage::FocusTab { group, tab })
+            }
+            None => editor_widget
+                .on_action(WorkshopMessage::EditorAction)
+                .key_binding(move |key_press| {
+                    Self::editor_key_binding(key_press, completing, keymap, mode)
+                }),
+        };
 
-        container(column![
-            header,
-            scrollable(editor_widget).height(Length::FillPortion(1))
-        ])
-        .width(Length::Fill)
-        .height(Length::FillPortion(1))
-        .into()
-    }
+        // Diagnostic, change and plugin markers beside each line, for
+        // Stratum files, files in a repository and decorated files
+        let body: Element<'a, WorkshopMessage> =
+            if self.git.is_some() || editor.language.is_some() || !editor.decorations.is_empty() {
+                row![Self::gutter(editor), editor_widget].into()
+            } else {
+                editor_widget.into()
+            };
 
-    /// Render the status bar
-    fn status_bar(&self) -> Element<'_, WorkshopMessage> {
-        container(text(&self.status).size(11))
-            .padding([2, 8])
+        let mut view = column![scrollable(body).height(Length::FillPortion(1))];
+        if let Some(language) = editor.language.as_ref().filter(|_| unfocused.is_none()) {
+            view = view.push(Self::language_view(language));
+        }
+        let line = editor.content.cursor().position.line + 1;
+        for decoration in editor.decorations.iter().filter(|d| d.line == line) {
+            view = view
+                .push(container(text(format!("◆ {}", decoration.text)).size(11)).padding([0, 8]));
+        }
+        container(view)
             .width(Length::Fill)
+            .height(Length::FillPortion(1))
+            .into()
+    }
+
+    /// Editor key bindings for language features, the completion list while
+    /// it is open, and the Vim or Emacs keymap
+    fn editor_key_binding(
+        key_press: text_editor::KeyPress,
+        completing: bool,
+        keymap: Keymap,
+        mode: Mode,
+    ) -> Option<text_editor::Binding<WorkshopMessage>> {
+        use keyboard::Key;
+
+        let message = match key_press.key.as_ref() {
+            // Emacs keeps Ctrl+Space for the mark
+            Key::Named(key::Named::Space)
+                if key_press.modifiers.command() && keymap != Keymap::Emacs =>
+            {
+                Some(WorkshopMessage::TriggerCompletion)
+            }
+            Key::Character("/") if key_press.modifiers.alt() && keymap == Keymap::Emacs => {
+                Some(WorkshopMessage::TriggerCompletion)
+            }
+            Key::Named(key::Named::F12) => Some(WorkshopMessage::GotoDefinition),
+            Key::Named(key::Named::ArrowDown) if completing => {
+                Some(WorkshopMessage::NextCompletion)
+            }
+            Key::Named(key::Named::ArrowUp) if completing => {
+                Some(WorkshopMessage::PreviousCompletion)
+            }
+            Key::Named(key::Named::Enter | key::Named::Tab) if completing => {
+                Some(WorkshopMessage::AcceptCompletion(None))
+            }
+            Key::Named(key::Named::Escape) if completing => Some(WorkshopMessage::CloseCompletion),
+            _ => None,
+        };
+        if let Some(message) = message {
+            return Some(text_editor::Binding::Custom(message));
+        }
+        match handling(keymap, mode, &key_press) {
+            Handling::Editor => text_editor::Binding::from_key_press(key_press),
+            Handling::Keymap(key) => Some(text_editor::Binding::Custom(
+                WorkshopMessage::EditorKey(key),
+            )),
+            Handling::Ignore => None,
+        }
+    }
+
+    /// Render the completion list, problems and hover information under the
+    /// editor
+    fn language_view(language: &Language) -> Element<'_, WorkshopMessage> {
+        let mut view = column![].spacing(2).padding([2, 8]);
+
+        if let Some(completion) = &language.completion {
+            let items = completion.items.iter().enumerate().map(|(i, item)| {
+                let detail = item.detail.as_deref().unwrap_or_default();
+                button(
+                    row![
+                        text(&item.label).size(12).font(iced::Font::MONOSPACE),
+                        text(detail).size(11),
+                    ]
+                    .spacing(12),
+                )
+                .on_press(WorkshopMessage::AcceptCompletion(Some(i)))
+                .padding([1, 6])
+                .width(Length::Fill)
+                .style(if i == completion.selected {
+                    button::primary
+                } else {
+                    button::text
+                })
+                .into()
+            });
+            view = view.push(Column::with_children(items));
+        }
+
+        let problems = language
+            .diagnostics
+            .iter()
+            .take(MAX_PROBLEMS)
+            .map(|diagnostic| {
+                let start = diagnostic.range.start;
+                let color = if is_error(diagnostic) {
+                    Color::from_rgb(0.9, 0.4, 0.4)
+                } else {
+                    Color::from_rgb(0.9, 0.7, 0.3)
+                };
+                button(
+                    text(format!(
+                        "{}:{}  {}",
+                        start.line + 1,
+                        start.character + 1,
+                        diagnostic.message
+                    ))
+                    .size(11)
+                    .color(color),
+                )
+                .on_press(WorkshopMessage::GotoLine {
+                    line: start.line as usize,
+                    column: start.character as usize,
+                })
+                .padding([0, 4])
+                .style(button::text)
+                .into()
+            });
+        view = view.push(Column::with_children(problems));
+        if language.diagnostics.len() > MAX_PROBLEMS {
+            view = view.push(
+                text(format!(
+                    "and {} more problems",
+                    language.diagnostics.len() - MAX_PROBLEMS
+                ))
+                .size(11),
+            );
+        }
+
+        if let Some(hover) = &language.hover {
+            view = view.push(text(hover).size(11).font(iced::Font::MONOSPACE));
+        }
+        view.into()
+    }
+
+    /// Render the gutter of markers for lines with errors or warnings, for
+    /// lines added, modified, or followed by removed lines since `HEAD`, and
+    /// for lines plugins decorated
+    fn gutter<'a>(editor: &EditorState) -> Element<'a, WorkshopMessage> {
+        let line_count = editor.content.line_count();
+        let mut errors = vec![None; line_count];
+        for diagnostic in editor.language.iter().flat_map(|l| &l.diagnostics) {
+            if let Some(error) = errors.get_mut(diagnostic.range.start.line as usize) {
+                *error = Some(error.unwrap_or(false) || is_error(diagnostic));
+            }
+        }
+
+        let mut changes = editor.line_changes.iter().peekable();
+        let mut decorations = editor.decorations.iter().peekable();
+        let markers = (1..=line_count).map(|line| {
+            let problem = match errors[line - 1] {
+                Some(true) => text("●").color(Color::from_rgb(0.9, 0.4, 0.4)),
+                Some(false) => text("●").color(Color::from_rgb(0.9, 0.7, 0.3)),
+                None => text(" "),
+            };
+            while changes.next_if(|(changed, _)| *changed < line).is_some() {}
+            let change = match changes.next_if(|(changed, _)| *changed == line) {
+                Some((_, LineChange::Added)) => text("+").color(Color::from_rgb(0.4, 0.8, 0.4)),
+                Some((_, LineChange::Modified)) => text("~").color(Color::from_rgb(0.5, 0.7, 1.0)),
+                Some((_, LineChange::Removed)) => text("_").color(Color::from_rgb(0.9, 0.4, 0.4)),
+                None => text(" "),
+            };
+            let mut decorated = false;
+            while decorations.next_if(|d| d.line <= line).is_some() {
+                decorated = true;
+            }
+            let decoration = if decorated {
+                text("◆").color(Color::from_rgb(0.7, 0.5, 0.9))
+            } else {
+                text(" ")
+            };
+            row![
+                problem.size(13).font(iced::Font::MONOSPACE),
+                change.size(13).font(iced::Font::MONOSPACE),
+                decoration.size(13).font(iced::Font::MONOSPACE),
+            ]
+            .spacing(2)
+            .into()
+        });
+        // Matches the editor's padding so markers line up with lines
+        Column::with_children(markers)
+            .padding([5, 2])
+            .width(Length::Fixed(36.0))
+            .into()
+    }
+
+    /// Render the plugins' commands and panels, and the plugins that failed
+    /// to load
+    fn plugins_view(&self) -> Element<'_, WorkshopMessage> {
+        let header = row![
+            text("Plugins").size(12),
+            Space::new().width(Length::Fill),
+            button(text("Refresh").size(10))
+                .on_press(WorkshopMessage::RefreshPluginPanels)
+                .padding([2, 6])
+                .style(button::text),
+            button(text("x").size(10))
+                .on_press(WorkshopMessage::TogglePlugins)
+                .padding([2, 6])
+                .style(button::text),
+        ]
+        .align_y(iced::Alignment::Center);
+
+        let mut view = column![header].spacing(6).padding(8);
+        if self.plugins.names().next().is_none() && self.plugins.errors().is_empty() {
+            let folder = plugin_dir().map(|dir| dir.display().to_string());
+            view = view.push(
+                text(format!(
+                    "No plugins. Add Stratum packages to {}",
+                    folder.as_deref().unwrap_or("the plugin folder")
+                ))
+                .size(11),
+            );
+        }
+
+        let commands = self
+            .plugins
+            .commands()
+            .iter()
+            .enumerate()
+            .map(|(i, command)| {
+                let chord = command
+                    .chord
+                    .as_ref()
+                    .map(ToString::to_string)
+                    .unwrap_or_default();
+                button(
+                    row![
+                        text(&command.title).size(11),
+                        Space::new().width(Length::Fill),
+                        text(chord).size(10),
+                    ]
+                    .spacing(8),
+                )
+                .on_press(WorkshopMessage::RunPluginCommand(i))
+                .padding([1, 8])
+                .width(Length::Fill)
+                .style(button::text)
+                .into()
+            });
+        view = view.push(Column::with_children(commands).spacing(1));
+
+        for panel in self.plugins.panels() {
+            view = view
+                .push(rule::horizontal(1))
+                .push(text(&panel.title).size(11))
+                .push(text(&panel.content).size(11).font(iced::Font::MONOSPACE));
+        }
+        for error in self.plugins.errors() {
+            view = view.push(text(error).size(11).color(Color::from_rgb(0.9, 0.4, 0.4)));
+        }
+
+        container(scrollable(view).height(Length::Fill))
+            .width(Length::Fixed(260.0))
+            .height(Length::Fill)
             .style(|theme: &Theme| {
                 let palette = theme.extended_palette();
                 container::Style {
@@ -451,6 +2039,39 @@ impl Workshop {
             .into()
     }
 
+    /// Render the status bar
+    fn status_bar(&self) -> Element<'_, WorkshopMessage> {
+        let branch = self
+            .git
+            .as_ref()
+            .map(|git| git.branch().unwrap_or("(detached)"))
+            .unwrap_or_default();
+        let mode = self
+            .editor
+            .as_ref()
+            .and(self.keymap.status())
+            .unwrap_or_default();
+        container(
+            row![
+                text(&self.status).size(11),
+                Space::new().width(Length::Fill),
+                text(mode).size(11).font(iced::Font::MONOSPACE),
+                text(branch).size(11),
+            ]
+            .spacing(12),
+        )
+        .padding([2, 8])
+        .width(Length::Fill)
+        .style(|theme: &Theme| {
+            let palette = theme.extended_palette();
+            container::Style {
+                background: Some(palette.background.weak.color.into()),
+                ..Default::default()
+            }
+        })
+        .into()
+    }
+
     /// Render modal overlay
     fn modal_overlay<'a>(
         &self,
@@ -527,7 +2148,14 @@ impl Workshop {
 
     /// Keyboard subscription
     pub fn subscription(&self) -> Subscription<WorkshopMessage> {
-        keyboard::listen().filter_map(|event| {
+        // A tab dragged anywhere but onto an editor group stays put
+        let drags = iced::event::listen_with(|event, _status, _window| match event {
+            iced::Event::Mouse(iced::mouse::Event::ButtonReleased(iced::mouse::Button::Left)) => {
+                Some(WorkshopMessage::EndTabDrag)
+            }
+            _ => None,
+        });
+        let keys = keyboard::listen().filter_map(|event| {
             let keyboard::Event::KeyPressed { key, modifiers, .. } = event else {
                 return None;
             };
@@ -539,8 +2167,18 @@ impl Workshop {
 
             // Keyboard shortcuts
             if let keyboard::Key::Character(ref c) = key {
+                // Ctrl+Shift+F toggles the search panel
+                if modifiers.command() && modifiers.shift() && c.eq_ignore_ascii_case("f") {
+                    return Some(WorkshopMessage::ToggleSearch);
+                }
+                // Ctrl+Shift+G toggles the source control panel
+                if modifiers.command() && modifiers.shift() && c.eq_ignore_ascii_case("g") {
+                    return Some(WorkshopMessage::ToggleSourceControl);
+                }
                 if modifiers.command() {
                     match c.as_ref() {
+                        "`" => return Some(WorkshopMessage::ToggleTerminal),
+                        "\\" => return Some(WorkshopMessage::SplitEditor(SplitAxis::Columns)),
                         "n" => return Some(WorkshopMessage::NewFile),
                         "o" => return Some(WorkshopMessage::OpenFile),
                         "s" => return Some(WorkshopMessage::SaveFile),
@@ -557,8 +2195,15 @@ impl Workshop {
                 return Some(WorkshopMessage::RunFile);
             }
 
-            None
-        })
+            // Other shortcuts may be bound by plugins
+            KeyChord::from_key(&key, modifiers).map(WorkshopMessage::BoundKey)
+        });
+        let window = Subscription::batch([
+            iced::window::resize_events().map(|(_id, size)| WorkshopMessage::WindowResized(size)),
+            // Closing the window exits like Ctrl+Q, saving the session
+            iced::window::close_requests().map(|_id| WorkshopMessage::Exit),
+        ]);
+        Subscription::batch([keys, drags, window])
     }
 }
 
@@ -592,4 +2237,470 @@ mod tests {
         assert!(workshop.editor.is_none());
         assert!(!workshop.show_editor);
     }
+
+    #[test]
+    fn test_dataframe_result_opens_table_viewer() {
+        let mut workshop = Workshop::new();
+        let _ = workshop.update(WorkshopMessage::Repl(ReplMessage::InputChanged(
+            r#"Data.frame([{"a": 1}, {"a": 2}])"#.to_string(),
+        )));
+        let _ = workshop.update(WorkshopMessage::Repl(ReplMessage::Submit));
+        assert_eq!(workshop.table.as_ref().unwrap().data().rows.len(), 2);
+        assert_eq!(workshop.status, "2 rows x 1 columns");
+
+        let _ = workshop.update(WorkshopMessage::Table(TableMessage::Close));
+        assert!(workshop.table.is_none());
+    }
+
+    #[test]
+    fn test_search_and_replace_respects_open_buffer() {
+        let dir = tempfile::tempdir().unwrap();
+        let open = dir.path().join("open.strat");
+        let other = dir.path().join("other.strat");
+        std::fs::write(&open, "let total = 1\n").unwrap();
+        std::fs::write(&other, "total(2)\n").unwrap();
+
+        let mut workshop = Workshop::new();
+        let _ = workshop.update(WorkshopMessage::FolderOpened(Some(
+            dir.path().to_path_buf(),
+        )));
+        let _ = workshop.update(WorkshopMessage::FileDialogOpened(Some((
+            open.clone(),
+            "let total = total + 1\n".to_string(),
+        ))));
+
+        let search = WorkshopMessage::Search;
+        let _ = workshop.update(search(SearchMessage::QueryChanged("total".to_string())));
+        let _ = workshop.update(search(SearchMessage::Search));
+        let results = workshop.search.as_ref().unwrap().results();
+        assert_eq!(results.files.len(), 2);
+        assert_eq!(results.files[0].matches[0].preview, "let total = total + 1");
+
+        let _ = workshop.update(search(SearchMessage::ReplacementChanged("sum".to_string())));
+        let _ = workshop.update(search(SearchMessage::ReplaceAll));
+        let editor = workshop.editor.as_ref().unwrap();
+        assert!(editor.content.text().starts_with("let sum = sum + 1"));
+        assert!(editor.modified);
+        // The open file is changed in the editor only
+        assert_eq!(std::fs::read_to_string(&open).unwrap(), "let total = 1\n");
+        assert_eq!(std::fs::read_to_string(&other).unwrap(), "sum(2)\n");
+        assert!(workshop.search.as_ref().unwrap().results().files.is_empty());
+
+        let _ = workshop.update(search(SearchMessage::Undo));
+        let editor = workshop.editor.as_ref().unwrap();
+        assert!(editor.content.text().starts_with("let total = total + 1"));
+        assert_eq!(std::fs::read_to_string(&other).unwrap(), "total(2)\n");
+        assert_eq!(workshop.search.as_ref().unwrap().results().files.len(), 2);
+    }
+
+    #[test]
+    fn test_undo_skips_files_changed_since_replace() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.strat");
+        std::fs::write(&file, "total").unwrap();
+
+        let mut workshop = Workshop::new();
+        let _ = workshop.update(WorkshopMessage::FolderOpened(Some(
+            dir.path().to_path_buf(),
+        )));
+        let search = WorkshopMessage::Search;
+        let _ = workshop.update(search(SearchMessage::QueryChanged("total".to_string())));
+        let _ = workshop.update(search(SearchMessage::ReplacementChanged("sum".to_string())));
+        let _ = workshop.update(search(SearchMessage::ReplaceAll));
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "sum");
+
+        std::fs::write(&file, "sum + 1").unwrap();
+        let _ = workshop.update(search(SearchMessage::Undo));
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "sum + 1");
+        assert!(workshop.status.starts_with("Undo failed"));
+    }
+
+    #[test]
+    fn test_terminal_follows_opened_folder() {
+        let mut workshop = Workshop::new();
+        workshop.terminal = Some(TerminalPanel::new(PathBuf::from("/tmp")));
+        let folder = PathBuf::from("/tmp/project");
+        let _ = workshop.update(WorkshopMessage::FolderOpened(Some(folder.clone())));
+        assert_eq!(workshop.terminal.as_ref().unwrap().cwd(), folder);
+
+        let _ = workshop.update(WorkshopMessage::Terminal(TerminalMessage::Close));
+        assert!(workshop.terminal.is_none());
+    }
+
+    #[test]
+    fn test_source_control_marks_changed_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init(dir.path()).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        let file = dir.path().join("a.strat");
+        std::fs::write(&file, "a\nb\n").unwrap();
+
+        let mut workshop = Workshop::new();
+        let _ = workshop.update(WorkshopMessage::FolderOpened(Some(
+            dir.path().to_path_buf(),
+        )));
+        assert!(workshop.git.is_some());
+        let _ = workshop.update(WorkshopMessage::ToggleSourceControl);
+        assert!(workshop.show_source_control);
+
+        let git = WorkshopMessage::SourceControl;
+        let _ = workshop.update(git(SourceControlMessage::Stage(PathBuf::from("a.strat"))));
+        let _ = workshop.update(git(SourceControlMessage::MessageChanged(
+            "Add a".to_string(),
+        )));
+        let _ = workshop.update(git(SourceControlMessage::Commit));
+        assert!(workshop.status.starts_with("Committed"));
+
+        let _ = workshop.update(WorkshopMessage::FileDialogOpened(Some((
+            file,
+            "a\nB\n".to_string(),
+        ))));
+        let editor = workshop.editor.as_ref().unwrap();
+        assert_eq!(editor.line_changes, [(2, LineChange::Modified)]);
+    }
+
+    #[test]
+    fn test_language_features_in_editor() {
+        let source = "fx add(a: Int, b: Int) -> Int { a + b }\nlet total = add(1, 2)\n";
+        let mut workshop = Workshop::new();
+        let _ = workshop.update(WorkshopMessage::FileDialogOpened(Some((
+            PathBuf::from("/tmp/a.strat"),
+            source.to_string(),
+        ))));
+        let language = workshop.editor.as_ref().unwrap().language.as_ref().unwrap();
+        assert!(language.diagnostics.is_empty());
+
+        let _ = workshop.update(WorkshopMessage::GotoLine {
+            line: 1,
+            column: 13,
+        });
+        let _ = workshop.update(WorkshopMessage::GotoDefinition);
+        let editor = workshop.editor.as_ref().unwrap();
+        let cursor = editor.content.cursor().position;
+        assert_eq!((cursor.line, cursor.column), (0, 3));
+        assert!(editor.language.as_ref().unwrap().hover.is_some());
+
+        let _ = workshop.update(WorkshopMessage::GotoLine { line: 2, column: 0 });
+        for c in "ad".chars() {
+            let _ = workshop.update(WorkshopMessage::EditorAction(text_editor::Action::Edit(
+                text_editor::Edit::Insert(c),
+            )));
+        }
+        let _ = workshop.update(WorkshopMessage::TriggerCompletion);
+        let completion = workshop.editor.as_ref().unwrap().language.as_ref().unwrap();
+        let index = completion
+            .completion
+            .as_ref()
+            .and_then(|c| c.items.iter().position(|item| item.label == "add"))
+            .unwrap();
+        let _ = workshop.update(WorkshopMessage::AcceptCompletion(Some(index)));
+        let editor = workshop.editor.as_ref().unwrap();
+        assert!(editor
+            .content
+            .text()
+            .lines()
+            .nth(2)
+            .unwrap()
+            .starts_with("add"));
+        assert!(!editor.content.text().contains("adadd"));
+        assert!(editor.modified);
+
+        let _ = workshop.update(WorkshopMessage::EditorAction(text_editor::Action::Edit(
+            text_editor::Edit::Insert('('),
+        )));
+        let language = workshop.editor.as_ref().unwrap().language.as_ref().unwrap();
+        assert!(language.completion.is_none());
+        assert!(!language.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_plain_text_has_no_language_features() {
+        let mut workshop = Workshop::new();
+        let _ = workshop.update(WorkshopMessage::FileDialogOpened(Some((
+            PathBuf::from("/tmp/notes.txt"),
+            "fx broken( {".to_string(),
+        ))));
+        assert!(workshop.editor.as_ref().unwrap().language.is_none());
+    }
+
+    #[test]
+    fn test_notebook_opens_and_runs() {
+        let json = r##"{"version": 1, "cells": [
+            {"kind": "markdown", "source": "# Frame"},
+            {"kind": "code", "source": "Data.frame([{\"a\": 1}])"}
+        ]}"##;
+        let mut workshop = Workshop::new();
+        let _ = workshop.update(WorkshopMessage::FileDialogOpened(Some((
+            PathBuf::from("/tmp/frame.stratnb"),
+            json.to_string(),
+        ))));
+        assert!(workshop.editor.is_none());
+        assert_eq!(workshop.notebook.as_ref().unwrap().cells().len(), 2);
+
+        let notebook = WorkshopMessage::Notebook;
+        let _ = workshop.update(notebook(NotebookMessage::RunAll));
+        let _ = workshop.update(notebook(NotebookMessage::OpenTable { cell: 1, output: 0 }));
+        assert_eq!(workshop.table.as_ref().unwrap().data().rows.len(), 1);
+
+        let _ = workshop.update(notebook(NotebookMessage::AddCell(CellKind::Code)));
+        let _ = workshop.update(WorkshopMessage::NewNotebook);
+        assert_eq!(workshop.notebook.as_ref().unwrap().cells().len(), 3);
+        let _ = workshop.update(WorkshopMessage::NotebookSaved(PathBuf::from(
+            "/tmp/frame.stratnb",
+        )));
+        assert!(!workshop.notebook.as_ref().unwrap().is_modified());
+
+        let _ = workshop.update(notebook(NotebookMessage::Close));
+        assert!(workshop.notebook.is_none());
+    }
+
+    #[test]
+    fn test_plugin_command_and_decorations() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("todo");
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("stratum.toml"),
+            "[package]\nname = \"todo\"\nversion = \"0.1.0\"\nedition = \"2025\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("src").join("lib.strat"),
+            r#"
+fx activate() {
+    Workshop.command("Uppercase", |ctx| ctx["text"].to_upper())
+    Workshop.keybinding("ctrl+shift+u", "Uppercase")
+    Workshop.decorations(|ctx| [[ctx["line"], "cursor"]])
+}
+"#,
+        )
+        .unwrap();
+
+        let mut workshop = Workshop::new();
+        workshop.plugins = PluginHost::load_dir(dir.path());
+        let _ = workshop.update(WorkshopMessage::FileDialogOpened(Some((
+            PathBuf::from("/tmp/notes.txt"),
+            "a\nb\n".to_string(),
+        ))));
+        let editor = workshop.editor.as_ref().unwrap();
+        assert_eq!(editor.decorations.len(), 1);
+        assert_eq!(editor.decorations[0].line, 1);
+
+        let _ = workshop.update(WorkshopMessage::BoundKey(
+            KeyChord::parse("ctrl+shift+u").unwrap(),
+        ));
+        let editor = workshop.editor.as_ref().unwrap();
+        assert!(editor.content.text().starts_with("A\nB"));
+        assert!(editor.modified);
+        assert_eq!(workshop.status, "Ran Uppercase");
+
+        // Unbound chords do nothing
+        let _ = workshop.update(WorkshopMessage::BoundKey(KeyChord::parse("alt+z").unwrap()));
+        assert_eq!(workshop.status, "Ran Uppercase");
+    }
+
+    #[test]
+    fn test_editor_tabs_in_split_groups() {
+        let mut workshop = Workshop::new();
+        workshop.tabs = TabGroups::new(crate::tabs::SplitLayout::default());
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            let _ = workshop.update(WorkshopMessage::FileDialogOpened(Some((
+                PathBuf::from("/tmp").join(name),
+                name.to_string(),
+            ))));
+        }
+        let focused = |workshop: &Workshop| workshop.editor.as_ref().map(EditorState::name);
+        assert_eq!(focused(&workshop).as_deref(), Some("c.txt"));
+
+        // Opening an open file focuses its tab
+        let _ = workshop.update(WorkshopMessage::FileDialogOpened(Some((
+            PathBuf::from("/tmp/a.txt"),
+            String::new(),
+        ))));
+        assert_eq!(focused(&workshop).as_deref(), Some("a.txt"));
+        assert_eq!(workshop.tabs.iter(workshop.editor.as_ref()).count(), 3);
+
+        // Splitting moves the tab to a new group; other tabs drag over
+        workshop
+            .tabs
+            .split(&mut workshop.editor, SplitAxis::Columns);
+        let _ = workshop.update(WorkshopMessage::DragTab { group: 0, tab: 0 });
+        assert_eq!(focused(&workshop).as_deref(), Some("b.txt"));
+        let _ = workshop.update(WorkshopMessage::DropTab(1));
+        assert_eq!(workshop.tabs.focused(), 1);
+        assert_eq!(workshop.tabs.group(1, workshop.editor.as_ref()).0.len(), 2);
+        assert_eq!(focused(&workshop).as_deref(), Some("b.txt"));
+
+        // A drag released elsewhere is dropped
+        let _ = workshop.update(WorkshopMessage::DragTab { group: 0, tab: 0 });
+        let _ = workshop.update(WorkshopMessage::EndTabDrag);
+        let _ = workshop.update(WorkshopMessage::DropTab(1));
+        assert_eq!(workshop.tabs.group(0, workshop.editor.as_ref()).0.len(), 1);
+        assert!(workshop.is_open(Path::new("/tmp/a.txt")));
+    }
+
+    #[test]
+    fn test_session_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let saved = dir.path().join("saved.strat");
+        let edited = dir.path().join("edited.txt");
+        std::fs::write(&saved, "let x = 1\nlet y = 2\n").unwrap();
+        std::fs::write(&edited, "draft").unwrap();
+
+        let mut workshop = Workshop::new();
+        workshop.tabs = TabGroups::new(SplitLayout::default());
+        for path in [&saved, &edited] {
+            let content = std::fs::read_to_string(path).unwrap();
+            let _ = workshop.update(WorkshopMessage::FileDialogOpened(Some((
+                path.clone(),
+                content,
+            ))));
+        }
+        let _ = workshop.update(WorkshopMessage::EditorAction(text_editor::Action::Edit(
+            text_editor::Edit::Insert('!'),
+        )));
+        let _ = workshop.update(WorkshopMessage::FocusTab { group: 0, tab: 0 });
+        move_cursor(&mut workshop.editor.as_mut().unwrap().content, 1, 4);
+        workshop.window_size = Some(Size::new(900.0, 640.0));
+
+        let mut session = workshop.session();
+        assert!(session.panels.editor);
+        assert_eq!(session.window_size, Some((900.0, 640.0)));
+        // A tab whose file is gone is skipped
+        session.groups[0].tabs.push(TabSession {
+            path: Some(dir.path().join("deleted.strat")),
+            ..TabSession::default()
+        });
+
+        let mut restored = Workshop::new();
+        let _ = restored.restore_session(session);
+        let editor = restored.editor.as_ref().unwrap();
+        assert_eq!(editor.name(), "saved.strat");
+        let cursor = editor.content.cursor().position;
+        assert_eq!((cursor.line, cursor.column), (1, 4));
+        assert!(!editor.modified);
+        assert_eq!(restored.tabs.iter(restored.editor.as_ref()).count(), 2);
+        assert_eq!(restored.window_size, Some(Size::new(900.0, 640.0)));
+
+        // Unsaved text comes back in place of the file
+        let _ = restored.update(WorkshopMessage::FocusTab { group: 0, tab: 1 });
+        let editor = restored.editor.as_ref().unwrap();
+        assert!(editor.modified);
+        assert!(editor.content.text().starts_with("!draft"));
+    }
+
+    #[test]
+    fn test_run_configs_of_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join(".workshop")).unwrap();
+        std::fs::write(
+            dir.path().join(crate::panels::RUN_CONFIG_FILE),
+            "[[config]]\nname = \"Dev\"\nshortcut = \"ctrl+shift+d\"\n",
+        )
+        .unwrap();
+
+        let mut workshop = Workshop::new();
+        let _ = workshop.update(WorkshopMessage::FolderOpened(Some(
+            dir.path().to_path_buf(),
+        )));
+        let targets = RunTarget::all(workshop.run_configs.configs());
+        assert_eq!(targets.len(), 2);
+        let _ = workshop.update(WorkshopMessage::SelectRunTarget(targets[1].clone()));
+        assert_eq!(workshop.run_configs.selected(), Some(0));
+
+        // The picked configuration is kept in the session
+        let session = workshop.session();
+        assert_eq!(session.run_target.as_deref(), Some("Dev"));
+        let mut restored = Workshop::new();
+        let _ = restored.restore_session(session);
+        assert_eq!(restored.run_configs.selected(), Some(0));
+
+        // Unsaved configurations keep the Workshop open
+        let _ = restored.update(WorkshopMessage::RunConfigs(RunConfigMessage::Add));
+        let _ = restored.update(WorkshopMessage::Exit);
+        assert!(restored.show_run_configs);
+    }
+
+    #[test]
+    fn test_profile_frames_open_source() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("app.strat"), "fx main() {\n    work()\n}\n").unwrap();
+        let speedscope = r#"{
+            "shared": {"frames": [{"name": "main", "file": "app.strat", "line": 2}]},
+            "profiles": [{"type": "sampled", "samples": [[0]], "weights": [1]}]
+        }"#;
+
+        let mut workshop = Workshop::new();
+        workshop.tabs = TabGroups::new(SplitLayout::default());
+        let _ = workshop.update(WorkshopMessage::FolderOpened(Some(
+            dir.path().to_path_buf(),
+        )));
+        let _ = workshop.update(WorkshopMessage::ProfileOpened(Some((
+            dir.path().join("profile.json"),
+            speedscope.to_string(),
+        ))));
+        assert_eq!(
+            workshop
+                .profiler
+                .as_ref()
+                .unwrap()
+                .profile()
+                .flamegraph
+                .total,
+            1
+        );
+
+        // Frame files are relative to the folder the run started in
+        let _ = workshop.update(WorkshopMessage::Profiler(ProfilerMessage::OpenFrame(0)));
+        let editor = workshop.editor.as_ref().unwrap();
+        assert_eq!(editor.name(), "app.strat");
+        assert_eq!(editor.content.cursor().position.line, 1);
+
+        let _ = workshop.update(WorkshopMessage::ProfileOpened(Some((
+            dir.path().join("other.json"),
+            "{}".to_string(),
+        ))));
+        assert!(workshop.status.starts_with("Not a speedscope profile"));
+    }
+
+    #[test]
+    fn test_vim_keymap_in_editor() {
+        let mut workshop = Workshop::new();
+        workshop.keymap = KeymapState::new(Keymap::Vim);
+        let _ = workshop.update(WorkshopMessage::FileDialogOpened(Some((
+            PathBuf::from("/tmp/notes.txt"),
+            "one\ntwo\n".to_string(),
+        ))));
+        for c in "ddp".chars() {
+            let _ = workshop.update(WorkshopMessage::EditorKey(EditorKey {
+                input: crate::keymap::Input::Char(c),
+                ctrl: false,
+                alt: false,
+            }));
+        }
+        let editor = workshop.editor.as_ref().unwrap();
+        assert!(editor.content.text().starts_with("two\none"));
+        assert_eq!(editor.content.cursor().position.line, 1);
+        assert!(editor.modified);
+        assert_eq!(workshop.keymap.status().as_deref(), Some("-- NORMAL --"));
+    }
+
+    #[test]
+    fn test_search_without_folder() {
+        let mut workshop = Workshop::new();
+        let _ = workshop.update(WorkshopMessage::ToggleSearch);
+        let _ = workshop.update(WorkshopMessage::Search(SearchMessage::QueryChanged(
+            "x".to_string(),
+        )));
+        let _ = workshop.update(WorkshopMessage::Search(SearchMessage::Search));
+        assert_eq!(
+            workshop.search.as_ref().unwrap().error(),
+            Some("Open a folder to search")
+        );
+
+        let _ = workshop.update(WorkshopMessage::ToggleSearch);
+        assert!(workshop.search.is_none());
+    }
 }
@@ -37,7 +37,12 @@ export async function activate(context: vscode.ExtensionContext): Promise<void>
     // Client options
     const clientOptions: LanguageClientOptions = {
         documentSelector: [{ scheme: 'file', language: 'stratum' }],
+        initializationOptions: {
+            inlayHints: config.get('inlayHints'),
+            diagnostics: config.get('diagnostics'),
+        },
         synchronize: {
+            configurationSection: 'stratum',
             fileEvents: vscode.workspace.createFileSystemWatcher('**/*.strat'),
         },
         outputChannel,
@@ -73,6 +78,25 @@ export async function activate(context: vscode.ExtensionContext): Promise<void>
     );
     context.subscriptions.push(restartCommand);
 
+    // Reference count code lenses carry LSP locations, which the built-in
+    // references view cannot take directly
+    const showReferencesCommand = vscode.commands.registerCommand(
+        'stratum.showReferences',
+        (uri: string, position: any, locations: any[]) => {
+            if (!client) {
+                return;
+            }
+            const converter = client.protocol2CodeConverter;
+            vscode.commands.executeCommand(
+                'editor.action.showReferences',
+                converter.asUri(uri),
+                converter.asPosition(position),
+                locations.map(converter.asLocation)
+            );
+        }
+    );
+    context.subscriptions.push(showReferencesCommand);
+
     // Register format on save if enabled
     const formatOnSave = config.get<boolean>('format.onSave', true);
     if (formatOnSave) {